@@ -19,6 +19,7 @@ typedef uint32_t LCID;
 bool SFileOpenArchive(const char* archive_name, DWORD priority, DWORD flags, HANDLE* archive);
 bool SFileCreateArchive(const char* archive_name, DWORD flags, DWORD max_file_count, HANDLE* archive);
 bool SFileCloseArchive(HANDLE archive);
+bool SFileSetMaxFileCount(HANDLE archive, DWORD max_file_count);
 
 /* File operations */
 bool SFileOpenFileEx(HANDLE archive, const char* filename, DWORD search_scope, HANDLE* file);
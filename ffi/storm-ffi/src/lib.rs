@@ -89,6 +89,7 @@ const ERROR_ACCESS_DENIED: u32 = 5;
 const ERROR_INVALID_HANDLE: u32 = 6;
 const _ERROR_NOT_ENOUGH_MEMORY: u32 = 8;
 const ERROR_INVALID_PARAMETER: u32 = 87;
+const ERROR_DISK_FULL: u32 = 112;
 const ERROR_INSUFFICIENT_BUFFER: u32 = 122;
 const ERROR_ALREADY_EXISTS: u32 = 183;
 const ERROR_FILE_CORRUPT: u32 = 1392;
@@ -109,6 +110,9 @@ const SFILE_VERIFY_SIGNATURE: u32 = 0x10;
 const SFILE_VERIFY_ALL_FILES: u32 = 0x20;
 const SFILE_VERIFY_ALL: u32 = 0xFF;
 
+// Signature types (for SFileSignArchive)
+const SIGNATURE_TYPE_WEAK: u32 = 0x01;
+
 // Info classes for SFileGetFileInfo
 const SFILE_INFO_ARCHIVE_SIZE: u32 = 1;
 const SFILE_INFO_HASH_TABLE_SIZE: u32 = 2;
@@ -1704,6 +1708,113 @@ pub unsafe extern "C" fn SFileFlushArchive(archive: HANDLE) -> bool {
     }
 }
 
+/// Resize the hash table of an archive to hold `max_file_count` files
+///
+/// The new table is written on the next flush. Fails with
+/// `ERROR_DISK_FULL` if the archive holds more files than that.
+///
+/// # Safety
+///
+/// - `archive` must be a valid archive handle
+#[no_mangle]
+pub unsafe extern "C" fn SFileSetMaxFileCount(archive: HANDLE, max_file_count: u32) -> bool {
+    // Validate parameters
+    if archive.is_null() {
+        set_last_error(ERROR_INVALID_PARAMETER);
+        return false;
+    }
+
+    // Convert handle
+    let archive_id = match handle_to_id(archive) {
+        Some(id) => id,
+        None => {
+            set_last_error(ERROR_INVALID_HANDLE);
+            return false;
+        }
+    };
+
+    // Get mutable archive handle
+    let mut archives = ARCHIVES.lock().unwrap();
+    let archive_handle = match archives.get_mut(&archive_id) {
+        Some(handle) => handle,
+        None => {
+            set_last_error(ERROR_INVALID_HANDLE);
+            return false;
+        }
+    };
+
+    let mutable_archive = match archive_handle.mutable_archive() {
+        Some(archive) => archive,
+        None => {
+            set_last_error(ERROR_ACCESS_DENIED);
+            return false;
+        }
+    };
+
+    // Rebuild the hash table
+    match mutable_archive.set_max_file_count(max_file_count) {
+        Ok(()) => {
+            set_last_error(ERROR_SUCCESS);
+            true
+        }
+        Err(e) => {
+            let error_code = match e {
+                wow_mpq::Error::CapacityExceeded(_) => ERROR_DISK_FULL,
+                _ => ERROR_FILE_CORRUPT,
+            };
+            set_last_error(error_code);
+            false
+        }
+    }
+}
+
+/// Sign an archive with the weak signature
+///
+/// Like StormLib, only `SIGNATURE_TYPE_WEAK` is supported. Pending changes
+/// are flushed first, and an existing weak signature is regenerated.
+///
+/// # Safety
+///
+/// - `archive` must be a valid archive handle
+#[no_mangle]
+pub unsafe extern "C" fn SFileSignArchive(archive: HANDLE, signature_type: u32) -> bool {
+    if archive.is_null() {
+        set_last_error(ERROR_INVALID_PARAMETER);
+        return false;
+    }
+    if signature_type != SIGNATURE_TYPE_WEAK {
+        set_last_error(ERROR_INVALID_PARAMETER);
+        return false;
+    }
+
+    let Some(archive_id) = handle_to_id(archive) else {
+        set_last_error(ERROR_INVALID_HANDLE);
+        return false;
+    };
+
+    let mut archives = ARCHIVES.lock().unwrap();
+    let Some(archive_handle) = archives.get_mut(&archive_id) else {
+        set_last_error(ERROR_INVALID_HANDLE);
+        return false;
+    };
+
+    let Some(mutable_archive) = archive_handle.mutable_archive() else {
+        set_last_error(ERROR_ACCESS_DENIED);
+        return false;
+    };
+
+    match mutable_archive.resign_weak() {
+        Ok(()) => {
+            set_last_error(ERROR_SUCCESS);
+            true
+        }
+        Err(_) => {
+            set_last_error(ERROR_ACCESS_DENIED);
+            false
+        }
+    }
+}
+
 /// Compact an archive to remove deleted files
 ///
 /// # Safety
@@ -2399,6 +2510,22 @@ mod tests {
         assert_eq!(SFileGetLastError(), ERROR_INVALID_HANDLE);
     }
 
+    #[test]
+    fn test_sign_archive_invalid_params() {
+        unsafe {
+            assert!(!SFileSignArchive(ptr::null_mut(), SIGNATURE_TYPE_WEAK));
+        }
+        assert_eq!(SFileGetLastError(), ERROR_INVALID_PARAMETER);
+    }
+
+    #[test]
+    fn test_set_max_file_count_invalid_params() {
+        unsafe {
+            assert!(!SFileSetMaxFileCount(ptr::null_mut(), 1024));
+        }
+        assert_eq!(SFileGetLastError(), ERROR_INVALID_PARAMETER);
+    }
+
     #[test]
     fn test_create_archive_invalid_params() {
         // Test SFileCreateArchive with invalid parameters
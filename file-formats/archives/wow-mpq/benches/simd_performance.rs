@@ -30,6 +30,44 @@ fn bench_crc32_performance(c: &mut Criterion) {
     group.finish();
 }
 
+/// Benchmark multi-buffer CRC32 and MD5 against one-at-a-time hashing
+fn bench_multi_buffer_checksums(c: &mut Criterion) {
+    let simd = SimdOps::new();
+
+    // Typical sector sizes for verification workloads
+    let sector_sizes = [4096, 65536];
+    let buffer_count = 16;
+
+    let mut group = c.benchmark_group("multi_buffer_checksums");
+
+    for size in sector_sizes {
+        let buffers: Vec<Vec<u8>> = (0..buffer_count).map(|i| vec![i as u8; size]).collect();
+        let refs: Vec<&[u8]> = buffers.iter().map(|b| b.as_slice()).collect();
+
+        group.throughput(Throughput::Bytes((size * buffer_count) as u64));
+
+        group.bench_with_input(BenchmarkId::new("crc32_multi", size), &refs, |b, refs| {
+            b.iter(|| simd.crc32_multi(black_box(refs), 0))
+        });
+
+        group.bench_with_input(
+            BenchmarkId::new("crc32_multi_scalar", size),
+            &refs,
+            |b, refs| b.iter(|| wow_mpq::simd::scalar::crc32_multi_scalar(black_box(refs), 0)),
+        );
+
+        group.bench_with_input(BenchmarkId::new("md5_multi", size), &refs, |b, refs| {
+            b.iter(|| simd.md5_multi(black_box(refs)))
+        });
+
+        group.bench_with_input(BenchmarkId::new("md5_scalar", size), &refs, |b, refs| {
+            b.iter(|| wow_mpq::simd::scalar::md5_multi_scalar(black_box(refs)))
+        });
+    }
+
+    group.finish();
+}
+
 /// Benchmark SIMD hash performance vs scalar implementation
 fn bench_hash_performance(c: &mut Criterion) {
     let simd = SimdOps::new();
@@ -308,6 +346,7 @@ criterion_group!(
     benches,
     bench_cpu_feature_detection,
     bench_crc32_performance,
+    bench_multi_buffer_checksums,
     bench_hash_performance,
     bench_jenkins_batch_performance,
     bench_realistic_workload
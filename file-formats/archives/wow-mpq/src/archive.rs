@@ -14,10 +14,14 @@ use crate::{
     compression,
     crypto::{decrypt_block, decrypt_dword, hash_string, hash_type},
     header::{self, MpqHeader, UserDataHeader},
+    path::PathPolicy,
+    read_cache::{ReadCache, ReadCacheConfig, ReadCacheStats, SectorKey},
     special_files,
     tables::{BetTable, BlockTable, HashTable, HetTable, HiBlockTable},
+    warcraft3::{W3_MAP_HEADER_SIZE, W3MapHeader},
 };
 use byteorder::{LittleEndian, ReadBytesExt};
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::{BufReader, Read, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
@@ -157,6 +161,21 @@ pub struct OpenOptions {
     /// This field is only used when creating new archives via `create()`.
     /// If `None`, defaults to MPQ version 1 for maximum compatibility.
     version: Option<crate::header::FormatVersion>,
+
+    /// How name lookups treat case and path separators.
+    ///
+    /// Defaults to [`PathPolicy::LENIENT`], the behavior of the name hash.
+    pub path_policy: PathPolicy,
+
+    /// Locale preferred when a file is stored in several locales.
+    ///
+    /// Defaults to 0, the neutral locale. See [`Archive::set_locale`].
+    pub locale: u16,
+
+    /// Cache of decoded file data, off by default.
+    ///
+    /// See [`Archive::enable_read_cache`].
+    pub read_cache: Option<ReadCacheConfig>,
 }
 
 impl OpenOptions {
@@ -165,10 +184,16 @@ impl OpenOptions {
     /// Returns an `OpenOptions` instance with default settings:
     /// - `load_tables = true` (immediate table loading)
     /// - `version = None` (defaults to MPQ v1 for new archives)
+    /// - `path_policy = PathPolicy::LENIENT` (hash-equivalent names match)
+    /// - `locale = 0` (neutral locale preferred)
+    /// - `read_cache = None` (decoded data is not cached)
     pub fn new() -> Self {
         Self {
             load_tables: true,
             version: None,
+            path_policy: PathPolicy::LENIENT,
+            locale: 0,
+            read_cache: None,
         }
     }
 
@@ -200,6 +225,39 @@ impl OpenOptions {
         self
     }
 
+    /// Set how name lookups treat case and path separators
+    ///
+    /// See [`Archive::set_path_policy`].
+    ///
+    /// # Returns
+    /// Self for method chaining
+    pub fn path_policy(mut self, policy: PathPolicy) -> Self {
+        self.path_policy = policy;
+        self
+    }
+
+    /// Set the locale preferred when a file is stored in several locales
+    ///
+    /// See [`Archive::set_locale`].
+    ///
+    /// # Returns
+    /// Self for method chaining
+    pub fn locale(mut self, locale: u16) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    /// Cache decoded file data for repeated reads
+    ///
+    /// See [`Archive::enable_read_cache`].
+    ///
+    /// # Returns
+    /// Self for method chaining
+    pub fn read_cache(mut self, config: ReadCacheConfig) -> Self {
+        self.read_cache = Some(config);
+        self
+    }
+
     /// Open an existing MPQ archive with these options
     ///
     /// # Parameters
@@ -251,17 +309,56 @@ impl Default for OpenOptions {
     }
 }
 
+/// Key under which the name hash sees a name: uppercase with `\` separators
+fn hash_key(name: &str) -> String {
+    crate::path::normalize_mpq_path(name).to_ascii_uppercase()
+}
+
+/// Byte source an [`Archive`] reads from
+///
+/// Implemented for every seekable reader that can be shared between threads,
+/// such as a [`File`] or a [`Cursor`](std::io::Cursor) over a buffer, so an
+/// [`Archive`] is `Send + Sync` whatever it reads from.
+pub trait ArchiveSource: Read + Seek + Send + Sync {}
+
+impl<T: Read + Seek + Send + Sync> ArchiveSource for T {}
+
+/// Length of a source, keeping its position
+fn source_len<R: Seek + ?Sized>(reader: &mut R) -> Result<u64> {
+    let pos = reader.stream_position()?;
+    let len = reader.seek(SeekFrom::End(0))?;
+    reader.seek(SeekFrom::Start(pos))?;
+    Ok(len)
+}
+
+/// Read the Warcraft III map header in front of MPQ data at `archive_offset`
+///
+/// A damaged map header does not keep the archive from opening.
+fn read_map_header(
+    reader: &mut Box<dyn ArchiveSource>,
+    archive_offset: u64,
+) -> Option<W3MapHeader> {
+    if archive_offset < W3_MAP_HEADER_SIZE as u64 {
+        return None;
+    }
+    W3MapHeader::read(reader).unwrap_or_else(|e| {
+        log::warn!("Ignoring unreadable Warcraft III map header: {e}");
+        None
+    })
+}
+
 /// An MPQ archive
-#[derive(Debug)]
 pub struct Archive {
-    /// Path to the archive file
+    /// Path to the archive file, empty for archives read from a reader
     path: PathBuf,
-    /// Archive file reader
-    reader: BufReader<File>,
+    /// Archive reader
+    reader: Box<dyn ArchiveSource>,
     /// Offset where the MPQ data starts in the file
     archive_offset: u64,
     /// Optional user data header
     user_data: Option<UserDataHeader>,
+    /// Warcraft III map header in front of the MPQ data
+    map_header: Option<W3MapHeader>,
     /// MPQ header
     header: MpqHeader,
     /// Hash table (optional, loaded on demand)
@@ -276,6 +373,36 @@ pub struct Archive {
     bet_table: Option<BetTable>,
     /// File attributes from (attributes) file
     attributes: Option<special_files::Attributes>,
+    /// Names recorded by successful reads, when enabled
+    pub(crate) recorded_names: Option<crate::learn::NameRecorder>,
+    /// How name lookups treat case and path separators
+    path_policy: PathPolicy,
+    /// Listfile spellings by hash-equivalent name, loaded for non-lenient policies
+    listed_names: Option<HashMap<String, Vec<String>>>,
+    /// Locale preferred by name lookups
+    locale: u16,
+    /// Decoded file data kept for repeated reads, when enabled
+    read_cache: Option<ReadCache>,
+}
+
+impl std::fmt::Debug for Archive {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Archive")
+            .field("path", &self.path)
+            .field("archive_offset", &self.archive_offset)
+            .field("user_data", &self.user_data)
+            .field("map_header", &self.map_header)
+            .field("header", &self.header)
+            .field("hash_table", &self.hash_table)
+            .field("block_table", &self.block_table)
+            .field("hi_block_table", &self.hi_block_table)
+            .field("het_table", &self.het_table)
+            .field("bet_table", &self.bet_table)
+            .field("attributes", &self.attributes)
+            .field("path_policy", &self.path_policy)
+            .field("locale", &self.locale)
+            .finish_non_exhaustive()
+    }
 }
 
 impl Archive {
@@ -288,16 +415,76 @@ impl Archive {
     pub fn open_with_options<P: AsRef<Path>>(path: P, options: OpenOptions) -> Result<Self> {
         let path = path.as_ref().to_path_buf();
         let file = File::open(&path)?;
-        let mut reader = BufReader::new(file);
+        Self::open_source(path, Box::new(BufReader::new(file)), options)
+    }
 
+    /// Open an archive split into volumes, such as `.mpq.part` files
+    ///
+    /// The volumes are joined in the order given and read as one archive,
+    /// wherever the boundaries fall. Like archives read with
+    /// [`Archive::from_reader`], the archive has no [`path`](Archive::path).
+    ///
+    /// ```no_run
+    /// use wow_mpq::Archive;
+    ///
+    /// let mut archive = Archive::open_multi(&["data.mpq.part1", "data.mpq.part2"])?;
+    /// let data = archive.read_file("readme.txt")?;
+    /// # Ok::<(), wow_mpq::Error>(())
+    /// ```
+    pub fn open_multi<P: AsRef<Path>>(paths: &[P]) -> Result<Self> {
+        Self::open_multi_with_options(paths, OpenOptions::default())
+    }
+
+    /// Open an archive split into volumes with specific options
+    pub fn open_multi_with_options<P: AsRef<Path>>(
+        paths: &[P],
+        options: OpenOptions,
+    ) -> Result<Self> {
+        if paths.is_empty() {
+            return Err(Error::invalid_format("No archive volumes given"));
+        }
+        let volumes = paths
+            .iter()
+            .map(|path| Ok(BufReader::new(File::open(path)?)))
+            .collect::<Result<Vec<_>>>()?;
+        let reader = crate::io::VolumeReader::new(volumes)?;
+        Self::open_source(PathBuf::new(), Box::new(reader), options)
+    }
+
+    /// Read an archive from any seekable source, such as a buffer in memory
+    ///
+    /// The archive is located the same way as in a file, so `reader` may
+    /// start with user data or an executable stub. Reads are not buffered;
+    /// wrap slow sources in a [`BufReader`]. [`Archive::path`] is empty for
+    /// such archives, so operations that reopen the archive by path, like
+    /// parallel extraction, run on this handle alone.
+    pub fn from_reader<R: ArchiveSource + 'static>(reader: R) -> Result<Self> {
+        Self::from_reader_with_options(reader, OpenOptions::default())
+    }
+
+    /// Read an archive from a seekable source with specific options
+    pub fn from_reader_with_options<R: ArchiveSource + 'static>(
+        reader: R,
+        options: OpenOptions,
+    ) -> Result<Self> {
+        Self::open_source(PathBuf::new(), Box::new(reader), options)
+    }
+
+    fn open_source(
+        path: PathBuf,
+        mut reader: Box<dyn ArchiveSource>,
+        options: OpenOptions,
+    ) -> Result<Self> {
         // Find and read the MPQ header
         let (archive_offset, user_data, header) = header::find_header(&mut reader)?;
+        let map_header = read_map_header(&mut reader, archive_offset);
 
         let mut archive = Archive {
             path,
             reader,
             archive_offset,
             user_data,
+            map_header,
             header,
             hash_table: None,
             block_table: None,
@@ -305,16 +492,58 @@ impl Archive {
             bet_table: None,
             het_table: None,
             attributes: None,
+            recorded_names: None,
+            path_policy: PathPolicy::LENIENT,
+            listed_names: None,
+            locale: 0,
+            read_cache: None,
         };
 
         // Load tables if requested
         if options.load_tables {
             archive.load_tables()?;
         }
+        if !options.path_policy.is_lenient() {
+            archive.set_path_policy(options.path_policy)?;
+        }
+        archive.locale = options.locale;
+        archive.read_cache = options.read_cache.map(ReadCache::new);
 
         Ok(archive)
     }
 
+    /// Assemble an archive from a header and tables read elsewhere
+    pub(crate) fn from_tables(
+        path: PathBuf,
+        mut reader: Box<dyn ArchiveSource>,
+        archive_offset: u64,
+        user_data: Option<UserDataHeader>,
+        header: MpqHeader,
+        hash_table: HashTable,
+        block_table: BlockTable,
+    ) -> Self {
+        let map_header = read_map_header(&mut reader, archive_offset);
+        Archive {
+            path,
+            reader,
+            archive_offset,
+            user_data,
+            map_header,
+            header,
+            hash_table: Some(hash_table),
+            block_table: Some(block_table),
+            hi_block_table: None,
+            bet_table: None,
+            het_table: None,
+            attributes: None,
+            recorded_names: None,
+            path_policy: PathPolicy::LENIENT,
+            listed_names: None,
+            locale: 0,
+            read_cache: None,
+        }
+    }
+
     /// Load hash and block tables
     pub fn load_tables(&mut self) -> Result<()> {
         log::debug!(
@@ -464,7 +693,7 @@ impl Archive {
             // For V4 archives, we have explicit compressed size info
             if let Some(v4_data) = &self.header.v4_data {
                 // Validate V4 sizes are reasonable (not corrupted)
-                let file_size = self.reader.get_ref().metadata()?.len();
+                let file_size = source_len(&mut self.reader)?;
                 let v4_size_valid = v4_data.hash_table_size_64 > 0
                     && v4_data.hash_table_size_64 < file_size
                     && v4_data.hash_table_size_64 < (uncompressed_size as u64 * 2); // Compressed shouldn't be much larger
@@ -478,7 +707,7 @@ impl Archive {
                     );
 
                     // Check if it would extend beyond file
-                    let file_size = self.reader.get_ref().metadata()?.len();
+                    let file_size = source_len(&mut self.reader)?;
                     if hash_table_offset + compressed_size > file_size {
                         log::warn!("Hash table extends beyond file, skipping");
                     } else {
@@ -529,7 +758,7 @@ impl Archive {
                     (block_table_offset - hash_table_offset) as usize
                 } else {
                     // If block table comes before hash table, calculate differently
-                    let file_size = self.reader.get_ref().metadata()?.len();
+                    let file_size = source_len(&mut self.reader)?;
                     (file_size - hash_table_offset) as usize
                 };
 
@@ -613,7 +842,7 @@ impl Archive {
             // For V4 archives, we have explicit compressed size info
             if let Some(v4_data) = &self.header.v4_data {
                 // Validate V4 sizes are reasonable (not corrupted)
-                let file_size = self.reader.get_ref().metadata()?.len();
+                let file_size = source_len(&mut self.reader)?;
                 let v4_size_valid = v4_data.block_table_size_64 > 0
                     && v4_data.block_table_size_64 < file_size
                     && v4_data.block_table_size_64 < (uncompressed_size as u64 * 2); // Compressed shouldn't be much larger
@@ -627,7 +856,7 @@ impl Archive {
                     );
 
                     // Check if it would extend beyond file
-                    let file_size = self.reader.get_ref().metadata()?.len();
+                    let file_size = source_len(&mut self.reader)?;
                     if block_table_offset + compressed_size > file_size {
                         log::warn!("Block table extends beyond file, skipping");
                     } else {
@@ -673,7 +902,7 @@ impl Archive {
             if self.block_table.is_none() {
                 // For V3 and earlier, or V4 with invalid sizes, we need to detect if tables are compressed
                 // Calculate available space for block table
-                let file_size = self.reader.get_ref().metadata()?.len();
+                let file_size = source_len(&mut self.reader)?;
                 let next_section = if let Some(hi_block_pos) = self.header.hi_block_table_pos {
                     if hi_block_pos != 0 {
                         self.archive_offset + hi_block_pos
@@ -761,7 +990,7 @@ impl Archive {
             let hi_block_offset = self.archive_offset + hi_block_pos;
             let hi_block_end = hi_block_offset + (self.header.block_table_size as u64 * 8);
 
-            let file_size = self.reader.get_ref().metadata()?.len();
+            let file_size = source_len(&mut self.reader)?;
             if hi_block_end > file_size {
                 log::warn!(
                     "Hi-block table extends beyond file (ends at 0x{hi_block_end:X}, file size 0x{file_size:X}). Skipping."
@@ -797,12 +1026,20 @@ impl Archive {
         self.user_data.as_ref()
     }
 
+    /// Get the Warcraft III map header if the archive is a `.w3m` or `.w3x`
+    /// map
+    pub fn map_header(&self) -> Option<&W3MapHeader> {
+        self.map_header.as_ref()
+    }
+
     /// Get the archive offset in the file
     pub fn archive_offset(&self) -> u64 {
         self.archive_offset
     }
 
     /// Get the path to the archive
+    ///
+    /// Empty for archives opened with [`Archive::from_reader`].
     pub fn path(&self) -> &Path {
         &self.path
     }
@@ -950,7 +1187,7 @@ impl Archive {
 
         // Get file size
         log::debug!("Getting file size");
-        let file_size = self.reader.get_ref().metadata()?.len();
+        let file_size = source_len(&mut self.reader)?;
 
         // Count files
         let file_count = if let Some(bet) = &self.bet_table {
@@ -1038,9 +1275,10 @@ impl Archive {
                 {
                     let mut temp_archive = Self {
                         path: self.path.clone(),
-                        reader: temp_reader,
+                        reader: Box::new(temp_reader),
                         archive_offset: self.archive_offset,
                         user_data: self.user_data.clone(),
+                        map_header: self.map_header.clone(),
                         header: self.header.clone(),
                         hash_table: None,
                         block_table: None,
@@ -1048,6 +1286,11 @@ impl Archive {
                         het_table: None,
                         bet_table: None,
                         attributes: None,
+                        recorded_names: None,
+                        path_policy: PathPolicy::LENIENT,
+                        listed_names: None,
+                        locale: 0,
+                        read_cache: None,
                     };
 
                     if let Ok(size) = temp_archive.read_het_table_size(pos) {
@@ -1081,9 +1324,10 @@ impl Archive {
                 {
                     let mut temp_archive = Self {
                         path: self.path.clone(),
-                        reader: temp_reader,
+                        reader: Box::new(temp_reader),
                         archive_offset: self.archive_offset,
                         user_data: self.user_data.clone(),
+                        map_header: self.map_header.clone(),
                         header: self.header.clone(),
                         hash_table: None,
                         block_table: None,
@@ -1091,6 +1335,11 @@ impl Archive {
                         het_table: None,
                         bet_table: None,
                         attributes: None,
+                        recorded_names: None,
+                        path_policy: PathPolicy::LENIENT,
+                        listed_names: None,
+                        locale: 0,
+                        read_cache: None,
                     };
 
                     if let Ok(size) = temp_archive.read_bet_table_size(pos) {
@@ -1184,7 +1433,125 @@ impl Archive {
     }
 
     /// Find a file in the archive
+    ///
+    /// Under a non-lenient [`PathPolicy`], a file the `(listfile)` names is
+    /// only found by a name the policy matches against the listed spelling.
     pub fn find_file(&self, filename: &str) -> Result<Option<FileInfo>> {
+        self.find_file_locale(filename, self.locale)
+    }
+
+    /// Find a file, preferring its entry for `locale`
+    ///
+    /// Files of localized archives can be stored once per locale under the
+    /// same name. Like the game, the lookup takes the entry of `locale` if
+    /// there is one, else the neutral entry (locale 0), else the first entry
+    /// of any locale; [`FileInfo::locale`] tells which was found. Archives
+    /// with only HET/BET tables store no locales.
+    pub fn find_file_locale(&self, filename: &str, locale: u16) -> Result<Option<FileInfo>> {
+        if let Some(listed) = &self.listed_names
+            && !(filename.starts_with('(') && filename.ends_with(')'))
+            && let Some(spellings) = listed.get(&hash_key(filename))
+            && !spellings
+                .iter()
+                .any(|stored| self.path_policy.matches(filename, stored))
+        {
+            return Ok(None);
+        }
+        self.find_file_by_hash(filename, locale)
+    }
+
+    /// Locale preferred by name lookups
+    pub fn locale(&self) -> u16 {
+        self.locale
+    }
+
+    /// Set the locale preferred by name lookups
+    ///
+    /// [`Archive::find_file`], [`Archive::read_file`] and the operations
+    /// built on them pick a file's entry for this locale over the neutral
+    /// one, as the game does with its configured locale. Locale codes are
+    /// Windows language ids, e.g. `0x407` for deDE; 0 prefers the neutral
+    /// entry.
+    pub fn set_locale(&mut self, locale: u16) {
+        self.locale = locale;
+    }
+
+    /// Keep decoded file data so that repeated reads skip decryption and
+    /// decompression
+    ///
+    /// Compressed files are cached sector by sector, which also serves
+    /// [`FileStream`](crate::FileStream) reads; other files are cached
+    /// whole. Enabling the cache again replaces it, dropping its entries.
+    /// See the [`read_cache`](crate::read_cache) module.
+    pub fn enable_read_cache(&mut self, config: ReadCacheConfig) {
+        self.read_cache = Some(ReadCache::new(config));
+    }
+
+    /// Stop caching decoded file data and drop what is cached
+    pub fn disable_read_cache(&mut self) {
+        self.read_cache = None;
+    }
+
+    /// Drop all cached data, keeping the cache enabled
+    pub fn clear_read_cache(&mut self) {
+        if let Some(cache) = &mut self.read_cache {
+            cache.clear();
+        }
+    }
+
+    /// Hit and miss counters of the read cache, if it is enabled
+    pub fn read_cache_stats(&self) -> Option<ReadCacheStats> {
+        self.read_cache.as_ref().map(ReadCache::stats)
+    }
+
+    /// Decoded data cached under `key`
+    pub(crate) fn cached_data(&mut self, key: &SectorKey) -> Option<Vec<u8>> {
+        self.read_cache
+            .as_mut()
+            .and_then(|cache| cache.get(key))
+            .map(<[u8]>::to_vec)
+    }
+
+    /// Cache decoded data under `key` if the cache is enabled
+    pub(crate) fn cache_data(&mut self, key: SectorKey, data: &[u8]) {
+        if let Some(cache) = &mut self.read_cache {
+            cache.insert(key, data);
+        }
+    }
+
+    /// Current policy for name lookups
+    pub fn path_policy(&self) -> PathPolicy {
+        self.path_policy
+    }
+
+    /// Set how name lookups treat case and path separators
+    ///
+    /// The name hash cannot tell `units\orc.mdx` from `Units/Orc.MDX`, so a
+    /// non-lenient policy checks requested names against the spellings in the
+    /// `(listfile)`, which is read here. Names the listfile lacks cannot be
+    /// checked and are still found by hash, as are special files such as
+    /// `(listfile)` itself.
+    pub fn set_path_policy(&mut self, policy: PathPolicy) -> Result<()> {
+        self.path_policy = policy;
+        if policy.is_lenient() {
+            self.listed_names = None;
+            return Ok(());
+        }
+
+        self.listed_names = None;
+        let mut listed: HashMap<String, Vec<String>> = HashMap::new();
+        if self.find_file("(listfile)")?.is_some() {
+            let data = self.read_file("(listfile)")?;
+            for name in special_files::parse_listfile(&data)? {
+                listed.entry(hash_key(&name)).or_default().push(name);
+            }
+        }
+        self.listed_names = Some(listed);
+        Ok(())
+    }
+
+    /// Look a file up by name hash alone
+    fn find_file_by_hash(&self, filename: &str, locale: u16) -> Result<Option<FileInfo>> {
         // Check if this is a special file that should be searched in both table types
         let is_special_file = matches!(
             filename,
@@ -1255,11 +1622,11 @@ impl Archive {
         // 2. HET/BET tables are empty/invalid
         // 3. File wasn't found in HET/BET but we're looking for a special file
         // 4. File wasn't found in HET/BET but hash/block tables exist
-        self.find_file_classic(filename)
+        self.find_file_classic(filename, locale)
     }
 
     /// Classic file lookup using hash/block tables
-    fn find_file_classic(&self, filename: &str) -> Result<Option<FileInfo>> {
+    fn find_file_classic(&self, filename: &str, locale: u16) -> Result<Option<FileInfo>> {
         // If tables aren't loaded, return None instead of error
         // This is common for V3+ archives that only have HET/BET tables
         let hash_table = match self.hash_table.as_ref() {
@@ -1271,8 +1638,7 @@ impl Archive {
             None => return Ok(None),
         };
 
-        // Try to find the file with default locale
-        if let Some((hash_index, hash_entry)) = hash_table.find_file(filename, 0) {
+        if let Some((hash_index, hash_entry)) = hash_table.find_file(filename, locale) {
             let block_entry = block_table
                 .get(hash_entry.block_index as usize)
                 .ok_or_else(|| Error::block_table("Invalid block index"))?;
@@ -1418,6 +1784,73 @@ impl Archive {
         Ok(entries)
     }
 
+    /// List files, naming them from an external listfile as well
+    ///
+    /// Community archives often ship without a (listfile). The external one
+    /// may be a plain listfile or a `FileDataID;path` CSV listfile; its names
+    /// are matched against the archive by hash, as StormLib does. Names from
+    /// the archive's own (listfile) are kept.
+    pub fn list_with_external_listfile<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+    ) -> Result<Vec<FileEntry>> {
+        let data = std::fs::read(path.as_ref())?;
+        let names = special_files::parse_external_listfile(&data)?;
+        let mut entries = self.list()?;
+        self.apply_listfile_names(&mut entries, names)?;
+        Ok(entries)
+    }
+
+    /// Name listed entries from candidate filenames
+    ///
+    /// Names that resolve in the archive are added for files missing from
+    /// `entries`, or replace generated names such as `file_00000012.dat`.
+    /// Names that already resolve are kept. Returns the number of files
+    /// named.
+    pub fn apply_listfile_names<I>(&self, entries: &mut Vec<FileEntry>, names: I) -> Result<usize>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let block_of = |entry: &FileEntry| {
+            entry
+                .table_indices
+                .map(|(hash_index, block_index)| block_index.unwrap_or(hash_index))
+        };
+        let mut by_block: HashMap<usize, usize> = entries
+            .iter()
+            .enumerate()
+            .filter_map(|(index, entry)| Some((block_of(entry)?, index)))
+            .collect();
+
+        let mut named = 0;
+        for name in names {
+            let Some(info) = self.find_file(&name)? else {
+                continue;
+            };
+            match by_block.get(&info.block_index) {
+                Some(&index) => {
+                    if self.find_file(&entries[index].name)?.is_none() {
+                        entries[index].name = name;
+                        named += 1;
+                    }
+                }
+                None => {
+                    by_block.insert(info.block_index, entries.len());
+                    entries.push(FileEntry {
+                        name,
+                        size: info.file_size,
+                        compressed_size: info.compressed_size,
+                        flags: info.flags,
+                        hashes: None,
+                        table_indices: Some((info.hash_index, Some(info.block_index))),
+                    });
+                    named += 1;
+                }
+            }
+        }
+        Ok(named)
+    }
+
     /// List all files in the archive by enumerating tables
     /// This shows all entries, using generic names for files not in listfile
     pub fn list_all(&mut self) -> Result<Vec<FileEntry>> {
@@ -1590,10 +2023,68 @@ impl Archive {
 
     /// Read a file from the archive
     pub fn read_file(&mut self, name: &str) -> Result<Vec<u8>> {
+        self.read_file_locale(name, self.locale)
+    }
+
+    /// Read a file, preferring its entry for `locale`
+    ///
+    /// See [`Archive::find_file_locale`] for how the entry is picked.
+    pub fn read_file_locale(&mut self, name: &str, locale: u16) -> Result<Vec<u8>> {
         let file_info = self
-            .find_file(name)?
+            .find_file_locale(name, locale)?
             .ok_or_else(|| Error::FileNotFound(name.to_string()))?;
+        let data = self.read_file_with_info(name, &file_info)?;
+        if let Some(recorder) = &mut self.recorded_names {
+            recorder.record(name);
+        }
+        Ok(data)
+    }
+
+    /// Read the file `file_info` describes
+    ///
+    /// The info may come from tables other than the loaded ones, such as the
+    /// pending tables of a [`MutableArchive`](crate::MutableArchive).
+    pub(crate) fn read_file_with_info(
+        &mut self,
+        name: &str,
+        file_info: &FileInfo,
+    ) -> Result<Vec<u8>> {
+        let key = self.file_key(name, file_info, file_info.file_size as u32);
+        self.read_file_with_key(name, file_info, key)
+    }
+
+    /// Read the file `file_info` describes, decrypting it with `key`
+    ///
+    /// `name` only appears in errors and logs, so files whose name is
+    /// unknown can be read with a key found some other way.
+    pub(crate) fn read_file_with_key(
+        &mut self,
+        name: &str,
+        file_info: &FileInfo,
+        key: u32,
+    ) -> Result<Vec<u8>> {
+        // Sectored files are cached sector by sector while they are read
+        let whole_file = file_info.is_single_unit() || !file_info.is_compressed();
+        if !whole_file || self.read_cache.is_none() || file_info.is_patch_file() {
+            return self.read_file_uncached(name, file_info, key);
+        }
 
+        let cache_key = SectorKey::whole_file(file_info, key);
+        if let Some(data) = self.cached_data(&cache_key) {
+            return Ok(data);
+        }
+        let data = self.read_file_uncached(name, file_info, key)?;
+        self.cache_data(cache_key, &data);
+        Ok(data)
+    }
+
+    /// Read and decode a file, bypassing the cache for files kept whole
+    fn read_file_uncached(
+        &mut self,
+        name: &str,
+        file_info: &FileInfo,
+        key: u32,
+    ) -> Result<Vec<u8>> {
         // Check if this is a patch file - patch files cannot be read directly
         if file_info.is_patch_file() {
             return Err(Error::OperationNotSupported {
@@ -1604,37 +2095,8 @@ impl Archive {
             });
         }
 
-        // For v3+ archives with HET/BET tables, we already have all the info we need in FileInfo
-        // For classic archives, we need to get additional info from the block table
-        let (file_size_for_key, actual_file_size) =
-            if self.het_table.is_some() && self.bet_table.is_some() {
-                // Using HET/BET tables - FileInfo already has all the data
-                (file_info.file_size as u32, file_info.file_size)
-            } else {
-                // Using classic tables - need block entry for accurate sizes
-                let block_table = self
-                    .block_table
-                    .as_ref()
-                    .ok_or_else(|| Error::invalid_format("Block table not loaded"))?;
-                let block_entry = block_table
-                    .get(file_info.block_index)
-                    .ok_or_else(|| Error::block_table("Invalid block index"))?;
-                (block_entry.file_size, block_entry.file_size as u64)
-            };
-
-        // Calculate encryption key if needed
-        let key = if file_info.is_encrypted() {
-            let base_key = hash_string(name, hash_type::FILE_KEY);
-            if file_info.has_fix_key() {
-                // Apply FIX_KEY modification
-                let file_pos = (file_info.file_pos - self.archive_offset) as u32;
-                (base_key.wrapping_add(file_pos)) ^ file_size_for_key
-            } else {
-                base_key
-            }
-        } else {
-            0
-        };
+        // FileInfo carries the block entry's sizes for both table layouts
+        let actual_file_size = file_info.file_size;
 
         // Read the file data
         self.reader.seek(SeekFrom::Start(file_info.file_pos))?;
@@ -1668,7 +2130,13 @@ impl Archive {
                 let expected_crc = u32::from_le_bytes(crc_bytes);
 
                 // CRC is calculated on the decompressed data
-                let data_to_check = if file_info.is_compressed() {
+                let data_to_check = if file_info.is_implode() {
+                    compression::decompress(
+                        &data,
+                        compression::flags::PKWARE,
+                        actual_file_size as usize,
+                    )?
+                } else if file_info.is_compressed() {
                     // We need to decompress first to check CRC
                     let compression_type = data[0];
                     let compressed_data = &data[1..];
@@ -1711,6 +2179,13 @@ impl Archive {
                         // Try treating as uncompressed data first
                         // This handles cases where the COMPRESS flag is set but data is actually uncompressed
                         Ok(data)
+                    } else if file_info.is_implode() {
+                        // IMPLODE files are PKWare DCL streams without a method byte
+                        compression::decompress(
+                            &data,
+                            compression::flags::PKWARE,
+                            actual_file_size as usize,
+                        )
                     } else if let Some(compression_method) = file_info.get_compression_method() {
                         // SINGLE_UNIT files DO have compression method byte prefix!
                         // This was our bug - we thought they didn't
@@ -1756,7 +2231,7 @@ impl Archive {
             }
         } else {
             // Multi-sector compressed file
-            self.read_sectored_file(&file_info, key)
+            self.read_sectored_file(file_info, key)
         }
     }
 
@@ -1798,18 +2273,7 @@ impl Archive {
             };
 
         // Calculate encryption key if needed
-        let key = if file_info.is_encrypted() {
-            let base_key = hash_string(name, hash_type::FILE_KEY);
-            if file_info.has_fix_key() {
-                // Apply FIX_KEY modification
-                let file_pos = (file_info.file_pos - self.archive_offset) as u32;
-                (base_key.wrapping_add(file_pos)) ^ file_size_for_key
-            } else {
-                base_key
-            }
-        } else {
-            0
-        };
+        let key = self.file_key(name, &file_info, file_size_for_key);
 
         // Read the file data
         // Patch files start with TPatchInfo structure (uncompressed metadata)
@@ -2170,19 +2634,42 @@ impl Archive {
         }
     }
 
-    /// Read a file that is split into sectors
-    fn read_sectored_file(&mut self, file_info: &FileInfo, key: u32) -> Result<Vec<u8>> {
-        let sector_size = self.header.sector_size();
-        let sector_count = (file_info.file_size as usize).div_ceil(sector_size);
+    /// Encryption key of a file, or 0 if it is not encrypted
+    pub(crate) fn file_key(&self, name: &str, file_info: &FileInfo, file_size: u32) -> u32 {
+        if !file_info.is_encrypted() {
+            return 0;
+        }
+        let file_pos = (file_info.file_pos - self.archive_offset) as u32;
+        crate::sectors::calculate_file_key(name, file_pos, file_size, file_info.has_fix_key())
+    }
 
-        log::debug!("Reading sectored file:");
-        log::debug!("  file_size: {} bytes", file_info.file_size);
-        log::debug!("  compressed_size: {} bytes", file_info.compressed_size);
-        log::debug!("  sector_size: {} bytes", sector_size);
-        log::debug!("  sector_count: {}", sector_count);
-        log::debug!("  is_patch_file: {}", file_info.is_patch_file());
+    /// Take the reader out of the archive, leaving an empty one behind
+    ///
+    /// The tables stay usable, but reading files through the archive fails
+    /// afterwards.
+    #[cfg(feature = "async")]
+    pub(crate) fn take_source(&mut self) -> Box<dyn ArchiveSource> {
+        std::mem::replace(&mut self.reader, Box::new(std::io::Cursor::new(Vec::new())))
+    }
 
-        // Read sector offset table
+    /// Read raw bytes at an absolute position in the archive file
+    pub(crate) fn read_raw(&mut self, pos: u64, len: usize) -> Result<Vec<u8>> {
+        self.reader.seek(SeekFrom::Start(pos))?;
+        let mut data = vec![0u8; len];
+        self.reader.read_exact(&mut data)?;
+        Ok(data)
+    }
+
+    /// Read and decrypt the sector offset table of a compressed sectored file
+    ///
+    /// Offsets are relative to `file_info.file_pos`; the reader is left
+    /// directly after the table.
+    pub(crate) fn read_sector_offsets(
+        &mut self,
+        file_info: &FileInfo,
+        key: u32,
+    ) -> Result<Vec<u32>> {
+        let sector_count = (file_info.file_size as usize).div_ceil(self.header.sector_size());
         self.reader.seek(SeekFrom::Start(file_info.file_pos))?;
         let offset_table_size = (sector_count + 1) * 4;
         log::debug!("  offset_table_size: {} bytes", offset_table_size);
@@ -2214,6 +2701,36 @@ impl Archive {
         for _ in 0..=sector_count {
             sector_offsets.push(cursor.read_u32::<LittleEndian>()?);
         }
+        Ok(sector_offsets)
+    }
+
+    /// Read a file that is split into sectors
+    fn read_sectored_file(&mut self, file_info: &FileInfo, key: u32) -> Result<Vec<u8>> {
+        let sector_size = self.header.sector_size();
+        let sector_count = (file_info.file_size as usize).div_ceil(sector_size);
+
+        log::debug!("Reading sectored file:");
+        log::debug!("  file_size: {} bytes", file_info.file_size);
+        log::debug!("  compressed_size: {} bytes", file_info.compressed_size);
+        log::debug!("  sector_size: {} bytes", sector_size);
+        log::debug!("  sector_count: {}", sector_count);
+        log::debug!("  is_patch_file: {}", file_info.is_patch_file());
+
+        // Fully cached files need neither the offset table nor the checksums
+        if let Some(cache) = &self.read_cache
+            && (0..sector_count).all(|i| cache.contains(&SectorKey::sector(file_info, key, i)))
+        {
+            let mut data = Vec::with_capacity(file_info.file_size as usize);
+            for i in 0..sector_count {
+                if let Some(sector) = self.cached_data(&SectorKey::sector(file_info, key, i)) {
+                    data.extend_from_slice(&sector);
+                }
+            }
+            return Ok(data);
+        }
+
+        let sector_offsets = self.read_sector_offsets(file_info, key)?;
+        let offset_table_size = sector_offsets.len() * 4;
 
         log::debug!(
             "Sector offsets: first={}, last={}",
@@ -2275,6 +2792,12 @@ impl Archive {
         let mut sector_buffer = vec![0u8; max_sector_size];
 
         for i in 0..sector_count {
+            let cache_key = SectorKey::sector(file_info, key, i);
+            if let Some(sector) = self.cached_data(&cache_key) {
+                decompressed_data.extend_from_slice(&sector);
+                continue;
+            }
+
             let sector_start = sector_offsets[i] as u64;
             let sector_end = sector_offsets[i + 1] as u64;
 
@@ -2320,12 +2843,6 @@ impl Archive {
                 );
             }
 
-            // Decrypt sector if needed
-            if file_info.is_encrypted() {
-                let sector_key = key.wrapping_add(i as u32);
-                decrypt_file_data(sector_data, sector_key);
-            }
-
             // Validate CRC if present - MUST be done AFTER decryption but BEFORE decompression
             // Skip CRC validation for now due to decryption key issues in some archives
             if let Some(ref _crcs) = sector_crcs {
@@ -2334,47 +2851,8 @@ impl Archive {
                 log::trace!("Skipping CRC validation for sector {i}");
             }
 
-            // Decompress sector
-            let decompressed_sector = if file_info.is_compressed()
-                && sector_size_compressed < expected_size
-            {
-                if !sector_data.is_empty() {
-                    // Check if this is IMPLODE compression (no compression type prefix)
-                    if file_info.is_implode() {
-                        // IMPLODE compression - no compression type byte prefix
-                        match compression::decompress(sector_data, 0x08, expected_size) {
-                            Ok(decompressed) => decompressed,
-                            Err(e) => {
-                                log::warn!(
-                                    "Failed to decompress IMPLODE sector {i}: {e}. Using zeros."
-                                );
-                                vec![0u8; expected_size]
-                            }
-                        }
-                    } else {
-                        // COMPRESS flag - has compression type byte prefix
-                        let compression_type = sector_data[0];
-                        let compressed_data = &sector_data[1..];
-                        match compression::decompress(
-                            compressed_data,
-                            compression_type,
-                            expected_size,
-                        ) {
-                            Ok(decompressed) => decompressed,
-                            Err(e) => {
-                                log::warn!("Failed to decompress sector {i}: {e}. Using zeros.");
-                                vec![0u8; expected_size]
-                            }
-                        }
-                    }
-                } else {
-                    log::warn!("Empty compressed sector data for sector {i}. Using zeros.");
-                    vec![0u8; expected_size]
-                }
-            } else {
-                // Sector is not compressed
-                sector_data[..expected_size.min(sector_data.len())].to_vec()
-            };
+            let decompressed_sector = decode_sector(file_info, sector_data, i, key, expected_size);
+            self.cache_data(cache_key, &decompressed_sector);
 
             decompressed_data.extend_from_slice(&decompressed_sector);
         }
@@ -2577,7 +3055,8 @@ impl Archive {
     /// Verify the digital signature of the archive
     pub fn verify_signature(&mut self) -> Result<SignatureStatus> {
         // First check for strong signature (external to archive)
-        if let Ok(strong_status) = self.verify_strong_signature()
+        if let Ok(strong_status) = crate::crypto::public_keys::strong_public_key()
+            .and_then(|key| self.verify_strong_signature_with_key(&key))
             && strong_status != SignatureStatus::None
         {
             return Ok(strong_status);
@@ -2754,14 +3233,23 @@ impl Archive {
         }
     }
 
-    /// Verify strong signature appended after the archive
-    fn verify_strong_signature(&mut self) -> Result<SignatureStatus> {
+    /// Verify the strong signature appended after the archive against a
+    /// specific public key
+    ///
+    /// [`verify_signature`](Self::verify_signature) checks strong signatures
+    /// against Blizzard's key. Archives signed with a custom key, see
+    /// [`ArchiveBuilder::strong_signature`](crate::ArchiveBuilder::strong_signature),
+    /// verify here with the matching public key.
+    pub fn verify_strong_signature_with_key(
+        &mut self,
+        public_key: &crate::crypto::RsaPublicKey,
+    ) -> Result<SignatureStatus> {
         use crate::crypto::{
-            STRONG_SIGNATURE_SIZE, parse_strong_signature, verify_strong_signature,
+            STRONG_SIGNATURE_SIZE, parse_strong_signature, verify_strong_signature_with_key,
         };
 
         // Get total file size
-        let file_size = self.reader.get_ref().metadata()?.len();
+        let file_size = source_len(&mut self.reader)?;
 
         // Calculate expected archive end position
         let archive_end = self.archive_offset + self.header.get_archive_size();
@@ -2789,10 +3277,11 @@ impl Archive {
                         self.reader.seek(SeekFrom::Start(self.archive_offset))?;
 
                         // Verify the strong signature
-                        match verify_strong_signature(
+                        match verify_strong_signature_with_key(
                             &mut self.reader,
                             &strong_sig,
                             archive_end - self.archive_offset,
+                            public_key,
                         ) {
                             Ok(true) => {
                                 log::info!("Strong signature verification successful");
@@ -2823,6 +3312,130 @@ impl Archive {
     }
 }
 
+/// Decrypt and decompress sector `index` of a sectored file
+///
+/// Sectors that fail to decompress are logged and come back as zeros, so one
+/// damaged sector does not make the rest of the file unreadable.
+pub(crate) fn decode_sector(
+    file_info: &FileInfo,
+    sector_data: &mut [u8],
+    index: usize,
+    key: u32,
+    expected_size: usize,
+) -> Vec<u8> {
+    // Decrypt sector if needed
+    if file_info.is_encrypted() {
+        let sector_key = key.wrapping_add(index as u32);
+        decrypt_file_data(sector_data, sector_key);
+    }
+
+    // Decompress sector
+    if file_info.is_compressed() && sector_data.len() < expected_size {
+        if !sector_data.is_empty() {
+            // Check if this is IMPLODE compression (no compression type prefix)
+            if file_info.is_implode() {
+                // IMPLODE compression - no compression type byte prefix
+                match compression::decompress(sector_data, 0x08, expected_size) {
+                    Ok(decompressed) => decompressed,
+                    Err(e) => {
+                        log::warn!(
+                            "Failed to decompress IMPLODE sector {index}: {e}. Using zeros."
+                        );
+                        vec![0u8; expected_size]
+                    }
+                }
+            } else {
+                // COMPRESS flag - has compression type byte prefix
+                let compression_type = sector_data[0];
+                let compressed_data = &sector_data[1..];
+                match compression::decompress(compressed_data, compression_type, expected_size) {
+                    Ok(decompressed) => decompressed,
+                    Err(e) => {
+                        log::warn!("Failed to decompress sector {index}: {e}. Using zeros.");
+                        vec![0u8; expected_size]
+                    }
+                }
+            }
+        } else {
+            log::warn!("Empty compressed sector data for sector {index}. Using zeros.");
+            vec![0u8; expected_size]
+        }
+    } else {
+        // Sector is not compressed
+        sector_data[..expected_size.min(sector_data.len())].to_vec()
+    }
+}
+
+/// Decrypt and decompress a file from its stored bytes
+///
+/// `stored` is the file as laid out in the archive, starting at
+/// `file_info.file_pos`, including the sector offset table of compressed
+/// sectored files. Used where the bytes are read without the archive's own
+/// reader, such as by [`AsyncArchive`](crate::AsyncArchive).
+#[cfg(feature = "async")]
+pub(crate) fn decode_stored_file(
+    file_info: &FileInfo,
+    key: u32,
+    sector_size: usize,
+    mut stored: Vec<u8>,
+) -> Result<Vec<u8>> {
+    let file_size = file_info.file_size as usize;
+
+    if file_info.is_single_unit() {
+        if file_info.is_encrypted() {
+            decrypt_file_data(&mut stored, key);
+        }
+        if !file_info.is_compressed() || stored.len() == file_size {
+            stored.truncate(file_size);
+            return Ok(stored);
+        }
+        return match stored.split_first() {
+            _ if file_info.is_implode() => compression::decompress(&stored, 0x08, file_size),
+            Some((&method, compressed)) => compression::decompress(compressed, method, file_size),
+            None => Err(Error::compression("Empty compressed data")),
+        };
+    }
+
+    if !file_info.is_compressed() {
+        if file_info.is_encrypted() {
+            for (index, sector) in stored.chunks_mut(sector_size).enumerate() {
+                decrypt_file_data(sector, key.wrapping_add(index as u32));
+            }
+        }
+        stored.truncate(file_size);
+        return Ok(stored);
+    }
+
+    let sector_count = file_size.div_ceil(sector_size);
+    let mut table = stored
+        .get(..(sector_count + 1) * 4)
+        .ok_or_else(|| Error::invalid_format("Sector offset table exceeds the stored file"))?
+        .to_vec();
+    if file_info.is_encrypted() {
+        decrypt_file_data(&mut table, key.wrapping_sub(1));
+    }
+    let offsets: Vec<usize> = table
+        .chunks_exact(4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+        .collect();
+
+    let mut data = Vec::with_capacity(file_size);
+    for index in 0..sector_count {
+        let expected_size = (file_size - data.len()).min(sector_size);
+        let (start, end) = (offsets[index], offsets[index + 1]);
+        if end < start {
+            log::warn!("Invalid sector offsets for sector {index}: start={start}, end={end}");
+            data.resize(data.len() + expected_size, 0);
+            continue;
+        }
+        let sector = stored.get_mut(start..end).ok_or_else(|| {
+            Error::invalid_format(format!("Sector {index} exceeds the stored file"))
+        })?;
+        data.extend(decode_sector(file_info, sector, index, key, expected_size));
+    }
+    Ok(data)
+}
+
 /// Decrypt file data in-place
 pub fn decrypt_file_data(data: &mut [u8], key: u32) {
     if data.is_empty() || key == 0 {
@@ -2938,6 +3551,12 @@ impl FileInfo {
         (self.flags & BlockEntry::FLAG_PATCH_FILE) != 0
     }
 
+    /// Check if the entry is a deletion marker rather than a file
+    pub fn is_delete_marker(&self) -> bool {
+        use crate::tables::BlockEntry;
+        (self.flags & BlockEntry::FLAG_DELETE_MARKER) != 0
+    }
+
     /// Check if the file uses IMPLODE compression specifically
     pub fn is_implode(&self) -> bool {
         use crate::tables::BlockEntry;
@@ -3046,6 +3665,12 @@ impl FileEntry {
         use crate::tables::BlockEntry;
         (self.flags & BlockEntry::FLAG_PATCH_FILE) != 0
     }
+
+    /// Check if the entry is a deletion marker rather than a file
+    pub fn is_delete_marker(&self) -> bool {
+        use crate::tables::BlockEntry;
+        (self.flags & BlockEntry::FLAG_DELETE_MARKER) != 0
+    }
 }
 
 #[cfg(test)]
@@ -3138,4 +3763,76 @@ mod tests {
         // This is the expected ADLER32 value for "Hello, World!"
         assert_eq!(crc, 0x1F9E046A);
     }
+
+    #[test]
+    fn test_archive_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Archive>();
+        assert_send_sync::<crate::stream::FileStream<'static>>();
+    }
+
+    #[test]
+    fn test_open_from_reader() {
+        let mut data = b"leading bytes".to_vec();
+        data.resize(0x200, 0);
+        let temp = tempfile::NamedTempFile::new().unwrap();
+        crate::ArchiveBuilder::new()
+            .add_file_data(b"in memory".to_vec(), "Data\\File.txt")
+            .build(temp.path())
+            .unwrap();
+        data.extend(std::fs::read(temp.path()).unwrap());
+
+        // The header is found past the leading bytes, as in a file
+        let mut archive = Archive::from_reader(std::io::Cursor::new(data)).unwrap();
+        assert_eq!(archive.archive_offset(), 0x200);
+        assert!(archive.path().as_os_str().is_empty());
+        assert_eq!(archive.read_file("Data\\File.txt").unwrap(), b"in memory");
+
+        let dir = tempfile::tempdir().unwrap();
+        let summary = archive
+            .extract_all(dir.path(), crate::ExtractOptions::default())
+            .unwrap();
+        assert_eq!(summary.extracted, 2);
+        assert_eq!(
+            std::fs::read(dir.path().join("Data").join("File.txt")).unwrap(),
+            b"in memory"
+        );
+    }
+
+    #[test]
+    fn test_locale_lookup() {
+        const DE_DE: u16 = 0x407;
+        const EN_US: u16 = 0x409;
+        let temp = tempfile::NamedTempFile::new().unwrap();
+        crate::ArchiveBuilder::new()
+            .add_file_data_with_options(b"neutral".to_vec(), "Sound\\Hello.wav", 0, false, 0)
+            .add_file_data_with_options(b"deutsch".to_vec(), "Sound\\Hello.wav", 0, false, DE_DE)
+            .add_file_data_with_options(b"nur".to_vec(), "Sound\\Only.wav", 0, false, DE_DE)
+            .build(temp.path())
+            .unwrap();
+        let data = std::fs::read(temp.path()).unwrap();
+
+        let mut archive = Archive::from_reader(std::io::Cursor::new(data.clone())).unwrap();
+        assert_eq!(archive.read_file("Sound\\Hello.wav").unwrap(), b"neutral");
+        assert_eq!(
+            archive.read_file_locale("Sound\\Hello.wav", DE_DE).unwrap(),
+            b"deutsch"
+        );
+        // Missing locales fall back to the neutral entry, then to any entry
+        assert_eq!(
+            archive.read_file_locale("Sound\\Hello.wav", EN_US).unwrap(),
+            b"neutral"
+        );
+        let only = archive.find_file("Sound\\Only.wav").unwrap().unwrap();
+        assert_eq!(only.locale, DE_DE);
+
+        archive.set_locale(DE_DE);
+        assert_eq!(archive.read_file("Sound\\Hello.wav").unwrap(), b"deutsch");
+
+        let options = OpenOptions::new().locale(DE_DE);
+        let mut archive =
+            Archive::from_reader_with_options(std::io::Cursor::new(data), options).unwrap();
+        assert_eq!(archive.locale(), DE_DE);
+        assert_eq!(archive.read_file("Sound\\Hello.wav").unwrap(), b"deutsch");
+    }
 }
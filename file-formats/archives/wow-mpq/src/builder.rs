@@ -1,17 +1,24 @@
 //! Archive builder for creating MPQ archives
 
 use crate::{
-    Error, Result,
-    compression::{compress, flags as compression_flags},
-    crypto::{encrypt_block, hash_string, hash_type, het_hash, jenkins_hash},
+    AddFileOptions, Error, ProgressSink, Result,
+    compression::{compress, ensure_standard, flags as compression_flags},
+    crypto::{
+        RsaPrivateKey, StrongSignatureTailType, encrypt_block, generate_strong_signature,
+        hash_string, hash_type, het_hash, jenkins_hash,
+    },
     header::{FormatVersion, MpqHeaderV4Data},
-    special_files::{AttributeFlags, Attributes, FileAttributes},
+    path::path_key,
+    progress::{ProgressStage, ProgressTracker, SinkHandle},
+    special_files::{self, AttributeFlags, Attributes, FileAttributes},
     tables::{BetHeader, BlockEntry, BlockTable, HashEntry, HashTable, HetHeader, HiBlockTable},
 };
 use md5::{Digest, Md5};
+use std::collections::HashMap;
 use std::fs::{self};
 use std::io::{Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use tempfile::NamedTempFile;
 
 /// Helper trait for writing little-endian integers
@@ -49,6 +56,25 @@ struct PendingFile {
     use_fix_key: bool,
     /// Locale code
     locale: u16,
+    /// Whether to store per-sector checksums
+    sector_crc: bool,
+    /// Whether this is a deletion marker without data
+    delete_marker: bool,
+}
+
+impl PendingFile {
+    fn with_options(source: FileSource, archive_name: &str, options: &AddFileOptions) -> Self {
+        Self {
+            source,
+            archive_name: crate::path::normalize_mpq_path(archive_name),
+            compression: options.compression.to_flags(),
+            encrypt: options.encrypt,
+            use_fix_key: options.fix_key,
+            locale: options.locale,
+            sector_crc: options.sector_crc,
+            delete_marker: false,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -69,12 +95,29 @@ struct FileWriteParams<'a> {
     encrypt: bool,
     /// Whether to use FIX_KEY encryption
     use_fix_key: bool,
+    /// Whether to store per-sector checksums
+    sector_crc: bool,
+    /// Whether to write a deletion marker instead of the data
+    delete_marker: bool,
     /// Sector size
     sector_size: usize,
     /// File position in archive (64-bit for large archives)
     file_pos: u64,
 }
 
+/// Data already written for a file, shared by later identical files
+#[derive(Clone)]
+struct WrittenFile {
+    file_pos: u64,
+    compressed_size: usize,
+    flags: u32,
+    attributes: FileAttributes,
+}
+
+/// Identifies files whose stored data can be shared: content digest, size and
+/// compression method
+type DedupKey = ([u8; 16], usize, u8);
+
 /// Parameters for writing the MPQ header
 struct HeaderWriteParams {
     archive_size: u64,
@@ -98,6 +141,13 @@ pub enum ListfileOption {
     Generate,
     /// Use external listfile
     External(PathBuf),
+    /// Generate a listfile that spells names as an external listfile does
+    ///
+    /// The external listfile (plain text or `FileDataID;path` CSV) is matched
+    /// against the added files by name hash, as StormLib does, so only the
+    /// names of files in the archive are written. Added files it does not
+    /// list keep the name they were added with.
+    Resolve(PathBuf),
     /// Don't include a listfile
     None,
 }
@@ -176,6 +226,16 @@ pub struct ArchiveBuilder {
     compress_tables: bool,
     /// Compression method for tables
     table_compression: u8,
+    /// Whether files with identical contents share their stored data
+    deduplicate: bool,
+    /// Key for the strong signature appended after the archive
+    strong_signature_key: Option<RsaPrivateKey>,
+    /// Receiver of per-file progress while writing
+    progress: SinkHandle,
+    /// Whether the output depends only on the files and settings
+    deterministic: bool,
+    /// Whether compression methods game clients cannot read are refused
+    strict_compatibility: bool,
 }
 
 impl ArchiveBuilder {
@@ -191,6 +251,11 @@ impl ArchiveBuilder {
             generate_crcs: false,
             compress_tables: false, // Default to uncompressed for compatibility
             table_compression: compression_flags::ZLIB,
+            deduplicate: false,
+            strong_signature_key: None,
+            progress: SinkHandle::default(),
+            deterministic: false,
+            strict_compatibility: true,
         }
     }
 
@@ -226,6 +291,102 @@ impl ArchiveBuilder {
         self
     }
 
+    /// Store files with identical contents only once
+    ///
+    /// Each copy keeps its own hash and block entry, but the block entries of
+    /// later copies point at the data written for the first one. Encrypted
+    /// files are always written separately since their key depends on the
+    /// file name.
+    pub fn deduplicate(mut self, deduplicate: bool) -> Self {
+        self.deduplicate = deduplicate;
+        self
+    }
+
+    /// Produce byte-identical archives from the same files and settings
+    ///
+    /// Files are written in the order of their names (and locales) rather
+    /// than the order they were added, which also fixes their hash table
+    /// slots and the order of the generated `(listfile)`. File times in a
+    /// generated `(attributes)` are zero instead of the build time.
+    /// Compression always runs at fixed levels, so with this set, building
+    /// the same inputs twice yields the same bytes, which lets pipelines
+    /// verify archives by hash.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use wow_mpq::{ArchiveBuilder, AttributesOption};
+    ///
+    /// ArchiveBuilder::new()
+    ///     .deterministic(true)
+    ///     .attributes_option(AttributesOption::GenerateFull)
+    ///     .add_file("build/Custom.lua", "Interface\\FrameXML\\Custom.lua")
+    ///     .build("patch-4.MPQ")?;
+    /// # Ok::<(), wow_mpq::Error>(())
+    /// ```
+    pub fn deterministic(mut self, deterministic: bool) -> Self {
+        self.deterministic = deterministic;
+        self
+    }
+
+    /// Refuse compression methods that game clients cannot read
+    ///
+    /// Enabled by default, so building an archive that uses
+    /// [`compression::flags::ZSTD`](crate::compression::flags::ZSTD) fails
+    /// with [`Error::UnsupportedFeature`]. Disable it for archives that are
+    /// only read by this crate, such as containers in private tooling.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use wow_mpq::{ArchiveBuilder, compression};
+    ///
+    /// ArchiveBuilder::new()
+    ///     .strict_compatibility(false)
+    ///     .default_compression(compression::flags::ZSTD)
+    ///     .add_file("assets/terrain.bin", "terrain.bin")
+    ///     .build("tooling.mpq")?;
+    /// # Ok::<(), wow_mpq::Error>(())
+    /// ```
+    pub fn strict_compatibility(mut self, strict: bool) -> Self {
+        self.strict_compatibility = strict;
+        self
+    }
+
+    /// Sign the archive with a strong signature
+    ///
+    /// After the archive is written, a 260-byte "NGIS" block signed with
+    /// `key` is appended behind it. The key must be a 2048-bit RSA key, such
+    /// as one loaded with
+    /// [`strong_private_key_from_pem`](crate::crypto::strong_private_key_from_pem).
+    /// Clients need the matching public key to verify it, see
+    /// [`Archive::verify_strong_signature_with_key`](crate::Archive::verify_strong_signature_with_key).
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use wow_mpq::ArchiveBuilder;
+    /// use wow_mpq::crypto::strong_private_key_from_pem;
+    ///
+    /// let key = strong_private_key_from_pem(&std::fs::read_to_string("patch-key.pem")?)?;
+    /// ArchiveBuilder::new()
+    ///     .add_file("Interface/FrameXML/Custom.lua", "Interface\\FrameXML\\Custom.lua")
+    ///     .strong_signature(key)
+    ///     .build("patch-4.MPQ")?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn strong_signature(mut self, key: RsaPrivateKey) -> Self {
+        self.strong_signature_key = Some(key);
+        self
+    }
+
+    /// Report each file written to `sink`
+    ///
+    /// Updates have the [`Write`](ProgressStage::Write) stage and count the
+    /// bytes each file takes in the archive; files sharing data with an
+    /// earlier copy add none.
+    pub fn progress(mut self, sink: Arc<dyn ProgressSink>) -> Self {
+        self.progress = SinkHandle(Some(sink));
+        self
+    }
+
     /// Set the default compression method
     pub fn default_compression(mut self, compression: u8) -> Self {
         self.default_compression = compression;
@@ -385,6 +546,8 @@ impl ArchiveBuilder {
             encrypt: false,
             use_fix_key: false,
             locale: 0, // Neutral locale
+            sector_crc: false,
+            delete_marker: false,
         });
         self
     }
@@ -430,6 +593,8 @@ impl ArchiveBuilder {
             encrypt,
             use_fix_key: false,
             locale,
+            sector_crc: false,
+            delete_marker: false,
         });
         self
     }
@@ -467,6 +632,8 @@ impl ArchiveBuilder {
             encrypt: false,
             use_fix_key: false,
             locale: 0,
+            sector_crc: false,
+            delete_marker: false,
         });
         self
     }
@@ -513,6 +680,8 @@ impl ArchiveBuilder {
             encrypt,
             use_fix_key: false,
             locale,
+            sector_crc: false,
+            delete_marker: false,
         });
         self
     }
@@ -533,6 +702,8 @@ impl ArchiveBuilder {
             encrypt: true,
             use_fix_key,
             locale,
+            sector_crc: false,
+            delete_marker: false,
         });
         self
     }
@@ -553,6 +724,82 @@ impl ArchiveBuilder {
             encrypt: true,
             use_fix_key,
             locale,
+            sector_crc: false,
+            delete_marker: false,
+        });
+        self
+    }
+
+    /// Add a file from disk using [`AddFileOptions`]
+    ///
+    /// Applies the compression, encryption, locale and sector checksum
+    /// settings of `options`. `replace_existing` and `platform` only apply to
+    /// [`MutableArchive`](crate::MutableArchive).
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use wow_mpq::{AddFileOptions, ArchiveBuilder};
+    ///
+    /// let builder = ArchiveBuilder::new()
+    ///     .add_file_with("Model.m2", "Creature\\Model\\Model.m2", AddFileOptions::new().sector_crc(true));
+    /// ```
+    pub fn add_file_with<P: AsRef<Path>>(
+        mut self,
+        path: P,
+        archive_name: &str,
+        options: AddFileOptions,
+    ) -> Self {
+        self.pending_files.push(PendingFile::with_options(
+            FileSource::Path(path.as_ref().to_path_buf()),
+            archive_name,
+            &options,
+        ));
+        self
+    }
+
+    /// Add a file from memory using [`AddFileOptions`]
+    ///
+    /// See [`add_file_with`](Self::add_file_with) for how the options apply.
+    pub fn add_file_data_with(
+        mut self,
+        data: Vec<u8>,
+        archive_name: &str,
+        options: AddFileOptions,
+    ) -> Self {
+        self.pending_files.push(PendingFile::with_options(
+            FileSource::Data(data),
+            archive_name,
+            &options,
+        ));
+        self
+    }
+
+    /// Add a deletion marker for a file
+    ///
+    /// The marker is a table entry without data. In a [`PatchChain`](crate::PatchChain)
+    /// it hides the file from every archive of lower priority, which is how
+    /// patch archives remove files.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use wow_mpq::ArchiveBuilder;
+    ///
+    /// ArchiveBuilder::new()
+    ///     .add_deletion_marker("Interface\\Obsolete.blp")
+    ///     .build("patch-2.mpq")?;
+    /// # Ok::<(), wow_mpq::Error>(())
+    /// ```
+    pub fn add_deletion_marker(mut self, archive_name: &str) -> Self {
+        self.pending_files.push(PendingFile {
+            source: FileSource::Data(Vec::new()),
+            archive_name: crate::path::normalize_mpq_path(archive_name),
+            compression: 0,
+            encrypt: false,
+            use_fix_key: false,
+            locale: 0,
+            sector_crc: false,
+            delete_marker: true,
         });
         self
     }
@@ -561,7 +808,9 @@ impl ArchiveBuilder {
     fn calculate_hash_table_size(&self) -> u32 {
         let file_count = self.pending_files.len()
             + match &self.listfile_option {
-                ListfileOption::Generate | ListfileOption::External(_) => 1,
+                ListfileOption::Generate
+                | ListfileOption::External(_)
+                | ListfileOption::Resolve(_) => 1,
                 ListfileOption::None => 0,
             }
             + match &self.attributes_option {
@@ -584,6 +833,67 @@ impl ArchiveBuilder {
 
         // Create a temporary file in the same directory
         let mut temp_file = NamedTempFile::new_in(path.parent().unwrap_or_else(|| Path::new(".")))?;
+        self.write_to(temp_file.as_file_mut())?;
+
+        // Atomically rename temp file to final destination
+        temp_file.persist(path).map_err(|e| Error::Io(e.error))?;
+
+        Ok(())
+    }
+
+    /// Build the archive and write it to `writer`
+    ///
+    /// The archive is assembled in memory and written at the writer's
+    /// current position, so it can go to a buffer, a socket or a file entry
+    /// of another archive without touching disk. The writer does not need to
+    /// seek.
+    pub fn build_to_writer<W: Write>(self, writer: &mut W) -> Result<()> {
+        writer.write_all(&self.build_to_vec()?)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Build the archive in memory and return its bytes
+    ///
+    /// Nothing touches disk, which suits tests, services that return
+    /// archives, and archives nested in other archives.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::io::Cursor;
+    /// use wow_mpq::{Archive, ArchiveBuilder};
+    ///
+    /// let bytes = ArchiveBuilder::new()
+    ///     .add_file_data(b"Hello, Azeroth!".to_vec(), "readme.txt")
+    ///     .build_to_vec()?;
+    ///
+    /// let mut archive = Archive::from_reader(Cursor::new(bytes))?;
+    /// assert_eq!(archive.read_file("readme.txt")?, b"Hello, Azeroth!");
+    /// # Ok::<(), wow_mpq::Error>(())
+    /// ```
+    pub fn build_to_vec(mut self) -> Result<Vec<u8>> {
+        let mut buffer = std::io::Cursor::new(Vec::new());
+        self.write_to(&mut buffer)?;
+        Ok(buffer.into_inner())
+    }
+
+    /// Write the archive, with special files and signature, starting at offset 0
+    fn write_to<F: Read + Write + Seek>(&mut self, file: &mut F) -> Result<()> {
+        if self.strict_compatibility {
+            for pending_file in &self.pending_files {
+                ensure_standard(pending_file.compression)?;
+            }
+            ensure_standard(self.default_compression)?;
+            if self.compress_tables {
+                ensure_standard(self.table_compression)?;
+            }
+        }
+
+        if self.deterministic {
+            // Stable sort, so copies of a name keep the order they were added in
+            self.pending_files
+                .sort_by_cached_key(|file| (path_key(&file.archive_name), file.locale));
+        }
 
         // Add listfile if needed
         self.prepare_listfile()?;
@@ -591,69 +901,79 @@ impl ArchiveBuilder {
         // Add attributes file if needed
         self.prepare_attributes()?;
 
-        // Write the archive directly to the temp file
-        {
-            let file = temp_file.as_file_mut();
-            use std::io::{Seek as _, Write as _};
-
-            // For v3+ archives that need read-back support, we need to write everything
-            // to a buffer first, then copy to file
-            if self.version >= FormatVersion::V3 {
-                // For v3+, we need to write everything to a buffer first
-                // Estimate total size needed to avoid buffer reallocation issues
-                let header_size = self.version.header_size() as usize;
-
-                // Estimate file data size (assuming 10:1 compression ratio average)
-                let estimated_file_data_size: usize = self
-                    .pending_files
-                    .iter()
-                    .map(|f| match &f.source {
-                        FileSource::Data(data) => data.len() / 10 + 1000, // Assume 10:1 compression + overhead
-                        FileSource::Path(_) => 100_000,                   // Conservative estimate
-                    })
-                    .sum();
-
-                // Add table sizes (conservative estimates)
-                let estimated_table_size = self.pending_files.len() * 1000; // Conservative per-file overhead
-
-                let total_estimated_size =
-                    header_size + estimated_file_data_size + estimated_table_size;
+        // For v3+ archives that need read-back support, we need to write everything
+        // to a buffer first, then copy to file
+        if self.version >= FormatVersion::V3 {
+            // For v3+, we need to write everything to a buffer first
+            // Estimate total size needed to avoid buffer reallocation issues
+            let header_size = self.version.header_size() as usize;
+
+            // Estimate file data size (assuming 10:1 compression ratio average)
+            let estimated_file_data_size: usize = self
+                .pending_files
+                .iter()
+                .map(|f| match &f.source {
+                    FileSource::Data(data) => data.len() / 10 + 1000, // Assume 10:1 compression + overhead
+                    FileSource::Path(_) => 100_000,                   // Conservative estimate
+                })
+                .sum();
+
+            // Add table sizes (conservative estimates)
+            let estimated_table_size = self.pending_files.len() * 1000; // Conservative per-file overhead
+
+            let total_estimated_size =
+                header_size + estimated_file_data_size + estimated_table_size;
 
-                log::debug!(
-                    "Pre-allocating buffer of {total_estimated_size} bytes for v3+ archive (header: {header_size}, estimated data: {estimated_file_data_size}, tables: {estimated_table_size})"
-                );
+            log::debug!(
+                "Pre-allocating buffer of {total_estimated_size} bytes for v3+ archive (header: {header_size}, estimated data: {estimated_file_data_size}, tables: {estimated_table_size})"
+            );
 
-                let mut vec = Vec::with_capacity(total_estimated_size);
-                vec.resize(header_size, 0u8);
-                let mut buffer = std::io::Cursor::new(vec);
-                buffer.seek(SeekFrom::Start(header_size as u64))?;
+            let mut vec = Vec::with_capacity(total_estimated_size);
+            vec.resize(header_size, 0u8);
+            let mut buffer = std::io::Cursor::new(vec);
+            buffer.seek(SeekFrom::Start(header_size as u64))?;
 
-                self.write_archive(&mut buffer)?;
+            self.write_archive(&mut buffer)?;
 
-                // Write the buffer to file
-                file.write_all(buffer.get_ref())?;
-                file.flush()?;
-            } else {
-                // For v1/v2, we can write directly
-                self.write_archive(file)?;
-                file.flush()?;
-            }
+            // Write the buffer to file
+            file.write_all(buffer.get_ref())?;
+            file.flush()?;
+        } else {
+            // For v1/v2, we can write directly
+            self.write_archive(file)?;
+            file.flush()?;
         }
 
-        // Atomically rename temp file to final destination
-        temp_file.persist(path).map_err(|e| Error::Io(e.error))?;
+        if let Some(key) = &self.strong_signature_key {
+            let archive_end = file.seek(SeekFrom::End(0))?;
+            append_strong_signature(file, 0, archive_end, key)?;
+        }
 
         Ok(())
     }
 
     /// Prepare the listfile based on the option
     fn prepare_listfile(&mut self) -> Result<()> {
+        let spellings = match &self.listfile_option {
+            ListfileOption::Resolve(path) => {
+                let names = special_files::parse_external_listfile(&fs::read(path)?)?;
+                names
+                    .into_iter()
+                    .map(|name| (listfile_hash(&name), name))
+                    .collect()
+            }
+            _ => HashMap::new(),
+        };
+
         match &self.listfile_option {
-            ListfileOption::Generate => {
+            ListfileOption::Generate | ListfileOption::Resolve(_) => {
                 // Generate listfile content from pending files
                 let mut content = String::new();
                 for file in &self.pending_files {
-                    content.push_str(&file.archive_name);
+                    let name = spellings
+                        .get(&listfile_hash(&file.archive_name))
+                        .unwrap_or(&file.archive_name);
+                    content.push_str(name);
                     content.push('\r');
                     content.push('\n');
                 }
@@ -676,6 +996,8 @@ impl ArchiveBuilder {
                     encrypt: false,
                     use_fix_key: false,
                     locale: 0,
+                    sector_crc: false,
+                    delete_marker: false,
                 });
             }
             ListfileOption::External(path) => {
@@ -689,6 +1011,8 @@ impl ArchiveBuilder {
                     encrypt: false,
                     use_fix_key: false,
                     locale: 0,
+                    sector_crc: false,
+                    delete_marker: false,
                 });
             }
             ListfileOption::None => {}
@@ -718,6 +1042,8 @@ impl ArchiveBuilder {
                     encrypt: false,
                     use_fix_key: false,
                     locale: 0,
+                    sector_crc: false,
+                    delete_marker: false,
                 });
             }
             AttributesOption::None => {}
@@ -775,6 +1101,8 @@ impl ArchiveBuilder {
 
         // Write all files and populate tables
         let mut actual_block_index = 0;
+        let mut written = HashMap::new();
+        let mut progress = self.write_progress(collect_attributes);
         for pending_file in self.pending_files.iter() {
             // Skip (attributes) file if it's being generated - we'll write it later
             if pending_file.archive_name == "(attributes)" && collect_attributes {
@@ -796,16 +1124,22 @@ impl ArchiveBuilder {
                 compression: pending_file.compression,
                 encrypt: pending_file.encrypt,
                 use_fix_key: pending_file.use_fix_key,
+                sector_crc: self.generate_crcs || pending_file.sector_crc,
+                delete_marker: pending_file.delete_marker,
                 sector_size,
                 file_pos,
             };
 
-            let (compressed_size, flags, file_attr) = if collect_attributes {
-                self.write_file_with_attributes(writer, &params)?
-            } else {
-                let (size, flags) = self.write_file(writer, &params)?;
-                (size, flags, FileAttributes::new())
-            };
+            let WrittenFile {
+                file_pos,
+                compressed_size,
+                flags,
+                attributes: file_attr,
+            } = self.write_or_share(writer, &params, collect_attributes, &mut written)?;
+            progress.file_done(
+                &pending_file.archive_name,
+                writer.stream_position()? - params.file_pos,
+            );
 
             // Collect attributes if needed
             if let Some(ref mut attrs) = collected_attributes {
@@ -938,6 +1272,8 @@ impl ArchiveBuilder {
 
         // Write all files and populate block table
         let mut actual_block_index = 0;
+        let mut written = HashMap::new();
+        let mut progress = self.write_progress(collect_attributes);
         for pending_file in self.pending_files.iter() {
             // Skip (attributes) file if it's being generated - we'll write it later
             if pending_file.archive_name == "(attributes)" && collect_attributes {
@@ -959,16 +1295,22 @@ impl ArchiveBuilder {
                 compression: pending_file.compression,
                 encrypt: pending_file.encrypt,
                 use_fix_key: pending_file.use_fix_key,
+                sector_crc: self.generate_crcs || pending_file.sector_crc,
+                delete_marker: pending_file.delete_marker,
                 sector_size,
                 file_pos,
             };
 
-            let (compressed_size, flags, file_attr) = if collect_attributes {
-                self.write_file_with_attributes(writer, &params)?
-            } else {
-                let (size, flags) = self.write_file(writer, &params)?;
-                (size, flags, FileAttributes::new())
-            };
+            let WrittenFile {
+                file_pos,
+                compressed_size,
+                flags,
+                attributes: file_attr,
+            } = self.write_or_share(writer, &params, collect_attributes, &mut written)?;
+            progress.file_done(
+                &pending_file.archive_name,
+                writer.stream_position()? - params.file_pos,
+            );
 
             // Collect attributes if needed
             if let Some(ref mut attrs) = collected_attributes {
@@ -1130,6 +1472,63 @@ impl ArchiveBuilder {
     }
 
     /// Write a single file to the archive and collect attributes
+    /// Write a file, or reuse the data of an identical file written earlier
+    /// Progress tracker for the files the write loops will visit
+    fn write_progress(&self, collect_attributes: bool) -> ProgressTracker<'_> {
+        let total = self
+            .pending_files
+            .iter()
+            .filter(|file| !(collect_attributes && file.archive_name == "(attributes)"))
+            .count();
+        ProgressTracker::new(self.progress.0.as_deref(), ProgressStage::Write, total)
+    }
+
+    fn write_or_share<W: Write>(
+        &self,
+        writer: &mut W,
+        params: &FileWriteParams<'_>,
+        collect_attributes: bool,
+        written: &mut HashMap<DedupKey, WrittenFile>,
+    ) -> Result<WrittenFile> {
+        if params.delete_marker {
+            return Ok(WrittenFile {
+                file_pos: params.file_pos,
+                compressed_size: 0,
+                flags: BlockEntry::FLAG_DELETE_MARKER,
+                attributes: FileAttributes::new(),
+            });
+        }
+
+        let key = (self.deduplicate && !params.encrypt).then(|| {
+            let digest: [u8; 16] = Md5::digest(params.file_data).into();
+            (digest, params.file_data.len(), params.compression)
+        });
+        if let Some(existing) = key.as_ref().and_then(|key| written.get(key)) {
+            log::debug!(
+                "Sharing data of {} with an identical file",
+                params.archive_name
+            );
+            return Ok(existing.clone());
+        }
+
+        let (compressed_size, flags, attributes) = if collect_attributes {
+            self.write_file_with_attributes(writer, params)?
+        } else {
+            let (size, flags) = self.write_file(writer, params)?;
+            (size, flags, FileAttributes::new())
+        };
+        let file = WrittenFile {
+            file_pos: params.file_pos,
+            compressed_size,
+            flags,
+            attributes,
+        };
+        if let Some(key) = key {
+            written.insert(key, file.clone());
+        }
+        Ok(file)
+    }
+
     fn write_file_with_attributes<W: Write>(
         &self,
         writer: &mut W,
@@ -1159,8 +1558,10 @@ impl ArchiveBuilder {
             file_attr.md5 = Some(md5_bytes);
         }
 
-        // File time (use current time for now)
-        if matches!(self.attributes_option, AttributesOption::GenerateFull) {
+        // File time: zero for reproducible output, else the current time
+        if matches!(self.attributes_option, AttributesOption::GenerateFull) && self.deterministic {
+            file_attr.filetime = Some(0);
+        } else if matches!(self.attributes_option, AttributesOption::GenerateFull) {
             // Convert current time to Windows FILETIME (100-nanosecond intervals since 1601-01-01)
             use std::time::{SystemTime, UNIX_EPOCH};
             let duration = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
@@ -1186,6 +1587,8 @@ impl ArchiveBuilder {
             compression,
             encrypt,
             use_fix_key,
+            sector_crc,
+            delete_marker: _,
             sector_size,
             file_pos,
         } = params;
@@ -1198,14 +1601,15 @@ impl ArchiveBuilder {
             flags |= BlockEntry::FLAG_SINGLE_UNIT;
 
             // Set CRC flag early if enabled (needed for encryption key calculation)
-            if self.generate_crcs {
+            if *sector_crc {
                 flags |= BlockEntry::FLAG_SECTOR_CRC;
             }
 
             // Compress if needed
             let compressed_data = if *compression != 0 && !file_data.is_empty() {
                 log::debug!("Compressing {archive_name} with method 0x{compression:02X}");
-                let compressed = compress(file_data, *compression)?;
+                let compressed =
+                    compress_unit(file_data, sector_compression(*compression, 0, file_data))?;
 
                 // The compress function now handles the compression byte prefix
                 // and only returns compressed data if it's beneficial
@@ -1216,7 +1620,7 @@ impl ArchiveBuilder {
                         file_data.len(),
                         compressed.len()
                     );
-                    flags |= BlockEntry::FLAG_COMPRESS;
+                    flags |= compression_block_flag(*compression);
                     compressed
                 } else {
                     // Compression not beneficial, returned original data
@@ -1246,7 +1650,7 @@ impl ArchiveBuilder {
             writer.write_all(&final_data)?;
 
             // Write CRC if enabled
-            if self.generate_crcs {
+            if *sector_crc {
                 // MPQ uses ADLER32 for sector checksums
                 let crc = adler2::adler32_slice(file_data);
                 writer.write_u32_le(crc)?;
@@ -1259,23 +1663,29 @@ impl ArchiveBuilder {
             // Multi-sector file
             let sector_count = file_data.len().div_ceil(*sector_size);
 
+            // Readers only load the sector offset table of compressed files, so
+            // the flag is set even if no sector shrinks, and sector checksums,
+            // which the table points to, need it too
+            if *compression != 0 {
+                flags |= compression_block_flag(*compression);
+            }
+            let sector_crc = *sector_crc && *compression != 0;
+
             // Set CRC flag early if enabled (needed for encryption key calculation)
-            if self.generate_crcs {
+            if sector_crc {
                 flags |= BlockEntry::FLAG_SECTOR_CRC;
             }
 
-            // Reserve space for sector offset table and CRC table if enabled
-            let offset_table_size = (sector_count + 1) * 4;
-            let crc_table_size = if self.generate_crcs {
-                sector_count * 4
-            } else {
-                0
-            };
-            let data_start = offset_table_size + crc_table_size;
+            // Reserve space for the sector offset table. With checksums it has
+            // one more entry, pointing at the end of the checksum block that
+            // follows the sector data
+            let offset_count = sector_count + if sector_crc { 2 } else { 1 };
+            let offset_table_size = offset_count * 4;
+            let data_start = offset_table_size;
 
-            let mut sector_offsets = vec![0u32; sector_count + 1];
+            let mut sector_offsets = vec![0u32; offset_count];
             let mut sector_data = Vec::new();
-            let mut sector_crcs = if self.generate_crcs {
+            let mut sector_crcs = if sector_crc {
                 Vec::with_capacity(sector_count)
             } else {
                 Vec::new()
@@ -1289,21 +1699,17 @@ impl ArchiveBuilder {
 
                 *offset = (data_start + sector_data.len()) as u32;
 
-                // Calculate CRC for uncompressed sector if enabled
-                if self.generate_crcs {
-                    // MPQ uses ADLER32 for sector checksums
-                    let crc = adler2::adler32_slice(sector_bytes);
-                    sector_crcs.push(crc);
-                }
-
                 // Compress sector if needed
                 let compressed_sector = if *compression != 0 && !sector_bytes.is_empty() {
                     // The compress function now handles the compression byte prefix
                     // and only returns compressed data if it's beneficial
-                    let compressed = compress(sector_bytes, *compression)?;
+                    let compressed = compress_unit(
+                        sector_bytes,
+                        sector_compression(*compression, i, sector_bytes),
+                    )?;
                     if compressed != *sector_bytes {
                         // Compression was beneficial and the data now includes the method byte
-                        flags |= BlockEntry::FLAG_COMPRESS;
+                        flags |= compression_block_flag(*compression);
                         compressed
                     } else {
                         // Compression not beneficial, returned original data
@@ -1313,21 +1719,38 @@ impl ArchiveBuilder {
                     sector_bytes.to_vec()
                 };
 
+                // Checksum the sector as stored, before encryption, like StormLib
+                if sector_crc {
+                    // MPQ uses ADLER32 for sector checksums
+                    sector_crcs.push(adler2::adler32_slice(&compressed_sector));
+                }
+
                 sector_data.extend_from_slice(&compressed_sector);
             }
 
-            // Set last offset
+            // Set the offset past the last sector
             sector_offsets[sector_count] = (data_start + sector_data.len()) as u32;
 
-            // Log CRC generation if enabled
-            if self.generate_crcs {
+            // The checksum block is compressed when that saves space, but never encrypted
+            let crc_block = if sector_crc {
                 log::debug!(
                     "Generated {} sector CRCs for file {}, first few: {:?}",
                     sector_count,
                     archive_name,
                     &sector_crcs[..5.min(sector_crcs.len())]
                 );
-            }
+
+                let crc_bytes: Vec<u8> = sector_crcs
+                    .iter()
+                    .flat_map(|crc| crc.to_le_bytes())
+                    .collect();
+                let crc_block = compress(&crc_bytes, compression_flags::ZLIB)?;
+                sector_offsets[sector_count + 1] =
+                    sector_offsets[sector_count] + crc_block.len() as u32;
+                crc_block
+            } else {
+                Vec::new()
+            };
 
             // Encrypt if needed
             if *encrypt {
@@ -1347,7 +1770,7 @@ impl ArchiveBuilder {
 
                 // Encrypt each sector using the original (unencrypted) offsets
                 let mut encrypted_sectors = Vec::new();
-                for (i, offset_pair) in original_offsets.windows(2).enumerate() {
+                for (i, offset_pair) in original_offsets[..=sector_count].windows(2).enumerate() {
                     let start = (offset_pair[0] - data_start as u32) as usize;
                     let end = (offset_pair[1] - data_start as u32) as usize;
 
@@ -1365,18 +1788,11 @@ impl ArchiveBuilder {
                 writer.write_u32_le(*offset)?;
             }
 
-            // Write CRC table if enabled
-            if self.generate_crcs {
-                for crc in &sector_crcs {
-                    writer.write_u32_le(*crc)?;
-                }
-            }
-
-            // Write sector data
+            // Write sector data, followed by the checksum block if enabled
             writer.write_all(&sector_data)?;
+            writer.write_all(&crc_block)?;
 
-            // Return size NOT including CRC table (offset table + sector data only)
-            let total_size = offset_table_size + sector_data.len();
+            let total_size = offset_table_size + sector_data.len() + crc_block.len();
             Ok((total_size, flags))
         }
     }
@@ -2277,8 +2693,114 @@ impl ArchiveBuilder {
     }
 }
 
+/// Sign `archive_start..archive_end` of `file` and write the strong
+/// signature block at `archive_end`
+pub(crate) fn append_strong_signature<F: Read + Write + Seek>(
+    file: &mut F,
+    archive_start: u64,
+    archive_end: u64,
+    key: &RsaPrivateKey,
+) -> Result<()> {
+    file.seek(SeekFrom::Start(archive_start))?;
+    let signature = generate_strong_signature(
+        &mut *file,
+        archive_end - archive_start,
+        StrongSignatureTailType::NoTail,
+        key,
+    )?;
+    file.seek(SeekFrom::Start(archive_end))?;
+    file.write_all(&signature)?;
+    file.flush()?;
+    Ok(())
+}
+
+/// Encode file data the way [`ArchiveBuilder::build`] stores it
+///
+/// Returns the stored bytes and block flags without `FLAG_EXISTS`. The file
+/// position only matters for FIX_KEY encryption and is taken as zero.
+pub(crate) fn encode_file(
+    data: &[u8],
+    archive_name: &str,
+    options: &AddFileOptions,
+    sector_size: usize,
+) -> Result<(Vec<u8>, u32)> {
+    let params = FileWriteParams {
+        file_data: data,
+        archive_name,
+        compression: options.compression.to_flags(),
+        encrypt: options.encrypt,
+        use_fix_key: options.fix_key,
+        sector_crc: options.sector_crc,
+        delete_marker: false,
+        sector_size,
+        file_pos: 0,
+    };
+
+    let mut stored = Vec::new();
+    let (size, mut flags) = ArchiveBuilder::new().write_file(&mut stored, &params)?;
+
+    // Single unit checksums trail the data without being part of it, so
+    // they are dropped rather than left for other writes to overwrite
+    if stored.len() > size {
+        stored.truncate(size);
+        flags &= !BlockEntry::FLAG_SECTOR_CRC;
+    }
+    Ok((stored, flags))
+}
+
 impl Default for ArchiveBuilder {
     fn default() -> Self {
         Self::new()
     }
 }
+
+/// Compress a single unit file or one sector with `compression`
+///
+/// [`compression_flags::IMPLODE`] implodes the data without a method byte, the
+/// way the oldest archives store files. The data is returned unchanged if
+/// compressing does not save space.
+fn compress_unit(data: &[u8], compression: u8) -> Result<Vec<u8>> {
+    if compression == compression_flags::IMPLODE {
+        crate::compression::implode(data)
+    } else {
+        compress(data, compression)
+    }
+}
+
+/// Compression for sector `index` of a file added with `compression`
+///
+/// ADPCM is lossy and only encodes whole samples, so like StormLib's
+/// `SFileAddWave` the first sector, which holds the RIFF header, and a last
+/// sector ending in part of a sample frame are compressed with PKWare DCL
+/// instead.
+fn sector_compression(compression: u8, index: usize, sector: &[u8]) -> u8 {
+    let frame_size = if compression & compression_flags::ADPCM_STEREO != 0 {
+        4
+    } else if compression & compression_flags::ADPCM_MONO != 0 {
+        2
+    } else {
+        return compression;
+    };
+    if index == 0 || !sector.len().is_multiple_of(frame_size) {
+        compression_flags::PKWARE
+    } else {
+        compression
+    }
+}
+
+/// Block table flag marking a file compressed with `compression`
+fn compression_block_flag(compression: u8) -> u32 {
+    if compression == compression_flags::IMPLODE {
+        BlockEntry::FLAG_IMPLODE
+    } else {
+        BlockEntry::FLAG_COMPRESS
+    }
+}
+
+/// Name hashes identifying a file in the hash table
+fn listfile_hash(name: &str) -> (u32, u32) {
+    (
+        hash_string(name, hash_type::NAME_A),
+        hash_string(name, hash_type::NAME_B),
+    )
+}
@@ -0,0 +1,160 @@
+//! Process-wide cache of open archives
+//!
+//! Opening an archive parses its header and tables, which adds up when an
+//! application opens the same archives from many places. The opt-in
+//! [`Archive::open_cached`] keeps one [`SharedArchive`] per canonical path
+//! and hands out clones of it.
+//!
+//! A cached archive is reopened when the file's size or modification time
+//! has changed since it was parsed, and [`MutableArchive`](crate::MutableArchive)
+//! evicts the archives it writes. Handles obtained before a change keep the
+//! old tables; call [`Archive::open_cached`] again for the new ones.
+//!
+//! ```no_run
+//! use wow_mpq::Archive;
+//!
+//! let archive = Archive::open_cached("Data/common.MPQ")?;
+//! let data = archive.lock().read_file("Interface\\FrameXML\\UIParent.lua")?;
+//!
+//! // Elsewhere: no tables are parsed again
+//! let same = Archive::open_cached("Data/common.MPQ")?;
+//! assert!(std::sync::Arc::ptr_eq(&archive, &same));
+//! # Ok::<(), wow_mpq::Error>(())
+//! ```
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock};
+use std::time::SystemTime;
+
+use parking_lot::Mutex;
+
+use crate::{Archive, Result};
+
+/// An archive shared through the cache
+///
+/// Reads need exclusive access to the archive's file handle, so the archive
+/// is behind a mutex.
+pub type SharedArchive = Arc<Mutex<Archive>>;
+
+/// File state an archive was parsed from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileStamp {
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+impl FileStamp {
+    fn of(path: &Path) -> Result<Self> {
+        let metadata = fs::metadata(path)?;
+        Ok(Self {
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+        })
+    }
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    archive: SharedArchive,
+    stamp: FileStamp,
+}
+
+static CACHE: LazyLock<Mutex<HashMap<PathBuf, CacheEntry>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+impl Archive {
+    /// Open an archive through the process-wide cache
+    ///
+    /// Returns the cached archive for the same file if its size and
+    /// modification time are unchanged, and opens and caches it otherwise.
+    pub fn open_cached<P: AsRef<Path>>(path: P) -> Result<SharedArchive> {
+        let path = fs::canonicalize(path)?;
+        let stamp = FileStamp::of(&path)?;
+
+        // Held while opening, so concurrent callers parse the tables once
+        let mut cache = CACHE.lock();
+        if let Some(entry) = cache.get(&path)
+            && entry.stamp == stamp
+        {
+            return Ok(Arc::clone(&entry.archive));
+        }
+
+        let archive = Arc::new(Mutex::new(Archive::open(&path)?));
+        cache.insert(
+            path,
+            CacheEntry {
+                archive: Arc::clone(&archive),
+                stamp,
+            },
+        );
+        Ok(archive)
+    }
+}
+
+/// Remove an archive from the cache
+///
+/// Returns whether it was cached. Outstanding handles stay usable.
+pub fn invalidate<P: AsRef<Path>>(path: P) -> bool {
+    match fs::canonicalize(path) {
+        Ok(path) => CACHE.lock().remove(&path).is_some(),
+        Err(_) => false,
+    }
+}
+
+/// Remove every archive from the cache
+pub fn clear() {
+    CACHE.lock().clear();
+}
+
+/// Number of cached archives
+pub fn len() -> usize {
+    CACHE.lock().len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ArchiveBuilder;
+
+    fn build(path: &Path, contents: &[u8]) {
+        ArchiveBuilder::new()
+            .add_file_data(contents.to_vec(), "file.txt")
+            .build(path)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_cached_handles_are_shared() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cached.mpq");
+        build(&path, b"first");
+
+        let first = Archive::open_cached(&path).unwrap();
+        // Relative and absolute spellings share the entry
+        let again = Archive::open_cached(dir.path().join(".").join("cached.mpq")).unwrap();
+        assert!(Arc::ptr_eq(&first, &again));
+        assert_eq!(again.lock().read_file("file.txt").unwrap(), b"first");
+
+        assert!(invalidate(&path));
+        assert!(!invalidate(&path));
+        let reopened = Archive::open_cached(&path).unwrap();
+        assert!(!Arc::ptr_eq(&first, &reopened));
+        // The evicted handle still works
+        assert_eq!(first.lock().read_file("file.txt").unwrap(), b"first");
+    }
+
+    #[test]
+    fn test_modified_archive_is_reopened() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("modified.mpq");
+        build(&path, b"old");
+        let old = Archive::open_cached(&path).unwrap();
+
+        build(&path, b"new contents");
+        let new = Archive::open_cached(&path).unwrap();
+        assert!(!Arc::ptr_eq(&old, &new));
+        assert_eq!(new.lock().read_file("file.txt").unwrap(), b"new contents");
+    }
+}
@@ -0,0 +1,278 @@
+//! Detection of file names the hash table cannot tell apart
+//!
+//! The hash table identifies a file only by the two 32-bit hashes of its
+//! name. Two different names with the same pair of hashes share a hash table
+//! entry: whichever file is added last replaces the other, and reading
+//! either name returns it. Duplicate entries with the same hashes, locale and
+//! platform are a related problem: lookups stop at the first one, so the
+//! files behind the others can never be read.
+//!
+//! [`find_name_collisions`] checks a list of names on its own, for example
+//! before building an archive. [`Archive::audit_hash_collisions`] also
+//! reports the hash table slots of colliding names and the duplicate entries
+//! of an existing archive.
+//!
+//! ```no_run
+//! use wow_mpq::Archive;
+//!
+//! let archive = Archive::open("patch-4.MPQ")?;
+//! let listfile = std::fs::read_to_string("listfile.txt")?;
+//! let report = archive.audit_hash_collisions(listfile.lines())?;
+//! for collision in &report.name_collisions {
+//!     println!("{} share slot(s) {:?}", collision.names.join(", "), collision.slots);
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::crypto::{hash_string, hash_type};
+use crate::path::{canonical_path, path_key};
+use crate::tables::HashEntry;
+use crate::{Archive, Error, Result};
+
+/// Names that hash to the same hash table entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NameCollision {
+    /// First name hash (`hashA`)
+    pub hash_a: u32,
+    /// Second name hash (`hashB`)
+    pub hash_b: u32,
+    /// The colliding names, sorted
+    pub names: Vec<String>,
+    /// Hash table slots holding these hashes; empty when the archive holds
+    /// none of the names or no archive was checked
+    pub slots: Vec<usize>,
+}
+
+/// Hash table entries that a lookup cannot tell apart
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateEntry {
+    /// First name hash (`hashA`)
+    pub hash_a: u32,
+    /// Second name hash (`hashB`)
+    pub hash_b: u32,
+    /// Locale of the entries
+    pub locale: u16,
+    /// Platform of the entries
+    pub platform: u16,
+    /// The slots, in lookup order when the name is known and table order
+    /// otherwise; lookups only ever find the first
+    pub slots: Vec<usize>,
+    /// Known name with these hashes
+    pub name: Option<String>,
+}
+
+/// Result of [`Archive::audit_hash_collisions`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CollisionReport {
+    /// Groups of known names sharing both name hashes
+    pub name_collisions: Vec<NameCollision>,
+    /// Groups of hash table entries with the same hashes, locale and platform
+    pub duplicate_entries: Vec<DuplicateEntry>,
+}
+
+impl CollisionReport {
+    /// Whether no collisions or duplicates were found
+    pub fn is_clean(&self) -> bool {
+        self.name_collisions.is_empty() && self.duplicate_entries.is_empty()
+    }
+}
+
+/// Name hashes of a file
+fn name_hashes(name: &str) -> (u32, u32) {
+    (
+        hash_string(name, hash_type::NAME_A),
+        hash_string(name, hash_type::NAME_B),
+    )
+}
+
+/// Distinct names, keyed by their name hashes
+///
+/// Spellings that differ only in case or separator hash alike and name the
+/// same file, so only the first of them is kept.
+fn names_by_hash<I, S>(names: I) -> BTreeMap<(u32, u32), Vec<String>>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut seen = HashSet::new();
+    let mut by_hash: BTreeMap<(u32, u32), Vec<String>> = BTreeMap::new();
+    for name in names {
+        let name = canonical_path(name.as_ref().trim());
+        if name.is_empty() || !seen.insert(path_key(&name)) {
+            continue;
+        }
+        by_hash.entry(name_hashes(&name)).or_default().push(name);
+    }
+    by_hash
+}
+
+/// Find distinct names that share both name hashes
+///
+/// Names are compared as the hash table sees them, ignoring case and
+/// separators. Collisions are sorted by their hashes.
+pub fn find_name_collisions<I, S>(names: I) -> Vec<NameCollision>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    names_by_hash(names)
+        .into_iter()
+        .filter(|(_, names)| names.len() > 1)
+        .map(|((hash_a, hash_b), mut names)| {
+            names.sort();
+            NameCollision {
+                hash_a,
+                hash_b,
+                names,
+                slots: Vec::new(),
+            }
+        })
+        .collect()
+}
+
+impl Archive {
+    /// Check the hash table for names it cannot tell apart
+    ///
+    /// `names` are the known file names, typically from a listfile. The
+    /// report lists groups of those names that share both name hashes,
+    /// together with the slots of any entries the archive has for them, and
+    /// groups of entries with the same hashes, locale and platform.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::OperationNotSupported`] for archives without a hash
+    /// table, which locate files through HET/BET only.
+    pub fn audit_hash_collisions<I, S>(&self, names: I) -> Result<CollisionReport>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let Some(table) = self.hash_table() else {
+            return Err(Error::OperationNotSupported {
+                version: self.header().format_version as u16,
+                operation: "hash collision audit without a hash table".to_string(),
+            });
+        };
+        Ok(audit_entries(table.entries(), names))
+    }
+}
+
+/// Collisions among `names` and duplicates among the valid `entries`
+fn audit_entries<I, S>(entries: &[HashEntry], names: I) -> CollisionReport
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mask = entries.len().saturating_sub(1);
+    let names = names_by_hash(names);
+
+    let mut slots_by_hash: HashMap<(u32, u32), Vec<usize>> = HashMap::new();
+    for (slot, entry) in entries.iter().enumerate() {
+        if entry.is_valid() {
+            slots_by_hash
+                .entry((entry.name_1, entry.name_2))
+                .or_default()
+                .push(slot);
+        }
+    }
+
+    let mut report = CollisionReport::default();
+    for ((hash_a, hash_b), names) in names.iter().filter(|(_, names)| names.len() > 1) {
+        let mut names = names.clone();
+        names.sort();
+        report.name_collisions.push(NameCollision {
+            hash_a: *hash_a,
+            hash_b: *hash_b,
+            names,
+            slots: slots_by_hash
+                .get(&(*hash_a, *hash_b))
+                .cloned()
+                .unwrap_or_default(),
+        });
+    }
+
+    let mut groups: BTreeMap<(u32, u32, u16, u16), Vec<usize>> = BTreeMap::new();
+    for &slot in slots_by_hash
+        .values()
+        .filter(|slots| slots.len() > 1)
+        .flatten()
+    {
+        let entry = &entries[slot];
+        groups
+            .entry((entry.name_1, entry.name_2, entry.locale, entry.platform))
+            .or_default()
+            .push(slot);
+    }
+
+    for ((hash_a, hash_b, locale, platform), mut slots) in groups {
+        if slots.len() < 2 {
+            continue;
+        }
+        let name = names.get(&(hash_a, hash_b)).map(|names| names[0].clone());
+        slots.sort_unstable();
+        if let Some(name) = &name {
+            // Lookups probe forward from the name's home slot
+            let home = hash_string(name, hash_type::TABLE_OFFSET) as usize & mask;
+            slots.sort_by_key(|&slot| slot.wrapping_sub(home) & mask);
+        }
+        report.duplicate_entries.push(DuplicateEntry {
+            hash_a,
+            hash_b,
+            locale,
+            platform,
+            slots,
+            name,
+        });
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, locale: u16, block_index: u32) -> HashEntry {
+        let (name_1, name_2) = name_hashes(name);
+        HashEntry {
+            name_1,
+            name_2,
+            locale,
+            platform: 0,
+            block_index,
+        }
+    }
+
+    #[test]
+    fn test_duplicate_entries_in_lookup_order() {
+        let mut entries = vec![HashEntry::empty(); 8];
+        let home = hash_string("a.txt", hash_type::TABLE_OFFSET) as usize & 7;
+        // The copy after the home slot wraps around the table end
+        entries[(home + 7) & 7] = entry("a.txt", 0, 1);
+        entries[home] = entry("a.txt", 0, 0);
+        // Another locale of the same file is not a duplicate
+        entries[(home + 1) & 7] = entry("a.txt", 0x409, 2);
+        entries[(home + 2) & 7] = entry("b.txt", 0, 3);
+
+        let report = audit_entries(&entries, ["a.txt", "b.txt"]);
+        assert!(report.name_collisions.is_empty());
+        assert_eq!(report.duplicate_entries.len(), 1);
+        let duplicate = &report.duplicate_entries[0];
+        assert_eq!(duplicate.slots, [home, (home + 7) & 7]);
+        assert_eq!(duplicate.name.as_deref(), Some("a.txt"));
+        assert_eq!(duplicate.locale, 0);
+
+        // Deleted entries are not looked up
+        entries[(home + 7) & 7].block_index = HashEntry::EMPTY_DELETED;
+        assert!(audit_entries(&entries, ["a.txt"]).is_clean());
+    }
+
+    #[test]
+    fn test_spellings_of_one_name_do_not_collide() {
+        let names = ["Data\\File.txt", "data/file.TXT", "other.txt"];
+        assert!(find_name_collisions(names).is_empty());
+        assert_eq!(names_by_hash(names).len(), 2);
+    }
+}
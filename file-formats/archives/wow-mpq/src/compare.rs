@@ -1,14 +1,23 @@
 //! MPQ Archive Comparison Functionality
 //!
 //! This module provides functionality to compare two MPQ archives, highlighting
-//! differences in metadata, file lists, and file contents.
-
-use crate::{Archive, FormatVersion, Result};
+//! differences in metadata, file lists, and file contents, and to turn those
+//! differences into a patch archive.
+//!
+//! Files present in both archives are compared on the rayon pool. With
+//! content checks, each worker opens its own handles to the two archives, so
+//! reads neither share a seek position nor wait on each other.
+
+use crate::compression::flags;
+use crate::rebuild::ProgressCallback;
+use crate::special_files::is_special_file;
+use crate::{Archive, ArchiveBuilder, FormatVersion, ListfileOption, Result};
+use rayon::prelude::*;
 use std::collections::{HashMap, HashSet};
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
 /// Options for archive comparison
-#[derive(Debug, Clone)]
 pub struct CompareOptions {
     /// Show detailed file-by-file comparison
     pub detailed: bool,
@@ -20,6 +29,14 @@ pub struct CompareOptions {
     pub ignore_order: bool,
     /// Filter files by pattern
     pub filter: Option<String>,
+    /// Stop at the first difference instead of collecting all of them
+    ///
+    /// The result then only tells whether the archives differ, and lists
+    /// whatever was found before stopping.
+    pub stop_at_first_difference: bool,
+    /// Called with the number of compared files, the total and the file
+    /// name after each file present in both archives, from the worker threads
+    pub progress: Option<ProgressCallback>,
 }
 
 impl Default for CompareOptions {
@@ -30,10 +47,26 @@ impl Default for CompareOptions {
             metadata_only: false,
             ignore_order: true,
             filter: None,
+            stop_at_first_difference: false,
+            progress: None,
         }
     }
 }
 
+impl std::fmt::Debug for CompareOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CompareOptions")
+            .field("detailed", &self.detailed)
+            .field("content_check", &self.content_check)
+            .field("metadata_only", &self.metadata_only)
+            .field("ignore_order", &self.ignore_order)
+            .field("filter", &self.filter)
+            .field("stop_at_first_difference", &self.stop_at_first_difference)
+            .field("progress", &self.progress.is_some())
+            .finish()
+    }
+}
+
 /// Result of comparing two archives
 #[derive(Debug, Clone)]
 pub struct ComparisonResult {
@@ -45,6 +78,9 @@ pub struct ComparisonResult {
     pub files: Option<FileComparison>,
     /// Summary of differences
     pub summary: ComparisonSummary,
+    /// Whether the comparison stopped at the first difference, leaving the
+    /// file lists incomplete
+    pub stopped_early: bool,
 }
 
 /// Archive metadata comparison
@@ -74,6 +110,8 @@ pub struct FileComparison {
     /// Files with different sizes
     pub size_differences: Vec<FileSizeDiff>,
     /// Files with different content (if content_check enabled)
+    ///
+    /// Files that cannot be read from either archive count as different.
     pub content_differences: Vec<String>,
     /// Files with different metadata
     pub metadata_differences: Vec<FileMetadataDiff>,
@@ -118,22 +156,67 @@ pub struct ComparisonSummary {
     pub source_only_count: usize,
     /// Files only in target
     pub target_only_count: usize,
-    /// Files with differences
+    /// Files present in both archives that differ in any way
     pub different_files: usize,
-    /// Files that are identical
+    /// Files present in both archives that were found identical
     pub identical_files: usize,
 }
 
 /// Compare two MPQ archives
+///
+/// Shorthand for [`compare_archives_with_options`] without early exit or
+/// progress reporting.
 pub fn compare_archives<P: AsRef<Path>>(
     source_path: P,
     target_path: P,
     detailed: bool,
     content_check: bool,
     metadata_only: bool,
-    _ignore_order: bool,
+    ignore_order: bool,
     filter: Option<String>,
 ) -> Result<ComparisonResult> {
+    let options = CompareOptions {
+        detailed,
+        content_check,
+        metadata_only,
+        ignore_order,
+        filter,
+        ..Default::default()
+    };
+    compare_archives_with_options(source_path, target_path, &options)
+}
+
+/// Compare two MPQ archives
+///
+/// Files present in both archives are compared in parallel. File lists in
+/// the result are sorted by name.
+///
+/// # Examples
+///
+/// ```no_run
+/// use wow_mpq::{CompareOptions, compare_archives_with_options};
+///
+/// let options = CompareOptions {
+///     content_check: true,
+///     stop_at_first_difference: true,
+///     progress: Some(Box::new(|done, total, name| {
+///         println!("[{done}/{total}] {name}");
+///     })),
+///     ..Default::default()
+/// };
+/// let result = compare_archives_with_options("enUS/locale-enUS.MPQ", "backup.MPQ", &options)?;
+/// println!("identical: {}", result.identical);
+/// # Ok::<(), wow_mpq::Error>(())
+/// ```
+pub fn compare_archives_with_options<S, T>(
+    source_path: S,
+    target_path: T,
+    options: &CompareOptions,
+) -> Result<ComparisonResult>
+where
+    S: AsRef<Path>,
+    T: AsRef<Path>,
+{
     let source_path = source_path.as_ref();
     let target_path = target_path.as_ref();
 
@@ -150,8 +233,9 @@ pub fn compare_archives<P: AsRef<Path>>(
     // Compare metadata
     let metadata = compare_metadata(&mut source_archive, &mut target_archive)?;
 
-    // Early return if only comparing metadata
-    if metadata_only {
+    // Early return if only comparing metadata, or if it already differs
+    let stopped_early = options.stop_at_first_difference && !metadata.matches;
+    if options.metadata_only || stopped_early {
         return Ok(ComparisonResult {
             identical: metadata.matches,
             metadata: metadata.clone(),
@@ -164,17 +248,12 @@ pub fn compare_archives<P: AsRef<Path>>(
                 different_files: 0,
                 identical_files: 0,
             },
+            stopped_early,
         });
     }
 
     // Compare files
-    let files = compare_files(
-        &mut source_archive,
-        &mut target_archive,
-        detailed,
-        content_check,
-        filter,
-    )?;
+    let (files, counts) = compare_files(&mut source_archive, &mut target_archive, options)?;
 
     // Generate summary
     let summary = ComparisonSummary {
@@ -182,13 +261,8 @@ pub fn compare_archives<P: AsRef<Path>>(
         target_files: metadata.file_count.1,
         source_only_count: files.source_only.len(),
         target_only_count: files.target_only.len(),
-        different_files: files.size_differences.len()
-            + files.content_differences.len()
-            + files.metadata_differences.len(),
-        identical_files: files.common_files.len()
-            - files.size_differences.len()
-            - files.content_differences.len()
-            - files.metadata_differences.len(),
+        different_files: counts.different,
+        identical_files: counts.identical,
     };
 
     // Determine if archives are identical
@@ -205,9 +279,156 @@ pub fn compare_archives<P: AsRef<Path>>(
         metadata,
         files: Some(files),
         summary,
+        stopped_early: counts.stopped_early,
     })
 }
 
+/// Options for patch archive generation
+#[derive(Debug, Clone)]
+pub struct PatchOptions {
+    /// Write deletion markers for files missing from the target
+    pub deletion_markers: bool,
+    /// Only consider files matching this pattern (supports `*`)
+    pub filter: Option<String>,
+    /// Compression for the files in the patch
+    pub compression: u8,
+    /// Format of the patch archive, the target's format if not set
+    pub version: Option<FormatVersion>,
+}
+
+impl Default for PatchOptions {
+    fn default() -> Self {
+        Self {
+            deletion_markers: false,
+            filter: None,
+            compression: flags::ZLIB,
+            version: None,
+        }
+    }
+}
+
+/// Files written to a patch archive
+#[derive(Debug, Clone, Default)]
+pub struct PatchSummary {
+    /// Files only in the target
+    pub added: Vec<String>,
+    /// Files in both archives whose contents differ
+    pub changed: Vec<String>,
+    /// Files only in the source, written as deletion markers if requested
+    pub deleted: Vec<String>,
+    /// Number of files left out because they are identical
+    pub unchanged: usize,
+}
+
+impl PatchSummary {
+    /// Whether the target adds, changes or removes nothing
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.deleted.is_empty()
+    }
+}
+
+/// Write an archive holding only what changed from `source` to `target`
+///
+/// Files are matched by name, ignoring case and separator style. The patch
+/// contains every file that is new in the target or whose contents differ,
+/// stored as complete files with `options.compression`; identical files are
+/// left out. With `options.deletion_markers`, files missing from the target
+/// become deletion markers, so a [`PatchChain`](crate::PatchChain) with the
+/// patch on top of the source no longer provides them.
+///
+/// The special files `(listfile)`, `(attributes)`, `(signature)` and
+/// `(patch_metadata)` are not compared; the patch gets its own listfile.
+/// Both archives need listfiles for their files to be found.
+///
+/// # Examples
+///
+/// ```no_run
+/// use wow_mpq::compare::{PatchOptions, generate_patch_archive};
+///
+/// let options = PatchOptions {
+///     deletion_markers: true,
+///     ..Default::default()
+/// };
+/// let summary = generate_patch_archive("release-1.mpq", "release-2.mpq", "patch-2.mpq", &options)?;
+/// println!(
+///     "{} added, {} changed, {} deleted",
+///     summary.added.len(),
+///     summary.changed.len(),
+///     summary.deleted.len()
+/// );
+/// # Ok::<(), wow_mpq::Error>(())
+/// ```
+pub fn generate_patch_archive<S, T, O>(
+    source_path: S,
+    target_path: T,
+    output_path: O,
+    options: &PatchOptions,
+) -> Result<PatchSummary>
+where
+    S: AsRef<Path>,
+    T: AsRef<Path>,
+    O: AsRef<Path>,
+{
+    let mut source = Archive::open(source_path)?;
+    let mut target = Archive::open(target_path)?;
+
+    let by_key = |files: HashMap<String, crate::FileEntry>| -> HashMap<String, crate::FileEntry> {
+        files
+            .into_iter()
+            .filter(|(name, _)| !is_special_file(name))
+            .map(|(name, entry)| (crate::path::path_key(&name), entry))
+            .collect()
+    };
+    let source_files = by_key(get_file_list(&mut source, &options.filter)?);
+    let target_files = by_key(get_file_list(&mut target, &options.filter)?);
+
+    let version = options.version.unwrap_or(target.header().format_version);
+    let mut builder = ArchiveBuilder::new()
+        .version(version)
+        .default_compression(options.compression)
+        .listfile_option(ListfileOption::Generate);
+    let mut summary = PatchSummary::default();
+
+    let mut target_names: Vec<_> = target_files.iter().collect();
+    target_names.sort_by(|a, b| a.0.cmp(b.0));
+    for (key, entry) in target_names {
+        let data = target.read_file(&entry.name)?;
+        match source_files.get(key) {
+            None => summary.added.push(entry.name.clone()),
+            Some(old) => {
+                if old.size == entry.size && source.read_file(&old.name)? == data {
+                    summary.unchanged += 1;
+                    continue;
+                }
+                summary.changed.push(entry.name.clone());
+            }
+        }
+        builder = builder.add_file_data(data, &entry.name);
+    }
+
+    let mut deleted: Vec<_> = source_files
+        .iter()
+        .filter(|(key, _)| !target_files.contains_key(*key))
+        .map(|(_, entry)| entry.name.clone())
+        .collect();
+    deleted.sort();
+    if options.deletion_markers {
+        for name in &deleted {
+            builder = builder.add_deletion_marker(name);
+        }
+    }
+    summary.deleted = deleted;
+
+    log::info!(
+        "Writing patch with {} added, {} changed and {} deleted files",
+        summary.added.len(),
+        summary.changed.len(),
+        summary.deleted.len()
+    );
+    builder.build(output_path)?;
+    Ok(summary)
+}
+
 /// Compare archive metadata
 fn compare_metadata(source: &mut Archive, target: &mut Archive) -> Result<MetadataComparison> {
     // Get info first (requires mutable borrow)
@@ -236,94 +457,187 @@ fn compare_metadata(source: &mut Archive, target: &mut Archive) -> Result<Metada
     })
 }
 
+/// Differences found for one file present in both archives
+#[derive(Default)]
+struct FileOutcome {
+    size: Option<FileSizeDiff>,
+    metadata: Option<FileMetadataDiff>,
+    content_differs: bool,
+}
+
+impl FileOutcome {
+    fn differs(&self) -> bool {
+        self.size.is_some() || self.metadata.is_some() || self.content_differs
+    }
+}
+
+/// Number of compared common files, by result
+struct FileCounts {
+    different: usize,
+    identical: usize,
+    stopped_early: bool,
+}
+
 /// Compare files between archives
 fn compare_files(
     source: &mut Archive,
     target: &mut Archive,
-    detailed: bool,
-    content_check: bool,
-    filter: Option<String>,
-) -> Result<FileComparison> {
+    options: &CompareOptions,
+) -> Result<(FileComparison, FileCounts)> {
     // Get file lists
-    let source_files = get_file_list(source, &filter)?;
-    let target_files = get_file_list(target, &filter)?;
+    let source_files = get_file_list(source, &options.filter)?;
+    let target_files = get_file_list(target, &options.filter)?;
 
     // Convert to HashSets for set operations
     let source_set: HashSet<_> = source_files.keys().collect();
     let target_set: HashSet<_> = target_files.keys().collect();
 
     // Find differences
-    let source_only: Vec<String> = source_set
+    let mut source_only: Vec<String> = source_set
         .difference(&target_set)
         .map(|s| s.to_string())
         .collect();
+    source_only.sort();
 
-    let target_only: Vec<String> = target_set
+    let mut target_only: Vec<String> = target_set
         .difference(&source_set)
         .map(|s| s.to_string())
         .collect();
+    target_only.sort();
 
-    let common_files: Vec<String> = source_set
+    let mut common_files: Vec<String> = source_set
         .intersection(&target_set)
         .map(|s| s.to_string())
         .collect();
+    common_files.sort();
+
+    // Missing files already make the archives differ
+    let stop = AtomicBool::new(
+        options.stop_at_first_difference && !(source_only.is_empty() && target_only.is_empty()),
+    );
+    let completed = AtomicUsize::new(0);
+    let total = common_files.len();
+    let source_path = source.path();
+    let target_path = target.path();
+
+    // Compare common files, each worker with its own archive handles
+    let outcomes = common_files
+        .par_iter()
+        .map_init(
+            || (None, None),
+            |handles: &mut (Option<Archive>, Option<Archive>), filename| -> Result<_> {
+                if stop.load(Ordering::Relaxed) {
+                    return Ok(None);
+                }
+                let outcome = compare_file(
+                    handles,
+                    (source_path, target_path),
+                    (&source_files[filename], &target_files[filename]),
+                    options,
+                )?;
+                if options.stop_at_first_difference && outcome.differs() {
+                    stop.store(true, Ordering::Relaxed);
+                }
+
+                let done = completed.fetch_add(1, Ordering::Relaxed) + 1;
+                if let Some(progress) = &options.progress {
+                    progress(done, total, filename);
+                }
+                Ok(Some(outcome))
+            },
+        )
+        .collect::<Result<Vec<_>>>()?;
 
-    // Compare common files
     let mut size_differences = Vec::new();
     let mut content_differences = Vec::new();
     let mut metadata_differences = Vec::new();
+    let mut counts = FileCounts {
+        different: 0,
+        identical: 0,
+        stopped_early: stop.load(Ordering::Relaxed),
+    };
 
-    for filename in &common_files {
-        let source_entry = &source_files[filename];
-        let target_entry = &target_files[filename];
-
-        // Check size differences
-        if source_entry.size != target_entry.size
-            || source_entry.compressed_size != target_entry.compressed_size
-        {
-            size_differences.push(FileSizeDiff {
-                name: filename.clone(),
-                source_size: source_entry.size,
-                target_size: target_entry.size,
-                source_compressed: source_entry.compressed_size,
-                target_compressed: target_entry.compressed_size,
-            });
-        }
-
-        // Check metadata differences (if detailed)
-        if detailed && source_entry.flags != target_entry.flags {
-            metadata_differences.push(FileMetadataDiff {
-                name: filename.clone(),
-                difference: "Flags".to_string(),
-                source_value: format!("0x{:08x}", source_entry.flags),
-                target_value: format!("0x{:08x}", target_entry.flags),
-            });
+    for (filename, outcome) in common_files.iter().zip(outcomes) {
+        let Some(outcome) = outcome else {
+            continue;
+        };
+        if outcome.differs() {
+            counts.different += 1;
+        } else {
+            counts.identical += 1;
         }
-
-        // Check content differences (if content_check enabled)
-        if content_check {
-            match (source.read_file(filename), target.read_file(filename)) {
-                (Ok(source_data), Ok(target_data)) => {
-                    if source_data != target_data {
-                        content_differences.push(filename.clone());
-                    }
-                }
-                _ => {
-                    // If we can't read either file, consider it a content difference
-                    content_differences.push(filename.clone());
-                }
-            }
+        size_differences.extend(outcome.size);
+        metadata_differences.extend(outcome.metadata);
+        if outcome.content_differs {
+            content_differences.push(filename.clone());
         }
     }
 
-    Ok(FileComparison {
+    let files = FileComparison {
         source_only,
         target_only,
         common_files,
         size_differences,
         content_differences,
         metadata_differences,
-    })
+    };
+    Ok((files, counts))
+}
+
+/// Compare one file present in both archives
+fn compare_file(
+    handles: &mut (Option<Archive>, Option<Archive>),
+    paths: (&Path, &Path),
+    entries: (&crate::FileEntry, &crate::FileEntry),
+    options: &CompareOptions,
+) -> Result<FileOutcome> {
+    let (source_entry, target_entry) = entries;
+    let filename = &source_entry.name;
+    let mut outcome = FileOutcome::default();
+
+    // Check size differences
+    if source_entry.size != target_entry.size
+        || source_entry.compressed_size != target_entry.compressed_size
+    {
+        outcome.size = Some(FileSizeDiff {
+            name: filename.clone(),
+            source_size: source_entry.size,
+            target_size: target_entry.size,
+            source_compressed: source_entry.compressed_size,
+            target_compressed: target_entry.compressed_size,
+        });
+    }
+
+    // Check metadata differences (if detailed)
+    if options.detailed && source_entry.flags != target_entry.flags {
+        outcome.metadata = Some(FileMetadataDiff {
+            name: filename.clone(),
+            difference: "Flags".to_string(),
+            source_value: format!("0x{:08x}", source_entry.flags),
+            target_value: format!("0x{:08x}", target_entry.flags),
+        });
+    }
+
+    // Check content differences (if content_check enabled). Different sizes
+    // decide this without reading when stopping at the first difference.
+    if options.content_check && !(options.stop_at_first_difference && outcome.differs()) {
+        let source = match &mut handles.0 {
+            Some(archive) => archive,
+            None => handles.0.insert(Archive::open(paths.0)?),
+        };
+        let source_data = source.read_file(filename);
+        let target = match &mut handles.1 {
+            Some(archive) => archive,
+            None => handles.1.insert(Archive::open(paths.1)?),
+        };
+        // If we can't read either file, consider it a content difference
+        outcome.content_differs = match (source_data, target.read_file(filename)) {
+            (Ok(source_data), Ok(target_data)) => source_data != target_data,
+            _ => true,
+        };
+    }
+
+    Ok(outcome)
 }
 
 /// Get file list from archive with optional filtering
@@ -407,6 +721,80 @@ mod tests {
         assert!(!options.metadata_only);
         assert!(options.ignore_order);
         assert!(options.filter.is_none());
+        assert!(!options.stop_at_first_difference);
+        assert!(options.progress.is_none());
+    }
+
+    fn build_archive(path: &Path, changed: &[usize]) {
+        let mut builder = ArchiveBuilder::new();
+        for i in 0..40 {
+            let data = if changed.contains(&i) {
+                format!("changed {i:04}")
+            } else {
+                format!("content {i:04}")
+            };
+            builder = builder.add_file_data(data.into_bytes(), &format!("Data\\File{i:02}.txt"));
+        }
+        builder.build(path).unwrap();
+    }
+
+    #[test]
+    fn test_compare_content_in_parallel() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("source.mpq");
+        let target = dir.path().join("target.mpq");
+        build_archive(&source, &[]);
+        build_archive(&target, &[3, 17, 29]);
+
+        let seen = std::sync::Arc::new(AtomicUsize::new(0));
+        let counter = std::sync::Arc::clone(&seen);
+        let options = CompareOptions {
+            content_check: true,
+            progress: Some(Box::new(move |done, total, _| {
+                assert!(done <= total);
+                counter.fetch_add(1, Ordering::Relaxed);
+            })),
+            ..Default::default()
+        };
+        let result = compare_archives_with_options(&source, &target, &options).unwrap();
+
+        assert!(!result.identical);
+        assert!(!result.stopped_early);
+        let files = result.files.unwrap();
+        assert_eq!(
+            files.content_differences,
+            ["Data\\File03.txt", "Data\\File17.txt", "Data\\File29.txt"]
+        );
+        assert_eq!(result.summary.different_files, 3);
+        assert_eq!(result.summary.identical_files, files.common_files.len() - 3);
+        assert_eq!(seen.load(Ordering::Relaxed), files.common_files.len());
+    }
+
+    #[test]
+    fn test_compare_stops_at_first_difference() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("source.mpq");
+        let target = dir.path().join("target.mpq");
+        build_archive(&source, &[]);
+        build_archive(&target, &[5]);
+
+        let options = CompareOptions {
+            content_check: true,
+            stop_at_first_difference: true,
+            ..Default::default()
+        };
+        let result = compare_archives_with_options(&source, &target, &options).unwrap();
+        assert!(!result.identical);
+        assert!(result.stopped_early);
+        assert_eq!(result.summary.different_files, 1);
+        assert_eq!(
+            result.files.unwrap().content_differences,
+            ["Data\\File05.txt"]
+        );
+
+        let result = compare_archives_with_options(&source, &source, &options).unwrap();
+        assert!(result.identical);
+        assert!(!result.stopped_early);
     }
 
     #[test]
@@ -1,14 +1,23 @@
 //! Huffman compression implementation for MPQ archives (StormLib-compatible)
 //!
-//! This is a complete rewrite to match StormLib's linked list approach.
+//! MPQ Huffman coding is adaptive: both sides start from one of nine weight
+//! tables, selected by the first byte of the stream, and rebalance the tree
+//! after each byte the same way. Bytes missing from the tree are sent as an
+//! escape code followed by the literal byte, which adds them to the tree.
+//! Types 6 to 8 are tuned for ADPCM output and used for WAVE files.
+//!
 //! Based on the algorithm from Ladislav Zezula's StormLib.
 
 use crate::{Error, Result};
 
-// Huffman tree constants
-const HUFF_ITEM_COUNT: usize = 0x203; // Number of items in the item pool
-const LINK_ITEM_COUNT: usize = 0x80; // Maximum number of quick-link items
-const HUFF_DECOMPRESS_ERROR: u32 = 0x1FF;
+/// Number of items in the item pool
+const HUFF_ITEM_COUNT: usize = 0x203;
+/// Symbol ending the stream
+const END_OF_STREAM: usize = 0x100;
+/// Symbol followed by a literal byte that is added to the tree
+const NEW_BYTE: usize = 0x101;
+/// The list head, linking to the highest and lowest weight items
+const HEAD: usize = 0;
 
 // All weight tables from StormLib - these define the initial character frequencies
 const BYTE_TO_WEIGHT_00: [u8; 258] = [
@@ -204,7 +213,17 @@ const WEIGHT_TABLES: [&[u8; 258]; 9] = [
     &BYTE_TO_WEIGHT_08,
 ];
 
-/// Bit stream reader for Huffman decompression
+/// Weight table the Huffman stage of a WAVE file uses after ADPCM at
+/// `adpcm_level`, as `SCompCompress` picks it
+pub(crate) fn wave_compression_type(adpcm_level: u8) -> u8 {
+    match adpcm_level {
+        4 => 6,
+        6 => 8,
+        _ => 7,
+    }
+}
+
+/// Reads bits least significant first
 struct BitReader<'a> {
     data: &'a [u8],
     position: usize,
@@ -224,11 +243,12 @@ impl<'a> BitReader<'a> {
 
     fn get_bit(&mut self) -> Result<u32> {
         if self.bit_count == 0 {
-            if self.position >= self.data.len() {
-                return Err(Error::compression("Unexpected end of Huffman data"));
-            }
-            self.bit_buffer = self.data[self.position] as u32;
+            let byte = *self
+                .data
+                .get(self.position)
+                .ok_or_else(|| Error::compression("Unexpected end of Huffman data"))?;
             self.position += 1;
+            self.bit_buffer = byte as u32;
             self.bit_count = 8;
         }
 
@@ -239,624 +259,428 @@ impl<'a> BitReader<'a> {
     }
 
     fn get_8_bits(&mut self) -> Result<u32> {
-        if self.position >= self.data.len() {
-            return Err(Error::compression("Unexpected end of Huffman data"));
-        }
-        let byte = self.data[self.position] as u32;
-        self.position += 1;
-        Ok(byte)
-    }
-
-    fn peek_7_bits(&mut self) -> Result<u32> {
-        // Ensure we have enough bits in the buffer
-        while self.bit_count < 7 {
-            if self.position >= self.data.len() {
-                return Err(Error::compression("Unexpected end of Huffman data"));
-            }
-            self.bit_buffer |= (self.data[self.position] as u32) << self.bit_count;
+        if self.bit_count < 8 {
+            let byte = *self
+                .data
+                .get(self.position)
+                .ok_or_else(|| Error::compression("Unexpected end of Huffman data"))?;
             self.position += 1;
+            self.bit_buffer |= (byte as u32) << self.bit_count;
             self.bit_count += 8;
         }
 
-        Ok(self.bit_buffer & 0x7F) // Get lower 7 bits
-    }
-
-    fn skip_bits(&mut self, count: u32) {
-        if count <= self.bit_count {
-            self.bit_buffer >>= count;
-            self.bit_count -= count;
-        } else {
-            let remaining = count - self.bit_count;
-            self.bit_buffer = 0;
-            self.bit_count = 0;
-            self.position += (remaining / 8) as usize;
-            // Handle remaining bits if any
-            if !remaining.is_multiple_of(8) && self.peek_7_bits().is_ok() {
-                self.skip_bits(remaining % 8);
-            }
-        }
+        let value = self.bit_buffer & 0xFF;
+        self.bit_buffer >>= 8;
+        self.bit_count -= 8;
+        Ok(value)
     }
 }
 
-/// Huffman tree node based on StormLib's THTreeItem
-#[derive(Debug, Clone)]
-struct HuffmanItem {
-    // Linked list pointers
-    next: Option<usize>, // Pointer to lower-weight tree item
-    prev: Option<usize>, // Pointer to higher-weight item
-
-    // Tree structure
-    decompressed_value: u32, // 08 - Decompressed byte value (also index in the array)
-    weight: u32,             // 0C - Weight
-    parent: Option<usize>,   // 10 - Pointer to parent item (NULL if none)
-    child_lo: Option<usize>, // 14 - Pointer to the child with lower-weight child ("left child")
-
-                             // In StormLib, the higher-weight child is accessed via pChildLo->pPrev
+/// Writes bits least significant first
+struct BitWriter {
+    output: Vec<u8>,
+    bit_buffer: u32,
+    bit_count: u32,
 }
 
-impl HuffmanItem {
-    fn new(value: u32, weight: u32) -> Self {
+impl BitWriter {
+    fn with_capacity(capacity: usize) -> Self {
         Self {
-            next: None,
-            prev: None,
-            decompressed_value: value,
-            weight,
-            parent: None,
-            child_lo: None,
+            output: Vec::with_capacity(capacity),
+            bit_buffer: 0,
+            bit_count: 0,
         }
     }
-}
-
-/// Quick link structure for optimized decompression
-#[derive(Debug, Clone)]
-struct QuickLink {
-    valid_value: u32,
-    valid_bits: u32,
-    decompressed_value: u32,
-    item_index: Option<usize>,
-}
 
-impl QuickLink {
-    fn new() -> Self {
-        Self {
-            valid_value: 0,
-            valid_bits: 0,
-            decompressed_value: 0,
-            item_index: None,
+    fn put_bit(&mut self, bit: u32) {
+        self.bit_buffer |= bit << self.bit_count;
+        self.bit_count += 1;
+        if self.bit_count == 8 {
+            self.output.push(self.bit_buffer as u8);
+            self.bit_buffer = 0;
+            self.bit_count = 0;
         }
     }
-}
-
-/// Huffman tree based on StormLib
-struct HuffmanTree {
-    items: Vec<HuffmanItem>,
-    items_by_byte: [Option<usize>; 0x102],
-    quick_links: [QuickLink; LINK_ITEM_COUNT],
-    first: Option<usize>, // Pointer to the highest weight item
-    last: Option<usize>,  // Pointer to the lowest weight item
-    min_valid_value: u32,
-}
-
-// Virtual list head marker
-const LIST_HEAD: Option<usize> = None;
 
-impl HuffmanTree {
-    fn new() -> Self {
-        Self {
-            items: Vec::with_capacity(HUFF_ITEM_COUNT),
-            items_by_byte: [None; 0x102],
-            quick_links: std::array::from_fn(|_| QuickLink::new()),
-            first: LIST_HEAD,
-            last: LIST_HEAD,
-            min_valid_value: 1,
+    fn put_8_bits(&mut self, value: u8) {
+        for bit in 0..8 {
+            self.put_bit(((value >> bit) & 1) as u32);
         }
     }
 
-    // Link two items in the linked list
-    fn link_two_items(&mut self, item1_idx: usize, item2_idx: usize) {
-        // pItem2->pNext = pItem1->pNext;
-        self.items[item2_idx].next = self.items[item1_idx].next;
-
-        // pItem2->pPrev = pItem1->pNext->pPrev;
-        if let Some(next_idx) = self.items[item1_idx].next {
-            self.items[item2_idx].prev = self.items[next_idx].prev;
-            // pItem1->pNext->pPrev = pItem2;
-            self.items[next_idx].prev = Some(item2_idx);
-        } else {
-            // item1 was the last item
-            self.items[item2_idx].prev = Some(item1_idx);
+    fn finish(mut self) -> Vec<u8> {
+        if self.bit_count > 0 {
+            self.output.push(self.bit_buffer as u8);
         }
-
-        // pItem1->pNext = pItem2;
-        self.items[item1_idx].next = Some(item2_idx);
+        self.output
     }
+}
 
-    // Insert item after a given position
-    fn insert_after(&mut self, new_item_idx: usize, after_idx: Option<usize>) {
-        if let Some(idx) = after_idx {
-            self.link_two_items(idx, new_item_idx);
-        } else {
-            // Insert at the beginning of the list
-            self.items[new_item_idx].prev = None;
-            self.items[new_item_idx].next = self.first;
+/// Item of the Huffman tree, StormLib's `THTreeItem`
+///
+/// All items are also kept in a list sorted by descending weight, in which
+/// the two children of an item are neighbours: the lower weight child and,
+/// before it, the higher weight one.
+#[derive(Debug, Clone, Copy, Default)]
+struct Item {
+    /// Next item in the list, with lower or equal weight; `None` if unlinked
+    next: Option<usize>,
+    /// Previous item in the list, with higher or equal weight
+    prev: Option<usize>,
+    /// Symbol of a leaf
+    value: usize,
+    weight: u32,
+    parent: Option<usize>,
+    /// Lower weight child; the higher weight child precedes it in the list
+    child_lo: Option<usize>,
+}
 
-            if let Some(first_idx) = self.first {
-                self.items[first_idx].prev = Some(new_item_idx);
-            }
+/// Adaptive Huffman tree shared by the compressor and decompressor
+struct HuffmanTree {
+    /// Item pool; the first item is the list head, whose `next` is the
+    /// highest weight item (the root) and whose `prev` the lowest weight one
+    items: Vec<Item>,
+    /// Leaf of each symbol
+    leaves: [Option<usize>; 0x102],
+    /// Type 0 increments the weight of every byte; the others only of bytes
+    /// added to the tree
+    adaptive: bool,
+}
 
-            self.first = Some(new_item_idx);
+impl HuffmanTree {
+    /// Build the initial tree for a compression type
+    fn new(compression_type: u8) -> Result<Self> {
+        let weights = WEIGHT_TABLES
+            .get((compression_type & 0x0F) as usize)
+            .ok_or_else(|| {
+                Error::compression(format!(
+                    "Invalid Huffman compression type: 0x{compression_type:02X}"
+                ))
+            })?;
+
+        let head = Item {
+            next: Some(HEAD),
+            prev: Some(HEAD),
+            ..Default::default()
+        };
+        let mut tree = Self {
+            items: vec![head],
+            leaves: [None; 0x102],
+            adaptive: compression_type == 0,
+        };
 
-            if self.last.is_none() {
-                self.last = Some(new_item_idx);
+        // The list of leaves, sorted by weight
+        let mut max_weight = 0;
+        for (value, &weight) in weights.iter().enumerate().take(0x100) {
+            if weight != 0 {
+                let item = tree.create_item(value, weight as u32, true)?;
+                tree.leaves[value] = Some(item);
+                max_weight = tree.fixup_position(item, max_weight);
             }
         }
-    }
-
-    // Insert item before a given position
-    fn insert_before(&mut self, new_item_idx: usize, before_idx: Option<usize>) {
-        if let Some(idx) = before_idx {
-            // Get the previous item
-            let prev_idx = self.items[idx].prev;
-
-            self.items[new_item_idx].next = Some(idx);
-            self.items[new_item_idx].prev = prev_idx;
-            self.items[idx].prev = Some(new_item_idx);
-
-            if let Some(prev) = prev_idx {
-                self.items[prev].next = Some(new_item_idx);
-            } else {
-                // This was the first item
-                self.first = Some(new_item_idx);
-            }
-        } else {
-            // Insert at the end of the list
-            self.items[new_item_idx].next = None;
-            self.items[new_item_idx].prev = self.last;
-
-            if let Some(last_idx) = self.last {
-                self.items[last_idx].next = Some(new_item_idx);
+        tree.leaves[END_OF_STREAM] = Some(tree.create_item(END_OF_STREAM, 1, false)?);
+        tree.leaves[NEW_BYTE] = Some(tree.create_item(NEW_BYTE, 1, false)?);
+
+        // Pair items from the lowest weight up until only the root is left
+        let mut child_lo = tree.last();
+        while child_lo != HEAD {
+            let child_hi = tree.prev(child_lo);
+            if child_hi == HEAD {
+                break;
             }
 
-            self.last = Some(new_item_idx);
+            let weight = tree.items[child_hi].weight + tree.items[child_lo].weight;
+            let parent = tree.create_item(0, weight, true)?;
+            tree.items[child_lo].parent = Some(parent);
+            tree.items[child_hi].parent = Some(parent);
+            tree.items[parent].child_lo = Some(child_lo);
+            max_weight = tree.fixup_position(parent, max_weight);
 
-            if self.first.is_none() {
-                self.first = Some(new_item_idx);
-            }
+            child_lo = tree.prev(child_hi);
         }
-    }
 
-    // Create a new item and add it to the list
-    fn create_new_item(
-        &mut self,
-        decompressed_value: u32,
-        weight: u32,
-        insert_after: bool,
-    ) -> usize {
-        let new_idx = self.items.len();
-        self.items
-            .push(HuffmanItem::new(decompressed_value, weight));
-
-        // For the initial list, just append to the end
-        // The fixup function will move it to the correct position
-        if self.first.is_none() {
-            self.first = Some(new_idx);
-            self.last = Some(new_idx);
-        } else if insert_after {
-            self.insert_after(new_idx, self.last);
-        } else {
-            self.insert_before(new_idx, self.first);
-        }
+        Ok(tree)
+    }
 
-        new_idx
+    fn first(&self) -> usize {
+        self.next(HEAD)
     }
 
-    // Find item with weight >= given weight
-    fn find_higher_or_equal_item(&self, start_idx: Option<usize>, weight: u32) -> Option<usize> {
-        let mut current = start_idx;
+    fn last(&self) -> usize {
+        self.prev(HEAD)
+    }
 
-        while let Some(idx) = current {
-            if self.items[idx].weight >= weight {
-                return Some(idx);
-            }
-            current = self.items[idx].prev;
-        }
+    fn next(&self, item: usize) -> usize {
+        self.items[item].next.unwrap_or(HEAD)
+    }
 
-        None
+    fn prev(&self, item: usize) -> usize {
+        self.items[item].prev.unwrap_or(HEAD)
     }
 
-    // Fix item position by weight (insertion sort)
-    fn fixup_item_pos_by_weight(&mut self, item_idx: usize, mut max_weight: u32) -> u32 {
-        let item_weight = self.items[item_idx].weight;
+    /// Insert `item` after `after` in the list
+    fn link_after(&mut self, after: usize, item: usize) {
+        let next = self.next(after);
+        self.items[item].next = Some(next);
+        self.items[item].prev = self.items[next].prev;
+        self.items[next].prev = Some(item);
+        self.items[after].next = Some(item);
+    }
 
-        // If this item's weight is less than max_weight, we need to move it
-        if item_weight < max_weight {
-            // Find the item with weight >= this item's weight, searching backwards from last
-            if let Some(higher_idx) = self.find_higher_or_equal_item(self.last, item_weight) {
-                // Remove item from its current position
-                self.remove_item_from_list(item_idx);
+    /// Insert `item` before `before` in the list
+    fn link_before(&mut self, before: usize, item: usize) {
+        let prev = self.prev(before);
+        self.items[item].next = Some(before);
+        self.items[item].prev = Some(prev);
+        self.items[prev].next = Some(item);
+        self.items[before].prev = Some(item);
+    }
 
-                // Insert after the found item (LinkTwoItems behavior)
-                self.insert_after(item_idx, Some(higher_idx));
-            }
-        } else {
-            // This is the new maximum weight
-            max_weight = item_weight;
+    fn unlink(&mut self, item: usize) {
+        if let (Some(next), Some(prev)) = (self.items[item].next, self.items[item].prev) {
+            self.items[prev].next = Some(next);
+            self.items[next].prev = Some(prev);
+            self.items[item].next = None;
+            self.items[item].prev = None;
         }
-
-        max_weight
     }
 
-    // Remove item from linked list (but keep in items array)
-    fn remove_item_from_list(&mut self, item_idx: usize) {
-        let prev = self.items[item_idx].prev;
-        let next = self.items[item_idx].next;
-
-        if let Some(prev_idx) = prev {
-            self.items[prev_idx].next = next;
-        } else {
-            // This was the first item
-            self.first = next;
+    /// Allocate an item at the front (`at_front`) or back of the list
+    fn create_item(&mut self, value: usize, weight: u32, at_front: bool) -> Result<usize> {
+        if self.items.len() > HUFF_ITEM_COUNT {
+            return Err(Error::compression("Huffman tree is full"));
         }
 
-        if let Some(next_idx) = next {
-            self.items[next_idx].prev = prev;
+        let item = self.items.len();
+        self.items.push(Item {
+            value,
+            weight,
+            ..Default::default()
+        });
+        if at_front {
+            self.link_after(HEAD, item);
         } else {
-            // This was the last item
-            self.last = prev;
+            self.link_before(HEAD, item);
         }
-
-        self.items[item_idx].prev = None;
-        self.items[item_idx].next = None;
+        Ok(item)
     }
 
-    fn build_tree(&mut self, compression_type: u32) -> Result<()> {
-        // Clear all items
-        self.items.clear();
-        self.items_by_byte = [None; 0x102];
-        self.first = LIST_HEAD;
-        self.last = LIST_HEAD;
-
-        // Ensure compression type is valid
-        let comp_type = (compression_type & 0x0F) as usize;
-        if comp_type >= WEIGHT_TABLES.len() {
-            return Err(Error::compression("Invalid Huffman compression type"));
-        }
-
-        let weight_table = WEIGHT_TABLES[comp_type];
-
-        // Build the linear list of entries that is sorted by byte weight
-        // First pass: create all items
-        for (i, &weight) in weight_table.iter().enumerate().take(0x100) {
-            if weight != 0 {
-                let item_idx = self.create_new_item(i as u32, weight as u32, true);
-                self.items_by_byte[i] = Some(item_idx);
+    /// Walk towards higher weights from `start` to the first item weighing
+    /// at least `weight`, or the list head if there is none
+    fn find_higher_or_equal(&self, mut item: usize, weight: u32) -> usize {
+        while item != HEAD {
+            if self.items[item].weight >= weight {
+                return item;
             }
+            item = self.prev(item);
         }
+        HEAD
+    }
 
-        // Insert termination entries
-        let end_idx = self.create_new_item(0x100, 1, true);
-        self.items_by_byte[0x100] = Some(end_idx);
-
-        let eof_idx = self.create_new_item(0x101, 1, true);
-        self.items_by_byte[0x101] = Some(eof_idx);
-
-        // Now sort the entire list by weight using insertion sort
-        // Build a temporary sorted list
-        let mut sorted_items: Vec<(usize, u32)> = Vec::new();
-        let mut curr = self.first;
-        while let Some(idx) = curr {
-            sorted_items.push((idx, self.items[idx].weight));
-            curr = self.items[idx].next;
+    /// Move a new item from the front of the list to its place by weight
+    fn fixup_position(&mut self, item: usize, max_weight: u32) -> u32 {
+        let weight = self.items[item].weight;
+        if weight < max_weight {
+            let higher = self.find_higher_or_equal(self.last(), weight);
+            self.unlink(item);
+            self.link_after(higher, item);
+            max_weight
+        } else {
+            weight
         }
+    }
 
-        // Sort by weight (DESCENDING - highest weight first)
-        // This matches StormLib's organization where pLast points to lowest weight
-        sorted_items.sort_by_key(|&(_, weight)| std::cmp::Reverse(weight));
-
-        // Rebuild the linked list in sorted order
-        self.first = None;
-        self.last = None;
-
-        for &(idx, _) in &sorted_items {
-            // Clear existing links
-            self.items[idx].prev = None;
-            self.items[idx].next = None;
-
-            // Add to the end of the new list
-            if self.first.is_none() {
-                self.first = Some(idx);
-                self.last = Some(idx);
-            } else {
-                self.items[idx].prev = self.last;
-                if let Some(last_idx) = self.last {
-                    self.items[last_idx].next = Some(idx);
+    /// Increment the weight of a leaf and its ancestors, swapping items
+    /// that end up out of order
+    fn increment_weight(&mut self, leaf: usize) -> Result<()> {
+        let mut current = Some(leaf);
+        while let Some(item) = current {
+            self.items[item].weight += 1;
+            let weight = self.items[item].weight;
+
+            let higher = self.find_higher_or_equal(self.prev(item), weight);
+            let child_hi = self.next(higher);
+            if child_hi != item {
+                // Swap the item with the first item of its old weight
+                self.unlink(child_hi);
+                self.link_after(item, child_hi);
+                self.unlink(item);
+                self.link_after(higher, item);
+
+                let (Some(item_parent), Some(other_parent)) =
+                    (self.items[item].parent, self.items[child_hi].parent)
+                else {
+                    return Err(Error::compression("Corrupt Huffman tree"));
+                };
+                let other_child_lo = self.items[other_parent].child_lo;
+                if self.items[item_parent].child_lo == Some(item) {
+                    self.items[item_parent].child_lo = Some(child_hi);
                 }
-                self.last = Some(idx);
-            }
-        }
-
-        log::debug!("Built initial list with {} leaf nodes", self.items.len());
-
-        // Debug: Print the linked list order
-        if log::log_enabled!(log::Level::Trace) {
-            log::trace!("Initial linked list (first -> last):");
-            let mut curr = self.first;
-            let mut count = 0;
-            while let Some(idx) = curr {
-                if count < 5 || count >= self.items.len() - 5 {
-                    log::trace!(
-                        "  [{}] idx={}, value=0x{:02X}, weight={}",
-                        count,
-                        idx,
-                        self.items[idx].decompressed_value,
-                        self.items[idx].weight
-                    );
-                } else if count == 5 {
-                    log::trace!("  ...");
+                if other_child_lo == Some(child_hi) {
+                    self.items[other_parent].child_lo = Some(item);
                 }
-                curr = self.items[idx].next;
-                count += 1;
+                self.items[item].parent = Some(other_parent);
+                self.items[child_hi].parent = Some(item_parent);
             }
+
+            current = self.items[item].parent;
         }
+        Ok(())
+    }
 
-        // Now we need to build the tree
-        // IMPORTANT: After reviewing StormLib more carefully:
-        // - The list is sorted DESCENDING (highest weight first, lowest weight last)
-        // - pLast points to the LOWEST weight node
-        // - We start from pLast and use pPrev to go towards higher weights
-        // - This way we pair the lowest weight nodes first (correct for Huffman)
-        let mut child_lo = self.last;
-        let mut internal_nodes = 0;
-        // Max weight is at the beginning of the list (descending order)
-        let mut max_weight = if let Some(first_idx) = self.first {
-            self.items[first_idx].weight
-        } else {
-            0
-        };
+    /// Add a byte to the tree by splitting the lowest weight leaf
+    fn insert_byte(&mut self, value: usize) -> Result<()> {
+        let last = self.last();
+        let last_value = self.items[last].value;
 
-        // Work as long as both children are valid
-        while let Some(lo_idx) = child_lo {
-            // Get the previous child (pPrev points to higher weight in descending list)
-            let child_hi = self.items[lo_idx].prev;
-            if child_hi.is_none() {
-                log::debug!(
-                    "Tree building stopped: no more pairs (created {internal_nodes} internal nodes)"
-                );
-                break; // Can't create a parent with only one child
-            }
-            let hi_idx = child_hi.unwrap();
-
-            log::trace!(
-                "Building parent for children: lo={} (w={}), hi={} (w={})",
-                lo_idx,
-                self.items[lo_idx].weight,
-                hi_idx,
-                self.items[hi_idx].weight
-            );
-
-            // Create new parent item for the children
-            let parent_weight = self.items[hi_idx].weight + self.items[lo_idx].weight;
-            let parent_idx = self.create_new_item(0xFFFFFFFFu32, parent_weight, true);
-            internal_nodes += 1;
-
-            // Link both child items to their new parent
-            self.items[lo_idx].parent = Some(parent_idx);
-            self.items[hi_idx].parent = Some(parent_idx);
-            self.items[parent_idx].child_lo = Some(lo_idx);
-
-            // IMPORTANT: Save the next child BEFORE fixup
-            // In StormLib: pChildLo = pChildHi->pPrev
-            let next_child = self.items[hi_idx].prev;
-
-            // Fixup the item's position by its weight
-            max_weight = self.fixup_item_pos_by_weight(parent_idx, max_weight);
-
-            // Continue from where we left off
-            // In StormLib, they just continue with pChildHi->pPrev
-            // The paired nodes are effectively out of consideration because they have parents
-            child_lo = next_child;
-
-            log::trace!("Next iteration will start from: {child_lo:?}");
-        }
+        let child_hi = self.create_item(last_value, self.items[last].weight, false)?;
+        self.items[child_hi].parent = Some(last);
+        self.leaves[last_value] = Some(child_hi);
 
-        log::debug!(
-            "Tree building complete: {} total items ({} leaf + {} internal)",
-            self.items.len(),
-            self.items.len() - internal_nodes,
-            internal_nodes
-        );
+        let child_lo = self.create_item(value, 0, false)?;
+        self.items[child_lo].parent = Some(last);
+        self.items[last].child_lo = Some(child_lo);
+        self.leaves[value] = Some(child_lo);
 
-        // Initialize the MinValidValue to 1, which invalidates all quick-link items
-        self.min_valid_value = 1;
-        Ok(())
+        self.increment_weight(child_lo)
     }
 
-    fn decode_one_byte(&mut self, reader: &mut BitReader<'_>) -> Result<u32> {
-        // Try quick links first
-        let item_link_index = reader.peek_7_bits().unwrap_or(0) as usize;
-        let has_item_link = item_link_index < LINK_ITEM_COUNT;
-
-        // Check if quick link is valid
-        let mut item_idx = if has_item_link
-            && self.quick_links[item_link_index].valid_value >= self.min_valid_value
+    /// Weight update after a byte was coded
+    fn update(&mut self, value: usize, added: bool) -> Result<()> {
+        if (added || self.adaptive)
+            && let Some(leaf) = self.leaves[value]
         {
-            // If that item needs less than 7 bits, we can get decompressed value directly
-            if self.quick_links[item_link_index].valid_bits <= 7 {
-                reader.skip_bits(self.quick_links[item_link_index].valid_bits);
-                return Ok(self.quick_links[item_link_index].decompressed_value);
-            }
-
-            // Otherwise skip 7 bits and use the stored item
-            reader.skip_bits(7);
-            self.quick_links[item_link_index].item_index
-        } else {
-            // Start from the root (first item)
-            self.first
-        };
+            self.increment_weight(leaf)?;
+        }
+        Ok(())
+    }
 
-        // Check if we have a valid starting point
-        if item_idx.is_none() {
+    fn decode_symbol(&self, reader: &mut BitReader<'_>) -> Result<usize> {
+        let mut item = self.first();
+        if item == HEAD {
             return Err(Error::compression("Empty Huffman tree"));
         }
 
-        let mut item_link: Option<usize> = None;
-        let mut bit_count = 0;
-
-        // Step down the tree until we find a terminal item
-        while let Some(idx) = item_idx {
-            let item = &self.items[idx];
-
-            // If this is a leaf node (no children), we found our value
-            if item.child_lo.is_none() {
-                break;
-            }
-
-            // Get next bit to decide which child to traverse
-            let bit_value = reader.get_bit()?;
-            bit_count += 1;
-
-            // In StormLib:
-            // If bit is 1: go to pItem->pChildLo->pPrev (higher weight child)
-            // If bit is 0: go to pItem->pChildLo (lower weight child)
-            let child_lo_idx = item.child_lo.unwrap();
-            item_idx = if bit_value != 0 {
-                // Get the previous sibling of child_lo (higher weight)
-                self.items[child_lo_idx].prev
+        // A set bit selects the higher weight child
+        while let Some(child_lo) = self.items[item].child_lo {
+            item = if reader.get_bit()? != 0 {
+                self.prev(child_lo)
             } else {
-                // Get child_lo directly (lower weight)
-                Some(child_lo_idx)
+                child_lo
             };
-
-            // Remember item for quick link at depth 7
-            if bit_count == 7 {
-                item_link = item_idx;
-            }
         }
+        Ok(self.items[item].value)
+    }
 
-        // We should have a valid item now
-        let final_idx =
-            item_idx.ok_or_else(|| Error::compression("Invalid Huffman tree traversal"))?;
-        let decompressed_value = self.items[final_idx].decompressed_value;
-
-        // Update quick link if needed
-        if has_item_link && self.quick_links[item_link_index].valid_value < self.min_valid_value {
-            if bit_count > 7 {
-                // Store pointer to tree item for faster access
-                self.quick_links[item_link_index].valid_value = self.min_valid_value;
-                self.quick_links[item_link_index].valid_bits = bit_count;
-                self.quick_links[item_link_index].item_index = item_link;
-            } else if bit_count > 0 {
-                // Store decompressed value directly
-                let mut index = item_link_index;
-                if bit_count < 7 {
-                    index &= (0xFFFFFFFFu32 >> (32 - bit_count)) as usize;
-                }
-
-                while index < LINK_ITEM_COUNT {
-                    self.quick_links[index].valid_value = self.min_valid_value;
-                    self.quick_links[index].valid_bits = bit_count;
-                    self.quick_links[index].decompressed_value = decompressed_value;
-                    index += 1 << bit_count;
-                }
-            }
+    fn encode_symbol(&self, writer: &mut BitWriter, leaf: usize, path: &mut Vec<u32>) {
+        path.clear();
+        let mut item = leaf;
+        while let Some(parent) = self.items[item].parent {
+            path.push((self.items[parent].child_lo != Some(item)) as u32);
+            item = parent;
+        }
+        for &bit in path.iter().rev() {
+            writer.put_bit(bit);
         }
-
-        Ok(decompressed_value)
     }
 }
 
-/// Huffman decompression function following StormLib's algorithm
+/// Decompress a Huffman stream into at most `expected_size` bytes
 pub(crate) fn decompress(data: &[u8], expected_size: usize) -> Result<Vec<u8>> {
-    if data.is_empty() {
+    if data.is_empty() || expected_size == 0 {
         return Ok(Vec::new());
     }
 
-    log::debug!(
-        "Huffman decompress input: {} bytes, expected: {}, first 16 bytes: {:02X?}",
-        data.len(),
-        expected_size,
-        &data[..std::cmp::min(16, data.len())]
-    );
-
     let mut reader = BitReader::new(data);
-    let mut output = Vec::with_capacity(expected_size);
-
-    // Get compression type from the first byte
-    let compression_type = reader.get_8_bits()?;
+    let compression_type = reader.get_8_bits()? as u8;
+    let mut tree = HuffmanTree::new(compression_type)?;
 
-    log::debug!("Huffman compression type: 0x{compression_type:02X}");
-
-    // Build Huffman tree
-    let mut tree = HuffmanTree::new();
-    tree.build_tree(compression_type)?;
-
-    log::debug!("Huffman tree built with {} items", tree.items.len());
-
-    // Decompress data
-    let mut decoded_count = 0;
+    let mut output = Vec::with_capacity(expected_size);
     loop {
-        let decoded_value = tree.decode_one_byte(&mut reader)?;
-        decoded_count += 1;
-
-        log::trace!("Huffman decoded byte {decoded_count}: value=0x{decoded_value:03X}");
-
-        // Check for end of stream marker
-        if decoded_value == 0x100 {
-            log::debug!("Huffman hit end marker after {decoded_count} symbols");
+        let mut value = tree.decode_symbol(&mut reader)?;
+        if value == END_OF_STREAM {
             break;
         }
 
-        // Check for error
-        if decoded_value == HUFF_DECOMPRESS_ERROR {
-            return Err(Error::compression("Huffman decompression error"));
-        }
-
-        // Handle tree modification marker
-        if decoded_value == 0x101 {
-            // Read the next byte directly
-            let next_byte = reader.get_8_bits()?;
-            output.push(next_byte as u8);
-            // Tree modification would happen here in full implementation
-            continue;
-        }
-
-        // Regular data byte
-        if decoded_value > 255 {
-            return Err(Error::compression("Invalid Huffman decoded value"));
+        let added = value == NEW_BYTE;
+        if added {
+            value = reader.get_8_bits()? as usize;
+            tree.insert_byte(value)?;
         }
-
-        output.push(decoded_value as u8);
-
-        // Stop if we've reached expected size
+        output.push(value as u8);
         if output.len() >= expected_size {
             break;
         }
+        tree.update(value, added)?;
     }
 
-    log::debug!(
-        "Huffman decompress output: {} bytes, first 16 bytes: {:02X?}",
-        output.len(),
-        &output[..std::cmp::min(16, output.len())]
-    );
-
     Ok(output)
 }
 
-/// Huffman compression stub - not needed for reading MPQ archives
-pub(crate) fn compress(_data: &[u8]) -> Result<Vec<u8>> {
-    // Compression is not implemented as it's not needed for reading MPQ archives
-    Err(Error::compression(
-        "Huffman compression not implemented - only decompression is available",
-    ))
+/// Compress data with Huffman type 0, as used on its own
+pub(crate) fn compress(data: &[u8]) -> Result<Vec<u8>> {
+    compress_with_type(data, 0)
+}
+
+/// Compress data starting from the weight table of `compression_type`
+pub(crate) fn compress_with_type(data: &[u8], compression_type: u8) -> Result<Vec<u8>> {
+    let mut tree = HuffmanTree::new(compression_type)?;
+    let mut writer = BitWriter::with_capacity(data.len() / 2 + 16);
+    let mut path = Vec::new();
+    writer.put_8_bits(compression_type);
+
+    for &byte in data {
+        let value = byte as usize;
+        let added = match tree.leaves[value] {
+            Some(leaf) => {
+                tree.encode_symbol(&mut writer, leaf, &mut path);
+                false
+            }
+            None => {
+                let escape = tree.leaves[NEW_BYTE]
+                    .ok_or_else(|| Error::compression("Corrupt Huffman tree"))?;
+                tree.encode_symbol(&mut writer, escape, &mut path);
+                writer.put_8_bits(byte);
+                tree.insert_byte(value)?;
+                true
+            }
+        };
+        tree.update(value, added)?;
+    }
+
+    let end =
+        tree.leaves[END_OF_STREAM].ok_or_else(|| Error::compression("Corrupt Huffman tree"))?;
+    tree.encode_symbol(&mut writer, end, &mut path);
+    Ok(writer.finish())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// Bytes resembling ADPCM output: small values around zero
+    fn adpcm_like(len: usize) -> Vec<u8> {
+        let mut state = 0x1234_5678u32;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                match state % 16 {
+                    0 => 0x80,
+                    1 => 0x81,
+                    n => (n as u8 & 0x07) | if state & 0x100 != 0 { 0x40 } else { 0 },
+                }
+            })
+            .collect()
+    }
+
     #[test]
     fn test_huffman_empty_data() {
-        assert!(decompress(&[], 0).is_ok());
+        assert!(decompress(&[], 0).unwrap().is_empty());
+        let compressed = compress(&[]).unwrap();
+        assert_eq!(compressed[0], 0);
+        assert!(decompress(&compressed, 16).unwrap().is_empty());
     }
 
     #[test]
@@ -869,9 +693,38 @@ mod tests {
         assert_eq!(reader.get_bit().unwrap(), 0); // bit 1
         assert_eq!(reader.get_bit().unwrap(), 1); // bit 2
         assert_eq!(reader.get_bit().unwrap(), 1); // bit 3
-        assert_eq!(reader.get_bit().unwrap(), 0); // bit 4
-        assert_eq!(reader.get_bit().unwrap(), 1); // bit 5
-        assert_eq!(reader.get_bit().unwrap(), 0); // bit 6
-        assert_eq!(reader.get_bit().unwrap(), 1); // bit 7
+
+        // A byte spanning two input bytes
+        assert_eq!(reader.get_8_bits().unwrap(), 0b0000_1010);
+    }
+
+    #[test]
+    fn test_huffman_round_trip_all_types() {
+        let text = b"The quick brown fox jumps over the lazy dog. \x00\x01\xFE\xFF".repeat(40);
+        let audio = adpcm_like(5000);
+
+        for compression_type in 0..WEIGHT_TABLES.len() as u8 {
+            for data in [&text, &audio] {
+                let compressed = compress_with_type(data, compression_type).unwrap();
+                assert_eq!(compressed[0], compression_type);
+                let decompressed = decompress(&compressed, data.len()).unwrap();
+                assert_eq!(&decompressed, data, "type {compression_type}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_huffman_compresses_adpcm_output() {
+        let audio = adpcm_like(4096);
+        let compressed = compress_with_type(&audio, wave_compression_type(5)).unwrap();
+        assert!(compressed.len() < audio.len());
+    }
+
+    #[test]
+    fn test_huffman_invalid_stream() {
+        assert!(decompress(&[0x09, 0x00], 16).is_err());
+        // Truncated before the end of stream symbol
+        let compressed = compress(b"truncated stream").unwrap();
+        assert!(decompress(&compressed[..compressed.len() / 2], 16).is_err());
     }
 }
@@ -9,3 +9,4 @@ pub(super) mod pkware;
 pub mod rle; // Public for patch file handling
 pub(super) mod sparse;
 pub(super) mod zlib;
+pub(super) mod zstd;
@@ -1,27 +1,125 @@
-//! PKWare compression implementation using implode crate for MPQ archives
+//! PKWare Data Compression Library (DCL) implode and explode
+//!
+//! Used by the PKWARE compression method and, without a compression byte, by
+//! files with the IMPLODE block flag in early archives (Diablo, StarCraft,
+//! Diablo II and early Warcraft III). A stream starts with two bytes, the
+//! literal mode and the dictionary size, followed by LSB-first codes: a 0 bit
+//! and a literal, or a 1 bit, a length and a distance. The length 519 ends the
+//! stream. Based on the tables and layout of StormLib's `explode.c` and
+//! `implode.c`.
+
+use std::sync::OnceLock;
 
 use crate::Result;
-use crate::compression::error_helpers::{compression_error, decompression_error};
-use implode::exploder::Exploder;
-use implode::symbol::DEFAULT_CODE_TABLE;
-use pklib::{CompressionMode, DictionarySize, implode_bytes};
+use crate::compression::error_helpers::decompression_error;
+
+/// Literals are stored as plain bytes
+pub(crate) const MODE_BINARY: u8 = 0;
+/// Literals use a fixed code favoring printable ASCII
+pub(crate) const MODE_ASCII: u8 = 1;
+
+/// Encoded length that marks the end of the stream
+const END_OF_STREAM: usize = 519;
+/// Longest match a stream can encode
+const MAX_MATCH: usize = END_OF_STREAM - 1;
+/// Matches of two bytes only encode two low distance bits
+const MAX_SHORT_DISTANCE: usize = 64 << 2;
+/// Candidates checked per position when looking for a match
+const MAX_CHAIN: usize = 32;
+
+const DIST_BITS: [u8; 64] = [
+    0x02, 0x04, 0x04, 0x05, 0x05, 0x05, 0x05, 0x06, 0x06, 0x06, 0x06, 0x06, 0x06, 0x06, 0x06, 0x06,
+    0x06, 0x06, 0x06, 0x06, 0x06, 0x06, 0x07, 0x07, 0x07, 0x07, 0x07, 0x07, 0x07, 0x07, 0x07, 0x07,
+    0x07, 0x07, 0x07, 0x07, 0x07, 0x07, 0x07, 0x07, 0x07, 0x07, 0x07, 0x07, 0x07, 0x07, 0x07, 0x07,
+    0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08,
+];
+
+const DIST_CODE: [u8; 64] = [
+    0x03, 0x0D, 0x05, 0x19, 0x09, 0x11, 0x01, 0x3E, 0x1E, 0x2E, 0x0E, 0x36, 0x16, 0x26, 0x06, 0x3A,
+    0x1A, 0x2A, 0x0A, 0x32, 0x12, 0x22, 0x42, 0x02, 0x7C, 0x3C, 0x5C, 0x1C, 0x6C, 0x2C, 0x4C, 0x0C,
+    0x74, 0x34, 0x54, 0x14, 0x64, 0x24, 0x44, 0x04, 0x78, 0x38, 0x58, 0x18, 0x68, 0x28, 0x48, 0x08,
+    0xF0, 0x70, 0xB0, 0x30, 0xD0, 0x50, 0x90, 0x10, 0xE0, 0x60, 0xA0, 0x20, 0xC0, 0x40, 0x80, 0x00,
+];
+
+const EX_LEN_BITS: [u8; 16] = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08,
+];
+
+const LEN_BASE: [u16; 16] = [
+    0x0000, 0x0001, 0x0002, 0x0003, 0x0004, 0x0005, 0x0006, 0x0007, 0x0008, 0x000A, 0x000E, 0x0016,
+    0x0026, 0x0046, 0x0086, 0x0106,
+];
 
-/// Compress data using PKWare DCL algorithm
+const LEN_BITS: [u8; 16] = [
+    0x03, 0x02, 0x03, 0x03, 0x04, 0x04, 0x04, 0x05, 0x05, 0x05, 0x05, 0x06, 0x06, 0x06, 0x07, 0x07,
+];
+
+const LEN_CODE: [u8; 16] = [
+    0x05, 0x03, 0x01, 0x06, 0x0A, 0x02, 0x0C, 0x14, 0x04, 0x18, 0x08, 0x30, 0x10, 0x20, 0x40, 0x00,
+];
+
+const CH_BITS_ASC: [u8; 256] = [
+    0x0B, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x08, 0x07, 0x0C, 0x0C, 0x07, 0x0C, 0x0C,
+    0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x0D, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C,
+    0x04, 0x0A, 0x08, 0x0C, 0x0A, 0x0C, 0x0A, 0x08, 0x07, 0x07, 0x08, 0x09, 0x07, 0x06, 0x07, 0x08,
+    0x07, 0x06, 0x07, 0x07, 0x07, 0x07, 0x08, 0x07, 0x07, 0x08, 0x08, 0x0C, 0x0B, 0x07, 0x09, 0x0B,
+    0x0C, 0x06, 0x07, 0x06, 0x06, 0x05, 0x07, 0x08, 0x08, 0x06, 0x0B, 0x09, 0x06, 0x07, 0x06, 0x06,
+    0x07, 0x0B, 0x06, 0x06, 0x06, 0x07, 0x09, 0x08, 0x09, 0x09, 0x0B, 0x08, 0x0B, 0x09, 0x0C, 0x08,
+    0x0C, 0x05, 0x06, 0x06, 0x06, 0x05, 0x06, 0x06, 0x06, 0x05, 0x0B, 0x07, 0x05, 0x06, 0x05, 0x05,
+    0x06, 0x0A, 0x05, 0x05, 0x05, 0x05, 0x08, 0x07, 0x08, 0x08, 0x0A, 0x0B, 0x0B, 0x0C, 0x0C, 0x0C,
+    0x0D, 0x0D, 0x0D, 0x0D, 0x0D, 0x0D, 0x0D, 0x0D, 0x0D, 0x0D, 0x0D, 0x0D, 0x0D, 0x0D, 0x0D, 0x0D,
+    0x0D, 0x0D, 0x0D, 0x0D, 0x0D, 0x0D, 0x0D, 0x0D, 0x0D, 0x0D, 0x0D, 0x0D, 0x0D, 0x0D, 0x0D, 0x0D,
+    0x0D, 0x0D, 0x0D, 0x0D, 0x0D, 0x0D, 0x0D, 0x0D, 0x0D, 0x0D, 0x0D, 0x0D, 0x0D, 0x0D, 0x0D, 0x0D,
+    0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C,
+    0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C,
+    0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C,
+    0x0D, 0x0C, 0x0D, 0x0D, 0x0D, 0x0C, 0x0D, 0x0D, 0x0D, 0x0C, 0x0D, 0x0D, 0x0D, 0x0D, 0x0C, 0x0D,
+    0x0D, 0x0D, 0x0C, 0x0C, 0x0C, 0x0D, 0x0D, 0x0D, 0x0D, 0x0D, 0x0D, 0x0D, 0x0D, 0x0D, 0x0D, 0x0D,
+];
+
+const CH_CODE_ASC: [u16; 256] = [
+    0x0490, 0x0FE0, 0x07E0, 0x0BE0, 0x03E0, 0x0DE0, 0x05E0, 0x09E0, 0x01E0, 0x00B8, 0x0062, 0x0EE0,
+    0x06E0, 0x0022, 0x0AE0, 0x02E0, 0x0CE0, 0x04E0, 0x08E0, 0x00E0, 0x0F60, 0x0760, 0x0B60, 0x0360,
+    0x0D60, 0x0560, 0x1240, 0x0960, 0x0160, 0x0E60, 0x0660, 0x0A60, 0x000F, 0x0250, 0x0038, 0x0260,
+    0x0050, 0x0C60, 0x0390, 0x00D8, 0x0042, 0x0002, 0x0058, 0x01B0, 0x007C, 0x0029, 0x003C, 0x0098,
+    0x005C, 0x0009, 0x001C, 0x006C, 0x002C, 0x004C, 0x0018, 0x000C, 0x0074, 0x00E8, 0x0068, 0x0460,
+    0x0090, 0x0034, 0x00B0, 0x0710, 0x0860, 0x0031, 0x0054, 0x0011, 0x0021, 0x0017, 0x0014, 0x00A8,
+    0x0028, 0x0001, 0x0310, 0x0130, 0x003E, 0x0064, 0x001E, 0x002E, 0x0024, 0x0510, 0x000E, 0x0036,
+    0x0016, 0x0044, 0x0030, 0x00C8, 0x01D0, 0x00D0, 0x0110, 0x0048, 0x0610, 0x0150, 0x0060, 0x0088,
+    0x0FA0, 0x0007, 0x0026, 0x0006, 0x003A, 0x001B, 0x001A, 0x002A, 0x000A, 0x000B, 0x0210, 0x0004,
+    0x0013, 0x0032, 0x0003, 0x001D, 0x0012, 0x0190, 0x000D, 0x0015, 0x0005, 0x0019, 0x0008, 0x0078,
+    0x00F0, 0x0070, 0x0290, 0x0410, 0x0010, 0x07A0, 0x0BA0, 0x03A0, 0x0240, 0x1C40, 0x0C40, 0x1440,
+    0x0440, 0x1840, 0x0840, 0x1040, 0x0040, 0x1F80, 0x0F80, 0x1780, 0x0780, 0x1B80, 0x0B80, 0x1380,
+    0x0380, 0x1D80, 0x0D80, 0x1580, 0x0580, 0x1980, 0x0980, 0x1180, 0x0180, 0x1E80, 0x0E80, 0x1680,
+    0x0680, 0x1A80, 0x0A80, 0x1280, 0x0280, 0x1C80, 0x0C80, 0x1480, 0x0480, 0x1880, 0x0880, 0x1080,
+    0x0080, 0x1F00, 0x0F00, 0x1700, 0x0700, 0x1B00, 0x0B00, 0x1300, 0x0DA0, 0x05A0, 0x09A0, 0x01A0,
+    0x0EA0, 0x06A0, 0x0AA0, 0x02A0, 0x0CA0, 0x04A0, 0x08A0, 0x00A0, 0x0F20, 0x0720, 0x0B20, 0x0320,
+    0x0D20, 0x0520, 0x0920, 0x0120, 0x0E20, 0x0620, 0x0A20, 0x0220, 0x0C20, 0x0420, 0x0820, 0x0020,
+    0x0FC0, 0x07C0, 0x0BC0, 0x03C0, 0x0DC0, 0x05C0, 0x09C0, 0x01C0, 0x0EC0, 0x06C0, 0x0AC0, 0x02C0,
+    0x0CC0, 0x04C0, 0x08C0, 0x00C0, 0x0F40, 0x0740, 0x0B40, 0x0340, 0x0300, 0x0D40, 0x1D00, 0x0D00,
+    0x1500, 0x0540, 0x0500, 0x1900, 0x0900, 0x0940, 0x1100, 0x0100, 0x1E00, 0x0E00, 0x0140, 0x1600,
+    0x0600, 0x1A00, 0x0E40, 0x0640, 0x0A40, 0x0A00, 0x1200, 0x0200, 0x1C00, 0x0C00, 0x1400, 0x0400,
+    0x1800, 0x0800, 0x1000, 0x0000,
+];
+
+/// Compress data with the PKWare DCL, as StormLib does for the PKWARE method
+///
+/// Binary literals, with the dictionary size StormLib picks for the input size.
 pub(crate) fn compress(data: &[u8]) -> Result<Vec<u8>> {
-    // Handle empty data
     if data.is_empty() {
         return Ok(Vec::new());
     }
 
-    // Use ASCII mode with 2KB dictionary as default for MPQ archives
-    // This provides good compression ratio for most data types
-    implode_bytes(data, CompressionMode::ASCII, DictionarySize::Size2K)
-        .map_err(|e| compression_error("PKWare", e))
+    let dict_bits = match data.len() {
+        0..0x600 => 4,
+        0x600..0xC00 => 5,
+        _ => 6,
+    };
+    Ok(implode(data, MODE_BINARY, dict_bits))
 }
 
-/// Decompress PKWare compressed data using implode crate (for MPQ archives)
+/// Decompress a PKWare DCL stream into at most `expected_size` bytes
 pub(crate) fn decompress(data: &[u8], expected_size: usize) -> Result<Vec<u8>> {
-    // Handle empty data
     if data.is_empty() {
         return Ok(Vec::new());
     }
@@ -33,65 +131,340 @@ pub(crate) fn decompress(data: &[u8], expected_size: usize) -> Result<Vec<u8>> {
         &data[..std::cmp::min(16, data.len())]
     );
 
-    // Use the implode crate for PKWare decompression in MPQ archives
-    // Based on the working implementation in msierks/mpq-rust
-    let mut exploder = Exploder::new(&DEFAULT_CODE_TABLE);
-    let mut output = Vec::with_capacity(expected_size);
-    let mut input_pos = 0;
-    let mut total_output = 0;
+    explode(data, expected_size).map_err(|e| decompression_error("PKWare", e))
+}
 
-    while !exploder.ended && input_pos < data.len() && total_output < expected_size {
-        let remaining_input = &data[input_pos..];
+/// Compress `data` into a DCL stream with the given literal mode and a
+/// dictionary of `64 << dict_bits` bytes (`dict_bits` 4 to 6)
+pub(crate) fn implode(data: &[u8], mode: u8, dict_bits: u8) -> Vec<u8> {
+    let mut writer = BitWriter::new(data.len() / 2 + 16);
+    writer.bytes.extend_from_slice(&[mode, dict_bits]);
+    let window = 64usize << dict_bits;
+    let mut matcher = Matcher::new(data.len());
 
-        match exploder.explode_block(remaining_input) {
-            Ok((consumed, output_block)) => {
-                input_pos += consumed;
+    let literal_cost = |byte: u8| -> u32 {
+        1 + if mode == MODE_ASCII {
+            u32::from(CH_BITS_ASC[byte as usize])
+        } else {
+            8
+        }
+    };
 
-                // Copy output block to our buffer
-                let copy_len = std::cmp::min(output_block.len(), expected_size - total_output);
-                output.extend_from_slice(&output_block[..copy_len]);
-                total_output += copy_len;
+    let mut pos = 0;
+    while pos < data.len() {
+        let (length, distance) = matcher.find(data, pos, window);
+        // A two byte match can cost more than its literals in ASCII mode
+        let take = match length {
+            0 | 1 => false,
+            2 => {
+                let code = (distance - 1) >> 2;
+                let match_cost = 1 + u32::from(LEN_BITS[0]) + u32::from(DIST_BITS[code]) + 2;
+                match_cost < literal_cost(data[pos]) + literal_cost(data[pos + 1])
+            }
+            _ => true,
+        };
 
-                log::debug!(
-                    "PKWare explode block: consumed {consumed} bytes, produced {copy_len} bytes, total output: {total_output}"
-                );
+        if take {
+            write_length(&mut writer, length);
+            let low_bits = if length == 2 { 2 } else { dict_bits };
+            let offset = distance - 1;
+            let code = offset >> low_bits;
+            writer.write(u32::from(DIST_CODE[code]), DIST_BITS[code]);
+            writer.write((offset & ((1 << low_bits) - 1)) as u32, low_bits);
+            for p in pos..pos + length {
+                matcher.insert(data, p);
             }
-            Err(e) => {
-                log::error!("PKWare decompression failed at input position {input_pos}: {e:?}");
-                return Err(decompression_error("PKWare", format!("{e:?}")));
+            pos += length;
+        } else {
+            let byte = data[pos];
+            writer.write(0, 1);
+            if mode == MODE_ASCII {
+                writer.write(
+                    u32::from(CH_CODE_ASC[byte as usize]),
+                    CH_BITS_ASC[byte as usize],
+                );
+            } else {
+                writer.write(u32::from(byte), 8);
             }
+            matcher.insert(data, pos);
+            pos += 1;
         }
     }
 
-    log::debug!(
-        "PKWare decompress complete: input {} bytes -> output {} bytes (expected {})",
-        data.len(),
-        output.len(),
-        expected_size
-    );
+    write_length(&mut writer, END_OF_STREAM);
+    writer.finish()
+}
+
+/// Write the match flag and a match length of 2 to 519
+fn write_length(writer: &mut BitWriter, length: usize) {
+    let value = (length - 2) as u16;
+    let code = (0..16).rev().find(|&c| LEN_BASE[c] <= value).unwrap_or(0);
+    writer.write(1, 1);
+    writer.write(u32::from(LEN_CODE[code]), LEN_BITS[code]);
+    writer.write(u32::from(value - LEN_BASE[code]), EX_LEN_BITS[code]);
+}
+
+/// Decompress a DCL stream, stopping at its end marker or after `max_size` bytes
+pub(crate) fn explode(data: &[u8], max_size: usize) -> std::result::Result<Vec<u8>, String> {
+    let [mode, dict_bits, ..] = *data else {
+        return Err("stream too short for its header".to_string());
+    };
+    if mode != MODE_BINARY && mode != MODE_ASCII {
+        return Err(format!("invalid literal mode {mode}"));
+    }
+    if !(4..=6).contains(&dict_bits) {
+        return Err(format!("invalid dictionary size bits {dict_bits}"));
+    }
+
+    let tables = tables();
+    let mut reader = BitReader::new(&data[2..]);
+    let mut output = Vec::with_capacity(max_size.min(data.len() * 8));
+
+    while output.len() < max_size {
+        if reader.read(1)? == 0 {
+            let literal = if mode == MODE_ASCII {
+                tables.literals.decode(&mut reader)?
+            } else {
+                reader.read(8)? as u16
+            };
+            output.push(literal as u8);
+            continue;
+        }
 
+        let code = tables.lengths.decode(&mut reader)? as usize;
+        let extra = reader.read(EX_LEN_BITS[code])? as usize;
+        let length = LEN_BASE[code] as usize + extra + 2;
+        if length == END_OF_STREAM {
+            break;
+        }
+
+        let low_bits = if length == 2 { 2 } else { dict_bits };
+        let code = tables.distances.decode(&mut reader)? as usize;
+        let distance = ((code << low_bits) | reader.read(low_bits)? as usize) + 1;
+        if distance > output.len() {
+            return Err(format!(
+                "match distance {distance} before the start of {} bytes of output",
+                output.len()
+            ));
+        }
+
+        // Matches may overlap the bytes they produce
+        let start = output.len() - distance;
+        for i in 0..length {
+            output.push(output[start + i]);
+        }
+    }
+
+    output.truncate(max_size);
     Ok(output)
 }
 
-/// Compress data using PKWare DCL algorithm with specific parameters
-#[allow(dead_code)]
-pub(crate) fn compress_with_options(
-    data: &[u8],
-    mode: CompressionMode,
-    dict_size: DictionarySize,
-) -> Result<Vec<u8>> {
-    // Handle empty data
-    if data.is_empty() {
-        return Ok(Vec::new());
+/// Lookup tables for the prefix codes of a stream
+struct Tables {
+    literals: PrefixCode,
+    lengths: PrefixCode,
+    distances: PrefixCode,
+}
+
+fn tables() -> &'static Tables {
+    static TABLES: OnceLock<Tables> = OnceLock::new();
+    TABLES.get_or_init(|| Tables {
+        literals: PrefixCode::new(CH_CODE_ASC.iter().copied().zip(CH_BITS_ASC)),
+        lengths: PrefixCode::new(LEN_CODE.iter().map(|&c| u16::from(c)).zip(LEN_BITS)),
+        distances: PrefixCode::new(DIST_CODE.iter().map(|&c| u16::from(c)).zip(DIST_BITS)),
+    })
+}
+
+/// An LSB-first prefix code, looked up by its longest code's worth of bits
+struct PrefixCode {
+    bits: u8,
+    /// Symbol and code length for every value of the next `bits` bits;
+    /// length 0 marks values no code starts
+    table: Vec<(u16, u8)>,
+}
+
+impl PrefixCode {
+    fn new(codes: impl Iterator<Item = (u16, u8)> + Clone) -> Self {
+        let bits = codes.clone().map(|(_, len)| len).max().unwrap_or(0);
+        let mut table = vec![(0, 0); 1 << bits];
+        for (symbol, (code, len)) in codes.enumerate() {
+            let mut index = code as usize;
+            while index < table.len() {
+                table[index] = (symbol as u16, len);
+                index += 1 << len;
+            }
+        }
+        Self { bits, table }
+    }
+
+    fn decode(&self, reader: &mut BitReader<'_>) -> std::result::Result<u16, String> {
+        let (symbol, len) = self.table[reader.peek(self.bits) as usize];
+        if len == 0 {
+            return Err("invalid prefix code".to_string());
+        }
+        reader.read(len)?;
+        Ok(symbol)
+    }
+}
+
+/// Reads bits LSB first; bits past the end read as zero when peeking
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    fn peek(&self, count: u8) -> u32 {
+        let mut value = 0u32;
+        for i in 0..count as usize {
+            let bit = self.position + i;
+            if let Some(byte) = self.data.get(bit / 8) {
+                value |= u32::from((byte >> (bit % 8)) & 1) << i;
+            }
+        }
+        value
+    }
+
+    fn read(&mut self, count: u8) -> std::result::Result<u32, String> {
+        if self.position + count as usize > self.data.len() * 8 {
+            return Err("stream truncated".to_string());
+        }
+        let value = self.peek(count);
+        self.position += count as usize;
+        Ok(value)
+    }
+}
+
+/// Writes bits LSB first
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    count: u8,
+}
+
+impl BitWriter {
+    fn new(capacity: usize) -> Self {
+        Self {
+            bytes: Vec::with_capacity(capacity),
+            buffer: 0,
+            count: 0,
+        }
+    }
+
+    fn write(&mut self, value: u32, bits: u8) {
+        self.buffer |= value << self.count;
+        self.count += bits;
+        while self.count >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+/// Hash chains over the positions seen so far
+struct Matcher {
+    /// Latest position of each three byte hash
+    head: Vec<u32>,
+    /// Previous position with the same hash, by position
+    previous: Vec<u32>,
+    /// Latest position of each byte pair
+    pairs: Vec<u32>,
+}
+
+const NONE: u32 = u32::MAX;
+const HASH_BITS: u32 = 13;
+
+impl Matcher {
+    fn new(len: usize) -> Self {
+        Self {
+            head: vec![NONE; 1 << HASH_BITS],
+            previous: vec![NONE; len],
+            pairs: vec![NONE; 1 << 16],
+        }
+    }
+
+    fn hash(data: &[u8], pos: usize) -> usize {
+        let key =
+            u32::from(data[pos]) << 16 | u32::from(data[pos + 1]) << 8 | u32::from(data[pos + 2]);
+        (key.wrapping_mul(0x9E37_79B1) >> (32 - HASH_BITS)) as usize
+    }
+
+    fn pair(data: &[u8], pos: usize) -> usize {
+        usize::from(data[pos]) << 8 | usize::from(data[pos + 1])
+    }
+
+    fn insert(&mut self, data: &[u8], pos: usize) {
+        if pos + 3 <= data.len() {
+            let hash = Self::hash(data, pos);
+            self.previous[pos] = self.head[hash];
+            self.head[hash] = pos as u32;
+        }
+        if pos + 2 <= data.len() {
+            self.pairs[Self::pair(data, pos)] = pos as u32;
+        }
     }
 
-    implode_bytes(data, mode, dict_size).map_err(|e| compression_error("PKWare", e))
+    /// Longest earlier match for `pos` within `window`, as (length, distance)
+    fn find(&self, data: &[u8], pos: usize, window: usize) -> (usize, usize) {
+        let limit = MAX_MATCH.min(data.len() - pos);
+        let mut best = (0, 0);
+        if limit < 2 {
+            return best;
+        }
+
+        if limit >= 3 {
+            let mut candidate = self.head[Self::hash(data, pos)];
+            for _ in 0..MAX_CHAIN {
+                if candidate == NONE || pos - candidate as usize > window {
+                    break;
+                }
+                let start = candidate as usize;
+                let length = data[start..]
+                    .iter()
+                    .zip(&data[pos..pos + limit])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                if length > best.0 {
+                    best = (length, pos - start);
+                    if length == limit {
+                        break;
+                    }
+                }
+                candidate = self.previous[start];
+            }
+        }
+
+        if best.0 < 3 {
+            best = (0, 0);
+            let candidate = self.pairs[Self::pair(data, pos)];
+            if candidate != NONE && pos - (candidate as usize) <= MAX_SHORT_DISTANCE {
+                best = (2, pos - candidate as usize);
+            }
+        }
+        best
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn sample(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 7 * 13 + i / 100) as u8).collect()
+    }
+
     #[test]
     fn test_pkware_empty_data() {
         // Test decompression of empty data
@@ -100,4 +473,39 @@ mod tests {
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), Vec::<u8>::new());
     }
+
+    #[test]
+    fn test_prefix_codes_are_complete() {
+        let tables = tables();
+        for code in [&tables.literals, &tables.lengths, &tables.distances] {
+            assert!(code.table.iter().all(|&(_, len)| len != 0));
+        }
+    }
+
+    #[test]
+    fn test_round_trip_all_modes() {
+        let text = b"The quick brown fox jumps over the lazy dog. ".repeat(200);
+        for data in [sample(10_000), text, vec![0u8; 70_000], b"ab".to_vec()] {
+            for mode in [MODE_BINARY, MODE_ASCII] {
+                for dict_bits in 4..=6 {
+                    let compressed = implode(&data, mode, dict_bits);
+                    assert_eq!(explode(&compressed, data.len()).unwrap(), data);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_known_stream() {
+        // "AIAIAIAIAIAIA" from the DCL documentation, binary with a 1 KiB dictionary
+        let stream = [0x00, 0x04, 0x82, 0x24, 0x25, 0x8F, 0x80, 0x7F];
+        assert_eq!(explode(&stream, 64).unwrap(), b"AIAIAIAIAIAIA");
+    }
+
+    #[test]
+    fn test_truncated_stream() {
+        let compressed = implode(&sample(1000), MODE_BINARY, 4);
+        assert!(explode(&compressed[..compressed.len() / 2], 1000).is_err());
+        assert!(explode(&[2, 4, 0], 10).is_err());
+    }
 }
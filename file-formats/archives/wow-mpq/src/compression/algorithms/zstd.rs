@@ -0,0 +1,72 @@
+//! Zstandard compression and decompression
+//!
+//! Zstandard is not an MPQ compression method. It is only available with the
+//! `zstd` feature and only written when strict compatibility is disabled.
+
+use crate::Result;
+#[cfg(feature = "zstd")]
+use crate::compression::error_helpers::decompression_error;
+#[cfg(feature = "zstd")]
+use ruzstd::decoding::StreamingDecoder;
+#[cfg(feature = "zstd")]
+use ruzstd::encoding::{CompressionLevel, compress_to_vec};
+#[cfg(feature = "zstd")]
+use std::io::Read;
+
+/// Decompress a Zstandard frame
+#[cfg(feature = "zstd")]
+pub(crate) fn decompress(data: &[u8], expected_size: usize) -> Result<Vec<u8>> {
+    let mut decoder =
+        StreamingDecoder::new(data).map_err(|e| decompression_error("Zstandard", e))?;
+    let mut decompressed = Vec::with_capacity(expected_size);
+    decoder
+        .read_to_end(&mut decompressed)
+        .map_err(|e| decompression_error("Zstandard", e))?;
+    Ok(decompressed)
+}
+
+/// Compress into a single Zstandard frame
+///
+/// Runs at a fixed level, so the output only depends on the input.
+#[cfg(feature = "zstd")]
+pub(crate) fn compress(data: &[u8]) -> Result<Vec<u8>> {
+    Ok(compress_to_vec(data, CompressionLevel::Fastest))
+}
+
+#[cfg(not(feature = "zstd"))]
+pub(crate) fn decompress(_data: &[u8], _expected_size: usize) -> Result<Vec<u8>> {
+    Err(disabled())
+}
+
+#[cfg(not(feature = "zstd"))]
+pub(crate) fn compress(_data: &[u8]) -> Result<Vec<u8>> {
+    Err(disabled())
+}
+
+#[cfg(not(feature = "zstd"))]
+fn disabled() -> crate::Error {
+    crate::Error::UnsupportedFeature(
+        "Zstandard compression requires the `zstd` feature of wow-mpq".to_string(),
+    )
+}
+
+#[cfg(all(test, feature = "zstd"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let original = "Zstandard in a custom archive. ".repeat(100).into_bytes();
+
+        let compressed = compress(&original).expect("Compression failed");
+        assert!(compressed.len() < original.len() / 2);
+
+        let decompressed = decompress(&compressed, original.len()).expect("Decompression failed");
+        assert_eq!(decompressed, original);
+    }
+
+    #[test]
+    fn test_corrupt_frame() {
+        assert!(decompress(b"not a zstd frame", 16).is_err());
+    }
+}
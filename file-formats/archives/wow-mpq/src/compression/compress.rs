@@ -27,6 +27,20 @@ pub fn compress(data: &[u8], method: u8) -> Result<Vec<u8>> {
     }
 }
 
+/// Implode data for a file stored with the IMPLODE block flag
+///
+/// Such files, found in Diablo, StarCraft and early Warcraft III archives,
+/// have no method byte. Like [`compress`], this returns the data unchanged if
+/// imploding does not save space.
+pub(crate) fn implode(data: &[u8]) -> Result<Vec<u8>> {
+    let imploded = algorithms::pkware::compress(data)?;
+    if imploded.len() < data.len() {
+        Ok(imploded)
+    } else {
+        Ok(data.to_vec())
+    }
+}
+
 /// Internal compression without the method byte prefix
 fn compress_internal(data: &[u8], method: u8) -> Result<Vec<u8>> {
     let compression = CompressionMethod::from_flags(method);
@@ -36,9 +50,10 @@ fn compress_internal(data: &[u8], method: u8) -> Result<Vec<u8>> {
         CompressionMethod::Zlib => algorithms::zlib::compress(data),
         CompressionMethod::BZip2 => algorithms::bzip2::compress(data),
         CompressionMethod::Lzma => algorithms::lzma::compress(data),
+        CompressionMethod::Zstd => algorithms::zstd::compress(data),
         CompressionMethod::Sparse => algorithms::sparse::compress(data),
-        CompressionMethod::AdpcmMono => algorithms::adpcm::compress_mono(data, 5), // Default compression level
-        CompressionMethod::AdpcmStereo => algorithms::adpcm::compress_stereo(data, 5), // Default compression level
+        CompressionMethod::AdpcmMono => algorithms::adpcm::compress_mono(data, ADPCM_LEVEL),
+        CompressionMethod::AdpcmStereo => algorithms::adpcm::compress_stereo(data, ADPCM_LEVEL),
         CompressionMethod::PKWare => algorithms::pkware::compress(data),
         CompressionMethod::Implode => algorithms::implode::compress(data),
         CompressionMethod::Huffman => algorithms::huffman::compress(data),
@@ -46,6 +61,9 @@ fn compress_internal(data: &[u8], method: u8) -> Result<Vec<u8>> {
     }
 }
 
+/// ADPCM compression level, the default StormLib uses for WAVE files
+const ADPCM_LEVEL: u8 = 5;
+
 /// Handle multiple compression methods
 fn compress_multiple(data: &[u8], flags: u8) -> Result<Vec<u8>> {
     // Check which compressions are requested
@@ -62,9 +80,9 @@ fn compress_multiple(data: &[u8], flags: u8) -> Result<Vec<u8>> {
 
     // Apply ADPCM first if requested
     if has_adpcm_mono {
-        current_data = algorithms::adpcm::compress_mono(&current_data, 5)?;
+        current_data = algorithms::adpcm::compress_mono(&current_data, ADPCM_LEVEL)?;
     } else if has_adpcm_stereo {
-        current_data = algorithms::adpcm::compress_stereo(&current_data, 5)?;
+        current_data = algorithms::adpcm::compress_stereo(&current_data, ADPCM_LEVEL)?;
     }
 
     // Count remaining compressions
@@ -87,7 +105,11 @@ fn compress_multiple(data: &[u8], flags: u8) -> Result<Vec<u8>> {
     }
 
     // Apply the single remaining compression
-    if has_huffman {
+    if has_huffman && (has_adpcm_mono || has_adpcm_stereo) {
+        // WAVE files: Huffman starts from the weight table for ADPCM output
+        let compression_type = algorithms::huffman::wave_compression_type(ADPCM_LEVEL);
+        current_data = algorithms::huffman::compress_with_type(&current_data, compression_type)?;
+    } else if has_huffman {
         current_data = algorithms::huffman::compress(&current_data)?;
     } else if has_zlib {
         current_data = algorithms::zlib::compress(&current_data)?;
@@ -111,6 +111,11 @@ fn decompress_with_monitor(
                 algorithms::lzma::decompress(d, s)
             })
         }
+        CompressionMethod::Zstd => {
+            decompress_algorithm_with_monitor(data, decompressed_size, monitor, |d, s| {
+                algorithms::zstd::decompress(d, s)
+            })
+        }
         CompressionMethod::Sparse => {
             decompress_algorithm_with_monitor(data, decompressed_size, monitor, |d, s| {
                 algorithms::sparse::decompress(d, s)
@@ -0,0 +1,166 @@
+//! Compression method evaluation
+//!
+//! Compresses a sample with several methods the way archives store it (sector
+//! by sector, falling back to raw sectors that would grow) and reports the
+//! resulting sizes and timings. Tooling uses it for "what-if" reports before
+//! committing to a rebuild strategy, and rebuilds use it to pick a method per
+//! file.
+
+use super::{compress, decompress, flags};
+use crate::Result;
+use std::time::{Duration, Instant};
+
+/// Options for [`evaluate_with`]
+#[derive(Debug, Clone)]
+pub struct EvaluateOptions {
+    /// Compression flags to try
+    pub methods: Vec<u8>,
+    /// Sector size the sample is split into
+    pub sector_size: usize,
+    /// Also time decompression, which is the cost paid on every read
+    pub measure_decompression: bool,
+}
+
+impl Default for EvaluateOptions {
+    fn default() -> Self {
+        Self {
+            methods: vec![flags::ZLIB, flags::BZIP2, flags::LZMA],
+            sector_size: crate::calculate_sector_size(3),
+            measure_decompression: true,
+        }
+    }
+}
+
+/// Outcome of one compression method on a sample
+#[derive(Debug, Clone, PartialEq)]
+pub struct MethodEvaluation {
+    /// Compression flags of the method
+    pub method: u8,
+    /// Stored size, including per-sector method bytes
+    pub compressed_size: usize,
+    /// Sectors that did not shrink and would be stored raw
+    pub raw_sectors: usize,
+    /// Time spent compressing
+    pub compress_time: Duration,
+    /// Time spent decompressing (zero unless measured)
+    pub decompress_time: Duration,
+}
+
+impl MethodEvaluation {
+    /// Stored size relative to the original, 1.0 meaning no savings
+    pub fn ratio(&self, original_size: usize) -> f64 {
+        if original_size == 0 {
+            1.0
+        } else {
+            self.compressed_size as f64 / original_size as f64
+        }
+    }
+}
+
+/// Outcome of every evaluated method on a sample
+#[derive(Debug, Clone, PartialEq)]
+pub struct Evaluation {
+    /// Size of the sample
+    pub original_size: usize,
+    /// One entry per method, in the order they were tried
+    pub methods: Vec<MethodEvaluation>,
+}
+
+impl Evaluation {
+    /// The method storing the sample in the fewest bytes, if any saves space
+    ///
+    /// Ties go to the faster compressor.
+    pub fn best(&self) -> Option<&MethodEvaluation> {
+        self.methods
+            .iter()
+            .filter(|m| m.compressed_size < self.original_size)
+            .min_by_key(|m| (m.compressed_size, m.compress_time))
+    }
+
+    /// Flags of the best method, or 0 when storing uncompressed is smallest
+    pub fn best_method(&self) -> u8 {
+        self.best().map_or(0, |m| m.method)
+    }
+}
+
+/// Evaluate the default methods on `data` with 4 KiB sectors
+pub fn evaluate(data: &[u8]) -> Result<Evaluation> {
+    evaluate_with(data, &EvaluateOptions::default())
+}
+
+/// Evaluate compression methods on `data`
+pub fn evaluate_with(data: &[u8], options: &EvaluateOptions) -> Result<Evaluation> {
+    let sector_size = options.sector_size.max(1);
+    let mut methods = Vec::with_capacity(options.methods.len());
+
+    for &method in &options.methods {
+        let mut evaluation = MethodEvaluation {
+            method,
+            compressed_size: 0,
+            raw_sectors: 0,
+            compress_time: Duration::ZERO,
+            decompress_time: Duration::ZERO,
+        };
+
+        for sector in data.chunks(sector_size) {
+            let start = Instant::now();
+            let compressed = compress(sector, method)?;
+            evaluation.compress_time += start.elapsed();
+            evaluation.compressed_size += compressed.len();
+
+            // `compress` returns the raw sector when compression would not save space
+            if compressed.len() >= sector.len() {
+                evaluation.raw_sectors += 1;
+            } else if options.measure_decompression {
+                let start = Instant::now();
+                decompress(&compressed[1..], compressed[0], sector.len())?;
+                evaluation.decompress_time += start.elapsed();
+            }
+        }
+
+        methods.push(evaluation);
+    }
+
+    Ok(Evaluation {
+        original_size: data.len(),
+        methods,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evaluate_text() {
+        let text = b"the quick brown fox jumps over the lazy dog ".repeat(300);
+        let evaluation = evaluate(&text).unwrap();
+
+        assert_eq!(evaluation.original_size, text.len());
+        assert_eq!(evaluation.methods.len(), 3);
+        let best = evaluation.best().unwrap();
+        assert!(best.compressed_size < text.len() / 4);
+        assert!(
+            evaluation
+                .methods
+                .iter()
+                .all(|m| m.compressed_size >= best.compressed_size)
+        );
+    }
+
+    #[test]
+    fn test_evaluate_incompressible() {
+        let evaluation = evaluate_with(
+            &[1, 2, 3],
+            &EvaluateOptions {
+                methods: vec![flags::ZLIB],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(evaluation.methods[0].compressed_size, 3);
+        assert_eq!(evaluation.methods[0].raw_sectors, 1);
+        assert_eq!(evaluation.best_method(), 0);
+    }
+}
@@ -1,5 +1,7 @@
 //! Compression method definitions and flags
 
+use crate::{Error, Result};
+
 /// Compression method flags
 pub mod flags {
     /// Huffman encoding (WAVE files only)
@@ -7,6 +9,10 @@ pub mod flags {
     /// Deflate/zlib compression
     pub const ZLIB: u8 = 0x02;
     /// PKWare Implode compression (older format, appears in newer MPQ v4 archives)
+    ///
+    /// [`ArchiveBuilder`](crate::ArchiveBuilder) stores files added with this
+    /// method the way Diablo and StarCraft archives do: imploded with PKWare
+    /// DCL, without a method byte, and marked with the IMPLODE block flag.
     pub const IMPLODE: u8 = 0x04;
     /// PKWare DCL compression
     pub const PKWARE: u8 = 0x08;
@@ -20,6 +26,13 @@ pub mod flags {
     pub const ADPCM_STEREO: u8 = 0x80;
     /// LZMA compression (not a flag combination)
     pub const LZMA: u8 = 0x12;
+    /// Zstandard compression (not a flag combination)
+    ///
+    /// Not part of the MPQ format: no game client or StormLib build can read
+    /// it. The byte sets the IMPLODE bit, which never appears in the method
+    /// byte of a client archive, so it cannot be mistaken for a real
+    /// combination of methods.
+    pub const ZSTD: u8 = 0x0C;
 }
 
 /// Compression methods enum
@@ -45,6 +58,10 @@ pub enum CompressionMethod {
     AdpcmStereo,
     /// LZMA compression
     Lzma,
+    /// Zstandard compression, for archives that no game client reads
+    ///
+    /// Needs the `zstd` feature.
+    Zstd,
     /// Multiple compression methods applied in sequence
     Multiple(u8),
 }
@@ -56,6 +73,9 @@ impl CompressionMethod {
         if flags == flags::LZMA {
             return CompressionMethod::Lzma;
         }
+        if flags == flags::ZSTD {
+            return CompressionMethod::Zstd;
+        }
 
         // Check for single compression methods
         match flags {
@@ -72,10 +92,48 @@ impl CompressionMethod {
         }
     }
 
+    /// Compression flags byte for this method
+    pub fn to_flags(self) -> u8 {
+        match self {
+            CompressionMethod::None => 0,
+            CompressionMethod::Huffman => flags::HUFFMAN,
+            CompressionMethod::Zlib => flags::ZLIB,
+            CompressionMethod::Implode => flags::IMPLODE,
+            CompressionMethod::PKWare => flags::PKWARE,
+            CompressionMethod::BZip2 => flags::BZIP2,
+            CompressionMethod::Sparse => flags::SPARSE,
+            CompressionMethod::AdpcmMono => flags::ADPCM_MONO,
+            CompressionMethod::AdpcmStereo => flags::ADPCM_STEREO,
+            CompressionMethod::Lzma => flags::LZMA,
+            CompressionMethod::Zstd => flags::ZSTD,
+            CompressionMethod::Multiple(flags) => flags,
+        }
+    }
+
     /// Check if this is a multi-compression method
     pub fn is_multiple(&self) -> bool {
         matches!(self, CompressionMethod::Multiple(_))
     }
+
+    /// Whether game clients can decompress data stored with this method
+    pub fn is_standard(&self) -> bool {
+        !matches!(self, CompressionMethod::Zstd)
+    }
+}
+
+/// Refuse `method` if game clients cannot decompress it
+///
+/// Used by the writers while strict compatibility is enabled.
+pub(crate) fn ensure_standard(method: u8) -> Result<()> {
+    let compression = CompressionMethod::from_flags(method);
+    if compression.is_standard() {
+        Ok(())
+    } else {
+        Err(Error::UnsupportedFeature(format!(
+            "{compression:?} compression cannot be read by game clients; \
+             disable strict compatibility to write it"
+        )))
+    }
 }
 
 #[cfg(test)]
@@ -98,6 +156,13 @@ mod tests {
             CompressionMethod::Lzma
         );
 
+        assert_eq!(
+            CompressionMethod::from_flags(flags::ZSTD),
+            CompressionMethod::Zstd
+        );
+        assert_eq!(CompressionMethod::Zstd.to_flags(), flags::ZSTD);
+        assert!(!CompressionMethod::Zstd.is_standard());
+
         // Multiple compression
         let multi = flags::ZLIB | flags::PKWARE;
         assert!(CompressionMethod::from_flags(multi).is_multiple());
@@ -3,11 +3,15 @@
 pub(crate) mod algorithms;
 mod compress;
 mod decompress;
+mod evaluate;
 mod methods;
 
 // Re-export the main public API
 pub use compress::compress;
+pub(crate) use compress::implode;
 pub use decompress::{decompress, decompress_secure};
+pub use evaluate::{EvaluateOptions, Evaluation, MethodEvaluation, evaluate, evaluate_with};
+pub(crate) use methods::ensure_standard;
 pub use methods::{CompressionMethod, flags};
 
 // Re-export security types for public use
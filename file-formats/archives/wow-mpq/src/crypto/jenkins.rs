@@ -2,7 +2,7 @@
 //!
 //! This module contains both Jenkins hash algorithms used in MPQ v3+ archives:
 //! - Jenkins one-at-a-time: Used for BET table hashes
-//! - Jenkins hashlittle2: Used for HET table hashes
+//! - Jenkins hashlittle2: Used for HET table hashes and CASC root name hashes
 
 /// Jenkins one-at-a-time hash function for BET tables
 ///
@@ -296,6 +296,26 @@ pub fn jenkins_hashlittle2(filename: &str, hash_bits: u32) -> (u64, u8) {
     (file_name_hash, name_hash1)
 }
 
+/// Calculate the CASC root name hash ("Jenkins96") of a filename
+///
+/// CASC root manifests identify named files by hashlittle2 of the uppercase
+/// path with backslash separators, using zero seeds. The primary hash forms
+/// the high 32 bits.
+pub fn jenkins96(filename: &str) -> u64 {
+    use crate::crypto::ASCII_TO_UPPER;
+
+    let normalized = filename
+        .bytes()
+        .map(|b| ASCII_TO_UPPER[if b == b'/' { b'\\' } else { b } as usize])
+        .collect::<Vec<u8>>();
+
+    let mut primary = 0u32;
+    let mut secondary = 0u32;
+    hashlittle2(&normalized, &mut primary, &mut secondary);
+
+    ((primary as u64) << 32) | (secondary as u64)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -351,4 +371,20 @@ mod tests {
         let (hash2, _) = jenkins_hashlittle2("FILE.TXT", 48);
         assert_eq!(hash1, hash2, "Filenames should be case-insensitive");
     }
+    #[test]
+    fn test_hashlittle2_reference_vector() {
+        // Test vector from Bob Jenkins' lookup3.c driver
+        let (mut c, mut b) = (0u32, 0u32);
+        hashlittle2(b"Four score and seven years ago", &mut c, &mut b);
+        assert_eq!((c, b), (0x17770551, 0xce7226e6));
+    }
+
+    #[test]
+    fn test_jenkins96_normalization() {
+        assert_eq!(
+            jenkins96("world/maps/azeroth/azeroth.wdt"),
+            jenkins96("WORLD\\MAPS\\AZEROTH\\AZEROTH.WDT")
+        );
+        assert_ne!(jenkins96("a.blp"), jenkins96("b.blp"));
+    }
 }
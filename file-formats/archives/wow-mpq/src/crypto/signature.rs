@@ -18,7 +18,7 @@
 //! - **Algorithm**: 2048-bit RSA with SHA-1 hash
 //! - **Storage**: Appended after archive data with "NGIS" header
 //! - **Size**: 260 bytes (4-byte header + 256-byte signature)
-//! - **Generation**: Requires private key (not publicly available)
+//! - **Generation**: Supported with a caller-supplied 2048-bit private key
 //! - **Usage**: Modern Blizzard games and patches
 //!
 //! ## Signature Generation
@@ -82,6 +82,7 @@ use md5::{Digest, Md5};
 use num_bigint::BigUint;
 use num_traits::Num;
 use rsa::pkcs1::DecodeRsaPrivateKey;
+use rsa::pkcs8::DecodePrivateKey;
 use rsa::traits::{PrivateKeyParts, PublicKeyParts};
 use rsa::{BigUint as RsaBigUint, RsaPrivateKey, RsaPublicKey};
 use sha1::Sha1;
@@ -427,13 +428,24 @@ pub fn verify_weak_signature<R: Read>(
 
 /// Verify a strong signature (2048-bit RSA with SHA-1)
 pub fn verify_strong_signature<R: Read>(
-    mut reader: R,
+    reader: R,
     signature: &[u8],
     archive_size: u64,
 ) -> Result<bool> {
-    // Get the public key
     let public_key = public_keys::strong_public_key()?;
+    verify_strong_signature_with_key(reader, signature, archive_size, &public_key)
+}
 
+/// Verify a strong signature against a specific public key
+///
+/// Used for archives signed with a custom key through
+/// [`generate_strong_signature`] instead of Blizzard's.
+pub fn verify_strong_signature_with_key<R: Read>(
+    mut reader: R,
+    signature: &[u8],
+    archive_size: u64,
+    public_key: &RsaPublicKey,
+) -> Result<bool> {
     // Calculate SHA-1 hash of the archive (excluding the signature)
     let mut hasher = Sha1::new();
     let mut buffer = vec![0u8; 65536]; // 64KB buffer
@@ -647,12 +659,28 @@ fn create_pkcs1_v15_padding_md5(hash: &[u8]) -> Result<Vec<u8>> {
 }
 
 /// Generate a strong signature for an MPQ archive
-/// This creates a 260-byte signature block with "NGIS" header
-pub fn generate_strong_signature<R: Read + std::io::Seek>(
+///
+/// Hashes `archive_size` bytes from the reader's current position (plus the
+/// tail) and signs them with `private_key`, which must be a 2048-bit RSA key.
+/// Returns the 260-byte block with the "NGIS" header that goes right after
+/// the archive data.
+///
+/// Blizzard's strong private key is not public, so signatures made here only
+/// verify against the matching public key, see
+/// [`verify_strong_signature_with_key`].
+pub fn generate_strong_signature<R: Read>(
     mut reader: R,
     archive_size: u64,
     tail_type: StrongSignatureTailType,
+    private_key: &RsaPrivateKey,
 ) -> Result<Vec<u8>> {
+    if private_key.size() != STRONG_SIGNATURE_SIZE - 4 {
+        return Err(Error::Crypto(format!(
+            "Strong signatures need a 2048-bit RSA key, got {} bits",
+            private_key.size() * 8
+        )));
+    }
+
     // Calculate SHA-1 hash with appropriate tail
     let mut hasher = Sha1::new();
     let mut buffer = vec![0u8; DIGEST_UNIT_SIZE];
@@ -661,10 +689,11 @@ pub fn generate_strong_signature<R: Read + std::io::Seek>(
     // Hash the archive data
     while bytes_read < archive_size {
         let to_read = ((archive_size - bytes_read) as usize).min(buffer.len());
-        reader.seek(std::io::SeekFrom::Start(bytes_read))?;
         let n = reader.read(&mut buffer[..to_read])?;
         if n == 0 {
-            break;
+            return Err(Error::Crypto(format!(
+                "Archive ended after {bytes_read} of {archive_size} bytes while signing"
+            )));
         }
         hasher.update(&buffer[..n]);
         bytes_read += n as u64;
@@ -688,12 +717,46 @@ pub fn generate_strong_signature<R: Read + std::io::Seek>(
     let hash = hasher.finalize();
     log::debug!("SHA-1 hash for strong signature: {:02X?}", hash.as_slice());
 
-    // For strong signatures, we would need the corresponding private key
-    // Since Blizzard hasn't released their strong signature private keys,
-    // this is left as a placeholder that returns an error
-    Err(Error::Crypto(
-        "Strong signature generation requires private key not available publicly".to_string(),
-    ))
+    // Sign the padded message: message^d mod n
+    let padded_int = BigUint::from_bytes_be(&create_mpq_strong_signature_padding(&hash));
+    let n = BigUint::from_bytes_be(&private_key.n().to_bytes_be());
+    let d = BigUint::from_bytes_be(&private_key.d().to_bytes_be());
+    let mut signature_bytes = padded_int.modpow(&d, &n).to_bytes_be();
+
+    // Ensure signature is exactly 256 bytes
+    if signature_bytes.len() < STRONG_SIGNATURE_SIZE - 4 {
+        let mut padded = vec![0u8; STRONG_SIGNATURE_SIZE - 4 - signature_bytes.len()];
+        padded.extend(signature_bytes);
+        signature_bytes = padded;
+    }
+
+    // Header followed by the signature in little-endian order
+    let mut block = Vec::with_capacity(STRONG_SIGNATURE_SIZE);
+    block.extend_from_slice(&STRONG_SIGNATURE_HEADER);
+    block.extend(reverse_bytes(&signature_bytes));
+
+    log::debug!("Generated strong signature ({} bytes)", block.len());
+    Ok(block)
+}
+
+/// Create MPQ strong signature padding (0x0B, 235 bytes of 0xBB, SHA-1)
+fn create_mpq_strong_signature_padding(hash: &[u8]) -> Vec<u8> {
+    let mut padded = Vec::with_capacity(STRONG_SIGNATURE_SIZE - 4);
+    padded.push(0x0B);
+    padded.extend(vec![0xBB; STRONG_SIGNATURE_SIZE - 4 - 1 - hash.len()]);
+    padded.extend(hash);
+    padded
+}
+
+/// Parse an RSA private key for strong signing from PEM text
+///
+/// Accepts both PKCS#1 (`BEGIN RSA PRIVATE KEY`) and PKCS#8
+/// (`BEGIN PRIVATE KEY`) encodings, as written by `openssl genrsa` and
+/// `openssl genpkey`.
+pub fn strong_private_key_from_pem(pem: &str) -> Result<RsaPrivateKey> {
+    RsaPrivateKey::from_pkcs1_pem(pem)
+        .or_else(|_| RsaPrivateKey::from_pkcs8_pem(pem))
+        .map_err(|e| Error::Crypto(format!("Failed to parse RSA private key: {e}")))
 }
 
 /// Tail types for strong signatures
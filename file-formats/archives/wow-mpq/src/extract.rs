@@ -0,0 +1,312 @@
+//! Parallel extraction of whole archives
+//!
+//! [`Archive::extract_all`] writes every listed file, or every file matching
+//! a glob pattern, below a destination directory. Files are read on the rayon
+//! pool; each worker opens its own handle to the archive, so reads neither
+//! share a seek position nor wait on each other. Archives read with
+//! [`Archive::from_reader`] have no path to reopen and are extracted on the
+//! calling thread.
+//!
+//! ```no_run
+//! use wow_mpq::{Archive, ExtractOptions};
+//!
+//! let mut archive = Archive::open("patch.MPQ")?;
+//! let options = ExtractOptions {
+//!     pattern: Some("DBFilesClient/*.dbc".to_string()),
+//!     skip_errors: true,
+//!     progress: Some(Box::new(|done, total, name| {
+//!         println!("[{done}/{total}] {name}");
+//!     })),
+//!     ..Default::default()
+//! };
+//! let summary = archive.extract_all("out", options)?;
+//! println!("{} files, {} bytes", summary.extracted, summary.bytes);
+//! # Ok::<(), wow_mpq::Error>(())
+//! ```
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use glob::{MatchOptions, Pattern};
+use rayon::prelude::*;
+
+use crate::path::mpq_path_to_system;
+use crate::rebuild::ProgressCallback;
+use crate::security::{SecurityLimits, validate_file_path};
+use crate::{Archive, Error, Result};
+
+/// Options for [`Archive::extract_all`]
+pub struct ExtractOptions {
+    /// Only extract files matching this glob
+    ///
+    /// Matching ignores case and treats `\` and `/` alike; `*` crosses
+    /// directories, so `*.blp` selects every texture.
+    pub pattern: Option<String>,
+
+    /// Keep the archive's directory structure below the destination;
+    /// otherwise every file is written directly into it
+    pub preserve_paths: bool,
+
+    /// Number of worker threads (None = the current rayon pool)
+    pub threads: Option<usize>,
+
+    /// Record failed files in the summary instead of stopping at the first
+    pub skip_errors: bool,
+
+    /// Called with the number of processed files, the total and the file
+    /// name after each file, from the worker threads
+    pub progress: Option<ProgressCallback>,
+}
+
+impl Default for ExtractOptions {
+    fn default() -> Self {
+        Self {
+            pattern: None,
+            preserve_paths: true,
+            threads: None,
+            skip_errors: false,
+            progress: None,
+        }
+    }
+}
+
+impl std::fmt::Debug for ExtractOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExtractOptions")
+            .field("pattern", &self.pattern)
+            .field("preserve_paths", &self.preserve_paths)
+            .field("threads", &self.threads)
+            .field("skip_errors", &self.skip_errors)
+            .field("progress", &self.progress.is_some())
+            .finish()
+    }
+}
+
+/// Outcome of [`Archive::extract_all`]
+#[derive(Debug, Default)]
+pub struct ExtractSummary {
+    /// Number of files written
+    pub extracted: usize,
+    /// Total size of the files written
+    pub bytes: u64,
+    /// Files that failed with `skip_errors`, sorted by name
+    pub failures: Vec<(String, Error)>,
+}
+
+impl Archive {
+    /// Extract all listed files below `dest` in parallel
+    ///
+    /// Files are named by the archive's listfile; see [`Archive::list`].
+    /// Names that would escape `dest` are rejected like unreadable files.
+    pub fn extract_all<P: AsRef<Path>>(
+        &mut self,
+        dest: P,
+        options: ExtractOptions,
+    ) -> Result<ExtractSummary> {
+        let pattern = options
+            .pattern
+            .as_deref()
+            .map(|pattern| {
+                Pattern::new(&pattern.replace('\\', "/")).map_err(|e| {
+                    Error::invalid_format(format!("Invalid glob pattern '{pattern}': {e}"))
+                })
+            })
+            .transpose()?;
+        let match_options = MatchOptions {
+            case_sensitive: false,
+            ..MatchOptions::new()
+        };
+
+        let files: Vec<String> = self
+            .list()?
+            .into_iter()
+            .map(|entry| entry.name)
+            .filter(|name| {
+                pattern
+                    .as_ref()
+                    .is_none_or(|p| p.matches_with(&name.replace('\\', "/"), match_options))
+            })
+            .collect();
+
+        let dest = dest.as_ref();
+        let archive_path = self.path().to_path_buf();
+        let total = files.len();
+        let completed = AtomicUsize::new(0);
+        let summary = Mutex::new(ExtractSummary::default());
+
+        let record = |name: &String, result: Result<u64>| -> Result<()> {
+            let done = completed.fetch_add(1, Ordering::Relaxed) + 1;
+            if let Some(progress) = &options.progress {
+                progress(done, total, name);
+            }
+
+            let mut summary = summary.lock().unwrap_or_else(|e| e.into_inner());
+            match result {
+                Ok(bytes) => {
+                    summary.extracted += 1;
+                    summary.bytes += bytes;
+                }
+                Err(e) if options.skip_errors => {
+                    log::warn!("Failed to extract {name}: {e}");
+                    summary.failures.push((name.clone(), e));
+                }
+                Err(e) => return Err(e),
+            }
+            Ok(())
+        };
+        let extract_file = |handle: &mut Option<Archive>, name: &String| -> Result<()> {
+            let archive = match handle {
+                Some(archive) => Ok(archive),
+                None => Archive::open(&archive_path).map(|archive| handle.insert(archive)),
+            };
+            let result = archive
+                .and_then(|archive| extract_one(archive, name, dest, options.preserve_paths));
+            record(name, result)
+        };
+        let run = || files.par_iter().try_for_each_init(|| None, extract_file);
+
+        if archive_path.as_os_str().is_empty() {
+            for name in &files {
+                let result = extract_one(self, name, dest, options.preserve_paths);
+                record(name, result)?;
+            }
+        } else {
+            match options.threads {
+                Some(threads) => rayon::ThreadPoolBuilder::new()
+                    .num_threads(threads)
+                    .build()
+                    .map_err(|e| {
+                        Error::Io(std::io::Error::other(format!(
+                            "Failed to create thread pool: {e}"
+                        )))
+                    })?
+                    .install(run)?,
+                None => run()?,
+            }
+        }
+
+        let mut summary = summary.into_inner().unwrap_or_else(|e| e.into_inner());
+        summary.failures.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(summary)
+    }
+}
+
+/// Extract one file through an archive handle
+fn extract_one(
+    archive: &mut Archive,
+    name: &str,
+    dest: &Path,
+    preserve_paths: bool,
+) -> Result<u64> {
+    let system_path = mpq_path_to_system(name);
+    let relative = if preserve_paths {
+        validate_file_path(&system_path, &SecurityLimits::default())?;
+        PathBuf::from(system_path)
+    } else {
+        Path::new(&system_path)
+            .file_name()
+            .map(PathBuf::from)
+            .ok_or_else(|| Error::invalid_format(format!("Invalid file name: {name}")))?
+    };
+
+    let data = archive.read_file(name)?;
+
+    let path = dest.join(relative);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, &data)?;
+    Ok(data.len() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ArchiveBuilder;
+    use std::sync::Arc;
+
+    fn build_archive(path: &Path) {
+        let mut builder = ArchiveBuilder::new();
+        for i in 0..20 {
+            builder = builder.add_file_data(
+                format!("model {i}").into_bytes(),
+                &format!("World\\Models\\Model{i:02}.m2"),
+            );
+        }
+        builder
+            .add_file_data(b"map".to_vec(), "DBFilesClient\\Map.dbc")
+            .build(path)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_extract_all_with_pattern() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.mpq");
+        build_archive(&path);
+
+        let seen = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&seen);
+        let options = ExtractOptions {
+            pattern: Some("world/*.M2".to_string()),
+            threads: Some(4),
+            progress: Some(Box::new(move |_, total, name| {
+                assert_eq!(total, 20);
+                assert!(name.ends_with(".m2"));
+                counter.fetch_add(1, Ordering::Relaxed);
+            })),
+            ..Default::default()
+        };
+        let out = dir.path().join("out");
+        let summary = Archive::open(&path)
+            .unwrap()
+            .extract_all(&out, options)
+            .unwrap();
+
+        assert_eq!(summary.extracted, 20);
+        assert_eq!(summary.bytes, 10 * 7 + 10 * 8);
+        assert!(summary.failures.is_empty());
+        assert_eq!(seen.load(Ordering::Relaxed), 20);
+        let model = out.join("World").join("Models").join("Model07.m2");
+        assert_eq!(fs::read(model).unwrap(), b"model 7");
+        assert!(!out.join("DBFilesClient").exists());
+    }
+
+    #[test]
+    fn test_extract_all_flat() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.mpq");
+        build_archive(&path);
+
+        let options = ExtractOptions {
+            preserve_paths: false,
+            ..Default::default()
+        };
+        let summary = Archive::open(&path)
+            .unwrap()
+            .extract_all(dir.path(), options)
+            .unwrap();
+
+        // The 21 files and the (listfile)
+        assert_eq!(summary.extracted, 22);
+        assert_eq!(fs::read(dir.path().join("Map.dbc")).unwrap(), b"map");
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.mpq");
+        build_archive(&path);
+
+        let options = ExtractOptions {
+            pattern: Some("[".to_string()),
+            ..Default::default()
+        };
+        let result = Archive::open(&path)
+            .unwrap()
+            .extract_all(dir.path(), options);
+        assert!(matches!(result, Err(Error::InvalidFormat(_))));
+    }
+}
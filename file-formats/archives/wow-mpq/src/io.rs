@@ -1,18 +1,24 @@
 //! I/O abstractions for MPQ archives
 
+#[cfg(feature = "async")]
+pub mod async_archive;
 #[cfg(feature = "async")]
 pub mod async_reader;
 pub mod memory_map;
+pub mod volumes;
 
 use crate::Result;
 use std::io::{Read, Seek, SeekFrom};
 
+#[cfg(feature = "async")]
+pub use async_archive::AsyncArchive;
 #[cfg(feature = "async")]
 pub use async_reader::{
     AsyncArchiveReader, AsyncConfig, AsyncDecompressionMonitor, AsyncMetrics, AsyncOperationStats,
 };
 
 pub use memory_map::{MemoryMapConfig, MemoryMapStats};
+pub use volumes::VolumeReader;
 
 #[cfg(feature = "mmap")]
 pub use memory_map::{MemoryMapManager, MemoryMappedArchive};
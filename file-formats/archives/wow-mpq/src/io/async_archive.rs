@@ -0,0 +1,255 @@
+//! Async archive access on tokio
+//!
+//! [`AsyncArchive`] parses the archive tables once and then reads file data
+//! through `tokio::fs`, with a file handle of its own for every read. Reads
+//! share nothing but the immutable tables, so any number of them can run
+//! concurrently; decryption and decompression run on tokio's blocking pool.
+//! Archives without a path, such as those from [`Archive::from_reader`] or
+//! [`Archive::open_multi`], are read through their own source on the
+//! blocking pool instead, one read at a time.
+//!
+//! ```no_run
+//! use wow_mpq::AsyncArchive;
+//!
+//! # async fn example() -> wow_mpq::Result<()> {
+//! let archive = AsyncArchive::open("patch.MPQ").await?;
+//! let names = ["DBFilesClient\\Spell.dbc", "DBFilesClient\\Item.dbc"];
+//! let tasks: Vec<_> = names
+//!     .iter()
+//!     .map(|&name| {
+//!         let archive = archive.clone();
+//!         let path = format!("out/{}", name.replace('\\', "/"));
+//!         tokio::spawn(async move { archive.extract(name, path).await })
+//!     })
+//!     .collect();
+//! for task in tasks {
+//!     task.await.expect("extraction task panicked")?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+use crate::archive::{Archive, ArchiveSource, FileInfo, decode_stored_file};
+use crate::{Error, Result};
+
+/// An MPQ archive read with tokio
+///
+/// Cloning is cheap; clones share the parsed tables. The archive is `Send +
+/// Sync`, so clones can move into tasks spawned on any runtime.
+#[derive(Clone)]
+pub struct AsyncArchive {
+    archive: Arc<Archive>,
+    /// Source of an archive without a path, shared by all reads
+    source: Option<Arc<Mutex<Box<dyn ArchiveSource>>>>,
+}
+
+impl std::fmt::Debug for AsyncArchive {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AsyncArchive")
+            .field("archive", &self.archive)
+            .field("shared_source", &self.source.is_some())
+            .finish()
+    }
+}
+
+impl AsyncArchive {
+    /// Open an archive and parse its tables
+    ///
+    /// The tables are parsed once by [`Archive::open`] on the blocking pool.
+    pub async fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let archive = tokio::task::spawn_blocking(move || Archive::open(path))
+            .await
+            .map_err(std::io::Error::other)??;
+        Ok(Self::from_archive(archive))
+    }
+
+    /// Wrap an archive that is already open
+    ///
+    /// Archives opened from a path are read with a file handle per read. For
+    /// archives without one the wrapper takes over their reader.
+    pub fn from_archive(mut archive: Archive) -> Self {
+        let source = archive
+            .path()
+            .as_os_str()
+            .is_empty()
+            .then(|| Arc::new(Mutex::new(archive.take_source())));
+        Self {
+            archive: Arc::new(archive),
+            source,
+        }
+    }
+
+    /// The underlying archive, for its header, tables and listing
+    pub fn archive(&self) -> &Archive {
+        &self.archive
+    }
+
+    /// Look up a file in the tables
+    pub fn find_file(&self, name: &str) -> Result<Option<FileInfo>> {
+        self.archive.find_file(name)
+    }
+
+    /// Read a file from the archive
+    ///
+    /// Patch files are rejected, as they are by [`Archive::read_file`].
+    pub async fn read_file(&self, name: &str) -> Result<Vec<u8>> {
+        let info = self
+            .find_file(name)?
+            .ok_or_else(|| Error::FileNotFound(name.to_string()))?;
+        if info.is_patch_file() {
+            return Err(Error::OperationNotSupported {
+                version: self.archive.header().format_version as u16,
+                operation: format!("Reading patch file '{name}' directly"),
+            });
+        }
+        let key = self.archive.file_key(name, &info, info.file_size as u32);
+
+        let mut stored = vec![0u8; info.compressed_size as usize];
+        if let Some(source) = &self.source {
+            let source = Arc::clone(source);
+            let file_pos = info.file_pos;
+            stored = tokio::task::spawn_blocking(move || -> Result<Vec<u8>> {
+                let mut source = source.lock();
+                source.seek(SeekFrom::Start(file_pos))?;
+                source.read_exact(&mut stored)?;
+                Ok(stored)
+            })
+            .await
+            .map_err(std::io::Error::other)??;
+        } else {
+            let mut file = tokio::fs::File::open(self.archive.path()).await?;
+            file.seek(SeekFrom::Start(info.file_pos)).await?;
+            file.read_exact(&mut stored).await?;
+        }
+
+        let sector_size = self.archive.header().sector_size();
+        tokio::task::spawn_blocking(move || decode_stored_file(&info, key, sector_size, stored))
+            .await
+            .map_err(std::io::Error::other)?
+    }
+
+    /// Extract a file to `path`, creating missing parent directories
+    ///
+    /// Returns the number of bytes written.
+    pub async fn extract<P: AsRef<Path>>(&self, name: &str, path: P) -> Result<u64> {
+        let data = self.read_file(name).await?;
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(path, &data).await?;
+        Ok(data.len() as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ArchiveBuilder;
+    use crate::compression::flags;
+
+    fn sample_data() -> Vec<u8> {
+        (0..5_000u32)
+            .flat_map(|i| (i % 251).to_le_bytes())
+            .collect()
+    }
+
+    const FILES: [&str; 4] = [
+        "data\\zlib.bin",
+        "data\\secret.bin",
+        "data\\small.bin",
+        "data\\empty.bin",
+    ];
+
+    fn build_archive(path: &Path) {
+        ArchiveBuilder::new()
+            .block_size(0)
+            .add_file_data_with_options(sample_data(), FILES[0], flags::ZLIB, false, 0)
+            .add_file_data_with_encryption(sample_data(), FILES[1], flags::BZIP2, true, 0)
+            .add_file_data(sample_data()[..300].to_vec(), FILES[2])
+            .add_file_data(Vec::new(), FILES[3])
+            .build(path)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_async_archive_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync + 'static>() {}
+        assert_send_sync::<AsyncArchive>();
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_reads_match_blocking_reads() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("async.mpq");
+        build_archive(&path);
+
+        let archive = AsyncArchive::open(&path).await.unwrap();
+        let tasks: Vec<_> = FILES
+            .iter()
+            .map(|name| {
+                let archive = archive.clone();
+                tokio::spawn(async move { archive.read_file(name).await })
+            })
+            .collect();
+
+        let mut blocking = Archive::open(&path).unwrap();
+        for (name, task) in FILES.iter().zip(tasks) {
+            let data = task.await.unwrap().unwrap();
+            assert_eq!(data, blocking.read_file(name).unwrap(), "{name}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reads_from_memory() {
+        let bytes = ArchiveBuilder::new()
+            .block_size(0)
+            .add_file_data_with_options(sample_data(), FILES[0], flags::ZLIB, false, 0)
+            .add_file_data_with_encryption(sample_data(), FILES[1], flags::BZIP2, true, 0)
+            .add_file_data(sample_data()[..300].to_vec(), FILES[2])
+            .add_file_data(Vec::new(), FILES[3])
+            .build_to_vec()
+            .unwrap();
+        let archive = AsyncArchive::from_archive(
+            Archive::from_reader(std::io::Cursor::new(bytes.clone())).unwrap(),
+        );
+        let tasks: Vec<_> = FILES
+            .iter()
+            .map(|name| {
+                let archive = archive.clone();
+                tokio::spawn(async move { archive.read_file(name).await })
+            })
+            .collect();
+
+        let mut blocking = Archive::from_reader(std::io::Cursor::new(bytes)).unwrap();
+        for (name, task) in FILES.iter().zip(tasks) {
+            let data = task.await.unwrap().unwrap();
+            assert_eq!(data, blocking.read_file(name).unwrap(), "{name}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_extract() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("async.mpq");
+        build_archive(&path);
+
+        let archive = AsyncArchive::open(&path).await.unwrap();
+        let output = dir.path().join("out/data/secret.bin");
+        assert_eq!(archive.extract(FILES[1], &output).await.unwrap(), 20_000);
+        assert_eq!(std::fs::read(&output).unwrap(), sample_data());
+
+        assert!(matches!(
+            archive.read_file("data\\missing.bin").await,
+            Err(Error::FileNotFound(_))
+        ));
+    }
+}
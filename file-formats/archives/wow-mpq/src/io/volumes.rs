@@ -0,0 +1,154 @@
+//! Archives split into several volumes
+//!
+//! Some distribution tools cut an archive into `.mpq.part` volumes that are
+//! only meaningful back to back. [`VolumeReader`] presents the volumes as one
+//! seekable stream, so the MPQ header, the tables and the files can start in
+//! one volume and continue in the next. [`Archive::open_multi`] opens such an
+//! archive from the paths of its volumes.
+//!
+//! [`Archive::open_multi`]: crate::Archive::open_multi
+
+use std::io::{self, Read, Seek, SeekFrom};
+
+/// One volume and where it starts in the joined stream
+#[derive(Debug)]
+struct Volume<R> {
+    reader: R,
+    start: u64,
+    len: u64,
+    /// Position of `reader` within the volume, if known
+    pos: Option<u64>,
+}
+
+/// Reader over volumes joined end to end
+#[derive(Debug)]
+pub struct VolumeReader<R> {
+    volumes: Vec<Volume<R>>,
+    pos: u64,
+    len: u64,
+}
+
+impl<R: Read + Seek> VolumeReader<R> {
+    /// Join `volumes`, in order
+    ///
+    /// The length of each volume is taken when it is joined.
+    pub fn new(volumes: Vec<R>) -> io::Result<Self> {
+        let mut joined = Vec::with_capacity(volumes.len());
+        let mut start = 0;
+        for mut reader in volumes {
+            let len = reader.seek(SeekFrom::End(0))?;
+            joined.push(Volume {
+                reader,
+                start,
+                len,
+                pos: Some(len),
+            });
+            start += len;
+        }
+        Ok(Self {
+            volumes: joined,
+            pos: 0,
+            len: start,
+        })
+    }
+
+    /// Number of volumes
+    pub fn volume_count(&self) -> usize {
+        self.volumes.len()
+    }
+
+    /// Total length of all volumes
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Whether all volumes are empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Give back the volumes
+    pub fn into_inner(self) -> Vec<R> {
+        self.volumes.into_iter().map(|v| v.reader).collect()
+    }
+}
+
+impl<R: Read + Seek> Read for VolumeReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() || self.pos >= self.len {
+            return Ok(0);
+        }
+        // Last volume starting at or before the position; empty volumes
+        // are skipped as they end where they start
+        let index = self
+            .volumes
+            .partition_point(|v| v.start + v.len <= self.pos);
+        let volume = &mut self.volumes[index];
+
+        let offset = self.pos - volume.start;
+        if volume.pos != Some(offset) {
+            volume.pos = None;
+            volume.reader.seek(SeekFrom::Start(offset))?;
+        }
+        let available = (volume.len - offset).min(buf.len() as u64) as usize;
+        let read = volume.reader.read(&mut buf[..available])?;
+        volume.pos = Some(offset + read as u64);
+        self.pos += read as u64;
+        Ok(read)
+    }
+}
+
+impl<R: Read + Seek> Seek for VolumeReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(delta) => self.len.checked_add_signed(delta),
+            SeekFrom::Current(delta) => self.pos.checked_add_signed(delta),
+        };
+        self.pos = target.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+        Ok(self.pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_read_across_volumes() {
+        let data: Vec<u8> = (0..=255).collect();
+        let volumes = vec![
+            Cursor::new(data[..100].to_vec()),
+            Cursor::new(Vec::new()),
+            Cursor::new(data[100..101].to_vec()),
+            Cursor::new(data[101..].to_vec()),
+        ];
+        let mut reader = VolumeReader::new(volumes).unwrap();
+        assert_eq!(reader.len(), 256);
+        assert_eq!(reader.volume_count(), 4);
+
+        let mut all = Vec::new();
+        reader.read_to_end(&mut all).unwrap();
+        assert_eq!(all, data);
+
+        reader.seek(SeekFrom::Start(95)).unwrap();
+        let mut middle = [0u8; 10];
+        reader.read_exact(&mut middle).unwrap();
+        assert_eq!(middle, data[95..105]);
+
+        reader.seek(SeekFrom::End(-3)).unwrap();
+        let mut tail = Vec::new();
+        reader.read_to_end(&mut tail).unwrap();
+        assert_eq!(tail, data[253..]);
+
+        assert_eq!(reader.seek(SeekFrom::Current(10)).unwrap(), 266);
+        assert_eq!(reader.read(&mut middle).unwrap(), 0);
+        assert!(reader.seek(SeekFrom::Current(-300)).is_err());
+    }
+}
@@ -0,0 +1,156 @@
+//! Recording file names learned from successful reads
+//!
+//! Archives without a `(listfile)`, or with an incomplete one, can only be
+//! listed anonymously. Every file a caller opens by name is proof of one more
+//! name for the archive. With recording enabled, [`Archive::read_file`]
+//! notes the names of files it read that the archive's own listfile does not
+//! know, so applications can feed them to a name database.
+//!
+//! ```no_run
+//! use wow_mpq::Archive;
+//!
+//! let mut archive = Archive::open("patch.MPQ")?;
+//! archive.set_record_names(true);
+//! let _ = archive.read_file("DBFilesClient\\Spell.dbc");
+//! for name in archive.take_recorded_names() {
+//!     println!("learned {name}");
+//! }
+//! # Ok::<(), wow_mpq::Error>(())
+//! ```
+
+use std::collections::{BTreeSet, HashSet};
+
+use crate::Archive;
+use crate::path::{canonical_path, path_key};
+use crate::special_files::{is_special_file, parse_listfile};
+
+/// Names read from an archive that its listfile does not contain
+#[derive(Debug, Default)]
+pub(crate) struct NameRecorder {
+    /// Lookup keys of the names in the archive's listfile
+    listed: HashSet<String>,
+    /// Recorded names in canonical form
+    names: BTreeSet<String>,
+}
+
+impl NameRecorder {
+    /// A recorder that ignores the names in `listed`
+    pub(crate) fn new<I, S>(listed: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Self {
+            listed: listed
+                .into_iter()
+                .map(|name| path_key(name.as_ref()))
+                .collect(),
+            names: BTreeSet::new(),
+        }
+    }
+
+    /// Record a name that was read successfully
+    ///
+    /// Special files such as `(listfile)` are known to every tool and are
+    /// not recorded.
+    pub(crate) fn record(&mut self, name: &str) {
+        let name = canonical_path(name);
+        if name.is_empty() || is_special_file(&name) {
+            return;
+        }
+        let key = path_key(&name);
+        if !self.listed.contains(&key) {
+            self.listed.insert(key);
+            self.names.insert(name);
+        }
+    }
+
+    /// Recorded names, sorted; the recorder keeps ignoring them afterwards
+    pub(crate) fn take(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.names).into_iter().collect()
+    }
+}
+
+impl Archive {
+    /// Start or stop recording the names of files read by name
+    ///
+    /// Enabling recording reads the archive's listfile, if it has one, so
+    /// that only names it lacks are recorded. Disabling it drops names not
+    /// yet taken.
+    pub fn set_record_names(&mut self, record: bool) {
+        if !record {
+            self.recorded_names = None;
+        } else if self.recorded_names.is_none() {
+            let listed = self
+                .read_file("(listfile)")
+                .ok()
+                .and_then(|data| parse_listfile(&data).ok())
+                .unwrap_or_default();
+            self.recorded_names = Some(NameRecorder::new(listed));
+        }
+    }
+
+    /// Whether names are being recorded
+    pub fn records_names(&self) -> bool {
+        self.recorded_names.is_some()
+    }
+
+    /// Take the names recorded since the last call, sorted
+    ///
+    /// Names are in canonical archive form with backslash separators and
+    /// keep the case they were read with.
+    pub fn take_recorded_names(&mut self) -> Vec<String> {
+        self.recorded_names
+            .as_mut()
+            .map(NameRecorder::take)
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ArchiveBuilder, ListfileOption};
+
+    #[test]
+    fn test_records_names_missing_from_listfile() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("unlisted.mpq");
+        ArchiveBuilder::new()
+            .listfile_option(ListfileOption::None)
+            .add_file_data(b"spell".to_vec(), "DBFilesClient\\Spell.dbc")
+            .add_file_data(b"item".to_vec(), "DBFilesClient\\Item.dbc")
+            .build(&path)
+            .unwrap();
+
+        let mut archive = Archive::open(&path).unwrap();
+        archive.read_file("DBFilesClient\\Item.dbc").unwrap();
+        assert!(archive.take_recorded_names().is_empty());
+
+        archive.set_record_names(true);
+        archive.read_file("dbfilesclient/Spell.dbc").unwrap();
+        archive.read_file("DBFilesClient\\SPELL.dbc").unwrap();
+        assert!(archive.read_file("DBFilesClient\\Missing.dbc").is_err());
+        assert_eq!(
+            archive.take_recorded_names(),
+            ["dbfilesclient\\Spell.dbc".to_string()]
+        );
+        assert!(archive.take_recorded_names().is_empty());
+    }
+
+    #[test]
+    fn test_listed_names_are_not_recorded() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("listed.mpq");
+        ArchiveBuilder::new()
+            .add_file_data(b"spell".to_vec(), "DBFilesClient\\Spell.dbc")
+            .build(&path)
+            .unwrap();
+
+        let mut archive = Archive::open(&path).unwrap();
+        archive.set_record_names(true);
+        archive.read_file("DBFilesClient\\Spell.dbc").unwrap();
+        archive.read_file("(listfile)").unwrap();
+        assert!(archive.take_recorded_names().is_empty());
+    }
+}
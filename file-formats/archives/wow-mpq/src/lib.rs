@@ -124,22 +124,34 @@
 pub mod archive;
 pub mod buffer_pool;
 pub mod builder;
+pub mod cache;
+pub mod collision;
 pub mod compare;
 pub mod compression;
 pub mod crypto;
 pub mod error;
+pub mod extract;
 pub mod header;
 pub mod io;
+pub mod learn;
 pub mod modification;
 pub mod parallel;
 pub mod patch;
 pub mod patch_chain;
 pub mod path;
+pub mod progress;
+pub mod read_cache;
 pub mod rebuild;
+pub mod recovery;
+pub mod sectors;
 pub mod security;
 pub mod single_archive_parallel;
 pub mod special_files;
+pub mod storage;
+pub mod stream;
 pub mod tables;
+pub mod verify;
+pub mod warcraft3;
 
 // SIMD optimizations (optional feature)
 #[cfg(feature = "simd")]
@@ -153,26 +165,44 @@ pub mod debug;
 
 // Re-export commonly used types
 pub use archive::{
-    Archive, ArchiveInfo, FileEntry, FileInfo, Md5Status, OpenOptions, SignatureStatus, TableInfo,
-    UserDataInfo,
+    Archive, ArchiveInfo, ArchiveSource, FileEntry, FileInfo, Md5Status, OpenOptions,
+    SignatureStatus, TableInfo, UserDataInfo,
 };
 pub use buffer_pool::{BufferPool, BufferSize, PoolConfig, PoolStatistics};
 pub use builder::{ArchiveBuilder, AttributesOption, ListfileOption};
+pub use cache::SharedArchive;
+pub use collision::{CollisionReport, DuplicateEntry, NameCollision, find_name_collisions};
 pub use compare::{
     CompareOptions, ComparisonResult, ComparisonSummary, FileComparison, MetadataComparison,
-    compare_archives,
+    PatchOptions, PatchSummary, compare_archives, compare_archives_with_options,
+    generate_patch_archive,
 };
 pub use error::{Error, Result};
+pub use extract::{ExtractOptions, ExtractSummary};
 pub use header::{FormatVersion, MpqHeader};
-pub use modification::{AddFileOptions, MutableArchive};
-pub use patch_chain::{ChainInfo, PatchChain};
-pub use rebuild::{RebuildOptions, RebuildSummary, rebuild_archive};
+pub use modification::{AddFileOptions, AttributesUpdateMode, MutableArchive};
+pub use patch_chain::{
+    ArchiveKind, ArchiveLayout, ChainInfo, GameVersion, LayoutArchive, MergedEntry, PatchChain,
+};
+pub use path::PathPolicy;
+pub use progress::{Progress, ProgressSink, ProgressStage};
+pub use read_cache::{CacheEviction, ReadCacheConfig, ReadCacheStats};
+pub use rebuild::{
+    ContentMismatch, RebuildOptions, RebuildSummary, RebuildVerification, rebuild_archive,
+    rebuild_archive_with_progress,
+};
+pub use recovery::{RecoveredFile, RecoveryIssue, RecoveryReport};
+pub use sectors::{RawFile, RawSector, calculate_file_key};
+pub use storage::{Storage, StorageEntry};
+pub use stream::FileStream;
 pub use tables::{BetFileInfo, BetTable, BlockEntry, BlockTable, HashEntry, HashTable, HetTable};
+pub use verify::{BlockIssue, CheckStatus, FileVerification};
+pub use warcraft3::W3MapHeader;
 
 // Re-export crypto for CLI usage
 pub use crypto::{
     calculate_het_hashes, calculate_mpq_hashes, decrypt_block, decrypt_dword, encrypt_block,
-    hash_string, hash_type, jenkins_hash,
+    hash_string, hash_type, jenkins_hash, jenkins96,
 };
 
 // Re-export compression for testing
@@ -185,7 +215,8 @@ pub use archive::decrypt_file_data;
 #[cfg(feature = "async")]
 #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
 pub use io::{
-    AsyncArchiveReader, AsyncConfig, AsyncDecompressionMonitor, AsyncMetrics, AsyncOperationStats,
+    AsyncArchive, AsyncArchiveReader, AsyncConfig, AsyncDecompressionMonitor, AsyncMetrics,
+    AsyncOperationStats,
 };
 
 // Re-export memory mapping types when mmap feature is enabled
@@ -5,18 +5,24 @@
 //! archives to reclaim space from deleted files.
 
 use crate::{
-    Archive, ArchiveBuilder, Error, ListfileOption, Result,
+    Archive, ArchiveBuilder, Error, ListfileOption, ProgressSink, Result,
     compression::{self, CompressionMethod, compress},
     crypto::{encrypt_block, hash_string, hash_type},
     header::FormatVersion,
+    progress::{ProgressStage, ProgressTracker},
     special_files::{AttributeFlags, Attributes, FileAttributes},
     tables::{BetHeader, BlockEntry, BlockTable, HashEntry, HashTable, HetHeader, HiBlockTable},
 };
 use bytes::Bytes;
-use std::collections::HashMap;
+use md5::{Digest, Md5};
+use std::collections::{HashMap, HashSet};
 use std::fs::{File, OpenOptions};
 use std::io::{Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Largest hash table the hash table is grown to, the limit StormLib uses
+const MAX_HASH_TABLE_SIZE: usize = 0x80000;
 
 /// Generate a generic filename for files without known names
 fn generate_anonymous_filename(hash: u32) -> String {
@@ -38,6 +44,8 @@ pub struct AddFileOptions {
     pub locale: u16,
     /// Platform code (default: 0 = all)
     pub platform: u8,
+    /// Whether to store per-sector checksums (default: false)
+    pub sector_crc: bool,
 }
 
 impl Default for AddFileOptions {
@@ -49,6 +57,7 @@ impl Default for AddFileOptions {
             replace_existing: true,
             locale: 0,
             platform: 0,
+            sector_crc: false,
         }
     }
 }
@@ -89,6 +98,32 @@ impl AddFileOptions {
         self.locale = locale;
         self
     }
+
+    /// Store an ADLER32 checksum for every sector (`MPQ_FILE_SECTOR_CRC`)
+    ///
+    /// Checksums are written in StormLib's layout, so clients and StormLib
+    /// can verify the file sector by sector. They are stored behind the
+    /// sector offset table, which only compressed files spanning more than
+    /// one sector have; `MutableArchive` stores other files without them, as
+    /// StormLib does.
+    pub fn sector_crc(mut self, enabled: bool) -> Self {
+        self.sector_crc = enabled;
+        self
+    }
+}
+
+/// How [`MutableArchive::flush`] updates the (attributes) file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AttributesUpdateMode {
+    /// Leave (attributes) untouched, even if it no longer matches the files
+    None,
+    /// Update the entries of added, replaced and removed files, and only if
+    /// the archive already has an (attributes) file
+    #[default]
+    Preserve,
+    /// Recompute the CRC32 and MD5 of every named file, creating (attributes)
+    /// with CRC32, MD5 and timestamps if the archive has none
+    Regenerate,
 }
 
 /// A mutable handle to an MPQ archive that supports modification operations
@@ -124,6 +159,14 @@ pub struct MutableArchive {
     updated_hash_table_pos: Option<u64>,
     /// Updated block table position for V3+ archives
     updated_block_table_pos: Option<u64>,
+    /// Whether incremental writes are enabled
+    incremental: bool,
+    /// (listfile) content waiting to be written on flush in incremental mode
+    pending_listfile: Option<String>,
+    /// How (attributes) is updated on flush
+    attributes_mode: AttributesUpdateMode,
+    /// Whether compression methods game clients cannot read are refused
+    strict_compatibility: bool,
 }
 
 impl MutableArchive {
@@ -165,9 +208,150 @@ impl MutableArchive {
             updated_bet_pos: None,
             updated_hash_table_pos: None,
             updated_block_table_pos: None,
+            incremental: false,
+            pending_listfile: None,
+            attributes_mode: AttributesUpdateMode::default(),
+            strict_compatibility: true,
         })
     }
 
+    /// Enable or disable incremental writes
+    ///
+    /// Incremental mode is meant for workflows that repeatedly patch a few
+    /// files in a large archive. While it is enabled:
+    ///
+    /// - files that do not fit in their previous block are written into the
+    ///   smallest deleted or superseded block that can hold them, and are only
+    ///   appended when no such block exists
+    /// - the (listfile) is rewritten once per [`flush`](Self::flush) instead
+    ///   of once per added, removed or renamed file
+    /// - flushing rewrites the tables where they are and only moves them to
+    ///   the end of the archive when they have outgrown their space
+    ///
+    /// Existing file data is never moved. Space that cannot be reused stays
+    /// in the archive until [`compact`](Self::compact) is called.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use wow_mpq::{AddFileOptions, MutableArchive};
+    ///
+    /// let mut archive = MutableArchive::open("patch-4.MPQ")?;
+    /// archive.set_incremental(true);
+    /// archive.add_file_data(b"-- v2", "Interface\\AddOns\\Mod\\Mod.lua", AddFileOptions::new().replace_existing(true))?;
+    /// archive.flush()?;
+    /// # Ok::<(), wow_mpq::Error>(())
+    /// ```
+    pub fn set_incremental(&mut self, enabled: bool) {
+        self.incremental = enabled;
+    }
+
+    /// Whether incremental writes are enabled
+    pub fn is_incremental(&self) -> bool {
+        self.incremental
+    }
+
+    /// Choose how [`flush`](Self::flush) updates the (attributes) file
+    ///
+    /// The default, [`AttributesUpdateMode::Preserve`], keeps an existing
+    /// (attributes) file in step with the files changed in this session.
+    /// [`AttributesUpdateMode::Regenerate`] also repairs entries that were
+    /// already stale, so tools that check them, such as StormLib, accept
+    /// every file.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use wow_mpq::{AddFileOptions, AttributesUpdateMode, MutableArchive};
+    ///
+    /// let mut archive = MutableArchive::open("patch.mpq")?;
+    /// archive.set_attributes_mode(AttributesUpdateMode::Regenerate);
+    /// archive.rename_file("old.txt", "new.txt")?;
+    /// archive.flush()?;
+    /// # Ok::<(), wow_mpq::Error>(())
+    /// ```
+    pub fn set_attributes_mode(&mut self, mode: AttributesUpdateMode) {
+        self.attributes_mode = mode;
+        if mode == AttributesUpdateMode::Regenerate {
+            self.attributes_dirty = true;
+            self.dirty = true;
+        }
+    }
+
+    /// How (attributes) is updated on flush
+    pub fn attributes_mode(&self) -> AttributesUpdateMode {
+        self.attributes_mode
+    }
+
+    /// Refuse compression methods that game clients cannot read
+    ///
+    /// Enabled by default, so adding a file with
+    /// [`CompressionMethod::Zstd`] fails with [`Error::UnsupportedFeature`]
+    /// and leaves the archive unchanged. Disable it for archives that are
+    /// only read by this crate.
+    pub fn set_strict_compatibility(&mut self, strict: bool) {
+        self.strict_compatibility = strict;
+    }
+
+    /// Whether compression methods game clients cannot read are refused
+    pub fn is_strict_compatibility(&self) -> bool {
+        self.strict_compatibility
+    }
+
+    /// Number of files the hash table can hold
+    pub fn max_file_count(&self) -> u32 {
+        self.hash_table
+            .as_ref()
+            .map(|table| table.size() as u32)
+            .unwrap_or(self.archive.header().hash_table_size)
+    }
+
+    /// Resize the hash table to hold at least `max_file_count` files
+    ///
+    /// The size is rounded up to a power of two. Adding files to a full
+    /// hash table grows it automatically, so this is only needed to reserve
+    /// room up front or to shrink a table that has become oversized. The
+    /// table is rebuilt in memory and written on [`flush`](Self::flush),
+    /// in place when it fits and behind the file data otherwise.
+    ///
+    /// Rebuilding places every file by its name, so all files must be
+    /// named in the (listfile). Fails with [`Error::CapacityExceeded`] if
+    /// the archive holds more than `max_file_count` files.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use wow_mpq::MutableArchive;
+    ///
+    /// let mut archive = MutableArchive::open("patch.mpq")?;
+    /// archive.set_max_file_count(4096)?;
+    /// archive.flush()?;
+    /// # Ok::<(), wow_mpq::Error>(())
+    /// ```
+    pub fn set_max_file_count(&mut self, max_file_count: u32) -> Result<()> {
+        self.ensure_tables_loaded()?;
+
+        let file_count = self
+            .hash_table
+            .as_ref()
+            .map(|table| table.entries().iter().filter(|e| e.is_valid()).count())
+            .unwrap_or(0);
+        if file_count > max_file_count as usize {
+            return Err(Error::CapacityExceeded(format!(
+                "{file_count} files do not fit in a hash table for {max_file_count}"
+            )));
+        }
+
+        let size = (max_file_count as usize).max(16).next_power_of_two();
+        if size > MAX_HASH_TABLE_SIZE {
+            return Err(Error::CapacityExceeded(format!(
+                "Hash table of {size} entries exceeds the limit of {MAX_HASH_TABLE_SIZE}"
+            )));
+        }
+
+        self.rebuild_hash_table(size)
+    }
+
     /// Get immutable access to the underlying archive
     ///
     /// This allows reading files and querying archive information while
@@ -210,11 +394,28 @@ impl MutableArchive {
 
     /// List files in the archive
     ///
-    /// This is a convenience method that delegates to the underlying Archive.
-    /// It allows listing files from a MutableArchive without having to call
-    /// archive_mut() explicitly.
+    /// Names come from the current (listfile), including modifications that
+    /// have not been flushed yet. Archives without a listfile are enumerated
+    /// by the underlying Archive.
     pub fn list(&mut self) -> Result<Vec<crate::FileEntry>> {
-        self.archive.list()
+        let Ok(listfile) = self.read_current_file("(listfile)") else {
+            return self.archive.list();
+        };
+
+        let mut entries = Vec::new();
+        for name in crate::special_files::parse_listfile(&listfile)? {
+            if let Some(info) = self.find_file(&name)? {
+                entries.push(crate::FileEntry {
+                    size: info.file_size,
+                    compressed_size: info.compressed_size,
+                    flags: info.flags,
+                    hashes: None,
+                    table_indices: Some((info.hash_index, Some(info.block_index))),
+                    name,
+                });
+            }
+        }
+        Ok(entries)
     }
 
     /// Find a file in the archive
@@ -269,6 +470,150 @@ impl MutableArchive {
         self.archive.verify_signature()
     }
 
+    /// Sign the archive with a strong signature
+    ///
+    /// Flushes pending changes, then appends a 260-byte "NGIS" block signed
+    /// with `key` behind the archive data, replacing a strong signature that
+    /// is already there. The header's archive size is brought up to date
+    /// first, since the signature covers exactly that range. The key must be
+    /// a 2048-bit RSA key.
+    ///
+    /// Further modifications invalidate the signature; sign again after
+    /// them.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use wow_mpq::{AddFileOptions, MutableArchive};
+    /// use wow_mpq::crypto::strong_private_key_from_pem;
+    ///
+    /// let key = strong_private_key_from_pem(&std::fs::read_to_string("patch-key.pem")?)?;
+    /// let mut archive = MutableArchive::open("patch-4.MPQ")?;
+    /// archive.add_file_data(b"-- custom", "Interface\\FrameXML\\Custom.lua", AddFileOptions::new())?;
+    /// archive.sign_strong(&key)?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn sign_strong(&mut self, key: &crate::crypto::RsaPrivateKey) -> Result<()> {
+        self.flush()?;
+
+        let archive_offset = self.archive.archive_offset();
+        let archive_end = self.sync_archive_size()?;
+        self.file.set_len(archive_end)?;
+
+        crate::builder::append_strong_signature(&mut self.file, archive_offset, archive_end, key)?;
+        self.file.sync_all()?;
+        crate::cache::invalidate(&self._path);
+
+        // Re-open so the archive sees the new size and signature
+        self.archive = Archive::open(&self._path)?;
+
+        Ok(())
+    }
+
+    /// Regenerate the weak signature
+    ///
+    /// Flushes pending changes, then signs the archive with the well-known
+    /// 512-bit Blizzard weak key, so that it verifies as
+    /// [`SignatureStatus::WeakValid`](crate::SignatureStatus::WeakValid) here
+    /// and in StormLib's `SFileVerifyArchive`. A `(signature)` file is added
+    /// when the archive has none, or replaced when it is not a plain 72-byte
+    /// file. The header's archive size is brought up to date first, since the
+    /// signature covers exactly that range.
+    ///
+    /// Further modifications invalidate the signature; sign again after
+    /// them. A strong signature, which covers the weak one, must be renewed
+    /// with [`sign_strong`](Self::sign_strong) afterwards.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use wow_mpq::{AddFileOptions, MutableArchive, SignatureStatus};
+    ///
+    /// let mut archive = MutableArchive::open("patch.MPQ")?;
+    /// archive.add_file_data(b"-- custom", "Interface\\FrameXML\\Custom.lua", AddFileOptions::new())?;
+    /// archive.resign_weak()?;
+    /// assert_eq!(archive.verify_signature()?, SignatureStatus::WeakValid);
+    /// # Ok::<(), wow_mpq::Error>(())
+    /// ```
+    pub fn resign_weak(&mut self) -> Result<()> {
+        use crate::crypto::{SignatureInfo, WEAK_SIGNATURE_FILE_SIZE, generate_weak_signature};
+
+        const SIGNATURE_SIZE: u64 = WEAK_SIGNATURE_FILE_SIZE as u64;
+
+        self.flush()?;
+
+        // The signature is written over the file's data, so it must be
+        // stored as is
+        let usable = self.find_file("(signature)")?.is_some_and(|info| {
+            !info.is_compressed()
+                && !info.is_encrypted()
+                && info.compressed_size == SIGNATURE_SIZE
+                && info.file_size == SIGNATURE_SIZE
+        });
+        if !usable {
+            let options = AddFileOptions::new()
+                .compression(CompressionMethod::None)
+                .replace_existing(true);
+            self.add_file_data(&[0; WEAK_SIGNATURE_FILE_SIZE], "(signature)", options)?;
+            self.flush()?;
+        }
+        let signature_file = self
+            .find_file("(signature)")?
+            .ok_or_else(|| Error::FileNotFound("(signature)".to_string()))?;
+
+        let archive_offset = self.archive.archive_offset();
+        let archive_end = self.sync_archive_size()?;
+
+        // The signature area is hashed as zeros, so its old content does not
+        // matter
+        let signature_info = SignatureInfo::new_weak(
+            archive_offset,
+            archive_end - archive_offset,
+            signature_file.file_pos,
+            SIGNATURE_SIZE,
+            Vec::new(),
+        );
+        let signature = generate_weak_signature(&mut self.file, &signature_info)?;
+        self.file.seek(SeekFrom::Start(signature_file.file_pos))?;
+        self.file.write_all(&signature)?;
+        self.file.sync_all()?;
+        crate::cache::invalidate(&self._path);
+
+        // Re-open so the archive sees the new signature
+        self.archive = Archive::open(&self._path)?;
+
+        Ok(())
+    }
+
+    /// Bring the header's archive size up to the end of the archive data
+    ///
+    /// Everything up to the end of the file is archive data, except a strong
+    /// signature at the very end. Returns the end offset of the data.
+    fn sync_archive_size(&mut self) -> Result<u64> {
+        use crate::crypto::{STRONG_SIGNATURE_HEADER, STRONG_SIGNATURE_SIZE};
+
+        let archive_offset = self.archive.archive_offset();
+        let header_size = self.archive.header().header_size as u64;
+        let file_len = self.file.metadata()?.len();
+
+        let mut archive_end = file_len;
+        if file_len >= archive_offset + header_size + STRONG_SIGNATURE_SIZE as u64 {
+            let mut magic = [0u8; 4];
+            self.file
+                .seek(SeekFrom::Start(file_len - STRONG_SIGNATURE_SIZE as u64))?;
+            self.file.read_exact(&mut magic)?;
+            if magic == STRONG_SIGNATURE_HEADER {
+                archive_end -= STRONG_SIGNATURE_SIZE as u64;
+            }
+        }
+
+        let archive_size = archive_end - archive_offset;
+        if archive_size != self.archive.header().get_archive_size() {
+            self.write_archive_size(archive_size)?;
+        }
+        Ok(archive_end)
+    }
+
     /// Load and cache attributes from the (attributes) file
     ///
     /// This is a convenience method that delegates to the underlying Archive.
@@ -315,6 +660,10 @@ impl MutableArchive {
 
     /// Add a file from memory to the archive
     ///
+    /// Replacing a file whose new data fits in its existing block overwrites
+    /// that block in place; otherwise the data is appended and the old block
+    /// is left for [`compact`](Self::compact) to reclaim.
+    ///
     /// # Parameters
     /// - `data`: File data to add
     /// - `archive_name`: Name for the file within the archive
@@ -325,6 +674,10 @@ impl MutableArchive {
         archive_name: &str,
         options: AddFileOptions,
     ) -> Result<()> {
+        if self.strict_compatibility {
+            compression::ensure_standard(options.compression.to_flags())?;
+        }
+
         // Normalize the archive name (convert forward slashes to backslashes)
         let archive_name = archive_name.replace('/', "\\");
 
@@ -335,7 +688,7 @@ impl MutableArchive {
         let is_internal_update = archive_name == "(listfile)" || archive_name == "(attributes)";
 
         // Check if file exists and if we should replace it
-        let existing_block_index =
+        let existing_block =
             if let Some((hash_index, entry)) = self.find_file_entry(&archive_name)? {
                 if !options.replace_existing {
                     return Err(Error::FileExists(archive_name));
@@ -344,39 +697,63 @@ impl MutableArchive {
                 if let Some(hash_table) = &mut self.hash_table {
                     hash_table.get_mut(hash_index).unwrap().block_index = HashEntry::EMPTY_DELETED;
                 }
-
-                // If this is a special file update, remember its block index for reuse
-                if is_internal_update {
-                    Some(entry.block_index)
-                } else {
-                    None
-                }
+                Some(entry.block_index)
             } else {
+                self.ensure_hash_capacity()?;
                 None
             };
 
-        // Determine block index - reuse for special files, allocate new for regular files
-        let block_index = if let Some(existing_idx) = existing_block_index {
-            existing_idx
-        } else {
-            self.block_table.as_ref().unwrap().entries().len() as u32
-        };
-
-        // Find where to place the file (append to end for now)
-        let file_offset = self.get_archive_end_offset()?;
-
         // Compress the file data if requested
         let (compressed_data, compressed_size, flags) =
             self.prepare_file_data(data, &archive_name, &options)?;
 
-        // Write the file data to the archive
-        self.file.seek(SeekFrom::Start(file_offset))?;
-        self.file.write_all(&compressed_data)?;
+        // Overwrite the replaced file's block when the new data fits, so that
+        // repeated updates do not grow the archive until the next compaction
+        let in_place = existing_block
+            .and_then(|block_index| self.in_place_slot(block_index, compressed_data.len()));
+
+        // In incremental mode, fall back to the best fitting free block
+        let free_block = if in_place.is_none() && self.incremental {
+            self.free_block_slot(compressed_data.len())
+        } else {
+            None
+        };
+
+        // Reuse the block for in-place writes and special files, allocate new for the rest
+        let existing_block_index =
+            existing_block.filter(|_| in_place.is_some() || is_internal_update);
+        let reused_block_index = free_block
+            .map(|(block_index, _, _)| block_index)
+            .or(existing_block_index);
+        let block_index = if let Some(reused_idx) = reused_block_index {
+            reused_idx
+        } else {
+            self.block_table.as_ref().unwrap().entries().len() as u32
+        };
 
-        // Update next file offset for subsequent files in this session
-        let next_offset = file_offset + compressed_data.len() as u64;
-        let aligned_next = (next_offset + 511) & !511; // Align to 512-byte boundary
-        self.next_file_offset = Some(aligned_next);
+        let slot = in_place.or(free_block.map(|(_, file_offset, size)| (file_offset, size)));
+        let file_offset = if let Some((file_offset, old_size)) = slot {
+            log::debug!("Writing {archive_name} into existing block space at 0x{file_offset:X}");
+            self.file.seek(SeekFrom::Start(file_offset))?;
+            self.file.write_all(&compressed_data)?;
+            // Clear the rest of the old data
+            self.file
+                .write_all(&vec![0u8; old_size - compressed_data.len()])?;
+            file_offset
+        } else {
+            // Find where to place the file (append to end)
+            let file_offset = self.get_archive_end_offset()?;
+
+            // Write the file data to the archive
+            self.file.seek(SeekFrom::Start(file_offset))?;
+            self.file.write_all(&compressed_data)?;
+
+            // Update next file offset for subsequent files in this session
+            let next_offset = file_offset + compressed_data.len() as u64;
+            let aligned_next = (next_offset + 511) & !511; // Align to 512-byte boundary
+            self.next_file_offset = Some(aligned_next);
+            file_offset
+        };
 
         // Add block table entry
         let relative_pos = (file_offset - self.archive.archive_offset()) as u32;
@@ -389,7 +766,7 @@ impl MutableArchive {
 
         // Add or update block table entry
         if let Some(block_table) = &mut self.block_table {
-            if existing_block_index.is_some() {
+            if reused_block_index.is_some() {
                 // Update existing entry
                 if let Some(entry) = block_table.get_mut(block_index as usize) {
                     *entry = block_entry;
@@ -441,30 +818,16 @@ impl MutableArchive {
 
     /// Read the current state of a file (considering modifications)
     fn read_current_file(&mut self, filename: &str) -> Result<Vec<u8>> {
-        // First check if we have a modified version
-        if let Some((_, entry)) = self.find_file_entry(filename)? {
-            let block_idx = entry.block_index as usize;
-            if let Some(block_table) = &self.block_table
-                && let Some(block) = block_table.entries().get(block_idx)
-            {
-                // Read from our file handle
-                let file_pos = self.archive.archive_offset() + block.file_pos as u64;
-                self.file.seek(SeekFrom::Start(file_pos))?;
-
-                let mut data = vec![0u8; block.compressed_size as usize];
-                self.file.read_exact(&mut data)?;
-
-                // Handle decompression/decryption if needed
-                // For now, assume (listfile) is uncompressed/unencrypted
-                if block.is_compressed() || block.is_encrypted() {
-                    // This would need proper decompression/decryption
-                    return self.archive.read_file(filename);
-                }
+        // The listfile may not have been written yet in incremental mode
+        if filename == "(listfile)"
+            && let Some(content) = &self.pending_listfile
+        {
+            return Ok(content.as_bytes().to_vec());
+        }
 
-                // Truncate to actual file size
-                data.truncate(block.file_size as usize);
-                return Ok(data);
-            }
+        // Read through the modified tables, whose blocks may have been rewritten
+        if let Some(file_info) = self.find_file(filename)? {
+            return self.archive.read_file_with_info(filename, &file_info);
         }
 
         // Fall back to original archive
@@ -556,6 +919,19 @@ impl MutableArchive {
     /// This creates a new archive file with all active files copied over,
     /// removing any gaps from deleted files.
     pub fn compact(&mut self) -> Result<()> {
+        self.compact_inner(None)
+    }
+
+    /// Compact the archive, reporting progress to `sink`
+    ///
+    /// The sink sees every file read from the archive
+    /// ([`ProgressStage::Read`]), then every file written to the compacted
+    /// copy ([`ProgressStage::Write`]).
+    pub fn compact_with_progress(&mut self, sink: Arc<dyn ProgressSink>) -> Result<()> {
+        self.compact_inner(Some(sink))
+    }
+
+    fn compact_inner(&mut self, sink: Option<Arc<dyn ProgressSink>>) -> Result<()> {
         use std::fs;
         use tempfile::NamedTempFile;
 
@@ -578,6 +954,9 @@ impl MutableArchive {
         let mut builder = ArchiveBuilder::new()
             .version(format_version)
             .listfile_option(ListfileOption::Generate);
+        if let Some(sink) = &sink {
+            builder = builder.progress(sink.clone());
+        }
 
         // First, get the list of files if available
         let file_list = self.list().ok();
@@ -620,21 +999,25 @@ impl MutableArchive {
             }
         }
 
+        // Skip internal files that will be handled automatically by the builder
+        files_to_copy.retain(|(_, _, filename, _, _)| {
+            filename != "(listfile)" && filename != "(attributes)" && filename != "(signature)"
+        });
+
         // Add all active files to the new archive
+        let mut progress =
+            ProgressTracker::new(sink.as_deref(), ProgressStage::Read, files_to_copy.len());
         for (_, _, filename, hash_entry, block_entry) in &files_to_copy {
-            // Skip internal files that will be handled automatically by the builder
-            if filename == "(listfile)" || filename == "(attributes)" || filename == "(signature)" {
-                continue;
-            }
-
             // Read the file data
-            let file_data = match self.read_file(filename) {
-                Ok(data) => data,
-                Err(_) => {
-                    // Skip files we can't read
-                    log::warn!("Skipping file {filename} during compaction (read error)");
-                    continue;
-                }
+            let file_data = self.read_file(filename);
+            progress.file_done(
+                filename,
+                file_data.as_ref().map_or(0, |data| data.len() as u64),
+            );
+            let Ok(file_data) = file_data else {
+                // Skip files we can't read
+                log::warn!("Skipping file {filename} during compaction (read error)");
+                continue;
             };
 
             // Determine compression and encryption from block flags
@@ -682,6 +1065,7 @@ impl MutableArchive {
         self.next_file_offset = None;
         self.attributes_dirty = false;
         self.modified_blocks.clear();
+        crate::cache::invalidate(&self._path);
 
         Ok(())
     }
@@ -695,19 +1079,43 @@ impl MutableArchive {
             return Ok(());
         }
 
+        // Write the listfile changes collected in incremental mode
+        if let Some(content) = self.pending_listfile.take() {
+            self.write_listfile(content)?;
+        }
+
         // Update attributes if needed
-        if self.attributes_dirty {
+        if self.attributes_dirty && self.attributes_mode != AttributesUpdateMode::None {
             self.update_attributes()?;
         }
 
+        // A resized hash table changes the header for the next flush as well
+        let hash_table_resized = self
+            .hash_table
+            .as_ref()
+            .is_some_and(|table| table.size() as u32 != self.archive.header().hash_table_size);
+
         // Write updated tables
-        self.write_tables()?;
+        let tables_end = self.write_tables()?;
 
         // Update archive header
         self.update_header()?;
 
+        let archive_size = self.data_end().max(tables_end) - self.archive.archive_offset();
+        self.write_archive_size(archive_size)?;
+
         self.file.sync_all()?;
         self.dirty = false;
+        crate::cache::invalidate(&self._path);
+
+        // Pick up the new table positions so the next flush starts from them
+        if self.incremental || hash_table_resized {
+            self.archive = Archive::open(&self._path)?;
+            self.updated_het_pos = None;
+            self.updated_bet_pos = None;
+            self.updated_hash_table_pos = None;
+            self.updated_block_table_pos = None;
+        }
 
         Ok(())
     }
@@ -716,49 +1124,42 @@ impl MutableArchive {
     fn update_attributes(&mut self) -> Result<()> {
         use std::time::{SystemTime, UNIX_EPOCH};
 
-        // Check if (attributes) exists
-        if self.archive.find_file("(attributes)")?.is_none() {
+        self.ensure_tables_loaded()?;
+        let regenerate = self.attributes_mode == AttributesUpdateMode::Regenerate;
+        let existing = if self.find_file_entry("(attributes)")?.is_some() {
+            Some(self.read_current_file("(attributes)")?)
+        } else if regenerate {
+            // Give (attributes) its block first so the entry count covers it
+            let options = AddFileOptions::new().compression(CompressionMethod::None);
+            self.add_file_data(&[], "(attributes)", options)?;
+            None
+        } else {
             // No attributes file to update
             return Ok(());
-        }
+        };
 
-        // Read existing attributes
-        let attrs_data = self.read_current_file("(attributes)")?;
         let block_count = self
             .block_table
             .as_ref()
             .map(|t| t.entries().len())
-            .unwrap_or_else(|| {
-                self.archive
-                    .block_table()
-                    .map(|t| t.entries().len())
-                    .unwrap_or(0)
-            });
-
-        let mut attrs = match Attributes::parse(&Bytes::from(attrs_data), block_count) {
-            Ok(a) => a,
-            Err(_) => {
-                // If we can't parse existing attributes, create new ones
-                Attributes {
-                    version: Attributes::EXPECTED_VERSION,
-                    flags: AttributeFlags::new(AttributeFlags::CRC32 | AttributeFlags::FILETIME),
-                    file_attributes: vec![FileAttributes::new(); block_count],
-                    crc32: None,    // Phase 1 placeholder
-                    md5: None,      // Phase 1 placeholder
-                    filetime: None, // Phase 1 placeholder
-                }
-            }
-        };
-
-        // Ensure we have enough attribute entries
-        while attrs.file_attributes.len() < block_count {
-            attrs.file_attributes.push(FileAttributes::new());
-        }
-
-        // If attributes vector is larger than block count, truncate it
-        if attrs.file_attributes.len() > block_count {
-            attrs.file_attributes.truncate(block_count);
-        }
+            .unwrap_or(0);
+        let parsed =
+            existing.and_then(|data| Attributes::parse(&Bytes::from(data), block_count).ok());
+        let mut attrs = parsed.unwrap_or_else(|| Attributes {
+            version: Attributes::EXPECTED_VERSION,
+            flags: AttributeFlags::new(
+                AttributeFlags::CRC32 | AttributeFlags::FILETIME | AttributeFlags::MD5,
+            ),
+            file_attributes: Vec::new(),
+            crc32: None,
+            md5: None,
+            filetime: None,
+        });
+        // Entries past the old end belong to blocks added since it was written
+        let known = attrs.file_attributes.len();
+        attrs
+            .file_attributes
+            .resize_with(block_count, FileAttributes::new);
 
         // Get current timestamp
         let now = SystemTime::now()
@@ -768,70 +1169,79 @@ impl MutableArchive {
         // Convert to Windows FILETIME (100ns intervals since 1601-01-01)
         let filetime = (now + 11644473600) * 10_000_000;
 
-        // Update attributes for modified files
-        // First collect the files to process to avoid borrowing issues
-        let modified_files: Vec<(u32, String)> = self
-            .modified_blocks
-            .iter()
-            .map(|(&idx, name)| (idx, name.clone()))
-            .collect();
+        // Names to read files by; the listfile covers unchanged files as well
+        let names = self.known_names()?;
 
-        for (block_idx, filename) in modified_files {
-            let block_idx = block_idx as usize;
-            if block_idx >= block_count {
+        // Live blocks with the hash entry pointing at them
+        let hash_table = self
+            .hash_table
+            .as_ref()
+            .ok_or_else(|| Error::InvalidFormat("No hash table".to_string()))?;
+        let mut live: HashMap<usize, (usize, HashEntry)> = HashMap::new();
+        for (hash_index, entry) in hash_table.entries().iter().enumerate() {
+            if entry.is_valid() && (entry.block_index as usize) < block_count {
+                live.insert(entry.block_index as usize, (hash_index, *entry));
+            }
+        }
+        let attributes_block = self
+            .find_file_entry("(attributes)")?
+            .map(|(_, entry)| entry.block_index as usize);
+        let signature_block = self
+            .find_file_entry("(signature)")?
+            .map(|(_, entry)| entry.block_index as usize);
+
+        for block_index in 0..block_count {
+            let Some(&(hash_index, hash_entry)) = live.get(&block_index) else {
+                // Deleted and superseded blocks keep no attributes
+                attrs.file_attributes[block_index] = FileAttributes::new();
+                continue;
+            };
+            if Some(block_index) == attributes_block || Some(block_index) == signature_block {
+                // (attributes) does not describe itself, and (signature) is
+                // written after the attributes it signs
+                attrs.file_attributes[block_index] = FileAttributes::new();
                 continue;
             }
 
-            // Update timestamp for modified files
-            if attrs.flags.has_filetime() {
-                attrs.file_attributes[block_idx].filetime = Some(filetime);
-            }
+            let changed =
+                block_index >= known || self.modified_blocks.contains_key(&(block_index as u32));
+            let block = self.block_table.as_ref().unwrap().entries()[block_index];
+            let name = names.get(&(hash_entry.name_1, hash_entry.name_2));
 
-            // Calculate CRC32 if enabled
-            if attrs.flags.has_crc32() && filename != "(listfile)" {
-                // Read the uncompressed file data to calculate CRC
-                match self.read_current_file(&filename) {
+            if (changed || regenerate)
+                && !block.is_patch_file()
+                && let Some(name) = name
+            {
+                let file_info = crate::FileInfo {
+                    filename: name.clone(),
+                    hash_index,
+                    block_index,
+                    file_pos: self.archive.archive_offset() + block.file_pos as u64,
+                    compressed_size: block.compressed_size as u64,
+                    file_size: block.file_size as u64,
+                    flags: block.flags,
+                    locale: hash_entry.locale,
+                };
+                match self.archive.read_file_with_info(name, &file_info) {
                     Ok(data) => {
-                        // Calculate CRC32 using standard algorithm
-                        let crc = crc32fast::hash(&data);
-                        attrs.file_attributes[block_idx].crc32 = Some(crc);
-                    }
-                    Err(_) => {
-                        // If we can't read the file, keep existing CRC or set to 0
-                        if attrs.file_attributes[block_idx].crc32.is_none() {
-                            attrs.file_attributes[block_idx].crc32 = Some(0);
+                        let file_attrs = &mut attrs.file_attributes[block_index];
+                        if attrs.flags.has_crc32() {
+                            file_attrs.crc32 = Some(crc32fast::hash(&data));
+                        }
+                        if attrs.flags.has_md5() {
+                            file_attrs.md5 = Some(Md5::digest(&data).into());
                         }
                     }
+                    Err(e) => log::warn!("Cannot read {name} to update its attributes: {e}"),
                 }
             }
 
-            // MD5 calculation would go here if we had the flag set
-            if attrs.flags.has_md5() && filename != "(listfile)" {
-                // For now, preserve existing MD5 or set to zeros
-                if attrs.file_attributes[block_idx].md5.is_none() {
-                    attrs.file_attributes[block_idx].md5 = Some([0u8; 16]);
-                }
+            let file_attrs = &mut attrs.file_attributes[block_index];
+            if attrs.flags.has_filetime() && (changed || file_attrs.filetime.is_none()) {
+                file_attrs.filetime = Some(filetime);
             }
-        }
-
-        // Also update timestamps for all other valid files (not modified)
-        if attrs.flags.has_filetime()
-            && let Some(hash_table) = &self.hash_table
-        {
-            for entry in hash_table.entries() {
-                if !entry.is_valid() {
-                    continue;
-                }
-
-                let block_idx = entry.block_index as usize;
-                if block_idx >= block_count {
-                    continue;
-                }
-
-                // Only update if not already updated above
-                if !self.modified_blocks.contains_key(&entry.block_index) {
-                    attrs.file_attributes[block_idx].filetime = Some(filetime);
-                }
+            if attrs.flags.has_patch_bit() {
+                file_attrs.is_patch = Some(block.is_patch_file());
             }
         }
 
@@ -931,6 +1341,152 @@ impl MutableArchive {
         }
     }
 
+    /// Position and size of a block that `len` bytes of replacement data can overwrite
+    ///
+    /// The block must be at least `len` bytes and not referenced by any live
+    /// hash entry, such as another locale of the same file.
+    fn in_place_slot(&self, block_index: u32, len: usize) -> Option<(u64, usize)> {
+        let block = self.block_table.as_ref()?.get(block_index as usize)?;
+        if !block.exists() || (block.compressed_size as usize) < len {
+            return None;
+        }
+
+        let shared = self
+            .hash_table
+            .as_ref()?
+            .entries()
+            .iter()
+            .any(|entry| entry.is_valid() && entry.block_index == block_index);
+        if shared {
+            return None;
+        }
+
+        Some((
+            self.archive.archive_offset() + block.file_pos as u64,
+            block.compressed_size as usize,
+        ))
+    }
+
+    /// Best fitting block whose space can be reused for `len` bytes of new data
+    ///
+    /// Candidates are blocks that no live hash entry references any more, such
+    /// as those of removed files or of files replaced by larger data. Blocks
+    /// overlapping live file data or the tables read from the header are never
+    /// returned. Yields the block index, position and size of the space.
+    fn free_block_slot(&self, len: usize) -> Option<(u32, u64, usize)> {
+        let block_table = self.block_table.as_ref()?;
+        let hash_table = self.hash_table.as_ref()?;
+        let archive_offset = self.archive.archive_offset();
+
+        let referenced: HashSet<u32> = hash_table
+            .entries()
+            .iter()
+            .filter(|entry| entry.is_valid())
+            .map(|entry| entry.block_index)
+            .collect();
+
+        // Space that must not be handed out again
+        let mut in_use: Vec<(u64, u64)> = block_table
+            .entries()
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| referenced.contains(&(*index as u32)))
+            .map(|(_, entry)| {
+                let start = entry.file_pos as u64;
+                (start, start + entry.compressed_size as u64)
+            })
+            .collect();
+        in_use.extend(self.header_table_ranges());
+
+        block_table
+            .entries()
+            .iter()
+            .enumerate()
+            .filter(|(index, entry)| {
+                let start = entry.file_pos as u64;
+                let end = start + entry.compressed_size as u64;
+                !referenced.contains(&(*index as u32))
+                    && entry.exists()
+                    && entry.compressed_size > 0
+                    && entry.compressed_size as usize >= len
+                    && in_use
+                        .iter()
+                        .all(|&(used_start, used_end)| end <= used_start || start >= used_end)
+            })
+            .min_by_key(|(_, entry)| entry.compressed_size)
+            .map(|(index, entry)| {
+                (
+                    index as u32,
+                    archive_offset + entry.file_pos as u64,
+                    entry.compressed_size as usize,
+                )
+            })
+    }
+
+    /// Ranges of the tables the header points to, relative to the archive start
+    ///
+    /// HET and BET tables without a recorded size are assumed to extend to the
+    /// next table, or to the end of the archive when they come last.
+    fn header_table_ranges(&self) -> Vec<(u64, u64)> {
+        let header = self.archive.header();
+        let hash_pos = header.get_hash_table_pos();
+        let block_pos = header.get_block_table_pos();
+        let het_pos = header.het_table_pos.filter(|&pos| pos != 0);
+        let bet_pos = header.bet_table_pos.filter(|&pos| pos != 0);
+
+        let starts: Vec<u64> = [Some(hash_pos), Some(block_pos), het_pos, bet_pos]
+            .into_iter()
+            .flatten()
+            .collect();
+        let extent = |pos: u64, size: Option<u64>| {
+            let end = size.filter(|&size| size != 0).map_or_else(
+                || {
+                    starts
+                        .iter()
+                        .copied()
+                        .filter(|&start| start > pos)
+                        .min()
+                        .unwrap_or_else(|| header.get_archive_size())
+                },
+                |size| pos + size,
+            );
+            (pos, end.max(pos))
+        };
+
+        let v4 = header.v4_data.as_ref();
+        let mut ranges = vec![
+            (hash_pos, hash_pos + header.hash_table_size as u64 * 16),
+            (block_pos, block_pos + header.block_table_size as u64 * 16),
+        ];
+        if let Some(pos) = het_pos {
+            ranges.push(extent(pos, v4.map(|v4| v4.het_table_size_64)));
+        }
+        if let Some(pos) = bet_pos {
+            ranges.push(extent(pos, v4.map(|v4| v4.bet_table_size_64)));
+        }
+        ranges
+    }
+
+    /// End offset of the last live file data
+    fn data_end(&self) -> u64 {
+        let archive_offset = self.archive.archive_offset();
+        self.block_table
+            .as_ref()
+            .or_else(|| self.archive.block_table())
+            .map(|table| {
+                table
+                    .entries()
+                    .iter()
+                    .filter(|entry| entry.exists())
+                    .map(|entry| {
+                        archive_offset + entry.file_pos as u64 + entry.compressed_size as u64
+                    })
+                    .max()
+                    .unwrap_or(0)
+            })
+            .unwrap_or(0)
+    }
+
     /// Get the current end offset of the archive
     fn get_archive_end_offset(&mut self) -> Result<u64> {
         // If we've already calculated the next offset, use it
@@ -991,24 +1547,24 @@ impl MutableArchive {
         let mut flags = BlockEntry::FLAG_EXISTS;
         let mut output_data = data.to_vec();
 
+        // Checksummed files are split into sectors, imploded files marked with
+        // the IMPLODE block flag and WAVE files compressed sector by sector the
+        // way ArchiveBuilder stores them
+        let sector_size = self.archive.header().sector_size();
+        let adpcm = compression::flags::ADPCM_MONO | compression::flags::ADPCM_STEREO;
+        if (options.sector_crc && data.len() > sector_size)
+            || options.compression == CompressionMethod::Implode
+            || options.compression.to_flags() & adpcm != 0
+        {
+            let (output_data, flags) =
+                crate::builder::encode_file(data, archive_name, options, sector_size)?;
+            let output_len = output_data.len();
+            return Ok((output_data, output_len, flags | BlockEntry::FLAG_EXISTS));
+        }
+
         // Compress if requested
         if options.compression != CompressionMethod::None {
-            // Convert CompressionMethod to u8 flag
-            let compression_flag = match options.compression {
-                CompressionMethod::None => 0,
-                CompressionMethod::Huffman => compression::flags::HUFFMAN,
-                CompressionMethod::Zlib => compression::flags::ZLIB,
-                CompressionMethod::Implode => compression::flags::IMPLODE,
-                CompressionMethod::PKWare => compression::flags::PKWARE,
-                CompressionMethod::BZip2 => compression::flags::BZIP2,
-                CompressionMethod::Sparse => compression::flags::SPARSE,
-                CompressionMethod::AdpcmMono => compression::flags::ADPCM_MONO,
-                CompressionMethod::AdpcmStereo => compression::flags::ADPCM_STEREO,
-                CompressionMethod::Lzma => compression::flags::LZMA,
-                CompressionMethod::Multiple(flags) => flags,
-            };
-
-            let compressed = compress(data, compression_flag)?;
+            let compressed = compress(data, options.compression.to_flags())?;
             if compressed.len() < data.len() {
                 output_data = compressed;
                 flags |= BlockEntry::FLAG_COMPRESS;
@@ -1080,7 +1636,7 @@ impl MutableArchive {
         let mut index = table_offset & (table_size - 1);
 
         // Linear probing to find empty or deleted slot
-        loop {
+        for _ in 0..table_size {
             let entry = hash_table.get_mut(index as usize).ok_or_else(|| {
                 Error::InvalidFormat("Hash table index out of bounds".to_string())
             })?;
@@ -1094,16 +1650,103 @@ impl MutableArchive {
                     platform: 0, // Always 0 - platform codes are vestigial
                     block_index,
                 };
-                break;
+                return Ok(());
             }
 
             // Move to next slot
             index = (index + 1) & (table_size - 1);
         }
 
+        Err(Error::hash_table(format!(
+            "No free hash table slot for {filename}"
+        )))
+    }
+
+    /// Double the hash table when it has no slot left for another file
+    fn ensure_hash_capacity(&mut self) -> Result<()> {
+        let hash_table = self
+            .hash_table
+            .as_ref()
+            .ok_or_else(|| Error::InvalidFormat("No hash table".to_string()))?;
+        if hash_table
+            .entries()
+            .iter()
+            .any(|entry| entry.is_empty() || entry.is_deleted())
+        {
+            return Ok(());
+        }
+
+        let size = hash_table.size() * 2;
+        if size > MAX_HASH_TABLE_SIZE {
+            return Err(Error::CapacityExceeded(format!(
+                "Hash table is full at {} entries",
+                hash_table.size()
+            )));
+        }
+        log::debug!("Growing hash table to {size} entries");
+        self.rebuild_hash_table(size)
+    }
+
+    /// Replace the hash table with one of `size` entries holding the same files
+    ///
+    /// Deleted entries are dropped. Every file must have a known name, since
+    /// its position in the new table depends on the name.
+    fn rebuild_hash_table(&mut self, size: usize) -> Result<()> {
+        let names = self.known_names()?;
+        let old_table = self
+            .hash_table
+            .as_ref()
+            .ok_or_else(|| Error::InvalidFormat("No hash table".to_string()))?;
+
+        let mut new_table = HashTable::new_mut(size)?;
+        for entry in old_table.entries().iter().filter(|entry| entry.is_valid()) {
+            let name = names.get(&(entry.name_1, entry.name_2)).ok_or_else(|| {
+                Error::hash_table(format!(
+                    "Cannot rebuild the hash table: file {:08X}{:08X} is not in the (listfile)",
+                    entry.name_1, entry.name_2
+                ))
+            })?;
+
+            let mut index = hash_string(name, hash_type::TABLE_OFFSET) as usize & (size - 1);
+            while !new_table.entries()[index].is_empty() {
+                index = (index + 1) & (size - 1);
+            }
+            new_table.entries_mut()[index] = *entry;
+        }
+
+        self.hash_table = Some(new_table);
+        self.dirty = true;
         Ok(())
     }
 
+    /// Names of the files in the archive, keyed by their two name hashes
+    ///
+    /// Covers the (listfile), the files written in this session and the
+    /// special files.
+    fn known_names(&mut self) -> Result<HashMap<(u32, u32), String>> {
+        let mut names: Vec<String> = self.modified_blocks.values().cloned().collect();
+        names.extend(
+            ["(listfile)", "(attributes)", "(signature)"]
+                .iter()
+                .map(|name| name.to_string()),
+        );
+        if let Ok(listfile) = self.read_current_file("(listfile)") {
+            names.extend(crate::special_files::parse_listfile(&listfile)?);
+        }
+
+        Ok(names
+            .into_iter()
+            .map(|name| {
+                let name = name.replace('/', "\\");
+                let key = (
+                    hash_string(&name, hash_type::NAME_A),
+                    hash_string(&name, hash_type::NAME_B),
+                );
+                (key, name)
+            })
+            .collect())
+    }
+
     /// Update the (listfile) with a new filename
     fn update_listfile(&mut self, filename: &str) -> Result<()> {
         // Check if (listfile) exists
@@ -1127,11 +1770,7 @@ impl MutableArchive {
             current_content.push('\n');
 
             // Write updated listfile back
-            let options = AddFileOptions::new()
-                .compression(CompressionMethod::None) // Keep listfile uncompressed
-                .replace_existing(true);
-
-            self.add_file_data(current_content.as_bytes(), "(listfile)", options)?;
+            self.store_listfile(current_content)?;
         }
 
         Ok(())
@@ -1164,18 +1803,36 @@ impl MutableArchive {
                 final_content.push('\n');
             }
 
-            let options = AddFileOptions::new()
-                .compression(CompressionMethod::None)
-                .replace_existing(true);
-
-            self.add_file_data(final_content.as_bytes(), "(listfile)", options)?;
+            self.store_listfile(final_content)?;
         }
 
         Ok(())
     }
 
+    /// Store new (listfile) content, deferring the write in incremental mode
+    fn store_listfile(&mut self, content: String) -> Result<()> {
+        if self.incremental {
+            self.pending_listfile = Some(content);
+            self.dirty = true;
+            return Ok(());
+        }
+
+        self.write_listfile(content)
+    }
+
+    /// Write (listfile) content to the archive
+    fn write_listfile(&mut self, content: String) -> Result<()> {
+        let options = AddFileOptions::new()
+            .compression(CompressionMethod::None) // Keep listfile uncompressed
+            .replace_existing(true);
+
+        self.add_file_data(content.as_bytes(), "(listfile)", options)
+    }
+
     /// Write updated tables back to the archive
-    fn write_tables(&mut self) -> Result<()> {
+    ///
+    /// Returns the absolute offset just past the last table written.
+    fn write_tables(&mut self) -> Result<u64> {
         let header = self.archive.header();
 
         // For V3+ archives, we need to rebuild the entire table structure
@@ -1186,69 +1843,60 @@ impl MutableArchive {
 
         // For V1/V2 archives, use the original simple approach
         let archive_offset = self.archive.archive_offset();
+        let hash_table_pos = archive_offset + header.hash_table_pos as u64;
+        let hash_table_capacity = header.hash_table_size as usize;
+        let block_table_pos = archive_offset + header.block_table_pos as u64;
+        let block_table_capacity = header.block_table_size as usize;
+        let mut tables_end = 0;
 
         // Write hash table
         if let Some(hash_table) = &self.hash_table {
-            let hash_table_pos = archive_offset + header.hash_table_pos as u64;
-            self.file.seek(SeekFrom::Start(hash_table_pos))?;
-
-            // Convert to bytes and encrypt
-            let mut table_data = Vec::new();
-            for entry in hash_table.entries() {
-                table_data.extend_from_slice(&entry.name_1.to_le_bytes());
-                table_data.extend_from_slice(&entry.name_2.to_le_bytes());
-                table_data.extend_from_slice(&entry.locale.to_le_bytes());
-                table_data.extend_from_slice(&entry.platform.to_le_bytes());
-                table_data.extend_from_slice(&entry.block_index.to_le_bytes());
-            }
-
-            // Encrypt the table
-            let key = hash_string("(hash table)", hash_type::FILE_KEY);
-            let mut u32_buffer: Vec<u32> = table_data
-                .chunks_exact(4)
-                .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
-                .collect();
-            encrypt_block(&mut u32_buffer, key);
+            let table_data = Self::hash_table_bytes(hash_table);
+
+            // A grown hash table moves behind the file data like the block table
+            let hash_table_pos = if hash_table.size() > hash_table_capacity {
+                let new_pos = self.get_archive_end_offset()?;
+                let next_offset = new_pos + table_data.len() as u64;
+                self.next_file_offset = Some((next_offset + 511) & !511);
+                self.updated_hash_table_pos = Some(new_pos - archive_offset);
+                new_pos
+            } else {
+                hash_table_pos
+            };
 
-            // Write back
-            for &value in &u32_buffer {
-                self.file.write_all(&value.to_le_bytes())?;
-            }
+            self.file.seek(SeekFrom::Start(hash_table_pos))?;
+            self.file.write_all(&table_data)?;
+            tables_end = hash_table_pos + table_data.len() as u64;
         }
 
         // Write block table
         if let Some(block_table) = &self.block_table {
-            let block_table_pos = archive_offset + header.block_table_pos as u64;
-            self.file.seek(SeekFrom::Start(block_table_pos))?;
-
-            // Convert to bytes and encrypt
-            let mut table_data = Vec::new();
-            for entry in block_table.entries() {
-                table_data.extend_from_slice(&entry.file_pos.to_le_bytes());
-                table_data.extend_from_slice(&entry.compressed_size.to_le_bytes());
-                table_data.extend_from_slice(&entry.file_size.to_le_bytes());
-                table_data.extend_from_slice(&entry.flags.to_le_bytes());
-            }
-
-            // Encrypt the table
-            let key = hash_string("(block table)", hash_type::FILE_KEY);
-            let mut u32_buffer: Vec<u32> = table_data
-                .chunks_exact(4)
-                .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
-                .collect();
-            encrypt_block(&mut u32_buffer, key);
+            let table_data = Self::block_table_bytes(block_table);
+
+            // A block table that has grown would overwrite whatever follows
+            // it, so move it behind the file data instead
+            let block_table_pos = if block_table.entries().len() > block_table_capacity {
+                let new_pos = self.get_archive_end_offset()?;
+                let next_offset = new_pos + table_data.len() as u64;
+                self.next_file_offset = Some((next_offset + 511) & !511);
+                self.updated_block_table_pos = Some(new_pos - archive_offset);
+                new_pos
+            } else {
+                block_table_pos
+            };
 
-            // Write back
-            for &value in &u32_buffer {
-                self.file.write_all(&value.to_le_bytes())?;
-            }
+            self.file.seek(SeekFrom::Start(block_table_pos))?;
+            self.file.write_all(&table_data)?;
+            tables_end = tables_end.max(block_table_pos + table_data.len() as u64);
         }
 
-        Ok(())
+        Ok(tables_end)
     }
 
     /// Write tables for V3+ archives with correct ordering
-    fn write_tables_v3_plus(&mut self) -> Result<()> {
+    ///
+    /// Returns the absolute offset just past the block table.
+    fn write_tables_v3_plus(&mut self) -> Result<u64> {
         let hash_table = self
             .hash_table
             .as_ref()
@@ -1258,22 +1906,72 @@ impl MutableArchive {
             .as_ref()
             .ok_or_else(|| Error::invalid_format("Block table not loaded for V3+ table write"))?;
 
-        // Find the end of file data to start writing tables
-        let current_pos = self.file.stream_position()?;
-        let archive_offset = self.archive.archive_offset();
+        // Lay out HET, BET, hash and block tables back to back (correct order for V3+)
+        let (mut tables, _het_header) = self.create_het_table_from_hash_table(hash_table)?;
+        let bet_offset = tables.len() as u64;
+        let (bet_data, _bet_header) = self.create_bet_table_from_block_table(block_table)?;
+        tables.extend_from_slice(&bet_data);
+        let hash_offset = tables.len() as u64;
+        tables.extend_from_slice(&Self::hash_table_bytes(hash_table));
+        let block_offset = tables.len() as u64;
+        tables.extend_from_slice(&Self::block_table_bytes(block_table));
+
+        // Incremental writes reuse the space of the current tables when the
+        // new ones fit; otherwise tables follow the data written last
+        let start = if self.incremental {
+            self.incremental_tables_pos(tables.len() as u64)?
+        } else {
+            self.file.stream_position()?
+        };
+        self.file.seek(SeekFrom::Start(start))?;
+        self.file.write_all(&tables)?;
 
-        // Write HET table first (correct order for V3+)
-        let het_pos = current_pos - archive_offset;
-        let (het_data, _het_header) = self.create_het_table_from_hash_table(hash_table)?;
-        self.file.write_all(&het_data)?;
+        // Store all the updated positions for header update
+        let table_pos = start - self.archive.archive_offset();
+        self.updated_het_pos = Some(table_pos);
+        self.updated_bet_pos = Some(table_pos + bet_offset);
+        self.updated_hash_table_pos = Some(table_pos + hash_offset);
+        self.updated_block_table_pos = Some(table_pos + block_offset);
 
-        // Write BET table second
-        let bet_pos = self.file.stream_position()? - archive_offset;
-        let (bet_data, _bet_header) = self.create_bet_table_from_block_table(block_table)?;
-        self.file.write_all(&bet_data)?;
+        Ok(start + tables.len() as u64)
+    }
 
-        // Write hash table third
-        let hash_table_pos = self.file.stream_position()? - archive_offset;
+    /// Absolute position for `len` bytes of V3+ tables in incremental mode
+    ///
+    /// The space of the tables the header points to is reused when the new
+    /// tables fit and no live file data has been placed inside it.
+    /// Otherwise the tables go behind the file data.
+    fn incremental_tables_pos(&mut self, len: u64) -> Result<u64> {
+        let ranges = self.header_table_ranges();
+        let start = ranges.iter().map(|&(start, _)| start).min().unwrap_or(0);
+        let end = ranges.iter().map(|&(_, end)| end).max().unwrap_or(0);
+
+        let referenced: HashSet<u32> = self
+            .hash_table
+            .iter()
+            .flat_map(|table| table.entries())
+            .filter(|entry| entry.is_valid())
+            .map(|entry| entry.block_index)
+            .collect();
+        let overlaps_data = self.block_table.iter().any(|table| {
+            table.entries().iter().enumerate().any(|(index, entry)| {
+                let data_start = entry.file_pos as u64;
+                let data_end = data_start + entry.compressed_size as u64;
+                referenced.contains(&(index as u32)) && data_start < start + len && data_end > start
+            })
+        });
+
+        if start != 0 && start + len <= end && !overlaps_data {
+            return Ok(self.archive.archive_offset() + start);
+        }
+
+        let pos = self.get_archive_end_offset()?;
+        self.next_file_offset = Some((pos + len + 511) & !511);
+        Ok(pos)
+    }
+
+    /// Serialize and encrypt a hash table
+    fn hash_table_bytes(hash_table: &HashTable) -> Vec<u8> {
         let mut table_data = Vec::new();
         for entry in hash_table.entries() {
             table_data.extend_from_slice(&entry.name_1.to_le_bytes());
@@ -1283,21 +1981,11 @@ impl MutableArchive {
             table_data.extend_from_slice(&entry.block_index.to_le_bytes());
         }
 
-        // Encrypt the hash table
-        let key = hash_string("(hash table)", hash_type::FILE_KEY);
-        let mut u32_buffer: Vec<u32> = table_data
-            .chunks_exact(4)
-            .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
-            .collect();
-        encrypt_block(&mut u32_buffer, key);
-
-        // Write encrypted hash table
-        for &value in &u32_buffer {
-            self.file.write_all(&value.to_le_bytes())?;
-        }
+        Self::encrypt_table(&table_data, "(hash table)")
+    }
 
-        // Write block table fourth
-        let block_table_pos = self.file.stream_position()? - archive_offset;
+    /// Serialize and encrypt a block table
+    fn block_table_bytes(block_table: &BlockTable) -> Vec<u8> {
         let mut table_data = Vec::new();
         for entry in block_table.entries() {
             table_data.extend_from_slice(&entry.file_pos.to_le_bytes());
@@ -1306,26 +1994,22 @@ impl MutableArchive {
             table_data.extend_from_slice(&entry.flags.to_le_bytes());
         }
 
-        // Encrypt the block table
-        let key = hash_string("(block table)", hash_type::FILE_KEY);
+        Self::encrypt_table(&table_data, "(block table)")
+    }
+
+    /// Encrypt table data with the key derived from `key_name`
+    fn encrypt_table(table_data: &[u8], key_name: &str) -> Vec<u8> {
+        let key = hash_string(key_name, hash_type::FILE_KEY);
         let mut u32_buffer: Vec<u32> = table_data
             .chunks_exact(4)
             .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
             .collect();
         encrypt_block(&mut u32_buffer, key);
 
-        // Write encrypted block table
-        for &value in &u32_buffer {
-            self.file.write_all(&value.to_le_bytes())?;
-        }
-
-        // Store all the updated positions for header update
-        self.updated_het_pos = Some(het_pos);
-        self.updated_bet_pos = Some(bet_pos);
-        self.updated_hash_table_pos = Some(hash_table_pos);
-        self.updated_block_table_pos = Some(block_table_pos);
-
-        Ok(())
+        u32_buffer
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect()
     }
 
     /// Create HET table data from hash table (simplified version of ArchiveBuilder logic)
@@ -1577,6 +2261,35 @@ impl MutableArchive {
         }
     }
 
+    /// Rewrite the archive size fields of the header
+    fn write_archive_size(&mut self, archive_size: u64) -> Result<()> {
+        use md5::{Digest, Md5};
+
+        let archive_offset = self.archive.archive_offset();
+        let version = self.archive.header().format_version;
+
+        self.file.seek(SeekFrom::Start(archive_offset + 0x08))?;
+        self.file
+            .write_all(&(archive_size.min(u32::MAX as u64) as u32).to_le_bytes())?;
+
+        if version >= FormatVersion::V3 {
+            self.file.seek(SeekFrom::Start(archive_offset + 0x2C))?;
+            self.file.write_all(&archive_size.to_le_bytes())?;
+        }
+
+        // The V4 header ends with an MD5 of everything before it
+        if version >= FormatVersion::V4 {
+            let header_data_size = version.header_size() as usize - 16;
+            let mut header_data = vec![0u8; header_data_size];
+            self.file.seek(SeekFrom::Start(archive_offset))?;
+            self.file.read_exact(&mut header_data)?;
+            let header_md5: [u8; 16] = Md5::digest(&header_data).into();
+            self.file.write_all(&header_md5)?;
+        }
+
+        Ok(())
+    }
+
     /// Update the archive header
     fn update_header(&mut self) -> Result<()> {
         let archive_offset = self.archive.archive_offset();
@@ -1592,6 +2305,24 @@ impl MutableArchive {
             }
         }
 
+        // Update hash table size if it has been rebuilt
+        if let Some(hash_table) = &self.hash_table {
+            let new_size = hash_table.size() as u32;
+            if new_size != header.hash_table_size {
+                header.hash_table_size = new_size;
+                needs_update = true;
+            }
+        }
+
+        // Tables that were written somewhere else need the header to follow them
+        if self.updated_hash_table_pos.is_some()
+            || self.updated_block_table_pos.is_some()
+            || self.updated_het_pos.is_some()
+            || self.updated_bet_pos.is_some()
+        {
+            needs_update = true;
+        }
+
         if needs_update {
             // Seek to header position
             self.file.seek(SeekFrom::Start(archive_offset))?;
@@ -1640,6 +2371,14 @@ impl MutableArchive {
                 self.file.write_all(&het_pos.to_le_bytes())?;
                 self.file.write_all(&bet_pos.to_le_bytes())?;
             }
+
+            // V4 stores the on-disk table sizes, which are uncompressed here
+            if header.format_version >= FormatVersion::V4 {
+                self.file
+                    .write_all(&(header.hash_table_size as u64 * 16).to_le_bytes())?;
+                self.file
+                    .write_all(&(header.block_table_size as u64 * 16).to_le_bytes())?;
+            }
         }
 
         Ok(())
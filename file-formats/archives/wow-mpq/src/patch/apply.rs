@@ -2,6 +2,8 @@
 //!
 //! This module implements patch application for both COPY and BSD0 patch types.
 
+use std::borrow::Cow;
+
 use super::header::{PatchFile, PatchType};
 use crate::{Error, Result};
 
@@ -22,7 +24,6 @@ use crate::{Error, Result};
 /// - Base file MD5 doesn't match expected hash
 /// - Patch application fails
 /// - Patched result MD5 doesn't match expected hash
-/// - Unsupported patch type (BSD0 not yet implemented)
 pub fn apply_patch(patch: &PatchFile, base_data: &[u8]) -> Result<Vec<u8>> {
     // Verify base file MD5 before patching
     patch.verify_base(base_data)?;
@@ -81,10 +82,50 @@ fn apply_copy_patch(patch: &PatchFile, base_data: &[u8]) -> Result<Vec<u8>> {
     Ok(patch.data.clone())
 }
 
+/// Signature at the start of uncompressed BSD0 patch data
+const BSDIFF40_SIGNATURE: &[u8; 8] = b"BSDIFF40";
+
+/// The bsdiff40 data of a BSD0 patch
+///
+/// Blizzard RLE-compresses the data unless that would not make it smaller, in
+/// which case it is stored as is. Stored data starts with the bsdiff40
+/// signature; compressed data starts with its decompressed size, which is
+/// bounded by the size in the PTCH header.
+fn bsdiff_payload(patch: &PatchFile) -> Result<Cow<'_, [u8]>> {
+    if patch.data.starts_with(BSDIFF40_SIGNATURE) {
+        log::debug!(
+            "BSD0 patch data is stored uncompressed: {} bytes",
+            patch.data.len()
+        );
+        return Ok(Cow::Borrowed(&patch.data));
+    }
+
+    let stated_size = patch
+        .data
+        .get(..4)
+        .map(|size| u32::from_le_bytes([size[0], size[1], size[2], size[3]]))
+        .ok_or_else(|| Error::invalid_format("BSD0 patch data too short"))?;
+    let decompressed_size = stated_size.min(patch.header.patch_data_size) as usize;
+
+    let bsdiff_data = crate::compression::rle::decompress(
+        &patch.data,
+        decompressed_size,
+        true, // skip 4-byte size header
+    )?;
+
+    log::debug!(
+        "RLE decompression complete: {} bytes → {} bytes",
+        patch.data.len(),
+        bsdiff_data.len()
+    );
+
+    Ok(Cow::Owned(bsdiff_data))
+}
+
 /// Apply a BSD0 (bsdiff40) patch
 ///
 /// BSD0 patches use binary diff algorithm to create space-efficient patches.
-/// The patch data is RLE-compressed and must be decompressed before applying.
+/// The patch data is usually RLE-compressed; see [`bsdiff_payload`].
 ///
 /// # Arguments
 ///
@@ -107,24 +148,7 @@ fn apply_bsd0_patch(patch: &PatchFile, base_data: &[u8]) -> Result<Vec<u8>> {
         )));
     }
 
-    // BSD0 patch data is RLE-compressed - decompress it first
-    // The RLE data has a 4-byte size header that should be skipped
-    log::debug!(
-        "RLE decompressing BSD0 patch data: {} bytes compressed",
-        patch.data.len()
-    );
-
-    let bsdiff_data = crate::compression::rle::decompress(
-        &patch.data,
-        patch.header.patch_data_size as usize,
-        true, // skip 4-byte size header
-    )?;
-
-    log::debug!(
-        "RLE decompression complete: {} bytes → {} bytes",
-        patch.data.len(),
-        bsdiff_data.len()
-    );
+    let bsdiff_data = bsdiff_payload(patch)?;
 
     let mut reader = Cursor::new(&bsdiff_data);
 
@@ -282,6 +306,7 @@ fn apply_bsd0_patch(patch: &PatchFile, base_data: &[u8]) -> Result<Vec<u8>> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::patch::PatchHeader;
 
     /// Helper to create a test COPY patch
     fn create_copy_patch(base_size: u32, new_data: Vec<u8>) -> PatchFile {
@@ -401,6 +426,23 @@ mod tests {
 
     /// Helper to create a test BSD0 patch
     fn create_bsd0_patch(base_data: &[u8], new_data: &[u8], patch_data: Vec<u8>) -> PatchFile {
+        // RLE compress the patch data (BSD0 patches use RLE compression)
+        let compressed_patch_data = rle_compress_test(&patch_data);
+        build_bsd0_patch(
+            base_data,
+            new_data,
+            patch_data.len() as u32,
+            &compressed_patch_data,
+        )
+    }
+
+    /// Helper to create a BSD0 patch with the given stored payload
+    fn build_bsd0_patch(
+        base_data: &[u8],
+        new_data: &[u8],
+        patch_data_size: u32,
+        compressed_patch_data: &[u8],
+    ) -> PatchFile {
         use md5::{Digest, Md5};
 
         // Calculate MD5 hashes
@@ -412,15 +454,12 @@ mod tests {
         hasher.update(new_data);
         let md5_after: [u8; 16] = hasher.finalize().into();
 
-        // RLE compress the patch data (BSD0 patches use RLE compression)
-        let compressed_patch_data = rle_compress_test(&patch_data);
-
         // Build PTCH file with BSD0 type
         let mut data = Vec::new();
 
         // PTCH Header
         data.extend_from_slice(&0x48435450u32.to_le_bytes());
-        data.extend_from_slice(&(patch_data.len() as u32).to_le_bytes()); // Decompressed size
+        data.extend_from_slice(&patch_data_size.to_le_bytes()); // Decompressed size
         data.extend_from_slice(&(base_data.len() as u32).to_le_bytes());
         data.extend_from_slice(&(new_data.len() as u32).to_le_bytes());
 
@@ -436,7 +475,7 @@ mod tests {
         data.extend_from_slice(&0x30445342u32.to_le_bytes()); // BSD0
 
         // Compressed patch data
-        data.extend_from_slice(&compressed_patch_data);
+        data.extend_from_slice(compressed_patch_data);
 
         PatchFile::parse(&data).expect("Failed to create test BSD0 patch")
     }
@@ -477,6 +516,34 @@ mod tests {
         assert_eq!(result, new_data);
     }
 
+    #[test]
+    fn test_apply_bsd0_uncompressed() {
+        // Old: [0x10, 0x20], New: [0x11, 0x22, 0x33]
+        let old_data = vec![0x10u8, 0x20];
+        let new_data = vec![0x11u8, 0x22, 0x33];
+
+        let mut patch_data = Vec::new();
+        patch_data.extend_from_slice(b"BSDIFF40");
+        patch_data.extend_from_slice(&12u64.to_le_bytes());
+        patch_data.extend_from_slice(&2u64.to_le_bytes());
+        patch_data.extend_from_slice(&3u64.to_le_bytes());
+        patch_data.extend_from_slice(&2u32.to_le_bytes()); // add=2
+        patch_data.extend_from_slice(&1u32.to_le_bytes()); // mov=1
+        patch_data.extend_from_slice(&0u32.to_le_bytes()); // old_move=0
+        patch_data.extend_from_slice(&[0x01, 0x02, 0x33]);
+
+        // Stored as is, with the size of the whole patch in the PTCH header
+        let patch_data_size = (PatchHeader::HEADER_SIZE + patch_data.len()) as u32;
+        let patch = build_bsd0_patch(&old_data, &new_data, patch_data_size, &patch_data);
+        let result = apply_patch(&patch, &old_data).expect("Failed to apply BSD0 patch");
+        assert_eq!(result, new_data);
+
+        // The RLE-compressed form under the same header gives the same result
+        let compressed = rle_compress_test(&patch_data);
+        let patch = build_bsd0_patch(&old_data, &new_data, patch_data_size, &compressed);
+        assert_eq!(apply_patch(&patch, &old_data).unwrap(), new_data);
+    }
+
     #[test]
     fn test_bsd0_wrapping_addition() {
         // Test that addition wraps at 256
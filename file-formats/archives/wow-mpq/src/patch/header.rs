@@ -46,7 +46,7 @@ impl PatchType {
 /// PTCH file header containing all metadata
 #[derive(Debug, Clone)]
 pub struct PatchHeader {
-    /// Total size of patch data (decompressed)
+    /// Total size of the patch (decompressed), including this header
     pub patch_data_size: u32,
     /// Size of original file before patching
     pub size_before: u32,
@@ -66,13 +66,13 @@ impl PatchHeader {
     /// Parse PTCH header from reader
     ///
     /// Reads and validates:
-    /// - PTCH header (12 bytes)
+    /// - PTCH header (16 bytes)
     /// - MD5 block (40 bytes)
     /// - XFRM header (12 bytes)
     ///
     /// Returns the parsed header. Caller must read remaining patch data.
     pub fn parse<R: Read>(reader: &mut R) -> Result<Self> {
-        // --- PTCH Header (12 bytes) ---
+        // --- PTCH Header (16 bytes) ---
         let ptch_sig = reader.read_u32::<LittleEndian>()?;
         if ptch_sig != PTCH_SIGNATURE {
             return Err(Error::invalid_format(format!(
@@ -148,7 +148,7 @@ impl PatchHeader {
     }
 
     /// Total header size in bytes (PTCH + MD5 + XFRM headers)
-    pub const HEADER_SIZE: usize = 12 + 40 + 12; // 64 bytes
+    pub const HEADER_SIZE: usize = 16 + 40 + 12; // 68 bytes
 }
 
 /// Complete patch file with header and data
@@ -246,6 +246,6 @@ mod tests {
 
     #[test]
     fn test_header_size() {
-        assert_eq!(PatchHeader::HEADER_SIZE, 64);
+        assert_eq!(PatchHeader::HEADER_SIZE, 68);
     }
 }
@@ -4,10 +4,16 @@
 //! where files in higher-priority archives override those in lower-priority ones.
 //! This is essential for World of Warcraft's patching system.
 
-use crate::{Archive, Error, FileEntry, Result};
-use std::collections::HashMap;
+use crate::{Archive, Error, FileEntry, Result, path::PathPolicy};
+use overlay::Overlay;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::path::{Path, PathBuf};
 
+mod discovery;
+mod overlay;
+
+pub use discovery::{ArchiveKind, ArchiveLayout, GameVersion, LayoutArchive};
+
 /// A chain of MPQ archives with priority ordering
 ///
 /// `PatchChain` manages multiple MPQ archives where files in higher-priority
@@ -26,6 +32,9 @@ use std::path::{Path, PathBuf};
 /// - Both COPY (replacement) and BSD0 (binary diff) patches are supported
 /// - MD5 verification ensures patch integrity
 ///
+/// A deletion marker (`MPQ_FILE_DELETE_MARKER`) removes the file: it is not
+/// read, listed or patched from any archive of lower priority.
+///
 /// # Examples
 ///
 /// ```no_run
@@ -54,6 +63,14 @@ pub struct PatchChain {
     archives: Vec<ChainEntry>,
     /// Cache of file locations for quick lookup
     file_map: HashMap<String, usize>,
+    /// Lookup keys whose highest-priority entry is a deletion marker
+    deleted: HashSet<String>,
+    /// Writable archive above every archive of the chain
+    overlay: Option<Overlay>,
+    /// Names found by hash that no listfile of the chain has, when recording
+    recorded_names: Option<BTreeSet<String>>,
+    /// Name lookup policy applied to every archive of the chain
+    path_policy: PathPolicy,
 }
 
 #[derive(Debug)]
@@ -72,6 +89,10 @@ impl PatchChain {
         Self {
             archives: Vec::new(),
             file_map: HashMap::new(),
+            deleted: HashSet::new(),
+            overlay: None,
+            recorded_names: None,
+            path_policy: PathPolicy::LENIENT,
         }
     }
 
@@ -130,6 +151,7 @@ impl PatchChain {
     pub fn clear(&mut self) {
         self.archives.clear();
         self.file_map.clear();
+        self.deleted.clear();
     }
 
     /// Get the number of archives in the chain
@@ -139,43 +161,104 @@ impl PatchChain {
 
     /// Read a file from the chain
     ///
-    /// Returns the file from the highest-priority archive that contains it.
+    /// Returns the file from the overlay, if one is set and has it, or else
+    /// from the highest-priority archive that contains it. Files missing from
+    /// every listfile are looked up by name hash.
     /// If the file is a patch file, this method will automatically:
     /// 1. Find the base file in lower-priority archives
     /// 2. Apply all patches in priority order
     /// 3. Return the fully patched result
     pub fn read_file(&mut self, filename: &str) -> Result<Vec<u8>> {
+        if let Some(overlay) = &mut self.overlay
+            && overlay.contains(filename)
+        {
+            return overlay.read_file(filename);
+        }
+
         // Normalize filename and convert to uppercase for case-insensitive lookup
         // This matches MPQ hashing behavior which is always case-insensitive
-        let lookup_key = crate::path::normalize_mpq_path(filename).to_uppercase();
-
-        if let Some(&archive_idx) = self.file_map.get(&lookup_key) {
-            // Check if this is a patch file by examining the file info
-            let file_info = self.archives[archive_idx]
-                .archive
-                .find_file(filename)?
-                .ok_or_else(|| Error::FileNotFound(filename.to_string()))?;
-
-            if file_info.is_patch_file() {
-                // This is a patch file - need to find base and apply patches
-                self.read_patched_file(filename, archive_idx)
-            } else {
-                // Regular file - read normally
-                self.archives[archive_idx].archive.read_file(filename)
+        let lookup_key = crate::path::path_key(filename);
+        if self.deleted.contains(&lookup_key) {
+            return Err(Error::FileNotFound(filename.to_string()));
+        }
+
+        let (archive_idx, probed) = match self.file_map.get(&lookup_key) {
+            Some(&archive_idx) => (archive_idx, false),
+            None => {
+                let archive_idx = self
+                    .probe_file(filename)?
+                    .ok_or_else(|| Error::FileNotFound(filename.to_string()))?;
+                self.file_map.insert(lookup_key, archive_idx);
+                (archive_idx, true)
             }
+        };
+
+        // Check if this is a patch file by examining the file info
+        let file_info = self.archives[archive_idx]
+            .archive
+            .find_file(filename)?
+            .ok_or_else(|| Error::FileNotFound(filename.to_string()))?;
+
+        let data = if file_info.is_delete_marker() {
+            return Err(Error::FileNotFound(filename.to_string()));
+        } else if file_info.is_patch_file() {
+            // This is a patch file - need to find base and apply patches
+            self.read_patched_file(filename, archive_idx)?
         } else {
-            Err(Error::FileNotFound(filename.to_string()))
+            // Regular file - read normally
+            self.archives[archive_idx].archive.read_file(filename)?
+        };
+
+        if probed && let Some(names) = &mut self.recorded_names {
+            names.insert(crate::path::canonical_path(filename));
+        }
+        Ok(data)
+    }
+
+    /// Find a file no listfile names by its hash, in priority order
+    fn probe_file(&mut self, filename: &str) -> Result<Option<usize>> {
+        for (idx, entry) in self.archives.iter_mut().enumerate() {
+            if entry.archive.find_file(filename)?.is_some() {
+                log::debug!(
+                    "Found unlisted file '{}' in archive {}",
+                    filename,
+                    entry.path.display()
+                );
+                return Ok(Some(idx));
+            }
         }
+        Ok(None)
+    }
+
+    /// Start or stop recording names of files read by name that no listfile
+    /// of the chain contains
+    ///
+    /// Such files are found by name hash in archives without a complete
+    /// listfile. See [`Archive::set_record_names`] for single archives.
+    pub fn set_record_names(&mut self, record: bool) {
+        if !record {
+            self.recorded_names = None;
+        } else if self.recorded_names.is_none() {
+            self.recorded_names = Some(BTreeSet::new());
+        }
+    }
+
+    /// Take the names recorded since the last call, sorted
+    pub fn take_recorded_names(&mut self) -> Vec<String> {
+        self.recorded_names
+            .as_mut()
+            .map(|names| std::mem::take(names).into_iter().collect())
+            .unwrap_or_default()
     }
 
     /// Read a patch file and apply it to the base file
     ///
     /// This method handles the patch chain resolution:
-    /// 1. Find the base file (non-patch version) in lower-priority archives
-    /// 2. Read all patches for this file in priority order
+    /// 1. Find the base file: the highest-priority non-patch version
+    /// 2. Read the patches for this file above the base, in priority order
     /// 3. Apply patches sequentially to produce the final result
     fn read_patched_file(&mut self, filename: &str, _patch_idx: usize) -> Result<Vec<u8>> {
-        use crate::patch::{apply_patch, PatchFile};
+        use crate::patch::{PatchFile, apply_patch};
 
         // Step 1: Find the base file (search lower priority archives)
         let mut base_data: Option<Vec<u8>> = None;
@@ -184,7 +267,10 @@ impl PatchChain {
         // Collect all versions of this file in priority order (highest first)
         for (idx, entry) in self.archives.iter_mut().enumerate() {
             if let Ok(Some(file_info)) = entry.archive.find_file(filename) {
-                if file_info.is_patch_file() {
+                if file_info.is_delete_marker() {
+                    // The file was deleted below the patches, so there is no base
+                    break;
+                } else if file_info.is_patch_file() {
                     // This is a patch - read it raw (bypass the read_file check)
                     match entry.archive.read_patch_file_raw(filename) {
                         Ok(patch_data) => {
@@ -212,8 +298,9 @@ impl PatchChain {
                             );
                         }
                     }
-                } else if base_data.is_none() {
-                    // This is a regular file - use as base if we haven't found one yet
+                } else {
+                    // This is a regular file - it replaces everything below it,
+                    // so older patches in lower-priority archives do not apply
                     match entry.archive.read_file(filename) {
                         Ok(data) => {
                             log::debug!(
@@ -223,6 +310,7 @@ impl PatchChain {
                                 entry.priority
                             );
                             base_data = Some(data);
+                            break;
                         }
                         Err(e) => {
                             log::warn!(
@@ -264,17 +352,59 @@ impl PatchChain {
 
     /// Check if a file exists in the chain
     pub fn contains_file(&self, filename: &str) -> bool {
-        let lookup_key = crate::path::normalize_mpq_path(filename).to_uppercase();
-        self.file_map.contains_key(&lookup_key)
+        if self.overlay.as_ref().is_some_and(|o| o.contains(filename)) {
+            return true;
+        }
+        let lookup_key = crate::path::path_key(filename);
+        if self.deleted.contains(&lookup_key) {
+            return false;
+        }
+        self.file_map
+            .get(&lookup_key)
+            .is_some_and(|&idx| self.policy_allows(idx, filename))
+    }
+
+    /// Whether the chain's path policy lets `filename` name the file the
+    /// file map places in archive `idx`
+    fn policy_allows(&self, idx: usize, filename: &str) -> bool {
+        self.path_policy.is_lenient()
+            || matches!(self.archives[idx].archive.find_file(filename), Ok(Some(_)))
+    }
+
+    /// Current policy for name lookups
+    pub fn path_policy(&self) -> PathPolicy {
+        self.path_policy
+    }
+
+    /// Set how name lookups treat case and path separators
+    ///
+    /// The policy applies to every archive of the chain, including archives
+    /// added later. See [`Archive::set_path_policy`] for how names are checked.
+    pub fn set_path_policy(&mut self, policy: PathPolicy) -> Result<()> {
+        self.path_policy = policy;
+        for entry in &mut self.archives {
+            entry.archive.set_path_policy(policy)?;
+        }
+        Ok(())
     }
 
     /// Find which archive contains a file
     ///
     /// Returns the path to the archive containing the file, or None if not found.
+    /// Files written to the overlay report the overlay's path.
     pub fn find_file_archive(&self, filename: &str) -> Option<&Path> {
-        let lookup_key = crate::path::normalize_mpq_path(filename).to_uppercase();
+        if let Some(overlay) = &self.overlay
+            && overlay.contains(filename)
+        {
+            return Some(overlay.path());
+        }
+        let lookup_key = crate::path::path_key(filename);
+        if self.deleted.contains(&lookup_key) {
+            return None;
+        }
         self.file_map
             .get(&lookup_key)
+            .filter(|&&idx| self.policy_allows(idx, filename))
             .map(|&idx| self.archives[idx].path.as_path())
     }
 
@@ -282,10 +412,21 @@ impl PatchChain {
     ///
     /// Returns a deduplicated list of all files across all archives,
     /// with file information from the highest-priority archive for each file.
+    /// Overlay files take precedence over every archive. Files whose
+    /// highest-priority entry is a deletion marker are left out.
     pub fn list(&mut self) -> Result<Vec<FileEntry>> {
         let mut seen = HashMap::new();
         let mut result = Vec::new();
 
+        if let Some(overlay) = &mut self.overlay {
+            for file in overlay.list()? {
+                if overlay.contains(&file.name) {
+                    seen.insert(file.name.clone(), usize::MAX);
+                    result.push(file);
+                }
+            }
+        }
+
         // Process archives in priority order (highest first)
         for (idx, entry) in self.archives.iter_mut().enumerate() {
             match entry.archive.list() {
@@ -312,12 +453,103 @@ impl PatchChain {
             }
         }
 
+        result.retain(|file| !file.is_delete_marker());
+
         // Sort by name for consistent output
         result.sort_by(|a, b| a.name.cmp(&b.name));
 
         Ok(result)
     }
 
+    /// List all files in the chain with the archive each one comes from
+    ///
+    /// Like [`list`](Self::list), every file appears once with the entry of
+    /// the archive that wins, but each entry also records that archive's path
+    /// and priority and the lower-priority archives whose copies it
+    /// overrides. For a PTCH patch file the winner is the patch; reading the
+    /// file applies it on top of the overridden copies.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use wow_mpq::{GameVersion, PatchChain};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut chain = PatchChain::from_game_dir("/games/WoW-3.3.5a", GameVersion::Wotlk)?;
+    /// for entry in chain.list_merged()? {
+    ///     if entry.file.name.starts_with("DBFilesClient\\") && entry.override_depth() > 0 {
+    ///         println!("{} from {}", entry.file.name, entry.source.display());
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn list_merged(&mut self) -> Result<Vec<MergedEntry>> {
+        // Lookup key -> index into `merged`, or None for a deleted file
+        let mut index: HashMap<String, Option<usize>> = HashMap::new();
+        let mut merged: Vec<MergedEntry> = Vec::new();
+
+        if let Some(overlay) = &mut self.overlay {
+            let source = overlay.path().to_path_buf();
+            for file in overlay.list()? {
+                if overlay.contains(&file.name) {
+                    index.insert(crate::path::path_key(&file.name), Some(merged.len()));
+                    merged.push(MergedEntry {
+                        file,
+                        source: source.clone(),
+                        priority: None,
+                        overridden: Vec::new(),
+                    });
+                }
+            }
+        }
+
+        // Process archives in priority order (highest first)
+        for entry in &mut self.archives {
+            let files = match entry.archive.list() {
+                Ok(files) => files,
+                Err(_) => match entry.archive.list_all() {
+                    Ok(files) => files,
+                    Err(_) => continue,
+                },
+            };
+
+            // An archive can hold several entries for a name, one per locale
+            let mut in_archive = HashSet::new();
+            for file in files {
+                let key = crate::path::path_key(&file.name);
+                if !in_archive.insert(key.clone()) {
+                    continue;
+                }
+                match index.entry(key) {
+                    std::collections::hash_map::Entry::Occupied(slot) => {
+                        if let Some(idx) = *slot.get()
+                            && !file.is_delete_marker()
+                        {
+                            merged[idx].overridden.push(entry.path.clone());
+                        }
+                    }
+                    std::collections::hash_map::Entry::Vacant(slot) => {
+                        if file.is_delete_marker() {
+                            slot.insert(None);
+                        } else {
+                            slot.insert(Some(merged.len()));
+                            merged.push(MergedEntry {
+                                file,
+                                source: entry.path.clone(),
+                                priority: Some(entry.priority),
+                                overridden: Vec::new(),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        merged.sort_by(|a, b| a.file.name.cmp(&b.file.name));
+        Ok(merged)
+    }
+
     /// Get information about all archives in the chain
     pub fn get_chain_info(&mut self) -> Vec<ChainInfo> {
         self.archives
@@ -337,6 +569,13 @@ impl PatchChain {
     /// Rebuild the internal file map
     fn rebuild_file_map(&mut self) -> Result<()> {
         self.file_map.clear();
+        self.deleted.clear();
+
+        for entry in &mut self.archives {
+            if entry.archive.path_policy() != self.path_policy {
+                entry.archive.set_path_policy(self.path_policy)?;
+            }
+        }
 
         // Process archives in priority order (highest first)
         for (idx, entry) in self.archives.iter_mut().enumerate() {
@@ -355,8 +594,15 @@ impl PatchChain {
             // Add files to map (only if not already present from higher priority)
             // MPQ hashing is case-insensitive, so normalize keys to uppercase
             for file in files {
-                let normalized_key = crate::path::normalize_mpq_path(&file.name).to_uppercase();
-                self.file_map.entry(normalized_key).or_insert(idx);
+                let normalized_key = crate::path::path_key(&file.name);
+                if let std::collections::hash_map::Entry::Vacant(slot) =
+                    self.file_map.entry(normalized_key)
+                {
+                    if file.is_delete_marker() {
+                        self.deleted.insert(slot.key().clone());
+                    }
+                    slot.insert(idx);
+                }
             }
         }
 
@@ -442,6 +688,10 @@ impl PatchChain {
         let mut chain = Self {
             archives: loaded_archives,
             file_map: HashMap::new(),
+            deleted: HashSet::new(),
+            overlay: None,
+            recorded_names: None,
+            path_policy: PathPolicy::LENIENT,
         };
 
         // Build the file map
@@ -543,6 +793,28 @@ pub struct ChainInfo {
     pub format_version: crate::FormatVersion,
 }
 
+/// A file of the chain's merged view, see [`PatchChain::list_merged`]
+#[derive(Debug)]
+pub struct MergedEntry {
+    /// Entry from the archive that provides the file
+    pub file: FileEntry,
+    /// Path of the archive that provides the file, or of the overlay
+    pub source: PathBuf,
+    /// Priority of the providing archive; `None` for the overlay
+    pub priority: Option<i32>,
+    /// Archives whose copy of the file is overridden, highest priority first
+    pub overridden: Vec<PathBuf>,
+}
+
+impl MergedEntry {
+    /// Number of archives whose copy of the file is overridden
+    ///
+    /// Zero means no other archive of the chain has the file.
+    pub fn override_depth(&self) -> usize {
+        self.overridden.len()
+    }
+}
+
 #[cfg(test)]
 mod tests;
 
@@ -627,6 +899,55 @@ mod integration_tests {
         assert!(names.contains(&"file3.txt"));
     }
 
+    #[test]
+    fn test_list_merged() {
+        let temp = TempDir::new().unwrap();
+
+        let base_files: Vec<(&str, &[u8])> = vec![("file1.txt", b"data1"), ("file2.txt", b"data2")];
+        let patch_files: Vec<(&str, &[u8])> =
+            vec![("FILE2.TXT", b"patch2"), ("file3.txt", b"data3")];
+        let patch2_files: Vec<(&str, &[u8])> = vec![("file2.txt", b"patch2-2")];
+
+        let base_path = create_test_archive(temp.path(), "base.mpq", &base_files);
+        let patch_path = create_test_archive(temp.path(), "patch.mpq", &patch_files);
+        let patch2_path = create_test_archive(temp.path(), "patch-2.mpq", &patch2_files);
+
+        let mut chain = PatchChain::new();
+        chain.add_archive(&base_path, 0).unwrap();
+        chain.add_archive(&patch2_path, 200).unwrap();
+        chain.add_archive(&patch_path, 100).unwrap();
+
+        let merged = chain.list_merged().unwrap();
+        let find = |name: &str| merged.iter().find(|e| e.file.name == name).unwrap();
+        assert_eq!(
+            merged
+                .iter()
+                .filter(|e| e.file.name != "(listfile)")
+                .count(),
+            3
+        );
+
+        let file1 = find("file1.txt");
+        assert_eq!(file1.source, base_path);
+        assert_eq!(file1.priority, Some(0));
+        assert_eq!(file1.override_depth(), 0);
+
+        let file2 = find("file2.txt");
+        assert_eq!(file2.source, patch2_path);
+        assert_eq!(file2.priority, Some(200));
+        assert_eq!(
+            file2.overridden,
+            vec![patch_path.clone(), base_path.clone()]
+        );
+        assert_eq!(file2.file.size, b"patch2-2".len() as u64);
+
+        let file3 = find("file3.txt");
+        assert_eq!(file3.source, patch_path);
+        assert_eq!(file3.override_depth(), 0);
+
+        assert_eq!(find("(listfile)").override_depth(), 2);
+    }
+
     #[test]
     fn test_find_file_archive() {
         let temp = TempDir::new().unwrap();
@@ -0,0 +1,618 @@
+//! Archive discovery in a game installation
+//!
+//! Each client version loads a fixed set of archives from its `Data`
+//! directory and the locale directory below it, in a fixed order: base
+//! archives first, then locale archives, patches, locale patches and, from
+//! Cataclysm on, incremental `wow-update-*` archives. Later archives override
+//! earlier ones. Archives outside these rules, such as the installer's
+//! `base-enUS.MPQ` or a `patch-custom.MPQ` with a multi-character suffix, are
+//! not loaded by the client and are reported as ignored.
+
+use super::PatchChain;
+use crate::{Error, FormatVersion, Result};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Client versions with distinct archive loading rules
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameVersion {
+    /// 1.x, no locale directory
+    Vanilla,
+    /// 2.x
+    Tbc,
+    /// 3.x
+    Wotlk,
+    /// 4.x, with incremental `wow-update-*` archives
+    Cataclysm,
+}
+
+impl GameVersion {
+    /// All versions, oldest first
+    pub const ALL: [GameVersion; 4] = [
+        GameVersion::Vanilla,
+        GameVersion::Tbc,
+        GameVersion::Wotlk,
+        GameVersion::Cataclysm,
+    ];
+
+    /// Guess the version from the base archives of an installation
+    ///
+    /// `game_dir` is the installation or its `Data` directory. Returns
+    /// `None` if none of the known base archives are present.
+    pub fn detect<P: AsRef<Path>>(game_dir: P) -> Option<Self> {
+        let data_dir = resolve_data_dir(game_dir.as_ref()).ok()?;
+        let stems: Vec<String> = fs::read_dir(data_dir)
+            .ok()?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| mpq_stem(&entry.path()))
+            .collect();
+        let has = |name: &str| stems.iter().any(|stem| stem == name);
+
+        if has("art") || has("world") || stems.iter().any(|s| s.starts_with("wow-update-")) {
+            Some(GameVersion::Cataclysm)
+        } else if has("lichking") {
+            Some(GameVersion::Wotlk)
+        } else if has("expansion") || has("common") {
+            Some(GameVersion::Tbc)
+        } else if has("dbc") || has("model") || has("texture") {
+            Some(GameVersion::Vanilla)
+        } else {
+            None
+        }
+    }
+
+    /// Base archives of the data directory, in loading order
+    pub fn base_archives(self) -> &'static [&'static str] {
+        match self {
+            GameVersion::Vanilla => &[
+                "base",
+                "dbc",
+                "fonts",
+                "interface",
+                "misc",
+                "model",
+                "sound",
+                "speech",
+                "terrain",
+                "texture",
+                "wmo",
+            ],
+            GameVersion::Tbc => &["common", "expansion"],
+            GameVersion::Wotlk => &["common", "common-2", "expansion", "lichking"],
+            GameVersion::Cataclysm => &[
+                "art",
+                "sound",
+                "world",
+                "world2",
+                "expansion1",
+                "expansion2",
+                "expansion3",
+            ],
+        }
+    }
+
+    /// Base archives of the locale directory, in loading order
+    ///
+    /// `{locale}` stands for the locale name, e.g. `locale-{locale}` is
+    /// `locale-enUS.MPQ` for the `enUS` locale.
+    pub fn locale_archives(self) -> &'static [&'static str] {
+        match self {
+            GameVersion::Vanilla => &[],
+            GameVersion::Tbc => &[
+                "locale-{locale}",
+                "speech-{locale}",
+                "expansion-locale-{locale}",
+                "expansion-speech-{locale}",
+            ],
+            GameVersion::Wotlk => &[
+                "locale-{locale}",
+                "speech-{locale}",
+                "expansion-locale-{locale}",
+                "expansion-speech-{locale}",
+                "lichking-locale-{locale}",
+                "lichking-speech-{locale}",
+            ],
+            GameVersion::Cataclysm => &[
+                "locale-{locale}",
+                "speech-{locale}",
+                "expansion1-locale-{locale}",
+                "expansion1-speech-{locale}",
+                "expansion2-locale-{locale}",
+                "expansion2-speech-{locale}",
+                "expansion3-locale-{locale}",
+                "expansion3-speech-{locale}",
+            ],
+        }
+    }
+
+    /// Whether the client has a locale directory with archives
+    pub fn has_locale_dir(self) -> bool {
+        self != GameVersion::Vanilla
+    }
+
+    /// Whether the client loads `wow-update-*` archives
+    pub fn has_updates(self) -> bool {
+        self == GameVersion::Cataclysm
+    }
+
+    /// Newest MPQ format version the client reads
+    pub fn max_format_version(self) -> FormatVersion {
+        match self {
+            GameVersion::Vanilla => FormatVersion::V1,
+            GameVersion::Tbc | GameVersion::Wotlk => FormatVersion::V2,
+            GameVersion::Cataclysm => FormatVersion::V4,
+        }
+    }
+
+    /// Classify an archive by lower-cased file stem
+    ///
+    /// `locale` is the lower-cased locale for archives in the locale
+    /// directory. Returns `None` for archives the client does not load.
+    fn classify(self, stem: &str, locale: Option<&str>) -> Option<(ArchiveKind, u32)> {
+        if let Some(rest) = stem.strip_prefix("wow-update-") {
+            if !self.has_updates() {
+                return None;
+            }
+            let (prefix, build) = rest.rsplit_once('-')?;
+            let build = build.parse().ok()?;
+            return match locale {
+                Some(locale) if prefix == locale => Some((ArchiveKind::LocaleUpdate, build)),
+                None if !prefix.contains('-') => Some((ArchiveKind::Update, build)),
+                _ => None,
+            };
+        }
+
+        match locale {
+            None => {
+                if let Some(rest) = stem.strip_prefix("patch") {
+                    return patch_rank(rest).map(|rank| (ArchiveKind::Patch, rank));
+                }
+                let rank = self.base_archives().iter().position(|base| *base == stem)?;
+                Some((ArchiveKind::Base, rank as u32))
+            }
+            Some(locale) => {
+                if let Some(rest) = stem
+                    .strip_prefix("patch-")
+                    .and_then(|rest| rest.strip_prefix(locale))
+                {
+                    return patch_rank(rest).map(|rank| (ArchiveKind::LocalePatch, rank));
+                }
+                let rank = self
+                    .locale_archives()
+                    .iter()
+                    .position(|template| template.replace("{locale}", locale) == stem)?;
+                Some((ArchiveKind::LocaleBase, rank as u32))
+            }
+        }
+    }
+}
+
+impl fmt::Display for GameVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            GameVersion::Vanilla => "vanilla",
+            GameVersion::Tbc => "tbc",
+            GameVersion::Wotlk => "wotlk",
+            GameVersion::Cataclysm => "cata",
+        })
+    }
+}
+
+impl FromStr for GameVersion {
+    type Err = Error;
+
+    /// Parse a version name (`vanilla`, `tbc`, `wotlk`, `cata`) or a client
+    /// version number such as `3.3.5a`
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.to_ascii_lowercase();
+        match s.as_str() {
+            "vanilla" | "classic" => return Ok(GameVersion::Vanilla),
+            "tbc" | "bc" => return Ok(GameVersion::Tbc),
+            "wotlk" | "wrath" => return Ok(GameVersion::Wotlk),
+            "cata" | "cataclysm" => return Ok(GameVersion::Cataclysm),
+            _ => {}
+        }
+        match s.split('.').next() {
+            Some("1") => Ok(GameVersion::Vanilla),
+            Some("2") => Ok(GameVersion::Tbc),
+            Some("3") => Ok(GameVersion::Wotlk),
+            Some("4") => Ok(GameVersion::Cataclysm),
+            _ => Err(Error::unsupported_feature(format!(
+                "Unknown game version '{s}', expected vanilla, tbc, wotlk or cata"
+            ))),
+        }
+    }
+}
+
+/// Loading tier of an archive; archives of later tiers override earlier ones
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ArchiveKind {
+    /// Base archive in the data directory
+    Base,
+    /// Base archive in the locale directory
+    LocaleBase,
+    /// `patch.MPQ`, `patch-2.MPQ`, ... in the data directory
+    Patch,
+    /// `patch-<locale>.MPQ`, `patch-<locale>-2.MPQ`, ... in the locale directory
+    LocalePatch,
+    /// `wow-update-base-<build>.MPQ` in the data directory
+    Update,
+    /// `wow-update-<locale>-<build>.MPQ` in the locale directory
+    LocaleUpdate,
+}
+
+impl ArchiveKind {
+    /// Priority of the first archive of the tier
+    fn base_priority(self) -> i32 {
+        match self {
+            ArchiveKind::Base => 0,
+            ArchiveKind::LocaleBase => 100,
+            ArchiveKind::Patch => 1000,
+            ArchiveKind::LocalePatch => 2000,
+            ArchiveKind::Update => 3000,
+            ArchiveKind::LocaleUpdate => 4000,
+        }
+    }
+}
+
+/// An archive the client loads
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayoutArchive {
+    /// Path of the archive
+    pub path: PathBuf,
+    /// Loading tier
+    pub kind: ArchiveKind,
+    /// Chain priority; higher overrides lower
+    pub priority: i32,
+}
+
+/// Archives of a game installation in the order the client loads them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveLayout {
+    /// Client version whose rules were applied
+    pub version: GameVersion,
+    /// The `Data` directory
+    pub data_dir: PathBuf,
+    /// Locale whose directory was searched
+    pub locale: Option<String>,
+    /// Loaded archives, lowest priority first
+    pub archives: Vec<LayoutArchive>,
+    /// Archives found but not loaded by the client
+    pub ignored: Vec<PathBuf>,
+}
+
+impl ArchiveLayout {
+    /// Discover the archives of a game installation
+    ///
+    /// `game_dir` is the installation or its `Data` directory. Without a
+    /// `locale`, the locale directory is detected; when there are several,
+    /// the first in alphabetical order is used.
+    pub fn discover<P: AsRef<Path>>(
+        game_dir: P,
+        version: GameVersion,
+        locale: Option<&str>,
+    ) -> Result<Self> {
+        let data_dir = resolve_data_dir(game_dir.as_ref())?;
+        let locale = match locale {
+            Some(locale) => Some(locale.to_string()),
+            None if version.has_locale_dir() => detect_locale(&data_dir)?,
+            None => None,
+        };
+
+        let mut found = Vec::new();
+        let mut ignored = Vec::new();
+        collect(&data_dir, version, None, &mut found, &mut ignored)?;
+        if let Some(locale) = &locale {
+            let locale_dir = data_dir.join(locale);
+            if locale_dir.is_dir() {
+                let lower = locale.to_ascii_lowercase();
+                collect(&locale_dir, version, Some(&lower), &mut found, &mut ignored)?;
+            } else {
+                log::warn!("Locale directory not found: {}", locale_dir.display());
+            }
+        }
+        found.sort_by(|a, b| (a.1, a.2, a.0.file_name()).cmp(&(b.1, b.2, b.0.file_name())));
+        ignored.sort();
+
+        let mut archives: Vec<LayoutArchive> = Vec::with_capacity(found.len());
+        for (path, kind, _) in found {
+            let priority = match archives.last() {
+                Some(last) if last.kind == kind => last.priority + 1,
+                _ => kind.base_priority(),
+            };
+            archives.push(LayoutArchive {
+                path,
+                kind,
+                priority,
+            });
+        }
+
+        Ok(Self {
+            version,
+            data_dir,
+            locale,
+            archives,
+            ignored,
+        })
+    }
+
+    /// Archive paths with their chain priorities
+    pub fn priorities(&self) -> Vec<(PathBuf, i32)> {
+        self.archives
+            .iter()
+            .map(|archive| (archive.path.clone(), archive.priority))
+            .collect()
+    }
+}
+
+impl PatchChain {
+    /// Mount the archives of a game installation in client loading order
+    ///
+    /// `game_dir` is the installation or its `Data` directory; the locale is
+    /// detected. Use [`ArchiveLayout::discover`] and
+    /// [`PatchChain::from_layout`] to choose the locale or inspect the order.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use wow_mpq::{GameVersion, PatchChain};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut chain = PatchChain::from_game_dir("/games/WoW-3.3.5a", GameVersion::Wotlk)?;
+    /// let data = chain.read_file("DBFilesClient\\Map.dbc")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_game_dir<P: AsRef<Path>>(game_dir: P, version: GameVersion) -> Result<Self> {
+        Self::from_layout(&ArchiveLayout::discover(game_dir, version, None)?)
+    }
+
+    /// Mount the archives of a discovered layout
+    pub fn from_layout(layout: &ArchiveLayout) -> Result<Self> {
+        if layout.archives.is_empty() {
+            return Err(Error::FileNotFound(format!(
+                "No {} archives in {}",
+                layout.version,
+                layout.data_dir.display()
+            )));
+        }
+        Self::from_archives_parallel(layout.priorities())
+    }
+}
+
+/// Ordering key for the suffix after `patch`: `patch` < `patch-2` .. `patch-9`
+/// < `patch-a` .. `patch-z`; other suffixes are not loaded
+fn patch_rank(suffix: &str) -> Option<u32> {
+    let Some(suffix) = suffix.strip_prefix('-') else {
+        return suffix.is_empty().then_some(1);
+    };
+    let mut chars = suffix.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c @ '2'..='9'), None) => Some(c as u32 - '0' as u32),
+        (Some(c @ 'a'..='z'), None) => Some(100 + c as u32 - 'a' as u32),
+        _ => None,
+    }
+}
+
+/// Lower-cased stem of an `.mpq` file
+fn mpq_stem(path: &Path) -> Option<String> {
+    let is_mpq = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("mpq"));
+    if !is_mpq || !path.is_file() {
+        return None;
+    }
+    Some(path.file_stem()?.to_str()?.to_ascii_lowercase())
+}
+
+fn collect(
+    dir: &Path,
+    version: GameVersion,
+    locale: Option<&str>,
+    found: &mut Vec<(PathBuf, ArchiveKind, u32)>,
+    ignored: &mut Vec<PathBuf>,
+) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let Some(stem) = mpq_stem(&path) else {
+            continue;
+        };
+        match version.classify(&stem, locale) {
+            Some((kind, rank)) => found.push((path, kind, rank)),
+            None => ignored.push(path),
+        }
+    }
+    Ok(())
+}
+
+/// Resolve `<WoW dir>` or `<WoW dir>/Data` to the data directory
+fn resolve_data_dir(dir: &Path) -> Result<PathBuf> {
+    for candidate in ["Data", "data"] {
+        let nested = dir.join(candidate);
+        if nested.is_dir() {
+            return Ok(nested);
+        }
+    }
+    if dir.is_dir() {
+        return Ok(dir.to_path_buf());
+    }
+    Err(Error::FileNotFound(format!(
+        "Data directory not found: {}",
+        dir.display()
+    )))
+}
+
+/// Locale directories look like `enUS`, `deDE`, ...
+fn is_locale_name(name: &str) -> bool {
+    let bytes = name.as_bytes();
+    bytes.len() == 4
+        && bytes[..2].iter().all(u8::is_ascii_lowercase)
+        && bytes[2..].iter().all(u8::is_ascii_uppercase)
+}
+
+fn detect_locale(data_dir: &Path) -> Result<Option<String>> {
+    let mut locales: Vec<String> = fs::read_dir(data_dir)?
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().is_dir())
+        .filter_map(|e| e.file_name().to_str().map(str::to_string))
+        .filter(|name| is_locale_name(name))
+        .collect();
+    locales.sort();
+
+    if locales.len() > 1 {
+        log::warn!(
+            "Multiple locales found ({}), using {}",
+            locales.join(", "),
+            locales[0]
+        );
+    }
+    Ok(locales.into_iter().next())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_wotlk() {
+        let v = GameVersion::Wotlk;
+        assert_eq!(v.classify("common", None), Some((ArchiveKind::Base, 0)));
+        assert_eq!(v.classify("lichking", None), Some((ArchiveKind::Base, 3)));
+        assert_eq!(v.classify("patch", None), Some((ArchiveKind::Patch, 1)));
+        assert_eq!(v.classify("patch-3", None), Some((ArchiveKind::Patch, 3)));
+        assert!(v.classify("patch-a", None) > v.classify("patch-9", None));
+        assert_eq!(v.classify("patch-custom", None), None);
+        assert_eq!(v.classify("mymod", None), None);
+        assert_eq!(v.classify("wow-update-base-15211", None), None);
+
+        let enus = Some("enus");
+        assert_eq!(
+            v.classify("locale-enus", enus),
+            Some((ArchiveKind::LocaleBase, 0))
+        );
+        assert!(
+            v.classify("lichking-locale-enus", enus) > v.classify("expansion-locale-enus", enus)
+        );
+        assert_eq!(
+            v.classify("patch-enus", enus),
+            Some((ArchiveKind::LocalePatch, 1))
+        );
+        assert_eq!(
+            v.classify("patch-enus-2", enus),
+            Some((ArchiveKind::LocalePatch, 2))
+        );
+        // Installer archives are not loaded
+        assert_eq!(v.classify("base-enus", enus), None);
+        assert_eq!(v.classify("backup-enus", enus), None);
+    }
+
+    #[test]
+    fn test_classify_cataclysm_updates() {
+        let v = GameVersion::Cataclysm;
+        assert_eq!(
+            v.classify("wow-update-base-15211", None),
+            Some((ArchiveKind::Update, 15211))
+        );
+        assert_eq!(
+            v.classify("wow-update-enus-15211", Some("enus")),
+            Some((ArchiveKind::LocaleUpdate, 15211))
+        );
+        assert_eq!(v.classify("wow-update-dede-15211", Some("enus")), None);
+        assert_eq!(
+            v.classify("expansion3-speech-enus", Some("enus")),
+            Some((ArchiveKind::LocaleBase, 7))
+        );
+        // Vanilla has no locale archives
+        assert_eq!(
+            GameVersion::Vanilla.classify("locale-enus", Some("enus")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_is_locale_name() {
+        assert!(is_locale_name("enUS"));
+        assert!(is_locale_name("deDE"));
+        assert!(!is_locale_name("Data"));
+        assert!(!is_locale_name("enUSX"));
+        // Four bytes without a char boundary after the second
+        assert!(!is_locale_name("aé!"));
+    }
+
+    #[test]
+    fn test_parse_game_version() {
+        assert_eq!("WotLK".parse::<GameVersion>().unwrap(), GameVersion::Wotlk);
+        assert_eq!("3.3.5a".parse::<GameVersion>().unwrap(), GameVersion::Wotlk);
+        assert_eq!(
+            "1.12.1".parse::<GameVersion>().unwrap(),
+            GameVersion::Vanilla
+        );
+        assert_eq!(
+            "cata".parse::<GameVersion>().unwrap(),
+            GameVersion::Cataclysm
+        );
+        assert!("5.4.8".parse::<GameVersion>().is_err());
+        for version in GameVersion::ALL {
+            assert_eq!(version.to_string().parse::<GameVersion>().unwrap(), version);
+        }
+    }
+
+    #[test]
+    fn test_discover_layout() {
+        let dir = tempfile::tempdir().unwrap();
+        let data = dir.path().join("Data");
+        fs::create_dir_all(data.join("enUS")).unwrap();
+        for name in [
+            "common.MPQ",
+            "expansion.MPQ",
+            "lichking.MPQ",
+            "patch.MPQ",
+            "patch-2.MPQ",
+            "patch-custom.MPQ",
+            "enUS/locale-enUS.MPQ",
+            "enUS/base-enUS.MPQ",
+            "enUS/patch-enUS.MPQ",
+            "enUS/patch-enUS-2.MPQ",
+            "readme.txt",
+        ] {
+            fs::write(data.join(name), b"").unwrap();
+        }
+
+        assert_eq!(GameVersion::detect(&data), Some(GameVersion::Wotlk));
+        let layout = ArchiveLayout::discover(dir.path(), GameVersion::Wotlk, None).unwrap();
+        assert_eq!(layout.data_dir, data);
+        assert_eq!(layout.locale.as_deref(), Some("enUS"));
+
+        let order: Vec<(String, i32)> = layout
+            .archives
+            .iter()
+            .map(|a| {
+                let name = a.path.file_name().unwrap().to_str().unwrap();
+                (name.to_string(), a.priority)
+            })
+            .collect();
+        let expected = [
+            ("common.MPQ", 0),
+            ("expansion.MPQ", 1),
+            ("lichking.MPQ", 2),
+            ("locale-enUS.MPQ", 100),
+            ("patch.MPQ", 1000),
+            ("patch-2.MPQ", 1001),
+            ("patch-enUS.MPQ", 2000),
+            ("patch-enUS-2.MPQ", 2001),
+        ];
+        assert_eq!(
+            order,
+            expected.map(|(name, priority)| (name.to_string(), priority))
+        );
+        let ignored: Vec<_> = layout
+            .ignored
+            .iter()
+            .map(|p| p.file_name().unwrap().to_str().unwrap())
+            .collect();
+        assert_eq!(ignored, ["base-enUS.MPQ", "patch-custom.MPQ"]);
+    }
+}
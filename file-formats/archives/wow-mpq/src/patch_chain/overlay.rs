@@ -0,0 +1,186 @@
+//! Copy-on-write overlay for patch chains
+//!
+//! The overlay is a writable archive layered above every archive of the
+//! chain. Reads check it first, writes only ever touch it, and the archives
+//! underneath are never modified. Once the edits are done, the overlay can be
+//! materialized into a compact standalone patch MPQ.
+
+use super::PatchChain;
+use crate::path::path_key;
+use crate::{
+    AddFileOptions, ArchiveBuilder, Error, FileEntry, FormatVersion, ListfileOption,
+    MutableArchive, Result,
+};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// The writable archive on top of a patch chain
+#[derive(Debug)]
+pub(super) struct Overlay {
+    archive: MutableArchive,
+    path: PathBuf,
+    /// Files in the overlay, keyed like the chain's file map
+    files: HashMap<String, String>,
+}
+
+impl Overlay {
+    pub(super) fn contains(&self, filename: &str) -> bool {
+        self.files.contains_key(&path_key(filename))
+    }
+
+    pub(super) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub(super) fn read_file(&mut self, filename: &str) -> Result<Vec<u8>> {
+        self.archive.read_file(filename)
+    }
+
+    pub(super) fn list(&mut self) -> Result<Vec<FileEntry>> {
+        self.archive.list()
+    }
+}
+
+impl PatchChain {
+    /// Designate a writable archive layered above the whole chain
+    ///
+    /// The archive is created when it does not exist yet, using the format
+    /// of the highest-priority archive capped at v2, which every client that
+    /// can read the chain also reads. Files it already contains take
+    /// precedence over the chain from now on. A previous overlay is flushed
+    /// and detached.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use wow_mpq::PatchChain;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut chain = PatchChain::new();
+    /// chain.add_archive("Data/common.MPQ", 0)?;
+    /// chain.add_archive("Data/patch.MPQ", 100)?;
+    /// chain.set_overlay("Data/patch-X.MPQ")?;
+    ///
+    /// let mut spells = chain.read_file("DBFilesClient\\Spell.dbc")?;
+    /// spells[20] = 0;
+    /// chain.write_file("DBFilesClient\\Spell.dbc", &spells)?;
+    /// chain.materialize_overlay("patch-custom.MPQ")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_overlay<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        self.close_overlay()?;
+
+        if !path.exists() {
+            let version = match self.archives.first() {
+                Some(entry) if entry.archive.header().format_version == FormatVersion::V1 => {
+                    FormatVersion::V1
+                }
+                _ => FormatVersion::V2,
+            };
+            ArchiveBuilder::new()
+                .version(version)
+                .listfile_option(ListfileOption::Generate)
+                .build(path)?;
+        }
+
+        let mut archive = MutableArchive::open(path)?;
+        let files = archive
+            .list()?
+            .into_iter()
+            .filter(|entry| !is_internal_file(&entry.name))
+            .map(|entry| (path_key(&entry.name), entry.name))
+            .collect();
+
+        self.overlay = Some(Overlay {
+            archive,
+            path: path.to_path_buf(),
+            files,
+        });
+        Ok(())
+    }
+
+    /// Path of the overlay archive, if one is set
+    pub fn overlay_path(&self) -> Option<&Path> {
+        self.overlay.as_ref().map(Overlay::path)
+    }
+
+    /// Flush pending overlay changes to disk
+    pub fn flush_overlay(&mut self) -> Result<()> {
+        match &mut self.overlay {
+            Some(overlay) => overlay.archive.flush(),
+            None => Ok(()),
+        }
+    }
+
+    /// Flush and detach the overlay, returning the chain to read-only
+    pub fn close_overlay(&mut self) -> Result<()> {
+        self.flush_overlay()?;
+        self.overlay = None;
+        Ok(())
+    }
+
+    /// Write a file to the overlay
+    ///
+    /// The file shadows every version in the chain, including patch (PTCH)
+    /// files. Returns [`Error::ReadOnly`] when no overlay is set.
+    pub fn write_file(&mut self, filename: &str, data: &[u8]) -> Result<()> {
+        self.write_file_with_options(filename, data, AddFileOptions::default())
+    }
+
+    /// Write a file to the overlay with explicit compression and encryption
+    pub fn write_file_with_options(
+        &mut self,
+        filename: &str,
+        data: &[u8],
+        options: AddFileOptions,
+    ) -> Result<()> {
+        let overlay = self.overlay.as_mut().ok_or(Error::ReadOnly)?;
+        let name = crate::path::normalize_mpq_path(filename);
+        overlay
+            .archive
+            .add_file_data(data, &name, options.replace_existing(true))?;
+        overlay.files.insert(path_key(&name), name);
+        Ok(())
+    }
+
+    /// Names of the files written to the overlay, sorted
+    pub fn overlay_files(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .overlay
+            .as_ref()
+            .map(|overlay| overlay.files.values().cloned().collect())
+            .unwrap_or_default();
+        names.sort();
+        names
+    }
+
+    /// Write the overlay's files into a new standalone patch archive
+    ///
+    /// The result holds only the overlay's files, without the space that
+    /// repeated writes orphan in the overlay, and uses the overlay's format.
+    /// Returns the number of files written.
+    pub fn materialize_overlay<P: AsRef<Path>>(&mut self, path: P) -> Result<usize> {
+        self.flush_overlay()?;
+        let overlay = self.overlay.as_mut().ok_or(Error::ReadOnly)?;
+
+        let version = overlay.archive.archive().header().format_version;
+        let mut builder = ArchiveBuilder::new()
+            .version(version)
+            .listfile_option(ListfileOption::Generate);
+
+        let mut names: Vec<String> = overlay.files.values().cloned().collect();
+        names.sort();
+        for name in &names {
+            let data = overlay.archive.read_file(name)?;
+            builder = builder.add_file_data(data, name);
+        }
+        builder.build(path)?;
+        Ok(names.len())
+    }
+}
+
+fn is_internal_file(name: &str) -> bool {
+    matches!(name, "(listfile)" | "(attributes)" | "(signature)")
+}
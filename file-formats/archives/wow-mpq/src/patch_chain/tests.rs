@@ -199,3 +199,100 @@ fn test_custom_patch_priority() {
         "Letter patches with high priority should override"
     );
 }
+
+#[test]
+fn test_overlay_copy_on_write() {
+    let temp = TempDir::new().unwrap();
+    let base = create_test_archive(
+        temp.path(),
+        "common.MPQ",
+        &[
+            ("DBFilesClient\\Spell.dbc", b"base spells"),
+            ("DBFilesClient\\Map.dbc", b"base maps"),
+        ],
+    );
+    let base_bytes = std::fs::read(&base).unwrap();
+
+    let mut chain = PatchChain::new();
+    chain.add_archive(&base, 0).unwrap();
+    assert!(matches!(
+        chain.write_file("DBFilesClient\\Spell.dbc", b"x"),
+        Err(Error::ReadOnly)
+    ));
+
+    let overlay_path = temp.path().join("patch-X.MPQ");
+    chain.set_overlay(&overlay_path).unwrap();
+    chain
+        .write_file("DBFilesClient/Spell.dbc", b"edited spells")
+        .unwrap();
+    chain.write_file("Custom\\New.txt", b"new file").unwrap();
+
+    assert_eq!(
+        chain.read_file("dbfilesclient\\spell.dbc").unwrap(),
+        b"edited spells"
+    );
+    assert_eq!(
+        chain.read_file("DBFilesClient\\Map.dbc").unwrap(),
+        b"base maps"
+    );
+    assert_eq!(
+        chain.find_file_archive("Custom\\New.txt"),
+        Some(overlay_path.as_path())
+    );
+    assert_eq!(chain.list().unwrap().len(), 4);
+    assert_eq!(
+        chain.overlay_files(),
+        ["Custom\\New.txt", "DBFilesClient\\Spell.dbc"]
+    );
+
+    // The archives underneath are never touched
+    assert_eq!(std::fs::read(&base).unwrap(), base_bytes);
+
+    let patch_path = temp.path().join("patch-custom.MPQ");
+    assert_eq!(chain.materialize_overlay(&patch_path).unwrap(), 2);
+    let mut patch = Archive::open(&patch_path).unwrap();
+    assert_eq!(
+        patch.read_file("DBFilesClient\\Spell.dbc").unwrap(),
+        b"edited spells"
+    );
+    assert!(patch.find_file("DBFilesClient\\Map.dbc").unwrap().is_none());
+
+    // Reattaching the overlay picks up what was written before
+    chain.close_overlay().unwrap();
+    assert_eq!(
+        chain.read_file("DBFilesClient\\Spell.dbc").unwrap(),
+        b"base spells"
+    );
+    chain.set_overlay(&overlay_path).unwrap();
+    assert_eq!(
+        chain.read_file("DBFilesClient\\Spell.dbc").unwrap(),
+        b"edited spells"
+    );
+}
+
+#[test]
+fn test_unlisted_files_are_found_and_recorded() {
+    let temp = TempDir::new().unwrap();
+    let base = create_test_archive(temp.path(), "common.MPQ", &[("Listed.txt", b"listed")]);
+    let unlisted = temp.path().join("patch.MPQ");
+    ArchiveBuilder::new()
+        .listfile_option(ListfileOption::None)
+        .add_file_data(b"hidden".to_vec(), "Interface\\Hidden.lua")
+        .build(&unlisted)
+        .unwrap();
+
+    let mut chain = PatchChain::new();
+    chain.add_archive(&base, 0).unwrap();
+    chain.add_archive(&unlisted, 100).unwrap();
+    chain.set_record_names(true);
+
+    assert_eq!(chain.read_file("Listed.txt").unwrap(), b"listed");
+    assert_eq!(chain.read_file("Interface/Hidden.lua").unwrap(), b"hidden");
+    assert!(chain.read_file("Interface\\Missing.lua").is_err());
+    assert_eq!(
+        chain.find_file_archive("interface\\hidden.lua"),
+        Some(unlisted.as_path())
+    );
+    assert_eq!(chain.take_recorded_names(), ["Interface\\Hidden.lua"]);
+    assert!(chain.take_recorded_names().is_empty());
+}
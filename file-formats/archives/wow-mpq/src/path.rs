@@ -8,7 +8,8 @@
 //!
 //! When working with MPQ archives:
 //! - **Adding files**: Paths are automatically normalized to use backslashes
-//! - **Reading files**: Both forward slashes and backslashes are accepted
+//! - **Reading files**: Both forward slashes and backslashes are accepted,
+//!   unless a stricter [`PathPolicy`] is set
 //! - **Listing files**: Paths are displayed using the system's native separator
 //! - **Extracting files**: Output paths use the system's native separator
 //!
@@ -30,6 +31,30 @@
 //! let data2 = archive.read_file("dir\\subdir\\file.txt").unwrap();
 //! assert_eq!(data1, data2);
 //! ```
+//!
+//! # Resolving References
+//!
+//! Formats refer to other files in whatever convention their tools used:
+//! forward slashes, mixed case, doubled separators or extensions from older
+//! clients (`.mdx` models are `.m2` files since Wrath of the Lich King).
+//! [`canonical_path`] and [`normalize_asset_path`] turn such references into
+//! the form stored in archives, and [`path_key`] gives the case-insensitive key
+//! resolvers should match on.
+//!
+//! ```
+//! use wow_mpq::path::{normalize_asset_path, path_key};
+//!
+//! assert_eq!(
+//!     normalize_asset_path("World//Generic/Barrel.MDX"),
+//!     "World\\Generic\\Barrel.m2"
+//! );
+//! assert_eq!(
+//!     path_key("world/generic/barrel.m2"),
+//!     path_key("WORLD\\Generic\\Barrel.M2")
+//! );
+//! ```
+
+use std::borrow::Cow;
 
 /// Normalize a file path to MPQ archive format
 ///
@@ -91,6 +116,158 @@ pub fn mpq_path_to_system(path: &str) -> String {
     }
 }
 
+/// Extensions that name a different file in current clients, as
+/// `(old, new)` pairs without the dot
+///
+/// Models were `.mdx` (and `.mdl` in early builds) before they became `.m2`,
+/// and older formats still reference them by the old extension.
+pub const EXTENSION_REMAPS: &[(&str, &str)] = &[("mdx", "m2"), ("mdl", "m2")];
+
+/// Canonical archive form of a path
+///
+/// Separators become backslashes, repeated separators are collapsed, and
+/// `.` components and leading or trailing separators are dropped. Surrounding
+/// whitespace is trimmed; case is kept.
+///
+/// # Examples
+///
+/// ```
+/// use wow_mpq::path::canonical_path;
+///
+/// assert_eq!(canonical_path("./World//Maps/Azeroth/"), "World\\Maps\\Azeroth");
+/// assert_eq!(canonical_path(" \\Interface\\Icons "), "Interface\\Icons");
+/// ```
+pub fn canonical_path(path: &str) -> String {
+    path.trim()
+        .split(['\\', '/'])
+        .filter(|component| !component.is_empty() && *component != ".")
+        .collect::<Vec<_>>()
+        .join("\\")
+}
+
+/// Case-insensitive lookup key of a path
+///
+/// The canonical path in ASCII uppercase, the case MPQ name hashes use. Two
+/// references name the same archive file exactly when their keys are equal.
+///
+/// # Examples
+///
+/// ```
+/// use wow_mpq::path::path_key;
+///
+/// assert_eq!(path_key("dbfilesclient/spell.dbc"), "DBFILESCLIENT\\SPELL.DBC");
+/// ```
+pub fn path_key(path: &str) -> String {
+    canonical_path(path).to_ascii_uppercase()
+}
+
+/// Whether two paths name the same archive file
+pub fn paths_equal(a: &str, b: &str) -> bool {
+    path_key(a) == path_key(b)
+}
+
+/// How name lookups treat case and path separators
+///
+/// MPQ name hashes ignore ASCII case and treat `/` like `\`, so by default
+/// any spelling that hashes the same finds a file. Byte-exact tooling can
+/// instead require the requested name to match the spelling the archive's
+/// `(listfile)` records. Names missing from the listfile cannot be checked
+/// and are still found by hash.
+///
+/// # Examples
+///
+/// ```
+/// use wow_mpq::path::PathPolicy;
+///
+/// assert!(PathPolicy::LENIENT.matches("units/orc.mdx", "Units\\Orc.mdx"));
+/// assert!(!PathPolicy::STRICT.matches("units/orc.mdx", "Units\\Orc.mdx"));
+/// assert!(PathPolicy::STRICT.matches("Units\\Orc.mdx", "Units\\Orc.mdx"));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PathPolicy {
+    /// Whether names differing only in ASCII case match
+    pub fold_case: bool,
+    /// Whether `/` in requested names is read as `\`
+    pub convert_separators: bool,
+}
+
+impl PathPolicy {
+    /// Match anything the name hash matches, the default
+    pub const LENIENT: Self = Self {
+        fold_case: true,
+        convert_separators: true,
+    };
+
+    /// Require the exact spelling the listfile records
+    pub const STRICT: Self = Self {
+        fold_case: false,
+        convert_separators: false,
+    };
+
+    /// Whether this policy lets `requested` name the file stored as `stored`
+    pub fn matches(&self, requested: &str, stored: &str) -> bool {
+        let requested = if self.convert_separators {
+            Cow::Owned(normalize_mpq_path(requested))
+        } else {
+            Cow::Borrowed(requested)
+        };
+        if self.fold_case {
+            requested.eq_ignore_ascii_case(stored)
+        } else {
+            requested == stored
+        }
+    }
+
+    /// Whether this policy accepts every name that hashes the same
+    pub fn is_lenient(&self) -> bool {
+        *self == Self::LENIENT
+    }
+}
+
+impl Default for PathPolicy {
+    fn default() -> Self {
+        Self::LENIENT
+    }
+}
+
+/// Replace a legacy extension with the one current clients use
+///
+/// Only extensions listed in [`EXTENSION_REMAPS`] change; matching ignores
+/// case.
+///
+/// # Examples
+///
+/// ```
+/// use wow_mpq::path::remap_extension;
+///
+/// assert_eq!(remap_extension("Creature\\Bear\\Bear.MDX"), "Creature\\Bear\\Bear.m2");
+/// assert_eq!(remap_extension("Creature\\Bear\\Bear.blp"), "Creature\\Bear\\Bear.blp");
+/// ```
+pub fn remap_extension(path: &str) -> Cow<'_, str> {
+    let Some((stem, ext)) = path.rsplit_once('.') else {
+        return Cow::Borrowed(path);
+    };
+    // A dot in a directory name is not an extension
+    if ext.contains(['\\', '/']) {
+        return Cow::Borrowed(path);
+    }
+    match EXTENSION_REMAPS
+        .iter()
+        .find(|(old, _)| ext.eq_ignore_ascii_case(old))
+    {
+        Some((_, new)) => Cow::Owned(format!("{stem}.{new}")),
+        None => Cow::Borrowed(path),
+    }
+}
+
+/// Normalize a file reference taken from game data
+///
+/// Combines [`canonical_path`] and [`remap_extension`]: the result is the
+/// path as stored in current archives, in the reference's own case.
+pub fn normalize_asset_path(path: &str) -> String {
+    remap_extension(&canonical_path(path)).into_owned()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -123,6 +300,58 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_canonical_path() {
+        assert_eq!(canonical_path("a/b\\c"), "a\\b\\c");
+        assert_eq!(canonical_path(".\\a\\\\b\\"), "a\\b");
+        assert_eq!(canonical_path("/"), "");
+        // Parent references are kept; archives have no directories to resolve them against
+        assert_eq!(canonical_path("a/../b"), "a\\..\\b");
+    }
+
+    #[test]
+    fn test_path_key() {
+        assert!(paths_equal(
+            "World/Maps/Azeroth/Azeroth.wdt",
+            "WORLD\\MAPS\\AZEROTH\\azeroth.WDT"
+        ));
+        assert!(!paths_equal("a.blp", "b.blp"));
+        // Only ASCII is folded, like the MPQ name hash
+        assert_eq!(path_key("straße"), "STRAßE");
+    }
+
+    #[test]
+    fn test_remap_extension() {
+        assert_eq!(remap_extension("Bear.mdl"), "Bear.m2");
+        assert_eq!(remap_extension("Bear.m2"), "Bear.m2");
+        assert_eq!(remap_extension("Bear"), "Bear");
+        assert_eq!(remap_extension("dir.mdx\\Bear"), "dir.mdx\\Bear");
+        assert_eq!(
+            normalize_asset_path("world/generic//barrel.MDX"),
+            "world\\generic\\barrel.m2"
+        );
+    }
+
+    #[test]
+    fn test_path_policy() {
+        let case_only = PathPolicy {
+            fold_case: true,
+            convert_separators: false,
+        };
+        assert!(case_only.matches("dir\\FILE.txt", "Dir\\file.txt"));
+        assert!(!case_only.matches("dir/file.txt", "dir\\file.txt"));
+
+        let separators_only = PathPolicy {
+            fold_case: false,
+            convert_separators: true,
+        };
+        assert!(separators_only.matches("Dir/file.txt", "Dir\\file.txt"));
+        assert!(!separators_only.matches("dir/file.txt", "Dir\\file.txt"));
+
+        assert!(PathPolicy::default().is_lenient());
+        assert!(!PathPolicy::STRICT.is_lenient());
+    }
+
     #[test]
     fn test_mpq_path_to_system() {
         let mpq_path = "dir\\subdir\\file.txt";
@@ -0,0 +1,146 @@
+//! Progress reporting for long-running archive operations
+//!
+//! [`ArchiveBuilder::build`](crate::ArchiveBuilder::build),
+//! [`MutableArchive::compact`](crate::MutableArchive::compact) and
+//! [`rebuild_archive`](crate::rebuild_archive) can take a [`ProgressSink`]
+//! that is told about each file as it is read or written, along with the
+//! running byte count. Closures taking a [`Progress`] implement the trait.
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//! use wow_mpq::{ArchiveBuilder, Progress};
+//!
+//! ArchiveBuilder::new()
+//!     .add_file("readme.txt", "README.txt")
+//!     .progress(Arc::new(|p: &Progress<'_>| {
+//!         println!("[{}/{}] {} ({} bytes)", p.files_done, p.files_total, p.current_file, p.bytes);
+//!     }))
+//!     .build("out.mpq")?;
+//! # Ok::<(), wow_mpq::Error>(())
+//! ```
+
+use std::fmt;
+use std::sync::Arc;
+
+/// What an operation is doing when it reports progress
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgressStage {
+    /// Reading files from a source archive
+    Read,
+    /// Writing files into the new archive
+    Write,
+}
+
+/// A snapshot of an operation's progress
+#[derive(Debug, Clone, Copy)]
+pub struct Progress<'a> {
+    /// What the operation is doing
+    pub stage: ProgressStage,
+    /// Files handled so far in this stage, including the current one
+    pub files_done: usize,
+    /// Files this stage will handle
+    pub files_total: usize,
+    /// Bytes read ([`ProgressStage::Read`]) or written
+    /// ([`ProgressStage::Write`]) so far in this stage
+    pub bytes: u64,
+    /// Name of the file just handled
+    pub current_file: &'a str,
+}
+
+/// Receiver of progress updates
+///
+/// Updates may arrive from worker threads and should return quickly.
+pub trait ProgressSink: Send + Sync {
+    /// Called after each file of a stage
+    fn progress(&self, progress: &Progress<'_>);
+}
+
+impl<F> ProgressSink for F
+where
+    F: Fn(&Progress<'_>) + Send + Sync,
+{
+    fn progress(&self, progress: &Progress<'_>) {
+        self(progress)
+    }
+}
+
+/// Optional shared sink that can sit in `Debug` types
+#[derive(Clone, Default)]
+pub(crate) struct SinkHandle(pub(crate) Option<Arc<dyn ProgressSink>>);
+
+impl fmt::Debug for SinkHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(if self.0.is_some() { "Some(..)" } else { "None" })
+    }
+}
+
+/// Counts files and bytes of one stage and forwards them to a sink
+pub(crate) struct ProgressTracker<'a> {
+    sink: Option<&'a dyn ProgressSink>,
+    stage: ProgressStage,
+    files_done: usize,
+    files_total: usize,
+    bytes: u64,
+}
+
+impl<'a> ProgressTracker<'a> {
+    pub(crate) fn new(
+        sink: Option<&'a dyn ProgressSink>,
+        stage: ProgressStage,
+        files_total: usize,
+    ) -> Self {
+        Self {
+            sink,
+            stage,
+            files_done: 0,
+            files_total,
+            bytes: 0,
+        }
+    }
+
+    /// Record a handled file and the bytes it accounted for
+    pub(crate) fn file_done(&mut self, name: &str, bytes: u64) {
+        self.files_done += 1;
+        self.bytes += bytes;
+        if let Some(sink) = self.sink {
+            sink.progress(&Progress {
+                stage: self.stage,
+                files_done: self.files_done,
+                files_total: self.files_total,
+                bytes: self.bytes,
+                current_file: name,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_tracker_accumulates() {
+        let seen = Mutex::new(Vec::new());
+        let sink = |p: &Progress<'_>| {
+            seen.lock().unwrap().push((
+                p.files_done,
+                p.files_total,
+                p.bytes,
+                p.current_file.to_string(),
+            ));
+        };
+
+        let mut tracker = ProgressTracker::new(Some(&sink), ProgressStage::Write, 2);
+        tracker.file_done("a.txt", 10);
+        tracker.file_done("b.txt", 5);
+
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![
+                (1, 2, 10, "a.txt".to_string()),
+                (2, 2, 15, "b.txt".to_string())
+            ]
+        );
+    }
+}
@@ -0,0 +1,262 @@
+//! Cache of decrypted and decompressed file data
+//!
+//! Viewers read the same files over and over: minimap tiles while panning,
+//! client databases on every lookup. [`Archive::enable_read_cache`] keeps the
+//! decoded sectors of such reads, so reading a file again skips decryption
+//! and decompression. Files stored as a single unit, and uncompressed files,
+//! are kept whole.
+//!
+//! The cache is bounded by the size of the data it holds and is off by
+//! default. Archives opened for modification never use it.
+//!
+//! ```no_run
+//! use wow_mpq::{Archive, ReadCacheConfig};
+//!
+//! let mut archive = Archive::open("Data/common.MPQ")?;
+//! archive.enable_read_cache(ReadCacheConfig::new(32 * 1024 * 1024));
+//!
+//! for _ in 0..2 {
+//!     archive.read_file("DBFilesClient\\Map.dbc")?;
+//! }
+//! let stats = archive.read_cache_stats().unwrap();
+//! assert!(stats.hits > 0);
+//! # Ok::<(), wow_mpq::Error>(())
+//! ```
+//!
+//! [`Archive::enable_read_cache`]: crate::Archive::enable_read_cache
+
+use lru::LruCache;
+
+use crate::archive::FileInfo;
+
+/// Which entry makes room when the cache is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CacheEviction {
+    /// Evict the entry that was read least recently
+    #[default]
+    LeastRecentlyUsed,
+    /// Evict the entry that was stored first, however often it is read
+    ///
+    /// Suits a sliding window over many files, such as panning across
+    /// minimap tiles, where recent hits say little about the next read.
+    FirstInFirstOut,
+}
+
+/// Size and eviction policy of a read cache
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadCacheConfig {
+    /// Maximum number of decoded bytes held
+    pub capacity: usize,
+    /// Which entry makes room when the cache is full
+    pub eviction: CacheEviction,
+}
+
+impl ReadCacheConfig {
+    /// Cache up to `capacity` bytes of decoded data
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            eviction: CacheEviction::default(),
+        }
+    }
+
+    /// Set which entry makes room when the cache is full
+    pub fn eviction(mut self, eviction: CacheEviction) -> Self {
+        self.eviction = eviction;
+        self
+    }
+}
+
+impl Default for ReadCacheConfig {
+    /// 64 MiB with least recently used eviction
+    fn default() -> Self {
+        Self::new(64 * 1024 * 1024)
+    }
+}
+
+/// Counters of a read cache
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReadCacheStats {
+    /// Reads served from the cache
+    pub hits: u64,
+    /// Reads that had to decode the data
+    pub misses: u64,
+    /// Entries dropped to make room
+    pub evictions: u64,
+    /// Entries currently held
+    pub entries: usize,
+    /// Decoded bytes currently held
+    pub bytes: usize,
+}
+
+impl ReadCacheStats {
+    /// Fraction of reads served from the cache (0.0 to 1.0)
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+}
+
+/// Identifies decoded data: the stored file, the key it was decrypted with
+/// and the sector, or [`SectorKey::WHOLE_FILE`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct SectorKey {
+    file_pos: u64,
+    key: u32,
+    sector: u32,
+}
+
+impl SectorKey {
+    const WHOLE_FILE: u32 = u32::MAX;
+
+    /// Key of sector `sector` of a sectored file
+    pub(crate) fn sector(file_info: &FileInfo, key: u32, sector: usize) -> Self {
+        Self {
+            file_pos: file_info.file_pos,
+            key,
+            sector: sector as u32,
+        }
+    }
+
+    /// Key of a file that is decoded as a whole
+    pub(crate) fn whole_file(file_info: &FileInfo, key: u32) -> Self {
+        Self {
+            file_pos: file_info.file_pos,
+            key,
+            sector: Self::WHOLE_FILE,
+        }
+    }
+}
+
+/// Decoded data bounded by total size
+#[derive(Debug)]
+pub(crate) struct ReadCache {
+    config: ReadCacheConfig,
+    entries: LruCache<SectorKey, Vec<u8>>,
+    stats: ReadCacheStats,
+}
+
+impl ReadCache {
+    pub(crate) fn new(config: ReadCacheConfig) -> Self {
+        Self {
+            config,
+            entries: LruCache::unbounded(),
+            stats: ReadCacheStats::default(),
+        }
+    }
+
+    /// Look up decoded data, counting a hit or a miss
+    pub(crate) fn get(&mut self, key: &SectorKey) -> Option<&[u8]> {
+        let data = match self.config.eviction {
+            CacheEviction::LeastRecentlyUsed => self.entries.get(key),
+            CacheEviction::FirstInFirstOut => self.entries.peek(key),
+        };
+        match data {
+            Some(data) => {
+                self.stats.hits += 1;
+                Some(data)
+            }
+            None => {
+                self.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Whether `key` is cached, without counting a lookup
+    pub(crate) fn contains(&self, key: &SectorKey) -> bool {
+        self.entries.contains(key)
+    }
+
+    /// Store decoded data, evicting entries until it fits
+    ///
+    /// Data larger than the whole cache is not stored.
+    pub(crate) fn insert(&mut self, key: SectorKey, data: &[u8]) {
+        if data.len() > self.config.capacity {
+            return;
+        }
+        if let Some(old) = self.entries.pop(&key) {
+            self.stats.bytes -= old.len();
+        }
+        while self.stats.bytes + data.len() > self.config.capacity {
+            let Some((_, evicted)) = self.entries.pop_lru() else {
+                break;
+            };
+            self.stats.bytes -= evicted.len();
+            self.stats.evictions += 1;
+        }
+        self.stats.bytes += data.len();
+        self.entries.put(key, data.to_vec());
+    }
+
+    /// Drop all entries, keeping the counters
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+        self.stats.bytes = 0;
+    }
+
+    pub(crate) fn stats(&self) -> ReadCacheStats {
+        ReadCacheStats {
+            entries: self.entries.len(),
+            ..self.stats
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(sector: u32) -> SectorKey {
+        SectorKey {
+            file_pos: 0x200,
+            key: 0,
+            sector,
+        }
+    }
+
+    #[test]
+    fn test_capacity_evicts_least_recently_used() {
+        let mut cache = ReadCache::new(ReadCacheConfig::new(300));
+        cache.insert(key(0), &[0; 100]);
+        cache.insert(key(1), &[1; 100]);
+        cache.insert(key(2), &[2; 100]);
+        assert!(cache.get(&key(0)).is_some());
+
+        cache.insert(key(3), &[3; 100]);
+        assert!(!cache.contains(&key(1)));
+        assert!(cache.contains(&key(0)));
+
+        let stats = cache.stats();
+        assert_eq!(stats.entries, 3);
+        assert_eq!(stats.bytes, 300);
+        assert_eq!(stats.evictions, 1);
+        assert_eq!((stats.hits, stats.misses), (1, 0));
+    }
+
+    #[test]
+    fn test_fifo_ignores_hits() {
+        let config = ReadCacheConfig::new(200).eviction(CacheEviction::FirstInFirstOut);
+        let mut cache = ReadCache::new(config);
+        cache.insert(key(0), &[0; 100]);
+        cache.insert(key(1), &[1; 100]);
+        assert!(cache.get(&key(0)).is_some());
+
+        cache.insert(key(2), &[2; 100]);
+        assert!(!cache.contains(&key(0)));
+        assert!(cache.contains(&key(1)));
+    }
+
+    #[test]
+    fn test_oversized_data_is_not_cached() {
+        let mut cache = ReadCache::new(ReadCacheConfig::new(50));
+        cache.insert(key(0), &[0; 100]);
+        assert!(cache.get(&key(0)).is_none());
+        assert_eq!(cache.stats().misses, 1);
+        assert_eq!(cache.stats().hit_rate(), 0.0);
+    }
+}
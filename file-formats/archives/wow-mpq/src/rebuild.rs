@@ -5,10 +5,14 @@
 //! upgrades and optimizations.
 
 use crate::{
-    Archive, ArchiveBuilder, Error, FormatVersion, ListfileOption, Result,
-    compression::flags as compression_flags,
+    Archive, ArchiveBuilder, FileEntry, FormatVersion, GameVersion, ListfileOption, Progress,
+    ProgressSink, ProgressStage, Result, compression::flags as compression_flags,
+    progress::ProgressTracker,
 };
+use md5::{Digest, Md5};
+use std::collections::HashSet;
 use std::path::Path;
+use std::sync::Arc;
 
 /// Options for rebuilding an MPQ archive
 #[derive(Debug, Clone)]
@@ -31,12 +35,23 @@ pub struct RebuildOptions {
     /// Whether to verify the rebuilt archive matches the original
     pub verify: bool,
 
+    /// Also check the tables of the rebuilt archive against what this
+    /// client expects (implies `verify`)
+    pub verify_layout: Option<GameVersion>,
+
     /// Override compression method for all files
     pub override_compression: Option<u8>,
 
     /// Override block size
     pub override_block_size: Option<u16>,
 
+    /// Pick the compression method that yields the smallest result for each
+    /// file (ignored when `override_compression` is set)
+    pub best_compression: bool,
+
+    /// Store files with identical contents only once
+    pub deduplicate: bool,
+
     /// Whether to perform a dry run (list only)
     pub list_only: bool,
 }
@@ -50,8 +65,11 @@ impl Default for RebuildOptions {
             skip_encrypted: false,
             skip_signatures: true,
             verify: false,
+            verify_layout: None,
             override_compression: None,
             override_block_size: None,
+            best_compression: false,
+            deduplicate: false,
             list_only: false,
         }
     }
@@ -93,15 +111,54 @@ struct FileMetadata {
 pub type ProgressCallback = Box<dyn Fn(usize, usize, &str) + Send + Sync>;
 
 /// Rebuild an MPQ archive with the specified options
+///
+/// `progress_callback` is called with the number of files read, the total
+/// and the file name as files are read from the source archive.
 pub fn rebuild_archive<P: AsRef<Path>>(
     source_path: P,
     target_path: P,
     options: RebuildOptions,
     progress_callback: Option<ProgressCallback>,
 ) -> Result<RebuildSummary> {
-    let source_path = source_path.as_ref();
-    let target_path = target_path.as_ref();
+    let sink = progress_callback.map(|callback| {
+        Arc::new(move |progress: &Progress<'_>| {
+            if progress.stage == ProgressStage::Read {
+                callback(
+                    progress.files_done,
+                    progress.files_total,
+                    progress.current_file,
+                );
+            }
+        }) as Arc<dyn ProgressSink>
+    });
+    rebuild(source_path.as_ref(), target_path.as_ref(), options, sink)
+}
+
+/// Rebuild an MPQ archive, reporting progress to `sink`
+///
+/// The sink sees every file read from the source archive
+/// ([`ProgressStage::Read`]), then every file written to the target
+/// ([`ProgressStage::Write`]).
+pub fn rebuild_archive_with_progress<P: AsRef<Path>>(
+    source_path: P,
+    target_path: P,
+    options: RebuildOptions,
+    sink: Arc<dyn ProgressSink>,
+) -> Result<RebuildSummary> {
+    rebuild(
+        source_path.as_ref(),
+        target_path.as_ref(),
+        options,
+        Some(sink),
+    )
+}
 
+fn rebuild(
+    source_path: &Path,
+    target_path: &Path,
+    options: RebuildOptions,
+    sink: Option<Arc<dyn ProgressSink>>,
+) -> Result<RebuildSummary> {
     log::info!(
         "Starting rebuild: {} -> {}",
         source_path.display(),
@@ -122,7 +179,7 @@ pub fn rebuild_archive<P: AsRef<Path>>(
     // Phase 2: Extract files and metadata
     log::debug!("Phase 2: Extracting files and metadata");
     let extracted_files =
-        extract_files_with_metadata(&mut source, &metadata, &options, &progress_callback)?;
+        extract_files_with_metadata(&mut source, &metadata, &options, sink.as_deref())?;
 
     let extracted_count = extracted_files.len();
     log::info!("Extracted {extracted_count} files from source archive");
@@ -133,38 +190,45 @@ pub fn rebuild_archive<P: AsRef<Path>>(
             extracted_files: extracted_count,
             skipped_files: metadata.file_count - extracted_count,
             target_format: determine_target_format(&metadata, &options),
+            deduplicated_files: 0,
             verified: false,
+            verification: None,
         });
     }
 
     // Phase 3: Rebuild archive
     log::debug!("Phase 3: Rebuilding archive");
     let target_format = determine_target_format(&metadata, &options);
-    rebuild_with_files(
+    let deduplicated_files = rebuild_with_files(
         target_path,
         &metadata,
         extracted_files,
         target_format,
         &options,
+        sink,
     )?;
 
     log::info!("Successfully rebuilt archive: {}", target_path.display());
 
     // Phase 4: Verification (optional)
-    let verified = if options.verify {
+    let verification = if options.verify || options.verify_layout.is_some() {
         log::debug!("Phase 4: Verifying rebuilt archive");
-        verify_rebuild(source_path, target_path, &options)?;
-        true
+        Some(verify_rebuild(source_path, target_path, &options)?)
     } else {
-        false
+        None
     };
+    let verified = verification
+        .as_ref()
+        .is_some_and(RebuildVerification::passed);
 
     Ok(RebuildSummary {
         source_files: metadata.file_count,
         extracted_files: extracted_count,
         skipped_files: metadata.file_count - extracted_count,
         target_format,
+        deduplicated_files,
         verified,
+        verification,
     })
 }
 
@@ -179,8 +243,62 @@ pub struct RebuildSummary {
     pub skipped_files: usize,
     /// Format version of the target archive
     pub target_format: FormatVersion,
-    /// Whether the rebuilt archive was verified against original
+    /// Number of files stored as references to identical data
+    pub deduplicated_files: usize,
+    /// Whether the rebuilt archive was verified against the original and
+    /// passed
     pub verified: bool,
+    /// Details of the verification, when it was requested
+    pub verification: Option<RebuildVerification>,
+}
+
+/// Result of comparing a rebuilt archive with its source
+///
+/// Each file is compared by the MD5 hash of its decompressed content.
+#[derive(Debug, Clone, Default)]
+pub struct RebuildVerification {
+    /// Number of files whose content was compared
+    pub files_checked: usize,
+    /// Number of files whose content matches
+    pub files_matched: usize,
+    /// Files whose content differs
+    pub mismatched: Vec<ContentMismatch>,
+    /// Files of the source that the rebuilt archive lacks or cannot read
+    pub missing: Vec<String>,
+    /// Files of the rebuilt archive that the source does not have
+    pub unexpected: Vec<String>,
+    /// Files that could not be read from the source and were not compared
+    ///
+    /// The rebuild skips these as well; they do not fail the verification.
+    pub unreadable: Vec<String>,
+    /// Ways the tables differ from what
+    /// [`RebuildOptions::verify_layout`] expects
+    pub layout_issues: Vec<String>,
+}
+
+impl RebuildVerification {
+    /// Whether every file matches and the layout has no issues
+    pub fn passed(&self) -> bool {
+        self.mismatched.is_empty()
+            && self.missing.is_empty()
+            && self.unexpected.is_empty()
+            && self.layout_issues.is_empty()
+    }
+}
+
+/// A file whose content differs between source and rebuilt archive
+#[derive(Debug, Clone)]
+pub struct ContentMismatch {
+    /// File name
+    pub name: String,
+    /// Decompressed size in the source archive
+    pub source_size: usize,
+    /// Decompressed size in the rebuilt archive
+    pub target_size: usize,
+    /// MD5 of the content in the source archive
+    pub source_md5: [u8; 16],
+    /// MD5 of the content in the rebuilt archive
+    pub target_md5: [u8; 16],
 }
 
 /// Analyze the source archive to extract metadata
@@ -209,7 +327,7 @@ fn extract_files_with_metadata(
     archive: &mut Archive,
     metadata: &ArchiveMetadata,
     options: &RebuildOptions,
-    progress_callback: &Option<ProgressCallback>,
+    sink: Option<&dyn ProgressSink>,
 ) -> Result<Vec<(Vec<u8>, FileMetadata)>> {
     // Get file list, preferring the most complete method
     let files = if metadata.has_het_bet {
@@ -223,32 +341,16 @@ fn extract_files_with_metadata(
     };
 
     let mut extracted_files = Vec::new();
-    let total_files = files.len();
+    let mut progress = ProgressTracker::new(sink, ProgressStage::Read, files.len());
 
     for (i, file) in files.iter().enumerate() {
-        // Report progress
-        if let Some(callback) = progress_callback {
-            callback(i + 1, total_files, &file.name);
-        }
-
-        // Apply filters
-        if options.skip_signatures && is_signature_file(&file.name) {
-            log::debug!("Skipping signature file: {}", file.name);
-            continue;
-        }
-
-        if options.skip_encrypted && file.flags & crate::tables::BlockEntry::FLAG_ENCRYPTED != 0 {
-            log::debug!("Skipping encrypted file: {}", file.name);
+        let data = read_included_file(archive, file, options);
+        progress.file_done(
+            &file.name,
+            data.as_ref().map_or(0, |data| data.len() as u64),
+        );
+        let Some(data) = data else {
             continue;
-        }
-
-        // Extract file data
-        let data = match archive.read_file(&file.name) {
-            Ok(data) => data,
-            Err(e) => {
-                log::warn!("Failed to read file {}: {}", file.name, e);
-                continue;
-            }
         };
 
         // Extract metadata
@@ -275,17 +377,49 @@ fn extract_files_with_metadata(
     Ok(extracted_files)
 }
 
-/// Rebuild the archive with extracted files
+/// Read a file unless the options exclude it or it cannot be read
+fn read_included_file(
+    archive: &mut Archive,
+    file: &FileEntry,
+    options: &RebuildOptions,
+) -> Option<Vec<u8>> {
+    if options.skip_signatures && is_signature_file(&file.name) {
+        log::debug!("Skipping signature file: {}", file.name);
+        return None;
+    }
+
+    if options.skip_encrypted && file.flags & crate::tables::BlockEntry::FLAG_ENCRYPTED != 0 {
+        log::debug!("Skipping encrypted file: {}", file.name);
+        return None;
+    }
+
+    match archive.read_file(&file.name) {
+        Ok(data) => Some(data),
+        Err(e) => {
+            log::warn!("Failed to read file {}: {}", file.name, e);
+            None
+        }
+    }
+}
+
+/// Rebuild the archive with extracted files; returns the number of
+/// deduplicated files
 fn rebuild_with_files(
     target_path: &Path,
     metadata: &ArchiveMetadata,
     files: Vec<(Vec<u8>, FileMetadata)>,
     target_format: FormatVersion,
     options: &RebuildOptions,
-) -> Result<()> {
+    sink: Option<Arc<dyn ProgressSink>>,
+) -> Result<usize> {
+    let block_size = options.override_block_size.unwrap_or(metadata.block_size);
     let mut builder = ArchiveBuilder::new()
         .version(target_format)
-        .block_size(options.override_block_size.unwrap_or(metadata.block_size));
+        .block_size(block_size)
+        .deduplicate(options.deduplicate);
+    if let Some(sink) = sink {
+        builder = builder.progress(sink);
+    }
 
     // Configure based on target format
     if target_format >= FormatVersion::V3 {
@@ -303,8 +437,24 @@ fn rebuild_with_files(
     });
 
     // Add files in order
+    let mut seen = HashSet::new();
+    let mut deduplicated = 0;
     for (data, meta) in files {
-        let compression = options.override_compression.unwrap_or(meta.compression);
+        let compression = match options.override_compression {
+            Some(compression) => compression,
+            None if options.best_compression => {
+                best_compression_method(&data, block_size, target_format)?
+            }
+            None => meta.compression,
+        };
+
+        // Mirrors the builder's rule for sharing data between files
+        if options.deduplicate && !meta.encrypted {
+            let digest: [u8; 16] = Md5::digest(&data).into();
+            if !seen.insert((digest, data.len(), compression)) {
+                deduplicated += 1;
+            }
+        }
 
         if meta.encrypted && meta.use_fix_key {
             builder = builder.add_file_data_with_encryption(
@@ -334,7 +484,36 @@ fn rebuild_with_files(
     }
 
     builder.build(target_path)?;
-    Ok(())
+    Ok(deduplicated)
+}
+
+/// Compression methods tried by [`RebuildOptions::best_compression`]
+///
+/// LZMA is only offered for v3+ archives; the clients that read v1/v2
+/// archives do not support it.
+fn compression_candidates(format: FormatVersion) -> &'static [u8] {
+    if format >= FormatVersion::V3 {
+        &[
+            compression_flags::ZLIB,
+            compression_flags::BZIP2,
+            compression_flags::LZMA,
+        ]
+    } else {
+        &[compression_flags::ZLIB, compression_flags::BZIP2]
+    }
+}
+
+/// Find the compression method that stores `data` in the fewest bytes
+///
+/// Sizes are measured per sector, the way the builder compresses files.
+/// Returns 0 (no compression) when no method saves any space.
+fn best_compression_method(data: &[u8], block_size: u16, format: FormatVersion) -> Result<u8> {
+    let options = crate::compression::EvaluateOptions {
+        methods: compression_candidates(format).to_vec(),
+        sector_size: crate::calculate_sector_size(block_size),
+        measure_decompression: false,
+    };
+    Ok(crate::compression::evaluate_with(data, &options)?.best_method())
 }
 
 /// Determine the target format based on options and source metadata
@@ -351,7 +530,9 @@ fn determine_target_format(metadata: &ArchiveMetadata, options: &RebuildOptions)
 
 /// Extract compression method from block entry flags
 fn extract_compression_method(flags: u32) -> u8 {
-    if flags & crate::tables::BlockEntry::FLAG_COMPRESS != 0 {
+    if flags & crate::tables::BlockEntry::FLAG_IMPLODE != 0 {
+        compression_flags::IMPLODE
+    } else if flags & crate::tables::BlockEntry::FLAG_COMPRESS != 0 {
         // Default to ZLIB if compressed (we can't determine exact method from flags alone)
         compression_flags::ZLIB
     } else {
@@ -364,8 +545,12 @@ fn is_signature_file(filename: &str) -> bool {
     matches!(filename, "(signature)" | "(strong signature)")
 }
 
-/// Verify that the rebuilt archive matches the original
-fn verify_rebuild(source_path: &Path, target_path: &Path, options: &RebuildOptions) -> Result<()> {
+/// Compare the rebuilt archive with the original, file by file
+fn verify_rebuild(
+    source_path: &Path,
+    target_path: &Path,
+    options: &RebuildOptions,
+) -> Result<RebuildVerification> {
     let mut source_archive = Archive::open(source_path)?;
     let mut target_archive = Archive::open(target_path)?;
 
@@ -376,7 +561,7 @@ fn verify_rebuild(source_path: &Path, target_path: &Path, options: &RebuildOptio
         .list()
         .unwrap_or_else(|_| target_archive.list_all().unwrap_or_default());
 
-    // Calculate expected file count after filtering
+    // Files expected in the target after filtering
     let mut expected_files = Vec::new();
     for file in &source_files {
         if options.skip_signatures && is_signature_file(&file.name) {
@@ -388,30 +573,111 @@ fn verify_rebuild(source_path: &Path, target_path: &Path, options: &RebuildOptio
         expected_files.push(&file.name);
     }
 
-    if target_files.len() != expected_files.len() {
-        return Err(Error::invalid_format(format!(
-            "File count mismatch: expected {}, got {}",
-            expected_files.len(),
-            target_files.len()
-        )));
+    let mut report = RebuildVerification::default();
+    for expected_file in &expected_files {
+        let Ok(source_data) = source_archive.read_file(expected_file) else {
+            report.unreadable.push(expected_file.to_string());
+            continue;
+        };
+        let Ok(target_data) = target_archive.read_file(expected_file) else {
+            report.missing.push(expected_file.to_string());
+            continue;
+        };
+
+        report.files_checked += 1;
+        let source_md5: [u8; 16] = Md5::digest(&source_data).into();
+        let target_md5: [u8; 16] = Md5::digest(&target_data).into();
+        if source_md5 == target_md5 {
+            report.files_matched += 1;
+        } else {
+            report.mismatched.push(ContentMismatch {
+                name: expected_file.to_string(),
+                source_size: source_data.len(),
+                target_size: target_data.len(),
+                source_md5,
+                target_md5,
+            });
+        }
     }
 
-    // Verify each file's content
-    for expected_file in &expected_files {
-        let source_data = source_archive.read_file(expected_file)?;
-        let target_data = target_archive.read_file(expected_file).map_err(|e| {
-            Error::invalid_format(format!("File {expected_file} missing in target: {e}"))
-        })?;
-
-        if source_data != target_data {
-            return Err(Error::invalid_format(format!(
-                "Content mismatch for file: {expected_file}"
-            )));
+    // The builder generates these when the source has none
+    let expected: HashSet<String> = expected_files
+        .iter()
+        .map(|name| name.to_ascii_uppercase())
+        .collect();
+    report.unexpected = target_files
+        .into_iter()
+        .map(|file| file.name)
+        .filter(|name| !matches!(name.as_str(), "(listfile)" | "(attributes)"))
+        .filter(|name| !expected.contains(&name.to_ascii_uppercase()))
+        .collect();
+
+    if let Some(client) = options.verify_layout {
+        report.layout_issues = check_layout(&mut target_archive, client)?;
+    }
+
+    if report.passed() {
+        log::info!("✅ Verification successful - rebuilt archive matches original");
+    } else {
+        log::warn!(
+            "Verification failed: {} mismatched, {} missing, {} unexpected, {} layout issues",
+            report.mismatched.len(),
+            report.missing.len(),
+            report.unexpected.len(),
+            report.layout_issues.len()
+        );
+    }
+    Ok(report)
+}
+
+/// Check the tables of `archive` against what `client` expects
+fn check_layout(archive: &mut Archive, client: GameVersion) -> Result<Vec<String>> {
+    let info = archive.get_info()?;
+    let mut issues = Vec::new();
+
+    let max_format = client.max_format_version();
+    if info.format_version > max_format {
+        issues.push(format!(
+            "Format {:?} is newer than {client} reads ({max_format:?})",
+            info.format_version
+        ));
+    }
+
+    // Every client looks files up through the classic tables
+    for (name, table) in [
+        ("Hash", &info.hash_table_info),
+        ("Block", &info.block_table_info),
+    ] {
+        if table.failed_to_load || table.size.is_none() {
+            issues.push(format!("{name} table is missing or unreadable"));
         }
     }
+    if let Some(size) = info.hash_table_info.size
+        && !size.is_power_of_two()
+    {
+        issues.push(format!("Hash table size {size} is not a power of two"));
+    }
+
+    if !info.has_listfile {
+        issues.push("No (listfile); client archives always carry one".to_string());
+    }
 
-    log::info!("✅ Verification successful - rebuilt archive matches original");
-    Ok(())
+    if let Some(md5) = &info.md5_status {
+        for (name, valid) in [
+            ("header", md5.header_valid),
+            ("hash table", md5.hash_table_valid),
+            ("block table", md5.block_table_valid),
+            ("hi-block table", md5.hi_block_table_valid),
+            ("HET table", md5.het_table_valid),
+            ("BET table", md5.bet_table_valid),
+        ] {
+            if !valid {
+                issues.push(format!("MD5 of the {name} does not match"));
+            }
+        }
+    }
+
+    Ok(issues)
 }
 
 #[cfg(test)]
@@ -436,6 +702,22 @@ mod tests {
         assert!(!is_signature_file("normal_file.txt"));
     }
 
+    #[test]
+    fn test_best_compression_method() {
+        let text = b"the quick brown fox jumps over the lazy dog ".repeat(200);
+        let method = best_compression_method(&text, 3, FormatVersion::V2).unwrap();
+        assert!(matches!(
+            method,
+            compression_flags::ZLIB | compression_flags::BZIP2
+        ));
+
+        // Nothing to gain on tiny, incompressible input
+        assert_eq!(
+            best_compression_method(&[1, 2, 3], 3, FormatVersion::V4).unwrap(),
+            0
+        );
+    }
+
     #[test]
     fn test_extract_compression_method() {
         assert_eq!(extract_compression_method(0), 0);
@@ -443,5 +725,9 @@ mod tests {
             extract_compression_method(crate::tables::BlockEntry::FLAG_COMPRESS),
             compression_flags::ZLIB
         );
+        assert_eq!(
+            extract_compression_method(crate::tables::BlockEntry::FLAG_IMPLODE),
+            compression_flags::IMPLODE
+        );
     }
 }
@@ -0,0 +1,724 @@
+//! Recovery of archives with damaged or deliberately corrupted tables
+//!
+//! Warcraft III reads maps leniently: it ignores the format version and the
+//! header size, reads the tables at whatever offsets the header gives and
+//! skips table entries that make no sense. "Map protectors" exploit this by
+//! writing headers and tables that the game accepts but stricter readers,
+//! [`Archive::open`] included, reject, and usually strip the `(listfile)` so
+//! the files cannot be listed by name.
+//!
+//! [`Archive::open_with_recovery`] reads such archives the way the game does
+//! and reports what it had to work around in a [`RecoveryReport`]. The report
+//! lists every block that holds a file, named from the `(listfile)` or the
+//! names every Warcraft III map uses where possible. Other files get a
+//! generic `FileXXXXXXXX.ext` name with the extension guessed from their
+//! content, and the key of encrypted ones is recovered from their sector
+//! offset table, as MPQ editors do in their recovery modes.
+
+use crate::archive::{Archive, FileInfo};
+use crate::crypto::{ENCRYPTION_TABLE, decrypt_block};
+use crate::header::{self, FormatVersion, HEADER_ALIGNMENT, MPQ_HEADER_SIGNATURE, MpqHeader};
+use crate::special_files;
+use crate::tables::{BlockEntry, BlockTable, HashEntry, HashTable};
+use crate::{Error, Result};
+use byteorder::{ByteOrder, LittleEndian};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Names found in Warcraft III maps, tried when a map has no `(listfile)`
+const KNOWN_NAMES: &[&str] = &[
+    "war3map.j",
+    "scripts\\war3map.j",
+    "war3map.w3e",
+    "war3map.w3i",
+    "war3map.wts",
+    "war3map.shd",
+    "war3map.mmp",
+    "war3map.wpm",
+    "war3map.doo",
+    "war3mapUnits.doo",
+    "war3map.w3r",
+    "war3map.w3c",
+    "war3map.w3s",
+    "war3map.w3u",
+    "war3map.w3t",
+    "war3map.w3a",
+    "war3map.w3b",
+    "war3map.w3d",
+    "war3map.w3q",
+    "war3map.imp",
+    "war3mapMap.blp",
+    "war3mapPreview.tga",
+    "war3mapMisc.txt",
+    "war3mapSkin.txt",
+    "war3mapExtra.txt",
+    "war3map.wct",
+    "war3map.wtg",
+    "(listfile)",
+    "(attributes)",
+    "(signature)",
+];
+
+/// Size of a hash or block table entry
+const TABLE_ENTRY_SIZE: u64 = 16;
+
+/// Sector size exponent used when the header's is unusable
+const DEFAULT_BLOCK_SIZE: u16 = 3;
+
+/// Something [`Archive::open_with_recovery`] had to work around
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecoveryIssue {
+    /// The header was rejected and only its version 1 fields were used
+    HeaderRepaired {
+        /// Why the header could not be used as stored
+        reason: String,
+    },
+    /// The sector size exponent is out of range and the default was used
+    SectorSize {
+        /// Stored exponent
+        block_size: u16,
+    },
+    /// The hash table does not fit in the file or is not a power of two
+    HashTableSize {
+        /// Entries given in the header
+        declared: u32,
+        /// Entries read
+        used: u32,
+    },
+    /// The block table does not fit in the file
+    BlockTableSize {
+        /// Entries given in the header
+        declared: u32,
+        /// Entries read
+        used: u32,
+    },
+    /// Blocks that start past the end of the file or have no data, ignored
+    InvalidBlocks(usize),
+    /// Blocks that extend past the end of the file, cut at the end
+    TruncatedBlocks(usize),
+    /// Hash entries pointing at blocks that do not exist, ignored
+    InvalidHashEntries(usize),
+    /// Unnamed encrypted files whose key could not be found
+    UnknownKeys(usize),
+}
+
+impl fmt::Display for RecoveryIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::HeaderRepaired { reason } => write!(f, "header repaired: {reason}"),
+            Self::SectorSize { block_size } => write!(
+                f,
+                "sector size exponent {block_size} out of range, using {DEFAULT_BLOCK_SIZE}"
+            ),
+            Self::HashTableSize { declared, used } => {
+                write!(f, "hash table declares {declared} entries, {used} used")
+            }
+            Self::BlockTableSize { declared, used } => {
+                write!(f, "block table declares {declared} entries, {used} used")
+            }
+            Self::InvalidBlocks(count) => write!(f, "{count} invalid block(s) ignored"),
+            Self::TruncatedBlocks(count) => {
+                write!(f, "{count} block(s) extend past the end of the file")
+            }
+            Self::InvalidHashEntries(count) => {
+                write!(f, "{count} hash entry(s) point at missing blocks")
+            }
+            Self::UnknownKeys(count) => {
+                write!(f, "{count} encrypted file(s) without a known key")
+            }
+        }
+    }
+}
+
+/// A file found by [`Archive::open_with_recovery`]
+#[derive(Debug, Clone)]
+pub struct RecoveredFile {
+    /// Real name, or a generic `FileXXXXXXXX.ext` name
+    pub name: String,
+    /// Whether `name` is the file's real name
+    pub named: bool,
+    /// Index in the block table
+    pub block_index: usize,
+    /// Uncompressed size
+    pub size: u64,
+    /// Stored size
+    pub compressed_size: u64,
+    /// Block flags
+    pub flags: u32,
+    /// Encryption key, 0 for unencrypted files and `None` if it is unknown
+    pub key: Option<u32>,
+}
+
+/// Result of opening an archive in recovery mode
+#[derive(Debug, Clone, Default)]
+pub struct RecoveryReport {
+    /// Problems that were worked around
+    pub issues: Vec<RecoveryIssue>,
+    /// Files in block table order
+    pub files: Vec<RecoveredFile>,
+}
+
+impl RecoveryReport {
+    /// Whether the archive opened without working around anything
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+impl Archive {
+    /// Open an archive whose header or tables are damaged or protected
+    ///
+    /// Unlike [`Archive::open`], this accepts what Warcraft III accepts:
+    /// bogus header sizes and versions, tables that run past the end of the
+    /// file and entries pointing nowhere. Only the hash and block tables are
+    /// used. Files are listed in the returned report and read with
+    /// [`Archive::read_recovered`]; files with a known name can also be read
+    /// with [`Archive::read_file`].
+    pub fn open_with_recovery<P: AsRef<Path>>(path: P) -> Result<(Archive, RecoveryReport)> {
+        let path = path.as_ref().to_path_buf();
+        let mut reader = BufReader::new(File::open(&path)?);
+        let file_len = reader.seek(SeekFrom::End(0))?;
+        let mut issues = Vec::new();
+
+        let (archive_offset, user_data, mut header) = match header::find_header(&mut reader) {
+            Ok(found) => found,
+            Err(e) => {
+                let (offset, header) = scan_header(&mut reader, file_len)?;
+                issues.push(RecoveryIssue::HeaderRepaired {
+                    reason: e.to_string(),
+                });
+                (offset, None, header)
+            }
+        };
+
+        if header.format_version != FormatVersion::V1
+            && !extended_fields_fit(&header, archive_offset, file_len)
+        {
+            issues.push(RecoveryIssue::HeaderRepaired {
+                reason: format!(
+                    "{:?} table positions lie outside the file",
+                    header.format_version
+                ),
+            });
+            header = v1_header(&header);
+        }
+
+        if header.block_size > 15 {
+            issues.push(RecoveryIssue::SectorSize {
+                block_size: header.block_size,
+            });
+            header.block_size = DEFAULT_BLOCK_SIZE;
+        }
+
+        // Hash table
+        let hash_pos = table_pos(&header, archive_offset, header.get_hash_table_pos());
+        let fit = entries_fitting(hash_pos, file_len);
+        let mut used = header.hash_table_size.min(fit);
+        if used > 0 && !used.is_power_of_two() {
+            used = 1 << used.ilog2();
+        }
+        if used != header.hash_table_size {
+            issues.push(RecoveryIssue::HashTableSize {
+                declared: header.hash_table_size,
+                used,
+            });
+        }
+        let mut hash_table = if used == 0 {
+            HashTable::new(1)?
+        } else {
+            let data = read_at(&mut reader, hash_pos, used as u64 * TABLE_ENTRY_SIZE)?;
+            HashTable::from_bytes(&data, used)?
+        };
+        header.hash_table_size = used;
+
+        // Block table
+        let block_pos = table_pos(&header, archive_offset, header.get_block_table_pos());
+        let fit = entries_fitting(block_pos, file_len);
+        let used = header.block_table_size.min(fit);
+        if used != header.block_table_size {
+            issues.push(RecoveryIssue::BlockTableSize {
+                declared: header.block_table_size,
+                used,
+            });
+        }
+        let data = read_at(&mut reader, block_pos, used as u64 * TABLE_ENTRY_SIZE)?;
+        let mut block_table = BlockTable::from_bytes(&data, used)?;
+        header.block_table_size = used;
+
+        let (mut invalid, mut truncated) = (0, 0);
+        for block in block_table.entries_mut() {
+            if !block.exists() {
+                continue;
+            }
+            let start = archive_offset + block.file_pos as u64;
+            if start > file_len || (block.compressed_size == 0 && block.file_size != 0) {
+                block.flags = 0;
+                invalid += 1;
+            } else if start + block.compressed_size as u64 > file_len {
+                block.compressed_size = (file_len - start) as u32;
+                truncated += 1;
+            }
+        }
+        if invalid > 0 {
+            issues.push(RecoveryIssue::InvalidBlocks(invalid));
+        }
+        if truncated > 0 {
+            issues.push(RecoveryIssue::TruncatedBlocks(truncated));
+        }
+
+        let blocks = block_table.entries();
+        let mut invalid_hashes = 0;
+        for entry in hash_table.entries_mut() {
+            let index = entry.block_index as usize;
+            if entry.is_valid() && blocks.get(index).is_none_or(|block| !block.exists()) {
+                entry.block_index = HashEntry::EMPTY_DELETED;
+                invalid_hashes += 1;
+            }
+        }
+        if invalid_hashes > 0 {
+            issues.push(RecoveryIssue::InvalidHashEntries(invalid_hashes));
+        }
+
+        let mut archive = Archive::from_tables(
+            path,
+            Box::new(reader),
+            archive_offset,
+            user_data,
+            header,
+            hash_table,
+            block_table,
+        );
+        // Protected maps often carry a corrupt (attributes) too
+        let _ = archive.load_attributes();
+
+        let files = archive.recover_files(&mut issues);
+        Ok((archive, RecoveryReport { issues, files }))
+    }
+
+    /// Read a file listed by [`Archive::open_with_recovery`]
+    pub fn read_recovered(&mut self, file: &RecoveredFile) -> Result<Vec<u8>> {
+        let key = file.key.ok_or_else(|| {
+            Error::Crypto(format!("Encryption key of '{}' is unknown", file.name))
+        })?;
+        let block = self
+            .block_table()
+            .and_then(|table| table.get(file.block_index))
+            .copied()
+            .ok_or_else(|| {
+                Error::block_table(format!("Block {} does not exist", file.block_index))
+            })?;
+        let info = self.block_file_info(file.block_index, &block, &file.name);
+        self.read_file_with_key(&file.name, &info, key)
+    }
+
+    /// List every block holding a file, naming what can be named
+    fn recover_files(&mut self, issues: &mut Vec<RecoveryIssue>) -> Vec<RecoveredFile> {
+        let mut candidates = Vec::new();
+        if let Ok(data) = self.read_file("(listfile)")
+            && let Ok(listed) = special_files::parse_listfile(&data)
+        {
+            candidates = listed;
+        }
+        candidates.extend(KNOWN_NAMES.iter().map(|name| name.to_string()));
+
+        let mut names: HashMap<usize, String> = HashMap::new();
+        for name in candidates {
+            if let Ok(Some(info)) = self.find_file(&name) {
+                names.entry(info.block_index).or_insert(name);
+            }
+        }
+
+        let blocks = self
+            .block_table()
+            .map(|table| table.entries().to_vec())
+            .unwrap_or_default();
+        let mut files = Vec::new();
+        let mut unknown_keys = 0;
+        for (index, block) in blocks.iter().enumerate() {
+            if !block.exists() || block.flags & BlockEntry::FLAG_DELETE_MARKER != 0 {
+                continue;
+            }
+
+            let (name, named, key) = match names.remove(&index) {
+                Some(name) => {
+                    let info = self.block_file_info(index, block, &name);
+                    let key = self.file_key(&name, &info, block.file_size);
+                    (name, true, Some(key))
+                }
+                None => {
+                    let (name, key) = self.identify_unnamed(index, block);
+                    (name, false, key)
+                }
+            };
+            if key.is_none() {
+                unknown_keys += 1;
+            }
+
+            files.push(RecoveredFile {
+                name,
+                named,
+                block_index: index,
+                size: block.file_size as u64,
+                compressed_size: block.compressed_size as u64,
+                flags: block.flags,
+                key,
+            });
+        }
+        if unknown_keys > 0 {
+            issues.push(RecoveryIssue::UnknownKeys(unknown_keys));
+        }
+        files
+    }
+
+    /// Generic name and key of a file whose name is unknown
+    fn identify_unnamed(&mut self, index: usize, block: &BlockEntry) -> (String, Option<u32>) {
+        let stem = generic_stem(index);
+        let info = self.block_file_info(index, block, &stem);
+        let key = if block.is_encrypted() {
+            self.detect_file_key(&info)
+        } else {
+            Some(0)
+        };
+
+        let data = key.and_then(|key| self.read_file_with_key(&stem, &info, key).ok());
+        match data {
+            Some(data) => (format!("{stem}.{}", guess_extension(&data)), key),
+            // A key that does not read the file is not the key
+            None => (format!("{stem}.dat"), key.filter(|_| !block.is_encrypted())),
+        }
+    }
+
+    /// Find the key of an encrypted sectored file from its sector offset table
+    ///
+    /// The table is encrypted with the file key minus one, and its first
+    /// entry is the size of the table itself, which gives the plaintext of
+    /// the first encrypted dword. Each of the 256 possible low key bytes then
+    /// yields one candidate key, checked against the second entry.
+    fn detect_file_key(&mut self, info: &FileInfo) -> Option<u32> {
+        if info.is_single_unit() || !info.is_compressed() {
+            return None;
+        }
+        let sector_size = self.header().sector_size() as u64;
+        let sectors = info.file_size.div_ceil(sector_size);
+        let table_size = ((sectors + 1 + info.has_sector_crc() as u64) * 4) as u32;
+
+        let raw = self.read_raw(info.file_pos, 8).ok()?;
+        let encrypted = [
+            LittleEndian::read_u32(&raw[0..4]),
+            LittleEndian::read_u32(&raw[4..8]),
+        ];
+        (0..256u32).find_map(|low| {
+            let key = (encrypted[0] ^ table_size)
+                .wrapping_sub(0xEEEE_EEEE)
+                .wrapping_sub(ENCRYPTION_TABLE[0x400 + low as usize]);
+            if key & 0xFF != low {
+                return None;
+            }
+            let mut decrypted = encrypted;
+            decrypt_block(&mut decrypted, key);
+            (decrypted[0] == table_size && decrypted[1] <= table_size + sector_size as u32)
+                .then(|| key.wrapping_add(1))
+        })
+    }
+
+    /// File information for a block, without a hash table entry
+    fn block_file_info(&self, index: usize, block: &BlockEntry, name: &str) -> FileInfo {
+        FileInfo {
+            filename: name.to_string(),
+            hash_index: 0,
+            block_index: index,
+            file_pos: self.archive_offset() + block.file_pos as u64,
+            compressed_size: block.compressed_size as u64,
+            file_size: block.file_size as u64,
+            flags: block.flags,
+            locale: 0,
+        }
+    }
+}
+
+fn generic_stem(index: usize) -> String {
+    format!("File{index:08}")
+}
+
+/// Find the first MPQ signature and read the version 1 header after it
+fn scan_header<R: Read + Seek>(reader: &mut R, file_len: u64) -> Result<(u64, MpqHeader)> {
+    let mut offset = 0;
+    while offset + 32 <= file_len {
+        let raw = read_at(reader, offset, 32)?;
+        if LittleEndian::read_u32(&raw[0..4]) == MPQ_HEADER_SIGNATURE {
+            let header = MpqHeader {
+                header_size: 32,
+                archive_size: LittleEndian::read_u32(&raw[8..12]),
+                format_version: FormatVersion::V1,
+                block_size: LittleEndian::read_u16(&raw[14..16]),
+                hash_table_pos: LittleEndian::read_u32(&raw[16..20]),
+                block_table_pos: LittleEndian::read_u32(&raw[20..24]),
+                hash_table_size: LittleEndian::read_u32(&raw[24..28]),
+                block_table_size: LittleEndian::read_u32(&raw[28..32]),
+                hi_block_table_pos: None,
+                hash_table_pos_hi: None,
+                block_table_pos_hi: None,
+                archive_size_64: None,
+                bet_table_pos: None,
+                het_table_pos: None,
+                v4_data: None,
+            };
+            return Ok((offset, header));
+        }
+        offset += HEADER_ALIGNMENT;
+    }
+    Err(Error::invalid_format("No MPQ header found"))
+}
+
+/// Whether the positions only version 2 and later headers have are usable
+fn extended_fields_fit(header: &MpqHeader, archive_offset: u64, file_len: u64) -> bool {
+    let inside = |pos: u64| archive_offset.saturating_add(pos) < file_len;
+    inside(header.get_hash_table_pos())
+        && inside(header.get_block_table_pos())
+        && header
+            .hi_block_table_pos
+            .is_none_or(|pos| pos == 0 || inside(pos))
+        && header
+            .het_table_pos
+            .is_none_or(|pos| pos == 0 || inside(pos))
+        && header
+            .bet_table_pos
+            .is_none_or(|pos| pos == 0 || inside(pos))
+}
+
+/// The version 1 part of a header
+fn v1_header(header: &MpqHeader) -> MpqHeader {
+    MpqHeader {
+        header_size: 32,
+        format_version: FormatVersion::V1,
+        hi_block_table_pos: None,
+        hash_table_pos_hi: None,
+        block_table_pos_hi: None,
+        archive_size_64: None,
+        bet_table_pos: None,
+        het_table_pos: None,
+        v4_data: None,
+        ..header.clone()
+    }
+}
+
+/// Absolute position of a table; version 1 offsets wrap at 4 GiB as in the game
+fn table_pos(header: &MpqHeader, archive_offset: u64, pos: u64) -> u64 {
+    if header.format_version == FormatVersion::V1 {
+        (archive_offset as u32).wrapping_add(pos as u32) as u64
+    } else {
+        archive_offset + pos
+    }
+}
+
+/// Number of table entries between `pos` and the end of the file
+fn entries_fitting(pos: u64, file_len: u64) -> u32 {
+    (file_len.saturating_sub(pos) / TABLE_ENTRY_SIZE).min(u32::MAX as u64) as u32
+}
+
+fn read_at<R: Read + Seek>(reader: &mut R, pos: u64, len: u64) -> Result<Vec<u8>> {
+    reader.seek(SeekFrom::Start(pos))?;
+    let mut data = vec![0u8; len as usize];
+    reader.read_exact(&mut data)?;
+    Ok(data)
+}
+
+/// File extension matching the content of an unnamed file
+fn guess_extension(data: &[u8]) -> &'static str {
+    let starts = |magic: &[u8]| data.starts_with(magic);
+    if starts(b"BLP0") || starts(b"BLP1") || starts(b"BLP2") {
+        "blp"
+    } else if starts(b"MDLX") {
+        "mdx"
+    } else if starts(b"W3E!") {
+        "w3e"
+    } else if starts(b"RIFF") && data.get(8..12) == Some(&b"WAVE"[..]) {
+        "wav"
+    } else if starts(b"ID3") || starts(&[0xFF, 0xFB]) {
+        "mp3"
+    } else if starts(b"DDS ") {
+        "dds"
+    } else if starts(b"HM3W") {
+        "w3m"
+    } else if starts(b"MPQ\x1A") {
+        "mpq"
+    } else if starts(b"\x89PNG") {
+        "png"
+    } else if starts(b"BM") {
+        "bmp"
+    } else if is_text(data) {
+        let text = String::from_utf8_lossy(&data[..data.len().min(4096)]);
+        if text.contains("globals") || text.contains("function ") {
+            "j"
+        } else {
+            "txt"
+        }
+    } else {
+        "dat"
+    }
+}
+
+/// Whether the start of `data` is mostly printable text
+fn is_text(data: &[u8]) -> bool {
+    let sample = &data[..data.len().min(512)];
+    if sample.is_empty() {
+        return false;
+    }
+    let printable = sample
+        .iter()
+        .filter(|&&b| b.is_ascii_graphic() || b.is_ascii_whitespace() || b >= 0x80)
+        .count();
+    printable * 20 >= sample.len() * 19
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::{ArchiveBuilder, ListfileOption};
+    use crate::compression::flags;
+    use crate::crypto::{encrypt_block, hash_string, hash_type};
+    use std::io::Write;
+
+    const SCRIPT: &str =
+        "globals\nendglobals\nfunction main takes nothing returns nothing\nendfunction\n";
+
+    fn build_map(path: &Path, listfile: ListfileOption) -> Vec<u8> {
+        let terrain: Vec<u8> = b"W3E!"
+            .iter()
+            .copied()
+            .chain((0..20000u32).map(|i| (i % 7) as u8))
+            .collect();
+        ArchiveBuilder::new()
+            .block_size(3)
+            .listfile_option(listfile)
+            .add_file_data_with_encryption(
+                SCRIPT.as_bytes().to_vec(),
+                "war3map.j",
+                flags::ZLIB,
+                true,
+                0,
+            )
+            .add_file_data_with_encryption(
+                terrain.clone(),
+                "Units\\Custom.bin",
+                flags::ZLIB,
+                true,
+                0,
+            )
+            .add_file_data(b"plain text".to_vec(), "war3mapMisc.txt")
+            .build(path)
+            .unwrap();
+        terrain
+    }
+
+    fn patch(path: &Path, offset: u64, bytes: &[u8]) {
+        let mut file = std::fs::OpenOptions::new().write(true).open(path).unwrap();
+        file.seek(SeekFrom::Start(offset)).unwrap();
+        file.write_all(bytes).unwrap();
+    }
+
+    #[test]
+    fn test_recover_clean_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("clean.w3x");
+        build_map(&path, ListfileOption::Generate);
+
+        let (mut archive, report) = Archive::open_with_recovery(&path).unwrap();
+        assert!(report.is_clean(), "{:?}", report.issues);
+        assert!(report.files.iter().all(|file| file.named));
+        let custom = report
+            .files
+            .iter()
+            .find(|file| file.name == "Units\\Custom.bin")
+            .unwrap();
+        assert_eq!(archive.read_recovered(custom).unwrap()[..4], *b"W3E!");
+    }
+
+    #[test]
+    fn test_recover_protected_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("protected.w3x");
+        let terrain = build_map(&path, ListfileOption::None);
+
+        let header = MpqHeader::read(&mut File::open(&path).unwrap()).unwrap();
+        let file_len = std::fs::metadata(&path).unwrap().len();
+        // Bogus header size and format version, and a huge block table
+        patch(&path, 4, &0xDEAD_BEEFu32.to_le_bytes());
+        patch(&path, 12, &0x7F31u16.to_le_bytes());
+        patch(&path, 28, &0x00FF_FFFFu32.to_le_bytes());
+        assert!(Archive::open(&path).is_err());
+
+        let (mut archive, report) = Archive::open_with_recovery(&path).unwrap();
+        assert!(
+            report
+                .issues
+                .iter()
+                .any(|issue| matches!(issue, RecoveryIssue::HeaderRepaired { .. }))
+        );
+        let used = ((file_len - header.block_table_pos as u64) / 16) as u32;
+        assert!(report.issues.contains(&RecoveryIssue::BlockTableSize {
+            declared: 0x00FF_FFFF,
+            used,
+        }));
+
+        let script = report
+            .files
+            .iter()
+            .find(|file| file.name == "war3map.j")
+            .unwrap();
+        assert!(script.named);
+        assert_eq!(archive.read_recovered(script).unwrap(), SCRIPT.as_bytes());
+        assert_eq!(archive.read_file("war3mapMisc.txt").unwrap(), b"plain text");
+
+        // The encrypted file without a known name is found with its key
+        let custom = report.files.iter().find(|file| !file.named).unwrap();
+        assert_eq!(custom.name, format!("File{:08}.w3e", custom.block_index));
+        assert!(custom.key.is_some());
+        assert_eq!(archive.read_recovered(custom).unwrap(), terrain);
+    }
+
+    #[test]
+    fn test_recover_invalid_hash_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dangling.w3x");
+        build_map(&path, ListfileOption::Generate);
+
+        let archive = Archive::open(&path).unwrap();
+        let info = archive.find_file("war3mapMisc.txt").unwrap().unwrap();
+        let hash_pos = archive.archive_offset() + archive.header().get_hash_table_pos();
+        let len = archive.header().hash_table_size as u64 * TABLE_ENTRY_SIZE;
+        drop(archive);
+
+        // Point the hash entry of war3mapMisc.txt at a block that does not
+        // exist; the key stream depends on everything before it, so the whole
+        // table is re-encrypted
+        let key = hash_string("(hash table)", hash_type::FILE_KEY);
+        let mut table = read_at(&mut File::open(&path).unwrap(), hash_pos, len).unwrap();
+        let mut words: Vec<u32> = table.chunks_exact(4).map(LittleEndian::read_u32).collect();
+        decrypt_block(&mut words, key);
+        words[info.hash_index * 4 + 3] = 9999;
+        encrypt_block(&mut words, key);
+        LittleEndian::write_u32_into(&words, &mut table);
+        patch(&path, hash_pos, &table);
+
+        let (mut archive, report) = Archive::open_with_recovery(&path).unwrap();
+        assert!(
+            report
+                .issues
+                .contains(&RecoveryIssue::InvalidHashEntries(1))
+        );
+        assert!(archive.read_file("war3mapMisc.txt").is_err());
+        // The block is still listed, under a generic name
+        let misc = report
+            .files
+            .iter()
+            .find(|file| file.block_index == info.block_index)
+            .unwrap();
+        assert!(!misc.named);
+        assert_eq!(misc.name, format!("File{:08}.txt", info.block_index));
+        assert_eq!(archive.read_recovered(misc).unwrap(), b"plain text");
+    }
+}
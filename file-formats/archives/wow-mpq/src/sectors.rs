@@ -0,0 +1,241 @@
+//! Raw access to stored file data
+//!
+//! [`Archive::file_encryption_key`] returns the key a file is encrypted with,
+//! and [`Archive::read_raw_sectors`] returns the sectors of a file exactly as
+//! they are stored: still compressed and, for encrypted files, still
+//! encrypted. Together they allow damaged or unusual files to be examined
+//! with external tools.
+//!
+//! ```no_run
+//! use wow_mpq::Archive;
+//!
+//! let mut archive = Archive::open("patch.mpq")?;
+//! let raw = archive.read_raw_sectors("(attributes)")?;
+//! for sector in &raw.sectors {
+//!     println!("sector {} at 0x{:X}: {} bytes", sector.index, sector.offset, sector.data.len());
+//! }
+//! # Ok::<(), wow_mpq::Error>(())
+//! ```
+
+use crate::archive::{Archive, decrypt_file_data};
+use crate::crypto::{hash_string, hash_type};
+use crate::{Error, Result};
+use byteorder::{ByteOrder, LittleEndian};
+
+/// Calculate the encryption key of a file
+///
+/// `block_offset` is the position of the file data relative to the start of
+/// the archive; it only matters for files stored with `FLAG_FIX_KEY`.
+/// Sector `i` is encrypted with `key + i` and the sector offset table with
+/// `key - 1`.
+pub fn calculate_file_key(name: &str, block_offset: u32, file_size: u32, fix_key: bool) -> u32 {
+    let base_key = hash_string(name, hash_type::FILE_KEY);
+    if fix_key {
+        base_key.wrapping_add(block_offset) ^ file_size
+    } else {
+        base_key
+    }
+}
+
+/// One sector of a file as stored in the archive
+#[derive(Debug, Clone)]
+pub struct RawSector {
+    /// Sector index within the file
+    pub index: usize,
+    /// Absolute position of the sector in the archive file
+    pub offset: u64,
+    /// Key the sector is encrypted with, if the file is encrypted
+    pub key: Option<u32>,
+    /// Stored bytes, not decrypted or decompressed
+    pub data: Vec<u8>,
+}
+
+/// Stored layout of a file, as returned by [`Archive::read_raw_sectors`]
+#[derive(Debug, Clone)]
+pub struct RawFile {
+    /// File name
+    pub name: String,
+    /// Index in the block table
+    pub block_index: usize,
+    /// Absolute position of the file data in the archive file
+    pub file_pos: u64,
+    /// Stored size of the file data
+    pub compressed_size: u64,
+    /// Uncompressed file size
+    pub file_size: u64,
+    /// Block table flags
+    pub flags: u32,
+    /// Encryption key, if the file is encrypted
+    pub key: Option<u32>,
+    /// Decrypted sector offset table, relative to `file_pos`
+    ///
+    /// Empty for single-unit and uncompressed files, which have no table.
+    pub sector_offsets: Vec<u32>,
+    /// Stored sectors in file order
+    pub sectors: Vec<RawSector>,
+}
+
+impl Archive {
+    /// Encryption key of a file, or `None` if the file is not encrypted
+    pub fn file_encryption_key(&self, name: &str) -> Result<Option<u32>> {
+        let file_info = self
+            .find_file(name)?
+            .ok_or_else(|| Error::FileNotFound(name.to_string()))?;
+        Ok(file_info
+            .is_encrypted()
+            .then(|| self.file_key(name, &file_info, file_info.file_size as u32)))
+    }
+
+    /// Read the sectors of a file without decrypting or decompressing them
+    ///
+    /// Sectors whose offsets point outside the file's block are returned
+    /// truncated to the block, or empty, rather than failing the whole read.
+    pub fn read_raw_sectors(&mut self, name: &str) -> Result<RawFile> {
+        let file_info = self
+            .find_file(name)?
+            .ok_or_else(|| Error::FileNotFound(name.to_string()))?;
+        let key = file_info
+            .is_encrypted()
+            .then(|| self.file_key(name, &file_info, file_info.file_size as u32));
+        let sector_key = |index: usize| key.map(|key| key.wrapping_add(index as u32));
+
+        let block_size = file_info.compressed_size;
+        let sector_size = self.header().sector_size() as u64;
+        let sector_count = file_info.file_size.div_ceil(sector_size) as usize;
+        let mut sector_offsets = Vec::new();
+        let mut ranges = Vec::new();
+        if file_info.is_single_unit() {
+            ranges.push((0, block_size));
+        } else if !file_info.is_compressed() {
+            // Uncompressed sectors are stored back to back without a table
+            for index in 0..sector_count as u64 {
+                let start = index * sector_size;
+                ranges.push((start, (start + sector_size).min(block_size)));
+            }
+        } else {
+            let table_len = (sector_count + 1) * 4;
+            if table_len as u64 > block_size {
+                return Err(Error::invalid_format("Truncated sector offset table"));
+            }
+            let mut table = self.read_raw(file_info.file_pos, table_len)?;
+            if let Some(key) = key {
+                decrypt_file_data(&mut table, key.wrapping_sub(1));
+            }
+            sector_offsets = table.chunks_exact(4).map(LittleEndian::read_u32).collect();
+            ranges = sector_offsets
+                .windows(2)
+                .map(|pair| {
+                    let end = (pair[1] as u64).min(block_size);
+                    ((pair[0] as u64).min(end), end)
+                })
+                .collect();
+        }
+
+        let mut sectors = Vec::with_capacity(ranges.len());
+        for (index, (start, end)) in ranges.into_iter().enumerate() {
+            let offset = file_info.file_pos + start;
+            sectors.push(RawSector {
+                index,
+                offset,
+                key: sector_key(index),
+                data: self.read_raw(offset, (end - start) as usize)?,
+            });
+        }
+
+        Ok(RawFile {
+            name: name.to_string(),
+            block_index: file_info.block_index,
+            file_pos: file_info.file_pos,
+            compressed_size: file_info.compressed_size,
+            file_size: file_info.file_size,
+            flags: file_info.flags,
+            key,
+            sector_offsets,
+            sectors,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ArchiveBuilder;
+    use crate::compression::flags;
+
+    fn sample_data() -> Vec<u8> {
+        (0..5_000u32)
+            .flat_map(|i| (i % 251).to_le_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn test_raw_sectors_decrypt_to_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("raw.mpq");
+        ArchiveBuilder::new()
+            .block_size(0)
+            .add_file_data_with_encryption(sample_data(), "data\\secret.bin", flags::ZLIB, true, 0)
+            .add_file_data(b"plain".to_vec(), "data\\plain.txt")
+            .build(&path)
+            .unwrap();
+
+        let mut archive = Archive::open(&path).unwrap();
+        assert_eq!(
+            archive.file_encryption_key("data\\plain.txt").unwrap(),
+            None
+        );
+        let info = archive.find_file("data\\secret.bin").unwrap().unwrap();
+        let expected = calculate_file_key(
+            "data\\secret.bin",
+            (info.file_pos - archive.archive_offset()) as u32,
+            info.file_size as u32,
+            true,
+        );
+        assert_eq!(
+            archive.file_encryption_key("data\\secret.bin").unwrap(),
+            Some(expected)
+        );
+
+        let raw = archive.read_raw_sectors("data\\secret.bin").unwrap();
+        assert_eq!(raw.key, Some(expected));
+        assert_eq!(raw.sectors.len(), 40);
+        assert_eq!(raw.sector_offsets.len(), 41);
+        assert_eq!(
+            raw.sectors[0].offset,
+            info.file_pos + raw.sector_offsets[0] as u64
+        );
+
+        // Decrypting and decompressing each sector gives back the file
+        let mut data = Vec::new();
+        for sector in raw.sectors {
+            let mut stored = sector.data;
+            decrypt_file_data(&mut stored, sector.key.unwrap());
+            let expected_size = (raw.file_size as usize - data.len()).min(512);
+            if stored.len() < expected_size {
+                data.extend(crate::decompress(&stored[1..], stored[0], expected_size).unwrap());
+            } else {
+                data.extend(stored);
+            }
+        }
+        assert_eq!(data, sample_data());
+    }
+
+    #[test]
+    fn test_raw_sectors_missing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("raw.mpq");
+        ArchiveBuilder::new()
+            .add_file_data(b"plain".to_vec(), "plain.txt")
+            .build(&path)
+            .unwrap();
+
+        let mut archive = Archive::open(&path).unwrap();
+        assert!(matches!(
+            archive.read_raw_sectors("missing.txt"),
+            Err(Error::FileNotFound(_))
+        ));
+        let raw = archive.read_raw_sectors("plain.txt").unwrap();
+        let stored: Vec<u8> = raw.sectors.into_iter().flat_map(|s| s.data).collect();
+        assert_eq!(stored.len() as u64, raw.compressed_size);
+    }
+}
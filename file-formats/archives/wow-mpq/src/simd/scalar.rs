@@ -18,6 +18,117 @@ pub fn crc32_scalar(data: &[u8], crc: u32) -> u32 {
     hasher.finish() as u32
 }
 
+/// Slicing-by-8 lookup tables for the reflected CRC32 polynomial (0xEDB88320)
+static CRC32_SLICE_TABLES: [[u32; 256]; 8] = build_crc32_slice_tables();
+
+const fn build_crc32_slice_tables() -> [[u32; 256]; 8] {
+    let mut tables = [[0u32; 256]; 8];
+
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB88320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        tables[0][i] = crc;
+        i += 1;
+    }
+
+    let mut slice = 1;
+    while slice < 8 {
+        let mut i = 0;
+        while i < 256 {
+            let prev = tables[slice - 1][i];
+            tables[slice][i] = (prev >> 8) ^ tables[0][(prev & 0xFF) as usize];
+            i += 1;
+        }
+        slice += 1;
+    }
+
+    tables
+}
+
+/// Number of buffers advanced together by the interleaved CRC32 loop
+pub const CRC32_INTERLEAVE_LANES: usize = 4;
+
+/// Advance a raw (non-inverted) CRC32 state by one 8-byte word
+#[inline(always)]
+fn crc32_slice8_step(state: u32, chunk: &[u8]) -> u32 {
+    let t = &CRC32_SLICE_TABLES;
+    let lo = state ^ u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+    let hi = u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]);
+
+    t[7][(lo & 0xFF) as usize]
+        ^ t[6][((lo >> 8) & 0xFF) as usize]
+        ^ t[5][((lo >> 16) & 0xFF) as usize]
+        ^ t[4][(lo >> 24) as usize]
+        ^ t[3][(hi & 0xFF) as usize]
+        ^ t[2][((hi >> 8) & 0xFF) as usize]
+        ^ t[1][((hi >> 16) & 0xFF) as usize]
+        ^ t[0][(hi >> 24) as usize]
+}
+
+/// Return buffer indices ordered by length
+///
+/// Grouping buffers of similar size keeps interleaved lanes busy for as long
+/// as possible before the shorter ones run out of data.
+pub(super) fn indices_by_length(buffers: &[&[u8]]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..buffers.len()).collect();
+    order.sort_by_key(|&i| buffers[i].len());
+    order
+}
+
+/// Interleaved multi-buffer CRC32 (fallback)
+///
+/// Advances up to [`CRC32_INTERLEAVE_LANES`] buffers in lockstep using
+/// slicing-by-8 tables. The per-buffer CRC chains are independent, so the CPU
+/// can overlap their table lookups instead of stalling on a single dependency
+/// chain. Results are returned in input order and match [`crc32_scalar`].
+pub fn crc32_multi_scalar(buffers: &[&[u8]], initial: u32) -> Vec<u32> {
+    let mut results = vec![0u32; buffers.len()];
+    let order = indices_by_length(buffers);
+
+    for group in order.chunks(CRC32_INTERLEAVE_LANES) {
+        let mut states = [!initial; CRC32_INTERLEAVE_LANES];
+
+        // The shortest buffer in the group bounds the interleaved section
+        let common = buffers[group[0]].len() & !7;
+
+        let mut offset = 0;
+        while offset < common {
+            for (lane, &index) in group.iter().enumerate() {
+                states[lane] = crc32_slice8_step(states[lane], &buffers[index][offset..offset + 8]);
+            }
+            offset += 8;
+        }
+
+        // Finish each buffer's remaining bytes on its own
+        for (lane, &index) in group.iter().enumerate() {
+            results[index] = crc32_scalar(&buffers[index][common..], !states[lane]);
+        }
+    }
+
+    results
+}
+
+/// Multi-buffer MD5 (fallback)
+///
+/// Hashes each buffer in turn. Results are returned in input order.
+pub fn md5_multi_scalar(buffers: &[&[u8]]) -> Vec<[u8; 16]> {
+    use md5::{Digest, Md5};
+
+    buffers
+        .iter()
+        .map(|buffer| Md5::digest(buffer).into())
+        .collect()
+}
+
 /// Scalar MPQ hash implementation (fallback)
 ///
 /// This is the reference implementation that matches the crypto/hash.rs module.
@@ -267,6 +378,37 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_crc32_multi_scalar_matches_single() {
+        let buffers: Vec<Vec<u8>> = (0..11)
+            .map(|i| (0..(i * 37 + 3)).map(|b| (b * 7 + i) as u8).collect())
+            .collect();
+        let refs: Vec<&[u8]> = buffers.iter().map(|b| b.as_slice()).collect();
+
+        for initial in [0, 0x12345678] {
+            let results = crc32_multi_scalar(&refs, initial);
+            assert_eq!(results.len(), refs.len());
+
+            for (buffer, crc) in refs.iter().zip(results) {
+                assert_eq!(
+                    crc,
+                    crc32_scalar(buffer, initial),
+                    "Interleaved CRC32 mismatch for {} bytes",
+                    buffer.len()
+                );
+            }
+        }
+
+        assert!(crc32_multi_scalar(&[], 0).is_empty());
+    }
+
+    #[test]
+    fn test_md5_multi_scalar() {
+        let results = md5_multi_scalar(&[b"", b"abc"]);
+        assert_eq!(hex::encode(results[0]), "d41d8cd98f00b204e9800998ecf8427e");
+        assert_eq!(hex::encode(results[1]), "900150983cd24fb0d6963f7d28e17f72");
+    }
+
     #[test]
     fn test_edge_cases() {
         // Empty data
@@ -246,6 +246,198 @@ unsafe fn jenkins_hash_scalar_optimized(filename: &str) -> u64 {
     hash
 }
 
+/// Number of independent MD5 streams processed by one AVX2 pass
+const MD5_LANES: usize = 8;
+
+/// MD5 per-round additive constants
+const MD5_K: [u32; 64] = [
+    0xd76aa478, 0xe8c7b756, 0x242070db, 0xc1bdceee, 0xf57c0faf, 0x4787c62a, 0xa8304613, 0xfd469501,
+    0x698098d8, 0x8b44f7af, 0xffff5bb1, 0x895cd7be, 0x6b901122, 0xfd987193, 0xa679438e, 0x49b40821,
+    0xf61e2562, 0xc040b340, 0x265e5a51, 0xe9b6c7aa, 0xd62f105d, 0x02441453, 0xd8a1e681, 0xe7d3fbc8,
+    0x21e1cde6, 0xc33707d6, 0xf4d50d87, 0x455a14ed, 0xa9e3e905, 0xfcefa3f8, 0x676f02d9, 0x8d2a4c8a,
+    0xfffa3942, 0x8771f681, 0x6d9d6122, 0xfde5380c, 0xa4beea44, 0x4bdecfa9, 0xf6bb4b60, 0xbebfbc70,
+    0x289b7ec6, 0xeaa127fa, 0xd4ef3085, 0x04881d05, 0xd9d4d039, 0xe6db99e5, 0x1fa27cf8, 0xc4ac5665,
+    0xf4292244, 0x432aff97, 0xab9423a7, 0xfc93a039, 0x655b59c3, 0x8f0ccc92, 0xffeff47d, 0x85845dd1,
+    0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1, 0xf7537e82, 0xbd3af235, 0x2ad7d2bb, 0xeb86d391,
+];
+
+/// MD5 per-round left-rotate amounts
+const MD5_S: [i32; 64] = [
+    7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 5, 9, 14, 20, 5, 9, 14, 20, 5, 9,
+    14, 20, 5, 9, 14, 20, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 6, 10, 15,
+    21, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21,
+];
+
+/// MD5 initial chaining values (A, B, C, D)
+const MD5_INIT: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+
+/// Per-lane view of a message as a sequence of 64-byte MD5 blocks
+///
+/// Full blocks are read straight from the input; the final one or two blocks
+/// carry the MD5 padding and bit length and are built once up front.
+struct Md5Lane<'a> {
+    data: &'a [u8],
+    full_blocks: usize,
+    tail: Vec<u8>,
+}
+
+impl<'a> Md5Lane<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        let full_blocks = data.len() / 64;
+        let remainder = &data[full_blocks * 64..];
+
+        let mut tail = Vec::with_capacity(128);
+        tail.extend_from_slice(remainder);
+        tail.push(0x80);
+        while tail.len() % 64 != 56 {
+            tail.push(0);
+        }
+        tail.extend_from_slice(&((data.len() as u64).wrapping_mul(8)).to_le_bytes());
+
+        Self {
+            data,
+            full_blocks,
+            tail,
+        }
+    }
+
+    fn block_count(&self) -> usize {
+        self.full_blocks + self.tail.len() / 64
+    }
+
+    fn block(&self, index: usize) -> &[u8] {
+        if index < self.full_blocks {
+            &self.data[index * 64..index * 64 + 64]
+        } else {
+            let start = (index - self.full_blocks) * 64;
+            &self.tail[start..start + 64]
+        }
+    }
+}
+
+/// AVX2 multi-buffer MD5
+///
+/// Runs eight independent MD5 computations side by side, one per 32-bit lane
+/// of a 256-bit register. MD5 is strictly serial within a single message, so
+/// this is the only way to vectorize it; throughput scales with the number of
+/// buffers in flight rather than with buffer length. Buffers are grouped by
+/// length so lanes finish at roughly the same time. Results are returned in
+/// input order.
+///
+/// # Safety
+///
+/// This function must only be called when AVX2 support has been
+/// verified through runtime detection.
+#[target_feature(enable = "avx2")]
+pub(super) unsafe fn md5_multi_avx2(buffers: &[&[u8]]) -> Vec<[u8; 16]> {
+    let mut results = vec![[0u8; 16]; buffers.len()];
+    let order = super::scalar::indices_by_length(buffers);
+
+    for group in order.chunks(MD5_LANES) {
+        let lanes: Vec<Md5Lane<'_>> = group.iter().map(|&i| Md5Lane::new(buffers[i])).collect();
+        let digests = unsafe { md5_lanes_avx2(&lanes) };
+
+        for (&index, digest) in group.iter().zip(digests) {
+            results[index] = digest;
+        }
+    }
+
+    results
+}
+
+/// Hash up to eight prepared lanes with a single AVX2 pass
+#[target_feature(enable = "avx2")]
+unsafe fn md5_lanes_avx2(lanes: &[Md5Lane<'_>]) -> Vec<[u8; 16]> {
+    let block_counts: Vec<usize> = lanes.iter().map(Md5Lane::block_count).collect();
+    let max_blocks = block_counts.iter().copied().max().unwrap_or(0);
+
+    let mut state = [
+        _mm256_set1_epi32(MD5_INIT[0] as i32),
+        _mm256_set1_epi32(MD5_INIT[1] as i32),
+        _mm256_set1_epi32(MD5_INIT[2] as i32),
+        _mm256_set1_epi32(MD5_INIT[3] as i32),
+    ];
+    let all_ones = _mm256_set1_epi32(-1);
+
+    for block in 0..max_blocks {
+        // Transpose the current block of every lane into word-major order
+        let mut words = [[0u32; MD5_LANES]; 16];
+        let mut active = [0i32; MD5_LANES];
+        for (lane, md5_lane) in lanes.iter().enumerate() {
+            if block < block_counts[lane] {
+                active[lane] = -1;
+                for (word, bytes) in md5_lane.block(block).chunks_exact(4).enumerate() {
+                    words[word][lane] =
+                        u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                }
+            }
+        }
+
+        let mut m = [_mm256_setzero_si256(); 16];
+        for (vector, word) in m.iter_mut().zip(words.iter()) {
+            *vector = unsafe { _mm256_loadu_si256(word.as_ptr() as *const __m256i) };
+        }
+
+        let [mut a, mut b, mut c, mut d] = state;
+
+        for round in 0..64 {
+            let (f, g) = match round / 16 {
+                0 => (
+                    _mm256_or_si256(_mm256_and_si256(b, c), _mm256_andnot_si256(b, d)),
+                    round,
+                ),
+                1 => (
+                    _mm256_or_si256(_mm256_and_si256(d, b), _mm256_andnot_si256(d, c)),
+                    (5 * round + 1) % 16,
+                ),
+                2 => (
+                    _mm256_xor_si256(_mm256_xor_si256(b, c), d),
+                    (3 * round + 5) % 16,
+                ),
+                _ => (
+                    _mm256_xor_si256(c, _mm256_or_si256(b, _mm256_xor_si256(d, all_ones))),
+                    (7 * round) % 16,
+                ),
+            };
+
+            let sum = _mm256_add_epi32(
+                _mm256_add_epi32(f, a),
+                _mm256_add_epi32(_mm256_set1_epi32(MD5_K[round] as i32), m[g]),
+            );
+            let rotated = _mm256_or_si256(
+                _mm256_sll_epi32(sum, _mm_cvtsi32_si128(MD5_S[round])),
+                _mm256_srl_epi32(sum, _mm_cvtsi32_si128(32 - MD5_S[round])),
+            );
+
+            a = d;
+            d = c;
+            c = b;
+            b = _mm256_add_epi32(b, rotated);
+        }
+
+        // Lanes that already consumed all their blocks keep their state
+        let mask = unsafe { _mm256_loadu_si256(active.as_ptr() as *const __m256i) };
+        for (chain, value) in state.iter_mut().zip([a, b, c, d]) {
+            *chain = _mm256_blendv_epi8(*chain, _mm256_add_epi32(*chain, value), mask);
+        }
+    }
+
+    let mut chains = [[0u32; MD5_LANES]; 4];
+    for (out, vector) in chains.iter_mut().zip(state.iter()) {
+        unsafe { _mm256_storeu_si256(out.as_mut_ptr() as *mut __m256i, *vector) };
+    }
+
+    (0..lanes.len())
+        .map(|lane| {
+            let mut digest = [0u8; 16];
+            for (word, chain) in chains.iter().enumerate() {
+                digest[word * 4..word * 4 + 4].copy_from_slice(&chain[lane].to_le_bytes());
+            }
+            digest
+        })
+        .collect()
+}
+
 /// Vectorized character normalization for batch string processing
 ///
 /// Uses AVX2 to normalize path separators and case simultaneously.
@@ -434,6 +626,26 @@ mod tests {
         }
     }
 
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_md5_multi_avx2_matches_reference() {
+        if is_x86_feature_detected!("avx2") {
+            // Lengths straddle the 55/56/64 byte padding boundaries
+            let buffers: Vec<Vec<u8>> = [0, 1, 55, 56, 63, 64, 65, 119, 120, 128, 1000, 4097, 3]
+                .iter()
+                .map(|&len| (0..len).map(|b| (b * 13 + len) as u8).collect())
+                .collect();
+            let refs: Vec<&[u8]> = buffers.iter().map(|b| b.as_slice()).collect();
+
+            let simd_results = unsafe { md5_multi_avx2(&refs) };
+            let scalar_results = super::super::scalar::md5_multi_scalar(&refs);
+
+            for ((buffer, simd), scalar) in refs.iter().zip(simd_results).zip(scalar_results) {
+                assert_eq!(simd, scalar, "AVX2 MD5 mismatch for {} bytes", buffer.len());
+            }
+        }
+    }
+
     #[cfg(target_arch = "x86_64")]
     #[test]
     fn test_normalization() {
@@ -38,6 +38,17 @@ pub fn get_special_file_info(filename: &str) -> Option<SpecialFileInfo> {
     }
 }
 
+/// Whether `name` is a special file holding archive bookkeeping rather than
+/// content
+///
+/// Tools comparing or listing the contents of archives skip these.
+pub fn is_special_file(name: &str) -> bool {
+    matches!(
+        name,
+        "(listfile)" | "(attributes)" | "(signature)" | "(patch_metadata)" | "(user data)"
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -54,4 +65,12 @@ mod tests {
         assert!(!info.encrypted);
         assert!(info.compressed);
     }
+
+    #[test]
+    fn test_is_special_file() {
+        assert!(is_special_file("(listfile)"));
+        assert!(is_special_file("(patch_metadata)"));
+        assert!(!is_special_file("(listfile).txt"));
+        assert!(!is_special_file("Interface\\(listfile)"));
+    }
 }
@@ -1,6 +1,8 @@
 //! (listfile) parsing functionality
 
 use crate::Result;
+use crate::path::{canonical_path, path_key};
+use std::collections::HashSet;
 
 /// Parse a (listfile) into individual filenames
 ///
@@ -49,6 +51,204 @@ pub fn parse_listfile(data: &[u8]) -> Result<Vec<String>> {
     Ok(files)
 }
 
+/// Parse a user-supplied listfile
+///
+/// Accepts plain listfiles, parsed like a (listfile), and community
+/// `FileDataID;path` CSV listfiles, whose paths are returned with MPQ-style
+/// backslash separators. Both kinds of line may be mixed.
+pub fn parse_external_listfile(data: &[u8]) -> Result<Vec<String>> {
+    let content = String::from_utf8_lossy(data);
+    let files: Vec<String> = content
+        .lines()
+        .flat_map(|line| match csv_path(line) {
+            Some(path) => vec![path],
+            None => parse_listfile(line.as_bytes()).unwrap_or_default(),
+        })
+        .collect();
+
+    log::debug!("Parsed {} files from external listfile", files.len());
+    Ok(files)
+}
+
+/// Path of a `FileDataID;path` line
+fn csv_path(line: &str) -> Option<String> {
+    let (id, path) = line.trim().split_once(';')?;
+    id.trim().parse::<u32>().ok()?;
+    let path = path.trim();
+    (!path.is_empty()).then(|| path.replace('/', "\\"))
+}
+
+/// Separator used by [`Listfile::normalize`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PathSeparator {
+    /// `\`, as stored in archives
+    #[default]
+    Backslash,
+    /// `/`, as in most community listfiles
+    Slash,
+}
+
+/// Case applied by [`Listfile::normalize`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NameCase {
+    /// Keep the spelling of each name
+    #[default]
+    Keep,
+    /// ASCII uppercase, the case of MPQ name hashes
+    Upper,
+    /// ASCII lowercase, as in most community listfiles
+    Lower,
+}
+
+/// An ordered list of file names
+///
+/// Combines listfiles from several sources: an archive's `(listfile)`,
+/// community listfiles in plain or `FileDataID;path` form, or names
+/// collected elsewhere. Names that differ only in case or separators are
+/// duplicates, as they name the same archive file.
+///
+/// ```
+/// use wow_mpq::special_files::{Listfile, NameCase, PathSeparator};
+///
+/// let mut listfile = Listfile::parse(b"Interface\\Icons\\Heal.blp\r\n")?;
+/// listfile.merge(&Listfile::parse(b"53;interface/icons/heal.blp\n54;world/lights.lit")?);
+/// assert_eq!(listfile.len(), 2);
+///
+/// listfile.normalize(PathSeparator::Slash, NameCase::Lower);
+/// assert_eq!(listfile.names(), ["interface/icons/heal.blp", "world/lights.lit"]);
+/// # Ok::<(), wow_mpq::Error>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Listfile {
+    names: Vec<String>,
+}
+
+impl Listfile {
+    /// Create an empty listfile
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse a plain or `FileDataID;path` listfile, as
+    /// [`parse_external_listfile`] does
+    ///
+    /// Duplicates are kept; see [`Listfile::dedup`].
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        Ok(Self {
+            names: parse_external_listfile(data)?,
+        })
+    }
+
+    /// Names in order
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Number of names, including duplicates
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Whether the listfile has no names
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Whether the listfile names `name`, ignoring case and separators
+    pub fn contains(&self, name: &str) -> bool {
+        let key = path_key(name);
+        self.names.iter().any(|listed| path_key(listed) == key)
+    }
+
+    /// Append a name, even if it is already listed
+    pub fn push(&mut self, name: impl Into<String>) {
+        self.names.push(name.into());
+    }
+
+    /// Append the names of `other` that are not listed yet
+    ///
+    /// Names already listed keep their spelling, so earlier sources win.
+    /// Returns the number of names added.
+    pub fn merge(&mut self, other: &Listfile) -> usize {
+        let mut seen: HashSet<String> = self.names.iter().map(|name| path_key(name)).collect();
+        let before = self.names.len();
+        for name in &other.names {
+            if seen.insert(path_key(name)) {
+                self.names.push(name.clone());
+            }
+        }
+        self.names.len() - before
+    }
+
+    /// Remove names that are listed earlier, keeping the first spelling
+    ///
+    /// Returns the number of names removed.
+    pub fn dedup(&mut self) -> usize {
+        let mut seen = HashSet::new();
+        let before = self.names.len();
+        self.names.retain(|name| seen.insert(path_key(name)));
+        before - self.names.len()
+    }
+
+    /// Rewrite every name in canonical form with `separator` and `case`
+    ///
+    /// Repeated separators and `.` components are dropped as by
+    /// [`canonical_path`]. Duplicates are kept; see [`Listfile::dedup`].
+    pub fn normalize(&mut self, separator: PathSeparator, case: NameCase) {
+        for name in &mut self.names {
+            let mut normalized = canonical_path(name);
+            if separator == PathSeparator::Slash {
+                normalized = normalized.replace('\\', "/");
+            }
+            match case {
+                NameCase::Keep => {}
+                NameCase::Upper => normalized.make_ascii_uppercase(),
+                NameCase::Lower => normalized.make_ascii_lowercase(),
+            }
+            *name = normalized;
+        }
+        self.names.retain(|name| !name.is_empty());
+    }
+
+    /// Sort names case-insensitively
+    pub fn sort(&mut self) {
+        self.names.sort_by_cached_key(|name| path_key(name));
+    }
+
+    /// Contents of a `(listfile)`: one name per line, with CRLF line endings
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut content = String::new();
+        for name in &self.names {
+            content.push_str(name);
+            content.push_str("\r\n");
+        }
+        content.into_bytes()
+    }
+}
+
+impl FromIterator<String> for Listfile {
+    fn from_iter<I: IntoIterator<Item = String>>(iter: I) -> Self {
+        Self {
+            names: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl Extend<String> for Listfile {
+    fn extend<I: IntoIterator<Item = String>>(&mut self, iter: I) {
+        self.names.extend(iter);
+    }
+}
+
+impl IntoIterator for Listfile {
+    type Item = String;
+    type IntoIter = std::vec::IntoIter<String>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.names.into_iter()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -124,4 +324,64 @@ mod tests {
         assert_eq!(files[1], "file2.dat");
         assert_eq!(files[2], "file3.bin");
     }
+
+    #[test]
+    fn test_listfile_merge_and_dedup() {
+        let mut listfile = Listfile::parse(b"Units\\Orc.mdx\nunits/orc.mdx\nWar3map.j").unwrap();
+        assert_eq!(listfile.len(), 3);
+        assert_eq!(listfile.dedup(), 1);
+        assert_eq!(listfile.names(), ["Units\\Orc.mdx", "War3map.j"]);
+
+        let other = Listfile::parse(b"WAR3MAP.J\n12;units/human.mdx").unwrap();
+        assert_eq!(listfile.merge(&other), 1);
+        assert_eq!(
+            listfile.names(),
+            ["Units\\Orc.mdx", "War3map.j", "units\\human.mdx"]
+        );
+        assert!(listfile.contains("UNITS/ORC.MDX"));
+        assert!(!listfile.contains("units\\undead.mdx"));
+    }
+
+    #[test]
+    fn test_listfile_normalize() {
+        let mut listfile: Listfile = ["./World//Maps/Azeroth.wdt", "Sound\\Music", "/"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        listfile.normalize(PathSeparator::Backslash, NameCase::Upper);
+        assert_eq!(
+            listfile.names(),
+            ["WORLD\\MAPS\\AZEROTH.WDT", "SOUND\\MUSIC"]
+        );
+
+        listfile.normalize(PathSeparator::Slash, NameCase::Lower);
+        listfile.sort();
+        assert_eq!(listfile.names(), ["sound/music", "world/maps/azeroth.wdt"]);
+    }
+
+    #[test]
+    fn test_listfile_round_trip() {
+        let listfile = Listfile::parse(b"a.txt\r\nDir\\b.txt\r\n").unwrap();
+        let bytes = listfile.to_bytes();
+        assert_eq!(bytes, b"a.txt\r\nDir\\b.txt\r\n");
+        assert_eq!(Listfile::parse(&bytes).unwrap(), listfile);
+        assert!(Listfile::new().to_bytes().is_empty());
+    }
+
+    #[test]
+    fn test_parse_external_listfile() {
+        let content = b"53;Cameras/FlyBy.m2\n\
+                       ; comment\n\
+                       Interface\\Icons\\Spell_Nature_Heal.blp\n\
+                       glue.toc;metadata";
+        let files = parse_external_listfile(content).unwrap();
+        assert_eq!(
+            files,
+            [
+                "Cameras\\FlyBy.m2",
+                "Interface\\Icons\\Spell_Nature_Heal.blp",
+                "glue.toc"
+            ]
+        );
+    }
 }
@@ -5,5 +5,5 @@ mod info;
 mod listfile;
 
 pub use attributes::{AttributeFlags, Attributes, FileAttributes};
-pub use info::{SpecialFileInfo, get_special_file_info};
-pub use listfile::parse_listfile;
+pub use info::{SpecialFileInfo, get_special_file_info, is_special_file};
+pub use listfile::{Listfile, NameCase, PathSeparator, parse_external_listfile, parse_listfile};
@@ -0,0 +1,128 @@
+//! Storage-independent access to game files
+//!
+//! Clients up to Mists of Pandaria keep their files in MPQ archives; later
+//! ones use CASC. [`Storage`] is the part tools need from either: open the
+//! storage, read a file by its client path and list what it holds. It is
+//! implemented by [`Archive`] for a single MPQ and by [`PatchChain`] for a
+//! client installation, and code written against `&mut dyn Storage` or a
+//! `S: Storage` bound works unchanged with other backends.
+//!
+//! ```no_run
+//! use wow_mpq::{PatchChain, Storage};
+//!
+//! fn map_table(storage: &mut dyn Storage) -> wow_mpq::Result<Option<Vec<u8>>> {
+//!     storage.read_if_exists("DBFilesClient\\Map.dbc")
+//! }
+//!
+//! let mut chain = PatchChain::open("/games/WoW-3.3.5a".as_ref())?;
+//! let data = map_table(&mut chain)?;
+//! # Ok::<(), wow_mpq::Error>(())
+//! ```
+
+use crate::{Archive, Error, GameVersion, PatchChain, Result};
+use std::path::Path;
+
+/// A file a [`Storage`] can name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StorageEntry {
+    /// Client path, with backslash separators
+    pub name: String,
+    /// Uncompressed size in bytes
+    pub size: u64,
+}
+
+/// Read access to the files of an archive or client installation
+///
+/// Paths are client paths such as `World\Maps\Azeroth\Azeroth.wdt`; either
+/// separator and any case are accepted.
+pub trait Storage {
+    /// Open the storage at `path`
+    fn open(path: &Path) -> Result<Self>
+    where
+        Self: Sized;
+
+    /// Read a file
+    ///
+    /// Fails with [`Error::FileNotFound`] if the storage does not hold it.
+    fn read(&mut self, name: &str) -> Result<Vec<u8>>;
+
+    /// Whether the storage holds a file
+    ///
+    /// Backends may only know the names they can list; reading a file is the
+    /// definitive check, see [`Storage::read_if_exists`].
+    fn contains(&self, name: &str) -> bool;
+
+    /// Files the storage holds, with the sizes they read as
+    ///
+    /// Storages identify files by hash, so only files whose names are known,
+    /// for MPQs from their `(listfile)`, can be listed.
+    fn list(&mut self) -> Result<Vec<StorageEntry>>;
+
+    /// Read a file, or return `None` if the storage does not hold it
+    fn read_if_exists(&mut self, name: &str) -> Result<Option<Vec<u8>>> {
+        match self.read(name) {
+            Ok(data) => Ok(Some(data)),
+            Err(Error::FileNotFound(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl Storage for Archive {
+    fn open(path: &Path) -> Result<Self> {
+        Archive::open(path)
+    }
+
+    fn read(&mut self, name: &str) -> Result<Vec<u8>> {
+        self.read_file(name)
+    }
+
+    fn contains(&self, name: &str) -> bool {
+        matches!(self.find_file(name), Ok(Some(_)))
+    }
+
+    fn list(&mut self) -> Result<Vec<StorageEntry>> {
+        let entries = Archive::list(self)?;
+        // Without a listfile, entries carry placeholder names
+        Ok(entries
+            .into_iter()
+            .filter(|entry| Storage::contains(self, &entry.name))
+            .map(|entry| StorageEntry {
+                name: entry.name,
+                size: entry.size,
+            })
+            .collect())
+    }
+}
+
+/// Opens a client installation or its `Data` directory, detecting the
+/// client version and locale
+impl Storage for PatchChain {
+    fn open(path: &Path) -> Result<Self> {
+        let version = GameVersion::detect(path).ok_or_else(|| {
+            Error::InvalidFormat(format!("No client archives found in {}", path.display()))
+        })?;
+        PatchChain::from_game_dir(path, version)
+    }
+
+    fn read(&mut self, name: &str) -> Result<Vec<u8>> {
+        self.read_file(name)
+    }
+
+    fn contains(&self, name: &str) -> bool {
+        self.contains_file(name)
+    }
+
+    fn list(&mut self) -> Result<Vec<StorageEntry>> {
+        let entries = PatchChain::list(self)?;
+        // Archives without a listfile contribute placeholder names
+        Ok(entries
+            .into_iter()
+            .filter(|entry| self.contains_file(&entry.name))
+            .map(|entry| StorageEntry {
+                name: entry.name,
+                size: entry.size,
+            })
+            .collect())
+    }
+}
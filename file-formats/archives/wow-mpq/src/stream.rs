@@ -0,0 +1,388 @@
+//! Streaming file reads
+//!
+//! [`Archive::open_file_stream`] returns a [`FileStream`], a [`Read`] +
+//! [`Seek`] handle that decrypts and decompresses one sector at a time as it
+//! is read. Only the current sector is kept in memory, so large files can be
+//! copied or scanned without holding the whole file.
+//!
+//! Files stored as a single unit have only one "sector" and are decoded whole
+//! on first read.
+//!
+//! A stream that owns its archive, from [`Archive::into_file_stream`], can in
+//! turn be the source of another archive; [`Archive::open_nested`] uses this
+//! to read archives stored inside archives.
+//!
+//! ```no_run
+//! use std::io::{Seek, SeekFrom};
+//! use wow_mpq::Archive;
+//!
+//! let mut archive = Archive::open("patch.mpq")?;
+//! let mut stream = archive.open_file_stream("World\\Maps\\Azeroth\\Azeroth.wdt")?;
+//! stream.seek(SeekFrom::Start(8))?;
+//! let mut out = std::fs::File::create("Azeroth.wdt.tail")?;
+//! std::io::copy(&mut stream, &mut out)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::io::{self, Read, Seek, SeekFrom};
+
+use crate::archive::{Archive, FileInfo, OpenOptions, decode_sector, decrypt_file_data};
+use crate::read_cache::SectorKey;
+use crate::{Error, Result};
+
+/// Lazily decoded contents of an archived file
+///
+/// Created by [`Archive::open_file_stream`], in which case the stream borrows
+/// the archive mutably for as long as it lives, or by
+/// [`Archive::into_file_stream`], in which case it owns the archive.
+#[derive(Debug)]
+pub struct FileStream<'a> {
+    archive: ArchiveHandle<'a>,
+    name: String,
+    info: FileInfo,
+    key: u32,
+    sector_size: usize,
+    /// Sector offset table of compressed sectored files
+    sector_offsets: Option<Vec<u32>>,
+    position: u64,
+    /// Index and decoded bytes of the most recently read sector
+    sector: Option<(usize, Vec<u8>)>,
+}
+
+/// Archive a stream reads from
+#[derive(Debug)]
+enum ArchiveHandle<'a> {
+    Borrowed(&'a mut Archive),
+    Owned(Box<Archive>),
+}
+
+impl ArchiveHandle<'_> {
+    fn get(&mut self) -> &mut Archive {
+        match self {
+            ArchiveHandle::Borrowed(archive) => archive,
+            ArchiveHandle::Owned(archive) => archive,
+        }
+    }
+}
+
+impl Archive {
+    /// Open a file for streaming reads
+    ///
+    /// Unlike [`Archive::read_file`], which returns the whole file, the
+    /// returned stream decodes sectors only as they are read. Patch files
+    /// are rejected, as they are by `read_file`.
+    pub fn open_file_stream(&mut self, name: &str) -> Result<FileStream<'_>> {
+        FileStream::new(ArchiveHandle::Borrowed(self), name)
+    }
+
+    /// Open a file for streaming reads, handing the archive over to the stream
+    ///
+    /// The stream has no borrow to outlive, so it can be stored, sent to
+    /// another thread or used as the source of another archive.
+    pub fn into_file_stream(self, name: &str) -> Result<FileStream<'static>> {
+        FileStream::new(ArchiveHandle::Owned(Box::new(self)), name)
+    }
+
+    /// Open an archive stored as a file of this one
+    ///
+    /// Installers and patches often ship whole archives inside another, like
+    /// `base.mpq` inside an installer's archive. The nested archive is read
+    /// through a [`FileStream`], so nothing is extracted to disk or held in
+    /// memory beyond the current sector. This archive is reopened from
+    /// [`Archive::path`] for the purpose, which fails for archives read with
+    /// [`Archive::from_reader`]; use [`Archive::into_nested`] for those.
+    pub fn open_nested(&self, name: &str) -> Result<Archive> {
+        if self.path().as_os_str().is_empty() {
+            return Err(Error::OperationNotSupported {
+                version: self.header().format_version as u16,
+                operation: format!("Reopening an archive without a path to read '{name}'"),
+            });
+        }
+        let outer = OpenOptions::new()
+            .path_policy(self.path_policy())
+            .open(self.path())?;
+        outer.into_nested(name)
+    }
+
+    /// Open an archive stored as a file of this one, consuming this archive
+    ///
+    /// See [`Archive::open_nested`]. The nested archive has no path of its
+    /// own, so archives nested deeper are opened with this method as well.
+    pub fn into_nested(self, name: &str) -> Result<Archive> {
+        let path_policy = self.path_policy();
+        let stream = self.into_file_stream(name)?;
+        Archive::from_reader_with_options(stream, OpenOptions::new().path_policy(path_policy))
+    }
+}
+
+impl<'a> FileStream<'a> {
+    fn new(mut handle: ArchiveHandle<'a>, name: &str) -> Result<Self> {
+        let archive = handle.get();
+        let info = archive
+            .find_file(name)?
+            .ok_or_else(|| Error::FileNotFound(name.to_string()))?;
+        if info.is_patch_file() {
+            return Err(Error::OperationNotSupported {
+                version: archive.header().format_version as u16,
+                operation: format!("Streaming patch file '{name}'"),
+            });
+        }
+        let key = archive.file_key(name, &info, info.file_size as u32);
+
+        let (sector_size, sector_offsets) = if info.is_single_unit() {
+            (info.file_size.max(1) as usize, None)
+        } else if info.is_compressed() {
+            let offsets = archive.read_sector_offsets(&info, key)?;
+            (archive.header().sector_size(), Some(offsets))
+        } else {
+            (archive.header().sector_size(), None)
+        };
+
+        Ok(FileStream {
+            archive: handle,
+            name: name.to_string(),
+            info,
+            key,
+            sector_size,
+            sector_offsets,
+            position: 0,
+            sector: None,
+        })
+    }
+
+    /// Uncompressed size of the file
+    pub fn len(&self) -> u64 {
+        self.info.file_size
+    }
+
+    /// Whether the file is empty
+    pub fn is_empty(&self) -> bool {
+        self.info.file_size == 0
+    }
+
+    /// Current read position
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Decode sector `index` unless it is the cached one
+    fn load_sector(&mut self, index: usize) -> Result<()> {
+        if matches!(self.sector, Some((current, _)) if current == index) {
+            return Ok(());
+        }
+
+        let start = index * self.sector_size;
+        let expected_size = (self.info.file_size as usize - start).min(self.sector_size);
+        let data = if self.info.is_single_unit() {
+            self.archive
+                .get()
+                .read_file_with_info(&self.name, &self.info)?
+        } else if let Some(offsets) = &self.sector_offsets {
+            let (begin, end) = match (offsets.get(index), offsets.get(index + 1)) {
+                (Some(&begin), Some(&end)) if begin <= end => (begin as u64, end as u64),
+                _ => {
+                    return Err(Error::invalid_format(format!(
+                        "Invalid sector offsets for sector {index} of '{}'",
+                        self.name
+                    )));
+                }
+            };
+            let cache_key = SectorKey::sector(&self.info, self.key, index);
+            let archive = self.archive.get();
+            if let Some(sector) = archive.cached_data(&cache_key) {
+                sector
+            } else {
+                let mut stored =
+                    archive.read_raw(self.info.file_pos + begin, (end - begin) as usize)?;
+                let sector = decode_sector(&self.info, &mut stored, index, self.key, expected_size);
+                archive.cache_data(cache_key, &sector);
+                sector
+            }
+        } else {
+            // Uncompressed sectors are stored back to back without a table
+            let mut stored = self
+                .archive
+                .get()
+                .read_raw(self.info.file_pos + start as u64, expected_size)?;
+            if self.info.is_encrypted() {
+                decrypt_file_data(&mut stored, self.key.wrapping_add(index as u32));
+            }
+            stored
+        };
+
+        self.sector = Some((index, data));
+        Ok(())
+    }
+}
+
+impl Read for FileStream<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() || self.position >= self.info.file_size {
+            return Ok(0);
+        }
+        let index = (self.position / self.sector_size as u64) as usize;
+        self.load_sector(index).map_err(io::Error::other)?;
+        let Some((_, sector)) = &self.sector else {
+            return Ok(0);
+        };
+
+        let offset = (self.position - (index * self.sector_size) as u64) as usize;
+        let available = sector.get(offset..).unwrap_or_default();
+        let count = available.len().min(buf.len());
+        buf[..count].copy_from_slice(&available[..count]);
+        self.position += count as u64;
+        Ok(count)
+    }
+}
+
+impl Seek for FileStream<'_> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.info.file_size.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        };
+        let Some(target) = target else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            ));
+        };
+        self.position = target;
+        Ok(target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ArchiveBuilder;
+    use crate::compression::flags;
+
+    fn sample_data() -> Vec<u8> {
+        (0..5_000u32)
+            .flat_map(|i| (i % 251).to_le_bytes())
+            .collect()
+    }
+
+    fn build_archive(path: &std::path::Path) {
+        ArchiveBuilder::new()
+            .block_size(0)
+            .add_file_data_with_options(sample_data(), "data\\zlib.bin", flags::ZLIB, false, 0)
+            .add_file_data(sample_data()[..300].to_vec(), "data\\small.bin")
+            .add_file_data_with_encryption(sample_data(), "data\\secret.bin", flags::ZLIB, true, 0)
+            .add_file_data(Vec::new(), "data\\empty.bin")
+            .build(path)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_stream_reads_whole_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("stream.mpq");
+        build_archive(&path);
+
+        let mut archive = Archive::open(&path).unwrap();
+        for name in ["data\\zlib.bin", "data\\secret.bin"] {
+            let mut stream = archive.open_file_stream(name).unwrap();
+            assert_eq!(stream.len(), 20_000);
+            let mut streamed = Vec::new();
+            // Small reads cross sector boundaries
+            let mut buf = [0u8; 300];
+            loop {
+                let count = stream.read(&mut buf).unwrap();
+                if count == 0 {
+                    break;
+                }
+                streamed.extend_from_slice(&buf[..count]);
+            }
+            assert_eq!(streamed, sample_data(), "{name}");
+        }
+
+        // Single-unit files are one sector
+        let mut small = Vec::new();
+        let mut stream = archive.open_file_stream("data\\small.bin").unwrap();
+        stream.seek(SeekFrom::Start(100)).unwrap();
+        stream.read_to_end(&mut small).unwrap();
+        assert_eq!(small, &sample_data()[100..300]);
+
+        let mut stream = archive.open_file_stream("data\\empty.bin").unwrap();
+        assert!(stream.is_empty());
+        assert_eq!(stream.read(&mut [0u8; 16]).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_stream_seek() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("stream.mpq");
+        build_archive(&path);
+        let data = sample_data();
+
+        let mut archive = Archive::open(&path).unwrap();
+        let mut stream = archive.open_file_stream("data\\secret.bin").unwrap();
+        let mut buf = [0u8; 1000];
+
+        stream.seek(SeekFrom::Start(12_345)).unwrap();
+        stream.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf[..], &data[12_345..13_345]);
+
+        stream.seek(SeekFrom::Current(-5_000)).unwrap();
+        stream.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf[..], &data[8_345..9_345]);
+
+        assert_eq!(stream.seek(SeekFrom::End(-10)).unwrap(), 19_990);
+        let mut tail = Vec::new();
+        stream.read_to_end(&mut tail).unwrap();
+        assert_eq!(tail, &data[19_990..]);
+
+        assert!(stream.seek(SeekFrom::Current(-30_000)).is_err());
+        stream.seek(SeekFrom::Start(30_000)).unwrap();
+        assert_eq!(stream.read(&mut buf).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_open_nested() {
+        let dir = tempfile::tempdir().unwrap();
+        let inner_path = dir.path().join("inner.mpq");
+        ArchiveBuilder::new()
+            .add_file_data(sample_data(), "data\\inner.bin")
+            .build(&inner_path)
+            .unwrap();
+        let inner = std::fs::read(&inner_path).unwrap();
+
+        let path = dir.path().join("outer.mpq");
+        ArchiveBuilder::new()
+            .add_file_data_with_options(inner.clone(), "base.mpq", flags::ZLIB, false, 0)
+            .build(&path)
+            .unwrap();
+
+        let outer = Archive::open(&path).unwrap();
+        let mut nested = outer.open_nested("base.mpq").unwrap();
+        assert_eq!(nested.read_file("data\\inner.bin").unwrap(), sample_data());
+
+        // Archives without a path are handed over instead
+        let bytes_path = dir.path().join("bytes.mpq");
+        ArchiveBuilder::new()
+            .add_file_data(inner, "base.mpq")
+            .build(&bytes_path)
+            .unwrap();
+        let outer_bytes = std::fs::read(&bytes_path).unwrap();
+        let outer = Archive::from_reader(std::io::Cursor::new(outer_bytes)).unwrap();
+        assert!(outer.open_nested("base.mpq").is_err());
+        let mut nested = outer.into_nested("base.mpq").unwrap();
+        assert_eq!(nested.read_file("data\\inner.bin").unwrap(), sample_data());
+    }
+
+    #[test]
+    fn test_stream_missing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("stream.mpq");
+        build_archive(&path);
+
+        let mut archive = Archive::open(&path).unwrap();
+        assert!(matches!(
+            archive.open_file_stream("data\\missing.bin"),
+            Err(Error::FileNotFound(_))
+        ));
+    }
+}
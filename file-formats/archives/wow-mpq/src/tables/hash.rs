@@ -220,6 +220,9 @@ impl HashTable {
     }
 
     /// Find a file in the hash table
+    ///
+    /// Takes the entry of `locale` if the file has one, else its neutral
+    /// entry (locale 0), else its first entry of any locale.
     pub fn find_file(&self, filename: &str, locale: u16) -> Option<(usize, &HashEntry)> {
         // Calculate hash values
         let name_a = hash_string(filename, hash_type::NAME_A);
@@ -228,33 +231,38 @@ impl HashTable {
 
         let mut index = start_index & self.mask;
         let end_index = index;
+        let mut neutral = None;
+        let mut first = None;
 
-        // Linear probing to find the file
+        // Linear probing through every entry of the file
         loop {
             let entry = &self.entries[index];
 
             // Check if this is our file
-            if entry.name_1 == name_a && entry.name_2 == name_b {
-                // Check locale (0 = default/any locale)
-                if (locale == 0 || entry.locale == 0 || entry.locale == locale) && entry.is_valid()
-                {
+            if entry.name_1 == name_a && entry.name_2 == name_b && entry.is_valid() {
+                if entry.locale == locale {
                     return Some((index, entry));
                 }
+                if entry.locale == 0 && neutral.is_none() {
+                    neutral = Some((index, entry));
+                }
+                first.get_or_insert((index, entry));
             }
 
-            // If we hit an empty entry that was never used, file doesn't exist
+            // If we hit an empty entry that was never used, the chain ends
             if entry.is_empty() {
-                return None;
+                break;
             }
 
             // Continue to next entry
             index = (index + 1) & self.mask;
 
-            // If we've wrapped around to where we started, file doesn't exist
+            // If we've wrapped around to where we started, the chain ends
             if index == end_index {
-                return None;
+                break;
             }
         }
+        neutral.or(first)
     }
 
     /// Create a new hash table with mutable entries
@@ -0,0 +1,599 @@
+//! Integrity verification of archive contents
+//!
+//! [`Archive::verify_blocks`] checks that every block table entry lies inside
+//! the archive and that no two blocks share bytes, and [`Archive::verify_file`]
+//! checks a single file against its sector checksums and the CRC32/MD5 values
+//! stored in `(attributes)`. [`Archive::verify_files`] checks many files and
+//! computes their `(attributes)` checksums in batches, which the `simd`
+//! feature hands to the multi-buffer routines of
+//! [`SimdOps`](crate::simd::SimdOps). Sector checksums are Adler-32, which
+//! has no SIMD routine, and are computed one sector after another.
+
+use crate::archive::{Archive, FileInfo, decrypt_file_data};
+use crate::{Error, Result, compression};
+use byteorder::{ByteOrder, LittleEndian};
+use std::fmt;
+
+/// Files whose attribute checksums are computed together
+const BATCH_FILES: usize = 16;
+
+/// Bytes of file data held for a batch before it is checked
+const BATCH_BYTES: usize = 32 * 1024 * 1024;
+
+/// Outcome of a single integrity check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    /// The archive stores nothing to check against
+    NotPresent,
+    /// The stored checksum matches the data
+    Valid,
+    /// The stored checksum does not match the data
+    Invalid,
+}
+
+impl CheckStatus {
+    fn from_match(matches: bool) -> Self {
+        if matches { Self::Valid } else { Self::Invalid }
+    }
+
+    /// Name used in reports
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::NotPresent => "not present",
+            Self::Valid => "valid",
+            Self::Invalid => "invalid",
+        }
+    }
+}
+
+/// Verification results for one file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileVerification {
+    /// File name
+    pub name: String,
+    /// Index in the block table
+    pub block_index: usize,
+    /// Error raised while reading the file, if it could not be read
+    pub read_error: Option<String>,
+    /// Sector checksums (`FLAG_SECTOR_CRC`)
+    pub sector_crc: CheckStatus,
+    /// Indices of sectors whose checksum does not match
+    pub bad_sectors: Vec<usize>,
+    /// CRC32 from `(attributes)`
+    pub crc32: CheckStatus,
+    /// MD5 from `(attributes)`
+    pub md5: CheckStatus,
+}
+
+impl FileVerification {
+    /// Whether the file was read and no check failed
+    pub fn is_ok(&self) -> bool {
+        self.read_error.is_none()
+            && self.sector_crc != CheckStatus::Invalid
+            && self.crc32 != CheckStatus::Invalid
+            && self.md5 != CheckStatus::Invalid
+    }
+}
+
+/// Problem with the placement of a block in the archive file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlockIssue {
+    /// The block extends past the end of the archive file
+    OutOfBounds {
+        /// Block table index
+        block_index: usize,
+        /// Absolute offset of the block
+        offset: u64,
+        /// Stored (compressed) size of the block
+        size: u64,
+        /// Size of the archive file
+        archive_size: u64,
+    },
+    /// Two blocks share bytes
+    Overlap {
+        /// Block starting first
+        first: usize,
+        /// Block starting inside `first`
+        second: usize,
+        /// Number of shared bytes
+        length: u64,
+    },
+}
+
+impl fmt::Display for BlockIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OutOfBounds {
+                block_index,
+                offset,
+                size,
+                archive_size,
+            } => write!(
+                f,
+                "block {block_index} at 0x{offset:X} ({size} bytes) extends past the end of the archive ({archive_size} bytes)"
+            ),
+            Self::Overlap {
+                first,
+                second,
+                length,
+            } => write!(f, "block {second} overlaps block {first} by {length} bytes"),
+        }
+    }
+}
+
+impl Archive {
+    /// Check every stored block for out-of-bounds extents and overlaps
+    ///
+    /// Blocks are taken from the block table, or from the BET table for
+    /// archives without one. Blocks with a stored size of 0 are ignored, and
+    /// blocks sharing exactly the same data (as written for deduplicated
+    /// files) do not count as overlapping.
+    pub fn verify_blocks(&self) -> Result<Vec<BlockIssue>> {
+        let archive_size = std::fs::metadata(self.path())?.len();
+        let mut extents: Vec<(usize, u64, u64)> = Vec::new();
+
+        if let Some(block_table) = self.block_table() {
+            for (index, entry) in block_table.entries().iter().enumerate() {
+                if !entry.exists() || entry.compressed_size == 0 {
+                    continue;
+                }
+                let high = self
+                    .hi_block_table()
+                    .map_or(0, |hi| hi.get_file_pos_high(index));
+                let offset = self.archive_offset() + ((high << 32) | entry.file_pos as u64);
+                extents.push((index, offset, entry.compressed_size as u64));
+            }
+        } else if let Some(bet_table) = self.bet_table() {
+            for index in 0..bet_table.header.file_count {
+                let Some(info) = bet_table.get_file_info(index) else {
+                    continue;
+                };
+                if info.compressed_size == 0 {
+                    continue;
+                }
+                let offset = self.archive_offset() + info.file_pos;
+                extents.push((index as usize, offset, info.compressed_size));
+            }
+        }
+
+        let mut issues = Vec::new();
+        for &(block_index, offset, size) in &extents {
+            if offset.saturating_add(size) > archive_size {
+                issues.push(BlockIssue::OutOfBounds {
+                    block_index,
+                    offset,
+                    size,
+                    archive_size,
+                });
+            }
+        }
+
+        // Sweep the blocks in file order, remembering the one reaching furthest
+        extents.sort_by_key(|&(index, offset, size)| (offset, size, index));
+        extents.dedup_by_key(|&mut (_, offset, size)| (offset, size));
+        let mut furthest: Option<(usize, u64)> = None;
+        for &(index, offset, size) in &extents {
+            let end = offset + size;
+            if let Some((first, first_end)) = furthest {
+                if offset < first_end {
+                    issues.push(BlockIssue::Overlap {
+                        first,
+                        second: index,
+                        length: first_end.min(end) - offset,
+                    });
+                }
+                if end > first_end {
+                    furthest = Some((index, end));
+                }
+            } else {
+                furthest = Some((index, end));
+            }
+        }
+
+        Ok(issues)
+    }
+
+    /// Read a file and check it against its sector checksums and attributes
+    ///
+    /// Errors are only returned when the file does not exist; read and
+    /// checksum failures are recorded in the result.
+    pub fn verify_file(&mut self, name: &str) -> Result<FileVerification> {
+        let (result, pending) = self.verify_stored(name)?;
+        let mut results = [result];
+        check_attributes(&mut results, pending.map(|p| (0, p)).as_slice());
+        let [result] = results;
+        Ok(result)
+    }
+
+    /// Read several files and check them like [`Archive::verify_file`]
+    ///
+    /// The `(attributes)` CRC32 and MD5 values of up to 16 files are checked
+    /// at once. Names that are not in the archive are skipped, as listfiles
+    /// often name more files than an archive holds.
+    pub fn verify_files<S: AsRef<str>>(&mut self, names: &[S]) -> Result<Vec<FileVerification>> {
+        let mut results = Vec::with_capacity(names.len());
+        let mut batch = Vec::new();
+        let mut batch_bytes = 0;
+        for name in names {
+            let (result, pending) = match self.verify_stored(name.as_ref()) {
+                Ok(verified) => verified,
+                Err(Error::FileNotFound(_)) => continue,
+                Err(e) => return Err(e),
+            };
+            if let Some(pending) = pending {
+                batch_bytes += pending.data.len();
+                batch.push((results.len(), pending));
+            }
+            results.push(result);
+
+            if batch.len() >= BATCH_FILES || batch_bytes >= BATCH_BYTES {
+                check_attributes(&mut results, &batch);
+                batch.clear();
+                batch_bytes = 0;
+            }
+        }
+        check_attributes(&mut results, &batch);
+        Ok(results)
+    }
+
+    /// Check a file's sectors and read it, returning the data when
+    /// `(attributes)` holds checksums for it
+    fn verify_stored(&mut self, name: &str) -> Result<(FileVerification, Option<PendingChecks>)> {
+        let file_info = self
+            .find_file(name)?
+            .ok_or_else(|| Error::FileNotFound(name.to_string()))?;
+        // Attributes are optional, so a missing or broken file is not an error here
+        let _ = self.load_attributes();
+
+        let mut result = FileVerification {
+            name: name.to_string(),
+            block_index: file_info.block_index,
+            read_error: None,
+            sector_crc: CheckStatus::NotPresent,
+            bad_sectors: Vec::new(),
+            crc32: CheckStatus::NotPresent,
+            md5: CheckStatus::NotPresent,
+        };
+
+        if file_info.has_sector_crc() && !file_info.is_single_unit() {
+            match self.check_sector_crcs(name, &file_info) {
+                Ok(Some(bad_sectors)) => {
+                    result.sector_crc = CheckStatus::from_match(bad_sectors.is_empty());
+                    result.bad_sectors = bad_sectors;
+                }
+                Ok(None) => {}
+                Err(e) => result.read_error = Some(e.to_string()),
+            }
+        }
+
+        // Patch files hold binary diffs and cannot be read on their own
+        if file_info.is_patch_file() || result.read_error.is_some() {
+            return Ok((result, None));
+        }
+
+        let data = match self.read_file(name) {
+            Ok(data) => data,
+            Err(Error::ChecksumMismatch { .. }) => {
+                // Only single-unit files are checked while reading
+                result.sector_crc = CheckStatus::Invalid;
+                return Ok((result, None));
+            }
+            Err(e) => {
+                result.read_error = Some(e.to_string());
+                return Ok((result, None));
+            }
+        };
+        if file_info.has_sector_crc() && file_info.is_single_unit() {
+            result.sector_crc = CheckStatus::Valid;
+        }
+
+        // The special files are not covered by their own attributes
+        if matches!(name, "(attributes)" | "(signature)") {
+            return Ok((result, None));
+        }
+        let pending = self
+            .get_file_attributes(file_info.block_index)
+            .filter(|attributes| attributes.crc32.is_some() || attributes.md5.is_some())
+            .map(|attributes| PendingChecks {
+                data,
+                crc32: attributes.crc32,
+                md5: attributes.md5,
+            });
+
+        Ok((result, pending))
+    }
+
+    /// Check each sector of a multi-sector file against its stored checksum
+    ///
+    /// Returns the mismatching sectors, or `None` when the file has the
+    /// sector CRC flag but no checksum table.
+    fn check_sector_crcs(
+        &mut self,
+        name: &str,
+        file_info: &FileInfo,
+    ) -> Result<Option<Vec<usize>>> {
+        let sector_size = self.header().sector_size();
+        let file_size = file_info.file_size as usize;
+        let sector_count = file_size.div_ceil(sector_size);
+        let key = self.file_key(name, file_info, file_info.file_size as u32);
+
+        // Read one entry past the offset table to recognise the StormLib layout
+        let table_len = ((sector_count + 2) * 4).min(file_info.compressed_size as usize);
+        let mut table = self.read_raw(file_info.file_pos, table_len)?;
+        if file_info.is_encrypted() {
+            decrypt_file_data(&mut table, key.wrapping_sub(1));
+        }
+        let offsets: Vec<usize> = table
+            .chunks_exact(4)
+            .map(|chunk| LittleEndian::read_u32(chunk) as usize)
+            .collect();
+        if offsets.len() <= sector_count {
+            return Err(Error::invalid_format("Truncated sector offset table"));
+        }
+
+        let crc_table_len = sector_count * 4;
+        let crc_table = if offsets[0] == (sector_count + 2) * 4 {
+            // StormLib: the checksums follow the last sector and may be compressed
+            let start = offsets[sector_count];
+            let end = offsets[sector_count + 1];
+            if end <= start {
+                return Ok(None);
+            }
+            let raw = self.read_raw(file_info.file_pos + start as u64, end - start)?;
+            if raw.len() < crc_table_len {
+                compression::decompress(&raw[1..], raw[0], crc_table_len)?
+            } else {
+                raw
+            }
+        } else if offsets[0] >= (sector_count + 1) * 4 + crc_table_len {
+            // ArchiveBuilder: the checksums sit between the offset table and the data
+            self.read_raw(
+                file_info.file_pos + ((sector_count + 1) * 4) as u64,
+                crc_table_len,
+            )?
+        } else {
+            return Ok(None);
+        };
+        if crc_table.len() < crc_table_len {
+            return Err(Error::invalid_format("Truncated sector checksum table"));
+        }
+
+        let mut bad_sectors = Vec::new();
+        let mut checked = Vec::new();
+        let mut sectors = Vec::new();
+        for i in 0..sector_count {
+            let expected = LittleEndian::read_u32(&crc_table[i * 4..]);
+            // A zero checksum means the sector was written without one
+            if expected == 0 {
+                continue;
+            }
+            let (start, end) = (offsets[i], offsets[i + 1]);
+            if end < start {
+                bad_sectors.push(i);
+                continue;
+            }
+            let mut sector = self.read_raw(file_info.file_pos + start as u64, end - start)?;
+            if file_info.is_encrypted() {
+                decrypt_file_data(&mut sector, key.wrapping_add(i as u32));
+            }
+            checked.push((i, expected));
+            sectors.push(sector);
+        }
+
+        // StormLib checksums the stored sector, ArchiveBuilder the
+        // uncompressed one; either is accepted
+        let mut retried = Vec::new();
+        let mut decompressed = Vec::new();
+        for ((&(i, expected), sector), actual) in
+            checked.iter().zip(&sectors).zip(sector_checksums(&sectors))
+        {
+            if actual == expected {
+                continue;
+            }
+            let expected_size = (file_size - i * sector_size).min(sector_size);
+            let data = if file_info.is_compressed() && sector.len() < expected_size {
+                if file_info.is_implode() {
+                    compression::decompress(sector, 0x08, expected_size).ok()
+                } else if let Some((&method, data)) = sector.split_first() {
+                    compression::decompress(data, method, expected_size).ok()
+                } else {
+                    None
+                }
+            } else {
+                None
+            };
+            match data {
+                Some(data) => {
+                    retried.push((i, expected));
+                    decompressed.push(data);
+                }
+                None => bad_sectors.push(i),
+            }
+        }
+        for (&(i, expected), actual) in retried.iter().zip(sector_checksums(&decompressed)) {
+            if actual != expected {
+                bad_sectors.push(i);
+            }
+        }
+        bad_sectors.sort_unstable();
+
+        Ok(Some(bad_sectors))
+    }
+}
+
+/// Data of a read file and the checksums `(attributes)` stores for it
+struct PendingChecks {
+    data: Vec<u8>,
+    crc32: Option<u32>,
+    md5: Option<[u8; 16]>,
+}
+
+/// Check a batch of files against their attributes, recording the outcome
+/// in `results` at each pending file's index
+fn check_attributes(results: &mut [FileVerification], batch: &[(usize, PendingChecks)]) {
+    let with_crc32: Vec<_> = batch.iter().filter(|(_, p)| p.crc32.is_some()).collect();
+    let data: Vec<&[u8]> = with_crc32.iter().map(|(_, p)| p.data.as_slice()).collect();
+    for ((index, pending), actual) in with_crc32.into_iter().zip(crc32_batch(&data)) {
+        results[*index].crc32 = CheckStatus::from_match(pending.crc32 == Some(actual));
+    }
+
+    let with_md5: Vec<_> = batch.iter().filter(|(_, p)| p.md5.is_some()).collect();
+    let data: Vec<&[u8]> = with_md5.iter().map(|(_, p)| p.data.as_slice()).collect();
+    for ((index, pending), actual) in with_md5.into_iter().zip(md5_batch(&data)) {
+        results[*index].md5 = CheckStatus::from_match(pending.md5 == Some(actual));
+    }
+}
+
+/// CRC32 of every buffer, as stored in `(attributes)`
+fn crc32_batch(buffers: &[&[u8]]) -> Vec<u32> {
+    #[cfg(feature = "simd")]
+    {
+        crate::simd::shared().crc32_multi(buffers, 0)
+    }
+    #[cfg(not(feature = "simd"))]
+    {
+        buffers
+            .iter()
+            .map(|buffer| crc32fast::hash(buffer))
+            .collect()
+    }
+}
+
+/// MD5 of every buffer, as stored in `(attributes)`
+fn md5_batch(buffers: &[&[u8]]) -> Vec<[u8; 16]> {
+    #[cfg(feature = "simd")]
+    {
+        crate::simd::shared().md5_multi(buffers)
+    }
+    #[cfg(not(feature = "simd"))]
+    {
+        use md5::{Digest, Md5};
+        buffers
+            .iter()
+            .map(|buffer| Md5::digest(buffer).into())
+            .collect()
+    }
+}
+
+/// Adler-32 of every sector, as stored in sector checksum tables
+fn sector_checksums(sectors: &[Vec<u8>]) -> Vec<u32> {
+    sectors
+        .iter()
+        .map(|sector| adler2::adler32_slice(sector))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ArchiveBuilder, AttributesOption};
+    use std::fs::OpenOptions;
+    use std::io::{Seek, SeekFrom, Write};
+
+    fn sample_data() -> Vec<u8> {
+        (0..20_000u32)
+            .flat_map(|i| (i * 7919).to_le_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn test_verify_detects_corrupted_sector() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("verify.mpq");
+        ArchiveBuilder::new()
+            .generate_crcs(true)
+            .attributes_option(AttributesOption::GenerateFull)
+            .add_file_data(sample_data(), "data\\sample.bin")
+            .build(&path)
+            .unwrap();
+
+        let mut archive = Archive::open(&path).unwrap();
+        assert!(archive.verify_blocks().unwrap().is_empty());
+        let result = archive.verify_file("data\\sample.bin").unwrap();
+        assert_eq!(result.sector_crc, CheckStatus::Valid);
+        assert_eq!(result.crc32, CheckStatus::Valid);
+        assert_eq!(result.md5, CheckStatus::Valid);
+        assert!(result.is_ok());
+
+        // Flip a byte near the end of the file's data
+        let info = archive.find_file("data\\sample.bin").unwrap().unwrap();
+        let pos = info.file_pos + info.compressed_size - 8;
+        drop(archive);
+        let mut file = OpenOptions::new().write(true).open(&path).unwrap();
+        file.seek(SeekFrom::Start(pos)).unwrap();
+        file.write_all(&[0xAA]).unwrap();
+        drop(file);
+
+        let mut archive = Archive::open(&path).unwrap();
+        let result = archive.verify_file("data\\sample.bin").unwrap();
+        assert!(!result.is_ok());
+        assert_eq!(result.sector_crc, CheckStatus::Invalid);
+        assert_eq!(result.bad_sectors.len(), 1);
+    }
+
+    #[test]
+    fn test_batched_checks_match_single_file_checks() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("batch.mpq");
+        let names: Vec<String> = (0..40).map(|i| format!("data\\file{i:02}.bin")).collect();
+        let mut builder = ArchiveBuilder::new()
+            .generate_crcs(true)
+            .attributes_option(AttributesOption::GenerateFull);
+        for (i, name) in names.iter().enumerate() {
+            let data = sample_data()[..(i + 1) * 1500].to_vec();
+            // One stored file, so damage to it still reads back and is
+            // only caught by the attributes
+            let compression = if i == 20 { 0 } else { compression::flags::ZLIB };
+            builder = builder.add_file_data_with_options(data, name, compression, false, 0);
+        }
+        builder.build(&path).unwrap();
+
+        let archive = Archive::open(&path).unwrap();
+        let info = archive.find_file(&names[20]).unwrap().unwrap();
+        drop(archive);
+        let mut file = OpenOptions::new().write(true).open(&path).unwrap();
+        file.seek(SeekFrom::Start(info.file_pos + info.compressed_size - 8))
+            .unwrap();
+        file.write_all(&[0xAA]).unwrap();
+        drop(file);
+
+        let mut archive = Archive::open(&path).unwrap();
+        let mut listed = names.clone();
+        listed.push("data\\missing.bin".to_string());
+        let batched = archive.verify_files(&listed).unwrap();
+        let single: Vec<_> = names
+            .iter()
+            .map(|name| archive.verify_file(name).unwrap())
+            .collect();
+        assert_eq!(batched, single);
+
+        let damaged = &batched[20];
+        assert_eq!(damaged.crc32, CheckStatus::Invalid);
+        assert_eq!(damaged.md5, CheckStatus::Invalid);
+        assert!(batched.iter().filter(|r| r.is_ok()).all(|r| {
+            r.sector_crc == CheckStatus::Valid
+                && r.crc32 == CheckStatus::Valid
+                && r.md5 == CheckStatus::Valid
+        }));
+        assert_eq!(batched.iter().filter(|r| r.is_ok()).count(), 39);
+    }
+
+    #[test]
+    fn test_deduplicated_blocks_are_not_overlaps() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dedup.mpq");
+        ArchiveBuilder::new()
+            .deduplicate(true)
+            .add_file_data(sample_data(), "a.bin")
+            .add_file_data(sample_data(), "b.bin")
+            .build(&path)
+            .unwrap();
+
+        let mut archive = Archive::open(&path).unwrap();
+        let a = archive.find_file("a.bin").unwrap().unwrap();
+        let b = archive.find_file("b.bin").unwrap().unwrap();
+        assert_eq!(a.file_pos, b.file_pos);
+        assert_eq!(archive.read_file("b.bin").unwrap(), sample_data());
+        assert!(archive.verify_blocks().unwrap().is_empty());
+    }
+}
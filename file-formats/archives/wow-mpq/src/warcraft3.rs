@@ -0,0 +1,176 @@
+//! Warcraft III map headers
+//!
+//! Warcraft III maps (`.w3m`, and `.w3x` for The Frozen Throne) are MPQ
+//! archives behind a 512-byte `HM3W` header that the game reads for its map
+//! list without opening the archive:
+//!
+//! | Offset | Type     | Field                        |
+//! |--------|----------|------------------------------|
+//! | 0      | char[4]  | `HM3W`                       |
+//! | 4      | u32      | Unused, zero                 |
+//! | 8      | string   | Map name, NUL-terminated     |
+//! | ...    | u32      | Map flags                    |
+//! | ...    | u32      | Maximum number of players    |
+//!
+//! The rest of the 512 bytes is zero padding, and the MPQ header follows at
+//! offset 512. [`Archive`](crate::Archive) reads the header when it opens such
+//! a map; see [`Archive::map_header`](crate::Archive::map_header).
+//!
+//! ```no_run
+//! use wow_mpq::Archive;
+//!
+//! let archive = Archive::open("(4)LostTemple.w3m")?;
+//! if let Some(map) = archive.map_header() {
+//!     println!("{} for up to {} players", map.name, map.max_players);
+//! }
+//! # Ok::<(), wow_mpq::Error>(())
+//! ```
+
+use crate::{Error, Result};
+use std::io::{Read, Seek, SeekFrom};
+
+/// Signature of a Warcraft III map header (`HM3W`)
+pub const W3_MAP_SIGNATURE: [u8; 4] = *b"HM3W";
+
+/// Size of a Warcraft III map header, which is also where the MPQ data starts
+pub const W3_MAP_HEADER_SIZE: usize = 512;
+
+/// The header in front of a Warcraft III map archive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct W3MapHeader {
+    /// Unused field after the signature, normally zero
+    pub unknown: u32,
+    /// Map name as shown in the map list; may be a `TRIGSTR_` reference into
+    /// the map's string table
+    pub name: String,
+    /// Map flags, see the `FLAG_` constants
+    pub flags: u32,
+    /// Maximum number of players
+    pub max_players: u32,
+}
+
+impl W3MapHeader {
+    /// Minimap is hidden in preview screens
+    pub const FLAG_HIDE_MINIMAP: u32 = 0x0001;
+    /// Ally priorities can be modified
+    pub const FLAG_MODIFY_ALLY_PRIORITIES: u32 = 0x0002;
+    /// Melee map
+    pub const FLAG_MELEE: u32 = 0x0004;
+    /// Masked areas are partially visible
+    pub const FLAG_MASKED_PARTIALLY_VISIBLE: u32 = 0x0010;
+    /// Players have fixed settings for custom forces
+    pub const FLAG_FIXED_PLAYER_SETTINGS: u32 = 0x0020;
+    /// Custom forces
+    pub const FLAG_CUSTOM_FORCES: u32 = 0x0040;
+    /// Custom tech tree
+    pub const FLAG_CUSTOM_TECH_TREE: u32 = 0x0080;
+    /// Custom abilities
+    pub const FLAG_CUSTOM_ABILITIES: u32 = 0x0100;
+    /// Custom upgrades
+    pub const FLAG_CUSTOM_UPGRADES: u32 = 0x0200;
+
+    /// Parse a header from the first [`W3_MAP_HEADER_SIZE`] bytes of a map
+    ///
+    /// Returns `Ok(None)` if `data` does not start with [`W3_MAP_SIGNATURE`].
+    pub fn parse(data: &[u8]) -> Result<Option<Self>> {
+        if data.len() < 4 || data[..4] != W3_MAP_SIGNATURE {
+            return Ok(None);
+        }
+        let header = &data[..data.len().min(W3_MAP_HEADER_SIZE)];
+        let truncated = || Error::invalid_format("Truncated Warcraft III map header");
+        let u32_at = |offset: usize| -> Result<u32> {
+            header
+                .get(offset..offset + 4)
+                .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+                .ok_or_else(truncated)
+        };
+
+        let unknown = u32_at(4)?;
+        let name_len = header[8..]
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or_else(truncated)?;
+        let name = String::from_utf8_lossy(&header[8..8 + name_len]).into_owned();
+        let fields = 8 + name_len + 1;
+
+        Ok(Some(Self {
+            unknown,
+            name,
+            flags: u32_at(fields)?,
+            max_players: u32_at(fields + 4)?,
+        }))
+    }
+
+    /// Read the header at the start of `reader`, if there is one
+    pub fn read<R: Read + Seek>(reader: &mut R) -> Result<Option<Self>> {
+        reader.seek(SeekFrom::Start(0))?;
+        let mut data = Vec::with_capacity(W3_MAP_HEADER_SIZE);
+        reader
+            .take(W3_MAP_HEADER_SIZE as u64)
+            .read_to_end(&mut data)?;
+        Self::parse(&data)
+    }
+
+    /// Serialize the header, padded to [`W3_MAP_HEADER_SIZE`] bytes
+    ///
+    /// Fails if the name is too long to fit.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut data = Vec::with_capacity(W3_MAP_HEADER_SIZE);
+        data.extend_from_slice(&W3_MAP_SIGNATURE);
+        data.extend_from_slice(&self.unknown.to_le_bytes());
+        data.extend_from_slice(self.name.as_bytes());
+        data.push(0);
+        data.extend_from_slice(&self.flags.to_le_bytes());
+        data.extend_from_slice(&self.max_players.to_le_bytes());
+        if data.len() > W3_MAP_HEADER_SIZE {
+            return Err(Error::invalid_format(format!(
+                "Map name of {} bytes does not fit a Warcraft III map header",
+                self.name.len()
+            )));
+        }
+        data.resize(W3_MAP_HEADER_SIZE, 0);
+        Ok(data)
+    }
+
+    /// Whether a flag is set
+    pub fn has_flag(&self, flag: u32) -> bool {
+        self.flags & flag != 0
+    }
+
+    /// Whether this is a melee map
+    pub fn is_melee(&self) -> bool {
+        self.has_flag(Self::FLAG_MELEE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header_round_trip() {
+        let header = W3MapHeader {
+            unknown: 0,
+            name: "Lost Temple".to_string(),
+            flags: W3MapHeader::FLAG_MELEE | W3MapHeader::FLAG_HIDE_MINIMAP,
+            max_players: 4,
+        };
+        let bytes = header.to_bytes().unwrap();
+        assert_eq!(bytes.len(), W3_MAP_HEADER_SIZE);
+        assert_eq!(&bytes[..4], b"HM3W");
+
+        let parsed = W3MapHeader::parse(&bytes).unwrap().unwrap();
+        assert_eq!(parsed, header);
+        assert!(parsed.is_melee());
+        assert!(!parsed.has_flag(W3MapHeader::FLAG_CUSTOM_FORCES));
+
+        assert_eq!(W3MapHeader::parse(b"MPQ\x1a").unwrap(), None);
+        assert!(W3MapHeader::parse(&bytes[..14]).is_err());
+
+        let long = W3MapHeader {
+            name: "x".repeat(W3_MAP_HEADER_SIZE),
+            ..header
+        };
+        assert!(long.to_bytes().is_err());
+    }
+}
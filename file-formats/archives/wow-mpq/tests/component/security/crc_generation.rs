@@ -2,7 +2,9 @@
 
 use std::fs;
 use tempfile::tempdir;
-use wow_mpq::{Archive, ArchiveBuilder, ListfileOption};
+use wow_mpq::{
+    AddFileOptions, Archive, ArchiveBuilder, CheckStatus, ListfileOption, MutableArchive,
+};
 
 /// Test that CRC generation works for single-unit files
 #[test]
@@ -191,3 +193,88 @@ fn test_crc_generation_with_encryption() {
     );
     assert!(file_info.is_encrypted(), "File should be encrypted");
 }
+
+/// Sector data that does not compress to a single byte
+fn sector_test_data(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i * 7 + i / 13) as u8).collect()
+}
+
+/// Test per-file sector checksums in StormLib's layout
+#[test]
+fn test_per_file_sector_crc() {
+    let temp_dir = tempdir().unwrap();
+    let archive_path = temp_dir.path().join("test_per_file_crc.mpq");
+    let test_data = sector_test_data(4096 * 3 + 1024);
+
+    ArchiveBuilder::new()
+        .block_size(3) // 4KB sectors
+        .add_file_data_with(
+            test_data.clone(),
+            "checked.bin",
+            AddFileOptions::new().sector_crc(true),
+        )
+        .add_file_data_with(
+            test_data.clone(),
+            "encrypted.bin",
+            AddFileOptions::new().sector_crc(true).encrypt(),
+        )
+        .add_file_data(test_data.clone(), "unchecked.bin")
+        .build(&archive_path)
+        .unwrap();
+
+    let mut archive = Archive::open(&archive_path).unwrap();
+    for name in ["checked.bin", "encrypted.bin"] {
+        assert_eq!(archive.read_file(name).unwrap(), test_data);
+        let verification = archive.verify_file(name).unwrap();
+        assert_eq!(verification.sector_crc, CheckStatus::Valid, "{name}");
+    }
+    assert_eq!(archive.read_file("unchecked.bin").unwrap(), test_data);
+    let verification = archive.verify_file("unchecked.bin").unwrap();
+    assert_eq!(verification.sector_crc, CheckStatus::NotPresent);
+
+    // A damaged sector is reported by its checksum
+    let file_pos = archive.find_file("checked.bin").unwrap().unwrap().file_pos as usize;
+    drop(archive);
+    let mut bytes = fs::read(&archive_path).unwrap();
+    let sector_start = u32::from_le_bytes(bytes[file_pos + 4..file_pos + 8].try_into().unwrap());
+    bytes[file_pos + sector_start as usize + 2] ^= 0xFF;
+    fs::write(&archive_path, bytes).unwrap();
+
+    let mut archive = Archive::open(&archive_path).unwrap();
+    let verification = archive.verify_file("checked.bin").unwrap();
+    assert_eq!(verification.sector_crc, CheckStatus::Invalid);
+    assert_eq!(verification.bad_sectors, vec![1]);
+}
+
+/// Test sector checksums for files added to an existing archive
+#[test]
+fn test_mutable_archive_sector_crc() {
+    let temp_dir = tempdir().unwrap();
+    let archive_path = temp_dir.path().join("test_mutable_crc.mpq");
+    let test_data = sector_test_data(4096 * 2 + 512);
+
+    ArchiveBuilder::new()
+        .block_size(3) // 4KB sectors
+        .add_file_data(b"Existing file".to_vec(), "existing.txt")
+        .build(&archive_path)
+        .unwrap();
+
+    let mut mutable = MutableArchive::open(&archive_path).unwrap();
+    mutable
+        .add_file_data(
+            &test_data,
+            "checked.bin",
+            AddFileOptions::new().sector_crc(true),
+        )
+        .unwrap();
+    mutable.flush().unwrap();
+    drop(mutable);
+
+    let mut archive = Archive::open(&archive_path).unwrap();
+    assert_eq!(archive.read_file("checked.bin").unwrap(), test_data);
+    let info = archive.find_file("checked.bin").unwrap().unwrap();
+    assert!(info.has_sector_crc());
+    assert!(!info.is_single_unit());
+    let verification = archive.verify_file("checked.bin").unwrap();
+    assert_eq!(verification.sector_crc, CheckStatus::Valid);
+}
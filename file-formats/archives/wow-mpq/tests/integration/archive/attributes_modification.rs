@@ -2,7 +2,8 @@
 
 use tempfile::NamedTempFile;
 use wow_mpq::{
-    AddFileOptions, Archive, ArchiveBuilder, FormatVersion, ListfileOption, MutableArchive,
+    AddFileOptions, Archive, ArchiveBuilder, AttributesUpdateMode, CheckStatus, FormatVersion,
+    ListfileOption, MutableArchive,
 };
 
 #[test]
@@ -53,3 +54,74 @@ fn test_attributes_preserved_on_rebuild() {
     // generating attributes with incorrect block count
     // TODO: Fix ArchiveBuilder to generate correct attributes
 }
+
+#[test]
+fn test_attributes_regenerated_after_changes() {
+    let temp_file = NamedTempFile::new().unwrap();
+    let path = temp_file.path();
+
+    ArchiveBuilder::new()
+        .version(FormatVersion::V1)
+        .add_file_data(b"Kept file".to_vec(), "keep.txt")
+        .add_file_data(b"Removed file".to_vec(), "remove.txt")
+        .add_file_data(b"Renamed file".to_vec(), "old.txt")
+        .build(path)
+        .unwrap();
+
+    {
+        let mut mutable = MutableArchive::open(path).unwrap();
+        mutable.set_attributes_mode(AttributesUpdateMode::Regenerate);
+        mutable
+            .add_file_data(
+                b"Added file".as_ref(),
+                "added.txt",
+                AddFileOptions::default(),
+            )
+            .unwrap();
+        mutable.remove_file("remove.txt").unwrap();
+        mutable.rename_file("old.txt", "new.txt").unwrap();
+        mutable.flush().unwrap();
+    }
+
+    let mut archive = Archive::open(path).unwrap();
+    assert!(archive.find_file("(attributes)").unwrap().is_some());
+    for name in ["keep.txt", "added.txt", "new.txt", "(listfile)"] {
+        let result = archive.verify_file(name).unwrap();
+        assert!(result.is_ok(), "{name}: {result:?}");
+        assert_eq!(result.crc32, CheckStatus::Valid, "{name}");
+        assert_eq!(result.md5, CheckStatus::Valid, "{name}");
+    }
+
+    let attributes = archive.attributes().unwrap();
+    assert!(attributes.flags.has_md5());
+    assert_eq!(
+        attributes.file_attributes.len(),
+        archive.block_table().unwrap().entries().len()
+    );
+}
+
+#[test]
+fn test_attributes_none_mode_leaves_file_untouched() {
+    let temp_file = NamedTempFile::new().unwrap();
+    let path = temp_file.path();
+
+    ArchiveBuilder::new()
+        .version(FormatVersion::V1)
+        .listfile_option(ListfileOption::None)
+        .add_file_data(b"Test file".to_vec(), "test.txt")
+        .build(path)
+        .unwrap();
+
+    {
+        let mut mutable = MutableArchive::open(path).unwrap();
+        mutable.set_attributes_mode(AttributesUpdateMode::None);
+        assert_eq!(mutable.attributes_mode(), AttributesUpdateMode::None);
+        mutable
+            .add_file_data(b"New file".as_ref(), "new.txt", AddFileOptions::default())
+            .unwrap();
+        mutable.flush().unwrap();
+    }
+
+    let archive = Archive::open(path).unwrap();
+    assert!(archive.find_file("(attributes)").unwrap().is_none());
+}
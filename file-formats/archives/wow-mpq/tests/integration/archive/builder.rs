@@ -133,6 +133,68 @@ fn test_external_listfile() {
     assert!(listfile_content.contains("custom_entry.txt"));
 }
 
+#[test]
+fn test_resolved_listfile() {
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("resolved_list.mpq");
+    let listfile_path = temp_dir.path().join("listfile.csv");
+
+    fs::write(
+        &listfile_path,
+        "1;Interface/Icons/Spell_Holy_Heal.blp\n2;World/Unused.wmo\n",
+    )
+    .unwrap();
+
+    ArchiveBuilder::new()
+        .add_file_data(b"icon".to_vec(), "INTERFACE\\ICONS\\SPELL_HOLY_HEAL.BLP")
+        .add_file_data(b"other".to_vec(), "other.txt")
+        .listfile_option(ListfileOption::Resolve(listfile_path))
+        .build(&archive_path)
+        .unwrap();
+
+    // Names take the external spelling; unlisted external names are dropped
+    let mut archive = Archive::open(&archive_path).unwrap();
+    let listfile_data = archive.read_file("(listfile)").unwrap();
+    let listfile_content = String::from_utf8(listfile_data).unwrap();
+    assert!(listfile_content.contains("Interface\\Icons\\Spell_Holy_Heal.blp"));
+    assert!(listfile_content.contains("other.txt"));
+    assert!(!listfile_content.contains("Unused"));
+}
+
+#[test]
+fn test_list_with_external_listfile() {
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("unlisted.mpq");
+    let listfile_path = temp_dir.path().join("listfile.txt");
+
+    ArchiveBuilder::new()
+        .add_file_data(b"File 1".to_vec(), "data\\file1.txt")
+        .add_file_data(b"File 2".to_vec(), "data\\file2.txt")
+        .listfile_option(ListfileOption::None)
+        .build(&archive_path)
+        .unwrap();
+
+    let mut archive = Archive::open(&archive_path).unwrap();
+    let anonymous = archive.list().unwrap();
+    assert_eq!(anonymous.len(), 2);
+    assert!(anonymous.iter().all(|e| e.name.starts_with("file_")));
+
+    // Plain and CSV lines can be mixed; unknown names are ignored
+    fs::write(
+        &listfile_path,
+        "data/FILE1.txt\n7;data/file2.txt\nmissing.txt\n",
+    )
+    .unwrap();
+    let mut names: Vec<String> = archive
+        .list_with_external_listfile(&listfile_path)
+        .unwrap()
+        .into_iter()
+        .map(|e| e.name)
+        .collect();
+    names.sort();
+    assert_eq!(names, ["data/FILE1.txt", "data\\file2.txt"]);
+}
+
 #[test]
 fn test_no_listfile() {
     let temp_dir = TempDir::new().unwrap();
@@ -652,3 +714,109 @@ fn test_v1_no_hi_block_table() {
     assert!(archive.header().hash_table_pos_hi.is_none());
     assert!(archive.header().block_table_pos_hi.is_none());
 }
+
+#[test]
+fn test_deterministic_build() {
+    use wow_mpq::AttributesOption;
+
+    let files: [(&str, &[u8]); 3] = [
+        (
+            "Data\\b.txt",
+            b"second file, long enough to be worth compressing",
+        ),
+        ("Data\\a.txt", b"first file"),
+        ("Data\\c.bin", &[7; 5000]),
+    ];
+    let build = |version: FormatVersion, reversed: bool| {
+        let mut builder = ArchiveBuilder::new()
+            .version(version)
+            .deterministic(true)
+            .attributes_option(AttributesOption::GenerateFull);
+        let ordered: Vec<_> = if reversed {
+            files.iter().rev().collect()
+        } else {
+            files.iter().collect()
+        };
+        for (name, data) in ordered {
+            builder = builder.add_file_data(data.to_vec(), name);
+        }
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("deterministic.mpq");
+        builder.build(&path).unwrap();
+        fs::read(&path).unwrap()
+    };
+
+    for version in [FormatVersion::V1, FormatVersion::V4] {
+        let first = build(version, false);
+        assert_eq!(first, build(version, true));
+
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("deterministic.mpq");
+        fs::write(&path, &first).unwrap();
+        let mut archive = Archive::open(&path).unwrap();
+        let listfile = archive.read_file("(listfile)").unwrap();
+        assert!(listfile.starts_with(b"Data\\a.txt\r\nData\\b.txt\r\nData\\c.bin\r\n"));
+        archive.load_attributes().unwrap();
+        let times: Vec<_> = archive
+            .attributes()
+            .unwrap()
+            .file_attributes
+            .iter()
+            .filter_map(|file| file.filetime)
+            .collect();
+        assert!(!times.is_empty());
+        assert!(times.iter().all(|&time| time == 0));
+    }
+}
+
+#[test]
+fn test_build_to_vec() {
+    let bytes = ArchiveBuilder::new()
+        .add_file_data(b"Hello, Azeroth!".to_vec(), "readme.txt")
+        .build_to_vec()
+        .unwrap();
+
+    let mut archive = Archive::from_reader(std::io::Cursor::new(bytes)).unwrap();
+    assert_eq!(archive.read_file("readme.txt").unwrap(), b"Hello, Azeroth!");
+}
+
+#[test]
+fn test_build_to_writer_appends() {
+    let mut data = b"leading bytes".to_vec();
+    data.resize(0x200, 0);
+    ArchiveBuilder::new()
+        .add_file_data(b"in memory".to_vec(), "Data\\File.txt")
+        .build_to_writer(&mut data)
+        .unwrap();
+
+    assert_eq!(&data[..13], b"leading bytes");
+    let mut archive = Archive::from_reader(std::io::Cursor::new(data)).unwrap();
+    assert_eq!(archive.archive_offset(), 0x200);
+    assert_eq!(archive.read_file("Data\\File.txt").unwrap(), b"in memory");
+}
+
+#[test]
+fn test_in_memory_build_matches_file_build() {
+    use wow_mpq::AttributesOption;
+
+    let builder = |version: FormatVersion| {
+        ArchiveBuilder::new()
+            .version(version)
+            .deterministic(true)
+            .attributes_option(AttributesOption::GenerateFull)
+            .add_file_data(b"first file".to_vec(), "Data\\a.txt")
+            .add_file_data(vec![7; 5000], "Data\\c.bin")
+    };
+
+    let temp_dir = TempDir::new().unwrap();
+    for version in [FormatVersion::V1, FormatVersion::V4] {
+        let path = temp_dir.path().join(format!("{version:?}.mpq"));
+        builder(version).build(&path).unwrap();
+        let on_disk = fs::read(&path).unwrap();
+
+        assert_eq!(builder(version).build_to_vec().unwrap(), on_disk);
+        let mut written = Vec::new();
+        builder(version).build_to_writer(&mut written).unwrap();
+        assert_eq!(written, on_disk);
+    }
+}
@@ -8,5 +8,11 @@ mod basic;
 mod builder;
 mod listfile_modification;
 mod modification;
+mod progress;
+mod read_cache;
+mod rebuild;
+mod storage;
 mod test_debug_add;
 mod test_encryption_keys;
+mod volumes;
+mod warcraft3;
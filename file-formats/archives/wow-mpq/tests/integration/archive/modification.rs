@@ -3,7 +3,7 @@
 use std::fs;
 use tempfile::TempDir;
 use wow_mpq::compression::CompressionMethod;
-use wow_mpq::{AddFileOptions, Archive, ArchiveBuilder, MutableArchive};
+use wow_mpq::{AddFileOptions, Archive, ArchiveBuilder, FormatVersion, MutableArchive};
 
 /// Helper function to create a test archive with some initial files
 fn create_test_archive(dir: &TempDir) -> std::path::PathBuf {
@@ -184,6 +184,152 @@ fn test_replace_existing_file() {
     assert_eq!(content, b"Replaced content");
 }
 
+#[test]
+fn test_replace_in_place_when_data_fits() {
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = create_test_archive(&temp_dir);
+
+    let mut mutable_archive = MutableArchive::open(&archive_path).unwrap();
+    mutable_archive
+        .add_file_data(b"Some replaced content", "file1.txt", Default::default())
+        .unwrap();
+    drop(mutable_archive);
+    let size = fs::metadata(&archive_path).unwrap().len();
+
+    // Shorter content overwrites the old block instead of growing the archive
+    for content in [b"Shorter content".as_slice(), b"Short"] {
+        let mut mutable_archive = MutableArchive::open(&archive_path).unwrap();
+        mutable_archive
+            .add_file_data(content, "file1.txt", Default::default())
+            .unwrap();
+        drop(mutable_archive);
+
+        assert_eq!(fs::metadata(&archive_path).unwrap().len(), size);
+        let mut archive = Archive::open(&archive_path).unwrap();
+        assert_eq!(archive.read_file("file1.txt").unwrap(), content);
+        assert_eq!(
+            archive.read_file("dir\\file2.txt").unwrap(),
+            b"Test content 2"
+        );
+    }
+}
+
+#[test]
+fn test_incremental_add_reuses_freed_block() {
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("incremental.mpq");
+    ArchiveBuilder::new()
+        .add_file_data_with_options(
+            vec![0xAB; 4096],
+            "big.bin",
+            0, // uncompressed
+            false,
+            0,
+        )
+        .add_file_data(b"Small content".to_vec(), "small.txt")
+        .build(&archive_path)
+        .unwrap();
+
+    let mut mutable_archive = MutableArchive::open(&archive_path).unwrap();
+    mutable_archive.set_incremental(true);
+    let big_pos = mutable_archive
+        .find_file("big.bin")
+        .unwrap()
+        .unwrap()
+        .file_pos;
+    let small_pos = mutable_archive
+        .find_file("small.txt")
+        .unwrap()
+        .unwrap()
+        .file_pos;
+    let size = fs::metadata(&archive_path).unwrap().len();
+
+    mutable_archive.remove_file("big.bin").unwrap();
+    let options = AddFileOptions::new().compression(CompressionMethod::None);
+    mutable_archive
+        .add_file_data(&[0xCD; 2048], "new.bin", options)
+        .unwrap();
+
+    // The new file takes the space of the removed one
+    let new_info = mutable_archive.find_file("new.bin").unwrap().unwrap();
+    assert_eq!(new_info.file_pos, big_pos);
+    mutable_archive.flush().unwrap();
+    drop(mutable_archive);
+
+    assert_eq!(fs::metadata(&archive_path).unwrap().len(), size);
+    let mut archive = Archive::open(&archive_path).unwrap();
+    assert_eq!(archive.read_file("new.bin").unwrap(), vec![0xCD; 2048]);
+    assert!(archive.read_file("big.bin").is_err());
+    assert_eq!(
+        archive.find_file("small.txt").unwrap().unwrap().file_pos,
+        small_pos
+    );
+    assert_eq!(archive.read_file("small.txt").unwrap(), b"Small content");
+}
+
+#[test]
+fn test_incremental_writes_listfile_on_flush() {
+    for version in [
+        FormatVersion::V1,
+        FormatVersion::V2,
+        FormatVersion::V3,
+        FormatVersion::V4,
+    ] {
+        let temp_dir = TempDir::new().unwrap();
+        let archive_path = temp_dir.path().join("incremental.mpq");
+        ArchiveBuilder::new()
+            .version(version)
+            .add_file_data(b"Test content 1".to_vec(), "file1.txt")
+            .build(&archive_path)
+            .unwrap();
+
+        let mut mutable_archive = MutableArchive::open(&archive_path).unwrap();
+        mutable_archive.set_incremental(true);
+        let listed = mutable_archive.list().unwrap().len();
+        for i in 0..8 {
+            let name = format!("mod\\file{i}.txt");
+            mutable_archive
+                .add_file_data(name.as_bytes(), &name, Default::default())
+                .unwrap();
+        }
+
+        // Pending listfile changes are visible before they are written
+        assert_eq!(mutable_archive.list().unwrap().len(), listed + 8);
+        mutable_archive.flush().unwrap();
+
+        // A second session continues from the tables written by the first
+        mutable_archive
+            .add_file_data(
+                b"Replaced with longer content",
+                "file1.txt",
+                Default::default(),
+            )
+            .unwrap();
+        drop(mutable_archive);
+
+        let mut archive = Archive::open(&archive_path).unwrap();
+        let names: Vec<String> = archive
+            .list()
+            .unwrap()
+            .into_iter()
+            .map(|e| e.name)
+            .collect();
+        assert!(names.contains(&"mod\\file7.txt".to_string()), "{version:?}");
+        for i in 0..8 {
+            let name = format!("mod\\file{i}.txt");
+            assert_eq!(
+                archive.read_file(&name).unwrap(),
+                name.as_bytes(),
+                "{version:?}"
+            );
+        }
+        assert_eq!(
+            archive.read_file("file1.txt").unwrap(),
+            b"Replaced with longer content"
+        );
+    }
+}
+
 #[test]
 fn test_add_without_replace_fails() {
     let temp_dir = TempDir::new().unwrap();
@@ -254,6 +400,77 @@ fn test_compact_archive() {
     );
 }
 
+#[test]
+fn test_hash_table_grows_when_full() {
+    for version in [FormatVersion::V1, FormatVersion::V3] {
+        let temp_dir = TempDir::new().unwrap();
+        let archive_path = temp_dir.path().join("small.mpq");
+        ArchiveBuilder::new()
+            .version(version)
+            .add_file_data(b"Test content 1".to_vec(), "file1.txt")
+            .build(&archive_path)
+            .unwrap();
+
+        let mut mutable_archive = MutableArchive::open(&archive_path).unwrap();
+        assert_eq!(mutable_archive.max_file_count(), 16);
+        for i in 0..40 {
+            mutable_archive
+                .add_file_data(
+                    format!("content {i}").as_bytes(),
+                    &format!("added\\{i}.txt"),
+                    AddFileOptions::default(),
+                )
+                .unwrap();
+        }
+        assert_eq!(mutable_archive.max_file_count(), 64);
+        mutable_archive.flush().unwrap();
+        drop(mutable_archive);
+
+        let mut archive = Archive::open(&archive_path).unwrap();
+        assert_eq!(archive.header().hash_table_size, 64);
+        assert_eq!(archive.read_file("file1.txt").unwrap(), b"Test content 1");
+        for i in 0..40 {
+            assert_eq!(
+                archive.read_file(&format!("added\\{i}.txt")).unwrap(),
+                format!("content {i}").as_bytes(),
+                "{version:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_set_max_file_count() {
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = create_test_archive(&temp_dir);
+
+    let mut mutable_archive = MutableArchive::open(&archive_path).unwrap();
+    assert!(matches!(
+        mutable_archive.set_max_file_count(2),
+        Err(wow_mpq::Error::CapacityExceeded(_))
+    ));
+
+    mutable_archive.set_max_file_count(1000).unwrap();
+    mutable_archive
+        .add_file_data(b"New content", "new.txt", AddFileOptions::default())
+        .unwrap();
+    mutable_archive.flush().unwrap();
+
+    // A later flush writes to the relocated table
+    mutable_archive.remove_file("file1.txt").unwrap();
+    mutable_archive.flush().unwrap();
+    drop(mutable_archive);
+
+    let mut archive = Archive::open(&archive_path).unwrap();
+    assert_eq!(archive.header().hash_table_size, 1024);
+    assert!(archive.find_file("file1.txt").unwrap().is_none());
+    assert_eq!(
+        archive.read_file("dir\\subdir\\file3.txt").unwrap(),
+        b"Test content 3"
+    );
+    assert_eq!(archive.read_file("new.txt").unwrap(), b"New content");
+}
+
 #[test]
 #[ignore = "Not yet implemented"]
 fn test_path_normalization() {
@@ -0,0 +1,118 @@
+//! Tests for progress reporting of long-running operations
+
+use std::sync::{Arc, Mutex};
+use tempfile::TempDir;
+use wow_mpq::{
+    ArchiveBuilder, MutableArchive, Progress, ProgressSink, ProgressStage, RebuildOptions,
+    rebuild_archive_with_progress,
+};
+
+/// Stage, files done, files total, bytes and file name of each update
+type Updates = Arc<Mutex<Vec<(ProgressStage, usize, usize, u64, String)>>>;
+
+fn recorder() -> (Updates, Arc<dyn ProgressSink>) {
+    let updates = Updates::default();
+    let sink = {
+        let updates = updates.clone();
+        Arc::new(move |p: &Progress<'_>| {
+            updates.lock().unwrap().push((
+                p.stage,
+                p.files_done,
+                p.files_total,
+                p.bytes,
+                p.current_file.to_string(),
+            ));
+        })
+    };
+    (updates, sink)
+}
+
+fn stage(updates: &Updates, stage: ProgressStage) -> Vec<(usize, usize, u64, String)> {
+    updates
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|update| update.0 == stage)
+        .map(|(_, done, total, bytes, name)| (*done, *total, *bytes, name.clone()))
+        .collect()
+}
+
+#[test]
+fn test_builder_reports_each_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("progress.mpq");
+    let (updates, sink) = recorder();
+
+    ArchiveBuilder::new()
+        .add_file_data(b"first".repeat(100), "first.txt")
+        .add_file_data(b"second".to_vec(), "second.txt")
+        .progress(sink)
+        .build(&path)
+        .unwrap();
+
+    let writes = stage(&updates, ProgressStage::Write);
+    // Both files and the generated listfile
+    assert_eq!(writes.len(), 3);
+    assert_eq!(writes[0].3, "first.txt");
+    assert!(writes.iter().all(|(_, total, _, _)| *total == 3));
+    assert_eq!(
+        writes.iter().map(|w| w.0).collect::<Vec<_>>(),
+        vec![1, 2, 3]
+    );
+    assert!(writes.windows(2).all(|w| w[0].2 < w[1].2));
+    assert!(writes[2].2 <= std::fs::metadata(&path).unwrap().len());
+}
+
+#[test]
+fn test_rebuild_reports_read_then_write() {
+    let temp_dir = TempDir::new().unwrap();
+    let source = temp_dir.path().join("source.mpq");
+    let target = temp_dir.path().join("target.mpq");
+    ArchiveBuilder::new()
+        .add_file_data(b"alpha".repeat(1000), "Data\\alpha.txt")
+        .add_file_data(b"beta".to_vec(), "Data\\beta.txt")
+        .build(&source)
+        .unwrap();
+
+    let (updates, sink) = recorder();
+    rebuild_archive_with_progress(&source, &target, RebuildOptions::default(), sink).unwrap();
+
+    let reads = stage(&updates, ProgressStage::Read);
+    let writes = stage(&updates, ProgressStage::Write);
+    assert!(reads.iter().any(|r| r.3 == "Data\\alpha.txt"));
+    assert_eq!(reads.last().unwrap().0, reads.last().unwrap().1);
+    assert!(reads.last().unwrap().2 >= 5000);
+    assert!(writes.iter().any(|w| w.3 == "Data\\beta.txt"));
+
+    let first_write = updates
+        .lock()
+        .unwrap()
+        .iter()
+        .position(|u| u.0 == ProgressStage::Write)
+        .unwrap();
+    assert_eq!(first_write, reads.len());
+}
+
+#[test]
+fn test_compact_reports_progress() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("compact.mpq");
+    ArchiveBuilder::new()
+        .add_file_data(b"keep".repeat(100), "keep.txt")
+        .add_file_data(b"drop".repeat(100), "drop.txt")
+        .build(&path)
+        .unwrap();
+
+    let mut archive = MutableArchive::open(&path).unwrap();
+    archive.remove_file("drop.txt").unwrap();
+    archive.flush().unwrap();
+
+    let (updates, sink) = recorder();
+    archive.compact_with_progress(sink).unwrap();
+
+    let reads = stage(&updates, ProgressStage::Read);
+    assert_eq!(reads, vec![(1, 1, 400, "keep.txt".to_string())]);
+    let writes = stage(&updates, ProgressStage::Write);
+    assert!(writes.iter().any(|w| w.3 == "keep.txt"));
+    assert!(!writes.iter().any(|w| w.3 == "drop.txt"));
+}
@@ -0,0 +1,76 @@
+//! Tests for the read cache of decoded file data
+
+use std::io::Read;
+use tempfile::TempDir;
+use wow_mpq::{Archive, ArchiveBuilder, ReadCacheConfig};
+
+fn create_archive(dir: &TempDir) -> std::path::PathBuf {
+    let path = dir.path().join("cached.mpq");
+    let large: Vec<u8> = (0..20_000u32)
+        .flat_map(|i| (i % 251).to_le_bytes())
+        .collect();
+    ArchiveBuilder::new()
+        .block_size(3) // 4 KiB sectors
+        .add_file_data(large, "large.bin")
+        .add_file_data(b"small file".to_vec(), "small.txt")
+        .build(&path)
+        .unwrap();
+    path
+}
+
+#[test]
+fn test_repeated_reads_hit_cache() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = create_archive(&temp_dir);
+
+    let mut uncached = Archive::open(&path).unwrap();
+    let expected = uncached.read_file("large.bin").unwrap();
+    assert!(uncached.read_cache_stats().is_none());
+
+    let mut archive = Archive::open(&path).unwrap();
+    archive.enable_read_cache(ReadCacheConfig::default());
+    assert_eq!(archive.read_file("large.bin").unwrap(), expected);
+    let first = archive.read_cache_stats().unwrap();
+    assert_eq!(first.hits, 0);
+    assert!(first.entries > 1, "sectors are cached separately");
+    assert_eq!(first.bytes, expected.len());
+
+    assert_eq!(archive.read_file("large.bin").unwrap(), expected);
+    assert_eq!(archive.read_file("small.txt").unwrap(), b"small file");
+    assert_eq!(archive.read_file("small.txt").unwrap(), b"small file");
+    let second = archive.read_cache_stats().unwrap();
+    assert_eq!(second.hits, first.entries as u64 + 1);
+    assert_eq!(second.hit_rate(), 0.5);
+
+    // Streams share the sector cache
+    let mut streamed = Vec::new();
+    archive
+        .open_file_stream("large.bin")
+        .unwrap()
+        .read_to_end(&mut streamed)
+        .unwrap();
+    assert_eq!(streamed, expected);
+    assert_eq!(archive.read_cache_stats().unwrap().misses, second.misses);
+
+    archive.clear_read_cache();
+    assert_eq!(archive.read_cache_stats().unwrap().bytes, 0);
+    archive.disable_read_cache();
+    assert!(archive.read_cache_stats().is_none());
+}
+
+#[test]
+fn test_capacity_bounds_cache() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = create_archive(&temp_dir);
+
+    let mut archive = wow_mpq::OpenOptions::new()
+        .read_cache(ReadCacheConfig::new(8192))
+        .open(&path)
+        .unwrap();
+    let data = archive.read_file("large.bin").unwrap();
+    assert_eq!(data.len(), 80_000);
+
+    let stats = archive.read_cache_stats().unwrap();
+    assert!(stats.bytes <= 8192);
+    assert!(stats.evictions > 0);
+}
@@ -0,0 +1,72 @@
+//! Tests for verifying rebuilt archives
+
+use tempfile::TempDir;
+use wow_mpq::{ArchiveBuilder, FormatVersion, GameVersion, RebuildOptions, rebuild_archive};
+
+fn create_archive(dir: &TempDir) -> std::path::PathBuf {
+    let path = dir.path().join("source.mpq");
+    ArchiveBuilder::new()
+        .version(FormatVersion::V2)
+        .add_file_data(b"first file".repeat(500), "Data\\first.txt")
+        .add_file_data(b"second file".to_vec(), "Data\\second.txt")
+        .build(&path)
+        .unwrap();
+    path
+}
+
+#[test]
+fn test_rebuild_verification_report() {
+    let temp_dir = TempDir::new().unwrap();
+    let source = create_archive(&temp_dir);
+    let target = temp_dir.path().join("target.mpq");
+
+    let options = RebuildOptions {
+        verify_layout: Some(GameVersion::Wotlk),
+        ..Default::default()
+    };
+    let summary = rebuild_archive(&source, &target, options, None).unwrap();
+
+    let report = summary.verification.expect("verification requested");
+    assert!(report.passed(), "{report:?}");
+    assert!(summary.verified);
+    assert_eq!(report.files_checked, report.files_matched);
+    assert!(report.files_checked >= 2);
+    assert!(report.layout_issues.is_empty());
+}
+
+#[test]
+fn test_rebuild_layout_newer_than_client() {
+    let temp_dir = TempDir::new().unwrap();
+    let source = create_archive(&temp_dir);
+    let target = temp_dir.path().join("target.mpq");
+
+    let options = RebuildOptions {
+        target_format: Some(FormatVersion::V4),
+        verify_layout: Some(GameVersion::Tbc),
+        ..Default::default()
+    };
+    let summary = rebuild_archive(&source, &target, options, None).unwrap();
+
+    let report = summary.verification.unwrap();
+    assert!(!summary.verified);
+    assert!(report.mismatched.is_empty() && report.missing.is_empty());
+    assert!(
+        report
+            .layout_issues
+            .iter()
+            .any(|issue| issue.contains("V4")),
+        "{:?}",
+        report.layout_issues
+    );
+}
+
+#[test]
+fn test_rebuild_without_verification() {
+    let temp_dir = TempDir::new().unwrap();
+    let source = create_archive(&temp_dir);
+    let target = temp_dir.path().join("target.mpq");
+
+    let summary = rebuild_archive(&source, &target, RebuildOptions::default(), None).unwrap();
+    assert!(summary.verification.is_none());
+    assert!(!summary.verified);
+}
@@ -0,0 +1,92 @@
+//! Tests for reading archives and patch chains through the `Storage` trait
+
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+use wow_mpq::{Archive, ArchiveBuilder, Error, PatchChain, Storage};
+
+/// Names and sizes of everything a storage lists
+fn listing(storage: &mut dyn Storage) -> Vec<(String, u64)> {
+    let mut entries: Vec<_> = storage
+        .list()
+        .unwrap()
+        .into_iter()
+        .map(|entry| (entry.name, entry.size))
+        .collect();
+    entries.sort();
+    entries
+}
+
+#[test]
+fn test_archive_storage() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("test.mpq");
+    ArchiveBuilder::new()
+        .add_file_data(b"map data".to_vec(), "DBFilesClient\\Map.dbc")
+        .add_file_data(b"readme".to_vec(), "readme.txt")
+        .build(&path)
+        .unwrap();
+
+    let mut archive = <Archive as Storage>::open(&path).unwrap();
+    let storage: &mut dyn Storage = &mut archive;
+
+    assert!(storage.contains("dbfilesclient/map.dbc"));
+    assert!(!storage.contains("missing.txt"));
+    assert_eq!(storage.read("DBFilesClient\\Map.dbc").unwrap(), b"map data");
+    assert_eq!(storage.read_if_exists("missing.txt").unwrap(), None);
+    assert!(matches!(
+        storage.read("missing.txt"),
+        Err(Error::FileNotFound(_))
+    ));
+
+    let names: Vec<String> = listing(storage).into_iter().map(|(name, _)| name).collect();
+    assert!(names.contains(&"DBFilesClient\\Map.dbc".to_string()));
+    assert!(names.contains(&"readme.txt".to_string()));
+}
+
+#[test]
+fn test_patch_chain_storage_opens_game_dir() {
+    let dir = TempDir::new().unwrap();
+    let data = dir.path().join("Data");
+    fs::create_dir(&data).unwrap();
+
+    let build = |path: &Path, files: &[(&str, &[u8])]| {
+        let mut builder = ArchiveBuilder::new();
+        for (name, contents) in files {
+            builder = builder.add_file_data(contents.to_vec(), name);
+        }
+        builder.build(path).unwrap();
+    };
+    build(
+        &data.join("dbc.MPQ"),
+        &[
+            ("DBFilesClient\\Map.dbc", b"base"),
+            ("base.txt", b"base only"),
+        ],
+    );
+    build(
+        &data.join("patch.MPQ"),
+        &[("DBFilesClient\\Map.dbc", b"patched")],
+    );
+
+    let mut chain = <PatchChain as Storage>::open(dir.path()).unwrap();
+    assert_eq!(chain.archive_count(), 2);
+
+    let storage: &mut dyn Storage = &mut chain;
+    assert_eq!(storage.read("DBFilesClient\\Map.dbc").unwrap(), b"patched");
+    assert_eq!(
+        storage.read_if_exists("base.txt").unwrap().as_deref(),
+        Some(&b"base only"[..])
+    );
+    assert_eq!(storage.read_if_exists("missing.txt").unwrap(), None);
+
+    let entries = listing(storage);
+    assert!(entries.contains(&("DBFilesClient\\Map.dbc".to_string(), 7)));
+    assert!(entries.contains(&("base.txt".to_string(), 9)));
+}
+
+#[test]
+fn test_patch_chain_storage_rejects_unknown_dir() {
+    let dir = TempDir::new().unwrap();
+    assert!(<PatchChain as Storage>::open(dir.path()).is_err());
+}
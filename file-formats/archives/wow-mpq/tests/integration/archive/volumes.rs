@@ -0,0 +1,38 @@
+//! Tests for archives split into several volumes
+
+use std::fs;
+use tempfile::TempDir;
+use wow_mpq::{Archive, ArchiveBuilder};
+
+#[test]
+fn test_open_multi() {
+    let large: Vec<u8> = (0..20_000u32).flat_map(|i| i.to_le_bytes()).collect();
+    let data = ArchiveBuilder::new()
+        .add_file_data(b"first file".to_vec(), "a.txt")
+        .add_file_data(large.clone(), "b.bin")
+        .build_to_vec()
+        .unwrap();
+
+    // Cut through the header, the file data and the tables
+    let temp_dir = TempDir::new().unwrap();
+    let cuts = [0, 17, data.len() / 2, data.len() - 30, data.len()];
+    let paths: Vec<_> = cuts
+        .windows(2)
+        .enumerate()
+        .map(|(i, range)| {
+            let path = temp_dir.path().join(format!("data.mpq.part{}", i + 1));
+            fs::write(&path, &data[range[0]..range[1]]).unwrap();
+            path
+        })
+        .collect();
+
+    let mut archive = Archive::open_multi(&paths).unwrap();
+    assert_eq!(archive.read_file("a.txt").unwrap(), b"first file");
+    assert_eq!(archive.read_file("b.bin").unwrap(), large);
+
+    // Without its last volumes the archive is cut short
+    let read_truncated =
+        Archive::open_multi(&paths[..2]).and_then(|mut archive| archive.read_file("b.bin"));
+    assert!(read_truncated.is_err());
+    assert!(Archive::open_multi::<&str>(&[]).is_err());
+}
@@ -0,0 +1,46 @@
+//! Tests for Warcraft III map headers in front of the MPQ data
+
+use std::io::Cursor;
+use wow_mpq::warcraft3::W3_MAP_HEADER_SIZE;
+use wow_mpq::{Archive, ArchiveBuilder, W3MapHeader};
+
+fn archive_bytes() -> Vec<u8> {
+    let temp = tempfile::NamedTempFile::new().unwrap();
+    ArchiveBuilder::new()
+        .add_file_data(b"war3map.j contents".to_vec(), "war3map.j")
+        .build(temp.path())
+        .unwrap();
+    std::fs::read(temp.path()).unwrap()
+}
+
+#[test]
+fn test_map_header_before_archive() {
+    let header = W3MapHeader {
+        unknown: 0,
+        name: "(2)Echo Isles".to_string(),
+        flags: W3MapHeader::FLAG_MELEE,
+        max_players: 2,
+    };
+    let mut map = header.to_bytes().unwrap();
+    map.extend_from_slice(&archive_bytes());
+
+    let mut archive = Archive::from_reader(Cursor::new(map)).unwrap();
+    assert_eq!(archive.archive_offset(), W3_MAP_HEADER_SIZE as u64);
+    assert_eq!(archive.map_header(), Some(&header));
+    assert_eq!(
+        archive.read_file("war3map.j").unwrap(),
+        b"war3map.j contents"
+    );
+}
+
+#[test]
+fn test_plain_archive_has_no_map_header() {
+    let archive = Archive::from_reader(Cursor::new(archive_bytes())).unwrap();
+    assert!(archive.map_header().is_none());
+
+    // Other data in front of the archive is not mistaken for a map header
+    let mut padded = vec![0xAA; W3_MAP_HEADER_SIZE];
+    padded.extend_from_slice(&archive_bytes());
+    let archive = Archive::from_reader(Cursor::new(padded)).unwrap();
+    assert!(archive.map_header().is_none());
+}
@@ -84,9 +84,9 @@ fn test_multiple_compression() {
 fn test_all_compression_methods_implemented() {
     let test_data = b"Test data for compression";
 
-    // Most compression methods are implemented, except Huffman (only decompression is available)
+    // Every compression method is implemented (ADPCM needs audio data)
     let implemented_methods = [
-        // flags::HUFFMAN,  // Only decompression is implemented
+        flags::HUFFMAN,
         flags::ZLIB,
         flags::PKWARE,
         flags::BZIP2,
@@ -138,12 +138,8 @@ fn test_all_compression_methods_implemented() {
         "ADPCM stereo compression should succeed with valid audio data"
     );
 
-    // Test that Huffman compression is not implemented
-    let result = compress_with_method(test_data, flags::HUFFMAN);
-    assert!(
-        result.is_err(),
-        "Huffman compression should fail (only decompression is available)"
-    );
+    // Huffman round trips on its own
+    test_round_trip(&test_data.repeat(8), flags::HUFFMAN).expect("Huffman round trip failed");
 }
 
 #[test]
@@ -0,0 +1,81 @@
+//! Files stored with the IMPLODE block flag, as in Diablo and StarCraft archives
+
+use tempfile::TempDir;
+use wow_mpq::compression::flags;
+use wow_mpq::{Archive, ArchiveBuilder, FormatVersion};
+
+/// Text with enough repetition for PKWare DCL to shrink it
+fn sample_text(len: usize) -> Vec<u8> {
+    let words = ["Tristram", "Cathedral", "Deckard", "Cain", "Diablo", "Lord"];
+    let mut data = Vec::with_capacity(len + 16);
+    let mut seed = 7u32;
+    while data.len() < len {
+        seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+        data.extend_from_slice(words[(seed >> 16) as usize % words.len()].as_bytes());
+        data.push(b' ');
+    }
+    data.truncate(len);
+    data
+}
+
+#[test]
+fn test_imploded_files_round_trip() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("imploded.mpq");
+
+    let small = sample_text(2_000);
+    let large = sample_text(20_000);
+    let mut state = 0x2545_f491u32;
+    let random: Vec<u8> = (0..3_000)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        })
+        .collect();
+
+    ArchiveBuilder::new()
+        .version(FormatVersion::V1)
+        .add_file_data_with_options(small.clone(), "small.txt", flags::IMPLODE, false, 0)
+        .add_file_data_with_options(large.clone(), "large.txt", flags::IMPLODE, false, 0)
+        .add_file_data_with_options(large.clone(), "secret.txt", flags::IMPLODE, true, 0)
+        .add_file_data_with_options(random.clone(), "random.bin", flags::IMPLODE, false, 0)
+        .build(&path)
+        .unwrap();
+
+    let mut archive = Archive::open(&path).unwrap();
+    for (name, data) in [
+        ("small.txt", &small),
+        ("large.txt", &large),
+        ("secret.txt", &large),
+    ] {
+        let info = archive.find_file(name).unwrap().unwrap();
+        assert!(info.is_implode(), "{name} should use the IMPLODE flag");
+        assert!(info.compressed_size < info.file_size);
+        assert_eq!(&archive.read_file(name).unwrap(), data);
+    }
+
+    // Data that does not shrink is stored as is
+    let info = archive.find_file("random.bin").unwrap().unwrap();
+    assert!(!info.is_implode());
+    assert_eq!(archive.read_file("random.bin").unwrap(), random);
+}
+
+#[test]
+fn test_pkware_multi_sector_round_trip() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("pkware.mpq");
+
+    // Sectors larger than the 4 KiB DCL dictionary
+    let data = sample_text(100_000);
+    ArchiveBuilder::new()
+        .version(FormatVersion::V1)
+        .block_size(4)
+        .add_file_data_with_options(data.clone(), "data.txt", flags::PKWARE, false, 0)
+        .build(&path)
+        .unwrap();
+
+    let mut archive = Archive::open(&path).unwrap();
+    assert_eq!(archive.read_file("data.txt").unwrap(), data);
+}
@@ -3,3 +3,6 @@
 //! Tests the complete compression API and multi-algorithm support.
 
 mod api;
+mod implode;
+mod wave;
+mod zstd;
@@ -0,0 +1,99 @@
+//! WAVE files compressed with ADPCM followed by Huffman, as in Warcraft III
+
+use tempfile::TempDir;
+use wow_mpq::compression::{compress, decompress, flags};
+use wow_mpq::{Archive, ArchiveBuilder, FormatVersion};
+
+/// A 16-bit PCM WAVE file with a tone on each channel
+fn wave_file(channels: u16, frames: usize) -> Vec<u8> {
+    let data_len = frames * channels as usize * 2;
+    let mut wave = Vec::with_capacity(44 + data_len);
+    wave.extend_from_slice(b"RIFF");
+    wave.extend_from_slice(&(36 + data_len as u32).to_le_bytes());
+    wave.extend_from_slice(b"WAVEfmt ");
+    wave.extend_from_slice(&16u32.to_le_bytes());
+    wave.extend_from_slice(&1u16.to_le_bytes());
+    wave.extend_from_slice(&channels.to_le_bytes());
+    wave.extend_from_slice(&22_050u32.to_le_bytes());
+    wave.extend_from_slice(&(22_050 * channels as u32 * 2).to_le_bytes());
+    wave.extend_from_slice(&(channels * 2).to_le_bytes());
+    wave.extend_from_slice(&16u16.to_le_bytes());
+    wave.extend_from_slice(b"data");
+    wave.extend_from_slice(&(data_len as u32).to_le_bytes());
+    for frame in 0..frames {
+        for channel in 0..channels {
+            let phase = frame as f32 * (0.03 + channel as f32 * 0.02);
+            let sample = (phase.sin() * 8000.0) as i16;
+            wave.extend_from_slice(&sample.to_le_bytes());
+        }
+    }
+    wave
+}
+
+/// Largest difference between the samples of two equally long PCM buffers
+fn max_sample_error(a: &[u8], b: &[u8]) -> i32 {
+    a.chunks_exact(2)
+        .zip(b.chunks_exact(2))
+        .map(|(a, b)| {
+            let a = i16::from_le_bytes([a[0], a[1]]) as i32;
+            let b = i16::from_le_bytes([b[0], b[1]]) as i32;
+            (a - b).abs()
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+#[test]
+fn test_adpcm_huffman_round_trip() {
+    let pcm = &wave_file(1, 2048)[44..];
+    let method = flags::ADPCM_MONO | flags::HUFFMAN;
+
+    let compressed = compress(pcm, method).unwrap();
+    assert_eq!(compressed[0], method);
+    assert!(compressed.len() < pcm.len() / 2);
+
+    let decompressed = decompress(&compressed[1..], method, pcm.len()).unwrap();
+    assert_eq!(decompressed.len(), pcm.len());
+    assert!(max_sample_error(pcm, &decompressed) < 800);
+}
+
+#[test]
+fn test_wave_files_in_archive() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("sound.mpq");
+
+    let mono = wave_file(1, 20_000);
+    let stereo = wave_file(2, 15_001);
+    ArchiveBuilder::new()
+        .version(FormatVersion::V1)
+        .add_file_data_with_options(
+            mono.clone(),
+            "Sound\\Mono.wav",
+            flags::ADPCM_MONO | flags::HUFFMAN,
+            false,
+            0,
+        )
+        .add_file_data_with_options(
+            stereo.clone(),
+            "Sound\\Stereo.wav",
+            flags::ADPCM_STEREO | flags::HUFFMAN,
+            true,
+            0,
+        )
+        .build(&path)
+        .unwrap();
+
+    let mut archive = Archive::open(&path).unwrap();
+    for (name, original) in [("Sound\\Mono.wav", &mono), ("Sound\\Stereo.wav", &stereo)] {
+        let info = archive.find_file(name).unwrap().unwrap();
+        assert!(info.compressed_size * 5 < info.file_size * 3);
+
+        let data = archive.read_file(name).unwrap();
+        assert_eq!(data.len(), original.len());
+
+        // The first sector, holding the header, is stored losslessly
+        let sector_size = archive.header().sector_size();
+        assert_eq!(data[..sector_size], original[..sector_size]);
+        assert!(max_sample_error(&original[sector_size..], &data[sector_size..]) < 800);
+    }
+}
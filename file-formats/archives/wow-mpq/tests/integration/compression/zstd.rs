@@ -0,0 +1,120 @@
+//! Zstandard, which only archives built with strict compatibility disabled use
+
+use tempfile::TempDir;
+use wow_mpq::compression::{CompressionMethod, flags};
+use wow_mpq::{AddFileOptions, ArchiveBuilder, Error, MutableArchive};
+
+#[cfg(feature = "zstd")]
+use wow_mpq::{Archive, FormatVersion};
+
+/// Data that compresses well and spans several sectors
+#[cfg(feature = "zstd")]
+fn sample_data() -> Vec<u8> {
+    (0..40_000u32)
+        .flat_map(|i| format!("row {} of the tooling table\n", i % 500).into_bytes())
+        .collect()
+}
+
+#[test]
+#[cfg(feature = "zstd")]
+fn test_zstd_builder_round_trip() {
+    let temp_dir = TempDir::new().unwrap();
+    let data = sample_data();
+
+    for version in [FormatVersion::V1, FormatVersion::V4] {
+        let path = temp_dir.path().join(format!("zstd-{version:?}.mpq"));
+        ArchiveBuilder::new()
+            .version(version)
+            .strict_compatibility(false)
+            .default_compression(flags::ZSTD)
+            .add_file_data(data.clone(), "data\\table.txt")
+            .add_file_data(b"tiny".to_vec(), "data\\tiny.txt")
+            .build(&path)
+            .unwrap();
+
+        let mut archive = Archive::open(&path).unwrap();
+        let info = archive.find_file("data\\table.txt").unwrap().unwrap();
+        assert!(info.compressed_size < data.len() as u64 / 4);
+        assert_eq!(archive.read_file("data\\table.txt").unwrap(), data);
+        assert_eq!(archive.read_file("data\\tiny.txt").unwrap(), b"tiny");
+    }
+}
+
+#[test]
+#[cfg(feature = "zstd")]
+fn test_zstd_mutable_round_trip() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("mutable.mpq");
+    ArchiveBuilder::new()
+        .add_file_data(b"existing".to_vec(), "existing.txt")
+        .build(&path)
+        .unwrap();
+
+    let data = sample_data();
+    let mut archive = MutableArchive::open(&path).unwrap();
+    archive.set_strict_compatibility(false);
+    archive
+        .add_file_data(
+            &data,
+            "added.txt",
+            AddFileOptions::new().compression(CompressionMethod::Zstd),
+        )
+        .unwrap();
+    archive
+        .add_file_data(
+            &data,
+            "checksummed.txt",
+            AddFileOptions::new()
+                .compression(CompressionMethod::Zstd)
+                .sector_crc(true),
+        )
+        .unwrap();
+    archive.flush().unwrap();
+    drop(archive);
+
+    let mut archive = Archive::open(&path).unwrap();
+    assert_eq!(archive.read_file("added.txt").unwrap(), data);
+    assert_eq!(archive.read_file("checksummed.txt").unwrap(), data);
+    assert_eq!(archive.read_file("existing.txt").unwrap(), b"existing");
+}
+
+#[test]
+fn test_strict_compatibility_refuses_zstd() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("strict.mpq");
+
+    // Per file and as the default, whether or not the feature is enabled
+    let per_file = ArchiveBuilder::new()
+        .add_file_data(b"standard".to_vec(), "standard.txt")
+        .add_file_data_with_options(b"custom".to_vec(), "custom.txt", flags::ZSTD, false, 0)
+        .build(&path);
+    assert!(matches!(per_file, Err(Error::UnsupportedFeature(_))));
+    assert!(!path.exists());
+
+    let default = ArchiveBuilder::new()
+        .default_compression(flags::ZSTD)
+        .add_file_data(b"custom".to_vec(), "custom.txt")
+        .build_to_vec();
+    assert!(matches!(default, Err(Error::UnsupportedFeature(_))));
+
+    ArchiveBuilder::new()
+        .add_file_data(b"standard".to_vec(), "standard.txt")
+        .build(&path)
+        .unwrap();
+    let before = std::fs::read(&path).unwrap();
+
+    let mut archive = MutableArchive::open(&path).unwrap();
+    assert!(archive.is_strict_compatibility());
+    let result = archive.add_file_data(
+        b"custom",
+        "standard.txt",
+        AddFileOptions::new()
+            .compression(CompressionMethod::Zstd)
+            .replace_existing(true),
+    );
+    assert!(matches!(result, Err(Error::UnsupportedFeature(_))));
+    archive.flush().unwrap();
+    drop(archive);
+
+    assert_eq!(std::fs::read(&path).unwrap(), before);
+}
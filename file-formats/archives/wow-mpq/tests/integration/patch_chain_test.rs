@@ -4,7 +4,9 @@
 
 use std::path::Path;
 use tempfile::TempDir;
-use wow_mpq::{ArchiveBuilder, ListfileOption, PatchChain};
+use wow_mpq::{
+    Archive, ArchiveBuilder, ListfileOption, PatchChain, PatchOptions, generate_patch_archive,
+};
 
 /// Helper to create a test MPQ archive
 fn create_archive(dir: &Path, name: &str, files: &[(&str, &[u8])]) -> std::path::PathBuf {
@@ -197,3 +199,85 @@ fn test_chain_listing() {
     assert!(names.contains(&"file2.txt"));
     assert!(names.contains(&"file3.txt"));
 }
+
+#[test]
+fn test_generated_patch_archive() {
+    let temp = TempDir::new().unwrap();
+    let source = create_archive(
+        temp.path(),
+        "release-1.mpq",
+        &[
+            ("Data\\same.txt", b"unchanged"),
+            ("Data\\changed.txt", b"old contents"),
+            ("Data\\resized.txt", b"short"),
+            ("Data\\removed.txt", b"going away"),
+        ],
+    );
+    let target = create_archive(
+        temp.path(),
+        "release-2.mpq",
+        &[
+            ("Data\\same.txt", b"unchanged"),
+            ("data/CHANGED.txt", b"new contents"),
+            ("Data\\resized.txt", b"a lot longer now"),
+            ("Data\\added.txt", b"brand new"),
+        ],
+    );
+    let patch = temp.path().join("patch-2.mpq");
+
+    let options = PatchOptions {
+        deletion_markers: true,
+        ..Default::default()
+    };
+    let summary = generate_patch_archive(&source, &target, &patch, &options).unwrap();
+    assert_eq!(summary.added, ["Data\\added.txt"]);
+    assert_eq!(summary.changed, ["data\\CHANGED.txt", "Data\\resized.txt"]);
+    assert_eq!(summary.deleted, ["Data\\removed.txt"]);
+    assert_eq!(summary.unchanged, 1);
+
+    // Only the differences are stored
+    let mut patch_archive = Archive::open(&patch).unwrap();
+    let names: Vec<_> = patch_archive
+        .list()
+        .unwrap()
+        .into_iter()
+        .map(|file| file.name)
+        .filter(|name| name != "(listfile)")
+        .collect();
+    assert_eq!(names.len(), 4);
+    assert!(!names.iter().any(|name| name.contains("same")));
+
+    // The source with the patch on top reads like the target
+    let mut chain = PatchChain::new();
+    chain.add_archive(&source, 0).unwrap();
+    chain.add_archive(&patch, 100).unwrap();
+    assert_eq!(chain.read_file("Data\\same.txt").unwrap(), b"unchanged");
+    assert_eq!(
+        chain.read_file("Data\\changed.txt").unwrap(),
+        b"new contents"
+    );
+    assert_eq!(
+        chain.read_file("Data\\resized.txt").unwrap(),
+        b"a lot longer now"
+    );
+    assert_eq!(chain.read_file("Data\\added.txt").unwrap(), b"brand new");
+    assert!(chain.read_file("Data\\removed.txt").is_err());
+    assert!(!chain.contains_file("Data\\removed.txt"));
+    assert!(
+        !chain
+            .list()
+            .unwrap()
+            .iter()
+            .any(|file| file.name.contains("removed"))
+    );
+
+    // Without markers the removed file stays visible through the source
+    let patch = temp.path().join("patch-2-keep.mpq");
+    let summary =
+        generate_patch_archive(&source, &target, &patch, &PatchOptions::default()).unwrap();
+    assert_eq!(summary.deleted, ["Data\\removed.txt"]);
+    let mut chain = PatchChain::new();
+    chain.add_archive(&source, 0).unwrap();
+    chain.add_archive(&patch, 100).unwrap();
+    assert_eq!(chain.read_file("Data\\removed.txt").unwrap(), b"going away");
+}
@@ -2,7 +2,7 @@
 
 use std::fs;
 use tempfile::TempDir;
-use wow_mpq::{Archive, ArchiveBuilder};
+use wow_mpq::{Archive, ArchiveBuilder, OpenOptions, PatchChain, PathPolicy};
 
 #[test]
 fn test_path_normalization_in_archive() {
@@ -148,3 +148,51 @@ fn test_jenkins_hash_consistency() {
     // Jenkins hash should also normalize paths
     assert_eq!(jenkins_hash(path1), jenkins_hash(path2));
 }
+
+#[test]
+fn test_strict_path_policy() {
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("test.mpq");
+
+    ArchiveBuilder::new()
+        .add_file_data(b"strict".to_vec(), "Dir\\File.txt")
+        .build(&archive_path)
+        .unwrap();
+
+    // The default policy finds every spelling the name hash matches
+    let mut archive = Archive::open(&archive_path).unwrap();
+    assert_eq!(archive.path_policy(), PathPolicy::LENIENT);
+    assert_eq!(archive.read_file("dir/file.txt").unwrap(), b"strict");
+
+    archive.set_path_policy(PathPolicy::STRICT).unwrap();
+    assert!(archive.find_file("dir/file.txt").unwrap().is_none());
+    assert!(archive.find_file("Dir/File.txt").unwrap().is_none());
+    assert!(archive.find_file("dir\\file.txt").unwrap().is_none());
+    assert_eq!(archive.read_file("Dir\\File.txt").unwrap(), b"strict");
+    // Special files are exempt
+    assert!(archive.find_file("(listfile)").unwrap().is_some());
+
+    let archive = OpenOptions::new()
+        .path_policy(PathPolicy {
+            fold_case: true,
+            convert_separators: false,
+        })
+        .open(&archive_path)
+        .unwrap();
+    assert!(archive.find_file("dir\\file.txt").unwrap().is_some());
+    assert!(archive.find_file("dir/file.txt").unwrap().is_none());
+
+    let mut chain = PatchChain::new();
+    chain.set_path_policy(PathPolicy::STRICT).unwrap();
+    // Archives added after the policy is set follow it too
+    chain.add_archive(&archive_path, 0).unwrap();
+    assert!(!chain.contains_file("dir/file.txt"));
+    assert!(chain.find_file_archive("dir/file.txt").is_none());
+    assert!(chain.read_file("dir/file.txt").is_err());
+    assert!(chain.contains_file("Dir\\File.txt"));
+    assert_eq!(chain.read_file("Dir\\File.txt").unwrap(), b"strict");
+
+    chain.set_path_policy(PathPolicy::LENIENT).unwrap();
+    assert!(chain.contains_file("dir/file.txt"));
+    assert_eq!(chain.read_file("dir/file.txt").unwrap(), b"strict");
+}
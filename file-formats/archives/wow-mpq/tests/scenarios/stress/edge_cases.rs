@@ -79,9 +79,9 @@ fn test_compression_efficiency() {
 fn test_all_methods_implemented() {
     let data = b"test data for compression";
 
-    // Most single compression methods should succeed (except Huffman and ADPCM which need special data)
+    // Single compression methods succeed (ADPCM needs audio data)
     let methods = [
-        // flags::HUFFMAN,  // Only decompression is implemented
+        flags::HUFFMAN,
         flags::ZLIB,
         flags::PKWARE,
         flags::BZIP2,
@@ -118,12 +118,8 @@ fn test_all_methods_implemented() {
         "ADPCM stereo compression should work with valid audio data"
     );
 
-    // Test that Huffman compression is not implemented
-    let result = compress_with_method(data, flags::HUFFMAN);
-    assert!(
-        result.is_err(),
-        "Huffman compression should not be implemented"
-    );
+    // Huffman round trips on its own
+    test_round_trip(&data.repeat(8), flags::HUFFMAN).expect("Huffman round trip failed");
 
     // Multiple compression with single method + ADPCM should work
     let result = compress_with_method(&audio_bytes, flags::ZLIB | flags::ADPCM_MONO);
@@ -81,7 +81,7 @@ impl TypeSize {
 }
 
 /// Represents a BUILD section with version info and fields
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct DbdBuild {
     pub versions: Vec<String>,
     pub fields: Vec<DbdField>,
@@ -90,7 +90,10 @@ pub struct DbdBuild {
 /// Represents a LAYOUT section with hash and associated builds
 #[derive(Debug)]
 pub struct DbdLayout {
+    /// First layout hash, as written in the definition
     pub hash: String,
+    /// All layout hashes sharing this section
+    pub hashes: Vec<u32>,
     pub builds: Vec<String>,
     pub fields: Vec<DbdField>,
 }
@@ -103,6 +106,76 @@ pub struct DbdFile {
     pub layouts: Vec<DbdLayout>,
 }
 
+impl DbdFile {
+    /// Find the LAYOUT section for a layout hash from a DB2 header
+    pub fn layout_for_hash(&self, hash: u32) -> Option<&DbdLayout> {
+        self.layouts
+            .iter()
+            .find(|layout| layout.hashes.contains(&hash))
+    }
+
+    /// Find the field layout used by `build`.
+    ///
+    /// `build` may be a full build (`3.3.5.12340`) or a version prefix
+    /// (`3.3.5`), which matches any build of that version. When several
+    /// definitions match, the most recently listed one wins.
+    pub fn definition_for_build(&self, build: &str) -> Option<DbdBuild> {
+        let from_layouts = self.layouts.iter().rev().find_map(|layout| {
+            layout
+                .builds
+                .iter()
+                .any(|spec| build_matches(spec, build))
+                .then(|| DbdBuild {
+                    versions: layout.builds.clone(),
+                    fields: layout.fields.clone(),
+                })
+        });
+        from_layouts.or_else(|| {
+            self.builds
+                .iter()
+                .rev()
+                .find(|b| b.versions.iter().any(|spec| build_matches(spec, build)))
+                .cloned()
+        })
+    }
+
+    /// Whether any definition in the file covers `build`
+    pub fn supports_build(&self, build: &str) -> bool {
+        self.builds
+            .iter()
+            .flat_map(|b| &b.versions)
+            .chain(self.layouts.iter().flat_map(|l| &l.builds))
+            .any(|spec| build_matches(spec, build))
+    }
+}
+
+/// Parse the numeric parts of a build string, padding missing parts with `fill`
+fn parse_build(s: &str, fill: u32) -> Option<[u32; 4]> {
+    let mut parts = [fill; 4];
+    for (i, part) in s.trim().split('.').enumerate() {
+        *parts.get_mut(i)? = part.parse().ok()?;
+    }
+    Some(parts)
+}
+
+/// Check whether a DBD build entry covers the target build.
+///
+/// `spec` is an entry from a `BUILD` line, either a single build
+/// (`3.3.5.12340`) or a range (`1.12.0.5595-1.12.1.5875`). `target` may be a
+/// full build or a version prefix such as `3.3.5`, which matches every build
+/// that starts with it.
+pub fn build_matches(spec: &str, target: &str) -> bool {
+    let (spec_low, spec_high) = spec.split_once('-').unwrap_or((spec, spec));
+    let (Some(spec_low), Some(spec_high)) = (parse_build(spec_low, 0), parse_build(spec_high, 0))
+    else {
+        return false;
+    };
+    let (Some(low), Some(high)) = (parse_build(target, 0), parse_build(target, u32::MAX)) else {
+        return false;
+    };
+    spec_low <= high && low <= spec_high
+}
+
 /// Parse a DBD file from the given path
 pub fn parse_dbd_file(path: &Path) -> Result<DbdFile, Box<dyn std::error::Error>> {
     let content = fs::read_to_string(path)?;
@@ -119,6 +192,7 @@ pub fn parse_dbd_content(content: &str) -> Result<DbdFile, Box<dyn std::error::E
     let mut current_build_versions = Vec::new();
     let mut current_build_fields = Vec::new();
     let mut current_layout_hash = String::new();
+    let mut current_layout_hashes = Vec::new();
     let mut current_layout_builds = Vec::new();
 
     for line in content.lines() {
@@ -140,6 +214,7 @@ pub fn parse_dbd_content(content: &str) -> Result<DbdFile, Box<dyn std::error::E
             save_pending_layout(
                 &mut layouts,
                 &mut current_layout_hash,
+                &mut current_layout_hashes,
                 &mut current_layout_builds,
                 &mut current_build_fields,
             );
@@ -148,6 +223,11 @@ pub fn parse_dbd_content(content: &str) -> Result<DbdFile, Box<dyn std::error::E
             continue;
         } else if let Some(stripped) = line.strip_prefix("BUILD ") {
             let versions: Vec<String> = stripped.split(", ").map(|s| s.to_string()).collect();
+            // BUILD lines directly after LAYOUT list the builds of that layout
+            if current_section == Some("LAYOUT") && current_build_fields.is_empty() {
+                current_layout_builds.extend(versions);
+                continue;
+            }
             // Stacked BUILD lines (consecutive BUILDs with no fields between them)
             // share the same following field block. Accumulate versions instead
             // of starting a new build with an empty body.
@@ -159,6 +239,13 @@ pub fn parse_dbd_content(content: &str) -> Result<DbdFile, Box<dyn std::error::E
                     &mut current_build_versions,
                     &mut current_build_fields,
                 );
+                save_pending_layout(
+                    &mut layouts,
+                    &mut current_layout_hash,
+                    &mut current_layout_hashes,
+                    &mut current_layout_builds,
+                    &mut current_build_fields,
+                );
                 current_build_versions = versions;
             }
             current_section = Some("BUILD");
@@ -173,6 +260,7 @@ pub fn parse_dbd_content(content: &str) -> Result<DbdFile, Box<dyn std::error::E
             save_pending_layout(
                 &mut layouts,
                 &mut current_layout_hash,
+                &mut current_layout_hashes,
                 &mut current_layout_builds,
                 &mut current_build_fields,
             );
@@ -180,6 +268,10 @@ pub fn parse_dbd_content(content: &str) -> Result<DbdFile, Box<dyn std::error::E
             current_section = Some("LAYOUT");
             let parts: Vec<&str> = stripped.split(", ").collect();
             current_layout_hash = parts[0].to_string();
+            current_layout_hashes = parts
+                .iter()
+                .filter_map(|hash| u32::from_str_radix(hash.trim(), 16).ok())
+                .collect();
             current_layout_builds.clear();
             continue;
         }
@@ -220,6 +312,7 @@ pub fn parse_dbd_content(content: &str) -> Result<DbdFile, Box<dyn std::error::E
     save_pending_layout(
         &mut layouts,
         &mut current_layout_hash,
+        &mut current_layout_hashes,
         &mut current_layout_builds,
         &mut current_build_fields,
     );
@@ -249,12 +342,14 @@ fn save_pending_build(
 fn save_pending_layout(
     layouts: &mut Vec<DbdLayout>,
     hash: &mut String,
+    hashes: &mut Vec<u32>,
     builds: &mut Vec<String>,
     fields: &mut Vec<DbdField>,
 ) {
     if !hash.is_empty() && !fields.is_empty() {
         layouts.push(DbdLayout {
             hash: hash.clone(),
+            hashes: std::mem::take(hashes),
             builds: builds.clone(),
             fields: fields.clone(),
         });
@@ -546,19 +641,6 @@ fn generate_filename(base_name: &str, version: &str) -> String {
     format!("{base_name}_{sanitized_version}.yaml")
 }
 
-/// Locale ordering for the WotLK-era (3.x and 4.0 pre-collapse) locstring layout.
-/// 16 stringref slots are stored in this order, followed by a single u32 flags field.
-const LOCSTRING_LOCALES_WOTLK: &[&str] = &[
-    "enUS", "koKR", "frFR", "deDE", "zhCN", "zhTW", "esES", "esMX", "ruRU", "unk9", "unk10",
-    "unk11", "unk12", "unk13", "unk14", "unk15",
-];
-
-/// Locale ordering for the Vanilla/TBC-era (1.x, 2.x) locstring layout.
-/// 8 stringref slots are stored in this order, followed by a single u32 flags field.
-const LOCSTRING_LOCALES_CLASSIC: &[&str] = &[
-    "enUS", "koKR", "frFR", "deDE", "zhCN", "zhTW", "esES", "esMX",
-];
-
 /// Determine the per-locale stringref slots that make up a `locstring`.
 ///
 /// When `target_version` is provided (e.g. the `--version` CLI filter), the
@@ -582,11 +664,11 @@ fn locstring_locales_for_build(
     let minor: u32 = parts.next().and_then(|s| s.parse().ok()).unwrap_or(0);
 
     match major {
-        0..=2 => LOCSTRING_LOCALES_CLASSIC,
-        3 => LOCSTRING_LOCALES_WOTLK,
+        0..=2 => crate::LOCALES_CLASSIC,
+        3 => crate::LOCALES_WOTLK,
         // The 4.0 pre-Cataclysm client kept the WotLK locstring layout; from
         // 4.1 onward it collapsed back to a single string field.
-        4 if minor == 0 => LOCSTRING_LOCALES_WOTLK,
+        4 if minor == 0 => crate::LOCALES_WOTLK,
         _ => &[],
     }
 }
@@ -712,6 +794,137 @@ fn generate_field_description(field: &DbdField, column: Option<&&DbdColumn>) ->
     }
 }
 
+/// Generate the YAML schema for the definition that covers `build`.
+///
+/// Returns `None` when the DBD file has no definition for the build.
+pub fn yaml_schema_for_build(dbd_file: &DbdFile, base_name: &str, build: &str) -> Option<String> {
+    let definition = dbd_file.definition_for_build(build)?;
+    let column_map: HashMap<String, &DbdColumn> = dbd_file
+        .columns
+        .iter()
+        .map(|c| (c.name.clone(), c))
+        .collect();
+    let version_suffix = determine_version_suffix(&definition.versions);
+    Some(generate_yaml_schema(
+        &column_map,
+        &definition,
+        base_name,
+        &version_suffix,
+        Some(build),
+    ))
+}
+
+/// Generate a Rust record struct for the definition that covers `build`.
+///
+/// Field types follow the record layout on disk: sized integers map to the
+/// matching primitive, strings to [`StringRef`](crate::StringRef) and
+/// pre-4.1 `locstring`s to one `StringRef` per locale plus a flags word.
+/// The output refers to `StringRef` unqualified, so callers emitting several
+/// structs into one file add the `use` themselves.
+///
+/// Returns `None` when the DBD file has no definition for the build.
+pub fn generate_rust_struct(dbd_file: &DbdFile, base_name: &str, build: &str) -> Option<String> {
+    let definition = dbd_file.definition_for_build(build)?;
+    let column_map: HashMap<&str, &DbdColumn> = dbd_file
+        .columns
+        .iter()
+        .map(|c| (c.name.as_str(), c))
+        .collect();
+    let locstring_locales = locstring_locales_for_build(&definition, Some(build));
+
+    let mut code = String::new();
+    code.push_str(&format!("/// `{base_name}` record for build {build}\n"));
+    code.push_str("#[derive(Debug, Clone, Copy, PartialEq)]\n");
+    code.push_str(&format!("pub struct {base_name} {{\n"));
+
+    for field in &definition.fields {
+        let column = column_map.get(field.name.as_str());
+        if let Some(comment) = column.and_then(|c| c.comment.as_deref()) {
+            code.push_str(&format!("    /// {comment}\n"));
+        } else if let Some(fk) = column.and_then(|c| c.foreign_key.as_ref()) {
+            code.push_str(&format!(
+                "    /// Reference to `{}::{}`\n",
+                fk.table, fk.field
+            ));
+        }
+
+        let name = rust_field_name(&field.name);
+        let base_type = column.map_or("int", |c| c.base_type.as_str());
+        let element = if base_type == "locstring" && !locstring_locales.is_empty() {
+            format!("[StringRef; {}]", locstring_locales.len())
+        } else {
+            rust_type(&field.type_size, base_type).to_string()
+        };
+        let ty = match field.array_size {
+            Some(size) if field.is_array => format!("[{element}; {size}]"),
+            _ => element,
+        };
+        code.push_str(&format!("    pub {name}: {ty},\n"));
+
+        if base_type == "locstring" && !locstring_locales.is_empty() {
+            code.push_str(&format!(
+                "    pub {}: u32,\n",
+                rust_field_name(&format!("{}_flags", field.name))
+            ));
+        }
+    }
+
+    code.push_str("}\n");
+    Some(code)
+}
+
+fn rust_type(type_size: &TypeSize, base_type: &str) -> &'static str {
+    match type_size {
+        TypeSize::Int8 => "i8",
+        TypeSize::UInt8 => "u8",
+        TypeSize::Int16 => "i16",
+        TypeSize::UInt16 => "u16",
+        TypeSize::Int32 => "i32",
+        TypeSize::UInt32 => "u32",
+        TypeSize::Float => "f32",
+        TypeSize::Unspecified => match base_type {
+            "float" => "f32",
+            "string" | "locstring" => "StringRef",
+            _ => "u32",
+        },
+    }
+}
+
+/// Convert a DBD field name such as `SpellIconID` to a snake_case identifier
+fn rust_field_name(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut out = String::with_capacity(name.len() + 4);
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_ascii_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next_lower = chars.get(i + 1).is_some_and(|n| n.is_ascii_lowercase());
+            if prev != '_' && (prev.is_ascii_lowercase() || prev.is_ascii_digit() || next_lower) {
+                out.push('_');
+            }
+        }
+        out.push(if c.is_ascii_alphanumeric() {
+            c.to_ascii_lowercase()
+        } else {
+            '_'
+        });
+    }
+
+    if out.starts_with(|c: char| c.is_ascii_digit()) {
+        out.insert(0, '_');
+    }
+    const KEYWORDS: &[&str] = &[
+        "as", "break", "const", "continue", "crate", "else", "enum", "extern", "false", "fn",
+        "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref",
+        "return", "static", "struct", "super", "trait", "true", "type", "unsafe", "use", "where",
+        "while", "async", "await", "dyn", "abstract", "become", "box", "do", "final", "macro",
+        "override", "priv", "typeof", "unsized", "virtual", "yield", "try", "gen",
+    ];
+    if KEYWORDS.contains(&out.as_str()) {
+        out.insert_str(0, "r#");
+    }
+    out
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -765,6 +978,34 @@ $id$ID<32>
         assert_eq!(field.type_size, TypeSize::Int32);
     }
 
+    #[test]
+    fn test_layout_for_hash() {
+        let dbd = "\
+COLUMNS
+int ID
+int Flags
+
+LAYOUT 0E84A21C, 1F2D3C4B
+BUILD 7.3.5.26972
+$id$ID<32>
+Flags<32>
+
+LAYOUT 5A6B7C8D
+BUILD 8.0.1.26231
+$id$ID<32>
+Flags<16>
+";
+        let dbd_file = parse_dbd_content(dbd).unwrap();
+        assert_eq!(dbd_file.layouts[0].hash, "0E84A21C");
+        assert_eq!(dbd_file.layouts[0].hashes, [0x0E84A21C, 0x1F2D3C4B]);
+
+        let layout = dbd_file.layout_for_hash(0x1F2D3C4B).unwrap();
+        assert_eq!(layout.builds, ["7.3.5.26972"]);
+        let layout = dbd_file.layout_for_hash(0x5A6B7C8D).unwrap();
+        assert_eq!(layout.fields[1].type_size, TypeSize::Int16);
+        assert!(dbd_file.layout_for_hash(0x12345678).is_none());
+    }
+
     #[test]
     fn test_parse_stacked_builds_share_fields() {
         // AreaTrigger.dbd-style: several consecutive BUILD lines share a single
@@ -960,4 +1201,61 @@ Ui_order<32>
         assert_eq!(col.name, "Name_lang");
         assert!(col.is_optional);
     }
+
+    #[test]
+    fn test_build_matches() {
+        assert!(build_matches("3.3.5.12340", "3.3.5.12340"));
+        assert!(build_matches("3.3.5.12340", "3.3.5"));
+        assert!(build_matches("3.3.5.12340", "3"));
+        assert!(!build_matches("3.3.5.12340", "3.3.3"));
+        assert!(!build_matches("3.3.5.12340", "3.3.5.12341"));
+
+        assert!(build_matches("1.12.0.5595-1.12.1.5875", "1.12.1"));
+        assert!(build_matches("1.12.0.5595-1.12.1.5875", "1.12"));
+        assert!(build_matches("1.12.0.5595-1.12.1.5875", "1.12.0.5600"));
+        assert!(!build_matches("1.12.0.5595-1.12.1.5875", "1.12.2"));
+        assert!(!build_matches("1.12.0.5595-1.12.1.5875", "garbage"));
+    }
+
+    #[test]
+    fn test_generate_rust_struct() {
+        let dbd = "\
+COLUMNS
+int ID
+locstring Name_lang
+float Speed
+int<SpellIcon::ID> SpellIconID
+int Type
+
+BUILD 1.12.0.5595-1.12.1.5875
+$id$ID<32>
+Name_lang
+Speed
+
+BUILD 3.3.5.12340
+$id$ID<32>
+Name_lang
+Speed
+SpellIconID<u16>[2]
+Type<8>
+";
+        let dbd_file = parse_dbd_content(dbd).unwrap();
+        assert!(dbd_file.supports_build("1.12.1"));
+        assert!(!dbd_file.supports_build("2.4.3"));
+        assert!(generate_rust_struct(&dbd_file, "Spell", "2.4.3").is_none());
+
+        let code = generate_rust_struct(&dbd_file, "Spell", "3.3.5").unwrap();
+        assert!(code.contains("pub struct Spell {"));
+        assert!(code.contains("    pub id: i32,\n"));
+        assert!(code.contains("    pub name_lang: [StringRef; 16],\n"));
+        assert!(code.contains("    pub name_lang_flags: u32,\n"));
+        assert!(code.contains("    pub speed: f32,\n"));
+        assert!(code.contains("    /// Reference to `SpellIcon::ID`\n"));
+        assert!(code.contains("    pub spell_icon_id: [u16; 2],\n"));
+        assert!(code.contains("    pub r#type: i8,\n"));
+
+        let classic = generate_rust_struct(&dbd_file, "Spell", "1.12.1").unwrap();
+        assert!(classic.contains("    pub name_lang: [StringRef; 8],\n"));
+        assert!(!classic.contains("spell_icon_id"));
+    }
 }
@@ -33,4 +33,13 @@ pub enum Error {
     /// Type conversion error
     #[error("Type conversion error: {0}")]
     TypeConversion(String),
+
+    /// Invalid query expression
+    #[error("Invalid query: {0}")]
+    InvalidQuery(String),
+
+    /// MPQ archive error
+    #[cfg(feature = "mpq")]
+    #[error("MPQ error: {0}")]
+    Mpq(#[from] wow_mpq::Error),
 }
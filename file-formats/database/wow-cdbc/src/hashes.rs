@@ -0,0 +1,159 @@
+//! Table and layout hashes of DB2 files
+//!
+//! DB2 headers and hotfix caches identify a table by the hash of its name
+//! rather than by file name. [`table_hash`] computes that hash, so a table
+//! can be found from a list of known names such as the WoWDBDefs definition
+//! files. From WDB5 on, headers also carry a layout hash, which changes with
+//! the record structure and is what DBD `LAYOUT` sections are keyed by.
+//!
+//! The client derives the layout hash from structure metadata compiled into
+//! the executable, so it cannot be recomputed from a definition; it is read
+//! with [`Db2Hashes`] and looked up in the DBD instead.
+
+use crate::{Error, Result};
+use std::io::Read;
+
+/// Nibble table of Blizzard's `SStrHash`
+const SSTR_HASH_TABLE: [u32; 16] = [
+    0x486E26EE, 0xDCAA16B3, 0xE1918EEF, 0x202DAFDB, 0x341C7DC7, 0x1C365303, 0x40EF2D37, 0x65FD5E49,
+    0xD6057177, 0x904ECE93, 0x1C38024F, 0x98FD323B, 0xE3061AE7, 0xA39B0FA1, 0x9797F25F, 0xE4444563,
+];
+
+/// Hash of a table name as stored in DB2 headers and hotfix entries
+///
+/// This is `SStrHash`: case-insensitive, with `/` treated as `\`, so
+/// `"ItemSparse"` and `"ITEMSPARSE"` give the same hash.
+pub fn table_hash(name: &str) -> u32 {
+    let mut seed: u32 = 0x7FED7FED;
+    let mut shift: u32 = 0xEEEEEEEE;
+    for byte in name.bytes() {
+        let c = match byte.to_ascii_uppercase() {
+            b'/' => b'\\',
+            c => c,
+        } as u32;
+        seed = SSTR_HASH_TABLE[(c >> 4) as usize].wrapping_sub(SSTR_HASH_TABLE[(c & 0xF) as usize])
+            ^ shift.wrapping_add(seed);
+        shift = c
+            .wrapping_add(seed)
+            .wrapping_add(shift.wrapping_mul(33))
+            .wrapping_add(3);
+    }
+    if seed == 0 { 1 } else { seed }
+}
+
+/// Find the name whose [`table_hash`] is `hash`
+pub fn find_table_by_hash<'a, I>(names: I, hash: u32) -> Option<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    names.into_iter().find(|name| table_hash(name) == hash)
+}
+
+/// Identification hashes from the start of a DB2 header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Db2Hashes {
+    /// Magic signature, e.g. `WDB2` or `WDC3`
+    pub magic: [u8; 4],
+    /// Hash of the table name, see [`table_hash`]
+    pub table_hash: u32,
+    /// Hash of the record layout (WDB5 and later)
+    pub layout_hash: Option<u32>,
+}
+
+impl Db2Hashes {
+    /// Read the hashes from the start of a DB2 file
+    ///
+    /// Supports WDB2 through WDC5. WDBC files have no hashes and are
+    /// rejected like any other unknown signature.
+    pub fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+
+        // Bytes between the magic and the record count, and whether a
+        // layout hash follows the table hash
+        let (skip, has_layout) = match &magic {
+            b"WDB2" | b"WDB3" | b"WDB4" => (0, false),
+            b"WDB5" | b"WDB6" | b"WDC1" | b"WDC2" | b"1SLC" | b"WDC3" | b"WDC4" => (0, true),
+            // Version number and schema string
+            b"WDC5" => (4 + 128, true),
+            _ => {
+                return Err(Error::InvalidHeader(format!(
+                    "No table hash in files with signature {:?}",
+                    String::from_utf8_lossy(&magic)
+                )));
+            }
+        };
+
+        // Skip the version fields, record count, field count, record size
+        // and string block size
+        let mut header = vec![0u8; skip + 16 + 8];
+        let len = if has_layout {
+            header.len()
+        } else {
+            header.len() - 4
+        };
+        reader.read_exact(&mut header[..len])?;
+        let read_u32 = |offset: usize| {
+            u32::from_le_bytes(header[offset..offset + 4].try_into().expect("4 bytes"))
+        };
+
+        Ok(Self {
+            magic,
+            table_hash: read_u32(skip + 16),
+            layout_hash: has_layout.then(|| read_u32(skip + 20)),
+        })
+    }
+
+    /// Magic signature as text
+    pub fn signature(&self) -> String {
+        String::from_utf8_lossy(&self.magic).into_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_table_hash_folds_case_and_separators() {
+        assert_eq!(table_hash("ItemSparse"), table_hash("ITEMSPARSE"));
+        assert_eq!(table_hash("a/b"), table_hash("A\\B"));
+        assert_ne!(table_hash("Item"), table_hash("ItemSparse"));
+        // An empty name keeps the initial seed
+        assert_eq!(table_hash(""), 0x7FED7FED);
+    }
+
+    #[test]
+    fn test_find_table_by_hash() {
+        let names = ["Achievement", "Item", "Spell"];
+        let hash = table_hash("item");
+        assert_eq!(find_table_by_hash(names, hash), Some("Item"));
+        assert_eq!(find_table_by_hash(names, hash ^ 1), None);
+    }
+
+    fn header(magic: &[u8; 4], prefix: usize, table: u32, layout: u32) -> Vec<u8> {
+        let mut data = magic.to_vec();
+        data.extend(vec![0xAA; prefix + 16]);
+        data.extend_from_slice(&table.to_le_bytes());
+        data.extend_from_slice(&layout.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_read_db2_hashes() {
+        let wdb2 = Db2Hashes::read(&mut Cursor::new(header(b"WDB2", 0, 7, 12340))).unwrap();
+        assert_eq!(wdb2.signature(), "WDB2");
+        assert_eq!(wdb2.table_hash, 7);
+        assert_eq!(wdb2.layout_hash, None);
+
+        let wdc3 = Db2Hashes::read(&mut Cursor::new(header(b"WDC3", 0, 7, 9))).unwrap();
+        assert_eq!(wdc3.layout_hash, Some(9));
+
+        let wdc5 = Db2Hashes::read(&mut Cursor::new(header(b"WDC5", 132, 7, 9))).unwrap();
+        assert_eq!((wdc5.table_hash, wdc5.layout_hash), (7, Some(9)));
+
+        assert!(Db2Hashes::read(&mut Cursor::new(header(b"WDBC", 0, 7, 9))).is_err());
+        assert!(Db2Hashes::read(&mut Cursor::new(b"WDC3\0\0".to_vec())).is_err());
+    }
+}
@@ -9,6 +9,7 @@
 //! - Schema-based parsing with validation
 //! - Export to JSON and CSV formats
 //! - Command-line interface for working with DBC files
+//! - Writing edited tables into patch MPQs (`mpq` feature)
 //!
 //! ## Example
 //!
@@ -73,12 +74,16 @@ mod error;
 #[cfg(any(feature = "serde", feature = "csv_export"))]
 mod export;
 mod field_parser;
+mod hashes;
 mod header;
+mod parquet;
 mod parser;
+mod query;
 mod schema;
 mod schema_discovery;
 mod schema_loader;
 mod stringblock;
+mod table_export;
 mod types;
 mod versions;
 mod writer;
@@ -87,20 +92,31 @@ mod writer;
 mod mmap;
 
 mod lazy;
+mod localization;
 
 #[cfg(feature = "parallel")]
 mod parallel;
 
+#[cfg(feature = "mpq")]
+mod patch;
+
 #[cfg(feature = "cli")]
 pub mod dbd;
 
 pub use error::Error;
+pub use hashes::{Db2Hashes, find_table_by_hash, table_hash};
 pub use header::DbcHeader;
 pub use lazy::{LazyDbcParser, LazyRecordIterator};
+pub use localization::{
+    LOCALES_CLASSIC, LOCALES_WOTLK, MergedField, MergedLocales, MergedRecord, TranslationEntry,
+    TranslationResult, apply_translations, extract_translations, merge_locales, read_po, write_po,
+};
 pub use parser::{DbcParser, Record, RecordSet, Value};
+pub use query::{CompareOp, Condition, Literal, Query, QueryResult};
 pub use schema::{FieldType, Schema, SchemaField};
 pub use schema_discovery::{Confidence, DiscoveredField, DiscoveredSchema, SchemaDiscoverer};
 pub use stringblock::{CachedStringBlock, StringBlock};
+pub use table_export::{export_to_parquet, export_to_sql, sql_type};
 pub use types::*;
 
 #[cfg(feature = "yaml")]
@@ -112,14 +128,20 @@ pub use export::{export_to_json, import_from_json};
 #[cfg(feature = "csv_export")]
 pub use export::export_to_csv;
 
+#[cfg(feature = "csv_export")]
+pub use localization::{read_translations_csv, write_merged_csv, write_translations_csv};
+
 #[cfg(feature = "mmap")]
 pub use mmap::MmapDbcFile;
 
 #[cfg(feature = "parallel")]
 pub use parallel::parse_records_parallel;
 
+#[cfg(feature = "mpq")]
+pub use patch::{DBC_DIRECTORY, DbcPatch, table_path, write_to_patch};
+
 pub use versions::{DbcVersion, Wdb2Header, Wdb5Header};
-pub use writer::DbcWriter;
+pub use writer::{DbcWriter, Wdb2Options};
 
 /// Result type used throughout the library
 pub type Result<T> = std::result::Result<T, Error>;
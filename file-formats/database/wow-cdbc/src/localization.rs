@@ -0,0 +1,890 @@
+//! Export and import of localized strings for translation
+//!
+//! Before 4.1 a localized string (`locstring`) takes one string column per
+//! locale, named `<Field>_<locale>` by the generated schemas, followed by a
+//! flags column. [`extract_translations`] pairs the strings of a source locale
+//! with those of a target locale, keyed by record id and field, and
+//! [`apply_translations`] writes translated strings back into the target
+//! locale's columns. String fields without a locale suffix are translated in
+//! place, which covers 4.1+ files that only carry the client's own locale.
+//!
+//! Translations are exchanged as gettext PO files ([`write_po`], [`read_po`])
+//! or, with the `csv_export` feature, as CSV.
+//!
+//! Each client locale ships its own copy of a table, usually with only its
+//! own locale filled in. [`merge_locales`] combines the copies from several
+//! locales into one view with every locale's string for each localized field.
+
+use crate::{Error, FieldType, Key, Record, RecordSet, Result, StringBlock, StringRef, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, Write};
+use std::sync::Arc;
+
+/// Locale order of the 8 string columns of a Vanilla/TBC locstring
+pub const LOCALES_CLASSIC: &[&str] = &[
+    "enUS", "koKR", "frFR", "deDE", "zhCN", "zhTW", "esES", "esMX",
+];
+
+/// Locale order of the 16 string columns of a WotLK/4.0 locstring
+pub const LOCALES_WOTLK: &[&str] = &[
+    "enUS", "koKR", "frFR", "deDE", "zhCN", "zhTW", "esES", "esMX", "ruRU", "unk9", "unk10",
+    "unk11", "unk12", "unk13", "unk14", "unk15",
+];
+
+/// A localized string and its translation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TranslationEntry {
+    /// Key of the record, or its index if the schema has no key field
+    pub record: Key,
+    /// Field name without the locale suffix, e.g. `Name_lang`
+    pub field: String,
+    /// String in the source locale
+    pub source: String,
+    /// String in the target locale, empty if untranslated
+    pub translation: String,
+}
+
+impl TranslationEntry {
+    /// Context string identifying the entry, `<record>:<field>`
+    pub fn context(&self) -> String {
+        format!("{}:{}", self.record, self.field)
+    }
+}
+
+/// Outcome of [`apply_translations`]
+#[derive(Debug, Clone)]
+pub struct TranslationResult {
+    /// Records with the translations written into the target locale
+    pub record_set: RecordSet,
+    /// Translations written
+    pub applied: usize,
+    /// Translations skipped because the source string changed since export
+    pub outdated: usize,
+    /// Translations for records or fields that do not exist
+    pub unmatched: usize,
+}
+
+/// A string field, with one column per locale for locstrings
+struct LocalizedField {
+    name: String,
+    columns: HashMap<&'static str, usize>,
+    /// Column of a field that is not split by locale
+    single: Option<usize>,
+}
+
+impl LocalizedField {
+    fn column(&self, locale: &str) -> Option<usize> {
+        self.single.or_else(|| self.columns.get(locale).copied())
+    }
+}
+
+/// Group the string columns of a schema into localized fields
+fn localized_fields(record_set: &RecordSet) -> Result<Vec<LocalizedField>> {
+    let schema = record_set
+        .schema()
+        .ok_or_else(|| Error::SchemaValidation("Localization requires a schema".to_string()))?;
+
+    let mut fields: Vec<LocalizedField> = Vec::new();
+    for (index, field) in schema.fields.iter().enumerate() {
+        if field.field_type != FieldType::String || field.is_array {
+            continue;
+        }
+
+        let locale = field.name.rsplit_once('_').and_then(|(base, suffix)| {
+            LOCALES_WOTLK
+                .iter()
+                .find(|locale| **locale == suffix)
+                .map(|locale| (base, *locale))
+        });
+        match locale {
+            Some((base, locale)) => {
+                match fields
+                    .iter_mut()
+                    .find(|f| f.single.is_none() && f.name == base)
+                {
+                    Some(existing) => {
+                        existing.columns.insert(locale, index);
+                    }
+                    None => fields.push(LocalizedField {
+                        name: base.to_string(),
+                        columns: HashMap::from([(locale, index)]),
+                        single: None,
+                    }),
+                }
+            }
+            None => fields.push(LocalizedField {
+                name: field.name.clone(),
+                columns: HashMap::new(),
+                single: Some(index),
+            }),
+        }
+    }
+    Ok(fields)
+}
+
+/// Key of a record, falling back to its index
+fn record_key(record_set: &RecordSet, index: usize, record: &Record) -> Key {
+    record_set
+        .schema()
+        .and_then(|schema| schema.key_field_index)
+        .and_then(|key| match record.get_value(key) {
+            Some(Value::UInt32(key)) => Some(*key),
+            Some(Value::Int32(key)) => Some(*key as u32),
+            _ => None,
+        })
+        .unwrap_or(index as Key)
+}
+
+fn string_at<'a>(record_set: &'a RecordSet, record: &Record, column: usize) -> Result<&'a str> {
+    match record.get_value(column) {
+        Some(Value::StringRef(string_ref)) => record_set.get_string(*string_ref),
+        _ => Ok(""),
+    }
+}
+
+fn check_locale(locale: &str) -> Result<()> {
+    if LOCALES_WOTLK.contains(&locale) {
+        Ok(())
+    } else {
+        Err(Error::SchemaValidation(format!(
+            "Unknown locale '{locale}'"
+        )))
+    }
+}
+
+/// Collect the non-empty strings of `source_locale` with their current
+/// translation in `target_locale`
+///
+/// Requires a schema; locstring columns are recognized by their locale
+/// suffix (`Name_lang_enUS`, `Name_lang_deDE`, ...).
+pub fn extract_translations(
+    record_set: &RecordSet,
+    source_locale: &str,
+    target_locale: &str,
+) -> Result<Vec<TranslationEntry>> {
+    check_locale(source_locale)?;
+    check_locale(target_locale)?;
+    let fields = localized_fields(record_set)?;
+
+    let mut entries = Vec::new();
+    for (index, record) in record_set.records().iter().enumerate() {
+        let key = record_key(record_set, index, record);
+        for field in &fields {
+            let Some(source_column) = field.column(source_locale) else {
+                continue;
+            };
+            let source = string_at(record_set, record, source_column)?;
+            if source.is_empty() {
+                continue;
+            }
+            let translation = match field.column(target_locale) {
+                Some(column) if field.single.is_none() => string_at(record_set, record, column)?,
+                _ => "",
+            };
+            entries.push(TranslationEntry {
+                record: key,
+                field: field.name.clone(),
+                source: source.to_string(),
+                translation: translation.to_string(),
+            });
+        }
+    }
+    Ok(entries)
+}
+
+/// Write translations into the `target_locale` columns of a copy of `record_set`
+///
+/// Entries with an empty translation are ignored. An entry whose source
+/// string no longer matches the record's `source_locale` string is counted as
+/// outdated and not applied. Locstring flags columns are left unchanged.
+pub fn apply_translations(
+    record_set: &RecordSet,
+    entries: &[TranslationEntry],
+    source_locale: &str,
+    target_locale: &str,
+) -> Result<TranslationResult> {
+    check_locale(source_locale)?;
+    check_locale(target_locale)?;
+    let fields = localized_fields(record_set)?;
+
+    let mut records: Vec<Vec<Value>> = record_set
+        .records()
+        .iter()
+        .map(|record| record.values().to_vec())
+        .collect();
+    let rows: HashMap<Key, usize> = record_set
+        .records()
+        .iter()
+        .enumerate()
+        .map(|(index, record)| (record_key(record_set, index, record), index))
+        .collect();
+
+    let mut translated: HashMap<(usize, usize), &str> = HashMap::new();
+    let (mut applied, mut outdated, mut unmatched) = (0, 0, 0);
+    for entry in entries.iter().filter(|entry| !entry.translation.is_empty()) {
+        let field = fields.iter().find(|field| field.name == entry.field);
+        let target = field.and_then(|field| field.column(target_locale));
+        let (Some(&row), Some(field), Some(target)) = (rows.get(&entry.record), field, target)
+        else {
+            unmatched += 1;
+            continue;
+        };
+
+        let record = &record_set.records()[row];
+        let source = match field.column(source_locale) {
+            Some(column) => string_at(record_set, record, column)?,
+            None => "",
+        };
+        if source != entry.source {
+            outdated += 1;
+            continue;
+        }
+        translated.insert((row, target), &entry.translation);
+        applied += 1;
+    }
+
+    // Rebuild the string block with every string still referenced
+    let mut string_data = vec![0u8];
+    let mut offsets: HashMap<String, u32> = HashMap::from([(String::new(), 0)]);
+    let mut intern = |s: &str| -> u32 {
+        if let Some(&offset) = offsets.get(s) {
+            return offset;
+        }
+        let offset = string_data.len() as u32;
+        string_data.extend_from_slice(s.as_bytes());
+        string_data.push(0);
+        offsets.insert(s.to_string(), offset);
+        offset
+    };
+
+    for (row, values) in records.iter_mut().enumerate() {
+        for (column, value) in values.iter_mut().enumerate() {
+            let Value::StringRef(string_ref) = value else {
+                continue;
+            };
+            let string = match translated.get(&(row, column)) {
+                Some(translation) => *translation,
+                None => record_set.get_string(*string_ref)?,
+            };
+            *string_ref = StringRef::new(intern(string));
+        }
+    }
+
+    let schema = record_set.schema().cloned().map(Arc::new);
+    let records = records
+        .into_iter()
+        .map(|values| Record::new(values, schema.clone()))
+        .collect();
+    Ok(TranslationResult {
+        record_set: RecordSet::new(records, schema, StringBlock::from_bytes(string_data)),
+        applied,
+        outdated,
+        unmatched,
+    })
+}
+
+/// Escape a string for a PO file
+fn po_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Unescape the contents of a quoted PO string
+fn po_unescape(s: &str, line: usize) -> Result<String> {
+    let mut unescaped = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('\\') => unescaped.push('\\'),
+            Some('"') => unescaped.push('"'),
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some('t') => unescaped.push('\t'),
+            other => {
+                return Err(Error::InvalidRecord(format!(
+                    "PO line {line}: invalid escape '\\{}'",
+                    other.map(String::from).unwrap_or_default()
+                )));
+            }
+        }
+    }
+    Ok(unescaped)
+}
+
+/// Write translations as a gettext PO file
+///
+/// Each entry's `msgctxt` is `<record>:<field>`; `table` and `locale` go into
+/// the header and reference comments.
+pub fn write_po<W: Write>(
+    entries: &[TranslationEntry],
+    table: &str,
+    locale: &str,
+    mut writer: W,
+) -> Result<()> {
+    writeln!(writer, "# Localized strings of {table}")?;
+    writeln!(writer, "msgid \"\"")?;
+    writeln!(writer, "msgstr \"\"")?;
+    writeln!(writer, "\"Content-Type: text/plain; charset=UTF-8\\n\"")?;
+    writeln!(writer, "\"Language: {}\\n\"", po_escape(locale))?;
+
+    for entry in entries {
+        writeln!(writer)?;
+        writeln!(writer, "#: {table}:{}", entry.context())?;
+        writeln!(writer, "msgctxt \"{}\"", po_escape(&entry.context()))?;
+        writeln!(writer, "msgid \"{}\"", po_escape(&entry.source))?;
+        writeln!(writer, "msgstr \"{}\"", po_escape(&entry.translation))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Which keyword a continuation line of a PO file belongs to
+#[derive(Clone, Copy, PartialEq)]
+enum PoKeyword {
+    Context,
+    Id,
+    Str,
+}
+
+/// Parse a `<record>:<field>` context
+fn parse_context(context: &str, line: usize) -> Result<(Key, String)> {
+    context
+        .split_once(':')
+        .and_then(|(record, field)| Some((record.parse().ok()?, field.to_string())))
+        .filter(|(_, field)| !field.is_empty())
+        .ok_or_else(|| {
+            Error::InvalidRecord(format!(
+                "PO line {line}: expected msgctxt \"<record>:<field>\", got \"{context}\""
+            ))
+        })
+}
+
+/// Read translations from a gettext PO file written by [`write_po`]
+///
+/// Comments, the header entry and entries without a `msgctxt` are skipped.
+pub fn read_po<R: BufRead>(reader: R) -> Result<Vec<TranslationEntry>> {
+    let mut entries = Vec::new();
+    let mut current: [Option<String>; 3] = [None, None, None];
+    let mut keyword = None;
+    let mut context_line = 0;
+
+    let mut finish = |current: &mut [Option<String>; 3], line: usize| -> Result<()> {
+        let [context, id, string] = std::mem::take(current);
+        if let (Some(context), Some(source)) = (context, id) {
+            let (record, field) = parse_context(&context, line)?;
+            entries.push(TranslationEntry {
+                record,
+                field,
+                source,
+                translation: string.unwrap_or_default(),
+            });
+        }
+        Ok(())
+    };
+
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        let number = number + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (next, rest) = if let Some(rest) = line.strip_prefix("msgctxt ") {
+            (Some(PoKeyword::Context), rest)
+        } else if let Some(rest) = line.strip_prefix("msgid ") {
+            (Some(PoKeyword::Id), rest)
+        } else if let Some(rest) = line.strip_prefix("msgstr ") {
+            (Some(PoKeyword::Str), rest)
+        } else if line.starts_with('"') {
+            (None, line)
+        } else {
+            return Err(Error::InvalidRecord(format!(
+                "PO line {number}: unexpected '{line}'"
+            )));
+        };
+
+        let quoted = rest
+            .trim()
+            .strip_prefix('"')
+            .and_then(|s| s.strip_suffix('"'))
+            .ok_or_else(|| {
+                Error::InvalidRecord(format!("PO line {number}: expected a quoted string"))
+            })?;
+        let text = po_unescape(quoted, number)?;
+
+        match next {
+            Some(next) => {
+                // A new msgctxt, or a msgid without one, starts the next entry
+                if next == PoKeyword::Context
+                    || (next == PoKeyword::Id && current[PoKeyword::Id as usize].is_some())
+                {
+                    finish(&mut current, context_line)?;
+                }
+                if next == PoKeyword::Context {
+                    context_line = number;
+                }
+                current[next as usize] = Some(text);
+                keyword = Some(next);
+            }
+            None => {
+                let slot = keyword
+                    .map(|k| &mut current[k as usize])
+                    .and_then(Option::as_mut);
+                match slot {
+                    Some(value) => value.push_str(&text),
+                    None => {
+                        return Err(Error::InvalidRecord(format!(
+                            "PO line {number}: string continuation without a keyword"
+                        )));
+                    }
+                }
+            }
+        }
+    }
+    finish(&mut current, context_line)?;
+
+    Ok(entries)
+}
+
+/// One localized field of a record, as merged by [`merge_locales`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergedField {
+    /// Field name without the locale suffix, e.g. `Name_lang`
+    pub field: String,
+    /// String in each merged locale, in [`MergedLocales::locales`] order;
+    /// `None` where the locale lacks the record or leaves the string empty
+    pub strings: Vec<Option<String>>,
+}
+
+/// A record's localized fields across locales
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergedRecord {
+    /// Key of the record, or its index if the schema has no key field
+    pub record: Key,
+    /// Localized fields in schema order
+    pub fields: Vec<MergedField>,
+}
+
+/// The same table from several client locales, merged by record
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergedLocales {
+    /// Locales in the order they were merged
+    pub locales: Vec<String>,
+    /// Records of every locale, sorted by key
+    pub records: Vec<MergedRecord>,
+}
+
+impl MergedLocales {
+    /// Index of `locale` in [`MergedLocales::locales`]
+    pub fn locale_index(&self, locale: &str) -> Option<usize> {
+        self.locales.iter().position(|l| l == locale)
+    }
+
+    /// Record with the given key
+    pub fn get(&self, record: Key) -> Option<&MergedRecord> {
+        self.records
+            .binary_search_by_key(&record, |merged| merged.record)
+            .ok()
+            .map(|index| &self.records[index])
+    }
+
+    /// Strings of `source_locale` paired with their `target_locale` version
+    ///
+    /// Gives the same entries as [`extract_translations`] on a single table,
+    /// so untranslated strings can be found or exported with [`write_po`].
+    pub fn translations(
+        &self,
+        source_locale: &str,
+        target_locale: &str,
+    ) -> Result<Vec<TranslationEntry>> {
+        let index = |locale: &str| {
+            self.locale_index(locale)
+                .ok_or_else(|| Error::SchemaValidation(format!("Locale '{locale}' was not merged")))
+        };
+        let (source, target) = (index(source_locale)?, index(target_locale)?);
+
+        let mut entries = Vec::new();
+        for record in &self.records {
+            for field in &record.fields {
+                let Some(text) = &field.strings[source] else {
+                    continue;
+                };
+                entries.push(TranslationEntry {
+                    record: record.record,
+                    field: field.field.clone(),
+                    source: text.clone(),
+                    translation: field.strings[target].clone().unwrap_or_default(),
+                });
+            }
+        }
+        Ok(entries)
+    }
+}
+
+/// String of a localized field as shipped in the client for `locale`
+///
+/// Uses the locale's own column, or the first filled one for locales without
+/// a column of their own, such as enGB, whose clients use another locale's.
+fn shipped_string<'a>(
+    record_set: &'a RecordSet,
+    record: &Record,
+    field: &LocalizedField,
+    locale: &str,
+) -> Result<&'a str> {
+    if let Some(column) = field.column(locale) {
+        return string_at(record_set, record, column);
+    }
+    for locale in LOCALES_WOTLK {
+        if let Some(&column) = field.columns.get(locale) {
+            let text = string_at(record_set, record, column)?;
+            if !text.is_empty() {
+                return Ok(text);
+            }
+        }
+    }
+    Ok("")
+}
+
+/// Merge copies of one table from several client locales
+///
+/// `sources` pairs each locale with its copy of the table, parsed with a
+/// schema. Records are matched by key; fields are those of the first copy.
+pub fn merge_locales(sources: &[(&str, &RecordSet)]) -> Result<MergedLocales> {
+    let Some((_, first)) = sources.first() else {
+        return Err(Error::SchemaValidation("No locales to merge".to_string()));
+    };
+    let names: Vec<String> = localized_fields(first)?
+        .into_iter()
+        .map(|field| field.name)
+        .collect();
+
+    let mut records: BTreeMap<Key, Vec<Vec<Option<String>>>> = BTreeMap::new();
+    for (locale_index, (locale, record_set)) in sources.iter().enumerate() {
+        let fields = localized_fields(record_set)?;
+        let fields: Vec<Option<&LocalizedField>> = names
+            .iter()
+            .map(|name| fields.iter().find(|field| field.name == *name))
+            .collect();
+
+        for (index, record) in record_set.records().iter().enumerate() {
+            let key = record_key(record_set, index, record);
+            let strings = records
+                .entry(key)
+                .or_insert_with(|| vec![vec![None; sources.len()]; names.len()]);
+            for (field_index, field) in fields.iter().enumerate() {
+                let Some(field) = field else {
+                    continue;
+                };
+                let text = shipped_string(record_set, record, field, locale)?;
+                if !text.is_empty() {
+                    strings[field_index][locale_index] = Some(text.to_string());
+                }
+            }
+        }
+    }
+
+    let records = records
+        .into_iter()
+        .map(|(record, strings)| MergedRecord {
+            record,
+            fields: names
+                .iter()
+                .zip(strings)
+                .map(|(field, strings)| MergedField {
+                    field: field.clone(),
+                    strings,
+                })
+                .collect(),
+        })
+        .collect();
+    Ok(MergedLocales {
+        locales: sources
+            .iter()
+            .map(|(locale, _)| locale.to_string())
+            .collect(),
+        records,
+    })
+}
+
+/// Write merged locales as CSV with the columns `record,field,<locale>...`
+#[cfg(feature = "csv_export")]
+pub fn write_merged_csv<W: Write>(merged: &MergedLocales, writer: W) -> Result<()> {
+    let mut csv_writer = csv::Writer::from_writer(writer);
+    let mut header = vec!["record", "field"];
+    header.extend(merged.locales.iter().map(String::as_str));
+    csv_writer
+        .write_record(&header)
+        .map_err(|e| Error::Io(e.into()))?;
+    for record in &merged.records {
+        for field in &record.fields {
+            let mut row = vec![record.record.to_string(), field.field.clone()];
+            row.extend(field.strings.iter().map(|s| s.clone().unwrap_or_default()));
+            csv_writer
+                .write_record(&row)
+                .map_err(|e| Error::Io(e.into()))?;
+        }
+    }
+    csv_writer.flush()?;
+    Ok(())
+}
+
+/// Write translations as CSV with the columns `record,field,source,translation`
+#[cfg(feature = "csv_export")]
+pub fn write_translations_csv<W: Write>(entries: &[TranslationEntry], writer: W) -> Result<()> {
+    let mut csv_writer = csv::Writer::from_writer(writer);
+    csv_writer
+        .write_record(["record", "field", "source", "translation"])
+        .map_err(|e| Error::Io(e.into()))?;
+    for entry in entries {
+        csv_writer
+            .write_record([
+                entry.record.to_string().as_str(),
+                &entry.field,
+                &entry.source,
+                &entry.translation,
+            ])
+            .map_err(|e| Error::Io(e.into()))?;
+    }
+    csv_writer.flush()?;
+    Ok(())
+}
+
+/// Read translations from CSV written by [`write_translations_csv`]
+#[cfg(feature = "csv_export")]
+pub fn read_translations_csv<R: std::io::Read>(reader: R) -> Result<Vec<TranslationEntry>> {
+    let mut csv_reader = csv::Reader::from_reader(reader);
+    let mut entries = Vec::new();
+    for (row, record) in csv_reader.records().enumerate() {
+        let record = record.map_err(|e| Error::Io(e.into()))?;
+        let column = |index: usize| record.get(index).unwrap_or_default().to_string();
+        let key = column(0).parse().map_err(|_| {
+            Error::InvalidRecord(format!(
+                "CSV row {}: invalid record '{}'",
+                row + 1,
+                column(0)
+            ))
+        })?;
+        entries.push(TranslationEntry {
+            record: key,
+            field: column(1),
+            source: column(2),
+            translation: column(3),
+        });
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Schema, SchemaField};
+
+    /// Two records with a `Name_lang` locstring, English only
+    fn record_set() -> RecordSet {
+        let mut schema = Schema::new("Test");
+        schema.add_field(SchemaField::new("ID", FieldType::UInt32));
+        for locale in LOCALES_CLASSIC {
+            schema.add_field(SchemaField::new(
+                format!("Name_lang_{locale}"),
+                FieldType::String,
+            ));
+        }
+        schema.add_field(SchemaField::new("Name_lang_flags", FieldType::UInt32));
+        schema.add_field(SchemaField::new("Icon", FieldType::String));
+        schema.set_key_field("ID");
+        let schema = Arc::new(schema);
+
+        let strings = b"\0Fireball\0Frost \"Nova\"\nRank 1\0Interface\\Icon\0".to_vec();
+        let record = |id: u32, name: u32| {
+            let mut values = vec![Value::UInt32(id), Value::StringRef(StringRef::new(name))];
+            values.extend((1..LOCALES_CLASSIC.len()).map(|_| Value::StringRef(StringRef::new(0))));
+            values.push(Value::UInt32(0xFF01FE));
+            values.push(Value::StringRef(StringRef::new(30)));
+            Record::new(values, Some(schema.clone()))
+        };
+        RecordSet::new(
+            vec![record(133, 1), record(122, 10)],
+            Some(schema.clone()),
+            StringBlock::from_bytes(strings),
+        )
+    }
+
+    #[test]
+    fn test_extract_translations() {
+        let entries = extract_translations(&record_set(), "enUS", "deDE").unwrap();
+        let contexts: Vec<String> = entries.iter().map(TranslationEntry::context).collect();
+        assert_eq!(
+            contexts,
+            ["133:Name_lang", "133:Icon", "122:Name_lang", "122:Icon"]
+        );
+        assert_eq!(entries[0].source, "Fireball");
+        assert_eq!(entries[0].translation, "");
+        assert_eq!(entries[2].source, "Frost \"Nova\"\nRank 1");
+        assert!(extract_translations(&record_set(), "enUS", "xxXX").is_err());
+    }
+
+    #[test]
+    fn test_merge_locales() {
+        let english = record_set();
+        let entries = vec![TranslationEntry {
+            record: 133,
+            field: "Name_lang".to_string(),
+            source: "Fireball".to_string(),
+            translation: "Feuerball".to_string(),
+        }];
+        let german = apply_translations(&english, &entries, "enUS", "deDE")
+            .unwrap()
+            .record_set;
+
+        let merged =
+            merge_locales(&[("enUS", &english), ("deDE", &german), ("enGB", &english)]).unwrap();
+        assert_eq!(merged.locales, ["enUS", "deDE", "enGB"]);
+        assert_eq!(
+            merged.records.iter().map(|r| r.record).collect::<Vec<_>>(),
+            [122, 133]
+        );
+
+        let fireball = merged.get(133).unwrap();
+        assert_eq!(fireball.fields[0].field, "Name_lang");
+        assert_eq!(
+            fireball.fields[0].strings,
+            [
+                Some("Fireball".to_string()),
+                Some("Feuerball".to_string()),
+                // enGB has no column of its own and reads the enUS one
+                Some("Fireball".to_string()),
+            ]
+        );
+        assert_eq!(merged.get(122).unwrap().fields[0].strings[1], None);
+
+        let untranslated: Vec<String> = merged
+            .translations("enUS", "deDE")
+            .unwrap()
+            .into_iter()
+            .filter(|entry| entry.translation.is_empty())
+            .map(|entry| entry.context())
+            .collect();
+        assert_eq!(untranslated, ["122:Name_lang"]);
+        assert!(merged.translations("enUS", "frFR").is_err());
+        assert!(merge_locales(&[]).is_err());
+    }
+
+    #[test]
+    fn test_po_round_trip() {
+        let mut entries = extract_translations(&record_set(), "enUS", "deDE").unwrap();
+        entries[2].translation = "Frost\\nova \"1\"\n\tRang 1".to_string();
+
+        let mut po = Vec::new();
+        write_po(&entries, "Test.dbc", "deDE", &mut po).unwrap();
+        let text = String::from_utf8(po.clone()).unwrap();
+        assert!(text.contains("msgctxt \"133:Name_lang\"\nmsgid \"Fireball\"\nmsgstr \"\""));
+
+        assert_eq!(read_po(po.as_slice()).unwrap(), entries);
+    }
+
+    #[test]
+    fn test_read_po_continuation_lines() {
+        let po = "msgid \"\"\nmsgstr \"\"\n\"Language: deDE\\n\"\n\n\
+                  #, fuzzy\nmsgctxt \"7:Name_lang\"\nmsgid \"\"\n\"Fire\"\n\"ball\"\n\
+                  msgstr \"Feuer\"\n\"ball\"\n";
+        let entries = read_po(po.as_bytes()).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].record, 7);
+        assert_eq!(entries[0].source, "Fireball");
+        assert_eq!(entries[0].translation, "Feuerball");
+
+        assert!(read_po("msgctxt \"Name\"\nmsgid \"x\"\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_apply_translations() {
+        let records = record_set();
+        let entries = vec![
+            TranslationEntry {
+                record: 133,
+                field: "Name_lang".to_string(),
+                source: "Fireball".to_string(),
+                translation: "Feuerball".to_string(),
+            },
+            TranslationEntry {
+                record: 122,
+                field: "Name_lang".to_string(),
+                source: "Frostbolt".to_string(),
+                translation: "Frostblitz".to_string(),
+            },
+            TranslationEntry {
+                record: 9999,
+                field: "Name_lang".to_string(),
+                source: "Missing".to_string(),
+                translation: "Fehlt".to_string(),
+            },
+        ];
+
+        let result = apply_translations(&records, &entries, "enUS", "deDE").unwrap();
+        assert_eq!(
+            (result.applied, result.outdated, result.unmatched),
+            (1, 1, 1)
+        );
+
+        let translated = extract_translations(&result.record_set, "enUS", "deDE").unwrap();
+        assert_eq!(translated[0].source, "Fireball");
+        assert_eq!(translated[0].translation, "Feuerball");
+        assert_eq!(translated[2].translation, "");
+
+        let record = result.record_set.get_record_by_key(133).unwrap();
+        let Some(Value::StringRef(icon)) = record.get_value_by_name("Icon") else {
+            panic!("Icon is not a string");
+        };
+        assert_eq!(
+            result.record_set.get_string(*icon).unwrap(),
+            "Interface\\Icon"
+        );
+    }
+
+    #[test]
+    fn test_apply_translations_in_place() {
+        let entries = vec![TranslationEntry {
+            record: 122,
+            field: "Icon".to_string(),
+            source: "Interface\\Icon".to_string(),
+            translation: "Interface\\Icons\\Frost".to_string(),
+        }];
+        let result = apply_translations(&record_set(), &entries, "enUS", "deDE").unwrap();
+        assert_eq!(result.applied, 1);
+
+        let record = result.record_set.get_record_by_key(122).unwrap();
+        let Some(Value::StringRef(icon)) = record.get_value_by_name("Icon") else {
+            panic!("Icon is not a string");
+        };
+        assert_eq!(
+            result.record_set.get_string(*icon).unwrap(),
+            "Interface\\Icons\\Frost"
+        );
+    }
+
+    #[cfg(feature = "csv_export")]
+    #[test]
+    fn test_csv_round_trip() {
+        let mut entries = extract_translations(&record_set(), "enUS", "deDE").unwrap();
+        entries[0].translation = "Feuerball, \"heiß\"".to_string();
+
+        let mut csv = Vec::new();
+        write_translations_csv(&entries, &mut csv).unwrap();
+        assert_eq!(read_translations_csv(csv.as_slice()).unwrap(), entries);
+    }
+}
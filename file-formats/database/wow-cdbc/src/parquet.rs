@@ -0,0 +1,342 @@
+//! Minimal Parquet file writer
+//!
+//! Writes flat tables of required columns: one row group with one PLAIN
+//! encoded, uncompressed data page per column. This is all a DBC table needs
+//! and keeps the crate free of the Arrow dependency tree. Metadata is encoded
+//! with the Thrift compact protocol as the format requires.
+
+use crate::FieldType;
+use std::io::{self, Write};
+
+const MAGIC: &[u8; 4] = b"PAR1";
+
+// Parquet physical types
+const TYPE_BOOLEAN: i32 = 0;
+const TYPE_INT32: i32 = 1;
+const TYPE_FLOAT: i32 = 4;
+const TYPE_BYTE_ARRAY: i32 = 6;
+
+// Parquet converted (logical) types
+const CONVERTED_UTF8: i32 = 0;
+const CONVERTED_UINT_8: i32 = 11;
+const CONVERTED_UINT_16: i32 = 12;
+const CONVERTED_UINT_32: i32 = 13;
+const CONVERTED_INT_8: i32 = 15;
+const CONVERTED_INT_16: i32 = 16;
+const CONVERTED_INT_32: i32 = 17;
+
+const ENCODING_PLAIN: i32 = 0;
+const ENCODING_RLE: i32 = 3;
+const PAGE_TYPE_DATA: i32 = 0;
+const REPETITION_REQUIRED: i32 = 0;
+const CODEC_UNCOMPRESSED: i32 = 0;
+
+/// Values of one column
+#[derive(Debug, Clone)]
+pub(crate) enum ParquetType {
+    Boolean(Vec<bool>),
+    /// Integers of any width; `field_type` selects the annotation
+    Int32 {
+        values: Vec<i32>,
+        field_type: FieldType,
+    },
+    Float(Vec<f32>),
+    Utf8(Vec<String>),
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct ParquetColumn {
+    pub name: String,
+    pub data: ParquetType,
+}
+
+impl ParquetType {
+    fn physical_type(&self) -> i32 {
+        match self {
+            Self::Boolean(_) => TYPE_BOOLEAN,
+            Self::Int32 { .. } => TYPE_INT32,
+            Self::Float(_) => TYPE_FLOAT,
+            Self::Utf8(_) => TYPE_BYTE_ARRAY,
+        }
+    }
+
+    fn converted_type(&self) -> Option<i32> {
+        match self {
+            Self::Utf8(_) => Some(CONVERTED_UTF8),
+            Self::Int32 { field_type, .. } => Some(match field_type {
+                FieldType::UInt8 => CONVERTED_UINT_8,
+                FieldType::UInt16 => CONVERTED_UINT_16,
+                FieldType::UInt32 => CONVERTED_UINT_32,
+                FieldType::Int8 => CONVERTED_INT_8,
+                FieldType::Int16 => CONVERTED_INT_16,
+                _ => CONVERTED_INT_32,
+            }),
+            _ => None,
+        }
+    }
+
+    /// PLAIN encoding of the values
+    fn encode(&self) -> Vec<u8> {
+        match self {
+            Self::Boolean(values) => {
+                let mut out = vec![0u8; values.len().div_ceil(8)];
+                for (i, _) in values.iter().enumerate().filter(|(_, v)| **v) {
+                    out[i / 8] |= 1 << (i % 8);
+                }
+                out
+            }
+            Self::Int32 { values, .. } => values.iter().flat_map(|v| v.to_le_bytes()).collect(),
+            Self::Float(values) => values.iter().flat_map(|v| v.to_le_bytes()).collect(),
+            Self::Utf8(values) => {
+                let mut out = Vec::new();
+                for value in values {
+                    out.extend_from_slice(&(value.len() as u32).to_le_bytes());
+                    out.extend_from_slice(value.as_bytes());
+                }
+                out
+            }
+        }
+    }
+}
+
+/// Thrift compact protocol encoder for the few shapes Parquet metadata uses
+#[derive(Default)]
+struct CompactWriter {
+    buf: Vec<u8>,
+    /// Last field id of each open struct
+    last_field: Vec<i16>,
+}
+
+const CT_I32: u8 = 5;
+const CT_I64: u8 = 6;
+const CT_BINARY: u8 = 8;
+const CT_LIST: u8 = 9;
+const CT_STRUCT: u8 = 12;
+
+impl CompactWriter {
+    fn varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.buf.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        self.buf.push(value as u8);
+    }
+
+    fn zigzag(&mut self, value: i64) {
+        self.varint(((value << 1) ^ (value >> 63)) as u64);
+    }
+
+    fn field(&mut self, id: i16, kind: u8) {
+        let last = self.last_field.last_mut().expect("field outside a struct");
+        let delta = id - std::mem::replace(last, id);
+        if (1..=15).contains(&delta) {
+            self.buf.push(((delta as u8) << 4) | kind);
+        } else {
+            self.buf.push(kind);
+            self.zigzag(i64::from(id));
+        }
+    }
+
+    fn begin(&mut self) {
+        self.last_field.push(0);
+    }
+
+    fn end(&mut self) {
+        self.buf.push(0);
+        self.last_field.pop();
+    }
+
+    fn list_header(&mut self, len: usize, kind: u8) {
+        if len < 15 {
+            self.buf.push(((len as u8) << 4) | kind);
+        } else {
+            self.buf.push(0xF0 | kind);
+            self.varint(len as u64);
+        }
+    }
+
+    fn i32_field(&mut self, id: i16, value: i32) {
+        self.field(id, CT_I32);
+        self.zigzag(i64::from(value));
+    }
+
+    fn i64_field(&mut self, id: i16, value: i64) {
+        self.field(id, CT_I64);
+        self.zigzag(value);
+    }
+
+    fn string(&mut self, value: &str) {
+        self.varint(value.len() as u64);
+        self.buf.extend_from_slice(value.as_bytes());
+    }
+
+    fn string_field(&mut self, id: i16, value: &str) {
+        self.field(id, CT_BINARY);
+        self.string(value);
+    }
+
+    fn struct_field(&mut self, id: i16) {
+        self.field(id, CT_STRUCT);
+        self.begin();
+    }
+
+    fn list_field(&mut self, id: i16, len: usize, kind: u8) {
+        self.field(id, CT_LIST);
+        self.list_header(len, kind);
+    }
+}
+
+fn page_header(num_values: usize, page_size: usize) -> Vec<u8> {
+    let mut w = CompactWriter::default();
+    w.begin();
+    w.i32_field(1, PAGE_TYPE_DATA);
+    w.i32_field(2, page_size as i32);
+    w.i32_field(3, page_size as i32);
+    w.struct_field(5);
+    w.i32_field(1, num_values as i32);
+    w.i32_field(2, ENCODING_PLAIN);
+    w.i32_field(3, ENCODING_RLE);
+    w.i32_field(4, ENCODING_RLE);
+    w.end();
+    w.end();
+    w.buf
+}
+
+/// Location of a written column chunk
+struct ChunkInfo {
+    offset: u64,
+    size: u64,
+}
+
+fn file_metadata(columns: &[ParquetColumn], chunks: &[ChunkInfo], num_rows: usize) -> Vec<u8> {
+    let mut w = CompactWriter::default();
+    w.begin();
+    w.i32_field(1, 1);
+
+    w.list_field(2, columns.len() + 1, CT_STRUCT);
+    w.begin();
+    w.string_field(4, "schema");
+    w.i32_field(5, columns.len() as i32);
+    w.end();
+    for column in columns {
+        w.begin();
+        w.i32_field(1, column.data.physical_type());
+        w.i32_field(3, REPETITION_REQUIRED);
+        w.string_field(4, &column.name);
+        if let Some(converted) = column.data.converted_type() {
+            w.i32_field(6, converted);
+        }
+        w.end();
+    }
+
+    w.i64_field(3, num_rows as i64);
+
+    w.list_field(4, 1, CT_STRUCT);
+    w.begin();
+    w.list_field(1, columns.len(), CT_STRUCT);
+    for (column, chunk) in columns.iter().zip(chunks) {
+        w.begin();
+        w.i64_field(2, chunk.offset as i64);
+        w.struct_field(3);
+        w.i32_field(1, column.data.physical_type());
+        w.list_field(2, 2, CT_I32);
+        w.zigzag(i64::from(ENCODING_PLAIN));
+        w.zigzag(i64::from(ENCODING_RLE));
+        w.list_field(3, 1, CT_BINARY);
+        w.string(&column.name);
+        w.i32_field(4, CODEC_UNCOMPRESSED);
+        w.i64_field(5, num_rows as i64);
+        w.i64_field(6, chunk.size as i64);
+        w.i64_field(7, chunk.size as i64);
+        w.i64_field(9, chunk.offset as i64);
+        w.end();
+        w.end();
+    }
+    w.i64_field(2, chunks.iter().map(|c| c.size as i64).sum());
+    w.i64_field(3, num_rows as i64);
+    w.end();
+
+    w.string_field(6, concat!("wow-cdbc version ", env!("CARGO_PKG_VERSION")));
+    w.end();
+    w.buf
+}
+
+/// Write `columns` (each holding `num_rows` values) as a Parquet file
+pub(crate) fn write_parquet<W: Write>(
+    columns: &[ParquetColumn],
+    num_rows: usize,
+    mut writer: W,
+) -> io::Result<()> {
+    if columns.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "a Parquet file needs at least one column",
+        ));
+    }
+
+    writer.write_all(MAGIC)?;
+    let mut position = MAGIC.len() as u64;
+    let mut chunks = Vec::with_capacity(columns.len());
+    for column in columns {
+        let data = column.data.encode();
+        let header = page_header(num_rows, data.len());
+        writer.write_all(&header)?;
+        writer.write_all(&data)?;
+        let size = (header.len() + data.len()) as u64;
+        chunks.push(ChunkInfo {
+            offset: position,
+            size,
+        });
+        position += size;
+    }
+
+    let metadata = file_metadata(columns, &chunks, num_rows);
+    writer.write_all(&metadata)?;
+    writer.write_all(&(metadata.len() as u32).to_le_bytes())?;
+    writer.write_all(MAGIC)?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compact_field_headers() {
+        let mut w = CompactWriter::default();
+        w.begin();
+        w.i32_field(1, -1);
+        w.i64_field(20, 300);
+        w.end();
+        // Short form (delta 1, i32), zigzag(-1) = 1, long form for id 20
+        assert_eq!(w.buf, vec![0x15, 0x01, 0x06, 0x28, 0xD8, 0x04, 0x00]);
+    }
+
+    #[test]
+    fn test_file_framing() {
+        let columns = vec![
+            ParquetColumn {
+                name: "ID".into(),
+                data: ParquetType::Int32 {
+                    values: vec![1, 2, 3],
+                    field_type: FieldType::UInt32,
+                },
+            },
+            ParquetColumn {
+                name: "Flag".into(),
+                data: ParquetType::Boolean(vec![true, false, true]),
+            },
+        ];
+        let mut out = Vec::new();
+        write_parquet(&columns, 3, &mut out).unwrap();
+
+        assert_eq!(&out[..4], MAGIC);
+        assert_eq!(&out[out.len() - 4..], MAGIC);
+        let footer_len = u32::from_le_bytes(out[out.len() - 8..out.len() - 4].try_into().unwrap());
+        assert!((footer_len as usize) < out.len() - 12);
+        assert_eq!(
+            ParquetType::Boolean(vec![true, false, true]).encode(),
+            [0b101]
+        );
+    }
+}
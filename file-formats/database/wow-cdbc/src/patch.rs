@@ -0,0 +1,243 @@
+//! Writing edited tables into a patch MPQ
+//!
+//! [`DbcPatch`] collects one or more tables, serializes them with
+//! [`DbcWriter`] and stores each at its `DBFilesClient\` path. An existing
+//! archive is updated in place, replacing any copy of the table it already
+//! holds; otherwise a new archive is created. [`write_to_patch`] covers the
+//! single-table case.
+
+use crate::{DbcWriter, RecordSet, Result, Wdb2Options};
+use std::io::Cursor;
+use std::path::Path;
+use wow_mpq::{AddFileOptions, ArchiveBuilder, FormatVersion, MutableArchive};
+
+/// Directory the client loads its database tables from
+pub const DBC_DIRECTORY: &str = "DBFilesClient";
+
+/// Archive path for a table
+///
+/// A bare table name such as `Spell` becomes `DBFilesClient\Spell.dbc`;
+/// `Spell.db2` keeps its extension. Names that already contain a directory
+/// are used as given, with forward slashes turned into backslashes.
+pub fn table_path(table: &str) -> String {
+    let table = table.replace('/', "\\");
+    if table.contains('\\') {
+        return table;
+    }
+
+    let lower = table.to_ascii_lowercase();
+    if lower.ends_with(".dbc") || lower.ends_with(".db2") {
+        format!("{DBC_DIRECTORY}\\{table}")
+    } else {
+        format!("{DBC_DIRECTORY}\\{table}.dbc")
+    }
+}
+
+/// A set of tables to write into a patch MPQ
+#[derive(Debug, Clone)]
+pub struct DbcPatch {
+    /// Archive path and serialized contents of each table
+    tables: Vec<(String, Vec<u8>)>,
+    /// Format of newly created archives
+    version: FormatVersion,
+}
+
+impl Default for DbcPatch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DbcPatch {
+    /// Create an empty patch
+    pub fn new() -> Self {
+        Self {
+            tables: Vec::new(),
+            version: FormatVersion::V1,
+        }
+    }
+
+    /// Set the MPQ format used when the archive does not exist yet
+    ///
+    /// Existing archives keep their format.
+    pub fn version(mut self, version: FormatVersion) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Add a table as WDBC
+    ///
+    /// The record set's own schema is used. Records are written in ID order
+    /// when the schema has a key field. Adding a table twice keeps the last
+    /// copy.
+    pub fn add_table(self, table: &str, records: &RecordSet) -> Result<Self> {
+        let mut data = Cursor::new(Vec::new());
+        DbcWriter::new(&mut data)
+            .sort_by_key(has_key(records))
+            .write_records(records)?;
+        Ok(self.add_table_data(table, data.into_inner()))
+    }
+
+    /// Add a table as WDB2 with the given header values
+    pub fn add_table_wdb2(
+        self,
+        table: &str,
+        records: &RecordSet,
+        options: Wdb2Options,
+    ) -> Result<Self> {
+        let mut data = Cursor::new(Vec::new());
+        DbcWriter::new(&mut data)
+            .sort_by_key(has_key(records))
+            .wdb2(options)
+            .write_records(records)?;
+        Ok(self.add_table_data(table, data.into_inner()))
+    }
+
+    /// Add an already serialized table
+    pub fn add_table_data(mut self, table: &str, data: Vec<u8>) -> Self {
+        let path = table_path(table);
+        self.tables
+            .retain(|(existing, _)| !existing.eq_ignore_ascii_case(&path));
+        self.tables.push((path, data));
+        self
+    }
+
+    /// Archive paths of the tables added so far
+    pub fn table_paths(&self) -> impl Iterator<Item = &str> {
+        self.tables.iter().map(|(path, _)| path.as_str())
+    }
+
+    /// Write the tables into the archive at `path`
+    ///
+    /// The archive is created if it does not exist. In an existing archive,
+    /// tables replace files of the same name and the listfile and attributes
+    /// are updated.
+    pub fn write<P: AsRef<Path>>(self, path: P) -> Result<()> {
+        let path = path.as_ref();
+
+        if path.exists() {
+            let mut archive = MutableArchive::open(path)?;
+            for (name, data) in &self.tables {
+                archive.add_file_data(data, name, AddFileOptions::new())?;
+            }
+            archive.flush()?;
+        } else {
+            let mut builder = ArchiveBuilder::new().version(self.version);
+            for (name, data) in self.tables {
+                builder = builder.add_file_data(data, &name);
+            }
+            builder.build(path)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Write a single table into the patch MPQ at `archive`
+///
+/// Shorthand for [`DbcPatch::new`], [`DbcPatch::add_table`] and
+/// [`DbcPatch::write`].
+pub fn write_to_patch<P: AsRef<Path>>(archive: P, table: &str, records: &RecordSet) -> Result<()> {
+    DbcPatch::new().add_table(table, records)?.write(archive)
+}
+
+fn has_key(records: &RecordSet) -> bool {
+    records
+        .schema()
+        .is_some_and(|schema| schema.key_field_index.is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DbcParser, FieldType, Schema, SchemaField, Value};
+    use wow_mpq::Archive;
+
+    fn schema() -> Schema {
+        let mut schema = Schema::new("Test");
+        schema.add_field(SchemaField::new("ID", FieldType::UInt32));
+        schema.add_field(SchemaField::new("Value", FieldType::UInt32));
+        schema.set_key_field("ID");
+        schema
+    }
+
+    fn records(values: &[(u32, u32)]) -> RecordSet {
+        let mut data = Vec::new();
+        data.extend_from_slice(b"WDBC");
+        data.extend_from_slice(&(values.len() as u32).to_le_bytes());
+        data.extend_from_slice(&2u32.to_le_bytes());
+        data.extend_from_slice(&8u32.to_le_bytes());
+        data.extend_from_slice(&1u32.to_le_bytes());
+        for (id, value) in values {
+            data.extend_from_slice(&id.to_le_bytes());
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.push(0);
+
+        DbcParser::parse_bytes(&data)
+            .unwrap()
+            .with_schema(schema())
+            .unwrap()
+            .parse_records()
+            .unwrap()
+    }
+
+    fn read_table(archive: &Path, name: &str) -> RecordSet {
+        let data = Archive::open(archive).unwrap().read_file(name).unwrap();
+        DbcParser::parse_bytes(&data)
+            .unwrap()
+            .with_schema(schema())
+            .unwrap()
+            .parse_records()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_table_path() {
+        assert_eq!(table_path("Spell"), "DBFilesClient\\Spell.dbc");
+        assert_eq!(table_path("Item.db2"), "DBFilesClient\\Item.db2");
+        assert_eq!(
+            table_path("DBFilesClient/Map.dbc"),
+            "DBFilesClient\\Map.dbc"
+        );
+    }
+
+    #[test]
+    fn test_write_creates_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("patch-9.mpq");
+
+        write_to_patch(&path, "Test", &records(&[(2, 20), (1, 10)])).unwrap();
+
+        let table = read_table(&path, "DBFilesClient\\Test.dbc");
+        assert_eq!(table.len(), 2);
+        let first = table.get_record(0).unwrap();
+        assert!(matches!(first.get_value(0), Some(Value::UInt32(1))));
+        assert!(matches!(first.get_value(1), Some(Value::UInt32(10))));
+    }
+
+    #[test]
+    fn test_write_replaces_table_in_existing_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("patch-9.mpq");
+
+        DbcPatch::new()
+            .add_table("Test", &records(&[(1, 10)]))
+            .unwrap()
+            .add_table_data("Other.dbc", b"keep".to_vec())
+            .write(&path)
+            .unwrap();
+        write_to_patch(&path, "Test", &records(&[(1, 99), (2, 20)])).unwrap();
+
+        let table = read_table(&path, "DBFilesClient\\Test.dbc");
+        assert_eq!(table.len(), 2);
+        let first = table.get_record(0).unwrap();
+        assert!(matches!(first.get_value(1), Some(Value::UInt32(99))));
+
+        let mut archive = Archive::open(&path).unwrap();
+        assert_eq!(
+            archive.read_file("DBFilesClient\\Other.dbc").unwrap(),
+            b"keep"
+        );
+    }
+}
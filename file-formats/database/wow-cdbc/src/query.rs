@@ -0,0 +1,717 @@
+//! Filtering and projection over parsed DBC records
+//!
+//! A [`Query`] selects records from a [`RecordSet`] with a condition such as
+//! `SpellLevel > 40 AND Name ~ 'fire'` and projects a subset of columns.
+//!
+//! Supported syntax:
+//!
+//! - Comparisons: `=`, `==`, `!=`, `<>`, `<`, `<=`, `>`, `>=` and `~`
+//!   (case-insensitive substring match)
+//! - Logic: `AND`, `OR`, `NOT` (also `&&`, `||`, `!`) and parentheses
+//! - Literals: integers, floats, hex (`0x1F`), quoted strings (`'...'` or
+//!   `"..."`) and bare words, which are treated as strings
+//!
+//! Field names are matched case-insensitively against the schema. Without a
+//! schema, fields are addressed as `field_N`, matching the export naming.
+//! A condition on an array field matches if any element matches.
+
+use crate::{Error, Record, RecordSet, Result, Value};
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+/// Comparison operator in a query condition
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    /// Equal
+    Eq,
+    /// Not equal
+    Ne,
+    /// Less than
+    Lt,
+    /// Less than or equal
+    Le,
+    /// Greater than
+    Gt,
+    /// Greater than or equal
+    Ge,
+    /// Case-insensitive substring match
+    Contains,
+}
+
+impl CompareOp {
+    fn test(self, ordering: Ordering) -> bool {
+        match self {
+            CompareOp::Eq => ordering == Ordering::Equal,
+            CompareOp::Ne => ordering != Ordering::Equal,
+            CompareOp::Lt => ordering == Ordering::Less,
+            CompareOp::Le => ordering != Ordering::Greater,
+            CompareOp::Gt => ordering == Ordering::Greater,
+            CompareOp::Ge => ordering != Ordering::Less,
+            CompareOp::Contains => false,
+        }
+    }
+}
+
+impl fmt::Display for CompareOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self {
+            CompareOp::Eq => "=",
+            CompareOp::Ne => "!=",
+            CompareOp::Lt => "<",
+            CompareOp::Le => "<=",
+            CompareOp::Gt => ">",
+            CompareOp::Ge => ">=",
+            CompareOp::Contains => "~",
+        };
+        f.write_str(op)
+    }
+}
+
+/// Literal value on the right-hand side of a comparison
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    /// Numeric literal
+    Number(f64),
+    /// String literal
+    Text(String),
+}
+
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Literal::Number(n) => write!(f, "{n}"),
+            Literal::Text(s) => write!(f, "'{s}'"),
+        }
+    }
+}
+
+/// A parsed query condition
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    /// Compare a field against a literal
+    Compare {
+        /// Field name
+        field: String,
+        /// Comparison operator
+        op: CompareOp,
+        /// Value to compare against
+        value: Literal,
+    },
+    /// Both conditions must match
+    And(Box<Condition>, Box<Condition>),
+    /// Either condition must match
+    Or(Box<Condition>, Box<Condition>),
+    /// The condition must not match
+    Not(Box<Condition>),
+}
+
+impl Condition {
+    /// Parse a condition expression
+    pub fn parse(expr: &str) -> Result<Self> {
+        let tokens = tokenize(expr)?;
+        let mut parser = ConditionParser { tokens, pos: 0 };
+        let condition = parser.parse_or()?;
+        if let Some(token) = parser.tokens.get(parser.pos) {
+            return Err(Error::InvalidQuery(format!("unexpected {token}")));
+        }
+        Ok(condition)
+    }
+
+    /// Resolve field names to column indices
+    fn bind(&self, record_set: &RecordSet) -> Result<BoundCondition> {
+        Ok(match self {
+            Condition::Compare { field, op, value } => BoundCondition::Compare {
+                index: resolve_column(record_set, field)?.1,
+                op: *op,
+                value: value.clone(),
+            },
+            Condition::And(a, b) => {
+                BoundCondition::And(Box::new(a.bind(record_set)?), Box::new(b.bind(record_set)?))
+            }
+            Condition::Or(a, b) => {
+                BoundCondition::Or(Box::new(a.bind(record_set)?), Box::new(b.bind(record_set)?))
+            }
+            Condition::Not(c) => BoundCondition::Not(Box::new(c.bind(record_set)?)),
+        })
+    }
+}
+
+impl FromStr for Condition {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+/// Condition with field names resolved to column indices
+enum BoundCondition {
+    Compare {
+        index: usize,
+        op: CompareOp,
+        value: Literal,
+    },
+    And(Box<BoundCondition>, Box<BoundCondition>),
+    Or(Box<BoundCondition>, Box<BoundCondition>),
+    Not(Box<BoundCondition>),
+}
+
+impl BoundCondition {
+    fn matches(&self, record: &Record, record_set: &RecordSet) -> Result<bool> {
+        match self {
+            BoundCondition::Compare { index, op, value } => match record.get_value(*index) {
+                Some(field) => compare_value(field, *op, value, record_set),
+                None => Ok(false),
+            },
+            BoundCondition::And(a, b) => {
+                Ok(a.matches(record, record_set)? && b.matches(record, record_set)?)
+            }
+            BoundCondition::Or(a, b) => {
+                Ok(a.matches(record, record_set)? || b.matches(record, record_set)?)
+            }
+            BoundCondition::Not(c) => Ok(!c.matches(record, record_set)?),
+        }
+    }
+}
+
+fn numeric_value(value: &Value) -> Option<f64> {
+    match value {
+        Value::Int32(v) => Some(*v as f64),
+        Value::UInt32(v) => Some(*v as f64),
+        Value::Float32(v) => Some(*v as f64),
+        Value::Bool(v) => Some(if *v { 1.0 } else { 0.0 }),
+        Value::UInt8(v) => Some(*v as f64),
+        Value::Int8(v) => Some(*v as f64),
+        Value::UInt16(v) => Some(*v as f64),
+        Value::Int16(v) => Some(*v as f64),
+        Value::StringRef(_) | Value::Array(_) => None,
+    }
+}
+
+fn compare_value(
+    value: &Value,
+    op: CompareOp,
+    literal: &Literal,
+    record_set: &RecordSet,
+) -> Result<bool> {
+    match value {
+        Value::Array(values) => {
+            for element in values {
+                if compare_value(element, op, literal, record_set)? {
+                    return Ok(true);
+                }
+            }
+            Ok(false)
+        }
+        Value::StringRef(string_ref) => {
+            let text = record_set.get_string(*string_ref)?;
+            let other = literal_text(literal);
+            if op == CompareOp::Contains {
+                return Ok(text.to_lowercase().contains(&other.to_lowercase()));
+            }
+            Ok(op.test(text.cmp(other.as_str())))
+        }
+        _ => {
+            let number = numeric_value(value).unwrap_or_default();
+            if op == CompareOp::Contains {
+                return Ok(number.to_string().contains(&literal_text(literal)));
+            }
+            let other = match literal {
+                Literal::Number(n) => *n,
+                Literal::Text(s) => s.parse::<f64>().map_err(|_| {
+                    Error::TypeConversion(format!("cannot compare numeric field with '{s}'"))
+                })?,
+            };
+            Ok(number
+                .partial_cmp(&other)
+                .is_some_and(|ordering| op.test(ordering)))
+        }
+    }
+}
+
+fn literal_text(literal: &Literal) -> String {
+    match literal {
+        Literal::Number(n) => n.to_string(),
+        Literal::Text(s) => s.clone(),
+    }
+}
+
+/// Resolve a column name to its display name and index
+fn resolve_column(record_set: &RecordSet, name: &str) -> Result<(String, usize)> {
+    if let Some(schema) = record_set.schema() {
+        return schema
+            .fields
+            .iter()
+            .position(|f| f.name.eq_ignore_ascii_case(name))
+            .map(|index| (schema.fields[index].name.clone(), index))
+            .ok_or_else(|| Error::InvalidQuery(format!("unknown field '{name}'")));
+    }
+
+    let field_count = record_set.get_record(0).map_or(0, Record::len);
+    name.to_ascii_lowercase()
+        .strip_prefix("field_")
+        .and_then(|n| n.parse::<usize>().ok())
+        .filter(|index| *index < field_count)
+        .map(|index| (format!("field_{index}"), index))
+        .ok_or_else(|| {
+            Error::InvalidQuery(format!(
+                "unknown field '{name}' (without a schema, use field_0..field_{})",
+                field_count.saturating_sub(1)
+            ))
+        })
+}
+
+/// Filter and projection over a [`RecordSet`]
+#[derive(Debug, Clone, Default)]
+pub struct Query {
+    condition: Option<Condition>,
+    columns: Vec<String>,
+    limit: Option<usize>,
+}
+
+impl Query {
+    /// Create a query that returns all records and columns
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only return records matching the condition expression
+    pub fn filter(mut self, expr: &str) -> Result<Self> {
+        self.condition = Some(Condition::parse(expr)?);
+        Ok(self)
+    }
+
+    /// Only return records matching an already parsed condition
+    pub fn with_condition(mut self, condition: Condition) -> Self {
+        self.condition = Some(condition);
+        self
+    }
+
+    /// Return only the named columns, in the given order
+    pub fn select<I, S>(mut self, columns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.columns = columns.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Stop after this many matching records
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Run the query against a record set
+    pub fn execute<'a>(&self, record_set: &'a RecordSet) -> Result<QueryResult<'a>> {
+        let columns: Vec<(String, usize)> = if self.columns.is_empty() {
+            match record_set.schema() {
+                Some(schema) => schema
+                    .fields
+                    .iter()
+                    .enumerate()
+                    .map(|(i, f)| (f.name.clone(), i))
+                    .collect(),
+                None => {
+                    let field_count = record_set.get_record(0).map_or(0, Record::len);
+                    (0..field_count)
+                        .map(|i| (format!("field_{i}"), i))
+                        .collect()
+                }
+            }
+        } else {
+            self.columns
+                .iter()
+                .map(|name| resolve_column(record_set, name))
+                .collect::<Result<_>>()?
+        };
+
+        let condition = self
+            .condition
+            .as_ref()
+            .map(|c| c.bind(record_set))
+            .transpose()?;
+
+        let limit = self.limit.unwrap_or(usize::MAX);
+        let mut records = Vec::new();
+        for record in record_set.records() {
+            if records.len() >= limit {
+                break;
+            }
+            let matched = match &condition {
+                Some(condition) => condition.matches(record, record_set)?,
+                None => true,
+            };
+            if matched {
+                records.push(record);
+            }
+        }
+
+        let (names, indices) = columns.into_iter().unzip();
+        Ok(QueryResult {
+            record_set,
+            columns: names,
+            indices,
+            records,
+        })
+    }
+}
+
+/// Records and columns selected by a [`Query`]
+#[derive(Debug)]
+pub struct QueryResult<'a> {
+    record_set: &'a RecordSet,
+    columns: Vec<String>,
+    indices: Vec<usize>,
+    records: Vec<&'a Record>,
+}
+
+impl<'a> QueryResult<'a> {
+    /// Names of the selected columns
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    /// The record set the query ran against, for resolving string references
+    pub fn record_set(&self) -> &'a RecordSet {
+        self.record_set
+    }
+
+    /// Matching records
+    pub fn records(&self) -> &[&'a Record] {
+        &self.records
+    }
+
+    /// Number of matching records
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    /// Whether no records matched
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Iterate over matching rows as the selected column values
+    pub fn rows(&self) -> impl Iterator<Item = Vec<Option<&'a Value>>> + '_ {
+        self.records.iter().map(|record| {
+            self.indices
+                .iter()
+                .map(|&index| record.get_value(index))
+                .collect()
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(f64),
+    Text(String),
+    Op(CompareOp),
+    And,
+    Or,
+    Not,
+    LParen,
+    RParen,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Ident(s) => write!(f, "'{s}'"),
+            Token::Number(n) => write!(f, "{n}"),
+            Token::Text(s) => write!(f, "\"{s}\""),
+            Token::Op(op) => write!(f, "'{op}'"),
+            Token::And => f.write_str("AND"),
+            Token::Or => f.write_str("OR"),
+            Token::Not => f.write_str("NOT"),
+            Token::LParen => f.write_str("'('"),
+            Token::RParen => f.write_str("')'"),
+        }
+    }
+}
+
+fn tokenize(expr: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = expr.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        let (token, len) = match (c, next) {
+            ('(', _) => (Token::LParen, 1),
+            (')', _) => (Token::RParen, 1),
+            ('=', Some('=')) => (Token::Op(CompareOp::Eq), 2),
+            ('=', _) => (Token::Op(CompareOp::Eq), 1),
+            ('!', Some('=')) => (Token::Op(CompareOp::Ne), 2),
+            ('!', _) => (Token::Not, 1),
+            ('<', Some('>')) => (Token::Op(CompareOp::Ne), 2),
+            ('<', Some('=')) => (Token::Op(CompareOp::Le), 2),
+            ('<', _) => (Token::Op(CompareOp::Lt), 1),
+            ('>', Some('=')) => (Token::Op(CompareOp::Ge), 2),
+            ('>', _) => (Token::Op(CompareOp::Gt), 1),
+            ('~', _) => (Token::Op(CompareOp::Contains), 1),
+            ('&', Some('&')) => (Token::And, 2),
+            ('|', Some('|')) => (Token::Or, 2),
+            ('\'' | '"', _) => {
+                let end = chars[i + 1..]
+                    .iter()
+                    .position(|&ch| ch == c)
+                    .ok_or_else(|| Error::InvalidQuery("unterminated string".to_string()))?;
+                let text: String = chars[i + 1..i + 1 + end].iter().collect();
+                (Token::Text(text), end + 2)
+            }
+            _ if c.is_ascii_digit()
+                || (c == '-' && next.is_some_and(|n| n.is_ascii_digit() || n == '.'))
+                || (c == '.' && next.is_some_and(|n| n.is_ascii_digit())) =>
+            {
+                let len = 1 + chars[i + 1..]
+                    .iter()
+                    .take_while(|ch| ch.is_ascii_alphanumeric() || **ch == '.')
+                    .count();
+                let text: String = chars[i..i + len].iter().collect();
+                (Token::Number(parse_number(&text)?), len)
+            }
+            _ if c.is_alphanumeric() || c == '_' => {
+                let len = chars[i..]
+                    .iter()
+                    .take_while(|ch| ch.is_alphanumeric() || **ch == '_')
+                    .count();
+                let word: String = chars[i..i + len].iter().collect();
+                let token = match word.to_ascii_uppercase().as_str() {
+                    "AND" => Token::And,
+                    "OR" => Token::Or,
+                    "NOT" => Token::Not,
+                    _ => Token::Ident(word),
+                };
+                (token, len)
+            }
+            _ => {
+                return Err(Error::InvalidQuery(format!(
+                    "unexpected character '{c}' at position {i}"
+                )));
+            }
+        };
+
+        tokens.push(token);
+        i += len;
+    }
+
+    Ok(tokens)
+}
+
+fn parse_number(text: &str) -> Result<f64> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text),
+    };
+    let value = match digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        Some(hex) => u64::from_str_radix(hex, 16).map(|v| v as f64).ok(),
+        None => digits.parse::<f64>().ok(),
+    }
+    .ok_or_else(|| Error::InvalidQuery(format!("invalid number '{text}'")))?;
+    Ok(if negative { -value } else { value })
+}
+
+struct ConditionParser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl ConditionParser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, expected: &Token) -> bool {
+        if self.tokens.get(self.pos) == Some(expected) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Condition> {
+        let mut left = self.parse_and()?;
+        while self.eat(&Token::Or) {
+            let right = self.parse_and()?;
+            left = Condition::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Condition> {
+        let mut left = self.parse_unary()?;
+        while self.eat(&Token::And) {
+            let right = self.parse_unary()?;
+            left = Condition::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Condition> {
+        match self.next() {
+            Some(Token::Not) => Ok(Condition::Not(Box::new(self.parse_unary()?))),
+            Some(Token::LParen) => {
+                let condition = self.parse_or()?;
+                if !self.eat(&Token::RParen) {
+                    return Err(Error::InvalidQuery("expected ')'".to_string()));
+                }
+                Ok(condition)
+            }
+            Some(Token::Ident(field)) => {
+                let op = match self.next() {
+                    Some(Token::Op(op)) => op,
+                    Some(other) => {
+                        return Err(Error::InvalidQuery(format!(
+                            "expected comparison after '{field}', found {other}"
+                        )));
+                    }
+                    None => {
+                        return Err(Error::InvalidQuery(format!(
+                            "expected comparison after '{field}'"
+                        )));
+                    }
+                };
+                let value = match self.next() {
+                    Some(Token::Number(n)) => Literal::Number(n),
+                    Some(Token::Text(s) | Token::Ident(s)) => Literal::Text(s),
+                    Some(other) => {
+                        return Err(Error::InvalidQuery(format!(
+                            "expected value after '{field} {op}', found {other}"
+                        )));
+                    }
+                    None => {
+                        return Err(Error::InvalidQuery(format!(
+                            "expected value after '{field} {op}'"
+                        )));
+                    }
+                };
+                Ok(Condition::Compare { field, op, value })
+            }
+            Some(other) => Err(Error::InvalidQuery(format!(
+                "expected field name, found {other}"
+            ))),
+            None => Err(Error::InvalidQuery("empty condition".to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DbcParser, FieldType, Schema, SchemaField};
+
+    fn test_records() -> RecordSet {
+        let mut data = Vec::new();
+        data.extend_from_slice(b"WDBC");
+        data.extend_from_slice(&3u32.to_le_bytes()); // Record count
+        data.extend_from_slice(&3u32.to_le_bytes()); // Field count
+        data.extend_from_slice(&12u32.to_le_bytes()); // Record size
+        data.extend_from_slice(&32u32.to_le_bytes()); // String block size
+
+        for (id, name, level) in [(1u32, 1u32, 10u32), (2, 10, 45), (3, 20, 60)] {
+            data.extend_from_slice(&id.to_le_bytes());
+            data.extend_from_slice(&name.to_le_bytes());
+            data.extend_from_slice(&level.to_le_bytes());
+        }
+        data.extend_from_slice(b"\0Fireball\0Frostbolt\0Flamestrike\0");
+
+        let mut schema = Schema::new("Spell");
+        schema.add_field(SchemaField::new("ID", FieldType::UInt32));
+        schema.add_field(SchemaField::new("Name", FieldType::String));
+        schema.add_field(SchemaField::new("SpellLevel", FieldType::UInt32));
+        schema.set_key_field("ID");
+
+        DbcParser::parse_bytes(&data)
+            .unwrap()
+            .with_schema(schema)
+            .unwrap()
+            .parse_records()
+            .unwrap()
+    }
+
+    fn matching_ids(record_set: &RecordSet, expr: &str) -> Vec<u32> {
+        let result = Query::new()
+            .filter(expr)
+            .unwrap()
+            .select(["id"])
+            .execute(record_set)
+            .unwrap();
+        result
+            .rows()
+            .map(|row| match row[0] {
+                Some(Value::UInt32(id)) => *id,
+                other => panic!("unexpected value {other:?}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_precedence() {
+        let condition = Condition::parse("a = 1 OR b = 2 AND NOT c = 3").unwrap();
+        assert!(
+            matches!(condition, Condition::Or(_, ref right) if matches!(**right, Condition::And(_, _)))
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Condition::parse("").is_err());
+        assert!(Condition::parse("Level >").is_err());
+        assert!(Condition::parse("(Level > 1").is_err());
+        assert!(Condition::parse("Name = 'open").is_err());
+    }
+
+    #[test]
+    fn test_numeric_filters() {
+        let records = test_records();
+        assert_eq!(matching_ids(&records, "SpellLevel > 40"), vec![2, 3]);
+        assert_eq!(matching_ids(&records, "spelllevel <= 45"), vec![1, 2]);
+        assert_eq!(
+            matching_ids(&records, "ID != 2 AND SpellLevel >= 0x3C"),
+            vec![3]
+        );
+    }
+
+    #[test]
+    fn test_string_filters() {
+        let records = test_records();
+        assert_eq!(matching_ids(&records, "Name = 'Frostbolt'"), vec![2]);
+        assert_eq!(
+            matching_ids(&records, "Name ~ fl OR Name ~ \"BALL\""),
+            vec![1, 3]
+        );
+        assert_eq!(matching_ids(&records, "NOT (Name ~ f)"), Vec::<u32>::new());
+    }
+
+    #[test]
+    fn test_select_and_limit() {
+        let records = test_records();
+        let result = Query::new()
+            .select(["name", "ID"])
+            .limit(2)
+            .execute(&records)
+            .unwrap();
+        assert_eq!(result.columns(), ["Name", "ID"]);
+        assert_eq!(result.len(), 2);
+
+        assert!(Query::new().select(["Missing"]).execute(&records).is_err());
+    }
+}
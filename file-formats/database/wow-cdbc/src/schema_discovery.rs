@@ -635,8 +635,9 @@ impl<'a> SchemaDiscoverer<'a> {
 
         // Add fields with meaningful names based on type and position
         for (i, field) in discovered.fields.iter().enumerate() {
-            // Use field index as a base for field names
-            let field_name = if field.is_key_candidate {
+            // Use field index as a base for field names; only the chosen key is "ID"
+            // so that other key candidates keep unique names
+            let field_name = if Some(i) == discovered.key_field_index {
                 "ID".to_string()
             } else {
                 match field.field_type {
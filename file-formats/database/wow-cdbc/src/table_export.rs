@@ -0,0 +1,310 @@
+//! Tabular exports (SQL and Parquet) of DBC record sets
+//!
+//! Both formats need one typed value per cell, so array fields are flattened
+//! into one column per element (`Name_0`, `Name_1`, ...). Column types come
+//! from the schema; without a schema they are taken from the first record.
+
+use crate::parquet::{ParquetColumn, ParquetType, write_parquet};
+use crate::{FieldType, Record, RecordSet, Value};
+use std::io;
+
+/// Rows per `INSERT` statement in SQL output
+const SQL_ROWS_PER_INSERT: usize = 500;
+
+/// An output column of a tabular export
+#[derive(Debug, Clone)]
+pub(crate) struct Column {
+    pub name: String,
+    pub field_type: FieldType,
+    /// Index of the field in the record
+    pub field: usize,
+    /// Element index for flattened array fields
+    pub element: Option<usize>,
+}
+
+impl Column {
+    /// The value of this column in `record`
+    pub fn value<'a>(&self, record: &'a Record) -> Option<&'a Value> {
+        let value = record.get_value(self.field)?;
+        match (self.element, value) {
+            (Some(i), Value::Array(values)) => values.get(i),
+            (None, value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+fn field_type_of(value: &Value) -> FieldType {
+    match value {
+        Value::Int32(_) => FieldType::Int32,
+        Value::UInt32(_) => FieldType::UInt32,
+        Value::Float32(_) => FieldType::Float32,
+        Value::StringRef(_) => FieldType::String,
+        Value::Bool(_) => FieldType::Bool,
+        Value::UInt8(_) => FieldType::UInt8,
+        Value::Int8(_) => FieldType::Int8,
+        Value::UInt16(_) => FieldType::UInt16,
+        Value::Int16(_) => FieldType::Int16,
+        Value::Array(values) => values.first().map_or(FieldType::UInt32, field_type_of),
+    }
+}
+
+/// Flattened columns of a record set
+pub(crate) fn table_columns(record_set: &RecordSet) -> Vec<Column> {
+    let Some(schema) = record_set.schema() else {
+        return record_set
+            .get_record(0)
+            .map(|record| {
+                record
+                    .values()
+                    .iter()
+                    .enumerate()
+                    .map(|(i, value)| Column {
+                        name: format!("field_{i}"),
+                        field_type: field_type_of(value),
+                        field: i,
+                        element: None,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+    };
+
+    let mut columns = Vec::new();
+    for (i, field) in schema.fields.iter().enumerate() {
+        match field.array_size {
+            Some(size) if field.is_array => {
+                columns.extend((0..size).map(|element| Column {
+                    name: format!("{}_{element}", field.name),
+                    field_type: field.field_type,
+                    field: i,
+                    element: Some(element),
+                }));
+            }
+            _ => columns.push(Column {
+                name: field.name.clone(),
+                field_type: field.field_type,
+                field: i,
+                element: None,
+            }),
+        }
+    }
+    columns
+}
+
+/// SQLite column type for a field type
+pub fn sql_type(field_type: FieldType) -> &'static str {
+    match field_type {
+        FieldType::Float32 => "REAL",
+        FieldType::String => "TEXT",
+        _ => "INTEGER",
+    }
+}
+
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn sql_literal(value: Option<&Value>, record_set: &RecordSet) -> String {
+    match value {
+        None => "NULL".to_string(),
+        Some(Value::Int32(v)) => v.to_string(),
+        Some(Value::UInt32(v)) => v.to_string(),
+        Some(Value::Float32(v)) if v.is_finite() => format!("{v:?}"),
+        Some(Value::Float32(_)) => "NULL".to_string(),
+        Some(Value::StringRef(v)) => {
+            let text = record_set.get_string(*v).unwrap_or_default();
+            format!("'{}'", text.replace('\'', "''"))
+        }
+        Some(Value::Bool(v)) => u8::from(*v).to_string(),
+        Some(Value::UInt8(v)) => v.to_string(),
+        Some(Value::Int8(v)) => v.to_string(),
+        Some(Value::UInt16(v)) => v.to_string(),
+        Some(Value::Int16(v)) => v.to_string(),
+        Some(Value::Array(_)) => "NULL".to_string(),
+    }
+}
+
+/// Export a record set as SQL statements that (re)create `table`
+///
+/// The output drops and recreates the table with SQLite column types derived
+/// from the schema, indexes the schema's key field and inserts all records
+/// inside a single transaction, so it can be fed to `sqlite3` directly.
+pub fn export_to_sql<W: io::Write>(
+    record_set: &RecordSet,
+    table: &str,
+    mut writer: W,
+) -> Result<(), io::Error> {
+    let columns = table_columns(record_set);
+    if columns.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{table} has no columns to export"),
+        ));
+    }
+    let quoted_table = quote_identifier(table);
+
+    writeln!(writer, "DROP TABLE IF EXISTS {quoted_table};")?;
+    let definitions: Vec<String> = columns
+        .iter()
+        .map(|c| format!("{} {}", quote_identifier(&c.name), sql_type(c.field_type)))
+        .collect();
+    writeln!(
+        writer,
+        "CREATE TABLE {quoted_table} ({});",
+        definitions.join(", ")
+    )?;
+
+    let key_column = record_set
+        .schema()
+        .and_then(|schema| schema.key_field_index)
+        .and_then(|key| {
+            columns
+                .iter()
+                .find(|c| c.field == key && c.element.is_none())
+        });
+    if let Some(key) = key_column {
+        writeln!(
+            writer,
+            "CREATE INDEX {} ON {quoted_table} ({});",
+            quote_identifier(&format!("{table}_{}", key.name)),
+            quote_identifier(&key.name)
+        )?;
+    }
+
+    writeln!(writer, "BEGIN;")?;
+    for chunk in record_set.records().chunks(SQL_ROWS_PER_INSERT) {
+        writeln!(writer, "INSERT INTO {quoted_table} VALUES")?;
+        for (i, record) in chunk.iter().enumerate() {
+            let values: Vec<String> = columns
+                .iter()
+                .map(|column| sql_literal(column.value(record), record_set))
+                .collect();
+            let separator = if i + 1 == chunk.len() { ";" } else { "," };
+            writeln!(writer, "  ({}){separator}", values.join(", "))?;
+        }
+    }
+    writeln!(writer, "COMMIT;")?;
+    Ok(())
+}
+
+/// Export a record set to a Parquet file with one typed column per field
+///
+/// Unsigned types keep their unsigned annotation, strings are UTF-8 byte
+/// arrays and booleans are native Parquet booleans. The file holds a single
+/// uncompressed row group.
+pub fn export_to_parquet<W: io::Write>(record_set: &RecordSet, writer: W) -> Result<(), io::Error> {
+    let columns = table_columns(record_set);
+    let records = record_set.records();
+
+    let parquet_columns = columns
+        .iter()
+        .map(|column| {
+            let values = records.iter().map(|record| column.value(record));
+            let data = match column.field_type {
+                FieldType::Bool => {
+                    ParquetType::Boolean(values.map(|v| int_value(v) != 0).collect())
+                }
+                FieldType::Float32 => ParquetType::Float(
+                    values
+                        .map(|v| match v {
+                            Some(Value::Float32(f)) => *f,
+                            _ => 0.0,
+                        })
+                        .collect(),
+                ),
+                FieldType::String => ParquetType::Utf8(
+                    values
+                        .map(|v| match v {
+                            Some(Value::StringRef(s)) => {
+                                record_set.get_string(*s).unwrap_or_default().to_string()
+                            }
+                            _ => String::new(),
+                        })
+                        .collect(),
+                ),
+                // Unsigned values keep their bit pattern; the column is annotated as unsigned
+                _ => ParquetType::Int32 {
+                    values: values.map(|v| int_value(v) as i32).collect(),
+                    field_type: column.field_type,
+                },
+            };
+            ParquetColumn {
+                name: column.name.clone(),
+                data,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    write_parquet(&parquet_columns, records.len(), writer)
+}
+
+fn int_value(value: Option<&Value>) -> i64 {
+    match value {
+        Some(Value::Int32(v)) => i64::from(*v),
+        Some(Value::UInt32(v)) => i64::from(*v),
+        Some(Value::Bool(v)) => i64::from(*v),
+        Some(Value::UInt8(v)) => i64::from(*v),
+        Some(Value::Int8(v)) => i64::from(*v),
+        Some(Value::UInt16(v)) => i64::from(*v),
+        Some(Value::Int16(v)) => i64::from(*v),
+        Some(Value::StringRef(v)) => i64::from(v.offset()),
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DbcParser, Schema, SchemaField};
+
+    fn make_record_set() -> RecordSet {
+        let mut data = Vec::new();
+        data.extend_from_slice(b"WDBC");
+        data.extend_from_slice(&2u32.to_le_bytes()); // record_count
+        data.extend_from_slice(&4u32.to_le_bytes()); // field_count
+        data.extend_from_slice(&16u32.to_le_bytes()); // record_size
+        data.extend_from_slice(&12u32.to_le_bytes()); // string_block_size
+        for (id, name, pos) in [(1u32, 1u32, [1u32, 2]), (2, 6, [3, 4])] {
+            data.extend_from_slice(&id.to_le_bytes());
+            data.extend_from_slice(&name.to_le_bytes());
+            for p in pos {
+                data.extend_from_slice(&p.to_le_bytes());
+            }
+        }
+        data.extend_from_slice(b"\x00Mage\x00O'Neil\x00");
+
+        let mut schema = Schema::new("Test");
+        schema.add_field(SchemaField::new("ID", FieldType::UInt32));
+        schema.add_field(SchemaField::new("Name", FieldType::String));
+        schema.add_field(SchemaField::new_array("Pos", FieldType::UInt32, 2));
+        schema.set_key_field("ID");
+        DbcParser::parse_bytes(&data)
+            .unwrap()
+            .with_schema(schema)
+            .unwrap()
+            .parse_records()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_columns_flatten_arrays() {
+        let names: Vec<_> = table_columns(&make_record_set())
+            .into_iter()
+            .map(|c| c.name)
+            .collect();
+        assert_eq!(names, ["ID", "Name", "Pos_0", "Pos_1"]);
+    }
+
+    #[test]
+    fn test_sql_export() {
+        let mut out = Vec::new();
+        export_to_sql(&make_record_set(), "Test", &mut out).unwrap();
+        let sql = String::from_utf8(out).unwrap();
+        assert!(sql.contains(
+            "CREATE TABLE \"Test\" (\"ID\" INTEGER, \"Name\" TEXT, \"Pos_0\" INTEGER, \"Pos_1\" INTEGER);"
+        ));
+        assert!(sql.contains("CREATE INDEX \"Test_ID\" ON \"Test\" (\"ID\");"));
+        assert!(sql.contains("  (1, 'Mage', 1, 2),\n  (2, 'O''Neil', 3, 4);"));
+    }
+}
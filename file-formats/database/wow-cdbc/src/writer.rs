@@ -1,9 +1,29 @@
 //! DBC file writing functionality
+//!
+//! Records are written as WDBC by default. [`DbcWriter::wdb2`] writes a WDB2
+//! file instead; for builds after 12880 its extended header carries the ID
+//! range and, when the schema has a key field, the ID index and string
+//! length arrays the client uses for lookups. [`DbcWriter::sort_by_key`]
+//! orders records by ID, as Blizzard's own files are.
 
+use crate::versions::Wdb2Header;
 use crate::{Error, FieldType, Record, RecordSet, Result, Schema, Value};
 use std::collections::HashMap;
 use std::io::{Seek, SeekFrom, Write};
 
+/// WDB2 header values for [`DbcWriter::wdb2`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Wdb2Options {
+    /// Table hash
+    pub table_hash: u32,
+    /// Client build; builds after 12880 get the extended header
+    pub build: u32,
+    /// Timestamp
+    pub timestamp: u32,
+    /// Locale flags (extended header only)
+    pub locale: i32,
+}
+
 /// Writer for DBC files
 #[derive(Debug)]
 pub struct DbcWriter<W: Write + Seek> {
@@ -11,6 +31,10 @@ pub struct DbcWriter<W: Write + Seek> {
     writer: W,
     /// The schema to use for writing
     schema: Option<Schema>,
+    /// Order records by their key field
+    sort_by_key: bool,
+    /// Write WDB2 instead of WDBC
+    wdb2: Option<Wdb2Options>,
 }
 
 impl<W: Write + Seek> DbcWriter<W> {
@@ -19,6 +43,8 @@ impl<W: Write + Seek> DbcWriter<W> {
         Self {
             writer,
             schema: None,
+            sort_by_key: false,
+            wdb2: None,
         }
     }
 
@@ -28,6 +54,20 @@ impl<W: Write + Seek> DbcWriter<W> {
         self
     }
 
+    /// Write records in ascending order of the schema's key field
+    ///
+    /// Records with equal keys keep their order.
+    pub fn sort_by_key(mut self, sort: bool) -> Self {
+        self.sort_by_key = sort;
+        self
+    }
+
+    /// Write a WDB2 file with the given header values
+    pub fn wdb2(mut self, options: Wdb2Options) -> Self {
+        self.wdb2 = Some(options);
+        self
+    }
+
     /// Write a record set to the output
     pub fn write_records(&mut self, record_set: &RecordSet) -> Result<()> {
         // Ensure we have a schema
@@ -41,25 +81,69 @@ impl<W: Write + Seek> DbcWriter<W> {
             ));
         };
 
+        let mut records: Vec<&Record> = record_set.records().iter().collect();
+        let mut keys = match schema.key_field_index {
+            Some(index) => Some(
+                records
+                    .iter()
+                    .map(|record| record_key(record, index))
+                    .collect::<Result<Vec<i64>>>()?,
+            ),
+            None => None,
+        };
+        if self.sort_by_key {
+            let Some(keys) = &mut keys else {
+                return Err(Error::InvalidRecord(
+                    "Sorting by key requires a schema with a key field".to_string(),
+                ));
+            };
+            let mut order: Vec<usize> = (0..records.len()).collect();
+            order.sort_by_key(|&i| keys[i]);
+            records = order.iter().map(|&i| records[i]).collect();
+            *keys = order.iter().map(|&i| keys[i]).collect();
+        }
+
         // Build the string block
-        let (string_block, string_offsets) = self.build_string_block(record_set)?;
+        let (string_block, string_offsets) = self.build_string_block(&records, record_set)?;
 
         // Calculate header values
-        let record_count = record_set.len() as u32;
-        let field_count = schema.fields.len() as u32;
+        let record_count = records.len() as u32;
+        // Array elements are separate fields in the header
+        let field_count = schema
+            .fields
+            .iter()
+            .map(|f| {
+                if f.is_array {
+                    f.array_size.unwrap_or(0)
+                } else {
+                    1
+                }
+            })
+            .sum::<usize>() as u32;
         let record_size = schema.record_size() as u32;
         let string_block_size = string_block.len() as u32;
 
         // Write header
         self.writer.seek(SeekFrom::Start(0))?;
-        self.writer.write_all(&crate::header::DBC_MAGIC)?;
+        match &self.wdb2 {
+            None => self.writer.write_all(&crate::header::DBC_MAGIC)?,
+            Some(_) => self.writer.write_all(b"WDB2")?,
+        }
         self.writer.write_all(&record_count.to_le_bytes())?;
         self.writer.write_all(&field_count.to_le_bytes())?;
         self.writer.write_all(&record_size.to_le_bytes())?;
         self.writer.write_all(&string_block_size.to_le_bytes())?;
+        if let Some(options) = self.wdb2.clone() {
+            self.writer.write_all(&options.table_hash.to_le_bytes())?;
+            self.writer.write_all(&options.build.to_le_bytes())?;
+            self.writer.write_all(&options.timestamp.to_le_bytes())?;
+            if options.build > Wdb2Header::EXTENDED_BUILD_THRESHOLD {
+                self.write_wdb2_index(&options, keys.as_deref(), &records, record_set, &schema)?;
+            }
+        }
 
         // Write records
-        for record in record_set.records() {
+        for record in &records {
             self.write_record(record, &schema, record_set, &string_offsets)?;
         }
 
@@ -69,9 +153,58 @@ impl<W: Write + Seek> DbcWriter<W> {
         Ok(())
     }
 
+    /// Write the extended WDB2 header fields and the ID index arrays
+    ///
+    /// For every ID from the minimum to the maximum key, the index array holds
+    /// the row of the record with that ID and the string length array the
+    /// total length of its strings; both are 0 for missing IDs. Tables
+    /// without a key field get an ID range of 0 and no arrays.
+    fn write_wdb2_index(
+        &mut self,
+        options: &Wdb2Options,
+        keys: Option<&[i64]>,
+        records: &[&Record],
+        record_set: &RecordSet,
+        schema: &Schema,
+    ) -> Result<()> {
+        let range = keys.and_then(|keys| Some((*keys.iter().min()?, *keys.iter().max()?)));
+        let (min_id, max_id) = match range {
+            Some((min, max)) => (
+                i32::try_from(min).map_err(|_| key_range_error(min))?,
+                i32::try_from(max).map_err(|_| key_range_error(max))?,
+            ),
+            None => (0, 0),
+        };
+        self.writer.write_all(&min_id.to_le_bytes())?;
+        self.writer.write_all(&max_id.to_le_bytes())?;
+        self.writer.write_all(&options.locale.to_le_bytes())?;
+        // Copy table size
+        self.writer.write_all(&0u32.to_le_bytes())?;
+
+        let Some(keys) = keys.filter(|_| max_id > 0) else {
+            return Ok(());
+        };
+        let span = (max_id as i64 - min_id as i64 + 1) as usize;
+        let mut rows = vec![0u32; span];
+        let mut string_lengths = vec![0u16; span];
+        for (row, (record, &key)) in records.iter().zip(keys).enumerate() {
+            let slot = (key - min_id as i64) as usize;
+            rows[slot] = row as u32;
+            string_lengths[slot] = record_string_length(record, record_set, schema)?;
+        }
+        for row in rows {
+            self.writer.write_all(&row.to_le_bytes())?;
+        }
+        for length in string_lengths {
+            self.writer.write_all(&length.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
     /// Build a string block from a record set
     fn build_string_block(
         &self,
+        records: &[&Record],
         record_set: &RecordSet,
     ) -> Result<(Vec<u8>, HashMap<String, u32>)> {
         let mut string_block = Vec::new();
@@ -81,8 +214,8 @@ impl<W: Write + Seek> DbcWriter<W> {
         string_block.push(0);
         string_offsets.insert(String::new(), 0);
 
-        // Add all strings from the record set
-        for record in record_set.records() {
+        // Add all strings in the order records are written
+        for record in records {
             for value in record.values() {
                 if let Value::StringRef(string_ref) = value {
                     let string = record_set.get_string(*string_ref)?;
@@ -172,3 +305,155 @@ impl<W: Write + Seek> DbcWriter<W> {
         Ok(())
     }
 }
+
+/// Key of a record as a signed ID
+fn record_key(record: &Record, index: usize) -> Result<i64> {
+    match record.get_value(index) {
+        Some(Value::UInt32(key)) => Ok(i64::from(*key)),
+        Some(Value::Int32(key)) => Ok(i64::from(*key)),
+        other => Err(Error::InvalidRecord(format!(
+            "Key field {index} holds {other:?} instead of an integer"
+        ))),
+    }
+}
+
+fn key_range_error(key: i64) -> Error {
+    Error::InvalidRecord(format!("Key {key} does not fit in a WDB2 ID range"))
+}
+
+/// Total length of the strings of a record
+fn record_string_length(record: &Record, record_set: &RecordSet, schema: &Schema) -> Result<u16> {
+    let mut length = 0usize;
+    for (index, field) in schema.fields.iter().enumerate() {
+        if field.field_type != FieldType::String {
+            continue;
+        }
+        let refs = match record.get_value(index) {
+            Some(Value::StringRef(string_ref)) => vec![*string_ref],
+            Some(Value::Array(values)) => values
+                .iter()
+                .filter_map(|value| match value {
+                    Value::StringRef(string_ref) => Some(*string_ref),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        for string_ref in refs {
+            length += record_set.get_string(string_ref)?.len();
+        }
+    }
+    Ok(length.min(u16::MAX as usize) as u16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DbcParser, SchemaField};
+    use std::io::Cursor;
+
+    fn schema() -> Schema {
+        let mut schema = Schema::new("Test");
+        schema.add_field(SchemaField::new("ID", FieldType::UInt32));
+        schema.add_field(SchemaField::new("Name", FieldType::String));
+        schema.set_key_field("ID");
+        schema
+    }
+
+    /// Records with IDs 12, 10 and 13, in that order
+    fn unsorted_records() -> RecordSet {
+        let mut data = Vec::new();
+        data.extend_from_slice(b"WDBC");
+        for value in [3u32, 2, 8, 14] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        for (id, name) in [(12u32, 1u32), (10, 6), (13, 0)] {
+            data.extend_from_slice(&id.to_le_bytes());
+            data.extend_from_slice(&name.to_le_bytes());
+        }
+        data.extend_from_slice(b"\0Late\0Early\0\0\0");
+        DbcParser::parse_bytes(&data)
+            .unwrap()
+            .with_schema(schema())
+            .unwrap()
+            .parse_records()
+            .unwrap()
+    }
+
+    fn ids(record_set: &RecordSet) -> Vec<u32> {
+        record_set
+            .records()
+            .iter()
+            .map(|record| match record.get_value(0) {
+                Some(Value::UInt32(id)) => *id,
+                other => panic!("unexpected key {other:?}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_sorted_wdb2_with_index() {
+        let mut buffer = Cursor::new(Vec::new());
+        DbcWriter::new(&mut buffer)
+            .sort_by_key(true)
+            .wdb2(Wdb2Options {
+                table_hash: 0x1234,
+                build: 15595,
+                ..Default::default()
+            })
+            .write_records(&unsorted_records())
+            .unwrap();
+        let bytes = buffer.into_inner();
+
+        let header = Wdb2Header::parse(&mut Cursor::new(&bytes)).unwrap();
+        assert_eq!((header.min_index, header.max_index), (10, 13));
+        assert_eq!(header.table_hash, 0x1234);
+
+        // Rows of IDs 10 to 13, then their string lengths
+        let index = &bytes[Wdb2Header::EXTENDED_SIZE..];
+        let rows: Vec<u32> = (0..4)
+            .map(|i| u32::from_le_bytes(index[i * 4..i * 4 + 4].try_into().unwrap()))
+            .collect();
+        assert_eq!(rows, [0, 0, 1, 2]);
+        let lengths: Vec<u16> = (0..4)
+            .map(|i| u16::from_le_bytes(index[16 + i * 2..18 + i * 2].try_into().unwrap()))
+            .collect();
+        assert_eq!(lengths, [5, 0, 4, 0]);
+
+        let written = DbcParser::parse_bytes(&bytes)
+            .unwrap()
+            .with_schema(schema())
+            .unwrap()
+            .parse_records()
+            .unwrap();
+        assert_eq!(ids(&written), [10, 12, 13]);
+        let Some(Value::StringRef(name)) = written.records()[0].get_value(1) else {
+            panic!("expected a string");
+        };
+        assert_eq!(written.get_string(*name).unwrap(), "Early");
+    }
+
+    #[test]
+    fn test_sort_requires_key_field() {
+        let mut schema = schema();
+        schema.key_field_index = None;
+        let result = DbcWriter::new(Cursor::new(Vec::new()))
+            .with_schema(schema)
+            .sort_by_key(true)
+            .write_records(&unsorted_records());
+        assert!(matches!(result, Err(Error::InvalidRecord(_))));
+
+        // Unsorted WDBC output keeps the input order
+        let mut buffer = Cursor::new(Vec::new());
+        DbcWriter::new(&mut buffer)
+            .write_records(&unsorted_records())
+            .unwrap();
+        let written = DbcParser::parse_bytes(buffer.get_ref())
+            .unwrap()
+            .with_schema(self::schema())
+            .unwrap()
+            .parse_records()
+            .unwrap();
+        assert_eq!(ids(&written), [12, 10, 13]);
+    }
+}
@@ -0,0 +1,296 @@
+//! Consistency checks for the mipmap chain of a parsed BLP file.
+//!
+//! Every mipmap level should be the previous level halved in each dimension
+//! and should look like a downsample of it. [check_mipmaps] decodes the chain
+//! level by level and reports levels that are missing, truncated, of the
+//! wrong size or whose content has nothing to do with their parent, which
+//! points at corrupted or crafted textures.
+//!
+//! ```no_run
+//! use wow_blp::{analysis::check_mipmaps, parser::load_blp};
+//!
+//! let blp = load_blp("texture.blp").expect("Failed to load BLP");
+//! for issue in check_mipmaps(&blp) {
+//!     println!("{issue}");
+//! }
+//! ```
+
+use crate::convert::blp_to_image;
+use crate::types::*;
+use ::image::{DynamicImage, RgbaImage, imageops, imageops::FilterType};
+use std::fmt;
+
+/// Largest side, in pixels, at which a level is compared with its parent.
+/// Comparing at a low resolution ignores the filter the encoder used.
+const COMPARE_SIZE: u32 = 32;
+
+/// Levels smaller than this in either dimension are not compared with their
+/// parent, as encoders often leave junk in the last few DXT blocks.
+const MIN_COMPARE_SIDE: u32 = 4;
+
+/// Mean difference per channel, from 0 to 1, above which a level is not
+/// considered a downsample of its parent.
+pub const MIPMAP_DIFFERENCE_THRESHOLD: f32 = 0.15;
+
+/// A problem found in the mipmap chain of a BLP file
+#[derive(Debug, Clone, PartialEq)]
+pub enum MipmapIssue {
+    /// The header asks for mipmaps, but the chain ends early
+    MissingLevels {
+        /// Number of levels the dimensions call for
+        expected: usize,
+        /// Number of levels in the file
+        found: usize,
+    },
+    /// DXT data of a level is shorter than its dimensions need
+    Truncated {
+        /// Mipmap level
+        level: usize,
+        /// Bytes needed for the level's dimensions
+        expected: usize,
+        /// Bytes in the file
+        found: usize,
+    },
+    /// A level could not be decoded
+    Undecodable {
+        /// Mipmap level
+        level: usize,
+        /// Why decoding failed
+        error: String,
+    },
+    /// A level does not have half the dimensions of the previous one
+    DimensionMismatch {
+        /// Mipmap level
+        level: usize,
+        /// Dimensions derived from the header
+        expected: (u32, u32),
+        /// Dimensions of the decoded level
+        found: (u32, u32),
+    },
+    /// A level does not look like a downsample of the previous one
+    ContentMismatch {
+        /// Mipmap level
+        level: usize,
+        /// Mean difference per channel from the downsampled parent, 0 to 1
+        difference: f32,
+    },
+}
+
+impl MipmapIssue {
+    /// Mipmap level the issue is about, if it concerns a single level
+    pub fn level(&self) -> Option<usize> {
+        match self {
+            MipmapIssue::MissingLevels { .. } => None,
+            MipmapIssue::Truncated { level, .. }
+            | MipmapIssue::Undecodable { level, .. }
+            | MipmapIssue::DimensionMismatch { level, .. }
+            | MipmapIssue::ContentMismatch { level, .. } => Some(*level),
+        }
+    }
+}
+
+impl fmt::Display for MipmapIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MipmapIssue::MissingLevels { expected, found } => write!(
+                f,
+                "Incomplete mipmap chain: expected {expected} levels, got {found}"
+            ),
+            MipmapIssue::Truncated {
+                level,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Mipmap {level} has {found} bytes of DXT data, {expected} needed"
+            ),
+            MipmapIssue::Undecodable { level, error } => {
+                write!(f, "Mipmap {level} cannot be decoded: {error}")
+            }
+            MipmapIssue::DimensionMismatch {
+                level,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Mipmap {level} is {}x{}, expected {}x{}",
+                found.0, found.1, expected.0, expected.1
+            ),
+            MipmapIssue::ContentMismatch { level, difference } => write!(
+                f,
+                "Mipmap {level} does not match a downsample of mipmap {} ({:.0}% difference)",
+                level - 1,
+                difference * 100.0
+            ),
+        }
+    }
+}
+
+/// Check that every mipmap level is a plausible downsample of the previous one
+///
+/// Returns an empty list for a consistent chain. Levels that cannot be
+/// decoded are reported and skipped, so the level after one is compared
+/// with the last level that decoded.
+pub fn check_mipmaps(image: &BlpImage) -> Vec<MipmapIssue> {
+    let mut issues = Vec::new();
+    let found = image.image_count();
+
+    if image.header.has_mipmaps() {
+        let expected = image.header.mipmaps_count() + 1;
+        if found < expected {
+            issues.push(MipmapIssue::MissingLevels { expected, found });
+        }
+    }
+
+    let mut parent: Option<RgbaImage> = None;
+    for level in 0..found {
+        let expected = image.header.mipmap_size(level);
+
+        if let Some((needed, present)) = dxtn_sizes(image, level)
+            && present < needed
+            && expected.0 >= MIN_COMPARE_SIDE
+            && expected.1 >= MIN_COMPARE_SIDE
+        {
+            issues.push(MipmapIssue::Truncated {
+                level,
+                expected: needed,
+                found: present,
+            });
+        }
+
+        let decoded = match blp_to_image(image, level) {
+            Ok(decoded) => decoded,
+            Err(err) => {
+                issues.push(MipmapIssue::Undecodable {
+                    level,
+                    error: err.to_string(),
+                });
+                continue;
+            }
+        };
+        let found_size = (decoded.width(), decoded.height());
+        if found_size != expected {
+            issues.push(MipmapIssue::DimensionMismatch {
+                level,
+                expected,
+                found: found_size,
+            });
+            parent = None;
+            continue;
+        }
+
+        let current = premultiplied(decoded);
+        if let Some(parent) = &parent
+            && expected.0 >= MIN_COMPARE_SIDE
+            && expected.1 >= MIN_COMPARE_SIDE
+        {
+            let difference = difference(parent, &current);
+            if difference > MIPMAP_DIFFERENCE_THRESHOLD {
+                issues.push(MipmapIssue::ContentMismatch { level, difference });
+            }
+        }
+        parent = Some(current);
+    }
+
+    issues
+}
+
+/// Bytes a DXT level needs for its dimensions and bytes it has
+fn dxtn_sizes(image: &BlpImage, level: usize) -> Option<(usize, usize)> {
+    let dxtn = image
+        .content
+        .dxt1()
+        .or_else(|| image.content.dxt3())
+        .or_else(|| image.content.dxt5())?;
+    let (width, height) = image.header.mipmap_size(level);
+    let blocks = (width as usize).div_ceil(4) * (height as usize).div_ceil(4);
+    let present = dxtn.images.get(level)?.len();
+    Some((blocks * dxtn.format.block_size(), present))
+}
+
+/// RGBA with colour scaled by alpha, so hidden colour under transparent
+/// pixels does not count
+fn premultiplied(image: DynamicImage) -> RgbaImage {
+    let mut image = image.into_rgba8();
+    for pixel in image.pixels_mut() {
+        let alpha = pixel.0[3] as u16;
+        for channel in &mut pixel.0[..3] {
+            *channel = (*channel as u16 * alpha / 255) as u8;
+        }
+    }
+    image
+}
+
+/// Mean difference per channel between `child` and `parent` scaled down to it
+fn difference(parent: &RgbaImage, child: &RgbaImage) -> f32 {
+    let width = child.width().min(COMPARE_SIZE);
+    let height = child.height().min(COMPARE_SIZE);
+    let parent = imageops::resize(parent, width, height, FilterType::Triangle);
+    let child = imageops::resize(child, width, height, FilterType::Triangle);
+
+    let total: u64 = parent
+        .as_raw()
+        .iter()
+        .zip(child.as_raw())
+        .map(|(a, b)| a.abs_diff(*b) as u64)
+        .sum();
+    total as f32 / (parent.as_raw().len() as f32 * 255.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convert::{Blp2Format, BlpTarget, image_to_blp};
+
+    fn gradient_blp() -> BlpImage {
+        let image = RgbaImage::from_fn(64, 64, |x, y| {
+            ::image::Rgba([(x * 4) as u8, (y * 4) as u8, 128, 255])
+        });
+        image_to_blp(
+            DynamicImage::ImageRgba8(image),
+            true,
+            BlpTarget::Blp2(Blp2Format::Raw3),
+            FilterType::Triangle,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_consistent_chain() {
+        assert_eq!(check_mipmaps(&gradient_blp()), Vec::new());
+    }
+
+    #[test]
+    fn test_mismatched_chain() {
+        let mut blp = gradient_blp();
+        let BlpContent::Raw3(raw3) = &mut blp.content else {
+            panic!("expected RAW3 content");
+        };
+        // Invert the colours of level 2 and cut level 3 short
+        for pixel in &mut raw3.images[2].pixels {
+            *pixel ^= 0x00FF_FFFF;
+        }
+        raw3.images[3].pixels.pop();
+        raw3.images.truncate(5);
+
+        let issues = check_mipmaps(&blp);
+        assert!(matches!(
+            issues[0],
+            MipmapIssue::MissingLevels {
+                expected: 7,
+                found: 5
+            }
+        ));
+        assert!(matches!(
+            issues[1],
+            MipmapIssue::ContentMismatch { level: 2, .. }
+        ));
+        assert!(matches!(
+            issues[2],
+            MipmapIssue::Undecodable { level: 3, .. }
+        ));
+        // Level 4 is compared with level 2, which no longer matches it
+        assert_eq!(issues[3].level(), Some(4));
+        assert_eq!(issues.len(), 4);
+    }
+}
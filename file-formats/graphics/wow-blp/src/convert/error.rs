@@ -35,6 +35,9 @@ pub enum Error {
     /// Palette size mismatch (expected 256 colors)
     #[error("Expected palette of 256 colors, but got {0}")]
     PaletteWrongSize(usize),
+    /// A preset was given an image whose sides are not powers of two
+    #[error("Image is {0}x{1}, but the target client needs power of two dimensions")]
+    NotPowerOfTwo(u32, u32),
     /// Failed to convert decompressed DXT1 data to raw format
     #[error("Failed to process bytes from DXT1 decomporession")]
     Dxt1RawConvertFail,
@@ -4,8 +4,10 @@ pub mod error;
 mod jpeg;
 mod mipmap;
 mod palette;
+mod preset;
 mod raw1;
 mod raw3;
+mod transcode;
 
 use crate::types::*;
 use ::image::DynamicImage;
@@ -13,10 +15,12 @@ pub use ::image::imageops::FilterType;
 use dxtn::*;
 pub use error::Error;
 use jpeg::*;
+pub use preset::BlpTargetPreset;
 use raw1::*;
 use raw3::*;
 use std::fmt;
 pub use texpresso::Algorithm as DxtAlgorithm;
+pub use transcode::{BlpEncoding, transcode};
 
 /// Convert from parsed raw BLP image to useful [DynamicImage]
 pub fn blp_to_image(image: &BlpImage, mipmap_level: usize) -> Result<DynamicImage, Error> {
@@ -0,0 +1,213 @@
+use super::error::Error;
+use super::{Blp2Format, BlpEncoding, BlpOldFormat, BlpTarget, DxtAlgorithm};
+use crate::types::*;
+use ::image::{DynamicImage, GenericImageView, imageops::FilterType};
+use std::fmt;
+
+/// Encoding settings known to load in a given client
+///
+/// A preset picks the BLP version, content type, alpha depth and mipmaps
+/// for the game, so an image can be converted without knowing which
+/// combinations each client reads:
+///
+/// - Warcraft III stores JPEG content in BLP1, with an 8 bit alpha channel
+///   if the image has transparency.
+/// - World of Warcraft stores DXT content in BLP2. Opaque images use DXT1,
+///   images with only fully transparent or opaque pixels DXT1 with 1 bit
+///   alpha, and images with smooth transparency DXT3 in 1.x or DXT5 from
+///   The Burning Crusade on.
+///
+/// Every preset writes a full mipmap chain, and requires power of two
+/// dimensions, which the clients need to use the mipmaps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BlpTargetPreset {
+    /// Warcraft III: Reign of Chaos and The Frozen Throne
+    WarcraftIII,
+    /// World of Warcraft 1.x
+    Classic,
+    /// The Burning Crusade (2.x)
+    Tbc,
+    /// Wrath of the Lich King (3.x)
+    Wotlk,
+    /// Cataclysm (4.x)
+    Cataclysm,
+    /// Mists of Pandaria (5.x)
+    Mop,
+}
+
+/// Transparency found in an image
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Transparency {
+    Opaque,
+    Binary,
+    Smooth,
+}
+
+fn transparency(image: &DynamicImage) -> Transparency {
+    if !image.color().has_alpha() {
+        return Transparency::Opaque;
+    }
+    let mut found = Transparency::Opaque;
+    for (_, _, pixel) in image.pixels() {
+        match pixel[3] {
+            255 => {}
+            0 => found = Transparency::Binary,
+            _ => return Transparency::Smooth,
+        }
+    }
+    found
+}
+
+impl BlpTargetPreset {
+    /// All presets, oldest game first
+    pub const ALL: [BlpTargetPreset; 6] = [
+        BlpTargetPreset::WarcraftIII,
+        BlpTargetPreset::Classic,
+        BlpTargetPreset::Tbc,
+        BlpTargetPreset::Wotlk,
+        BlpTargetPreset::Cataclysm,
+        BlpTargetPreset::Mop,
+    ];
+
+    /// BLP version the client reads
+    pub fn version(self) -> BlpVersion {
+        match self {
+            BlpTargetPreset::WarcraftIII => BlpVersion::Blp1,
+            _ => BlpVersion::Blp2,
+        }
+    }
+
+    /// Version and pixel format for `image`, chosen by its transparency
+    pub fn target_for(self, image: &DynamicImage) -> BlpTarget {
+        let transparency = transparency(image);
+        let compress_algorithm = DxtAlgorithm::default();
+
+        match (self, transparency) {
+            (BlpTargetPreset::WarcraftIII, transparency) => BlpTarget::Blp1(BlpOldFormat::Jpeg {
+                has_alpha: transparency != Transparency::Opaque,
+            }),
+            (_, Transparency::Opaque | Transparency::Binary) => BlpTarget::Blp2(Blp2Format::Dxt1 {
+                has_alpha: transparency == Transparency::Binary,
+                compress_algorithm,
+            }),
+            (BlpTargetPreset::Classic, Transparency::Smooth) => BlpTarget::Blp2(Blp2Format::Dxt3 {
+                has_alpha: true,
+                compress_algorithm,
+            }),
+            (_, Transparency::Smooth) => BlpTarget::Blp2(Blp2Format::Dxt5 {
+                has_alpha: true,
+                compress_algorithm,
+            }),
+        }
+    }
+
+    /// Encoding choices for `image`
+    ///
+    /// Fails if the image does not have power of two dimensions. The result
+    /// can be adjusted before encoding, e.g. with
+    /// [`BlpEncoding::with_compress_algorithm`].
+    pub fn encoding_for(self, image: &DynamicImage) -> Result<BlpEncoding, Error> {
+        let (width, height) = image.dimensions();
+        if !width.is_power_of_two() || !height.is_power_of_two() {
+            return Err(Error::NotPowerOfTwo(width, height));
+        }
+        Ok(BlpEncoding {
+            target: self.target_for(image),
+            // The most a BLP holds; smaller images get a shorter chain
+            mipmap_count: 16,
+            alpha_type: None,
+        })
+    }
+
+    /// Encode `image` for the client
+    pub fn encode(self, image: DynamicImage, mipmap_filter: FilterType) -> Result<BlpImage, Error> {
+        self.encoding_for(&image)?.encode(image, mipmap_filter)
+    }
+}
+
+impl fmt::Display for BlpTargetPreset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlpTargetPreset::WarcraftIII => write!(f, "Warcraft III"),
+            BlpTargetPreset::Classic => write!(f, "World of Warcraft 1.x"),
+            BlpTargetPreset::Tbc => write!(f, "The Burning Crusade"),
+            BlpTargetPreset::Wotlk => write!(f, "Wrath of the Lich King"),
+            BlpTargetPreset::Cataclysm => write!(f, "Cataclysm"),
+            BlpTargetPreset::Mop => write!(f, "Mists of Pandaria"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::image::{Rgba, RgbaImage};
+
+    fn image(alpha: impl Fn(u32) -> u8) -> DynamicImage {
+        DynamicImage::ImageRgba8(RgbaImage::from_fn(64, 32, |x, _| {
+            Rgba([x as u8 * 4, 0, 128, alpha(x)])
+        }))
+    }
+
+    #[test]
+    fn test_preset_targets() {
+        let opaque = image(|_| 255);
+        let binary = image(|x| if x < 32 { 0 } else { 255 });
+        let smooth = image(|x| x as u8 * 4);
+
+        assert!(matches!(
+            BlpTargetPreset::Wotlk.target_for(&opaque),
+            BlpTarget::Blp2(Blp2Format::Dxt1 {
+                has_alpha: false,
+                ..
+            })
+        ));
+        assert!(matches!(
+            BlpTargetPreset::Wotlk.target_for(&binary),
+            BlpTarget::Blp2(Blp2Format::Dxt1 {
+                has_alpha: true,
+                ..
+            })
+        ));
+        assert!(matches!(
+            BlpTargetPreset::Wotlk.target_for(&smooth),
+            BlpTarget::Blp2(Blp2Format::Dxt5 { .. })
+        ));
+        assert!(matches!(
+            BlpTargetPreset::Classic.target_for(&smooth),
+            BlpTarget::Blp2(Blp2Format::Dxt3 { .. })
+        ));
+        assert!(
+            BlpTargetPreset::WarcraftIII.target_for(&opaque)
+                == BlpTarget::Blp1(BlpOldFormat::Jpeg { has_alpha: false })
+        );
+        assert!(
+            BlpTargetPreset::WarcraftIII.target_for(&smooth)
+                == BlpTarget::Blp1(BlpOldFormat::Jpeg { has_alpha: true })
+        );
+    }
+
+    #[test]
+    fn test_preset_encode() {
+        let blp = BlpTargetPreset::Wotlk
+            .encode(image(|x| x as u8 * 4), FilterType::Triangle)
+            .unwrap();
+        assert_eq!(blp.header.version, BlpVersion::Blp2);
+        assert_eq!(blp.header.alpha_bits(), 8);
+        assert!(blp.header.has_mipmaps());
+        // 64x32 down to 2x1
+        assert_eq!(blp.image_count(), 6);
+
+        let blp = BlpTargetPreset::WarcraftIII
+            .encode(image(|_| 255), FilterType::Triangle)
+            .unwrap();
+        assert_eq!(blp.header.version, BlpVersion::Blp1);
+        assert_eq!(blp.header.alpha_bits(), 0);
+
+        let odd = DynamicImage::new_rgba8(100, 64);
+        assert!(matches!(
+            BlpTargetPreset::Tbc.encode(odd, FilterType::Triangle),
+            Err(Error::NotPowerOfTwo(100, 64))
+        ));
+    }
+}
@@ -0,0 +1,258 @@
+use super::error::Error;
+use super::{AlphaBits, Blp2Format, BlpOldFormat, BlpTarget, DxtAlgorithm, image_to_blp};
+use crate::types::*;
+use ::image::{DynamicImage, imageops::FilterType};
+
+/// Encoding choices of an existing BLP file
+///
+/// Re-encoding edited pixels with the encoding of the original keeps the
+/// characteristics the client relies on: content type, alpha depth and alpha
+/// type, DXT variant and the number of mipmaps. Fields can be changed before
+/// encoding to deliberately override one of them.
+#[derive(Clone, PartialEq, Eq)]
+pub struct BlpEncoding {
+    /// Version and pixel format
+    pub target: BlpTarget,
+    /// Number of stored images, including the full size one
+    pub mipmap_count: usize,
+    /// BLP2 alpha type written to the header
+    pub alpha_type: Option<AlphaType>,
+}
+
+impl BlpEncoding {
+    /// Encoding of a parsed BLP file
+    ///
+    /// DXT targets use the default compression algorithm, which is not
+    /// recorded in the file.
+    pub fn of(image: &BlpImage) -> Self {
+        let alpha_bits = image.header.alpha_bits();
+        let has_alpha = alpha_bits > 0;
+        let raw1_alpha = match alpha_bits {
+            0 => AlphaBits::NoAlpha,
+            1 => AlphaBits::Bit1,
+            4 => AlphaBits::Bit4,
+            _ => AlphaBits::Bit8,
+        };
+        let compress_algorithm = DxtAlgorithm::default();
+
+        let target = match (image.header.version, &image.content) {
+            (BlpVersion::Blp0 | BlpVersion::Blp1, content) => {
+                let format = match content {
+                    BlpContent::Jpeg(_) => BlpOldFormat::Jpeg { has_alpha },
+                    _ => BlpOldFormat::Raw1 {
+                        alpha_bits: raw1_alpha,
+                    },
+                };
+                if image.header.version == BlpVersion::Blp0 {
+                    BlpTarget::Blp0(format)
+                } else {
+                    BlpTarget::Blp1(format)
+                }
+            }
+            (BlpVersion::Blp2, content) => BlpTarget::Blp2(match content {
+                BlpContent::Jpeg(_) => Blp2Format::Jpeg { has_alpha },
+                BlpContent::Raw1(_) => Blp2Format::Raw1 {
+                    alpha_bits: raw1_alpha,
+                },
+                BlpContent::Raw3(_) => Blp2Format::Raw3,
+                BlpContent::Dxt1(_) => Blp2Format::Dxt1 {
+                    has_alpha,
+                    compress_algorithm,
+                },
+                BlpContent::Dxt3(_) => Blp2Format::Dxt3 {
+                    has_alpha,
+                    compress_algorithm,
+                },
+                BlpContent::Dxt5(_) => Blp2Format::Dxt5 {
+                    has_alpha,
+                    compress_algorithm,
+                },
+            }),
+        };
+
+        let mipmap_count = if image.header.has_mipmaps() {
+            image.image_count().max(1)
+        } else {
+            1
+        };
+
+        Self {
+            target,
+            mipmap_count,
+            alpha_type: image.header.alpha_type(),
+        }
+    }
+
+    /// Set the compression algorithm of DXT targets
+    pub fn with_compress_algorithm(mut self, algorithm: DxtAlgorithm) -> Self {
+        if let BlpTarget::Blp2(
+            Blp2Format::Dxt1 {
+                compress_algorithm, ..
+            }
+            | Blp2Format::Dxt3 {
+                compress_algorithm, ..
+            }
+            | Blp2Format::Dxt5 {
+                compress_algorithm, ..
+            },
+        ) = &mut self.target
+        {
+            *compress_algorithm = algorithm;
+        }
+        self
+    }
+
+    /// Encode an image with these choices
+    ///
+    /// Images of a different size than the original are accepted; they get
+    /// at most `mipmap_count` images, fewer if their mipmap chain is shorter.
+    pub fn encode(
+        &self,
+        image: DynamicImage,
+        mipmap_filter: FilterType,
+    ) -> Result<BlpImage, Error> {
+        let make_mipmaps = self.mipmap_count > 1;
+        let mut blp = image_to_blp(image, make_mipmaps, self.target.clone(), mipmap_filter)?;
+
+        if make_mipmaps {
+            truncate_mipmaps(&mut blp, self.mipmap_count);
+        }
+        if let (
+            BlpFlags::Blp2 {
+                alpha_type: encoded,
+                ..
+            },
+            Some(alpha_type),
+        ) = (&mut blp.header.flags, self.alpha_type)
+        {
+            *encoded = alpha_type;
+        }
+        Ok(blp)
+    }
+}
+
+/// Re-encode edited pixels with the encoding of the BLP file they came from
+///
+/// Shorthand for [`BlpEncoding::of`] followed by [`BlpEncoding::encode`].
+pub fn transcode(
+    source: &BlpImage,
+    image: DynamicImage,
+    mipmap_filter: FilterType,
+) -> Result<BlpImage, Error> {
+    BlpEncoding::of(source).encode(image, mipmap_filter)
+}
+
+/// Drop mipmaps beyond `count` and move the remaining ones accordingly
+fn truncate_mipmaps(blp: &mut BlpImage, count: usize) {
+    if blp.image_count() <= count {
+        return;
+    }
+    let version = blp.header.version;
+    let locator = match &mut blp.content {
+        BlpContent::Jpeg(content) => {
+            content.images.truncate(count);
+            content.mipmap_locator(version)
+        }
+        BlpContent::Raw1(content) => {
+            content.images.truncate(count);
+            content.mipmap_locator(version)
+        }
+        BlpContent::Raw3(content) => {
+            content.images.truncate(count);
+            content.mipmap_locator(version)
+        }
+        BlpContent::Dxt1(content) | BlpContent::Dxt3(content) | BlpContent::Dxt5(content) => {
+            content.images.truncate(count);
+            content.mipmap_locator(version)
+        }
+    };
+    // BLP0 keeps its mipmaps in external files
+    if blp.header.mipmap_locator != MipmapLocator::External {
+        blp.header.mipmap_locator = locator;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convert::blp_to_image;
+    use crate::encode::encode_blp;
+    use crate::parser::parse_blp;
+
+    fn gradient(size: u32) -> DynamicImage {
+        DynamicImage::ImageRgba8(::image::RgbaImage::from_fn(size, size, |x, y| {
+            ::image::Rgba([x as u8 * 4, y as u8 * 4, 128, (x + y) as u8])
+        }))
+    }
+
+    #[test]
+    fn test_transcode_keeps_encoding() {
+        let target = BlpTarget::Blp2(Blp2Format::Dxt5 {
+            has_alpha: true,
+            compress_algorithm: DxtAlgorithm::RangeFit,
+        });
+        let mut source = image_to_blp(gradient(64), true, target, FilterType::Nearest).unwrap();
+        // Only the first four levels
+        truncate_mipmaps(&mut source, 4);
+        let source = parse_blp(&encode_blp(&source).unwrap()).unwrap();
+
+        let edited = blp_to_image(&source, 0).unwrap().fliph();
+        let encoding = BlpEncoding::of(&source).with_compress_algorithm(DxtAlgorithm::RangeFit);
+        assert_eq!(encoding.mipmap_count, 4);
+        let result = encoding.encode(edited, FilterType::Nearest).unwrap();
+
+        assert_eq!(result.compression_type(), CompressionType::Dxt5);
+        assert_eq!(result.header.alpha_bits(), 8);
+        assert_eq!(result.header.alpha_type(), Some(AlphaType::Enhanced));
+        assert_eq!(result.image_count(), 4);
+
+        // The truncated mipmaps are located correctly when written
+        let reparsed = parse_blp(&encode_blp(&result).unwrap()).unwrap();
+        assert_eq!(reparsed.image_count(), 4);
+        assert_eq!(
+            blp_to_image(&reparsed, 3).unwrap().width(),
+            result.header.mipmap_size(3).0
+        );
+    }
+
+    #[test]
+    fn test_transcode_keeps_alpha_type() {
+        let target = BlpTarget::Blp2(Blp2Format::Raw1 {
+            alpha_bits: AlphaBits::Bit8,
+        });
+        let mut source = image_to_blp(gradient(8), true, target, FilterType::Nearest).unwrap();
+        // Palettized textures of the client use alpha type 8
+        if let BlpFlags::Blp2 { alpha_type, .. } = &mut source.header.flags {
+            *alpha_type = AlphaType::EightBit;
+        }
+
+        let result = transcode(&source, gradient(8).flipv(), FilterType::Nearest).unwrap();
+        assert_eq!(result.compression_type(), CompressionType::Raw1);
+        assert_eq!(result.header.alpha_type(), Some(AlphaType::EightBit));
+        assert_eq!(result.image_count(), source.image_count());
+    }
+
+    #[test]
+    fn test_transcode_without_mipmaps() {
+        let target = BlpTarget::Blp1(BlpOldFormat::Raw1 {
+            alpha_bits: AlphaBits::Bit4,
+        });
+        let source = image_to_blp(gradient(16), false, target, FilterType::Nearest).unwrap();
+
+        let encoding = BlpEncoding::of(&source);
+        assert_eq!(encoding.mipmap_count, 1);
+        assert!(encoding.target == source_target());
+
+        let result = transcode(&source, gradient(32), FilterType::Nearest).unwrap();
+        assert!(!result.header.has_mipmaps());
+        assert_eq!(result.image_count(), 1);
+        assert_eq!(result.header.alpha_bits(), 4);
+        assert_eq!(result.header.width, 32);
+
+        fn source_target() -> BlpTarget {
+            BlpTarget::Blp1(BlpOldFormat::Raw1 {
+                alpha_bits: AlphaBits::Bit4,
+            })
+        }
+    }
+}
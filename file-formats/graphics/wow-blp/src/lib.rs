@@ -54,6 +54,8 @@
 #![warn(missing_docs)]
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
+/// Consistency checks for parsed BLP files
+pub mod analysis;
 /// Conversion utilities to/from DynamicImage
 pub mod convert;
 /// Encoding BLP format into stream of bytes
@@ -120,7 +120,16 @@ pub fn parse_dxtn<'a>(
     read_image(0)?;
     if blp_header.has_mipmaps() {
         trace!("Mipmaps count: {}", blp_header.mipmaps_count());
-        for i in 1..(blp_header.mipmaps_count() + 1).min(16) {
+        for (i, &size) in sizes
+            .iter()
+            .enumerate()
+            .take((blp_header.mipmaps_count() + 1).min(16))
+            .skip(1)
+        {
+            if size == 0 {
+                trace!("Size of mipmap {i} is 0 bytes, I stop reading of images");
+                break;
+            }
             read_image(i)?;
         }
     }
@@ -6,6 +6,10 @@
 use super::interpolation::interpolate_with_blend;
 use super::state::{AnimationState, LcgRng};
 use super::types::{Lerp, Quat, ResolvedTrack, Vec3};
+use std::collections::HashMap;
+
+/// Longest alias or fallback chain followed before giving up
+const MAX_CHAIN: usize = 100;
 
 /// Animation sequence data (resolved from M2Sequence)
 #[derive(Debug, Clone)]
@@ -91,6 +95,10 @@ pub struct AnimationManager {
     blend_factor: f32,
     /// Random number generator for variation selection
     rng: LcgRng,
+    /// Index of the main variation for each animation ID (0xFFFF = none)
+    sequence_lookup: Vec<u16>,
+    /// Animation to play instead when a model lacks one
+    fallbacks: HashMap<u16, u16>,
 }
 
 impl AnimationManager {
@@ -107,27 +115,45 @@ impl AnimationManager {
     ) -> Self {
         let global_sequence_times = vec![0.0; global_sequence_durations.len()];
 
-        // Find "Stand" animation (ID 0) as default
-        let stand_index = sequences.iter().position(|s| s.id == 0);
-
-        let mut rng = LcgRng::default();
-        let mut current_animation = AnimationState::new(stand_index);
-
-        // Set initial repeat count
-        if let Some(idx) = stand_index {
-            current_animation.repeat_times = sequences[idx].calculate_repeats(&mut rng);
-        }
-
-        Self {
+        let mut manager = Self {
             global_sequence_durations,
             global_sequence_times,
             sequences,
             bones,
-            current_animation,
+            current_animation: AnimationState::none(),
             next_animation: AnimationState::none(),
             blend_factor: 1.0,
-            rng,
+            rng: LcgRng::default(),
+            sequence_lookup: Vec::new(),
+            fallbacks: HashMap::new(),
+        };
+
+        // Start with "Stand" (ID 0)
+        manager.set_animation_id(0);
+        manager
+    }
+
+    /// Use the model's sequence lookup to find animations by ID
+    ///
+    /// Entry `id` holds the index of that animation's main variation, or
+    /// 0xFFFF when the model lacks it. Without a lookup, or when an entry
+    /// does not name a sequence of that ID, sequences are searched in order.
+    pub fn with_sequence_lookup(mut self, lookup: Vec<u16>) -> Self {
+        self.sequence_lookup = lookup;
+        if self.current_animation.animation_index.is_some() {
+            self.set_animation_id(0);
         }
+        self
+    }
+
+    /// Animations to play instead of ones a model lacks
+    ///
+    /// Maps an animation ID to its fallback, as in the `Fallback` column of
+    /// `AnimationData.dbc`; fallbacks are followed until the model has one
+    /// of the animations. Run, for instance, falls back to Walk.
+    pub fn with_fallbacks(mut self, fallbacks: HashMap<u16, u16>) -> Self {
+        self.fallbacks = fallbacks;
+        self
     }
 
     /// Create an empty AnimationManager (no animations)
@@ -141,6 +167,8 @@ impl AnimationManager {
             next_animation: AnimationState::none(),
             blend_factor: 1.0,
             rng: LcgRng::default(),
+            sequence_lookup: Vec::new(),
+            fallbacks: HashMap::new(),
         }
     }
 
@@ -258,40 +286,97 @@ impl AnimationManager {
             self.sequences[next_index].calculate_repeats(&mut self.rng);
     }
 
-    /// Resolve animation alias chain
-    fn resolve_alias(&self, index: usize) -> usize {
+    /// Follow the alias chain from a sequence to the one holding its data
+    ///
+    /// Sequences flagged as aliases (0x40 without 0x20) have no keyframes of
+    /// their own and play the sequence at `alias_next`. Broken or cyclic
+    /// chains resolve to `index` itself.
+    pub fn resolve_alias(&self, index: usize) -> usize {
         let mut current = index;
-        let mut iterations = 0;
 
-        while iterations < 100 {
-            // Safety limit
-            if current >= self.sequences.len() {
+        for _ in 0..MAX_CHAIN {
+            let Some(seq) = self.sequences.get(current) else {
                 return index;
-            }
-
-            let seq = &self.sequences[current];
+            };
             if !seq.is_alias() {
                 return current;
             }
-
             current = seq.alias_next as usize;
-            iterations += 1;
         }
 
         index
     }
 
+    /// Index of the main variation of an animation the model has
+    fn main_variation(&self, id: u16) -> Option<usize> {
+        let from_lookup = self
+            .sequence_lookup
+            .get(id as usize)
+            .map(|&index| index as usize)
+            .filter(|&index| self.sequences.get(index).is_some_and(|s| s.id == id));
+
+        from_lookup.or_else(|| self.sequences.iter().position(|s| s.id == id))
+    }
+
+    /// Variations of the animation played for `id`, main variation first
+    ///
+    /// When the model lacks `id`, its fallbacks are tried in turn. The
+    /// variations are linked through `variation_next`; the indices returned
+    /// may still be aliases, see [`resolve_alias`](Self::resolve_alias).
+    pub fn variations(&self, id: u16) -> Vec<usize> {
+        let mut id = id;
+        let mut main = self.main_variation(id);
+        for _ in 0..MAX_CHAIN {
+            if main.is_some() {
+                break;
+            }
+            let Some(&fallback) = self.fallbacks.get(&id) else {
+                break;
+            };
+            id = fallback;
+            main = self.main_variation(id);
+        }
+
+        let mut variations = Vec::new();
+        let mut next = main;
+        while let Some(index) = next {
+            if variations.contains(&index) {
+                break;
+            }
+            variations.push(index);
+            next = usize::try_from(self.sequences[index].variation_next)
+                .ok()
+                .filter(|&n| self.sequences.get(n).is_some_and(|s| s.id == id));
+        }
+        variations
+    }
+
+    /// Index of the sequence with the keyframes for animation `id`
+    ///
+    /// Resolves fallbacks for animations the model lacks and aliases to the
+    /// main variation, the way the client does before playing it.
+    pub fn find_sequence(&self, id: u16) -> Option<usize> {
+        self.variations(id)
+            .first()
+            .map(|&main| self.resolve_alias(main))
+    }
+
     /// Set the current animation by ID
+    ///
+    /// Plays the sequence [`find_sequence`](Self::find_sequence) picks;
+    /// once it ends, the next variation is chosen by frequency.
     pub fn set_animation_id(&mut self, id: u16) {
-        let index = self.sequences.iter().position(|s| s.id == id);
+        let Some(&main) = self.variations(id).first() else {
+            return;
+        };
+        let index = self.resolve_alias(main);
 
-        if let Some(idx) = index {
-            self.current_animation = AnimationState::new(Some(idx));
-            self.current_animation.repeat_times =
-                self.sequences[idx].calculate_repeats(&mut self.rng);
-            self.next_animation = AnimationState::none();
-            self.blend_factor = 1.0;
-        }
+        self.current_animation = AnimationState::new(Some(index));
+        self.current_animation.main_variation_index = main;
+        self.current_animation.repeat_times =
+            self.sequences[index].calculate_repeats(&mut self.rng);
+        self.next_animation = AnimationState::none();
+        self.blend_factor = 1.0;
     }
 
     /// Set the current animation by index
@@ -441,19 +526,10 @@ impl AnimationManagerBuilder {
             .animations
             .iter()
             .map(|seq| {
-                // Duration calculation differs by version:
-                // - Classic: end_timestamp - start_timestamp
-                // - BC+: start_timestamp IS the duration
-                let duration = seq
-                    .end_timestamp
-                    .map(|end| end.saturating_sub(seq.start_timestamp))
-                    .unwrap_or(seq.start_timestamp);
-
-                // Replay range (Classic only, defaults for BC+)
-                let (replay_min, replay_max) = seq
-                    .replay
-                    .map(|r| (r.minimum as u32, r.maximum as u32))
-                    .unwrap_or((0, 0));
+                let duration = seq.duration();
+
+                let (replay_min, replay_max) =
+                    seq.replay.map(|r| (r.minimum, r.maximum)).unwrap_or((0, 0));
 
                 AnimSequence {
                     id: seq.animation_id,
@@ -499,11 +575,10 @@ impl AnimationManagerBuilder {
             });
         }
 
-        Ok(AnimationManager::new(
-            global_sequence_durations,
-            sequences,
-            bones,
-        ))
+        Ok(
+            AnimationManager::new(global_sequence_durations, sequences, bones)
+                .with_sequence_lookup(model.animation_lookup.clone()),
+        )
     }
 
     /// Resolve a Vec3 animation track from M2 data
@@ -740,6 +815,50 @@ mod tests {
         assert!((manager.current_time() - 0.0).abs() < 0.001);
     }
 
+    #[test]
+    fn test_find_sequence_resolves_alias() {
+        let mut alias = create_test_sequence(5, 0);
+        alias.flags = 0x40;
+        alias.alias_next = 2;
+        let mut target = create_test_sequence(5, 700);
+        target.sub_id = 1;
+        target.flags = 0x20;
+        let sequences = vec![create_test_sequence(0, 1000), alias, target];
+        let mut manager = AnimationManager::new(vec![], sequences, vec![]);
+
+        assert_eq!(manager.find_sequence(5), Some(2));
+        manager.set_animation_id(5);
+        assert_eq!(manager.current_animation_index(), Some(2));
+    }
+
+    #[test]
+    fn test_find_sequence_uses_fallbacks() {
+        let sequences = vec![create_test_sequence(0, 1000), create_test_sequence(4, 500)];
+        let manager = AnimationManager::new(vec![], sequences, vec![]);
+        assert_eq!(manager.find_sequence(5), None);
+
+        let manager = manager.with_fallbacks(HashMap::from([(13, 5), (5, 4)]));
+        assert_eq!(manager.find_sequence(5), Some(1));
+        assert_eq!(manager.find_sequence(13), Some(1));
+        assert_eq!(manager.find_sequence(6), None);
+    }
+
+    #[test]
+    fn test_sequence_lookup_and_variations() {
+        let mut first = create_test_sequence(4, 500);
+        first.variation_next = 2;
+        let mut second = create_test_sequence(4, 600);
+        second.sub_id = 1;
+        let sequences = vec![create_test_sequence(0, 1000), first, second];
+
+        // Entry 4 points at the main variation; a stale entry is ignored
+        let manager = AnimationManager::new(vec![], sequences, vec![])
+            .with_sequence_lookup(vec![2, 0xFFFF, 0xFFFF, 0xFFFF, 1]);
+        assert_eq!(manager.variations(4), vec![1, 2]);
+        assert_eq!(manager.find_sequence(0), Some(0));
+        assert_eq!(manager.current_animation_index(), Some(0));
+    }
+
     #[test]
     fn test_global_sequences() {
         let sequences = vec![create_test_sequence(0, 1000)];
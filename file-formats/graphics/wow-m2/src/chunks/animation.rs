@@ -48,28 +48,29 @@ bitflags::bitflags! {
     }
 }
 
-/// Animation value ranges
-#[derive(Debug, Clone, Copy, PartialEq)]
+/// Replay range of a sequence: how many times it repeats before the client
+/// picks the next one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct M2Range {
-    /// Minimum value
-    pub minimum: f32,
-    /// Maximum value
-    pub maximum: f32,
+    /// Minimum number of repetitions
+    pub minimum: u32,
+    /// Maximum number of repetitions
+    pub maximum: u32,
 }
 
 impl M2Range {
     /// Parse a range from a reader
     pub fn parse<R: Read>(reader: &mut R) -> Result<Self> {
-        let minimum = reader.read_f32_le()?;
-        let maximum = reader.read_f32_le()?;
+        let minimum = reader.read_u32_le()?;
+        let maximum = reader.read_u32_le()?;
 
         Ok(Self { minimum, maximum })
     }
 
     /// Write a range to a writer
     pub fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
-        writer.write_f32_le(self.minimum)?;
-        writer.write_f32_le(self.maximum)?;
+        writer.write_u32_le(self.minimum)?;
+        writer.write_u32_le(self.maximum)?;
 
         Ok(())
     }
@@ -171,16 +172,23 @@ impl<T: M2Parse> M2AnimationBlock<T> {
     }
 }
 
+/// First header version (WotLK) whose sequences store a duration instead of
+/// start and end timestamps
+const SEQUENCE_DURATION_VERSION: u32 = 264;
+
 /// Animation data for a model
+///
+/// Sequences are 68 bytes up to TBC and 64 bytes from WotLK on, where the
+/// start and end timestamps were replaced by a duration.
 #[derive(Debug, Clone)]
 pub struct M2Animation {
     /// Animation ID
     pub animation_id: u16,
     /// Sub-animation ID (variation index)
     pub sub_animation_id: u16,
-    /// Start timestamp (Classic) or Duration (BC+) in milliseconds
+    /// Start timestamp (pre-WotLK) or Duration (WotLK+) in milliseconds
     pub start_timestamp: u32,
-    /// End timestamp (Classic only)
+    /// End timestamp (pre-WotLK only)
     pub end_timestamp: Option<u32>,
     /// Movement speed
     pub movement_speed: f32,
@@ -190,93 +198,91 @@ pub struct M2Animation {
     pub frequency: i16,
     /// Padding/Realignment
     pub padding: u16,
-    /// Replay range (Classic only)
+    /// Replay range
     pub replay: Option<M2Range>,
-    /// Minimum extent (BC+ only)
+    /// Blend time in milliseconds (blend in and out as two `u16` from WoD on)
+    pub blend_time: u32,
+    /// Minimum extent
     pub minimum_extent: Option<[f32; 3]>,
-    /// Maximum extent (BC+ only)
+    /// Maximum extent
     pub maximum_extent: Option<[f32; 3]>,
-    /// Extent radius (BC+ only)
+    /// Extent radius
     pub extent_radius: Option<f32>,
-    /// Next animation ID (BC+ only)
+    /// Next animation ID
     pub next_animation: Option<i16>,
-    /// Aliasing (BC+ only)
+    /// Aliasing
     pub aliasing: Option<u16>,
 }
 
 impl M2Animation {
+    /// Size of one sequence record for a header version
+    pub fn size_for_version(version: u32) -> usize {
+        if version < SEQUENCE_DURATION_VERSION {
+            68
+        } else {
+            64
+        }
+    }
+
+    /// Duration of the sequence in milliseconds
+    pub fn duration(&self) -> u32 {
+        match self.end_timestamp {
+            Some(end) => end.saturating_sub(self.start_timestamp),
+            None => self.start_timestamp,
+        }
+    }
+
     /// Parse an animation from a reader based on the M2 version
     pub fn parse<R: Read>(reader: &mut R, version: u32) -> Result<Self> {
         let animation_id = reader.read_u16_le()?;
         let sub_animation_id = reader.read_u16_le()?;
 
-        // Version differences: Classic (256) vs BC+ (260+)
-        if version <= 256 {
-            // Vanilla format
-            let start_timestamp = reader.read_u32_le()?;
-            let end_timestamp = reader.read_u32_le()?;
-            let movement_speed = reader.read_f32_le()?;
-            let flags = reader.read_u32_le()?;
-            let frequency = reader.read_i16_le()?;
-            let padding = reader.read_u16_le()?;
-            let replay = M2Range::parse(reader)?;
-
-            Ok(Self {
-                animation_id,
-                sub_animation_id,
-                start_timestamp,
-                end_timestamp: Some(end_timestamp),
-                movement_speed,
-                flags,
-                frequency,
-                padding,
-                replay: Some(replay),
-                minimum_extent: None,
-                maximum_extent: None,
-                extent_radius: None,
-                next_animation: None,
-                aliasing: None,
-            })
+        // Pre-WotLK sequences are placed on one shared timeline
+        let start_timestamp = reader.read_u32_le()?;
+        let end_timestamp = if version < SEQUENCE_DURATION_VERSION {
+            Some(reader.read_u32_le()?)
         } else {
-            // BC+ format
-            let duration = reader.read_u32_le()?;
-            let movement_speed = reader.read_f32_le()?;
-            let flags = reader.read_u32_le()?;
-            let frequency = reader.read_i16_le()?;
-            let padding = reader.read_u16_le()?;
-
-            let mut minimum_extent = [0.0; 3];
-            let mut maximum_extent = [0.0; 3];
-
-            for item in &mut minimum_extent {
-                *item = reader.read_f32_le()?;
-            }
-
-            for item in &mut maximum_extent {
-                *item = reader.read_f32_le()?;
-            }
-
-            let extent_radius = reader.read_f32_le()?;
-            let next_animation = reader.read_i16_le()?;
-            let aliasing = reader.read_u16_le()?;
-
-            Ok(Self {
-                animation_id,
-                sub_animation_id,
-                start_timestamp: duration, // In BC+, this field is duration
-                end_timestamp: None,
-                movement_speed,
-                flags,
-                frequency,
-                padding,
-                replay: None,
-                minimum_extent: Some(minimum_extent),
-                maximum_extent: Some(maximum_extent),
-                extent_radius: Some(extent_radius),
-                next_animation: Some(next_animation),
-                aliasing: Some(aliasing),
-            })
+            None
+        };
+        let movement_speed = reader.read_f32_le()?;
+        let flags = reader.read_u32_le()?;
+        let frequency = reader.read_i16_le()?;
+        let padding = reader.read_u16_le()?;
+        let replay = M2Range::parse(reader)?;
+        let blend_time = reader.read_u32_le()?;
+
+        let mut minimum_extent = [0.0; 3];
+        let mut maximum_extent = [0.0; 3];
+
+        for item in &mut minimum_extent {
+            *item = reader.read_f32_le()?;
+        }
+
+        for item in &mut maximum_extent {
+            *item = reader.read_f32_le()?;
         }
+
+        let extent_radius = reader.read_f32_le()?;
+        let next_animation = reader.read_i16_le()?;
+        let aliasing = reader.read_u16_le()?;
+
+        Ok(Self {
+            animation_id,
+            sub_animation_id,
+            start_timestamp,
+            end_timestamp,
+            movement_speed,
+            flags,
+            frequency,
+            padding,
+            replay: Some(replay),
+            blend_time,
+            minimum_extent: Some(minimum_extent),
+            maximum_extent: Some(maximum_extent),
+            extent_radius: Some(extent_radius),
+            next_animation: Some(next_animation),
+            aliasing: Some(aliasing),
+        })
     }
 
     /// Write an animation to a writer
@@ -284,49 +290,40 @@ impl M2Animation {
         writer.write_u16_le(self.animation_id)?;
         writer.write_u16_le(self.sub_animation_id)?;
 
-        if version <= 256 {
-            // Vanilla format
+        if version < SEQUENCE_DURATION_VERSION {
             writer.write_u32_le(self.start_timestamp)?;
             writer.write_u32_le(self.end_timestamp.unwrap_or(self.start_timestamp + 1000))?;
-            writer.write_f32_le(self.movement_speed)?;
-            writer.write_u32_le(self.flags)?;
-            writer.write_i16_le(self.frequency)?;
-            writer.write_u16_le(self.padding)?;
-
-            if let Some(replay) = &self.replay {
-                replay.write(writer)?;
-            } else {
-                // Default replay range
-                M2Range {
-                    minimum: 0.0,
-                    maximum: 1.0,
-                }
-                .write(writer)?;
-            }
         } else {
-            // BC+ format
-            writer.write_u32_le(self.start_timestamp)?; // This is duration in BC+
-            writer.write_f32_le(self.movement_speed)?;
-            writer.write_u32_le(self.flags)?;
-            writer.write_i16_le(self.frequency)?;
-            writer.write_u16_le(self.padding)?;
-
-            let minimum_extent = self.minimum_extent.unwrap_or([0.0, 0.0, 0.0]);
-            let maximum_extent = self.maximum_extent.unwrap_or([0.0, 0.0, 0.0]);
-
-            for &value in &minimum_extent {
-                writer.write_f32_le(value)?;
-            }
-
-            for &value in &maximum_extent {
-                writer.write_f32_le(value)?;
-            }
-
-            writer.write_f32_le(self.extent_radius.unwrap_or(0.0))?;
-            writer.write_i16_le(self.next_animation.unwrap_or(-1))?;
-            writer.write_u16_le(self.aliasing.unwrap_or(0))?;
+            writer.write_u32_le(self.duration())?;
+        }
+        writer.write_f32_le(self.movement_speed)?;
+        writer.write_u32_le(self.flags)?;
+        writer.write_i16_le(self.frequency)?;
+        writer.write_u16_le(self.padding)?;
+
+        self.replay
+            .unwrap_or(M2Range {
+                minimum: 0,
+                maximum: 0,
+            })
+            .write(writer)?;
+        writer.write_u32_le(self.blend_time)?;
+
+        let minimum_extent = self.minimum_extent.unwrap_or([0.0, 0.0, 0.0]);
+        let maximum_extent = self.maximum_extent.unwrap_or([0.0, 0.0, 0.0]);
+
+        for &value in &minimum_extent {
+            writer.write_f32_le(value)?;
         }
 
+        for &value in &maximum_extent {
+            writer.write_f32_le(value)?;
+        }
+
+        writer.write_f32_le(self.extent_radius.unwrap_or(0.0))?;
+        writer.write_i16_le(self.next_animation.unwrap_or(-1))?;
+        writer.write_u16_le(self.aliasing.unwrap_or(0))?;
+
         Ok(())
     }
 
@@ -335,3 +332,64 @@ impl M2Animation {
         self.clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn sequence() -> M2Animation {
+        M2Animation {
+            animation_id: 4,
+            sub_animation_id: 1,
+            start_timestamp: 2000,
+            end_timestamp: Some(3333),
+            movement_speed: 2.5,
+            flags: 0x20,
+            frequency: 100,
+            padding: 0,
+            replay: Some(M2Range {
+                minimum: 1,
+                maximum: 3,
+            }),
+            blend_time: 150,
+            minimum_extent: Some([-1.0, -1.0, 0.0]),
+            maximum_extent: Some([1.0, 1.0, 2.0]),
+            extent_radius: Some(2.0),
+            next_animation: Some(-1),
+            aliasing: Some(0),
+        }
+    }
+
+    #[test]
+    fn test_sequence_sizes() {
+        for (version, size) in [(256, 68), (263, 68), (264, 64), (274, 64)] {
+            let mut data = Vec::new();
+            sequence().write(&mut data, version).unwrap();
+            assert_eq!(data.len(), size, "version {version}");
+            assert_eq!(M2Animation::size_for_version(version), size);
+        }
+    }
+
+    #[test]
+    fn test_sequence_roundtrip() {
+        let mut data = Vec::new();
+        sequence().write(&mut data, 256).unwrap();
+        // A second record must start right after the first
+        sequence().write(&mut data, 256).unwrap();
+        let mut cursor = Cursor::new(&data);
+        let _ = M2Animation::parse(&mut cursor, 256).unwrap();
+        let parsed = M2Animation::parse(&mut cursor, 256).unwrap();
+        assert_eq!(parsed.duration(), 1333);
+        assert_eq!(parsed.replay.unwrap().maximum, 3);
+        assert_eq!(parsed.blend_time, 150);
+        assert_eq!(parsed.extent_radius, Some(2.0));
+
+        let mut data = Vec::new();
+        sequence().write(&mut data, 264).unwrap();
+        let parsed = M2Animation::parse(&mut Cursor::new(&data), 264).unwrap();
+        assert_eq!(parsed.start_timestamp, 1333);
+        assert_eq!(parsed.end_timestamp, None);
+        assert_eq!(parsed.next_animation, Some(-1));
+    }
+}
@@ -750,6 +750,16 @@ impl M2ParticleEmitter {
         Ok(())
     }
 
+    /// Index selecting the emitter's colors
+    ///
+    /// In the file this is the `u16` after the emitter type, which this
+    /// parser reads as `particle_type` and `head_or_tail`. Values 11 to 13
+    /// take the colors from sets 0 to 2 of the display's ParticleColor.dbc
+    /// row instead of the color track.
+    pub fn particle_color_index(&self) -> u16 {
+        u16::from_le_bytes([self.particle_type, self.head_or_tail])
+    }
+
     /// Convert this particle emitter to a different version
     pub fn convert(&self, target_version: M2Version) -> Self {
         let mut new_emitter = self.clone();
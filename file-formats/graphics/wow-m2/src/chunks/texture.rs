@@ -6,38 +6,51 @@ use crate::error::Result;
 use crate::version::M2Version;
 
 /// Texture type enum as defined in the M2 format
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+///
+/// Every type except [`Hardcoded`](Self::Hardcoded) is a replaceable slot:
+/// the filename stored in the model is empty and the client fills it in at
+/// runtime from character customization, item or creature display data. See
+/// [`ReplaceableTextures`](crate::ReplaceableTextures) for resolving them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum M2TextureType {
-    /// Regular texture
+    /// Texture given by the filename in the model
     Hardcoded = 0,
-    /// Body + clothes
+    /// Character body skin, composited with face, underwear and armor
     Body = 1,
-    /// Item, capes
+    /// Item or object skin, such as capes
     Item = 2,
-    /// Weapon, armor (armorless)
-    WeaponArmorBasic = 3,
     /// Weapon blade
-    WeaponBlade = 4,
+    WeaponBlade = 3,
     /// Weapon handle
-    WeaponHandle = 5,
-    /// Environment
-    Environment = 6,
-    /// Hair, beard
-    Hair = 7,
-    /// Skin extra (accessories)
+    WeaponHandle = 4,
+    /// Environment (obsolete)
+    Environment = 5,
+    /// Character hair
+    Hair = 6,
+    /// Character facial hair (obsolete)
+    FacialHair = 7,
+    /// Skin extra, such as fur or tails
     SkinExtra = 8,
     /// Inventory art
     UiSkin = 9,
-    /// Tauren mane
+    /// Tauren mane (obsolete)
     TaurenMane = 10,
-    /// Monster skin 1
+    /// Monster skin 1, from the first creature texture variation
     Monster1 = 11,
-    /// Monster skin 2
+    /// Monster skin 2, from the second creature texture variation
     Monster2 = 12,
-    /// Monster skin 3
+    /// Monster skin 3, from the third creature texture variation
     Monster3 = 13,
     /// Item icon
     ItemIcon = 14,
+    /// Guild tabard background color
+    GuildBackgroundColor = 15,
+    /// Guild tabard emblem color
+    GuildEmblemColor = 16,
+    /// Guild tabard border color
+    GuildBorderColor = 17,
+    /// Guild tabard emblem
+    GuildEmblem = 18,
     /// Unknown
     Unknown = 255,
 }
@@ -49,11 +62,11 @@ impl M2TextureType {
             0 => Some(Self::Hardcoded),
             1 => Some(Self::Body),
             2 => Some(Self::Item),
-            3 => Some(Self::WeaponArmorBasic),
-            4 => Some(Self::WeaponBlade),
-            5 => Some(Self::WeaponHandle),
-            6 => Some(Self::Environment),
-            7 => Some(Self::Hair),
+            3 => Some(Self::WeaponBlade),
+            4 => Some(Self::WeaponHandle),
+            5 => Some(Self::Environment),
+            6 => Some(Self::Hair),
+            7 => Some(Self::FacialHair),
             8 => Some(Self::SkinExtra),
             9 => Some(Self::UiSkin),
             10 => Some(Self::TaurenMane),
@@ -61,9 +74,50 @@ impl M2TextureType {
             12 => Some(Self::Monster2),
             13 => Some(Self::Monster3),
             14 => Some(Self::ItemIcon),
+            15 => Some(Self::GuildBackgroundColor),
+            16 => Some(Self::GuildEmblemColor),
+            17 => Some(Self::GuildBorderColor),
+            18 => Some(Self::GuildEmblem),
             _ => None,
         }
     }
+
+    /// Whether the texture is chosen at runtime instead of named in the model
+    pub fn is_replaceable(self) -> bool {
+        self != Self::Hardcoded
+    }
+
+    /// Human-readable name of the texture type
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Hardcoded => "Hardcoded",
+            Self::Body => "Body + Clothes",
+            Self::Item => "Item / Cape",
+            Self::WeaponBlade => "Weapon Blade",
+            Self::WeaponHandle => "Weapon Handle",
+            Self::Environment => "Environment",
+            Self::Hair => "Hair",
+            Self::FacialHair => "Facial Hair",
+            Self::SkinExtra => "Skin Extra",
+            Self::UiSkin => "UI Skin",
+            Self::TaurenMane => "Tauren Mane",
+            Self::Monster1 => "Monster Skin 1",
+            Self::Monster2 => "Monster Skin 2",
+            Self::Monster3 => "Monster Skin 3",
+            Self::ItemIcon => "Item Icon",
+            Self::GuildBackgroundColor => "Guild Background Color",
+            Self::GuildEmblemColor => "Guild Emblem Color",
+            Self::GuildBorderColor => "Guild Border Color",
+            Self::GuildEmblem => "Guild Emblem",
+            Self::Unknown => "Unknown",
+        }
+    }
+}
+
+impl std::fmt::Display for M2TextureType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
 }
 
 bitflags::bitflags! {
@@ -0,0 +1,765 @@
+//! Geometry cleanup for M2 models
+//!
+//! Models converted from other tools often carry duplicated vertices, zero
+//! area triangles and bones nothing is skinned to. Besides wasting memory,
+//! they push the vertex and bone counts of submeshes past what the client can
+//! draw in one batch. [`M2Model::cleanup_mesh`] removes them from a model and
+//! its skin profiles before the model is exported or saved.
+//!
+//! Every skin profile of the model has to be passed: vertices are only kept
+//! if one of the given profiles uses them. The raw embedded skin profiles of
+//! pre-WotLK models (`raw_data.embedded_skins`) are not rewritten.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use crate::chunks::vertex::M2Vertex;
+use crate::model::M2Model;
+use crate::skin::{SkinFile, SkinSubmesh};
+
+/// Weld tolerance of [`CleanupOptions::default`]
+pub const DEFAULT_WELD_TOLERANCE: f32 = 0.0001;
+
+/// Passes run by [`M2Model::cleanup_mesh`]
+#[derive(Debug, Clone)]
+pub struct CleanupOptions {
+    /// Merge vertices closer than this distance whose bones, normals and
+    /// texture coordinates match; `None` disables welding
+    pub weld_tolerance: Option<f32>,
+    /// Largest difference of normal and texture coordinate components
+    /// between welded vertices
+    pub attribute_tolerance: f32,
+    /// Drop triangles with a repeated corner or an area below the square of
+    /// the weld tolerance
+    pub remove_degenerate_triangles: bool,
+    /// Drop vertices no triangle uses
+    pub remove_unused_vertices: bool,
+    /// Drop bones nothing is skinned or attached to, and rebuild the bone
+    /// palette of every submesh from the bones its vertices use
+    pub remove_unused_bones: bool,
+}
+
+impl Default for CleanupOptions {
+    fn default() -> Self {
+        Self {
+            weld_tolerance: Some(DEFAULT_WELD_TOLERANCE),
+            attribute_tolerance: 0.001,
+            remove_degenerate_triangles: true,
+            remove_unused_vertices: true,
+            remove_unused_bones: true,
+        }
+    }
+}
+
+/// What [`M2Model::cleanup_mesh`] removed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CleanupReport {
+    /// Vertices merged into an identical one
+    pub welded_vertices: usize,
+    /// Triangles dropped from the skin profiles
+    pub degenerate_triangles: usize,
+    /// Vertices dropped from the model
+    pub removed_vertices: usize,
+    /// Bones dropped from the model
+    pub removed_bones: usize,
+}
+
+impl CleanupReport {
+    /// Whether the model was left unchanged
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl M2Model {
+    /// Clean up the geometry of the model and its skin profiles
+    ///
+    /// Submeshes and batches keep their indices, so geosets and texture
+    /// units still refer to the same geometry; submeshes may end up empty.
+    pub fn cleanup_mesh(
+        &mut self,
+        skins: &mut [SkinFile],
+        options: &CleanupOptions,
+    ) -> CleanupReport {
+        let mut report = CleanupReport::default();
+
+        let weld = match options.weld_tolerance {
+            Some(tolerance) if tolerance > 0.0 => {
+                weld_vertices(&self.vertices, tolerance, options.attribute_tolerance)
+            }
+            _ => (0..self.vertices.len()).collect(),
+        };
+        report.welded_vertices = weld.iter().enumerate().filter(|(i, w)| i != *w).count();
+
+        let area_tolerance = options
+            .weld_tolerance
+            .unwrap_or(DEFAULT_WELD_TOLERANCE)
+            .powi(2);
+        for skin in skins.iter_mut() {
+            report.degenerate_triangles +=
+                rebuild_skin(skin, &self.vertices, &weld, options, area_tolerance);
+        }
+
+        if options.remove_unused_vertices && !skins.is_empty() {
+            report.removed_vertices = self.remove_unused_vertices(skins);
+        }
+        if options.remove_unused_bones && !self.bones.is_empty() {
+            report.removed_bones = self.remove_unused_bones(skins.is_empty());
+            if !skins.is_empty() {
+                self.rebuild_bone_palettes(skins);
+            }
+        }
+        report
+    }
+
+    /// Drop vertices none of the skins reference, returning how many
+    fn remove_unused_vertices(&mut self, skins: &mut [SkinFile]) -> usize {
+        let mut used = vec![false; self.vertices.len()];
+        for skin in skins.iter_mut() {
+            for &index in skin_parts(skin).lookup.iter() {
+                if let Some(used) = used.get_mut(index as usize) {
+                    *used = true;
+                }
+            }
+        }
+
+        let mut remap = vec![0u16; self.vertices.len()];
+        let mut next = 0u16;
+        for (index, &used) in used.iter().enumerate() {
+            if used {
+                remap[index] = next;
+                next += 1;
+            }
+        }
+        let removed = self.vertices.len() - next as usize;
+        if removed == 0 {
+            return 0;
+        }
+
+        let mut index = 0;
+        self.vertices.retain(|_| {
+            index += 1;
+            used[index - 1]
+        });
+        for skin in skins.iter_mut() {
+            for lookup in skin_parts(skin).lookup.iter_mut() {
+                if let Some(&new) = remap.get(*lookup as usize) {
+                    *lookup = new;
+                }
+            }
+        }
+        removed
+    }
+
+    /// Drop bones nothing references, returning how many
+    ///
+    /// Without skins, the bone lookup table counts as a reference because
+    /// the submesh palettes cannot be rebuilt.
+    fn remove_unused_bones(&mut self, keep_lookup: bool) -> usize {
+        let count = self.bones.len();
+        let mut used = vec![false; count];
+        let mut mark = |index: i64| {
+            if (0..count as i64).contains(&index) {
+                used[index as usize] = true;
+            }
+        };
+
+        // The root keeps vertices without weights in place
+        mark(0);
+        for vertex in &self.vertices {
+            for (&bone, &weight) in vertex.bone_indices.iter().zip(&vertex.bone_weights) {
+                if weight > 0 {
+                    mark(bone.into());
+                }
+            }
+        }
+        self.attachments
+            .iter()
+            .for_each(|a| mark(a.bone_index.into()));
+        self.events.iter().for_each(|e| mark(e.bone_index.into()));
+        self.lights.iter().for_each(|l| mark(l.bone_index.into()));
+        self.particle_emitters
+            .iter()
+            .for_each(|p| mark(p.bone_index.into()));
+        self.ribbon_emitters
+            .iter()
+            .for_each(|r| mark(r.bone_index.into()));
+        self.key_bone_lookup.iter().for_each(|&b| mark(b.into()));
+        if keep_lookup {
+            self.raw_data
+                .bone_lookup_table
+                .iter()
+                .for_each(|&b| mark(b.into()));
+        }
+
+        // Parents of used bones are needed for their transforms
+        for index in 0..count {
+            if !used[index] {
+                continue;
+            }
+            let mut parent = self.bones[index].parent_bone;
+            let mut steps = 0;
+            while parent >= 0 && (parent as usize) < count && steps < count {
+                used[parent as usize] = true;
+                parent = self.bones[parent as usize].parent_bone;
+                steps += 1;
+            }
+        }
+
+        let mut remap = vec![None; count];
+        let mut next = 0u16;
+        for (index, &used) in used.iter().enumerate() {
+            if used {
+                remap[index] = Some(next);
+                next += 1;
+            }
+        }
+        let removed = count - next as usize;
+        if removed == 0 {
+            return 0;
+        }
+        let new_index = |index: i64| -> Option<u16> {
+            usize::try_from(index)
+                .ok()
+                .and_then(|index| remap.get(index).copied().flatten())
+        };
+
+        let mut index = 0;
+        self.bones.retain(|_| {
+            index += 1;
+            used[index - 1]
+        });
+        for bone in &mut self.bones {
+            bone.parent_bone = new_index(bone.parent_bone.into()).map_or(-1, |p| p as i16);
+        }
+        self.raw_data
+            .bone_animation_data
+            .retain(|track| used.get(track.bone_index).copied().unwrap_or(false));
+        for track in &mut self.raw_data.bone_animation_data {
+            track.bone_index = new_index(track.bone_index as i64).unwrap_or(0) as usize;
+        }
+
+        for vertex in &mut self.vertices {
+            for (bone, &weight) in vertex.bone_indices.iter_mut().zip(&vertex.bone_weights) {
+                *bone = if weight > 0 {
+                    new_index((*bone).into()).unwrap_or(0) as u8
+                } else {
+                    0
+                };
+            }
+        }
+        for attachment in &mut self.attachments {
+            if let Some(bone) = new_index(attachment.bone_index.into()) {
+                attachment.bone_index = bone.into();
+            }
+        }
+        for event in &mut self.events {
+            if let Some(bone) = new_index(event.bone_index.into()) {
+                event.bone_index = bone as i16;
+            }
+        }
+        for light in &mut self.lights {
+            if let Some(bone) = new_index(light.bone_index.into()) {
+                light.bone_index = bone;
+            }
+        }
+        for emitter in &mut self.particle_emitters {
+            if let Some(bone) = new_index(emitter.bone_index.into()) {
+                emitter.bone_index = bone;
+            }
+        }
+        for emitter in &mut self.ribbon_emitters {
+            if let Some(bone) = new_index(emitter.bone_index.into()) {
+                emitter.bone_index = bone.into();
+            }
+        }
+        for key_bone in &mut self.key_bone_lookup {
+            if let Some(bone) = new_index((*key_bone).into()) {
+                *key_bone = bone;
+            }
+        }
+        for lookup in &mut self.raw_data.bone_lookup_table {
+            if let Some(bone) = new_index((*lookup).into()) {
+                *lookup = bone;
+            }
+        }
+        removed
+    }
+
+    /// Give every submesh a palette of exactly the bones its vertices use
+    fn rebuild_bone_palettes(&mut self, skins: &mut [SkinFile]) {
+        let mut table: Vec<u16> = Vec::new();
+        let mut palettes: HashMap<Vec<u16>, usize> = HashMap::new();
+
+        for skin in skins.iter_mut() {
+            let parts = skin_parts(skin);
+            parts.properties.resize(parts.lookup.len() * 4, 0);
+
+            for submesh in parts.submeshes.iter_mut() {
+                let start = (submesh.vertex_start as usize).min(parts.lookup.len());
+                let end = (start + submesh.vertex_count as usize).min(parts.lookup.len());
+                let vertices: Vec<Option<&M2Vertex>> = parts.lookup[start..end]
+                    .iter()
+                    .map(|&v| self.vertices.get(v as usize))
+                    .collect();
+
+                let mut bones = BTreeSet::new();
+                let mut influence = 1;
+                for vertex in vertices.iter().flatten() {
+                    let weighted = weighted_bones(vertex);
+                    influence = influence.max(weighted.clone().count());
+                    bones.extend(weighted);
+                }
+                if bones.is_empty() {
+                    bones.insert(0);
+                }
+                let palette: Vec<u16> = bones.into_iter().collect();
+
+                for (offset, vertex) in vertices.iter().enumerate() {
+                    let properties = &mut parts.properties[(start + offset) * 4..][..4];
+                    properties.fill(0);
+                    let Some(vertex) = vertex else { continue };
+                    let slots = vertex.bone_indices.iter().zip(&vertex.bone_weights);
+                    for (property, (&bone, &weight)) in properties.iter_mut().zip(slots) {
+                        if weight > 0 {
+                            let position = palette.binary_search(&u16::from(bone)).unwrap_or(0);
+                            *property = u8::try_from(position).unwrap_or(0);
+                        }
+                    }
+                }
+
+                let bone_start = *palettes.entry(palette.clone()).or_insert_with(|| {
+                    table.extend_from_slice(&palette);
+                    table.len() - palette.len()
+                });
+                submesh.bone_start = bone_start as u16;
+                submesh.bone_count = palette.len() as u16;
+                submesh.bone_influence = influence as u16;
+            }
+        }
+        self.raw_data.bone_lookup_table = table;
+    }
+}
+
+/// Bones a vertex has a weight for, with their weights
+fn influences(vertex: &M2Vertex) -> impl Iterator<Item = (u8, u8)> + Clone + '_ {
+    vertex
+        .bone_indices
+        .iter()
+        .zip(&vertex.bone_weights)
+        .filter(|(_, weight)| **weight > 0)
+        .map(|(&bone, &weight)| (bone, weight))
+}
+
+/// Bones a vertex has a weight for
+fn weighted_bones(vertex: &M2Vertex) -> impl Iterator<Item = u16> + Clone + '_ {
+    influences(vertex).map(|(bone, _)| u16::from(bone))
+}
+
+/// Mutable view of the fields of either skin format
+struct SkinParts<'a> {
+    lookup: &'a mut Vec<u16>,
+    triangles: &'a mut Vec<u16>,
+    properties: &'a mut Vec<u8>,
+    submeshes: &'a mut Vec<SkinSubmesh>,
+}
+
+fn skin_parts(skin: &mut SkinFile) -> SkinParts<'_> {
+    match skin {
+        SkinFile::New(skin) => SkinParts {
+            lookup: &mut skin.indices,
+            triangles: &mut skin.triangles,
+            properties: &mut skin.bone_indices,
+            submeshes: &mut skin.submeshes,
+        },
+        SkinFile::Old(skin) => SkinParts {
+            lookup: &mut skin.indices,
+            triangles: &mut skin.triangles,
+            properties: &mut skin.bone_indices,
+            submeshes: &mut skin.submeshes,
+        },
+    }
+}
+
+/// Map every vertex to the first vertex it can be merged with
+fn weld_vertices(vertices: &[M2Vertex], tolerance: f32, attribute_tolerance: f32) -> Vec<usize> {
+    let cell = |v: &M2Vertex| {
+        [v.position.x, v.position.y, v.position.z].map(|c| (c / tolerance).floor() as i64)
+    };
+    let mut grid: HashMap<[i64; 3], Vec<usize>> = HashMap::new();
+    let mut weld = Vec::with_capacity(vertices.len());
+
+    for (index, vertex) in vertices.iter().enumerate() {
+        let [x, y, z] = cell(vertex);
+        let mut target = index;
+        'search: for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    let Some(candidates) = grid.get(&[x + dx, y + dy, z + dz]) else {
+                        continue;
+                    };
+                    if let Some(&other) = candidates.iter().find(|&&other| {
+                        can_weld(&vertices[other], vertex, tolerance, attribute_tolerance)
+                    }) {
+                        target = other;
+                        break 'search;
+                    }
+                }
+            }
+        }
+        if target == index {
+            grid.entry([x, y, z]).or_default().push(index);
+        }
+        weld.push(target);
+    }
+    weld
+}
+
+fn can_weld(a: &M2Vertex, b: &M2Vertex, tolerance: f32, attribute_tolerance: f32) -> bool {
+    let close = |a: f32, b: f32, tolerance: f32| (a - b).abs() <= tolerance;
+    let distance_squared = (a.position.x - b.position.x).powi(2)
+        + (a.position.y - b.position.y).powi(2)
+        + (a.position.z - b.position.z).powi(2);
+    let same_uv2 = match (&a.tex_coords2, &b.tex_coords2) {
+        (Some(a), Some(b)) => {
+            close(a.x, b.x, attribute_tolerance) && close(a.y, b.y, attribute_tolerance)
+        }
+        (None, None) => true,
+        _ => false,
+    };
+
+    distance_squared <= tolerance * tolerance
+        && influences(a).eq(influences(b))
+        && close(a.normal.x, b.normal.x, attribute_tolerance)
+        && close(a.normal.y, b.normal.y, attribute_tolerance)
+        && close(a.normal.z, b.normal.z, attribute_tolerance)
+        && close(a.tex_coords.x, b.tex_coords.x, attribute_tolerance)
+        && close(a.tex_coords.y, b.tex_coords.y, attribute_tolerance)
+        && same_uv2
+}
+
+/// Rewrite the lookup, triangles and submeshes of a skin, returning the
+/// number of dropped triangles
+fn rebuild_skin(
+    skin: &mut SkinFile,
+    vertices: &[M2Vertex],
+    weld: &[usize],
+    options: &CleanupOptions,
+    area_tolerance: f32,
+) -> usize {
+    let SkinParts {
+        lookup,
+        triangles,
+        properties,
+        submeshes,
+    } = skin_parts(skin);
+    let has_properties = properties.len() == lookup.len() * 4;
+    let source = SkinSource {
+        lookup,
+        triangles,
+        properties: has_properties.then_some(properties.as_slice()),
+        vertices,
+        weld,
+        options,
+        area_tolerance,
+    };
+    let mut rebuilt = RebuiltSkin::default();
+    // Submeshes drawing the same ranges (e.g. for different geosets) keep
+    // sharing them
+    let mut done: HashMap<[usize; 4], [usize; 4]> = HashMap::new();
+
+    for submesh in submeshes.iter_mut() {
+        let triangle_start = submesh.triangle_start as usize + ((submesh.level as usize) << 16);
+        let key = [
+            submesh.vertex_start as usize,
+            submesh.vertex_count as usize,
+            triangle_start,
+            submesh.triangle_count as usize,
+        ];
+        let [vertex_start, vertex_count, triangle_start, triangle_count] = *done
+            .entry(key)
+            .or_insert_with(|| rebuilt.add_submesh(key, &source));
+
+        submesh.vertex_start = vertex_start as u16;
+        submesh.vertex_count = vertex_count as u16;
+        submesh.triangle_start = (triangle_start & 0xFFFF) as u16;
+        submesh.level = (triangle_start >> 16) as u16;
+        submesh.triangle_count = triangle_count as u16;
+    }
+
+    *lookup = rebuilt.lookup;
+    *triangles = rebuilt.triangles;
+    if has_properties {
+        *properties = rebuilt.properties;
+    }
+    rebuilt.dropped
+}
+
+/// Original skin data and the settings of a cleanup
+struct SkinSource<'a> {
+    lookup: &'a [u16],
+    triangles: &'a [u16],
+    /// Bone palette indices, if there are four for every lookup entry
+    properties: Option<&'a [u8]>,
+    vertices: &'a [M2Vertex],
+    weld: &'a [usize],
+    options: &'a CleanupOptions,
+    area_tolerance: f32,
+}
+
+impl SkinSource<'_> {
+    /// Welded model vertex of a lookup entry
+    fn global(&self, local: u16) -> Option<u16> {
+        let global = *self.lookup.get(local as usize)?;
+        Some(self.weld.get(global as usize).map_or(global, |&w| w as u16))
+    }
+}
+
+#[derive(Default)]
+struct RebuiltSkin {
+    lookup: Vec<u16>,
+    triangles: Vec<u16>,
+    properties: Vec<u8>,
+    dropped: usize,
+}
+
+impl RebuiltSkin {
+    /// Append the cleaned ranges of one submesh, returning its new vertex
+    /// start and count and triangle start and count
+    fn add_submesh(
+        &mut self,
+        [vertex_start, vertex_count, triangle_start, triangle_count]: [usize; 4],
+        source: &SkinSource<'_>,
+    ) -> [usize; 4] {
+        let options = source.options;
+
+        // Triangles as (model vertex, original lookup entry) corners
+        let triangle_end = (triangle_start + triangle_count).min(source.triangles.len());
+        let mut kept = Vec::new();
+        for triangle in source
+            .triangles
+            .get(triangle_start..triangle_end)
+            .unwrap_or_default()
+            .chunks(3)
+        {
+            let corners: Option<Vec<(u16, u16)>> = triangle
+                .iter()
+                .map(|&local| source.global(local).map(|global| (global, local)))
+                .collect();
+            match corners {
+                Some(corners)
+                    if corners.len() == 3
+                        && !(options.remove_degenerate_triangles
+                            && is_degenerate(&corners, source.vertices, source.area_tolerance)) =>
+                {
+                    kept.push([corners[0], corners[1], corners[2]])
+                }
+                _ => self.dropped += 1,
+            }
+        }
+
+        let used: HashSet<u16> = kept.iter().flatten().map(|&(global, _)| global).collect();
+        let base = self.lookup.len();
+        let mut locals: HashMap<u16, u16> = HashMap::new();
+        let vertex_end = (vertex_start + vertex_count).min(source.lookup.len());
+
+        for original in vertex_start.min(vertex_end)..vertex_end {
+            let Some(global) = source.global(original as u16) else {
+                continue;
+            };
+            let wanted = !options.remove_unused_vertices || used.contains(&global);
+            if wanted && !locals.contains_key(&global) {
+                let local = self.push(source, global, original as u16);
+                locals.insert(global, local);
+            } else if !options.remove_unused_vertices {
+                // Keep the slot so the remaining entries stay in place
+                self.push(source, global, original as u16);
+            }
+        }
+
+        let first_triangle = self.triangles.len();
+        for corners in kept {
+            for (global, original) in corners {
+                let local = match locals.get(&global) {
+                    Some(&local) => local,
+                    // Corner outside the submesh's vertex range
+                    None => {
+                        let local = self.push(source, global, original);
+                        locals.insert(global, local);
+                        local
+                    }
+                };
+                self.triangles.push(local);
+            }
+        }
+
+        [
+            base,
+            self.lookup.len() - base,
+            first_triangle,
+            self.triangles.len() - first_triangle,
+        ]
+    }
+
+    /// Add a lookup entry with the bone palette indices of `original`
+    fn push(&mut self, source: &SkinSource<'_>, global: u16, original: u16) -> u16 {
+        let local = self.lookup.len() as u16;
+        self.lookup.push(global);
+        if let Some(properties) = source.properties {
+            let start = original as usize * 4;
+            self.properties
+                .extend_from_slice(&properties[start..start + 4]);
+        }
+        local
+    }
+}
+
+/// Whether a triangle has a repeated corner or no area
+fn is_degenerate(corners: &[(u16, u16)], vertices: &[M2Vertex], area_tolerance: f32) -> bool {
+    let [a, b, c] = [corners[0].0, corners[1].0, corners[2].0];
+    if a == b || b == c || a == c {
+        return true;
+    }
+    let position = |i: u16| {
+        vertices
+            .get(i as usize)
+            .map(|v| [v.position.x, v.position.y, v.position.z])
+    };
+    let (Some(a), Some(b), Some(c)) = (position(a), position(b), position(c)) else {
+        return false;
+    };
+    let u = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+    let v = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+    let cross = [
+        u[1] * v[2] - u[2] * v[1],
+        u[2] * v[0] - u[0] * v[2],
+        u[0] * v[1] - u[1] * v[0],
+    ];
+    let area = 0.5 * (cross[0] * cross[0] + cross[1] * cross[1] + cross[2] * cross[2]).sqrt();
+    area < area_tolerance
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunks::attachment::M2Attachment;
+    use crate::chunks::bone::M2Bone;
+    use crate::common::{C2Vector, C3Vector};
+    use crate::skin::{OldSkin, OldSkinHeader};
+
+    fn vertex(x: f32, y: f32, bone: u8) -> M2Vertex {
+        M2Vertex {
+            position: C3Vector { x, y, z: 0.0 },
+            bone_weights: [255, 0, 0, 0],
+            bone_indices: [bone, 0, 0, 0],
+            normal: C3Vector {
+                x: 0.0,
+                y: 0.0,
+                z: 1.0,
+            },
+            tex_coords: C2Vector { x, y },
+            tex_coords2: None,
+        }
+    }
+
+    fn submesh(vertex_count: u16, triangle_count: u16) -> SkinSubmesh {
+        SkinSubmesh {
+            id: 0,
+            level: 0,
+            vertex_start: 0,
+            vertex_count,
+            triangle_start: 0,
+            triangle_count,
+            bone_count: 4,
+            bone_start: 0,
+            bone_influence: 1,
+            center: [0.0; 3],
+            sort_center: [0.0; 3],
+            bounding_radius: 1.0,
+        }
+    }
+
+    /// Two triangles of a quad, stored with a duplicated corner, a zero
+    /// area triangle and a stray vertex
+    fn dirty_model() -> (M2Model, SkinFile) {
+        let model = M2Model {
+            vertices: vec![
+                vertex(0.0, 0.0, 1),
+                vertex(1.0, 0.0, 1),
+                vertex(1.0, 1.0, 1),
+                vertex(1.0, 1.0, 1), // duplicate of 2
+                vertex(0.0, 1.0, 1),
+                vertex(2.0, 0.0, 1), // collinear with 0 and 1
+                vertex(5.0, 5.0, 3), // unused
+            ],
+            bones: vec![
+                M2Bone::new(0, -1),
+                M2Bone::new(1, 0),
+                M2Bone::new(2, -1),
+                M2Bone::new(3, 0),
+            ],
+            ..Default::default()
+        };
+        let skin = SkinFile::Old(OldSkin {
+            header: OldSkinHeader::new(),
+            indices: (0..7).collect(),
+            triangles: vec![0, 1, 2, 0, 3, 4, 0, 1, 5],
+            bone_indices: vec![0; 28],
+            submeshes: vec![submesh(7, 9)],
+            batches: Vec::new(),
+        });
+        (model, skin)
+    }
+
+    #[test]
+    fn test_cleanup_mesh() {
+        let (mut model, skin) = dirty_model();
+        let mut skins = [skin];
+        let report = model.cleanup_mesh(&mut skins, &CleanupOptions::default());
+
+        assert_eq!(
+            report,
+            CleanupReport {
+                welded_vertices: 1,
+                degenerate_triangles: 1,
+                removed_vertices: 3,
+                removed_bones: 2,
+            }
+        );
+        assert_eq!(model.vertices.len(), 4);
+        assert_eq!(skins[0].indices(), &vec![0, 1, 2, 3]);
+        assert_eq!(skins[0].triangles(), &vec![0, 1, 2, 0, 2, 3]);
+
+        // Bone 1 and its parent remain; every vertex uses palette slot 0
+        assert_eq!(model.bones.len(), 2);
+        assert_eq!(model.bones[1].parent_bone, 0);
+        assert!(model.vertices.iter().all(|v| v.bone_indices[0] == 1));
+        assert_eq!(model.raw_data.bone_lookup_table, vec![1]);
+        let submesh = &skins[0].submeshes()[0];
+        assert_eq!((submesh.bone_start, submesh.bone_count), (0, 1));
+        assert_eq!((submesh.vertex_count, submesh.triangle_count), (4, 6));
+        assert_eq!(skins[0].bone_indices(), &vec![0; 16]);
+    }
+
+    #[test]
+    fn test_cleanup_keeps_referenced_bones() {
+        let (mut model, skin) = dirty_model();
+        model.attachments.push(M2Attachment::new(0, 2));
+        let options = CleanupOptions {
+            weld_tolerance: None,
+            remove_unused_vertices: false,
+            ..Default::default()
+        };
+        let mut skins = [skin];
+        let report = model.cleanup_mesh(&mut skins, &options);
+
+        // The unused vertex still references bone 3
+        assert_eq!(report.welded_vertices, 0);
+        assert_eq!(report.degenerate_triangles, 1);
+        assert_eq!(report.removed_vertices, 0);
+        assert_eq!(report.removed_bones, 0);
+        assert_eq!(model.attachments[0].bone_index, 2);
+        assert_eq!(skins[0].indices().len(), 7);
+        assert_eq!(skins[0].triangles().len(), 6);
+        assert_eq!(model.raw_data.bone_lookup_table, vec![1, 3]);
+    }
+}
@@ -0,0 +1,218 @@
+//! Collision geometry and its export to server-side vmap files
+//!
+//! Next to its render geometry an M2 model carries a simplified collision
+//! mesh: the header's bounding triangles, vertices and normals, together with
+//! a collision box and sphere. Servers never load models themselves. The vmap
+//! extractors of TrinityCore and CMaNGOS write each model's collision mesh to
+//! an intermediate "raw vmap" file, which their assemblers turn into the
+//! `.vmo` files the server reads for line of sight and height checks.
+//! [`M2Collision::write_vmap`] writes that intermediate file, so an
+//! extraction pipeline built on this crate feeds the same assemblers.
+//!
+//! ```no_run
+//! use std::fs::File;
+//! use wow_m2::M2Model;
+//!
+//! let model = M2Model::load("Tree.m2")?;
+//! let collision = model.model().collision();
+//! if !collision.is_empty() {
+//!     // The magic must match the RAW_VMAP_MAGIC of the assembler reading the file
+//!     collision.write_vmap(&mut File::create("Tree.m2.vmap")?, b"VMAP04A")?;
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::io::Write;
+
+use crate::common::C3Vector;
+use crate::error::{M2Error, Result};
+use crate::io_ext::WriteExt;
+use crate::model::M2Model;
+
+/// Collision mesh and bounds of a model, in model space
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct M2Collision {
+    /// Collision vertices
+    pub vertices: Vec<C3Vector>,
+    /// Collision triangles as indices into `vertices`
+    pub triangles: Vec<[u16; 3]>,
+    /// Collision face normals, one per triangle
+    pub normals: Vec<C3Vector>,
+    /// Collision box minimum corner
+    pub box_min: [f32; 3],
+    /// Collision box maximum corner
+    pub box_max: [f32; 3],
+    /// Collision sphere radius
+    pub sphere_radius: f32,
+}
+
+impl M2Model {
+    /// Collision mesh and bounds of the model
+    pub fn collision(&self) -> M2Collision {
+        let vector = |bytes: &[u8]| C3Vector {
+            x: f32::from_le_bytes(bytes[0..4].try_into().unwrap()),
+            y: f32::from_le_bytes(bytes[4..8].try_into().unwrap()),
+            z: f32::from_le_bytes(bytes[8..12].try_into().unwrap()),
+        };
+        let indices: Vec<u16> = self
+            .raw_data
+            .bounding_triangles
+            .chunks_exact(2)
+            .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+            .collect();
+
+        M2Collision {
+            vertices: self
+                .raw_data
+                .bounding_vertices
+                .chunks_exact(12)
+                .map(vector)
+                .collect(),
+            triangles: indices
+                .chunks_exact(3)
+                .map(|t| [t[0], t[1], t[2]])
+                .collect(),
+            normals: self
+                .raw_data
+                .bounding_normals
+                .chunks_exact(12)
+                .map(vector)
+                .collect(),
+            box_min: self.header.collision_box_min,
+            box_max: self.header.collision_box_max,
+            sphere_radius: self.header.collision_sphere_radius,
+        }
+    }
+}
+
+impl M2Collision {
+    /// Whether the model has no collision triangles
+    ///
+    /// The vmap extractors write no file for such models, as nothing can
+    /// collide with them.
+    pub fn is_empty(&self) -> bool {
+        self.triangles.is_empty()
+    }
+
+    /// Write the mesh as a raw vmap model file
+    ///
+    /// The layout is the one the vmap extractors write for M2 models: a
+    /// single group without bounds or liquid, followed by its `GRP `, `INDX`
+    /// and `VERT` chunks. Like the extractors, vertices are mirrored on the Y
+    /// axis and the winding of every triangle is swapped to match.
+    ///
+    /// `magic` is the `RAW_VMAP_MAGIC` of the assembler that reads the file,
+    /// which differs between projects and versions. It is padded with zeros
+    /// to 8 bytes.
+    pub fn write_vmap<W: Write>(&self, writer: &mut W, magic: &[u8]) -> Result<()> {
+        if magic.len() > 8 {
+            return Err(M2Error::ValidationError(format!(
+                "vmap magic is {} bytes, at most 8 fit",
+                magic.len()
+            )));
+        }
+        if let Some(index) = self
+            .triangles
+            .iter()
+            .flatten()
+            .find(|&&index| index as usize >= self.vertices.len())
+        {
+            return Err(M2Error::ValidationError(format!(
+                "Collision triangle references vertex {index}, but there are {} vertices",
+                self.vertices.len()
+            )));
+        }
+
+        let mut header = [0u8; 8];
+        header[..magic.len()].copy_from_slice(magic);
+        writer.write_all(&header)?;
+
+        let vertex_count = self.vertices.len() as u32;
+        let index_count = self.triangles.len() as u32 * 3;
+        writer.write_u32_le(vertex_count)?;
+        // One group: root WMO id, group flags and group WMO id, then the
+        // group bounds and liquid flags, all unused for models
+        writer.write_u32_le(1)?;
+        writer.write_all(&[0u8; 12 + 24 + 4])?;
+
+        writer.write_all(b"GRP ")?;
+        writer.write_u32_le(8)?;
+        writer.write_u32_le(1)?;
+        writer.write_u32_le(index_count)?;
+
+        writer.write_all(b"INDX")?;
+        writer.write_u32_le(4 + 2 * index_count)?;
+        writer.write_u32_le(index_count)?;
+        for [a, b, c] in &self.triangles {
+            for index in [a, c, b] {
+                writer.write_u16_le(*index)?;
+            }
+        }
+
+        writer.write_all(b"VERT")?;
+        writer.write_u32_le(4 + 12 * vertex_count)?;
+        writer.write_u32_le(vertex_count)?;
+        for vertex in &self.vertices {
+            writer.write_f32_le(vertex.x)?;
+            writer.write_f32_le(-vertex.y)?;
+            writer.write_f32_le(vertex.z)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_vmap() {
+        let mut model = M2Model::default();
+        for value in [0.0f32, 0.0, 0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0] {
+            model
+                .raw_data
+                .bounding_vertices
+                .extend_from_slice(&value.to_le_bytes());
+        }
+        for index in [0u16, 1, 2] {
+            model
+                .raw_data
+                .bounding_triangles
+                .extend_from_slice(&index.to_le_bytes());
+        }
+        model.header.collision_box_max = [4.0, 5.0, 6.0];
+
+        let collision = model.collision();
+        assert!(!collision.is_empty());
+        assert_eq!(collision.triangles, vec![[0, 1, 2]]);
+        assert_eq!(collision.box_max, [4.0, 5.0, 6.0]);
+
+        let mut data = Vec::new();
+        collision.write_vmap(&mut data, b"VMAP04A").unwrap();
+        assert_eq!(&data[..8], b"VMAP04A\0");
+        assert_eq!(&data[8..16], &[3, 0, 0, 0, 1, 0, 0, 0]);
+
+        let grp = 16 + 40;
+        assert_eq!(&data[grp..grp + 4], b"GRP ");
+        let indx = grp + 16;
+        assert_eq!(&data[indx..indx + 4], b"INDX");
+        assert_eq!(&data[indx + 8..indx + 18], &[3, 0, 0, 0, 0, 0, 2, 0, 1, 0]);
+        let vert = indx + 18;
+        assert_eq!(&data[vert..vert + 4], b"VERT");
+        let floats: Vec<f32> = data[vert + 12..]
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+            .collect();
+        assert_eq!(floats, vec![0.0, -0.0, 0.0, 1.0, -2.0, 3.0, 4.0, -5.0, 6.0]);
+
+        model.raw_data.bounding_vertices.truncate(24);
+        assert!(
+            model
+                .collision()
+                .write_vmap(&mut Vec::new(), b"VMAP04A")
+                .is_err()
+        );
+        assert!(collision.write_vmap(&mut Vec::new(), b"TOO LONG!").is_err());
+    }
+}
@@ -2,6 +2,7 @@ use std::collections::HashMap;
 
 use crate::error::{M2Error, Result};
 use crate::model::M2Model;
+use crate::skin::SkinFile;
 use crate::version::M2Version;
 
 /// Functions for converting models between different versions
@@ -59,6 +60,34 @@ impl M2Converter {
         }
     }
 
+    /// Convert a model after checking that the target can represent it
+    ///
+    /// Downgrades are checked with [`M2Model::check_downgrade`] first; any
+    /// issue fails the conversion with the list of what to fix. `skins` are
+    /// the model's skin profiles in order.
+    pub fn convert_checked(
+        &self,
+        model: &M2Model,
+        skins: &[SkinFile],
+        target_version: M2Version,
+    ) -> Result<M2Model> {
+        let report = model.check_downgrade(skins, target_version);
+        if !report.is_empty() {
+            let reason = report
+                .issues
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("; ");
+            return Err(M2Error::ConversionError {
+                from: model.header.version,
+                to: target_version.to_header_version(),
+                reason,
+            });
+        }
+        self.convert(model, target_version)
+    }
+
     /// Build the conversion paths between versions
     fn build_conversion_paths(&mut self) {
         // Define a list of all versions in order
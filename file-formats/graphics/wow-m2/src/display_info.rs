@@ -0,0 +1,469 @@
+//! Display-driven textures and particle colors
+//!
+//! A model file on its own does not say how a creature or item looks in the
+//! game. The client picks a row of `CreatureDisplayInfo.dbc` or
+//! `ItemDisplayInfo.dbc` by display ID, fills the replaceable texture slots
+//! from it and, for emitters that ask for it, takes particle colors from the
+//! `ParticleColor.dbc` row the display points to. [`DisplayTables`] performs
+//! that join for a parsed model.
+//!
+//! Column positions follow the tables of The Burning Crusade and Wrath of
+//! the Lich King. Columns missing from older tables read as empty.
+//!
+//! ```no_run
+//! use wow_m2::{DisplayTables, M2Model};
+//!
+//! let tables = DisplayTables::new()
+//!     .with_creature_display_info(&std::fs::read("CreatureDisplayInfo.dbc")?)?
+//!     .with_particle_color(&std::fs::read("ParticleColor.dbc")?)?;
+//!
+//! let format = M2Model::load("Creature/Wolf/Wolf.m2")?;
+//! let appearance =
+//!     tables.creature_appearance(format.model(), "Creature\\Wolf\\Wolf.m2", 161)?;
+//! for texture in &appearance.textures {
+//!     println!("{}: {:?}", texture.texture_type, texture.path);
+//! }
+//! for color in &appearance.particle_colors {
+//!     println!("emitter {}: {:?}", color.emitter_index, color.start);
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use wow_cdbc::{DbcParser, Record, RecordSet, StringRef, Value};
+
+use crate::chunks::color_animation::M2Color;
+use crate::chunks::texture::M2TextureType;
+use crate::error::{M2Error, Result};
+use crate::model::M2Model;
+use crate::texture_resolver::{ReplaceableTextures, ResolvedTexture, creature_texture_path};
+
+/// First `TextureVariation` column of CreatureDisplayInfo.dbc
+const CREATURE_TEXTURE_VARIATION: usize = 6;
+/// `ParticleColorID` column of CreatureDisplayInfo.dbc
+const CREATURE_PARTICLE_COLOR: usize = 13;
+
+/// First `ModelName` column of ItemDisplayInfo.dbc
+const ITEM_MODEL_NAME: usize = 1;
+/// First `ModelTexture` column of ItemDisplayInfo.dbc
+const ITEM_MODEL_TEXTURE: usize = 3;
+/// `ParticleColorID` column of ItemDisplayInfo.dbc
+const ITEM_PARTICLE_COLOR: usize = 24;
+
+/// First particle color index of an emitter that refers to ParticleColor.dbc
+const FIRST_DBC_COLOR_INDEX: u16 = 11;
+
+/// A row of CreatureDisplayInfo.dbc
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CreatureDisplay {
+    /// Display ID
+    pub id: u32,
+    /// Row of CreatureModelData.dbc naming the model
+    pub model_id: u32,
+    /// Skin names for the three monster texture slots
+    pub texture_variations: [String; 3],
+    /// Row of ParticleColor.dbc, 0 for none
+    pub particle_color_id: u32,
+}
+
+/// A row of ItemDisplayInfo.dbc
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ItemDisplay {
+    /// Display ID
+    pub id: u32,
+    /// Model names; the second is the left shoulder or off-hand model
+    pub model_names: [String; 2],
+    /// Textures of the two models
+    pub model_textures: [String; 2],
+    /// Row of ParticleColor.dbc, 0 for none
+    pub particle_color_id: u32,
+}
+
+/// A row of ParticleColor.dbc
+///
+/// Colors are stored as ARGB. Set `n` applies to emitters with particle
+/// color index `11 + n`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParticleColor {
+    /// Row ID
+    pub id: u32,
+    /// Colors at the start of a particle's life
+    pub start: [u32; 3],
+    /// Colors at the middle of a particle's life
+    pub mid: [u32; 3],
+    /// Colors at the end of a particle's life
+    pub end: [u32; 3],
+}
+
+/// Colors that replace an emitter's color track
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParticleColorOverride {
+    /// Index of the emitter in the model
+    pub emitter_index: usize,
+    /// Color at the start of a particle's life
+    pub start: M2Color,
+    /// Color at the middle of a particle's life
+    pub mid: M2Color,
+    /// Color at the end of a particle's life
+    pub end: M2Color,
+}
+
+/// Textures and particle colors of a model for one display
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DisplayAppearance {
+    /// Every texture of the model, with replaceable slots filled in
+    pub textures: Vec<ResolvedTexture>,
+    /// Emitters whose colors come from ParticleColor.dbc
+    pub particle_colors: Vec<ParticleColorOverride>,
+}
+
+/// Display tables of the client, joined with models by display ID
+///
+/// Each table is optional; lookups in a table that was not loaded fail.
+#[derive(Debug, Default)]
+pub struct DisplayTables {
+    creature_display_info: Option<RecordSet>,
+    item_display_info: Option<RecordSet>,
+    particle_color: Option<RecordSet>,
+}
+
+impl DisplayTables {
+    /// No tables loaded
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load CreatureDisplayInfo.dbc
+    pub fn with_creature_display_info(mut self, data: &[u8]) -> Result<Self> {
+        self.creature_display_info = Some(parse_table(data, "CreatureDisplayInfo.dbc")?);
+        Ok(self)
+    }
+
+    /// Load ItemDisplayInfo.dbc
+    pub fn with_item_display_info(mut self, data: &[u8]) -> Result<Self> {
+        self.item_display_info = Some(parse_table(data, "ItemDisplayInfo.dbc")?);
+        Ok(self)
+    }
+
+    /// Load ParticleColor.dbc
+    pub fn with_particle_color(mut self, data: &[u8]) -> Result<Self> {
+        self.particle_color = Some(parse_table(data, "ParticleColor.dbc")?);
+        Ok(self)
+    }
+
+    /// The CreatureDisplayInfo.dbc row of a display
+    pub fn creature_display(&self, display_id: u32) -> Result<CreatureDisplay> {
+        let table = loaded(&self.creature_display_info, "CreatureDisplayInfo.dbc")?;
+        let record = find_row(table, display_id, "Creature display")?;
+        Ok(CreatureDisplay {
+            id: display_id,
+            model_id: column(record, 1),
+            texture_variations: std::array::from_fn(|i| {
+                string_column(table, record, CREATURE_TEXTURE_VARIATION + i)
+            }),
+            particle_color_id: column(record, CREATURE_PARTICLE_COLOR),
+        })
+    }
+
+    /// The ItemDisplayInfo.dbc row of a display
+    pub fn item_display(&self, display_id: u32) -> Result<ItemDisplay> {
+        let table = loaded(&self.item_display_info, "ItemDisplayInfo.dbc")?;
+        let record = find_row(table, display_id, "Item display")?;
+        Ok(ItemDisplay {
+            id: display_id,
+            model_names: std::array::from_fn(|i| string_column(table, record, ITEM_MODEL_NAME + i)),
+            model_textures: std::array::from_fn(|i| {
+                string_column(table, record, ITEM_MODEL_TEXTURE + i)
+            }),
+            particle_color_id: column(record, ITEM_PARTICLE_COLOR),
+        })
+    }
+
+    /// A row of ParticleColor.dbc
+    pub fn particle_color(&self, id: u32) -> Result<ParticleColor> {
+        let table = loaded(&self.particle_color, "ParticleColor.dbc")?;
+        let record = find_row(table, id, "Particle color")?;
+        Ok(ParticleColor {
+            id,
+            start: std::array::from_fn(|i| column(record, 1 + i)),
+            mid: std::array::from_fn(|i| column(record, 4 + i)),
+            end: std::array::from_fn(|i| column(record, 7 + i)),
+        })
+    }
+
+    /// Replaceable textures of a creature display
+    ///
+    /// Texture variations are placed next to the model at `model_path`.
+    pub fn creature_textures(
+        &self,
+        model_path: &str,
+        display_id: u32,
+    ) -> Result<ReplaceableTextures> {
+        let display = self.creature_display(display_id)?;
+        let variations = display.texture_variations.each_ref().map(String::as_str);
+        Ok(ReplaceableTextures::creature(model_path, &variations))
+    }
+
+    /// Replaceable textures of one model of an item display
+    ///
+    /// `model_index` selects the display's first or second model, such as
+    /// the right or left shoulder. Its texture fills the item slot and is
+    /// placed next to the model at `model_path`.
+    pub fn item_textures(
+        &self,
+        model_path: &str,
+        display_id: u32,
+        model_index: usize,
+    ) -> Result<ReplaceableTextures> {
+        let display = self.item_display(display_id)?;
+        let texture = display.model_textures.get(model_index).ok_or_else(|| {
+            M2Error::ReferenceError(format!(
+                "Item display {display_id} has no model {model_index}"
+            ))
+        })?;
+        let mut textures = ReplaceableTextures::new();
+        if !texture.is_empty() {
+            textures.set(
+                M2TextureType::Item,
+                creature_texture_path(model_path, texture),
+            );
+        }
+        Ok(textures)
+    }
+
+    /// Appearance of a creature model for a creature display
+    ///
+    /// See [`creature_textures`](Self::creature_textures). Particle colors
+    /// need ParticleColor.dbc when the display has them.
+    pub fn creature_appearance(
+        &self,
+        model: &M2Model,
+        model_path: &str,
+        display_id: u32,
+    ) -> Result<DisplayAppearance> {
+        let textures = self.creature_textures(model_path, display_id)?;
+        let particle_color_id = self.creature_display(display_id)?.particle_color_id;
+        self.appearance(model, &textures, particle_color_id)
+    }
+
+    /// Appearance of an item model for an item display
+    ///
+    /// See [`item_textures`](Self::item_textures). Particle colors need
+    /// ParticleColor.dbc when the display has them.
+    pub fn item_appearance(
+        &self,
+        model: &M2Model,
+        model_path: &str,
+        display_id: u32,
+        model_index: usize,
+    ) -> Result<DisplayAppearance> {
+        let textures = self.item_textures(model_path, display_id, model_index)?;
+        let particle_color_id = self.item_display(display_id)?.particle_color_id;
+        self.appearance(model, &textures, particle_color_id)
+    }
+
+    fn appearance(
+        &self,
+        model: &M2Model,
+        textures: &ReplaceableTextures,
+        particle_color_id: u32,
+    ) -> Result<DisplayAppearance> {
+        let particle_colors = if particle_color_id == 0 {
+            Vec::new()
+        } else {
+            particle_color_overrides(model, &self.particle_color(particle_color_id)?)
+        };
+        Ok(DisplayAppearance {
+            textures: textures.resolve_model(model),
+            particle_colors,
+        })
+    }
+}
+
+/// Color overrides for the emitters of a model that use ParticleColor.dbc
+pub fn particle_color_overrides(
+    model: &M2Model,
+    colors: &ParticleColor,
+) -> Vec<ParticleColorOverride> {
+    model
+        .particle_emitters
+        .iter()
+        .enumerate()
+        .filter_map(|(emitter_index, emitter)| {
+            let set = emitter
+                .particle_color_index()
+                .checked_sub(FIRST_DBC_COLOR_INDEX)? as usize;
+            Some(ParticleColorOverride {
+                emitter_index,
+                start: argb_color(*colors.start.get(set)?),
+                mid: argb_color(colors.mid[set]),
+                end: argb_color(colors.end[set]),
+            })
+        })
+        .collect()
+}
+
+/// Convert an ARGB color of a DBC column; the alpha channel is not used
+pub fn argb_color(argb: u32) -> M2Color {
+    let [b, g, r, _] = argb.to_le_bytes();
+    M2Color {
+        r: f32::from(r) / 255.0,
+        g: f32::from(g) / 255.0,
+        b: f32::from(b) / 255.0,
+    }
+}
+
+fn parse_table(data: &[u8], name: &str) -> Result<RecordSet> {
+    DbcParser::parse_bytes(data)
+        .and_then(|parser| parser.parse_records())
+        .map_err(|e| M2Error::ParseError(format!("Failed to parse {name}: {e}")))
+}
+
+fn loaded<'a>(table: &'a Option<RecordSet>, name: &str) -> Result<&'a RecordSet> {
+    table
+        .as_ref()
+        .ok_or_else(|| M2Error::ReferenceError(format!("{name} is not loaded")))
+}
+
+fn find_row<'a>(table: &'a RecordSet, id: u32, kind: &str) -> Result<&'a Record> {
+    table
+        .records()
+        .iter()
+        .find(|record| column(record, 0) == id)
+        .ok_or_else(|| M2Error::ReferenceError(format!("{kind} {id} not found")))
+}
+
+fn column(record: &Record, index: usize) -> u32 {
+    match record.get_value(index) {
+        Some(Value::UInt32(value)) => *value,
+        Some(Value::Int32(value)) => *value as u32,
+        _ => 0,
+    }
+}
+
+fn string_column(table: &RecordSet, record: &Record, index: usize) -> String {
+    table
+        .get_string(StringRef::new(column(record, index)))
+        .map(str::to_string)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A WDBC file with `rows` of `fields` columns and a string block
+    fn dbc(fields: u32, rows: &[Vec<u32>], strings: &[u8]) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(b"WDBC");
+        for value in [rows.len() as u32, fields, fields * 4, strings.len() as u32] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        for row in rows {
+            assert_eq!(row.len(), fields as usize);
+            for value in row {
+                data.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        data.extend_from_slice(strings);
+        data
+    }
+
+    fn tables() -> DisplayTables {
+        let mut creature = vec![0u32; 16];
+        creature[0] = 161;
+        creature[CREATURE_TEXTURE_VARIATION + 1] = 1; // "WolfSkinGray"
+        creature[CREATURE_PARTICLE_COLOR] = 5;
+
+        let mut item = vec![0u32; 25];
+        item[0] = 900;
+        item[ITEM_MODEL_TEXTURE + 1] = 1; // "Shoulder_Left"
+
+        let particle = vec![5, 0xFF00_00FF, 0xFFFF_0000, 0, 0, 0, 0, 0, 0, 0x8000_FF00];
+
+        DisplayTables::new()
+            .with_creature_display_info(&dbc(16, &[creature], b"\0WolfSkinGray\0"))
+            .unwrap()
+            .with_item_display_info(&dbc(25, &[item], b"\0Shoulder_Left\0"))
+            .unwrap()
+            .with_particle_color(&dbc(10, &[particle], b"\0"))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_display_rows() {
+        let tables = tables();
+        let creature = tables.creature_display(161).unwrap();
+        assert_eq!(
+            creature.texture_variations,
+            ["", "WolfSkinGray", ""].map(String::from)
+        );
+        assert_eq!(creature.particle_color_id, 5);
+
+        let item = tables.item_display(900).unwrap();
+        assert_eq!(item.model_textures[1], "Shoulder_Left");
+        assert_eq!(item.particle_color_id, 0);
+
+        let colors = tables.particle_color(5).unwrap();
+        assert_eq!(colors.start, [0xFF00_00FF, 0xFFFF_0000, 0]);
+        assert_eq!(colors.end[2], 0x8000_FF00);
+
+        assert!(matches!(
+            tables.creature_display(1),
+            Err(M2Error::ReferenceError(_))
+        ));
+        assert!(DisplayTables::new().particle_color(5).is_err());
+    }
+
+    fn emitter(color_index: u16) -> crate::M2ParticleEmitter {
+        let mut emitter =
+            crate::M2ParticleEmitter::parse(&mut std::io::Cursor::new(vec![0u8; 1024]), 264)
+                .unwrap();
+        [emitter.particle_type, emitter.head_or_tail] = color_index.to_le_bytes();
+        emitter
+    }
+
+    #[test]
+    fn test_appearance() {
+        use crate::chunks::texture::M2Texture;
+        use crate::common::M2ArrayString;
+
+        let tables = tables();
+        let model = M2Model {
+            textures: [M2TextureType::Monster2, M2TextureType::Item]
+                .map(|t| M2Texture::new(t, M2ArrayString::default()))
+                .to_vec(),
+            particle_emitters: vec![emitter(0), emitter(12)],
+            ..Default::default()
+        };
+
+        let creature = tables
+            .creature_appearance(&model, "Creature\\Wolf\\Wolf.m2", 161)
+            .unwrap();
+        assert_eq!(
+            creature.textures[0].path.as_deref(),
+            Some("Creature\\Wolf\\WolfSkinGray.blp")
+        );
+        assert_eq!(creature.textures[1].path, None);
+        assert_eq!(creature.particle_colors.len(), 1);
+        let colors = creature.particle_colors[0];
+        assert_eq!(colors.emitter_index, 1);
+        assert_eq!((colors.start.r, colors.start.b), (1.0, 0.0));
+        assert_eq!(colors.end.r, 0.0);
+
+        let item = tables
+            .item_appearance(&model, "Item\\ObjectComponents\\Shoulder\\L.m2", 900, 1)
+            .unwrap();
+        assert_eq!(
+            item.textures[1].path.as_deref(),
+            Some("Item\\ObjectComponents\\Shoulder\\Shoulder_Left.blp")
+        );
+        assert!(item.particle_colors.is_empty());
+        assert!(tables.item_appearance(&model, "L.m2", 900, 2).is_err());
+    }
+
+    #[test]
+    fn test_argb_color() {
+        let color = argb_color(0x80FF_8000);
+        assert_eq!((color.r, color.g, color.b), (1.0, 128.0 / 255.0, 0.0));
+    }
+}
@@ -0,0 +1,332 @@
+//! Pre-flight checks for converting models to older versions
+//!
+//! [`M2Converter::convert`](crate::M2Converter::convert) rewrites headers and
+//! structures for the target version, but it cannot invent what an older
+//! client needs and a newer model lacks. Chunked (Legion and later) models
+//! reference textures and skin profiles by FileDataID, may keep their bones
+//! in a separate skeleton file, and were authored for bone palettes larger
+//! than older clients draw in one batch. [`M2Model::check_downgrade`] lists
+//! such problems before converting, so they can be fixed instead of ending up
+//! in a file the target client renders wrongly or rejects.
+
+use std::fmt;
+
+use crate::chunks::texture::M2TextureType;
+use crate::model::M2Model;
+use crate::skin::SkinFile;
+use crate::version::M2Version;
+
+/// Largest number of bones a submesh of a model for `version` can use
+///
+/// Clients up to Cataclysm upload the bone palette of a batch into a fixed
+/// number of shader constants; later clients have no practical limit.
+pub fn max_bones_per_batch(version: M2Version) -> Option<u16> {
+    (version <= M2Version::Cataclysm).then_some(64)
+}
+
+/// A feature of a model that the target version cannot represent
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DowngradeIssue {
+    /// A submesh uses more bones than the target draws in one batch
+    TooManyBones {
+        /// Index of the skin profile
+        skin: usize,
+        /// Index of the submesh in the skin profile
+        submesh: usize,
+        /// Bones the submesh uses
+        bones: u16,
+        /// Bones the target allows
+        limit: u16,
+    },
+    /// A texture has a type this library does not know, most likely one
+    /// added after the target version
+    UnsupportedTextureType {
+        /// Index of the texture
+        texture: usize,
+    },
+    /// A hardcoded texture is only referenced by FileDataID, which the
+    /// target cannot store
+    TextureWithoutFilename {
+        /// Index of the texture
+        texture: usize,
+        /// FileDataID from the TXID chunk, if any
+        file_data_id: Option<u32>,
+    },
+    /// The target keeps skin profiles in `.skin` files and fewer were given
+    /// than the model has
+    MissingSkinProfiles {
+        /// Skin profiles the model has
+        expected: usize,
+        /// Skin profiles given to the check
+        given: usize,
+    },
+    /// The target embeds skin profiles in the model, and the model has none
+    /// to embed
+    MissingEmbeddedSkins,
+    /// The bones are stored in a separate skeleton file
+    ExternalSkeleton {
+        /// FileDataID of the `.skel` file
+        file_data_id: u32,
+    },
+}
+
+impl fmt::Display for DowngradeIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooManyBones {
+                skin,
+                submesh,
+                bones,
+                limit,
+            } => write!(
+                f,
+                "submesh {submesh} of skin profile {skin} uses {bones} bones, more than the \
+                 {limit} the target draws per batch; split the submesh or remove unused bones"
+            ),
+            Self::UnsupportedTextureType { texture } => write!(
+                f,
+                "texture {texture} has a texture type the target does not know; make it a \
+                 hardcoded texture with a filename"
+            ),
+            Self::TextureWithoutFilename {
+                texture,
+                file_data_id: Some(id),
+            } => write!(
+                f,
+                "texture {texture} is only referenced by FileDataID {id}; set its filename \
+                 from a listfile"
+            ),
+            Self::TextureWithoutFilename {
+                texture,
+                file_data_id: None,
+            } => write!(
+                f,
+                "texture {texture} is hardcoded but has no filename; set its filename"
+            ),
+            Self::MissingSkinProfiles { expected, given } => write!(
+                f,
+                "the model has {expected} skin profiles but {given} were given; load every \
+                 .skin file so it can be converted and saved next to the model"
+            ),
+            Self::MissingEmbeddedSkins => write!(
+                f,
+                "the target embeds skin profiles in the model, but the model has none to \
+                 embed; convert to WotLK or later instead"
+            ),
+            Self::ExternalSkeleton { file_data_id } => write!(
+                f,
+                "the bones are stored in skeleton file {file_data_id}; merge them into the \
+                 model before converting"
+            ),
+        }
+    }
+}
+
+/// Result of [`M2Model::check_downgrade`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DowngradeReport {
+    /// Version the model was checked against
+    pub target: M2Version,
+    /// Problems found, in model order
+    pub issues: Vec<DowngradeIssue>,
+}
+
+impl DowngradeReport {
+    /// Whether the model can be converted without losing anything
+    pub fn is_empty(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+impl M2Model {
+    /// Check whether the model and its skin profiles can be converted to an
+    /// older `target` version
+    ///
+    /// `skins` are the model's skin profiles in order. The report is empty if
+    /// `target` is not older than the model.
+    pub fn check_downgrade(&self, skins: &[SkinFile], target: M2Version) -> DowngradeReport {
+        let mut report = DowngradeReport {
+            target,
+            issues: Vec::new(),
+        };
+        let Some(source) = self.header.version() else {
+            return report;
+        };
+        if target >= source {
+            return report;
+        }
+        let issues = &mut report.issues;
+
+        if let Some(limit) = max_bones_per_batch(target) {
+            for (skin, profile) in skins.iter().enumerate() {
+                for (submesh, mesh) in profile.submeshes().iter().enumerate() {
+                    if mesh.bone_count > limit {
+                        issues.push(DowngradeIssue::TooManyBones {
+                            skin,
+                            submesh,
+                            bones: mesh.bone_count,
+                            limit,
+                        });
+                    }
+                }
+            }
+        }
+
+        let chunked_target = target >= M2Version::Legion;
+        for (index, texture) in self.textures.iter().enumerate() {
+            match texture.texture_type {
+                M2TextureType::Unknown => {
+                    issues.push(DowngradeIssue::UnsupportedTextureType { texture: index });
+                }
+                M2TextureType::Hardcoded if !chunked_target && texture.filename.is_empty() => {
+                    let file_data_id = self
+                        .texture_file_ids
+                        .as_ref()
+                        .and_then(|ids| ids.ids.get(index).copied())
+                        .filter(|&id| id != 0);
+                    issues.push(DowngradeIssue::TextureWithoutFilename {
+                        texture: index,
+                        file_data_id,
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        if target <= M2Version::TBC {
+            if self.raw_data.embedded_skins.is_empty() {
+                issues.push(DowngradeIssue::MissingEmbeddedSkins);
+            }
+        } else {
+            let expected = self.header.num_skin_profiles.unwrap_or(0) as usize;
+            if skins.len() < expected {
+                issues.push(DowngradeIssue::MissingSkinProfiles {
+                    expected,
+                    given: skins.len(),
+                });
+            }
+        }
+
+        if !chunked_target
+            && self.bones.is_empty()
+            && let Some(skeleton) = &self.skeleton_file_id
+        {
+            issues.push(DowngradeIssue::ExternalSkeleton {
+                file_data_id: skeleton.id,
+            });
+        }
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunks::file_references::{SkeletonFileId, TextureFileIds};
+    use crate::chunks::texture::M2Texture;
+    use crate::common::M2ArrayString;
+    use crate::skin::{OldSkin, OldSkinHeader, SkinSubmesh};
+
+    fn skin(bone_counts: &[u16]) -> SkinFile {
+        let submeshes = bone_counts
+            .iter()
+            .map(|&bone_count| SkinSubmesh {
+                id: 0,
+                level: 0,
+                vertex_start: 0,
+                vertex_count: 0,
+                triangle_start: 0,
+                triangle_count: 0,
+                bone_count,
+                bone_start: 0,
+                bone_influence: 4,
+                center: [0.0; 3],
+                sort_center: [0.0; 3],
+                bounding_radius: 0.0,
+            })
+            .collect();
+        SkinFile::Old(OldSkin {
+            header: OldSkinHeader::new(),
+            indices: Vec::new(),
+            triangles: Vec::new(),
+            bone_indices: Vec::new(),
+            submeshes,
+            batches: Vec::new(),
+        })
+    }
+
+    /// A Legion model with two skin profiles, a texture only referenced by
+    /// FileDataID and a texture of a type added after WotLK
+    fn legion_model() -> M2Model {
+        let mut model = M2Model {
+            textures: vec![
+                M2Texture::new(M2TextureType::Hardcoded, M2ArrayString::default()),
+                M2Texture::new(M2TextureType::Unknown, M2ArrayString::default()),
+            ],
+            texture_file_ids: Some(TextureFileIds {
+                ids: vec![123456, 0],
+            }),
+            skeleton_file_id: Some(SkeletonFileId { id: 654321 }),
+            ..Default::default()
+        };
+        model.header.version = M2Version::Legion.to_header_version();
+        model.header.num_skin_profiles = Some(2);
+        model
+    }
+
+    #[test]
+    fn test_check_downgrade() {
+        let model = legion_model();
+        let report = model.check_downgrade(&[skin(&[12, 80])], M2Version::WotLK);
+
+        assert_eq!(
+            report.issues,
+            vec![
+                DowngradeIssue::TooManyBones {
+                    skin: 0,
+                    submesh: 1,
+                    bones: 80,
+                    limit: 64,
+                },
+                DowngradeIssue::TextureWithoutFilename {
+                    texture: 0,
+                    file_data_id: Some(123456),
+                },
+                DowngradeIssue::UnsupportedTextureType { texture: 1 },
+                DowngradeIssue::MissingSkinProfiles {
+                    expected: 2,
+                    given: 1,
+                },
+                DowngradeIssue::ExternalSkeleton {
+                    file_data_id: 654321,
+                },
+            ]
+        );
+        assert!(report.issues[0].to_string().contains("80 bones"));
+    }
+
+    #[test]
+    fn test_check_downgrade_embedded_skins() {
+        let model = legion_model();
+        let report = model.check_downgrade(&[], M2Version::TBC);
+        assert!(
+            report
+                .issues
+                .contains(&DowngradeIssue::MissingEmbeddedSkins)
+        );
+        assert!(
+            !report
+                .issues
+                .iter()
+                .any(|issue| matches!(issue, DowngradeIssue::MissingSkinProfiles { .. }))
+        );
+    }
+
+    #[test]
+    fn test_check_upgrade_is_empty() {
+        let model = legion_model();
+        assert!(model.check_downgrade(&[], M2Version::Legion).is_empty());
+        assert!(model.check_downgrade(&[], M2Version::BfA).is_empty());
+    }
+}
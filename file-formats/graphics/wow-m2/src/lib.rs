@@ -141,9 +141,14 @@
 pub mod anim;
 pub mod animation;
 pub mod chunks;
+pub mod cleanup;
+pub mod collision;
 pub mod common;
 pub mod converter;
 pub mod coordinate;
+#[cfg(feature = "display-info")]
+pub mod display_info;
+pub mod downgrade;
 pub mod embedded_skin;
 pub mod error;
 pub mod file_resolver;
@@ -153,8 +158,10 @@ pub mod model;
 pub mod model_animation_resolver;
 pub mod model_enhanced;
 pub mod particles;
+pub mod render_stats;
 pub mod skin;
 pub mod skinning;
+pub mod texture_resolver;
 pub mod version;
 
 // Re-export common types
@@ -166,10 +173,18 @@ pub use animation::{
 };
 pub use chunks::particle_emitter::{M2ParticleEmitter, M2ParticleEmitterType, M2ParticleFlags};
 pub use chunks::vertex::ValidationMode;
+pub use cleanup::{CleanupOptions, CleanupReport};
+pub use collision::M2Collision;
 pub use converter::M2Converter;
 pub use coordinate::{
     CoordinateSystem, CoordinateTransformer, transform_position, transform_quaternion,
 };
+#[cfg(feature = "display-info")]
+pub use display_info::{
+    CreatureDisplay, DisplayAppearance, DisplayTables, ItemDisplay, ParticleColor,
+    ParticleColorOverride,
+};
+pub use downgrade::{DowngradeIssue, DowngradeReport};
 pub use error::{M2Error, Result};
 pub use file_resolver::{FileResolver, ListfileResolver, PathResolver};
 pub use model::{M2Format, M2Model, parse_m2};
@@ -178,8 +193,10 @@ pub use model_enhanced::{
     AnimationInfo, BoneInfo, BoundingBox, EnhancedModelData, MaterialInfo, ModelStats, TextureInfo,
 };
 pub use particles::{EmissionType, EmitterParams, Particle, ParticleEmitter, TEXELS_PER_PARTICLE};
+pub use render_stats::{BatchStats, RenderStats};
 pub use skin::{OldSkin, Skin, SkinFile, load_skin, parse_skin};
 pub use skinning::{BoneTransform, M2Skinner, SkinningOptions};
+pub use texture_resolver::{ReplaceableTextures, ResolvedTexture, creature_texture_path};
 pub use version::M2Version;
 
 // Re-export BLP types from wow-blp crate for backwards compatibility
@@ -3661,8 +3661,7 @@ impl M2Model {
                 data_section.extend_from_slice(&anim_data);
             }
 
-            // Animation size depends on version: 32 bytes for Classic, 52 bytes for BC+
-            let anim_size = if header.version <= 256 { 32 } else { 52 };
+            let anim_size = M2Animation::size_for_version(header.version);
             current_offset += (self.animations.len() * anim_size) as u32;
         } else {
             header.animations = M2Array::new(0, 0);
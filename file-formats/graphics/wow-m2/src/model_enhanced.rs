@@ -313,19 +313,7 @@ impl M2Model {
             // Determine animation name and properties
             let (name, is_looping) = self.get_animation_info(i as u16, &animation);
 
-            // Handle version differences in animation format
-            let duration_ms = if self.header.version <= 256 {
-                // Vanilla (version 256) uses start/end timestamps
-                if let Some(end_timestamp) = animation.end_timestamp {
-                    end_timestamp.saturating_sub(animation.start_timestamp)
-                } else {
-                    // Fallback if no end timestamp
-                    animation.start_timestamp
-                }
-            } else {
-                // BC+ (version 260+) uses duration in start_timestamp field
-                animation.start_timestamp
-            };
+            let duration_ms = animation.duration();
 
             animation_infos.push(AnimationInfo {
                 animation,
@@ -663,17 +651,7 @@ impl M2Model {
     }
 
     fn get_texture_type_description(&self, texture: &M2Texture) -> String {
-        match texture.texture_type {
-            crate::chunks::texture::M2TextureType::Hardcoded => "Hardcoded",
-            crate::chunks::texture::M2TextureType::Body => "Body + Clothes",
-            crate::chunks::texture::M2TextureType::Item => "Cape",
-            crate::chunks::texture::M2TextureType::SkinExtra => "Skin Extra",
-            crate::chunks::texture::M2TextureType::Hair => "Hair",
-            crate::chunks::texture::M2TextureType::Environment => "Environment",
-            crate::chunks::texture::M2TextureType::Monster1 => "Monster Skin 1",
-            _ => "Unknown",
-        }
-        .to_string()
+        texture.texture_type.name().to_string()
     }
 
     fn get_blend_mode_description(&self, material: &M2Material) -> String {
@@ -0,0 +1,270 @@
+//! Render statistics for skin profiles
+//!
+//! The client issues one draw call for every batch of a skin profile whose
+//! submesh has geometry. How expensive a model is to render therefore depends
+//! on its batch count, the number of triangles per batch, how many bones each
+//! batch has to upload and how many texture units it samples.
+//! [`SkinFile::render_stats`] collects those numbers so custom models can be
+//! compared against the client's own before they are shipped.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::chunks::material::M2BlendMode;
+use crate::model::M2Model;
+use crate::skin::SkinFile;
+
+/// Statistics of a single batch (texture unit) of a skin profile
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchStats {
+    /// Index of the batch in the skin profile
+    pub index: usize,
+    /// Index of the submesh the batch draws, `None` if it is out of range
+    pub submesh: Option<usize>,
+    /// Geoset id of the submesh
+    pub geoset_id: u16,
+    /// Triangles drawn by the batch
+    pub triangles: usize,
+    /// Vertices referenced by the batch
+    pub vertices: usize,
+    /// Bones uploaded for the batch
+    pub bones: u16,
+    /// Maximum number of bones influencing a single vertex
+    pub bone_influences: u16,
+    /// Texture units sampled by the batch
+    pub texture_units: u16,
+    /// Model texture indices sampled by the batch, resolved through the texture lookup table
+    pub textures: Vec<u16>,
+    /// Blend mode of the batch's material, `None` if the material is out of range
+    pub blend_mode: Option<M2BlendMode>,
+    /// Render priority plane
+    pub priority_plane: i8,
+    /// Material layer within the submesh
+    pub material_layer: u16,
+}
+
+impl BatchStats {
+    /// Whether the batch is a draw call, i.e. its submesh has geometry
+    pub fn is_draw_call(&self) -> bool {
+        self.triangles > 0
+    }
+
+    /// Whether the batch is blended and has to be sorted back to front
+    pub fn is_transparent(&self) -> bool {
+        self.blend_mode
+            .is_some_and(|mode| mode.bits() > M2BlendMode::ALPHA_KEY.bits())
+    }
+}
+
+/// Render statistics of a skin profile
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RenderStats {
+    /// Statistics of every batch, in skin order
+    pub batches: Vec<BatchStats>,
+    /// Number of submeshes
+    pub submeshes: usize,
+    /// Triangles of all submeshes
+    pub triangles: usize,
+    /// Vertices of all submeshes
+    pub vertices: usize,
+    /// Estimated draw calls, one per batch with geometry
+    pub draw_calls: usize,
+    /// Draw calls with a blended material
+    pub transparent_draw_calls: usize,
+    /// Triangles drawn by all batches, counting submeshes drawn by several batches once per batch
+    pub drawn_triangles: usize,
+    /// Largest number of bones uploaded for a single batch
+    pub max_bones: u16,
+    /// Largest number of bones influencing a single vertex
+    pub max_bone_influences: u16,
+    /// Largest number of texture units sampled by a single batch
+    pub max_texture_units: u16,
+    /// Texture units sampled by all batches
+    pub texture_units: usize,
+    /// Distinct model textures sampled by any batch
+    pub unique_textures: usize,
+    /// Number of draw calls per blend mode
+    pub blend_modes: BTreeMap<u16, usize>,
+    /// Batches referencing a submesh, material or texture that does not exist
+    pub invalid_batches: Vec<usize>,
+}
+
+impl SkinFile {
+    /// Collect render statistics of this skin profile
+    ///
+    /// Materials and texture lookups are resolved through `model`, which
+    /// must be the model the skin belongs to.
+    pub fn render_stats(&self, model: &M2Model) -> RenderStats {
+        let submeshes = self.submeshes();
+        let mut stats = RenderStats {
+            submeshes: submeshes.len(),
+            triangles: submeshes
+                .iter()
+                .map(|submesh| submesh.triangle_count as usize / 3)
+                .sum(),
+            vertices: submeshes
+                .iter()
+                .map(|submesh| submesh.vertex_count as usize)
+                .sum(),
+            ..Default::default()
+        };
+
+        let mut textures = BTreeSet::new();
+        for (index, batch) in self.batches().iter().enumerate() {
+            let section = batch.skin_section_index as usize;
+            let submesh = submeshes.get(section);
+            let material = model.materials.get(batch.material_index as usize);
+
+            let lookup_start = batch.texture_combo_index as usize;
+            let lookup_end = lookup_start + batch.texture_count as usize;
+            let lookups = model
+                .raw_data
+                .texture_lookup_table
+                .get(lookup_start..lookup_end);
+            let batch_textures = lookups.map(<[u16]>::to_vec).unwrap_or_default();
+
+            let valid_textures = lookups.is_some()
+                && batch_textures
+                    .iter()
+                    .all(|&texture| (texture as usize) < model.textures.len());
+            if submesh.is_none() || material.is_none() || !valid_textures {
+                stats.invalid_batches.push(index);
+            }
+
+            let batch_stats = BatchStats {
+                index,
+                submesh: submesh.map(|_| section),
+                geoset_id: submesh.map_or(0, |submesh| submesh.id),
+                triangles: submesh.map_or(0, |submesh| submesh.triangle_count as usize / 3),
+                vertices: submesh.map_or(0, |submesh| submesh.vertex_count as usize),
+                bones: submesh.map_or(0, |submesh| submesh.bone_count),
+                bone_influences: submesh.map_or(0, |submesh| submesh.bone_influence),
+                texture_units: batch.texture_count,
+                textures: batch_textures,
+                blend_mode: material.map(|material| material.blend_mode),
+                priority_plane: batch.priority_plane,
+                material_layer: batch.material_layer,
+            };
+
+            stats.texture_units += batch_stats.texture_units as usize;
+            stats.max_texture_units = stats.max_texture_units.max(batch_stats.texture_units);
+            textures.extend(batch_stats.textures.iter().copied());
+
+            if batch_stats.is_draw_call() {
+                stats.draw_calls += 1;
+                stats.drawn_triangles += batch_stats.triangles;
+                stats.max_bones = stats.max_bones.max(batch_stats.bones);
+                stats.max_bone_influences =
+                    stats.max_bone_influences.max(batch_stats.bone_influences);
+                if batch_stats.is_transparent() {
+                    stats.transparent_draw_calls += 1;
+                }
+                if let Some(mode) = batch_stats.blend_mode {
+                    *stats.blend_modes.entry(mode.bits()).or_default() += 1;
+                }
+            }
+
+            stats.batches.push(batch_stats);
+        }
+        stats.unique_textures = textures.len();
+
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunks::material::M2Material;
+    use crate::chunks::texture::{M2Texture, M2TextureType};
+    use crate::common::{FixedString, M2Array, M2ArrayString};
+    use crate::skin::{OldSkin, OldSkinHeader, SkinBatch, SkinSubmesh};
+
+    fn submesh(id: u16, vertex_count: u16, triangle_count: u16, bone_count: u16) -> SkinSubmesh {
+        SkinSubmesh {
+            id,
+            level: 0,
+            vertex_start: 0,
+            vertex_count,
+            triangle_start: 0,
+            triangle_count,
+            bone_count,
+            bone_start: 0,
+            bone_influence: 4,
+            center: [0.0; 3],
+            sort_center: [0.0; 3],
+            bounding_radius: 1.0,
+        }
+    }
+
+    fn batch(skin_section_index: u16, material_index: u16, texture_count: u16) -> SkinBatch {
+        SkinBatch {
+            flags: 0,
+            priority_plane: 0,
+            shader_id: 0,
+            skin_section_index,
+            geoset_index: skin_section_index,
+            color_index: u16::MAX,
+            material_index,
+            material_layer: 0,
+            texture_count,
+            texture_combo_index: 0,
+            texture_coord_combo_index: 0,
+            texture_weight_combo_index: 0,
+            texture_transform_combo_index: 0,
+        }
+    }
+
+    fn texture() -> M2Texture {
+        M2Texture::new(
+            M2TextureType::Hardcoded,
+            M2ArrayString {
+                string: FixedString {
+                    data: b"Test.blp".to_vec(),
+                },
+                array: M2Array::new(0, 0),
+            },
+        )
+    }
+
+    #[test]
+    fn test_render_stats() {
+        let mut model = M2Model {
+            materials: vec![
+                M2Material::new(M2BlendMode::OPAQUE),
+                M2Material::new(M2BlendMode::ADD),
+            ],
+            textures: vec![texture(), texture()],
+            ..Default::default()
+        };
+        model.raw_data.texture_lookup_table = vec![0, 1];
+
+        let mut transparent = batch(1, 1, 1);
+        transparent.texture_combo_index = 1;
+        let skin = SkinFile::Old(OldSkin {
+            header: OldSkinHeader::new(),
+            indices: Vec::new(),
+            triangles: Vec::new(),
+            bone_indices: Vec::new(),
+            submeshes: vec![submesh(0, 100, 300, 20), submesh(1, 10, 0, 2)],
+            batches: vec![batch(0, 0, 2), transparent, batch(5, 0, 1)],
+        });
+
+        let stats = skin.render_stats(&model);
+        assert_eq!(stats.submeshes, 2);
+        assert_eq!(stats.triangles, 100);
+        assert_eq!(stats.vertices, 110);
+        assert_eq!(stats.draw_calls, 1);
+        assert_eq!(stats.transparent_draw_calls, 0);
+        assert_eq!(stats.max_bones, 20);
+        assert_eq!(stats.max_texture_units, 2);
+        assert_eq!(stats.texture_units, 4);
+        assert_eq!(stats.unique_textures, 2);
+        assert_eq!(stats.blend_modes.get(&0), Some(&1));
+        assert_eq!(stats.invalid_batches, vec![2]);
+
+        assert_eq!(stats.batches[0].textures, vec![0, 1]);
+        assert!(stats.batches[1].is_transparent());
+        assert!(!stats.batches[1].is_draw_call());
+        assert_eq!(stats.batches[2].submesh, None);
+    }
+}
@@ -0,0 +1,203 @@
+//! Replaceable texture resolution
+//!
+//! Most textures of character and creature models are not named in the model.
+//! Their [`M2TextureType`] marks a slot the client fills in at runtime: the
+//! body skin and hair come from character customization, capes from the
+//! equipped item and monster skins from the `TextureVariation` columns of
+//! `CreatureDisplayInfo.dbc`. [`ReplaceableTextures`] holds those choices and
+//! turns a model's texture list into concrete paths.
+
+use std::collections::HashMap;
+
+use crate::chunks::texture::{M2Texture, M2TextureType};
+use crate::model::M2Model;
+
+/// Texture paths chosen for the replaceable texture slots of a model
+///
+/// # Examples
+///
+/// ```
+/// use wow_m2::ReplaceableTextures;
+/// use wow_m2::chunks::texture::M2TextureType;
+///
+/// // A creature, from the TextureVariation columns of CreatureDisplayInfo.dbc
+/// let wolf = ReplaceableTextures::creature("Creature\\Wolf\\Wolf.m2", &["WolfSkinBrown"]);
+/// assert_eq!(
+///     wolf.get(M2TextureType::Monster1),
+///     Some("Creature\\Wolf\\WolfSkinBrown.blp")
+/// );
+///
+/// // A character, from the CharSections.dbc rows picked by customization
+/// let human = ReplaceableTextures::new()
+///     .with(
+///         M2TextureType::Body,
+///         "Character\\Human\\Male\\HumanMaleSkin00_00.blp",
+///     )
+///     .with(
+///         M2TextureType::Hair,
+///         "Character\\Human\\Hair00_00.blp",
+///     );
+/// assert_eq!(human.len(), 2);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplaceableTextures {
+    textures: HashMap<M2TextureType, String>,
+}
+
+/// A texture of a model with its resolved path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedTexture {
+    /// Index of the texture in the model
+    pub index: usize,
+    /// Type of the texture slot
+    pub texture_type: M2TextureType,
+    /// Concrete texture path, `None` when the slot has no texture assigned
+    pub path: Option<String>,
+}
+
+impl ReplaceableTextures {
+    /// No textures assigned
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Textures of a creature from its display's texture variations
+    ///
+    /// The client looks up each variation name next to the model, so
+    /// `WolfSkinBrown` for `Creature\Wolf\Wolf.m2` becomes
+    /// `Creature\Wolf\WolfSkinBrown.blp`. Variations fill the monster skin
+    /// slots in order; empty ones are skipped.
+    pub fn creature(model_path: &str, variations: &[&str]) -> Self {
+        let slots = [
+            M2TextureType::Monster1,
+            M2TextureType::Monster2,
+            M2TextureType::Monster3,
+        ];
+        let mut textures = Self::new();
+        for (slot, variation) in slots.into_iter().zip(variations) {
+            if !variation.is_empty() {
+                textures.set(slot, creature_texture_path(model_path, variation));
+            }
+        }
+        textures
+    }
+
+    /// Assign a texture to a slot, returning `self` for chaining
+    pub fn with(mut self, texture_type: M2TextureType, path: impl Into<String>) -> Self {
+        self.set(texture_type, path);
+        self
+    }
+
+    /// Assign a texture to a slot
+    pub fn set(&mut self, texture_type: M2TextureType, path: impl Into<String>) {
+        self.textures.insert(texture_type, path.into());
+    }
+
+    /// The texture assigned to a slot
+    pub fn get(&self, texture_type: M2TextureType) -> Option<&str> {
+        self.textures.get(&texture_type).map(String::as_str)
+    }
+
+    /// Number of assigned slots
+    pub fn len(&self) -> usize {
+        self.textures.len()
+    }
+
+    /// Whether no slot is assigned
+    pub fn is_empty(&self) -> bool {
+        self.textures.is_empty()
+    }
+
+    /// Concrete path of a model texture
+    ///
+    /// Hardcoded textures resolve to their own filename, replaceable ones to
+    /// the texture assigned to their slot.
+    pub fn resolve(&self, texture: &M2Texture) -> Option<String> {
+        match texture.texture_type {
+            M2TextureType::Hardcoded => {
+                let name = texture.filename.string.to_string_lossy();
+                (!name.is_empty()).then_some(name)
+            }
+            texture_type => self.get(texture_type).map(str::to_string),
+        }
+    }
+
+    /// Resolve every texture of a model
+    pub fn resolve_model(&self, model: &M2Model) -> Vec<ResolvedTexture> {
+        model
+            .textures
+            .iter()
+            .enumerate()
+            .map(|(index, texture)| ResolvedTexture {
+                index,
+                texture_type: texture.texture_type,
+                path: self.resolve(texture),
+            })
+            .collect()
+    }
+}
+
+/// Path of a creature texture variation for a model
+///
+/// The variation is placed in the model's directory, using the separator the
+/// model path uses, and gets a `.blp` extension unless it has one.
+pub fn creature_texture_path(model_path: &str, variation: &str) -> String {
+    let dir = model_path
+        .rfind(['\\', '/'])
+        .map_or("", |i| &model_path[..=i]);
+    let has_extension = variation
+        .rsplit_once('.')
+        .is_some_and(|(_, ext)| ext.eq_ignore_ascii_case("blp"));
+    if has_extension {
+        format!("{dir}{variation}")
+    } else {
+        format!("{dir}{variation}.blp")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{FixedString, M2Array, M2ArrayString};
+
+    fn texture(texture_type: M2TextureType, name: &str) -> M2Texture {
+        M2Texture::new(
+            texture_type,
+            M2ArrayString {
+                string: FixedString {
+                    data: name.as_bytes().to_vec(),
+                },
+                array: M2Array::new(0, 0),
+            },
+        )
+    }
+
+    #[test]
+    fn test_creature_texture_path() {
+        assert_eq!(
+            creature_texture_path("Creature\\Wolf\\Wolf.m2", "WolfSkinBrown"),
+            "Creature\\Wolf\\WolfSkinBrown.blp"
+        );
+        assert_eq!(
+            creature_texture_path("data/creature/wolf/wolf.m2", "WolfSkin.BLP"),
+            "data/creature/wolf/WolfSkin.BLP"
+        );
+        assert_eq!(creature_texture_path("Wolf.m2", "Skin"), "Skin.blp");
+    }
+
+    #[test]
+    fn test_resolve() {
+        let textures = ReplaceableTextures::creature("Creature\\Bear\\Bear.m2", &["", "BearSkin"]);
+        assert_eq!(textures.get(M2TextureType::Monster1), None);
+
+        assert_eq!(
+            textures.resolve(&texture(M2TextureType::Monster2, "")),
+            Some("Creature\\Bear\\BearSkin.blp".to_string())
+        );
+        assert_eq!(
+            textures.resolve(&texture(M2TextureType::Hardcoded, "Eyes.blp")),
+            Some("Eyes.blp".to_string())
+        );
+        assert_eq!(textures.resolve(&texture(M2TextureType::Hair, "")), None);
+    }
+}
@@ -0,0 +1,705 @@
+//! Building client-ready single-group WMOs from triangle meshes
+//!
+//! [`WmoWriter`](crate::WmoWriter) serializes WMOs that were parsed from
+//! existing files. [`StaticWmoBuilder`] starts from plain geometry instead
+//! (vertices, triangle batches and materials) and derives everything else
+//! the Wrath of the Lich King client needs to load a world object: bounds,
+//! render batches, per-triangle collision flags and a BSP tree for
+//! collision queries. The result is a root file and a single group file.
+
+use std::io::Write;
+
+use crate::chunk::ChunkHeader;
+use crate::error::{Result, WmoError};
+use crate::parser::chunks;
+use crate::types::{BoundingBox, ChunkId, Vec3};
+use crate::wmo_group_types::TexCoord;
+use crate::wmo_types::WmoMaterialFlags;
+
+/// File version written by the builder
+const VERSION: u32 = 17;
+
+/// Group has a BSP tree (MOBN and MOBR)
+const GROUP_HAS_BSP: u32 = 0x1;
+/// Group has vertex colors (MOCV)
+const GROUP_HAS_VERTEX_COLORS: u32 = 0x4;
+/// Group is outdoors
+const GROUP_EXTERIOR: u32 = 0x8;
+/// Group is indoors
+const GROUP_INDOOR: u32 = 0x2000;
+
+/// Triangle is a detail face, it is rendered but does not collide
+const TRIANGLE_DETAIL: u8 = 0x04;
+/// Triangle collides
+const TRIANGLE_COLLISION: u8 = 0x08;
+/// Triangle is rendered
+const TRIANGLE_RENDER: u8 = 0x20;
+/// Material id of triangles that are not rendered
+const NO_MATERIAL: u8 = 0xFF;
+
+/// BSP leaf node flag
+const BSP_LEAF: u16 = 0x4;
+/// Faces per BSP leaf before a node is split
+const BSP_LEAF_FACES: usize = 32;
+/// Maximum depth of the BSP tree
+const BSP_MAX_DEPTH: usize = 24;
+
+/// Vertex color of indoor groups, a neutral grey so textures are not tinted
+const INDOOR_VERTEX_COLOR: [u8; 4] = [0x7F, 0x7F, 0x7F, 0xFF];
+
+/// Material of a static WMO
+#[derive(Debug, Clone, PartialEq)]
+pub struct StaticMaterial {
+    /// Path of the diffuse texture
+    pub texture: String,
+    /// Blend mode (0 opaque, 1 alpha key, 2 alpha)
+    pub blend_mode: u32,
+    /// Material flags
+    pub flags: WmoMaterialFlags,
+}
+
+impl StaticMaterial {
+    /// Create an opaque, single-sided material
+    pub fn new(texture: impl Into<String>) -> Self {
+        Self {
+            texture: texture.into(),
+            blend_mode: 0,
+            flags: WmoMaterialFlags::empty(),
+        }
+    }
+}
+
+/// Triangles drawn with one material
+#[derive(Debug, Clone, PartialEq)]
+pub struct StaticBatch {
+    /// Index of the material
+    pub material: u8,
+    /// Vertex indices, three per triangle
+    pub indices: Vec<u16>,
+}
+
+/// Builder for a WMO with a single group of static geometry
+///
+/// ```
+/// use wow_wmo::{StaticMaterial, StaticWmoBuilder, TexCoord, Vec3};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let vertex = |x, y| Vec3 { x, y, z: 0.0 };
+/// let up = Vec3 { x: 0.0, y: 0.0, z: 1.0 };
+/// let builder = StaticWmoBuilder::new("Floor")
+///     .vertices(
+///         vec![vertex(0.0, 0.0), vertex(4.0, 0.0), vertex(0.0, 4.0)],
+///         vec![up; 3],
+///         vec![TexCoord { u: 0.0, v: 0.0 }; 3],
+///     )
+///     .material(StaticMaterial::new("Tileset\\Generic\\Stone.blp"))
+///     .batch(0, vec![0, 1, 2]);
+///
+/// let mut root = Vec::new();
+/// let mut group = Vec::new();
+/// builder.write_root(&mut root)?;
+/// builder.write_group(&mut group)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct StaticWmoBuilder {
+    group_name: String,
+    positions: Vec<Vec3>,
+    normals: Vec<Vec3>,
+    tex_coords: Vec<TexCoord>,
+    materials: Vec<StaticMaterial>,
+    batches: Vec<StaticBatch>,
+    collision_positions: Vec<Vec3>,
+    collision_indices: Vec<u16>,
+    render_collision: bool,
+    indoor: bool,
+}
+
+impl StaticWmoBuilder {
+    /// Create an empty builder for a group called `group_name`
+    pub fn new(group_name: impl Into<String>) -> Self {
+        Self {
+            group_name: group_name.into(),
+            positions: Vec::new(),
+            normals: Vec::new(),
+            tex_coords: Vec::new(),
+            materials: Vec::new(),
+            batches: Vec::new(),
+            collision_positions: Vec::new(),
+            collision_indices: Vec::new(),
+            render_collision: true,
+            indoor: false,
+        }
+    }
+
+    /// Set the render vertices; all three lists must have the same length
+    pub fn vertices(
+        mut self,
+        positions: Vec<Vec3>,
+        normals: Vec<Vec3>,
+        tex_coords: Vec<TexCoord>,
+    ) -> Self {
+        self.positions = positions;
+        self.normals = normals;
+        self.tex_coords = tex_coords;
+        self
+    }
+
+    /// Add a material, batches refer to materials in the order they were added
+    pub fn material(mut self, material: StaticMaterial) -> Self {
+        self.materials.push(material);
+        self
+    }
+
+    /// Add a batch of triangles drawn with `material`
+    pub fn batch(mut self, material: u8, indices: Vec<u16>) -> Self {
+        self.batches.push(StaticBatch { material, indices });
+        self
+    }
+
+    /// Add invisible collision geometry
+    ///
+    /// Collision triangles are stored after the render triangles and are
+    /// never drawn. Use [`render_collision`](Self::render_collision) to stop
+    /// the render triangles from colliding as well.
+    pub fn collision(mut self, positions: Vec<Vec3>, indices: Vec<u16>) -> Self {
+        self.collision_positions = positions;
+        self.collision_indices = indices;
+        self
+    }
+
+    /// Whether render triangles collide (the default)
+    pub fn render_collision(mut self, collide: bool) -> Self {
+        self.render_collision = collide;
+        self
+    }
+
+    /// Mark the group as indoors instead of outdoors
+    ///
+    /// Indoor groups are not lit by the sun; they get neutral vertex colors
+    /// so the object is not rendered black.
+    pub fn indoor(mut self, indoor: bool) -> Self {
+        self.indoor = indoor;
+        self
+    }
+
+    /// Bounding box of all render and collision vertices
+    pub fn bounding_box(&self) -> BoundingBox {
+        bounds(self.positions.iter().chain(&self.collision_positions))
+    }
+
+    /// Number of triangles in the group, render and collision
+    pub fn triangle_count(&self) -> usize {
+        self.batches
+            .iter()
+            .map(|batch| batch.indices.len() / 3)
+            .sum::<usize>()
+            + self.collision_indices.len() / 3
+    }
+
+    /// Write the root file
+    pub fn write_root<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.validate()?;
+        let bounding_box = self.bounding_box();
+
+        write_chunk(writer, chunks::MVER, &VERSION.to_le_bytes())?;
+
+        let mut mohd = Vec::with_capacity(64);
+        for count in [self.materials.len() as u32, 1, 0, 0, 0, 0, 1] {
+            mohd.extend_from_slice(&count.to_le_bytes());
+        }
+        mohd.extend_from_slice(&INDOOR_VERTEX_COLOR); // ambient color
+        mohd.extend_from_slice(&0u32.to_le_bytes()); // WMOAreaTable id
+        push_bounding_box(&mut mohd, &bounding_box);
+        mohd.extend_from_slice(&0u16.to_le_bytes()); // flags
+        mohd.extend_from_slice(&0u16.to_le_bytes()); // LOD count
+        write_chunk(writer, chunks::MOHD, &mohd)?;
+
+        // Texture names start at 4-byte aligned offsets
+        let mut motx = Vec::new();
+        let mut texture_offsets = Vec::with_capacity(self.materials.len());
+        for material in &self.materials {
+            texture_offsets.push(motx.len() as u32);
+            motx.extend_from_slice(material.texture.as_bytes());
+            motx.push(0);
+            motx.resize(motx.len().next_multiple_of(4), 0);
+        }
+        write_chunk(writer, chunks::MOTX, &motx)?;
+
+        let mut momt = Vec::with_capacity(self.materials.len() * 64);
+        for (material, &texture) in self.materials.iter().zip(&texture_offsets) {
+            momt.extend_from_slice(&material.flags.bits().to_le_bytes());
+            momt.extend_from_slice(&0u32.to_le_bytes()); // shader
+            momt.extend_from_slice(&material.blend_mode.to_le_bytes());
+            momt.extend_from_slice(&texture.to_le_bytes());
+            // Emissive colors, second texture, diffuse color, ground type,
+            // third texture, second color, second flags and runtime data
+            momt.resize(momt.len() + 48, 0);
+        }
+        write_chunk(writer, chunks::MOMT, &momt)?;
+
+        let mut mogn = self.group_name.as_bytes().to_vec();
+        mogn.push(0);
+        mogn.resize(mogn.len().next_multiple_of(4), 0);
+        write_chunk(writer, chunks::MOGN, &mogn)?;
+
+        let mut mogi = Vec::with_capacity(32);
+        mogi.extend_from_slice(&self.group_flags().to_le_bytes());
+        push_bounding_box(&mut mogi, &bounding_box);
+        mogi.extend_from_slice(&0i32.to_le_bytes()); // name offset
+        write_chunk(writer, chunks::MOGI, &mogi)?;
+
+        write_chunk(writer, chunks::MOSB, &[0; 4])?;
+        for id in [
+            chunks::MOPV,
+            chunks::MOPT,
+            chunks::MOPR,
+            chunks::MOVV,
+            chunks::MOVB,
+            chunks::MOLT,
+        ] {
+            write_chunk(writer, id, &[])?;
+        }
+
+        let mut mods = [0u8; 32];
+        let set_name = b"Set_$DefaultGlobal";
+        mods[..set_name.len()].copy_from_slice(set_name);
+        write_chunk(writer, chunks::MODS, &mods)?;
+        write_chunk(writer, chunks::MODN, &[])?;
+        write_chunk(writer, chunks::MODD, &[])?;
+
+        // One fog with the client's default distances, referenced by the group
+        let mut mfog = Vec::with_capacity(48);
+        mfog.resize(24, 0); // flags, position and radii
+        for (end, start_scalar) in [(444.4445f32, 0.25f32), (222.2222, -0.5)] {
+            mfog.extend_from_slice(&end.to_le_bytes());
+            mfog.extend_from_slice(&start_scalar.to_le_bytes());
+            mfog.extend_from_slice(&0xFF00_0000u32.to_le_bytes());
+        }
+        write_chunk(writer, chunks::MFOG, &mfog)
+    }
+
+    /// Write the group file, usually saved as `<root>_000.wmo`
+    pub fn write_group<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.validate()?;
+        let bounding_box = self.bounding_box();
+        let collision_base = self.positions.len() as u16;
+
+        let mut indices = Vec::with_capacity(self.triangle_count() * 3);
+        let mut mopy = Vec::with_capacity(self.triangle_count() * 2);
+        let mut moba = Vec::with_capacity(self.batches.len() * 24);
+        let render_flags = if self.render_collision {
+            TRIANGLE_RENDER
+        } else {
+            TRIANGLE_RENDER | TRIANGLE_DETAIL
+        };
+        for batch in &self.batches {
+            let start = indices.len() as u32;
+            indices.extend_from_slice(&batch.indices);
+            for _ in 0..batch.indices.len() / 3 {
+                mopy.extend_from_slice(&[render_flags, batch.material]);
+            }
+
+            let min_index = batch.indices.iter().copied().min().unwrap_or(0);
+            let max_index = batch.indices.iter().copied().max().unwrap_or(0);
+            let batch_box = bounds(batch.indices.iter().map(|&i| &self.positions[i as usize]));
+            for value in [
+                batch_box.min.x.floor(),
+                batch_box.min.y.floor(),
+                batch_box.min.z.floor(),
+                batch_box.max.x.ceil(),
+                batch_box.max.y.ceil(),
+                batch_box.max.z.ceil(),
+            ] {
+                moba.extend_from_slice(&(value as i16).to_le_bytes());
+            }
+            moba.extend_from_slice(&start.to_le_bytes());
+            moba.extend_from_slice(&(batch.indices.len() as u16).to_le_bytes());
+            moba.extend_from_slice(&min_index.to_le_bytes());
+            moba.extend_from_slice(&max_index.to_le_bytes());
+            moba.extend_from_slice(&[0, batch.material]);
+        }
+        indices.extend(self.collision_indices.iter().map(|i| i + collision_base));
+        for _ in 0..self.collision_indices.len() / 3 {
+            mopy.extend_from_slice(&[TRIANGLE_COLLISION, NO_MATERIAL]);
+        }
+
+        let positions: Vec<Vec3> = self
+            .positions
+            .iter()
+            .chain(&self.collision_positions)
+            .copied()
+            .collect();
+        let up = Vec3 {
+            x: 0.0,
+            y: 0.0,
+            z: 1.0,
+        };
+        let normals = self
+            .normals
+            .iter()
+            .chain(std::iter::repeat_n(&up, self.collision_positions.len()));
+        let tex_coords = self.tex_coords.iter().copied().chain(std::iter::repeat_n(
+            TexCoord { u: 0.0, v: 0.0 },
+            self.collision_positions.len(),
+        ));
+
+        let mut body = Vec::new();
+        write_chunk(&mut body, chunks::MOPY, &mopy)?;
+        write_chunk(
+            &mut body,
+            chunks::MOVI,
+            &indices
+                .iter()
+                .flat_map(|i| i.to_le_bytes())
+                .collect::<Vec<u8>>(),
+        )?;
+        write_chunk(&mut body, chunks::MOVT, &vector_bytes(positions.iter()))?;
+        write_chunk(&mut body, chunks::MONR, &vector_bytes(normals))?;
+        write_chunk(
+            &mut body,
+            chunks::MOTV,
+            &tex_coords
+                .flat_map(|uv| [uv.u.to_le_bytes(), uv.v.to_le_bytes()])
+                .flatten()
+                .collect::<Vec<u8>>(),
+        )?;
+        write_chunk(&mut body, chunks::MOBA, &moba)?;
+        if self.indoor {
+            write_chunk(
+                &mut body,
+                chunks::MOCV,
+                &INDOOR_VERTEX_COLOR.repeat(positions.len()),
+            )?;
+        }
+
+        let (nodes, faces) = build_bsp(&positions, &indices);
+        let mut mobn = Vec::with_capacity(nodes.len() * 16);
+        for node in &nodes {
+            mobn.extend_from_slice(&node.flags.to_le_bytes());
+            mobn.extend_from_slice(&node.negative.to_le_bytes());
+            mobn.extend_from_slice(&node.positive.to_le_bytes());
+            mobn.extend_from_slice(&node.face_count.to_le_bytes());
+            mobn.extend_from_slice(&node.face_start.to_le_bytes());
+            mobn.extend_from_slice(&node.distance.to_le_bytes());
+        }
+        write_chunk(&mut body, chunks::MOBN, &mobn)?;
+        write_chunk(
+            &mut body,
+            chunks::MOBR,
+            &faces
+                .iter()
+                .flat_map(|f| f.to_le_bytes())
+                .collect::<Vec<u8>>(),
+        )?;
+
+        let batch_count = self.batches.len() as u16;
+        let (interior_batches, exterior_batches) = if self.indoor {
+            (batch_count, 0)
+        } else {
+            (0, batch_count)
+        };
+        let mut mogp = Vec::with_capacity(68 + body.len());
+        mogp.extend_from_slice(&0u32.to_le_bytes()); // name offset
+        mogp.extend_from_slice(&0u32.to_le_bytes()); // descriptive name offset
+        mogp.extend_from_slice(&self.group_flags().to_le_bytes());
+        push_bounding_box(&mut mogp, &bounding_box);
+        for value in [0u16, 0, 0, interior_batches, exterior_batches, 0] {
+            mogp.extend_from_slice(&value.to_le_bytes());
+        }
+        mogp.extend_from_slice(&[0; 4]); // fog ids, all the default fog
+        // Liquid, WMOAreaTable id, second flags and split group indices
+        mogp.resize(mogp.len() + 16, 0);
+        mogp.extend_from_slice(&body);
+
+        write_chunk(writer, chunks::MVER, &VERSION.to_le_bytes())?;
+        write_chunk(writer, chunks::MOGP, &mogp)
+    }
+
+    /// Group flags shared by MOGI and MOGP
+    fn group_flags(&self) -> u32 {
+        if self.indoor {
+            GROUP_HAS_BSP | GROUP_HAS_VERTEX_COLORS | GROUP_INDOOR
+        } else {
+            GROUP_HAS_BSP | GROUP_EXTERIOR
+        }
+    }
+
+    /// Check that the geometry fits the format before anything is written
+    fn validate(&self) -> Result<()> {
+        if self.normals.len() != self.positions.len()
+            || self.tex_coords.len() != self.positions.len()
+        {
+            return Err(WmoError::InvalidFormat(format!(
+                "{} positions, {} normals and {} texture coordinates",
+                self.positions.len(),
+                self.normals.len(),
+                self.tex_coords.len()
+            )));
+        }
+        let vertex_count = self.positions.len() + self.collision_positions.len();
+        if vertex_count > u16::MAX as usize {
+            return Err(WmoError::InvalidReference {
+                field: "vertex count".to_string(),
+                value: vertex_count as u32,
+                max: u16::MAX as u32,
+            });
+        }
+        let triangle_count = self.triangle_count();
+        if triangle_count > u16::MAX as usize {
+            return Err(WmoError::InvalidReference {
+                field: "triangle count".to_string(),
+                value: triangle_count as u32,
+                max: u16::MAX as u32,
+            });
+        }
+        if self.materials.len() >= NO_MATERIAL as usize {
+            return Err(WmoError::InvalidReference {
+                field: "material count".to_string(),
+                value: self.materials.len() as u32,
+                max: NO_MATERIAL as u32 - 1,
+            });
+        }
+
+        for batch in &self.batches {
+            if batch.material as usize >= self.materials.len() {
+                return Err(WmoError::InvalidReference {
+                    field: "batch material".to_string(),
+                    value: batch.material as u32,
+                    max: self.materials.len().saturating_sub(1) as u32,
+                });
+            }
+            check_triangles("batch", &batch.indices, self.positions.len())?;
+        }
+        check_triangles(
+            "collision",
+            &self.collision_indices,
+            self.collision_positions.len(),
+        )
+    }
+}
+
+/// Check that `indices` form whole triangles of existing vertices
+fn check_triangles(kind: &str, indices: &[u16], vertex_count: usize) -> Result<()> {
+    if !indices.len().is_multiple_of(3) {
+        return Err(WmoError::InvalidFormat(format!(
+            "{kind} has {} indices, which is not a whole number of triangles",
+            indices.len()
+        )));
+    }
+    if let Some(&index) = indices.iter().find(|&&i| i as usize >= vertex_count) {
+        return Err(WmoError::InvalidReference {
+            field: format!("{kind} vertex index"),
+            value: index as u32,
+            max: vertex_count.saturating_sub(1) as u32,
+        });
+    }
+    Ok(())
+}
+
+fn write_chunk<W: Write>(writer: &mut W, id: ChunkId, data: &[u8]) -> Result<()> {
+    ChunkHeader {
+        id,
+        size: data.len() as u32,
+    }
+    .write(writer)?;
+    writer.write_all(data)?;
+    Ok(())
+}
+
+fn vector_bytes<'a>(vectors: impl Iterator<Item = &'a Vec3>) -> Vec<u8> {
+    vectors
+        .flat_map(|v| [v.x, v.y, v.z])
+        .flat_map(f32::to_le_bytes)
+        .collect()
+}
+
+fn push_bounding_box(data: &mut Vec<u8>, bounding_box: &BoundingBox) {
+    let BoundingBox { min, max } = bounding_box;
+    for value in [min.x, min.y, min.z, max.x, max.y, max.z] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+}
+
+fn component(v: &Vec3, axis: usize) -> f32 {
+    match axis {
+        0 => v.x,
+        1 => v.y,
+        _ => v.z,
+    }
+}
+
+/// Bounding box of `positions`, all zero if there are none
+fn bounds<'a>(positions: impl Iterator<Item = &'a Vec3>) -> BoundingBox {
+    let mut positions = positions.peekable();
+    let Some(&first) = positions.peek() else {
+        return BoundingBox {
+            min: Vec3::default(),
+            max: Vec3::default(),
+        };
+    };
+    positions.fold(
+        BoundingBox {
+            min: *first,
+            max: *first,
+        },
+        |bounding_box, p| BoundingBox {
+            min: Vec3 {
+                x: bounding_box.min.x.min(p.x),
+                y: bounding_box.min.y.min(p.y),
+                z: bounding_box.min.z.min(p.z),
+            },
+            max: Vec3 {
+                x: bounding_box.max.x.max(p.x),
+                y: bounding_box.max.y.max(p.y),
+                z: bounding_box.max.z.max(p.z),
+            },
+        },
+    )
+}
+
+/// A MOBN entry
+struct BspNode {
+    flags: u16,
+    negative: i16,
+    positive: i16,
+    face_count: u16,
+    face_start: u32,
+    distance: f32,
+}
+
+/// Build a BSP tree over all triangles, returning MOBN nodes and MOBR faces
+///
+/// Nodes split the longest axis of their bounds in half until a leaf holds
+/// at most [`BSP_LEAF_FACES`] triangles. Triangles crossing a plane are
+/// referenced from both sides.
+fn build_bsp(positions: &[Vec3], indices: &[u16]) -> (Vec<BspNode>, Vec<u16>) {
+    let triangles: Vec<[Vec3; 3]> = indices
+        .chunks_exact(3)
+        .map(|t| {
+            [
+                positions[t[0] as usize],
+                positions[t[1] as usize],
+                positions[t[2] as usize],
+            ]
+        })
+        .collect();
+    let faces: Vec<u16> = (0..triangles.len() as u16).collect();
+
+    let mut nodes = Vec::new();
+    let mut references = Vec::new();
+    build_bsp_node(
+        &triangles,
+        faces,
+        bounds(positions.iter()),
+        0,
+        &mut nodes,
+        &mut references,
+    );
+
+    // Node indices are signed 16-bit; fall back to a single leaf rather than
+    // writing a tree the client cannot address
+    if nodes.len() > i16::MAX as usize {
+        let node_faces = (0..triangles.len() as u16).collect();
+        nodes.clear();
+        references.clear();
+        build_bsp_node(
+            &triangles,
+            node_faces,
+            bounds(positions.iter()),
+            BSP_MAX_DEPTH,
+            &mut nodes,
+            &mut references,
+        );
+    }
+    (nodes, references)
+}
+
+fn build_bsp_node(
+    triangles: &[[Vec3; 3]],
+    faces: Vec<u16>,
+    bounding_box: BoundingBox,
+    depth: usize,
+    nodes: &mut Vec<BspNode>,
+    references: &mut Vec<u16>,
+) -> i16 {
+    let index = nodes.len();
+    nodes.push(BspNode {
+        flags: BSP_LEAF,
+        negative: -1,
+        positive: -1,
+        face_count: faces.len() as u16,
+        face_start: references.len() as u32,
+        distance: 0.0,
+    });
+
+    let extent = |axis| component(&bounding_box.max, axis) - component(&bounding_box.min, axis);
+    let axis = (0..3)
+        .max_by(|&a, &b| extent(a).total_cmp(&extent(b)))
+        .unwrap_or(0);
+    let distance = component(&bounding_box.min, axis) + extent(axis) / 2.0;
+
+    let (negative, positive): (Vec<u16>, Vec<u16>) = (
+        faces
+            .iter()
+            .copied()
+            .filter(|&f| {
+                let t = &triangles[f as usize];
+                t.iter().any(|v| component(v, axis) <= distance)
+            })
+            .collect(),
+        faces
+            .iter()
+            .copied()
+            .filter(|&f| {
+                let t = &triangles[f as usize];
+                t.iter().any(|v| component(v, axis) >= distance)
+            })
+            .collect(),
+    );
+
+    // Stop when splitting no longer separates triangles
+    if faces.len() <= BSP_LEAF_FACES
+        || depth >= BSP_MAX_DEPTH
+        || negative.len() == faces.len()
+        || positive.len() == faces.len()
+    {
+        references.extend_from_slice(&faces);
+        return index as i16;
+    }
+
+    let mut negative_box = bounding_box;
+    let mut positive_box = bounding_box;
+    match axis {
+        0 => (negative_box.max.x, positive_box.min.x) = (distance, distance),
+        1 => (negative_box.max.y, positive_box.min.y) = (distance, distance),
+        _ => (negative_box.max.z, positive_box.min.z) = (distance, distance),
+    }
+
+    let negative = build_bsp_node(
+        triangles,
+        negative,
+        negative_box,
+        depth + 1,
+        nodes,
+        references,
+    );
+    let positive = build_bsp_node(
+        triangles,
+        positive,
+        positive_box,
+        depth + 1,
+        nodes,
+        references,
+    );
+    nodes[index] = BspNode {
+        flags: axis as u16,
+        negative,
+        positive,
+        face_count: 0,
+        face_start: 0,
+        distance,
+    };
+    index as i16
+}
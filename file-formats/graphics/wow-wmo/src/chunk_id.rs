@@ -58,6 +58,8 @@ impl ChunkId {
             b"ABOM" => "MOBA",
             b"RLOM" => "MOLR",
             b"RDOM" => "MODR",
+            b"SLOM" => "MOLS",
+            b"PLOM" => "MOLP",
             b"NBOM" => "MOBN",
             b"RBOM" => "MOBR",
             b"VCOM" => "MOCV",
@@ -101,6 +101,58 @@ pub struct MoltEntry {
     pub attenuation_end: f32,
 }
 
+impl MoltEntry {
+    /// Light type (omni, spot, directional or ambient)
+    pub fn light_type(&self) -> crate::WmoLightType {
+        crate::WmoLightType::from_raw(self.light_type).unwrap_or(crate::WmoLightType::Omni)
+    }
+
+    /// Whether the attenuation range limits the light
+    pub fn uses_attenuation(&self) -> bool {
+        self.use_attenuation != 0
+    }
+}
+
+/// MOLS - Spot lights of a group (Legion+)
+///
+/// 56 bytes per entry. Fields past the cone angles are not understood and
+/// are kept verbatim.
+#[derive(Debug, Clone, BinRead)]
+#[br(little)]
+pub struct MolsEntry {
+    pub unknown0: u32,
+    /// Color as BGRA
+    pub color: [u8; 4],
+    pub position: [f32; 3],
+    pub attenuation_start: f32,
+    pub attenuation_end: f32,
+    pub intensity: f32,
+    /// Direction the cone points to
+    pub direction: [f32; 3],
+    /// Angle where the falloff starts, in radians
+    pub inner_angle: f32,
+    /// Angle of the cone edge, in radians
+    pub outer_angle: f32,
+    pub unknown1: u32,
+}
+
+/// MOLP - Point lights of a group (Legion+)
+///
+/// 48 bytes per entry. The trailing fields are not understood and are kept
+/// verbatim.
+#[derive(Debug, Clone, BinRead)]
+#[br(little)]
+pub struct MolpEntry {
+    pub unknown0: u32,
+    /// Color as BGRA
+    pub color: [u8; 4],
+    pub position: [f32; 3],
+    pub attenuation_start: f32,
+    pub attenuation_end: f32,
+    pub intensity: f32,
+    pub unknown1: [u32; 4],
+}
+
 /// MODS - Doodad sets chunk
 #[derive(Debug, Clone, BinRead)]
 #[br(little)]
@@ -128,6 +180,18 @@ impl ModdEntry {
         self.name_index_and_flags & 0x00FFFFFF
     }
 
+    /// Resolve the model path; the name index is a byte offset into MODN
+    pub fn get_name<'a>(
+        &self,
+        doodad_names: &'a [String],
+        name_offset_index_map: &HashMap<u32, u32>,
+    ) -> Option<&'a str> {
+        name_offset_index_map
+            .get(&self.name_index())
+            .and_then(|&i| doodad_names.get(i as usize))
+            .map(String::as_str)
+    }
+
     /// Check if accepts projected textures (bit 24)
     pub fn accepts_proj_tex(&self) -> bool {
         (self.name_index_and_flags & 0x01000000) != 0
@@ -379,6 +443,7 @@ pub struct MovbEntry {
 #[derive(Debug, Clone)]
 pub struct Modn {
     pub names: Vec<String>,
+    pub name_offset_index_map: HashMap<u32, u32>,
 }
 
 /// MOMO - Alpha version container chunk (version 14 only)
@@ -497,17 +562,22 @@ impl Modn {
     pub fn parse(data: &[u8]) -> Result<Self, Box<dyn std::error::Error>> {
         let mut names = Vec::new();
         let mut start = 0;
+        let mut name_offset_index_map = HashMap::new();
 
         for i in 0..data.len() {
             if data[i] == 0 {
                 if i > start {
                     let name = String::from_utf8(data[start..i].to_vec())?;
                     names.push(name);
+                    name_offset_index_map.insert(start as u32, names.len() as u32 - 1);
                 }
                 start = i + 1;
             }
         }
 
-        Ok(Self { names })
+        Ok(Self {
+            names,
+            name_offset_index_map,
+        })
     }
 }
@@ -2,11 +2,145 @@ use crate::error::{Result, WmoError};
 use crate::version::{WmoFeature, WmoVersion};
 use crate::wmo_group_types::{WmoGroup, WmoLiquid};
 use crate::wmo_types::{WmoFlags, WmoHeader, WmoMaterial, WmoMaterialFlags, WmoRoot};
+use std::fmt;
 use tracing::{info, warn};
 
 // Use WmoGroupFlags from wmo_group_types since that's where WmoGroupHeader uses it
 use crate::wmo_group_types::WmoGroupFlags;
 
+/// How a conversion changed part of a WMO
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConversionChangeKind {
+    /// Data the target version cannot hold was removed
+    Dropped,
+    /// Data the target version expects was filled in with defaults
+    Synthesized,
+    /// Data was carried over in a form that only approximates the original
+    Approximated,
+}
+
+impl fmt::Display for ConversionChangeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Dropped => "dropped",
+            Self::Synthesized => "synthesized",
+            Self::Approximated => "approximated",
+        })
+    }
+}
+
+/// A single change made while converting a WMO
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConversionChange {
+    /// What kind of change this is
+    pub kind: ConversionChangeKind,
+    /// Chunk the change affects, such as `MOSB`
+    pub chunk: &'static str,
+    /// Group the change belongs to, for group file changes
+    pub group: Option<usize>,
+    /// What was changed
+    pub description: String,
+}
+
+impl fmt::Display for ConversionChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.kind, self.chunk)?;
+        if let Some(group) = self.group {
+            write!(f, " (group {group})")?;
+        }
+        write!(f, ": {}", self.description)
+    }
+}
+
+/// What a version conversion dropped, synthesized or approximated
+///
+/// An empty report means the converted WMO carries the same information as
+/// the original.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConversionReport {
+    /// Version converted from
+    pub from: WmoVersion,
+    /// Version converted to
+    pub to: WmoVersion,
+    /// Changes in the order they were made
+    pub changes: Vec<ConversionChange>,
+}
+
+impl ConversionReport {
+    /// An empty report for a conversion between two versions
+    pub fn new(from: WmoVersion, to: WmoVersion) -> Self {
+        Self {
+            from,
+            to,
+            changes: Vec::new(),
+        }
+    }
+
+    /// Whether nothing was dropped or approximated
+    ///
+    /// Synthesized data only adds defaults, so it does not make a
+    /// conversion lossy.
+    pub fn is_lossless(&self) -> bool {
+        self.changes
+            .iter()
+            .all(|change| change.kind == ConversionChangeKind::Synthesized)
+    }
+
+    /// Changes of one kind
+    pub fn changes_of(
+        &self,
+        kind: ConversionChangeKind,
+    ) -> impl Iterator<Item = &ConversionChange> + '_ {
+        self.changes
+            .iter()
+            .filter(move |change| change.kind == kind)
+    }
+
+    /// Append the changes of a group conversion, tagged with its index
+    pub fn merge_group(&mut self, group: usize, report: ConversionReport) {
+        self.changes
+            .extend(report.changes.into_iter().map(|change| ConversionChange {
+                group: Some(group),
+                ..change
+            }));
+    }
+
+    fn push(
+        &mut self,
+        kind: ConversionChangeKind,
+        chunk: &'static str,
+        description: impl Into<String>,
+    ) {
+        let description = description.into();
+        warn!("Conversion {kind} {chunk}: {description}");
+        self.changes.push(ConversionChange {
+            kind,
+            chunk,
+            group: None,
+            description,
+        });
+    }
+}
+
+impl fmt::Display for ConversionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} -> {}: ",
+            self.from.expansion_name(),
+            self.to.expansion_name()
+        )?;
+        if self.changes.is_empty() {
+            return f.write_str("no changes");
+        }
+        write!(f, "{} change(s)", self.changes.len())?;
+        for change in &self.changes {
+            write!(f, "\n  {change}")?;
+        }
+        Ok(())
+    }
+}
+
 /// Converter for WMO files between different versions
 pub struct WmoConverter;
 
@@ -23,13 +157,21 @@ impl WmoConverter {
     }
 
     /// Convert a WMO root file from its current version to a target version
-    pub fn convert_root(&self, wmo: &mut WmoRoot, target_version: WmoVersion) -> Result<()> {
+    ///
+    /// The returned report lists everything the conversion dropped,
+    /// synthesized or approximated.
+    pub fn convert_root(
+        &self,
+        wmo: &mut WmoRoot,
+        target_version: WmoVersion,
+    ) -> Result<ConversionReport> {
+        let mut report = ConversionReport::new(wmo.version, target_version);
         if wmo.version == target_version {
             info!(
                 "WMO is already at target version {}",
                 target_version.to_raw()
             );
-            return Ok(());
+            return Ok(report);
         }
 
         // Check if conversion is supported
@@ -57,40 +199,76 @@ impl WmoConverter {
         );
 
         // Update header flags based on version
+        let old_flags = wmo.header.flags;
         self.convert_header_flags(&mut wmo.header, wmo.version, target_version);
 
         // Update materials for version changes
-        self.convert_materials(&mut wmo.materials, wmo.version, target_version)?;
+        self.convert_materials(&mut wmo.materials, wmo.version, target_version, &mut report)?;
 
         // Handle skybox changes
-        if !target_version.supports_feature(WmoFeature::SkyboxReferences) && wmo.skybox.is_some() {
-            warn!(
-                "Target version {} does not support skyboxes, removing skybox",
-                target_version.to_raw()
+        if !target_version.supports_feature(WmoFeature::SkyboxReferences)
+            && let Some(skybox) = wmo.skybox.take()
+        {
+            report.push(
+                ConversionChangeKind::Dropped,
+                "MOSB",
+                format!(
+                    "skybox model {skybox:?} (needs {})",
+                    WmoFeature::SkyboxReferences.min_version().expansion_name()
+                ),
             );
-            wmo.skybox = None;
             wmo.header.flags &= !WmoFlags::HAS_SKYBOX;
         }
 
+        let cleared = old_flags & !wmo.header.flags;
+        if !cleared.is_empty() {
+            report.push(
+                ConversionChangeKind::Dropped,
+                "MOHD",
+                format!("header flags {:#x}", cleared.bits()),
+            );
+        }
+
+        // Transport collision planes only exist from Cataclysm on
+        if !target_version.supports_feature(WmoFeature::ConvexVolumePlanes)
+            && let Some(planes) = wmo.convex_volume_planes.take()
+        {
+            report.push(
+                ConversionChangeKind::Dropped,
+                "MCVP",
+                format!(
+                    "{} convex volume planes (needs {})",
+                    planes.planes.len(),
+                    WmoFeature::ConvexVolumePlanes
+                        .min_version()
+                        .expansion_name()
+                ),
+            );
+        }
+
         // Update version number
         wmo.version = target_version;
 
-        Ok(())
+        Ok(report)
     }
 
     /// Convert a WMO group file from its current version to a target version
+    ///
+    /// The returned report lists everything the conversion dropped,
+    /// synthesized or approximated.
     pub fn convert_group(
         &self,
         group: &mut WmoGroup,
         target_version: WmoVersion,
         current_version: WmoVersion,
-    ) -> Result<()> {
+    ) -> Result<ConversionReport> {
+        let mut report = ConversionReport::new(current_version, target_version);
         if current_version == target_version {
             info!(
                 "WMO group is already at target version {}",
                 target_version.to_raw()
             );
-            return Ok(());
+            return Ok(report);
         }
 
         // Check if conversion is supported
@@ -118,7 +296,16 @@ impl WmoConverter {
         );
 
         // Update group flags based on version
+        let old_flags = group.header.flags;
         self.convert_group_flags(&mut group.header.flags, current_version, target_version);
+        let cleared = old_flags & !group.header.flags;
+        if !cleared.is_empty() {
+            report.push(
+                ConversionChangeKind::Dropped,
+                "MOGP",
+                format!("group flags {:#x}", cleared.bits()),
+            );
+        }
 
         // Handle liquid data changes if needed
         if let Some(ref mut liquid) = group.liquid {
@@ -127,15 +314,33 @@ impl WmoConverter {
             {
                 // Upgrade to LiquidV2 format (more complex liquid data)
                 self.upgrade_liquid_to_v2(liquid)?;
+                report.push(
+                    ConversionChangeKind::Synthesized,
+                    "MLIQ",
+                    format!(
+                        "{} liquid vertices widened to the {} layout",
+                        liquid.vertices.len(),
+                        WmoFeature::LiquidV2.min_version().expansion_name()
+                    ),
+                );
             } else if target_version < WmoVersion::Wod
                 && current_version.supports_feature(WmoFeature::LiquidV2)
             {
                 // Downgrade from LiquidV2 format (simplify liquid data)
                 self.downgrade_liquid_from_v2(liquid)?;
+                report.push(
+                    ConversionChangeKind::Approximated,
+                    "MLIQ",
+                    format!(
+                        "{} liquid vertices are written as heights only, their base \
+                         positions are not kept",
+                        liquid.vertices.len()
+                    ),
+                );
             }
         }
 
-        Ok(())
+        Ok(report)
     }
 
     /// Convert header flags based on version changes
@@ -159,19 +364,32 @@ impl WmoConverter {
     /// Convert material definitions based on version changes
     fn convert_materials(
         &self,
-        materials: &mut Vec<WmoMaterial>,
+        materials: &mut [WmoMaterial],
         from_version: WmoVersion,
         to_version: WmoVersion,
+        report: &mut ConversionReport,
     ) -> Result<()> {
         // If downgrading to before MoP, ensure materials use old format
         if to_version < WmoVersion::Mop && from_version >= WmoVersion::Mop {
-            for material in materials {
+            let shadow_batches =
+                WmoMaterialFlags::SHADOW_BATCH_1 | WmoMaterialFlags::SHADOW_BATCH_2;
+            let mut affected = 0;
+            for material in materials.iter_mut() {
                 // Clear flags not supported in older versions
-                material.flags &=
-                    !(WmoMaterialFlags::SHADOW_BATCH_1 | WmoMaterialFlags::SHADOW_BATCH_2);
+                if material.flags.intersects(shadow_batches) {
+                    material.flags &= !shadow_batches;
+                    affected += 1;
+                }
 
                 // Reset any extended material properties (would be fields beyond the basic format)
             }
+            if affected > 0 {
+                report.push(
+                    ConversionChangeKind::Dropped,
+                    "MOMT",
+                    format!("shadow batch flags of {affected} material(s)"),
+                );
+            }
         }
 
         // If upgrading to or beyond MoP, ensure extended material format
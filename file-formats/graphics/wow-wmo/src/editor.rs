@@ -3,17 +3,29 @@
 //! This module provides a user-friendly interface for modifying WMO files,
 //! including materials, groups, transformations, and doodad management.
 
-use crate::converter::WmoConverter;
+use crate::converter::{ConversionReport, WmoConverter};
 use crate::error::{Result, WmoError};
-use crate::types::{BoundingBox, Vec3};
+use crate::types::{BoundingBox, Color, Vec3};
 use crate::version::WmoVersion;
 use crate::wmo_group_types::{WmoGroup, WmoGroupHeader};
-use crate::wmo_types::{WmoDoodadDef, WmoDoodadSet, WmoGroupInfo, WmoMaterial, WmoRoot};
-use crate::writer::WmoWriter;
+use crate::wmo_types::{WmoDoodadDef, WmoDoodadSet, WmoGroupInfo, WmoLight, WmoMaterial, WmoRoot};
+use crate::writer::{WmoWriter, group_name_offsets};
 
 // Use WmoGroupFlags from wmo_group_types since that's where WmoGroupHeader uses it
 use crate::wmo_group_types::WmoGroupFlags;
 
+/// Group flag telling the client to read the group's light references (MOLR)
+const GROUP_HAS_LIGHTS: u32 = 0x200;
+
+/// Group flag for groups lit and culled as outdoors
+const GROUP_EXTERIOR: u32 = 0x8;
+
+/// Group flag for groups lit and culled as indoors
+const GROUP_INDOOR: u32 = 0x2000;
+
+/// Group flag for antiportal groups, which hide what lies behind them
+const GROUP_ANTIPORTAL: u32 = 0x400_0000;
+
 /// WMO editor for modifying WMO files
 pub struct WmoEditor {
     /// Root WMO data
@@ -89,6 +101,7 @@ impl WmoEditor {
                 vertex_colors: None,
                 bsp_nodes: None,
                 liquid: None,
+                light_refs: None,
                 doodad_refs: None,
             });
         }
@@ -145,26 +158,31 @@ impl WmoEditor {
     }
 
     /// Convert to a specific version
-    pub fn convert_to_version(&mut self, target_version: WmoVersion) -> Result<()> {
+    ///
+    /// Returns what the root and group conversions dropped, synthesized or
+    /// approximated, with group changes tagged by group index.
+    pub fn convert_to_version(&mut self, target_version: WmoVersion) -> Result<ConversionReport> {
         // Only convert if necessary
         if self.root.version == target_version {
-            return Ok(());
+            return Ok(ConversionReport::new(target_version, target_version));
         }
 
         // Convert root
         let converter = WmoConverter::new();
-        converter.convert_root(&mut self.root, target_version)?;
+        let mut report = converter.convert_root(&mut self.root, target_version)?;
         self.root_modified = true;
 
         // Convert all loaded groups
         for (i, group) in self.groups.iter_mut().enumerate() {
-            converter.convert_group(group, target_version, self.original_version)?;
+            let group_report =
+                converter.convert_group(group, target_version, self.original_version)?;
+            report.merge_group(i, group_report);
             if i < self.group_modified.len() {
                 self.group_modified[i] = true;
             }
         }
 
-        Ok(())
+        Ok(report)
     }
 
     /// Get the original version of the WMO
@@ -411,6 +429,7 @@ impl WmoEditor {
             vertex_colors: None,
             bsp_nodes: None,
             liquid: None,
+            light_refs: None,
             doodad_refs: None,
         };
 
@@ -443,12 +462,14 @@ impl WmoEditor {
                 vertex_colors: None,
                 bsp_nodes: None,
                 liquid: None,
+                light_refs: None,
                 doodad_refs: None,
             });
         }
 
         self.groups.push(group);
         self.group_modified.push(true);
+        self.sync_group_name_offsets();
 
         group_index
     }
@@ -506,10 +527,140 @@ impl WmoEditor {
                 std::cmp::Ordering::Less => {}
             }
         }
+        self.sync_group_name_offsets();
 
         Ok(group_info)
     }
 
+    /// Name of a group (MOGN)
+    pub fn group_name(&self, index: usize) -> Option<&str> {
+        self.root.groups.get(index).map(|group| group.name.as_str())
+    }
+
+    /// Rename a group
+    ///
+    /// The MOGN string table is rebuilt on save; the name offsets of loaded
+    /// groups are updated to match.
+    pub fn set_group_name(&mut self, index: usize, name: impl Into<String>) -> Result<()> {
+        let group_info = self.group_info_mut(index)?;
+        group_info.name = name.into();
+        self.sync_group_name_offsets();
+        Ok(())
+    }
+
+    /// Flags of a group (MOGI)
+    pub fn group_flags(&self, index: usize) -> Option<WmoGroupFlags> {
+        self.root.groups.get(index).map(|group| group.flags)
+    }
+
+    /// Set the flags of a group in MOGI and, if it is loaded, in its MOGP header
+    pub fn set_group_flags(&mut self, index: usize, flags: WmoGroupFlags) -> Result<()> {
+        self.group_info_mut(index)?.flags = flags;
+        if let Some(group) = self.groups.get_mut(index) {
+            group.header.flags = flags;
+            self.mark_group_modified(index);
+        }
+        Ok(())
+    }
+
+    /// Mark a group as indoor or outdoor
+    ///
+    /// Indoor groups use the interior lighting and are only drawn through
+    /// portals; outdoor groups are lit by the sky and drawn from outside.
+    pub fn set_group_indoor(&mut self, index: usize, indoor: bool) -> Result<()> {
+        let (set, clear) = if indoor {
+            (GROUP_INDOOR, GROUP_EXTERIOR)
+        } else {
+            (GROUP_EXTERIOR, GROUP_INDOOR)
+        };
+        self.update_group_flags(index, set, clear)
+    }
+
+    /// Whether a group is flagged as indoor
+    pub fn is_group_indoor(&self, index: usize) -> Option<bool> {
+        self.group_flags(index)
+            .map(|flags| flags.bits() & GROUP_INDOOR != 0)
+    }
+
+    /// Mark a group as an antiportal, hiding whatever lies behind it
+    pub fn set_group_antiportal(&mut self, index: usize, antiportal: bool) -> Result<()> {
+        if antiportal {
+            self.update_group_flags(index, GROUP_ANTIPORTAL, 0)
+        } else {
+            self.update_group_flags(index, 0, GROUP_ANTIPORTAL)
+        }
+    }
+
+    /// Whether a group is flagged as an antiportal
+    pub fn is_group_antiportal(&self, index: usize) -> Option<bool> {
+        self.group_flags(index)
+            .map(|flags| flags.bits() & GROUP_ANTIPORTAL != 0)
+    }
+
+    /// Set the bounding box of a group in MOGI and, if it is loaded, in its MOGP header
+    ///
+    /// Use [`recalculate_group_bounding_box`](Self::recalculate_group_bounding_box)
+    /// to fit it to the group's vertices instead.
+    pub fn set_group_bounding_box(
+        &mut self,
+        index: usize,
+        bounding_box: BoundingBox,
+    ) -> Result<()> {
+        self.group_info_mut(index)?.bounding_box = bounding_box;
+        if let Some(group) = self.groups.get_mut(index) {
+            group.header.bounding_box = bounding_box;
+            self.mark_group_modified(index);
+        }
+        Ok(())
+    }
+
+    /// Set and clear raw flag bits of a group
+    fn update_group_flags(&mut self, index: usize, set: u32, clear: u32) -> Result<()> {
+        let flags = self
+            .group_flags(index)
+            .ok_or_else(|| self.group_index_error(index))?;
+        let flags = WmoGroupFlags::from_bits_retain((flags.bits() & !clear) | set);
+        self.set_group_flags(index, flags)
+    }
+
+    /// Group info entry for editing, marking the root as modified
+    fn group_info_mut(&mut self, index: usize) -> Result<&mut WmoGroupInfo> {
+        let error = self.group_index_error(index);
+        self.root_modified = true;
+        self.root.groups.get_mut(index).ok_or(error)
+    }
+
+    fn mark_group_modified(&mut self, index: usize) {
+        if let Some(modified) = self.group_modified.get_mut(index) {
+            *modified = true;
+        }
+    }
+
+    fn group_index_error(&self, index: usize) -> WmoError {
+        WmoError::InvalidReference {
+            field: "group_index".to_string(),
+            value: index as u32,
+            max: (self.root.groups.len() as u32).saturating_sub(1),
+        }
+    }
+
+    /// Point the MOGP name offsets of loaded groups at their names in the
+    /// rebuilt MOGN string table
+    fn sync_group_name_offsets(&mut self) {
+        let offsets = group_name_offsets(&self.root.groups);
+        for (index, group) in self.groups.iter_mut().enumerate() {
+            let Some(&offset) = offsets.get(index) else {
+                break;
+            };
+            if group.header.name_offset != offset {
+                group.header.name_offset = offset;
+                if let Some(modified) = self.group_modified.get_mut(index) {
+                    *modified = true;
+                }
+            }
+        }
+    }
+
     // Vertex manipulation methods
 
     /// Add a vertex to a group
@@ -855,4 +1006,111 @@ impl WmoEditor {
 
         Ok(set)
     }
+
+    // Light manipulation methods
+
+    /// All lights (MOLT)
+    pub fn lights(&self) -> &[WmoLight] {
+        &self.root.lights
+    }
+
+    /// Get a light by index
+    pub fn light(&self, index: usize) -> Option<&WmoLight> {
+        self.root.lights.get(index)
+    }
+
+    /// Get a mutable light by index
+    pub fn light_mut(&mut self, index: usize) -> Option<&mut WmoLight> {
+        if index < self.root.lights.len() {
+            self.root_modified = true;
+            self.root.lights.get_mut(index)
+        } else {
+            None
+        }
+    }
+
+    /// Add a light, returning its index
+    ///
+    /// The light only affects groups that reference it; see
+    /// [`add_group_light`](Self::add_group_light).
+    pub fn add_light(&mut self, light: WmoLight) -> usize {
+        self.root_modified = true;
+        self.root.lights.push(light);
+        self.root.header.n_lights += 1;
+
+        self.root.lights.len() - 1
+    }
+
+    /// Remove a light
+    ///
+    /// References to it are dropped from loaded groups and references to
+    /// later lights are shifted down.
+    pub fn remove_light(&mut self, index: usize) -> Result<WmoLight> {
+        if index >= self.root.lights.len() {
+            return Err(WmoError::InvalidReference {
+                field: "light_index".to_string(),
+                value: index as u32,
+                max: (self.root.lights.len() as u32).saturating_sub(1),
+            });
+        }
+
+        self.root_modified = true;
+        let light = self.root.lights.remove(index);
+        self.root.header.n_lights -= 1;
+
+        for (i, group) in self.groups.iter_mut().enumerate() {
+            let Some(refs) = &mut group.light_refs else {
+                continue;
+            };
+            let before = refs.clone();
+            refs.retain(|&light_ref| light_ref as usize != index);
+            for light_ref in refs.iter_mut() {
+                if *light_ref as usize > index {
+                    *light_ref -= 1;
+                }
+            }
+            if *refs != before && i < self.group_modified.len() {
+                self.group_modified[i] = true;
+            }
+        }
+
+        Ok(light)
+    }
+
+    /// Make a loaded group reference a light
+    pub fn add_group_light(&mut self, group_index: usize, light_index: usize) -> Result<()> {
+        if light_index >= self.root.lights.len() {
+            return Err(WmoError::InvalidReference {
+                field: "light_index".to_string(),
+                value: light_index as u32,
+                max: (self.root.lights.len() as u32).saturating_sub(1),
+            });
+        }
+        let group_count = self.groups.len() as u32;
+        let group = self
+            .groups
+            .get_mut(group_index)
+            .ok_or_else(|| WmoError::InvalidReference {
+                field: "group_index".to_string(),
+                value: group_index as u32,
+                max: group_count.saturating_sub(1),
+            })?;
+
+        let refs = group.light_refs.get_or_insert_with(Vec::new);
+        if !refs.contains(&(light_index as u16)) {
+            refs.push(light_index as u16);
+        }
+        group.header.flags =
+            WmoGroupFlags::from_bits_retain(group.header.flags.bits() | GROUP_HAS_LIGHTS);
+        if group_index < self.group_modified.len() {
+            self.group_modified[group_index] = true;
+        }
+        Ok(())
+    }
+
+    /// Set the ambient color applied to the whole WMO (MOHD)
+    pub fn set_ambient_color(&mut self, color: Color) {
+        self.root_modified = true;
+        self.root.header.ambient_color = color;
+    }
 }
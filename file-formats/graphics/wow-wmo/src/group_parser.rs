@@ -1,8 +1,8 @@
 use crate::chunk_discovery::ChunkDiscovery;
 use crate::chunk_header::ChunkHeader;
 use crate::chunks::{
-    MliqHeader, MobaEntry, MobnEntry, MobsEntry, MocvEntry, MonrEntry, MopyEntry, MorbEntry,
-    MotaEntry, MotvEntry, MovtEntry, Mpy2Entry,
+    MliqHeader, MobaEntry, MobnEntry, MobsEntry, MocvEntry, MolpEntry, MolsEntry, MonrEntry,
+    MopyEntry, MorbEntry, MotaEntry, MotvEntry, MovtEntry, Mpy2Entry,
 };
 use crate::error::{Result, WmoError};
 use crate::wmo_group_types::WmoGroup as LegacyWmoGroup;
@@ -61,14 +61,28 @@ pub struct WmoGroup {
     pub vertex_normals: Vec<MonrEntry>,
     /// Texture coordinates (MOTV)
     pub texture_coords: Vec<MotvEntry>,
+    /// Second texture coordinate set (second MOTV, Cataclysm+)
+    ///
+    /// Used by shaders that blend two textures, such as the lightmapped
+    /// interiors of later expansions. Empty when the group has one set.
+    pub texture_coords2: Vec<MotvEntry>,
     /// Render batches (MOBA)
     pub render_batches: Vec<MobaEntry>,
     /// Vertex colors (MOCV)
     pub vertex_colors: Vec<MocvEntry>,
+    /// Second vertex color set (second MOCV, Cataclysm+)
+    ///
+    /// Its alpha blends the two textures of two-layer shaders. Empty when
+    /// the group has one set.
+    pub vertex_colors2: Vec<MocvEntry>,
     /// Light references (MOLR)
     pub light_refs: Vec<u16>,
     /// Doodad references (MODR)
     pub doodad_refs: Vec<u16>,
+    /// Spot lights (MOLS - Legion+)
+    pub spot_lights: Vec<MolsEntry>,
+    /// Point lights (MOLP - Legion+)
+    pub point_lights: Vec<MolpEntry>,
     /// BSP tree nodes (MOBN)
     pub bsp_nodes: Vec<MobnEntry>,
     /// BSP face indices (MOBR)
@@ -148,10 +162,14 @@ pub fn parse_group_file<R: Read + Seek>(
         vertex_positions: Vec::new(),
         vertex_normals: Vec::new(),
         texture_coords: Vec::new(),
+        texture_coords2: Vec::new(),
         render_batches: Vec::new(),
         vertex_colors: Vec::new(),
+        vertex_colors2: Vec::new(),
         light_refs: Vec::new(),
         doodad_refs: Vec::new(),
+        spot_lights: Vec::new(),
+        point_lights: Vec::new(),
         bsp_nodes: Vec::new(),
         bsp_face_indices: Vec::new(),
         liquid_header: None,
@@ -236,10 +254,17 @@ pub fn parse_group_file<R: Read + Seek>(
                 }
             }
             "MOTV" => {
-                // Read texture coordinates
+                // Read texture coordinates; a second MOTV holds the second set
                 let count = chunk_info.size / 8; // Each coord is 2 floats (8 bytes)
-                for _ in 0..count {
-                    group.texture_coords.push(MotvEntry::read(reader)?);
+                let coords = if group.texture_coords.is_empty() {
+                    &mut group.texture_coords
+                } else {
+                    &mut group.texture_coords2
+                };
+                if coords.is_empty() {
+                    for _ in 0..count {
+                        coords.push(MotvEntry::read(reader)?);
+                    }
                 }
             }
             "MOBA" => {
@@ -250,10 +275,17 @@ pub fn parse_group_file<R: Read + Seek>(
                 }
             }
             "MOCV" => {
-                // Read vertex colors
+                // Read vertex colors; a second MOCV holds the second set
                 let count = chunk_info.size / 4; // Each color is 4 bytes
-                for _ in 0..count {
-                    group.vertex_colors.push(MocvEntry::read(reader)?);
+                let colors = if group.vertex_colors.is_empty() {
+                    &mut group.vertex_colors
+                } else {
+                    &mut group.vertex_colors2
+                };
+                if colors.is_empty() {
+                    for _ in 0..count {
+                        colors.push(MocvEntry::read(reader)?);
+                    }
                 }
             }
             "MOLR" => {
@@ -270,6 +302,20 @@ pub fn parse_group_file<R: Read + Seek>(
                     group.doodad_refs.push(reader.read_le()?);
                 }
             }
+            "MOLS" => {
+                // Read spot lights (Legion+)
+                let count = chunk_info.size / 56; // Each light is 56 bytes
+                for _ in 0..count {
+                    group.spot_lights.push(MolsEntry::read(reader)?);
+                }
+            }
+            "MOLP" => {
+                // Read point lights (Legion+)
+                let count = chunk_info.size / 48; // Each light is 48 bytes
+                for _ in 0..count {
+                    group.point_lights.push(MolpEntry::read(reader)?);
+                }
+            }
             "MOBN" => {
                 // Read BSP tree nodes
                 let count = chunk_info.size / 16; // Each node is 16 bytes
@@ -460,10 +506,17 @@ fn parse_nested_chunks<R: Read + Seek>(
                 }
             }
             "MOTV" => {
-                // Read texture coordinates
+                // Read texture coordinates; a second MOTV holds the second set
                 let count = chunk_size / 8; // Each coord is 2 floats (8 bytes)
-                for _ in 0..count {
-                    group.texture_coords.push(MotvEntry::read(reader)?);
+                let coords = if group.texture_coords.is_empty() {
+                    &mut group.texture_coords
+                } else {
+                    &mut group.texture_coords2
+                };
+                if coords.is_empty() {
+                    for _ in 0..count {
+                        coords.push(MotvEntry::read(reader)?);
+                    }
                 }
             }
             "MOBA" => {
@@ -474,10 +527,45 @@ fn parse_nested_chunks<R: Read + Seek>(
                 }
             }
             "MOCV" => {
-                // Read vertex colors
+                // Read vertex colors; a second MOCV holds the second set
                 let count = chunk_size / 4; // Each color is 4 bytes (BGRA)
+                let colors = if group.vertex_colors.is_empty() {
+                    &mut group.vertex_colors
+                } else {
+                    &mut group.vertex_colors2
+                };
+                if colors.is_empty() {
+                    for _ in 0..count {
+                        colors.push(MocvEntry::read(reader)?);
+                    }
+                }
+            }
+            "MOLR" => {
+                // Read light references
+                let count = chunk_size / 2; // Each ref is 2 bytes
+                for _ in 0..count {
+                    group.light_refs.push(reader.read_le()?);
+                }
+            }
+            "MODR" => {
+                // Read doodad references
+                let count = chunk_size / 2; // Each ref is 2 bytes
+                for _ in 0..count {
+                    group.doodad_refs.push(reader.read_le()?);
+                }
+            }
+            "MOLS" => {
+                // Read spot lights (Legion+)
+                let count = chunk_size / 56; // Each light is 56 bytes
+                for _ in 0..count {
+                    group.spot_lights.push(MolsEntry::read(reader)?);
+                }
+            }
+            "MOLP" => {
+                // Read point lights (Legion+)
+                let count = chunk_size / 48; // Each light is 48 bytes
                 for _ in 0..count {
-                    group.vertex_colors.push(MocvEntry::read(reader)?);
+                    group.point_lights.push(MolpEntry::read(reader)?);
                 }
             }
             "MOBN" => {
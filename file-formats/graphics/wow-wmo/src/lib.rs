@@ -88,6 +88,8 @@
 //! - [`converter`]: Version conversion between expansions
 //! - [`editor`]: High-level editing API
 //! - [`writer`]: Binary serialization
+//! - [`builder`]: Client-ready single-group WMOs from triangle meshes
+//! - [`portal_graph`]: Group connectivity graph for pathfinding
 //! - [`visualizer`]: 3D export functionality
 //! - [`version`]: Version detection and feature support
 //! - [`error`]: Error types and handling
@@ -109,6 +111,7 @@
 
 pub mod api;
 pub mod bsp;
+pub mod builder;
 pub mod chunk;
 pub mod chunk_discovery;
 pub mod chunk_header;
@@ -120,6 +123,7 @@ pub mod file_type;
 pub mod group_parser;
 pub mod parser;
 pub mod portal;
+pub mod portal_graph;
 pub mod root_parser;
 pub mod types;
 pub mod validator;
@@ -139,7 +143,8 @@ pub mod visualizer;
 #[cfg(test)]
 mod missing_chunks_test;
 
-pub use converter::WmoConverter;
+pub use builder::{StaticBatch, StaticMaterial, StaticWmoBuilder};
+pub use converter::{ConversionChange, ConversionChangeKind, ConversionReport, WmoConverter};
 pub use editor::WmoEditor;
 pub use error::{Result, WmoError};
 pub use group_parser::WmoGroupParser;
@@ -168,6 +173,9 @@ pub use portal::{
     PortalRef, VisibilityResult, WmoGroupLocator,
 };
 
+// Portal graph exports
+pub use portal_graph::{GroupNode, PortalEdge, PortalGraph, PortalPolygon};
+
 // BSP tree exports
 pub use bsp::{BspAxisType, BspNodeExt, BspTree, point_in_group};
 
@@ -434,7 +434,7 @@ impl WmoParser {
         let mut groups = Vec::with_capacity(n_groups as usize);
 
         for i in 0..n_groups {
-            let flags = WmoGroupFlags::from_bits_truncate(reader.read_u32_le()?);
+            let flags = WmoGroupFlags::from_bits_retain(reader.read_u32_le()?);
 
             let min_x = reader.read_f32_le()?;
             let min_y = reader.read_f32_le()?;
@@ -0,0 +1,339 @@
+//! Group connectivity graph for pathfinding and visibility
+//!
+//! A [`PortalGraph`] has one node per group and one directed edge per portal
+//! reference, carrying the portal polygon. Server-side indoor pathfinding and
+//! dynamic visibility systems can walk it without knowing about MOGI, MOPT,
+//! MOPV or MOPR. With the `serde` feature the graph can be serialized as is.
+//!
+//! Portal references are stored in the root, but the group owning each one
+//! is only recorded in the group headers. [`PortalGraph::from_root_and_groups`]
+//! uses those headers; [`PortalGraph::from_root`] pairs the two references of
+//! each portal instead, which resolves every portal between two groups.
+//! Portals it cannot resolve are listed in
+//! [`PortalGraph::unresolved_portals`].
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::fs::File;
+//! use std::io::BufReader;
+//! use wow_wmo::{ParsedWmo, PortalGraph, parse_wmo};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let ParsedWmo::Root(root) = parse_wmo(&mut BufReader::new(File::open("Stormwind.wmo")?))?
+//! else {
+//!     return Err("not a root WMO".into());
+//! };
+//! let graph = PortalGraph::from_root(&root);
+//! for edge in graph.neighbors(0) {
+//!     let portal = &graph.portals[edge.portal as usize];
+//!     println!("group 0 -> {} through a {}-gon", edge.to_group, portal.vertices.len());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+
+use crate::chunks::{MogiEntry, MoprEntry, MoptEntry, MopvEntry};
+use crate::group_parser::WmoGroup;
+use crate::root_parser::WmoRoot;
+
+/// Group flag marking outdoor groups
+const GROUP_EXTERIOR: u32 = 0x8;
+/// Group flag marking indoor groups
+const GROUP_INDOOR: u32 = 0x2000;
+
+/// Connectivity of the groups of a WMO
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PortalGraph {
+    /// One node per group, in group order
+    pub groups: Vec<GroupNode>,
+    /// Portal polygons, indexed by portal number
+    pub portals: Vec<PortalPolygon>,
+    /// Directed edges, one per resolved portal reference
+    pub edges: Vec<PortalEdge>,
+    /// Portals with a reference whose owning group is unknown
+    pub unresolved_portals: Vec<u32>,
+}
+
+/// A group of the WMO
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GroupNode {
+    /// Group index
+    pub index: u32,
+    /// MOGI group flags
+    pub flags: u32,
+    /// Whether the group is outdoors
+    pub exterior: bool,
+    /// Whether the group is indoors
+    pub indoor: bool,
+    /// Bounding box minimum
+    pub bounding_box_min: [f32; 3],
+    /// Bounding box maximum
+    pub bounding_box_max: [f32; 3],
+}
+
+/// A portal polygon
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PortalPolygon {
+    /// Portal index
+    pub index: u32,
+    /// Polygon vertices in order
+    pub vertices: Vec<[f32; 3]>,
+    /// Plane normal
+    pub normal: [f32; 3],
+    /// Plane distance
+    pub distance: f32,
+}
+
+/// Passage from one group into another through a portal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PortalEdge {
+    /// Portal index
+    pub portal: u32,
+    /// Group the portal reference belongs to
+    pub from_group: u32,
+    /// Group on the other side of the portal
+    pub to_group: u32,
+    /// Side of the portal plane `from_group` is on (-1 or 1)
+    pub side: i16,
+}
+
+impl PortalGraph {
+    /// Build the graph from a root file alone
+    ///
+    /// The owner of each portal reference is inferred from the other
+    /// reference of the same portal.
+    pub fn from_root(root: &WmoRoot) -> Self {
+        Self::build(
+            &root.group_info,
+            &root.portal_vertices,
+            &root.portals,
+            &root.portal_refs,
+            &[],
+        )
+    }
+
+    /// Build the graph using the portal ranges of the group headers
+    ///
+    /// `groups` are the group files in group order. References outside every
+    /// group's range are resolved as by [`PortalGraph::from_root`].
+    pub fn from_root_and_groups(root: &WmoRoot, groups: &[WmoGroup]) -> Self {
+        let ranges: Vec<(u16, u16)> = groups
+            .iter()
+            .map(|group| (group.portal_start, group.portal_count))
+            .collect();
+        Self::build(
+            &root.group_info,
+            &root.portal_vertices,
+            &root.portals,
+            &root.portal_refs,
+            &ranges,
+        )
+    }
+
+    /// Edges leaving `group`
+    pub fn neighbors(&self, group: u32) -> impl Iterator<Item = &PortalEdge> {
+        self.edges
+            .iter()
+            .filter(move |edge| edge.from_group == group)
+    }
+
+    fn build(
+        group_info: &[MogiEntry],
+        vertices: &[MopvEntry],
+        portals: &[MoptEntry],
+        refs: &[MoprEntry],
+        ranges: &[(u16, u16)],
+    ) -> Self {
+        let groups = group_info
+            .iter()
+            .enumerate()
+            .map(|(index, info)| GroupNode {
+                index: index as u32,
+                flags: info.flags,
+                exterior: info.flags & GROUP_EXTERIOR != 0,
+                indoor: info.flags & GROUP_INDOOR != 0,
+                bounding_box_min: info.bounding_box_min,
+                bounding_box_max: info.bounding_box_max,
+            })
+            .collect();
+
+        let portals = portals
+            .iter()
+            .enumerate()
+            .map(|(index, portal)| {
+                let start = portal.start_vertex as usize;
+                let end = (start + portal.n_vertices as usize).min(vertices.len());
+                PortalPolygon {
+                    index: index as u32,
+                    vertices: vertices
+                        .get(start..end)
+                        .unwrap_or_default()
+                        .iter()
+                        .map(|v| [v.x, v.y, v.z])
+                        .collect(),
+                    normal: [portal.normal.x, portal.normal.y, portal.normal.z],
+                    distance: portal.distance,
+                }
+            })
+            .collect();
+
+        // Owners from the group headers
+        let mut owners: Vec<Option<u32>> = vec![None; refs.len()];
+        for (group, &(start, count)) in ranges.iter().enumerate() {
+            let start = start as usize;
+            let end = (start + count as usize).min(refs.len());
+            for owner in owners.get_mut(start..end).unwrap_or_default() {
+                *owner = Some(group as u32);
+            }
+        }
+
+        // Otherwise the two references of a portal own each other
+        let mut by_portal: HashMap<u16, Vec<usize>> = HashMap::new();
+        for (index, reference) in refs.iter().enumerate() {
+            by_portal
+                .entry(reference.portal_index)
+                .or_default()
+                .push(index);
+        }
+        for pair in by_portal.values() {
+            if let [a, b] = pair[..] {
+                owners[a].get_or_insert(refs[b].group_index as u32);
+                owners[b].get_or_insert(refs[a].group_index as u32);
+            }
+        }
+
+        let mut edges = Vec::new();
+        let mut unresolved_portals = Vec::new();
+        for (reference, owner) in refs.iter().zip(owners) {
+            match owner {
+                Some(from_group) => edges.push(PortalEdge {
+                    portal: reference.portal_index as u32,
+                    from_group,
+                    to_group: reference.group_index as u32,
+                    side: reference.side,
+                }),
+                None => unresolved_portals.push(reference.portal_index as u32),
+            }
+        }
+        unresolved_portals.sort_unstable();
+        unresolved_portals.dedup();
+
+        Self {
+            groups,
+            portals,
+            edges,
+            unresolved_portals,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn group(flags: u32) -> MogiEntry {
+        MogiEntry {
+            flags,
+            bounding_box_min: [0.0; 3],
+            bounding_box_max: [1.0; 3],
+            name_offset: 0,
+        }
+    }
+
+    fn vertex(x: f32, y: f32, z: f32) -> MopvEntry {
+        MopvEntry { x, y, z }
+    }
+
+    fn portal(start_vertex: u16) -> MoptEntry {
+        MoptEntry {
+            start_vertex,
+            n_vertices: 4,
+            normal: vertex(1.0, 0.0, 0.0),
+            distance: -2.0,
+        }
+    }
+
+    fn reference(portal_index: u16, group_index: u16, side: i16) -> MoprEntry {
+        MoprEntry {
+            portal_index,
+            group_index,
+            side,
+            padding: 0,
+        }
+    }
+
+    /// Exterior group 0 opens into hall 1, which opens into room 2
+    fn parts() -> (
+        Vec<MogiEntry>,
+        Vec<MopvEntry>,
+        Vec<MoptEntry>,
+        Vec<MoprEntry>,
+    ) {
+        let groups = vec![
+            group(GROUP_EXTERIOR),
+            group(GROUP_INDOOR),
+            group(GROUP_INDOOR),
+        ];
+        let vertices = (0..8)
+            .map(|i| vertex(2.0, (i % 4) as f32, (i / 4) as f32))
+            .collect();
+        let portals = vec![portal(0), portal(4)];
+        let refs = vec![
+            reference(0, 1, 1),
+            reference(0, 0, -1),
+            reference(1, 2, 1),
+            reference(1, 1, -1),
+        ];
+        (groups, vertices, portals, refs)
+    }
+
+    #[test]
+    fn test_graph_from_reference_pairs() {
+        let (groups, vertices, portals, refs) = parts();
+        let graph = PortalGraph::build(&groups, &vertices, &portals, &refs, &[]);
+
+        assert!(graph.groups[0].exterior);
+        assert!(graph.groups[2].indoor);
+        assert_eq!(graph.portals[1].vertices.len(), 4);
+        assert_eq!(graph.portals[1].vertices[0], [2.0, 0.0, 1.0]);
+        assert!(graph.unresolved_portals.is_empty());
+
+        let hall: Vec<(u32, u32)> = graph
+            .neighbors(1)
+            .map(|edge| (edge.portal, edge.to_group))
+            .collect();
+        assert_eq!(hall, [(0, 0), (1, 2)]);
+        assert_eq!(
+            graph.neighbors(0).collect::<Vec<_>>(),
+            [&PortalEdge {
+                portal: 0,
+                from_group: 0,
+                to_group: 1,
+                side: 1
+            }]
+        );
+    }
+
+    #[test]
+    fn test_graph_from_group_ranges() {
+        let (groups, vertices, portals, mut refs) = parts();
+        // A portal referenced only once needs the group headers
+        refs.push(reference(1, 0, 1));
+        let pairs = PortalGraph::build(&groups, &vertices, &portals, &refs, &[]);
+        assert_eq!(pairs.unresolved_portals, [1]);
+
+        let ranges = [(0, 1), (1, 2), (3, 2)];
+        let graph = PortalGraph::build(&groups, &vertices, &portals, &refs, &ranges);
+        assert!(graph.unresolved_portals.is_empty());
+        assert_eq!(graph.edges.len(), 5);
+        assert_eq!(graph.edges[4].from_group, 2);
+        assert_eq!(graph.edges[4].to_group, 0);
+    }
+}
@@ -68,6 +68,7 @@ pub struct WmoRoot {
     pub doodad_sets: Vec<ModsEntry>,
     /// Doodad names (MODN)
     pub doodad_names: Vec<String>,
+    pub doodad_name_offset_index_map: HashMap<u32, u32>,
     /// Doodad definitions (MODD)
     pub doodad_defs: Vec<ModdEntry>,
     /// Fog definitions (MFOG)
@@ -155,6 +156,7 @@ pub fn parse_root_file<R: Read + Seek>(
         lights: Vec::new(),
         doodad_sets: Vec::new(),
         doodad_names: Vec::new(),
+        doodad_name_offset_index_map: HashMap::new(),
         doodad_defs: Vec::new(),
         fogs: Vec::new(),
         convex_volume_planes: Vec::new(),
@@ -284,6 +286,7 @@ pub fn parse_root_file<R: Read + Seek>(
                 reader.read_exact(&mut data)?;
                 let modn = Modn::parse(&data)?;
                 root.doodad_names = modn.names;
+                root.doodad_name_offset_index_map = modn.name_offset_index_map;
             }
             "MODD" => {
                 // Read doodad definitions
@@ -1,7 +1,11 @@
+use crate::chunk::ChunkHeader;
 use crate::error::Result;
+use crate::group_parser::WmoGroup as ParsedGroup;
+use crate::root_parser::WmoRoot as ParsedRoot;
 use crate::version::WmoVersion;
 use crate::wmo_group_types::WmoGroup;
 use crate::wmo_types::{WmoFlags, WmoRoot};
+use std::io::{Read, Seek, SeekFrom};
 
 // Use WmoGroupFlags from wmo_group_types since that's where WmoGroupHeader uses it
 use crate::wmo_group_types::WmoGroupFlags;
@@ -447,6 +451,164 @@ impl WmoValidator {
     }
 }
 
+impl WmoValidator {
+    /// Validate a group file as returned by [`parse_wmo`](crate::parse_wmo)
+    ///
+    /// Checks the geometry for internal consistency and, when the root is
+    /// given, that materials, doodads, lights and portals referenced by the
+    /// group exist in it.
+    pub fn validate_parsed_group(
+        &self,
+        group: &ParsedGroup,
+        root: Option<&ParsedRoot>,
+    ) -> Result<ValidationReport> {
+        let mut report = ValidationReport::new();
+        let vertex_count = group.vertex_positions.len();
+        let index_count = group.vertex_indices.len();
+
+        if vertex_count == 0 {
+            report.add_error(ValidationError::EmptyData {
+                field: "vertices".to_string(),
+                explanation: "Group has no vertices".to_string(),
+            });
+        }
+        if index_count == 0 {
+            report.add_error(ValidationError::EmptyData {
+                field: "indices".to_string(),
+                explanation: "Group has no indices".to_string(),
+            });
+        } else if !index_count.is_multiple_of(3) {
+            report.add_warning(ValidationWarning::UnusualStructure {
+                field: "indices".to_string(),
+                explanation: format!("{index_count} indices do not form whole triangles"),
+            });
+        }
+
+        // Report only the first bad index; a broken MOVI tends to be broken throughout
+        if let Some((i, &index)) = group
+            .vertex_indices
+            .iter()
+            .enumerate()
+            .find(|&(_, &index)| index as usize >= vertex_count)
+        {
+            report.add_error(ValidationError::InvalidReference {
+                field: format!("indices[{i}]"),
+                value: u32::from(index),
+                max: vertex_count.saturating_sub(1) as u32,
+            });
+        }
+
+        if !group.vertex_normals.is_empty() && group.vertex_normals.len() != vertex_count {
+            report.add_warning(ValidationWarning::UnusualValue {
+                field: "normals".to_string(),
+                value: group.vertex_normals.len() as u32,
+                explanation: format!("expected one normal per vertex ({vertex_count})"),
+            });
+        }
+        if vertex_count > 0 && !group.texture_coords.len().is_multiple_of(vertex_count) {
+            report.add_warning(ValidationWarning::UnusualValue {
+                field: "texture_coords".to_string(),
+                value: group.texture_coords.len() as u32,
+                explanation: format!("expected a multiple of the vertex count ({vertex_count})"),
+            });
+        }
+        if !group.material_info.is_empty() && group.material_info.len() != index_count / 3 {
+            report.add_warning(ValidationWarning::UnusualValue {
+                field: "material_info".to_string(),
+                value: group.material_info.len() as u32,
+                explanation: format!("expected one entry per triangle ({})", index_count / 3),
+            });
+        }
+
+        let batch_total = u32::from(group.trans_batch_count)
+            + u32::from(group.int_batch_count)
+            + u32::from(group.ext_batch_count);
+        if batch_total as usize != group.render_batches.len() {
+            report.add_warning(ValidationWarning::UnusualValue {
+                field: "render_batches".to_string(),
+                value: group.render_batches.len() as u32,
+                explanation: format!("header batch counts add up to {batch_total}"),
+            });
+        }
+        for (i, batch) in group.render_batches.iter().enumerate() {
+            let end = batch.start_index as usize + batch.count as usize;
+            if end > index_count {
+                report.add_error(ValidationError::InvalidReference {
+                    field: format!("render_batches[{i}].indices"),
+                    value: end as u32,
+                    max: index_count as u32,
+                });
+            }
+            if let Some(root) = root
+                && batch.material_id as usize >= root.materials.len()
+            {
+                report.add_error(ValidationError::InvalidReference {
+                    field: format!("render_batches[{i}].material_id"),
+                    value: u32::from(batch.material_id),
+                    max: root.materials.len().saturating_sub(1) as u32,
+                });
+            }
+        }
+
+        if group.bounding_box.len() == 6 {
+            let (min, max) = group.bounding_box.split_at(3);
+            if min.iter().zip(max).any(|(lo, hi)| lo > hi) {
+                report.add_error(ValidationError::InvalidBoundingBox {
+                    min: format!("({}, {}, {})", min[0], min[1], min[2]),
+                    max: format!("({}, {}, {})", max[0], max[1], max[2]),
+                });
+            }
+        }
+
+        let Some(root) = root else {
+            return Ok(report);
+        };
+
+        // Collision-only triangles use material 0xFF
+        if let Some((i, entry)) = group.material_info.iter().enumerate().find(|(_, entry)| {
+            entry.material_id != 0xFF && entry.material_id as usize >= root.materials.len()
+        }) {
+            report.add_error(ValidationError::InvalidReference {
+                field: format!("material_info[{i}].material_id"),
+                value: u32::from(entry.material_id),
+                max: root.materials.len().saturating_sub(1) as u32,
+            });
+        }
+        if let Some(&doodad) = group
+            .doodad_refs
+            .iter()
+            .find(|&&doodad| doodad as usize >= root.doodad_defs.len())
+        {
+            report.add_error(ValidationError::InvalidReference {
+                field: "doodad_refs".to_string(),
+                value: u32::from(doodad),
+                max: root.doodad_defs.len().saturating_sub(1) as u32,
+            });
+        }
+        if let Some(&light) = group
+            .light_refs
+            .iter()
+            .find(|&&light| light as usize >= root.lights.len())
+        {
+            report.add_error(ValidationError::InvalidReference {
+                field: "light_refs".to_string(),
+                value: u32::from(light),
+                max: root.lights.len().saturating_sub(1) as u32,
+            });
+        }
+        let portal_end = u32::from(group.portal_start) + u32::from(group.portal_count);
+        if group.portal_count > 0 && portal_end as usize > root.portal_refs.len() {
+            report.add_error(ValidationError::InvalidReference {
+                field: "portal_refs".to_string(),
+                value: portal_end,
+                max: root.portal_refs.len() as u32,
+            });
+        }
+
+        Ok(report)
+    }
+}
+
 /// Report of validation results
 #[derive(Debug)]
 pub struct ValidationReport {
@@ -661,3 +823,477 @@ impl std::fmt::Display for ValidationWarning {
         }
     }
 }
+
+/// Chunk data is scanned through a buffer of at most this many bytes
+const STREAM_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Special texture values at or above this are markers rather than offsets
+const SPECIAL_TEXTURE_THRESHOLD: u32 = 0xFF000000;
+
+/// Location of a chunk found while streaming through a file
+#[derive(Debug, Clone, Copy)]
+struct StreamChunk {
+    id: [u8; 4],
+    /// Offset of the chunk data, just past the header
+    offset: u64,
+    size: u32,
+}
+
+impl StreamChunk {
+    fn records(&self, record_size: usize) -> u32 {
+        self.size / record_size as u32
+    }
+}
+
+impl WmoValidator {
+    /// Validate a root or group file straight from a reader
+    ///
+    /// Walks the chunk headers and checks header counts, chunk sizes, index
+    /// ranges and bounding boxes without building the model. Chunk contents
+    /// are read through a fixed-size buffer, so memory use does not grow with
+    /// the file. Whether the file is a root or a group is decided by the
+    /// presence of `MOHD` or `MOGP`.
+    ///
+    /// Only problems that can be seen within the single file are reported;
+    /// group references into the root are checked by
+    /// [`validate_parsed_group`](Self::validate_parsed_group).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    /// use std::io::BufReader;
+    /// use wow_wmo::WmoValidator;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut reader = BufReader::new(File::open("building_000.wmo")?);
+    /// let report = WmoValidator::new().validate_stream(&mut reader)?;
+    /// if report.has_errors() {
+    ///     report.print();
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn validate_stream<R: Read + Seek>(&self, reader: &mut R) -> Result<ValidationReport> {
+        let mut report = ValidationReport::new();
+        let file_len = reader.seek(SeekFrom::End(0))?;
+        let chunks = scan_chunks(reader, 0, file_len, "file", &mut report)?;
+
+        match chunks.first() {
+            Some(mver) if &mver.id == b"MVER" && mver.size >= 4 => {
+                let mut raw = [0u8; 4];
+                read_at(reader, mver.offset, &mut raw)?;
+                let raw = u32::from_le_bytes(raw);
+                let supported = WmoVersion::from_raw(raw).is_some_and(|version| {
+                    version >= WmoVersion::min_supported() && version <= WmoVersion::max_supported()
+                });
+                if !supported {
+                    report.add_error(ValidationError::UnsupportedVersion(raw));
+                }
+            }
+            _ => report.add_error(ValidationError::EmptyData {
+                field: "MVER".to_string(),
+                explanation: "File does not start with a version chunk".to_string(),
+            }),
+        }
+
+        if let Some(mohd) = find_chunk(&chunks, b"MOHD") {
+            stream_root(reader, mohd, &chunks, &mut report)?;
+        } else if let Some(mogp) = find_chunk(&chunks, b"MOGP") {
+            stream_group(reader, mogp, &mut report)?;
+        } else {
+            report.add_error(ValidationError::EmptyData {
+                field: "MOHD/MOGP".to_string(),
+                explanation: "Neither a root header nor a group header was found".to_string(),
+            });
+        }
+
+        Ok(report)
+    }
+}
+
+/// Collect the chunk headers between `start` and `end`, seeking over the data
+fn scan_chunks<R: Read + Seek>(
+    reader: &mut R,
+    start: u64,
+    end: u64,
+    container: &str,
+    report: &mut ValidationReport,
+) -> Result<Vec<StreamChunk>> {
+    let mut chunks = Vec::new();
+    let mut pos = start;
+    while pos + ChunkHeader::SIZE as u64 <= end {
+        reader.seek(SeekFrom::Start(pos))?;
+        let header = ChunkHeader::read(reader)?;
+        let offset = pos + ChunkHeader::SIZE as u64;
+        let chunk_end = offset + u64::from(header.size);
+        if chunk_end > end {
+            report.add_error(ValidationError::InvalidValue {
+                field: format!("{} chunk at {pos:#x}", header.id),
+                value: header.size,
+                explanation: format!(
+                    "extends {} bytes past the end of the {container}",
+                    chunk_end - end
+                ),
+            });
+            return Ok(chunks);
+        }
+        chunks.push(StreamChunk {
+            id: header.id.0,
+            offset,
+            size: header.size,
+        });
+        pos = chunk_end;
+    }
+    if pos < end {
+        report.add_warning(ValidationWarning::UnusualStructure {
+            field: container.to_string(),
+            explanation: format!("{} trailing bytes after the last chunk", end - pos),
+        });
+    }
+    Ok(chunks)
+}
+
+fn find_chunk<'a>(chunks: &'a [StreamChunk], id: &[u8; 4]) -> Option<&'a StreamChunk> {
+    chunks.iter().find(|chunk| &chunk.id == id)
+}
+
+fn read_at<R: Read + Seek>(reader: &mut R, offset: u64, buf: &mut [u8]) -> Result<()> {
+    reader.seek(SeekFrom::Start(offset))?;
+    reader.read_exact(buf)?;
+    Ok(())
+}
+
+fn u16_at(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+fn f32_at(bytes: &[u8], offset: usize) -> f32 {
+    f32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+/// Feed every whole record of a chunk to `visit` until it returns `false`
+fn for_each_record<R: Read + Seek>(
+    reader: &mut R,
+    chunk: &StreamChunk,
+    record_size: usize,
+    mut visit: impl FnMut(usize, &[u8]) -> bool,
+) -> Result<()> {
+    let per_buffer = (STREAM_BUFFER_SIZE / record_size).max(1);
+    let mut remaining = chunk.records(record_size) as usize;
+    let mut buf = vec![0u8; per_buffer.min(remaining) * record_size];
+    let mut index = 0;
+    reader.seek(SeekFrom::Start(chunk.offset))?;
+    while remaining > 0 {
+        let count = per_buffer.min(remaining);
+        let bytes = &mut buf[..count * record_size];
+        reader.read_exact(bytes)?;
+        for record in bytes.chunks_exact(record_size) {
+            if !visit(index, record) {
+                return Ok(());
+            }
+            index += 1;
+        }
+        remaining -= count;
+    }
+    Ok(())
+}
+
+/// Record count of an array chunk, warning when its size is not a whole number of records
+fn record_count(
+    chunk: Option<&StreamChunk>,
+    field: &str,
+    record_size: usize,
+    report: &mut ValidationReport,
+) -> u32 {
+    let Some(chunk) = chunk else {
+        return 0;
+    };
+    if !(chunk.size as usize).is_multiple_of(record_size) {
+        report.add_warning(ValidationWarning::UnusualStructure {
+            field: field.to_string(),
+            explanation: format!(
+                "chunk size {} is not a multiple of the {record_size}-byte entry size",
+                chunk.size
+            ),
+        });
+    }
+    chunk.records(record_size)
+}
+
+fn check_bounding_box(bytes: &[u8], report: &mut ValidationReport) {
+    let min: Vec<f32> = (0..3).map(|i| f32_at(bytes, i * 4)).collect();
+    let max: Vec<f32> = (3..6).map(|i| f32_at(bytes, i * 4)).collect();
+    if min.iter().zip(&max).any(|(lo, hi)| lo > hi) {
+        report.add_error(ValidationError::InvalidBoundingBox {
+            min: format!("({}, {}, {})", min[0], min[1], min[2]),
+            max: format!("({}, {}, {})", max[0], max[1], max[2]),
+        });
+    }
+}
+
+fn stream_root<R: Read + Seek>(
+    reader: &mut R,
+    mohd: &StreamChunk,
+    chunks: &[StreamChunk],
+    report: &mut ValidationReport,
+) -> Result<()> {
+    if mohd.size < 64 {
+        report.add_error(ValidationError::InvalidValue {
+            field: "MOHD".to_string(),
+            value: mohd.size,
+            explanation: "header chunk is shorter than 64 bytes".to_string(),
+        });
+        return Ok(());
+    }
+    let mut header = [0u8; 64];
+    read_at(reader, mohd.offset, &mut header)?;
+
+    let chunk = |id: &[u8; 4]| find_chunk(chunks, id);
+    let mut counts = [0u32; 6];
+    let tables: [(&str, &[u8; 4], usize, usize); 6] = [
+        ("materials", b"MOMT", 64, 0),
+        ("groups", b"MOGI", 32, 4),
+        ("portals", b"MOPT", 20, 8),
+        ("lights", b"MOLT", 48, 12),
+        ("doodad_defs", b"MODD", 40, 20),
+        ("doodad_sets", b"MODS", 32, 24),
+    ];
+    for (count, (field, id, record_size, header_offset)) in counts.iter_mut().zip(tables) {
+        let expected = u32_at(&header, header_offset);
+        *count = record_count(chunk(id), field, record_size, report);
+        if *count != expected {
+            report.add_error(ValidationError::CountMismatch {
+                field: field.to_string(),
+                expected,
+                actual: *count,
+            });
+        }
+    }
+    let [_, n_groups, n_portals, _, n_doodad_defs, _] = counts;
+
+    if let Some(momt) = chunk(b"MOMT") {
+        let texture_size = chunk(b"MOTX").map_or(0, |motx| motx.size);
+        let mut bad = None;
+        for_each_record(reader, momt, 64, |i, material| {
+            for (name, offset) in [("texture1", 12), ("texture2", 24)] {
+                let texture = u32_at(material, offset);
+                if texture != 0 && texture < SPECIAL_TEXTURE_THRESHOLD && texture >= texture_size {
+                    bad = Some((format!("material[{i}].{name}"), texture));
+                    return false;
+                }
+            }
+            true
+        })?;
+        if let Some((field, value)) = bad {
+            report.add_error(ValidationError::InvalidReference {
+                field,
+                value,
+                max: texture_size.saturating_sub(1),
+            });
+        }
+    }
+
+    if let Some(mopr) = chunk(b"MOPR") {
+        record_count(Some(mopr), "portal_references", 8, report);
+        let mut bad = None;
+        for_each_record(reader, mopr, 8, |i, reference| {
+            let portal = u32::from(u16_at(reference, 0));
+            let group = u32::from(u16_at(reference, 2));
+            if portal >= n_portals {
+                bad = Some((
+                    format!("portal_reference[{i}].portal_index"),
+                    portal,
+                    n_portals,
+                ));
+            } else if group >= n_groups {
+                bad = Some((
+                    format!("portal_reference[{i}].group_index"),
+                    group,
+                    n_groups,
+                ));
+            }
+            bad.is_none()
+        })?;
+        if let Some((field, value, count)) = bad {
+            report.add_error(ValidationError::InvalidReference {
+                field,
+                value,
+                max: count.saturating_sub(1),
+            });
+        }
+    }
+    if let Some(mopv) = chunk(b"MOPV") {
+        record_count(Some(mopv), "portal_vertices", 12, report);
+    }
+
+    if let Some(mods) = chunk(b"MODS") {
+        let mut bad = None;
+        for_each_record(reader, mods, 32, |i, set| {
+            let end = u32_at(set, 20).saturating_add(u32_at(set, 24));
+            if end > n_doodad_defs {
+                bad = Some((i, end));
+            }
+            bad.is_none()
+        })?;
+        if let Some((i, end)) = bad {
+            report.add_error(ValidationError::InvalidReference {
+                field: format!("doodad_set[{i}]"),
+                value: end,
+                max: n_doodad_defs,
+            });
+        }
+    }
+
+    if let Some(modd) = chunk(b"MODD") {
+        let names_size = chunk(b"MODN").map_or(0, |modn| modn.size);
+        let mut bad = None;
+        for_each_record(reader, modd, 40, |i, def| {
+            let name = u32_at(def, 0) & 0x00FF_FFFF;
+            if name >= names_size {
+                bad = Some((i, name));
+            }
+            bad.is_none()
+        })?;
+        if let Some((i, name)) = bad {
+            report.add_error(ValidationError::InvalidReference {
+                field: format!("doodad_def[{i}].name_offset"),
+                value: name,
+                max: names_size.saturating_sub(1),
+            });
+        }
+    }
+
+    check_bounding_box(&header[36..60], report);
+    Ok(())
+}
+
+fn stream_group<R: Read + Seek>(
+    reader: &mut R,
+    mogp: &StreamChunk,
+    report: &mut ValidationReport,
+) -> Result<()> {
+    const MOGP_HEADER_SIZE: u32 = 68;
+    if mogp.size < MOGP_HEADER_SIZE {
+        report.add_error(ValidationError::InvalidValue {
+            field: "MOGP".to_string(),
+            value: mogp.size,
+            explanation: format!("group chunk is shorter than its {MOGP_HEADER_SIZE}-byte header"),
+        });
+        return Ok(());
+    }
+    let mut header = [0u8; MOGP_HEADER_SIZE as usize];
+    read_at(reader, mogp.offset, &mut header)?;
+    let chunks = scan_chunks(
+        reader,
+        mogp.offset + u64::from(MOGP_HEADER_SIZE),
+        mogp.offset + u64::from(mogp.size),
+        "MOGP chunk",
+        report,
+    )?;
+    let chunk = |id: &[u8; 4]| find_chunk(&chunks, id);
+
+    let vertex_count = record_count(chunk(b"MOVT"), "vertices", 12, report);
+    let index_count = record_count(chunk(b"MOVI"), "indices", 2, report);
+    let normal_count = record_count(chunk(b"MONR"), "normals", 12, report);
+    let material_info_count = record_count(chunk(b"MOPY"), "material_info", 2, report);
+    let batch_count = record_count(chunk(b"MOBA"), "render_batches", 24, report);
+    let mut texture_coord_count = 0;
+    for motv in chunks.iter().filter(|chunk| &chunk.id == b"MOTV") {
+        texture_coord_count += record_count(Some(motv), "texture_coords", 8, report);
+    }
+
+    if vertex_count == 0 {
+        report.add_error(ValidationError::EmptyData {
+            field: "vertices".to_string(),
+            explanation: "Group has no vertices".to_string(),
+        });
+    }
+    if index_count == 0 {
+        report.add_error(ValidationError::EmptyData {
+            field: "indices".to_string(),
+            explanation: "Group has no indices".to_string(),
+        });
+    } else if !index_count.is_multiple_of(3) {
+        report.add_warning(ValidationWarning::UnusualStructure {
+            field: "indices".to_string(),
+            explanation: format!("{index_count} indices do not form whole triangles"),
+        });
+    }
+
+    // Report only the first bad index; a broken MOVI tends to be broken throughout
+    if let Some(movi) = chunk(b"MOVI") {
+        let mut bad = None;
+        for_each_record(reader, movi, 2, |i, index| {
+            let index = u32::from(u16_at(index, 0));
+            if index >= vertex_count {
+                bad = Some((i, index));
+            }
+            bad.is_none()
+        })?;
+        if let Some((i, index)) = bad {
+            report.add_error(ValidationError::InvalidReference {
+                field: format!("indices[{i}]"),
+                value: index,
+                max: vertex_count.saturating_sub(1),
+            });
+        }
+    }
+
+    if normal_count != 0 && normal_count != vertex_count {
+        report.add_warning(ValidationWarning::UnusualValue {
+            field: "normals".to_string(),
+            value: normal_count,
+            explanation: format!("expected one normal per vertex ({vertex_count})"),
+        });
+    }
+    if vertex_count > 0 && !texture_coord_count.is_multiple_of(vertex_count) {
+        report.add_warning(ValidationWarning::UnusualValue {
+            field: "texture_coords".to_string(),
+            value: texture_coord_count,
+            explanation: format!("expected a multiple of the vertex count ({vertex_count})"),
+        });
+    }
+    if material_info_count != 0 && material_info_count != index_count / 3 {
+        report.add_warning(ValidationWarning::UnusualValue {
+            field: "material_info".to_string(),
+            value: material_info_count,
+            explanation: format!("expected one entry per triangle ({})", index_count / 3),
+        });
+    }
+
+    let batch_total = u32::from(u16_at(&header, 40))
+        + u32::from(u16_at(&header, 42))
+        + u32::from(u16_at(&header, 44));
+    if batch_total != batch_count {
+        report.add_warning(ValidationWarning::UnusualValue {
+            field: "render_batches".to_string(),
+            value: batch_count,
+            explanation: format!("header batch counts add up to {batch_total}"),
+        });
+    }
+    if let Some(moba) = chunk(b"MOBA") {
+        let mut bad = Vec::new();
+        for_each_record(reader, moba, 24, |i, batch| {
+            let end = u32_at(batch, 12) as u64 + u64::from(u16_at(batch, 16));
+            if end > u64::from(index_count) {
+                bad.push((i, end as u32));
+            }
+            true
+        })?;
+        for (i, end) in bad {
+            report.add_error(ValidationError::InvalidReference {
+                field: format!("render_batches[{i}].indices"),
+                value: end,
+                max: index_count,
+            });
+        }
+    }
+
+    check_bounding_box(&header[12..36], report);
+    Ok(())
+}
@@ -34,6 +34,9 @@ pub struct WmoGroup {
     /// Liquid data (if present)
     pub liquid: Option<WmoLiquid>,
 
+    /// Light references into the root's lights (if present)
+    pub light_refs: Option<Vec<u16>>,
+
     /// Doodad references (if present)
     pub doodad_refs: Option<Vec<u16>>,
 }
@@ -193,6 +193,13 @@ impl WmoWriter {
             self.write_liquid(writer, liquid, target_version)?;
         }
 
+        // Write light references if available
+        if let Some(refs) = &group.light_refs
+            && !refs.is_empty()
+        {
+            self.write_light_refs(writer, refs)?;
+        }
+
         // Write doodad references if available
         if let Some(refs) = &group.doodad_refs
             && !refs.is_empty()
@@ -414,7 +421,8 @@ impl WmoWriter {
 
         header.write(writer)?;
 
-        for group in groups {
+        let name_offsets = group_name_offsets(groups);
+        for (group, name_offset) in groups.iter().zip(name_offsets) {
             writer.write_u32_le(group.flags.bits())?;
 
             writer.write_f32_le(group.bounding_box.min.x)?;
@@ -425,9 +433,7 @@ impl WmoWriter {
             writer.write_f32_le(group.bounding_box.max.y)?;
             writer.write_f32_le(group.bounding_box.max.z)?;
 
-            // Write name offset in MOGN chunk
-            // This is a simplification - in a real implementation, you'd need to calculate actual offsets
-            writer.write_u32_le(0)?; // Placeholder
+            writer.write_u32_le(name_offset)?;
         }
 
         Ok(())
@@ -1046,6 +1052,22 @@ impl WmoWriter {
         Ok(())
     }
 
+    /// Write light references
+    fn write_light_refs<W: Write>(&self, writer: &mut W, light_refs: &[u16]) -> Result<()> {
+        let header = ChunkHeader {
+            id: chunks::MOLR,
+            size: (light_refs.len() * 2) as u32, // 2 bytes per reference (u16)
+        };
+
+        header.write(writer)?;
+
+        for &light_ref in light_refs {
+            writer.write_u16_le(light_ref)?;
+        }
+
+        Ok(())
+    }
+
     /// Write doodad references
     fn write_doodad_refs<W: Write>(&self, writer: &mut W, doodad_refs: &[u16]) -> Result<()> {
         if doodad_refs.is_empty() {
@@ -1066,3 +1088,16 @@ impl WmoWriter {
         Ok(())
     }
 }
+
+/// Offsets of the group names in the MOGN chunk as written by [`WmoWriter`]
+pub(crate) fn group_name_offsets(groups: &[WmoGroupInfo]) -> Vec<u32> {
+    let mut offset = 0;
+    groups
+        .iter()
+        .map(|group| {
+            let name_offset = offset;
+            offset += group.name.len() as u32 + 1;
+            name_offset
+        })
+        .collect()
+}
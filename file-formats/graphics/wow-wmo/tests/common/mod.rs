@@ -0,0 +1,12 @@
+//! Shared fixtures for the integration tests
+
+/// A chunk as stored in a WMO file: reversed FourCC, payload size, payload
+pub fn chunk(id: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+    let mut data = Vec::new();
+    let mut reversed = *id;
+    reversed.reverse();
+    data.extend_from_slice(&reversed);
+    data.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    data.extend_from_slice(payload);
+    data
+}
@@ -0,0 +1,172 @@
+mod common;
+
+use common::chunk;
+use std::io::Cursor;
+use wow_wmo::{
+    BoundingBox, Color, ConversionChangeKind, Vec3, WmoConverter, WmoConvexVolumePlanes, WmoFlags,
+    WmoGroup, WmoGroupFlags, WmoGroupHeader, WmoLiquid, WmoLiquidVertex, WmoMaterial,
+    WmoMaterialFlags, WmoParser, WmoRoot, WmoVersion,
+};
+
+fn root() -> WmoRoot {
+    let mut data = chunk(b"MVER", &17u32.to_le_bytes());
+    data.extend(chunk(b"MOHD", &[0; 64]));
+    WmoParser::new().parse_root(&mut Cursor::new(data)).unwrap()
+}
+
+fn material(flags: WmoMaterialFlags) -> WmoMaterial {
+    let black = Color {
+        r: 0,
+        g: 0,
+        b: 0,
+        a: 0,
+    };
+    WmoMaterial {
+        flags,
+        shader: 0,
+        blend_mode: 0,
+        texture1: 0,
+        emissive_color: black,
+        sidn_color: black,
+        framebuffer_blend: black,
+        texture2: 0,
+        diffuse_color: black,
+        ground_type: 0,
+    }
+}
+
+fn zero() -> Vec3 {
+    Vec3 {
+        x: 0.0,
+        y: 0.0,
+        z: 0.0,
+    }
+}
+
+fn group(flags: WmoGroupFlags, liquid: Option<WmoLiquid>) -> WmoGroup {
+    WmoGroup {
+        header: WmoGroupHeader {
+            flags,
+            bounding_box: BoundingBox {
+                min: zero(),
+                max: zero(),
+            },
+            name_offset: 0,
+            group_index: 0,
+        },
+        materials: Vec::new(),
+        vertices: Vec::new(),
+        normals: Vec::new(),
+        tex_coords: Vec::new(),
+        batches: Vec::new(),
+        indices: Vec::new(),
+        vertex_colors: None,
+        bsp_nodes: None,
+        liquid,
+        light_refs: None,
+        doodad_refs: None,
+    }
+}
+
+#[test]
+fn test_downgrade_reports_dropped_root_data() {
+    let mut wmo = root();
+    wmo.version = WmoVersion::Mop;
+    wmo.skybox = Some("Environments\\Stars\\Sky.m2".to_string());
+    wmo.header.flags |= WmoFlags::HAS_SKYBOX;
+    wmo.materials = vec![
+        material(WmoMaterialFlags::SHADOW_BATCH_1),
+        material(WmoMaterialFlags::UNLIT),
+    ];
+    wmo.convex_volume_planes = Some(WmoConvexVolumePlanes { planes: Vec::new() });
+
+    let report = WmoConverter::new()
+        .convert_root(&mut wmo, WmoVersion::Classic)
+        .unwrap();
+
+    assert_eq!(wmo.version, WmoVersion::Classic);
+    assert!(wmo.skybox.is_none());
+    assert!(wmo.convex_volume_planes.is_none());
+    assert!(!report.is_lossless());
+
+    let dropped: Vec<&str> = report
+        .changes_of(ConversionChangeKind::Dropped)
+        .map(|change| change.chunk)
+        .collect();
+    assert_eq!(dropped, ["MOMT", "MOSB", "MOHD", "MCVP"]);
+    assert!(report.changes[1].description.contains("Sky.m2"));
+    assert!(
+        report
+            .to_string()
+            .contains("dropped MOMT: shadow batch flags of 1 material(s)")
+    );
+}
+
+#[test]
+fn test_group_conversion_report() {
+    let liquid = WmoLiquid {
+        liquid_type: 1,
+        flags: 0x2,
+        width: 2,
+        height: 1,
+        vertices: vec![
+            WmoLiquidVertex {
+                position: zero(),
+                height: 1.0,
+            };
+            2
+        ],
+        tile_flags: None,
+    };
+    let mut group = group(
+        WmoGroupFlags::MOUNT_ALLOWED | WmoGroupFlags::INDOOR,
+        Some(liquid),
+    );
+
+    let converter = WmoConverter::new();
+    let report = converter
+        .convert_group(&mut group, WmoVersion::Wotlk, WmoVersion::Legion)
+        .unwrap();
+
+    let mut root_report = converter
+        .convert_root(&mut root(), WmoVersion::Wotlk)
+        .unwrap();
+    assert!(root_report.changes.is_empty());
+    root_report.merge_group(3, report);
+
+    let changes: Vec<(ConversionChangeKind, &str, Option<usize>)> = root_report
+        .changes
+        .iter()
+        .map(|change| (change.kind, change.chunk, change.group))
+        .collect();
+    assert_eq!(
+        changes,
+        [
+            (ConversionChangeKind::Dropped, "MOGP", Some(3)),
+            (ConversionChangeKind::Approximated, "MLIQ", Some(3)),
+        ]
+    );
+    assert_eq!(group.header.flags, WmoGroupFlags::INDOOR);
+    assert!(
+        root_report.changes[0]
+            .to_string()
+            .starts_with("dropped MOGP (group 3)")
+    );
+}
+
+#[test]
+fn test_compatible_conversion_is_lossless() {
+    let mut wmo = root();
+    wmo.materials = vec![material(WmoMaterialFlags::UNLIT)];
+
+    let report = WmoConverter::new()
+        .convert_root(&mut wmo, WmoVersion::Wotlk)
+        .unwrap();
+
+    assert!(report.changes.is_empty());
+    assert!(report.is_lossless());
+    assert_eq!(
+        report.to_string(),
+        "Classic/Vanilla -> Wrath of the Lich King: no changes"
+    );
+}
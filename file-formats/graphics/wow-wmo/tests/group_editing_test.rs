@@ -0,0 +1,122 @@
+mod common;
+
+use common::chunk;
+use std::io::Cursor;
+use wow_wmo::{
+    BoundingBox, Vec3, WmoEditor, WmoGroup, WmoGroupFlags, WmoGroupHeader, WmoParser, WmoRoot,
+};
+
+fn group_info(flags: u32, name_offset: u32, max: f32) -> Vec<u8> {
+    let mut entry = flags.to_le_bytes().to_vec();
+    for value in [0.0, 0.0, 0.0, max, max, max] {
+        entry.extend_from_slice(&f32::to_le_bytes(value));
+    }
+    entry.extend_from_slice(&name_offset.to_le_bytes());
+    entry
+}
+
+/// A root with an outdoor "Hall" and an indoor "Tower" group
+fn root() -> WmoRoot {
+    let mut header = vec![0; 64];
+    header[4..8].copy_from_slice(&2u32.to_le_bytes());
+
+    let mut mogi = group_info(0x8, 0, 10.0);
+    mogi.extend(group_info(0x2000 | 0x800_0000, 5, 20.0));
+
+    let mut data = chunk(b"MVER", &17u32.to_le_bytes());
+    data.extend(chunk(b"MOHD", &header));
+    data.extend(chunk(b"MOGN", b"Hall\0Tower\0"));
+    data.extend(chunk(b"MOGI", &mogi));
+    WmoParser::new().parse_root(&mut Cursor::new(data)).unwrap()
+}
+
+fn reparse(editor: &WmoEditor) -> WmoRoot {
+    let mut data = Cursor::new(Vec::new());
+    editor.save_root(&mut data).unwrap();
+    data.set_position(0);
+    WmoParser::new().parse_root(&mut data).unwrap()
+}
+
+fn group(index: u32) -> WmoGroup {
+    let zero = Vec3 {
+        x: 0.0,
+        y: 0.0,
+        z: 0.0,
+    };
+    WmoGroup {
+        header: WmoGroupHeader {
+            flags: WmoGroupFlags::empty(),
+            bounding_box: BoundingBox {
+                min: zero,
+                max: zero,
+            },
+            name_offset: 5,
+            group_index: index,
+        },
+        materials: Vec::new(),
+        vertices: Vec::new(),
+        normals: Vec::new(),
+        tex_coords: Vec::new(),
+        batches: Vec::new(),
+        indices: Vec::new(),
+        vertex_colors: None,
+        bsp_nodes: None,
+        liquid: None,
+        light_refs: None,
+        doodad_refs: None,
+    }
+}
+
+#[test]
+fn test_rename_groups() {
+    let mut editor = WmoEditor::new(root());
+    editor.add_group(group(1)).unwrap();
+    assert_eq!(editor.group_name(1), Some("Tower"));
+
+    editor.set_group_name(0, "Great Hall").unwrap();
+    assert!(editor.is_root_modified());
+    assert_eq!(editor.group(1).unwrap().header.name_offset, 11);
+    assert!(editor.set_group_name(2, "Missing").is_err());
+
+    let saved = reparse(&editor);
+    let names: Vec<&str> = saved.groups.iter().map(|g| g.name.as_str()).collect();
+    assert_eq!(names, ["Great Hall", "Tower"]);
+}
+
+#[test]
+fn test_reflag_groups() {
+    let mut editor = WmoEditor::new(root());
+    editor.add_group(group(1)).unwrap();
+    assert_eq!(editor.is_group_indoor(0), Some(false));
+    assert_eq!(editor.is_group_indoor(1), Some(true));
+
+    editor.set_group_indoor(0, true).unwrap();
+    editor.set_group_indoor(1, false).unwrap();
+    editor.set_group_antiportal(1, true).unwrap();
+    assert_eq!(
+        editor.group(1).unwrap().header.flags.bits(),
+        0x8 | 0x400_0000 | 0x800_0000
+    );
+
+    let bounding_box = BoundingBox {
+        min: Vec3 {
+            x: -1.0,
+            y: -2.0,
+            z: -3.0,
+        },
+        max: Vec3 {
+            x: 1.0,
+            y: 2.0,
+            z: 3.0,
+        },
+    };
+    editor.set_group_bounding_box(1, bounding_box).unwrap();
+    assert_eq!(editor.group(1).unwrap().header.bounding_box, bounding_box);
+
+    let saved = reparse(&editor);
+    assert_eq!(saved.groups[0].flags.bits(), 0x2000);
+    // Flags without a named constant survive the round trip
+    assert_eq!(saved.groups[1].flags.bits(), 0x8 | 0x400_0000 | 0x800_0000);
+    assert_eq!(saved.groups[1].bounding_box, bounding_box);
+    assert_eq!(saved.groups[1].name, "Tower");
+}
@@ -0,0 +1,58 @@
+mod common;
+
+use common::chunk;
+use std::io::Cursor;
+use wow_wmo::{ParsedWmo, parse_wmo};
+
+fn floats(values: &[f32]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+#[test]
+fn test_group_light_chunks() {
+    let mut nested = Vec::new();
+    nested.extend(chunk(b"MOLR", &[2, 0, 5, 0]));
+
+    let mut point = Vec::new();
+    point.extend_from_slice(&7u32.to_le_bytes());
+    point.extend_from_slice(&[0x10, 0x20, 0x30, 0xFF]);
+    point.extend(floats(&[1.0, 2.0, 3.0, 0.5, 8.0, 1.5]));
+    point.extend_from_slice(&[0; 16]);
+    nested.extend(chunk(b"MOLP", &point));
+
+    let mut spot = Vec::new();
+    spot.extend_from_slice(&0u32.to_le_bytes());
+    spot.extend_from_slice(&[0, 0, 0xFF, 0xFF]);
+    spot.extend(floats(&[
+        4.0, 5.0, 6.0, 1.0, 10.0, 2.0, 0.0, 0.0, -1.0, 0.3, 0.6,
+    ]));
+    spot.extend_from_slice(&0u32.to_le_bytes());
+    nested.extend(chunk(b"MOLS", &spot));
+
+    let mut mogp = vec![0; 68];
+    mogp.extend(nested);
+
+    let mut data = chunk(b"MVER", &17u32.to_le_bytes());
+    data.extend(chunk(b"MOGP", &mogp));
+
+    let ParsedWmo::Group(group) = parse_wmo(&mut Cursor::new(data)).unwrap() else {
+        panic!("Expected group WMO file, got root file");
+    };
+
+    assert_eq!(group.light_refs, [2, 5]);
+
+    assert_eq!(group.point_lights.len(), 1);
+    let point = &group.point_lights[0];
+    assert_eq!(point.unknown0, 7);
+    assert_eq!(point.color, [0x10, 0x20, 0x30, 0xFF]);
+    assert_eq!(point.position, [1.0, 2.0, 3.0]);
+    assert_eq!(point.attenuation_end, 8.0);
+    assert_eq!(point.intensity, 1.5);
+
+    assert_eq!(group.spot_lights.len(), 1);
+    let spot = &group.spot_lights[0];
+    assert_eq!(spot.position, [4.0, 5.0, 6.0]);
+    assert_eq!(spot.direction, [0.0, 0.0, -1.0]);
+    assert_eq!(spot.inner_angle, 0.3);
+    assert_eq!(spot.outer_angle, 0.6);
+}
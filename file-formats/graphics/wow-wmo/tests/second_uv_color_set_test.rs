@@ -0,0 +1,74 @@
+mod common;
+
+use common::chunk;
+use std::io::Cursor;
+use wow_wmo::{ParsedWmo, parse_wmo};
+
+fn uvs(u: f32, v: f32) -> Vec<u8> {
+    let mut data = Vec::new();
+    for _ in 0..3 {
+        data.extend_from_slice(&u.to_le_bytes());
+        data.extend_from_slice(&v.to_le_bytes());
+    }
+    data
+}
+
+/// Group file with a triangle and the given chunks nested in MOGP
+fn group_file(extra: &[Vec<u8>]) -> Vec<u8> {
+    let mut nested = chunk(b"MOVI", &[0, 0, 1, 0, 2, 0]);
+    nested.extend(chunk(b"MOVT", &[0; 36]));
+    for data in extra {
+        nested.extend_from_slice(data);
+    }
+
+    let mut mogp = vec![0; 68];
+    mogp.extend(nested);
+
+    let mut data = chunk(b"MVER", &17u32.to_le_bytes());
+    data.extend(chunk(b"MOGP", &mogp));
+    data
+}
+
+#[test]
+fn test_second_motv_and_mocv_are_kept_apart() {
+    let data = group_file(&[
+        chunk(b"MOTV", &uvs(0.25, 0.5)),
+        chunk(b"MOCV", &[10, 20, 30, 40].repeat(3)),
+        chunk(b"MOTV", &uvs(0.75, 1.0)),
+        chunk(b"MOCV", &[50, 60, 70, 80].repeat(3)),
+    ]);
+
+    let ParsedWmo::Group(group) = parse_wmo(&mut Cursor::new(data)).unwrap() else {
+        panic!("Expected group WMO file, got root file");
+    };
+
+    assert_eq!(group.texture_coords.len(), 3);
+    assert_eq!(group.texture_coords2.len(), 3);
+    assert_eq!(
+        (group.texture_coords[0].u, group.texture_coords[0].v),
+        (0.25, 0.5)
+    );
+    assert_eq!(
+        (group.texture_coords2[2].u, group.texture_coords2[2].v),
+        (0.75, 1.0)
+    );
+
+    assert_eq!(group.vertex_colors.len(), 3);
+    assert_eq!(group.vertex_colors2.len(), 3);
+    assert_eq!(group.vertex_colors[0].b, 10);
+    assert_eq!(group.vertex_colors2[0].a, 80);
+}
+
+#[test]
+fn test_single_motv_leaves_second_set_empty() {
+    let data = group_file(&[chunk(b"MOTV", &uvs(0.0, 1.0))]);
+
+    let ParsedWmo::Group(group) = parse_wmo(&mut Cursor::new(data)).unwrap() else {
+        panic!("Expected group WMO file, got root file");
+    };
+
+    assert_eq!(group.texture_coords.len(), 3);
+    assert!(group.texture_coords2.is_empty());
+    assert!(group.vertex_colors.is_empty());
+    assert!(group.vertex_colors2.is_empty());
+}
@@ -0,0 +1,195 @@
+use std::io::Cursor;
+use wow_wmo::group_parser::WmoGroup;
+use wow_wmo::root_parser::WmoRoot;
+use wow_wmo::{
+    ParsedWmo, StaticMaterial, StaticWmoBuilder, TexCoord, Vec3, WmoError, WmoMaterialFlags,
+    parse_wmo,
+};
+
+/// A flat 10 by 10 grid of quads, split between two materials
+fn floor() -> StaticWmoBuilder {
+    let mut positions = Vec::new();
+    for y in 0..=10 {
+        for x in 0..=10 {
+            positions.push(Vec3 {
+                x: x as f32,
+                y: y as f32,
+                z: 0.0,
+            });
+        }
+    }
+    let mut batches = [Vec::new(), Vec::new()];
+    for y in 0..10u16 {
+        for x in 0..10u16 {
+            let corner = y * 11 + x;
+            batches[(x < 5) as usize].extend_from_slice(&[
+                corner,
+                corner + 1,
+                corner + 12,
+                corner,
+                corner + 12,
+                corner + 11,
+            ]);
+        }
+    }
+    let [first, second] = batches;
+
+    let mut two_sided = StaticMaterial::new("Tileset\\Generic\\Grass.blp");
+    two_sided.blend_mode = 1;
+    two_sided.flags = WmoMaterialFlags::TWO_SIDED;
+    let up = Vec3 {
+        x: 0.0,
+        y: 0.0,
+        z: 1.0,
+    };
+    StaticWmoBuilder::new("Floor")
+        .vertices(
+            positions.clone(),
+            vec![up; positions.len()],
+            vec![TexCoord { u: 0.0, v: 0.0 }; positions.len()],
+        )
+        .material(StaticMaterial::new("Tileset\\Generic\\Stone.blp"))
+        .material(two_sided)
+        .batch(0, first)
+        .batch(1, second)
+}
+
+fn build(builder: &StaticWmoBuilder) -> (WmoRoot, WmoGroup) {
+    let mut root = Vec::new();
+    let mut group = Vec::new();
+    builder.write_root(&mut root).unwrap();
+    builder.write_group(&mut group).unwrap();
+
+    let ParsedWmo::Root(root) = parse_wmo(&mut Cursor::new(root)).unwrap() else {
+        panic!("expected a root file");
+    };
+    let ParsedWmo::Group(group) = parse_wmo(&mut Cursor::new(group)).unwrap() else {
+        panic!("expected a group file");
+    };
+    (root, group)
+}
+
+#[test]
+fn test_static_root() {
+    let (root, _) = build(&floor());
+    assert_eq!(root.n_groups, 1);
+    assert_eq!(root.n_materials, 2);
+    assert_eq!(root.group_names, ["Floor"]);
+    assert_eq!(
+        root.textures,
+        ["Tileset\\Generic\\Stone.blp", "Tileset\\Generic\\Grass.blp"]
+    );
+    assert_eq!(root.materials[1].blend_mode, 1);
+    assert_eq!(root.materials[1].flags, 0x4);
+    assert_eq!(
+        root.materials[1].get_texture1_index(&root.texture_offset_index_map),
+        1
+    );
+    assert_eq!(root.bounding_box_max, [10.0, 10.0, 0.0]);
+    assert_eq!(root.group_info[0].flags, 0x1 | 0x8);
+    assert_eq!(root.doodad_sets.len(), 1);
+}
+
+#[test]
+fn test_static_group() {
+    let (_, group) = build(&floor());
+    assert_eq!(group.flags, 0x1 | 0x8);
+    assert_eq!(group.ext_batch_count, 2);
+    assert_eq!(group.int_batch_count, 0);
+    assert_eq!(group.vertex_positions.len(), 121);
+    assert_eq!(group.vertex_indices.len(), 600);
+    assert_eq!(group.material_info.len(), 200);
+    assert!(group.material_info.iter().all(|m| m.flags == 0x20));
+
+    let batch = &group.render_batches[1];
+    assert_eq!(batch.start_index, 300);
+    assert_eq!(batch.count, 300);
+    assert_eq!(batch.material_id, 1);
+    assert_eq!(batch.bounding_box_min, [0, 0, 0]);
+    assert_eq!(batch.bounding_box_max, [5, 10, 0]);
+}
+
+#[test]
+fn test_static_bsp() {
+    let (_, group) = build(&floor());
+    assert!(group.bsp_nodes.len() > 1);
+
+    // Every triangle is referenced by a leaf
+    let mut referenced = vec![false; group.material_info.len()];
+    for node in group.bsp_nodes.iter().filter(|node| node.flags & 0x4 != 0) {
+        let start = node.face_start as usize;
+        for &face in &group.bsp_face_indices[start..start + node.n_faces as usize] {
+            referenced[face as usize] = true;
+        }
+    }
+    assert!(referenced.iter().all(|&r| r));
+
+    // Walking the tree towards a point ends in a leaf holding its triangle
+    let point = [7.25, 2.5];
+    let mut node = &group.bsp_nodes[0];
+    while node.flags & 0x4 == 0 {
+        let child = if point[node.flags as usize] < node.plane_distance {
+            node.neg_child
+        } else {
+            node.pos_child
+        };
+        node = &group.bsp_nodes[child as usize];
+    }
+    let start = node.face_start as usize;
+    let faces = &group.bsp_face_indices[start..start + node.n_faces as usize];
+    assert!(faces.contains(&25));
+}
+
+#[test]
+fn test_static_collision() {
+    let collision = vec![
+        Vec3 {
+            x: 0.0,
+            y: 0.0,
+            z: 2.0,
+        },
+        Vec3 {
+            x: 1.0,
+            y: 0.0,
+            z: 2.0,
+        },
+        Vec3 {
+            x: 0.0,
+            y: 1.0,
+            z: 2.0,
+        },
+    ];
+    let builder = floor()
+        .collision(collision, vec![0, 1, 2])
+        .render_collision(false)
+        .indoor(true);
+    assert_eq!(builder.triangle_count(), 201);
+    assert_eq!(builder.bounding_box().max.z, 2.0);
+
+    let (root, group) = build(&builder);
+    assert_eq!(root.group_info[0].flags, 0x1 | 0x4 | 0x2000);
+    assert_eq!(group.int_batch_count, 2);
+    assert_eq!(group.vertex_colors.len(), 124);
+    assert!(group.material_info[..200].iter().all(|m| m.flags == 0x24));
+    assert_eq!(group.material_info[200].flags, 0x08);
+    assert_eq!(group.material_info[200].material_id, 0xFF);
+    assert_eq!(&group.vertex_indices[600..], [121, 122, 123]);
+}
+
+#[test]
+fn test_static_validation() {
+    let missing_material = floor().batch(2, vec![0, 1, 2]);
+    assert!(matches!(
+        missing_material.write_root(&mut Vec::new()),
+        Err(WmoError::InvalidReference { .. })
+    ));
+
+    let missing_vertex = floor().batch(0, vec![0, 1, 121]);
+    assert!(missing_vertex.write_group(&mut Vec::new()).is_err());
+
+    let partial_triangle = floor().collision(Vec::new(), vec![0, 1]);
+    assert!(matches!(
+        partial_triangle.write_group(&mut Vec::new()),
+        Err(WmoError::InvalidFormat(_))
+    ));
+}
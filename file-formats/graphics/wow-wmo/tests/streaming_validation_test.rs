@@ -0,0 +1,119 @@
+use std::io::Cursor;
+use wow_wmo::{
+    StaticMaterial, StaticWmoBuilder, TexCoord, ValidationError, ValidationReport, Vec3,
+    WmoValidator,
+};
+
+fn quad() -> (Vec<u8>, Vec<u8>) {
+    let positions = vec![
+        Vec3 {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        },
+        Vec3 {
+            x: 1.0,
+            y: 0.0,
+            z: 0.0,
+        },
+        Vec3 {
+            x: 1.0,
+            y: 1.0,
+            z: 0.0,
+        },
+        Vec3 {
+            x: 0.0,
+            y: 1.0,
+            z: 0.0,
+        },
+    ];
+    let up = Vec3 {
+        x: 0.0,
+        y: 0.0,
+        z: 1.0,
+    };
+    let builder = StaticWmoBuilder::new("Quad")
+        .vertices(positions, vec![up; 4], vec![TexCoord { u: 0.0, v: 0.0 }; 4])
+        .material(StaticMaterial::new("Tileset\\Generic\\Stone.blp"))
+        .batch(0, vec![0, 1, 2, 0, 2, 3]);
+
+    let mut root = Vec::new();
+    let mut group = Vec::new();
+    builder.write_root(&mut root).unwrap();
+    builder.write_group(&mut group).unwrap();
+    (root, group)
+}
+
+fn validate(bytes: Vec<u8>) -> ValidationReport {
+    WmoValidator::new()
+        .validate_stream(&mut Cursor::new(bytes))
+        .unwrap()
+}
+
+/// Offset of the data of the first chunk with the given on-disk magic
+fn chunk_data(bytes: &[u8], magic: &[u8; 4]) -> usize {
+    bytes.windows(4).position(|window| window == magic).unwrap() + 8
+}
+
+#[test]
+fn test_stream_valid_files() {
+    let (root, group) = quad();
+
+    let report = validate(root);
+    assert!(!report.has_errors(), "{:?}", report.errors);
+    assert!(!report.has_warnings(), "{:?}", report.warnings);
+
+    let report = validate(group);
+    assert!(!report.has_errors(), "{:?}", report.errors);
+    assert!(!report.has_warnings(), "{:?}", report.warnings);
+}
+
+#[test]
+fn test_stream_root_count_mismatch() {
+    let (mut root, _) = quad();
+    let mohd = chunk_data(&root, b"DHOM");
+    root[mohd..mohd + 4].copy_from_slice(&3u32.to_le_bytes());
+
+    let report = validate(root);
+    assert!(report.errors.iter().any(|error| matches!(
+        error,
+        ValidationError::CountMismatch { field, expected: 3, actual: 1 } if field == "materials"
+    )));
+}
+
+#[test]
+fn test_stream_group_bad_index() {
+    let (_, mut group) = quad();
+    let movi = chunk_data(&group, b"IVOM");
+    group[movi + 8..movi + 10].copy_from_slice(&9u16.to_le_bytes());
+
+    let report = validate(group);
+    assert!(report.errors.iter().any(|error| matches!(
+        error,
+        ValidationError::InvalidReference { field, value: 9, max: 3 } if field == "indices[4]"
+    )));
+}
+
+#[test]
+fn test_stream_truncated_file() {
+    let (_, mut group) = quad();
+    group.truncate(group.len() - 10);
+
+    let report = validate(group);
+    assert!(
+        report
+            .errors
+            .iter()
+            .any(|error| matches!(error, ValidationError::InvalidValue { .. }))
+    );
+}
+
+#[test]
+fn test_stream_not_a_wmo() {
+    let report = validate(b"not a model file at all".to_vec());
+    assert!(
+        report.errors.iter().any(
+            |error| matches!(error, ValidationError::EmptyData { field, .. } if field == "MVER")
+        )
+    );
+}
@@ -0,0 +1,278 @@
+//! Format detection for World of Warcraft files.
+//!
+//! [`sniff`] tells which game format a buffer holds from its magic and, for
+//! the chunked formats that all start with an `MVER` chunk, from the chunk
+//! that follows it. File names are not consulted, so files with a wrong or
+//! missing extension, such as those extracted from archives without a
+//! listfile, are still recognized.
+//!
+//! ```
+//! use warcraft_formats::{DetectedFormat, sniff};
+//!
+//! assert_eq!(sniff(b"BLP2\x01\x00\x00\x00"), DetectedFormat::Blp);
+//! assert_eq!(sniff(b"MPQ\x1a\x20\x00\x00\x00"), DetectedFormat::Mpq);
+//! assert_eq!(sniff(b"not a game file"), DetectedFormat::Unknown);
+//! ```
+//!
+//! Only the start of a file is needed: a few hundred bytes identify every
+//! format except MPQ archives behind an executable stub, which are found at
+//! any 512 byte boundary of the buffer.
+
+use std::fmt;
+
+/// File format recognized by [`sniff`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum DetectedFormat {
+    /// MPQ archive, possibly behind user data or an executable stub
+    Mpq,
+    /// BLP texture (`BLP0`, `BLP1` or `BLP2`)
+    Blp,
+    /// DBC client database (`WDBC`)
+    Dbc,
+    /// DB2 client database (`WDB2` through `WDC5`)
+    Db2,
+    /// M2 model with a plain `MD20` header
+    M2,
+    /// M2 model in the chunked `MD21` layout of Legion and later
+    M2Chunked,
+    /// M2 skin profile
+    Skin,
+    /// WMO root file
+    WmoRoot,
+    /// WMO group file
+    WmoGroup,
+    /// ADT terrain tile, or the root file of a split tile
+    Adt,
+    /// Texture file of a split ADT tile (`_tex0`, `_tex1`)
+    AdtTex,
+    /// Object file of a split ADT tile (`_obj0`, `_obj1`)
+    AdtObj,
+    /// Level of detail file of a split ADT tile (`_lod`)
+    AdtLod,
+    /// WDT map definition
+    Wdt,
+    /// WDL low-resolution map
+    Wdl,
+    /// Not a recognized format
+    Unknown,
+}
+
+impl DetectedFormat {
+    /// Human readable name of the format
+    pub fn name(self) -> &'static str {
+        match self {
+            DetectedFormat::Mpq => "MPQ",
+            DetectedFormat::Blp => "BLP",
+            DetectedFormat::Dbc => "DBC",
+            DetectedFormat::Db2 => "DB2",
+            DetectedFormat::M2 => "M2 (MD20)",
+            DetectedFormat::M2Chunked => "M2 (MD21)",
+            DetectedFormat::Skin => "SKIN",
+            DetectedFormat::WmoRoot => "WMO root",
+            DetectedFormat::WmoGroup => "WMO group",
+            DetectedFormat::Adt => "ADT",
+            DetectedFormat::AdtTex => "ADT textures",
+            DetectedFormat::AdtObj => "ADT objects",
+            DetectedFormat::AdtLod => "ADT LOD",
+            DetectedFormat::Wdt => "WDT",
+            DetectedFormat::Wdl => "WDL",
+            DetectedFormat::Unknown => "unknown",
+        }
+    }
+
+    /// Usual file extension of the format, without the dot
+    pub fn extension(self) -> Option<&'static str> {
+        match self {
+            DetectedFormat::Mpq => Some("mpq"),
+            DetectedFormat::Blp => Some("blp"),
+            DetectedFormat::Dbc => Some("dbc"),
+            DetectedFormat::Db2 => Some("db2"),
+            DetectedFormat::M2 | DetectedFormat::M2Chunked => Some("m2"),
+            DetectedFormat::Skin => Some("skin"),
+            DetectedFormat::WmoRoot | DetectedFormat::WmoGroup => Some("wmo"),
+            DetectedFormat::Adt
+            | DetectedFormat::AdtTex
+            | DetectedFormat::AdtObj
+            | DetectedFormat::AdtLod => Some("adt"),
+            DetectedFormat::Wdt => Some("wdt"),
+            DetectedFormat::Wdl => Some("wdl"),
+            DetectedFormat::Unknown => None,
+        }
+    }
+
+    /// Whether the format is a sequence of IFF-style chunks starting with `MVER`
+    pub fn is_chunked(self) -> bool {
+        matches!(
+            self,
+            DetectedFormat::WmoRoot
+                | DetectedFormat::WmoGroup
+                | DetectedFormat::Adt
+                | DetectedFormat::AdtTex
+                | DetectedFormat::AdtObj
+                | DetectedFormat::AdtLod
+                | DetectedFormat::Wdt
+                | DetectedFormat::Wdl
+        )
+    }
+}
+
+impl fmt::Display for DetectedFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Detect the format of `bytes` from its content alone
+pub fn sniff(bytes: &[u8]) -> DetectedFormat {
+    match bytes.get(..4) {
+        Some(b"BLP0" | b"BLP1" | b"BLP2") => return DetectedFormat::Blp,
+        Some(b"WDBC") => return DetectedFormat::Dbc,
+        Some(
+            b"WDB2" | b"WDB3" | b"WDB4" | b"WDB5" | b"WDB6" | b"WDC1" | b"WDC2" | b"WDC3" | b"WDC4"
+            | b"WDC5",
+        ) => return DetectedFormat::Db2,
+        Some(b"MD20") => return DetectedFormat::M2,
+        Some(b"MD21") => return DetectedFormat::M2Chunked,
+        Some(b"SKIN") => return DetectedFormat::Skin,
+        _ => {}
+    }
+
+    if let Some(second) = chunk_after_mver(bytes) {
+        let format = match &second {
+            b"MHDR" => DetectedFormat::Adt,
+            b"MAMP" | b"MTEX" | b"MDID" => DetectedFormat::AdtTex,
+            b"MMDX" | b"MDDF" | b"MLDX" => DetectedFormat::AdtObj,
+            b"MLHD" => DetectedFormat::AdtLod,
+            b"MOHD" => DetectedFormat::WmoRoot,
+            b"MOGP" => DetectedFormat::WmoGroup,
+            b"MPHD" => DetectedFormat::Wdt,
+            b"MAOF" | b"MWMO" | b"MLDD" => DetectedFormat::Wdl,
+            _ => DetectedFormat::Unknown,
+        };
+        if format != DetectedFormat::Unknown {
+            return format;
+        }
+    }
+
+    if is_mpq(bytes) {
+        return DetectedFormat::Mpq;
+    }
+    DetectedFormat::Unknown
+}
+
+/// Detect the format of `bytes`, using the file extension for chunked files
+/// whose layout does not decide it
+///
+/// The content always wins: a BLP with an `.m2` extension is a BLP. The
+/// extension only names chunked files whose chunk after `MVER` is not one
+/// [`sniff`] knows, such as empty or unusual tiles.
+pub fn sniff_with_extension(bytes: &[u8], extension: Option<&str>) -> DetectedFormat {
+    let format = sniff(bytes);
+    if format != DetectedFormat::Unknown || chunk_after_mver(bytes).is_none() {
+        return format;
+    }
+    match extension.map(str::to_ascii_lowercase).as_deref() {
+        Some("adt") => DetectedFormat::Adt,
+        Some("wmo") => DetectedFormat::WmoRoot,
+        Some("wdt") => DetectedFormat::Wdt,
+        Some("wdl") => DetectedFormat::Wdl,
+        _ => DetectedFormat::Unknown,
+    }
+}
+
+/// ID of the chunk following a leading `MVER` chunk
+///
+/// Chunk IDs are stored byte-reversed; the returned ID reads forwards.
+fn chunk_after_mver(bytes: &[u8]) -> Option<[u8; 4]> {
+    if bytes.get(..4)? != b"REVM" {
+        return None;
+    }
+    let size = u32::from_le_bytes(bytes.get(4..8)?.try_into().ok()?) as usize;
+    if size != 4 {
+        return None;
+    }
+    let mut id: [u8; 4] = bytes.get(12..16)?.try_into().ok()?;
+    if !id
+        .iter()
+        .all(|b| b.is_ascii_uppercase() || b.is_ascii_digit())
+    {
+        return None;
+    }
+    id.reverse();
+    Some(id)
+}
+
+/// Whether an MPQ header or user data header starts at a 512 byte boundary
+fn is_mpq(bytes: &[u8]) -> bool {
+    bytes
+        .chunks(0x200)
+        .any(|block| matches!(block.get(..4), Some(b"MPQ\x1a" | b"MPQ\x1b")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(id: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut out: Vec<u8> = id.iter().rev().copied().collect();
+        out.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        out.extend_from_slice(payload);
+        out
+    }
+
+    fn chunked(second: &[u8; 4]) -> Vec<u8> {
+        let mut data = chunk(b"MVER", &18u32.to_le_bytes());
+        data.extend(chunk(second, &[0; 16]));
+        data
+    }
+
+    #[test]
+    fn test_sniff_magic() {
+        assert_eq!(sniff(b"BLP1\0\0\0\0"), DetectedFormat::Blp);
+        assert_eq!(sniff(b"WDBC\0\0\0\0"), DetectedFormat::Dbc);
+        assert_eq!(sniff(b"WDC3\0\0\0\0"), DetectedFormat::Db2);
+        assert_eq!(sniff(b"MD20\x08\x01\0\0"), DetectedFormat::M2);
+        assert_eq!(sniff(b"MD21\0\0\0\0"), DetectedFormat::M2Chunked);
+        assert_eq!(sniff(b"SKIN\0\0\0\0"), DetectedFormat::Skin);
+        assert_eq!(sniff(b"MPQ\x1a\x20\0\0\0"), DetectedFormat::Mpq);
+        assert_eq!(sniff(b""), DetectedFormat::Unknown);
+
+        // Archives behind an executable stub
+        let mut installer = b"MZ".to_vec();
+        installer.resize(0x400, 0);
+        installer.extend_from_slice(b"MPQ\x1a");
+        assert_eq!(sniff(&installer), DetectedFormat::Mpq);
+    }
+
+    #[test]
+    fn test_sniff_chunked() {
+        assert_eq!(sniff(&chunked(b"MHDR")), DetectedFormat::Adt);
+        assert_eq!(sniff(&chunked(b"MAMP")), DetectedFormat::AdtTex);
+        assert_eq!(sniff(&chunked(b"MMDX")), DetectedFormat::AdtObj);
+        assert_eq!(sniff(&chunked(b"MLHD")), DetectedFormat::AdtLod);
+        assert_eq!(sniff(&chunked(b"MOHD")), DetectedFormat::WmoRoot);
+        assert_eq!(sniff(&chunked(b"MOGP")), DetectedFormat::WmoGroup);
+        assert_eq!(sniff(&chunked(b"MPHD")), DetectedFormat::Wdt);
+        assert_eq!(sniff(&chunked(b"MAOF")), DetectedFormat::Wdl);
+        assert!(sniff(&chunked(b"MOGP")).is_chunked());
+    }
+
+    #[test]
+    fn test_sniff_with_extension() {
+        let odd = chunked(b"MXYZ");
+        assert_eq!(sniff(&odd), DetectedFormat::Unknown);
+        assert_eq!(sniff_with_extension(&odd, Some("ADT")), DetectedFormat::Adt);
+        assert_eq!(sniff_with_extension(&odd, None), DetectedFormat::Unknown);
+
+        // Content wins over the extension
+        assert_eq!(
+            sniff_with_extension(b"BLP2\0\0\0\0", Some("m2")),
+            DetectedFormat::Blp
+        );
+        assert_eq!(
+            sniff_with_extension(b"garbage!", Some("adt")),
+            DetectedFormat::Unknown
+        );
+    }
+}
@@ -141,8 +141,9 @@ impl AdtSet {
             }
         };
 
+        // Split files are only loaded when present; pre-Cataclysm tiles have none
         // Load texture (optional but expected for Cataclysm+)
-        let texture = if let Some(tex_path) = &file_set.tex0 {
+        let texture = if let Some(tex_path) = file_set.tex0.as_ref().filter(|p| p.exists()) {
             let tex_data = fs::read(tex_path)?;
             let mut cursor = Cursor::new(tex_data);
             match parse_adt(&mut cursor)? {
@@ -154,7 +155,7 @@ impl AdtSet {
         };
 
         // Load object (optional but expected for Cataclysm+)
-        let object = if let Some(obj_path) = &file_set.obj0 {
+        let object = if let Some(obj_path) = file_set.obj0.as_ref().filter(|p| p.exists()) {
             let obj_data = fs::read(obj_path)?;
             let mut cursor = Cursor::new(obj_data);
             match parse_adt(&mut cursor)? {
@@ -166,7 +167,7 @@ impl AdtSet {
         };
 
         // Load LOD (optional, Legion+)
-        let lod = if let Some(lod_path) = &file_set.lod {
+        let lod = if let Some(lod_path) = file_set.lod.as_ref().filter(|p| p.exists()) {
             let lod_data = fs::read(lod_path)?;
             let mut cursor = Cursor::new(lod_data);
             match parse_adt(&mut cursor)? {
@@ -303,6 +304,7 @@ mod tests {
             blend_mesh_bounds: None,
             blend_mesh_vertices: None,
             blend_mesh_indices: None,
+            unknown_chunks: vec![],
         }
     }
 
@@ -386,4 +388,23 @@ mod tests {
         assert_eq!(merged.textures.len(), 0);
         assert_eq!(merged.models.len(), 0);
     }
+
+    #[test]
+    fn test_load_monolithic_without_split_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Test_32_48.adt");
+        crate::builder::AdtBuilder::new()
+            .with_version(AdtVersion::WotLK)
+            .add_texture("terrain/grass_01.blp")
+            .build()
+            .unwrap()
+            .write_to_file(&path)
+            .unwrap();
+
+        let adt_set = AdtSet::load_from_path(&path).unwrap();
+        assert!(adt_set.texture.is_none());
+        assert!(adt_set.object.is_none());
+        assert!(adt_set.lod.is_none());
+        assert!(!adt_set.is_complete());
+    }
 }
@@ -45,10 +45,12 @@ use std::io::{Read, Seek};
 use std::time::{Duration, Instant};
 
 use crate::chunk_discovery::{ChunkDiscovery, discover_chunks};
+use crate::chunk_id::ChunkId;
 use crate::chunks::mh2o::Mh2oChunk;
 use crate::chunks::{
     DoodadPlacement, MampChunk, MbbbChunk, MbmhChunk, MbmiChunk, MbnvChunk, McalChunk, McinChunk,
-    MclyChunk, McnkChunk, MfboChunk, MhdrChunk, MtxfChunk, MtxpChunk, WmoPlacement,
+    MclyChunk, McnkChunk, MfboChunk, MhdrChunk, MlhdChunk, MlllEntry, MlndEntry, MtxfChunk,
+    MtxpChunk, RawChunk, WmoPlacement,
 };
 use crate::error::Result;
 use crate::file_type::AdtFileType;
@@ -178,6 +180,12 @@ pub struct RootAdt {
     /// Triangle indices (u16) referencing MBNV vertex array.
     /// MCBB chunks in MCNK reference ranges within this array.
     pub blend_mesh_indices: Option<MbmiChunk>,
+
+    /// Root-level chunks this crate does not recognize, in file order
+    ///
+    /// Kept as opaque bytes and written back after the MCNK chunks, so
+    /// editing a tile from a newer expansion or custom editor keeps them.
+    pub unknown_chunks: Vec<RawChunk>,
 }
 
 impl RootAdt {
@@ -494,14 +502,38 @@ pub struct Obj0Adt {
     pub mcnk_objects: Vec<McnkChunkObject>,
 }
 
-/// Parsed LOD file (Cataclysm+ `_lod.adt`).
+/// Parsed LOD file (Legion+ `_lod.adt`).
 ///
-/// Contains level-of-detail data for distant terrain rendering.
-/// This format is minimally documented and currently a stub implementation.
+/// Contains level-of-detail terrain for distant rendering. The terrain mesh
+/// chunks are parsed into typed fields; liquid and object chunks, whose
+/// layouts are not fully known, are kept verbatim in `other_chunks` and
+/// written back unchanged. See [`crate::lod`] for generating LOD data from a
+/// root tile.
 #[derive(Debug, Clone)]
 pub struct LodAdt {
     /// Detected ADT version
     pub version: AdtVersion,
+
+    /// Header from MLHD chunk
+    pub header: Option<MlhdChunk>,
+
+    /// Vertex heights from MLVH chunk
+    pub heights: Vec<f32>,
+
+    /// Triangle indices from MLVI chunk
+    pub indices: Vec<u16>,
+
+    /// Levels of detail from MLLL chunk
+    pub levels: Vec<MlllEntry>,
+
+    /// Quadtree nodes from MLND chunk
+    pub nodes: Vec<MlndEntry>,
+
+    /// Skirt indices from MLSI chunk
+    pub skirt_indices: Vec<u16>,
+
+    /// Other chunks with their raw data, in file order
+    pub other_chunks: Vec<(ChunkId, Vec<u8>)>,
 }
 
 // ============================================================================
@@ -877,6 +909,7 @@ mod tests {
             blend_mesh_bounds: None,
             blend_mesh_vertices: None,
             blend_mesh_indices: None,
+            unknown_chunks: vec![],
         };
 
         let parsed = ParsedAdt::Root(Box::new(root));
@@ -925,6 +958,7 @@ mod tests {
             blend_mesh_bounds: None,
             blend_mesh_vertices: None,
             blend_mesh_indices: None,
+            unknown_chunks: vec![],
         };
 
         assert_eq!(root.texture_count(), 2);
@@ -10,7 +10,7 @@ use crate::builder::validation::{
 use crate::chunks::mh2o::Mh2oChunk;
 use crate::chunks::{
     DoodadPlacement, MampChunk, MbbbChunk, MbmhChunk, MbmiChunk, MbnvChunk, McnkChunk, MfboChunk,
-    MtxfChunk, MtxpChunk, WmoPlacement,
+    MtxfChunk, MtxpChunk, RawChunk, WmoPlacement,
 };
 use crate::error::{AdtError, Result};
 use crate::version::AdtVersion;
@@ -97,6 +97,9 @@ pub struct AdtBuilder {
 
     /// Blend mesh indices (MBMI chunk, MoP+)
     blend_mesh_indices: Option<MbmiChunk>,
+
+    /// Unrecognized chunks written back verbatim
+    unknown_chunks: Vec<RawChunk>,
 }
 
 impl AdtBuilder {
@@ -128,6 +131,7 @@ impl AdtBuilder {
             blend_mesh_bounds: None,
             blend_mesh_vertices: None,
             blend_mesh_indices: None,
+            unknown_chunks: Vec::new(),
         }
     }
 
@@ -583,6 +587,31 @@ impl AdtBuilder {
         self
     }
 
+    /// Add a chunk this crate has no structure for.
+    ///
+    /// The chunk is written verbatim after the MCNK chunks. Parsed ADTs keep
+    /// their unrecognized chunks this way through
+    /// [`from_parsed()`](Self::from_parsed).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use wow_adt::builder::AdtBuilder;
+    /// use wow_adt::{ChunkId, RawChunk};
+    ///
+    /// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let id = ChunkId::from_str("MXYZ").unwrap();
+    /// let builder = AdtBuilder::new()
+    ///     .add_unknown_chunk(RawChunk::new(id, vec![1, 2, 3, 4]));
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn add_unknown_chunk(mut self, chunk: RawChunk) -> Self {
+        self.unknown_chunks.push(chunk);
+        self
+    }
+
     /// Create builder from parsed ADT for modify workflows.
     ///
     /// This method enables load-modify-save patterns:
@@ -636,6 +665,7 @@ impl AdtBuilder {
             blend_mesh_bounds: root.blend_mesh_bounds,
             blend_mesh_vertices: root.blend_mesh_vertices,
             blend_mesh_indices: root.blend_mesh_indices,
+            unknown_chunks: root.unknown_chunks,
         }
     }
 
@@ -738,6 +768,7 @@ impl AdtBuilder {
             self.blend_mesh_bounds,
             self.blend_mesh_vertices,
             self.blend_mesh_indices,
+            self.unknown_chunks,
         ))
     }
 }
@@ -8,7 +8,7 @@ use crate::api::RootAdt;
 use crate::chunks::mh2o::Mh2oChunk;
 use crate::chunks::{
     DoodadPlacement, MampChunk, MbbbChunk, MbmhChunk, MbmiChunk, MbnvChunk, McnkChunk, MfboChunk,
-    MtxfChunk, MtxpChunk, WmoPlacement,
+    MtxfChunk, MtxpChunk, RawChunk, WmoPlacement,
 };
 use crate::error::Result;
 use crate::version::AdtVersion;
@@ -96,6 +96,9 @@ pub struct BuiltAdt {
 
     /// Blend mesh indices (MBMI chunk, MoP+)
     blend_mesh_indices: Option<MbmiChunk>,
+
+    /// Unrecognized chunks, written verbatim after MCNK
+    unknown_chunks: Vec<RawChunk>,
 }
 
 impl BuiltAdt {
@@ -121,6 +124,7 @@ impl BuiltAdt {
         blend_mesh_bounds: Option<MbbbChunk>,
         blend_mesh_vertices: Option<MbnvChunk>,
         blend_mesh_indices: Option<MbmiChunk>,
+        unknown_chunks: Vec<RawChunk>,
     ) -> Self {
         Self {
             version,
@@ -139,6 +143,7 @@ impl BuiltAdt {
             blend_mesh_bounds,
             blend_mesh_vertices,
             blend_mesh_indices,
+            unknown_chunks,
         }
     }
 
@@ -151,6 +156,9 @@ impl BuiltAdt {
     /// - **Upgrading**: Adds empty version-specific chunks as needed
     /// - **Downgrading**: Removes chunks not supported in target version
     ///
+    /// Unrecognized chunks are kept whatever the target version, since there
+    /// is no way to tell which version they belong to.
+    ///
     /// # Arguments
     ///
     /// * `root` - Parsed RootAdt to convert
@@ -255,6 +263,7 @@ impl BuiltAdt {
             blend_mesh_bounds,
             blend_mesh_vertices,
             blend_mesh_indices,
+            unknown_chunks: root.unknown_chunks,
         }
     }
 
@@ -354,6 +363,12 @@ impl BuiltAdt {
         self.blend_mesh_indices.as_ref()
     }
 
+    /// Get unrecognized chunks written after MCNK.
+    #[must_use]
+    pub fn unknown_chunks(&self) -> &[RawChunk] {
+        &self.unknown_chunks
+    }
+
     /// Serialize ADT to file.
     ///
     /// This method will:
@@ -379,6 +394,7 @@ impl BuiltAdt {
     /// MAMP (if present)
     /// MTXP (if present)
     /// MCNK[0..N]
+    /// unrecognized chunks (if any)
     /// ```
     ///
     /// # Errors
@@ -464,6 +480,7 @@ mod tests {
             None,
             None,
             None,
+            Vec::new(),
         )
     }
 
@@ -211,6 +211,13 @@ pub fn serialize_to_writer<W: Write + Seek>(adt: &BuiltAdt, writer: &mut W) -> R
         }
     }
 
+    // Unrecognized chunks go last so they do not shift any offsets
+    for chunk in adt.unknown_chunks() {
+        writer.write_all(&chunk.id.0)?;
+        writer.write_all(&(chunk.data.len() as u32).to_le_bytes())?;
+        writer.write_all(&chunk.data)?;
+    }
+
     // PASS 2: Update offset tables
 
     // Calculate MHDR offsets (relative to MHDR data start)
@@ -95,6 +95,26 @@ impl ChunkId {
     /// Blend mesh indices (MoP 5.0+) - triangle indices referencing MBNV
     pub const MBMI: Self = Self([b'I', b'M', b'B', b'M']);
 
+    // LOD file chunks (Legion 7.x+ `_lod.adt`)
+
+    /// LOD header - tile bounding box
+    pub const MLHD: Self = Self([b'D', b'H', b'L', b'M']);
+
+    /// LOD vertex heights - outer and inner grids of every level
+    pub const MLVH: Self = Self([b'H', b'V', b'L', b'M']);
+
+    /// LOD vertex indices - triangle indices into MLVH
+    pub const MLVI: Self = Self([b'I', b'V', b'L', b'M']);
+
+    /// LOD levels - height and index ranges per level of detail
+    pub const MLLL: Self = Self([b'L', b'L', b'L', b'M']);
+
+    /// LOD nodes - quadtree over the index buffer
+    pub const MLND: Self = Self([b'D', b'N', b'L', b'M']);
+
+    /// LOD skirt indices - close gaps at tile borders
+    pub const MLSI: Self = Self([b'I', b'S', b'L', b'M']);
+
     // MCNK subchunks
 
     /// Height map vertices - 9x9 + 8x8 grid (145 vertices total)
@@ -0,0 +1,118 @@
+//! Level-of-detail chunk structures (`_lod.adt`, Legion 7.x+)
+//!
+//! LOD files replace WDL-based distant terrain with a per-tile mesh:
+//!
+//! - MLHD - Header with the tile's bounding box
+//! - MLVH - Vertex heights of every level, outer 129×129 grid first, then inner 128×128
+//! - MLVI - Triangle indices (u16) into the heights of a level
+//! - MLLL - One entry per level of detail, with its height and index ranges
+//! - MLND - Quadtree over the index buffer for culling
+//! - MLSI - Skirt indices closing the gaps at tile borders
+//!
+//! Liquid (MLLD/MLLN/MLLI/MLLV) and object (MLDD/MLDX/MLMD/MLMX) chunks are
+//! kept as raw chunks by [`LodAdt`](crate::LodAdt).
+//!
+//! Reference: <https://wowdev.wiki/ADT/v18#_lod.adt>
+
+use binrw::{BinRead, BinWrite};
+
+/// MLHD - LOD header (28 bytes)
+#[derive(Debug, Clone, Copy, Default, PartialEq, BinRead, BinWrite)]
+#[brw(little)]
+pub struct MlhdChunk {
+    /// Unknown, usually 0
+    pub unknown: u32,
+
+    /// Bounding box of the tile as `[min_x, min_y, min_z, max_x, max_y, max_z]`
+    pub bounding_box: [f32; 6],
+}
+
+/// MLLL entry - one level of detail (20 bytes)
+#[derive(Debug, Clone, Copy, Default, PartialEq, BinRead, BinWrite)]
+#[brw(little)]
+pub struct MlllEntry {
+    /// Distance band the level is used for
+    pub lod_band: f32,
+
+    /// Number of MLVH heights of this level
+    pub height_count: u32,
+
+    /// First MLVH height of this level
+    pub height_index: u32,
+
+    /// Number of MLVI indices of this level
+    pub index_count: u32,
+
+    /// First MLVI index of this level
+    pub index_start: u32,
+}
+
+impl MlllEntry {
+    /// Size of one entry in bytes
+    pub const SIZE: usize = 20;
+}
+
+/// MLND entry - quadtree node over the index buffer (24 bytes)
+#[derive(Debug, Clone, Copy, Default, PartialEq, BinRead, BinWrite)]
+#[brw(little)]
+pub struct MlndEntry {
+    /// First MLVI index covered by the node
+    pub index_start: u32,
+
+    /// Number of MLVI indices covered by the node
+    pub index_count: u32,
+
+    /// Unknown, kept verbatim
+    pub unknown: [u32; 2],
+
+    /// MLND indices of the four children, 0 for none (the root is never a child)
+    pub children: [u16; 4],
+}
+
+impl MlndEntry {
+    /// Size of one entry in bytes
+    pub const SIZE: usize = 24;
+
+    /// Whether the node has no children
+    #[must_use]
+    pub fn is_leaf(&self) -> bool {
+        self.children.iter().all(|&child| child == 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_entry_sizes() {
+        let mut cursor = Cursor::new(Vec::new());
+        MlhdChunk::default().write(&mut cursor).unwrap();
+        assert_eq!(cursor.into_inner().len(), 28);
+
+        let mut cursor = Cursor::new(Vec::new());
+        MlllEntry::default().write(&mut cursor).unwrap();
+        assert_eq!(cursor.into_inner().len(), MlllEntry::SIZE);
+
+        let mut cursor = Cursor::new(Vec::new());
+        MlndEntry::default().write(&mut cursor).unwrap();
+        assert_eq!(cursor.into_inner().len(), MlndEntry::SIZE);
+    }
+
+    #[test]
+    fn test_mlnd_round_trip() {
+        let node = MlndEntry {
+            index_start: 96,
+            index_count: 768,
+            unknown: [0, 0],
+            children: [1, 2, 3, 4],
+        };
+        let mut cursor = Cursor::new(Vec::new());
+        node.write(&mut cursor).unwrap();
+        cursor.set_position(0);
+        let parsed = MlndEntry::read(&mut cursor).unwrap();
+        assert_eq!(parsed, node);
+        assert!(!parsed.is_leaf());
+    }
+}
@@ -11,11 +11,15 @@
 //! - [`mcnk`] - Terrain chunk with nested subchunks (MCVT, MCNR, MCLY, MCAL, etc.)
 //! - [`mh2o`] - Multi-level water structure (WotLK+)
 //! - [`blend_mesh`] - Blend mesh system chunks (MBMH, MBBB, MBNV, MBMI) for MoP 5.x+
+//! - [`lod`] - Level-of-detail chunks (MLHD, MLLL, MLND) of `_lod.adt` files, Legion 7.x+
+//! - [`raw`] - Unrecognized chunks kept as opaque bytes
 
 pub mod blend_mesh;
+pub mod lod;
 pub mod mcnk;
 pub mod mh2o;
 pub mod placement;
+pub mod raw;
 pub mod simple;
 pub mod strings;
 
@@ -48,3 +52,9 @@ pub use mh2o::{
 pub use blend_mesh::{
     MbbbChunk, MbbbEntry, MbmhChunk, MbmhEntry, MbmiChunk, MbnvChunk, MbnvVertex,
 };
+
+// Re-export LOD chunk structures (Legion 7.x+)
+pub use lod::{MlhdChunk, MlllEntry, MlndEntry};
+
+// Re-export opaque chunk storage
+pub use raw::RawChunk;
@@ -0,0 +1,25 @@
+//! Opaque storage for chunks the parser does not recognize.
+
+use crate::chunk_id::ChunkId;
+
+/// A root-level chunk kept as raw bytes.
+///
+/// Files from newer expansions or custom editors can carry chunks this crate
+/// has no structure for. They are kept verbatim so that parsing, editing and
+/// writing a tile does not strip them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawChunk {
+    /// Chunk identifier
+    pub id: ChunkId,
+
+    /// Chunk payload, without the 8-byte header
+    pub data: Vec<u8>,
+}
+
+impl RawChunk {
+    /// Create a raw chunk from its identifier and payload.
+    #[must_use]
+    pub fn new(id: ChunkId, data: Vec<u8>) -> Self {
+        Self { id, data }
+    }
+}
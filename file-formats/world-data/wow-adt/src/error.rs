@@ -338,6 +338,21 @@ pub enum AdtError {
     /// ```
     #[error("Version detection failed: {0}")]
     VersionDetectionFailed(String),
+
+    /// Coverage masks given for texture layer generation are unusable.
+    #[error("Invalid texture mask: {0}")]
+    InvalidTextureMask(String),
+
+    /// A WDT or WDL file of a map could not be read.
+    ///
+    /// Reported by the map audit, which reads these files alongside the ADTs.
+    #[error("Failed to read {path}: {message}")]
+    MapFile {
+        /// Path of the file
+        path: String,
+        /// Error reported by the file's parser
+        message: String,
+    },
 }
 
 impl From<binrw::Error> for AdtError {
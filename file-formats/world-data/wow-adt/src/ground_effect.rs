@@ -0,0 +1,144 @@
+//! Ground effects of terrain texture layers.
+//!
+//! Every MCLY layer carries an `effect_id`, a row of
+//! `GroundEffectTexture.dbc` that lists the grass and pebble doodads the
+//! client scatters where the layer is visible. Editors that swap a texture
+//! usually leave the old id behind, so retextured terrain grows the clutter
+//! of the previous texture, or none at all.
+//!
+//! [`RootAdt::ground_effects`] lists the ids in use,
+//! [`RootAdt::texture_ground_effects`] learns which effect goes with which
+//! texture, and [`RootAdt::apply_ground_effects`] assigns effects by texture.
+//! An id of 0 means the layer has no ground effect.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::fs::File;
+//! use wow_adt::builder::AdtBuilder;
+//! use wow_adt::{ParsedAdt, parse_adt};
+//!
+//! # fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let ParsedAdt::Root(original) = parse_adt(&mut File::open("original/Azeroth_32_48.adt")?)?
+//! else {
+//!     return Err("not a root ADT".into());
+//! };
+//! let ParsedAdt::Root(mut edited) = parse_adt(&mut File::open("Azeroth_32_48.adt")?)? else {
+//!     return Err("not a root ADT".into());
+//! };
+//!
+//! // Give every layer the effect its texture had in the original tile
+//! let effects = original.texture_ground_effects();
+//! let changed = edited.apply_ground_effects(&effects, true);
+//! println!("Updated {changed} layers");
+//! AdtBuilder::from_parsed(*edited).build()?.write_to_file("Azeroth_32_48.adt")?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::api::RootAdt;
+
+/// Ground effect of one texture layer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroundEffectLayer {
+    /// Index of the MCNK chunk (0-255)
+    pub chunk: usize,
+    /// Index of the layer within the chunk
+    pub layer: usize,
+    /// MTEX filename of the layer, if its texture id is valid
+    pub texture: Option<String>,
+    /// `GroundEffectTexture.dbc` id, 0 for none
+    pub effect_id: u32,
+}
+
+impl RootAdt {
+    /// Ground effect of every texture layer, in chunk and layer order.
+    pub fn ground_effects(&self) -> Vec<GroundEffectLayer> {
+        self.mcnk_chunks
+            .iter()
+            .enumerate()
+            .flat_map(|(chunk, mcnk)| {
+                mcnk.layers
+                    .iter()
+                    .flat_map(|l| l.layers.iter())
+                    .enumerate()
+                    .map(move |(layer, mcly)| GroundEffectLayer {
+                        chunk,
+                        layer,
+                        texture: self.textures.get(mcly.texture_id as usize).cloned(),
+                        effect_id: mcly.effect_id,
+                    })
+            })
+            .collect()
+    }
+
+    /// Ground effect used with each texture.
+    ///
+    /// When layers of one texture disagree, the effect used by most of them
+    /// wins; layers without an effect are not counted. Textures that never
+    /// have an effect are left out.
+    pub fn texture_ground_effects(&self) -> BTreeMap<String, u32> {
+        let mut counts: BTreeMap<String, HashMap<u32, usize>> = BTreeMap::new();
+        for layer in self.ground_effects() {
+            if let (Some(texture), effect_id @ 1..) = (layer.texture, layer.effect_id) {
+                *counts
+                    .entry(texture)
+                    .or_default()
+                    .entry(effect_id)
+                    .or_default() += 1;
+            }
+        }
+        counts
+            .into_iter()
+            .filter_map(|(texture, effects)| {
+                // Lowest id on ties, so the result does not depend on hashing
+                let best = effects
+                    .into_iter()
+                    .max_by_key(|&(effect_id, count)| (count, std::cmp::Reverse(effect_id)))?;
+                Some((texture, best.0))
+            })
+            .collect()
+    }
+
+    /// Set the ground effect of every layer whose texture is in `effects`.
+    ///
+    /// Textures are matched ignoring case and path separator style. Layers
+    /// that already have an effect are only changed when `overwrite` is set.
+    /// An effect id of 0 removes the ground effect. Returns the number of
+    /// layers whose effect changed.
+    pub fn apply_ground_effects(
+        &mut self,
+        effects: &BTreeMap<String, u32>,
+        overwrite: bool,
+    ) -> usize {
+        let effects: HashMap<String, u32> = effects
+            .iter()
+            .map(|(texture, &effect_id)| (texture_key(texture), effect_id))
+            .collect();
+        let texture_effects: Vec<Option<u32>> = self
+            .textures
+            .iter()
+            .map(|texture| effects.get(&texture_key(texture)).copied())
+            .collect();
+
+        let mut changed = 0;
+        for chunk in &mut self.mcnk_chunks {
+            for layer in chunk.layers.iter_mut().flat_map(|l| l.layers.iter_mut()) {
+                let Some(Some(effect_id)) = texture_effects.get(layer.texture_id as usize) else {
+                    continue;
+                };
+                if layer.effect_id != *effect_id && (overwrite || layer.effect_id == 0) {
+                    layer.effect_id = *effect_id;
+                    changed += 1;
+                }
+            }
+        }
+        changed
+    }
+}
+
+fn texture_key(path: &str) -> String {
+    path.replace('/', "\\").to_ascii_lowercase()
+}
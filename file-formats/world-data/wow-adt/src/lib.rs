@@ -164,6 +164,10 @@
 //! - [`file_type`] - AdtFileType enum (Root, Tex0, Obj0, etc.)
 //! - [`error`] - AdtError types with detailed context
 //! - [`chunks`] - Chunk structure definitions (MVER, MHDR, MCNK, etc.)
+//! - [`lod`] - `_lod.adt` terrain generation from root tiles
+//! - `map_audit` - WDT/ADT/WDL consistency audit of a whole map (`map-audit` feature)
+//! - [`prune`] - Removal of unreferenced textures, models and WMOs
+//! - [`validator`] - Streaming structural validation without a full parse
 //!
 //! ## References
 //!
@@ -183,8 +187,16 @@ pub mod chunks;
 pub mod combined_alpha_map;
 pub mod error;
 pub mod file_type;
+pub mod ground_effect;
+pub mod lod;
+#[cfg(feature = "map-audit")]
+pub mod map_audit;
 pub mod merger;
+pub mod ocean;
+pub mod prune;
 pub mod split_set;
+pub mod texture_layers;
+pub mod validator;
 pub mod version;
 
 // Internal parser modules
@@ -204,6 +216,12 @@ pub use chunk_id::ChunkId;
 pub use combined_alpha_map::CombinedAlphaMap;
 pub use error::{AdtError, Result};
 pub use file_type::AdtFileType;
+pub use ground_effect::GroundEffectLayer;
+pub use lod::LodOptions;
+pub use ocean::{OCEAN_LIQUID_TYPE, OceanFill, OceanFillReport};
+pub use prune::PrunedReferences;
+pub use texture_layers::{ChunkTextureLayers, TextureMask, generate_texture_layers};
+pub use validator::{ValidationLevel, ValidationReport, validate_stream};
 pub use version::AdtVersion;
 
 // Chunk structure re-exports
@@ -255,6 +273,8 @@ pub use chunks::{
     MverChunk,
     MwidChunk,
     MwmoChunk,
+    // Unrecognized chunks
+    RawChunk,
     SoundEmitter,
     TextureHeightParams,
     UvMapEntry,
@@ -0,0 +1,512 @@
+//! Level-of-detail terrain generation for `_lod.adt` files.
+//!
+//! Legion replaced WDL-based distant terrain with per-tile `_lod.adt` files.
+//! This module builds their terrain mesh from the heights of a root ADT and
+//! writes the result:
+//!
+//! - **Level 0** uses the full tile resolution: the outer 129×129 and inner
+//!   128×128 vertex grids (the MCVT grids of all 256 chunks joined), four
+//!   triangles per quad around its inner vertex, with holes left open. Its
+//!   indices are grouped by an MLND quadtree whose 256 leaves are the MCNK
+//!   chunks.
+//! - **Coarser levels** sample every 2nd, 4th, ... outer vertex and use two
+//!   triangles per cell. A cell is only dropped when every quad it covers is
+//!   a hole.
+//!
+//! MLVI indices are relative to the first MLVH height of their level.
+//! Skirts (MLSI), liquids and object chunks are not generated.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::fs::File;
+//! use std::io::BufReader;
+//! use wow_adt::{LodAdt, ParsedAdt, parse_adt};
+//!
+//! # fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut reader = BufReader::new(File::open("Azeroth_32_48.adt")?);
+//! let ParsedAdt::Root(root) = parse_adt(&mut reader)? else {
+//!     return Err("not a root ADT".into());
+//! };
+//!
+//! let lod = LodAdt::from_root(&root)?;
+//! std::fs::write("Azeroth_32_48_lod.adt", lod.to_bytes()?)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::io::{Cursor, Write};
+
+use binrw::BinWrite;
+
+use crate::api::{LodAdt, RootAdt};
+use crate::chunk_id::ChunkId;
+use crate::chunks::{McnkHeader, MlhdChunk, MlllEntry, MlndEntry};
+use crate::error::{AdtError, Result};
+
+/// Vertices per row of the tile's outer grid
+pub const OUTER_GRID_SIZE: usize = 129;
+
+/// Vertices per row of the tile's inner grid
+pub const INNER_GRID_SIZE: usize = 128;
+
+/// Size of an ADT tile in yards
+pub const TILE_SIZE: f32 = 533.333_3;
+
+/// Quads per row of the tile (and of the inner grid)
+const QUADS: usize = INNER_GRID_SIZE;
+
+/// Quads per row of one MCNK chunk
+const CHUNK_QUADS: usize = 8;
+
+/// Highest number of levels; the coarsest samples only the tile corners
+pub const MAX_LEVELS: usize = 8;
+
+/// Options for [`LodAdt::from_root_with_options`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LodOptions {
+    /// Number of levels to generate, including the full resolution level
+    /// (1 to [`MAX_LEVELS`])
+    pub levels: usize,
+}
+
+impl Default for LodOptions {
+    fn default() -> Self {
+        Self { levels: 3 }
+    }
+}
+
+/// Absolute heights of a tile on the joined outer and inner grids
+struct TileHeights {
+    outer: Vec<f32>,
+    inner: Vec<f32>,
+    /// Per quad, row-major: whether the quad is a hole
+    holes: Vec<bool>,
+    /// North-west corner of the tile in world coordinates (X, Y)
+    corner: [f32; 2],
+}
+
+impl TileHeights {
+    fn from_root(root: &RootAdt) -> Result<Self> {
+        if root.mcnk_chunks.is_empty() {
+            return Err(AdtError::MissingRequiredChunk(ChunkId::MCNK));
+        }
+
+        let mut tile = Self {
+            outer: vec![0.0; OUTER_GRID_SIZE * OUTER_GRID_SIZE],
+            inner: vec![0.0; INNER_GRID_SIZE * INNER_GRID_SIZE],
+            holes: vec![false; QUADS * QUADS],
+            corner: [f32::MIN, f32::MIN],
+        };
+
+        for mcnk in &root.mcnk_chunks {
+            let (cx, cy) = (mcnk.header.index_x as usize, mcnk.header.index_y as usize);
+            if cx >= 16 || cy >= 16 {
+                continue;
+            }
+            let mcvt = mcnk
+                .heights
+                .as_ref()
+                .ok_or(AdtError::MissingRequiredChunk(ChunkId::MCVT))?;
+            let [x, y, base] = mcnk.header.world_position();
+            tile.corner = [tile.corner[0].max(x), tile.corner[1].max(y)];
+
+            for row in 0..=CHUNK_QUADS {
+                for col in 0..=CHUNK_QUADS {
+                    let height = mcvt.get_outer_height(col, row).unwrap_or(0.0);
+                    let (r, c) = (cy * CHUNK_QUADS + row, cx * CHUNK_QUADS + col);
+                    tile.outer[r * OUTER_GRID_SIZE + c] = base + height;
+                }
+            }
+            for row in 0..CHUNK_QUADS {
+                for col in 0..CHUNK_QUADS {
+                    let height = mcvt.get_inner_height(col, row).unwrap_or(0.0);
+                    let (r, c) = (cy * CHUNK_QUADS + row, cx * CHUNK_QUADS + col);
+                    tile.inner[r * INNER_GRID_SIZE + c] = base + height;
+                    tile.holes[r * QUADS + c] = is_hole(&mcnk.header, col, row);
+                }
+            }
+        }
+
+        Ok(tile)
+    }
+
+    fn bounding_box(&self) -> [f32; 6] {
+        let (min_z, max_z) = self
+            .outer
+            .iter()
+            .chain(&self.inner)
+            .fold((f32::MAX, f32::MIN), |(lo, hi), &h| (lo.min(h), hi.max(h)));
+        let [x, y] = self.corner;
+        [x - TILE_SIZE, y - TILE_SIZE, min_z, x, y, max_z]
+    }
+}
+
+/// Whether quad (`x`, `y`) of a chunk is a hole
+fn is_hole(header: &McnkHeader, x: usize, y: usize) -> bool {
+    match header.holes_high_res() {
+        Some(holes) => holes & (1 << (y * 8 + x)) != 0,
+        None => header.holes_low_res & (1 << ((y / 2) * 4 + x / 2)) != 0,
+    }
+}
+
+fn outer_index(row: usize, col: usize) -> u16 {
+    (row * OUTER_GRID_SIZE + col) as u16
+}
+
+fn inner_index(row: usize, col: usize) -> u16 {
+    (OUTER_GRID_SIZE * OUTER_GRID_SIZE + row * INNER_GRID_SIZE + col) as u16
+}
+
+/// Build the quadtree node for a square of quads, emitting its triangles
+fn build_node(
+    tile: &TileHeights,
+    (row, col, size): (usize, usize, usize),
+    nodes: &mut Vec<MlndEntry>,
+    indices: &mut Vec<u16>,
+) -> u16 {
+    let node_index = nodes.len();
+    nodes.push(MlndEntry::default());
+    let index_start = indices.len();
+
+    if size == CHUNK_QUADS {
+        for r in row..row + size {
+            for c in col..col + size {
+                if tile.holes[r * QUADS + c] {
+                    continue;
+                }
+                let center = inner_index(r, c);
+                let corners = [
+                    outer_index(r, c),
+                    outer_index(r, c + 1),
+                    outer_index(r + 1, c + 1),
+                    outer_index(r + 1, c),
+                ];
+                for i in 0..4 {
+                    indices.extend([center, corners[i], corners[(i + 1) % 4]]);
+                }
+            }
+        }
+    } else {
+        let half = size / 2;
+        let quadrants = [(0, 0), (0, half), (half, 0), (half, half)];
+        for (i, (dr, dc)) in quadrants.into_iter().enumerate() {
+            let child = build_node(tile, (row + dr, col + dc, half), nodes, indices);
+            nodes[node_index].children[i] = child;
+        }
+    }
+
+    let node = &mut nodes[node_index];
+    node.index_start = index_start as u32;
+    node.index_count = (indices.len() - index_start) as u32;
+    node_index as u16
+}
+
+/// Heights and indices of a level sampling every `stride`-th outer vertex
+fn coarse_level(tile: &TileHeights, stride: usize) -> (Vec<f32>, Vec<u16>) {
+    let cells = QUADS / stride;
+    let side = cells + 1;
+
+    let mut heights = Vec::with_capacity(side * side);
+    for row in 0..side {
+        for col in 0..side {
+            heights.push(tile.outer[row * stride * OUTER_GRID_SIZE + col * stride]);
+        }
+    }
+
+    let mut indices = Vec::with_capacity(cells * cells * 6);
+    for row in 0..cells {
+        for col in 0..cells {
+            let all_holes = (row * stride..(row + 1) * stride)
+                .all(|r| (col * stride..(col + 1) * stride).all(|c| tile.holes[r * QUADS + c]));
+            if all_holes {
+                continue;
+            }
+            let index = |r: usize, c: usize| (r * side + c) as u16;
+            let (tl, tr) = (index(row, col), index(row, col + 1));
+            let (bl, br) = (index(row + 1, col), index(row + 1, col + 1));
+            indices.extend([tl, tr, br, tl, br, bl]);
+        }
+    }
+
+    (heights, indices)
+}
+
+impl LodAdt {
+    /// Generate LOD terrain from a root ADT with the default options
+    pub fn from_root(root: &RootAdt) -> Result<Self> {
+        Self::from_root_with_options(root, &LodOptions::default())
+    }
+
+    /// Generate LOD terrain from a root ADT
+    ///
+    /// Fails when the tile has no MCNK chunks or a chunk has no heights.
+    pub fn from_root_with_options(root: &RootAdt, options: &LodOptions) -> Result<Self> {
+        let tile = TileHeights::from_root(root)?;
+
+        let mut heights = Vec::with_capacity(tile.outer.len() + tile.inner.len());
+        heights.extend_from_slice(&tile.outer);
+        heights.extend_from_slice(&tile.inner);
+        let mut indices = Vec::new();
+        let mut nodes = Vec::new();
+        build_node(&tile, (0, 0, QUADS), &mut nodes, &mut indices);
+
+        let mut levels = vec![MlllEntry {
+            lod_band: TILE_SIZE,
+            height_count: heights.len() as u32,
+            height_index: 0,
+            index_count: indices.len() as u32,
+            index_start: 0,
+        }];
+
+        for level in 1..options.levels.clamp(1, MAX_LEVELS) {
+            let (level_heights, level_indices) = coarse_level(&tile, 1 << level);
+            levels.push(MlllEntry {
+                lod_band: TILE_SIZE * (1 << level) as f32,
+                height_count: level_heights.len() as u32,
+                height_index: heights.len() as u32,
+                index_count: level_indices.len() as u32,
+                index_start: indices.len() as u32,
+            });
+            heights.extend(level_heights);
+            indices.extend(level_indices);
+        }
+
+        Ok(Self {
+            version: root.version,
+            header: Some(MlhdChunk {
+                unknown: 0,
+                bounding_box: tile.bounding_box(),
+            }),
+            heights,
+            indices,
+            levels,
+            nodes,
+            skirt_indices: Vec::new(),
+            other_chunks: Vec::new(),
+        })
+    }
+
+    /// Heights and indices of one level
+    ///
+    /// Indices are relative to the returned heights. `None` when the level
+    /// does not exist or its ranges fall outside MLVH/MLVI.
+    #[must_use]
+    pub fn level_mesh(&self, level: usize) -> Option<(&[f32], &[u16])> {
+        let entry = self.levels.get(level)?;
+        let heights = self
+            .heights
+            .get(entry.height_index as usize..(entry.height_index + entry.height_count) as usize)?;
+        let indices = self
+            .indices
+            .get(entry.index_start as usize..(entry.index_start + entry.index_count) as usize)?;
+        Some((heights, indices))
+    }
+
+    /// Write the LOD file
+    ///
+    /// Retained chunks are written after the terrain chunks.
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        write_chunk(writer, ChunkId::MVER, &18u32.to_le_bytes())?;
+        if let Some(header) = &self.header {
+            write_chunk(writer, ChunkId::MLHD, &to_le_bytes(header)?)?;
+        }
+        let heights: Vec<u8> = self.heights.iter().flat_map(|h| h.to_le_bytes()).collect();
+        write_chunk(writer, ChunkId::MLVH, &heights)?;
+        let indices: Vec<u8> = self.indices.iter().flat_map(|i| i.to_le_bytes()).collect();
+        write_chunk(writer, ChunkId::MLVI, &indices)?;
+        write_chunk(writer, ChunkId::MLLL, &to_le_bytes(&self.levels)?)?;
+        write_chunk(writer, ChunkId::MLND, &to_le_bytes(&self.nodes)?)?;
+        if !self.skirt_indices.is_empty() {
+            let skirts: Vec<u8> = self
+                .skirt_indices
+                .iter()
+                .flat_map(|i| i.to_le_bytes())
+                .collect();
+            write_chunk(writer, ChunkId::MLSI, &skirts)?;
+        }
+        for (id, data) in &self.other_chunks {
+            write_chunk(writer, *id, data)?;
+        }
+        Ok(())
+    }
+
+    /// Serialize the LOD file to bytes
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+        self.write(&mut data)?;
+        Ok(data)
+    }
+}
+
+fn to_le_bytes<T>(value: &T) -> Result<Vec<u8>>
+where
+    T: BinWrite,
+    for<'a> T::Args<'a>: Default,
+{
+    let mut cursor = Cursor::new(Vec::new());
+    value
+        .write_le(&mut cursor)
+        .map_err(|e| AdtError::BinrwError(e.to_string()))?;
+    Ok(cursor.into_inner())
+}
+
+fn write_chunk<W: Write>(writer: &mut W, id: ChunkId, data: &[u8]) -> Result<()> {
+    writer.write_all(&id.0)?;
+    writer.write_all(&(data.len() as u32).to_le_bytes())?;
+    writer.write_all(data)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{ParsedAdt, parse_adt};
+    use crate::chunks::{McinChunk, McnkChunk, McnkFlags, McvtChunk, MhdrChunk};
+    use crate::version::AdtVersion;
+
+    fn header(index_x: u32, index_y: u32) -> McnkHeader {
+        let chunk_size = TILE_SIZE / 16.0;
+        McnkHeader {
+            flags: McnkFlags { value: 0 },
+            index_x,
+            index_y,
+            n_layers: 0,
+            n_doodad_refs: 0,
+            multipurpose_field: [0; 8],
+            ofs_layer: 0,
+            ofs_refs: 0,
+            ofs_alpha: 0,
+            size_alpha: 0,
+            ofs_shadow: 0,
+            size_shadow: 0,
+            area_id: 0,
+            n_map_obj_refs: 0,
+            holes_low_res: 0,
+            unknown_but_used: 1,
+            pred_tex: [0; 8],
+            no_effect_doodad: [0; 8],
+            unknown_8bytes: [0; 8],
+            ofs_snd_emitters: 0,
+            n_snd_emitters: 0,
+            ofs_liquid: 0,
+            size_liquid: 0,
+            // Stored as [Z, X, Y]
+            position: [
+                100.0,
+                1000.0 - index_y as f32 * chunk_size,
+                2000.0 - index_x as f32 * chunk_size,
+            ],
+            ofs_mccv: 0,
+            ofs_mclv: 0,
+            unused: 0,
+            _padding: [0; 8],
+        }
+    }
+
+    fn flat_root() -> RootAdt {
+        let mcnk_chunks = (0..256)
+            .map(|i| {
+                let mut chunk = McnkChunk {
+                    header: header(i % 16, i / 16),
+                    heights: Some(McvtChunk {
+                        heights: vec![i as f32; McvtChunk::VERTEX_COUNT],
+                    }),
+                    normals: None,
+                    layers: None,
+                    materials: None,
+                    refs: None,
+                    doodad_refs: None,
+                    wmo_refs: None,
+                    alpha: None,
+                    shadow: None,
+                    vertex_colors: None,
+                    vertex_lighting: None,
+                    sound_emitters: None,
+                    liquid: None,
+                    doodad_disable: None,
+                    blend_batches: None,
+                };
+                if i == 0 {
+                    // Hole over the first 2×2 quads
+                    chunk.header.holes_low_res = 1;
+                }
+                chunk
+            })
+            .collect();
+
+        RootAdt {
+            version: AdtVersion::WotLK,
+            mhdr: MhdrChunk::default(),
+            mcin: McinChunk::default(),
+            textures: Vec::new(),
+            models: Vec::new(),
+            model_indices: Vec::new(),
+            wmos: Vec::new(),
+            wmo_indices: Vec::new(),
+            doodad_placements: Vec::new(),
+            wmo_placements: Vec::new(),
+            mcnk_chunks,
+            flight_bounds: None,
+            water_data: None,
+            texture_flags: None,
+            texture_amplifier: None,
+            texture_params: None,
+            blend_mesh_headers: None,
+            blend_mesh_bounds: None,
+            blend_mesh_vertices: None,
+            blend_mesh_indices: None,
+            unknown_chunks: vec![],
+        }
+    }
+
+    #[test]
+    fn test_generate_full_resolution_level() {
+        let lod = LodAdt::from_root_with_options(&flat_root(), &LodOptions { levels: 1 }).unwrap();
+
+        assert_eq!(lod.heights.len(), 129 * 129 + 128 * 128);
+        // Four triangles per quad, minus the four hole quads
+        assert_eq!(lod.indices.len(), (128 * 128 - 4) * 12);
+        assert_eq!(lod.nodes.len(), 1 + 4 + 16 + 64 + 256);
+        assert_eq!(lod.nodes[0].index_count as usize, lod.indices.len());
+        assert_eq!(lod.nodes.iter().filter(|n| n.is_leaf()).count(), 256);
+
+        let [min_x, min_y, min_z, max_x, max_y, max_z] = lod.header.unwrap().bounding_box;
+        assert_eq!((max_x, max_y), (1000.0, 2000.0));
+        assert!((max_x - min_x - TILE_SIZE).abs() < 1e-3);
+        assert!((max_y - min_y - TILE_SIZE).abs() < 1e-3);
+        assert_eq!((min_z, max_z), (100.0, 355.0));
+
+        // The last chunk (15, 15) sits at the south-east corner
+        assert_eq!(lod.heights[129 * 129 - 1], 355.0);
+    }
+
+    #[test]
+    fn test_generate_coarse_levels() {
+        let lod = LodAdt::from_root_with_options(&flat_root(), &LodOptions { levels: 3 }).unwrap();
+        assert_eq!(lod.levels.len(), 3);
+
+        let (heights, indices) = lod.level_mesh(2).unwrap();
+        assert_eq!(heights.len(), 33 * 33);
+        assert_eq!(indices.len(), 32 * 32 * 6);
+        assert!(indices.iter().all(|&i| (i as usize) < heights.len()));
+    }
+
+    #[test]
+    fn test_write_and_parse_round_trip() {
+        let mut lod = LodAdt::from_root(&flat_root()).unwrap();
+        lod.other_chunks
+            .push((ChunkId::from_str("MLDD").unwrap(), vec![9; 8]));
+        let data = lod.to_bytes().unwrap();
+
+        let ParsedAdt::Lod(parsed) = parse_adt(&mut Cursor::new(data)).unwrap() else {
+            panic!("expected a LOD file");
+        };
+        assert_eq!(parsed.header, lod.header);
+        assert_eq!(parsed.heights, lod.heights);
+        assert_eq!(parsed.indices, lod.indices);
+        assert_eq!(parsed.levels, lod.levels);
+        assert_eq!(parsed.nodes, lod.nodes);
+        assert_eq!(parsed.other_chunks, lod.other_chunks);
+    }
+}
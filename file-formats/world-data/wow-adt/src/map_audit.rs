@@ -0,0 +1,588 @@
+//! Consistency audit of a whole map.
+//!
+//! Loads the WDT, every root ADT and the WDL of a map and reports what map
+//! reviewers otherwise check by hand:
+//!
+//! - tiles the WDT flags without an ADT, and ADTs the WDT does not flag
+//! - ADTs that fail to parse
+//! - WDL tiles missing for an ADT or present without one
+//! - WDL heights diverging from the ADT heights at the chunk corners the WDL
+//!   samples
+//! - area IDs that change along a whole tile border, the usual trace of a
+//!   tile repainted without its neighbours
+//!
+//! ADTs are reduced to a [`TileSummary`] as they are read, so auditing a
+//! continent does not keep every tile in memory.
+//!
+//! Requires the `map-audit` feature.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use wow_adt::map_audit::{MapAuditOptions, audit_map_dir};
+//! use wow_wdt::version::WowVersion;
+//!
+//! # fn example() -> wow_adt::Result<()> {
+//! let report = audit_map_dir(
+//!     "World/Maps/Azeroth",
+//!     "Azeroth",
+//!     WowVersion::WotLK,
+//!     &MapAuditOptions::default(),
+//! )?;
+//! for issue in &report.issues {
+//!     println!("{issue}");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use wow_wdl::WdlFile;
+use wow_wdl::parser::WdlParser;
+use wow_wdt::version::WowVersion;
+use wow_wdt::{WdtFile, WdtReader};
+
+use crate::api::{ParsedAdt, RootAdt, parse_adt};
+use crate::error::{AdtError, Result};
+
+/// Chunks per row of a tile
+const CHUNKS: usize = 16;
+
+/// Chunk corners per row of a tile, as sampled by the WDL
+const CORNERS: usize = CHUNKS + 1;
+
+/// Options for [`MapAudit`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MapAuditOptions {
+    /// Largest tolerated difference between WDL and ADT heights, in yards
+    pub max_height_difference: f32,
+    /// Chunks along a tile border (of 16) whose area IDs must differ from
+    /// the neighbour's for the border to be reported
+    pub min_area_seam: usize,
+}
+
+impl Default for MapAuditOptions {
+    fn default() -> Self {
+        Self {
+            max_height_difference: 25.0,
+            min_area_seam: CHUNKS,
+        }
+    }
+}
+
+/// What the audit keeps of a root ADT
+#[derive(Debug, Clone, PartialEq)]
+pub struct TileSummary {
+    /// Area ID per chunk, indexed `[index_y][index_x]`
+    pub area_ids: [[u32; CHUNKS]; CHUNKS],
+    /// Absolute heights at the chunk corners, indexed `[row][column]`;
+    /// `None` where no chunk has heights
+    pub corner_heights: [[Option<f32>; CORNERS]; CORNERS],
+}
+
+impl TileSummary {
+    /// Summarize a root ADT
+    pub fn from_root(root: &RootAdt) -> Self {
+        let mut summary = Self {
+            area_ids: [[0; CHUNKS]; CHUNKS],
+            corner_heights: [[None; CORNERS]; CORNERS],
+        };
+        for mcnk in &root.mcnk_chunks {
+            let (cx, cy) = (mcnk.header.index_x as usize, mcnk.header.index_y as usize);
+            if cx >= CHUNKS || cy >= CHUNKS {
+                continue;
+            }
+            summary.area_ids[cy][cx] = mcnk.header.area_id;
+
+            let Some(mcvt) = &mcnk.heights else {
+                continue;
+            };
+            let base = mcnk.header.world_position()[2];
+            // Each chunk fills its north-west corner, and the last row and
+            // column also their far corners
+            let rows: &[usize] = if cy == CHUNKS - 1 { &[0, 8] } else { &[0] };
+            let cols: &[usize] = if cx == CHUNKS - 1 { &[0, 8] } else { &[0] };
+            for &row in rows {
+                for &col in cols {
+                    if let Some(height) = mcvt.get_outer_height(col, row) {
+                        summary.corner_heights[cy + row / 8][cx + col / 8] = Some(base + height);
+                    }
+                }
+            }
+        }
+        summary
+    }
+}
+
+/// A problem found by the audit
+#[derive(Debug, Clone, PartialEq)]
+pub enum MapIssue {
+    /// The WDT flags the tile but there is no ADT for it
+    MissingTile {
+        /// Tile column
+        x: usize,
+        /// Tile row
+        y: usize,
+    },
+    /// There is an ADT for a tile the WDT does not flag
+    UnflaggedTile {
+        /// Tile column
+        x: usize,
+        /// Tile row
+        y: usize,
+    },
+    /// The ADT of the tile could not be parsed
+    UnreadableTile {
+        /// Tile column
+        x: usize,
+        /// Tile row
+        y: usize,
+        /// Parser error
+        error: String,
+    },
+    /// The WDL has no heights for a tile with an ADT
+    MissingWdlTile {
+        /// Tile column
+        x: usize,
+        /// Tile row
+        y: usize,
+    },
+    /// The WDL has heights for a tile the WDT does not flag
+    ExtraWdlTile {
+        /// Tile column
+        x: usize,
+        /// Tile row
+        y: usize,
+    },
+    /// WDL heights differ from the ADT heights by more than the tolerance
+    WdlHeightMismatch {
+        /// Tile column
+        x: usize,
+        /// Tile row
+        y: usize,
+        /// Largest difference, in yards
+        max_difference: f32,
+        /// Chunk corners beyond the tolerance (of 289)
+        points: usize,
+    },
+    /// Area IDs change along the border between two tiles
+    AreaSeam {
+        /// Tile column
+        x: usize,
+        /// Tile row
+        y: usize,
+        /// Column of the neighbour east or south of the tile
+        neighbor_x: usize,
+        /// Row of the neighbour east or south of the tile
+        neighbor_y: usize,
+        /// Chunks along the border with different area IDs (of 16)
+        chunks: usize,
+    },
+}
+
+impl MapIssue {
+    /// Tile the issue is about
+    pub fn tile(&self) -> (usize, usize) {
+        match *self {
+            Self::MissingTile { x, y }
+            | Self::UnflaggedTile { x, y }
+            | Self::UnreadableTile { x, y, .. }
+            | Self::MissingWdlTile { x, y }
+            | Self::ExtraWdlTile { x, y }
+            | Self::WdlHeightMismatch { x, y, .. }
+            | Self::AreaSeam { x, y, .. } => (x, y),
+        }
+    }
+}
+
+impl fmt::Display for MapIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingTile { x, y } => write!(f, "{x}_{y}: flagged in the WDT but has no ADT"),
+            Self::UnflaggedTile { x, y } => {
+                write!(f, "{x}_{y}: has an ADT but is not flagged in the WDT")
+            }
+            Self::UnreadableTile { x, y, error } => write!(f, "{x}_{y}: unreadable ADT: {error}"),
+            Self::MissingWdlTile { x, y } => write!(f, "{x}_{y}: no WDL heights for the ADT"),
+            Self::ExtraWdlTile { x, y } => {
+                write!(f, "{x}_{y}: WDL heights for a tile not flagged in the WDT")
+            }
+            Self::WdlHeightMismatch {
+                x,
+                y,
+                max_difference,
+                points,
+            } => write!(
+                f,
+                "{x}_{y}: WDL heights differ from the ADT at {points} points (up to {max_difference:.1} yards)"
+            ),
+            Self::AreaSeam {
+                x,
+                y,
+                neighbor_x,
+                neighbor_y,
+                chunks,
+            } => write!(
+                f,
+                "{x}_{y}: area IDs differ from {neighbor_x}_{neighbor_y} along {chunks} of 16 border chunks"
+            ),
+        }
+    }
+}
+
+/// Outcome of a map audit
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MapAuditReport {
+    /// Tiles flagged in the WDT
+    pub tiles_flagged: usize,
+    /// ADTs that were parsed and checked
+    pub tiles_checked: usize,
+    /// Whether WDL heights were compared
+    pub has_wdl: bool,
+    /// Problems, ordered by tile
+    pub issues: Vec<MapIssue>,
+}
+
+impl MapAuditReport {
+    /// Whether the audit found no problems
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Audit of one map, fed one tile at a time
+#[derive(Debug)]
+pub struct MapAudit<'a> {
+    wdt: &'a WdtFile,
+    wdl: Option<&'a WdlFile>,
+    options: MapAuditOptions,
+    tiles: BTreeMap<(usize, usize), TileSummary>,
+    unreadable: BTreeMap<(usize, usize), String>,
+}
+
+impl<'a> MapAudit<'a> {
+    /// Start an audit against the map's WDT and, if it has one, WDL
+    pub fn new(wdt: &'a WdtFile, wdl: Option<&'a WdlFile>, options: MapAuditOptions) -> Self {
+        Self {
+            wdt,
+            wdl,
+            options,
+            tiles: BTreeMap::new(),
+            unreadable: BTreeMap::new(),
+        }
+    }
+
+    /// Add the ADT of tile (`x`, `y`)
+    pub fn add_tile(&mut self, x: usize, y: usize, summary: TileSummary) {
+        self.tiles.insert((x, y), summary);
+    }
+
+    /// Record an ADT of tile (`x`, `y`) that could not be parsed
+    pub fn add_unreadable_tile(&mut self, x: usize, y: usize, error: impl ToString) {
+        self.unreadable.insert((x, y), error.to_string());
+    }
+
+    /// Run the checks
+    pub fn finish(self) -> MapAuditReport {
+        let flagged = |x: usize, y: usize| self.wdt.get_tile(x, y).is_some_and(|t| t.has_adt);
+        let mut issues = Vec::new();
+
+        let on_disk: BTreeSet<(usize, usize)> = self
+            .tiles
+            .keys()
+            .chain(self.unreadable.keys())
+            .copied()
+            .collect();
+        let mut tiles_flagged = 0;
+        for y in 0..64 {
+            for x in 0..64 {
+                let flagged = flagged(x, y);
+                tiles_flagged += usize::from(flagged);
+                match (flagged, on_disk.contains(&(x, y))) {
+                    (true, false) => issues.push(MapIssue::MissingTile { x, y }),
+                    (false, true) => issues.push(MapIssue::UnflaggedTile { x, y }),
+                    _ => {}
+                }
+            }
+        }
+        for (&(x, y), error) in &self.unreadable {
+            issues.push(MapIssue::UnreadableTile {
+                x,
+                y,
+                error: error.clone(),
+            });
+        }
+
+        if let Some(wdl) = self.wdl {
+            for &(x, y) in wdl.heightmap_tiles.keys().collect::<BTreeSet<_>>() {
+                let (x, y) = (x as usize, y as usize);
+                if !flagged(x, y) && !on_disk.contains(&(x, y)) {
+                    issues.push(MapIssue::ExtraWdlTile { x, y });
+                }
+            }
+            for (&(x, y), summary) in &self.tiles {
+                match wdl.heightmap_tiles.get(&(x as u32, y as u32)) {
+                    Some(heights) => issues.extend(self.compare_heights(x, y, summary, heights)),
+                    None => issues.push(MapIssue::MissingWdlTile { x, y }),
+                }
+            }
+        }
+
+        for (&(x, y), summary) in &self.tiles {
+            for (neighbor_x, neighbor_y) in [(x + 1, y), (x, y + 1)] {
+                let Some(neighbor) = self.tiles.get(&(neighbor_x, neighbor_y)) else {
+                    continue;
+                };
+                let chunks = (0..CHUNKS)
+                    .filter(|&i| {
+                        if neighbor_x > x {
+                            summary.area_ids[i][CHUNKS - 1] != neighbor.area_ids[i][0]
+                        } else {
+                            summary.area_ids[CHUNKS - 1][i] != neighbor.area_ids[0][i]
+                        }
+                    })
+                    .count();
+                if chunks > 0 && chunks >= self.options.min_area_seam {
+                    issues.push(MapIssue::AreaSeam {
+                        x,
+                        y,
+                        neighbor_x,
+                        neighbor_y,
+                        chunks,
+                    });
+                }
+            }
+        }
+
+        issues.sort_by_key(|issue| {
+            let (x, y) = issue.tile();
+            (y, x)
+        });
+        MapAuditReport {
+            tiles_flagged,
+            tiles_checked: self.tiles.len(),
+            has_wdl: self.wdl.is_some(),
+            issues,
+        }
+    }
+
+    fn compare_heights(
+        &self,
+        x: usize,
+        y: usize,
+        summary: &TileSummary,
+        wdl: &wow_wdl::types::HeightMapTile,
+    ) -> Option<MapIssue> {
+        let mut max_difference = 0.0f32;
+        let mut points = 0;
+        for (row, heights) in summary.corner_heights.iter().enumerate() {
+            for (col, height) in heights.iter().enumerate() {
+                let (Some(adt), Some(&wdl)) = (height, wdl.outer_values.get(row * CORNERS + col))
+                else {
+                    continue;
+                };
+                let difference = (adt - f32::from(wdl)).abs();
+                max_difference = max_difference.max(difference);
+                points += usize::from(difference > self.options.max_height_difference);
+            }
+        }
+        (points > 0).then_some(MapIssue::WdlHeightMismatch {
+            x,
+            y,
+            max_difference,
+            points,
+        })
+    }
+}
+
+/// Parse `<map>_<x>_<y>.adt` (case-insensitive) into tile coordinates
+///
+/// Split files (`_tex0`, `_obj0`, `_lod`) do not parse as root tiles.
+fn tile_of(map: &str, file_name: &str) -> Option<(usize, usize)> {
+    let lower = file_name.to_ascii_lowercase();
+    let rest = lower
+        .strip_prefix(&format!("{}_", map.to_ascii_lowercase()))?
+        .strip_suffix(".adt")?;
+    let (x, y) = rest.split_once('_')?;
+    let (x, y) = (x.parse().ok()?, y.parse().ok()?);
+    (x < 64 && y < 64).then_some((x, y))
+}
+
+fn map_file_error(path: &Path, error: impl fmt::Display) -> AdtError {
+    AdtError::MapFile {
+        path: path.display().to_string(),
+        message: error.to_string(),
+    }
+}
+
+/// Audit the map `map` in `dir`
+///
+/// Reads `<map>.wdt`, `<map>.wdl` if present, and every `<map>_<x>_<y>.adt`.
+/// ADTs that fail to parse are reported rather than failing the audit.
+pub fn audit_map_dir<P: AsRef<Path>>(
+    dir: P,
+    map: &str,
+    wdt_version: WowVersion,
+    options: &MapAuditOptions,
+) -> Result<MapAuditReport> {
+    let dir = dir.as_ref();
+
+    let wdt_path = dir.join(format!("{map}.wdt"));
+    let file = File::open(&wdt_path).map_err(|e| map_file_error(&wdt_path, e))?;
+    let wdt = WdtReader::new(BufReader::new(file), wdt_version)
+        .read()
+        .map_err(|e| map_file_error(&wdt_path, e))?;
+
+    let wdl_path = dir.join(format!("{map}.wdl"));
+    let wdl = if wdl_path.exists() {
+        let file = File::open(&wdl_path).map_err(|e| map_file_error(&wdl_path, e))?;
+        let wdl = WdlParser::new()
+            .parse(&mut BufReader::new(file))
+            .map_err(|e| map_file_error(&wdl_path, e))?;
+        Some(wdl)
+    } else {
+        None
+    };
+
+    let mut audit = MapAudit::new(&wdt, wdl.as_ref(), *options);
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let Some((x, y)) = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| tile_of(map, name))
+        else {
+            continue;
+        };
+        let parsed = File::open(entry.path())
+            .map_err(AdtError::from)
+            .and_then(|file| parse_adt(&mut BufReader::new(file)));
+        match parsed {
+            Ok(ParsedAdt::Root(root)) => audit.add_tile(x, y, TileSummary::from_root(&root)),
+            Ok(_) => audit.add_unreadable_tile(x, y, "not a root ADT"),
+            Err(e) => audit.add_unreadable_tile(x, y, e),
+        }
+    }
+    Ok(audit.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wow_wdl::types::HeightMapTile;
+
+    fn summary(area_id: u32, height: f32) -> TileSummary {
+        TileSummary {
+            area_ids: [[area_id; CHUNKS]; CHUNKS],
+            corner_heights: [[Some(height); CORNERS]; CORNERS],
+        }
+    }
+
+    fn wdl_tile(height: i16) -> HeightMapTile {
+        HeightMapTile {
+            outer_values: vec![height; HeightMapTile::OUTER_COUNT],
+            inner_values: vec![height; HeightMapTile::INNER_COUNT],
+        }
+    }
+
+    #[test]
+    fn test_audit_reports_each_check() {
+        let mut wdt = WdtFile::new(WowVersion::WotLK);
+        for (x, y) in [(30, 40), (31, 40), (30, 41), (5, 5)] {
+            wdt.main.get_mut(x, y).unwrap().set_has_adt(true);
+        }
+
+        let mut wdl = WdlFile::new();
+        let mut off = wdl_tile(100);
+        off.outer_values[20] = 300;
+        wdl.heightmap_tiles.insert((30, 40), wdl_tile(100));
+        wdl.heightmap_tiles.insert((31, 40), off);
+        wdl.heightmap_tiles.insert((10, 10), wdl_tile(0));
+
+        let mut audit = MapAudit::new(&wdt, Some(&wdl), MapAuditOptions::default());
+        audit.add_tile(30, 40, summary(12, 101.5));
+        audit.add_tile(31, 40, summary(14, 100.0));
+        let mut south = summary(12, 100.0);
+        south.area_ids[0][3] = 40;
+        audit.add_tile(30, 41, south);
+        audit.add_tile(2, 2, summary(1, 0.0));
+        audit.add_unreadable_tile(6, 6, "bad magic");
+
+        let report = audit.finish();
+        assert_eq!(report.tiles_flagged, 4);
+        assert_eq!(report.tiles_checked, 4);
+        assert_eq!(
+            report.issues,
+            [
+                MapIssue::UnflaggedTile { x: 2, y: 2 },
+                MapIssue::MissingWdlTile { x: 2, y: 2 },
+                MapIssue::MissingTile { x: 5, y: 5 },
+                MapIssue::UnflaggedTile { x: 6, y: 6 },
+                MapIssue::UnreadableTile {
+                    x: 6,
+                    y: 6,
+                    error: "bad magic".to_string()
+                },
+                MapIssue::ExtraWdlTile { x: 10, y: 10 },
+                MapIssue::AreaSeam {
+                    x: 30,
+                    y: 40,
+                    neighbor_x: 31,
+                    neighbor_y: 40,
+                    chunks: 16
+                },
+                MapIssue::WdlHeightMismatch {
+                    x: 31,
+                    y: 40,
+                    max_difference: 200.0,
+                    points: 1
+                },
+                MapIssue::MissingWdlTile { x: 30, y: 41 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_partial_area_seam_threshold() {
+        let mut wdt = WdtFile::new(WowVersion::WotLK);
+        wdt.main.get_mut(0, 0).unwrap().set_has_adt(true);
+        wdt.main.get_mut(0, 1).unwrap().set_has_adt(true);
+
+        let mut south = summary(1, 0.0);
+        for x in 0..10 {
+            south.area_ids[0][x] = 2;
+        }
+        let options = MapAuditOptions {
+            min_area_seam: 8,
+            ..Default::default()
+        };
+        let mut audit = MapAudit::new(&wdt, None, options);
+        audit.add_tile(0, 0, summary(1, 0.0));
+        audit.add_tile(0, 1, south);
+
+        let report = audit.finish();
+        assert!(!report.has_wdl);
+        assert_eq!(
+            report.issues,
+            [MapIssue::AreaSeam {
+                x: 0,
+                y: 0,
+                neighbor_x: 0,
+                neighbor_y: 1,
+                chunks: 10
+            }]
+        );
+    }
+
+    #[test]
+    fn test_tile_file_names() {
+        assert_eq!(tile_of("Azeroth", "azeroth_32_48.adt"), Some((32, 48)));
+        assert_eq!(tile_of("Azeroth", "Azeroth_32_48_tex0.adt"), None);
+        assert_eq!(tile_of("Azeroth", "Kalimdor_32_48.adt"), None);
+    }
+}
@@ -247,6 +247,7 @@ mod tests {
             blend_mesh_bounds: None,
             blend_mesh_vertices: None,
             blend_mesh_indices: None,
+            unknown_chunks: vec![],
         }
     }
 
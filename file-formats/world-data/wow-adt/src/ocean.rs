@@ -0,0 +1,232 @@
+//! Filling low terrain with ocean water.
+//!
+//! [`RootAdt::fill_ocean`] places a flat MH2O liquid layer at a fixed height
+//! over every part of the tile where the terrain dips below it. Each MCNK is
+//! looked at in its 8×8 liquid tiles: a tile gets water when at least one of
+//! its four corner heights is below the water level, so the water reaches
+//! the shoreline but stops at land that is entirely above it. The exists
+//! bitmap of the layer marks those tiles, which are also made fishable.
+//!
+//! Layers cover the full chunk with depth-only vertices (LVF 2), as the
+//! client's own oceans do; the depth of each vertex is the distance from the
+//! water surface down to the terrain.
+//!
+//! MH2O is a WotLK chunk. Earlier versions keep liquids in MCLQ, and
+//! [`BuiltAdt`](crate::BuiltAdt) drops MH2O when writing them.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::fs::File;
+//! use wow_adt::builder::AdtBuilder;
+//! use wow_adt::{OceanFill, ParsedAdt, parse_adt};
+//!
+//! # fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let ParsedAdt::Root(mut root) = parse_adt(&mut File::open("Kalimdor_20_30.adt")?)? else {
+//!     return Err("not a root ADT".into());
+//! };
+//!
+//! let report = root.fill_ocean(&OceanFill::new(0.0));
+//! println!("Flooded {} tiles in {} chunks", report.tiles, report.chunks);
+//! AdtBuilder::from_parsed(*root).build()?.write_to_file("Kalimdor_20_30.adt")?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::api::RootAdt;
+use crate::chunks::mcnk::McnkChunk;
+use crate::chunks::mh2o::{
+    DepthOnlyVertex, LiquidVertexFormat, Mh2oAttributes, Mh2oChunk, Mh2oEntry, Mh2oHeader,
+    Mh2oInstance, VertexDataArray,
+};
+
+/// Liquid tiles per chunk side
+const TILES: usize = 8;
+
+/// Liquid vertices per chunk side
+const VERTICES: usize = TILES + 1;
+
+/// `LiquidType.dbc` id of ocean water
+pub const OCEAN_LIQUID_TYPE: u16 = 2;
+
+/// Settings for [`RootAdt::fill_ocean`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OceanFill {
+    /// World height of the water surface
+    pub level: f32,
+    /// `LiquidType.dbc` id of the water
+    pub liquid_type: u16,
+    /// Replace the liquid of chunks that already have some
+    pub replace_existing: bool,
+}
+
+impl OceanFill {
+    /// Ocean water at `level`, leaving chunks with liquid untouched
+    pub fn new(level: f32) -> Self {
+        Self {
+            level,
+            liquid_type: OCEAN_LIQUID_TYPE,
+            replace_existing: false,
+        }
+    }
+
+    /// Use another liquid type, such as a lake or magma
+    pub fn liquid_type(mut self, liquid_type: u16) -> Self {
+        self.liquid_type = liquid_type;
+        self
+    }
+
+    /// Replace the liquid of chunks that already have some
+    pub fn replace_existing(mut self, replace: bool) -> Self {
+        self.replace_existing = replace;
+        self
+    }
+}
+
+/// Outcome of [`RootAdt::fill_ocean`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OceanFillReport {
+    /// Chunks that received a water layer
+    pub chunks: usize,
+    /// Liquid tiles (1/64 of a chunk) covered with water
+    pub tiles: usize,
+    /// Chunks below the level that were left alone because they already
+    /// have liquid
+    pub skipped: usize,
+}
+
+impl RootAdt {
+    /// Cover all terrain below `fill.level` with water.
+    ///
+    /// Chunks without terrain below the level are not changed. Chunks that
+    /// already have MH2O liquid are counted as skipped unless
+    /// [`OceanFill::replace_existing`] is set, in which case their layers
+    /// and attributes are replaced.
+    pub fn fill_ocean(&mut self, fill: &OceanFill) -> OceanFillReport {
+        let mut report = OceanFillReport::default();
+
+        for mcnk in &self.mcnk_chunks {
+            let (x, y) = (mcnk.header.index_x as usize, mcnk.header.index_y as usize);
+            if x >= Mh2oChunk::GRID_SIZE || y >= Mh2oChunk::GRID_SIZE {
+                continue;
+            }
+            let Some(ground) = ground_heights(mcnk) else {
+                continue;
+            };
+
+            let bitmap = flooded_tiles(&ground, fill.level);
+            if bitmap == 0 {
+                continue;
+            }
+
+            let water = self.water_data.get_or_insert_with(Mh2oChunk::new);
+            if water.entries.len() < Mh2oChunk::ENTRY_COUNT {
+                water
+                    .entries
+                    .resize_with(Mh2oChunk::ENTRY_COUNT, Mh2oEntry::default);
+            }
+            let entry = &mut water.entries[y * Mh2oChunk::GRID_SIZE + x];
+            // Offsets in the header are only set once the ADT is written, so
+            // look at the layers themselves
+            if !entry.instances.is_empty() && !fill.replace_existing {
+                report.skipped += 1;
+                continue;
+            }
+
+            *entry = ocean_entry(&ground, bitmap, fill);
+            report.chunks += 1;
+            report.tiles += bitmap.count_ones() as usize;
+        }
+
+        report
+    }
+}
+
+/// Absolute heights of the 9×9 outer vertices of a chunk, row by row
+fn ground_heights(mcnk: &McnkChunk) -> Option<[f32; VERTICES * VERTICES]> {
+    let mcvt = mcnk.heights.as_ref()?;
+    let base = mcnk.header.world_position()[2];
+
+    let mut heights = [0.0; VERTICES * VERTICES];
+    for row in 0..VERTICES {
+        for col in 0..VERTICES {
+            heights[row * VERTICES + col] = base + mcvt.get_outer_height(col, row)?;
+        }
+    }
+    Some(heights)
+}
+
+/// Exists bitmap of the tiles with a corner below `level`
+fn flooded_tiles(ground: &[f32; VERTICES * VERTICES], level: f32) -> u64 {
+    let mut bitmap = 0u64;
+    for row in 0..TILES {
+        for col in 0..TILES {
+            let corners = [
+                ground[row * VERTICES + col],
+                ground[row * VERTICES + col + 1],
+                ground[(row + 1) * VERTICES + col],
+                ground[(row + 1) * VERTICES + col + 1],
+            ];
+            if corners.iter().any(|&height| height < level) {
+                bitmap |= 1 << (row * TILES + col);
+            }
+        }
+    }
+    bitmap
+}
+
+/// A full-chunk flat layer showing the tiles of `bitmap`
+fn ocean_entry(ground: &[f32; VERTICES * VERTICES], bitmap: u64, fill: &OceanFill) -> Mh2oEntry {
+    let instance = Mh2oInstance {
+        liquid_type: fill.liquid_type,
+        liquid_object_or_lvf: LiquidVertexFormat::DepthOnly as u16,
+        min_height_level: fill.level,
+        max_height_level: fill.level,
+        x_offset: 0,
+        y_offset: 0,
+        width: TILES as u8,
+        height: TILES as u8,
+        offset_exists_bitmap: 0,
+        offset_vertex_data: 0,
+    };
+
+    let depths = ground.map(|height| {
+        Some(DepthOnlyVertex {
+            depth: (fill.level - height).clamp(0.0, 255.0).round() as u8,
+        })
+    });
+
+    Mh2oEntry {
+        header: Mh2oHeader {
+            layer_count: 1,
+            ..Default::default()
+        },
+        instances: vec![instance],
+        vertex_data: vec![Some(VertexDataArray::DepthOnly(Box::new(depths)))],
+        exists_bitmaps: vec![Some(bitmap)],
+        attributes: Some(Mh2oAttributes {
+            fishable: bitmap,
+            deep: 0,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flooded_tiles() {
+        // Ground falls by 2 per vertex column, from 10 down to -6
+        let ground: [f32; VERTICES * VERTICES] =
+            std::array::from_fn(|i| 10.0 - 2.0 * (i % VERTICES) as f32);
+
+        // Tile 4 spans 2 to 0 and stays dry; tiles 5-7 reach below 0
+        let bitmap = flooded_tiles(&ground, 0.0);
+        for row in 0..TILES {
+            assert_eq!((bitmap >> (row * TILES)) & 0xFF, 0b1110_0000);
+        }
+        assert_eq!(flooded_tiles(&ground, -6.0), 0);
+        assert_eq!(flooded_tiles(&ground, 11.0), u64::MAX);
+    }
+}
@@ -0,0 +1,243 @@
+//! Removal of unreferenced filenames from root ADTs.
+//!
+//! Editors append to MTEX, MMDX/MMID and MWMO/MWID when a texture or object
+//! is first used, but rarely remove an entry once the last layer or placement
+//! using it is gone. [`RootAdt::prune_unused_references`] drops those entries
+//! and remaps the indices that point at the remaining ones:
+//!
+//! - MCLY `texture_id` of every terrain layer, together with the per-texture
+//!   MTXF flags and MTXP parameters
+//! - MDDF and MODF `name_id`, which index MMID/MWID. The serializer writes one
+//!   MMID/MWID entry per filename, so after pruning `name_id` is the index of
+//!   the filename.
+//!
+//! MCRF/MCRD/MCRW reference placements rather than filenames and are not
+//! affected.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::fs::File;
+//! use wow_adt::builder::AdtBuilder;
+//! use wow_adt::{ParsedAdt, parse_adt};
+//!
+//! # fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let ParsedAdt::Root(mut root) = parse_adt(&mut File::open("Azeroth_32_48.adt")?)? else {
+//!     return Err("not a root ADT".into());
+//! };
+//!
+//! let pruned = root.prune_unused_references()?;
+//! println!("Removed {} unused filenames", pruned.len());
+//! AdtBuilder::from_parsed(*root).build()?.write_to_file("Azeroth_32_48.adt")?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::api::RootAdt;
+use crate::chunks::mcnk::MclyLayer;
+use crate::error::{AdtError, Result};
+
+/// Filenames removed by [`RootAdt::prune_unused_references`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PrunedReferences {
+    /// Removed MTEX textures
+    pub textures: Vec<String>,
+    /// Removed MMDX models
+    pub models: Vec<String>,
+    /// Removed MWMO world objects
+    pub wmos: Vec<String>,
+}
+
+impl PrunedReferences {
+    /// Total number of removed filenames
+    pub fn len(&self) -> usize {
+        self.textures.len() + self.models.len() + self.wmos.len()
+    }
+
+    /// Whether nothing was removed
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl RootAdt {
+    /// Remove textures, models and WMOs that no layer or placement references.
+    ///
+    /// Remaining entries keep their order; MCLY texture ids, MTXF/MTXP entries
+    /// and placement name ids are remapped to match, and MMID/MWID are
+    /// rebuilt with one offset per remaining filename.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdtError::InvalidTextureReference`] or
+    /// [`AdtError::InvalidModelReference`] if a layer or placement points at
+    /// an entry that does not exist. The ADT is left unchanged in that case.
+    pub fn prune_unused_references(&mut self) -> Result<PrunedReferences> {
+        // Resolve every reference before changing anything
+        let texture_count = self.textures.len();
+        for layer in self.layers() {
+            if layer.texture_id as usize >= texture_count {
+                return Err(AdtError::InvalidTextureReference {
+                    index: layer.texture_id,
+                    count: texture_count as u32,
+                });
+            }
+        }
+        let model_entries = name_entries(&self.models, &self.model_indices);
+        let model_refs = resolve(
+            self.doodad_placements.iter().map(|p| p.name_id),
+            &model_entries,
+        )?;
+        let wmo_entries = name_entries(&self.wmos, &self.wmo_indices);
+        let wmo_refs = resolve(self.wmo_placements.iter().map(|p| p.name_id), &wmo_entries)?;
+
+        let mut texture_used = vec![false; texture_count];
+        for layer in self.layers() {
+            texture_used[layer.texture_id as usize] = true;
+        }
+        let mut model_used = vec![false; self.models.len()];
+        for &model in &model_refs {
+            model_used[model] = true;
+        }
+        let mut wmo_used = vec![false; self.wmos.len()];
+        for &wmo in &wmo_refs {
+            wmo_used[wmo] = true;
+        }
+
+        let (texture_map, textures) = retain_used(&mut self.textures, &texture_used);
+        let (model_map, models) = retain_used(&mut self.models, &model_used);
+        let (wmo_map, wmos) = retain_used(&mut self.wmos, &wmo_used);
+
+        for chunk in &mut self.mcnk_chunks {
+            for layer in chunk.layers.iter_mut().flat_map(|l| l.layers.iter_mut()) {
+                layer.texture_id = texture_map[layer.texture_id as usize];
+            }
+        }
+        if let Some(flags) = &mut self.texture_flags {
+            retain_parallel(&mut flags.flags, &texture_used);
+        }
+        if let Some(params) = &mut self.texture_params {
+            retain_parallel(&mut params.entries, &texture_used);
+        }
+
+        for (placement, model) in self.doodad_placements.iter_mut().zip(model_refs) {
+            placement.name_id = model_map[model];
+        }
+        for (placement, wmo) in self.wmo_placements.iter_mut().zip(wmo_refs) {
+            placement.name_id = wmo_map[wmo];
+        }
+        self.model_indices = name_offsets(&self.models);
+        self.wmo_indices = name_offsets(&self.wmos);
+
+        Ok(PrunedReferences {
+            textures,
+            models,
+            wmos,
+        })
+    }
+
+    fn layers(&self) -> impl Iterator<Item = &MclyLayer> {
+        self.mcnk_chunks
+            .iter()
+            .flat_map(|chunk| chunk.layers.iter().flat_map(|l| l.layers.iter()))
+    }
+}
+
+/// Filename index of every MMID/MWID entry.
+///
+/// Entries are byte offsets into the filename block; an entry that does not
+/// point at the start of a filename resolves to `None`. Without offsets,
+/// e.g. for ADTs assembled in memory, entry `i` is filename `i`.
+fn name_entries(names: &[String], offsets: &[u32]) -> Vec<Option<usize>> {
+    if offsets.is_empty() {
+        return (0..names.len()).map(Some).collect();
+    }
+    let starts = name_offsets(names);
+    offsets
+        .iter()
+        .map(|offset| starts.iter().position(|start| start == offset))
+        .collect()
+}
+
+/// Resolve placement name ids to filename indices
+fn resolve(name_ids: impl Iterator<Item = u32>, entries: &[Option<usize>]) -> Result<Vec<usize>> {
+    name_ids
+        .map(|name_id| {
+            entries.get(name_id as usize).copied().flatten().ok_or(
+                AdtError::InvalidModelReference {
+                    index: name_id,
+                    count: entries.len() as u32,
+                },
+            )
+        })
+        .collect()
+}
+
+/// Byte offset of every filename in a null-terminated filename block
+fn name_offsets(names: &[String]) -> Vec<u32> {
+    names
+        .iter()
+        .scan(0u32, |offset, name| {
+            let start = *offset;
+            *offset += name.len() as u32 + 1;
+            Some(start)
+        })
+        .collect()
+}
+
+/// Keep used names, returning the new index of every old index and the removed names
+fn retain_used(names: &mut Vec<String>, used: &[bool]) -> (Vec<u32>, Vec<String>) {
+    let mut map = Vec::with_capacity(names.len());
+    let mut kept = Vec::new();
+    let mut removed = Vec::new();
+    for (name, &used) in names.drain(..).zip(used) {
+        map.push(kept.len() as u32);
+        if used {
+            kept.push(name);
+        } else {
+            removed.push(name);
+        }
+    }
+    *names = kept;
+    (map, removed)
+}
+
+/// Keep the entries of a per-texture list whose texture is used
+fn retain_parallel<T>(entries: &mut Vec<T>, used: &[bool]) {
+    let mut index = 0;
+    entries.retain(|_| {
+        let keep = used.get(index).copied().unwrap_or(false);
+        index += 1;
+        keep
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_name_entries() {
+        let names = vec!["a.m2".to_string(), "bb.m2".to_string()];
+        assert_eq!(name_offsets(&names), vec![0, 5]);
+        assert_eq!(
+            name_entries(&names, &[5, 0, 3]),
+            vec![Some(1), Some(0), None]
+        );
+        assert_eq!(name_entries(&names, &[]), vec![Some(0), Some(1)]);
+    }
+
+    #[test]
+    fn test_retain_used() {
+        let mut names = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let (map, removed) = retain_used(&mut names, &[true, false, true]);
+        assert_eq!(names, ["a", "c"]);
+        assert_eq!(removed, ["b"]);
+        assert_eq!(map[0], 0);
+        assert_eq!(map[2], 1);
+
+        let mut flags = vec![1, 2, 3];
+        retain_parallel(&mut flags, &[false, true]);
+        assert_eq!(flags, [2]);
+    }
+}
@@ -19,7 +19,7 @@ use crate::chunks::mh2o::{Mh2oAttributes, Mh2oChunk, Mh2oEntry, Mh2oHeader, Mh2o
 use crate::chunks::{
     MampChunk, MbbbChunk, MbmhChunk, MbmiChunk, MbnvChunk, McinChunk, McnkChunk, MddfChunk,
     MfboChunk, MhdrChunk, MmdxChunk, MmidChunk, ModfChunk, MtexChunk, MtxfChunk, MtxpChunk,
-    MwidChunk, MwmoChunk,
+    MwidChunk, MwmoChunk, RawChunk,
 };
 use crate::error::{AdtError, Result};
 use crate::version::AdtVersion;
@@ -254,7 +254,10 @@ pub fn parse_root_adt<R: Read + Seek>(
         if let Some(chunks) = discovery.get_chunks(ChunkId::MTXF) {
             if let Some(chunk_info) = chunks.first() {
                 reader.seek(SeekFrom::Start(chunk_info.offset + 8))?;
-                Some(MtxfChunk::read_le(reader)?)
+                // Entries are read until end of stream, so bound it to the chunk
+                let mut chunk_data = vec![0u8; chunk_info.size as usize];
+                reader.read_exact(&mut chunk_data)?;
+                Some(MtxfChunk::read_le(&mut std::io::Cursor::new(chunk_data))?)
             } else {
                 None
             }
@@ -286,7 +289,10 @@ pub fn parse_root_adt<R: Read + Seek>(
         if let Some(chunks) = discovery.get_chunks(ChunkId::MTXP) {
             if let Some(chunk_info) = chunks.first() {
                 reader.seek(SeekFrom::Start(chunk_info.offset + 8))?;
-                Some(MtxpChunk::read_le(reader)?)
+                // Entries are read until end of stream, so bound it to the chunk
+                let mut chunk_data = vec![0u8; chunk_info.size as usize];
+                reader.read_exact(&mut chunk_data)?;
+                Some(MtxpChunk::read_le(&mut std::io::Cursor::new(chunk_data))?)
             } else {
                 None
             }
@@ -361,6 +367,8 @@ pub fn parse_root_adt<R: Read + Seek>(
         None
     };
 
+    let unknown_chunks = read_unknown_chunks(reader, discovery)?;
+
     let root = RootAdt {
         version,
         mhdr,
@@ -382,11 +390,65 @@ pub fn parse_root_adt<R: Read + Seek>(
         blend_mesh_bounds,
         blend_mesh_vertices,
         blend_mesh_indices,
+        unknown_chunks,
     };
 
     Ok((root, warnings))
 }
 
+/// Root-level chunks the parser turns into `RootAdt` fields
+const PARSED_ROOT_CHUNKS: [ChunkId; 20] = [
+    ChunkId::MVER,
+    ChunkId::MHDR,
+    ChunkId::MCIN,
+    ChunkId::MTEX,
+    ChunkId::MMDX,
+    ChunkId::MMID,
+    ChunkId::MWMO,
+    ChunkId::MWID,
+    ChunkId::MDDF,
+    ChunkId::MODF,
+    ChunkId::MCNK,
+    ChunkId::MFBO,
+    ChunkId::MH2O,
+    ChunkId::MTXF,
+    ChunkId::MAMP,
+    ChunkId::MTXP,
+    ChunkId::MBMH,
+    ChunkId::MBBB,
+    ChunkId::MBNV,
+    ChunkId::MBMI,
+];
+
+/// Read all unrecognized root-level chunks as raw bytes, in file order.
+fn read_unknown_chunks<R: Read + Seek>(
+    reader: &mut R,
+    discovery: &ChunkDiscovery,
+) -> Result<Vec<RawChunk>> {
+    let mut locations: Vec<_> = discovery
+        .chunks
+        .iter()
+        .filter(|(id, _)| !PARSED_ROOT_CHUNKS.contains(id))
+        .flat_map(|(id, locations)| locations.iter().map(move |location| (*id, *location)))
+        .collect();
+    locations.sort_by_key(|(_, location)| location.offset);
+
+    let mut chunks = Vec::with_capacity(locations.len());
+    for (id, location) in locations {
+        log::debug!(
+            "Keeping unrecognized chunk {} ({} bytes) at offset {}",
+            id,
+            location.size,
+            location.offset
+        );
+        reader.seek(SeekFrom::Start(location.offset + 8))?;
+        let mut data = vec![0u8; location.size as usize];
+        reader.read_exact(&mut data)?;
+        chunks.push(RawChunk::new(id, data));
+    }
+    Ok(chunks)
+}
+
 /// Parse MH2O chunk with full 256-header structure.
 ///
 /// MH2O contains 256 headers (one per MCNK chunk), each with offsets to
@@ -36,8 +36,8 @@ use crate::chunk_header::ChunkHeader;
 use crate::chunk_id::ChunkId;
 use crate::chunks::mcnk::{McrdChunk, McrwChunk};
 use crate::chunks::{
-    McalChunk, MclyChunk, MddfChunk, MmdxChunk, MmidChunk, ModfChunk, MtexChunk, MtxpChunk,
-    MwidChunk, MwmoChunk,
+    McalChunk, MclyChunk, MddfChunk, MlhdChunk, MlllEntry, MlndEntry, MmdxChunk, MmidChunk,
+    ModfChunk, MtexChunk, MtxpChunk, MwidChunk, MwmoChunk,
 };
 use crate::error::Result;
 use crate::version::AdtVersion;
@@ -257,10 +257,17 @@ pub fn parse_obj_adt<R: Read + Seek>(
     Ok((obj, warnings))
 }
 
-/// Parse Cataclysm+ LOD file (_lod.adt).
+/// Parse Legion+ LOD file (_lod.adt).
 ///
-/// LOD files contain simplified geometry for distant terrain rendering.
-/// This format is minimally documented and this is a stub implementation.
+/// LOD files contain simplified geometry for distant terrain rendering:
+/// - MLHD - Header with bounding box
+/// - MLVH - Vertex heights
+/// - MLVI - Triangle indices
+/// - MLLL - Levels of detail
+/// - MLND - Quadtree nodes
+/// - MLSI - Skirt indices
+///
+/// Every other chunk (liquids, objects) is kept with its raw data.
 ///
 /// # Arguments
 ///
@@ -272,17 +279,87 @@ pub fn parse_obj_adt<R: Read + Seek>(
 ///
 /// Tuple of (LodAdt, warnings)
 pub fn parse_lod_adt<R: Read + Seek>(
-    _reader: &mut R,
-    _discovery: &ChunkDiscovery,
+    reader: &mut R,
+    discovery: &ChunkDiscovery,
     version: AdtVersion,
 ) -> Result<(LodAdt, Vec<String>)> {
-    let warnings = vec!["LOD file format not yet fully implemented".to_string()];
+    let mut warnings = Vec::new();
+    let mut lod = LodAdt {
+        version,
+        header: None,
+        heights: Vec::new(),
+        indices: Vec::new(),
+        levels: Vec::new(),
+        nodes: Vec::new(),
+        skirt_indices: Vec::new(),
+        other_chunks: Vec::new(),
+    };
 
-    let lod = LodAdt { version };
+    // Walk the chunks in file order so retained chunks keep their position
+    let mut chunks: Vec<_> = discovery
+        .chunks
+        .iter()
+        .flat_map(|(id, locations)| locations.iter().map(move |location| (*id, *location)))
+        .collect();
+    chunks.sort_by_key(|(_, location)| location.offset);
+
+    for (id, location) in chunks {
+        reader.seek(SeekFrom::Start(location.offset + 8))?;
+        let mut chunk_data = vec![0u8; location.size as usize];
+        reader.read_exact(&mut chunk_data)?;
+
+        match id {
+            ChunkId::MVER => {}
+            ChunkId::MLHD => {
+                lod.header = Some(MlhdChunk::read_le(&mut Cursor::new(chunk_data))?);
+            }
+            ChunkId::MLVH => lod.heights = read_array(&chunk_data, f32::from_le_bytes),
+            ChunkId::MLVI => lod.indices = read_array(&chunk_data, u16::from_le_bytes),
+            ChunkId::MLSI => lod.skirt_indices = read_array(&chunk_data, u16::from_le_bytes),
+            ChunkId::MLLL => {
+                lod.levels = read_entries(&chunk_data, MlllEntry::SIZE)?;
+            }
+            ChunkId::MLND => {
+                lod.nodes = read_entries(&chunk_data, MlndEntry::SIZE)?;
+            }
+            _ => lod.other_chunks.push((id, chunk_data)),
+        }
+    }
+
+    if lod.header.is_none() {
+        warnings.push("LOD file has no MLHD header".to_string());
+    }
+    let height_count = lod.heights.len() as u64;
+    for (i, level) in lod.levels.iter().enumerate() {
+        if u64::from(level.height_index) + u64::from(level.height_count) > height_count
+            || u64::from(level.index_start) + u64::from(level.index_count)
+                > lod.indices.len() as u64
+        {
+            warnings.push(format!("LOD level {i} references data past MLVH/MLVI"));
+        }
+    }
 
     Ok((lod, warnings))
 }
 
+/// Decode a chunk of little-endian primitives, ignoring trailing bytes
+fn read_array<T, const N: usize>(data: &[u8], decode: fn([u8; N]) -> T) -> Vec<T> {
+    data.chunks_exact(N)
+        .map(|bytes| decode(bytes.try_into().unwrap()))
+        .collect()
+}
+
+/// Decode a chunk of fixed-size binrw entries
+fn read_entries<T>(data: &[u8], entry_size: usize) -> Result<Vec<T>>
+where
+    T: for<'a> BinRead<Args<'a> = ()>,
+{
+    let mut cursor = Cursor::new(data);
+    (0..data.len() / entry_size)
+        .map(|_| Ok(T::read_le(&mut cursor)?))
+        .collect()
+}
+
 /// Parse MCNK chunks from texture file to extract texture layers and alpha maps.
 ///
 /// In split texture files, MCNK chunks are simplified containers that only hold
@@ -556,6 +633,50 @@ mod tests {
 
     #[test]
     fn test_parse_lod_adt() {
+        let mut data = Vec::new();
+        let mut chunk = |id: ChunkId, payload: &[u8]| {
+            data.extend_from_slice(&id.0);
+            data.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+            data.extend_from_slice(payload);
+        };
+        chunk(ChunkId::MVER, &18u32.to_le_bytes());
+        let mut mlhd = 0u32.to_le_bytes().to_vec();
+        for value in [0.0f32, 0.0, -5.0, 10.0, 10.0, 25.0] {
+            mlhd.extend_from_slice(&value.to_le_bytes());
+        }
+        chunk(ChunkId::MLHD, &mlhd);
+        let heights: Vec<u8> = [1.0f32, 2.0, 3.0]
+            .iter()
+            .flat_map(|h| h.to_le_bytes())
+            .collect();
+        chunk(ChunkId::MLVH, &heights);
+        chunk(ChunkId::MLVI, &[0, 0, 1, 0, 2, 0]);
+        let mut mlll = 0.0f32.to_le_bytes().to_vec();
+        for value in [3u32, 0, 3, 0] {
+            mlll.extend_from_slice(&value.to_le_bytes());
+        }
+        chunk(ChunkId::MLLL, &mlll);
+        chunk(ChunkId::from_str("MLDD").unwrap(), &[1, 2, 3, 4]);
+
+        let mut cursor = Cursor::new(data);
+        let discovery = discover_chunks(&mut cursor).unwrap();
+        let (lod, warnings) =
+            parse_lod_adt(&mut cursor, &discovery, AdtVersion::Cataclysm).unwrap();
+
+        assert!(warnings.is_empty(), "{warnings:?}");
+        assert_eq!(lod.header.unwrap().bounding_box[5], 25.0);
+        assert_eq!(lod.heights, [1.0, 2.0, 3.0]);
+        assert_eq!(lod.indices, [0, 1, 2]);
+        assert_eq!(lod.levels.len(), 1);
+        assert_eq!(lod.levels[0].index_count, 3);
+        assert_eq!(
+            lod.other_chunks,
+            [(ChunkId::from_str("MLDD").unwrap(), vec![1, 2, 3, 4])]
+        );
+    }
+
+    #[test]
+    fn test_parse_empty_lod_adt() {
         let data = Vec::new();
         let mut cursor = Cursor::new(data);
 
@@ -568,6 +689,7 @@ mod tests {
         let (lod, warnings) = result.unwrap();
 
         assert_eq!(lod.version, AdtVersion::Cataclysm);
+        assert!(lod.heights.is_empty());
         assert!(!warnings.is_empty());
     }
 
@@ -0,0 +1,378 @@
+//! Texture layers generated from coverage masks.
+//!
+//! Terrain generators usually produce one grayscale mask per texture, saying
+//! how much of the texture covers each point of the tile. ADT stores the same
+//! information per MCNK chunk as at most four MCLY layers, where layer 0 is
+//! drawn opaque and every further layer is blended over the layers below it
+//! with its own 64×64 MCAL alpha map.
+//!
+//! [`generate_texture_layers`] converts a set of masks into those layers for
+//! all 256 chunks, and [`RootAdt::apply_texture_masks`] does the same for a
+//! parsed tile, referencing the textures by filename. Masks are 1024×1024
+//! bytes per tile ([`TILE_MASK_SIZE`]), row by row from the north-west
+//! corner, so chunk `(x, y)` covers texels `x * 64..x * 64 + 64` of rows
+//! `y * 64..y * 64 + 64`.
+//!
+//! Coverage is normalized per texel: the masks give relative weights, and
+//! texels no mask covers show the chunk's base texture. A chunk only gets
+//! layers for textures it shows; if more than four do, the four with the
+//! most coverage in the chunk are kept and the weights of the others are
+//! shared between them. Layers keep the order of the masks.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::fs::File;
+//! use wow_adt::builder::AdtBuilder;
+//! use wow_adt::{AlphaFormat, ParsedAdt, parse_adt};
+//!
+//! # fn example(grass: &[u8], rock: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+//! let ParsedAdt::Root(mut adt) = parse_adt(&mut File::open("Custom_32_32.adt")?)? else {
+//!     return Err("not a root ADT".into());
+//! };
+//! adt.apply_texture_masks(
+//!     &[
+//!         ("Tileset\\Grass\\Grass01.blp", grass),
+//!         ("Tileset\\Rock\\Rock01.blp", rock),
+//!     ],
+//!     AlphaFormat::Compressed,
+//! )?;
+//! AdtBuilder::from_parsed(*adt).build()?.write_to_file("Custom_32_32.adt")?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::api::RootAdt;
+use crate::chunks::mcnk::{AlphaFormat, AlphaMap, McalChunk, MclyChunk, MclyFlags, MclyLayer};
+use crate::{AdtError, McnkChunk, Result};
+
+/// Width and height of a tile's coverage mask in texels
+pub const TILE_MASK_SIZE: usize = 16 * AlphaMap::RESOLUTION;
+
+/// MCLY flag: the layer is blended with an alpha map
+const FLAG_USE_ALPHA_MAP: u32 = 0x100;
+/// MCLY flag: the alpha map is RLE compressed
+const FLAG_ALPHA_COMPRESSED: u32 = 0x200;
+/// MCNK flag: alpha maps are a full 64×64, not 63×63 with a copied edge
+const FLAG_DO_NOT_FIX_ALPHA_MAP: u32 = 0x8000;
+
+/// Coverage of one texture across a tile
+#[derive(Debug, Clone, Copy)]
+pub struct TextureMask<'a> {
+    /// Index of the texture in MTEX
+    pub texture_id: u32,
+    /// [`TILE_MASK_SIZE`]² coverage values, row by row
+    pub coverage: &'a [u8],
+}
+
+/// Texture layers and alpha maps of one MCNK chunk
+#[derive(Debug, Clone)]
+pub struct ChunkTextureLayers {
+    /// Chunk column (0-15)
+    pub index_x: u32,
+    /// Chunk row (0-15)
+    pub index_y: u32,
+    /// MCLY layers, the first without an alpha map
+    pub layers: MclyChunk,
+    /// MCAL data the layers point into
+    pub alpha: McalChunk,
+}
+
+impl ChunkTextureLayers {
+    /// Replace the layers and alpha maps of `chunk`.
+    ///
+    /// Also marks the chunk's alpha maps as full 64×64, so the client does
+    /// not overwrite the last row and column.
+    pub fn apply(self, chunk: &mut McnkChunk) {
+        chunk.header.n_layers = self.layers.layers.len() as u32;
+        chunk.header.flags.value |= FLAG_DO_NOT_FIX_ALPHA_MAP;
+        chunk.layers = Some(self.layers);
+        chunk.alpha = Some(self.alpha);
+    }
+}
+
+/// Texture layers of all 256 chunks of a tile, in row-major chunk order.
+///
+/// `format` selects how alpha maps are stored. [`AlphaFormat::Compressed`]
+/// and [`AlphaFormat::Uncompressed4096`] need a WDT with 8 bit alpha maps
+/// enabled, [`AlphaFormat::Uncompressed2048`] one without; 4 bit maps lose
+/// precision. Layers get no ground effect; see
+/// [`RootAdt::apply_ground_effects`].
+pub fn generate_texture_layers(
+    masks: &[TextureMask<'_>],
+    format: AlphaFormat,
+) -> Result<Vec<ChunkTextureLayers>> {
+    if masks.is_empty() {
+        return Err(AdtError::InvalidTextureMask(
+            "at least one mask is required".to_string(),
+        ));
+    }
+    for mask in masks {
+        if mask.coverage.len() != TILE_MASK_SIZE * TILE_MASK_SIZE {
+            return Err(AdtError::InvalidTextureMask(format!(
+                "mask of texture {} has {} values, expected {}",
+                mask.texture_id,
+                mask.coverage.len(),
+                TILE_MASK_SIZE * TILE_MASK_SIZE
+            )));
+        }
+    }
+
+    let mut chunks = Vec::with_capacity(256);
+    for index_y in 0..16 {
+        for index_x in 0..16 {
+            chunks.push(chunk_layers(masks, index_x, index_y, format));
+        }
+    }
+    Ok(chunks)
+}
+
+/// Coverage of `mask` within a chunk, row by row
+fn chunk_coverage(mask: &TextureMask<'_>, index_x: usize, index_y: usize) -> Vec<u8> {
+    const SIZE: usize = AlphaMap::RESOLUTION;
+    (0..SIZE)
+        .flat_map(|row| {
+            let start = (index_y * SIZE + row) * TILE_MASK_SIZE + index_x * SIZE;
+            mask.coverage[start..start + SIZE].iter().copied()
+        })
+        .collect()
+}
+
+fn chunk_layers(
+    masks: &[TextureMask<'_>],
+    index_x: usize,
+    index_y: usize,
+    format: AlphaFormat,
+) -> ChunkTextureLayers {
+    let coverage: Vec<Vec<u8>> = masks
+        .iter()
+        .map(|mask| chunk_coverage(mask, index_x, index_y))
+        .collect();
+
+    // Textures shown in the chunk, the four with the most coverage, in mask order
+    let totals: Vec<u64> = coverage
+        .iter()
+        .map(|values| values.iter().map(|&v| v as u64).sum())
+        .collect();
+    let mut shown: Vec<usize> = (0..masks.len()).filter(|&i| totals[i] > 0).collect();
+    shown.sort_by_key(|&i| std::cmp::Reverse(totals[i]));
+    shown.truncate(MclyChunk::MAX_LAYERS_CLASSIC);
+    shown.sort_unstable();
+    if shown.is_empty() {
+        shown.push(0);
+    }
+
+    let mut layers = Vec::with_capacity(shown.len());
+    let mut alpha = Vec::new();
+    layers.push(MclyLayer {
+        texture_id: masks[shown[0]].texture_id,
+        ..Default::default()
+    });
+
+    let texels = AlphaMap::SIZE_UNCOMPRESSED_8BIT;
+    let mut alphas = vec![vec![0u8; texels]; shown.len()];
+    for texel in 0..texels {
+        let weights: Vec<f32> = shown.iter().map(|&i| coverage[i][texel] as f32).collect();
+        // Each layer covers its share of what it is drawn over; the layers
+        // below it are left with the rest
+        let mut below: f32 = weights.iter().sum();
+        for layer in (1..shown.len()).rev() {
+            if below > 0.0 {
+                alphas[layer][texel] = (weights[layer] / below * 255.0).round() as u8;
+            }
+            below -= weights[layer];
+        }
+    }
+
+    for (layer, &mask) in shown.iter().enumerate().skip(1) {
+        let (data, flags) = match format {
+            AlphaFormat::Compressed => (
+                compress_rows(&alphas[layer]),
+                FLAG_USE_ALPHA_MAP | FLAG_ALPHA_COMPRESSED,
+            ),
+            AlphaFormat::Uncompressed4096 => (alphas[layer].clone(), FLAG_USE_ALPHA_MAP),
+            AlphaFormat::Uncompressed2048 => (pack_4bit(&alphas[layer]), FLAG_USE_ALPHA_MAP),
+        };
+        layers.push(MclyLayer {
+            texture_id: masks[mask].texture_id,
+            flags: MclyFlags { value: flags },
+            offset_in_mcal: alpha.len() as u32,
+            effect_id: 0,
+        });
+        alpha.extend_from_slice(&data);
+    }
+
+    ChunkTextureLayers {
+        index_x: index_x as u32,
+        index_y: index_y as u32,
+        layers: MclyChunk { layers },
+        alpha: McalChunk::new(alpha),
+    }
+}
+
+/// RLE compress an alpha map one row at a time
+///
+/// The client expects runs not to continue into the next row.
+fn compress_rows(values: &[u8]) -> Vec<u8> {
+    let mut output = Vec::new();
+    for row in values.chunks(AlphaMap::RESOLUTION) {
+        let mut i = 0;
+        while i < row.len() {
+            let run = row[i..].iter().take_while(|&&v| v == row[i]).count();
+            if run >= 3 {
+                output.push(0x80 | run as u8);
+                output.push(row[i]);
+                i += run;
+                continue;
+            }
+            // Copy up to the next run of three
+            let start = i;
+            while i < row.len()
+                && !(i + 2 < row.len() && row[i] == row[i + 1] && row[i] == row[i + 2])
+            {
+                i += 1;
+            }
+            output.push((i - start) as u8);
+            output.extend_from_slice(&row[start..i]);
+        }
+    }
+    output
+}
+
+/// Pack an alpha map into 4 bits per texel, low nibble first
+fn pack_4bit(values: &[u8]) -> Vec<u8> {
+    let nibble = |value: u8| ((value as u16 + 8) / 17) as u8;
+    values
+        .chunks(2)
+        .map(|pair| nibble(pair[0]) | (nibble(pair[1]) << 4))
+        .collect()
+}
+
+impl RootAdt {
+    /// Replace the texture layers of every chunk with layers generated from
+    /// per-texture coverage masks.
+    ///
+    /// Textures are given by filename and added to MTEX if the tile does not
+    /// use them yet; names are matched ignoring case and path separator
+    /// style. See [`generate_texture_layers`] for the masks and `format`.
+    pub fn apply_texture_masks(
+        &mut self,
+        masks: &[(&str, &[u8])],
+        format: AlphaFormat,
+    ) -> Result<()> {
+        let key = |name: &str| name.replace('/', "\\").to_ascii_lowercase();
+        let masks: Vec<TextureMask<'_>> = masks
+            .iter()
+            .map(|&(texture, coverage)| {
+                let texture_id = match self.textures.iter().position(|t| key(t) == key(texture)) {
+                    Some(index) => index,
+                    None => {
+                        self.textures.push(texture.to_string());
+                        self.textures.len() - 1
+                    }
+                };
+                TextureMask {
+                    texture_id: texture_id as u32,
+                    coverage,
+                }
+            })
+            .collect();
+
+        let generated = generate_texture_layers(&masks, format)?;
+        for chunk in &mut self.mcnk_chunks {
+            let index = (chunk.header.index_y * 16 + chunk.header.index_x) as usize;
+            if let Some(layers) = generated.get(index) {
+                layers.clone().apply(chunk);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(layers: &ChunkTextureLayers, layer: usize) -> Vec<u8> {
+        let mcly = &layers.layers.layers[layer];
+        let data = &layers.alpha.data[mcly.offset_in_mcal as usize..];
+        let map = if mcly.flags.alpha_map_compressed() {
+            // The decoder stops after 4096 values
+            AlphaMap::new(data.to_vec(), AlphaFormat::Compressed)
+        } else {
+            AlphaMap::new(data[..2048].to_vec(), AlphaFormat::Uncompressed2048)
+        };
+        map.decompress().unwrap()
+    }
+
+    #[test]
+    fn test_generate_texture_layers() {
+        let size = TILE_MASK_SIZE;
+        // Grass everywhere, rock fading in from the west edge of every chunk,
+        // sand only in the first chunk
+        let grass = vec![255u8; size * size];
+        let rock: Vec<u8> = (0..size * size).map(|i| (i % 64 * 4) as u8).collect();
+        let mut sand = vec![0u8; size * size];
+        sand[0] = 255;
+        let masks = [
+            TextureMask {
+                texture_id: 0,
+                coverage: &grass,
+            },
+            TextureMask {
+                texture_id: 1,
+                coverage: &rock,
+            },
+            TextureMask {
+                texture_id: 2,
+                coverage: &sand,
+            },
+        ];
+
+        let chunks = generate_texture_layers(&masks, AlphaFormat::Compressed).unwrap();
+        assert_eq!(chunks.len(), 256);
+        assert_eq!(chunks[0].layers.layers.len(), 3);
+        let last = &chunks[255];
+        assert_eq!((last.index_x, last.index_y), (15, 15));
+        assert_eq!(last.layers.layers.len(), 2);
+
+        let base = &last.layers.layers[0];
+        assert_eq!(base.texture_id, 0);
+        assert!(!base.flags.use_alpha_map());
+        assert!(last.layers.layers[1].flags.alpha_map_compressed());
+
+        // Rock at weight w over grass at 255 gets alpha w / (w + 255)
+        let rock_alpha = decode(last, 1);
+        assert_eq!(rock_alpha[0], 0);
+        assert_eq!(
+            rock_alpha[64 + 63],
+            (252.0f32 / 507.0 * 255.0).round() as u8
+        );
+
+        // Sand shares the first texel of the first chunk with grass
+        assert_eq!(decode(&chunks[0], 1)[0], 0);
+        assert_eq!(decode(&chunks[0], 2)[0], 128);
+        assert_eq!(decode(&chunks[0], 2)[1], 0);
+
+        let chunks = generate_texture_layers(&masks, AlphaFormat::Uncompressed2048).unwrap();
+        assert_eq!(chunks[255].alpha.data.len(), 2048);
+        let nibble = (rock_alpha[63] as u16 + 8) / 17;
+        assert_eq!(decode(&chunks[255], 1)[63] as u16, nibble * 17);
+    }
+
+    #[test]
+    fn test_generate_texture_layers_rejects_bad_masks() {
+        assert!(generate_texture_layers(&[], AlphaFormat::Compressed).is_err());
+        let short = vec![0u8; 64 * 64];
+        assert!(matches!(
+            generate_texture_layers(
+                &[TextureMask {
+                    texture_id: 0,
+                    coverage: &short
+                }],
+                AlphaFormat::Compressed
+            ),
+            Err(AdtError::InvalidTextureMask(_))
+        ));
+    }
+}
@@ -1,360 +1,521 @@
-// validator.rs - Comprehensive ADT validation
-
-use crate::Adt;
-use crate::error::{AdtError, Result};
-use crate::split_adt::SplitAdtType;
-use crate::version::AdtVersion;
-use std::collections::HashSet;
-use std::path::Path;
+//! Streaming structural validation of ADT files
+//!
+//! [`validate_stream`] walks the chunk headers of a root or split ADT file and
+//! checks offsets, counts and cross-references without building the terrain
+//! model. Chunk contents are read through a fixed-size buffer, so memory use
+//! stays flat no matter how large the file is, which keeps validation of
+//! whole map directories cheap.
+//!
+//! ```no_run
+//! use std::fs::File;
+//! use std::io::BufReader;
+//! use wow_adt::validator::{ValidationLevel, validate_stream};
+//!
+//! let mut reader = BufReader::new(File::open("Azeroth_32_48.adt")?);
+//! let report = validate_stream(&mut reader, ValidationLevel::Standard)?;
+//! if !report.is_valid() {
+//!     print!("{}", report.format());
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Seek, SeekFrom};
+
+use binrw::BinRead;
+
+use crate::chunk_discovery::ChunkLocation;
+use crate::chunk_header::ChunkHeader;
+use crate::chunk_id::ChunkId;
+use crate::error::Result;
+use crate::file_type::AdtFileType;
+
+/// Chunk data is scanned through a buffer of at most this many bytes
+const STREAM_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Size of the fixed MCNK header that precedes the sub-chunks in root files
+const MCNK_HEADER_SIZE: u32 = 128;
+
+/// MHDR fields holding chunk offsets, relative to the MHDR data
+const MHDR_OFFSETS: [(ChunkId, usize); 11] = [
+    (ChunkId::MCIN, 4),
+    (ChunkId::MTEX, 8),
+    (ChunkId::MMDX, 12),
+    (ChunkId::MMID, 16),
+    (ChunkId::MWMO, 20),
+    (ChunkId::MWID, 24),
+    (ChunkId::MDDF, 28),
+    (ChunkId::MODF, 32),
+    (ChunkId::MFBO, 36),
+    (ChunkId::MH2O, 40),
+    (ChunkId::MTXF, 44),
+];
 
 /// Validation levels
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum ValidationLevel {
-    /// Basic structure validation
+    /// Chunk framing, version and required chunks
     Basic,
-    /// Standard validation with consistency checks
+    /// Adds offset tables and cross-references between chunks
     #[default]
     Standard,
-    /// Strict validation with all checks enabled
+    /// Adds checks for duplicate placement IDs
     Strict,
 }
 
-/// Validate an ADT file
-pub fn validate_adt(adt: &Adt, level: ValidationLevel) -> Result<ValidationReport> {
-    validate_adt_with_context(adt, level, None::<&Path>)
-}
-
-/// Validate an ADT file with file context
-pub fn validate_adt_with_context<P: AsRef<Path>>(
-    adt: &Adt,
+/// Validate an ADT file straight from a reader
+///
+/// Root files are recognised by their MHDR chunk; split `_tex`/`_obj` files
+/// are checked for framing and their string tables and placements only,
+/// since their MCNK chunks carry no header.
+///
+/// I/O errors are returned as `Err`; everything wrong with the file itself
+/// ends up in the report.
+pub fn validate_stream<R: Read + Seek>(
+    reader: &mut R,
     level: ValidationLevel,
-    file_path: Option<P>,
 ) -> Result<ValidationReport> {
     let mut report = ValidationReport::new();
+    let file_size = reader.seek(SeekFrom::End(0))?;
+    let chunks = scan_chunks(reader, file_size, &mut report)?;
+
+    match chunks
+        .get(&ChunkId::MVER)
+        .and_then(|locations| locations.first())
+    {
+        Some(mver) if mver.offset == 0 && mver.size >= 4 => {
+            let version = read_u32(reader, mver.offset + 8)?;
+            if version != 18 {
+                report.add_error(format!("Unsupported ADT version {version}, expected 18"));
+            }
+        }
+        _ => report.add_error("File does not start with an MVER chunk".to_string()),
+    }
 
-    // Determine file type if path is provided
-    let file_type = file_path
-        .as_ref()
-        .map(|p| SplitAdtType::from_filename(&p.as_ref().to_string_lossy()));
+    let file_type = AdtFileType::detect_from_chunks(&chunks);
+    let mcnk_count = chunks.get(&ChunkId::MCNK).map_or(0, Vec::len);
+    report.add_info(format!(
+        "{file_type} with {} chunks, {mcnk_count} MCNK",
+        chunks.values().map(Vec::len).sum::<usize>()
+    ));
+
+    let is_root = chunks.contains_key(&ChunkId::MHDR);
+    if file_type == AdtFileType::Root {
+        if !is_root {
+            report.add_error("Missing MHDR chunk".to_string());
+        }
+        if mcnk_count != 256 {
+            report.add_warning(format!(
+                "Expected 256 MCNK chunks for a complete map tile, found {mcnk_count}"
+            ));
+        }
+    }
 
-    // Basic structure validation (always performed)
-    basic_validation(adt, &mut report, file_type)?;
+    let chunk = |id: ChunkId| chunks.get(&id).and_then(|locations| locations.first());
+    let mmid_count = record_count(chunk(ChunkId::MMID), "MMID", 4, &mut report);
+    let mwid_count = record_count(chunk(ChunkId::MWID), "MWID", 4, &mut report);
+    let mddf_count = record_count(chunk(ChunkId::MDDF), "MDDF", 36, &mut report);
+    let modf_count = record_count(chunk(ChunkId::MODF), "MODF", 64, &mut report);
 
-    // Return early if only basic validation is requested
     if level == ValidationLevel::Basic {
         return Ok(report);
     }
 
-    // Cross-reference validation
-    xref_validation(adt, &mut report)?;
+    if is_root {
+        check_mhdr(reader, &chunks, &mut report)?;
+        check_mcin(reader, &chunks, &mut report)?;
+    }
+    for (table, block) in [
+        (ChunkId::MMID, ChunkId::MMDX),
+        (ChunkId::MWID, ChunkId::MWMO),
+    ] {
+        if let Some(offsets) = chunk(table) {
+            let block_size = chunk(block).map_or(0, |strings| strings.size);
+            check_string_offsets(reader, (table, offsets), (block, block_size), &mut report)?;
+        }
+    }
+    let strict = level == ValidationLevel::Strict;
+    for (table, record_size, names, name_count) in [
+        (ChunkId::MDDF, 36, ChunkId::MMID, mmid_count),
+        (ChunkId::MODF, 64, ChunkId::MWID, mwid_count),
+    ] {
+        if let Some(placements) = chunk(table) {
+            check_placements(
+                reader,
+                (table, placements),
+                record_size,
+                (names, name_count),
+                strict,
+                &mut report,
+            )?;
+        }
+    }
 
-    // Strict validation checks
-    if level == ValidationLevel::Strict {
-        strict_validation(adt, &mut report)?;
+    if is_root && let Some(mcnks) = chunks.get(&ChunkId::MCNK) {
+        let texture_count = match chunk(ChunkId::MTEX) {
+            Some(mtex) => count_strings(reader, mtex)?,
+            None => 0,
+        };
+        let limits = McnkLimits {
+            textures: texture_count,
+            doodads: mddf_count,
+            map_objects: modf_count,
+        };
+        let mut seen = [false; 256];
+        for (i, mcnk) in mcnks.iter().enumerate() {
+            check_mcnk(reader, i, mcnk, &limits, &mut seen, &mut report)?;
+        }
     }
 
     Ok(report)
 }
 
-/// Basic structure validation
-fn basic_validation(
-    adt: &Adt,
+/// Collect chunk locations, reporting a chunk that runs past the end of the file
+fn scan_chunks<R: Read + Seek>(
+    reader: &mut R,
+    file_size: u64,
     report: &mut ValidationReport,
-    file_type: Option<SplitAdtType>,
-) -> Result<()> {
-    // For split ADT files, different validation rules apply
-    match file_type {
-        Some(SplitAdtType::Obj0) | Some(SplitAdtType::Obj1) => {
-            // Object files don't require MHDR or MCNK chunks
-            // They contain object placement data (MMDX, MMID, MWMO, MWID, MDDF, MODF)
-            return Ok(());
-        }
-        Some(SplitAdtType::Tex0) | Some(SplitAdtType::Tex1) => {
-            // Texture files don't require MHDR or MCNK chunks
-            // They contain texture data (MTEX and texture-related MCNK subchunks)
-            return Ok(());
-        }
-        Some(SplitAdtType::Lod) => {
-            // LOD files have different requirements
-            return Ok(());
-        }
-        _ => {
-            // Root ADT file - apply normal validation
+) -> Result<HashMap<ChunkId, Vec<ChunkLocation>>> {
+    let mut chunks: HashMap<ChunkId, Vec<ChunkLocation>> = HashMap::new();
+    let mut offset = 0;
+    while offset + 8 <= file_size {
+        reader.seek(SeekFrom::Start(offset))?;
+        let header = ChunkHeader::read_le(reader)?;
+        let data_end = offset + 8 + u64::from(header.size);
+        if data_end > file_size {
+            report.add_error(format!(
+                "{} chunk at offset {offset:#x} extends {} bytes past the end of the file",
+                header.id,
+                data_end - file_size
+            ));
+            return Ok(chunks);
         }
+        chunks.entry(header.id).or_default().push(ChunkLocation {
+            offset,
+            size: header.size,
+        });
+        offset = data_end;
     }
-
-    // Check for required chunks in root ADT files
-    if adt.mhdr.is_none() {
-        report.add_error("Missing MHDR chunk".to_string());
-        return Err(AdtError::MissingChunk("MHDR".to_string()));
-    }
-
-    // Check for MCNK chunks - should be 256 (16x16) for a complete map tile
-    if adt.mcnk_chunks.is_empty() {
-        report.add_error("No MCNK chunks found".to_string());
-        return Err(AdtError::ValidationError(
-            "No MCNK chunks found".to_string(),
-        ));
-    }
-
-    if adt.mcnk_chunks.len() != 256 {
+    if offset < file_size {
         report.add_warning(format!(
-            "Expected 256 MCNK chunks for a complete map tile, found {}",
-            adt.mcnk_chunks.len()
+            "{} trailing bytes after the last chunk",
+            file_size - offset
         ));
     }
+    Ok(chunks)
+}
 
-    // Version-specific validation
-    match adt.version() {
-        AdtVersion::TBC | AdtVersion::WotLK | AdtVersion::Cataclysm => {
-            // Validate that MHDR has appropriate offsets for the version
-            if let Some(ref mhdr) = adt.mhdr {
-                if adt.version() >= AdtVersion::TBC && mhdr.mfbo_offset.is_none() {
-                    report.add_warning("TBC+ ADT should have MFBO offset in MHDR".to_string());
-                }
+fn read_u32<R: Read + Seek>(reader: &mut R, offset: u64) -> Result<u32> {
+    let mut bytes = [0u8; 4];
+    reader.seek(SeekFrom::Start(offset))?;
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
 
-                if adt.version() >= AdtVersion::WotLK && mhdr.mh2o_offset.is_none() {
-                    report.add_warning("WotLK+ ADT should have MH2O offset in MHDR".to_string());
-                }
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
 
-                if adt.version() >= AdtVersion::Cataclysm && mhdr.mtfx_offset.is_none() {
-                    report
-                        .add_warning("Cataclysm+ ADT should have MTFX offset in MHDR".to_string());
-                }
+/// Feed every whole record of a chunk to `visit` until it returns `false`
+fn for_each_record<R: Read + Seek>(
+    reader: &mut R,
+    location: &ChunkLocation,
+    record_size: usize,
+    mut visit: impl FnMut(usize, &[u8]) -> bool,
+) -> Result<()> {
+    let per_buffer = (STREAM_BUFFER_SIZE / record_size).max(1);
+    let mut remaining = location.size as usize / record_size;
+    let mut buf = vec![0u8; per_buffer.min(remaining) * record_size];
+    let mut index = 0;
+    reader.seek(SeekFrom::Start(location.offset + 8))?;
+    while remaining > 0 {
+        let count = per_buffer.min(remaining);
+        let bytes = &mut buf[..count * record_size];
+        reader.read_exact(bytes)?;
+        for record in bytes.chunks_exact(record_size) {
+            if !visit(index, record) {
+                return Ok(());
             }
+            index += 1;
         }
-        _ => {}
+        remaining -= count;
     }
+    Ok(())
+}
 
-    // Validate MHDR offsets vs actual data
-    if let Some(ref mhdr) = adt.mhdr {
-        if mhdr.mcin_offset > 0 && adt.mcin.is_none() {
-            report.add_warning("MHDR has MCIN offset but no MCIN chunk found".to_string());
-        }
-
-        if mhdr.mtex_offset > 0 && adt.mtex.is_none() {
-            report.add_warning("MHDR has MTEX offset but no MTEX chunk found".to_string());
-        }
-
-        if mhdr.mmdx_offset > 0 && adt.mmdx.is_none() {
-            report.add_warning("MHDR has MMDX offset but no MMDX chunk found".to_string());
-        }
+/// Entry count of an array chunk, warning when it is not a whole number of entries
+fn record_count(
+    location: Option<&ChunkLocation>,
+    name: &str,
+    record_size: u32,
+    report: &mut ValidationReport,
+) -> u32 {
+    let Some(location) = location else {
+        return 0;
+    };
+    if !location.size.is_multiple_of(record_size) {
+        report.add_warning(format!(
+            "{name} size {} is not a multiple of its {record_size}-byte entry size",
+            location.size
+        ));
     }
-
-    Ok(())
+    location.size / record_size
 }
 
-/// Cross-reference validation
-fn xref_validation(adt: &Adt, report: &mut ValidationReport) -> Result<()> {
-    // Validate MCNK indices
-    for (i, chunk) in adt.mcnk_chunks.iter().enumerate() {
-        let expected_x = (i % 16) as u32;
-        let expected_y = (i / 16) as u32;
+/// Number of NUL-terminated strings in a string block
+fn count_strings<R: Read + Seek>(reader: &mut R, location: &ChunkLocation) -> Result<u32> {
+    let mut count = 0;
+    for_each_record(reader, location, 1, |_, byte| {
+        count += u32::from(byte[0] == 0);
+        true
+    })?;
+    Ok(count)
+}
 
-        if chunk.ix != expected_x || chunk.iy != expected_y {
+/// Check that non-zero MHDR offsets point at the chunk they name
+fn check_mhdr<R: Read + Seek>(
+    reader: &mut R,
+    chunks: &HashMap<ChunkId, Vec<ChunkLocation>>,
+    report: &mut ValidationReport,
+) -> Result<()> {
+    let mhdr = chunks[&ChunkId::MHDR][0];
+    if mhdr.size < 64 {
+        report.add_error(format!("MHDR is {} bytes, expected 64", mhdr.size));
+        return Ok(());
+    }
+    let mut header = [0u8; 64];
+    reader.seek(SeekFrom::Start(mhdr.offset + 8))?;
+    reader.read_exact(&mut header)?;
+
+    for (id, field) in MHDR_OFFSETS {
+        let offset = u32_at(&header, field);
+        if offset == 0 {
+            continue;
+        }
+        let target = mhdr.offset + 8 + u64::from(offset);
+        let found = chunks
+            .get(&id)
+            .is_some_and(|locations| locations.iter().any(|location| location.offset == target));
+        if !found {
             report.add_warning(format!(
-                "MCNK chunk at index {} has incorrect indices [{}, {}], expected [{}, {}]",
-                i, chunk.ix, chunk.iy, expected_x, expected_y
+                "MHDR {id} offset {offset:#x} does not point at an {id} chunk"
             ));
         }
     }
+    Ok(())
+}
 
-    // Validate MCIN indices point to valid MCNK chunks
-    if let Some(ref mcin) = adt.mcin {
-        for (i, entry) in mcin.entries.iter().enumerate() {
-            if entry.offset == 0 && entry.size == 0 {
-                // Empty entry
-                continue;
-            }
-
-            // In a file, the offset would be relative to the file start
-            // But we don't know the actual offset here, so we can only check
-            // if the size seems reasonable
-            if entry.size < 8 || entry.size > 1024 * 1024 {
-                report.add_warning(format!(
-                    "MCIN entry {} has suspicious size: {}",
-                    i, entry.size
-                ));
-            }
+/// Check that MCIN entries point at MCNK chunks
+fn check_mcin<R: Read + Seek>(
+    reader: &mut R,
+    chunks: &HashMap<ChunkId, Vec<ChunkLocation>>,
+    report: &mut ValidationReport,
+) -> Result<()> {
+    let Some(mcin) = chunks
+        .get(&ChunkId::MCIN)
+        .and_then(|locations| locations.first())
+    else {
+        return Ok(());
+    };
+    if mcin.size < 256 * 16 {
+        report.add_error(format!(
+            "MCIN is {} bytes, expected {}",
+            mcin.size,
+            256 * 16
+        ));
+        return Ok(());
+    }
+    let mcnk_offsets: HashSet<u64> = chunks
+        .get(&ChunkId::MCNK)
+        .map(|locations| locations.iter().map(|location| location.offset).collect())
+        .unwrap_or_default();
+
+    let mut bad = None;
+    for_each_record(reader, mcin, 16, |i, entry| {
+        let offset = u32_at(entry, 0);
+        if offset != 0 && !mcnk_offsets.contains(&u64::from(offset)) {
+            bad = Some((i, offset));
         }
+        bad.is_none()
+    })?;
+    if let Some((i, offset)) = bad {
+        report.add_warning(format!(
+            "MCIN entry {i} offset {offset:#x} does not point at an MCNK chunk"
+        ));
     }
+    Ok(())
+}
 
-    // Validate MMID references valid indices in MMDX
-    if let Some(ref _mmdx) = adt.mmdx {
-        if let Some(ref mmid) = adt.mmid {
-            for (i, &offset) in mmid.offsets.iter().enumerate() {
-                let found = false;
-
-                // Ideally, we would check if each offset points to a valid position
-                // in the MMDX chunk, but we don't have the raw data here
-
-                if !found {
-                    report.add_info(format!("MMID entry {i} references offset {offset} in MMDX"));
-                }
-            }
+/// Check that a filename offset table stays within its string block
+fn check_string_offsets<R: Read + Seek>(
+    reader: &mut R,
+    (table, offsets): (ChunkId, &ChunkLocation),
+    (block, block_size): (ChunkId, u32),
+    report: &mut ValidationReport,
+) -> Result<()> {
+    let mut bad = None;
+    for_each_record(reader, offsets, 4, |i, entry| {
+        let offset = u32_at(entry, 0);
+        if offset >= block_size {
+            bad = Some((i, offset));
         }
+        bad.is_none()
+    })?;
+    if let Some((i, offset)) = bad {
+        report.add_error(format!(
+            "{table} entry {i} offset {offset} is outside {block} ({block_size} bytes)"
+        ));
     }
+    Ok(())
+}
 
-    // Validate MWID references valid indices in MWMO
-    if let Some(ref _mwmo) = adt.mwmo {
-        if let Some(ref mwid) = adt.mwid {
-            for (i, &offset) in mwid.offsets.iter().enumerate() {
-                let found = false;
+/// Check placement name indices and, in strict mode, unique IDs
+fn check_placements<R: Read + Seek>(
+    reader: &mut R,
+    (table, placements): (ChunkId, &ChunkLocation),
+    record_size: usize,
+    (names, name_count): (ChunkId, u32),
+    strict: bool,
+    report: &mut ValidationReport,
+) -> Result<()> {
+    let mut bad_name = None;
+    let mut unique_ids = HashSet::new();
+    let mut duplicates = Vec::new();
+    for_each_record(reader, placements, record_size, |i, entry| {
+        let name_id = u32_at(entry, 0);
+        if bad_name.is_none() && name_id >= name_count {
+            bad_name = Some((i, name_id));
+        }
+        if strict && !unique_ids.insert(u32_at(entry, 4)) {
+            duplicates.push((i, u32_at(entry, 4)));
+        }
+        true
+    })?;
+    if let Some((i, name_id)) = bad_name {
+        report.add_error(format!(
+            "{table} entry {i} references {names} index {name_id}, but there are {name_count}"
+        ));
+    }
+    for (i, unique_id) in duplicates {
+        report.add_warning(format!(
+            "{table} entry {i} has duplicate unique ID: {unique_id}"
+        ));
+    }
+    Ok(())
+}
 
-                // Similar to MMID, we would need the raw data to properly validate
+/// Entry counts MCNK references are checked against
+struct McnkLimits {
+    textures: u32,
+    doodads: u32,
+    map_objects: u32,
+}
 
-                if !found {
-                    report.add_info(format!("MWID entry {i} references offset {offset} in MWMO"));
-                }
-            }
-        }
+/// Check a root MCNK header, its position and its layer and object references
+fn check_mcnk<R: Read + Seek>(
+    reader: &mut R,
+    i: usize,
+    mcnk: &ChunkLocation,
+    limits: &McnkLimits,
+    seen: &mut [bool; 256],
+    report: &mut ValidationReport,
+) -> Result<()> {
+    if mcnk.size < MCNK_HEADER_SIZE {
+        report.add_error(format!(
+            "MCNK chunk {i} is {} bytes, shorter than its {MCNK_HEADER_SIZE}-byte header",
+            mcnk.size
+        ));
+        return Ok(());
+    }
+    let mut header = [0u8; 64];
+    reader.seek(SeekFrom::Start(mcnk.offset + 8))?;
+    reader.read_exact(&mut header)?;
+
+    let (x, y) = (u32_at(&header, 4), u32_at(&header, 8));
+    if x >= 16 || y >= 16 {
+        report.add_error(format!(
+            "MCNK chunk {i} has out-of-range indices [{x}, {y}]"
+        ));
+    } else if std::mem::replace(&mut seen[(y * 16 + x) as usize], true) {
+        report.add_error(format!("MCNK chunk {i} repeats position [{x}, {y}]"));
     }
 
-    // Validate MDDF references valid MMID indices
-    if let Some(ref mddf) = adt.mddf {
-        if let Some(ref mmid) = adt.mmid {
-            for (i, doodad) in mddf.doodads.iter().enumerate() {
-                if doodad.name_id as usize >= mmid.offsets.len() {
+    let chunk_end = u64::from(mcnk.size) + 8;
+    let n_layers = u32_at(&header, 12);
+    let ofs_layer = u32_at(&header, 28);
+    if n_layers > 0 && ofs_layer > 0 {
+        let layers = read_subchunk(reader, mcnk, ofs_layer, n_layers * 16, chunk_end)?;
+        match layers {
+            Some(data) => {
+                if let Some((layer, texture)) = data
+                    .chunks_exact(16)
+                    .map(|entry| u32_at(entry, 0))
+                    .enumerate()
+                    .find(|&(_, texture)| texture >= limits.textures)
+                {
                     report.add_error(format!(
-                        "MDDF entry {} references invalid MMID index: {}",
-                        i, doodad.name_id
+                        "MCNK chunk {i}, layer {layer} references texture {texture}, but MTEX has {}",
+                        limits.textures
                     ));
                 }
             }
-        } else if !mddf.doodads.is_empty() {
-            report.add_error("MDDF references doodads but no MMID chunk found".to_string());
+            None => report.add_error(format!(
+                "MCNK chunk {i} MCLY offset {ofs_layer:#x} does not hold {n_layers} layers"
+            )),
         }
     }
 
-    // Validate MODF references valid MWID indices
-    if let Some(ref modf) = adt.modf {
-        if let Some(ref mwid) = adt.mwid {
-            for (i, model) in modf.models.iter().enumerate() {
-                if model.name_id as usize >= mwid.offsets.len() {
+    let n_doodad_refs = u32_at(&header, 16);
+    let n_map_obj_refs = u32_at(&header, 56);
+    let ofs_refs = u32_at(&header, 32);
+    let n_refs = n_doodad_refs.saturating_add(n_map_obj_refs);
+    if n_refs > 0 && ofs_refs > 0 {
+        match read_subchunk(reader, mcnk, ofs_refs, n_refs.saturating_mul(4), chunk_end)? {
+            Some(data) => {
+                let refs: Vec<u32> = data.chunks_exact(4).map(|entry| u32_at(entry, 0)).collect();
+                let (doodads, map_objects) = refs.split_at(n_doodad_refs as usize);
+                if let Some(&doodad) = doodads.iter().find(|&&doodad| doodad >= limits.doodads) {
                     report.add_error(format!(
-                        "MODF entry {} references invalid MWID index: {}",
-                        i, model.name_id
+                        "MCNK chunk {i} references doodad placement {doodad}, but MDDF has {}",
+                        limits.doodads
                     ));
                 }
-            }
-        } else if !modf.models.is_empty() {
-            report.add_error("MODF references WMOs but no MWID chunk found".to_string());
-        }
-    }
-
-    // Validate texture references
-    if let Some(ref mtex) = adt.mtex {
-        let texture_count = mtex.filenames.len();
-
-        for (i, chunk) in adt.mcnk_chunks.iter().enumerate() {
-            for (j, layer) in chunk.texture_layers.iter().enumerate() {
-                if layer.texture_id as usize >= texture_count {
+                if let Some(&object) = map_objects
+                    .iter()
+                    .find(|&&object| object >= limits.map_objects)
+                {
                     report.add_error(format!(
-                        "MCNK chunk {}, layer {} references invalid texture ID: {}",
-                        i, j, layer.texture_id
+                        "MCNK chunk {i} references WMO placement {object}, but MODF has {}",
+                        limits.map_objects
                     ));
                 }
             }
+            None => report.add_error(format!(
+                "MCNK chunk {i} MCRF offset {ofs_refs:#x} does not hold {n_refs} references"
+            )),
         }
     }
-
-    // Validate MCNK doodad references
-    for (i, chunk) in adt.mcnk_chunks.iter().enumerate() {
-        if !chunk.doodad_refs.is_empty() {
-            if let Some(ref mmid) = adt.mmid {
-                for (j, &doodad_ref) in chunk.doodad_refs.iter().enumerate() {
-                    if doodad_ref as usize >= mmid.offsets.len() {
-                        report.add_error(format!(
-                            "MCNK chunk {i}, doodad ref {j} references invalid MMID index: {doodad_ref}"
-                        ));
-                    }
-                }
-            } else {
-                report.add_error(format!(
-                    "MCNK chunk {i} references doodads but no MMID chunk found"
-                ));
-            }
-        }
-
-        if !chunk.map_obj_refs.is_empty() {
-            if let Some(ref mwid) = adt.mwid {
-                for (j, &map_obj_ref) in chunk.map_obj_refs.iter().enumerate() {
-                    if map_obj_ref as usize >= mwid.offsets.len() {
-                        report.add_error(format!(
-                            "MCNK chunk {i}, map object ref {j} references invalid MWID index: {map_obj_ref}"
-                        ));
-                    }
-                }
-            } else {
-                report.add_error(format!(
-                    "MCNK chunk {i} references map objects but no MWID chunk found"
-                ));
-            }
-        }
-    }
-
     Ok(())
 }
 
-/// Strict validation with detailed checks
-fn strict_validation(adt: &Adt, report: &mut ValidationReport) -> Result<()> {
-    // Check for unique IDs in doodad placements
-    if let Some(ref mddf) = adt.mddf {
-        let mut doodad_ids = HashSet::new();
-
-        for (i, doodad) in mddf.doodads.iter().enumerate() {
-            if !doodad_ids.insert(doodad.unique_id) {
-                report.add_warning(format!(
-                    "MDDF entry {} has duplicate unique ID: {}",
-                    i, doodad.unique_id
-                ));
-            }
-        }
-    }
-
-    // Check for unique IDs in model placements
-    if let Some(ref modf) = adt.modf {
-        let mut model_ids = HashSet::new();
-
-        for (i, model) in modf.models.iter().enumerate() {
-            if !model_ids.insert(model.unique_id) {
-                report.add_warning(format!(
-                    "MODF entry {} has duplicate unique ID: {}",
-                    i, model.unique_id
-                ));
-            }
-        }
-    }
-
-    // Check for holes consistency
-    for (i, chunk) in adt.mcnk_chunks.iter().enumerate() {
-        if chunk.holes != 0 {
-            // Holes are stored as a bit field where each bit represents
-            // a triangle in the terrain mesh.
-            // We could do more detailed validation here.
-            report.add_info(format!("MCNK chunk {} has holes: {:#06x}", i, chunk.holes));
-        }
-    }
-
-    // Version-specific strict validation
-    match adt.version() {
-        AdtVersion::WotLK | AdtVersion::Cataclysm => {
-            // Validate MH2O consistency
-            if let Some(ref mh2o) = adt.mh2o {
-                if mh2o.chunks.len() != 256 {
-                    report.add_error(format!(
-                        "MH2O has {} chunks, expected 256",
-                        mh2o.chunks.len()
-                    ));
-                }
-
-                // More detailed MH2O validation could be done here
-            }
-        }
-        _ => {}
+/// Read `len` bytes of sub-chunk data at `offset` from the MCNK start
+///
+/// Returns `None` when the sub-chunk would run past the MCNK chunk.
+fn read_subchunk<R: Read + Seek>(
+    reader: &mut R,
+    mcnk: &ChunkLocation,
+    offset: u32,
+    len: u32,
+    chunk_end: u64,
+) -> Result<Option<Vec<u8>>> {
+    let data_start = u64::from(offset) + 8;
+    if data_start + u64::from(len) > chunk_end {
+        return Ok(None);
     }
-
-    Ok(())
+    reader.seek(SeekFrom::Start(mcnk.offset + data_start))?;
+    let mut data = vec![0u8; len as usize];
+    reader.read_exact(&mut data)?;
+    Ok(Some(data))
 }
 
 /// Report of validation results
@@ -1065,3 +1065,38 @@ fn test_mh2o_without_vertex_data() {
         "Should have no exists bitmap"
     );
 }
+
+#[test]
+fn test_unknown_chunks_survive_round_trip() {
+    use wow_adt::builder::BuiltAdt;
+    use wow_adt::{ChunkId, RawChunk};
+
+    let custom = RawChunk::new(ChunkId::from_str("MXYZ").unwrap(), vec![1, 2, 3, 4, 5]);
+    let future = RawChunk::new(ChunkId::from_str("MNEW").unwrap(), Vec::new());
+
+    let mut bytes = AdtBuilder::new()
+        .with_version(AdtVersion::WotLK)
+        .add_texture("terrain/grass.blp")
+        .add_unknown_chunk(custom.clone())
+        .build()
+        .expect("Failed to build ADT")
+        .to_bytes()
+        .expect("Failed to serialize ADT");
+
+    // A chunk appended by some other tool
+    bytes.extend_from_slice(&future.id.0);
+    bytes.extend_from_slice(&0u32.to_le_bytes());
+
+    let mut root = extract_root(parse_adt(&mut Cursor::new(bytes)).expect("Failed to parse ADT"));
+    assert_eq!(root.unknown_chunks, [custom.clone(), future.clone()]);
+
+    // Editing and writing the tile again keeps both chunks
+    root.textures.push("terrain/dirt.blp".to_string());
+    let bytes = BuiltAdt::from_root_adt(root, None)
+        .to_bytes()
+        .expect("Failed to serialize ADT");
+    let root = extract_root(parse_adt(&mut Cursor::new(bytes)).expect("Failed to parse ADT"));
+    assert_eq!(root.textures.len(), 2);
+    assert_eq!(root.unknown_chunks, [custom, future]);
+    assert_eq!(root.mcnk_chunks.len(), 256);
+}
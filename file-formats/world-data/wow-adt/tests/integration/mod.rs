@@ -1,4 +1,5 @@
 pub mod builder;
 pub mod modify;
+pub mod validate;
 // TODO: Update parser tests to work with new API
 // pub mod parser;
@@ -8,16 +8,21 @@
 //! 3. Model placement addition round-trip
 //! 4. Water data modification round-trip
 //! 5. Blend mesh data modification round-trip (MoP+)
+//! 6. Pruning unused textures, models and WMOs
+//! 7. Ground effects of retextured layers
+//! 8. Filling low terrain with ocean water
 
 use std::io::Cursor;
-use wow_adt::AdtVersion;
 use wow_adt::api::{ParsedAdt, parse_adt};
 use wow_adt::builder::AdtBuilder;
-use wow_adt::chunks::DoodadPlacement;
 use wow_adt::chunks::mcnk::McnkChunk;
 use wow_adt::chunks::mcnk::header::{McnkFlags, McnkHeader};
 use wow_adt::chunks::mcnk::mcvt::McvtChunk;
 use wow_adt::chunks::mcnk::{BlendBatch, McbbChunk};
+use wow_adt::chunks::mcnk::{MclyChunk, MclyLayer};
+use wow_adt::chunks::mh2o::VertexDataArray;
+use wow_adt::chunks::{DoodadPlacement, MtxfChunk, WmoPlacement};
+use wow_adt::{AdtVersion, OCEAN_LIQUID_TYPE, OceanFill};
 
 /// Helper: Create minimal MCNK chunk with heights
 fn create_mcnk_with_heights(base_height: f32) -> McnkChunk {
@@ -585,3 +590,265 @@ fn test_blend_batch_in_memory_modification() {
         "Modified ADT should contain MCBB chunk (written as BBCM)"
     );
 }
+
+#[test]
+fn test_prune_unused_references_round_trip() {
+    let mut mcnk = create_mcnk_with_heights(0.0);
+    mcnk.header.n_layers = 2;
+    mcnk.layers = Some(MclyChunk {
+        layers: vec![
+            MclyLayer {
+                texture_id: 2,
+                ..Default::default()
+            },
+            MclyLayer {
+                texture_id: 0,
+                ..Default::default()
+            },
+        ],
+    });
+
+    let doodad = DoodadPlacement {
+        name_id: 1,
+        unique_id: 100,
+        position: [1000.0, 1000.0, 50.0],
+        rotation: [0.0, 0.0, 0.0],
+        scale: 1024,
+        flags: 0,
+    };
+    let wmo = WmoPlacement {
+        name_id: 2,
+        unique_id: 101,
+        position: [2000.0, 2000.0, 50.0],
+        rotation: [0.0, 0.0, 0.0],
+        extents_min: [-10.0, -10.0, -10.0],
+        extents_max: [10.0, 10.0, 10.0],
+        flags: 0,
+        doodad_set: 0,
+        name_set: 0,
+        scale: 1024,
+    };
+
+    let built = AdtBuilder::new()
+        .with_version(AdtVersion::WotLK)
+        .add_textures([
+            "terrain/grass.blp",
+            "terrain/unused.blp",
+            "terrain/rock.blp",
+        ])
+        .add_texture_flags(MtxfChunk {
+            flags: vec![1, 2, 3],
+        })
+        .add_model("doodad/unused.m2")
+        .add_model("doodad/tree_01.m2")
+        .add_wmo("wmo/unused_a.wmo")
+        .add_wmo("wmo/unused_b.wmo")
+        .add_wmo("wmo/tower.wmo")
+        .add_doodad_placement(doodad)
+        .add_wmo_placement(wmo)
+        .add_mcnk_chunk(mcnk)
+        .build()
+        .expect("Failed to build ADT");
+
+    let bytes = built.to_bytes().expect("Failed to serialize ADT");
+    let ParsedAdt::Root(mut root) = parse_adt(&mut Cursor::new(bytes)).expect("Failed to parse")
+    else {
+        panic!("Expected Root ADT");
+    };
+
+    let pruned = root
+        .prune_unused_references()
+        .expect("Failed to prune references");
+    assert_eq!(pruned.textures, ["terrain/unused.blp"]);
+    assert_eq!(pruned.models, ["doodad/unused.m2"]);
+    assert_eq!(pruned.wmos, ["wmo/unused_a.wmo", "wmo/unused_b.wmo"]);
+    assert_eq!(pruned.len(), 4);
+
+    // A second pass has nothing left to remove
+    assert!(root.prune_unused_references().unwrap().is_empty());
+
+    let rebuilt = AdtBuilder::from_parsed(*root)
+        .build()
+        .expect("Failed to rebuild ADT")
+        .to_bytes()
+        .expect("Failed to serialize pruned ADT");
+    let ParsedAdt::Root(root) = parse_adt(&mut Cursor::new(rebuilt)).expect("Failed to reparse")
+    else {
+        panic!("Expected Root ADT after reparse");
+    };
+
+    assert_eq!(root.textures, ["terrain/grass.blp", "terrain/rock.blp"]);
+    assert_eq!(root.texture_flags.as_ref().unwrap().flags, [1, 3]);
+    let layers = &root.mcnk_chunks[0].layers.as_ref().unwrap().layers;
+    assert_eq!(layers[0].texture_id, 1);
+    assert_eq!(layers[1].texture_id, 0);
+
+    assert_eq!(root.models, ["doodad/tree_01.m2"]);
+    assert_eq!(root.model_indices, [0]);
+    assert_eq!(root.doodad_placements[0].name_id, 0);
+    assert_eq!(root.wmos, ["wmo/tower.wmo"]);
+    assert_eq!(root.wmo_placements[0].name_id, 0);
+}
+
+#[test]
+fn test_prune_rejects_dangling_references() {
+    let mut mcnk = create_mcnk_with_heights(0.0);
+    mcnk.header.n_layers = 1;
+    mcnk.layers = Some(MclyChunk {
+        layers: vec![MclyLayer {
+            texture_id: 5,
+            ..Default::default()
+        }],
+    });
+
+    let built = AdtBuilder::new()
+        .add_textures(["terrain/grass.blp", "terrain/unused.blp"])
+        .add_mcnk_chunk(mcnk)
+        .build()
+        .expect("Failed to build ADT");
+    let bytes = built.to_bytes().expect("Failed to serialize ADT");
+    let ParsedAdt::Root(mut root) = parse_adt(&mut Cursor::new(bytes)).expect("Failed to parse")
+    else {
+        panic!("Expected Root ADT");
+    };
+
+    assert!(root.prune_unused_references().is_err());
+    assert_eq!(root.textures.len(), 2);
+}
+
+#[test]
+fn test_apply_ground_effects_round_trip() {
+    let layer = |texture_id, effect_id| MclyLayer {
+        texture_id,
+        effect_id,
+        ..Default::default()
+    };
+    let mut original = create_mcnk_with_heights(0.0);
+    original.header.n_layers = 2;
+    original.layers = Some(MclyChunk {
+        layers: vec![layer(0, 12), layer(1, 0)],
+    });
+    let mut retextured = create_mcnk_with_heights(0.0);
+    retextured.header.n_layers = 3;
+    retextured.layers = Some(MclyChunk {
+        // The grass layer kept the effect of the texture it replaced
+        layers: vec![layer(2, 0), layer(0, 40), layer(1, 0)],
+    });
+
+    let built = AdtBuilder::new()
+        .with_version(AdtVersion::WotLK)
+        .add_textures([
+            "tileset/elwynn/elwynngrass.blp",
+            "tileset/elwynn/elwynnrock.blp",
+            "tileset/elwynn/elwynndirt.blp",
+        ])
+        .add_mcnk_chunk(original)
+        .add_mcnk_chunk(retextured)
+        .build()
+        .expect("Failed to build ADT");
+    let bytes = built.to_bytes().expect("Failed to serialize ADT");
+    let ParsedAdt::Root(mut root) = parse_adt(&mut Cursor::new(bytes)).expect("Failed to parse")
+    else {
+        panic!("Expected Root ADT");
+    };
+
+    let effects = root.ground_effects();
+    assert_eq!(effects.len(), 5);
+    assert_eq!((effects[3].chunk, effects[3].layer), (1, 1));
+    assert_eq!(effects[3].effect_id, 40);
+
+    // Ties go to the lowest id
+    let learned = root.texture_ground_effects();
+    assert_eq!(learned.len(), 1);
+    assert_eq!(learned["tileset/elwynn/elwynngrass.blp"], 12);
+
+    let mut assignments = learned.clone();
+    assignments.insert("Tileset\\Elwynn\\ElwynnRock.blp".into(), 7);
+    assert_eq!(root.apply_ground_effects(&assignments, false), 2);
+    assert_eq!(root.apply_ground_effects(&assignments, true), 1);
+    assert_eq!(root.apply_ground_effects(&assignments, true), 0);
+
+    let rebuilt = AdtBuilder::from_parsed(*root)
+        .build()
+        .expect("Failed to rebuild ADT")
+        .to_bytes()
+        .expect("Failed to serialize ADT");
+    let ParsedAdt::Root(root) = parse_adt(&mut Cursor::new(rebuilt)).expect("Failed to reparse")
+    else {
+        panic!("Expected Root ADT after reparse");
+    };
+    let effect_ids: Vec<u32> = root.ground_effects().iter().map(|l| l.effect_id).collect();
+    assert_eq!(effect_ids, [12, 7, 0, 12, 7]);
+}
+
+#[test]
+fn test_fill_ocean_round_trip() {
+    // Chunk 0 slopes from 10 down to -6 across its columns; chunk 1 is land
+    let mut sloped = create_mcnk_with_heights(0.0);
+    let heights = &mut sloped.heights.as_mut().unwrap().heights;
+    for (i, height) in heights.iter_mut().enumerate() {
+        // Outer rows hold 9 vertices, inner rows 8
+        let col = match i % 17 {
+            c @ 0..=8 => c as f32,
+            c => (c - 9) as f32 + 0.5,
+        };
+        *height = 10.0 - 2.0 * col;
+    }
+    let mut land = create_mcnk_with_heights(20.0);
+    land.header.index_x = 1;
+
+    let built = AdtBuilder::new()
+        .with_version(AdtVersion::WotLK)
+        .add_texture("terrain/sand.blp")
+        .add_mcnk_chunk(sloped)
+        .add_mcnk_chunk(land)
+        .build()
+        .expect("Failed to build ADT");
+    let bytes = built.to_bytes().expect("Failed to serialize ADT");
+    let ParsedAdt::Root(mut root) = parse_adt(&mut Cursor::new(bytes)).expect("Failed to parse")
+    else {
+        panic!("Expected Root ADT");
+    };
+
+    let report = root.fill_ocean(&OceanFill::new(0.0));
+    assert_eq!((report.chunks, report.tiles, report.skipped), (1, 24, 0));
+
+    // Existing liquid is kept unless replacing is asked for
+    let report = root.fill_ocean(&OceanFill::new(4.0).liquid_type(5));
+    assert_eq!((report.chunks, report.skipped), (0, 1));
+    let report = root.fill_ocean(&OceanFill::new(4.0).replace_existing(true));
+    assert_eq!((report.chunks, report.tiles), (1, 40));
+
+    let rebuilt = AdtBuilder::from_parsed(*root)
+        .build()
+        .expect("Failed to rebuild ADT")
+        .to_bytes()
+        .expect("Failed to serialize ADT");
+    let ParsedAdt::Root(root) = parse_adt(&mut Cursor::new(rebuilt)).expect("Failed to reparse")
+    else {
+        panic!("Expected Root ADT after reparse");
+    };
+
+    let water = root.water_data.expect("MH2O should be written");
+    assert_eq!(water.liquid_chunk_count(), 1);
+    assert!(!water.entries[1].has_liquid());
+
+    let entry = &water.entries[0];
+    let instance = &entry.instances[0];
+    assert_eq!(instance.liquid_type, OCEAN_LIQUID_TYPE);
+    assert_eq!(instance.min_height_level, 4.0);
+    assert_eq!((instance.width, instance.height), (8, 8));
+
+    let bitmap = entry.exists_bitmaps[0].expect("exists bitmap");
+    for row in 0..8 {
+        assert_eq!((bitmap >> (row * 8)) & 0xFF, 0b1111_1000);
+    }
+    assert_eq!(entry.attributes.expect("attributes").fishable, bitmap);
+
+    let Some(VertexDataArray::DepthOnly(depths)) = &entry.vertex_data[0] else {
+        panic!("Expected depth-only vertices");
+    };
+    // The last vertex of a row lies 10 yards below the surface, the first is dry
+    assert_eq!(depths[8].unwrap().depth, 10);
+    assert_eq!(depths[0].unwrap().depth, 0);
+}
@@ -0,0 +1,78 @@
+//! Integration tests for streaming validation.
+
+use std::io::Cursor;
+
+use wow_adt::builder::AdtBuilder;
+use wow_adt::{AdtVersion, DoodadPlacement, ValidationLevel, ValidationReport, validate_stream};
+
+fn doodad(unique_id: u32) -> DoodadPlacement {
+    DoodadPlacement {
+        name_id: 0,
+        unique_id,
+        position: [1000.0, 1000.0, 100.0],
+        rotation: [0.0, 0.0, 0.0],
+        scale: 1024,
+        flags: 0,
+    }
+}
+
+fn build(placements: &[DoodadPlacement]) -> Vec<u8> {
+    let mut builder = AdtBuilder::new()
+        .with_version(AdtVersion::WotLK)
+        .add_texture("terrain/grass.blp")
+        .add_model("world/doodads/tree_01.m2");
+    for placement in placements {
+        builder = builder.add_doodad_placement(*placement);
+    }
+    builder.build().unwrap().to_bytes().unwrap()
+}
+
+fn validate(bytes: Vec<u8>, level: ValidationLevel) -> ValidationReport {
+    validate_stream(&mut Cursor::new(bytes), level).unwrap()
+}
+
+/// Offset of the data of the first chunk with the given on-disk magic
+fn chunk_data(bytes: &[u8], magic: &[u8; 4]) -> usize {
+    bytes.windows(4).position(|window| window == magic).unwrap() + 8
+}
+
+#[test]
+fn test_stream_valid_root() {
+    let report = validate(build(&[doodad(1)]), ValidationLevel::Strict);
+    assert!(report.is_clean(), "{}", report.format());
+}
+
+#[test]
+fn test_stream_bad_placement_reference() {
+    let mut bytes = build(&[doodad(1)]);
+    let mddf = chunk_data(&bytes, b"FDDM");
+    bytes[mddf..mddf + 4].copy_from_slice(&5u32.to_le_bytes());
+
+    // Cross-references are not checked at the basic level
+    assert!(validate(bytes.clone(), ValidationLevel::Basic).is_valid());
+
+    let report = validate(bytes, ValidationLevel::Standard);
+    assert_eq!(report.errors.len(), 1, "{}", report.format());
+    assert!(report.errors[0].contains("MDDF entry 0 references MMID index 5"));
+}
+
+#[test]
+fn test_stream_duplicate_unique_ids() {
+    let bytes = build(&[doodad(7), doodad(7)]);
+    assert!(validate(bytes.clone(), ValidationLevel::Standard).is_clean());
+
+    let report = validate(bytes, ValidationLevel::Strict);
+    assert!(report.is_valid());
+    assert_eq!(report.warnings, ["MDDF entry 1 has duplicate unique ID: 7"]);
+}
+
+#[test]
+fn test_stream_truncated_file() {
+    let mut bytes = build(&[]);
+    bytes.truncate(bytes.len() - 100);
+
+    let report = validate(bytes, ValidationLevel::Basic);
+    assert!(!report.is_valid());
+    assert!(report.errors[0].contains("past the end of the file"));
+    assert!(report.warnings[0].contains("found 255"));
+}
@@ -101,6 +101,37 @@ impl MphdChunk {
         self.flags.contains(MphdFlags::WDT_HAS_MAID)
     }
 
+    /// Check if ADTs carry vertex colors (MCCV)
+    pub fn has_vertex_shading(&self) -> bool {
+        self.flags.contains(MphdFlags::ADT_HAS_MCCV)
+    }
+
+    /// Set whether ADTs carry vertex colors (MCCV)
+    pub fn set_vertex_shading(&mut self, enabled: bool) {
+        self.flags.set(MphdFlags::ADT_HAS_MCCV, enabled);
+    }
+
+    /// Check if ADT alpha maps are 8-bit (4096 bytes per layer) rather
+    /// than 4-bit
+    pub fn has_big_alpha(&self) -> bool {
+        self.flags.contains(MphdFlags::ADT_HAS_BIG_ALPHA)
+    }
+
+    /// Set whether ADT alpha maps are 8-bit
+    pub fn set_big_alpha(&mut self, enabled: bool) {
+        self.flags.set(MphdFlags::ADT_HAS_BIG_ALPHA, enabled);
+    }
+
+    /// Check if terrain blends textures by their `_h` height maps
+    pub fn has_height_texturing(&self) -> bool {
+        self.flags.contains(MphdFlags::ADT_HAS_HEIGHT_TEXTURING)
+    }
+
+    /// Set whether terrain blends textures by their `_h` height maps
+    pub fn set_height_texturing(&mut self, enabled: bool) {
+        self.flags.set(MphdFlags::ADT_HAS_HEIGHT_TEXTURING, enabled);
+    }
+
     /// Set FileDataIDs (for BfA+ format)
     pub fn set_file_data_ids(&mut self, ids: FileDataIds) {
         self.flags |= MphdFlags::WDT_HAS_MAID;
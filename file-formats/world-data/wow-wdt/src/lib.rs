@@ -90,6 +90,26 @@ impl WdtFile {
         self.mphd.is_wmo_only()
     }
 
+    /// Check if the map's ADTs use 8-bit alpha maps
+    pub fn has_big_alpha(&self) -> bool {
+        self.mphd.has_big_alpha()
+    }
+
+    /// Check if the map's ADTs carry vertex colors (MCCV)
+    pub fn has_vertex_shading(&self) -> bool {
+        self.mphd.has_vertex_shading()
+    }
+
+    /// Check if the map's terrain uses height-based texture blending
+    pub fn has_height_texturing(&self) -> bool {
+        self.mphd.has_height_texturing()
+    }
+
+    /// Check if MPHD announces a MAID chunk
+    pub fn has_maid(&self) -> bool {
+        self.mphd.has_maid()
+    }
+
     /// Count tiles with ADT data
     pub fn count_existing_tiles(&self) -> usize {
         if let Some(ref maid) = self.maid {
@@ -142,20 +162,11 @@ impl WdtFile {
                 .validate_mphd_flags(self.mphd.flags.bits()),
         );
 
-        // Structure validation
-        if self.is_wmo_only() {
-            if self.mwmo.is_none() {
-                warnings.push("WMO-only map missing MWMO chunk".to_string());
-            }
-            if self.modf.is_none() {
-                warnings.push("WMO-only map missing MODF chunk".to_string());
-            }
-        } else {
-            // Terrain map validations
-            if self.modf.is_some() {
-                warnings.push("Terrain map should not have MODF chunk".to_string());
-            }
+        // Flags that disagree with the chunks present
+        warnings.extend(self.flag_mismatches());
 
+        // Structure validation
+        if !self.is_wmo_only() {
             // Check MWMO presence based on version
             let should_have_mwmo = self.version_config.should_have_chunk("MWMO", false);
             let has_mwmo = self.mwmo.is_some();
@@ -168,15 +179,48 @@ impl WdtFile {
             }
         }
 
-        // MAID validation
+        warnings
+    }
+
+    /// List the MPHD flags that disagree with the chunks present
+    ///
+    /// The client decides from the flags alone which chunks to look for, so
+    /// a WMO-only flag without MWMO and MODF, or a MAID flag without the
+    /// chunk, makes it misread the map.
+    pub fn flag_mismatches(&self) -> Vec<String> {
+        let mut mismatches = Vec::new();
+
+        if self.is_wmo_only() {
+            if self.mwmo.is_none() {
+                mismatches.push("WMO-only map missing MWMO chunk".to_string());
+            }
+            if self.modf.is_none() {
+                mismatches.push("WMO-only map missing MODF chunk".to_string());
+            }
+        } else if self.modf.is_some() {
+            mismatches.push("Terrain map should not have MODF chunk".to_string());
+        }
+
         if self.mphd.has_maid() && self.maid.is_none() {
-            warnings
+            mismatches
                 .push("MPHD indicates MAID chunk should be present but it's missing".to_string());
         } else if !self.mphd.has_maid() && self.maid.is_some() {
-            warnings.push("MAID chunk present but not indicated in MPHD flags".to_string());
+            mismatches.push("MAID chunk present but not indicated in MPHD flags".to_string());
         }
 
-        warnings
+        mismatches
+    }
+
+    /// Fail if any MPHD flag disagrees with the chunks present
+    ///
+    /// See [`WdtFile::flag_mismatches`].
+    pub fn check_flags(&self) -> Result<()> {
+        let mismatches = self.flag_mismatches();
+        if mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::ValidationError(mismatches.join("; ")))
+        }
     }
 }
 
@@ -357,7 +401,13 @@ impl<W: Write> WdtWriter<W> {
     }
 
     /// Write a complete WDT file
+    ///
+    /// Fails with [`Error::ValidationError`] before writing anything if the
+    /// MPHD flags disagree with the chunks present, see
+    /// [`WdtFile::check_flags`].
     pub fn write(&mut self, wdt: &WdtFile) -> Result<()> {
+        wdt.check_flags()?;
+
         // Write required chunks in order
         wdt.mver.write_chunk(&mut self.writer)?;
         wdt.mphd.write_chunk(&mut self.writer)?;
@@ -444,6 +494,20 @@ mod tests {
         assert!(read_wdt.main.get(10, 20).unwrap().has_adt());
     }
 
+    #[test]
+    fn test_writer_rejects_flag_mismatch() {
+        let mut wdt = WdtFile::new(WowVersion::BfA);
+        wdt.mphd.flags |= chunks::MphdFlags::WDT_HAS_MAID;
+
+        let mut buffer = Vec::new();
+        let result = WdtWriter::new(&mut buffer).write(&wdt);
+        assert!(matches!(result, Err(Error::ValidationError(_))));
+        assert!(buffer.is_empty());
+
+        wdt.maid = Some(MaidChunk::new());
+        WdtWriter::new(&mut buffer).write(&wdt).unwrap();
+    }
+
     #[test]
     fn test_coordinate_conversion() {
         // Test center of map
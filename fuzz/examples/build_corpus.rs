@@ -0,0 +1,19 @@
+//! Write seed inputs for every fuzz target
+//!
+//! ```text
+//! cargo run --manifest-path fuzz/Cargo.toml --example build_corpus [DIR]
+//! ```
+//!
+//! `DIR` defaults to `fuzz/corpus`, where `cargo fuzz run` looks for them.
+
+use std::path::PathBuf;
+
+fn main() -> std::io::Result<()> {
+    let dir = std::env::args_os()
+        .nth(1)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("corpus"));
+    let written = fuzz_targets::corpus::write_corpus(&dir)?;
+    println!("Wrote {written} seed files to {}", dir.display());
+    Ok(())
+}
@@ -0,0 +1,441 @@
+//! Seed inputs for the fuzz targets
+//!
+//! Fuzzers find their way into deeper parser states much faster when they
+//! start from valid files. The seeds are the BLP fixtures from `wow-blp`'s
+//! test data plus small files written by the crates' own builders and
+//! writers, so they follow any format change without being checked in.
+
+use std::fs;
+use std::io::{self, Cursor};
+use std::path::Path;
+
+/// A named seed input
+pub type Seed = (String, Vec<u8>);
+
+/// Seed inputs of a target
+///
+/// Returns an empty list for unknown target names.
+pub fn seeds(target: &str) -> io::Result<Vec<Seed>> {
+    Ok(match target {
+        "mpq_header" => mpq_archives()?
+            .into_iter()
+            .map(|(name, data)| (name, data[..data.len().min(512)].to_vec()))
+            .collect(),
+        "mpq_tables" => mpq_tables()?,
+        "mpq_archive" => mpq_archives()?,
+        "blp" => blp()?,
+        "dbc" => vec![("items.dbc".to_string(), dbc())],
+        "wmo_root" => vec![("static.wmo".to_string(), wmo().0)],
+        "wmo_group" => vec![("static_000.wmo".to_string(), wmo().1)],
+        "adt" => adt(),
+        "m2" => m2(),
+        "m2_skin" => vec![("model00.skin".to_string(), m2_skin())],
+        "wdt" => wdt(),
+        "wdl" => wdl(),
+        _ => Vec::new(),
+    })
+}
+
+/// Write the seeds of every target to `<dir>/<target>/<seed>`
+///
+/// This is the layout `cargo fuzz run <target>` reads its corpus from when
+/// `dir` is `fuzz/corpus`. Returns the number of files written.
+pub fn write_corpus(dir: &Path) -> io::Result<usize> {
+    let mut written = 0;
+    for target in crate::TARGETS {
+        let target_dir = dir.join(target);
+        fs::create_dir_all(&target_dir)?;
+        for (name, data) in seeds(target)? {
+            fs::write(target_dir.join(name), data)?;
+            written += 1;
+        }
+    }
+    Ok(written)
+}
+
+fn other(error: impl std::fmt::Display) -> io::Error {
+    io::Error::other(error.to_string())
+}
+
+/// Small archives of every format version
+fn mpq_archives() -> io::Result<Vec<Seed>> {
+    use wow_mpq::compression::flags;
+    use wow_mpq::{ArchiveBuilder, AttributesOption, FormatVersion};
+
+    let text: Vec<u8> = (0..400)
+        .flat_map(|i| format!("line {i}\n").into_bytes())
+        .collect();
+    let dir = tempfile::tempdir()?;
+    let mut seeds = Vec::new();
+    for (name, version) in [
+        ("v1.mpq", FormatVersion::V1),
+        ("v2.mpq", FormatVersion::V2),
+        ("v3.mpq", FormatVersion::V3),
+        ("v4.mpq", FormatVersion::V4),
+    ] {
+        let path = dir.path().join(name);
+        ArchiveBuilder::new()
+            .version(version)
+            .block_size(0)
+            .generate_crcs(true)
+            .attributes_option(AttributesOption::GenerateFull)
+            .add_file_data(text.clone(), "data\\text.txt")
+            .add_file_data_with_options(text.clone(), "data\\bzip2.txt", flags::BZIP2, false, 0)
+            .add_file_data_with_encryption(text.clone(), "data\\secret.txt", flags::ZLIB, true, 0)
+            .add_file_data(vec![0; 16], "data\\small.bin")
+            .build(&path)
+            .map_err(other)?;
+        seeds.push((name.to_string(), fs::read(&path)?));
+    }
+    Ok(seeds)
+}
+
+/// Raw hash, block, HET and BET tables of the seed archives
+fn mpq_tables() -> io::Result<Vec<Seed>> {
+    let mut seeds = Vec::new();
+    for (name, data) in mpq_archives()? {
+        let header = wow_mpq::MpqHeader::read(&mut Cursor::new(&data)).map_err(other)?;
+        let stem = name.trim_end_matches(".mpq");
+        let mut table = |kind: &str, pos: u64, size: u64| {
+            let (start, end) = (pos as usize, (pos + size) as usize);
+            if size > 0 && end <= data.len() {
+                seeds.push((format!("{stem}_{kind}.bin"), data[start..end].to_vec()));
+            }
+        };
+        table(
+            "hash",
+            header.get_hash_table_pos(),
+            header.hash_table_size as u64 * 16,
+        );
+        table(
+            "block",
+            header.get_block_table_pos(),
+            header.block_table_size as u64 * 16,
+        );
+        if let Some(v4) = &header.v4_data {
+            if let Some(pos) = header.het_table_pos {
+                table("het", pos, v4.het_table_size_64);
+            }
+            if let Some(pos) = header.bet_table_pos {
+                table("bet", pos, v4.bet_table_size_64);
+            }
+        }
+    }
+    Ok(seeds)
+}
+
+/// The BLP files in `wow-blp`'s test data
+fn blp() -> io::Result<Vec<Seed>> {
+    let dir =
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("../file-formats/graphics/wow-blp/test-data");
+    let mut seeds = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "blp") {
+            let name = path.file_name().unwrap().to_string_lossy().into_owned();
+            seeds.push((name, fs::read(&path)?));
+        }
+    }
+    seeds.sort();
+    Ok(seeds)
+}
+
+/// A table with an integer, a string and a float column
+fn dbc() -> Vec<u8> {
+    let strings = b"\0Sword\0Shield\0";
+    let records: [(u32, u32, f32); 3] = [(1, 1, 2.5), (2, 7, 0.0), (3, 0, -1.0)];
+
+    let mut data = b"WDBC".to_vec();
+    for value in [records.len() as u32, 3, 12, strings.len() as u32] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    for (id, name, weight) in records {
+        data.extend_from_slice(&id.to_le_bytes());
+        data.extend_from_slice(&name.to_le_bytes());
+        data.extend_from_slice(&weight.to_le_bytes());
+    }
+    data.extend_from_slice(strings);
+    data
+}
+
+/// Root and group file of a one-group WMO
+fn wmo() -> (Vec<u8>, Vec<u8>) {
+    use wow_wmo::{StaticMaterial, StaticWmoBuilder, TexCoord, Vec3};
+
+    let corners = [(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0)];
+    let positions: Vec<Vec3> = corners
+        .iter()
+        .map(|&(x, y)| Vec3 { x, y, z: 0.0 })
+        .collect();
+    let up = Vec3 {
+        x: 0.0,
+        y: 0.0,
+        z: 1.0,
+    };
+    let uvs = corners
+        .iter()
+        .map(|&(x, y)| TexCoord {
+            u: x / 4.0,
+            v: y / 4.0,
+        })
+        .collect();
+    let builder = StaticWmoBuilder::new("Floor")
+        .vertices(positions, vec![up; 4], uvs)
+        .material(StaticMaterial::new("Tileset\\Generic\\Stone.blp"))
+        .batch(0, vec![0, 1, 2, 0, 2, 3])
+        .indoor(true);
+
+    let mut root = Vec::new();
+    let mut group = Vec::new();
+    builder
+        .write_root(&mut root)
+        .and_then(|()| builder.write_group(&mut group))
+        .expect("static WMO seed");
+    (root, group)
+}
+
+/// One-chunk root ADTs of the vanilla, WotLK and Cataclysm layouts
+fn adt() -> Vec<Seed> {
+    use wow_adt::AdtVersion;
+    use wow_adt::builder::AdtBuilder;
+    use wow_adt::chunks::mcnk::header::{McnkFlags, McnkHeader};
+    use wow_adt::chunks::mcnk::mcvt::McvtChunk;
+    use wow_adt::chunks::mcnk::{MclyChunk, MclyLayer, McnkChunk};
+    use wow_adt::chunks::{DoodadPlacement, WmoPlacement};
+
+    let heights: Vec<f32> = (0..145).map(|i| (i % 9) as f32).collect();
+    let mcnk = McnkChunk {
+        header: McnkHeader {
+            flags: McnkFlags { value: 0 },
+            index_x: 0,
+            index_y: 0,
+            n_layers: 1,
+            n_doodad_refs: 0,
+            multipurpose_field: McnkHeader::multipurpose_from_offsets(0, 0),
+            ofs_layer: 0,
+            ofs_refs: 0,
+            ofs_alpha: 0,
+            size_alpha: 0,
+            ofs_shadow: 0,
+            size_shadow: 0,
+            area_id: 12,
+            n_map_obj_refs: 0,
+            holes_low_res: 0,
+            unknown_but_used: 0,
+            pred_tex: [0; 8],
+            no_effect_doodad: [0; 8],
+            unknown_8bytes: [0; 8],
+            ofs_snd_emitters: 0,
+            n_snd_emitters: 0,
+            ofs_liquid: 0,
+            size_liquid: 0,
+            position: [0.0, 0.0, 0.0],
+            ofs_mccv: 0,
+            ofs_mclv: 0,
+            unused: 0,
+            _padding: [0; 8],
+        },
+        heights: Some(McvtChunk { heights }),
+        normals: None,
+        layers: Some(MclyChunk {
+            layers: vec![MclyLayer::default()],
+        }),
+        materials: None,
+        refs: None,
+        doodad_refs: None,
+        wmo_refs: None,
+        alpha: None,
+        shadow: None,
+        vertex_colors: None,
+        vertex_lighting: None,
+        sound_emitters: None,
+        liquid: None,
+        doodad_disable: None,
+        blend_batches: None,
+    };
+    let doodad = DoodadPlacement {
+        name_id: 0,
+        unique_id: 1,
+        position: [100.0, 100.0, 5.0],
+        rotation: [0.0, 90.0, 0.0],
+        scale: 1024,
+        flags: 0,
+    };
+    let wmo = WmoPlacement {
+        name_id: 0,
+        unique_id: 2,
+        position: [200.0, 200.0, 5.0],
+        rotation: [0.0, 0.0, 0.0],
+        extents_min: [-4.0, -4.0, -1.0],
+        extents_max: [4.0, 4.0, 1.0],
+        flags: 0,
+        doodad_set: 0,
+        name_set: 0,
+        scale: 1024,
+    };
+
+    [
+        ("vanilla.adt", AdtVersion::VanillaEarly),
+        ("wotlk.adt", AdtVersion::WotLK),
+        ("cataclysm.adt", AdtVersion::Cataclysm),
+    ]
+    .into_iter()
+    .map(|(name, version)| {
+        let data = AdtBuilder::new()
+            .with_version(version)
+            .add_texture("tileset/generic/grass.blp")
+            .add_model("world/generic/tree.m2")
+            .add_wmo("world/wmo/tower.wmo")
+            .add_doodad_placement(doodad)
+            .add_wmo_placement(wmo)
+            .add_mcnk_chunk(mcnk.clone())
+            .build()
+            .and_then(|adt| adt.to_bytes())
+            .expect("ADT seed");
+        (name.to_string(), data)
+    })
+    .collect()
+}
+
+/// A three-vertex, two-bone model
+fn m2_model(version: wow_m2::M2Version) -> wow_m2::M2Model {
+    use wow_m2::M2Model;
+    use wow_m2::chunks::M2Vertex;
+    use wow_m2::chunks::bone::M2Bone;
+    use wow_m2::common::{C2Vector, C3Vector};
+    use wow_m2::header::M2Header;
+
+    let vertex = |x: f32, y: f32, bone: u8| M2Vertex {
+        position: C3Vector { x, y, z: 0.0 },
+        bone_weights: [255, 0, 0, 0],
+        bone_indices: [bone, 0, 0, 0],
+        normal: C3Vector {
+            x: 0.0,
+            y: 0.0,
+            z: 1.0,
+        },
+        tex_coords: C2Vector { x, y },
+        tex_coords2: Some(C2Vector { x: 0.0, y: 0.0 }),
+    };
+    let mut model = M2Model {
+        header: M2Header::new(version),
+        name: Some("Seed".to_string()),
+        ..Default::default()
+    };
+    model.vertices = vec![
+        vertex(0.0, 0.0, 0),
+        vertex(1.0, 0.0, 1),
+        vertex(0.0, 1.0, 1),
+    ];
+    model.bones = vec![M2Bone::new(0, -1), M2Bone::new(1, 0)];
+    model
+}
+
+fn m2() -> Vec<Seed> {
+    use wow_m2::M2Version;
+
+    [
+        ("vanilla.m2", M2Version::Vanilla),
+        ("wotlk.m2", M2Version::WotLK),
+        ("legion.m2", M2Version::Legion),
+    ]
+    .into_iter()
+    .map(|(name, version)| {
+        let mut data = Cursor::new(Vec::new());
+        m2_model(version).write(&mut data).expect("M2 seed");
+        (name.to_string(), data.into_inner())
+    })
+    .collect()
+}
+
+fn m2_skin() -> Vec<u8> {
+    use wow_m2::M2Version;
+    use wow_m2::skin::{SkinG, SkinHeader, SkinSubmesh};
+
+    let submesh = SkinSubmesh {
+        id: 0,
+        level: 0,
+        vertex_start: 0,
+        vertex_count: 3,
+        triangle_start: 0,
+        triangle_count: 3,
+        bone_count: 2,
+        bone_start: 0,
+        bone_influence: 1,
+        center: [0.0; 3],
+        sort_center: [0.0; 3],
+        bounding_radius: 1.0,
+    };
+    let skin = SkinG::<SkinHeader> {
+        header: SkinHeader::new(M2Version::WotLK),
+        indices: vec![0, 1, 2],
+        triangles: vec![0, 1, 2],
+        bone_indices: vec![0, 0, 0, 0],
+        submeshes: vec![submesh],
+        batches: Vec::new(),
+    };
+    let mut data = Cursor::new(Vec::new());
+    skin.write(&mut data).expect("skin seed");
+    data.into_inner()
+}
+
+/// Terrain maps with a few tiles, in the classic and WotLK layouts
+fn wdt() -> Vec<Seed> {
+    use wow_wdt::version::WowVersion;
+    use wow_wdt::{WdtFile, WdtWriter};
+
+    [
+        ("classic.wdt", WowVersion::Classic),
+        ("wotlk.wdt", WowVersion::WotLK),
+    ]
+    .into_iter()
+    .map(|(name, version)| {
+        let mut wdt = WdtFile::new(version);
+        for (x, y) in [(31, 31), (32, 31), (32, 32)] {
+            let tile = wdt.main.get_mut(x, y).expect("tile in range");
+            tile.set_has_adt(true);
+            tile.area_id = 12;
+        }
+        let mut data = Vec::new();
+        WdtWriter::new(&mut data).write(&wdt).expect("WDT seed");
+        (name.to_string(), data)
+    })
+    .collect()
+}
+
+/// Empty low-resolution maps of the vanilla and WotLK versions
+fn wdl() -> Vec<Seed> {
+    use wow_wdl::parser::WdlParser;
+    use wow_wdl::{WdlFile, WdlVersion};
+
+    [
+        ("vanilla.wdl", WdlVersion::Vanilla),
+        ("wotlk.wdl", WdlVersion::Wotlk),
+    ]
+    .into_iter()
+    .map(|(name, version)| {
+        let mut data = Cursor::new(Vec::new());
+        WdlParser::with_version(version)
+            .write(&mut data, &WdlFile::with_version(version))
+            .expect("WDL seed");
+        (name.to_string(), data.into_inner())
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_target_has_seeds() {
+        for target in crate::TARGETS {
+            let seeds = seeds(target).unwrap();
+            assert!(!seeds.is_empty(), "no seeds for {target}");
+            for (_, data) in &seeds {
+                assert!(crate::run(target, data));
+            }
+        }
+        assert!(!crate::run("missing", &[]));
+    }
+}
@@ -0,0 +1,160 @@
+//! Fuzzing harnesses for the warcraft-rs parsers
+//!
+//! Each function feeds arbitrary bytes to one parser and drops the result.
+//! Errors are expected; panics, hangs and runaway allocations are the bugs
+//! these targets look for. The binaries in `fuzz_targets/` wrap them for
+//! `cargo fuzz`, and [`corpus`] builds seed inputs for every target.
+
+use std::io::{Cursor, Write};
+
+pub mod corpus;
+
+/// Names of all fuzz targets, matching the binaries in `fuzz_targets/`
+pub const TARGETS: &[&str] = &[
+    "mpq_header",
+    "mpq_tables",
+    "mpq_archive",
+    "blp",
+    "dbc",
+    "wmo_root",
+    "wmo_group",
+    "adt",
+    "m2",
+    "m2_skin",
+    "wdt",
+    "wdl",
+];
+
+/// Run the harness of a target by name
+///
+/// Returns `false` for unknown target names.
+pub fn run(target: &str, data: &[u8]) -> bool {
+    let harness: fn(&[u8]) = match target {
+        "mpq_header" => mpq_header,
+        "mpq_tables" => mpq_tables,
+        "mpq_archive" => mpq_archive,
+        "blp" => blp,
+        "dbc" => dbc,
+        "wmo_root" => wmo_root,
+        "wmo_group" => wmo_group,
+        "adt" => adt,
+        "m2" => m2,
+        "m2_skin" => m2_skin,
+        "wdt" => wdt,
+        "wdl" => wdl,
+        _ => return false,
+    };
+    harness(data);
+    true
+}
+
+/// MPQ user data and archive headers
+pub fn mpq_header(data: &[u8]) {
+    let _ = wow_mpq::header::find_header(&mut Cursor::new(data));
+    let _ = wow_mpq::MpqHeader::read(&mut Cursor::new(data));
+}
+
+/// Classic hash/block tables and HET/BET tables, read from the whole input
+pub fn mpq_tables(data: &[u8]) {
+    use wow_mpq::{BetTable, BlockTable, HashTable, HetTable, hash_string, hash_type};
+
+    let entries = (data.len() / 16) as u32;
+    if entries > 0 {
+        // Hash tables must have a power of two size
+        let _ = HashTable::from_bytes(data, 1 << entries.ilog2());
+        let _ = BlockTable::from_bytes(data, entries);
+    }
+    let len = data.len() as u64;
+    let het_key = hash_string("(hash table)", hash_type::FILE_KEY);
+    let _ = HetTable::read(&mut Cursor::new(data), 0, len, het_key);
+    let bet_key = hash_string("(block table)", hash_type::FILE_KEY);
+    let _ = BetTable::read(&mut Cursor::new(data), 0, len, bet_key);
+}
+
+/// Whole archives: open, list and read every file
+pub fn mpq_archive(data: &[u8]) {
+    // Archives are opened by path
+    let Ok(mut file) = tempfile::NamedTempFile::new() else {
+        return;
+    };
+    if file.write_all(data).is_err() {
+        return;
+    }
+    let Ok(mut archive) = wow_mpq::Archive::open(file.path()) else {
+        return;
+    };
+    let _ = archive.get_info();
+    let _ = archive.verify_blocks();
+    if let Ok(entries) = archive.list() {
+        for entry in entries {
+            // Files missing from the listfile only have generated names
+            if archive.read_file(&entry.name).is_err()
+                && let Some((hash_index, block_index)) = entry.table_indices
+            {
+                let _ = archive.read_file_by_indices(hash_index, block_index);
+            }
+        }
+    }
+}
+
+/// BLP textures, decoding every mipmap
+pub fn blp(data: &[u8]) {
+    let Ok(image) = wow_blp::parser::load_blp_from_buf(data) else {
+        return;
+    };
+    for level in 0..image.image_count() {
+        let _ = wow_blp::convert::blp_to_image(&image, level);
+    }
+}
+
+/// DBC tables, including records and strings
+pub fn dbc(data: &[u8]) {
+    let Ok(parser) = wow_cdbc::DbcParser::parse_bytes(data) else {
+        return;
+    };
+    let _ = parser.parse_records();
+}
+
+/// WMO root files
+pub fn wmo_root(data: &[u8]) {
+    let _ = wow_wmo::WmoParser::new().parse_root(&mut Cursor::new(data));
+    let _ = wow_wmo::parse_wmo(&mut Cursor::new(data));
+}
+
+/// WMO group files
+pub fn wmo_group(data: &[u8]) {
+    let mut reader = Cursor::new(data);
+    if let Ok(discovery) = wow_wmo::discover_wmo_chunks(&mut reader) {
+        let _ = wow_wmo::group_parser::parse_group_file(&mut reader, discovery);
+    }
+    let _ = wow_wmo::parse_wmo(&mut Cursor::new(data));
+}
+
+/// ADT terrain files of any kind (root, split, LOD)
+pub fn adt(data: &[u8]) {
+    let _ = wow_adt::parse_adt(&mut Cursor::new(data));
+}
+
+/// M2 models
+pub fn m2(data: &[u8]) {
+    let _ = wow_m2::parse_m2(&mut Cursor::new(data));
+}
+
+/// M2 skin profiles
+pub fn m2_skin(data: &[u8]) {
+    let _ = wow_m2::parse_skin(&mut Cursor::new(data));
+}
+
+/// WDT map definitions, with the rules of the oldest and newest versions
+pub fn wdt(data: &[u8]) {
+    use wow_wdt::{WdtReader, version::WowVersion};
+
+    for version in [WowVersion::Classic, WowVersion::WoD] {
+        let _ = WdtReader::new(Cursor::new(data), version).read();
+    }
+}
+
+/// WDL low-resolution terrain
+pub fn wdl(data: &[u8]) {
+    let _ = wow_wdl::parser::WdlParser::new().parse(&mut Cursor::new(data));
+}
@@ -19,6 +19,10 @@ pub struct Cli {
     /// Suppress all output except errors
     #[arg(short, long, global = true)]
     pub quiet: bool,
+
+    /// Game installation profile from the config file to take defaults from
+    #[arg(long, global = true, env = "WARCRAFT_RS_PROFILE")]
+    pub profile: Option<String>,
 }
 
 #[derive(Subcommand)]
@@ -86,6 +90,116 @@ pub enum Commands {
         command: crate::commands::wdl::WdlCommands,
     },
 
+    /// Compare the game data of two client installations
+    #[cfg(feature = "mpq")]
+    Compare {
+        /// Old installation or Data directory
+        #[arg(long, value_name = "DATA_DIR")]
+        old: std::path::PathBuf,
+
+        /// New installation or Data directory
+        #[arg(long, value_name = "DATA_DIR")]
+        new: std::path::PathBuf,
+
+        /// Locale of the old installation (auto-detected if not given)
+        #[arg(long)]
+        old_locale: Option<String>,
+
+        /// Locale of the new installation (auto-detected if not given)
+        #[arg(long)]
+        new_locale: Option<String>,
+
+        /// Kinds of data to compare (all if not given)
+        #[arg(long, value_enum, value_delimiter = ',')]
+        kinds: Vec<crate::commands::compare::DataKind>,
+
+        /// Compare the contents of files whose size did not change
+        #[arg(long)]
+        content: bool,
+
+        /// Report format
+        #[arg(short, long, value_enum, default_value = "text")]
+        format: crate::commands::compare::CompareFormat,
+
+        /// Write the report to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<std::path::PathBuf>,
+
+        /// Changed records listed per table in the text report
+        #[arg(long, default_value_t = 20)]
+        max_records: usize,
+    },
+
+    /// Extract a game asset with the files it references and optionally convert it
+    #[cfg(feature = "mpq")]
+    ExtractAsset {
+        /// Client path of the asset (e.g. "Creature/Illidan/Illidan.m2")
+        asset: String,
+
+        /// Installation, Data directory or MPQ archive (defaults to the
+        /// profile's data_dir)
+        #[arg(long, value_name = "DATA_DIR")]
+        data_dir: Option<std::path::PathBuf>,
+
+        /// Client locale (defaults to the profile's locale, else auto-detected)
+        #[arg(long)]
+        locale: Option<String>,
+
+        /// Output directory; files keep their client paths below it
+        #[arg(short, long, default_value = ".")]
+        output: std::path::PathBuf,
+
+        /// Convert the extracted asset
+        #[arg(long, value_enum)]
+        convert: Option<crate::commands::asset::AssetFormat>,
+
+        /// Also extract animations, textures and doodads the asset references
+        #[arg(long)]
+        with_dependencies: bool,
+    },
+
+    /// List every file an asset references, with sizes and missing files
+    #[cfg(feature = "mpq")]
+    AssetDeps {
+        /// Client path of the asset: an M2 model, WMO root or ADT tile
+        asset: String,
+
+        /// Installation, Data directory or MPQ archive (defaults to the
+        /// profile's data_dir)
+        #[arg(long, value_name = "DATA_DIR")]
+        data_dir: Option<std::path::PathBuf>,
+
+        /// Client locale (defaults to the profile's locale, else auto-detected)
+        #[arg(long)]
+        locale: Option<String>,
+
+        /// Report format
+        #[arg(short, long, value_enum, default_value = "text")]
+        format: crate::commands::asset::DepsFormat,
+    },
+
+    /// Show the chunk/structure tree of any supported file
+    Tree {
+        /// File to inspect (ADT, WMO, WDT, WDL, M2, SKIN, BLP or DBC)
+        file: std::path::PathBuf,
+
+        /// Maximum depth to display
+        #[arg(long)]
+        depth: Option<usize>,
+
+        /// Only show chunks whose ID matches the pattern (supports wildcards)
+        #[arg(short, long)]
+        filter: Option<String>,
+
+        /// Disable colored output
+        #[arg(long)]
+        no_color: bool,
+
+        /// Compact output (hide metadata)
+        #[arg(long)]
+        compact: bool,
+    },
+
     /// Generate shell completions
     Completions {
         /// Shell to generate completions for
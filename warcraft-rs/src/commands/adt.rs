@@ -1,12 +1,26 @@
 //! ADT terrain command implementations
 
+mod convert;
+mod export;
+mod ground_effects;
+mod ocean;
+mod visualize;
+
 use anyhow::{Context, Result};
 use clap::Subcommand;
 use prettytable::{Cell, Row, Table, format};
 use std::fs::File;
 use std::io::BufReader;
-use std::path::Path;
-use wow_adt::{AdtVersion, BuiltAdt, ParsedAdt, parse_adt_with_metadata};
+use std::path::{Path, PathBuf};
+use wow_adt::map_audit::{MapAuditOptions, audit_map_dir};
+use wow_adt::{
+    AdtVersion, BuiltAdt, LodAdt, LodOptions, ParsedAdt, ValidationLevel, parse_adt_with_metadata,
+    validate_stream,
+};
+use wow_wdt::version::WowVersion;
+
+use crate::utils::scene::TextureMode;
+use export::AdtExportFormat;
 
 #[cfg(feature = "parallel")]
 use wow_adt::parse_adt;
@@ -35,19 +49,41 @@ pub enum AdtCommands {
         /// Show warnings in addition to errors
         #[arg(short, long)]
         warnings: bool,
+
+        /// Check structure while streaming through the file instead of parsing it fully
+        #[arg(long)]
+        stream: bool,
     },
 
     /// Convert ADT between different WoW versions
     Convert {
         /// Input ADT file
-        input: String,
+        #[arg(required_unless_present = "map", conflicts_with = "map")]
+        input: Option<String>,
 
         /// Output ADT file
-        output: String,
+        #[arg(required_unless_present = "map", conflicts_with = "map")]
+        output: Option<String>,
 
         /// Target WoW version (classic, tbc, wotlk, cataclysm)
         #[arg(short, long)]
         to: String,
+
+        /// Convert every tile of a map directory (e.g. World/Maps/Azeroth)
+        #[arg(long, value_name = "DIR", requires = "out")]
+        map: Option<PathBuf>,
+
+        /// Output directory for --map
+        #[arg(long, value_name = "DIR", requires = "map")]
+        out: Option<PathBuf>,
+
+        /// Only convert tiles of this source version; others are skipped
+        #[arg(long, requires = "map")]
+        from: Option<String>,
+
+        /// Number of parallel workers for --map (defaults to the CPU count)
+        #[arg(short, long, requires = "map")]
+        jobs: Option<usize>,
     },
 
     /// Extract data from ADT files
@@ -81,6 +117,135 @@ pub enum AdtCommands {
         all: bool,
     },
 
+    /// Render heightmap, texture layers, holes and water as PNG images
+    Visualize {
+        /// Path to the root ADT file
+        file: String,
+
+        /// Write the heightmap to this PNG; other images are named after it
+        #[arg(long, value_name = "PNG")]
+        heightmap: Option<PathBuf>,
+
+        /// Render texture layer coverage (layers 1-3 as red, green, blue)
+        #[arg(long)]
+        layers: bool,
+
+        /// Render the terrain hole mask
+        #[arg(long)]
+        holes: bool,
+
+        /// Render water extents colored by liquid type
+        #[arg(long)]
+        water: bool,
+    },
+
+    /// Generate a Legion-style _lod.adt from a root ADT's heights
+    Lod {
+        /// Path to the root ADT file
+        file: String,
+
+        /// Output file (defaults to <name>_lod.adt next to the input)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Levels of detail to generate, including the full resolution level
+        #[arg(long, default_value_t = 3, value_parser = clap::value_parser!(u8).range(1..=8))]
+        levels: u8,
+    },
+
+    /// List, check and assign ground effects (grass and clutter) of texture layers
+    GroundEffects {
+        /// Path to the root ADT file
+        file: PathBuf,
+
+        /// GroundEffectTexture.dbc to check effect ids against
+        #[arg(long)]
+        dbc: Option<PathBuf>,
+
+        /// Assign the effects textures have in these ADTs
+        #[arg(long, value_name = "ADT")]
+        learn_from: Vec<PathBuf>,
+
+        /// Assign an effect to every layer of a texture (TEXTURE=EFFECT_ID)
+        #[arg(long, value_name = "TEXTURE=ID", value_parser = ground_effects::parse_assignment)]
+        set: Vec<(String, u32)>,
+
+        /// Replace effects that layers already have
+        #[arg(long)]
+        overwrite: bool,
+
+        /// Write the updated ADT here
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Fill terrain below a height with ocean water
+    Ocean {
+        /// Root ADT file, or a map directory to fill every tile of
+        path: PathBuf,
+
+        /// Height of the water surface
+        #[arg(long, allow_hyphen_values = true)]
+        level: f32,
+
+        /// LiquidType.dbc id of the water
+        #[arg(long, default_value_t = wow_adt::OCEAN_LIQUID_TYPE)]
+        liquid_type: u16,
+
+        /// Replace liquid that chunks already have
+        #[arg(long)]
+        replace: bool,
+
+        /// Output file, or output directory for a map (defaults to updating in place)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Export the terrain mesh to OBJ or glTF
+    Export {
+        /// Path to the root ADT file
+        file: PathBuf,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value_t = AdtExportFormat::Obj)]
+        format: AdtExportFormat,
+
+        /// Output directory (defaults to <name>_export next to the ADT)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// How to handle textures
+        #[arg(long, value_enum, default_value_t = TextureMode::None)]
+        textures: TextureMode,
+
+        /// MPQ archive, client data directory or extracted tree to resolve
+        /// textures from (defaults to the profile's data directory)
+        #[arg(long, value_name = "MPQ_OR_DIR")]
+        source: Option<PathBuf>,
+    },
+
+    /// Check a map's WDT, ADTs and WDL against each other
+    Audit {
+        /// Map directory with the WDT, WDL and ADT files
+        dir: PathBuf,
+
+        /// Map name (defaults to the directory name)
+        #[arg(long)]
+        map: Option<String>,
+
+        /// WoW version of the WDT (e.g., "1.12.1", "3.3.5a", "WotLK", "TBC", "MoP")
+        #[arg(long, default_value = "WotLK")]
+        version: String,
+
+        /// Largest tolerated difference between WDL and ADT heights, in yards
+        #[arg(long, default_value_t = 25.0)]
+        height_tolerance: f32,
+
+        /// Report tile borders where at least this many of the 16 chunks change area ID
+        #[arg(long, default_value_t = 16, value_parser = clap::value_parser!(u8).range(1..=16))]
+        seam_chunks: u8,
+    },
+
     /// Visualize ADT structure as a tree
     Tree {
         /// Path to the ADT file
@@ -138,8 +303,30 @@ pub fn execute(command: AdtCommands) -> Result<()> {
             file,
             level,
             warnings,
-        } => execute_validate(&file, &level, warnings),
-        AdtCommands::Convert { input, output, to } => execute_convert(&input, &output, &to),
+            stream,
+        } => execute_validate(&file, &level, warnings, stream),
+        AdtCommands::Convert {
+            input,
+            output,
+            to,
+            map,
+            out,
+            from,
+            jobs,
+        } => match (map, out) {
+            (Some(map_dir), Some(output_dir)) => convert::convert_map(convert::MapConvertOptions {
+                map_dir,
+                output_dir,
+                from: from.as_deref().map(parse_version).transpose()?,
+                to: parse_version(&to)?,
+                jobs,
+            }),
+            _ => execute_convert(
+                input.as_deref().unwrap_or_default(),
+                output.as_deref().unwrap_or_default(),
+                &to,
+            ),
+        },
         #[cfg(feature = "extract")]
         AdtCommands::Extract {
             file,
@@ -157,6 +344,78 @@ pub fn execute(command: AdtCommands) -> Result<()> {
             textures || all,
             models || all,
         ),
+        AdtCommands::Visualize {
+            file,
+            heightmap,
+            layers,
+            holes,
+            water,
+        } => execute_visualize(&file, heightmap, layers, holes, water),
+        AdtCommands::Lod {
+            file,
+            output,
+            levels,
+        } => execute_lod(&file, output, levels.into()),
+        AdtCommands::GroundEffects {
+            file,
+            dbc,
+            learn_from,
+            set,
+            overwrite,
+            output,
+        } => ground_effects::execute(
+            &file,
+            &ground_effects::GroundEffectOptions {
+                dbc: dbc.as_deref(),
+                learn_from: &learn_from,
+                set: &set,
+                overwrite,
+                output: output.as_deref(),
+            },
+        ),
+        AdtCommands::Ocean {
+            path,
+            level,
+            liquid_type,
+            replace,
+            output,
+        } => ocean::execute(
+            &path,
+            output.as_deref(),
+            &wow_adt::OceanFill::new(level)
+                .liquid_type(liquid_type)
+                .replace_existing(replace),
+        ),
+        AdtCommands::Export {
+            file,
+            format,
+            output,
+            textures,
+            source,
+        } => execute_export(
+            &file,
+            output,
+            export::ExportOptions {
+                format,
+                textures,
+                source: source.or_else(crate::utils::config::profile_data_dir),
+            },
+        ),
+        AdtCommands::Audit {
+            dir,
+            map,
+            version,
+            height_tolerance,
+            seam_chunks,
+        } => execute_audit(
+            &dir,
+            map,
+            &version,
+            MapAuditOptions {
+                max_height_difference: height_tolerance,
+                min_area_seam: seam_chunks.into(),
+            },
+        ),
         AdtCommands::Tree {
             file,
             depth,
@@ -331,9 +590,33 @@ fn execute_info(file: &str, detailed: bool) -> Result<()> {
                 }
             }
         }
-        ParsedAdt::Lod(_) => {
+        ParsedAdt::Lod(lod) => {
             println!("\nLOD File Information:");
-            println!("  Level-of-detail file (Cataclysm+)");
+            println!("  Heights: {}", lod.heights.len());
+            println!("  Triangles: {}", lod.indices.len() / 3);
+            println!("  Quadtree nodes: {}", lod.nodes.len());
+            if !lod.skirt_indices.is_empty() {
+                println!("  Skirt indices: {}", lod.skirt_indices.len());
+            }
+            if let Some(header) = &lod.header {
+                let [min_x, min_y, min_z, max_x, max_y, max_z] = header.bounding_box;
+                println!(
+                    "  Bounds: ({min_x:.1}, {min_y:.1}, {min_z:.1}) - ({max_x:.1}, {max_y:.1}, {max_z:.1})"
+                );
+            }
+            for (i, level) in lod.levels.iter().enumerate() {
+                println!(
+                    "  Level {i}: {} heights, {} triangles, band {:.1}",
+                    level.height_count,
+                    level.index_count / 3,
+                    level.lod_band
+                );
+            }
+            if !lod.other_chunks.is_empty() {
+                let names: Vec<String> =
+                    lod.other_chunks.iter().map(|(id, _)| id.as_str()).collect();
+                println!("  Other chunks: {}", names.join(", "));
+            }
         }
     }
 
@@ -375,7 +658,7 @@ fn execute_info(file: &str, detailed: bool) -> Result<()> {
     Ok(())
 }
 
-fn execute_validate(file: &str, level: &str, warnings: bool) -> Result<()> {
+fn execute_validate(file: &str, level: &str, warnings: bool, stream: bool) -> Result<()> {
     println!("Validating ADT File");
     println!("===================");
     println!();
@@ -386,6 +669,26 @@ fn execute_validate(file: &str, level: &str, warnings: bool) -> Result<()> {
     let file_handle =
         File::open(file).with_context(|| format!("Failed to open ADT file: {file}"))?;
     let mut reader = BufReader::new(file_handle);
+
+    if stream {
+        let level = match level {
+            "basic" => ValidationLevel::Basic,
+            "standard" => ValidationLevel::Standard,
+            "strict" => ValidationLevel::Strict,
+            _ => anyhow::bail!("Invalid level '{level}'. Valid options: basic, standard, strict"),
+        };
+        let mut report = validate_stream(&mut reader, level)
+            .with_context(|| format!("Failed to read ADT file: {file}"))?;
+        if !warnings {
+            report.warnings.clear();
+        }
+        print!("{}", report.format());
+        if !report.is_valid() {
+            anyhow::bail!("{file} failed validation");
+        }
+        return Ok(());
+    }
+
     let (adt, metadata) = parse_adt_with_metadata(&mut reader)
         .with_context(|| format!("Failed to parse ADT file: {file}"))?;
 
@@ -430,14 +733,18 @@ fn execute_validate(file: &str, level: &str, warnings: bool) -> Result<()> {
     Ok(())
 }
 
-fn execute_convert(input: &str, output: &str, to_version: &str) -> Result<()> {
-    // Parse target version from expansion name
-    let target_version = AdtVersion::from_expansion_name(to_version).with_context(|| {
+/// Parse a version from its expansion name
+fn parse_version(name: &str) -> Result<AdtVersion> {
+    AdtVersion::from_expansion_name(name).with_context(|| {
         format!(
-            "Invalid target version '{}'. Valid options: classic, tbc, wotlk, cataclysm, mop",
-            to_version
+            "Invalid version '{}'. Valid options: classic, tbc, wotlk, cataclysm, mop",
+            name
         )
-    })?;
+    })
+}
+
+fn execute_convert(input: &str, output: &str, to_version: &str) -> Result<()> {
+    let target_version = parse_version(to_version)?;
 
     println!("ADT Conversion");
     println!("==============");
@@ -520,6 +827,161 @@ fn execute_extract(_: &str, _: Option<&str>, _: bool, _: &str, _: bool, _: bool)
     anyhow::bail!("Extract command requires the 'extract' feature to be enabled")
 }
 
+fn execute_export(
+    file: &Path,
+    output: Option<PathBuf>,
+    options: export::ExportOptions,
+) -> Result<()> {
+    if !file.exists() {
+        anyhow::bail!("File not found: {}", file.display());
+    }
+    let output_dir = output.unwrap_or_else(|| {
+        let stem = file.file_stem().and_then(|s| s.to_str()).unwrap_or("adt");
+        file.with_file_name(format!("{stem}_export"))
+    });
+
+    println!("Exporting ADT terrain: {}", file.display());
+    let stats = export::export(file, &output_dir, options)?;
+
+    println!("Wrote {}", stats.output.display());
+    println!("  Chunks:    {}", stats.chunks);
+    println!("  Triangles: {}", stats.triangles);
+    println!("  Materials: {}", stats.materials);
+    if stats.textures_written > 0 {
+        println!("  Textures:  {}", stats.textures_written);
+    }
+    if !stats.textures_missing.is_empty() {
+        println!("  Missing textures: {}", stats.textures_missing.len());
+        for name in &stats.textures_missing {
+            println!("    {name}");
+        }
+    }
+    Ok(())
+}
+
+fn execute_audit(
+    dir: &Path,
+    map: Option<String>,
+    version: &str,
+    options: MapAuditOptions,
+) -> Result<()> {
+    let map = match map {
+        Some(map) => map,
+        None => dir
+            .file_name()
+            .and_then(|s| s.to_str())
+            .context("Cannot derive the map name from the directory; pass --map")?
+            .to_string(),
+    };
+    let version = WowVersion::from_expansion_name(version).context("Invalid version string")?;
+
+    println!("Auditing map {map} in {}", dir.display());
+    let report = audit_map_dir(dir, &map, version, &options)
+        .with_context(|| format!("Failed to audit map {map}"))?;
+
+    println!("  Tiles flagged in WDT: {}", report.tiles_flagged);
+    println!("  ADTs checked:         {}", report.tiles_checked);
+    if !report.has_wdl {
+        println!("  No WDL found; heights not compared");
+    }
+    println!();
+    if report.is_clean() {
+        println!("✓ No issues found");
+        return Ok(());
+    }
+    for issue in &report.issues {
+        println!("  {issue}");
+    }
+    println!();
+    println!("⚠ {} issues found", report.issues.len());
+    Ok(())
+}
+
+fn execute_lod(file: &str, output: Option<PathBuf>, levels: usize) -> Result<()> {
+    let file_handle =
+        File::open(file).with_context(|| format!("Failed to open ADT file: {file}"))?;
+    let mut reader = BufReader::new(file_handle);
+    let (adt, _) = parse_adt_with_metadata(&mut reader)
+        .with_context(|| format!("Failed to parse ADT file: {file}"))?;
+    let ParsedAdt::Root(root) = adt else {
+        anyhow::bail!("{file} is a split file; LOD generation needs the root ADT");
+    };
+
+    let lod = LodAdt::from_root_with_options(&root, &LodOptions { levels })
+        .with_context(|| format!("Failed to generate LOD data for {file}"))?;
+    let output = output.unwrap_or_else(|| {
+        let path = Path::new(file);
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("adt");
+        path.with_file_name(format!("{stem}_lod.adt"))
+    });
+    std::fs::write(&output, lod.to_bytes()?)
+        .with_context(|| format!("Failed to write {}", output.display()))?;
+
+    println!("Wrote {}", output.display());
+    for (i, level) in lod.levels.iter().enumerate() {
+        println!(
+            "  Level {i}: {} heights, {} triangles",
+            level.height_count,
+            level.index_count / 3
+        );
+    }
+    Ok(())
+}
+
+fn execute_visualize(
+    file: &str,
+    heightmap: Option<PathBuf>,
+    layers: bool,
+    holes: bool,
+    water: bool,
+) -> Result<()> {
+    let file_handle =
+        File::open(file).with_context(|| format!("Failed to open ADT file: {file}"))?;
+    let mut reader = BufReader::new(file_handle);
+    let (adt, _) = parse_adt_with_metadata(&mut reader)
+        .with_context(|| format!("Failed to parse ADT file: {file}"))?;
+    let ParsedAdt::Root(root) = adt else {
+        anyhow::bail!("{file} is a split file; visualize needs the root ADT");
+    };
+
+    // Images are named after the heightmap, or after the ADT when none is given
+    let base = heightmap
+        .clone()
+        .unwrap_or_else(|| Path::new(file).with_extension("png"));
+    let sibling = |suffix: &str| {
+        let stem = base.file_stem().and_then(|s| s.to_str()).unwrap_or("adt");
+        base.with_file_name(format!("{stem}_{suffix}.png"))
+    };
+
+    // Without any selection, render everything
+    let all = heightmap.is_none() && !layers && !holes && !water;
+    let heightmap = heightmap.or_else(|| all.then(|| sibling("height")));
+
+    if let Some(path) = heightmap {
+        visualize::write_png(&visualize::render_heightmap(&root), &path)?;
+        match visualize::height_range(&root) {
+            Some((min, max)) => println!("Heightmap: {} ({min:.2} to {max:.2})", path.display()),
+            None => println!("Heightmap: {} (no height data)", path.display()),
+        }
+    }
+    if layers || all {
+        let path = sibling("layers");
+        visualize::write_png(&visualize::render_layers(&root), &path)?;
+        println!("Layers:    {}", path.display());
+    }
+    if holes || all {
+        let path = sibling("holes");
+        visualize::write_png(&visualize::render_holes(&root), &path)?;
+        println!("Holes:     {}", path.display());
+    }
+    if water || all {
+        let path = sibling("water");
+        visualize::write_png(&visualize::render_water(&root), &path)?;
+        println!("Water:     {}", path.display());
+    }
+    Ok(())
+}
+
 fn execute_tree(
     file: &str,
     depth: Option<usize>,
@@ -562,11 +1024,8 @@ fn execute_tree(
         ParsedAdt::Obj0(obj) | ParsedAdt::Obj1(obj) => {
             root = build_obj_adt_tree(root, &obj, show_refs, no_metadata, compact);
         }
-        ParsedAdt::Lod(_) => {
-            root = root.add_child(TreeNode::new(
-                "LOD file (minimal structure)".to_string(),
-                NodeType::Data,
-            ));
+        ParsedAdt::Lod(lod) => {
+            root = build_lod_adt_tree(root, &lod, no_metadata);
         }
     }
 
@@ -1154,6 +1613,51 @@ fn build_tex_adt_tree(
     root
 }
 
+fn build_lod_adt_tree(
+    mut root: crate::utils::tree::TreeNode,
+    adt: &wow_adt::LodAdt,
+    no_metadata: bool,
+) -> crate::utils::tree::TreeNode {
+    use crate::utils::tree::{NodeType, TreeNode};
+
+    root = root.add_child(TreeNode::new(
+        format!("MLVH ({} heights)", adt.heights.len()),
+        NodeType::Data,
+    ));
+    root = root.add_child(TreeNode::new(
+        format!("MLVI ({} triangles)", adt.indices.len() / 3),
+        NodeType::Data,
+    ));
+
+    let mut levels_node = TreeNode::new(
+        format!("MLLL ({} levels)", adt.levels.len()),
+        NodeType::Directory,
+    );
+    for (i, level) in adt.levels.iter().enumerate() {
+        let mut level_node = TreeNode::new(format!("Level {i}"), NodeType::Data);
+        if !no_metadata {
+            level_node = level_node
+                .with_metadata("heights", &level.height_count.to_string())
+                .with_metadata("triangles", &(level.index_count / 3).to_string());
+        }
+        levels_node = levels_node.add_child(level_node);
+    }
+    root = root.add_child(levels_node);
+
+    root = root.add_child(TreeNode::new(
+        format!("MLND ({} quadtree nodes)", adt.nodes.len()),
+        NodeType::Data,
+    ));
+    for (id, data) in &adt.other_chunks {
+        let mut node = TreeNode::new(id.as_str(), NodeType::Chunk);
+        if !no_metadata {
+            node = node.with_metadata("size", &format!("{} bytes", data.len()));
+        }
+        root = root.add_child(node);
+    }
+    root
+}
+
 fn build_obj_adt_tree(
     mut root: crate::utils::tree::TreeNode,
     adt: &wow_adt::api::Obj0Adt,
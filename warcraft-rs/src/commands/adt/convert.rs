@@ -0,0 +1,228 @@
+//! Batch conversion of every tile of a map directory
+
+use anyhow::{Context, Result};
+use prettytable::{Cell, Row, Table, format};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+use wow_adt::{AdtSet, AdtVersion, BuiltAdt};
+
+use super::format_version;
+
+/// Failures listed in the summary
+const SUMMARY_FAILURES: usize = 10;
+
+pub struct MapConvertOptions {
+    /// Directory holding `<Map>_<x>_<y>.adt` tiles
+    pub map_dir: PathBuf,
+    pub output_dir: PathBuf,
+    /// Only convert tiles of this source version
+    pub from: Option<AdtVersion>,
+    pub to: AdtVersion,
+    pub jobs: Option<usize>,
+}
+
+/// Result of converting one tile
+enum TileOutcome {
+    Converted { source: AdtVersion, size: u64 },
+    Skipped { source: AdtVersion },
+    Failed(String),
+}
+
+/// `Azeroth_32_48.adt` -> `(32, 48)` for the root file of a tile
+fn parse_tile_name(map: &str, file_name: &str) -> Option<(usize, usize)> {
+    let lower = file_name.to_ascii_lowercase();
+    let rest = lower
+        .strip_prefix(&format!("{}_", map.to_ascii_lowercase()))?
+        .strip_suffix(".adt")?;
+    let (x, y) = rest.split_once('_')?;
+    let (x, y) = (x.parse().ok()?, y.parse().ok()?);
+    (x < 64 && y < 64).then_some((x, y))
+}
+
+/// Root ADT files of a map directory, sorted by tile
+pub(super) fn map_tiles(map_dir: &Path) -> Result<Vec<PathBuf>> {
+    let map = map_dir
+        .file_name()
+        .and_then(|n| n.to_str())
+        .with_context(|| format!("Invalid map directory {}", map_dir.display()))?;
+    let entries = fs::read_dir(map_dir)
+        .with_context(|| format!("Failed to read map directory {}", map_dir.display()))?;
+
+    let mut tiles: Vec<((usize, usize), PathBuf)> = entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let tile = parse_tile_name(map, e.file_name().to_str()?)?;
+            Some((tile, e.path()))
+        })
+        .collect();
+    tiles.sort();
+    Ok(tiles.into_iter().map(|(_, path)| path).collect())
+}
+
+/// Load a tile together with its split files and write it for the target version
+fn convert_tile(path: &Path, output: &Path, options: &MapConvertOptions) -> TileOutcome {
+    let set = match AdtSet::load_from_path(path) {
+        Ok(set) => set,
+        Err(e) => return TileOutcome::Failed(format!("Failed to parse: {e}")),
+    };
+    let source = set.version();
+    if options.from.is_some_and(|from| from != source) {
+        return TileOutcome::Skipped { source };
+    }
+
+    let root = match set.merge() {
+        Ok(root) => root,
+        Err(e) => return TileOutcome::Failed(format!("Failed to merge split files: {e}")),
+    };
+    let built = BuiltAdt::from_root_adt(root, Some(options.to));
+    if let Err(e) = built.write_to_file(output) {
+        return TileOutcome::Failed(format!("Failed to write {}: {e}", output.display()));
+    }
+    let size = fs::metadata(output).map(|m| m.len()).unwrap_or(0);
+    TileOutcome::Converted { source, size }
+}
+
+pub fn convert_map(options: MapConvertOptions) -> Result<()> {
+    let tiles = map_tiles(&options.map_dir)?;
+    if tiles.is_empty() {
+        anyhow::bail!("No ADT tiles found in {}", options.map_dir.display());
+    }
+    fs::create_dir_all(&options.output_dir)
+        .with_context(|| format!("Failed to create {}", options.output_dir.display()))?;
+
+    println!(
+        "Converting {} tiles of {} to {}",
+        tiles.len(),
+        options.map_dir.display(),
+        options.to.expansion_name()
+    );
+
+    let start = Instant::now();
+    let threads = options
+        .jobs
+        .unwrap_or_else(|| {
+            std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(4)
+        })
+        .clamp(1, tiles.len());
+    let next = AtomicUsize::new(0);
+    let outcomes: Mutex<Vec<(usize, TileOutcome)>> = Mutex::new(Vec::with_capacity(tiles.len()));
+    let pb = crate::utils::progress::create_progress_bar(tiles.len() as u64, "Converting tiles");
+
+    std::thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| {
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(path) = tiles.get(index) else {
+                        break;
+                    };
+                    let output = options
+                        .output_dir
+                        .join(path.file_name().unwrap_or_default());
+                    let outcome = convert_tile(path, &output, &options);
+                    outcomes
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .push((index, outcome));
+                    pb.inc(1);
+                }
+            });
+        }
+    });
+    pb.finish_and_clear();
+
+    let mut outcomes = outcomes.into_inner().unwrap_or_else(|e| e.into_inner());
+    outcomes.sort_by_key(|(index, _)| *index);
+
+    // Tiles per source version and outcome
+    let mut by_source: BTreeMap<&'static str, (usize, usize)> = BTreeMap::new();
+    let mut failures = Vec::new();
+    let mut converted = 0;
+    let mut skipped = 0;
+    let mut bytes = 0;
+    for (index, outcome) in &outcomes {
+        match outcome {
+            TileOutcome::Converted { source, size } => {
+                by_source.entry(format_version(source)).or_default().0 += 1;
+                converted += 1;
+                bytes += size;
+            }
+            TileOutcome::Skipped { source } => {
+                by_source.entry(format_version(source)).or_default().1 += 1;
+                skipped += 1;
+            }
+            TileOutcome::Failed(error) => failures.push((&tiles[*index], error)),
+        }
+    }
+
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_BOX_CHARS);
+    table.set_titles(Row::new(vec![
+        Cell::new("Source Version"),
+        Cell::new("Converted"),
+        Cell::new("Skipped"),
+    ]));
+    for (version, (done, skip)) in &by_source {
+        table.add_row(Row::new(vec![
+            Cell::new(version),
+            Cell::new(&done.to_string()),
+            Cell::new(&skip.to_string()),
+        ]));
+    }
+    if !failures.is_empty() {
+        table.add_row(Row::new(vec![
+            Cell::new("Failed"),
+            Cell::new(&failures.len().to_string()),
+            Cell::new("-"),
+        ]));
+    }
+    table.printstd();
+
+    println!(
+        "Converted {converted} tiles ({}) in {:.1?} using {threads} worker(s)",
+        crate::utils::format::format_bytes(bytes),
+        start.elapsed()
+    );
+    if skipped > 0 {
+        println!("Skipped {skipped} tiles that are not the --from version");
+    }
+    println!("Output: {}", options.output_dir.display());
+
+    if failures.is_empty() {
+        return Ok(());
+    }
+    println!("\nFailed tiles:");
+    for (path, error) in failures.iter().take(SUMMARY_FAILURES) {
+        println!("  {}: {error}", path.display());
+    }
+    if failures.len() > SUMMARY_FAILURES {
+        println!("  ... and {} more", failures.len() - SUMMARY_FAILURES);
+    }
+    anyhow::bail!(
+        "{} of {} tiles failed to convert",
+        failures.len(),
+        tiles.len()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tile_name_skips_split_files() {
+        assert_eq!(
+            parse_tile_name("Azeroth", "Azeroth_32_48.adt"),
+            Some((32, 48))
+        );
+        assert_eq!(parse_tile_name("Azeroth", "azeroth_1_2.ADT"), Some((1, 2)));
+        assert_eq!(parse_tile_name("Azeroth", "Azeroth_32_48_tex0.adt"), None);
+        assert_eq!(parse_tile_name("Azeroth", "Kalimdor_32_48.adt"), None);
+    }
+}
@@ -0,0 +1,261 @@
+//! Export ADT terrain to OBJ or glTF
+//!
+//! Every MCNK becomes its 145 height vertices and four triangles per quad
+//! around the inner vertex, with holes left open. Chunks with the same
+//! texture layers share a material; layer textures repeat once per quad as
+//! they do in the client. Neither output format can blend the alpha-mapped
+//! layers, so the writers show the base layer only.
+//!
+//! Vertices stay in world coordinates, so exported tiles line up.
+
+use anyhow::{Context, Result};
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use wow_adt::{McnkChunk, ParsedAdt, RootAdt, parse_adt};
+
+use crate::utils::assets::AssetSource;
+use crate::utils::scene::{
+    Instance, Mesh, MeshPart, ResolvedMaterial, Scene, TextureMode, TextureWriter, write_gltf,
+    write_obj,
+};
+
+/// Side of a quad: a chunk is 100/3 yards over 8 quads
+const UNIT_SIZE: f32 = 100.0 / 3.0 / 8.0;
+
+/// Output format of `adt export`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum AdtExportFormat {
+    /// Wavefront OBJ with an MTL material library
+    Obj,
+    /// glTF 2.0
+    Gltf,
+}
+
+pub struct ExportOptions {
+    pub format: AdtExportFormat,
+    pub textures: TextureMode,
+    /// MPQ archive, client installation or extracted tree for textures
+    pub source: Option<PathBuf>,
+}
+
+#[derive(Debug, Default)]
+pub struct ExportStats {
+    pub chunks: usize,
+    pub triangles: usize,
+    pub materials: usize,
+    pub textures_written: usize,
+    pub textures_missing: Vec<String>,
+    pub output: PathBuf,
+}
+
+/// Resolve the texture layers of a chunk; terrain is always opaque
+fn chunk_material(root: &RootAdt, chunk: &McnkChunk) -> ResolvedMaterial {
+    let textures = chunk
+        .layers
+        .iter()
+        .flat_map(|mcly| &mcly.layers)
+        .filter_map(|layer| root.textures.get(layer.texture_id as usize).cloned())
+        .collect();
+    ResolvedMaterial {
+        textures,
+        ..Default::default()
+    }
+}
+
+/// Whether quad (`x`, `y`) of a chunk is a hole
+fn is_hole(chunk: &McnkChunk, x: usize, y: usize) -> bool {
+    if chunk.header.flags.high_res_holes() {
+        chunk.header.is_hole_high_res(x, y)
+    } else {
+        chunk.header.is_hole_low_res(x / 2, y / 2)
+    }
+}
+
+/// Average the normals of the triangles around each vertex
+fn smooth_normals(positions: &[[f32; 3]], parts: &[MeshPart]) -> Vec<[f32; 3]> {
+    let mut normals = vec![[0.0f32; 3]; positions.len()];
+    for tri in parts.iter().flat_map(|p| p.indices.chunks_exact(3)) {
+        let [a, b, c] = [0, 1, 2].map(|i| positions[tri[i] as usize]);
+        let (u, v) = (
+            [0, 1, 2].map(|i| b[i] - a[i]),
+            [0, 1, 2].map(|i| c[i] - a[i]),
+        );
+        let face = [
+            u[1] * v[2] - u[2] * v[1],
+            u[2] * v[0] - u[0] * v[2],
+            u[0] * v[1] - u[1] * v[0],
+        ];
+        for &index in tri {
+            let normal = &mut normals[index as usize];
+            for axis in 0..3 {
+                normal[axis] += face[axis];
+            }
+        }
+    }
+    for normal in &mut normals {
+        let length = normal.iter().map(|v| v * v).sum::<f32>().sqrt();
+        *normal = if length > f32::EPSILON {
+            normal.map(|v| v / length)
+        } else {
+            [0.0, 0.0, 1.0]
+        };
+    }
+    normals
+}
+
+/// Build the terrain mesh of a root ADT
+fn terrain_mesh(root: &RootAdt, name: &str) -> Mesh {
+    let mut mesh = Mesh {
+        name: name.to_string(),
+        ..Default::default()
+    };
+
+    for chunk in &root.mcnk_chunks {
+        let Some(mcvt) = &chunk.heights else {
+            log::warn!(
+                "Chunk {},{} has no heights; skipping it",
+                chunk.header.index_x,
+                chunk.header.index_y
+            );
+            continue;
+        };
+        let [x, y, base] = chunk.header.world_position();
+        let first = mesh.positions.len() as u32;
+
+        // 9x9 outer vertices, then the 8x8 quad centers
+        for row in 0..=8 {
+            for col in 0..=8 {
+                let height = mcvt.get_outer_height(col, row).unwrap_or(0.0);
+                let (r, c) = (row as f32, col as f32);
+                mesh.positions
+                    .push([x - r * UNIT_SIZE, y - c * UNIT_SIZE, base + height]);
+                mesh.uvs.push([c, r]);
+            }
+        }
+        for row in 0..8 {
+            for col in 0..8 {
+                let height = mcvt.get_inner_height(col, row).unwrap_or(0.0);
+                let (r, c) = (row as f32 + 0.5, col as f32 + 0.5);
+                mesh.positions
+                    .push([x - r * UNIT_SIZE, y - c * UNIT_SIZE, base + height]);
+                mesh.uvs.push([c, r]);
+            }
+        }
+
+        let outer = |row: usize, col: usize| first + (row * 9 + col) as u32;
+        let mut indices = Vec::with_capacity(8 * 8 * 12);
+        for row in 0..8 {
+            for col in 0..8 {
+                if is_hole(chunk, col, row) {
+                    continue;
+                }
+                let center = first + 81 + (row * 8 + col) as u32;
+                let corners = [
+                    outer(row, col),
+                    outer(row, col + 1),
+                    outer(row + 1, col + 1),
+                    outer(row + 1, col),
+                ];
+                // Counter-clockwise seen from above
+                for i in 0..4 {
+                    indices.extend([center, corners[(i + 1) % 4], corners[i]]);
+                }
+            }
+        }
+
+        let material = chunk_material(root, chunk);
+        match mesh.parts.iter_mut().find(|p| p.material == material) {
+            Some(part) => part.indices.extend(indices),
+            None => mesh.parts.push(MeshPart { indices, material }),
+        }
+    }
+
+    mesh.normals = smooth_normals(&mesh.positions, &mesh.parts);
+    mesh
+}
+
+fn load_root(path: &Path) -> Result<Box<RootAdt>> {
+    let file =
+        File::open(path).with_context(|| format!("Failed to open ADT file: {}", path.display()))?;
+    let adt = parse_adt(&mut BufReader::new(file))
+        .with_context(|| format!("Failed to parse ADT file: {}", path.display()))?;
+    let ParsedAdt::Root(root) = adt else {
+        anyhow::bail!(
+            "{} is a split file; terrain is exported from the root ADT",
+            path.display()
+        );
+    };
+    Ok(root)
+}
+
+/// Export the terrain of a root ADT into `output_dir`
+pub fn export(input: &Path, output_dir: &Path, options: ExportOptions) -> Result<ExportStats> {
+    let root = load_root(input)?;
+    let stem = input
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .context("Invalid ADT file name")?
+        .to_string();
+
+    let mesh = terrain_mesh(&root, &stem);
+    if mesh.parts.is_empty() {
+        anyhow::bail!("{} has no chunks with heights", input.display());
+    }
+    let mut stats = ExportStats {
+        chunks: root
+            .mcnk_chunks
+            .iter()
+            .filter(|c| c.heights.is_some())
+            .count(),
+        triangles: mesh.triangle_count(),
+        materials: mesh.parts.len(),
+        ..Default::default()
+    };
+    let scene = Scene {
+        name: stem.clone(),
+        meshes: vec![mesh],
+        instances: vec![Instance::identity(&stem, 0)],
+        ..Default::default()
+    };
+
+    let textures = match (options.textures, options.format) {
+        (TextureMode::Embed, AdtExportFormat::Obj) => {
+            log::warn!("OBJ cannot embed textures; extracting them instead");
+            TextureMode::Extract
+        }
+        (mode, _) => mode,
+    };
+    let source = match options.source.as_deref() {
+        Some(source) if textures != TextureMode::None => Some(AssetSource::open(source)?),
+        _ => None,
+    };
+    if textures != TextureMode::None && source.is_none() {
+        anyhow::bail!("--textures {textures:?} requires --source to locate the BLP files");
+    }
+
+    fs::create_dir_all(output_dir)
+        .with_context(|| format!("Failed to create {}", output_dir.display()))?;
+    let mut writer = TextureWriter::new(textures, source, output_dir);
+
+    stats.output = match options.format {
+        AdtExportFormat::Obj => {
+            let path = output_dir.join(format!("{stem}.obj"));
+            write_obj(&scene, &path, &mut writer)?;
+            path
+        }
+        AdtExportFormat::Gltf => {
+            let extension = if textures == TextureMode::Embed {
+                "glb"
+            } else {
+                "gltf"
+            };
+            let path = output_dir.join(format!("{stem}.{extension}"));
+            write_gltf(&scene, &path, &mut writer)?;
+            path
+        }
+    };
+    stats.textures_written = writer.written;
+    stats.textures_missing = writer.missing;
+    Ok(stats)
+}
@@ -0,0 +1,180 @@
+//! Ground effect listing, checking and assignment
+//!
+//! Ground effect ids are rows of `GroundEffectTexture.dbc`; when the table is
+//! given, ids missing from it are reported, and refused as assignments, since
+//! the client shows no clutter for them.
+
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use wow_adt::{BuiltAdt, ParsedAdt, RootAdt, parse_adt};
+use wow_cdbc::{DbcParser, Value};
+
+use crate::utils::{add_table_row, create_table};
+
+pub struct GroundEffectOptions<'a> {
+    /// `GroundEffectTexture.dbc` to check ids against
+    pub dbc: Option<&'a Path>,
+    /// ADTs to learn texture effects from
+    pub learn_from: &'a [PathBuf],
+    /// Explicit texture effects, applied after the learned ones
+    pub set: &'a [(String, u32)],
+    /// Replace effects layers already have
+    pub overwrite: bool,
+    pub output: Option<&'a Path>,
+}
+
+/// Parse `TEXTURE=EFFECT_ID`
+pub fn parse_assignment(s: &str) -> Result<(String, u32), String> {
+    let (texture, id) = s
+        .rsplit_once('=')
+        .ok_or_else(|| format!("expected TEXTURE=EFFECT_ID, got '{s}'"))?;
+    let id = id
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid effect id '{id}'"))?;
+    Ok((texture.trim().to_string(), id))
+}
+
+/// Doodad ids of every `GroundEffectTexture.dbc` row, by effect id
+///
+/// All versions start a row with the id followed by four
+/// `GroundEffectDoodad.dbc` ids.
+fn load_effect_table(path: &Path) -> Result<HashMap<u32, Vec<u32>>> {
+    let data = std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let records = DbcParser::parse_bytes(&data)?.parse_records()?;
+    let uint = |record: &wow_cdbc::Record, field| match record.get_value(field) {
+        Some(Value::UInt32(value)) => Some(*value),
+        Some(Value::Int32(value)) => Some(*value as u32),
+        _ => None,
+    };
+    Ok(records
+        .records()
+        .iter()
+        .filter_map(|record| {
+            let doodads = (1..=4)
+                .filter_map(|field| uint(record, field))
+                .filter(|&id| id != 0)
+                .collect();
+            Some((uint(record, 0)?, doodads))
+        })
+        .collect())
+}
+
+fn load_root(path: &Path) -> Result<Box<RootAdt>> {
+    let file =
+        File::open(path).with_context(|| format!("Failed to open ADT file: {}", path.display()))?;
+    let adt = parse_adt(&mut BufReader::new(file))
+        .with_context(|| format!("Failed to parse ADT file: {}", path.display()))?;
+    let ParsedAdt::Root(root) = adt else {
+        anyhow::bail!(
+            "{} is a split file; ground effects are edited in the root ADT",
+            path.display()
+        );
+    };
+    Ok(root)
+}
+
+pub fn execute(file: &Path, options: &GroundEffectOptions) -> Result<()> {
+    let mut root = load_root(file)?;
+    let table = options.dbc.map(load_effect_table).transpose()?;
+
+    let mut assignments = BTreeMap::new();
+    for source in options.learn_from {
+        assignments.extend(load_root(source)?.texture_ground_effects());
+    }
+    assignments.extend(options.set.iter().cloned());
+    if let Some(table) = &table {
+        let unknown: Vec<_> = assignments
+            .iter()
+            .filter(|&(_, id)| *id != 0 && !table.contains_key(id))
+            .map(|(texture, id)| format!("{texture}={id}"))
+            .collect();
+        if !unknown.is_empty() {
+            anyhow::bail!(
+                "Effect ids missing from GroundEffectTexture.dbc: {}",
+                unknown.join(", ")
+            );
+        }
+    }
+    let changed = root.apply_ground_effects(&assignments, options.overwrite);
+
+    // Effect ids and layer counts per texture
+    let mut usage: BTreeMap<String, (BTreeSet<u32>, usize)> = BTreeMap::new();
+    for layer in root.ground_effects() {
+        let texture = layer
+            .texture
+            .unwrap_or_else(|| "<invalid texture>".to_string());
+        let entry = usage.entry(texture).or_default();
+        entry.0.insert(layer.effect_id);
+        entry.1 += 1;
+    }
+
+    println!("Ground Effects: {}", file.display());
+    let mut headers = vec!["Texture", "Layers", "Effect IDs"];
+    if table.is_some() {
+        headers.push("Doodads");
+    }
+    let mut output = create_table(headers);
+    let mut missing: BTreeSet<u32> = BTreeSet::new();
+    for (texture, (ids, layers)) in &usage {
+        let mut row = vec![
+            texture.clone(),
+            layers.to_string(),
+            ids.iter()
+                .map(|id| id.to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+        ];
+        if let Some(table) = &table {
+            let doodads: usize = ids
+                .iter()
+                .filter_map(|id| table.get(id))
+                .map(|doodads| doodads.len())
+                .sum();
+            missing.extend(
+                ids.iter()
+                    .filter(|&&id| id != 0 && !table.contains_key(&id)),
+            );
+            row.push(doodads.to_string());
+        }
+        add_table_row(&mut output, row);
+    }
+    println!("{output}");
+
+    if !missing.is_empty() {
+        let ids: Vec<String> = missing.iter().map(|id| id.to_string()).collect();
+        println!(
+            "⚠ Effect ids missing from GroundEffectTexture.dbc: {}",
+            ids.join(", ")
+        );
+    }
+    if !assignments.is_empty() {
+        println!("Updated {changed} layers");
+    }
+
+    if let Some(output) = options.output {
+        BuiltAdt::from_root_adt(*root, None)
+            .write_to_file(output)
+            .with_context(|| format!("Failed to write {}", output.display()))?;
+        println!("✓ Saved {}", output.display());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_assignment() {
+        assert_eq!(
+            parse_assignment("tileset/elwynn/elwynngrass.blp=12"),
+            Ok(("tileset/elwynn/elwynngrass.blp".to_string(), 12))
+        );
+        assert!(parse_assignment("grass.blp").is_err());
+        assert!(parse_assignment("grass.blp=x").is_err());
+    }
+}
@@ -0,0 +1,118 @@
+//! Ocean fill of single tiles and whole maps
+
+use anyhow::{Context, Result};
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use wow_adt::{AdtVersion, BuiltAdt, OceanFill, OceanFillReport, ParsedAdt, parse_adt};
+
+use super::convert::map_tiles;
+
+/// Fill one tile, writing it to `output` when anything changed
+fn fill_tile(path: &Path, output: &Path, fill: &OceanFill) -> Result<OceanFillReport> {
+    let file =
+        File::open(path).with_context(|| format!("Failed to open ADT file: {}", path.display()))?;
+    let adt = parse_adt(&mut BufReader::new(file))
+        .with_context(|| format!("Failed to parse ADT file: {}", path.display()))?;
+    let ParsedAdt::Root(mut root) = adt else {
+        anyhow::bail!(
+            "{} is a split file; water is added to the root ADT",
+            path.display()
+        );
+    };
+    if root.version < AdtVersion::WotLK {
+        anyhow::bail!(
+            "{} is a {} ADT; MH2O water needs WotLK or later",
+            path.display(),
+            root.version.expansion_name()
+        );
+    }
+
+    let report = root.fill_ocean(fill);
+    if report.chunks > 0 || path != output {
+        BuiltAdt::from_root_adt(*root, None)
+            .write_to_file(output)
+            .with_context(|| format!("Failed to write {}", output.display()))?;
+    }
+    Ok(report)
+}
+
+fn print_report(name: &str, report: &OceanFillReport) {
+    print!(
+        "{name}: {} tiles in {} chunks flooded",
+        report.tiles, report.chunks
+    );
+    if report.skipped > 0 {
+        print!(", {} chunks with liquid kept", report.skipped);
+    }
+    println!();
+}
+
+/// Fill the root ADT or map directory at `path`
+///
+/// Files are updated in place unless `output` is given, which is a file for
+/// a single ADT and a directory for a map.
+pub fn execute(path: &Path, output: Option<&Path>, fill: &OceanFill) -> Result<()> {
+    if !path.is_dir() {
+        let output = output.unwrap_or(path);
+        let report = fill_tile(path, output, fill)?;
+        print_report(&path.display().to_string(), &report);
+        if report.chunks > 0 || output != path {
+            println!("Wrote {}", output.display());
+        }
+        return Ok(());
+    }
+
+    let tiles = map_tiles(path)?;
+    if tiles.is_empty() {
+        anyhow::bail!("No ADT tiles found in {}", path.display());
+    }
+    let output_dir = output.map(PathBuf::from);
+    if let Some(dir) = &output_dir {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+
+    let mut total = OceanFillReport::default();
+    let mut flooded = 0;
+    let mut failed = 0;
+    for tile in &tiles {
+        let name = tile.file_name().unwrap_or_default();
+        let target = output_dir
+            .as_ref()
+            .map_or_else(|| tile.clone(), |dir| dir.join(name));
+        match fill_tile(tile, &target, fill) {
+            Ok(report) => {
+                if report.chunks > 0 || report.skipped > 0 {
+                    print_report(&name.to_string_lossy(), &report);
+                }
+                if report.chunks > 0 {
+                    flooded += 1;
+                }
+                total.chunks += report.chunks;
+                total.tiles += report.tiles;
+                total.skipped += report.skipped;
+            }
+            Err(e) => {
+                println!("✗ {e:#}");
+                failed += 1;
+            }
+        }
+    }
+
+    println!(
+        "Flooded {} tiles in {} chunks across {flooded} of {} ADTs",
+        total.tiles,
+        total.chunks,
+        tiles.len()
+    );
+    if total.skipped > 0 {
+        println!(
+            "Kept {} chunks that already have liquid (use --replace to overwrite)",
+            total.skipped
+        );
+    }
+    if failed > 0 {
+        anyhow::bail!("{failed} ADTs could not be filled");
+    }
+    Ok(())
+}
@@ -0,0 +1,213 @@
+//! Render ADT terrain data as images for quick inspection
+
+use anyhow::{Context, Result};
+use image::{GrayImage, ImageBuffer, Luma, Rgb, RgbImage};
+use std::path::Path;
+use wow_adt::{CombinedAlphaMap, McnkChunk, RootAdt};
+
+/// Chunks per tile side
+const CHUNKS: u32 = 16;
+/// Terrain cells per chunk side
+const CELLS: u32 = 8;
+/// Alpha map texels per chunk side
+const ALPHA_SIZE: u32 = 64;
+
+/// Minimum and maximum absolute terrain height in the tile
+pub fn height_range(root: &RootAdt) -> Option<(f32, f32)> {
+    root.mcnk_chunks
+        .iter()
+        .filter_map(|chunk| {
+            let heights = chunk.heights.as_ref()?;
+            let base = chunk.header.position[2];
+            Some((heights.min_height()? + base, heights.max_height()? + base))
+        })
+        .reduce(|(lo, hi), (min, max)| (lo.min(min), hi.max(max)))
+}
+
+/// 129x129 16-bit heightmap of the outer vertex grid, normalized to the tile's range
+pub fn render_heightmap(root: &RootAdt) -> ImageBuffer<Luma<u16>, Vec<u16>> {
+    let size = CHUNKS * CELLS + 1;
+    let mut image = ImageBuffer::new(size, size);
+    let Some((min, max)) = height_range(root) else {
+        return image;
+    };
+    let range = (max - min).max(f32::EPSILON);
+
+    for chunk in &root.mcnk_chunks {
+        let Some(heights) = &chunk.heights else {
+            continue;
+        };
+        let base = chunk.header.position[2];
+        let (cx, cy) = chunk_origin(chunk, CELLS);
+        for y in 0..=CELLS {
+            for x in 0..=CELLS {
+                let Some(h) = heights.get_outer_height(x as usize, y as usize) else {
+                    continue;
+                };
+                let value = ((h + base - min) / range * f32::from(u16::MAX)).round() as u16;
+                image.put_pixel(cx + x, cy + y, Luma([value]));
+            }
+        }
+    }
+    image
+}
+
+/// 1024x1024 coverage of texture layers 1-3 in the red, green and blue channels
+///
+/// Layer 0 is the opaque base texture and shows through where all channels are black.
+pub fn render_layers(root: &RootAdt) -> RgbImage {
+    let size = CHUNKS * ALPHA_SIZE;
+    let mut image = RgbImage::new(size, size);
+
+    for chunk in &root.mcnk_chunks {
+        let alpha = CombinedAlphaMap::new(
+            chunk,
+            has_big_alpha(chunk),
+            !chunk.header.flags.do_not_fix_alpha_map(),
+        );
+        let (cx, cy) = chunk_origin(chunk, ALPHA_SIZE);
+        for (i, texel) in alpha.as_slice().chunks_exact(4).enumerate() {
+            let x = i as u32 % ALPHA_SIZE;
+            let y = i as u32 / ALPHA_SIZE;
+            image.put_pixel(cx + x, cy + y, Rgb([texel[0], texel[1], texel[2]]));
+        }
+    }
+    image
+}
+
+/// 128x128 hole mask, one pixel per terrain cell; holes are black
+pub fn render_holes(root: &RootAdt) -> GrayImage {
+    let size = CHUNKS * CELLS;
+    let mut image = GrayImage::from_pixel(size, size, Luma([255]));
+
+    for chunk in &root.mcnk_chunks {
+        let (cx, cy) = chunk_origin(chunk, CELLS);
+        for y in 0..CELLS {
+            for x in 0..CELLS {
+                if is_hole(chunk, x, y) {
+                    image.put_pixel(cx + x, cy + y, Luma([0]));
+                }
+            }
+        }
+    }
+    image
+}
+
+/// 128x128 liquid extents, one pixel per terrain cell, colored by liquid kind
+pub fn render_water(root: &RootAdt) -> RgbImage {
+    let size = CHUNKS * CELLS;
+    let mut image = RgbImage::new(size, size);
+
+    for (index, chunk) in root.mcnk_chunks.iter().enumerate() {
+        let (cx, cy) = chunk_origin(chunk, CELLS);
+
+        // MH2O (WotLK+) describes liquid per cell
+        if let Some(entry) = root.water_data.as_ref().and_then(|w| w.entries.get(index)) {
+            for (i, instance) in entry.instances.iter().enumerate() {
+                let color = liquid_color(instance.liquid_type);
+                let exists = entry.exists_bitmaps.get(i).copied().flatten();
+                for y in 0..u32::from(instance.height) {
+                    for x in 0..u32::from(instance.width) {
+                        let bit = y * u32::from(instance.width) + x;
+                        if exists.is_some_and(|bits| bits & (1 << bit) == 0) {
+                            continue;
+                        }
+                        let px = u32::from(instance.x_offset) + x;
+                        let py = u32::from(instance.y_offset) + y;
+                        if px < CELLS && py < CELLS {
+                            image.put_pixel(cx + px, cy + py, color);
+                        }
+                    }
+                }
+            }
+        }
+
+        // Pre-WotLK tiles flag the liquid kind on the whole chunk (MCLQ)
+        if chunk.liquid.is_some() {
+            let flags = &chunk.header.flags;
+            let kind = if flags.has_ocean() {
+                Some(2)
+            } else if flags.has_magma() {
+                Some(3)
+            } else if flags.has_slime() {
+                Some(4)
+            } else if flags.has_river() {
+                Some(1)
+            } else {
+                None
+            };
+            if let Some(kind) = kind {
+                for y in 0..CELLS {
+                    for x in 0..CELLS {
+                        image.put_pixel(cx + x, cy + y, liquid_color(kind));
+                    }
+                }
+            }
+        }
+    }
+    image
+}
+
+/// Save an image as PNG
+pub fn write_png<P, C>(image: &ImageBuffer<P, C>, path: &Path) -> Result<()>
+where
+    P: image::PixelWithColorType,
+    [P::Subpixel]: image::EncodableLayout,
+    C: std::ops::Deref<Target = [P::Subpixel]>,
+{
+    image
+        .save_with_format(path, image::ImageFormat::Png)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn chunk_origin(chunk: &McnkChunk, cell_size: u32) -> (u32, u32) {
+    (
+        chunk.header.index_x.min(CHUNKS - 1) * cell_size,
+        chunk.header.index_y.min(CHUNKS - 1) * cell_size,
+    )
+}
+
+/// Whether terrain cell (x, y) of an 8x8 chunk is cut out
+fn is_hole(chunk: &McnkChunk, x: u32, y: u32) -> bool {
+    match chunk.header.holes_high_res() {
+        Some(holes) => holes & (1 << (y * CELLS + x)) != 0,
+        // The low resolution mask covers the chunk with 4x4 bits of 2x2 cells each
+        None => chunk.header.holes_low_res & (1 << ((y / 2) * 4 + x / 2)) != 0,
+    }
+}
+
+/// Uncompressed alpha maps are 4096 bytes in 8-bit mode and 2048 bytes in 4-bit mode
+fn has_big_alpha(chunk: &McnkChunk) -> bool {
+    let (Some(layers), Some(alpha)) = (&chunk.layers, &chunk.alpha) else {
+        return false;
+    };
+    let uncompressed = layers
+        .layers
+        .iter()
+        .skip(1)
+        .filter(|l| l.flags.use_alpha_map() && !l.flags.alpha_map_compressed())
+        .count();
+    uncompressed > 0 && alpha.data.len() >= uncompressed * 4096
+}
+
+/// LiquidType.dbc entries repeat water, ocean, magma and slime in that order
+fn liquid_color(liquid_type: u16) -> Rgb<u8> {
+    match liquid_type.saturating_sub(1) % 4 {
+        0 => Rgb([64, 128, 255]),
+        1 => Rgb([16, 48, 160]),
+        2 => Rgb([255, 96, 0]),
+        _ => Rgb([64, 200, 64]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_liquid_color_cycles_by_kind() {
+        assert_eq!(liquid_color(1), liquid_color(5));
+        assert_eq!(liquid_color(2), liquid_color(14));
+        assert_ne!(liquid_color(1), liquid_color(3));
+    }
+}
@@ -0,0 +1,372 @@
+//! One-shot extraction of a game asset and everything it references
+//!
+//! The asset is resolved through the client's patch chain, so the version the
+//! game would load is the one written; a single MPQ archive works as well.
+//! Parts an asset cannot be used without (M2 skin profiles, WMO group files,
+//! ADT split files) always come along; with `--with-dependencies` external
+//! animations, textures and doodads are followed as well. Files keep their
+//! client paths below the output directory, and `--convert` turns the result
+//! into formats common tools read.
+//!
+//! `asset-deps` walks the same references without extracting anything and
+//! reports the full dependency set with sizes and the files that are missing.
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use wow_mpq::path::path_key;
+use wow_mpq::{Archive, Storage};
+
+use crate::commands::mpq::chain::mount_chain;
+use crate::utils::dependencies::{
+    AssetKind, DependencyGraph, dependencies, is_wmo_group, walk_dependencies,
+};
+use crate::utils::format_bytes;
+
+/// Target format of `extract-asset --convert`
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AssetFormat {
+    /// Models to binary glTF with embedded textures
+    Gltf,
+    /// WMO buildings to Wavefront OBJ with PNG textures
+    Obj,
+    /// BLP textures to PNG
+    Png,
+}
+
+pub struct ExtractAssetParams {
+    /// Client path of the asset, e.g. `Creature\Illidan\Illidan.m2`
+    pub asset: String,
+    pub data_dir: Option<PathBuf>,
+    pub locale: Option<String>,
+    pub output: PathBuf,
+    pub convert: Option<AssetFormat>,
+    pub with_dependencies: bool,
+}
+
+/// Report format of `asset-deps`
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DepsFormat {
+    /// Human readable list
+    Text,
+    /// Machine readable JSON
+    Json,
+}
+
+pub struct AssetDepsParams {
+    /// Client path of the asset
+    pub asset: String,
+    pub data_dir: Option<PathBuf>,
+    pub locale: Option<String>,
+    pub format: DepsFormat,
+}
+
+/// Where a client path lands below the output directory
+fn output_path(output: &Path, name: &str) -> PathBuf {
+    name.split('\\')
+        .filter(|c| !c.is_empty())
+        .fold(output.to_path_buf(), |path, component| path.join(component))
+}
+
+/// Extracted files by client path key
+type Extracted = BTreeMap<String, (String, PathBuf)>;
+
+/// Write the asset and the files it references below the output directory
+fn extract_files(
+    storage: &mut dyn Storage,
+    params: &ExtractAssetParams,
+) -> Result<(Extracted, DependencyGraph)> {
+    let mut extracted = Extracted::new();
+    let graph = walk_dependencies(
+        storage,
+        &params.asset,
+        params.with_dependencies,
+        |file, data| {
+            let path = output_path(&params.output, &file.name);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)
+                    .with_context(|| format!("Failed to create {}", parent.display()))?;
+            }
+            fs::write(&path, data)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            extracted.insert(path_key(&file.name), (file.name.clone(), path));
+            Ok(())
+        },
+    )?;
+    Ok((extracted, graph))
+}
+
+#[cfg(feature = "m2")]
+fn convert_model(path: &Path, output: &Path) -> Result<PathBuf> {
+    use crate::commands::m2::export::{AnimationSelection, ExportOptions, export_gltf};
+
+    let target = path.with_extension("glb");
+    let options = ExportOptions {
+        skin: 0,
+        animations: AnimationSelection::All,
+        animation_fallbacks: Default::default(),
+        textures: Some(output.to_path_buf()),
+        replaceable: wow_m2::ReplaceableTextures::new(),
+        fps: 30,
+        cleanup: false,
+    };
+    export_gltf(path, &target, &options)?;
+    Ok(target)
+}
+
+#[cfg(not(feature = "m2"))]
+fn convert_model(_path: &Path, _output: &Path) -> Result<PathBuf> {
+    anyhow::bail!("Model conversion requires the 'm2' feature to be enabled")
+}
+
+#[cfg(feature = "wmo")]
+fn convert_building(path: &Path, output: &Path, format: AssetFormat) -> Result<PathBuf> {
+    use crate::commands::wmo::export::{ExportOptions, WmoExportFormat, export};
+    use crate::utils::scene::TextureMode;
+
+    let (format, textures) = match format {
+        AssetFormat::Obj => (WmoExportFormat::Obj, TextureMode::Extract),
+        _ => (WmoExportFormat::Gltf, TextureMode::Embed),
+    };
+    let options = ExportOptions {
+        format,
+        doodad_set: 0,
+        textures,
+        source: Some(output.to_path_buf()),
+    };
+    let directory = path.parent().unwrap_or(output);
+    Ok(export(path, directory, options)?.output)
+}
+
+#[cfg(not(feature = "wmo"))]
+fn convert_building(_path: &Path, _output: &Path, _format: AssetFormat) -> Result<PathBuf> {
+    anyhow::bail!("WMO conversion requires the 'wmo' feature to be enabled")
+}
+
+#[cfg(feature = "blp")]
+fn convert_texture(path: &Path) -> Result<PathBuf> {
+    let data = fs::read(path)?;
+    let target = path.with_extension("png");
+    fs::write(&target, crate::utils::assets::blp_to_png(&data)?)?;
+    Ok(target)
+}
+
+#[cfg(not(feature = "blp"))]
+fn convert_texture(_path: &Path) -> Result<PathBuf> {
+    anyhow::bail!("Texture conversion requires the 'blp' feature to be enabled")
+}
+
+/// Convert the extracted files; returns the files written
+fn convert(
+    extracted: &Extracted,
+    params: &ExtractAssetParams,
+    format: AssetFormat,
+) -> Vec<PathBuf> {
+    let mut written = Vec::new();
+    let root = path_key(&params.asset);
+
+    for (key, (name, path)) in extracted {
+        let result = match (AssetKind::of(name), format) {
+            (AssetKind::Texture, AssetFormat::Png) => convert_texture(path),
+            // Doodads and other pulled-in models are embedded in the building
+            (AssetKind::Model, AssetFormat::Gltf) if *key == root => {
+                convert_model(path, &params.output)
+            }
+            (AssetKind::Building, AssetFormat::Gltf | AssetFormat::Obj)
+                if *key == root && !is_wmo_group(name) =>
+            {
+                convert_building(path, &params.output, format)
+            }
+            _ => continue,
+        };
+        match result {
+            Ok(path) => written.push(path),
+            Err(e) => log::warn!("Failed to convert {name}: {e:#}"),
+        }
+    }
+    written
+}
+
+/// Open the game data: an MPQ archive, or an installation mounted in patch
+/// order, falling back to the active profile's data directory and locale
+fn open_game_data(data_dir: Option<&Path>, locale: Option<&str>) -> Result<Box<dyn Storage>> {
+    let data_dir = data_dir
+        .map(Path::to_path_buf)
+        .or_else(crate::utils::config::profile_data_dir)
+        .context("No game data given: pass --data-dir or select a profile with a data_dir")?;
+    if data_dir.is_file() {
+        let archive = Archive::open(&data_dir)
+            .with_context(|| format!("Failed to open archive {}", data_dir.display()))?;
+        return Ok(Box::new(archive));
+    }
+
+    let locale = locale
+        .map(str::to_string)
+        .or_else(|| crate::utils::config::active().and_then(|profile| profile.locale.clone()));
+    let (chain, _) = mount_chain(&data_dir, locale.as_deref(), None)?;
+    Ok(Box::new(chain))
+}
+
+pub fn execute(params: ExtractAssetParams) -> Result<()> {
+    if let Some(format) = params.convert {
+        let kind = AssetKind::of(&params.asset);
+        let supported = match format {
+            AssetFormat::Gltf => matches!(kind, AssetKind::Model | AssetKind::Building),
+            AssetFormat::Obj => kind == AssetKind::Building,
+            AssetFormat::Png => true,
+        };
+        if !supported {
+            anyhow::bail!("--convert {format:?} does not apply to {}", params.asset);
+        }
+    }
+
+    let mut storage = open_game_data(params.data_dir.as_deref(), params.locale.as_deref())?;
+    let (extracted, graph) = extract_files(storage.as_mut(), &params)?;
+
+    println!(
+        "Extracted {} file(s) to {}",
+        extracted.len(),
+        params.output.display()
+    );
+    for (name, _) in extracted.values() {
+        println!("  {name}");
+    }
+    if !graph.missing.is_empty() {
+        println!("Not found in the game data ({}):", graph.missing.len());
+        for missing in &graph.missing {
+            if missing.required {
+                log::warn!("Required file {} is missing", missing.name);
+            }
+            println!("  {}", missing.name);
+        }
+    }
+
+    if let Some(format) = params.convert {
+        let written = convert(&extracted, &params, format);
+        if written.is_empty() {
+            anyhow::bail!("Conversion to {format:?} produced no files");
+        }
+        println!("Converted {} file(s):", written.len());
+        for path in &written {
+            println!("  {}", path.display());
+        }
+    }
+    Ok(())
+}
+
+fn print_deps_text(graph: &DependencyGraph) {
+    for file in &graph.files {
+        let kind = match (&file.referenced_by, file.required) {
+            (None, _) => "root",
+            (Some(_), true) => "required",
+            (Some(_), false) => "optional",
+        };
+        println!("{kind:>8}  {:>10}  {}", format_bytes(file.size), file.name);
+    }
+    println!(
+        "{} file(s), {} in total",
+        graph.files.len(),
+        format_bytes(graph.total_size())
+    );
+
+    if !graph.missing.is_empty() {
+        println!("Not found in the game data ({}):", graph.missing.len());
+        for missing in &graph.missing {
+            let kind = if missing.required {
+                "required"
+            } else {
+                "optional"
+            };
+            println!(
+                "{kind:>8}  {} (referenced by {})",
+                missing.name, missing.referenced_by
+            );
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+fn print_deps_json(asset: &str, graph: &DependencyGraph) -> Result<()> {
+    let files: Vec<_> = graph
+        .files
+        .iter()
+        .map(|file| {
+            serde_json::json!({
+                "name": file.name,
+                "size": file.size,
+                "required": file.required,
+                "referenced_by": file.referenced_by,
+                "references": file.references,
+            })
+        })
+        .collect();
+    let missing: Vec<_> = graph
+        .missing
+        .iter()
+        .map(|missing| {
+            serde_json::json!({
+                "name": missing.name,
+                "required": missing.required,
+                "referenced_by": missing.referenced_by,
+            })
+        })
+        .collect();
+
+    let document = serde_json::json!({
+        "asset": asset,
+        "total_size": graph.total_size(),
+        "files": files,
+        "missing": missing,
+    });
+    println!("{}", serde_json::to_string_pretty(&document)?);
+    Ok(())
+}
+
+#[cfg(not(feature = "serde"))]
+fn print_deps_json(_asset: &str, _graph: &DependencyGraph) -> Result<()> {
+    anyhow::bail!("JSON output requires the 'serde' feature to be enabled");
+}
+
+/// List everything an asset references, with sizes and missing files
+///
+/// Fails when a required part is missing, so packaging scripts can stop
+/// before shipping an incomplete asset.
+pub fn execute_deps(params: AssetDepsParams) -> Result<()> {
+    let mut storage = open_game_data(params.data_dir.as_deref(), params.locale.as_deref())?;
+    let graph = dependencies(storage.as_mut(), &params.asset)?;
+
+    match params.format {
+        DepsFormat::Text => print_deps_text(&graph),
+        DepsFormat::Json => print_deps_json(&params.asset, &graph)?,
+    }
+
+    if graph.is_incomplete() {
+        anyhow::bail!("{} is missing required files", params.asset);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wow_mpq::path::normalize_asset_path;
+
+    #[test]
+    fn test_names_and_paths() {
+        assert_eq!(
+            normalize_asset_path("Creature/Illidan/Illidan.mdx"),
+            "Creature\\Illidan\\Illidan.m2"
+        );
+        assert!(is_wmo_group("World\\wmo\\Inn_000.wmo"));
+        assert!(!is_wmo_group("World\\wmo\\Inn.wmo"));
+        assert_eq!(
+            output_path(Path::new("out"), "Creature\\Illidan\\Illidan.m2"),
+            Path::new("out")
+                .join("Creature")
+                .join("Illidan")
+                .join("Illidan.m2")
+        );
+    }
+}
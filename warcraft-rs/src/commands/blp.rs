@@ -5,14 +5,22 @@ use clap::{Subcommand, ValueEnum};
 use image::{ImageFormat, ImageReader, imageops::FilterType};
 use std::path::{Path, PathBuf};
 use wow_blp::{
+    analysis::{MipmapIssue, check_mipmaps},
     convert::{
-        AlphaBits, Blp2Format, BlpOldFormat, BlpTarget, DxtAlgorithm, blp_to_image, image_to_blp,
+        AlphaBits, Blp2Format, BlpEncoding, BlpOldFormat, BlpTarget, BlpTargetPreset, DxtAlgorithm,
+        blp_to_image, image_to_blp,
     },
     encode::save_blp,
     parser::load_blp,
-    types::BlpContent,
+    types::{BlpContent, BlpImage, MipmapLocator},
 };
 
+use crate::utils::{add_table_row, create_progress_bar, create_table};
+
+mod preview;
+
+use preview::{PreviewMode, render_preview};
+
 #[derive(Subcommand)]
 pub enum BlpCommands {
     /// Display information about a BLP file
@@ -43,6 +51,14 @@ pub enum BlpCommands {
         /// Show all information (equivalent to --mipmaps --compression --size)
         #[arg(long)]
         all: bool,
+
+        /// Render a low-resolution preview in the terminal
+        #[arg(long, value_enum, value_name = "MODE", num_args = 0..=1, default_missing_value = "ansi")]
+        preview: Option<PreviewMode>,
+
+        /// Width of the preview in characters
+        #[arg(long, default_value_t = 48, requires = "preview")]
+        preview_width: u32,
     },
 
     /// Validate BLP file integrity
@@ -50,7 +66,8 @@ pub enum BlpCommands {
         /// Path to the BLP file
         file: PathBuf,
 
-        /// Strict validation mode
+        /// Strict validation mode; mipmap levels that don't match their
+        /// parent level are errors instead of warnings
         #[arg(long)]
         strict: bool,
     },
@@ -58,17 +75,31 @@ pub enum BlpCommands {
     /// Convert BLP files to/from other image formats
     Convert {
         /// Input file path (BLP or other image format)
-        input: PathBuf,
+        #[arg(required_unless_present = "recursive")]
+        input: Option<PathBuf>,
 
         /// Output file path
-        output: PathBuf,
+        #[arg(required_unless_present = "recursive")]
+        output: Option<PathBuf>,
+
+        /// Convert every file matching a glob pattern (e.g. "textures/**/*.blp")
+        #[arg(long, value_name = "PATTERN", conflicts_with_all = ["input", "output"], requires_all = ["out", "output_format"])]
+        recursive: Option<String>,
+
+        /// Output directory for --recursive; the directory structure below the pattern's base is preserved
+        #[arg(long, value_name = "DIR")]
+        out: Option<PathBuf>,
+
+        /// Number of parallel workers for --recursive (requires the 'parallel' feature)
+        #[arg(short, long)]
+        jobs: Option<usize>,
 
         /// Input format (auto-detected from extension if not specified)
         #[arg(short = 'i', long)]
         input_format: Option<InputFormat>,
 
         /// Output format (auto-detected from extension if not specified)
-        #[arg(short = 'o', long)]
+        #[arg(short = 'o', long, visible_alias = "to")]
         output_format: Option<OutputFormat>,
 
         /// BLP version to use when encoding to BLP
@@ -98,6 +129,17 @@ pub enum BlpCommands {
         /// DXT compression quality
         #[arg(long, default_value = "medium")]
         dxt_compression: DxtCompression,
+
+        /// Reuse the version, format, alpha and mipmap count of an existing BLP
+        /// instead of the --blp-* options. With --recursive, a directory of
+        /// originals matched by relative path
+        #[arg(long, value_name = "BLP")]
+        like: Option<PathBuf>,
+
+        /// Choose version, format, alpha and mipmaps for a game client instead
+        /// of the --blp-* options
+        #[arg(long, value_name = "GAME", conflicts_with_all = ["like", "blp_version", "blp_format", "alpha_bits", "no_mipmaps"])]
+        preset: Option<BlpPreset>,
     },
 }
 
@@ -162,6 +204,36 @@ pub enum BlpFormat {
     Dxt5,
 }
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum BlpPreset {
+    /// Warcraft III: BLP1 JPEG
+    #[value(name = "warcraft3")]
+    WarcraftIII,
+    /// World of Warcraft 1.x: BLP2 DXT1/DXT3
+    Classic,
+    /// The Burning Crusade: BLP2 DXT1/DXT5
+    Tbc,
+    /// Wrath of the Lich King: BLP2 DXT1/DXT5
+    Wotlk,
+    /// Cataclysm: BLP2 DXT1/DXT5
+    Cataclysm,
+    /// Mists of Pandaria: BLP2 DXT1/DXT5
+    Mop,
+}
+
+impl From<BlpPreset> for BlpTargetPreset {
+    fn from(value: BlpPreset) -> Self {
+        match value {
+            BlpPreset::WarcraftIII => BlpTargetPreset::WarcraftIII,
+            BlpPreset::Classic => BlpTargetPreset::Classic,
+            BlpPreset::Tbc => BlpTargetPreset::Tbc,
+            BlpPreset::Wotlk => BlpTargetPreset::Wotlk,
+            BlpPreset::Cataclysm => BlpTargetPreset::Cataclysm,
+            BlpPreset::Mop => BlpTargetPreset::Mop,
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
 pub enum MipmapFilter {
     /// Nearest Neighbor
@@ -435,38 +507,190 @@ fn determine_alpha_bits(
     })
 }
 
-fn convert_blp(args: ConvertArgs) -> Result<()> {
+/// File extension written for an output format
+fn output_extension(format: OutputFormat) -> &'static str {
+    match format {
+        OutputFormat::Blp => "blp",
+        OutputFormat::Png => "png",
+        OutputFormat::Jpeg => "jpg",
+        OutputFormat::Gif => "gif",
+        OutputFormat::Bmp => "bmp",
+        OutputFormat::Ico => "ico",
+        OutputFormat::Tiff => "tiff",
+        OutputFormat::Pnm => "pnm",
+        OutputFormat::Tga => "tga",
+        OutputFormat::OpenExr => "exr",
+        OutputFormat::Farbfeld => "ff",
+    }
+}
+
+/// Directory part of a glob pattern that contains no wildcards
+fn glob_base(pattern: &str) -> PathBuf {
+    let mut base = PathBuf::new();
+    for component in Path::new(pattern).components() {
+        let part = component.as_os_str().to_string_lossy();
+        if part.contains(['*', '?', '[', '{']) {
+            return base;
+        }
+        base.push(component);
+    }
+    // The pattern names a single file
+    base.parent().map(Path::to_path_buf).unwrap_or_default()
+}
+
+/// Convert every file matching `pattern` into `out_dir`
+fn convert_batch(
+    pattern: &str,
+    out_dir: &Path,
+    jobs: Option<usize>,
+    args: &ConvertArgs,
+) -> Result<()> {
+    let output_format = args
+        .output_format
+        .context("Batch conversion requires --to <format>")?;
+    let extension = output_extension(output_format);
+
+    let files: Vec<PathBuf> = glob::glob(pattern)
+        .context("Invalid glob pattern")?
+        .filter_map(|p| p.ok())
+        .filter(|p| p.is_file())
+        .collect();
+    if files.is_empty() {
+        anyhow::bail!("No files found matching pattern: {}", pattern);
+    }
+
+    let base = glob_base(pattern);
+    let pb = create_progress_bar(files.len() as u64, "Converting");
+    let failures = std::sync::Mutex::new(Vec::new());
+
+    let convert = |input: &PathBuf| {
+        let relative = input.strip_prefix(&base).unwrap_or(input);
+        let output = out_dir.join(relative).with_extension(extension);
+        // Originals mirror the layout of the inputs
+        let like = args
+            .like
+            .as_ref()
+            .map(|dir| dir.join(relative).with_extension("blp"));
+        let result = output
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .context("Failed to create output directory")
+            .and_then(|()| convert_blp(input, &output, like.as_deref(), args));
+        if let Err(e) = result {
+            failures
+                .lock()
+                .expect("failure list lock poisoned")
+                .push((input.clone(), e));
+        }
+        pb.inc(1);
+    };
+
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+        let mut pool = rayon::ThreadPoolBuilder::new();
+        if let Some(jobs) = jobs {
+            pool = pool.num_threads(jobs);
+        }
+        pool.build()
+            .context("Failed to create worker pool")?
+            .install(|| files.par_iter().for_each(convert));
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        if jobs.is_some_and(|j| j > 1) {
+            log::warn!("--jobs requires the 'parallel' feature; converting sequentially");
+        }
+        files.iter().for_each(convert);
+    }
+
+    let failures = failures.into_inner().expect("failure list lock poisoned");
+    pb.finish_with_message(format!(
+        "Converted {} of {} files",
+        files.len() - failures.len(),
+        files.len()
+    ));
+
+    for (file, error) in &failures {
+        eprintln!("✗ {}: {:#}", file.display(), error);
+    }
+    if !failures.is_empty() {
+        anyhow::bail!("{} file(s) failed to convert", failures.len());
+    }
+
+    println!(
+        "✓ Converted {} files into {}",
+        files.len(),
+        out_dir.display()
+    );
+    Ok(())
+}
+
+/// Convert one file; `like` is a BLP whose encoding the output reuses
+fn convert_blp(input: &Path, output: &Path, like: Option<&Path>, args: &ConvertArgs) -> Result<()> {
     // Determine input format
     let input_format = args
         .input_format
-        .or_else(|| guess_input_format(&args.input))
+        .or_else(|| guess_input_format(input))
         .context("Failed to determine input format. Please specify with --input-format")?;
 
     // Determine output format
     let output_format = args
         .output_format
-        .or_else(|| guess_output_format(&args.output))
+        .or_else(|| guess_output_format(output))
         .context("Failed to determine output format. Please specify with --output-format")?;
 
     log::info!("Converting from {input_format:?} to {output_format:?}");
 
     // Load input image
     let input_image = if input_format == InputFormat::Blp {
-        let blp_image = load_blp(&args.input)
-            .with_context(|| format!("Failed to load BLP file: {}", args.input.display()))?;
+        let blp_image = load_blp(input)
+            .with_context(|| format!("Failed to load BLP file: {}", input.display()))?;
 
         blp_to_image(&blp_image, args.mipmap_level)
             .with_context(|| format!("Failed to convert BLP mipmap level {}", args.mipmap_level))?
     } else {
-        ImageReader::open(&args.input)
-            .with_context(|| format!("Failed to open image file: {}", args.input.display()))?
+        ImageReader::open(input)
+            .with_context(|| format!("Failed to open image file: {}", input.display()))?
             .decode()
-            .with_context(|| format!("Failed to decode image: {}", args.input.display()))?
+            .with_context(|| format!("Failed to decode image: {}", input.display()))?
     };
 
     // Save output
-    match output_format {
-        OutputFormat::Blp => {
+    match (output_format, like, args.preset) {
+        (OutputFormat::Blp, Some(like), _) => {
+            let original = load_blp(like)
+                .with_context(|| format!("Failed to load original BLP: {}", like.display()))?;
+            let encoding =
+                BlpEncoding::of(&original).with_compress_algorithm(args.dxt_compression.into());
+            log::info!(
+                "Reusing encoding of {}: {}, {} mipmap(s)",
+                like.display(),
+                encoding.target,
+                encoding.mipmap_count
+            );
+            let blp = encoding
+                .encode(input_image, args.mipmap_filter.into())
+                .context("Failed to convert image to BLP")?;
+
+            save_blp(&blp, output)
+                .with_context(|| format!("Failed to save BLP file: {}", output.display()))?;
+        }
+        (OutputFormat::Blp, None, Some(preset)) => {
+            let preset = BlpTargetPreset::from(preset);
+            let encoding = preset
+                .encoding_for(&input_image)
+                .with_context(|| format!("Cannot convert {} for {preset}", input.display()))?
+                .with_compress_algorithm(args.dxt_compression.into());
+            log::info!("Using {preset} preset: {}", encoding.target);
+            let blp = encoding
+                .encode(input_image, args.mipmap_filter.into())
+                .context("Failed to convert image to BLP")?;
+
+            save_blp(&blp, output)
+                .with_context(|| format!("Failed to save BLP file: {}", output.display()))?;
+        }
+        (OutputFormat::Blp, None, None) => {
             let has_alpha = image_has_alpha(&input_image);
 
             // Auto-detect or validate alpha bits
@@ -500,41 +724,84 @@ fn convert_blp(args: ConvertArgs) -> Result<()> {
             )
             .context("Failed to convert image to BLP")?;
 
-            save_blp(&blp, &args.output)
-                .with_context(|| format!("Failed to save BLP file: {}", args.output.display()))?;
+            save_blp(&blp, output)
+                .with_context(|| format!("Failed to save BLP file: {}", output.display()))?;
         }
         _ => {
             let img_format = output_format.try_into()?;
             input_image
-                .save_with_format(&args.output, img_format)
-                .with_context(|| format!("Failed to save image: {}", args.output.display()))?;
+                .save_with_format(output, img_format)
+                .with_context(|| format!("Failed to save image: {}", output.display()))?;
         }
     }
 
-    println!(
-        "✓ Converted {} to {}",
-        args.input.display(),
-        args.output.display()
-    );
     Ok(())
 }
 
-fn show_blp_info(
-    file: PathBuf,
-    show_mipmaps: bool,
-    show_raw: bool,
-    show_compression: bool,
-    show_size: bool,
+struct InfoOptions {
+    mipmaps: bool,
+    raw: bool,
+    compression: bool,
+    size: bool,
     best_mipmap_for: Option<u32>,
-    show_all: bool,
-) -> Result<()> {
+    preview: Option<PreviewMode>,
+    preview_width: u32,
+}
+
+/// Name of the pixel encoding, e.g. `DXT5` or `Palettized, 8-bit alpha`
+fn encoding_name(blp: &BlpImage) -> String {
+    match &blp.content {
+        BlpContent::Jpeg(_) => "JPEG".to_string(),
+        BlpContent::Raw1(_) => match blp.alpha_bit_depth() {
+            0 => "Palettized".to_string(),
+            bits => format!("Palettized, {bits}-bit alpha"),
+        },
+        BlpContent::Raw3(_) => "BGRA".to_string(),
+        BlpContent::Dxt1(_) => "DXT1".to_string(),
+        BlpContent::Dxt3(_) => "DXT3".to_string(),
+        BlpContent::Dxt5(_) => "DXT5".to_string(),
+    }
+}
+
+fn print_mipmap_table(blp: &BlpImage) {
+    let encoding = encoding_name(blp);
+    let mut table = create_table(vec![
+        "Level", "Size", "Offset", "Stored", "Decoded", "Encoding",
+    ]);
+    for info in blp.mipmap_info() {
+        let (offset, stored) = match &blp.header.mipmap_locator {
+            MipmapLocator::Internal { offsets, sizes } => (
+                format!("0x{:08X}", offsets[info.level]),
+                sizes[info.level].to_string(),
+            ),
+            MipmapLocator::External => (format!("external (.b{:02})", info.level), "-".into()),
+        };
+        add_table_row(
+            &mut table,
+            vec![
+                info.level.to_string(),
+                format!("{}x{}", info.width, info.height),
+                offset,
+                stored,
+                (info.pixel_count * 4).to_string(),
+                encoding.clone(),
+            ],
+        );
+    }
+    table.printstd();
+}
+
+fn show_blp_info(file: PathBuf, options: InfoOptions) -> Result<()> {
     let blp =
         load_blp(&file).with_context(|| format!("Failed to load BLP file: {}", file.display()))?;
-
-    // Apply --all flag
-    let show_mipmaps = show_mipmaps || show_all;
-    let show_compression = show_compression || show_all;
-    let show_size = show_size || show_all;
+    let InfoOptions {
+        mipmaps: show_mipmaps,
+        raw: show_raw,
+        compression: show_compression,
+        size: show_size,
+        best_mipmap_for,
+        ..
+    } = options;
 
     println!("BLP File Information: {}", file.display());
     println!("=====================================");
@@ -614,17 +881,11 @@ fn show_blp_info(
         println!("Best Level: {best_level} ({width}x{height})");
     }
 
-    // Mipmap info (using new convenience method)
+    // Offsets and sizes as stored, decoded sizes as RGBA
     if show_mipmaps {
         println!("\nMipmap Information:");
         println!("-------------------");
-        let mipmap_info = blp.mipmap_info();
-        for info in &mipmap_info {
-            println!(
-                "  Level {}: {}x{} ({} bytes, {} pixels)",
-                info.level, info.width, info.height, info.data_size, info.pixel_count
-            );
-        }
+        print_mipmap_table(&blp);
     }
 
     // Raw header data
@@ -639,6 +900,11 @@ fn show_blp_info(
         println!("  Mipmap Locator: {:?}", blp.header.mipmap_locator);
     }
 
+    if let Some(mode) = options.preview {
+        println!("\nPreview:");
+        print!("{}", render_preview(&blp, options.preview_width, mode)?);
+    }
+
     Ok(())
 }
 
@@ -671,14 +937,15 @@ fn validate_blp(file: PathBuf, strict: bool) -> Result<()> {
         }
     }
 
-    if blp.header.has_mipmaps() {
-        let expected_levels = (blp.header.width.max(blp.header.height) as f32).log2() as usize + 1;
-        let actual_levels = blp.image_count();
-
-        if actual_levels < expected_levels {
-            warnings.push(format!(
-                "Incomplete mipmap chain: expected {expected_levels} levels, got {actual_levels}"
-            ));
+    // Missing levels are common; content that doesn't match its parent level
+    // is a heuristic finding, so only strict mode treats it as an error
+    for issue in check_mipmaps(&blp) {
+        match issue {
+            MipmapIssue::MissingLevels { .. } => warnings.push(issue.to_string()),
+            MipmapIssue::Truncated { .. } | MipmapIssue::ContentMismatch { .. } if !strict => {
+                warnings.push(issue.to_string())
+            }
+            _ => errors.push(issue.to_string()),
         }
     }
 
@@ -728,8 +995,6 @@ fn validate_blp(file: PathBuf, strict: bool) -> Result<()> {
 
 // Helper struct for convert arguments
 struct ConvertArgs {
-    input: PathBuf,
-    output: PathBuf,
     input_format: Option<InputFormat>,
     output_format: Option<OutputFormat>,
     blp_version: BlpVersionCli,
@@ -739,6 +1004,8 @@ struct ConvertArgs {
     no_mipmaps: bool,
     mipmap_filter: MipmapFilter,
     dxt_compression: DxtCompression,
+    like: Option<PathBuf>,
+    preset: Option<BlpPreset>,
 }
 
 pub fn execute(command: BlpCommands) -> Result<()> {
@@ -746,6 +1013,9 @@ pub fn execute(command: BlpCommands) -> Result<()> {
         BlpCommands::Convert {
             input,
             output,
+            recursive,
+            out,
+            jobs,
             input_format,
             output_format,
             blp_version,
@@ -755,19 +1025,35 @@ pub fn execute(command: BlpCommands) -> Result<()> {
             no_mipmaps,
             mipmap_filter,
             dxt_compression,
-        } => convert_blp(ConvertArgs {
-            input,
-            output,
-            input_format,
-            output_format,
-            blp_version,
-            blp_format,
-            alpha_bits,
-            mipmap_level,
-            no_mipmaps,
-            mipmap_filter,
-            dxt_compression,
-        }),
+            like,
+            preset,
+        } => {
+            let args = ConvertArgs {
+                input_format,
+                output_format,
+                blp_version,
+                blp_format,
+                alpha_bits,
+                mipmap_level,
+                no_mipmaps,
+                mipmap_filter,
+                dxt_compression,
+                like,
+                preset,
+            };
+            match (recursive, input, output) {
+                (Some(pattern), _, _) => {
+                    let out = out.context("--recursive requires --out <dir>")?;
+                    convert_batch(&pattern, &out, jobs, &args)
+                }
+                (None, Some(input), Some(output)) => {
+                    convert_blp(&input, &output, args.like.as_deref(), &args)?;
+                    println!("✓ Converted {} to {}", input.display(), output.display());
+                    Ok(())
+                }
+                _ => anyhow::bail!("Specify an input and output file, or --recursive <pattern>"),
+            }
+        }
         BlpCommands::Info {
             file,
             mipmaps,
@@ -776,7 +1062,20 @@ pub fn execute(command: BlpCommands) -> Result<()> {
             size,
             best_mipmap_for,
             all,
-        } => show_blp_info(file, mipmaps, raw, compression, size, best_mipmap_for, all),
+            preview,
+            preview_width,
+        } => show_blp_info(
+            file,
+            InfoOptions {
+                mipmaps: mipmaps || all,
+                raw,
+                compression: compression || all,
+                size: size || all,
+                best_mipmap_for,
+                preview,
+                preview_width,
+            },
+        ),
         BlpCommands::Validate { file, strict } => validate_blp(file, strict),
     }
 }
@@ -0,0 +1,144 @@
+//! Terminal preview of a BLP texture
+//!
+//! Renders a small version of the texture straight to the terminal so a file
+//! can be identified without exporting it. The ANSI mode packs two pixels into
+//! each character cell with the upper half block and 24-bit colors; the ASCII
+//! mode maps luminance to characters for terminals without color support.
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use image::{Rgba, RgbaImage, imageops::FilterType};
+use std::fmt::Write;
+use wow_blp::{convert::blp_to_image, types::BlpImage};
+
+/// Darkest to brightest
+const ASCII_RAMP: &[u8] = b" .:-=+*#%@";
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PreviewMode {
+    /// 24-bit color half blocks
+    Ansi,
+    /// Plain characters by brightness
+    Ascii,
+}
+
+/// Smallest mipmap level that is still at least `width` pixels wide
+fn preview_level(blp: &BlpImage, width: u32) -> usize {
+    (0..blp.image_count())
+        .rev()
+        .find(|&level| blp.header.mipmap_size(level).0 >= width)
+        .unwrap_or(0)
+}
+
+/// Blend a pixel over a checkerboard so transparent areas stay visible
+fn flatten(pixel: Rgba<u8>, x: u32, y: u32) -> [u8; 3] {
+    let background = if (x / 2 + y / 2).is_multiple_of(2) {
+        0x66
+    } else {
+        0x99
+    };
+    let alpha = u32::from(pixel[3]);
+    let blend = |c: u8| ((u32::from(c) * alpha + background * (255 - alpha)) / 255) as u8;
+    [blend(pixel[0]), blend(pixel[1]), blend(pixel[2])]
+}
+
+fn scaled(blp: &BlpImage, columns: u32, rows_per_cell: u32) -> Result<RgbaImage> {
+    let columns = columns.max(1);
+    let level = preview_level(blp, columns);
+    let image = blp_to_image(blp, level)
+        .with_context(|| format!("Failed to decode mipmap level {level}"))?
+        .to_rgba8();
+    let columns = columns.min(image.width().max(1));
+    // Terminal cells are about twice as tall as they are wide
+    let rows = (u64::from(columns) * u64::from(image.height()) / u64::from(image.width().max(1)))
+        .div_ceil(2)
+        .max(1) as u32;
+    Ok(image::imageops::resize(
+        &image,
+        columns,
+        rows * rows_per_cell,
+        FilterType::Triangle,
+    ))
+}
+
+/// Render a preview `columns` characters wide
+pub fn render_preview(blp: &BlpImage, columns: u32, mode: PreviewMode) -> Result<String> {
+    let mut out = String::new();
+    match mode {
+        PreviewMode::Ansi => {
+            let image = scaled(blp, columns, 2)?;
+            for y in (0..image.height()).step_by(2) {
+                for x in 0..image.width() {
+                    let [tr, tg, tb] = flatten(*image.get_pixel(x, y), x, y);
+                    let [br, bg, bb] = flatten(*image.get_pixel(x, y + 1), x, y + 1);
+                    let _ = write!(out, "\x1b[38;2;{tr};{tg};{tb}m\x1b[48;2;{br};{bg};{bb}m▀");
+                }
+                out.push_str("\x1b[0m\n");
+            }
+        }
+        PreviewMode::Ascii => {
+            let image = scaled(blp, columns, 1)?;
+            for y in 0..image.height() {
+                for x in 0..image.width() {
+                    let p = image.get_pixel(x, y);
+                    let luma = (0.299 * f32::from(p[0])
+                        + 0.587 * f32::from(p[1])
+                        + 0.114 * f32::from(p[2]))
+                        * f32::from(p[3])
+                        / 255.0;
+                    let index = (luma / 256.0 * ASCII_RAMP.len() as f32) as usize;
+                    out.push(ASCII_RAMP[index.min(ASCII_RAMP.len() - 1)] as char);
+                }
+                out.push('\n');
+            }
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::DynamicImage;
+    use wow_blp::convert::{Blp2Format, BlpTarget, image_to_blp};
+
+    fn test_blp() -> BlpImage {
+        // White on the left, black on the right
+        let image = RgbaImage::from_fn(64, 32, |x, _| {
+            if x < 32 {
+                Rgba([255, 255, 255, 255])
+            } else {
+                Rgba([0, 0, 0, 255])
+            }
+        });
+        image_to_blp(
+            DynamicImage::ImageRgba8(image),
+            true,
+            BlpTarget::Blp2(Blp2Format::Raw3),
+            FilterType::Nearest,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_preview_level() {
+        let blp = test_blp();
+        assert_eq!(preview_level(&blp, 16), 2);
+        assert_eq!(preview_level(&blp, 200), 0);
+    }
+
+    #[test]
+    fn test_ascii_preview() {
+        let preview = render_preview(&test_blp(), 8, PreviewMode::Ascii).unwrap();
+        // 8 columns of a 2:1 image at half height
+        assert_eq!(preview, "@@@@    \n@@@@    \n");
+    }
+
+    #[test]
+    fn test_ansi_preview() {
+        let preview = render_preview(&test_blp(), 4, PreviewMode::Ansi).unwrap();
+        assert_eq!(preview.lines().count(), 1);
+        assert!(preview.starts_with("\x1b[38;2;255;255;255m\x1b[48;2;255;255;255m▀"));
+        assert!(preview.ends_with("\x1b[0m\n"));
+    }
+}
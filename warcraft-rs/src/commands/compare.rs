@@ -0,0 +1,655 @@
+//! Cross-build comparison of two game installations
+//!
+//! Mounts both installs as patch chains, diffs the merged file lists and, for
+//! client databases, the records inside each table. This is the usual
+//! PTR-vs-live datamining workflow.
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::PathBuf;
+use wow_mpq::PatchChain;
+use wow_mpq::special_files::is_special_file;
+
+use crate::commands::mpq::chain::mount_chain;
+use crate::utils::{create_progress_bar, format_bytes};
+
+/// Category of game data, used to select what to compare
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DataKind {
+    /// Client databases under DBFilesClient
+    Dbc,
+    /// Interface files (FrameXML, AddOns, art)
+    Interface,
+    /// Maps and buildings (WDT, ADT, WDL, WMO)
+    Maps,
+    /// Models and their skins and animations
+    Models,
+    /// Textures outside the interface
+    Textures,
+    /// Sound and music
+    Sound,
+    /// Everything else
+    Other,
+}
+
+impl DataKind {
+    fn classify(name: &str) -> Self {
+        let lower = name.to_ascii_lowercase().replace('/', "\\");
+        let ext = lower.rsplit_once('.').map_or("", |(_, ext)| ext);
+        if lower.starts_with("dbfilesclient\\") {
+            Self::Dbc
+        } else if lower.starts_with("interface\\") {
+            Self::Interface
+        } else if lower.starts_with("world\\maps\\") || matches!(ext, "wmo" | "adt" | "wdt" | "wdl")
+        {
+            Self::Maps
+        } else if matches!(ext, "m2" | "mdx" | "skin" | "anim" | "phys" | "bone") {
+            Self::Models
+        } else if ext == "blp" {
+            Self::Textures
+        } else if lower.starts_with("sound\\") || matches!(ext, "wav" | "mp3" | "ogg") {
+            Self::Sound
+        } else {
+            Self::Other
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Dbc => "dbc",
+            Self::Interface => "interface",
+            Self::Maps => "maps",
+            Self::Models => "models",
+            Self::Textures => "textures",
+            Self::Sound => "sound",
+            Self::Other => "other",
+        }
+    }
+}
+
+/// Report format of `compare`
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompareFormat {
+    /// Human readable report
+    Text,
+    /// Machine readable JSON
+    Json,
+}
+
+pub struct CompareParams {
+    pub old: PathBuf,
+    pub new: PathBuf,
+    pub old_locale: Option<String>,
+    pub new_locale: Option<String>,
+    /// Kinds to compare; empty compares everything
+    pub kinds: Vec<DataKind>,
+    /// Compare the contents of same-sized files, not just their sizes
+    pub content: bool,
+    pub format: CompareFormat,
+    pub output: Option<PathBuf>,
+    /// Changed records listed per table in the text report
+    pub max_records: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+impl ChangeKind {
+    fn marker(self) -> char {
+        match self {
+            ChangeKind::Added => 'A',
+            ChangeKind::Removed => 'D',
+            ChangeKind::Changed => 'M',
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    fn as_str(self) -> &'static str {
+        match self {
+            ChangeKind::Added => "added",
+            ChangeKind::Removed => "removed",
+            ChangeKind::Changed => "changed",
+        }
+    }
+}
+
+/// A file added, removed or changed between the builds
+#[derive(Debug)]
+struct FileChange {
+    name: String,
+    kind: DataKind,
+    change: ChangeKind,
+    old_size: Option<u64>,
+    new_size: Option<u64>,
+}
+
+/// A record whose values differ, keyed by its ID
+#[derive(Debug)]
+struct RecordChange {
+    key: u32,
+    /// Field name, old value and new value
+    fields: Vec<(String, String, String)>,
+}
+
+/// Record-level differences of one client database
+#[derive(Debug, Default)]
+struct TableDiff {
+    name: String,
+    old_records: usize,
+    new_records: usize,
+    /// Field counts when the record layout changed between builds
+    layout: Option<(u32, u32)>,
+    added: Vec<u32>,
+    removed: Vec<u32>,
+    changed: Vec<RecordChange>,
+}
+
+impl TableDiff {
+    fn is_empty(&self) -> bool {
+        self.layout.is_none()
+            && self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+    }
+}
+
+#[derive(Debug, Default)]
+struct Report {
+    old: String,
+    new: String,
+    kinds: Vec<DataKind>,
+    files: Vec<FileChange>,
+    unchanged: usize,
+    tables: Vec<TableDiff>,
+    table_errors: Vec<(String, String)>,
+}
+
+/// File sizes of the merged view keyed by normalized name
+fn file_index(
+    chain: &mut PatchChain,
+    kinds: &[DataKind],
+) -> Result<BTreeMap<String, (String, u64)>> {
+    Ok(chain
+        .list()?
+        .into_iter()
+        .filter(|entry| !is_special_file(&entry.name))
+        .filter(|entry| kinds.contains(&DataKind::classify(&entry.name)))
+        .map(|entry| {
+            let key = entry.name.to_ascii_lowercase().replace('/', "\\");
+            (key, (entry.name, entry.size))
+        })
+        .collect())
+}
+
+pub fn execute(params: CompareParams) -> Result<()> {
+    let kinds = if params.kinds.is_empty() {
+        DataKind::value_variants().to_vec()
+    } else {
+        let mut kinds = params.kinds.clone();
+        kinds.sort();
+        kinds.dedup();
+        kinds
+    };
+
+    let (mut old_chain, _) = mount_chain(&params.old, params.old_locale.as_deref(), None)
+        .with_context(|| format!("Failed to mount {}", params.old.display()))?;
+    let (mut new_chain, _) = mount_chain(&params.new, params.new_locale.as_deref(), None)
+        .with_context(|| format!("Failed to mount {}", params.new.display()))?;
+
+    let old_files = file_index(&mut old_chain, &kinds)?;
+    let new_files = file_index(&mut new_chain, &kinds)?;
+
+    let mut report = Report {
+        old: params.old.display().to_string(),
+        new: params.new.display().to_string(),
+        kinds: kinds.clone(),
+        ..Default::default()
+    };
+
+    for (key, (name, size)) in &old_files {
+        if !new_files.contains_key(key) {
+            report.files.push(FileChange {
+                name: name.clone(),
+                kind: DataKind::classify(name),
+                change: ChangeKind::Removed,
+                old_size: Some(*size),
+                new_size: None,
+            });
+        }
+    }
+
+    let common: Vec<_> = new_files
+        .iter()
+        .filter_map(|(key, (name, size))| {
+            let Some((_, old_size)) = old_files.get(key) else {
+                report.files.push(FileChange {
+                    name: name.clone(),
+                    kind: DataKind::classify(name),
+                    change: ChangeKind::Added,
+                    old_size: None,
+                    new_size: Some(*size),
+                });
+                return None;
+            };
+            Some((name.clone(), *old_size, *size))
+        })
+        .collect();
+
+    let pb = create_progress_bar(common.len() as u64, "Comparing files");
+    for (name, old_size, new_size) in common {
+        pb.inc(1);
+        let kind = DataKind::classify(&name);
+        let read_contents = kind == DataKind::Dbc || (params.content && old_size == new_size);
+
+        let changed = if read_contents {
+            let old_data = old_chain
+                .read_file(&name)
+                .with_context(|| format!("Failed to read {name} from old build"))?;
+            let new_data = new_chain
+                .read_file(&name)
+                .with_context(|| format!("Failed to read {name} from new build"))?;
+            let changed = old_data != new_data;
+            if changed && kind == DataKind::Dbc {
+                match diff_table(&name, &old_data, &new_data) {
+                    Ok(Some(table)) if !table.is_empty() => report.tables.push(table),
+                    Ok(_) => {}
+                    Err(e) => report.table_errors.push((name.clone(), format!("{e:#}"))),
+                }
+            }
+            changed
+        } else {
+            old_size != new_size
+        };
+
+        if changed {
+            report.files.push(FileChange {
+                name,
+                kind,
+                change: ChangeKind::Changed,
+                old_size: Some(old_size),
+                new_size: Some(new_size),
+            });
+        } else {
+            report.unchanged += 1;
+        }
+    }
+    pb.finish_and_clear();
+
+    report
+        .files
+        .sort_by(|a, b| (a.kind, a.name.to_lowercase()).cmp(&(b.kind, b.name.to_lowercase())));
+    report.tables.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let rendered = match params.format {
+        CompareFormat::Text => render_text(&report, params.max_records),
+        CompareFormat::Json => render_json(&report)?,
+    };
+    match &params.output {
+        Some(path) => {
+            fs::write(path, rendered)
+                .with_context(|| format!("Failed to write report {}", path.display()))?;
+            println!("Report written to {}", path.display());
+        }
+        None => print!("{rendered}"),
+    }
+    Ok(())
+}
+
+fn table_name(path: &str) -> String {
+    path.rsplit(['\\', '/']).next().unwrap_or(path).to_string()
+}
+
+/// Record-level diff of a client database, or `None` for non-DBC files
+#[cfg(feature = "dbc")]
+fn diff_table(path: &str, old: &[u8], new: &[u8]) -> Result<Option<TableDiff>> {
+    use wow_cdbc::{DbcParser, RecordSet, SchemaDiscoverer, Value};
+
+    let lower = path.to_ascii_lowercase();
+    if !lower.ends_with(".dbc") && !lower.ends_with(".db2") {
+        return Ok(None);
+    }
+    let name = table_name(path);
+
+    let old_parser = DbcParser::parse_bytes(old).context("Failed to parse old table")?;
+    let new_parser = DbcParser::parse_bytes(new).context("Failed to parse new table")?;
+    let old_fields = old_parser.header().field_count;
+    let new_fields = new_parser.header().field_count;
+
+    let key_of = |value: Option<&Value>| match value {
+        Some(Value::UInt32(v)) => Some(*v),
+        Some(Value::Int32(v)) => Some(*v as u32),
+        _ => None,
+    };
+    // Record indices by key; duplicate or missing keys fall back to row positions
+    let keyed = |records: &RecordSet, key_index: Option<usize>| -> BTreeMap<u32, usize> {
+        let mut map = BTreeMap::new();
+        if let Some(key_index) = key_index {
+            for (index, record) in records.records().iter().enumerate() {
+                match key_of(record.get_value(key_index)) {
+                    Some(key) if map.insert(key, index).is_none() => {}
+                    _ => {
+                        map.clear();
+                        break;
+                    }
+                }
+            }
+        }
+        if map.is_empty() {
+            map = (0..records.len()).map(|i| (i as u32, i)).collect();
+        }
+        map
+    };
+
+    if old_fields != new_fields
+        || old_parser.header().record_size != new_parser.header().record_size
+    {
+        // Different layouts cannot be compared field by field; IDs are nearly
+        // always the first column, so report added and removed rows by it
+        let old_records = old_parser.parse_records()?;
+        let new_records = new_parser.parse_records()?;
+        let old_keys = keyed(&old_records, Some(0));
+        let new_keys = keyed(&new_records, Some(0));
+        return Ok(Some(TableDiff {
+            name,
+            old_records: old_records.len(),
+            new_records: new_records.len(),
+            layout: Some((old_fields, new_fields)),
+            added: new_keys
+                .keys()
+                .filter(|k| !old_keys.contains_key(k))
+                .copied()
+                .collect(),
+            removed: old_keys
+                .keys()
+                .filter(|k| !new_keys.contains_key(k))
+                .copied()
+                .collect(),
+            changed: Vec::new(),
+        }));
+    }
+
+    // Discover string and float columns on the new build and read both with it
+    let schema = {
+        let raw = new_parser.parse_records()?;
+        SchemaDiscoverer::new(new_parser.header(), new_parser.data(), raw.string_block())
+            .generate_schema(&name)
+            .ok()
+    };
+    let (old_records, new_records) = match schema {
+        Some(schema) => match (
+            DbcParser::parse_bytes(old)?.with_schema(schema.clone()),
+            DbcParser::parse_bytes(new)?.with_schema(schema),
+        ) {
+            (Ok(old), Ok(new)) => (old.parse_records()?, new.parse_records()?),
+            _ => (old_parser.parse_records()?, new_parser.parse_records()?),
+        },
+        None => (old_parser.parse_records()?, new_parser.parse_records()?),
+    };
+
+    let key_index = new_records.schema().and_then(|s| s.key_field_index);
+    let field_names: Vec<String> = match new_records.schema() {
+        Some(schema) => schema.fields.iter().map(|f| f.name.clone()).collect(),
+        None => (0..new_fields).map(|i| format!("field_{i}")).collect(),
+    };
+    let old_keys = keyed(&old_records, key_index);
+    let new_keys = keyed(&new_records, key_index);
+
+    let mut diff = TableDiff {
+        name,
+        old_records: old_records.len(),
+        new_records: new_records.len(),
+        ..Default::default()
+    };
+    diff.removed = old_keys
+        .keys()
+        .filter(|k| !new_keys.contains_key(k))
+        .copied()
+        .collect();
+
+    for (&key, &new_index) in &new_keys {
+        let Some(&old_index) = old_keys.get(&key) else {
+            diff.added.push(key);
+            continue;
+        };
+        let old_record = &old_records.records()[old_index];
+        let new_record = &new_records.records()[new_index];
+        let fields: Vec<_> = old_record
+            .values()
+            .iter()
+            .zip(new_record.values())
+            .enumerate()
+            .filter_map(|(i, (old_value, new_value))| {
+                let old_text = crate::commands::dbc::format_cell(old_value, &old_records);
+                let new_text = crate::commands::dbc::format_cell(new_value, &new_records);
+                (old_text != new_text).then(|| {
+                    let field = field_names
+                        .get(i)
+                        .cloned()
+                        .unwrap_or_else(|| format!("field_{i}"));
+                    (field, old_text, new_text)
+                })
+            })
+            .collect();
+        if !fields.is_empty() {
+            diff.changed.push(RecordChange { key, fields });
+        }
+    }
+    Ok(Some(diff))
+}
+
+#[cfg(not(feature = "dbc"))]
+fn diff_table(_path: &str, _old: &[u8], _new: &[u8]) -> Result<Option<TableDiff>> {
+    Ok(None)
+}
+
+fn size_text(size: Option<u64>) -> String {
+    size.map(format_bytes).unwrap_or_default()
+}
+
+fn render_text(report: &Report, max_records: usize) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "Compare: {} -> {}", report.old, report.new);
+    let kinds: Vec<_> = report.kinds.iter().map(|k| k.name()).collect();
+    let _ = writeln!(out, "Kinds: {}", kinds.join(", "));
+
+    for &kind in &report.kinds {
+        let files: Vec<_> = report.files.iter().filter(|f| f.kind == kind).collect();
+        if files.is_empty() {
+            continue;
+        }
+        let count = |change| files.iter().filter(|f| f.change == change).count();
+        let _ = writeln!(
+            out,
+            "\n[{}] {} added, {} removed, {} changed",
+            kind.name(),
+            count(ChangeKind::Added),
+            count(ChangeKind::Removed),
+            count(ChangeKind::Changed)
+        );
+        for file in files {
+            let sizes = match (file.old_size, file.new_size) {
+                (Some(old), Some(new)) if old != new => {
+                    format!("{} -> {}", format_bytes(old), format_bytes(new))
+                }
+                (old, new) => size_text(new.or(old)),
+            };
+            let _ = writeln!(out, "  {}  {}  {}", file.change.marker(), file.name, sizes);
+        }
+    }
+
+    if !report.tables.is_empty() || !report.table_errors.is_empty() {
+        let _ = writeln!(out, "\nDatabase changes");
+    }
+    for table in &report.tables {
+        let _ = write!(
+            out,
+            "  {}: {} -> {} records, {} added, {} removed, {} changed",
+            table.name,
+            table.old_records,
+            table.new_records,
+            table.added.len(),
+            table.removed.len(),
+            table.changed.len()
+        );
+        if let Some((old, new)) = table.layout {
+            let _ = write!(out, " (layout changed: {old} -> {new} fields)");
+        }
+        let _ = writeln!(out);
+
+        let ids = |keys: &[u32]| {
+            let mut text: Vec<_> = keys.iter().take(max_records).map(u32::to_string).collect();
+            if keys.len() > max_records {
+                text.push(format!("... {} more", keys.len() - max_records));
+            }
+            text.join(", ")
+        };
+        if !table.added.is_empty() {
+            let _ = writeln!(out, "    + {}", ids(&table.added));
+        }
+        if !table.removed.is_empty() {
+            let _ = writeln!(out, "    - {}", ids(&table.removed));
+        }
+        for record in table.changed.iter().take(max_records) {
+            let fields: Vec<_> = record
+                .fields
+                .iter()
+                .map(|(field, old, new)| format!("{field}: {old:?} -> {new:?}"))
+                .collect();
+            let _ = writeln!(out, "    ~ {}: {}", record.key, fields.join(", "));
+        }
+        if table.changed.len() > max_records {
+            let _ = writeln!(
+                out,
+                "    ... {} more changed records",
+                table.changed.len() - max_records
+            );
+        }
+    }
+    for (name, error) in &report.table_errors {
+        let _ = writeln!(out, "  {name}: could not compare records: {error}");
+    }
+
+    let count = |change| report.files.iter().filter(|f| f.change == change).count();
+    let _ = writeln!(
+        out,
+        "\nSummary: {} added, {} removed, {} changed, {} unchanged files",
+        count(ChangeKind::Added),
+        count(ChangeKind::Removed),
+        count(ChangeKind::Changed),
+        report.unchanged
+    );
+    out
+}
+
+#[cfg(feature = "serde")]
+fn render_json(report: &Report) -> Result<String> {
+    let files: Vec<_> = report
+        .files
+        .iter()
+        .map(|file| {
+            serde_json::json!({
+                "name": file.name,
+                "kind": file.kind.name(),
+                "status": file.change.as_str(),
+                "old_size": file.old_size,
+                "new_size": file.new_size,
+            })
+        })
+        .collect();
+    let tables: Vec<_> = report
+        .tables
+        .iter()
+        .map(|table| {
+            let changed: Vec<_> = table
+                .changed
+                .iter()
+                .map(|record| {
+                    let fields: Vec<_> = record
+                        .fields
+                        .iter()
+                        .map(|(field, old, new)| {
+                            serde_json::json!({ "field": field, "old": old, "new": new })
+                        })
+                        .collect();
+                    serde_json::json!({ "id": record.key, "fields": fields })
+                })
+                .collect();
+            serde_json::json!({
+                "name": table.name,
+                "old_records": table.old_records,
+                "new_records": table.new_records,
+                "layout_changed": table.layout.map(|(old, new)| {
+                    serde_json::json!({ "old_fields": old, "new_fields": new })
+                }),
+                "added": table.added,
+                "removed": table.removed,
+                "changed": changed,
+            })
+        })
+        .collect();
+    let errors: Vec<_> = report
+        .table_errors
+        .iter()
+        .map(|(name, error)| serde_json::json!({ "name": name, "error": error }))
+        .collect();
+
+    let count = |change| report.files.iter().filter(|f| f.change == change).count();
+    let document = serde_json::json!({
+        "old": report.old,
+        "new": report.new,
+        "kinds": report.kinds.iter().map(|k| k.name()).collect::<Vec<_>>(),
+        "summary": {
+            "added": count(ChangeKind::Added),
+            "removed": count(ChangeKind::Removed),
+            "changed": count(ChangeKind::Changed),
+            "unchanged": report.unchanged,
+        },
+        "files": files,
+        "tables": tables,
+        "table_errors": errors,
+    });
+    Ok(serde_json::to_string_pretty(&document)? + "\n")
+}
+
+#[cfg(not(feature = "serde"))]
+fn render_json(_report: &Report) -> Result<String> {
+    anyhow::bail!("JSON output requires the 'serde' feature to be enabled");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(
+            DataKind::classify("DBFilesClient\\Spell.dbc"),
+            DataKind::Dbc
+        );
+        assert_eq!(
+            DataKind::classify("Interface/Icons/INV_Sword_01.blp"),
+            DataKind::Interface
+        );
+        assert_eq!(
+            DataKind::classify("World\\wmo\\Azeroth\\Stormwind.wmo"),
+            DataKind::Maps
+        );
+        assert_eq!(
+            DataKind::classify("Creature\\Wolf\\Wolf00.skin"),
+            DataKind::Models
+        );
+        assert_eq!(DataKind::classify("Textures\\Sky.blp"), DataKind::Textures);
+        assert_eq!(
+            DataKind::classify("Sound\\Music\\Zone.mp3"),
+            DataKind::Sound
+        );
+        assert_eq!(DataKind::classify("Fonts\\FRIZQT__.TTF"), DataKind::Other);
+    }
+}
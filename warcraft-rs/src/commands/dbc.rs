@@ -7,10 +7,14 @@ use std::io::{self, BufReader, BufWriter};
 use std::path::{Path, PathBuf};
 use std::time::Instant;
 use wow_cdbc::{
-    DbcParser, DbcWriter, RecordSet, SchemaDefinition, SchemaDiscoverer, Value, export_to_csv,
-    export_to_json, import_from_json,
+    DbcParser, DbcWriter, Query, QueryResult, RecordSet, SchemaDefinition, SchemaDiscoverer,
+    TranslationEntry, Value, Wdb2Options, apply_translations, export_to_csv, export_to_json,
+    export_to_parquet, export_to_sql, extract_translations, import_from_json, merge_locales,
+    read_po, read_translations_csv, write_merged_csv, write_po, write_translations_csv,
 };
 
+use crate::utils::{add_table_row, create_table};
+
 #[cfg(feature = "yaml")]
 use wow_cdbc::FieldType;
 
@@ -46,12 +50,39 @@ pub enum DbcCommands {
         limit: usize,
     },
 
+    /// Query records with a filter expression
+    Query {
+        /// Path to the DBC file
+        file: PathBuf,
+
+        /// Path to the schema YAML file (optional, fields are field_N without it)
+        #[arg(short, long)]
+        schema: Option<PathBuf>,
+
+        /// Filter expression, e.g. "SpellLevel > 40 AND Name ~ 'fire'"
+        #[arg(short = 'w', long = "where")]
+        filter: Option<String>,
+
+        /// Comma-separated list of columns to show (all if not specified)
+        #[arg(long, value_delimiter = ',')]
+        select: Vec<String>,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "table")]
+        format: QueryFormat,
+
+        /// Maximum number of records to return
+        #[arg(short, long)]
+        limit: Option<usize>,
+    },
+
     /// Export DBC data to various formats
     Export {
-        /// Path to the DBC file
+        /// Path to the DBC file, or a DBFilesClient directory with --to
         file: PathBuf,
 
-        /// Path to the schema YAML file
+        /// Path to the schema YAML file, or a directory of `<Table>.yaml`
+        /// schemas when exporting a directory
         #[arg(short, long)]
         schema: PathBuf,
 
@@ -60,8 +91,13 @@ pub enum DbcCommands {
         format: ExportFormat,
 
         /// Output file (stdout if not specified)
-        #[arg(short, long)]
+        #[arg(short, long, conflicts_with = "to")]
         output: Option<PathBuf>,
+
+        /// Export to a typed table store instead: `sqlite <database>` or
+        /// `parquet <directory>`
+        #[arg(long, num_args = 2, value_names = ["FORMAT", "PATH"])]
+        to: Option<Vec<String>>,
     },
 
     /// Import DBC data from a JSON file
@@ -76,6 +112,85 @@ pub enum DbcCommands {
         /// Output DBC file path
         #[arg(short, long)]
         output: PathBuf,
+
+        /// Write records in ascending order of the schema's key field
+        #[arg(long)]
+        sort_by_key: bool,
+
+        /// Write a WDB2 file for this client build, with an ID index after 12880
+        #[arg(long, value_name = "BUILD")]
+        wdb2: Option<u32>,
+
+        /// Table hash of the WDB2 header
+        #[arg(long, requires = "wdb2")]
+        table_hash: Option<u32>,
+    },
+
+    /// Export localized strings for translation as a gettext .po or a .csv file
+    ExportStrings {
+        /// Path to the DBC file
+        file: PathBuf,
+
+        /// Path to the schema YAML file
+        #[arg(short, long)]
+        schema: PathBuf,
+
+        /// Locale of the strings to translate
+        #[arg(long, default_value = "enUS")]
+        source: String,
+
+        /// Locale to translate into (e.g. deDE, frFR, ruRU)
+        #[arg(long)]
+        target: String,
+
+        /// Output file; the format follows the extension (.po or .csv)
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+
+    /// Write translated strings from a .po or .csv file back into a DBC
+    ImportStrings {
+        /// Path to the DBC file
+        file: PathBuf,
+
+        /// Path to the schema YAML file
+        #[arg(short, long)]
+        schema: PathBuf,
+
+        /// Translations written by `dbc export-strings`
+        #[arg(short, long)]
+        translations: PathBuf,
+
+        /// Locale the translations were made from
+        #[arg(long, default_value = "enUS")]
+        source: String,
+
+        /// Locale to write the translations into
+        #[arg(long)]
+        target: String,
+
+        /// Output DBC file path
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+
+    /// Compare the localized strings of a table across client locales
+    CompareLocales {
+        /// Table path inside the archives, e.g. DBFilesClient\\Spell.dbc
+        table: String,
+
+        /// Path to the schema YAML file
+        #[arg(short, long)]
+        schema: PathBuf,
+
+        /// Locale and the MPQ archive or DBC file with its copy of the table,
+        /// as LOCALE=PATH; repeat for each locale
+        #[arg(short, long = "locale", value_parser = parse_locale_source, required = true)]
+        locales: Vec<LocaleSource>,
+
+        /// Write every record's strings in each locale to a CSV file
+        #[arg(short, long)]
+        output: Option<PathBuf>,
     },
 
     /// Analyze a DBC file for performance and structure
@@ -129,8 +244,9 @@ pub enum DbcCommands {
         #[arg(short, long)]
         yaml: bool,
 
-        /// Output file path for the generated schema
-        #[arg(short, long)]
+        /// Write the schema as YAML to this file (implies --yaml), ready for
+        /// `dbc export`/`dbc query --schema`
+        #[arg(short = 'o', long = "output", alias = "out")]
         output: Option<PathBuf>,
     },
 }
@@ -141,7 +257,14 @@ pub enum ExportFormat {
     Csv,
 }
 
-pub fn execute(command: DbcCommands) -> Result<()> {
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+pub enum QueryFormat {
+    Table,
+    Json,
+    Csv,
+}
+
+pub async fn execute(command: DbcCommands) -> Result<()> {
     match command {
         DbcCommands::Info { file } => info_command(&file),
         DbcCommands::List {
@@ -149,17 +272,70 @@ pub fn execute(command: DbcCommands) -> Result<()> {
             schema,
             limit,
         } => list_command(&file, schema.as_deref(), limit),
+        DbcCommands::Query {
+            file,
+            schema,
+            filter,
+            select,
+            format,
+            limit,
+        } => query_command(
+            &file,
+            schema.as_deref(),
+            filter.as_deref(),
+            select,
+            format,
+            limit,
+        ),
+        DbcCommands::Export {
+            file,
+            schema,
+            to: Some(to),
+            ..
+        } => export_tables_command(&file, &schema, ExportTarget::parse(&to)?).await,
         DbcCommands::Export {
             file,
             schema,
             format,
             output,
+            to: None,
         } => export_command(&file, &schema, format, output.as_deref()),
         DbcCommands::Import {
             file,
             schema,
             output,
-        } => import_command(&file, &schema, &output),
+            sort_by_key,
+            wdb2,
+            table_hash,
+        } => {
+            let wdb2 = wdb2.map(|build| Wdb2Options {
+                build,
+                table_hash: table_hash.unwrap_or(0),
+                ..Default::default()
+            });
+            import_command(&file, &schema, &output, sort_by_key, wdb2)
+        }
+        DbcCommands::ExportStrings {
+            file,
+            schema,
+            source,
+            target,
+            output,
+        } => export_strings_command(&file, &schema, &source, &target, &output),
+        DbcCommands::ImportStrings {
+            file,
+            schema,
+            translations,
+            source,
+            target,
+            output,
+        } => import_strings_command(&file, &schema, &translations, &source, &target, &output),
+        DbcCommands::CompareLocales {
+            table,
+            schema,
+            locales,
+            output,
+        } => compare_locales_command(&table, &schema, &locales, output.as_deref()),
         DbcCommands::Analyze {
             file,
             schema,
@@ -333,23 +509,167 @@ fn list_command(file: &Path, schema_path: Option<&Path>, limit: usize) -> Result
     Ok(())
 }
 
-/// Export DBC data to file or stdout
-fn export_command(
+/// Query records from a DBC file
+fn query_command(
     file: &Path,
-    schema_path: &Path,
-    format: ExportFormat,
-    output_path: Option<&Path>,
+    schema_path: Option<&Path>,
+    filter: Option<&str>,
+    select: Vec<String>,
+    format: QueryFormat,
+    limit: Option<usize>,
 ) -> Result<()> {
     let dbc_file =
         File::open(file).with_context(|| format!("Failed to open DBC file: {}", file.display()))?;
     let mut reader = BufReader::new(dbc_file);
 
-    // Load schema
-    let schema_def = SchemaDefinition::from_yaml(schema_path)
-        .map_err(|e| anyhow::anyhow!("Failed to load schema {}: {}", schema_path.display(), e))?;
-    let schema = schema_def
-        .to_schema()
-        .map_err(|e| anyhow::anyhow!("Failed to convert schema definition: {}", e))?;
+    let mut parser = DbcParser::parse(&mut reader)
+        .with_context(|| format!("Failed to parse DBC file: {}", file.display()))?;
+
+    if let Some(schema_path) = schema_path {
+        let schema_def = SchemaDefinition::from_yaml(schema_path).map_err(|e| {
+            anyhow::anyhow!("Failed to load schema {}: {}", schema_path.display(), e)
+        })?;
+        let schema = schema_def
+            .to_schema()
+            .map_err(|e| anyhow::anyhow!("Failed to convert schema definition: {}", e))?;
+        parser = parser
+            .with_schema(schema)
+            .context("Failed to apply schema")?;
+    }
+
+    let record_set = parser.parse_records().context("Failed to parse records")?;
+
+    let mut query = Query::new().select(select);
+    if let Some(filter) = filter {
+        query = query.filter(filter).context("Failed to parse filter")?;
+    }
+    if let Some(limit) = limit {
+        query = query.limit(limit);
+    }
+    let result = query.execute(&record_set).context("Query failed")?;
+
+    match format {
+        QueryFormat::Table => {
+            let mut table = create_table(result.columns().iter().map(String::as_str).collect());
+            for row in result.rows() {
+                let cells = row
+                    .iter()
+                    .map(|value| value.map_or_else(String::new, |v| format_cell(v, &record_set)))
+                    .collect();
+                add_table_row(&mut table, cells);
+            }
+            table.printstd();
+            println!("{} record(s)", result.len());
+        }
+        QueryFormat::Json => print_query_json(&result)?,
+        QueryFormat::Csv => {
+            println!(
+                "{}",
+                result
+                    .columns()
+                    .iter()
+                    .map(|c| csv_escape(c))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            );
+            for row in result.rows() {
+                let line = row
+                    .iter()
+                    .map(|value| {
+                        csv_escape(&value.map_or_else(String::new, |v| format_cell(v, &record_set)))
+                    })
+                    .collect::<Vec<_>>()
+                    .join(",");
+                println!("{line}");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Render a value as plain text with string references resolved
+pub(crate) fn format_cell(value: &Value, record_set: &RecordSet) -> String {
+    match value {
+        Value::StringRef(v) => record_set
+            .get_string(*v)
+            .map(str::to_string)
+            .unwrap_or_else(|_| format!("<Invalid string ref: {}>", v.offset())),
+        Value::Array(vals) => format!(
+            "[{}]",
+            vals.iter()
+                .map(|v| format_cell(v, record_set))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        other => other.to_string(),
+    }
+}
+
+fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(feature = "serde")]
+fn query_value_json(value: &Value, record_set: &RecordSet) -> serde_json::Value {
+    match value {
+        Value::Int32(v) => serde_json::json!(v),
+        Value::UInt32(v) => serde_json::json!(v),
+        Value::Float32(v) => serde_json::json!(v),
+        Value::StringRef(_) => serde_json::json!(format_cell(value, record_set)),
+        Value::Bool(v) => serde_json::json!(v),
+        Value::UInt8(v) => serde_json::json!(v),
+        Value::Int8(v) => serde_json::json!(v),
+        Value::UInt16(v) => serde_json::json!(v),
+        Value::Int16(v) => serde_json::json!(v),
+        Value::Array(vals) => serde_json::Value::Array(
+            vals.iter()
+                .map(|v| query_value_json(v, record_set))
+                .collect(),
+        ),
+    }
+}
+
+#[cfg(feature = "serde")]
+fn print_query_json(result: &QueryResult<'_>) -> Result<()> {
+    let record_set = result.record_set();
+    let rows: Vec<serde_json::Value> = result
+        .rows()
+        .map(|row| {
+            let object = result
+                .columns()
+                .iter()
+                .zip(row)
+                .map(|(column, value)| {
+                    (
+                        column.clone(),
+                        value.map_or(serde_json::Value::Null, |v| query_value_json(v, record_set)),
+                    )
+                })
+                .collect();
+            serde_json::Value::Object(object)
+        })
+        .collect();
+
+    println!("{}", serde_json::to_string_pretty(&rows)?);
+    Ok(())
+}
+
+#[cfg(not(feature = "serde"))]
+fn print_query_json(_result: &QueryResult<'_>) -> Result<()> {
+    anyhow::bail!("JSON output requires the 'serde' feature to be enabled");
+}
+
+/// Parse a DBC file with the schema from a YAML file
+fn load_records(file: &Path, schema_path: &Path) -> Result<RecordSet> {
+    let dbc_file =
+        File::open(file).with_context(|| format!("Failed to open DBC file: {}", file.display()))?;
+    let mut reader = BufReader::new(dbc_file);
+    let schema = load_schema(schema_path)?;
 
     // Parse DBC file with schema
     let parser = DbcParser::parse(&mut reader)
@@ -357,7 +677,25 @@ fn export_command(
     let parser = parser
         .with_schema(schema)
         .context("Failed to apply schema")?;
-    let record_set = parser.parse_records().context("Failed to parse records")?;
+    parser.parse_records().context("Failed to parse records")
+}
+
+fn load_schema(schema_path: &Path) -> Result<wow_cdbc::Schema> {
+    let schema_def = SchemaDefinition::from_yaml(schema_path)
+        .map_err(|e| anyhow::anyhow!("Failed to load schema {}: {}", schema_path.display(), e))?;
+    schema_def
+        .to_schema()
+        .map_err(|e| anyhow::anyhow!("Failed to convert schema definition: {}", e))
+}
+
+/// Export DBC data to file or stdout
+fn export_command(
+    file: &Path,
+    schema_path: &Path,
+    format: ExportFormat,
+    output_path: Option<&Path>,
+) -> Result<()> {
+    let record_set = load_records(file, schema_path)?;
 
     // Export to output
     match output_path {
@@ -404,8 +742,382 @@ fn export_command(
     Ok(())
 }
 
+/// Table store given with `dbc export --to`
+#[derive(Debug, PartialEq)]
+enum ExportTarget {
+    /// One SQLite database holding a table per DBC
+    Sqlite(PathBuf),
+    /// A directory with one `<Table>.parquet` file per DBC
+    Parquet(PathBuf),
+}
+
+impl ExportTarget {
+    fn parse(args: &[String]) -> Result<Self> {
+        let [format, path] = args else {
+            anyhow::bail!("--to expects a format and a path");
+        };
+        match format.to_ascii_lowercase().as_str() {
+            "sqlite" => Ok(Self::Sqlite(PathBuf::from(path))),
+            "parquet" => Ok(Self::Parquet(PathBuf::from(path))),
+            other => anyhow::bail!("Unknown export target '{other}' (expected sqlite or parquet)"),
+        }
+    }
+}
+
+/// DBC files of a directory paired with their schemas
+///
+/// Tables without a schema are reported and left out.
+fn batch_jobs(dir: &Path, schema_dir: &Path) -> Result<Vec<(String, PathBuf, PathBuf)>> {
+    if !schema_dir.is_dir() {
+        anyhow::bail!(
+            "Exporting a directory needs a schema directory, got {}",
+            schema_dir.display()
+        );
+    }
+    let schemas: Vec<PathBuf> = std::fs::read_dir(schema_dir)
+        .with_context(|| format!("Failed to read {}", schema_dir.display()))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| e.eq_ignore_ascii_case("yaml") || e.eq_ignore_ascii_case("yml"))
+        })
+        .collect();
+
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to read {}", dir.display()))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| e.eq_ignore_ascii_case("dbc"))
+        })
+        .collect();
+    files.sort();
+
+    let mut jobs = Vec::new();
+    for file in files {
+        let table = table_name(&file);
+        let schema = schemas.iter().find(|s| {
+            s.file_stem()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.eq_ignore_ascii_case(&table))
+        });
+        match schema {
+            Some(schema) => jobs.push((table, file, schema.clone())),
+            None => eprintln!("⚠ Skipping {table}: no schema in {}", schema_dir.display()),
+        }
+    }
+    Ok(jobs)
+}
+
+fn table_name(file: &Path) -> String {
+    file.file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "dbc".to_string())
+}
+
+/// Export one DBC, or every DBC of a directory, to SQLite or Parquet
+async fn export_tables_command(
+    file: &Path,
+    schema_path: &Path,
+    target: ExportTarget,
+) -> Result<()> {
+    let batch = file.is_dir();
+    let jobs = if batch {
+        batch_jobs(file, schema_path)?
+    } else {
+        vec![(
+            table_name(file),
+            file.to_path_buf(),
+            schema_path.to_path_buf(),
+        )]
+    };
+
+    let (db, conn) = match &target {
+        ExportTarget::Sqlite(path) => {
+            if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                std::fs::create_dir_all(parent)?;
+            }
+            let db = turso::Builder::new_local(&path.to_string_lossy())
+                .build()
+                .await
+                .with_context(|| format!("Failed to open database: {}", path.display()))?;
+            let conn = db.connect()?;
+            (Some(db), Some(conn))
+        }
+        ExportTarget::Parquet(dir) => {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+            (None, None)
+        }
+    };
+
+    let start = Instant::now();
+    let mut tables = 0;
+    let mut records = 0;
+    let mut failed = 0;
+    for (table, file, schema) in &jobs {
+        let result = async {
+            let record_set = load_records(file, schema)?;
+            match (&target, &conn) {
+                (ExportTarget::Sqlite(_), Some(conn)) => {
+                    let mut sql = Vec::new();
+                    export_to_sql(&record_set, table, &mut sql)?;
+                    conn.execute_batch(&String::from_utf8(sql)?)
+                        .await
+                        .with_context(|| format!("Failed to write table {table}"))?;
+                }
+                (ExportTarget::Parquet(dir), _) => {
+                    let path = dir.join(format!("{table}.parquet"));
+                    let output = File::create(&path)
+                        .with_context(|| format!("Failed to create {}", path.display()))?;
+                    export_to_parquet(&record_set, BufWriter::new(output))
+                        .with_context(|| format!("Failed to write {}", path.display()))?;
+                }
+                _ => unreachable!("SQLite export without a connection"),
+            }
+            anyhow::Ok(record_set.len())
+        }
+        .await;
+
+        match result {
+            Ok(count) => {
+                tables += 1;
+                records += count;
+                if batch {
+                    println!("  {table}: {count} records");
+                }
+            }
+            Err(e) if batch => {
+                failed += 1;
+                eprintln!("✗ {table}: {e:#}");
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    drop(conn);
+    drop(db);
+
+    let (kind, path) = match &target {
+        ExportTarget::Sqlite(path) => ("SQLite", path),
+        ExportTarget::Parquet(dir) => ("Parquet", dir),
+    };
+    println!(
+        "Exported {tables} table(s), {records} records to {kind}: {} ({:.2?})",
+        path.display(),
+        start.elapsed()
+    );
+    if failed > 0 {
+        anyhow::bail!("{failed} table(s) failed to export");
+    }
+    Ok(())
+}
+
+/// Whether a translation file is CSV rather than gettext PO, by extension
+fn is_csv(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"))
+}
+
+/// Export the localized strings of a DBC for translation
+fn export_strings_command(
+    file: &Path,
+    schema_path: &Path,
+    source: &str,
+    target: &str,
+    output: &Path,
+) -> Result<()> {
+    let record_set = load_records(file, schema_path)?;
+    let entries = extract_translations(&record_set, source, target)
+        .context("Failed to collect localized strings")?;
+
+    let output_file = File::create(output)
+        .with_context(|| format!("Failed to create output file: {}", output.display()))?;
+    let writer = BufWriter::new(output_file);
+    if is_csv(output) {
+        write_translations_csv(&entries, writer)
+    } else {
+        let table = file
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default();
+        write_po(&entries, &table, target, writer)
+    }
+    .with_context(|| format!("Failed to write {}", output.display()))?;
+
+    let translated = entries.iter().filter(|e| !e.translation.is_empty()).count();
+    println!(
+        "Exported {} strings ({translated} already in {target}) to {}",
+        entries.len(),
+        output.display()
+    );
+    Ok(())
+}
+
+/// Write translated strings back into a DBC
+fn import_strings_command(
+    file: &Path,
+    schema_path: &Path,
+    translations: &Path,
+    source: &str,
+    target: &str,
+    output: &Path,
+) -> Result<()> {
+    let record_set = load_records(file, schema_path)?;
+
+    let input = File::open(translations)
+        .with_context(|| format!("Failed to open {}", translations.display()))?;
+    let reader = BufReader::new(input);
+    let entries: Vec<TranslationEntry> = if is_csv(translations) {
+        read_translations_csv(reader)
+    } else {
+        read_po(reader)
+    }
+    .with_context(|| format!("Failed to read {}", translations.display()))?;
+
+    let result = apply_translations(&record_set, &entries, source, target)
+        .context("Failed to apply translations")?;
+
+    let output_file = File::create(output)
+        .with_context(|| format!("Failed to create output file: {}", output.display()))?;
+    DbcWriter::new(BufWriter::new(output_file))
+        .write_records(&result.record_set)
+        .with_context(|| format!("Failed to write DBC file: {}", output.display()))?;
+
+    println!(
+        "Applied {} translations to {}",
+        result.applied,
+        output.display()
+    );
+    if result.outdated > 0 {
+        println!(
+            "Skipped {} translations whose {source} string changed since export",
+            result.outdated
+        );
+    }
+    if result.unmatched > 0 {
+        println!(
+            "Skipped {} translations for unknown records or fields",
+            result.unmatched
+        );
+    }
+    Ok(())
+}
+
+/// A client locale and the MPQ archive or DBC file holding its copy of a table
+#[derive(Debug, Clone)]
+pub struct LocaleSource {
+    locale: String,
+    path: PathBuf,
+}
+
+fn parse_locale_source(value: &str) -> std::result::Result<LocaleSource, String> {
+    match value.split_once('=') {
+        Some((locale, path)) if !locale.is_empty() && !path.is_empty() => Ok(LocaleSource {
+            locale: locale.to_string(),
+            path: PathBuf::from(path),
+        }),
+        _ => Err(format!("expected LOCALE=PATH, got '{value}'")),
+    }
+}
+
+/// Read one locale's copy of a table from its archive or DBC file
+fn load_locale_records(
+    source: &LocaleSource,
+    table: &str,
+    schema: &wow_cdbc::Schema,
+) -> Result<RecordSet> {
+    let path = &source.path;
+    let is_dbc = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("dbc"));
+    let data = if is_dbc {
+        std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?
+    } else {
+        wow_mpq::Archive::open(path)
+            .with_context(|| format!("Failed to open archive: {}", path.display()))?
+            .read_file(table)
+            .with_context(|| format!("Failed to read {table} from {}", path.display()))?
+    };
+
+    DbcParser::parse_bytes(&data)
+        .and_then(|parser| parser.with_schema(schema.clone()))
+        .and_then(|parser| parser.parse_records())
+        .with_context(|| format!("Failed to parse {table} for {}", source.locale))
+}
+
+/// Compare the localized strings of a table across client locales
+fn compare_locales_command(
+    table: &str,
+    schema_path: &Path,
+    sources: &[LocaleSource],
+    output: Option<&Path>,
+) -> Result<()> {
+    let schema = load_schema(schema_path)?;
+    let record_sets = sources
+        .iter()
+        .map(|source| load_locale_records(source, table, &schema))
+        .collect::<Result<Vec<_>>>()?;
+    let pairs: Vec<(&str, &RecordSet)> = sources
+        .iter()
+        .zip(&record_sets)
+        .map(|(source, record_set)| (source.locale.as_str(), record_set))
+        .collect();
+    let merged = merge_locales(&pairs).context("Failed to merge locales")?;
+
+    // Strings another locale has but this one lacks
+    let mut strings = vec![0usize; sources.len()];
+    let mut missing = vec![0usize; sources.len()];
+    for field in merged.records.iter().flat_map(|record| &record.fields) {
+        if field.strings.iter().all(Option::is_none) {
+            continue;
+        }
+        for (index, text) in field.strings.iter().enumerate() {
+            match text {
+                Some(_) => strings[index] += 1,
+                None => missing[index] += 1,
+            }
+        }
+    }
+
+    println!(
+        "{table}: {} records across {} locales",
+        merged.records.len(),
+        sources.len()
+    );
+    let mut summary = create_table(vec!["Locale", "Records", "Strings", "Missing"]);
+    for (index, source) in sources.iter().enumerate() {
+        add_table_row(
+            &mut summary,
+            vec![
+                source.locale.clone(),
+                record_sets[index].len().to_string(),
+                strings[index].to_string(),
+                missing[index].to_string(),
+            ],
+        );
+    }
+    summary.printstd();
+
+    if let Some(output) = output {
+        let output_file = File::create(output)
+            .with_context(|| format!("Failed to create output file: {}", output.display()))?;
+        write_merged_csv(&merged, BufWriter::new(output_file))
+            .with_context(|| format!("Failed to write {}", output.display()))?;
+        println!("Merged strings written to {}", output.display());
+    }
+    Ok(())
+}
+
 /// Import DBC data from a JSON file using a schema
-fn import_command(file: &Path, schema_path: &Path, output: &Path) -> Result<()> {
+fn import_command(
+    file: &Path,
+    schema_path: &Path,
+    output: &Path,
+    sort_by_key: bool,
+    wdb2: Option<Wdb2Options>,
+) -> Result<()> {
     // Load schema
     let schema_def = SchemaDefinition::from_yaml(schema_path)
         .map_err(|e| anyhow::anyhow!("Failed to load schema {}: {}", schema_path.display(), e))?;
@@ -429,7 +1141,10 @@ fn import_command(file: &Path, schema_path: &Path, output: &Path) -> Result<()>
         .with_context(|| format!("Failed to create output file: {}", output.display()))?;
     let writer = BufWriter::new(output_file);
 
-    let mut dbc_writer = DbcWriter::new(writer);
+    let mut dbc_writer = DbcWriter::new(writer).sort_by_key(sort_by_key);
+    if let Some(options) = wdb2 {
+        dbc_writer = dbc_writer.wdb2(options);
+    }
     dbc_writer
         .write_records(&record_set)
         .with_context(|| format!("Failed to write DBC file: {}", output.display()))?;
@@ -768,71 +1483,16 @@ fn discover_command(
         .generate_schema(&file_stem)
         .context("Failed to generate schema")?;
 
-    // Output schema
-    if yaml {
+    // Output schema; writing to a file always produces YAML
+    if yaml || output_path.is_some() {
         #[cfg(feature = "yaml")]
         {
-            use serde::{Deserialize, Serialize};
-
-            // Convert to YAML-compatible structures
-            #[derive(Serialize, Deserialize)]
-            struct YamlSchemaField {
-                name: String,
-                type_name: String,
-                #[serde(skip_serializing_if = "Option::is_none")]
-                is_array: Option<bool>,
-                #[serde(skip_serializing_if = "Option::is_none")]
-                array_size: Option<usize>,
-            }
-
-            #[derive(Serialize, Deserialize)]
-            struct YamlSchema {
-                name: String,
-                #[serde(skip_serializing_if = "Option::is_none")]
-                key_field: Option<String>,
-                fields: Vec<YamlSchemaField>,
-            }
+            let yaml_content = discovered_schema_yaml(file, &schema, &discovered.fields)?;
 
-            let mut fields = Vec::new();
-            for field in schema.fields.iter() {
-                let type_name = match field.field_type {
-                    FieldType::Int32 => "Int32",
-                    FieldType::UInt32 => "UInt32",
-                    FieldType::Float32 => "Float32",
-                    FieldType::String => "String",
-                    FieldType::Bool => "Bool",
-                    FieldType::UInt8 => "UInt8",
-                    FieldType::Int8 => "Int8",
-                    FieldType::UInt16 => "UInt16",
-                    FieldType::Int16 => "Int16",
-                };
-
-                fields.push(YamlSchemaField {
-                    name: field.name.clone(),
-                    type_name: type_name.to_string(),
-                    is_array: if field.is_array { Some(true) } else { None },
-                    array_size: field.array_size,
-                });
-            }
-
-            let key_field = if let Some(key_index) = schema.key_field_index {
-                if key_index < schema.fields.len() {
-                    Some(schema.fields[key_index].name.clone())
-                } else {
-                    None
-                }
-            } else {
-                None
-            };
-
-            let yaml_schema = YamlSchema {
-                name: schema.name.clone(),
-                key_field,
-                fields,
-            };
-
-            let yaml_content = serde_yaml_ng::to_string(&yaml_schema)
-                .context("Failed to serialize schema to YAML")?;
+            // The generated file must load back the same way `--schema` does
+            SchemaDefinition::from_yaml_str(&yaml_content)
+                .and_then(|def| def.to_schema())
+                .map_err(|e| anyhow::anyhow!("Generated schema does not load back: {e}"))?;
 
             // Output to file or stdout
             if let Some(output_path) = output_path {
@@ -925,3 +1585,115 @@ fn discover_command(
 
     Ok(())
 }
+
+/// Serialize a discovered schema in the `--schema` YAML format, annotated with
+/// the discoverer's confidence for each field
+#[cfg(feature = "yaml")]
+fn discovered_schema_yaml(
+    file: &Path,
+    schema: &wow_cdbc::Schema,
+    discovered: &[wow_cdbc::DiscoveredField],
+) -> Result<String> {
+    use serde::Serialize;
+    use wow_cdbc::Confidence;
+
+    // Extra keys (confidence, key_candidate) are ignored when the schema is loaded
+    #[derive(Serialize)]
+    struct YamlSchemaField {
+        name: String,
+        type_name: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        is_array: Option<bool>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        array_size: Option<usize>,
+        confidence: &'static str,
+        #[serde(skip_serializing_if = "std::ops::Not::not")]
+        key_candidate: bool,
+    }
+
+    #[derive(Serialize)]
+    struct YamlSchema {
+        name: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        key_field: Option<String>,
+        fields: Vec<YamlSchemaField>,
+    }
+
+    let fields = schema
+        .fields
+        .iter()
+        .zip(discovered)
+        .map(|(field, found)| {
+            let type_name = match field.field_type {
+                FieldType::Int32 => "Int32",
+                FieldType::UInt32 => "UInt32",
+                FieldType::Float32 => "Float32",
+                FieldType::String => "String",
+                FieldType::Bool => "Bool",
+                FieldType::UInt8 => "UInt8",
+                FieldType::Int8 => "Int8",
+                FieldType::UInt16 => "UInt16",
+                FieldType::Int16 => "Int16",
+            };
+            YamlSchemaField {
+                name: field.name.clone(),
+                type_name: type_name.to_string(),
+                is_array: field.is_array.then_some(true),
+                array_size: field.array_size,
+                confidence: match found.confidence {
+                    Confidence::High => "high",
+                    Confidence::Medium => "medium",
+                    Confidence::Low => "low",
+                },
+                key_candidate: found.is_key_candidate,
+            }
+        })
+        .collect();
+
+    let yaml_schema = YamlSchema {
+        name: schema.name.clone(),
+        key_field: schema
+            .key_field_index
+            .and_then(|i| schema.fields.get(i))
+            .map(|f| f.name.clone()),
+        fields,
+    };
+
+    let body =
+        serde_yaml_ng::to_string(&yaml_schema).context("Failed to serialize schema to YAML")?;
+    Ok(format!(
+        "# Discovered by `warcraft-rs dbc discover` from {}\n\
+         # Field names are placeholders; review fields with low confidence before relying on them\n\
+         {body}",
+        file.file_name().unwrap_or_default().to_string_lossy()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{Cli, Commands};
+    use clap::Parser;
+
+    #[test]
+    fn test_discover_output_flags() {
+        for flag in ["-o", "--output", "--out"] {
+            let cli = Cli::try_parse_from([
+                "warcraft-rs",
+                "dbc",
+                "discover",
+                "Spell.dbc",
+                flag,
+                "schema.yaml",
+            ])
+            .unwrap();
+            let Commands::Dbc {
+                command: DbcCommands::Discover { output, .. },
+            } = cli.command
+            else {
+                panic!("{flag} did not parse as dbc discover");
+            };
+            assert_eq!(output, Some(PathBuf::from("schema.yaml")));
+        }
+    }
+}
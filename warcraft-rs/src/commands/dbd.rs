@@ -1,9 +1,22 @@
 //! DBD (Database Definition) file handling commands
+//!
+//! Besides converting single DBD files, these commands manage a local clone of
+//! the WoWDBDefs repository so schemas and record structs can be generated by
+//! table name and build without downloading definitions by hand.
 
+use anyhow::Context;
 use clap::Subcommand;
+use directories::ProjectDirs;
 use std::fs;
 use std::path::{Path, PathBuf};
-use wow_cdbc::dbd::{convert_to_yaml_schemas, parse_dbd_file};
+use std::process::Command;
+use wow_cdbc::dbd::{
+    DbdFile, convert_to_yaml_schemas, generate_rust_struct, parse_dbd_file, yaml_schema_for_build,
+};
+use wow_cdbc::{Db2Hashes, table_hash};
+
+/// Upstream repository holding the definitions
+const WOWDBDEFS_URL: &str = "https://github.com/wowdev/WoWDBDefs";
 
 #[derive(Debug, Subcommand)]
 pub enum DbdCommand {
@@ -25,6 +38,72 @@ pub enum DbdCommand {
         #[arg(short, long, default_value_t = false)]
         all: bool,
     },
+
+    /// Download or update the WoWDBDefs definitions cache
+    Fetch {
+        /// Cache directory (defaults to the user cache directory)
+        #[arg(long, value_name = "DIR")]
+        cache: Option<PathBuf>,
+    },
+
+    /// List cached definitions, optionally only those covering a build
+    List {
+        /// Only list tables with a definition for this build (e.g., "3.3.5.12340" or "3.3.5")
+        #[arg(long)]
+        build: Option<String>,
+
+        /// Cache directory (defaults to the user cache directory)
+        #[arg(long, value_name = "DIR")]
+        cache: Option<PathBuf>,
+    },
+
+    /// Generate YAML schemas for cached definitions
+    Schema {
+        /// Table names (e.g., "Spell"); all tables covering the build if omitted
+        #[arg(value_name = "TABLE")]
+        tables: Vec<String>,
+
+        /// Target build (e.g., "3.3.5.12340" or "3.3.5")
+        #[arg(long)]
+        build: String,
+
+        /// Output directory for YAML files
+        #[arg(short, long, default_value = "./schemas")]
+        output: PathBuf,
+
+        /// Cache directory (defaults to the user cache directory)
+        #[arg(long, value_name = "DIR")]
+        cache: Option<PathBuf>,
+    },
+
+    /// Generate Rust record structs for cached definitions
+    Structs {
+        /// Table names (e.g., "Spell"); all tables covering the build if omitted
+        #[arg(value_name = "TABLE")]
+        tables: Vec<String>,
+
+        /// Target build (e.g., "3.3.5.12340" or "3.3.5")
+        #[arg(long)]
+        build: String,
+
+        /// Output file (prints to stdout if omitted)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Cache directory (defaults to the user cache directory)
+        #[arg(long, value_name = "DIR")]
+        cache: Option<PathBuf>,
+    },
+
+    /// Identify a DB2 file's table and layout from the hashes in its header
+    Identify {
+        /// Path to the DB2 file
+        file: PathBuf,
+
+        /// Cache directory (defaults to the user cache directory)
+        #[arg(long, value_name = "DIR")]
+        cache: Option<PathBuf>,
+    },
 }
 
 impl DbdCommand {
@@ -37,8 +116,276 @@ impl DbdCommand {
                 version,
                 all,
             } => convert_dbd_to_yaml(file, output, version.as_deref(), *all),
+            DbdCommand::Fetch { cache } => fetch_definitions(&cache_dir(cache.as_deref())?),
+            DbdCommand::List { build, cache } => {
+                list_definitions(&cache_dir(cache.as_deref())?, build.as_deref())
+            }
+            DbdCommand::Schema {
+                tables,
+                build,
+                output,
+                cache,
+            } => generate_schemas(&cache_dir(cache.as_deref())?, tables, build, output),
+            DbdCommand::Structs {
+                tables,
+                build,
+                output,
+                cache,
+            } => generate_structs(
+                &cache_dir(cache.as_deref())?,
+                tables,
+                build,
+                output.as_deref(),
+            ),
+            DbdCommand::Identify { file, cache } => {
+                identify_table(&cache_dir(cache.as_deref())?, file)
+            }
+        }
+    }
+}
+
+/// Location of the WoWDBDefs clone
+fn cache_dir(cache: Option<&Path>) -> anyhow::Result<PathBuf> {
+    if let Some(cache) = cache {
+        return Ok(cache.to_path_buf());
+    }
+    ProjectDirs::from("network", "kogito", "warcraft-rs")
+        .map(|dirs| dirs.cache_dir().join("WoWDBDefs"))
+        .context("Could not determine a cache directory; pass --cache")
+}
+
+fn run_git(args: &[&str], dir: Option<&Path>) -> anyhow::Result<()> {
+    let mut command = Command::new("git");
+    if let Some(dir) = dir {
+        command.current_dir(dir);
+    }
+    let status = command
+        .args(args)
+        .status()
+        .context("Failed to run git; it must be installed to fetch definitions")?;
+    if !status.success() {
+        anyhow::bail!("git {} failed with {status}", args.join(" "));
+    }
+    Ok(())
+}
+
+/// Clone WoWDBDefs into the cache, or fast-forward an existing clone
+fn fetch_definitions(cache: &Path) -> anyhow::Result<()> {
+    if cache.join(".git").is_dir() {
+        println!("Updating definitions in {}", cache.display());
+        run_git(&["pull", "--ff-only", "--quiet"], Some(cache))?;
+    } else {
+        if cache.exists() && fs::read_dir(cache)?.next().is_some() {
+            anyhow::bail!(
+                "{} exists and is not a git checkout; remove it or pick another --cache",
+                cache.display()
+            );
+        }
+        if let Some(parent) = cache.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        println!("Cloning {WOWDBDEFS_URL} into {}", cache.display());
+        let target = cache.to_string_lossy();
+        run_git(
+            &["clone", "--depth", "1", "--quiet", WOWDBDEFS_URL, &target],
+            None,
+        )?;
+    }
+
+    let count = definition_files(cache)?.len();
+    println!("{count} definitions available");
+    Ok(())
+}
+
+/// All cached definition files as (table name, path), sorted by name
+fn definition_files(cache: &Path) -> anyhow::Result<Vec<(String, PathBuf)>> {
+    let dir = cache.join("definitions");
+    if !dir.is_dir() {
+        anyhow::bail!(
+            "No definitions found in {}; run `warcraft-rs dbd fetch` first",
+            cache.display()
+        );
+    }
+
+    let mut files = Vec::new();
+    for entry in fs::read_dir(&dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("dbd") {
+            continue;
+        }
+        if let Some(name) = path.file_stem().and_then(|s| s.to_str()) {
+            files.push((name.to_string(), path.clone()));
+        }
+    }
+    files.sort_by_key(|(name, _)| name.to_lowercase());
+    Ok(files)
+}
+
+/// Parse the requested tables, or every table covering `build` when none are named
+fn load_definitions(
+    cache: &Path,
+    tables: &[String],
+    build: &str,
+) -> anyhow::Result<Vec<(String, DbdFile)>> {
+    let files = definition_files(cache)?;
+    let parse = |path: &Path| {
+        parse_dbd_file(path)
+            .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))
+    };
+
+    if tables.is_empty() {
+        let mut definitions = Vec::new();
+        for (name, path) in files {
+            match parse(&path) {
+                Ok(dbd) if dbd.supports_build(build) => definitions.push((name, dbd)),
+                Ok(_) => {}
+                Err(e) => log::warn!("{e}"),
+            }
+        }
+        return Ok(definitions);
+    }
+
+    tables
+        .iter()
+        .map(|table| {
+            // Accept "Spell", "spell" and "Spell.dbc"
+            let wanted = table.trim_end_matches(".dbc").trim_end_matches(".db2");
+            let (name, path) = files
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(wanted))
+                .with_context(|| format!("No definition for table '{table}'"))?;
+            let dbd = parse(path)?;
+            if !dbd.supports_build(build) {
+                anyhow::bail!("{name} has no definition for build {build}");
+            }
+            Ok((name.clone(), dbd))
+        })
+        .collect()
+}
+
+fn list_definitions(cache: &Path, build: Option<&str>) -> anyhow::Result<()> {
+    let files = definition_files(cache)?;
+    let mut count = 0;
+    for (name, path) in &files {
+        if let Some(build) = build {
+            match parse_dbd_file(path) {
+                Ok(dbd) if dbd.supports_build(build) => {}
+                Ok(_) => continue,
+                Err(e) => {
+                    log::warn!("Failed to parse {}: {}", path.display(), e);
+                    continue;
+                }
+            }
+        }
+        println!("{name}");
+        count += 1;
+    }
+
+    println!();
+    match build {
+        Some(build) => println!("{count} of {} tables cover build {build}", files.len()),
+        None => println!("{count} tables"),
+    }
+    Ok(())
+}
+
+/// Match a DB2 file to its definition by table hash, then to a LAYOUT by layout hash
+fn identify_table(cache: &Path, file: &Path) -> anyhow::Result<()> {
+    let mut reader =
+        fs::File::open(file).with_context(|| format!("Failed to open {}", file.display()))?;
+    let hashes = Db2Hashes::read(&mut reader)
+        .with_context(|| format!("Failed to read the header of {}", file.display()))?;
+
+    println!("File: {}", file.display());
+    println!("Signature: {}", hashes.signature());
+    println!("Table hash: 0x{:08X}", hashes.table_hash);
+    if let Some(layout_hash) = hashes.layout_hash {
+        println!("Layout hash: 0x{layout_hash:08X}");
+    }
+
+    let files = definition_files(cache)?;
+    let Some((name, path)) = files
+        .iter()
+        .find(|(name, _)| table_hash(name) == hashes.table_hash)
+    else {
+        println!("Table: no cached definition matches the table hash");
+        return Ok(());
+    };
+    println!("Table: {name}");
+
+    let Some(layout_hash) = hashes.layout_hash else {
+        return Ok(());
+    };
+    let dbd = parse_dbd_file(path)
+        .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?;
+    match dbd.layout_for_hash(layout_hash) {
+        Some(layout) if layout.builds.is_empty() => {
+            println!("Layout: {} (no builds listed)", layout.hash)
+        }
+        Some(layout) => println!("Layout: {} ({})", layout.hash, layout.builds.join(", ")),
+        None => println!("Layout: not in the cached definition; run `warcraft-rs dbd fetch`"),
+    }
+    Ok(())
+}
+
+fn generate_schemas(
+    cache: &Path,
+    tables: &[String],
+    build: &str,
+    output: &Path,
+) -> anyhow::Result<()> {
+    let definitions = load_definitions(cache, tables, build)?;
+    fs::create_dir_all(output)
+        .with_context(|| format!("Failed to create output directory {}", output.display()))?;
+
+    let mut count = 0;
+    for (name, dbd) in &definitions {
+        let Some(yaml) = yaml_schema_for_build(dbd, name, build) else {
+            continue;
+        };
+        let path = output.join(format!("{name}.yaml"));
+        fs::write(&path, yaml).with_context(|| format!("Failed to write {}", path.display()))?;
+        println!("  Generated: {}", path.display());
+        count += 1;
+    }
+
+    println!();
+    println!("Total schemas generated for build {build}: {count}");
+    Ok(())
+}
+
+fn generate_structs(
+    cache: &Path,
+    tables: &[String],
+    build: &str,
+    output: Option<&Path>,
+) -> anyhow::Result<()> {
+    let definitions = load_definitions(cache, tables, build)?;
+
+    let mut code = format!(
+        "//! DBC record structs for build {build}\n//!\n//! Generated by `warcraft-rs dbd structs` from {WOWDBDEFS_URL}\n\n"
+    );
+    code.push_str("#![allow(dead_code)]\n\nuse wow_cdbc::StringRef;\n");
+    for (name, dbd) in &definitions {
+        if let Some(record) = generate_rust_struct(dbd, name, build) {
+            code.push('\n');
+            code.push_str(&record);
         }
     }
+
+    match output {
+        Some(path) => {
+            fs::write(path, code).with_context(|| format!("Failed to write {}", path.display()))?;
+            println!(
+                "Wrote {} struct(s) for build {build} to {}",
+                definitions.len(),
+                path.display()
+            );
+        }
+        None => print!("{code}"),
+    }
+    Ok(())
 }
 
 /// Convert a DBD file to YAML schemas
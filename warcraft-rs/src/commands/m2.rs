@@ -4,9 +4,20 @@ use anyhow::{Context, Result};
 use clap::Subcommand;
 use std::path::PathBuf;
 
+mod animations;
+mod cleanup;
+pub(crate) mod export;
+mod stats;
+mod textures;
+
+use animations::AnimationsParams;
+pub(crate) use export::static_mesh;
+use export::{AnimationSelection, ExportOptions};
+use textures::TextureArgs;
+
 use wow_blp::parser::load_blp;
 use wow_m2::{
-    AnimFile, M2Converter, M2Model, M2Version, SkinFile,
+    AnimFile, CleanupOptions, M2Converter, M2Model, M2Version, SkinFile,
     skin::{OldSkinHeader, SkinG, SkinHeaderT},
 };
 
@@ -45,6 +56,126 @@ pub enum M2Commands {
         /// Target version (e.g., "3.3.5a", "WotLK", "MoP")
         #[arg(long)]
         version: String,
+
+        /// Convert even if the target cannot represent every feature of the model
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Export an M2 model for use in 3D tools
+    Export {
+        /// Path to the M2 file
+        file: PathBuf,
+
+        /// Output file (.glb for binary glTF, .gltf for JSON plus .bin)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "gltf")]
+        format: ExportFormat,
+
+        /// Skin profile (level of detail) to export
+        #[arg(long, default_value = "0")]
+        skin: usize,
+
+        /// Animations to include: "all", "none", or comma-separated animation IDs
+        #[arg(long, default_value = "all")]
+        animations: AnimationSelection,
+
+        /// AnimationData.dbc, or an MPQ archive or data directory containing it,
+        /// for the fallbacks of requested animations the model lacks (defaults
+        /// to the profile's data directory)
+        #[arg(long, value_name = "DBC_OR_DATA_DIR")]
+        animation_data: Option<PathBuf>,
+
+        /// Embed textures found in an extracted data directory, an MPQ archive,
+        /// or a client installation (the profile's data directory if no path is given)
+        #[arg(long, value_name = "DATA_DIR_OR_MPQ", num_args = 0..=1)]
+        resolve_textures: Option<Option<PathBuf>>,
+
+        /// Animation sampling rate in frames per second
+        #[arg(long, default_value = "30")]
+        fps: u32,
+
+        /// Weld duplicate vertices and drop degenerate triangles before exporting
+        #[arg(long)]
+        cleanup: bool,
+
+        #[command(flatten)]
+        texture_args: TextureArgs,
+    },
+
+    /// List textures, resolving replaceable ones for a creature or character
+    Textures {
+        /// Path to the M2 file
+        file: PathBuf,
+
+        #[command(flatten)]
+        texture_args: TextureArgs,
+    },
+
+    /// Show draw calls, triangles, bones and texture units per batch of a skin profile
+    Stats {
+        /// Path to the M2 file
+        file: PathBuf,
+
+        /// Skin profile (level of detail) to inspect
+        #[arg(long, default_value = "0")]
+        skin: usize,
+
+        /// List every batch
+        #[arg(short, long)]
+        batches: bool,
+    },
+
+    /// Show the collision mesh and bounds, or write them for server vmap tools
+    Collision {
+        /// Path to the M2 file
+        file: PathBuf,
+
+        /// Write the collision mesh as a raw vmap model file, the intermediate
+        /// format the TrinityCore and CMaNGOS vmap assemblers read
+        #[arg(short, long, requires = "magic")]
+        output: Option<PathBuf>,
+
+        /// RAW_VMAP_MAGIC of the assembler that reads the file, e.g. VMAP04A
+        #[arg(long)]
+        magic: Option<String>,
+    },
+
+    /// Weld duplicate vertices and drop degenerate triangles and unused vertices and bones
+    Cleanup {
+        /// Path to the M2 file; its skin profiles are read from the same directory
+        input: PathBuf,
+
+        /// Output M2 file; skin profiles are written next to it
+        #[arg(required_unless_present = "dry_run")]
+        output: Option<PathBuf>,
+
+        /// Largest distance between welded vertices
+        #[arg(long, default_value_t = wow_m2::cleanup::DEFAULT_WELD_TOLERANCE)]
+        weld_tolerance: f32,
+
+        /// Do not weld duplicate vertices
+        #[arg(long)]
+        no_weld: bool,
+
+        /// Keep triangles with a repeated corner or no area
+        #[arg(long)]
+        keep_degenerate: bool,
+
+        /// Keep vertices no triangle uses
+        #[arg(long)]
+        keep_unused_vertices: bool,
+
+        /// Keep unused bones and the original bone palettes
+        #[arg(long)]
+        keep_bones: bool,
+
+        /// Only report what would be removed
+        #[arg(long)]
+        dry_run: bool,
     },
 
     /// Display M2 file structure as a tree
@@ -92,6 +223,25 @@ pub enum M2Commands {
         version: String,
     },
 
+    /// List animation sequences with names, durations and their .anim files
+    Animations {
+        /// Path to the M2 file
+        file: PathBuf,
+
+        /// Read the sequences from a .skel file (the model's own if no path is given)
+        #[arg(long, value_name = "SKEL", num_args = 0..=1)]
+        skel: Option<Option<PathBuf>>,
+
+        /// Directory with the model's .anim files (defaults to the model's directory)
+        #[arg(long)]
+        anim_dir: Option<PathBuf>,
+
+        /// AnimationData.dbc, or an MPQ archive or data directory containing it,
+        /// for animation names (defaults to the profile's data directory)
+        #[arg(long, value_name = "DBC_OR_DATA_DIR")]
+        animation_data: Option<PathBuf>,
+    },
+
     /// Display information about an ANIM file
     AnimInfo {
         /// Path to the ANIM file
@@ -126,6 +276,12 @@ pub enum M2Commands {
     },
 }
 
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+pub enum ExportFormat {
+    /// glTF 2.0
+    Gltf,
+}
+
 pub fn execute(cmd: M2Commands) -> Result<()> {
     match cmd {
         M2Commands::Info { file, detailed } => handle_info(file, detailed),
@@ -133,8 +289,77 @@ pub fn execute(cmd: M2Commands) -> Result<()> {
             input,
             output,
             version,
-        } => handle_convert(input, output, version),
+            force,
+        } => handle_convert(input, output, version, force),
         M2Commands::Validate { file, warnings } => handle_validate(file, warnings),
+        M2Commands::Export {
+            file,
+            output,
+            format,
+            skin,
+            animations,
+            animation_data,
+            resolve_textures,
+            fps,
+            cleanup,
+            texture_args,
+        } => handle_export(
+            file.clone(),
+            output,
+            format,
+            ExportOptions {
+                skin,
+                animation_fallbacks: export::animation_fallbacks(
+                    &animations,
+                    animation_data.as_deref(),
+                )?,
+                animations,
+                textures: resolve_textures
+                    .map(|path| {
+                        path.or_else(crate::utils::config::profile_data_dir).context(
+                        "--resolve-textures needs a path when no profile with a data_dir is active",
+                    )
+                    })
+                    .transpose()?,
+                replaceable: texture_args.replaceable_textures(&file)?,
+                fps,
+                cleanup,
+            },
+        ),
+        M2Commands::Textures { file, texture_args } => {
+            textures::list_textures(&file, &texture_args)
+        }
+        M2Commands::Stats {
+            file,
+            skin,
+            batches,
+        } => stats::render_stats(&file, skin, batches),
+        M2Commands::Collision {
+            file,
+            output,
+            magic,
+        } => handle_collision(file, output, magic),
+        M2Commands::Cleanup {
+            input,
+            output,
+            weld_tolerance,
+            no_weld,
+            keep_degenerate,
+            keep_unused_vertices,
+            keep_bones,
+            dry_run,
+        } => cleanup::cleanup_model(
+            &input,
+            output.as_deref(),
+            &CleanupOptions {
+                weld_tolerance: (!no_weld).then_some(weld_tolerance),
+                remove_degenerate_triangles: !keep_degenerate,
+                remove_unused_vertices: !keep_unused_vertices,
+                remove_unused_bones: !keep_bones,
+                ..Default::default()
+            },
+            dry_run,
+        ),
         M2Commands::Tree {
             file,
             depth,
@@ -154,6 +379,17 @@ pub fn execute(cmd: M2Commands) -> Result<()> {
             output,
             version,
         } => handle_skin_convert(input, output, version),
+        M2Commands::Animations {
+            file,
+            skel,
+            anim_dir,
+            animation_data,
+        } => animations::list_animations(AnimationsParams {
+            file,
+            skel,
+            anim_dir,
+            animation_data,
+        }),
         M2Commands::AnimInfo { file, detailed } => handle_anim_info(file, detailed),
         M2Commands::AnimConvert {
             input,
@@ -164,6 +400,68 @@ pub fn execute(cmd: M2Commands) -> Result<()> {
     }
 }
 
+fn handle_export(
+    input: PathBuf,
+    output: Option<PathBuf>,
+    format: ExportFormat,
+    options: ExportOptions,
+) -> Result<()> {
+    let output = output.unwrap_or_else(|| match format {
+        ExportFormat::Gltf => input.with_extension("glb"),
+    });
+    println!("Exporting {} -> {}", input.display(), output.display());
+
+    let stats = match format {
+        ExportFormat::Gltf => export::export_gltf(&input, &output, &options)?,
+    };
+
+    println!("Vertices: {}", stats.vertices);
+    println!("Triangles: {}", stats.triangles);
+    println!("Submeshes: {}", stats.primitives);
+    println!("Bones: {}", stats.bones);
+    println!("Animations: {}", stats.animations);
+    if options.textures.is_some() {
+        println!("Textures embedded: {}", stats.textures_embedded);
+        for missing in &stats.textures_missing {
+            println!("  Missing texture: {missing}");
+        }
+    }
+    println!("✓ Export complete");
+    Ok(())
+}
+
+fn handle_collision(input: PathBuf, output: Option<PathBuf>, magic: Option<String>) -> Result<()> {
+    let m2_format = M2Model::load(&input)
+        .with_context(|| format!("Failed to load M2 model from {}", input.display()))?;
+    let collision = m2_format.model().collision();
+
+    println!("Collision vertices: {}", collision.vertices.len());
+    println!("Collision triangles: {}", collision.triangles.len());
+    println!(
+        "Collision box: [{:.2}, {:.2}, {:.2}] - [{:.2}, {:.2}, {:.2}]",
+        collision.box_min[0],
+        collision.box_min[1],
+        collision.box_min[2],
+        collision.box_max[0],
+        collision.box_max[1],
+        collision.box_max[2]
+    );
+    println!("Collision sphere radius: {:.2}", collision.sphere_radius);
+
+    if let Some(output) = output {
+        if collision.is_empty() {
+            anyhow::bail!("{} has no collision mesh to export", input.display());
+        }
+        let magic = magic.unwrap_or_default();
+        let mut data = Vec::new();
+        collision.write_vmap(&mut data, magic.as_bytes())?;
+        std::fs::write(&output, data)
+            .with_context(|| format!("Failed to write {}", output.display()))?;
+        println!("✓ Wrote {}", output.display());
+    }
+    Ok(())
+}
+
 fn handle_info(path: PathBuf, detailed: bool) -> Result<()> {
     println!("Loading M2 model: {}", path.display());
 
@@ -258,7 +556,27 @@ fn handle_info(path: PathBuf, detailed: bool) -> Result<()> {
     Ok(())
 }
 
-fn handle_convert(input: PathBuf, output: PathBuf, version_str: String) -> Result<()> {
+/// Skin profiles stored next to a WotLK or later model, up to the first missing one
+fn load_external_skins(input: &std::path::Path, model: &M2Model) -> Result<Vec<SkinFile>> {
+    let stem = input
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .context("Invalid model file name")?;
+    let mut skins = Vec::new();
+    for index in 0..model.header.num_skin_profiles.unwrap_or(0) {
+        let path = input.with_file_name(format!("{stem}{index:02}.skin"));
+        if !path.exists() {
+            break;
+        }
+        skins.push(
+            SkinFile::load(&path)
+                .with_context(|| format!("Failed to load skin {}", path.display()))?,
+        );
+    }
+    Ok(skins)
+}
+
+fn handle_convert(input: PathBuf, output: PathBuf, version_str: String, force: bool) -> Result<()> {
     println!("Loading M2 model: {}", input.display());
 
     let m2_format = M2Model::load(&input)
@@ -268,6 +586,21 @@ fn handle_convert(input: PathBuf, output: PathBuf, version_str: String) -> Resul
     let target_version = M2Version::from_expansion_name(&version_str)
         .with_context(|| format!("Invalid target version: {version_str}"))?;
 
+    let skins = load_external_skins(&input, model)?;
+    let report = model.check_downgrade(&skins, target_version);
+    if !report.is_empty() {
+        println!("{target_version:?} cannot represent everything in this model:");
+        for issue in &report.issues {
+            println!("  - {issue}");
+        }
+        if !force {
+            anyhow::bail!(
+                "{} conversion issues found; fix them or pass --force to convert anyway",
+                report.issues.len()
+            );
+        }
+    }
+
     println!("Converting to {target_version:?}");
 
     let converter = M2Converter::new();
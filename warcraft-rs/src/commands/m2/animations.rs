@@ -0,0 +1,385 @@
+//! Listing of the animation sequences of a model
+//!
+//! Shows what the client sees for each sequence: its animation ID and name,
+//! duration, replay range, flags and where the keyframes live. Keyframes are
+//! either embedded in the model, delegated to another sequence (alias), or
+//! stored in an external `.anim` file named `<Model><ID:04>-<Sub:02>.anim`
+//! (WotLK+) or referenced by FileDataID (Legion+ `AFID` chunk). Models with a
+//! `.skel` file keep their sequences there, in the `SKS1` chunk.
+
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use wow_cdbc::{DbcParser, StringRef, Value};
+use wow_m2::M2Model;
+use wow_m2::chunks::animation::M2Animation;
+
+use crate::utils::assets::{AssetSource, find_case_insensitive};
+use crate::utils::{add_table_row, create_table};
+
+/// Client path of the animation names table
+const ANIMATION_DATA: &str = "DBFilesClient\\AnimationData.dbc";
+
+/// Column of `AnimationData.dbc` with the animation played when a model
+/// lacks one
+const FALLBACK_COLUMN: usize = 5;
+
+/// Header version used by `.skel` sequences (Legion+)
+const SKEL_SEQUENCE_VERSION: u32 = 274;
+
+/// Keyframes of the sequence are stored in the model or skeleton
+const FLAG_EMBEDDED: u32 = 0x20;
+/// The sequence is an alias for `aliasing`
+const FLAG_ALIAS: u32 = 0x40;
+/// The sequence is blended with its neighbours
+const FLAG_BLENDED: u32 = 0x80;
+
+pub struct AnimationsParams {
+    pub file: PathBuf,
+    /// Read sequences from a `.skel` file; `Some(None)` uses the model's
+    pub skel: Option<Option<PathBuf>>,
+    /// Directory with the model's `.anim` files (defaults to the model's)
+    pub anim_dir: Option<PathBuf>,
+    /// AnimationData.dbc, or an archive or data directory containing it
+    pub animation_data: Option<PathBuf>,
+}
+
+/// Where the keyframes of a sequence live
+#[derive(Debug, PartialEq, Eq)]
+enum Source {
+    Embedded,
+    Alias(u16),
+    /// External `.anim` file and whether it was found
+    AnimFile(String, bool),
+    /// FileDataID from the `AFID` chunk
+    FileDataId(u32),
+}
+
+impl std::fmt::Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Embedded => write!(f, "embedded"),
+            Self::Alias(index) => write!(f, "alias of #{index}"),
+            Self::AnimFile(name, true) => write!(f, "{name}"),
+            Self::AnimFile(name, false) => write!(f, "{name} (missing)"),
+            Self::FileDataId(id) => write!(f, "FileDataID {id}"),
+        }
+    }
+}
+
+/// `Character\Human\Male\HumanMale` + 4, 1 -> `HumanMale0004-01.anim`
+fn anim_file_name(stem: &str, animation_id: u16, sub_animation_id: u16) -> String {
+    format!("{stem}{animation_id:04}-{sub_animation_id:02}.anim")
+}
+
+/// Names of the flags that matter for playback
+fn flag_names(flags: u32) -> String {
+    let names: Vec<&str> = [
+        (FLAG_EMBEDDED, "embedded"),
+        (FLAG_ALIAS, "alias"),
+        (FLAG_BLENDED, "blended"),
+    ]
+    .iter()
+    .filter(|(bit, _)| flags & bit != 0)
+    .map(|(_, name)| *name)
+    .collect();
+    if names.is_empty() {
+        format!("0x{flags:X}")
+    } else {
+        format!("0x{flags:X} ({})", names.join(", "))
+    }
+}
+
+/// `AFID` entries as (animation ID, sub ID) -> FileDataID
+///
+/// The chunk holds `{u16 animation_id, u16 sub_animation_id, u32 file_id}`
+/// records, which the model exposes as a flat list of `u32`s.
+fn anim_file_ids(model: &M2Model) -> HashMap<(u16, u16), u32> {
+    model
+        .animation_file_ids
+        .as_ref()
+        .map(|afid| {
+            afid.ids
+                .chunks_exact(2)
+                .filter(|entry| entry[1] != 0)
+                .map(|entry| {
+                    let key = ((entry[0] & 0xFFFF) as u16, (entry[0] >> 16) as u16);
+                    (key, entry[1])
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Sequences from the `SKS1` chunk of a `.skel` file
+fn skel_sequences(data: &[u8]) -> Result<Vec<M2Animation>> {
+    let mut pos = 0;
+    while pos + 8 <= data.len() {
+        let magic = &data[pos..pos + 4];
+        let size = u32::from_le_bytes(data[pos + 4..pos + 8].try_into()?) as usize;
+        let body = data
+            .get(pos + 8..pos + 8 + size)
+            .context("Truncated chunk in .skel file")?;
+        if magic == b"SKS1" {
+            // M2Array global loops, sequences, lookups; offsets are chunk-relative
+            if body.len() < 24 {
+                anyhow::bail!("SKS1 chunk is too small");
+            }
+            let count = u32::from_le_bytes(body[8..12].try_into()?) as usize;
+            let offset = u32::from_le_bytes(body[12..16].try_into()?) as usize;
+            let size = M2Animation::size_for_version(SKEL_SEQUENCE_VERSION);
+            let records = body
+                .get(offset..offset + count * size)
+                .context("SKS1 sequences point outside the chunk")?;
+            return records
+                .chunks_exact(size)
+                .map(|record| {
+                    M2Animation::parse(&mut Cursor::new(record), SKEL_SEQUENCE_VERSION)
+                        .context("Failed to parse sequence")
+                })
+                .collect();
+        }
+        pos += 8 + size;
+    }
+    anyhow::bail!("No SKS1 chunk in .skel file")
+}
+
+/// Animation names by ID from AnimationData.dbc
+fn load_animation_names(data: &[u8]) -> Result<HashMap<u32, String>> {
+    let records = DbcParser::parse_bytes(data)?.parse_records()?;
+    let mut names = HashMap::new();
+    for record in records.records() {
+        if let (Some(Value::UInt32(id)), Some(Value::UInt32(name))) =
+            (record.get_value(0), record.get_value(1))
+            && let Ok(name) = records.get_string(StringRef::new(*name))
+        {
+            names.insert(*id, name.to_string());
+        }
+    }
+    Ok(names)
+}
+
+/// Fallback animation for each animation ID of `AnimationData.dbc`
+pub(super) fn load_animation_fallbacks(data: &[u8]) -> Result<HashMap<u16, u16>> {
+    let records = DbcParser::parse_bytes(data)?.parse_records()?;
+    let mut fallbacks = HashMap::new();
+    for record in records.records() {
+        if let (Some(Value::UInt32(id)), Some(Value::UInt32(fallback))) =
+            (record.get_value(0), record.get_value(FALLBACK_COLUMN))
+            && id != fallback
+            && let (Ok(id), Ok(fallback)) = (u16::try_from(*id), u16::try_from(*fallback))
+        {
+            fallbacks.insert(id, fallback);
+        }
+    }
+    Ok(fallbacks)
+}
+
+/// Names from `--animation-data`, or from the active profile's client data
+fn animation_names(path: Option<&Path>) -> Result<Option<HashMap<u32, String>>> {
+    read_animation_data(path)?
+        .map(|data| load_animation_names(&data).context("Failed to parse AnimationData.dbc"))
+        .transpose()
+}
+
+/// `AnimationData.dbc` from `path`, or from the active profile's client data
+pub(super) fn read_animation_data(path: Option<&Path>) -> Result<Option<Vec<u8>>> {
+    Ok(match path {
+        Some(path)
+            if path
+                .extension()
+                .is_some_and(|e| e.eq_ignore_ascii_case("dbc")) =>
+        {
+            Some(fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?)
+        }
+        Some(path) => Some(
+            AssetSource::open(path)?
+                .read(ANIMATION_DATA)?
+                .with_context(|| format!("{ANIMATION_DATA} not found in {}", path.display()))?,
+        ),
+        // Best effort: names are a convenience, not a requirement
+        None => crate::utils::config::profile_data_dir()
+            .and_then(|dir| AssetSource::open(&dir).ok())
+            .and_then(|mut source| source.read(ANIMATION_DATA).ok().flatten()),
+    })
+}
+
+fn file_stem(path: &Path) -> String {
+    path.file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+pub fn list_animations(params: AnimationsParams) -> Result<()> {
+    let format = M2Model::load(&params.file)
+        .with_context(|| format!("Failed to load M2 model from {}", params.file.display()))?;
+    let model = format.model();
+    let model_dir = params
+        .file
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+
+    let (sequences, stem) = match &params.skel {
+        Some(skel) => {
+            let path = skel.clone().unwrap_or_else(|| {
+                let name = format!("{}.skel", file_stem(&params.file));
+                find_case_insensitive(model_dir, &name).unwrap_or_else(|| model_dir.join(name))
+            });
+            let data =
+                fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+            let sequences = skel_sequences(&data)
+                .with_context(|| format!("Failed to read sequences from {}", path.display()))?;
+            (sequences, file_stem(&path))
+        }
+        None => (model.animations.clone(), file_stem(&params.file)),
+    };
+
+    if sequences.is_empty() {
+        println!("{} has no animation sequences", params.file.display());
+        if params.skel.is_none() && model.skeleton_file_id.is_some() {
+            println!("The model has a skeleton file; pass --skel to read its sequences");
+        }
+        return Ok(());
+    }
+
+    let names = animation_names(params.animation_data.as_deref())?;
+    let file_ids = anim_file_ids(model);
+    let anim_dir = params.anim_dir.as_deref().unwrap_or(model_dir);
+    // Pre-WotLK models keep all keyframes in the model
+    let external_files = params.skel.is_some() || model.header.version >= 264;
+
+    let mut table = create_table(vec![
+        "#", "ID", "Sub", "Name", "Duration", "Replay", "Flags", "Next", "Source",
+    ]);
+    let mut external = 0;
+    let mut missing = 0;
+    for (index, sequence) in sequences.iter().enumerate() {
+        let source = if sequence.flags & FLAG_ALIAS != 0 && sequence.flags & FLAG_EMBEDDED == 0 {
+            Source::Alias(sequence.aliasing.unwrap_or(0))
+        } else if sequence.flags & FLAG_EMBEDDED != 0 || !external_files {
+            Source::Embedded
+        } else if let Some(id) = file_ids.get(&(sequence.animation_id, sequence.sub_animation_id)) {
+            Source::FileDataId(*id)
+        } else {
+            let name = anim_file_name(&stem, sequence.animation_id, sequence.sub_animation_id);
+            let found = find_case_insensitive(anim_dir, &name).is_some();
+            missing += usize::from(!found);
+            Source::AnimFile(name, found)
+        };
+        if matches!(source, Source::AnimFile(..) | Source::FileDataId(_)) {
+            external += 1;
+        }
+
+        let name = names
+            .as_ref()
+            .and_then(|names| names.get(&u32::from(sequence.animation_id)).cloned())
+            .unwrap_or_default();
+        let replay = sequence
+            .replay
+            .filter(|r| r.maximum > 0)
+            .map(|r| format!("{}-{}", r.minimum, r.maximum))
+            .unwrap_or_else(|| "-".to_string());
+        let next = sequence
+            .next_animation
+            .filter(|&n| n >= 0)
+            .map(|n| format!("#{n}"))
+            .unwrap_or_else(|| "-".to_string());
+
+        add_table_row(
+            &mut table,
+            vec![
+                index.to_string(),
+                sequence.animation_id.to_string(),
+                sequence.sub_animation_id.to_string(),
+                name,
+                format!("{} ms", sequence.duration()),
+                replay,
+                flag_names(sequence.flags),
+                next,
+                source.to_string(),
+            ],
+        );
+    }
+
+    println!("Animations: {}", params.file.display());
+    table.printstd();
+    println!(
+        "\n{} sequence(s), {} in external files{}",
+        sequences.len(),
+        external,
+        if missing > 0 {
+            format!(
+                ", {missing} .anim file(s) missing from {}",
+                anim_dir.display()
+            )
+        } else {
+            String::new()
+        }
+    );
+    if names.is_none() {
+        println!("Pass --animation-data to show animation names");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_anim_file_name() {
+        assert_eq!(anim_file_name("HumanMale", 4, 1), "HumanMale0004-01.anim");
+        assert_eq!(anim_file_name("Bear", 143, 0), "Bear0143-00.anim");
+    }
+
+    #[test]
+    fn test_flag_names() {
+        assert_eq!(flag_names(0x20), "0x20 (embedded)");
+        assert_eq!(flag_names(0xC0), "0xC0 (alias, blended)");
+        assert_eq!(flag_names(0x1), "0x1");
+    }
+
+    #[test]
+    fn test_skel_sequences() {
+        let mut record = Vec::new();
+        M2Animation {
+            animation_id: 5,
+            sub_animation_id: 0,
+            start_timestamp: 800,
+            end_timestamp: None,
+            movement_speed: 7.0,
+            flags: 0,
+            frequency: 0x7FFF,
+            padding: 0,
+            replay: None,
+            blend_time: 150,
+            minimum_extent: None,
+            maximum_extent: None,
+            extent_radius: None,
+            next_animation: None,
+            aliasing: None,
+        }
+        .write(&mut record, SKEL_SEQUENCE_VERSION)
+        .unwrap();
+
+        let mut body = vec![0u8; 32];
+        body[8..12].copy_from_slice(&1u32.to_le_bytes());
+        body[12..16].copy_from_slice(&32u32.to_le_bytes());
+        body.extend_from_slice(&record);
+
+        let mut data = b"SKL1".to_vec();
+        data.extend_from_slice(&4u32.to_le_bytes());
+        data.extend_from_slice(&[0; 4]);
+        data.extend_from_slice(b"SKS1");
+        data.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        data.extend_from_slice(&body);
+
+        let sequences = skel_sequences(&data).unwrap();
+        assert_eq!(sequences.len(), 1);
+        assert_eq!(sequences[0].animation_id, 5);
+        assert_eq!(sequences[0].duration(), 800);
+    }
+}
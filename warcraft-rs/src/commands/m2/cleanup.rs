@@ -0,0 +1,105 @@
+//! Geometry cleanup of a model and all of its skin profiles
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+use wow_m2::{CleanupOptions, M2Model, SkinFile, parse_m2};
+
+use super::export::load_skin;
+use crate::utils::{add_table_row, create_table};
+
+/// Largest bone palette of any submesh
+fn max_submesh_bones(skins: &[SkinFile]) -> u16 {
+    skins
+        .iter()
+        .flat_map(|skin| skin.submeshes())
+        .map(|submesh| submesh.bone_count)
+        .max()
+        .unwrap_or(0)
+}
+
+fn triangle_count(skins: &[SkinFile]) -> usize {
+    skins.iter().map(|skin| skin.triangles().len() / 3).sum()
+}
+
+/// Skin profiles of a WotLK or later model, stored next to it
+fn load_skins(input: &Path, data: &[u8], model: &M2Model) -> Result<Vec<SkinFile>> {
+    if !model.raw_data.embedded_skins.is_empty() {
+        anyhow::bail!(
+            "Models with embedded skin profiles (before WotLK) are not supported; \
+             convert the model to WotLK or later first"
+        );
+    }
+    let count = model.header.num_skin_profiles.unwrap_or(1).max(1) as usize;
+    (0..count)
+        .map(|index| load_skin(input, data, model, index))
+        .collect()
+}
+
+pub fn cleanup_model(
+    input: &Path,
+    output: Option<&Path>,
+    options: &CleanupOptions,
+    dry_run: bool,
+) -> Result<()> {
+    let data = fs::read(input).with_context(|| format!("Failed to read {}", input.display()))?;
+    let mut format = parse_m2(&mut Cursor::new(&data))
+        .with_context(|| format!("Failed to parse M2 model {}", input.display()))?;
+    let model = format.model_mut();
+    let mut skins = load_skins(input, &data, model)?;
+
+    let before = (
+        model.vertices.len(),
+        triangle_count(&skins),
+        model.bones.len(),
+        max_submesh_bones(&skins),
+    );
+    let report = model.cleanup_mesh(&mut skins, options);
+    let after = (
+        model.vertices.len(),
+        triangle_count(&skins),
+        model.bones.len(),
+        max_submesh_bones(&skins),
+    );
+
+    println!("Mesh Cleanup: {} ({} skins)", input.display(), skins.len());
+    let mut table = create_table(vec!["", "Before", "After"]);
+    for (name, before, after) in [
+        ("Vertices", before.0, after.0),
+        ("Triangles", before.1, after.1),
+        ("Bones", before.2, after.2),
+        ("Max bones per submesh", before.3.into(), after.3.into()),
+    ] {
+        add_table_row(
+            &mut table,
+            vec![name.to_string(), before.to_string(), after.to_string()],
+        );
+    }
+    println!("{table}");
+    println!("Welded vertices: {}", report.welded_vertices);
+    println!("Degenerate triangles: {}", report.degenerate_triangles);
+
+    if dry_run {
+        return Ok(());
+    }
+    let output = output.context("An output file is required unless --dry-run is given")?;
+    model
+        .save(output)
+        .with_context(|| format!("Failed to save {}", output.display()))?;
+    let stem = output
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .context("Invalid output file name")?;
+    for (index, skin) in skins.iter().enumerate() {
+        let path = output.with_file_name(format!("{stem}{index:02}.skin"));
+        skin.save(&path)
+            .with_context(|| format!("Failed to save {}", path.display()))?;
+    }
+    println!(
+        "✓ Saved {} and {} skin profiles",
+        output.display(),
+        skins.len()
+    );
+    Ok(())
+}
@@ -0,0 +1,772 @@
+//! Export M2 models to glTF 2.0
+//!
+//! Geometry comes from a single skin profile, bones become a glTF skin and
+//! animation sequences are resampled at a fixed frame rate so any glTF
+//! viewer can play them without knowing M2 interpolation rules.
+
+use anyhow::{Context, Result};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use wow_m2::animation::{AnimationManager, ResolvedTrack, interpolate_track};
+use wow_m2::chunks::M2Vertex;
+use wow_m2::chunks::material::M2RenderFlags;
+use wow_m2::chunks::texture::M2TextureType;
+use wow_m2::{
+    CleanupOptions, M2Model, ReplaceableTextures, SkinFile, animation::AnimationManagerBuilder,
+    parse_m2, parse_skin,
+};
+
+use super::animations::{load_animation_fallbacks, read_animation_data};
+use crate::utils::assets::{AssetSource, blp_to_png};
+use crate::utils::gltf::{
+    GltfBuilder, node, primitive, translation_matrix, wow_quat_to_gltf, wow_to_gltf,
+};
+use crate::utils::scene::{Mesh, MeshPart, ResolvedMaterial};
+
+/// Which animation sequences to export
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnimationSelection {
+    All,
+    None,
+    /// Sequences whose animation ID is in the list
+    Ids(Vec<u16>),
+}
+
+impl FromStr for AnimationSelection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "all" => Ok(Self::All),
+            "none" => Ok(Self::None),
+            list => list
+                .split(',')
+                .map(|id| {
+                    id.trim()
+                        .parse::<u16>()
+                        .map_err(|_| format!("invalid animation ID '{}'", id.trim()))
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map(Self::Ids),
+        }
+    }
+}
+
+/// Options controlling a glTF export
+pub struct ExportOptions {
+    /// Skin profile (level of detail) index
+    pub skin: usize,
+    pub animations: AnimationSelection,
+    /// Animation to export in place of each requested one the model lacks
+    pub animation_fallbacks: HashMap<u16, u16>,
+    /// Extracted data directory, MPQ archive, or client installation
+    pub textures: Option<PathBuf>,
+    /// Textures for the model's replaceable texture slots
+    pub replaceable: ReplaceableTextures,
+    /// Animation sampling rate
+    pub fps: u32,
+    /// Run the default mesh cleanup on the exported skin first
+    pub cleanup: bool,
+}
+
+/// Summary of what ended up in the exported file
+#[derive(Debug, Default)]
+pub struct ExportStats {
+    pub vertices: usize,
+    pub triangles: usize,
+    pub primitives: usize,
+    pub bones: usize,
+    pub animations: usize,
+    pub textures_embedded: usize,
+    pub textures_missing: Vec<String>,
+}
+
+pub(super) fn load_skin(
+    input: &Path,
+    data: &[u8],
+    model: &M2Model,
+    index: usize,
+) -> Result<SkinFile> {
+    let stem = input
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .context("Invalid model file name")?;
+    let skin_path = input.with_file_name(format!("{stem}{index:02}.skin"));
+
+    if skin_path.exists() {
+        return SkinFile::load(&skin_path)
+            .with_context(|| format!("Failed to load skin {}", skin_path.display()));
+    }
+    if model.header.version <= 263 {
+        return model
+            .parse_embedded_skin(data, index)
+            .with_context(|| format!("Failed to parse embedded skin profile {index}"));
+    }
+    anyhow::bail!("Skin file not found: {}", skin_path.display())
+}
+
+/// Offsets inside chunked (MD21) files are relative to the MD20 payload
+fn md20_payload(data: &[u8]) -> &[u8] {
+    if data.starts_with(b"MD21") && data.len() > 8 {
+        &data[8..]
+    } else {
+        data
+    }
+}
+
+/// Normalize four byte weights so they sum to exactly 255
+fn normalize_weights(vertex: &M2Vertex, bone_count: usize) -> ([u8; 4], [u8; 4]) {
+    let mut joints = [0u8; 4];
+    let mut weights = [0u8; 4];
+    for i in 0..4 {
+        if (vertex.bone_indices[i] as usize) < bone_count && vertex.bone_weights[i] > 0 {
+            joints[i] = vertex.bone_indices[i];
+            weights[i] = vertex.bone_weights[i];
+        }
+    }
+
+    let total: u32 = weights.iter().map(|&w| u32::from(w)).sum();
+    if total == 0 {
+        return ([0; 4], [255, 0, 0, 0]);
+    }
+    let mut scaled = weights.map(|w| ((u32::from(w) * 255 + total / 2) / total) as u8);
+    let sum: i32 = scaled.iter().map(|&w| i32::from(w)).sum();
+    let largest = (0..4).max_by_key(|&i| scaled[i]).unwrap_or(0);
+    scaled[largest] = (i32::from(scaled[largest]) + 255 - sum).clamp(0, 255) as u8;
+    (joints, scaled)
+}
+
+/// Parent index for a bone, or `None` for roots and broken hierarchies
+fn bone_parent(model: &M2Model, index: usize) -> Option<usize> {
+    let count = model.bones.len();
+    let parent_of = |bone: usize| {
+        usize::try_from(model.bones[bone].parent_bone)
+            .ok()
+            .filter(|&p| p < count)
+    };
+    let direct = parent_of(index)?;
+
+    // Walk up to the root so cycles, which cannot form a node tree, are rejected
+    let mut current = direct;
+    for _ in 0..count {
+        if current == index {
+            return None;
+        }
+        match parent_of(current) {
+            Some(p) => current = p,
+            None => return Some(direct),
+        }
+    }
+    None
+}
+
+fn has_keys<T>(track: &ResolvedTrack<T>, sequence: usize) -> bool {
+    if track.uses_global_sequence() {
+        return track.timestamps.first().is_some_and(|t| !t.is_empty());
+    }
+    track
+        .timestamps
+        .get(sequence)
+        .is_some_and(|t| !t.is_empty())
+}
+
+fn animation_name(id: u16, sub_id: u16) -> String {
+    if sub_id == 0 {
+        format!("Animation_{id}")
+    } else {
+        format!("Animation_{id}_{sub_id}")
+    }
+}
+
+struct Skeleton {
+    nodes: Vec<usize>,
+    parents: Vec<Option<usize>>,
+}
+
+impl Skeleton {
+    /// Rest translation of a bone relative to its parent, in WoW space
+    fn rest_offset(&self, model: &M2Model, bone: usize) -> [f32; 3] {
+        let pivot = model.bones[bone].pivot;
+        match self.parents[bone] {
+            Some(parent) => {
+                let parent_pivot = model.bones[parent].pivot;
+                [
+                    pivot.x - parent_pivot.x,
+                    pivot.y - parent_pivot.y,
+                    pivot.z - parent_pivot.z,
+                ]
+            }
+            None => [pivot.x, pivot.y, pivot.z],
+        }
+    }
+}
+
+fn build_skeleton(builder: &mut GltfBuilder, model: &M2Model) -> Skeleton {
+    let parents: Vec<Option<usize>> = (0..model.bones.len())
+        .map(|i| bone_parent(model, i))
+        .collect();
+
+    let mut skeleton = Skeleton {
+        nodes: Vec::with_capacity(model.bones.len()),
+        parents,
+    };
+    for i in 0..model.bones.len() {
+        let offset = wow_to_gltf(skeleton.rest_offset(model, i));
+        skeleton
+            .nodes
+            .push(builder.add_node(node(&format!("Bone_{i}"), Some(offset), None, None)));
+    }
+
+    let mut children: Vec<Vec<usize>> = vec![Vec::new(); model.bones.len()];
+    for (i, parent) in skeleton.parents.iter().enumerate() {
+        if let Some(p) = parent {
+            children[*p].push(skeleton.nodes[i]);
+        }
+    }
+    for (i, children) in children.iter().enumerate() {
+        builder.set_children(skeleton.nodes[i], children);
+    }
+    skeleton
+}
+
+/// Fallbacks from `AnimationData.dbc` for a selection of animation IDs
+///
+/// Only a list of IDs can ask for animations the model lacks. Without an
+/// explicit `path` the profile's client data is tried, and its absence is
+/// not an error.
+pub(super) fn animation_fallbacks(
+    selection: &AnimationSelection,
+    path: Option<&Path>,
+) -> Result<HashMap<u16, u16>> {
+    if !matches!(selection, AnimationSelection::Ids(_)) {
+        return Ok(HashMap::new());
+    }
+    let Some(data) = read_animation_data(path)? else {
+        return Ok(HashMap::new());
+    };
+    load_animation_fallbacks(&data).context("Failed to parse AnimationData.dbc")
+}
+
+/// Sequence index, animation ID and sub-ID of each animation to export
+///
+/// Requested IDs export every variation, each resolved through aliases and
+/// fallbacks to the sequence holding its keyframes but named after the ID
+/// that was asked for.
+fn selected_sequences(
+    manager: &AnimationManager,
+    selection: &AnimationSelection,
+) -> Vec<(usize, u16, u16)> {
+    let sequences = manager.sequences();
+    match selection {
+        AnimationSelection::None => Vec::new(),
+        AnimationSelection::All => sequences
+            .iter()
+            .enumerate()
+            .filter(|(_, sequence)| !sequence.is_alias())
+            .map(|(index, sequence)| (index, sequence.id, sequence.sub_id))
+            .collect(),
+        AnimationSelection::Ids(ids) => ids
+            .iter()
+            .flat_map(|&id| {
+                manager.variations(id).into_iter().map(move |variation| {
+                    (
+                        manager.resolve_alias(variation),
+                        id,
+                        sequences[variation].sub_id,
+                    )
+                })
+            })
+            .collect(),
+    }
+}
+
+fn export_animations(
+    builder: &mut GltfBuilder,
+    model: &M2Model,
+    manager: &AnimationManager,
+    skeleton: &Skeleton,
+    options: &ExportOptions,
+) -> usize {
+    let fps = f64::from(options.fps.max(1));
+    let bones = manager.bones();
+    let mut exported = 0;
+
+    for (index, id, sub_id) in selected_sequences(manager, &options.animations) {
+        let sequence = &manager.sequences()[index];
+
+        // Pre-WotLK tracks share one timeline; later versions start each
+        // sequence at zero.
+        let start = match model.animations[index].end_timestamp {
+            Some(_) => f64::from(model.animations[index].start_timestamp),
+            None => 0.0,
+        };
+        let duration = f64::from(sequence.duration);
+        let frames = ((duration / 1000.0) * fps).ceil().max(1.0) as usize;
+        let sample_times: Vec<f64> = (0..=frames)
+            .map(|k| (k as f64 * 1000.0 / fps).min(duration))
+            .collect();
+        let times: Vec<[f32; 1]> = sample_times
+            .iter()
+            .map(|&t| [(t / 1000.0) as f32])
+            .collect();
+
+        let global_times = |t: f64| -> Vec<f64> {
+            model
+                .global_sequences
+                .iter()
+                .map(|&d| if d > 0 { t % f64::from(d) } else { t })
+                .collect()
+        };
+
+        let mut animation = builder.begin_animation(&animation_name(id, sub_id));
+        for (b, bone) in bones.iter().enumerate().take(skeleton.nodes.len()) {
+            let node = skeleton.nodes[b];
+
+            if has_keys(&bone.translation, index) {
+                let rest = skeleton.rest_offset(model, b);
+                let values: Vec<[f32; 3]> = sample_times
+                    .iter()
+                    .map(|&t| {
+                        let v = interpolate_track(
+                            &bone.translation,
+                            index,
+                            start + t,
+                            &global_times(t),
+                            wow_m2::animation::Vec3::ZERO,
+                        );
+                        wow_to_gltf([rest[0] + v.x, rest[1] + v.y, rest[2] + v.z])
+                    })
+                    .collect();
+                animation.add_channel(node, "translation", &times, &values);
+            }
+
+            if has_keys(&bone.rotation, index) {
+                let values: Vec<[f32; 4]> = sample_times
+                    .iter()
+                    .map(|&t| {
+                        let q = interpolate_track(
+                            &bone.rotation,
+                            index,
+                            start + t,
+                            &global_times(t),
+                            wow_m2::animation::Quat::IDENTITY,
+                        );
+                        wow_quat_to_gltf([q.x, q.y, q.z, q.w])
+                    })
+                    .collect();
+                animation.add_channel(node, "rotation", &times, &values);
+            }
+
+            if has_keys(&bone.scale, index) {
+                let values: Vec<[f32; 3]> = sample_times
+                    .iter()
+                    .map(|&t| {
+                        let s = interpolate_track(
+                            &bone.scale,
+                            index,
+                            start + t,
+                            &global_times(t),
+                            wow_m2::animation::Vec3::ONE,
+                        );
+                        // Axis permutation only; scale factors carry no sign
+                        [s.x, s.z, s.y]
+                    })
+                    .collect();
+                animation.add_channel(node, "scale", &times, &values);
+            }
+        }
+        if animation.finish() {
+            exported += 1;
+        }
+    }
+    exported
+}
+
+/// Triangles of one submesh with the texture and render flags of its first layer
+struct SubmeshPart {
+    indices: Vec<u32>,
+    texture: Option<usize>,
+    render: Option<usize>,
+}
+
+fn submesh_parts(model: &M2Model, skin: &SkinFile) -> Vec<SubmeshPart> {
+    let lookup = &model.raw_data.texture_lookup_table;
+    let vertex_lookup = skin.indices();
+    let triangles = skin.triangles();
+    let mut parts = Vec::new();
+
+    for (submesh_index, submesh) in skin.submeshes().iter().enumerate() {
+        let start = submesh.triangle_start as usize + ((submesh.level as usize) << 16);
+        let end = (start + submesh.triangle_count as usize).min(triangles.len());
+        if start >= end {
+            continue;
+        }
+
+        let indices: Vec<u32> = triangles[start..end]
+            .iter()
+            .map(|&i| u32::from(vertex_lookup.get(i as usize).copied().unwrap_or(i)))
+            .filter(|&i| (i as usize) < model.vertices.len())
+            .collect();
+        if indices.len() < 3 || !indices.len().is_multiple_of(3) {
+            continue;
+        }
+
+        // The first texture layer of the submesh decides its material
+        let batch = skin
+            .batches()
+            .iter()
+            .filter(|b| b.skin_section_index as usize == submesh_index)
+            .min_by_key(|b| b.material_layer);
+        let texture = batch
+            .and_then(|b| lookup.get(b.texture_combo_index as usize))
+            .map(|&t| t as usize)
+            .filter(|&t| t < model.textures.len());
+        let render = batch
+            .map(|b| b.material_index as usize)
+            .filter(|&m| m < model.materials.len());
+
+        parts.push(SubmeshPart {
+            indices,
+            texture,
+            render,
+        });
+    }
+    parts
+}
+
+/// Resolve the render flags entry of a submesh
+fn resolve_material(
+    model: &M2Model,
+    textures: Vec<String>,
+    render: Option<usize>,
+) -> ResolvedMaterial {
+    match render {
+        Some(m) => ResolvedMaterial::from_gx_blend(
+            u32::from(model.materials[m].blend_mode.bits()),
+            textures,
+            model.materials[m]
+                .flags
+                .contains(M2RenderFlags::NO_BACKFACE_CULLING),
+        ),
+        None => ResolvedMaterial {
+            textures,
+            ..Default::default()
+        },
+    }
+}
+
+/// Texture path of a hardcoded texture; replaceable ones are chosen at runtime
+fn hardcoded_texture(model: &M2Model, index: usize) -> Option<String> {
+    let texture = &model.textures[index];
+    let name = texture.filename.string.to_string_lossy();
+    (texture.texture_type == M2TextureType::Hardcoded && !name.is_empty()).then_some(name)
+}
+
+/// Read a texture as a file on disk, e.g. a creature skin next to the model,
+/// or from the texture source
+fn read_texture(source: &mut AssetSource, name: &str) -> Result<Option<Vec<u8>>> {
+    let path = Path::new(name);
+    if path.is_file() {
+        return fs::read(path)
+            .map(Some)
+            .with_context(|| format!("Failed to read {name}"));
+    }
+    source.read(name)
+}
+
+/// Load a model's rest pose as a static mesh, e.g. for placing doodads
+///
+/// `skin_data` is the contents of the first `.skin` file; models from before
+/// Wrath of the Lich King embed their skin profiles and may pass `None`.
+pub(crate) fn static_mesh(name: &str, data: &[u8], skin_data: Option<&[u8]>) -> Result<Mesh> {
+    let format = parse_m2(&mut Cursor::new(data))?;
+    let model = format.model();
+    let skin = match skin_data {
+        Some(skin) => parse_skin(&mut Cursor::new(skin))?,
+        None => model.parse_embedded_skin(data, 0)?,
+    };
+
+    let parts = submesh_parts(model, &skin)
+        .into_iter()
+        .map(|part| {
+            let texture = part.texture.and_then(|t| hardcoded_texture(model, t));
+            MeshPart {
+                indices: part.indices,
+                material: resolve_material(model, texture.into_iter().collect(), part.render),
+            }
+        })
+        .collect();
+
+    Ok(Mesh {
+        name: name.to_string(),
+        positions: model
+            .vertices
+            .iter()
+            .map(|v| [v.position.x, v.position.y, v.position.z])
+            .collect(),
+        normals: model
+            .vertices
+            .iter()
+            .map(|v| [v.normal.x, v.normal.y, v.normal.z])
+            .collect(),
+        uvs: model
+            .vertices
+            .iter()
+            .map(|v| [v.tex_coords.x, v.tex_coords.y])
+            .collect(),
+        parts,
+        ..Default::default()
+    })
+}
+
+/// Export an M2 model as glTF (`.gltf` + `.bin`) or binary glTF (`.glb`)
+pub fn export_gltf(input: &Path, output: &Path, options: &ExportOptions) -> Result<ExportStats> {
+    let data = fs::read(input).with_context(|| format!("Failed to read {}", input.display()))?;
+    let format = parse_m2(&mut Cursor::new(&data))
+        .with_context(|| format!("Failed to parse M2 model {}", input.display()))?;
+    let mut model = Cow::Borrowed(format.model());
+    let mut skin = load_skin(input, &data, &model, options.skin)?;
+    if options.cleanup {
+        model
+            .to_mut()
+            .cleanup_mesh(std::slice::from_mut(&mut skin), &CleanupOptions::default());
+    }
+    let model = &*model;
+
+    let model_name = model.name.clone().unwrap_or_else(|| {
+        input
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("model")
+            .to_string()
+    });
+
+    let mut stats = ExportStats {
+        vertices: model.vertices.len(),
+        bones: model.bones.len(),
+        ..Default::default()
+    };
+    let mut builder = GltfBuilder::new();
+
+    // Shared vertex attributes; every submesh indexes into the same buffers
+    let positions: Vec<[f32; 3]> = model
+        .vertices
+        .iter()
+        .map(|v| wow_to_gltf([v.position.x, v.position.y, v.position.z]))
+        .collect();
+    let normals: Vec<[f32; 3]> = model
+        .vertices
+        .iter()
+        .map(|v| {
+            let n = wow_to_gltf([v.normal.x, v.normal.y, v.normal.z]);
+            let len = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+            if len > f32::EPSILON {
+                [n[0] / len, n[1] / len, n[2] / len]
+            } else {
+                [0.0, 1.0, 0.0]
+            }
+        })
+        .collect();
+    let uvs: Vec<[f32; 2]> = model
+        .vertices
+        .iter()
+        .map(|v| [v.tex_coords.x, v.tex_coords.y])
+        .collect();
+
+    let mut attributes = vec![
+        ("POSITION", builder.add_floats(&positions, true)),
+        ("NORMAL", builder.add_floats(&normals, true)),
+        ("TEXCOORD_0", builder.add_floats(&uvs, true)),
+    ];
+    let skinned = !model.bones.is_empty();
+    if skinned {
+        let (joints, weights): (Vec<[u8; 4]>, Vec<[u8; 4]>) = model
+            .vertices
+            .iter()
+            .map(|v| normalize_weights(v, model.bones.len()))
+            .unzip();
+        attributes.push(("JOINTS_0", builder.add_bytes4(&joints, false)));
+        attributes.push(("WEIGHTS_0", builder.add_bytes4(&weights, true)));
+    }
+
+    // Textures are converted lazily so unused ones are never decoded
+    let mut source = options
+        .textures
+        .as_deref()
+        .map(AssetSource::open)
+        .transpose()?;
+    let mut texture_cache: HashMap<usize, Option<usize>> = HashMap::new();
+    let mut material_cache: HashMap<(Option<usize>, Option<usize>), usize> = HashMap::new();
+
+    let mut primitives = Vec::new();
+
+    for part in submesh_parts(model, &skin) {
+        let SubmeshPart {
+            indices,
+            texture: texture_index,
+            render: render_index,
+        } = part;
+
+        let gltf_texture = match (texture_index, source.as_mut()) {
+            (Some(t), Some(source)) => *texture_cache.entry(t).or_insert_with(|| {
+                let name = options.replaceable.resolve(&model.textures[t])?;
+                match read_texture(source, &name)
+                    .and_then(|blp| blp.map(|b| blp_to_png(&b)).transpose())
+                {
+                    Ok(Some(png)) => {
+                        stats.textures_embedded += 1;
+                        Some(builder.add_png_texture(&name, &png))
+                    }
+                    Ok(None) => {
+                        stats.textures_missing.push(name);
+                        None
+                    }
+                    Err(e) => {
+                        log::warn!("Failed to convert texture {name}: {e:#}");
+                        stats.textures_missing.push(name);
+                        None
+                    }
+                }
+            }),
+            _ => None,
+        };
+
+        let material = *material_cache
+            .entry((gltf_texture, render_index))
+            .or_insert_with(|| {
+                // Textures are already embedded; only blending and culling are needed
+                let resolved = resolve_material(model, Vec::new(), render_index);
+                let name = format!("Material_{}", material_name(gltf_texture, render_index));
+                builder.add_material(
+                    &name,
+                    gltf_texture,
+                    resolved.alpha_mode(),
+                    resolved.two_sided,
+                )
+            });
+
+        let index_accessor = builder.add_indices(&indices);
+        stats.triangles += indices.len() / 3;
+        primitives.push(primitive(&attributes, index_accessor, Some(material)));
+    }
+    stats.primitives = primitives.len();
+
+    if primitives.is_empty() {
+        anyhow::bail!(
+            "Skin profile {} contains no drawable geometry",
+            options.skin
+        );
+    }
+
+    let mesh = builder.add_mesh(&model_name, primitives);
+    let root = builder.add_node(node(&model_name, None, None, None));
+    let mesh_node = builder.add_node(node(&format!("{model_name}_mesh"), None, None, None));
+    builder.set_node_property(mesh_node, "mesh", serde_json::json!(mesh));
+    builder.add_scene_root(root);
+
+    if skinned {
+        let skeleton = build_skeleton(&mut builder, model);
+        let inverse_bind: Vec<[f32; 16]> = model
+            .bones
+            .iter()
+            .map(|b| {
+                let p = wow_to_gltf([b.pivot.x, b.pivot.y, b.pivot.z]);
+                translation_matrix([-p[0], -p[1], -p[2]])
+            })
+            .collect();
+        let skin_index = builder.add_skin(&model_name, &skeleton.nodes, &inverse_bind, Some(root));
+        builder.set_node_property(mesh_node, "skin", serde_json::json!(skin_index));
+
+        if options.animations != AnimationSelection::None && !model.animations.is_empty() {
+            match AnimationManagerBuilder::from_model(model, md20_payload(&data)) {
+                Ok(manager) => {
+                    let manager = manager.with_fallbacks(options.animation_fallbacks.clone());
+                    stats.animations =
+                        export_animations(&mut builder, model, &manager, &skeleton, options);
+                }
+                Err(e) => log::warn!("Skipping animations: {e}"),
+            }
+        }
+        // The mesh node sits next to the skeleton under the model root
+        let mut children: Vec<usize> = skeleton
+            .parents
+            .iter()
+            .zip(&skeleton.nodes)
+            .filter(|(parent, _)| parent.is_none())
+            .map(|(_, &node)| node)
+            .collect();
+        children.insert(0, mesh_node);
+        builder.set_children(root, &children);
+    } else {
+        builder.set_children(root, &[mesh_node]);
+    }
+
+    builder.write(output)?;
+    Ok(stats)
+}
+
+fn material_name(texture: Option<usize>, render: Option<usize>) -> String {
+    match (texture, render) {
+        (Some(t), Some(r)) => format!("T{t}_R{r}"),
+        (Some(t), None) => format!("T{t}"),
+        (None, Some(r)) => format!("R{r}"),
+        (None, None) => "Default".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_animation_selection() {
+        assert_eq!("all".parse(), Ok(AnimationSelection::All));
+        assert_eq!("None".parse(), Ok(AnimationSelection::None));
+        assert_eq!("0, 4,5".parse(), Ok(AnimationSelection::Ids(vec![0, 4, 5])));
+        assert!("walk".parse::<AnimationSelection>().is_err());
+    }
+
+    #[test]
+    fn test_selected_sequences_resolve_aliases_and_fallbacks() {
+        use wow_m2::animation::AnimSequence;
+
+        let sequence = |id, flags, alias_next| AnimSequence {
+            id,
+            sub_id: 0,
+            duration: 1000,
+            movement_speed: 0.0,
+            flags,
+            frequency: 0x7fff,
+            replay_min: 0,
+            replay_max: 0,
+            blend_time: 0,
+            variation_next: -1,
+            alias_next,
+        };
+        // Stand, Walk, and Run as an alias of Walk
+        let manager = AnimationManager::new(
+            vec![],
+            vec![
+                sequence(0, 0x20, 0),
+                sequence(4, 0x20, 0),
+                sequence(5, 0x40, 1),
+            ],
+            vec![],
+        )
+        .with_fallbacks(HashMap::from([(13, 4)]));
+
+        assert_eq!(
+            selected_sequences(&manager, &AnimationSelection::All),
+            vec![(0, 0, 0), (1, 4, 0)]
+        );
+        assert_eq!(
+            selected_sequences(&manager, &AnimationSelection::Ids(vec![5, 13, 99])),
+            vec![(1, 5, 0), (1, 13, 0)]
+        );
+    }
+}
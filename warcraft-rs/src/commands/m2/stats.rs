@@ -0,0 +1,113 @@
+//! Render statistics of a skin profile
+//!
+//! Every batch with geometry is a draw call; together with the bones and
+//! texture units per batch this gives a rough idea of how expensive a custom
+//! model is compared to the client's own.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+use wow_m2::chunks::material::M2BlendMode;
+use wow_m2::parse_m2;
+
+use super::export::load_skin;
+use crate::utils::{add_table_row, create_table};
+
+/// Readable name of a blend mode
+fn blend_mode_name(mode: u16) -> String {
+    match M2BlendMode::from_bits_retain(mode) {
+        M2BlendMode::OPAQUE => "Opaque".to_string(),
+        M2BlendMode::ALPHA_KEY => "AlphaKey".to_string(),
+        M2BlendMode::ALPHA => "Alpha".to_string(),
+        M2BlendMode::NO_ALPHA_ADD => "NoAlphaAdd".to_string(),
+        M2BlendMode::ADD => "Add".to_string(),
+        M2BlendMode::MOD => "Mod".to_string(),
+        M2BlendMode::MOD2X => "Mod2x".to_string(),
+        M2BlendMode::BLEND_ADD => "BlendAdd".to_string(),
+        _ => format!("Unknown ({mode})"),
+    }
+}
+
+pub fn render_stats(file: &Path, skin_index: usize, list_batches: bool) -> Result<()> {
+    let data = fs::read(file).with_context(|| format!("Failed to read {}", file.display()))?;
+    let format = parse_m2(&mut Cursor::new(&data))
+        .with_context(|| format!("Failed to parse M2 model {}", file.display()))?;
+    let model = format.model();
+    let skin = load_skin(file, &data, model, skin_index)?;
+    let stats = skin.render_stats(model);
+
+    println!("Render Statistics: {} (skin {skin_index})", file.display());
+    println!("=================");
+    println!("Submeshes: {}", stats.submeshes);
+    println!("Triangles: {}", stats.triangles);
+    println!("Vertices: {}", stats.vertices);
+    println!("Batches: {}", stats.batches.len());
+    println!(
+        "Draw calls: {} ({} transparent)",
+        stats.draw_calls, stats.transparent_draw_calls
+    );
+    println!("Triangles drawn: {}", stats.drawn_triangles);
+    println!("Max bones per batch: {}", stats.max_bones);
+    println!("Max bone influences: {}", stats.max_bone_influences);
+    println!(
+        "Texture units: {} (max {} per batch, {} unique textures)",
+        stats.texture_units, stats.max_texture_units, stats.unique_textures
+    );
+    if !stats.blend_modes.is_empty() {
+        let modes: Vec<String> = stats
+            .blend_modes
+            .iter()
+            .map(|(&mode, count)| format!("{} {count}", blend_mode_name(mode)))
+            .collect();
+        println!("Blend modes: {}", modes.join(", "));
+    }
+
+    if list_batches && !stats.batches.is_empty() {
+        let mut table = create_table(vec![
+            "#",
+            "Submesh",
+            "Geoset",
+            "Triangles",
+            "Bones",
+            "Influences",
+            "Textures",
+            "Blend",
+            "Layer",
+            "Priority",
+        ]);
+        for batch in &stats.batches {
+            let textures: Vec<String> = batch.textures.iter().map(u16::to_string).collect();
+            add_table_row(
+                &mut table,
+                vec![
+                    batch.index.to_string(),
+                    batch
+                        .submesh
+                        .map_or_else(|| "-".to_string(), |s| s.to_string()),
+                    batch.geoset_id.to_string(),
+                    batch.triangles.to_string(),
+                    batch.bones.to_string(),
+                    batch.bone_influences.to_string(),
+                    format!("{} [{}]", batch.texture_units, textures.join(", ")),
+                    batch
+                        .blend_mode
+                        .map_or_else(|| "-".to_string(), |mode| blend_mode_name(mode.bits())),
+                    batch.material_layer.to_string(),
+                    batch.priority_plane.to_string(),
+                ],
+            );
+        }
+        println!();
+        table.printstd();
+    }
+
+    if !stats.invalid_batches.is_empty() {
+        let batches: Vec<String> = stats.invalid_batches.iter().map(usize::to_string).collect();
+        println!(
+            "\nWarning: batches {} reference a missing submesh, material or texture",
+            batches.join(", ")
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,329 @@
+//! Texture listing with replaceable texture resolution
+//!
+//! Character and creature models leave most texture filenames empty and only
+//! record a texture type. The client fills these slots at runtime; here they
+//! come from a `CreatureDisplayInfo.dbc` row (`--display-id`), an
+//! `ItemDisplayInfo.dbc` row (`--item-display-id`) or explicit
+//! `--texture TYPE=PATH` assignments, e.g. the CharSections.dbc textures of a
+//! character. With a data directory or archive, particle colors come from the
+//! display's `ParticleColor.dbc` row.
+
+use anyhow::{Context, Result};
+use clap::Args;
+use std::fs;
+use std::path::{Path, PathBuf};
+use wow_m2::chunks::texture::M2TextureType;
+use wow_m2::{DisplayTables, M2Model, ParticleColorOverride, ReplaceableTextures};
+
+use crate::utils::assets::AssetSource;
+use crate::utils::{add_table_row, create_table};
+
+/// Client path of the creature display table
+const CREATURE_DISPLAY_INFO: &str = "DBFilesClient\\CreatureDisplayInfo.dbc";
+
+/// Client path of the item display table
+const ITEM_DISPLAY_INFO: &str = "DBFilesClient\\ItemDisplayInfo.dbc";
+
+/// Client path of the particle color table
+const PARTICLE_COLOR: &str = "DBFilesClient\\ParticleColor.dbc";
+
+/// Names accepted for replaceable texture types in `--texture`
+const TEXTURE_TYPE_NAMES: &[(&str, M2TextureType)] = &[
+    ("body", M2TextureType::Body),
+    ("item", M2TextureType::Item),
+    ("cape", M2TextureType::Item),
+    ("weapon-blade", M2TextureType::WeaponBlade),
+    ("weapon-handle", M2TextureType::WeaponHandle),
+    ("environment", M2TextureType::Environment),
+    ("hair", M2TextureType::Hair),
+    ("facial-hair", M2TextureType::FacialHair),
+    ("skin-extra", M2TextureType::SkinExtra),
+    ("ui-skin", M2TextureType::UiSkin),
+    ("tauren-mane", M2TextureType::TaurenMane),
+    ("monster1", M2TextureType::Monster1),
+    ("monster2", M2TextureType::Monster2),
+    ("monster3", M2TextureType::Monster3),
+    ("item-icon", M2TextureType::ItemIcon),
+    ("guild-background", M2TextureType::GuildBackgroundColor),
+    ("guild-emblem-color", M2TextureType::GuildEmblemColor),
+    ("guild-border", M2TextureType::GuildBorderColor),
+    ("guild-emblem", M2TextureType::GuildEmblem),
+];
+
+/// Inputs for the replaceable textures of a model
+#[derive(Args, Debug, Clone, Default)]
+pub struct TextureArgs {
+    /// Take monster skins from this CreatureDisplayInfo.dbc row
+    #[arg(long, value_name = "ID", group = "display")]
+    pub display_id: Option<u32>,
+
+    /// Take the item texture from this ItemDisplayInfo.dbc row
+    #[arg(long, value_name = "ID", group = "display")]
+    pub item_display_id: Option<u32>,
+
+    /// Which model of the item display this is: 0, or 1 for the left
+    /// shoulder or off-hand model
+    #[arg(
+        long,
+        value_name = "N",
+        default_value = "0",
+        requires = "item_display_id"
+    )]
+    pub item_model: usize,
+
+    /// The display's DBC file, or an archive or data directory containing it
+    /// and ParticleColor.dbc (the profile's data directory if not given)
+    #[arg(long, value_name = "DBC_OR_DATA", requires = "display")]
+    pub display_info: Option<PathBuf>,
+
+    /// Texture for a replaceable slot, e.g. "hair=Character\Human\Hair00_00.blp"
+    /// (types: body, cape, hair, skin-extra, monster1-3, ... or the numeric type)
+    #[arg(long = "texture", value_name = "TYPE=PATH", value_parser = parse_texture_assignment)]
+    pub textures: Vec<(M2TextureType, String)>,
+}
+
+fn parse_texture_assignment(s: &str) -> Result<(M2TextureType, String), String> {
+    let (kind, path) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected TYPE=PATH, got '{s}'"))?;
+    let kind = kind.trim();
+    let texture_type = match kind.parse::<u32>() {
+        Ok(value) => M2TextureType::from_u32(value),
+        Err(_) => TEXTURE_TYPE_NAMES
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(kind))
+            .map(|(_, texture_type)| *texture_type),
+    }
+    .filter(|t| t.is_replaceable())
+    .ok_or_else(|| format!("'{kind}' is not a replaceable texture type"))?;
+    Ok((texture_type, path.trim().to_string()))
+}
+
+/// Load the display table for `--display-id` or `--item-display-id`
+///
+/// A single DBC file is taken as that table. From an archive or data
+/// directory ParticleColor.dbc is loaded too, when present.
+fn load_display_tables(path: Option<&Path>, table: &str) -> Result<DisplayTables> {
+    let path = match path {
+        Some(path) => path.to_path_buf(),
+        None => crate::utils::config::profile_data_dir().context(
+            "a display ID needs --display-info when no profile with a data_dir is active",
+        )?,
+    };
+    let (data, particle_colors) = if path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("dbc"))
+    {
+        let data = fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+        (data, None)
+    } else {
+        let mut source = AssetSource::open(&path)?;
+        let data = source
+            .read(table)?
+            .with_context(|| format!("{table} not found in {}", path.display()))?;
+        (data, source.read(PARTICLE_COLOR)?)
+    };
+
+    let tables = if table == ITEM_DISPLAY_INFO {
+        DisplayTables::new().with_item_display_info(&data)?
+    } else {
+        DisplayTables::new().with_creature_display_info(&data)?
+    };
+    Ok(match particle_colors {
+        Some(data) => tables.with_particle_color(&data)?,
+        None => tables,
+    })
+}
+
+impl TextureArgs {
+    fn display_tables(&self) -> Result<Option<DisplayTables>> {
+        let table = match (self.display_id, self.item_display_id) {
+            (Some(_), _) => CREATURE_DISPLAY_INFO,
+            (None, Some(_)) => ITEM_DISPLAY_INFO,
+            (None, None) => return Ok(None),
+        };
+        load_display_tables(self.display_info.as_deref(), table).map(Some)
+    }
+
+    /// Replaceable textures for the model at `model_path`
+    ///
+    /// Creature skins and item textures are placed next to the model;
+    /// explicit `--texture` assignments take precedence.
+    pub fn replaceable_textures(&self, model_path: &Path) -> Result<ReplaceableTextures> {
+        let tables = self.display_tables()?;
+        self.textures_from(tables.as_ref(), model_path)
+    }
+
+    fn textures_from(
+        &self,
+        tables: Option<&DisplayTables>,
+        model_path: &Path,
+    ) -> Result<ReplaceableTextures> {
+        let model_path = model_path.to_string_lossy();
+        let mut textures = match (tables, self.display_id, self.item_display_id) {
+            (Some(tables), Some(display_id), _) => tables
+                .creature_textures(&model_path, display_id)
+                .context("Failed to read CreatureDisplayInfo.dbc")?,
+            (Some(tables), None, Some(display_id)) => tables
+                .item_textures(&model_path, display_id, self.item_model)
+                .context("Failed to read ItemDisplayInfo.dbc")?,
+            _ => ReplaceableTextures::new(),
+        };
+        for (texture_type, path) in &self.textures {
+            textures.set(*texture_type, path.clone());
+        }
+        Ok(textures)
+    }
+}
+
+/// Particle colors of the display, if it has them and ParticleColor.dbc was found
+fn particle_colors(
+    tables: &DisplayTables,
+    args: &TextureArgs,
+    model: &M2Model,
+) -> Vec<ParticleColorOverride> {
+    let appearance = match (args.display_id, args.item_display_id) {
+        (Some(display_id), _) => tables.creature_appearance(model, "", display_id),
+        (None, Some(display_id)) => tables.item_appearance(model, "", display_id, args.item_model),
+        (None, None) => return Vec::new(),
+    };
+    appearance.map_or_else(
+        |e| {
+            log::warn!("Particle colors unavailable: {e}");
+            Vec::new()
+        },
+        |appearance| appearance.particle_colors,
+    )
+}
+
+fn format_color(color: wow_m2::chunks::color_animation::M2Color) -> String {
+    let [r, g, b] = [color.r, color.g, color.b].map(|c| (c * 255.0).round() as u8);
+    format!("#{r:02X}{g:02X}{b:02X}")
+}
+
+pub fn list_textures(file: &Path, args: &TextureArgs) -> Result<()> {
+    let format = M2Model::load(file)
+        .with_context(|| format!("Failed to load M2 model from {}", file.display()))?;
+    let model = format.model();
+    let tables = args.display_tables()?;
+    let replaceable = args.textures_from(tables.as_ref(), file)?;
+
+    if model.textures.is_empty() {
+        println!("{} has no textures", file.display());
+        return Ok(());
+    }
+
+    let mut table = create_table(vec!["#", "Type", "Flags", "Path"]);
+    let mut unresolved = 0;
+    for resolved in replaceable.resolve_model(model) {
+        let texture = &model.textures[resolved.index];
+        let path = resolved.path.unwrap_or_else(|| {
+            unresolved += usize::from(resolved.texture_type.is_replaceable());
+            "-".to_string()
+        });
+        add_table_row(
+            &mut table,
+            vec![
+                resolved.index.to_string(),
+                format!(
+                    "{} ({})",
+                    resolved.texture_type, texture.texture_type as u32
+                ),
+                format!("0x{:X}", texture.flags.bits()),
+                path,
+            ],
+        );
+    }
+
+    println!("Textures: {}", file.display());
+    table.printstd();
+    if unresolved > 0 {
+        println!(
+            "\n{unresolved} replaceable texture(s) unresolved; pass --display-id for creatures \
+             or --texture TYPE=PATH"
+        );
+    }
+
+    let particle_colors = match &tables {
+        Some(tables) => particle_colors(tables, args, model),
+        None => Vec::new(),
+    };
+    if !particle_colors.is_empty() {
+        let mut table = create_table(vec!["Emitter", "Start", "Mid", "End"]);
+        for colors in &particle_colors {
+            add_table_row(
+                &mut table,
+                vec![
+                    colors.emitter_index.to_string(),
+                    format_color(colors.start),
+                    format_color(colors.mid),
+                    format_color(colors.end),
+                ],
+            );
+        }
+        println!("\nParticle colors from {PARTICLE_COLOR}:");
+        table.printstd();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_texture_assignment() {
+        assert_eq!(
+            parse_texture_assignment("cape=Item\\ObjectComponents\\Cape\\Cape_A.blp").unwrap(),
+            (
+                M2TextureType::Item,
+                "Item\\ObjectComponents\\Cape\\Cape_A.blp".to_string()
+            )
+        );
+        assert_eq!(
+            parse_texture_assignment("11=skin.blp").unwrap().0,
+            M2TextureType::Monster1
+        );
+        assert!(parse_texture_assignment("0=skin.blp").is_err());
+        assert!(parse_texture_assignment("hair").is_err());
+    }
+
+    #[test]
+    fn test_display_textures() {
+        let mut data = Vec::new();
+        data.extend_from_slice(b"WDBC");
+        data.extend_from_slice(&1u32.to_le_bytes()); // record_count
+        data.extend_from_slice(&9u32.to_le_bytes()); // field_count
+        data.extend_from_slice(&36u32.to_le_bytes()); // record_size
+        data.extend_from_slice(&14u32.to_le_bytes()); // string_block_size
+        for value in [42u32, 7, 0, 0, 0, 0, 1, 0, 0] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(b"\0WolfSkinGray\0");
+
+        let dir = tempfile::tempdir().unwrap();
+        let dbc = dir.path().join("CreatureDisplayInfo.dbc");
+        fs::write(&dbc, data).unwrap();
+
+        let args = TextureArgs {
+            display_id: Some(42),
+            display_info: Some(dbc),
+            textures: vec![(M2TextureType::Monster2, "Other.blp".to_string())],
+            ..Default::default()
+        };
+        let textures = args
+            .replaceable_textures(Path::new("Creature/Wolf/Wolf.m2"))
+            .unwrap();
+        assert_eq!(
+            textures.get(M2TextureType::Monster1),
+            Some("Creature/Wolf/WolfSkinGray.blp")
+        );
+        assert_eq!(textures.get(M2TextureType::Monster2), Some("Other.blp"));
+
+        let missing = TextureArgs {
+            display_id: Some(1),
+            ..args
+        };
+        assert!(missing.replaceable_textures(Path::new("Wolf.m2")).is_err());
+    }
+}
@@ -26,3 +26,11 @@ pub mod wdt;
 
 #[cfg(feature = "wdl")]
 pub mod wdl;
+
+#[cfg(feature = "mpq")]
+pub mod compare;
+
+#[cfg(feature = "mpq")]
+pub mod asset;
+
+pub mod tree;
@@ -2,20 +2,31 @@
 
 use anyhow::{Context, Result};
 use clap::{Subcommand, ValueEnum};
+use indicatif::ProgressBar;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use wow_mpq::{
-    Archive, ArchiveBuilder, FormatVersion, PatchChain, RebuildOptions,
-    compare_archives as mpq_compare_archives,
+    Archive, ArchiveBuilder, FormatVersion, PatchChain, Progress, ProgressSink, ProgressStage,
+    RebuildOptions, compare_archives_with_options as mpq_compare_archives,
     debug::{
         HexDumpConfig, dump_block_entry, dump_hash_entry, format_bet_table, format_block_table,
         format_hash_table, format_het_table, hex_dump,
     },
-    path::mpq_path_to_system,
-    rebuild_archive,
+    rebuild_archive_with_progress,
     single_archive_parallel::{ParallelArchive, ParallelConfig},
 };
 
+pub(crate) mod chain;
+mod collisions;
+mod diff;
+mod extract;
+mod optimize;
+mod recover;
+mod shell;
+mod verify;
+mod watch;
+
 use crate::utils::{
     NodeType, TreeNode, TreeOptions, add_table_row, create_progress_bar, create_spinner,
     create_table, detect_ref_type, format_bytes, format_compression_ratio, matches_pattern,
@@ -58,6 +69,15 @@ pub enum MpqCommands {
         /// Show block table details
         #[arg(long)]
         show_block_table: bool,
+
+        /// Show the file's encryption key and stored sector layout
+        #[arg(long, requires = "file")]
+        sectors: bool,
+
+        /// Write each stored sector of the file, still compressed and
+        /// encrypted, to this directory
+        #[arg(long, value_name = "DIR", requires = "file")]
+        dump_sectors: Option<PathBuf>,
     },
 
     /// Validate integrity of an MPQ archive
@@ -74,6 +94,40 @@ pub enum MpqCommands {
         threads: Option<usize>,
     },
 
+    /// Verify signature, block table, sector CRCs and attributes for CI
+    Verify {
+        /// Path to the MPQ archive
+        archive: String,
+
+        /// Read every file and check its sector CRCs and attribute CRC32/MD5
+        #[arg(long)]
+        deep: bool,
+
+        /// Write a JSON report with per-file results
+        #[arg(long)]
+        report: Option<PathBuf>,
+    },
+
+    /// Find file names the hash table cannot tell apart
+    Collisions {
+        /// Path to the MPQ archive
+        archive: String,
+
+        /// Listfiles with names to check besides the archive's own (listfile)
+        #[arg(short, long)]
+        listfile: Vec<PathBuf>,
+    },
+
+    /// Open a protected or damaged archive and list or extract what is readable
+    Recover {
+        /// Path to the MPQ archive
+        archive: String,
+
+        /// Extract the recovered files into this directory
+        #[arg(long)]
+        extract: Option<PathBuf>,
+    },
+
     /// List files in an MPQ archive
     List {
         /// Path to the MPQ archive
@@ -98,6 +152,11 @@ pub enum MpqCommands {
         /// Show only files with patch flag (Cataclysm+ PTCH files)
         #[arg(long)]
         show_patches: bool,
+
+        /// External listfile naming files the archive's own listfile lacks
+        /// (defaults to the profile's listfile)
+        #[arg(long, value_name = "FILE")]
+        listfile: Option<std::path::PathBuf>,
     },
 
     /// Extract files from an MPQ archive
@@ -116,21 +175,46 @@ pub enum MpqCommands {
         #[arg(short, long)]
         file_type: Option<String>,
 
+        /// Only extract files matching this glob (e.g. "*.blp", "Interface/**"); repeatable
+        #[arg(long, value_name = "GLOB", action = clap::ArgAction::Append)]
+        include: Vec<String>,
+
+        /// Skip files matching this glob; repeatable
+        #[arg(long, value_name = "GLOB", action = clap::ArgAction::Append)]
+        exclude: Vec<String>,
+
         /// Preserve directory structure
-        #[arg(short, long)]
+        #[arg(short, long, conflicts_with = "flat")]
         preserve_paths: bool,
 
-        /// Number of threads for parallel extraction (default: CPU cores)
+        /// Write every file directly into the output directory (default)
         #[arg(long)]
-        threads: Option<usize>,
+        flat: bool,
 
-        /// Continue extraction even if some files fail
+        /// Number of parallel extraction jobs (default: CPU cores)
+        #[arg(short, long, alias = "threads")]
+        jobs: Option<usize>,
+
+        /// Exit successfully even if some files fail to extract
         #[arg(long)]
         skip_errors: bool,
 
+        /// Skip files that already exist in the output directory, to resume an earlier run
+        #[arg(long)]
+        skip_existing: bool,
+
+        /// Write the list of failed files and their errors to this file
+        #[arg(long, value_name = "FILE")]
+        error_log: Option<PathBuf>,
+
         /// Patch archives to apply (in order of priority)
         #[arg(long = "patch", action = clap::ArgAction::Append)]
         patches: Vec<String>,
+
+        /// Add names of extracted files that the archives' listfiles lack to
+        /// the hash database (default: the profile's learn_names)
+        #[arg(long)]
+        learn_names: bool,
     },
 
     /// Create a new MPQ archive
@@ -146,13 +230,43 @@ pub enum MpqCommands {
         #[arg(long, default_value = "v2")]
         version: String,
 
-        /// Compression method (none, zlib, bzip2, lzma)
+        /// Compression method (none, zlib, bzip2, lzma, implode)
         #[arg(short, long, default_value = "zlib")]
         compression: String,
 
         /// Create or update (listfile)
         #[arg(long)]
         with_listfile: bool,
+
+        /// Produce byte-identical output for the same inputs, regardless of
+        /// file order or build time
+        #[arg(long)]
+        deterministic: bool,
+    },
+
+    /// Rebuild an archive from a directory whenever its files change
+    Watch {
+        /// Directory whose contents make up the archive
+        source: PathBuf,
+
+        /// Archive to keep up to date
+        archive: PathBuf,
+
+        /// Archive format version used when building from scratch
+        #[arg(long, value_enum, default_value = "v2")]
+        version: VersionArg,
+
+        /// Compression method (none, zlib, bzip2, lzma, implode)
+        #[arg(short, long, default_value = "zlib")]
+        compression: String,
+
+        /// Milliseconds without changes before the archive is updated
+        #[arg(long, default_value_t = 300)]
+        debounce: u64,
+
+        /// Rebuild the archive on start even if it already exists
+        #[arg(long)]
+        rebuild: bool,
     },
 
     /// Rebuild an MPQ archive 1:1
@@ -183,6 +297,11 @@ pub enum MpqCommands {
         #[arg(long)]
         verify: bool,
 
+        /// Also check the rebuilt tables against what this client expects:
+        /// vanilla, tbc, wotlk, cata or a version number (implies --verify)
+        #[arg(long)]
+        verify_layout: Option<wow_mpq::GameVersion>,
+
         /// Override compression method
         #[arg(long)]
         compression: Option<String>,
@@ -196,6 +315,36 @@ pub enum MpqCommands {
         list_only: bool,
     },
 
+    /// Rebuild an archive for size: recompress, drop dead space, share duplicates
+    Optimize {
+        /// Source MPQ archive
+        source: String,
+
+        /// Path for the optimized archive
+        #[arg(required_unless_present = "what_if")]
+        target: Option<String>,
+
+        /// Convert to this format version (defaults to the source format)
+        #[arg(long, value_enum)]
+        target_version: Option<VersionArg>,
+
+        /// How to recompress files
+        #[arg(long, value_enum, default_value = "best")]
+        recompress: optimize::Recompress,
+
+        /// Store files with identical contents only once
+        #[arg(long)]
+        dedup: bool,
+
+        /// Override block size (sector size = 512 * 2^block_size)
+        #[arg(long)]
+        block_size: Option<u16>,
+
+        /// Only report what each compression method would store, without rebuilding
+        #[arg(long)]
+        what_if: bool,
+    },
+
     /// Compare two MPQ archives
     Compare {
         /// Source MPQ archive
@@ -227,6 +376,35 @@ pub enum MpqCommands {
         /// Filter files by pattern (supports wildcards)
         #[arg(short, long)]
         filter: Option<String>,
+
+        /// Stop at the first difference instead of listing all of them
+        #[arg(long)]
+        stop_at_first: bool,
+    },
+
+    /// Show added, removed and changed files between two archives
+    Diff {
+        /// Old MPQ archive
+        old: String,
+
+        /// New MPQ archive
+        new: String,
+
+        /// Output format
+        #[arg(long, value_enum, default_value = "text")]
+        format: diff::DiffFormat,
+
+        /// Filter files by pattern (supports wildcards)
+        #[arg(short, long)]
+        filter: Option<String>,
+
+        /// Write added and changed files to a patch archive
+        #[arg(long)]
+        patch_out: Option<String>,
+
+        /// Write deletion markers for removed files into the patch archive
+        #[arg(long, requires = "patch_out")]
+        deletion_markers: bool,
     },
 
     /// Show tree structure of an MPQ archive
@@ -307,6 +485,32 @@ pub enum MpqCommands {
         detailed: bool,
     },
 
+    /// Mount a game installation's archives as a patch chain
+    Chain {
+        /// WoW installation or Data directory (defaults to the profile's data_dir)
+        #[arg(long)]
+        data_dir: Option<std::path::PathBuf>,
+
+        /// Locale to mount (the profile's locale, or auto-detected)
+        #[arg(long)]
+        locale: Option<String>,
+
+        /// Client whose loading order to use: vanilla, tbc, wotlk, cata or a
+        /// version number (auto-detected from the base archives)
+        #[arg(long)]
+        game_version: Option<wow_mpq::GameVersion>,
+
+        #[command(subcommand)]
+        command: chain::ChainCommands,
+    },
+
+    /// Interactive shell over one or more archives
+    Shell {
+        /// MPQ archives to mount (later archives override earlier ones)
+        #[arg(required = true)]
+        archives: Vec<String>,
+    },
+
     /// Database operations for MPQ hash resolution
     #[command(subcommand)]
     Db(DbCommands),
@@ -323,13 +527,17 @@ pub enum DbCommands {
 
     /// Import filenames from various sources
     Import {
-        /// Path to import from (listfile, MPQ archive, or directory)
+        /// Path to import from (listfile, CSV listfile, CASC root, MPQ archive, or directory)
         path: String,
 
         /// Source type
         #[arg(value_enum)]
         source_type: ImportSourceArg,
 
+        /// Community listfile naming the files of a CASC root manifest
+        #[arg(long, value_name = "CSV", required_if_eq("source_type", "casc-root"))]
+        names: Option<PathBuf>,
+
         /// Show progress
         #[arg(long)]
         show_progress: bool,
@@ -381,6 +589,10 @@ pub enum DbCommands {
 pub enum ImportSourceArg {
     /// Import from a listfile
     Listfile,
+    /// Import from a community listfile (`FileDataID;path` CSV)
+    ListfileCsv,
+    /// Import the files of a CASC root manifest (requires --names)
+    CascRoot,
     /// Import from an MPQ archive's internal listfile
     Archive,
     /// Scan a directory for WoW file patterns
@@ -396,44 +608,144 @@ pub async fn execute(command: MpqCommands) -> Result<()> {
             use_db,
             record_to_db,
             show_patches,
-        } => list_archive(&archive, long, filter, use_db, record_to_db, show_patches).await,
+            listfile,
+        } => {
+            let listfile = listfile
+                .or_else(|| crate::utils::config::active().and_then(|p| p.listfile.clone()));
+            list_archive(
+                &archive,
+                long,
+                filter,
+                use_db,
+                record_to_db,
+                show_patches,
+                listfile.as_deref(),
+            )
+            .await
+        }
         MpqCommands::Extract {
             archive,
             output,
             files,
             file_type,
+            include,
+            exclude,
             preserve_paths,
-            threads,
-            skip_errors,
-            patches,
-        } => extract_files(
-            &archive,
-            &output,
-            files,
-            file_type,
-            preserve_paths,
-            threads,
+            flat: _,
+            jobs,
             skip_errors,
+            skip_existing,
+            error_log,
             patches,
-        ),
+            learn_names,
+        } => {
+            let learn_names = learn_names
+                || crate::utils::config::active().is_some_and(|profile| profile.learn_names);
+            let source = format!(
+                "learned:{}",
+                Path::new(&archive)
+                    .file_name()
+                    .map_or_else(|| archive.clone(), |n| n.to_string_lossy().into_owned())
+            );
+            let mut learned = Vec::new();
+            let result = extract::extract(
+                extract::ExtractOptions {
+                    archive_path: archive,
+                    output_dir: output,
+                    files,
+                    file_type,
+                    include,
+                    exclude,
+                    preserve_paths,
+                    jobs,
+                    skip_errors,
+                    skip_existing,
+                    error_log,
+                    patches,
+                    learn_names,
+                },
+                &mut learned,
+            );
+            if !learned.is_empty() {
+                match crate::database::learn_names(&learned, &source).await {
+                    Ok(0) => {}
+                    Ok(count) => println!("Learned {count} new file names into the hash database"),
+                    Err(e) => log::warn!("Failed to record learned names: {e}"),
+                }
+            }
+            result
+        }
         MpqCommands::Create {
             archive,
             add,
             version,
             compression,
             with_listfile,
-        } => create_archive(&archive, add, &version, &compression, with_listfile),
+            deterministic,
+        } => create_archive(
+            &archive,
+            add,
+            &version,
+            &compression,
+            with_listfile,
+            deterministic,
+        ),
         MpqCommands::Info {
             archive,
             file,
             show_hash_table,
             show_block_table,
-        } => show_info(&archive, file.as_deref(), show_hash_table, show_block_table),
+            sectors,
+            dump_sectors,
+        } => show_info(
+            &archive,
+            file.as_deref(),
+            show_hash_table,
+            show_block_table,
+            sectors,
+            dump_sectors.as_deref(),
+        ),
         MpqCommands::Validate {
             archive,
             check_checksums,
             threads,
         } => validate_archive(&archive, check_checksums, threads),
+        MpqCommands::Watch {
+            source,
+            archive,
+            version,
+            compression,
+            debounce,
+            rebuild,
+        } => watch::watch_archive(watch::WatchParams {
+            source: &source,
+            archive: &archive,
+            version: version.into(),
+            compression: compression_flags(&compression)?,
+            debounce: std::time::Duration::from_millis(debounce),
+            rebuild,
+        }),
+        MpqCommands::Verify {
+            archive,
+            deep,
+            report,
+        } => verify::verify_archive(verify::VerifyParams {
+            archive: &archive,
+            deep,
+            report: report.as_deref(),
+        }),
+        MpqCommands::Collisions { archive, listfile } => {
+            collisions::audit_collisions(collisions::CollisionParams {
+                archive: &archive,
+                listfiles: &listfile,
+            })
+        }
+        MpqCommands::Recover { archive, extract } => {
+            recover::recover_archive(recover::RecoverParams {
+                archive: &archive,
+                extract: extract.as_deref(),
+            })
+        }
         MpqCommands::Rebuild {
             source,
             target,
@@ -442,6 +754,7 @@ pub async fn execute(command: MpqCommands) -> Result<()> {
             skip_encrypted,
             skip_signatures,
             verify,
+            verify_layout,
             compression,
             block_size,
             list_only,
@@ -453,10 +766,30 @@ pub async fn execute(command: MpqCommands) -> Result<()> {
             skip_encrypted,
             skip_signatures,
             verify,
+            verify_layout,
             compression,
             block_size,
             list_only,
         }),
+        MpqCommands::Optimize {
+            source,
+            target,
+            target_version,
+            recompress,
+            dedup,
+            block_size,
+            what_if,
+        } => match target {
+            Some(target) if !what_if => optimize::optimize_archive(optimize::OptimizeParams {
+                source: &source,
+                target: &target,
+                target_version: target_version.map(Into::into),
+                recompress,
+                dedup,
+                block_size,
+            }),
+            _ => optimize::what_if_report(&source, block_size),
+        },
         MpqCommands::Compare {
             source,
             target,
@@ -466,6 +799,7 @@ pub async fn execute(command: MpqCommands) -> Result<()> {
             ignore_order,
             output,
             filter,
+            stop_at_first,
         } => compare_archives(CompareParams {
             source_path: &source,
             target_path: &target,
@@ -475,6 +809,22 @@ pub async fn execute(command: MpqCommands) -> Result<()> {
             ignore_order,
             output_format: &output,
             filter,
+            stop_at_first,
+        }),
+        MpqCommands::Diff {
+            old,
+            new,
+            format,
+            filter,
+            patch_out,
+            deletion_markers,
+        } => diff::diff_archives(diff::DiffParams {
+            old_path: &old,
+            new_path: &new,
+            format,
+            filter,
+            patch_out: patch_out.as_deref(),
+            deletion_markers,
         }),
         MpqCommands::Tree {
             archive,
@@ -516,6 +866,20 @@ pub async fn execute(command: MpqCommands) -> Result<()> {
             patches,
             detailed,
         } => visualize_patch_chain(&base, patches, detailed),
+        MpqCommands::Chain {
+            data_dir,
+            locale,
+            game_version,
+            command,
+        } => {
+            let profile = crate::utils::config::active();
+            let data_dir = data_dir
+                .or_else(|| profile.and_then(|p| p.data_dir.clone()))
+                .context("--data-dir is required when no profile with a data_dir is active")?;
+            let locale = locale.or_else(|| profile.and_then(|p| p.locale.clone()));
+            chain::execute_chain(&data_dir, locale.as_deref(), game_version, command)
+        }
+        MpqCommands::Shell { archives } => shell::run_shell(&archives),
         MpqCommands::Db(db_command) => execute_db_command(db_command).await,
     }
 }
@@ -527,6 +891,7 @@ async fn list_archive(
     use_db: bool,
     record_to_db: bool,
     show_patches: bool,
+    listfile: Option<&Path>,
 ) -> Result<()> {
     use crate::database::Database;
 
@@ -554,7 +919,7 @@ async fn list_archive(
     }
 
     // Get file list
-    let entries = if use_db {
+    let mut entries = if use_db {
         if let Some(ref db) = db {
             list_with_db(&mut archive, db).await?
         } else {
@@ -564,26 +929,34 @@ async fn list_archive(
         archive.list()?
     };
 
+    if let Some(listfile) = listfile {
+        let named = apply_external_listfile(&mut archive, &mut entries, listfile)?;
+        if named > 0 {
+            log::info!("Named {named} files from {}", listfile.display());
+        }
+    }
+
+    if !use_db {
+        let named = resolve_from_db(&mut archive, &mut entries).await?;
+        if named > 0 {
+            log::info!("Named {named} files from the hash database");
+        }
+    }
+
     // Filter for patch files if requested
-    let files: Vec<String> = if show_patches {
-        entries
-            .into_iter()
-            .filter(|e| e.is_patch_file())
-            .map(|e| e.name)
-            .collect()
-    } else {
-        entries.into_iter().map(|e| e.name).collect()
-    };
+    if show_patches {
+        entries.retain(|e| e.is_patch_file());
+    }
 
     let pattern = filter.as_deref().unwrap_or("*");
 
-    let mut filtered_files: Vec<_> = files
+    let mut filtered: Vec<_> = entries
         .iter()
-        .filter(|f| matches_pattern(f, pattern))
+        .filter(|e| matches_pattern(&e.name, pattern))
         .collect();
-    filtered_files.sort();
+    filtered.sort_by(|a, b| a.name.cmp(&b.name));
 
-    if filtered_files.is_empty() {
+    if filtered.is_empty() {
         println!("No files found matching pattern: {pattern}");
         return Ok(());
     }
@@ -591,33 +964,78 @@ async fn list_archive(
     if long {
         let mut table = create_table(vec!["File", "Size", "Compressed", "Ratio"]);
 
-        for file in filtered_files {
-            // Try to get file info from the entries
-            if let Ok(entries) = archive.list()
-                && let Some(entry) = entries.iter().find(|e| &e.name == file)
-            {
-                add_table_row(
-                    &mut table,
-                    vec![
-                        truncate_path(file, 50),
-                        format_bytes(entry.size),
-                        format_bytes(entry.compressed_size),
-                        format_compression_ratio(entry.size, entry.compressed_size),
-                    ],
-                );
-            }
+        for entry in filtered {
+            add_table_row(
+                &mut table,
+                vec![
+                    truncate_path(&entry.name, 50),
+                    format_bytes(entry.size),
+                    format_bytes(entry.compressed_size),
+                    format_compression_ratio(entry.size, entry.compressed_size),
+                ],
+            );
         }
 
         table.printstd();
     } else {
-        for file in filtered_files {
-            println!("{file}");
+        for entry in filtered {
+            println!("{}", entry.name);
         }
     }
 
     Ok(())
 }
 
+/// Name listed entries from an external listfile
+///
+/// Files the archive does not list at all are added, and entries that only
+/// carry a generated name are renamed. Returns the number of files named.
+fn apply_external_listfile(
+    archive: &mut Archive,
+    entries: &mut Vec<wow_mpq::FileEntry>,
+    listfile: &Path,
+) -> Result<usize> {
+    let data = fs::read(listfile)
+        .with_context(|| format!("Failed to read listfile {}", listfile.display()))?;
+    let names = wow_mpq::special_files::parse_external_listfile(&data)
+        .with_context(|| format!("Failed to parse listfile {}", listfile.display()))?;
+    Ok(archive.apply_listfile_names(entries, names)?)
+}
+
+/// Name listed entries that still carry generated names from the hash database
+///
+/// The database is only consulted when it already exists and the archive
+/// has unnamed files. Returns the number of files named.
+async fn resolve_from_db(
+    archive: &mut Archive,
+    entries: &mut Vec<wow_mpq::FileEntry>,
+) -> Result<usize> {
+    let mut unnamed = false;
+    for entry in entries.iter() {
+        if archive.find_file(&entry.name)?.is_none() {
+            unnamed = true;
+            break;
+        }
+    }
+    if !unnamed {
+        return Ok(0);
+    }
+
+    let resolver = match crate::database::NameResolver::open_default().await {
+        Ok(Some(resolver)) => resolver,
+        Ok(None) => return Ok(0),
+        Err(e) => {
+            log::warn!("Hash database unavailable: {e}");
+            return Ok(0);
+        }
+    };
+    let names = resolver
+        .names_for_entries(&archive.list_all_with_hashes()?)
+        .await
+        .context("Failed to query hash database")?;
+    Ok(archive.apply_listfile_names(entries, names)?)
+}
+
 /// List files in an archive with database lookup for names
 async fn list_with_db(
     archive: &mut Archive,
@@ -670,295 +1088,16 @@ async fn record_listfile_to_db(
     Ok(0)
 }
 
-struct ExtractOptions {
-    archive_path: String,
-    output_dir: String,
-    files: Vec<String>,
-    file_type: Option<String>,
-    preserve_paths: bool,
-    threads: Option<usize>,
-    skip_errors: bool,
-    patches: Vec<String>,
-}
-
-#[allow(clippy::too_many_arguments)]
-fn extract_files(
-    archive_path: &str,
-    output_dir: &str,
-    files: Vec<String>,
-    file_type: Option<String>,
-    preserve_paths: bool,
-    threads: Option<usize>,
-    skip_errors: bool,
-    patches: Vec<String>,
-) -> Result<()> {
-    let options = ExtractOptions {
-        archive_path: archive_path.to_string(),
-        output_dir: output_dir.to_string(),
-        files,
-        file_type,
-        preserve_paths,
-        threads,
-        skip_errors,
-        patches,
-    };
-
-    extract_files_with_options(options)
-}
-
-fn extract_files_with_options(options: ExtractOptions) -> Result<()> {
-    let ExtractOptions {
-        archive_path,
-        output_dir,
-        files,
-        file_type,
-        preserve_paths,
-        threads,
-        skip_errors,
-        patches,
-    } = options;
-    if patches.is_empty() {
-        // Use parallel extraction by default
-        let files_to_extract: Vec<String> = if files.is_empty() {
-            // For bulk extraction, read listfile directly to avoid slow database lookups
-            println!("Reading file list from archive...");
-            let mut archive = Archive::open(&archive_path).context("Failed to open archive")?;
-
-            // Try to read (listfile) directly for faster bulk operations
-            let mut file_list = match archive.read_file("(listfile)") {
-                Ok(listfile_data) => {
-                    println!("Parsing listfile...");
-                    match wow_mpq::special_files::parse_listfile(&listfile_data) {
-                        Ok(filenames) => {
-                            println!("Found {} files in listfile", filenames.len());
-                            filenames
-                        }
-                        Err(_) => {
-                            println!(
-                                "Failed to parse listfile, falling back to slow enumeration..."
-                            );
-                            let file_list = archive
-                                .list()?
-                                .into_iter()
-                                .map(|e| e.name)
-                                .collect::<Vec<String>>();
-                            println!("Found {} files", file_list.len());
-                            file_list
-                        }
-                    }
-                }
-                Err(_) => {
-                    println!("No listfile found, using slow enumeration...");
-                    let file_list = archive
-                        .list()?
-                        .into_iter()
-                        .map(|e| e.name)
-                        .collect::<Vec<String>>();
-                    println!("Found {} files", file_list.len());
-                    file_list
-                }
-            };
-
-            // Filter by file type if specified
-            if let Some(file_type) = file_type {
-                println!("Filtering by file type...");
-                file_list.retain(|f| {
-                    // Ignore case
-                    let lowercase_filename = f.to_lowercase();
-                    let lowercase_file_type = file_type.to_lowercase();
-                    lowercase_filename.ends_with(&lowercase_file_type)
-                });
-                println!(
-                    "Found {} files matching type {}",
-                    file_list.len(),
-                    file_type
-                );
-            }
-
-            file_list
-        } else {
-            files
-        };
-
-        let pb = create_progress_bar(files_to_extract.len() as u64, "Extracting files");
-
-        // Configure parallel extraction with sensible defaults for large extractions
-        let default_threads = std::thread::available_parallelism()
-            .map(|n| n.get())
-            .unwrap_or(4);
-        let batch_size = if files_to_extract.len() > 5000 {
-            // For very large extractions, use bigger batches to reduce overhead
-            std::cmp::max(
-                50,
-                files_to_extract.len() / (threads.unwrap_or(default_threads) * 4),
-            )
-        } else if files_to_extract.len() > 1000 {
-            // For moderately large extractions, use medium batches
-            25
-        } else {
-            // For small extractions, use small batches
-            10
-        };
-
-        let mut config = ParallelConfig::new()
-            .batch_size(batch_size)
-            .skip_errors(skip_errors);
-
-        if let Some(num_threads) = threads {
-            config = config.threads(num_threads);
-        }
-
-        // Extract files in parallel using the direct API (avoids slow ParallelArchive::open)
-        let file_refs: Vec<&str> = files_to_extract.iter().map(|s| s.as_str()).collect();
-        use wow_mpq::single_archive_parallel::extract_with_config;
-        let results = extract_with_config(archive_path, &file_refs, config)?;
-
-        // Write extracted files to disk
-        let mut success_count = 0;
-        let mut error_count = 0;
-
-        for (file, data_result) in results {
-            pb.set_message(format!("Writing: {file}"));
-
-            match data_result {
-                Ok(data) => {
-                    let output_path = if preserve_paths {
-                        let system_path = mpq_path_to_system(&file);
-                        Path::new(&output_dir).join(system_path)
-                    } else {
-                        let system_path = mpq_path_to_system(&file);
-                        let filename = Path::new(&system_path).file_name().unwrap_or_default();
-                        Path::new(&output_dir).join(filename)
-                    };
-
-                    if let Some(parent) = output_path.parent() {
-                        fs::create_dir_all(parent)?;
-                    }
-
-                    fs::write(&output_path, data)?;
-                    success_count += 1;
-                }
-                Err(e) => {
-                    log::warn!("Failed to extract {file}: {e}");
-                    error_count += 1;
-                }
-            }
-
-            pb.inc(1);
-        }
-
-        let msg = if error_count > 0 {
-            format!("Extraction complete: {success_count} succeeded, {error_count} failed")
-        } else {
-            format!("Extraction complete: {success_count} files")
-        };
-        pb.finish_with_message(msg);
-
-        // Return error if skip_errors is false and some files failed
-        if !skip_errors && error_count > 0 {
-            anyhow::bail!(
-                "Failed to extract {error_count} file(s). Use --skip-errors to ignore extraction failures."
-            );
-        }
-    } else {
-        // Use patch chain logic
-        let spinner = create_spinner("Building patch chain...");
-        let mut chain = PatchChain::new();
-
-        // Add base archive with priority 0
-        chain
-            .add_archive(archive_path, 0)
-            .context("Failed to add base archive to patch chain")?;
-
-        // Add patch archives with increasing priority
-        for (index, patch_path) in patches.iter().enumerate() {
-            let priority = (index + 1) * 100;
-            chain
-                .add_archive(patch_path, priority as i32)
-                .with_context(|| format!("Failed to add patch archive: {patch_path}"))?;
-        }
-
-        spinner.finish_and_clear();
-
-        println!("Patch chain built with {} archives", chain.archive_count());
-
-        let files_to_extract = if files.is_empty() {
-            // Get all files from the chain
-            let entries = chain.list()?;
-            entries.into_iter().map(|e| e.name).collect()
-        } else {
-            files
-        };
-
-        let pb = create_progress_bar(files_to_extract.len() as u64, "Extracting files");
-
-        let mut success_count = 0;
-        let mut error_count = 0;
-
-        for file in files_to_extract.iter() {
-            pb.set_message(format!("Extracting: {file}"));
-
-            match chain.read_file(file) {
-                Ok(data) => {
-                    let output_path = if preserve_paths {
-                        // Convert MPQ path separators to system path separators
-                        let system_path = mpq_path_to_system(file);
-                        Path::new(&output_dir).join(system_path)
-                    } else {
-                        // Convert MPQ path to system path, then extract just the filename
-                        let system_path = mpq_path_to_system(file);
-                        let filename = Path::new(&system_path).file_name().unwrap_or_default();
-                        Path::new(&output_dir).join(filename)
-                    };
-
-                    if let Some(parent) = output_path.parent() {
-                        fs::create_dir_all(parent)?;
-                    }
-
-                    fs::write(&output_path, data)?;
-                    success_count += 1;
-
-                    // Show which archive the file came from
-                    if let Some(source) = chain.find_file_archive(file) {
-                        log::debug!("Extracted {} from {}", file, source.display());
-                    }
-                }
-                Err(e) => {
-                    log::warn!("Failed to extract {file}: {e}");
-                    error_count += 1;
-                }
-            }
-
-            pb.inc(1);
-        }
-
-        let msg = if error_count > 0 {
-            format!("Extraction complete: {success_count} succeeded, {error_count} failed")
-        } else {
-            format!("Extraction complete: {success_count} files")
-        };
-        pb.finish_with_message(msg);
-
-        // Show patch chain info
-        println!("\nPatch chain info:");
-        for info in chain.get_chain_info() {
-            println!(
-                "  {} (priority {}, {} files)",
-                info.path.display(),
-                info.priority,
-                info.file_count
-            );
-        }
-
-        // Return error if skip_errors is false and some files failed
-        if !skip_errors && error_count > 0 {
-            anyhow::bail!(
-                "Failed to extract {error_count} file(s) from patch chain. Use --skip-errors to ignore extraction failures."
-            );
-        }
-    }
-
-    Ok(())
+/// Compression flags for a `--compression` name
+fn compression_flags(name: &str) -> Result<u8> {
+    Ok(match name {
+        "none" => 0,
+        "zlib" => wow_mpq::compression::flags::ZLIB,
+        "bzip2" => wow_mpq::compression::flags::BZIP2,
+        "lzma" => wow_mpq::compression::flags::LZMA,
+        "implode" => wow_mpq::compression::flags::IMPLODE,
+        _ => anyhow::bail!("Invalid compression: {}", name),
+    })
 }
 
 fn create_archive(
@@ -967,8 +1106,9 @@ fn create_archive(
     version: &str,
     compression: &str,
     with_listfile: bool,
+    deterministic: bool,
 ) -> Result<()> {
-    let mut builder = ArchiveBuilder::new();
+    let mut builder = ArchiveBuilder::new().deterministic(deterministic);
 
     // Parse version
     let format_version = match version {
@@ -980,15 +1120,7 @@ fn create_archive(
     };
     builder = builder.version(format_version);
 
-    // Parse compression
-    let compression_flags = match compression {
-        "none" => 0,
-        "zlib" => wow_mpq::compression::flags::ZLIB,
-        "bzip2" => wow_mpq::compression::flags::BZIP2,
-        "lzma" => wow_mpq::compression::flags::LZMA,
-        _ => anyhow::bail!("Invalid compression: {}", compression),
-    };
-    builder = builder.default_compression(compression_flags);
+    builder = builder.default_compression(compression_flags(compression)?);
 
     if with_listfile {
         builder = builder.listfile_option(wow_mpq::ListfileOption::Generate);
@@ -1009,18 +1141,41 @@ fn create_archive(
 
     pb.finish_and_clear();
 
-    let spinner = create_spinner("Building archive...");
-    builder.build(path)?;
-    spinner.finish_with_message("Archive created successfully");
+    let pb = create_progress_bar(0, "Building archive...");
+    builder.progress(progress_sink(&pb)).build(path)?;
+    pb.finish_with_message("Archive created successfully");
 
     Ok(())
 }
 
+/// Sink that drives `pb` from the progress of an archive operation
+///
+/// The bar restarts for each stage; its message names the current file and
+/// the bytes handled so far.
+fn progress_sink(pb: &ProgressBar) -> Arc<dyn ProgressSink> {
+    let pb = pb.clone();
+    Arc::new(move |progress: &Progress<'_>| {
+        let stage = match progress.stage {
+            ProgressStage::Read => "Reading",
+            ProgressStage::Write => "Writing",
+        };
+        pb.set_length(progress.files_total as u64);
+        pb.set_position(progress.files_done as u64);
+        pb.set_message(format!(
+            "{stage} {} ({})",
+            progress.current_file,
+            format_bytes(progress.bytes)
+        ));
+    })
+}
+
 fn show_info(
     path: &str,
     file: Option<&str>,
     include_hash_table: bool,
     include_block_table: bool,
+    include_sectors: bool,
+    dump_sectors: Option<&Path>,
 ) -> Result<()> {
     let spinner = create_spinner("Opening archive...");
     let mut archive = Archive::open(path).context("Failed to open archive")?;
@@ -1029,6 +1184,10 @@ fn show_info(
     // If a specific file is requested, show file-specific information
     if let Some(filename) = file {
         show_file_info(&mut archive, filename)?;
+        if include_sectors || dump_sectors.is_some() {
+            println!();
+            show_file_sectors(&mut archive, filename, include_sectors, dump_sectors)?;
+        }
         return Ok(());
     }
 
@@ -1042,6 +1201,18 @@ fn show_info(
     println!("Archive size: {}", format_bytes(info.file_size));
     println!("Number of files: {}", info.file_count);
 
+    if let Some(map) = archive.map_header() {
+        println!();
+        println!("Warcraft III Map");
+        println!("  Name: {}", map.name);
+        println!("  Max players: {}", map.max_players);
+        println!(
+            "  Flags: 0x{:08X}{}",
+            map.flags,
+            if map.is_melee() { " (melee)" } else { "" }
+        );
+    }
+
     if include_hash_table {
         println!();
         show_hash_table(&mut archive, false)?;
@@ -1119,6 +1290,61 @@ fn show_file_info(archive: &mut Archive, filename: &str) -> Result<()> {
     Ok(())
 }
 
+fn show_file_sectors(
+    archive: &mut Archive,
+    filename: &str,
+    include_sectors: bool,
+    dump_dir: Option<&Path>,
+) -> Result<()> {
+    let raw = archive
+        .read_raw_sectors(filename)
+        .with_context(|| format!("Failed to read sectors of {filename}"))?;
+
+    if include_sectors {
+        println!("Sector Layout");
+        println!("=============");
+        match raw.key {
+            Some(key) => println!("Encryption key: 0x{key:08X}"),
+            None => println!("Encryption key: none (not encrypted)"),
+        }
+        if raw.sector_offsets.is_empty() {
+            println!("Sector offset table: none");
+        } else {
+            let offsets: Vec<String> = raw.sector_offsets.iter().map(|o| o.to_string()).collect();
+            println!("Sector offset table: [{}]", offsets.join(", "));
+        }
+        println!("Sectors: {}", raw.sectors.len());
+        for sector in &raw.sectors {
+            let key = sector
+                .key
+                .map_or_else(|| "-".to_string(), |key| format!("0x{key:08X}"));
+            println!(
+                "  #{:<4} offset 0x{:08X}  size {:>8}  key {key:>10}  {}",
+                sector.index,
+                sector.offset,
+                sector.data.len(),
+                wow_mpq::debug::hex_string(&sector.data, 8)
+            );
+        }
+    }
+
+    if let Some(dir) = dump_dir {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        for sector in &raw.sectors {
+            let path = dir.join(format!("sector_{:04}.bin", sector.index));
+            fs::write(&path, &sector.data)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+        }
+        println!(
+            "Wrote {} raw sectors to {}",
+            raw.sectors.len(),
+            dir.display()
+        );
+    }
+
+    Ok(())
+}
+
 fn validate_archive(path: &str, check_checksums: bool, threads: Option<usize>) -> Result<()> {
     // Use parallel validation by default
     let spinner = create_spinner("Opening archive...");
@@ -1191,6 +1417,7 @@ struct RebuildParams<'a> {
     skip_encrypted: bool,
     skip_signatures: bool,
     verify: bool,
+    verify_layout: Option<wow_mpq::GameVersion>,
     compression: Option<String>,
     block_size: Option<u16>,
     list_only: bool,
@@ -1219,8 +1446,11 @@ fn rebuild_mpq_archive(params: RebuildParams<'_>) -> Result<()> {
         skip_encrypted: params.skip_encrypted,
         skip_signatures: params.skip_signatures,
         verify: params.verify,
+        verify_layout: params.verify_layout,
         override_compression,
         override_block_size: params.block_size,
+        best_compression: false,
+        deduplicate: false,
         list_only: params.list_only,
     };
 
@@ -1233,29 +1463,17 @@ fn rebuild_mpq_archive(params: RebuildParams<'_>) -> Result<()> {
         );
     }
 
-    // Set up progress callback
-    let progress_callback = Some(Box::new(|current: usize, total: usize, file: &str| {
-        if current.is_multiple_of(100) || current == total {
-            println!("  [{current}/{total}] Processing: {file}");
-        }
-    }) as Box<dyn Fn(usize, usize, &str) + Send + Sync>);
-
     // Perform the rebuild
-    let spinner = if params.list_only {
-        create_spinner("Analyzing archive...")
-    } else {
-        create_spinner("Rebuilding archive...")
-    };
-
-    let summary = rebuild_archive(
+    let pb = create_progress_bar(0, "");
+    let summary = rebuild_archive_with_progress(
         params.source_path,
         params.target_path,
         options,
-        progress_callback,
+        progress_sink(&pb),
     )
     .context("Failed to rebuild archive")?;
 
-    spinner.finish_and_clear();
+    pb.finish_and_clear();
 
     // Display results
     println!("\nRebuild Summary:");
@@ -1269,18 +1487,56 @@ fn rebuild_mpq_archive(params: RebuildParams<'_>) -> Result<()> {
 
     if params.list_only {
         println!("\nDry run completed. Use without --list-only to perform actual rebuild.");
-    } else {
-        if summary.verified {
-            println!("✓ Verification: PASSED");
-        } else if params.verify {
-            println!("⚠ Verification: SKIPPED");
+        return Ok(());
+    }
+
+    println!("✓ Archive rebuilt successfully: {}", params.target_path);
+    if let Some(report) = &summary.verification {
+        print_verification(report);
+        if !report.passed() {
+            anyhow::bail!("Rebuilt archive failed verification");
         }
-        println!("✓ Archive rebuilt successfully: {}", params.target_path);
     }
 
     Ok(())
 }
 
+fn print_verification(report: &wow_mpq::RebuildVerification) {
+    let hex = |md5: &[u8; 16]| md5.iter().map(|b| format!("{b:02x}")).collect::<String>();
+    println!("\nVerification:");
+    println!(
+        "  Files compared: {} ({} matching)",
+        report.files_checked, report.files_matched
+    );
+    for mismatch in &report.mismatched {
+        println!(
+            "  ✗ Content differs: {} ({} bytes, MD5 {} -> {} bytes, MD5 {})",
+            mismatch.name,
+            mismatch.source_size,
+            hex(&mismatch.source_md5),
+            mismatch.target_size,
+            hex(&mismatch.target_md5)
+        );
+    }
+    for name in &report.missing {
+        println!("  ✗ Missing from rebuilt archive: {name}");
+    }
+    for name in &report.unexpected {
+        println!("  ✗ Not in source archive: {name}");
+    }
+    for name in &report.unreadable {
+        println!("  ⚠ Unreadable in source, not compared: {name}");
+    }
+    for issue in &report.layout_issues {
+        println!("  ✗ Layout: {issue}");
+    }
+    if report.passed() {
+        println!("✓ Verification: PASSED");
+    } else {
+        println!("✗ Verification: FAILED");
+    }
+}
+
 /// Parameters for MPQ archive comparison operation
 struct CompareParams<'a> {
     source_path: &'a str,
@@ -1291,6 +1547,7 @@ struct CompareParams<'a> {
     ignore_order: bool,
     output_format: &'a str,
     filter: Option<String>,
+    stop_at_first: bool,
 }
 
 /// Parameters for MPQ archive debug operation
@@ -1306,19 +1563,33 @@ struct DebugParams<'a> {
 }
 
 fn compare_archives(params: CompareParams<'_>) -> Result<()> {
-    let spinner = create_spinner("Comparing archives...");
+    // Content checks read every common file, so they get a progress bar
+    let pb = if params.content_check {
+        create_progress_bar(0, "Comparing files")
+    } else {
+        create_spinner("Comparing archives...")
+    };
+    let progress = pb.clone();
+    let options = wow_mpq::CompareOptions {
+        detailed: params.detailed,
+        content_check: params.content_check,
+        metadata_only: params.metadata_only,
+        ignore_order: params.ignore_order,
+        filter: params.filter,
+        stop_at_first_difference: params.stop_at_first,
+        progress: Some(Box::new(move |_, total, _| {
+            progress.set_length(total as u64);
+            progress.inc(1);
+        })),
+    };
 
-    let comparison_result = mpq_compare_archives(
-        params.source_path,
-        params.target_path,
-        params.detailed,
-        params.content_check,
-        params.metadata_only,
-        params.ignore_order,
-        params.filter,
-    )?;
+    let comparison_result = mpq_compare_archives(params.source_path, params.target_path, &options)?;
 
-    spinner.finish_and_clear();
+    pb.finish_and_clear();
+
+    if comparison_result.stopped_early {
+        eprintln!("Stopped at the first difference; the lists below are incomplete");
+    }
 
     // Display results based on output format
     match params.output_format {
@@ -1854,9 +2125,7 @@ fn create_file_node(
                     .with_external_ref(&format!("{base_name}.skin"), detect_ref_type("file.skin"));
                 node = node.with_external_ref("*.blp", detect_ref_type("file.blp"));
             }
-            "dbc"
-                if file_name.to_lowercase().contains("item") =>
-            {
+            "dbc" if file_name.to_lowercase().contains("item") => {
                 node = node.with_external_ref("Interface/Icons/*.blp", detect_ref_type("file.blp"));
             }
             _ => {}
@@ -2165,6 +2434,7 @@ async fn execute_db_command(command: DbCommands) -> Result<()> {
         DbCommands::Import {
             path,
             source_type,
+            names,
             show_progress,
         } => {
             let db = Database::open_default()
@@ -2174,6 +2444,10 @@ async fn execute_db_command(command: DbCommands) -> Result<()> {
 
             let import_source = match source_type {
                 ImportSourceArg::Listfile => ImportSource::Listfile,
+                ImportSourceArg::ListfileCsv => ImportSource::CommunityListfile,
+                ImportSourceArg::CascRoot => ImportSource::CascRoot {
+                    listfile: names.context("--names is required for CASC root imports")?,
+                },
                 ImportSourceArg::Archive => ImportSource::Archive,
                 ImportSourceArg::Directory => ImportSource::Directory,
             };
@@ -0,0 +1,278 @@
+//! Patch chain mounting from a game installation
+//!
+//! Discovers the client's MPQ archives under a data directory, orders them the
+//! way the client loads them and exposes list/extract/read over the merged view,
+//! plus copy-on-write edits through an overlay archive.
+
+use anyhow::{Context, Result};
+use clap::Subcommand;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use wow_mpq::path::mpq_path_to_system;
+use wow_mpq::{ArchiveLayout, GameVersion, PatchChain};
+
+use crate::utils::{
+    add_table_row, create_progress_bar, create_spinner, create_table, format_bytes,
+    matches_pattern, truncate_path,
+};
+
+#[derive(Subcommand)]
+pub enum ChainCommands {
+    /// Show the discovered archives in loading order
+    Archives,
+
+    /// List files in the merged view
+    List {
+        /// Filter files by pattern (supports wildcards)
+        #[arg(short, long)]
+        filter: Option<String>,
+
+        /// Show which archive provides each file and how many it overrides
+        #[arg(short, long)]
+        long: bool,
+    },
+
+    /// Extract files from the merged view
+    Extract {
+        /// Specific files to extract (extracts all matching --filter if not specified)
+        files: Vec<String>,
+
+        /// Output directory
+        #[arg(short, long, default_value = ".")]
+        output: String,
+
+        /// Filter files by pattern (supports wildcards)
+        #[arg(short, long)]
+        filter: Option<String>,
+
+        /// Continue extraction even if some files fail
+        #[arg(long)]
+        skip_errors: bool,
+    },
+
+    /// Read a single file from the merged view
+    Read {
+        /// File to read
+        file: String,
+
+        /// Write to this path instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Write a file into an overlay archive above the merged view
+    Write {
+        /// Path of the file inside the archives
+        file: String,
+
+        /// Local file with the new contents
+        input: PathBuf,
+
+        /// Overlay archive receiving the write (created if missing)
+        #[arg(long)]
+        overlay: PathBuf,
+
+        /// Also write the overlay's files to a compact standalone patch MPQ
+        #[arg(long, value_name = "MPQ")]
+        materialize: Option<PathBuf>,
+    },
+}
+
+/// Discover, order and mount the archives of a game installation
+///
+/// The client version is detected from the base archives unless given.
+pub(crate) fn mount_chain(
+    data_dir: &Path,
+    locale: Option<&str>,
+    version: Option<GameVersion>,
+) -> Result<(PatchChain, ArchiveLayout)> {
+    let version = match version {
+        Some(version) => version,
+        None => GameVersion::detect(data_dir).with_context(|| {
+            format!(
+                "Cannot tell the client version of {}; pass --game-version",
+                data_dir.display()
+            )
+        })?,
+    };
+    let layout = ArchiveLayout::discover(data_dir, version, locale)?;
+    for path in &layout.ignored {
+        log::info!("Not loaded by the {version} client: {}", path.display());
+    }
+
+    let spinner = create_spinner("Mounting archives...");
+    let chain = PatchChain::from_layout(&layout)?;
+    spinner.finish_and_clear();
+
+    Ok((chain, layout))
+}
+
+pub fn execute_chain(
+    data_dir: &Path,
+    locale: Option<&str>,
+    version: Option<GameVersion>,
+    command: ChainCommands,
+) -> Result<()> {
+    let (mut chain, layout) = mount_chain(data_dir, locale, version)?;
+
+    match command {
+        ChainCommands::Archives => {
+            println!(
+                "Client: {}, locale: {}",
+                layout.version,
+                layout.locale.as_deref().unwrap_or("none")
+            );
+            let mut table = create_table(vec!["#", "Priority", "Archive", "Size"]);
+            for (index, archive) in layout.archives.iter().enumerate() {
+                let size = fs::metadata(&archive.path).map(|m| m.len()).unwrap_or(0);
+                add_table_row(
+                    &mut table,
+                    vec![
+                        index.to_string(),
+                        archive.priority.to_string(),
+                        truncate_path(&archive.path.display().to_string(), 60),
+                        format_bytes(size),
+                    ],
+                );
+            }
+            table.printstd();
+            if !layout.ignored.is_empty() {
+                println!("Not loaded by the client:");
+                for path in &layout.ignored {
+                    println!("  {}", path.display());
+                }
+            }
+            Ok(())
+        }
+        ChainCommands::List { filter, long } => {
+            let pattern = filter.as_deref().unwrap_or("*");
+
+            if long {
+                let entries = chain.list_merged()?;
+                let mut table = create_table(vec!["File", "Size", "Archive", "Overrides"]);
+                for entry in entries
+                    .iter()
+                    .filter(|e| matches_pattern(&e.file.name, pattern))
+                {
+                    let source = entry
+                        .source
+                        .file_name()
+                        .and_then(|n| n.to_str())
+                        .unwrap_or("?")
+                        .to_string();
+                    add_table_row(
+                        &mut table,
+                        vec![
+                            truncate_path(&entry.file.name, 60),
+                            format_bytes(entry.file.size),
+                            source,
+                            entry.override_depth().to_string(),
+                        ],
+                    );
+                }
+                table.printstd();
+            } else {
+                let entries = chain.list()?;
+                for entry in entries.iter().filter(|e| matches_pattern(&e.name, pattern)) {
+                    println!("{}", entry.name);
+                }
+            }
+            Ok(())
+        }
+        ChainCommands::Extract {
+            files,
+            output,
+            filter,
+            skip_errors,
+        } => {
+            let targets: Vec<String> = if files.is_empty() {
+                let pattern = filter.as_deref().unwrap_or("*");
+                chain
+                    .list()?
+                    .into_iter()
+                    .map(|e| e.name)
+                    .filter(|name| matches_pattern(name, pattern))
+                    .collect()
+            } else {
+                files
+            };
+
+            let pb = create_progress_bar(targets.len() as u64, "Extracting files");
+            let mut errors = 0;
+            for file in &targets {
+                pb.set_message(format!("Extracting: {file}"));
+                match chain.read_file(file) {
+                    Ok(data) => {
+                        let path = Path::new(&output).join(mpq_path_to_system(file));
+                        if let Some(parent) = path.parent() {
+                            fs::create_dir_all(parent)?;
+                        }
+                        fs::write(&path, data)?;
+                    }
+                    Err(e) => {
+                        log::warn!("Failed to extract {file}: {e}");
+                        errors += 1;
+                    }
+                }
+                pb.inc(1);
+            }
+            pb.finish_with_message(format!(
+                "Extraction complete: {} succeeded, {errors} failed",
+                targets.len() - errors
+            ));
+
+            if errors > 0 && !skip_errors {
+                anyhow::bail!(
+                    "Failed to extract {errors} file(s). Use --skip-errors to ignore extraction failures."
+                );
+            }
+            Ok(())
+        }
+        ChainCommands::Read { file, output } => {
+            let data = chain
+                .read_file(&file)
+                .with_context(|| format!("Failed to read {file}"))?;
+            match output {
+                Some(path) => fs::write(&path, data)?,
+                None => std::io::stdout().write_all(&data)?,
+            }
+            Ok(())
+        }
+        ChainCommands::Write {
+            file,
+            input,
+            overlay,
+            materialize,
+        } => {
+            let data =
+                fs::read(&input).with_context(|| format!("Failed to read {}", input.display()))?;
+            let replaces = chain
+                .find_file_archive(&file)
+                .map(|p| p.display().to_string());
+
+            chain
+                .set_overlay(&overlay)
+                .with_context(|| format!("Failed to open overlay {}", overlay.display()))?;
+            chain
+                .write_file(&file, &data)
+                .with_context(|| format!("Failed to write {file}"))?;
+            match replaces {
+                Some(archive) => println!("Wrote {file} over the version in {archive}"),
+                None => println!("Wrote new file {file}"),
+            }
+
+            if let Some(path) = materialize {
+                let count = chain
+                    .materialize_overlay(&path)
+                    .with_context(|| format!("Failed to write {}", path.display()))?;
+                println!(
+                    "Materialized {count} overlay file(s) into {}",
+                    path.display()
+                );
+            }
+            chain.close_overlay()?;
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,79 @@
+//! Hash collision audit of an MPQ archive
+//!
+//! Checks the names of the archive's own `(listfile)` and any external
+//! listfiles for pairs the hash table cannot tell apart, and the hash table
+//! for duplicate entries that shadow each other. Exits with [`EXIT_FOUND`]
+//! when anything is found.
+
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+use wow_mpq::Archive;
+use wow_mpq::special_files::Listfile;
+
+/// Exit code used when collisions or duplicate entries are found
+pub const EXIT_FOUND: i32 = 2;
+
+pub struct CollisionParams<'a> {
+    pub archive: &'a str,
+    /// Listfiles with further names to check
+    pub listfiles: &'a [PathBuf],
+}
+
+pub fn audit_collisions(params: CollisionParams) -> Result<()> {
+    let mut archive = Archive::open(params.archive)
+        .with_context(|| format!("Failed to open archive: {}", params.archive))?;
+
+    let mut names = match archive.read_file("(listfile)") {
+        Ok(data) => Listfile::parse(&data).context("Failed to parse (listfile)")?,
+        Err(wow_mpq::Error::FileNotFound(_)) => Listfile::new(),
+        Err(e) => return Err(e).context("Failed to read (listfile)"),
+    };
+    for path in params.listfiles {
+        let data =
+            std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let listfile = Listfile::parse(&data)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        names.merge(&listfile);
+    }
+
+    let report = archive
+        .audit_hash_collisions(names.names())
+        .context("Failed to audit the hash table")?;
+
+    println!(
+        "Checked {} names against {} hash table entries",
+        names.len(),
+        archive.hash_table().map_or(0, |table| table.size())
+    );
+    for collision in &report.name_collisions {
+        let held = if collision.slots.is_empty() {
+            "not in the archive".to_string()
+        } else {
+            format!("slot(s) {:?}", collision.slots)
+        };
+        println!(
+            "✗ {:08X}:{:08X} shared by {} ({held})",
+            collision.hash_a,
+            collision.hash_b,
+            collision.names.join(", ")
+        );
+    }
+    for duplicate in &report.duplicate_entries {
+        let name = duplicate.name.as_deref().unwrap_or("<unknown name>");
+        println!(
+            "✗ {name} (locale {:#06x}) has duplicate entries in slots {:?}; only slot {} is read",
+            duplicate.locale, duplicate.slots, duplicate.slots[0]
+        );
+    }
+
+    if report.is_clean() {
+        println!("✓ No hash collisions or duplicate entries");
+        return Ok(());
+    }
+    println!(
+        "Found {} name collision(s) and {} duplicate entry group(s)",
+        report.name_collisions.len(),
+        report.duplicate_entries.len()
+    );
+    std::process::exit(EXIT_FOUND);
+}
@@ -0,0 +1,298 @@
+//! File-level diff between two MPQ archives
+//!
+//! Builds on [`wow_mpq::compare_archives_with_options`], which compares the
+//! contents of common files in parallel, and adds checksums for the files
+//! that were added, removed or changed.
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use md5::{Digest, Md5};
+use wow_mpq::{
+    Archive, CompareOptions, PatchOptions, compare_archives_with_options, generate_patch_archive,
+    special_files::is_special_file,
+};
+
+use crate::utils::{create_progress_bar, format_bytes};
+
+/// Output format for `mpq diff`
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiffFormat {
+    /// Human readable listing
+    Text,
+    /// Machine readable JSON
+    Json,
+}
+
+/// Kind of change for a single file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+impl ChangeKind {
+    fn marker(self) -> char {
+        match self {
+            ChangeKind::Added => 'A',
+            ChangeKind::Removed => 'D',
+            ChangeKind::Changed => 'M',
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    fn as_str(self) -> &'static str {
+        match self {
+            ChangeKind::Added => "added",
+            ChangeKind::Removed => "removed",
+            ChangeKind::Changed => "changed",
+        }
+    }
+}
+
+/// Size and MD5 of one side of a change
+#[derive(Debug, Clone)]
+struct FileState {
+    size: u64,
+    md5: String,
+}
+
+impl FileState {
+    fn from_data(data: &[u8]) -> Self {
+        Self {
+            size: data.len() as u64,
+            md5: hex_md5(data),
+        }
+    }
+}
+
+/// A single added, removed or changed file
+#[derive(Debug, Clone)]
+struct FileChange {
+    name: String,
+    kind: ChangeKind,
+    old: Option<FileState>,
+    new: Option<FileState>,
+}
+
+/// Parameters for the `mpq diff` command
+pub struct DiffParams<'a> {
+    pub old_path: &'a str,
+    pub new_path: &'a str,
+    pub format: DiffFormat,
+    pub filter: Option<String>,
+    pub patch_out: Option<&'a str>,
+    /// Write deletion markers for removed files into the patch archive
+    pub deletion_markers: bool,
+}
+
+fn hex_md5(data: &[u8]) -> String {
+    Md5::digest(data)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+pub fn diff_archives(params: DiffParams<'_>) -> Result<()> {
+    let pb = create_progress_bar(0, "Comparing files");
+    let progress = pb.clone();
+    let options = CompareOptions {
+        content_check: true,
+        filter: params.filter.clone(),
+        progress: Some(Box::new(move |_, total, _| {
+            progress.set_length(total as u64);
+            progress.inc(1);
+        })),
+        ..Default::default()
+    };
+
+    let comparison = compare_archives_with_options(params.old_path, params.new_path, &options)
+        .context("Failed to compare archives")?;
+    let files = comparison
+        .files
+        .context("Archive comparison did not produce a file list")?;
+
+    let mut old_archive = Archive::open(params.old_path).context("Failed to open old archive")?;
+    let mut new_archive = Archive::open(params.new_path).context("Failed to open new archive")?;
+
+    let mut changes = Vec::new();
+
+    let mut removed = files.source_only;
+    removed.retain(|name| !is_special_file(name));
+    for name in removed {
+        let data = old_archive
+            .read_file(&name)
+            .with_context(|| format!("Failed to read {name} from old archive"))?;
+        changes.push(FileChange {
+            name,
+            kind: ChangeKind::Removed,
+            old: Some(FileState::from_data(&data)),
+            new: None,
+        });
+    }
+
+    let mut added = files.target_only;
+    added.retain(|name| !is_special_file(name));
+    for name in added {
+        let data = new_archive
+            .read_file(&name)
+            .with_context(|| format!("Failed to read {name} from new archive"))?;
+        changes.push(FileChange {
+            name,
+            kind: ChangeKind::Added,
+            old: None,
+            new: Some(FileState::from_data(&data)),
+        });
+    }
+
+    let mut common = files.common_files;
+    common.retain(|name| !is_special_file(name));
+    let mut changed = files.content_differences;
+    changed.retain(|name| !is_special_file(name));
+    let unchanged = common.len() - changed.len();
+    for name in changed {
+        let old_data = old_archive
+            .read_file(&name)
+            .with_context(|| format!("Failed to read {name} from old archive"))?;
+        let new_data = new_archive
+            .read_file(&name)
+            .with_context(|| format!("Failed to read {name} from new archive"))?;
+
+        changes.push(FileChange {
+            name,
+            kind: ChangeKind::Changed,
+            old: Some(FileState::from_data(&old_data)),
+            new: Some(FileState::from_data(&new_data)),
+        });
+    }
+
+    changes.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+    pb.finish_and_clear();
+
+    match params.format {
+        DiffFormat::Text => print_text(params.old_path, params.new_path, &changes, unchanged),
+        DiffFormat::Json => print_json(params.old_path, params.new_path, &changes, unchanged)?,
+    }
+
+    if let Some(patch_path) = params.patch_out {
+        write_patch_archive(&params, patch_path)?;
+    }
+
+    Ok(())
+}
+
+fn print_text(old_path: &str, new_path: &str, changes: &[FileChange], unchanged: usize) {
+    println!("Diff: {old_path} -> {new_path}");
+    println!();
+
+    for change in changes {
+        let detail = match (&change.old, &change.new) {
+            (Some(old), Some(new)) => format!(
+                "{} -> {}  {} -> {}",
+                format_bytes(old.size),
+                format_bytes(new.size),
+                old.md5,
+                new.md5
+            ),
+            (Some(state), None) | (None, Some(state)) => {
+                format!("{}  {}", format_bytes(state.size), state.md5)
+            }
+            (None, None) => String::new(),
+        };
+        println!("{}  {}  {}", change.kind.marker(), change.name, detail);
+    }
+
+    let count = |kind| changes.iter().filter(|c| c.kind == kind).count();
+    if !changes.is_empty() {
+        println!();
+    }
+    println!(
+        "Summary: {} added, {} removed, {} changed, {} unchanged",
+        count(ChangeKind::Added),
+        count(ChangeKind::Removed),
+        count(ChangeKind::Changed),
+        unchanged
+    );
+}
+
+#[cfg(feature = "serde")]
+fn print_json(
+    old_path: &str,
+    new_path: &str,
+    changes: &[FileChange],
+    unchanged: usize,
+) -> Result<()> {
+    let state_json = |state: &Option<FileState>| {
+        state.as_ref().map(|s| {
+            serde_json::json!({
+                "size": s.size,
+                "md5": s.md5,
+            })
+        })
+    };
+
+    let entries: Vec<_> = changes
+        .iter()
+        .map(|change| {
+            serde_json::json!({
+                "name": change.name,
+                "status": change.kind.as_str(),
+                "old": state_json(&change.old),
+                "new": state_json(&change.new),
+            })
+        })
+        .collect();
+
+    let count = |kind| changes.iter().filter(|c| c.kind == kind).count();
+    let document = serde_json::json!({
+        "old": old_path,
+        "new": new_path,
+        "summary": {
+            "added": count(ChangeKind::Added),
+            "removed": count(ChangeKind::Removed),
+            "changed": count(ChangeKind::Changed),
+            "unchanged": unchanged,
+        },
+        "files": entries,
+    });
+
+    println!("{}", serde_json::to_string_pretty(&document)?);
+    Ok(())
+}
+
+#[cfg(not(feature = "serde"))]
+fn print_json(
+    _old_path: &str,
+    _new_path: &str,
+    _changes: &[FileChange],
+    _unchanged: usize,
+) -> Result<()> {
+    anyhow::bail!("JSON output requires the 'serde' feature to be enabled");
+}
+
+/// Write added and changed files into a new archive that can be layered on
+/// top of the old one as a patch
+fn write_patch_archive(params: &DiffParams<'_>, path: &str) -> Result<()> {
+    let options = PatchOptions {
+        deletion_markers: params.deletion_markers,
+        filter: params.filter.clone(),
+        ..Default::default()
+    };
+    let summary = generate_patch_archive(params.old_path, params.new_path, path, &options)
+        .with_context(|| format!("Failed to write patch archive: {path}"))?;
+
+    eprintln!(
+        "Wrote {} file(s) to patch archive {path}",
+        summary.added.len() + summary.changed.len()
+    );
+    let removed = summary.deleted.len();
+    if removed > 0 && params.deletion_markers {
+        eprintln!("Wrote {removed} deletion marker(s)");
+    } else if removed > 0 {
+        eprintln!(
+            "Note: {removed} removed file(s) are not in the patch; use --deletion-markers to remove them"
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,403 @@
+//! Parallel, filtered extraction for `mpq extract`
+
+use anyhow::{Context, Result};
+use glob::{MatchOptions, Pattern};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use wow_mpq::{Archive, PatchChain, path::mpq_path_to_system};
+
+use crate::utils::{create_progress_bar, create_spinner, format_bytes};
+
+/// Failures listed in the summary before pointing at `--error-log`
+const SUMMARY_FAILURES: usize = 10;
+
+pub struct ExtractOptions {
+    pub archive_path: String,
+    pub output_dir: String,
+    pub files: Vec<String>,
+    pub file_type: Option<String>,
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub preserve_paths: bool,
+    pub jobs: Option<usize>,
+    pub skip_errors: bool,
+    pub skip_existing: bool,
+    pub error_log: Option<PathBuf>,
+    pub patches: Vec<String>,
+    /// Record names of files read that no listfile knows
+    pub learn_names: bool,
+}
+
+/// Include/exclude glob filter over archive paths
+///
+/// Patterns match case-insensitively against the path with `/` separators, and
+/// `*` crosses directories, so `*.blp` selects every texture in the archive.
+pub struct FileFilter {
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+}
+
+impl FileFilter {
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self> {
+        let compile = |patterns: &[String]| -> Result<Vec<Pattern>> {
+            patterns
+                .iter()
+                .map(|p| {
+                    Pattern::new(&p.replace('\\', "/"))
+                        .with_context(|| format!("Invalid glob pattern: {p}"))
+                })
+                .collect()
+        };
+        Ok(Self {
+            include: compile(include)?,
+            exclude: compile(exclude)?,
+        })
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        let options = MatchOptions {
+            case_sensitive: false,
+            ..MatchOptions::new()
+        };
+        let name = name.replace('\\', "/");
+        (self.include.is_empty() || self.include.iter().any(|p| p.matches_with(&name, options)))
+            && !self.exclude.iter().any(|p| p.matches_with(&name, options))
+    }
+}
+
+/// Running totals shared by the extraction workers
+#[derive(Default)]
+struct Outcome {
+    extracted: AtomicUsize,
+    bytes: AtomicU64,
+    failures: Mutex<Vec<(String, String)>>,
+}
+
+impl Outcome {
+    fn record(&self, file: &str, result: Result<usize>) {
+        match result {
+            Ok(size) => {
+                self.extracted.fetch_add(1, Ordering::Relaxed);
+                self.bytes.fetch_add(size as u64, Ordering::Relaxed);
+            }
+            Err(e) => {
+                log::warn!("Failed to extract {file}: {e:#}");
+                self.failures
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .push((file.to_string(), format!("{e:#}")));
+            }
+        }
+    }
+}
+
+/// Extract files as `options` describe
+///
+/// With `learn_names`, the names of extracted files that the archives'
+/// listfiles lack are appended to `learned`, also when extraction fails.
+pub fn extract(options: ExtractOptions, learned: &mut Vec<String>) -> Result<()> {
+    let filter = FileFilter::new(&options.include, &options.exclude)?;
+    let output_dir = Path::new(&options.output_dir);
+
+    let mut chain = if options.patches.is_empty() {
+        None
+    } else {
+        Some(build_chain(&options.archive_path, &options.patches)?)
+    };
+    if let Some(chain) = &mut chain {
+        chain.set_record_names(options.learn_names);
+    }
+
+    let mut files = if !options.files.is_empty() {
+        options.files.clone()
+    } else {
+        let mut listed = match &mut chain {
+            Some(chain) => chain.list()?.into_iter().map(|e| e.name).collect(),
+            None => list_archive(&options.archive_path)?,
+        };
+        if let Some(file_type) = &options.file_type {
+            let file_type = file_type.to_lowercase();
+            listed.retain(|f| f.to_lowercase().ends_with(&file_type));
+        }
+        listed
+    };
+    let listed = files.len();
+    files.retain(|f| filter.matches(f));
+    if files.len() != listed {
+        println!("{} of {listed} files match the filters", files.len());
+    }
+
+    let mut jobs: Vec<(String, PathBuf)> = files
+        .into_iter()
+        .map(|file| {
+            let path = output_path(output_dir, &file, options.preserve_paths);
+            (file, path)
+        })
+        .collect();
+
+    if !options.preserve_paths {
+        report_flat_collisions(&jobs);
+    }
+
+    let mut skipped = 0;
+    if options.skip_existing {
+        let before = jobs.len();
+        jobs.retain(|(_, path)| !path.exists());
+        skipped = before - jobs.len();
+        if skipped > 0 {
+            println!("Skipping {skipped} files that already exist");
+        }
+    }
+
+    let pb = create_progress_bar(jobs.len() as u64, "Extracting files");
+    let outcome = Outcome::default();
+
+    match chain.as_mut() {
+        None => {
+            let threads = options
+                .jobs
+                .unwrap_or_else(|| {
+                    std::thread::available_parallelism()
+                        .map(|n| n.get())
+                        .unwrap_or(4)
+                })
+                .clamp(1, jobs.len().max(1));
+            let next = AtomicUsize::new(0);
+            let archive_path = Path::new(&options.archive_path);
+            let recorded = Mutex::new(Vec::new());
+
+            std::thread::scope(|scope| {
+                for _ in 0..threads {
+                    scope.spawn(|| {
+                        // Each worker reads through its own archive handle
+                        let mut archive = match Archive::open(archive_path) {
+                            Ok(archive) => archive,
+                            Err(e) => {
+                                log::error!("Failed to open archive: {e}");
+                                return;
+                            }
+                        };
+                        archive.set_record_names(options.learn_names);
+                        loop {
+                            let index = next.fetch_add(1, Ordering::Relaxed);
+                            let Some((file, path)) = jobs.get(index) else {
+                                break;
+                            };
+                            let result = archive
+                                .read_file(file)
+                                .map_err(anyhow::Error::from)
+                                .and_then(|data| write_file(path, &data));
+                            outcome.record(file, result);
+                            pb.inc(1);
+                        }
+                        recorded
+                            .lock()
+                            .unwrap_or_else(|e| e.into_inner())
+                            .extend(archive.take_recorded_names());
+                    });
+                }
+            });
+
+            let mut recorded = recorded.into_inner().unwrap_or_else(|e| e.into_inner());
+            recorded.sort();
+            recorded.dedup();
+            learned.extend(recorded);
+
+            // Workers that could not open the archive leave files unprocessed
+            let processed = next.load(Ordering::Relaxed).min(jobs.len());
+            if processed < jobs.len() {
+                anyhow::bail!("Failed to open archive: {}", options.archive_path);
+            }
+        }
+        Some(chain) => {
+            for (file, path) in &jobs {
+                pb.set_message(format!("Extracting: {file}"));
+                let result = chain
+                    .read_file(file)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|data| write_file(path, &data));
+                if result.is_ok()
+                    && let Some(source) = chain.find_file_archive(file)
+                {
+                    log::debug!("Extracted {} from {}", file, source.display());
+                }
+                outcome.record(file, result);
+                pb.inc(1);
+            }
+            learned.extend(chain.take_recorded_names());
+        }
+    }
+
+    let extracted = outcome.extracted.load(Ordering::Relaxed);
+    let mut failures = outcome
+        .failures
+        .into_inner()
+        .unwrap_or_else(|e| e.into_inner());
+    failures.sort();
+
+    pb.finish_with_message(if failures.is_empty() {
+        format!("Extraction complete: {extracted} files")
+    } else {
+        format!(
+            "Extraction complete: {extracted} succeeded, {} failed",
+            failures.len()
+        )
+    });
+
+    println!();
+    println!(
+        "Extracted: {extracted} files ({})",
+        format_bytes(outcome.bytes.load(Ordering::Relaxed))
+    );
+    if skipped > 0 {
+        println!("Skipped:   {skipped} existing files");
+    }
+
+    if let Some(chain) = &mut chain {
+        println!("\nPatch chain info:");
+        for info in chain.get_chain_info() {
+            println!(
+                "  {} (priority {}, {} files)",
+                info.path.display(),
+                info.priority,
+                info.file_count
+            );
+        }
+    }
+
+    if failures.is_empty() {
+        return Ok(());
+    }
+
+    println!("Failed:    {} files", failures.len());
+    for (file, error) in failures.iter().take(SUMMARY_FAILURES) {
+        println!("  {file}: {error}");
+    }
+    if failures.len() > SUMMARY_FAILURES {
+        println!("  ... and {} more", failures.len() - SUMMARY_FAILURES);
+    }
+
+    if let Some(log_path) = &options.error_log {
+        let log: String = failures
+            .iter()
+            .map(|(file, error)| format!("{file}\t{error}\n"))
+            .collect();
+        fs::write(log_path, log)
+            .with_context(|| format!("Failed to write error log {}", log_path.display()))?;
+        println!("Failures written to {}", log_path.display());
+    }
+    println!("Re-run with --skip-existing to retry only the files that are missing");
+
+    if !options.skip_errors {
+        anyhow::bail!(
+            "Failed to extract {} file(s). Use --skip-errors to ignore extraction failures.",
+            failures.len()
+        );
+    }
+    Ok(())
+}
+
+fn build_chain(archive_path: &str, patches: &[String]) -> Result<PatchChain> {
+    let spinner = create_spinner("Building patch chain...");
+    let mut chain = PatchChain::new();
+
+    // Base archive at priority 0, patches above it in the given order
+    chain
+        .add_archive(archive_path, 0)
+        .context("Failed to add base archive to patch chain")?;
+    for (index, patch_path) in patches.iter().enumerate() {
+        let priority = (index + 1) * 100;
+        chain
+            .add_archive(patch_path, priority as i32)
+            .with_context(|| format!("Failed to add patch archive: {patch_path}"))?;
+    }
+
+    spinner.finish_and_clear();
+    println!("Patch chain built with {} archives", chain.archive_count());
+    Ok(chain)
+}
+
+/// List an archive, reading `(listfile)` directly since it is much faster than enumeration
+fn list_archive(archive_path: &str) -> Result<Vec<String>> {
+    println!("Reading file list from archive...");
+    let mut archive = Archive::open(archive_path).context("Failed to open archive")?;
+
+    let listed = archive
+        .read_file("(listfile)")
+        .ok()
+        .and_then(|data| wow_mpq::special_files::parse_listfile(&data).ok());
+    let files = match listed {
+        Some(files) => files,
+        None => {
+            println!("No usable listfile found, using slow enumeration...");
+            archive.list()?.into_iter().map(|e| e.name).collect()
+        }
+    };
+    println!("Found {} files", files.len());
+    Ok(files)
+}
+
+fn output_path(output_dir: &Path, file: &str, preserve_paths: bool) -> PathBuf {
+    let system_path = mpq_path_to_system(file);
+    if preserve_paths {
+        output_dir.join(system_path)
+    } else {
+        output_dir.join(Path::new(&system_path).file_name().unwrap_or_default())
+    }
+}
+
+fn write_file(path: &Path, data: &[u8]) -> Result<usize> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    fs::write(path, data).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(data.len())
+}
+
+/// Warn when flattening makes several archive files write to the same output file
+fn report_flat_collisions(jobs: &[(String, PathBuf)]) {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for (_, path) in jobs {
+        *seen
+            .entry(path.to_string_lossy().to_lowercase())
+            .or_default() += 1;
+    }
+    let overwritten: usize = seen.values().filter(|&&n| n > 1).map(|n| n - 1).sum();
+    if overwritten > 0 {
+        log::warn!(
+            "{overwritten} files share a file name with another file and will overwrite it; use --preserve-paths to keep them apart"
+        );
+        println!(
+            "Warning: {overwritten} files collide when flattened; use --preserve-paths to keep them apart"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_include_exclude() {
+        let filter = FileFilter::new(
+            &["*.blp".to_string(), "Interface/**".to_string()],
+            &["*/Minimap/*".to_string()],
+        )
+        .unwrap();
+
+        assert!(filter.matches("Textures\\Sky.BLP"));
+        assert!(filter.matches("Interface\\FrameXML\\UIParent.lua"));
+        assert!(!filter.matches("World\\Minimap\\map32_48.blp"));
+        assert!(!filter.matches("DBFilesClient\\Map.dbc"));
+    }
+
+    #[test]
+    fn test_empty_filter_matches_everything() {
+        let filter = FileFilter::new(&[], &[]).unwrap();
+        assert!(filter.matches("(listfile)"));
+    }
+}
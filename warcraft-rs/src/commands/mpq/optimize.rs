@@ -0,0 +1,344 @@
+//! Shrink an archive by rebuilding it
+//!
+//! Optimizing is a rebuild with size in mind: every file is written again
+//! (which drops dead space left by deleted or replaced files), optionally with
+//! the compression method that suits it best and with identical files stored
+//! once. Statistics for both archives are printed side by side afterwards.
+//! A what-if report shows what each compression method would store before
+//! committing to a rebuild.
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use std::collections::HashSet;
+use std::path::Path;
+use std::time::Duration;
+use wow_mpq::compression::{EvaluateOptions, evaluate_with, flags};
+use wow_mpq::{Archive, FormatVersion, RebuildOptions, rebuild_archive};
+
+use crate::utils::{add_table_row, create_spinner, create_table, format_bytes};
+
+/// How files are recompressed
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Recompress {
+    /// Try each method and keep the smallest result per file
+    Best,
+    /// Keep each file's current compression
+    Keep,
+    /// Store files uncompressed
+    None,
+    Zlib,
+    Bzip2,
+    Lzma,
+}
+
+pub struct OptimizeParams<'a> {
+    pub source: &'a str,
+    pub target: &'a str,
+    pub target_version: Option<FormatVersion>,
+    pub recompress: Recompress,
+    /// Store files with identical contents once
+    pub dedup: bool,
+    pub block_size: Option<u16>,
+}
+
+/// Size breakdown of an archive
+struct ArchiveStats {
+    format: FormatVersion,
+    files: usize,
+    archive_size: u64,
+    /// Bytes of distinct stored file data
+    stored: u64,
+    /// Uncompressed size of all files
+    unpacked: u64,
+}
+
+impl ArchiveStats {
+    fn collect(path: &str) -> Result<Self> {
+        let mut archive =
+            Archive::open(path).with_context(|| format!("Failed to open archive: {path}"))?;
+        let format = archive.header().format_version;
+        let archive_size = std::fs::metadata(path)?.len();
+        let entries = archive
+            .list()
+            .or_else(|_| archive.list_all())
+            .context("Failed to list archive")?;
+
+        // Deduplicated files share a position; count their data once
+        let mut positions = HashSet::new();
+        let mut stored = 0;
+        let mut unpacked = 0;
+        for entry in &entries {
+            unpacked += entry.size;
+            match archive.find_file(&entry.name) {
+                Ok(Some(info)) if !positions.insert(info.file_pos) => {}
+                _ => stored += entry.compressed_size,
+            }
+        }
+
+        Ok(Self {
+            format,
+            files: entries.len(),
+            archive_size,
+            stored,
+            unpacked,
+        })
+    }
+
+    /// Header, tables and unused space
+    fn overhead(&self) -> u64 {
+        self.archive_size.saturating_sub(self.stored)
+    }
+}
+
+pub fn optimize_archive(params: OptimizeParams) -> Result<()> {
+    if Path::new(params.source) == Path::new(params.target) {
+        anyhow::bail!("The optimized archive must be written to a different path");
+    }
+
+    let before = ArchiveStats::collect(params.source)?;
+
+    let override_compression = match params.recompress {
+        Recompress::Best | Recompress::Keep => None,
+        Recompress::None => Some(0),
+        Recompress::Zlib => Some(wow_mpq::compression::flags::ZLIB),
+        Recompress::Bzip2 => Some(wow_mpq::compression::flags::BZIP2),
+        Recompress::Lzma => Some(wow_mpq::compression::flags::LZMA),
+    };
+    let options = RebuildOptions {
+        target_format: params.target_version,
+        override_compression,
+        override_block_size: params.block_size,
+        best_compression: params.recompress == Recompress::Best,
+        deduplicate: params.dedup,
+        ..RebuildOptions::default()
+    };
+
+    let spinner = create_spinner("Optimizing archive...");
+    let summary = rebuild_archive(params.source, params.target, options, None)
+        .context("Failed to optimize archive")?;
+    spinner.finish_and_clear();
+
+    let after = ArchiveStats::collect(params.target)?;
+    print_stats(&before, &after);
+
+    if summary.skipped_files > 0 {
+        println!(
+            "\n⚠ {} file(s) could not be read and were left out",
+            summary.skipped_files
+        );
+    }
+    if summary.deduplicated_files > 0 {
+        println!(
+            "\n{} duplicate file(s) now share stored data",
+            summary.deduplicated_files
+        );
+    }
+    println!("\n✓ Optimized archive written to {}", params.target);
+    Ok(())
+}
+
+/// Display name of a compression method
+fn method_name(method: u8) -> &'static str {
+    match method {
+        flags::ZLIB => "zlib",
+        flags::BZIP2 => "bzip2",
+        flags::LZMA => "lzma",
+        _ => "other",
+    }
+}
+
+/// Totals of one compression method over an archive
+#[derive(Default)]
+struct MethodTotals {
+    stored: u64,
+    compress_time: Duration,
+    decompress_time: Duration,
+    best_for: usize,
+}
+
+/// Print what each compression method would store for every file of an archive
+pub fn what_if_report(source: &str, block_size: Option<u16>) -> Result<()> {
+    let mut archive =
+        Archive::open(source).with_context(|| format!("Failed to open archive: {source}"))?;
+    let header = archive.header();
+    let format = header.format_version;
+    let options = EvaluateOptions {
+        sector_size: wow_mpq::calculate_sector_size(block_size.unwrap_or(header.block_size)),
+        ..EvaluateOptions::default()
+    };
+    let entries = archive
+        .list()
+        .or_else(|_| archive.list_all())
+        .context("Failed to list archive")?;
+
+    let spinner = create_spinner("Evaluating compression...");
+    let mut totals: Vec<MethodTotals> =
+        options.methods.iter().map(|_| Default::default()).collect();
+    let (mut files, mut unpacked, mut current, mut best_total, mut skipped) =
+        (0, 0u64, 0u64, 0u64, 0);
+    for entry in &entries {
+        let Ok(data) = archive.read_file(&entry.name) else {
+            skipped += 1;
+            continue;
+        };
+        let evaluation = evaluate_with(&data, &options)?;
+        for (total, method) in totals.iter_mut().zip(&evaluation.methods) {
+            total.stored += method.compressed_size as u64;
+            total.compress_time += method.compress_time;
+            total.decompress_time += method.decompress_time;
+        }
+        // `--recompress best` only picks LZMA for v3+ archives
+        let best = evaluation
+            .methods
+            .iter()
+            .enumerate()
+            .filter(|(_, m)| m.method != flags::LZMA || format >= FormatVersion::V3)
+            .filter(|(_, m)| m.compressed_size < data.len())
+            .min_by_key(|(_, m)| (m.compressed_size, m.compress_time));
+        match best {
+            Some((index, best)) => {
+                best_total += best.compressed_size as u64;
+                totals[index].best_for += 1;
+            }
+            None => best_total += data.len() as u64,
+        }
+        files += 1;
+        unpacked += data.len() as u64;
+        current += entry.compressed_size;
+    }
+    spinner.finish_and_clear();
+
+    let ratio = |stored: u64| {
+        if unpacked == 0 {
+            "-".to_string()
+        } else {
+            format!("{:.1}%", stored as f64 / unpacked as f64 * 100.0)
+        }
+    };
+    let mut table = create_table(vec![
+        "Method",
+        "Stored",
+        "Ratio",
+        "Compress",
+        "Decompress",
+        "Best for",
+    ]);
+    add_table_row(
+        &mut table,
+        vec![
+            "current".to_string(),
+            format_bytes(current),
+            ratio(current),
+            String::new(),
+            String::new(),
+            String::new(),
+        ],
+    );
+    for (method, total) in options.methods.iter().zip(&totals) {
+        let mut name = method_name(*method).to_string();
+        if *method == flags::LZMA && format < FormatVersion::V3 {
+            name.push_str(" (v3+)");
+        }
+        add_table_row(
+            &mut table,
+            vec![
+                name,
+                format_bytes(total.stored),
+                ratio(total.stored),
+                format!("{:.1?}", total.compress_time),
+                format!("{:.1?}", total.decompress_time),
+                format!("{} files", total.best_for),
+            ],
+        );
+    }
+    add_table_row(
+        &mut table,
+        vec![
+            "best per file".to_string(),
+            format_bytes(best_total),
+            ratio(best_total),
+            String::new(),
+            String::new(),
+            String::new(),
+        ],
+    );
+
+    println!(
+        "{files} files, {} uncompressed, {} byte sectors",
+        format_bytes(unpacked),
+        options.sector_size
+    );
+    table.printstd();
+    if skipped > 0 {
+        println!("\n⚠ {skipped} file(s) could not be read and were left out");
+    }
+    if format < FormatVersion::V3 {
+        println!("\nLZMA needs a v3+ archive and is left out of the best per file total");
+    }
+    Ok(())
+}
+
+fn print_stats(before: &ArchiveStats, after: &ArchiveStats) {
+    let change = |old: u64, new: u64| {
+        if old == 0 {
+            "-".to_string()
+        } else {
+            format!("{:+.1}%", (new as f64 - old as f64) / old as f64 * 100.0)
+        }
+    };
+    let row = |table: &mut prettytable::Table, label: &str, old: u64, new: u64| {
+        add_table_row(
+            table,
+            vec![
+                label.to_string(),
+                format_bytes(old),
+                format_bytes(new),
+                change(old, new),
+            ],
+        );
+    };
+
+    let mut table = create_table(vec!["", "Before", "After", "Change"]);
+    add_table_row(
+        &mut table,
+        vec![
+            "Format".to_string(),
+            format!("{:?}", before.format),
+            format!("{:?}", after.format),
+            String::new(),
+        ],
+    );
+    add_table_row(
+        &mut table,
+        vec![
+            "Files".to_string(),
+            before.files.to_string(),
+            after.files.to_string(),
+            String::new(),
+        ],
+    );
+    row(&mut table, "Uncompressed", before.unpacked, after.unpacked);
+    row(&mut table, "Stored data", before.stored, after.stored);
+    row(
+        &mut table,
+        "Tables & unused",
+        before.overhead(),
+        after.overhead(),
+    );
+    row(
+        &mut table,
+        "Archive size",
+        before.archive_size,
+        after.archive_size,
+    );
+
+    println!();
+    table.printstd();
+
+    let saved = before.archive_size as i64 - after.archive_size as i64;
+    if saved > 0 {
+        println!("\nSaved {}", format_bytes(saved as u64));
+    } else if saved < 0 {
+        println!("\nArchive grew by {}", format_bytes(saved.unsigned_abs()));
+    }
+}
@@ -0,0 +1,116 @@
+//! Recovery of protected or damaged MPQ archives
+//!
+//! Opens the archive with [`Archive::open_with_recovery`], which tolerates the
+//! corrupted headers and tables "map protectors" write into Warcraft III maps,
+//! lists what it found and optionally extracts every file it can read.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use wow_mpq::{Archive, RecoveredFile, path::mpq_path_to_system};
+
+use crate::utils::{create_progress_bar, format_bytes};
+
+pub struct RecoverParams<'a> {
+    pub archive: &'a str,
+    /// Extract the recovered files into this directory
+    pub extract: Option<&'a Path>,
+}
+
+pub fn recover_archive(params: RecoverParams) -> Result<()> {
+    let (mut archive, report) = Archive::open_with_recovery(params.archive)
+        .with_context(|| format!("Failed to open archive: {}", params.archive))?;
+
+    println!("Recovering {}", params.archive);
+    if report.is_clean() {
+        println!("  No damage found");
+    } else {
+        println!("  Worked around:");
+        for issue in &report.issues {
+            println!("    {issue}");
+        }
+    }
+
+    let named = report.files.iter().filter(|file| file.named).count();
+    println!(
+        "  Files: {} ({} named, {} unnamed)",
+        report.files.len(),
+        named,
+        report.files.len() - named
+    );
+    for file in &report.files {
+        println!(
+            "    {:>5}  {:>10}  {}{}",
+            file.block_index,
+            format_bytes(file.size),
+            file.name,
+            if file.key.is_none() {
+                "  (key unknown)"
+            } else {
+                ""
+            }
+        );
+    }
+
+    let Some(output_dir) = params.extract else {
+        return Ok(());
+    };
+
+    let pb = create_progress_bar(report.files.len() as u64, "Extracting files");
+    let mut failed = Vec::new();
+    for file in &report.files {
+        pb.set_message(file.name.clone());
+        if let Err(e) = extract_file(&mut archive, file, output_dir) {
+            failed.push((file.name.clone(), e));
+        }
+        pb.inc(1);
+    }
+    pb.finish_and_clear();
+
+    println!(
+        "Extracted {} of {} files to {}",
+        report.files.len() - failed.len(),
+        report.files.len(),
+        output_dir.display()
+    );
+    for (name, e) in &failed {
+        println!("  Failed: {name}: {e:#}");
+    }
+    Ok(())
+}
+
+fn extract_file(archive: &mut Archive, file: &RecoveredFile, output_dir: &Path) -> Result<()> {
+    let data = archive.read_recovered(file)?;
+    let path = output_dir.join(safe_relative_path(&file.name));
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    fs::write(&path, data).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Output path for an archive name, which may come from a hostile listfile
+fn safe_relative_path(name: &str) -> PathBuf {
+    let system_path = mpq_path_to_system(name);
+    Path::new(&system_path)
+        .components()
+        .filter(|component| matches!(component, Component::Normal(_)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_safe_relative_path() {
+        assert_eq!(
+            safe_relative_path("Units\\Human\\Footman.mdx"),
+            Path::new("Units").join("Human").join("Footman.mdx")
+        );
+        assert_eq!(
+            safe_relative_path("..\\..\\etc\\passwd"),
+            Path::new("etc").join("passwd")
+        );
+    }
+}
@@ -0,0 +1,593 @@
+//! Interactive MPQ shell
+//!
+//! Mounts one or more archives as a patch chain and exposes the merged
+//! namespace through a small REPL with `ls`, `cd`, `cat`, `extract` and `info`.
+
+use anyhow::{Context, Result};
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Editor, Helper};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use wow_mpq::debug::{HexDumpConfig, hex_dump};
+use wow_mpq::path::mpq_path_to_system;
+use wow_mpq::security::validate_file_path;
+use wow_mpq::{FileEntry, PatchChain, SecurityLimits};
+
+use crate::utils::{add_table_row, create_spinner, create_table, format_bytes, matches_pattern};
+
+/// Commands understood by the shell as (name, arguments, description)
+const SHELL_COMMANDS: &[(&str, &str, &str)] = &[
+    ("ls", "[-l] [path]", "List directory contents"),
+    ("cd", "<path>", "Change the current directory"),
+    ("pwd", "", "Print the current directory"),
+    (
+        "cat",
+        "[-x] <file>",
+        "Print a file (hex dump with -x or for binary data)",
+    ),
+    (
+        "extract",
+        "<path> [dest]",
+        "Extract a file, directory or pattern",
+    ),
+    ("info", "[file]", "Show chain or file information"),
+    ("help", "", "Show this help"),
+    ("exit", "", "Leave the shell"),
+];
+
+/// Bytes shown by `cat` before the output is truncated
+const CAT_PREVIEW_LIMIT: usize = 64 * 1024;
+
+/// A directory node in the merged archive namespace
+#[derive(Debug, Default)]
+struct DirNode {
+    /// Subdirectories keyed by upper-cased name
+    dirs: BTreeMap<String, (String, DirNode)>,
+    /// Files keyed by upper-cased name
+    files: BTreeMap<String, FileEntry>,
+}
+
+/// Directory view of all files visible through a patch chain
+#[derive(Debug, Default)]
+struct Namespace {
+    root: DirNode,
+    /// Current directory as display-cased components
+    cwd: Vec<String>,
+}
+
+impl Namespace {
+    fn from_entries(entries: Vec<FileEntry>) -> Self {
+        let mut root = DirNode::default();
+
+        for entry in entries {
+            let normalized = entry.name.replace('/', "\\");
+            let mut components: Vec<&str> =
+                normalized.split('\\').filter(|c| !c.is_empty()).collect();
+            let Some(file_name) = components.pop() else {
+                continue;
+            };
+
+            let mut node = &mut root;
+            for component in components {
+                node = &mut node
+                    .dirs
+                    .entry(component.to_uppercase())
+                    .or_insert_with(|| (component.to_string(), DirNode::default()))
+                    .1;
+            }
+            node.files.insert(file_name.to_uppercase(), entry);
+        }
+
+        Self {
+            root,
+            cwd: Vec::new(),
+        }
+    }
+
+    /// Current directory in MPQ notation
+    fn cwd_display(&self) -> String {
+        format!("\\{}", self.cwd.join("\\"))
+    }
+
+    /// Resolve a user supplied path against the current directory
+    ///
+    /// Accepts both separators, `.` and `..`, and a leading separator for
+    /// paths relative to the archive root.
+    fn resolve(&self, path: &str) -> Vec<String> {
+        let normalized = path.replace('/', "\\");
+        let mut components = if normalized.starts_with('\\') {
+            Vec::new()
+        } else {
+            self.cwd.clone()
+        };
+
+        for component in normalized.split('\\') {
+            match component {
+                "" | "." => {}
+                ".." => {
+                    components.pop();
+                }
+                other => components.push(other.to_string()),
+            }
+        }
+
+        components
+    }
+
+    fn dir(&self, components: &[String]) -> Option<&DirNode> {
+        let mut node = &self.root;
+        for component in components {
+            node = &node.dirs.get(&component.to_uppercase())?.1;
+        }
+        Some(node)
+    }
+
+    fn file(&self, components: &[String]) -> Option<&FileEntry> {
+        let (name, parent) = components.split_last()?;
+        self.dir(parent)?.files.get(&name.to_uppercase())
+    }
+
+    /// Canonical display-cased components for an existing directory
+    fn canonical_dir(&self, components: &[String]) -> Option<Vec<String>> {
+        let mut node = &self.root;
+        let mut canonical = Vec::with_capacity(components.len());
+        for component in components {
+            let (name, child) = node.dirs.get(&component.to_uppercase())?;
+            canonical.push(name.clone());
+            node = child;
+        }
+        Some(canonical)
+    }
+
+    /// Completion candidates for a partially typed path
+    fn complete(&self, partial: &str) -> Vec<Pair> {
+        let normalized = partial.replace('/', "\\");
+        let (dir_part, prefix) = match normalized.rfind('\\') {
+            Some(pos) => (&normalized[..=pos], &normalized[pos + 1..]),
+            None => ("", normalized.as_str()),
+        };
+
+        let Some(dir) = self.dir(&self.resolve(dir_part)) else {
+            return Vec::new();
+        };
+        let prefix_upper = prefix.to_uppercase();
+
+        let dirs = dir
+            .dirs
+            .iter()
+            .map(|(key, (name, _))| (key, name.as_str(), true));
+        let files = dir
+            .files
+            .iter()
+            .map(|(key, entry)| (key, base_name(entry), false));
+
+        dirs.chain(files)
+            .filter(|(key, _, _)| key.starts_with(&prefix_upper))
+            .map(|(_, name, is_dir)| {
+                let suffix = if is_dir { "\\" } else { "" };
+                Pair {
+                    display: format!("{name}{suffix}"),
+                    replacement: format!("{dir_part}{name}{suffix}"),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Final path component of an archive entry
+fn base_name(entry: &FileEntry) -> &str {
+    entry.name.rsplit(['\\', '/']).next().unwrap_or(&entry.name)
+}
+
+/// Collect all files below a directory node
+fn collect_files<'a>(node: &'a DirNode, out: &mut Vec<&'a FileEntry>) {
+    out.extend(node.files.values());
+    for (_, child) in node.dirs.values() {
+        collect_files(child, out);
+    }
+}
+
+/// Line editor helper providing tab completion over the namespace
+struct ShellHelper {
+    namespace: Namespace,
+}
+
+impl Completer for ShellHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &rustyline::Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let before = &line[..pos];
+        let start = before.rfind(' ').map_or(0, |i| i + 1);
+        let word = &before[start..];
+
+        if start == 0 {
+            let candidates = SHELL_COMMANDS
+                .iter()
+                .filter(|(name, _, _)| name.starts_with(word))
+                .map(|(name, _, _)| Pair {
+                    display: name.to_string(),
+                    replacement: format!("{name} "),
+                })
+                .collect();
+            return Ok((0, candidates));
+        }
+
+        Ok((start, self.namespace.complete(word)))
+    }
+}
+
+impl Hinter for ShellHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ShellHelper {}
+
+impl Validator for ShellHelper {}
+
+impl Helper for ShellHelper {}
+
+/// Run the interactive shell over the given archives
+///
+/// The first archive is the base, every further archive is mounted on top of
+/// it with increasing priority, matching `mpq extract --patch`.
+pub fn run_shell(archives: &[String]) -> Result<()> {
+    let spinner = create_spinner("Mounting archives...");
+    let mut chain = PatchChain::new();
+    for (index, path) in archives.iter().enumerate() {
+        chain
+            .add_archive(path, (index * 100) as i32)
+            .with_context(|| format!("Failed to mount archive: {path}"))?;
+    }
+    let entries = chain.list().context("Failed to list archive contents")?;
+    spinner.finish_and_clear();
+
+    println!(
+        "Mounted {} archive(s), {} files. Type 'help' for commands.",
+        chain.archive_count(),
+        entries.len()
+    );
+
+    let mut editor: Editor<ShellHelper, DefaultHistory> =
+        Editor::new().context("Failed to initialize line editor")?;
+    editor.set_helper(Some(ShellHelper {
+        namespace: Namespace::from_entries(entries),
+    }));
+
+    loop {
+        let prompt = match editor.helper() {
+            Some(helper) => format!("mpq:{}> ", helper.namespace.cwd_display()),
+            None => "mpq> ".to_string(),
+        };
+
+        let line = match editor.readline(&prompt) {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(e.into()),
+        };
+
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let _ = editor.add_history_entry(line);
+
+        let args: Vec<&str> = line.split_whitespace().collect();
+        let Some(helper) = editor.helper_mut() else {
+            break;
+        };
+        let namespace = &mut helper.namespace;
+
+        let result = match args[0] {
+            "exit" | "quit" => break,
+            "help" => {
+                for (name, args, description) in SHELL_COMMANDS {
+                    println!("  {:<22}{description}", format!("{name} {args}"));
+                }
+                Ok(())
+            }
+            "pwd" => {
+                println!("{}", namespace.cwd_display());
+                Ok(())
+            }
+            "ls" | "dir" => cmd_ls(namespace, &args[1..]),
+            "cd" => cmd_cd(namespace, args.get(1).copied().unwrap_or("\\")),
+            "cat" => cmd_cat(namespace, &mut chain, &args[1..]),
+            "extract" => cmd_extract(namespace, &mut chain, &args[1..]),
+            "info" => cmd_info(namespace, &mut chain, args.get(1).copied()),
+            other => Err(anyhow::anyhow!(
+                "Unknown command: {other} (type 'help' for commands)"
+            )),
+        };
+
+        if let Err(e) = result {
+            eprintln!("Error: {e:#}");
+        }
+    }
+
+    Ok(())
+}
+
+fn cmd_ls(namespace: &Namespace, args: &[&str]) -> Result<()> {
+    let long = args.contains(&"-l");
+    let target = args.iter().find(|a| !a.starts_with('-')).copied();
+    let components = namespace.resolve(target.unwrap_or("."));
+
+    // A path naming a single file lists just that file
+    if let Some(entry) = namespace.file(&components) {
+        println!("{}  {}", entry.name, format_bytes(entry.size));
+        return Ok(());
+    }
+
+    let dir = namespace
+        .dir(&components)
+        .ok_or_else(|| anyhow::anyhow!("No such directory: {}", target.unwrap_or(".")))?;
+
+    if long {
+        let mut table = create_table(vec!["Name", "Size", "Compressed", "Flags"]);
+        for (name, child) in dir.dirs.values() {
+            let mut files = Vec::new();
+            collect_files(child, &mut files);
+            let size: u64 = files.iter().map(|f| f.size).sum();
+            add_table_row(
+                &mut table,
+                vec![
+                    format!("{name}\\"),
+                    format!("{} ({} files)", format_bytes(size), files.len()),
+                    String::new(),
+                    String::new(),
+                ],
+            );
+        }
+        for entry in dir.files.values() {
+            add_table_row(
+                &mut table,
+                vec![
+                    base_name(entry).to_string(),
+                    format_bytes(entry.size),
+                    format_bytes(entry.compressed_size),
+                    format!("0x{:08X}", entry.flags),
+                ],
+            );
+        }
+        table.printstd();
+    } else {
+        for (name, _) in dir.dirs.values() {
+            println!("{name}\\");
+        }
+        for entry in dir.files.values() {
+            println!("{}", base_name(entry));
+        }
+    }
+
+    Ok(())
+}
+
+fn cmd_cd(namespace: &mut Namespace, path: &str) -> Result<()> {
+    let components = namespace.resolve(path);
+    namespace.cwd = namespace
+        .canonical_dir(&components)
+        .ok_or_else(|| anyhow::anyhow!("No such directory: {path}"))?;
+    Ok(())
+}
+
+fn cmd_cat(namespace: &Namespace, chain: &mut PatchChain, args: &[&str]) -> Result<()> {
+    let force_hex = args.contains(&"-x");
+    let path = args
+        .iter()
+        .find(|a| !a.starts_with('-'))
+        .ok_or_else(|| anyhow::anyhow!("Usage: cat [-x] <file>"))?;
+
+    let entry = namespace
+        .file(&namespace.resolve(path))
+        .ok_or_else(|| anyhow::anyhow!("No such file: {path}"))?;
+    let data = chain.read_file(&entry.name)?;
+
+    let preview = &data[..data.len().min(CAT_PREVIEW_LIMIT)];
+    match std::str::from_utf8(preview) {
+        Ok(text) if !force_hex => println!("{text}"),
+        _ => print!("{}", hex_dump(preview, &HexDumpConfig::default())),
+    }
+
+    if data.len() > CAT_PREVIEW_LIMIT {
+        println!(
+            "... ({} of {} shown, use 'extract' for the full file)",
+            format_bytes(CAT_PREVIEW_LIMIT as u64),
+            format_bytes(data.len() as u64)
+        );
+    }
+
+    Ok(())
+}
+
+fn cmd_extract(namespace: &Namespace, chain: &mut PatchChain, args: &[&str]) -> Result<()> {
+    let path = args
+        .first()
+        .ok_or_else(|| anyhow::anyhow!("Usage: extract <path> [dest]"))?;
+    let dest = PathBuf::from(args.get(1).copied().unwrap_or("."));
+
+    let components = namespace.resolve(path);
+    let mut targets = Vec::new();
+    if let Some(entry) = namespace.file(&components) {
+        targets.push(entry);
+    } else if let Some(dir) = namespace.dir(&components) {
+        collect_files(dir, &mut targets);
+    } else if path.contains('*') {
+        // Patterns match file names within the current directory tree
+        let mut candidates = Vec::new();
+        if let Some(dir) = namespace.dir(&namespace.cwd) {
+            collect_files(dir, &mut candidates);
+        }
+        let pattern = path.replace('/', "\\");
+        targets = candidates
+            .into_iter()
+            .filter(|e| {
+                matches_pattern(base_name(e), &pattern) || matches_pattern(&e.name, &pattern)
+            })
+            .collect();
+    }
+
+    if targets.is_empty() {
+        anyhow::bail!("Nothing to extract for: {path}");
+    }
+
+    let mut written = 0;
+    for entry in targets {
+        // Names come from the archive and must not escape the destination
+        let system_path = mpq_path_to_system(&entry.name);
+        if let Err(e) = validate_file_path(&system_path, &SecurityLimits::default()) {
+            eprintln!("Skipping {}: {e}", entry.name);
+            continue;
+        }
+        let output = dest.join(system_path);
+        if let Some(parent) = output.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        match chain.read_file(&entry.name) {
+            Ok(data) => {
+                fs::write(&output, data)
+                    .with_context(|| format!("Failed to write {}", output.display()))?;
+                written += 1;
+            }
+            Err(e) => eprintln!("Failed to extract {}: {e}", entry.name),
+        }
+    }
+
+    println!("Extracted {written} file(s) to {}", dest.display());
+    Ok(())
+}
+
+fn cmd_info(namespace: &Namespace, chain: &mut PatchChain, file: Option<&str>) -> Result<()> {
+    let Some(path) = file else {
+        let mut table = create_table(vec!["Archive", "Priority", "Files", "Size"]);
+        for info in chain.get_chain_info() {
+            add_table_row(
+                &mut table,
+                vec![
+                    info.path.display().to_string(),
+                    info.priority.to_string(),
+                    info.file_count.to_string(),
+                    format_bytes(info.archive_size),
+                ],
+            );
+        }
+        table.printstd();
+        return Ok(());
+    };
+
+    let entry = namespace
+        .file(&namespace.resolve(path))
+        .ok_or_else(|| anyhow::anyhow!("No such file: {path}"))?;
+
+    println!("Name:            {}", entry.name);
+    println!("Size:            {}", format_bytes(entry.size));
+    println!("Compressed size: {}", format_bytes(entry.compressed_size));
+    println!("Flags:           0x{:08X}", entry.flags);
+    println!("Compressed:      {}", entry.is_compressed());
+    println!("Encrypted:       {}", entry.is_encrypted());
+    println!("Patch file:      {}", entry.is_patch_file());
+    if let Some(source) = chain.find_file_archive(&entry.name) {
+        println!(
+            "Provided by:     {}",
+            source
+                .file_name()
+                .map(Path::new)
+                .unwrap_or(source)
+                .display()
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str) -> FileEntry {
+        FileEntry {
+            name: name.to_string(),
+            size: 1,
+            compressed_size: 1,
+            flags: 0,
+            hashes: None,
+            table_indices: None,
+        }
+    }
+
+    fn namespace() -> Namespace {
+        Namespace::from_entries(vec![
+            entry("Interface\\Icons\\INV_Misc_QuestionMark.blp"),
+            entry("Interface\\Icons\\Spell_Fire_Fireball.blp"),
+            entry("DBFilesClient\\Spell.dbc"),
+            entry("readme.txt"),
+        ])
+    }
+
+    #[test]
+    fn test_resolve_and_lookup() {
+        let mut ns = namespace();
+        assert!(
+            ns.file(&ns.resolve("interface/icons/inv_misc_questionmark.blp"))
+                .is_some()
+        );
+        assert!(ns.dir(&ns.resolve("INTERFACE")).is_some());
+
+        cmd_cd(&mut ns, "interface\\icons").unwrap();
+        assert_eq!(ns.cwd_display(), "\\Interface\\Icons");
+        assert!(ns.file(&ns.resolve("..\\..\\readme.txt")).is_some());
+        assert!(ns.file(&ns.resolve("\\DBFilesClient\\Spell.dbc")).is_some());
+        assert!(cmd_cd(&mut ns, "missing").is_err());
+    }
+
+    #[test]
+    fn test_extract_rejects_parent_references() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("evil.mpq");
+        wow_mpq::ArchiveBuilder::new()
+            .add_file_data(b"safe".to_vec(), "Data\\safe.txt")
+            .add_file_data(b"evil".to_vec(), "..\\evil.txt")
+            .build(&archive)
+            .unwrap();
+        let mut chain = PatchChain::new();
+        chain.add_archive(&archive, 0).unwrap();
+        let ns = Namespace::from_entries(chain.list().unwrap());
+
+        let dest = dir.path().join("out");
+        cmd_extract(&ns, &mut chain, &["\\", dest.to_str().unwrap()]).unwrap();
+        assert_eq!(
+            fs::read(dest.join("Data").join("safe.txt")).unwrap(),
+            b"safe"
+        );
+        assert!(!dir.path().join("evil.txt").exists());
+    }
+
+    #[test]
+    fn test_completion() {
+        let ns = namespace();
+        let names: Vec<String> = ns
+            .complete("int")
+            .into_iter()
+            .map(|p| p.replacement)
+            .collect();
+        assert_eq!(names, vec!["Interface\\"]);
+
+        let names: Vec<String> = ns
+            .complete("Interface/Icons/spell")
+            .into_iter()
+            .map(|p| p.replacement)
+            .collect();
+        assert_eq!(names, vec!["Interface\\Icons\\Spell_Fire_Fireball.blp"]);
+    }
+}
@@ -0,0 +1,272 @@
+//! Integrity verification of an MPQ archive for CI use
+//!
+//! Runs the signature and block placement checks and, with `--deep`, reads
+//! every named file to check its sector checksums and `(attributes)` entries.
+//! The process exits with [`EXIT_FAILED`] when any check fails, so scripts can
+//! tell a damaged archive from a command error (exit code 1).
+
+use anyhow::{Context, Result};
+use std::path::Path;
+use wow_mpq::{Archive, BlockIssue, CheckStatus, FileVerification, SignatureStatus};
+
+use crate::utils::create_progress_bar;
+
+/// Exit code used when the archive fails verification
+pub const EXIT_FAILED: i32 = 2;
+
+/// Failing files listed in the text summary
+const SUMMARY_FAILURES: usize = 20;
+
+pub struct VerifyParams<'a> {
+    pub archive: &'a str,
+    /// Read and check every named file
+    pub deep: bool,
+    /// Write a JSON report to this path
+    pub report: Option<&'a Path>,
+}
+
+/// Everything found by one verification run
+struct VerifyReport {
+    archive: String,
+    deep: bool,
+    signature: SignatureStatus,
+    block_issues: Vec<BlockIssue>,
+    files: Vec<FileVerification>,
+}
+
+impl VerifyReport {
+    fn signature_failed(&self) -> bool {
+        matches!(
+            self.signature,
+            SignatureStatus::WeakInvalid | SignatureStatus::StrongInvalid
+        )
+    }
+
+    fn failed_files(&self) -> impl Iterator<Item = &FileVerification> {
+        self.files.iter().filter(|file| !file.is_ok())
+    }
+
+    fn passed(&self) -> bool {
+        !self.signature_failed()
+            && self.block_issues.is_empty()
+            && self.failed_files().next().is_none()
+    }
+
+    fn exit_code(&self) -> i32 {
+        if self.passed() { 0 } else { EXIT_FAILED }
+    }
+}
+
+fn signature_name(status: &SignatureStatus) -> &'static str {
+    match status {
+        SignatureStatus::None => "none",
+        SignatureStatus::WeakValid => "weak valid",
+        SignatureStatus::WeakInvalid => "weak invalid",
+        SignatureStatus::StrongValid => "strong valid",
+        SignatureStatus::StrongInvalid => "strong invalid",
+        SignatureStatus::StrongNoKey => "strong (no public key)",
+    }
+}
+
+pub fn verify_archive(params: VerifyParams) -> Result<()> {
+    let mut archive = Archive::open(params.archive)
+        .with_context(|| format!("Failed to open archive: {}", params.archive))?;
+
+    let signature = archive
+        .verify_signature()
+        .context("Failed to verify signature")?;
+    let block_issues = archive
+        .verify_blocks()
+        .context("Failed to check block table")?;
+
+    let mut files = Vec::new();
+    if params.deep {
+        let entries = archive.list().context("Failed to list archive")?;
+        let pb = create_progress_bar(entries.len() as u64, "Verifying files");
+        // Chunks let the checksums of several files be computed together;
+        // listfile names that are not in the archive are skipped
+        for chunk in entries.chunks(64) {
+            pb.set_message(chunk[0].name.clone());
+            let names: Vec<&str> = chunk.iter().map(|entry| entry.name.as_str()).collect();
+            let results = archive
+                .verify_files(&names)
+                .with_context(|| format!("Failed to verify files from {}", chunk[0].name))?;
+            files.extend(results);
+            pb.inc(chunk.len() as u64);
+        }
+        pb.finish_and_clear();
+    }
+
+    let report = VerifyReport {
+        archive: params.archive.to_string(),
+        deep: params.deep,
+        signature,
+        block_issues,
+        files,
+    };
+
+    print_summary(&report);
+    if let Some(path) = params.report {
+        std::fs::write(path, render_json(&report)?)
+            .with_context(|| format!("Failed to write report to {}", path.display()))?;
+        println!("Report written to {}", path.display());
+    }
+
+    let code = report.exit_code();
+    if code != 0 {
+        std::process::exit(code);
+    }
+    Ok(())
+}
+
+fn print_summary(report: &VerifyReport) {
+    let mark = |ok: bool| if ok { "✓" } else { "✗" };
+
+    println!("Verifying {}", report.archive);
+    println!(
+        "{} Signature: {}",
+        mark(!report.signature_failed()),
+        signature_name(&report.signature)
+    );
+    println!(
+        "{} Blocks: {} issue(s)",
+        mark(report.block_issues.is_empty()),
+        report.block_issues.len()
+    );
+    for issue in &report.block_issues {
+        println!("    {issue}");
+    }
+
+    if report.deep {
+        let failed: Vec<_> = report.failed_files().collect();
+        let count = |check: fn(&FileVerification) -> CheckStatus| {
+            report
+                .files
+                .iter()
+                .filter(|file| check(file) != CheckStatus::NotPresent)
+                .count()
+        };
+        println!(
+            "{} Files: {} verified, {} failed (sector CRC on {}, attribute CRC32 on {}, MD5 on {})",
+            mark(failed.is_empty()),
+            report.files.len(),
+            failed.len(),
+            count(|file| file.sector_crc),
+            count(|file| file.crc32),
+            count(|file| file.md5)
+        );
+        for file in failed.iter().take(SUMMARY_FAILURES) {
+            println!("    {}: {}", file.name, describe_failure(file));
+        }
+        if failed.len() > SUMMARY_FAILURES {
+            println!("    ... and {} more", failed.len() - SUMMARY_FAILURES);
+        }
+    } else {
+        println!("  Files: skipped (use --deep to read and check every file)");
+    }
+
+    if report.passed() {
+        println!("Archive passed verification");
+    } else {
+        println!("Archive FAILED verification");
+    }
+}
+
+/// Short reason a file failed
+fn describe_failure(file: &FileVerification) -> String {
+    if let Some(error) = &file.read_error {
+        return format!("read error: {error}");
+    }
+    let mut reasons = Vec::new();
+    if file.sector_crc == CheckStatus::Invalid {
+        if file.bad_sectors.is_empty() {
+            reasons.push("sector CRC mismatch".to_string());
+        } else {
+            reasons.push(format!(
+                "sector CRC mismatch in sectors {:?}",
+                file.bad_sectors
+            ));
+        }
+    }
+    if file.crc32 == CheckStatus::Invalid {
+        reasons.push("attribute CRC32 mismatch".to_string());
+    }
+    if file.md5 == CheckStatus::Invalid {
+        reasons.push("attribute MD5 mismatch".to_string());
+    }
+    reasons.join(", ")
+}
+
+#[cfg(feature = "serde")]
+fn render_json(report: &VerifyReport) -> Result<String> {
+    let block_issues: Vec<_> = report
+        .block_issues
+        .iter()
+        .map(|issue| match issue {
+            BlockIssue::OutOfBounds {
+                block_index,
+                offset,
+                size,
+                archive_size,
+            } => serde_json::json!({
+                "kind": "out_of_bounds",
+                "block_index": block_index,
+                "offset": offset,
+                "size": size,
+                "archive_size": archive_size,
+            }),
+            BlockIssue::Overlap {
+                first,
+                second,
+                length,
+            } => serde_json::json!({
+                "kind": "overlap",
+                "first": first,
+                "second": second,
+                "length": length,
+            }),
+        })
+        .collect();
+    let files: Vec<_> = report
+        .files
+        .iter()
+        .map(|file| {
+            serde_json::json!({
+                "name": file.name,
+                "block_index": file.block_index,
+                "ok": file.is_ok(),
+                "read_error": file.read_error,
+                "sector_crc": file.sector_crc.as_str(),
+                "bad_sectors": file.bad_sectors,
+                "crc32": file.crc32.as_str(),
+                "md5": file.md5.as_str(),
+            })
+        })
+        .collect();
+
+    let document = serde_json::json!({
+        "archive": report.archive,
+        "deep": report.deep,
+        "passed": report.passed(),
+        "exit_code": report.exit_code(),
+        "signature": {
+            "status": signature_name(&report.signature),
+            "ok": !report.signature_failed(),
+        },
+        "blocks": {
+            "ok": report.block_issues.is_empty(),
+            "issues": block_issues,
+        },
+        "files": {
+            "checked": report.files.len(),
+            "failed": report.failed_files().count(),
+            "results": files,
+        },
+    });
+    Ok(serde_json::to_string_pretty(&document)? + "\n")
+}
+
+#[cfg(not(feature = "serde"))]
+fn render_json(_report: &VerifyReport) -> Result<String> {
+    anyhow::bail!("JSON reports require the 'serde' feature to be enabled");
+}
@@ -0,0 +1,244 @@
+//! Keep an archive in sync with a source directory
+//!
+//! The archive is built once from the directory and then updated in place
+//! through [`MutableArchive`] whenever files change, so a patch under test is
+//! rebuilt in milliseconds instead of being packed from scratch. Changes are
+//! collected until the directory has been quiet for the debounce period. If
+//! an in-place update fails (for example because the hash table is full) the
+//! archive is rebuilt from the directory instead.
+
+use anyhow::{Context, Result};
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use wow_mpq::compression::CompressionMethod;
+use wow_mpq::{AddFileOptions, ArchiveBuilder, FormatVersion, ListfileOption, MutableArchive};
+
+pub struct WatchParams<'a> {
+    pub source: &'a Path,
+    pub archive: &'a Path,
+    pub version: FormatVersion,
+    /// Compression flags from [`wow_mpq::compression::flags`]
+    pub compression: u8,
+    /// Quiet period before pending changes are applied
+    pub debounce: Duration,
+    /// Rebuild the archive on start even if it already exists
+    pub rebuild: bool,
+}
+
+/// Name of a source file inside the archive
+fn archive_name(source: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(source).ok()?;
+    let name = relative.to_str()?.replace('/', "\\");
+    (!name.is_empty()).then_some(name)
+}
+
+/// Every file below `dir`, recursively
+fn walk_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+        let path = entry?.path();
+        if path.is_dir() {
+            walk_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Pack the whole source directory into a fresh archive
+fn build_archive(params: &WatchParams) -> Result<usize> {
+    let mut files = Vec::new();
+    walk_files(params.source, &mut files)?;
+    files.sort();
+
+    let mut builder = ArchiveBuilder::new()
+        .version(params.version)
+        .default_compression(params.compression)
+        .listfile_option(ListfileOption::Generate);
+    let mut count = 0;
+    for path in &files {
+        if is_archive(params, path) {
+            continue;
+        }
+        let Some(name) = archive_name(params.source, path) else {
+            continue;
+        };
+        let data = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        builder = builder.add_file_data(data, &name);
+        count += 1;
+    }
+    builder
+        .build(params.archive)
+        .with_context(|| format!("Failed to build {}", params.archive.display()))?;
+    Ok(count)
+}
+
+/// Whether `path` is the output archive, which may live inside the source tree
+fn is_archive(params: &WatchParams, path: &Path) -> bool {
+    path == params.archive
+        || fs::canonicalize(path)
+            .ok()
+            .zip(fs::canonicalize(params.archive).ok())
+            .is_some_and(|(a, b)| a == b)
+}
+
+/// Apply changed paths to the archive in place; returns (updated, removed)
+fn apply_changes(params: &WatchParams, changed: &BTreeSet<PathBuf>) -> Result<(usize, usize)> {
+    let mut archive = MutableArchive::open(params.archive)
+        .with_context(|| format!("Failed to open {}", params.archive.display()))?;
+    let options =
+        AddFileOptions::new().compression(CompressionMethod::from_flags(params.compression));
+
+    // Resolve the events to unique archive names first: one change often
+    // reports both a directory and the files inside it
+    let mut additions = BTreeMap::new();
+    let mut deleted = Vec::new();
+    for path in changed {
+        if path.is_dir() {
+            // A directory moved into the tree brings its files along
+            let mut files = Vec::new();
+            walk_files(path, &mut files)?;
+            for file in files {
+                if let Some(name) = archive_name(params.source, &file) {
+                    additions.insert(name, file);
+                }
+            }
+        } else if let Some(name) = archive_name(params.source, path) {
+            if path.is_file() {
+                additions.insert(name, path.clone());
+            } else {
+                deleted.push(name);
+            }
+        }
+    }
+
+    // A deleted path is either a single file or a whole directory
+    let mut removals = BTreeSet::new();
+    if !deleted.is_empty() {
+        let names: Vec<String> = archive.list()?.into_iter().map(|e| e.name).collect();
+        for name in &deleted {
+            let prefix = format!("{name}\\");
+            removals.extend(
+                names
+                    .iter()
+                    .filter(|entry| entry.eq_ignore_ascii_case(name) || entry.starts_with(&prefix))
+                    .filter(|entry| !additions.contains_key(*entry))
+                    .cloned(),
+            );
+        }
+    }
+
+    for name in &removals {
+        archive
+            .remove_file(name)
+            .with_context(|| format!("Failed to remove {name}"))?;
+    }
+    for (name, path) in &additions {
+        archive
+            .add_file(path, name, options.clone())
+            .with_context(|| format!("Failed to add {name}"))?;
+    }
+
+    archive.flush().context("Failed to write archive")?;
+    Ok((additions.len(), removals.len()))
+}
+
+pub fn watch_archive(params: WatchParams) -> Result<()> {
+    if !params.source.is_dir() {
+        anyhow::bail!("{} is not a directory", params.source.display());
+    }
+    // Watcher events carry absolute paths
+    let source = fs::canonicalize(params.source)?;
+    let archive = std::path::absolute(params.archive)?;
+    let params = WatchParams {
+        source: &source,
+        archive: &archive,
+        ..params
+    };
+
+    if params.rebuild || !params.archive.exists() {
+        let start = Instant::now();
+        let count = build_archive(&params)?;
+        println!(
+            "Built {} with {count} file(s) in {:.1?}",
+            params.archive.display(),
+            start.elapsed()
+        );
+    }
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx).context("Failed to start file watcher")?;
+    watcher
+        .watch(params.source, RecursiveMode::Recursive)
+        .with_context(|| format!("Failed to watch {}", params.source.display()))?;
+    println!(
+        "Watching {} for changes (Ctrl+C to stop)",
+        params.source.display()
+    );
+
+    let mut changed = BTreeSet::new();
+    let collect =
+        |event: notify::Result<notify::Event>, changed: &mut BTreeSet<PathBuf>| match event {
+            Ok(event) if !matches!(event.kind, EventKind::Access(_)) => {
+                changed.extend(
+                    event
+                        .paths
+                        .into_iter()
+                        .filter(|path| !is_archive(&params, path)),
+                );
+            }
+            Ok(_) => {}
+            Err(e) => log::warn!("Watch error: {e}"),
+        };
+
+    // Block for the first change, then gather the rest until things settle
+    while let Ok(event) = rx.recv() {
+        collect(event, &mut changed);
+        while let Ok(event) = rx.recv_timeout(params.debounce) {
+            collect(event, &mut changed);
+        }
+        if changed.is_empty() {
+            continue;
+        }
+
+        let start = Instant::now();
+        let time = chrono::Local::now().format("%H:%M:%S");
+        match apply_changes(&params, &changed) {
+            Ok((updated, removed)) => println!(
+                "[{time}] Updated {updated} file(s), removed {removed} in {:.1?}",
+                start.elapsed()
+            ),
+            Err(e) => {
+                log::warn!("In-place update failed: {e:#}");
+                let count = build_archive(&params)?;
+                println!(
+                    "[{time}] Rebuilt archive with {count} file(s) in {:.1?}",
+                    start.elapsed()
+                );
+            }
+        }
+        changed.clear();
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_archive_name_uses_backslashes() {
+        let source = Path::new("/src/patch");
+        assert_eq!(
+            archive_name(source, Path::new("/src/patch/Interface/AddOns/a.lua")).as_deref(),
+            Some("Interface\\AddOns\\a.lua")
+        );
+        assert_eq!(archive_name(source, source), None);
+        assert_eq!(archive_name(source, Path::new("/other/a.lua")), None);
+    }
+}
@@ -0,0 +1,510 @@
+//! Format-independent structure tree
+//!
+//! Detects the file format from its magic (falling back to the extension) and
+//! walks the raw chunk or header layout, so files that the format crates fail
+//! to parse can still be inspected.
+
+use anyhow::{Context, Result};
+use glob::{MatchOptions, Pattern};
+use std::path::Path;
+use warcraft_formats::{DetectedFormat, sniff_with_extension};
+
+use crate::utils::tree::{NodeType, TreeNode, TreeOptions, render_tree};
+
+/// A structural element found while walking the file
+#[derive(Debug, Clone)]
+struct Node {
+    id: String,
+    label: Option<String>,
+    node_type: NodeType,
+    offset: usize,
+    size: usize,
+    details: Vec<(&'static str, String)>,
+    children: Vec<Node>,
+}
+
+impl Node {
+    fn new(id: impl Into<String>, node_type: NodeType, offset: usize, size: usize) -> Self {
+        Self {
+            id: id.into(),
+            label: None,
+            node_type,
+            offset,
+            size,
+            details: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    fn detail(mut self, key: &'static str, value: impl ToString) -> Self {
+        self.details.push((key, value.to_string()));
+        self
+    }
+
+    fn into_tree_node(self) -> TreeNode {
+        let name = match &self.label {
+            _ if self.node_type == NodeType::Root => self.id.clone(),
+            Some(label) => format!("{} {label} @ 0x{:08X}", self.id, self.offset),
+            None => format!("{} @ 0x{:08X}", self.id, self.offset),
+        };
+        // Tables only know their count, not their byte size
+        let mut node = TreeNode::new(name, self.node_type.clone());
+        if self.size > 0 || self.node_type == NodeType::Chunk {
+            node = node.with_size(self.size as u64);
+        }
+        for (key, value) in &self.details {
+            node = node.with_metadata(key, value);
+        }
+        for child in self.children {
+            node = node.add_child(child.into_tree_node());
+        }
+        node
+    }
+
+    /// Keep only nodes whose ID matches, along with their ancestors
+    fn retain_matching(&mut self, pattern: &Pattern) -> bool {
+        let options = MatchOptions {
+            case_sensitive: false,
+            ..MatchOptions::default()
+        };
+        self.children
+            .retain_mut(|child| child.retain_matching(pattern));
+        pattern.matches_with(&self.id, options) || !self.children.is_empty()
+    }
+}
+
+pub fn execute(
+    file: &Path,
+    depth: Option<usize>,
+    filter: Option<&str>,
+    no_color: bool,
+    compact: bool,
+) -> Result<()> {
+    let data = std::fs::read(file).with_context(|| format!("Failed to read {}", file.display()))?;
+    let extension = file.extension().and_then(|e| e.to_str());
+    let format = sniff_with_extension(&data, extension);
+
+    let mut children = match format {
+        format if format.is_chunked() => walk_chunks(&data, 0, data.len(), true),
+        DetectedFormat::M2Chunked => walk_chunks(&data, 0, data.len(), false),
+        DetectedFormat::M2 => vec![m2_header(&data, 0)],
+        DetectedFormat::Skin => vec![skin_header(&data)],
+        DetectedFormat::Blp => blp_structure(&data),
+        DetectedFormat::Dbc | DetectedFormat::Db2 => dbc_structure(&data),
+        DetectedFormat::Mpq => anyhow::bail!(
+            "{} is an MPQ archive; use `mpq info` or `mpq list` to inspect it",
+            file.display()
+        ),
+        _ => anyhow::bail!(
+            "Unrecognised file format: {} (supported: ADT, WMO, WDT, WDL, M2, SKIN, BLP, DBC, DB2)",
+            file.display()
+        ),
+    };
+
+    if let Some(filter) = filter {
+        let pattern =
+            Pattern::new(filter).with_context(|| format!("Invalid filter pattern: {filter}"))?;
+        children.retain_mut(|child| child.retain_matching(&pattern));
+    }
+
+    let file_name = file
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| file.display().to_string());
+    let mut root =
+        Node::new(file_name, NodeType::Root, 0, data.len()).detail("format", format.name());
+    root.children = children;
+
+    let options = TreeOptions {
+        max_depth: depth,
+        show_external_refs: false,
+        no_color,
+        show_metadata: true,
+        compact,
+        verbose: false,
+    };
+    print!("{}", render_tree(&root.into_tree_node(), &options));
+
+    Ok(())
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    data.get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+/// Read a chunk ID, un-reversing the on-disk byte order of IFF-style formats
+fn chunk_id(data: &[u8], offset: usize, reversed: bool) -> Option<String> {
+    let bytes = data.get(offset..offset + 4)?;
+    if !bytes
+        .iter()
+        .all(|b| b.is_ascii_uppercase() || b.is_ascii_digit() || *b == b'_')
+    {
+        return None;
+    }
+    let mut bytes = bytes.to_vec();
+    if reversed {
+        bytes.reverse();
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// Walk a sequence of `[id][size][data]` chunks between `start` and `end`
+fn walk_chunks(data: &[u8], start: usize, end: usize, reversed: bool) -> Vec<Node> {
+    let mut nodes = Vec::new();
+    let mut offset = start;
+
+    while offset + 8 <= end {
+        let (Some(id), Some(size)) = (chunk_id(data, offset, reversed), read_u32(data, offset + 4))
+        else {
+            nodes.push(trailing_data(offset, end));
+            return nodes;
+        };
+        let data_start = offset + 8;
+        let size = size as usize;
+        if data_start + size > end {
+            nodes.push(
+                Node::new(id, NodeType::Chunk, offset, end - data_start)
+                    .detail("warning", format!("declared size {size} exceeds file")),
+            );
+            return nodes;
+        }
+
+        let mut node = Node::new(id.as_str(), NodeType::Chunk, offset, size);
+        match id.as_str() {
+            "MCNK" => {
+                if let (Some(x), Some(y)) = (
+                    read_u32(data, data_start + 4),
+                    read_u32(data, data_start + 8),
+                ) {
+                    node.label = Some(format!("[{x},{y}]"));
+                }
+                // 128 byte header, except in split _tex/_obj files which have none
+                let header = if size >= 128 && chunk_id(data, data_start, true).is_none() {
+                    128
+                } else {
+                    0
+                };
+                node.children = walk_chunks(data, data_start + header, data_start + size, reversed);
+            }
+            "MOGP" => {
+                node.children = walk_chunks(data, data_start + 0x44, data_start + size, reversed);
+            }
+            "MD21" => {
+                node.children = vec![m2_header(&data[..data_start + size], data_start)];
+            }
+            _ => {}
+        }
+        nodes.push(node);
+
+        offset = data_start + size;
+
+        // Pre-Cataclysm MCNR declares 435 bytes but is followed by 13 bytes of padding
+        if id == "MCNR"
+            && size == 435
+            && chunk_id(data, offset, reversed).is_none()
+            && chunk_id(data, offset + 13, reversed).is_some()
+        {
+            offset += 13;
+        }
+    }
+
+    if offset < end {
+        nodes.push(trailing_data(offset, end));
+    }
+    nodes
+}
+
+fn trailing_data(offset: usize, end: usize) -> Node {
+    Node::new("(data)", NodeType::Data, offset, end - offset)
+}
+
+/// One field of the M2 header
+enum M2Field {
+    /// `M2Array` of count and offset
+    Array(&'static str),
+    /// Plain value or fixed-size block
+    Bytes(&'static str, usize),
+}
+
+/// M2 header fields following magic and version
+///
+/// Fields that only exist before Wrath (version < 264) are filtered out in
+/// [`m2_header_fields`].
+const M2_HEADER: &[(M2Field, bool)] = &[
+    (M2Field::Array("name"), true),
+    (M2Field::Bytes("global_flags", 4), true),
+    (M2Field::Array("global_sequences"), true),
+    (M2Field::Array("animations"), true),
+    (M2Field::Array("animation_lookup"), true),
+    (M2Field::Array("playable_animation_lookup"), false),
+    (M2Field::Array("bones"), true),
+    (M2Field::Array("key_bone_lookup"), true),
+    (M2Field::Array("vertices"), true),
+    (M2Field::Array("views"), false),
+    (M2Field::Bytes("num_skin_profiles", 4), true),
+    (M2Field::Array("colors"), true),
+    (M2Field::Array("textures"), true),
+    (M2Field::Array("texture_weights"), true),
+    (M2Field::Array("texture_flipbooks"), false),
+    (M2Field::Array("texture_transforms"), true),
+    (M2Field::Array("replaceable_texture_lookup"), true),
+    (M2Field::Array("materials"), true),
+    (M2Field::Array("bone_lookup"), true),
+    (M2Field::Array("texture_lookup"), true),
+    (M2Field::Array("texture_unit_lookup"), true),
+    (M2Field::Array("transparency_lookup"), true),
+    (M2Field::Array("texture_transform_lookup"), true),
+    (M2Field::Bytes("bounding_box", 28), true),
+    (M2Field::Bytes("collision_box", 28), true),
+    (M2Field::Array("collision_triangles"), true),
+    (M2Field::Array("collision_vertices"), true),
+    (M2Field::Array("collision_normals"), true),
+    (M2Field::Array("attachments"), true),
+    (M2Field::Array("attachment_lookup"), true),
+    (M2Field::Array("events"), true),
+    (M2Field::Array("lights"), true),
+    (M2Field::Array("cameras"), true),
+    (M2Field::Array("camera_lookup"), true),
+    (M2Field::Array("ribbon_emitters"), true),
+    (M2Field::Array("particle_emitters"), true),
+];
+
+/// Header fields present for an M2 version
+///
+/// `views` replaced `num_skin_profiles` in Wrath; the other pre-Wrath fields
+/// were dropped outright.
+fn m2_header_fields(version: u32) -> impl Iterator<Item = &'static M2Field> {
+    let legacy = version < 264;
+    M2_HEADER.iter().filter_map(move |(field, modern)| {
+        let keep = match field {
+            M2Field::Bytes("num_skin_profiles", _) => !legacy,
+            _ => *modern || legacy,
+        };
+        keep.then_some(field)
+    })
+}
+
+/// Describe the MD20 header at `base`; array offsets are relative to `base`
+fn m2_header(data: &[u8], base: usize) -> Node {
+    let version = read_u32(data, base + 4).unwrap_or(0);
+    let mut header = Node::new("MD20", NodeType::Header, base, 0).detail("version", version);
+    let mut offset = base + 8;
+
+    for field in m2_header_fields(version) {
+        match field {
+            M2Field::Array(name) => {
+                let (Some(count), Some(array_offset)) =
+                    (read_u32(data, offset), read_u32(data, offset + 4))
+                else {
+                    break;
+                };
+                if count > 0 {
+                    let mut node =
+                        Node::new(*name, NodeType::Table, base + array_offset as usize, 0)
+                            .detail("count", count);
+                    node.label = Some(format!("[{count}]"));
+                    header.children.push(node);
+                }
+                offset += 8;
+            }
+            M2Field::Bytes(name, len) => {
+                if *len == 4
+                    && let Some(value) = read_u32(data, offset)
+                {
+                    header.children.push(
+                        Node::new(*name, NodeType::Property, offset, 4).detail("value", value),
+                    );
+                }
+                offset += len;
+            }
+        }
+    }
+
+    header.size = offset - base;
+    header
+}
+
+fn skin_header(data: &[u8]) -> Node {
+    let mut header = Node::new("SKIN", NodeType::Header, 0, 0);
+    let mut offset = 4;
+    for name in ["indices", "triangles", "properties", "submeshes", "batches"] {
+        let (Some(count), Some(array_offset)) =
+            (read_u32(data, offset), read_u32(data, offset + 4))
+        else {
+            break;
+        };
+        let mut node =
+            Node::new(name, NodeType::Table, array_offset as usize, 0).detail("count", count);
+        node.label = Some(format!("[{count}]"));
+        header.children.push(node);
+        offset += 8;
+    }
+    if let Some(bone_count) = read_u32(data, offset) {
+        header.children.push(
+            Node::new("bone_count_max", NodeType::Property, offset, 4).detail("value", bone_count),
+        );
+        offset += 4;
+    }
+    header.size = offset;
+    header
+}
+
+fn blp_structure(data: &[u8]) -> Vec<Node> {
+    let magic = String::from_utf8_lossy(&data[..4]).to_string();
+    let mut nodes = Vec::new();
+
+    // BLP2 header: type, compression/alpha bytes, size, then the mipmap tables
+    // BLP1 header: compression, alpha bits, size, extra, has_mips, then the tables
+    let (header_size, compression, has_mips) = if magic == "BLP2" {
+        (
+            20,
+            data.get(8).copied().map(u32::from),
+            data.get(11).is_some_and(|b| *b != 0),
+        )
+    } else {
+        (
+            28,
+            read_u32(data, 4),
+            read_u32(data, 24).is_some_and(|v| v != 0),
+        )
+    };
+    let width = read_u32(data, 12).unwrap_or(0);
+    let height = read_u32(data, 16).unwrap_or(0);
+    let mut header = Node::new(magic, NodeType::Header, 0, header_size)
+        .detail("width", width)
+        .detail("height", height);
+    if let Some(compression) = compression {
+        header = header.detail("compression", compression);
+    }
+    nodes.push(header);
+
+    let tables = header_size;
+    nodes.push(Node::new("mipmap_offsets", NodeType::Table, tables, 64));
+    nodes.push(Node::new("mipmap_sizes", NodeType::Table, tables + 64, 64));
+
+    let after_tables = tables + 128;
+    let first_mip = read_u32(data, tables).unwrap_or(0) as usize;
+    if first_mip > after_tables {
+        let id = if compression == Some(0) && data.get(..4) == Some(b"BLP1") {
+            "jpeg_header"
+        } else {
+            "palette"
+        };
+        nodes.push(Node::new(
+            id,
+            NodeType::Data,
+            after_tables,
+            first_mip - after_tables,
+        ));
+    }
+
+    // Unused table entries are not always zeroed, so stop at the last real level
+    let levels = if has_mips {
+        (32 - width.max(height).max(1).leading_zeros() as usize).min(16)
+    } else {
+        1
+    };
+    for level in 0..levels {
+        let offset = read_u32(data, tables + level * 4).unwrap_or(0) as usize;
+        let size = read_u32(data, tables + 64 + level * 4).unwrap_or(0) as usize;
+        if offset == 0 || size == 0 {
+            break;
+        }
+        let mut node = Node::new("mipmap", NodeType::Data, offset, size).detail(
+            "dimensions",
+            format!("{}x{}", (width >> level).max(1), (height >> level).max(1)),
+        );
+        node.label = Some(format!("{level}"));
+        if offset + size > data.len() {
+            node = node.detail("warning", "extends past end of file");
+        }
+        nodes.push(node);
+    }
+
+    nodes
+}
+
+fn dbc_structure(data: &[u8]) -> Vec<Node> {
+    let magic = String::from_utf8_lossy(&data[..4]).to_string();
+    if magic != "WDBC" {
+        return vec![
+            Node::new(magic, NodeType::Header, 0, 4),
+            trailing_data(4, data.len()),
+        ];
+    }
+
+    let record_count = read_u32(data, 4).unwrap_or(0) as usize;
+    let field_count = read_u32(data, 8).unwrap_or(0);
+    let record_size = read_u32(data, 12).unwrap_or(0) as usize;
+    let string_block_size = read_u32(data, 16).unwrap_or(0) as usize;
+
+    let records_size = record_count * record_size;
+    let mut nodes = vec![
+        Node::new("WDBC", NodeType::Header, 0, 20)
+            .detail("records", record_count)
+            .detail("fields", field_count)
+            .detail("record_size", record_size),
+        Node::new("records", NodeType::Table, 20, records_size).detail("count", record_count),
+        Node::new(
+            "string_block",
+            NodeType::Data,
+            20 + records_size,
+            string_block_size,
+        ),
+    ];
+
+    let expected = 20 + records_size + string_block_size;
+    if data.len() > expected {
+        nodes.push(trailing_data(expected, data.len()));
+    }
+    if data.len() < expected {
+        nodes[2] = nodes[2].clone().detail("warning", "truncated");
+    }
+    nodes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(id: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut out: Vec<u8> = id.iter().rev().copied().collect();
+        out.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        out.extend_from_slice(payload);
+        out
+    }
+
+    #[test]
+    fn test_walks_nested_mcnk_chunks() {
+        let mut mcnk = vec![0u8; 128];
+        mcnk[4..8].copy_from_slice(&3u32.to_le_bytes());
+        mcnk[8..12].copy_from_slice(&7u32.to_le_bytes());
+        mcnk.extend(chunk(b"MCVT", &[0; 580]));
+        mcnk.extend(chunk(b"MCNR", &[0; 435]));
+        mcnk.extend_from_slice(&[0; 13]);
+        mcnk.extend(chunk(b"MCLY", &[0; 16]));
+
+        let mut data = chunk(b"MVER", &18u32.to_le_bytes());
+        data.extend(chunk(b"MCNK", &mcnk));
+
+        let nodes = walk_chunks(&data, 0, data.len(), true);
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(nodes[1].id, "MCNK");
+        assert_eq!(nodes[1].label.as_deref(), Some("[3,7]"));
+        let ids: Vec<_> = nodes[1].children.iter().map(|n| n.id.as_str()).collect();
+        assert_eq!(ids, ["MCVT", "MCNR", "MCLY"]);
+    }
+
+    #[test]
+    fn test_filter_keeps_ancestors() {
+        let mut root = Node::new("MCNK", NodeType::Chunk, 0, 0);
+        root.children.push(Node::new("MCVT", NodeType::Chunk, 0, 0));
+        root.children.push(Node::new("MCLY", NodeType::Chunk, 0, 0));
+
+        let pattern = Pattern::new("mcly").unwrap();
+        assert!(root.retain_matching(&pattern));
+        assert_eq!(root.children.len(), 1);
+        assert_eq!(root.children[0].id, "MCLY");
+    }
+}
@@ -2,9 +2,10 @@
 
 use anyhow::{Context, Result};
 use clap::Subcommand;
+use std::collections::HashSet;
 use std::fs::File;
 use std::io::{BufReader, BufWriter};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use wow_wdt::{
     WdtReader, WdtWriter,
@@ -80,6 +81,28 @@ pub enum WdtCommands {
         format: String,
     },
 
+    /// Show a 64x64 grid of the map's tiles
+    Coverage {
+        /// Path to the WDT file
+        file: PathBuf,
+
+        /// WoW version (e.g., "1.12.1", "3.3.5a", "WotLK", "TBC", "MoP")
+        #[arg(long, default_value = "WotLK")]
+        version: String,
+
+        /// Directory with the map's ADT files to cross-check against the WDT
+        #[arg(long)]
+        adt_dir: Option<PathBuf>,
+
+        /// Output format (text, json)
+        #[arg(short, long, default_value = "text")]
+        format: String,
+
+        /// Draw the grid with emoji squares instead of ASCII
+        #[arg(long)]
+        emoji: bool,
+    },
+
     /// Show tree structure of a WDT file
     Tree {
         /// Path to the WDT file
@@ -131,6 +154,13 @@ pub fn execute(command: WdtCommands) -> Result<()> {
             version,
             format,
         } => execute_tiles(file, version, format),
+        WdtCommands::Coverage {
+            file,
+            version,
+            adt_dir,
+            format,
+            emoji,
+        } => execute_coverage(file, version, adt_dir, format, emoji),
         WdtCommands::Tree {
             file,
             version,
@@ -446,6 +476,198 @@ fn execute_tiles(path: PathBuf, version_str: String, format: String) -> Result<(
     Ok(())
 }
 
+/// State of one map tile in the coverage grid
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum TileCoverage {
+    /// Neither referenced by the WDT nor on disk
+    Empty,
+    /// Referenced by the WDT (not cross-checked)
+    Referenced,
+    /// Referenced and the ADT file exists
+    Present,
+    /// Referenced but the ADT file is missing
+    Missing,
+    /// An ADT file exists that the WDT does not reference
+    Extra,
+}
+
+impl TileCoverage {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Empty => "empty",
+            Self::Referenced => "referenced",
+            Self::Present => "present",
+            Self::Missing => "missing",
+            Self::Extra => "extra",
+        }
+    }
+
+    fn symbol(self, emoji: bool) -> &'static str {
+        match (self, emoji) {
+            (Self::Empty, false) => ".",
+            (Self::Referenced | Self::Present, false) => "#",
+            (Self::Missing, false) => "!",
+            (Self::Extra, false) => "?",
+            (Self::Empty, true) => "⬛",
+            (Self::Referenced | Self::Present, true) => "🟩",
+            (Self::Missing, true) => "🟥",
+            (Self::Extra, true) => "🟨",
+        }
+    }
+}
+
+/// Parse `<map>_<x>_<y>.adt` (case-insensitive) into tile coordinates
+///
+/// Split files (`_tex0`, `_obj0`, `_lod`) are not root tiles and are ignored.
+fn parse_adt_tile_name(map: &str, file_name: &str) -> Option<(usize, usize)> {
+    let lower = file_name.to_ascii_lowercase();
+    let rest = lower
+        .strip_prefix(&format!("{}_", map.to_ascii_lowercase()))?
+        .strip_suffix(".adt")?;
+    let (x, y) = rest.split_once('_')?;
+    let (x, y) = (x.parse().ok()?, y.parse().ok()?);
+    (x < 64 && y < 64).then_some((x, y))
+}
+
+fn adt_tiles_on_disk(dir: &Path, map: &str) -> Result<HashSet<(usize, usize)>> {
+    let entries = std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to read ADT directory {}", dir.display()))?;
+    Ok(entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| parse_adt_tile_name(map, e.file_name().to_str()?))
+        .collect())
+}
+
+fn execute_coverage(
+    path: PathBuf,
+    version_str: String,
+    adt_dir: Option<PathBuf>,
+    format: String,
+    emoji: bool,
+) -> Result<()> {
+    use console::style;
+
+    let version =
+        WowVersion::from_expansion_name(&version_str).context("Invalid version string")?;
+
+    let file = File::open(&path).context("Failed to open WDT file")?;
+    let mut reader = WdtReader::new(BufReader::new(file), version);
+    let wdt = reader.read().context("Failed to parse WDT file")?;
+
+    let map = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .context("Invalid WDT file name")?;
+    let on_disk = adt_dir
+        .as_deref()
+        .map(|dir| adt_tiles_on_disk(dir, map))
+        .transpose()?;
+
+    let mut grid = [[TileCoverage::Empty; 64]; 64];
+    let mut area_ids = [[0u32; 64]; 64];
+    for (y, row) in grid.iter_mut().enumerate() {
+        for (x, cell) in row.iter_mut().enumerate() {
+            let tile = wdt.get_tile(x, y);
+            let referenced = tile.as_ref().is_some_and(|t| t.has_adt);
+            area_ids[y][x] = tile.map_or(0, |t| t.area_id);
+            *cell = match (&on_disk, referenced) {
+                (None, true) => TileCoverage::Referenced,
+                (None, false) => TileCoverage::Empty,
+                (Some(files), true) if files.contains(&(x, y)) => TileCoverage::Present,
+                (Some(_), true) => TileCoverage::Missing,
+                (Some(files), false) if files.contains(&(x, y)) => TileCoverage::Extra,
+                (Some(_), false) => TileCoverage::Empty,
+            };
+        }
+    }
+
+    let count = |state: TileCoverage| grid.iter().flatten().filter(|&&c| c == state).count();
+    let referenced = count(TileCoverage::Referenced)
+        + count(TileCoverage::Present)
+        + count(TileCoverage::Missing);
+
+    match format.as_str() {
+        "json" => {
+            #[cfg(feature = "serde")]
+            {
+                let mut tiles = Vec::new();
+                for (y, row) in grid.iter().enumerate() {
+                    for (x, &state) in row.iter().enumerate() {
+                        if state != TileCoverage::Empty {
+                            tiles.push(serde_json::json!({
+                                "x": x,
+                                "y": y,
+                                "area_id": area_ids[y][x],
+                                "status": state.name(),
+                            }));
+                        }
+                    }
+                }
+                let mut report = serde_json::json!({
+                    "map": map,
+                    "wmo_only": wdt.is_wmo_only(),
+                    "referenced": referenced,
+                    "tiles": tiles,
+                });
+                if on_disk.is_some() {
+                    report["present"] = count(TileCoverage::Present).into();
+                    report["missing"] = count(TileCoverage::Missing).into();
+                    report["extra"] = count(TileCoverage::Extra).into();
+                }
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            }
+            #[cfg(not(feature = "serde"))]
+            {
+                anyhow::bail!("JSON output requires the 'serde' feature to be enabled");
+            }
+        }
+        _ => {
+            println!("{}", style(format!("Tile Coverage: {map}")).bold().cyan());
+            println!();
+
+            // Column header with the tens and units digits of x
+            let width = if emoji { 2 } else { 1 };
+            for digits in [10, 1] {
+                let header: String = (0..64)
+                    .map(|x| format!("{:<width$}", (x / digits) % 10))
+                    .collect();
+                println!("    {header}");
+            }
+            for (y, row) in grid.iter().enumerate() {
+                let line: String = row.iter().map(|c| c.symbol(emoji)).collect();
+                println!("{y:>3} {line}");
+            }
+            println!();
+
+            if wdt.is_wmo_only() {
+                println!("Map is WMO-only (no terrain tiles)");
+            }
+            println!("Referenced tiles: {referenced}");
+            if on_disk.is_some() {
+                let legend =
+                    |state: TileCoverage| format!("{} {}", state.symbol(emoji), state.name());
+                println!(
+                    "  {}: {}",
+                    legend(TileCoverage::Present),
+                    count(TileCoverage::Present)
+                );
+                println!(
+                    "  {}: {}",
+                    legend(TileCoverage::Missing),
+                    style(count(TileCoverage::Missing)).red()
+                );
+                println!(
+                    "  {}: {}",
+                    legend(TileCoverage::Extra),
+                    style(count(TileCoverage::Extra)).yellow()
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
 fn print_flags(flags: &MphdFlags) {
     use console::style;
 
@@ -709,3 +931,22 @@ fn execute_tree(
     println!("{}", render_tree(&root, &options));
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_adt_tile_name() {
+        assert_eq!(
+            parse_adt_tile_name("Azeroth", "azeroth_32_48.adt"),
+            Some((32, 48))
+        );
+        assert_eq!(
+            parse_adt_tile_name("Azeroth", "Azeroth_32_48_tex0.adt"),
+            None
+        );
+        assert_eq!(parse_adt_tile_name("Azeroth", "Kalimdor_32_48.adt"), None);
+        assert_eq!(parse_adt_tile_name("Azeroth", "Azeroth_64_0.adt"), None);
+    }
+}
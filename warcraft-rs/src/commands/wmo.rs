@@ -1,11 +1,19 @@
 //! WMO world map object command implementations
 
+pub(crate) mod export;
+#[cfg(feature = "m2")]
+mod from_m2;
+mod portal_graph;
+mod validate;
+
+use crate::utils::scene::TextureMode;
 use crate::utils::tree::{NodeType, TreeNode, TreeOptions};
 use anyhow::{Context, Result};
 use clap::Subcommand;
+use export::{ExportOptions, WmoExportFormat};
 use std::fs::File;
 use std::io::{BufReader, BufWriter};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use wow_wmo::{
     WmoConverter, WmoParser, WmoVersion, WmoWriter, discover_wmo_chunks, parse_wmo_with_metadata,
 };
@@ -22,18 +30,38 @@ pub enum WmoCommands {
         detailed: bool,
     },
 
-    /// Validate a WMO file
+    /// Validate a WMO file, optionally together with all of its groups
+    ///
+    /// Exits with 2 when only warnings were found and 3 on errors.
     Validate {
-        /// Path to the WMO file
+        /// Path to the WMO file (a client path when reading from --source)
         file: String,
 
         /// Show warnings in addition to errors
         #[arg(short, long)]
         warnings: bool,
 
-        /// Show detailed validation report
+        /// Also list files without issues
         #[arg(short, long)]
         detailed: bool,
+
+        /// Load and validate every group file referenced by the root
+        #[arg(long)]
+        groups: bool,
+
+        /// MPQ archive, client data directory or extracted tree to load the
+        /// WMO from (defaults to the local file system)
+        #[arg(long, value_name = "MPQ_OR_DIR")]
+        source: Option<PathBuf>,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = validate::ReportFormat::Text)]
+        format: validate::ReportFormat,
+
+        /// Check chunk structure while streaming instead of parsing each
+        /// file fully; group references into the root are not checked
+        #[arg(long)]
+        stream: bool,
     },
 
     /// Convert WMO between different WoW versions
@@ -49,26 +77,72 @@ pub enum WmoCommands {
         version: String,
     },
 
-    /// Export WMO data
+    /// Export a WMO with its groups, doodads and textures into a folder
     Export {
-        /// Path to the WMO file
+        /// Path to the root WMO file (group files are loaded from the same directory)
         file: String,
 
-        /// Output format (obj, gltf, json)
-        #[arg(short, long, default_value = "obj")]
-        format: String,
+        /// Output format
+        #[arg(short, long, value_enum, default_value_t = WmoExportFormat::Obj)]
+        format: WmoExportFormat,
 
-        /// Output directory
+        /// Output directory (defaults to <name>_export next to the WMO)
         #[arg(short, long)]
         output: Option<String>,
 
-        /// Include textures
+        /// Doodad set to place in addition to the default set 0
+        #[arg(long, default_value_t = 0)]
+        doodad_set: usize,
+
+        /// How to handle textures
+        #[arg(long, value_enum, default_value_t = TextureMode::None)]
+        textures: TextureMode,
+
+        /// MPQ archive, client data directory or extracted tree to resolve
+        /// textures and doodad models from (defaults to the profile's data directory)
+        #[arg(long, value_name = "MPQ_OR_DIR")]
+        source: Option<PathBuf>,
+    },
+
+    /// Convert a static M2 model into a single-group WMO with collision
+    #[cfg(feature = "m2")]
+    FromM2 {
+        /// Path to the M2 file (its 00.skin is loaded from the same directory)
+        file: PathBuf,
+
+        /// Output root WMO (defaults to the model's name with a .wmo extension);
+        /// the group is written next to it as <name>_000.wmo
         #[arg(short, long)]
-        textures: bool,
+        output: Option<PathBuf>,
+
+        /// Mark the group as indoors instead of outdoors
+        #[arg(long)]
+        indoor: bool,
+
+        /// Do not generate collision
+        #[arg(long)]
+        no_collision: bool,
 
-        /// Include doodads
+        /// Convert models with animated bones in their rest pose
+        #[arg(long)]
+        rest_pose: bool,
+
+        /// Texture for submeshes without a hardcoded texture, e.g. a creature skin
+        #[arg(long)]
+        texture: Option<String>,
+    },
+
+    /// Export the group connectivity graph for pathfinding
+    ///
+    /// Groups are nodes and portals are edges carrying their polygons. Group
+    /// files next to the root are used to resolve portal ownership.
+    PortalGraph {
+        /// Path to the root WMO file
+        file: PathBuf,
+
+        /// Write the graph as JSON to this file
         #[arg(short, long)]
-        doodads: bool,
+        output: Option<PathBuf>,
     },
 
     /// List WMO components
@@ -133,14 +207,61 @@ pub fn execute(command: WmoCommands) -> Result<()> {
             file,
             warnings,
             detailed,
-        } => validate(&file, warnings, detailed),
+            groups,
+            source,
+            format,
+            stream,
+        } => validate::validate(validate::ValidateParams {
+            file: &file,
+            warnings,
+            detailed,
+            groups,
+            source: source.as_deref(),
+            format,
+            stream,
+        }),
         WmoCommands::Convert {
             input,
             output,
             version,
         } => convert(&input, &output, &version),
-        WmoCommands::Export { .. } => {
-            anyhow::bail!("WMO export functionality not yet implemented");
+        WmoCommands::Export {
+            file,
+            format,
+            output,
+            doodad_set,
+            textures,
+            source,
+        } => export_wmo(
+            &file,
+            output.as_deref(),
+            ExportOptions {
+                format,
+                doodad_set,
+                textures,
+                source: source.or_else(crate::utils::config::profile_data_dir),
+            },
+        ),
+        #[cfg(feature = "m2")]
+        WmoCommands::FromM2 {
+            file,
+            output,
+            indoor,
+            no_collision,
+            rest_pose,
+            texture,
+        } => from_m2::from_m2(
+            &file,
+            output.as_deref(),
+            from_m2::FromM2Options {
+                indoor,
+                no_collision,
+                rest_pose,
+                texture,
+            },
+        ),
+        WmoCommands::PortalGraph { file, output } => {
+            portal_graph::portal_graph(&file, output.as_deref())
         }
         WmoCommands::List { file, component } => list(&file, &component),
         WmoCommands::ExtractGroups { .. } => {
@@ -274,6 +395,12 @@ fn info(path: &str, detailed: bool) -> Result<()> {
                     group.texture_coords.len()
                 );
             }
+            if !group.texture_coords2.is_empty() {
+                println!(
+                    "  Second Texture Coordinates (MOTV): {} coords",
+                    group.texture_coords2.len()
+                );
+            }
             if !group.vertex_normals.is_empty() {
                 println!(
                     "  Vertex Normals (MONR): {} normals",
@@ -292,6 +419,12 @@ fn info(path: &str, detailed: bool) -> Result<()> {
                     group.vertex_colors.len()
                 );
             }
+            if !group.vertex_colors2.is_empty() {
+                println!(
+                    "  Second Vertex Colors (MOCV): {} colors",
+                    group.vertex_colors2.len()
+                );
+            }
             if group.liquid_header.is_some() {
                 println!("  Liquid Data (MLIQ): Present");
             }
@@ -419,32 +552,6 @@ fn info(path: &str, detailed: bool) -> Result<()> {
     Ok(())
 }
 
-fn validate(path: &str, _show_warnings: bool, _detailed: bool) -> Result<()> {
-    // TODO: Implement validation with new parser
-    let path = Path::new(path);
-
-    if !path.exists() {
-        anyhow::bail!("File not found: {}", path.display());
-    }
-
-    let file =
-        File::open(path).with_context(|| format!("Failed to open file: {}", path.display()))?;
-
-    let mut reader = BufReader::new(file);
-
-    // For now, just check if it parses successfully
-    match parse_wmo_with_metadata(&mut reader) {
-        Ok(_) => {
-            println!("✓ WMO file is valid (can be parsed)");
-            Ok(())
-        }
-        Err(e) => {
-            println!("✗ WMO file is invalid: {}", e);
-            std::process::exit(1);
-        }
-    }
-}
-
 fn convert(input_path: &str, output_path: &str, version_str: &str) -> Result<()> {
     println!("Loading WMO file: {}", input_path);
 
@@ -496,7 +603,7 @@ fn convert(input_path: &str, output_path: &str, version_str: &str) -> Result<()>
 
     // Convert the root file
     let converter = WmoConverter::new();
-    converter
+    let report = converter
         .convert_root(&mut root, target_version)
         .with_context(|| "Failed to convert WMO root")?;
 
@@ -513,11 +620,67 @@ fn convert(input_path: &str, output_path: &str, version_str: &str) -> Result<()>
     println!("  Groups: {}", root.groups.len());
     println!("  Materials: {}", root.materials.len());
     println!("  Doodad sets: {}", root.doodad_sets.len());
+    if report.changes.is_empty() {
+        println!("  Fidelity: nothing dropped or approximated");
+    } else {
+        println!(
+            "  Fidelity: {}",
+            if report.is_lossless() {
+                "lossless, defaults filled in"
+            } else {
+                "lossy, review before shipping"
+            }
+        );
+        for change in &report.changes {
+            println!("    {change}");
+        }
+    }
     println!("Output written to: {}", output_path);
 
     Ok(())
 }
 
+fn export_wmo(path: &str, output: Option<&str>, options: ExportOptions) -> Result<()> {
+    let input = Path::new(path);
+    if !input.exists() {
+        anyhow::bail!("File not found: {}", input.display());
+    }
+    let output_dir = match output {
+        Some(output) => PathBuf::from(output),
+        None => {
+            let stem = input.file_stem().and_then(|s| s.to_str()).unwrap_or("wmo");
+            input.with_file_name(format!("{stem}_export"))
+        }
+    };
+
+    println!("Exporting WMO: {}", input.display());
+    let stats = export::export(input, &output_dir, options)?;
+
+    println!("Wrote {}", stats.output.display());
+    println!("  Groups:    {}", stats.groups);
+    println!("  Triangles: {}", stats.triangles);
+    println!("  Doodads:   {}", stats.doodads_placed);
+    if stats.lights > 0 {
+        println!("  Lights:    {}", stats.lights);
+    }
+    if !stats.doodads_missing.is_empty() {
+        println!("  Missing doodad models: {}", stats.doodads_missing.len());
+        for name in &stats.doodads_missing {
+            println!("    {name}");
+        }
+    }
+    if stats.textures_written > 0 {
+        println!("  Textures:  {}", stats.textures_written);
+    }
+    if !stats.textures_missing.is_empty() {
+        println!("  Missing textures: {}", stats.textures_missing.len());
+        for name in &stats.textures_missing {
+            println!("    {name}");
+        }
+    }
+    Ok(())
+}
+
 fn list(_path: &str, _component: &str) -> Result<()> {
     // TODO: Update for new parser
     anyhow::bail!("List command needs updating for new parser");
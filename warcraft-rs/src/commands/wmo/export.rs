@@ -0,0 +1,413 @@
+//! Export WMO buildings, their groups and doodads to OBJ or glTF
+
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+use wow_mpq::path::normalize_asset_path;
+use wow_wmo::chunks::{MocvEntry, MomtEntry};
+use wow_wmo::group_parser::WmoGroup;
+use wow_wmo::root_parser::WmoRoot;
+use wow_wmo::{ParsedWmo, WmoLightType, parse_wmo};
+
+use crate::utils::assets::AssetSource;
+use crate::utils::scene::{
+    Instance, Light, LightKind, Mesh, MeshPart, ResolvedMaterial, Scene, TextureMode,
+    TextureWriter, rotate, write_gltf, write_obj,
+};
+
+/// Material flag: render both faces (F_UNCULLED)
+const MATERIAL_UNCULLED: u32 = 0x04;
+
+/// Output format of `wmo export`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum WmoExportFormat {
+    /// Wavefront OBJ with an MTL material library
+    Obj,
+    /// glTF 2.0
+    Gltf,
+}
+
+pub struct ExportOptions {
+    pub format: WmoExportFormat,
+    /// Doodad set placed in addition to the default set 0
+    pub doodad_set: usize,
+    pub textures: TextureMode,
+    /// MPQ archive, client installation or extracted tree for textures and doodads
+    pub source: Option<PathBuf>,
+}
+
+#[derive(Debug, Default)]
+pub struct ExportStats {
+    pub groups: usize,
+    pub triangles: usize,
+    pub doodads_placed: usize,
+    /// Lights exported (glTF only)
+    pub lights: usize,
+    pub doodads_missing: Vec<String>,
+    pub textures_written: usize,
+    pub textures_missing: Vec<String>,
+    pub output: PathBuf,
+}
+
+pub(super) fn load_root(path: &Path) -> Result<WmoRoot> {
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    match parse_wmo(&mut BufReader::new(file))
+        .with_context(|| format!("Failed to parse {}", path.display()))?
+    {
+        ParsedWmo::Root(root) => Ok(root),
+        ParsedWmo::Group(_) => anyhow::bail!(
+            "{} is a group file; use the root WMO instead",
+            path.display()
+        ),
+    }
+}
+
+pub(super) fn load_group(path: &Path) -> Result<WmoGroup> {
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    match parse_wmo(&mut BufReader::new(file))
+        .with_context(|| format!("Failed to parse {}", path.display()))?
+    {
+        ParsedWmo::Group(group) => Ok(group),
+        ParsedWmo::Root(_) => anyhow::bail!("{} is not a group file", path.display()),
+    }
+}
+
+fn texture_name(root: &WmoRoot, offset: u32) -> Option<String> {
+    root.texture_offset_index_map
+        .get(&offset)
+        .and_then(|&i| root.textures.get(i as usize))
+        .cloned()
+}
+
+/// Resolve a MOMT entry; textures 2 and 3 are only set by multi-texture shaders
+fn material(root: &WmoRoot, entry: Option<&MomtEntry>) -> ResolvedMaterial {
+    let Some(entry) = entry else {
+        return ResolvedMaterial::default();
+    };
+    let textures = [entry.texture_1, entry.texture_2, entry.texture_3]
+        .into_iter()
+        .enumerate()
+        .filter(|&(i, offset)| i == 0 || offset != 0)
+        .map_while(|(_, offset)| texture_name(root, offset))
+        .collect();
+    ResolvedMaterial::from_gx_blend(
+        entry.blend_mode,
+        textures,
+        entry.flags & MATERIAL_UNCULLED != 0,
+    )
+}
+
+fn group_mesh(root: &WmoRoot, group: &WmoGroup, name: String) -> Mesh {
+    let vertex_count = group.vertex_positions.len();
+    let parts = group
+        .render_batches
+        .iter()
+        .filter_map(|batch| {
+            let start = batch.start_index as usize;
+            let end = (start + batch.count as usize).min(group.vertex_indices.len());
+            let indices: Vec<u32> = group
+                .vertex_indices
+                .get(start..end)?
+                .iter()
+                .map(|&i| u32::from(i))
+                .filter(|&i| (i as usize) < vertex_count)
+                .collect();
+            (indices.len() >= 3 && indices.len().is_multiple_of(3)).then(|| MeshPart {
+                indices,
+                material: material(root, root.materials.get(batch.material_id as usize)),
+            })
+        })
+        .collect();
+
+    Mesh {
+        name,
+        positions: group
+            .vertex_positions
+            .iter()
+            .map(|v| [v.x, v.y, v.z])
+            .collect(),
+        normals: group
+            .vertex_normals
+            .iter()
+            .map(|n| [n.x, n.y, n.z])
+            .collect(),
+        uvs: group.texture_coords.iter().map(|t| [t.u, t.v]).collect(),
+        uvs2: group.texture_coords2.iter().map(|t| [t.u, t.v]).collect(),
+        colors: group.vertex_colors.iter().map(rgba).collect(),
+        colors2: group.vertex_colors2.iter().map(rgba).collect(),
+        parts,
+    }
+}
+
+/// MOCV colors are stored as BGRA
+fn rgba(color: &MocvEntry) -> [u8; 4] {
+    [color.r, color.g, color.b, color.a]
+}
+
+#[cfg(feature = "m2")]
+fn load_doodad(source: &mut AssetSource, model_path: &str) -> Result<Option<Mesh>> {
+    let Some(data) = source.read(model_path)? else {
+        return Ok(None);
+    };
+    let stem = model_path
+        .rsplit_once('.')
+        .map_or(model_path, |(stem, _)| stem);
+    let skin = source.read(&format!("{stem}00.skin"))?;
+    let display = model_path
+        .rsplit(['\\', '/'])
+        .next()
+        .unwrap_or(model_path)
+        .to_string();
+    crate::commands::m2::static_mesh(&display, &data, skin.as_deref()).map(Some)
+}
+
+#[cfg(not(feature = "m2"))]
+fn load_doodad(_source: &mut AssetSource, _model_path: &str) -> Result<Option<Mesh>> {
+    anyhow::bail!("doodad export requires the 'm2' feature to be enabled")
+}
+
+/// Place the doodads of set 0 and the selected set
+fn add_doodads(
+    root: &WmoRoot,
+    set: usize,
+    scene: &mut Scene,
+    source: &mut AssetSource,
+    stats: &mut ExportStats,
+) -> Result<()> {
+    let Some(selected) = root.doodad_sets.get(set) else {
+        anyhow::bail!(
+            "Doodad set {set} does not exist (the WMO has {} sets)",
+            root.doodad_sets.len()
+        );
+    };
+
+    let mut ranges = vec![selected];
+    if set != 0 {
+        ranges.extend(root.doodad_sets.first());
+    }
+
+    let mut meshes: HashMap<String, Option<usize>> = HashMap::new();
+    for range in ranges {
+        let start = range.start_index as usize;
+        let end = (start + range.count as usize).min(root.doodad_defs.len());
+        for (index, def) in root.doodad_defs[start.min(end)..end].iter().enumerate() {
+            let Some(name) = def.get_name(&root.doodad_names, &root.doodad_name_offset_index_map)
+            else {
+                continue;
+            };
+
+            let key = name.to_lowercase();
+            let mesh = match meshes.get(&key) {
+                Some(&mesh) => mesh,
+                None => {
+                    let mesh = match load_doodad(source, &normalize_asset_path(name)) {
+                        Ok(Some(mesh)) => {
+                            scene.meshes.push(mesh);
+                            Some(scene.meshes.len() - 1)
+                        }
+                        Ok(None) => {
+                            stats.doodads_missing.push(name.to_string());
+                            None
+                        }
+                        Err(e) => {
+                            log::warn!("Failed to load doodad {name}: {e:#}");
+                            stats.doodads_missing.push(name.to_string());
+                            None
+                        }
+                    };
+                    meshes.insert(key, mesh);
+                    mesh
+                }
+            };
+
+            if let Some(mesh) = mesh {
+                scene.instances.push(Instance {
+                    name: format!("Doodad_{:04}_{}", start + index, scene.meshes[mesh].name),
+                    mesh,
+                    translation: def.position,
+                    rotation: def.orientation,
+                    scale: def.scale,
+                });
+                stats.doodads_placed += 1;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// RGB in 0..1 from a BGRA color
+fn light_color([b, g, r, _]: [u8; 4]) -> [f32; 3] {
+    [r, g, b].map(|c| f32::from(c) / 255.0)
+}
+
+/// Root lights (MOLT); ambient lights have no glTF equivalent and are skipped
+fn root_lights(root: &WmoRoot) -> Vec<Light> {
+    root.lights
+        .iter()
+        .enumerate()
+        .filter_map(|(i, light)| {
+            let kind = match light.light_type() {
+                WmoLightType::Omni => LightKind::Point,
+                // MOLT has no cone angles; use the glTF defaults
+                WmoLightType::Spot => LightKind::Spot {
+                    inner_cone: 0.0,
+                    outer_cone: std::f32::consts::FRAC_PI_4,
+                },
+                WmoLightType::Directional => LightKind::Directional,
+                WmoLightType::Ambient => return None,
+            };
+            // Unset rotations are all zero; such lights point down
+            let down = [0.0, 0.0, -1.0];
+            let direction = if light.rotation.iter().any(|&r| r != 0.0) {
+                rotate(light.rotation, down)
+            } else {
+                down
+            };
+            Some(Light {
+                name: format!("Light_{i:03}"),
+                kind,
+                color: light_color(light.color),
+                intensity: light.intensity,
+                range: light.uses_attenuation().then_some(light.attenuation_end),
+                position: light.position,
+                direction,
+            })
+        })
+        .collect()
+}
+
+/// Point (MOLP) and spot (MOLS) lights of a group
+fn group_lights(group: &WmoGroup, group_index: usize) -> Vec<Light> {
+    let points = group
+        .point_lights
+        .iter()
+        .enumerate()
+        .map(|(i, light)| Light {
+            name: format!("Group_{group_index:03}_PointLight_{i:03}"),
+            kind: LightKind::Point,
+            color: light_color(light.color),
+            intensity: light.intensity,
+            range: Some(light.attenuation_end),
+            position: light.position,
+            direction: [0.0, 0.0, -1.0],
+        });
+    let spots = group
+        .spot_lights
+        .iter()
+        .enumerate()
+        .map(|(i, light)| Light {
+            name: format!("Group_{group_index:03}_SpotLight_{i:03}"),
+            kind: LightKind::Spot {
+                inner_cone: light.inner_angle.max(0.0),
+                outer_cone: light.outer_angle.max(light.inner_angle),
+            },
+            color: light_color(light.color),
+            intensity: light.intensity,
+            range: Some(light.attenuation_end),
+            position: light.position,
+            direction: light.direction,
+        });
+    points.chain(spots).collect()
+}
+
+/// Export a root WMO with all of its groups into `output_dir`
+pub fn export(input: &Path, output_dir: &Path, options: ExportOptions) -> Result<ExportStats> {
+    let root = load_root(input)?;
+    let stem = input
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .context("Invalid WMO file name")?
+        .to_string();
+
+    let mut scene = Scene {
+        name: stem.clone(),
+        ..Default::default()
+    };
+    let mut stats = ExportStats::default();
+
+    for index in 0..root.n_groups as usize {
+        let group_path = input.with_file_name(format!("{stem}_{index:03}.wmo"));
+        if !group_path.exists() {
+            log::warn!("Group file not found: {}", group_path.display());
+            continue;
+        }
+        let group = load_group(&group_path)?;
+        scene.lights.extend(group_lights(&group, index));
+        let name = format!("Group_{index:03}");
+        let mesh = group_mesh(&root, &group, name.clone());
+        stats.triangles += mesh.triangle_count();
+        scene.meshes.push(mesh);
+        scene
+            .instances
+            .push(Instance::identity(&name, scene.meshes.len() - 1));
+        stats.groups += 1;
+    }
+    if stats.groups == 0 && root.n_groups > 0 {
+        anyhow::bail!(
+            "None of the {} group files were found next to {}",
+            root.n_groups,
+            input.display()
+        );
+    }
+
+    scene.lights.extend(root_lights(&root));
+    if options.format == WmoExportFormat::Gltf {
+        stats.lights = scene.lights.len();
+    }
+
+    let mut source = options
+        .source
+        .as_deref()
+        .map(AssetSource::open)
+        .transpose()?;
+
+    if let Some(source) = source.as_mut() {
+        if !root.doodad_sets.is_empty() {
+            add_doodads(&root, options.doodad_set, &mut scene, source, &mut stats)?;
+        }
+    } else if !root.doodad_defs.is_empty() {
+        log::info!(
+            "No --source given; skipping {} doodads",
+            root.doodad_defs.len()
+        );
+    }
+
+    let textures = match (options.textures, options.format) {
+        (TextureMode::Embed, WmoExportFormat::Obj) => {
+            log::warn!("OBJ cannot embed textures; extracting them instead");
+            TextureMode::Extract
+        }
+        (mode, _) => mode,
+    };
+    if textures != TextureMode::None && source.is_none() {
+        anyhow::bail!("--textures {textures:?} requires --source to locate the BLP files");
+    }
+
+    fs::create_dir_all(output_dir)
+        .with_context(|| format!("Failed to create {}", output_dir.display()))?;
+    let mut writer = TextureWriter::new(textures, source, output_dir);
+
+    stats.output = match options.format {
+        WmoExportFormat::Obj => {
+            let path = output_dir.join(format!("{stem}.obj"));
+            write_obj(&scene, &path, &mut writer)?;
+            path
+        }
+        WmoExportFormat::Gltf => {
+            let extension = if textures == TextureMode::Embed {
+                "glb"
+            } else {
+                "gltf"
+            };
+            let path = output_dir.join(format!("{stem}.{extension}"));
+            write_gltf(&scene, &path, &mut writer)?;
+            path
+        }
+    };
+    stats.textures_written = writer.written;
+    stats.textures_missing = writer.missing;
+    Ok(stats)
+}
@@ -0,0 +1,174 @@
+//! Convert static M2 models into single-group WMOs
+//!
+//! Large props are often placed as doodads, but doodads have no BSP tree,
+//! no area name and are culled with whatever they are placed in. Turning the
+//! model into a world object of its own lets it be placed with MODF like any
+//! other building.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use wow_m2::parse_m2;
+use wow_wmo::{StaticMaterial, StaticWmoBuilder, TexCoord, Vec3, WmoMaterialFlags};
+
+use crate::utils::scene::ResolvedMaterial;
+
+pub struct FromM2Options {
+    /// Mark the group as indoors
+    pub indoor: bool,
+    /// Generate no collision at all
+    pub no_collision: bool,
+    /// Convert models with animated bones in their rest pose
+    pub rest_pose: bool,
+    /// Texture for submeshes without a hardcoded texture
+    pub texture: Option<String>,
+}
+
+/// Group file belonging to a root WMO, `<name>_000.wmo`
+fn group_path(root: &Path) -> Result<PathBuf> {
+    let stem = root
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .context("Invalid output file name")?;
+    Ok(root.with_file_name(format!("{stem}_000.wmo")))
+}
+
+fn wmo_material(material: &ResolvedMaterial, fallback: Option<&str>) -> Result<StaticMaterial> {
+    // Replaceable textures are chosen per creature or item and have no
+    // equivalent in a WMO
+    let texture = material
+        .diffuse()
+        .or(fallback)
+        .context("Model has a submesh without a hardcoded texture; pass --texture to use one")?;
+    let mut wmo_material = StaticMaterial::new(texture);
+    wmo_material.blend_mode = material.gx_blend();
+    if material.two_sided {
+        wmo_material.flags |= WmoMaterialFlags::TWO_SIDED;
+    }
+    Ok(wmo_material)
+}
+
+fn vec3([x, y, z]: [f32; 3]) -> Vec3 {
+    Vec3 { x, y, z }
+}
+
+fn read_vec3(bytes: &[u8]) -> Vec3 {
+    vec3(
+        [0, 4, 8].map(|o| f32::from_le_bytes([bytes[o], bytes[o + 1], bytes[o + 2], bytes[o + 3]])),
+    )
+}
+
+pub fn from_m2(file: &Path, output: Option<&Path>, options: FromM2Options) -> Result<()> {
+    let data = fs::read(file).with_context(|| format!("Failed to read {}", file.display()))?;
+    let format = parse_m2(&mut Cursor::new(&data))
+        .with_context(|| format!("Failed to parse M2 model {}", file.display()))?;
+    let model = format.model();
+
+    let animated = model
+        .bones
+        .iter()
+        .filter(|bone| {
+            bone.translation.has_data() || bone.rotation.has_data() || bone.scale.has_data()
+        })
+        .count();
+    if animated > 0 && !options.rest_pose {
+        anyhow::bail!(
+            "{} has {animated} animated bones; pass --rest-pose to convert it in its rest pose",
+            file.display()
+        );
+    }
+
+    let stem = file
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .context("Invalid model file name")?;
+    let skin = fs::read(file.with_file_name(format!("{stem}00.skin"))).ok();
+    let name = model.name.clone().unwrap_or_else(|| stem.to_string());
+    let mesh = crate::commands::m2::static_mesh(&name, &data, skin.as_deref())?;
+
+    let mut builder = StaticWmoBuilder::new(name.as_str()).vertices(
+        mesh.positions.iter().copied().map(vec3).collect(),
+        mesh.normals.iter().copied().map(vec3).collect(),
+        mesh.uvs.iter().map(|&[u, v]| TexCoord { u, v }).collect(),
+    );
+    let mut materials: Vec<&ResolvedMaterial> = Vec::new();
+    for part in &mesh.parts {
+        let material = match materials.iter().position(|&m| *m == part.material) {
+            Some(index) => index,
+            None => {
+                builder =
+                    builder.material(wmo_material(&part.material, options.texture.as_deref())?);
+                materials.push(&part.material);
+                materials.len() - 1
+            }
+        };
+        let material = u8::try_from(material).context("Model has too many materials")?;
+        let indices = part
+            .indices
+            .iter()
+            .map(|&index| u16::try_from(index))
+            .collect::<Result<Vec<u16>, _>>()
+            .context("Model has too many vertices for a WMO group")?;
+        builder = builder.batch(material, indices);
+    }
+
+    // The model's own collision mesh replaces the render triangles
+    let collision_positions: Vec<Vec3> = model
+        .raw_data
+        .bounding_vertices
+        .chunks_exact(12)
+        .map(read_vec3)
+        .collect();
+    let collision_indices: Vec<u16> = model
+        .raw_data
+        .bounding_triangles
+        .chunks_exact(2)
+        .map(|i| u16::from_le_bytes([i[0], i[1]]))
+        .collect();
+    let collision = if options.no_collision {
+        builder = builder.render_collision(false);
+        "none"
+    } else if !collision_indices.is_empty() {
+        builder = builder
+            .collision(collision_positions, collision_indices)
+            .render_collision(false);
+        "model collision mesh"
+    } else {
+        "render triangles"
+    };
+    builder = builder.indoor(options.indoor);
+
+    let root_path = output.map_or_else(|| file.with_extension("wmo"), Path::to_path_buf);
+    let group_path = group_path(&root_path)?;
+    let mut root = Vec::new();
+    let mut group = Vec::new();
+    builder
+        .write_root(&mut root)
+        .and_then(|()| builder.write_group(&mut group))
+        .with_context(|| format!("Failed to convert {}", file.display()))?;
+    fs::write(&root_path, root)
+        .with_context(|| format!("Failed to write {}", root_path.display()))?;
+    fs::write(&group_path, group)
+        .with_context(|| format!("Failed to write {}", group_path.display()))?;
+
+    let bounding_box = builder.bounding_box();
+    println!("Converted {} to a WMO", file.display());
+    println!("  Vertices: {}", mesh.positions.len());
+    println!("  Triangles: {}", builder.triangle_count());
+    println!("  Batches: {}", mesh.parts.len());
+    println!("  Materials: {}", materials.len());
+    println!("  Collision: {collision}");
+    println!(
+        "  Bounds: ({:.2}, {:.2}, {:.2}) - ({:.2}, {:.2}, {:.2})",
+        bounding_box.min.x,
+        bounding_box.min.y,
+        bounding_box.min.z,
+        bounding_box.max.x,
+        bounding_box.max.y,
+        bounding_box.max.z
+    );
+    println!("Root: {}", root_path.display());
+    println!("Group: {}", group_path.display());
+    Ok(())
+}
@@ -0,0 +1,85 @@
+//! Group connectivity graph export
+
+use anyhow::{Context, Result};
+use std::path::Path;
+use wow_wmo::PortalGraph;
+
+use super::export::{load_group, load_root};
+use crate::utils::{add_table_row, create_table};
+
+/// Print the graph of a root WMO and optionally write it as JSON
+pub fn portal_graph(input: &Path, output: Option<&Path>) -> Result<()> {
+    let root = load_root(input)?;
+    let stem = input
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .context("Invalid file name")?;
+
+    // Portal ranges of the group headers, when every group file is present
+    let mut groups = Vec::new();
+    for index in 0..root.n_groups as usize {
+        let path = input.with_file_name(format!("{stem}_{index:03}.wmo"));
+        if !path.exists() {
+            log::warn!(
+                "Group file not found: {}; pairing portal references instead",
+                path.display()
+            );
+            groups.clear();
+            break;
+        }
+        groups.push(load_group(&path)?);
+    }
+    let graph = if groups.is_empty() {
+        PortalGraph::from_root(&root)
+    } else {
+        PortalGraph::from_root_and_groups(&root, &groups)
+    };
+
+    println!(
+        "Portal Graph: {} ({} groups, {} portals)",
+        input.display(),
+        graph.groups.len(),
+        graph.portals.len()
+    );
+    let mut table = create_table(vec!["Group", "Location", "Portals", "Leads To"]);
+    for group in &graph.groups {
+        let location = match (group.exterior, group.indoor) {
+            (true, _) => "Exterior",
+            (false, true) => "Interior",
+            (false, false) => "-",
+        };
+        let edges: Vec<_> = graph.neighbors(group.index).collect();
+        let targets: Vec<String> = edges
+            .iter()
+            .map(|edge| format!("{} (#{})", edge.to_group, edge.portal))
+            .collect();
+        add_table_row(
+            &mut table,
+            vec![
+                group.index.to_string(),
+                location.to_string(),
+                edges.len().to_string(),
+                targets.join(", "),
+            ],
+        );
+    }
+    println!("{table}");
+    if !graph.unresolved_portals.is_empty() {
+        let portals: Vec<String> = graph
+            .unresolved_portals
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        println!(
+            "⚠ Portals with references of unknown groups: {}",
+            portals.join(", ")
+        );
+    }
+
+    if let Some(output) = output {
+        std::fs::write(output, serde_json::to_string_pretty(&graph)?)
+            .with_context(|| format!("Failed to write {}", output.display()))?;
+        println!("✓ Saved {}", output.display());
+    }
+    Ok(())
+}
@@ -0,0 +1,455 @@
+//! Validation of a WMO root and, optionally, all of its group files
+//!
+//! Every file gets its own validator report; the command combines them into
+//! one result whose exit code reflects the worst severity found, so content QA
+//! pipelines can fail on errors and still tell them apart from warnings.
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+use wow_wmo::chunk_discovery::discover_chunks;
+use wow_wmo::root_parser::WmoRoot;
+use wow_wmo::{ChunkId, ParsedWmo, ValidationReport, WmoParser, WmoValidator, parse_wmo};
+
+use crate::utils::assets::{AssetSource, find_case_insensitive};
+
+/// Exit code when only warnings were found
+pub const EXIT_WARNINGS: i32 = 2;
+/// Exit code when at least one error was found
+pub const EXIT_ERRORS: i32 = 3;
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReportFormat {
+    Text,
+    Json,
+}
+
+pub struct ValidateParams<'a> {
+    pub file: &'a str,
+    /// Show warnings in the text output
+    pub warnings: bool,
+    /// List files without issues as well
+    pub detailed: bool,
+    /// Validate every group referenced by the root
+    pub groups: bool,
+    /// Archive, client data directory or extracted tree to load files from
+    pub source: Option<&'a Path>,
+    pub format: ReportFormat,
+    /// Check chunk structure without building the model
+    pub stream: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Severity {
+    Ok,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::Warning => "warning",
+            Self::Error => "error",
+        }
+    }
+
+    fn exit_code(self) -> i32 {
+        match self {
+            Self::Ok => 0,
+            Self::Warning => EXIT_WARNINGS,
+            Self::Error => EXIT_ERRORS,
+        }
+    }
+}
+
+/// Validation result of one file
+struct FileResult {
+    name: String,
+    /// Group index, `None` for the root
+    group: Option<usize>,
+    /// Set when the file could not be found or parsed
+    load_error: Option<String>,
+    errors: Vec<String>,
+    warnings: Vec<String>,
+}
+
+impl FileResult {
+    fn new(name: String, group: Option<usize>) -> Self {
+        Self {
+            name,
+            group,
+            load_error: None,
+            errors: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    fn failed(name: String, group: Option<usize>, error: String) -> Self {
+        Self {
+            load_error: Some(error),
+            ..Self::new(name, group)
+        }
+    }
+
+    fn add_report(&mut self, report: &ValidationReport) {
+        self.errors
+            .extend(report.errors.iter().map(ToString::to_string));
+        self.warnings
+            .extend(report.warnings.iter().map(ToString::to_string));
+    }
+
+    fn severity(&self) -> Severity {
+        if self.load_error.is_some() || !self.errors.is_empty() {
+            Severity::Error
+        } else if !self.warnings.is_empty() {
+            Severity::Warning
+        } else {
+            Severity::Ok
+        }
+    }
+}
+
+/// Combined result of all validated files
+struct Summary {
+    root: String,
+    files: Vec<FileResult>,
+}
+
+impl Summary {
+    fn severity(&self) -> Severity {
+        self.files
+            .iter()
+            .map(FileResult::severity)
+            .max()
+            .unwrap_or(Severity::Ok)
+    }
+
+    fn error_count(&self) -> usize {
+        self.files
+            .iter()
+            .map(|f| f.errors.len() + usize::from(f.load_error.is_some()))
+            .sum()
+    }
+
+    fn warning_count(&self) -> usize {
+        self.files.iter().map(|f| f.warnings.len()).sum()
+    }
+}
+
+/// Reads the root and its groups from disk or from a `--source`
+struct Loader {
+    source: Option<AssetSource>,
+}
+
+impl Loader {
+    fn read_root(&mut self, file: &str) -> Result<Vec<u8>> {
+        let path = Path::new(file);
+        if path.is_file() {
+            return fs::read(path).with_context(|| format!("Failed to read {file}"));
+        }
+        match self.source.as_mut() {
+            Some(source) => source
+                .read(file)?
+                .with_context(|| format!("{file} not found in the source")),
+            None => anyhow::bail!("File not found: {file}"),
+        }
+    }
+
+    /// Read group `index`; `None` if the file does not exist
+    fn read_group(&mut self, file: &str, index: usize) -> Result<Option<Vec<u8>>> {
+        let name = group_name(file, index);
+        match self.source.as_mut() {
+            Some(source) => source.read(&name),
+            None => {
+                let path = Path::new(&name);
+                let dir = path.parent().unwrap_or(Path::new("."));
+                let dir = if dir.as_os_str().is_empty() {
+                    Path::new(".")
+                } else {
+                    dir
+                };
+                let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or(&name);
+                find_case_insensitive(dir, file_name)
+                    .map(|path| {
+                        fs::read(&path)
+                            .with_context(|| format!("Failed to read {}", path.display()))
+                    })
+                    .transpose()
+            }
+        }
+    }
+}
+
+/// `World\wmo\Foo.wmo` -> `World\wmo\Foo_003.wmo`
+fn group_name(root: &str, index: usize) -> String {
+    let stem = match root.rsplit_once('.') {
+        Some((stem, ext)) if ext.eq_ignore_ascii_case("wmo") => stem,
+        _ => root,
+    };
+    format!("{stem}_{index:03}.wmo")
+}
+
+fn validate_root(data: &[u8], result: &mut FileResult) -> Option<WmoRoot> {
+    // The validator checks the header against the full root structure
+    match WmoParser::new().parse_root(&mut Cursor::new(data)) {
+        Ok(root) => match WmoValidator::new().validate_root(&root) {
+            Ok(report) => result.add_report(&report),
+            Err(e) => result.errors.push(format!("Validator failed: {e}")),
+        },
+        Err(e) => result.load_error = Some(format!("Failed to parse root: {e}")),
+    }
+
+    match parse_wmo(&mut Cursor::new(data)) {
+        Ok(ParsedWmo::Root(root)) => Some(root),
+        Ok(ParsedWmo::Group(_)) => None,
+        Err(e) => {
+            result
+                .load_error
+                .get_or_insert_with(|| format!("Failed to parse root: {e}"));
+            None
+        }
+    }
+}
+
+fn validate_group(data: &[u8], root: Option<&WmoRoot>, result: &mut FileResult) {
+    match parse_wmo(&mut Cursor::new(data)) {
+        Ok(ParsedWmo::Group(group)) => {
+            match WmoValidator::new().validate_parsed_group(&group, root) {
+                Ok(report) => result.add_report(&report),
+                Err(e) => result.errors.push(format!("Validator failed: {e}")),
+            }
+        }
+        Ok(ParsedWmo::Root(_)) => {
+            result.load_error = Some("Expected a group file but found a root file".to_string())
+        }
+        Err(e) => result.load_error = Some(format!("Failed to parse group: {e}")),
+    }
+}
+
+fn validate_stream(data: &[u8], result: &mut FileResult) {
+    match WmoValidator::new().validate_stream(&mut Cursor::new(data)) {
+        Ok(report) => result.add_report(&report),
+        Err(e) => result.load_error = Some(format!("Failed to read file: {e}")),
+    }
+}
+
+/// Group count from the root header, read without parsing the root
+fn stream_group_count(data: &[u8]) -> Option<usize> {
+    let discovery = discover_chunks(&mut Cursor::new(data)).ok()?;
+    let mohd = discovery
+        .chunks
+        .iter()
+        .find(|chunk| chunk.id == ChunkId::from_bytes(*b"MOHD"))?;
+    let start = mohd.offset as usize + 12;
+    let count = data.get(start..start + 4)?;
+    Some(u32::from_le_bytes(count.try_into().ok()?) as usize)
+}
+
+/// Validate the file and its groups chunk by chunk, without cross-references
+fn run_stream(params: &ValidateParams, loader: &mut Loader, data: &[u8]) -> Result<Summary> {
+    let mut root_result = FileResult::new(params.file.to_string(), None);
+    validate_stream(data, &mut root_result);
+    let group_count = params.groups.then(|| stream_group_count(data)).flatten();
+    let mut summary = Summary {
+        root: params.file.to_string(),
+        files: vec![root_result],
+    };
+
+    for index in 0..group_count.unwrap_or(0) {
+        let name = group_name(params.file, index);
+        let result = match loader.read_group(params.file, index) {
+            Ok(Some(data)) => {
+                let mut result = FileResult::new(name, Some(index));
+                validate_stream(&data, &mut result);
+                result
+            }
+            Ok(None) => FileResult::failed(name, Some(index), "File not found".to_string()),
+            Err(e) => FileResult::failed(name, Some(index), format!("{e:#}")),
+        };
+        summary.files.push(result);
+    }
+    Ok(summary)
+}
+
+fn run(params: &ValidateParams) -> Result<Summary> {
+    let mut loader = Loader {
+        source: params.source.map(AssetSource::open).transpose()?,
+    };
+    let data = loader.read_root(params.file)?;
+    if params.stream {
+        return run_stream(params, &mut loader, &data);
+    }
+    let mut summary = Summary {
+        root: params.file.to_string(),
+        files: Vec::new(),
+    };
+
+    // A group file on its own is validated without cross-references
+    if let Ok(ParsedWmo::Group(_)) = parse_wmo(&mut Cursor::new(&data)) {
+        if params.groups {
+            anyhow::bail!("{} is a group file; pass the root WMO", params.file);
+        }
+        let mut result = FileResult::new(params.file.to_string(), None);
+        validate_group(&data, None, &mut result);
+        summary.files.push(result);
+        return Ok(summary);
+    }
+
+    let mut root_result = FileResult::new(params.file.to_string(), None);
+    let root = validate_root(&data, &mut root_result);
+    summary.files.push(root_result);
+
+    if params.groups {
+        let Some(root) = root else {
+            return Ok(summary);
+        };
+        for index in 0..root.n_groups as usize {
+            let name = group_name(params.file, index);
+            let result = match loader.read_group(params.file, index) {
+                Ok(Some(data)) => {
+                    let mut result = FileResult::new(name, Some(index));
+                    validate_group(&data, Some(&root), &mut result);
+                    result
+                }
+                Ok(None) => FileResult::failed(name, Some(index), "File not found".to_string()),
+                Err(e) => FileResult::failed(name, Some(index), format!("{e:#}")),
+            };
+            summary.files.push(result);
+        }
+    }
+
+    Ok(summary)
+}
+
+pub fn validate(params: ValidateParams) -> Result<()> {
+    let summary = run(&params)?;
+    match params.format {
+        ReportFormat::Text => print_text(&summary, &params),
+        ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&to_json(&summary))?),
+    }
+
+    let code = summary.severity().exit_code();
+    if code != 0 {
+        std::process::exit(code);
+    }
+    Ok(())
+}
+
+fn print_text(summary: &Summary, params: &ValidateParams) {
+    let groups = summary.files.iter().filter(|f| f.group.is_some()).count();
+    if params.groups {
+        println!("Validating {} ({groups} group(s))", summary.root);
+    } else {
+        println!("Validating {}", summary.root);
+    }
+
+    for file in &summary.files {
+        let severity = file.severity();
+        if severity == Severity::Ok && !params.detailed && summary.files.len() > 1 {
+            continue;
+        }
+        let mark = match severity {
+            Severity::Ok => "✓",
+            Severity::Warning => "⚠",
+            Severity::Error => "✗",
+        };
+        match &file.load_error {
+            Some(error) => println!("{mark} {}: {error}", file.name),
+            None => println!(
+                "{mark} {}: {} error(s), {} warning(s)",
+                file.name,
+                file.errors.len(),
+                file.warnings.len()
+            ),
+        }
+        for error in &file.errors {
+            println!("    error: {error}");
+        }
+        if params.warnings {
+            for warning in &file.warnings {
+                println!("    warning: {warning}");
+            }
+        }
+    }
+
+    println!();
+    println!(
+        "{} file(s) checked: {} error(s), {} warning(s)",
+        summary.files.len(),
+        summary.error_count(),
+        summary.warning_count()
+    );
+    match summary.severity() {
+        Severity::Ok => println!("✓ WMO is valid"),
+        Severity::Warning if !params.warnings => {
+            println!("⚠ WMO has warnings (use --warnings to show them)")
+        }
+        Severity::Warning => println!("⚠ WMO has warnings"),
+        Severity::Error => println!("✗ WMO is invalid"),
+    }
+}
+
+fn to_json(summary: &Summary) -> serde_json::Value {
+    let files: Vec<_> = summary
+        .files
+        .iter()
+        .map(|file| {
+            serde_json::json!({
+                "file": file.name,
+                "group": file.group,
+                "severity": file.severity().as_str(),
+                "load_error": file.load_error,
+                "errors": file.errors,
+                "warnings": file.warnings,
+            })
+        })
+        .collect();
+    let severity = summary.severity();
+    serde_json::json!({
+        "root": summary.root,
+        "severity": severity.as_str(),
+        "exit_code": severity.exit_code(),
+        "counts": {
+            "files": summary.files.len(),
+            "groups": summary.files.iter().filter(|f| f.group.is_some()).count(),
+            "missing": summary.files.iter().filter(|f| f.load_error.is_some()).count(),
+            "errors": summary.error_count(),
+            "warnings": summary.warning_count(),
+        },
+        "files": files,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_group_name() {
+        assert_eq!(
+            group_name("World\\wmo\\Test\\House.wmo", 3),
+            "World\\wmo\\Test\\House_003.wmo"
+        );
+        assert_eq!(group_name("house.WMO", 12), "house_012.wmo");
+    }
+
+    #[test]
+    fn test_worst_severity_wins() {
+        let mut warning = FileResult::new("a.wmo".into(), None);
+        warning.warnings.push("odd".into());
+        let missing = FileResult::failed("a_000.wmo".into(), Some(0), "File not found".into());
+        let mut summary = Summary {
+            root: "a.wmo".into(),
+            files: vec![warning],
+        };
+        assert_eq!(summary.severity().exit_code(), EXIT_WARNINGS);
+        summary.files.push(missing);
+        assert_eq!(summary.severity().exit_code(), EXIT_ERRORS);
+        assert_eq!(summary.error_count(), 1);
+        assert_eq!(summary.warning_count(), 1);
+    }
+}
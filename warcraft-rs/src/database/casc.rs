@@ -0,0 +1,262 @@
+//! CASC root manifests and community listfiles
+//!
+//! CASC builds do not ship filenames. Their root manifest maps FileDataIDs to
+//! content keys and, for files that still have names, to a Jenkins96 hash of
+//! the path. Community listfiles (`FileDataID;path` CSV lines) supply the
+//! names, and the root tells which of them belong to a build.
+
+use std::collections::HashMap;
+use std::io;
+
+/// Magic of the 8.2+ root format ("MFST" read as a little-endian u32)
+const MFST_MAGIC: u32 = 0x4D46_5354;
+/// Size of the extended MFST header introduced in 10.1.7
+const MFST_EXTENDED_HEADER_SIZE: u32 = 0x18;
+/// Content flag marking blocks that carry no name hashes
+const CONTENT_NO_NAME_HASH: u32 = 0x1000_0000;
+/// Size of a content key
+const CKEY_SIZE: usize = 16;
+
+/// A file recorded in a CASC root manifest
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RootEntry {
+    pub file_data_id: u32,
+    /// Jenkins96 hash of the path, absent for unnamed files
+    pub name_hash: Option<u64>,
+}
+
+struct RootReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> RootReader<'a> {
+    fn remaining(&self) -> usize {
+        self.data.len() - self.pos
+    }
+
+    fn bytes(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        if self.remaining() < len {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("root manifest truncated at offset {}", self.pos),
+            ));
+        }
+        let bytes = &self.data[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> io::Result<u64> {
+        Ok(u64::from_le_bytes(self.bytes(8)?.try_into().unwrap()))
+    }
+}
+
+/// Parse a CASC root manifest
+///
+/// Both the legacy (WoD to 8.1) layout and the `MFST` layout of 8.2 and
+/// later, including the extended 10.1.7 header, are supported. Files listed
+/// once per locale are reported once per record.
+pub fn parse_root(data: &[u8]) -> io::Result<Vec<RootEntry>> {
+    let mut reader = RootReader { data, pos: 0 };
+
+    // Block layout version, `None` for the legacy layout
+    let mut mfst_version = None;
+    if data.len() >= 4 && u32::from_le_bytes(data[..4].try_into().unwrap()) == MFST_MAGIC {
+        reader.pos = 4;
+        let first = reader.u32()?;
+        if first == MFST_EXTENDED_HEADER_SIZE {
+            let version = reader.u32()?;
+            reader.pos = MFST_EXTENDED_HEADER_SIZE as usize;
+            mfst_version = Some(version);
+        } else {
+            // total_file_count, named_file_count
+            reader.u32()?;
+            mfst_version = Some(1);
+        }
+    }
+
+    let mut entries = Vec::new();
+    while reader.remaining() > 0 {
+        let count = reader.u32()? as usize;
+        let content_flags = match mfst_version {
+            Some(2) => {
+                let _locale_flags = reader.u32()?;
+                let flags1 = reader.u32()?;
+                let flags2 = reader.u32()?;
+                let flags3 = reader.u8()?;
+                flags1 | flags2 | (u32::from(flags3) << 17)
+            }
+            _ => {
+                let content_flags = reader.u32()?;
+                let _locale_flags = reader.u32()?;
+                content_flags
+            }
+        };
+
+        let mut file_data_ids = Vec::with_capacity(count.min(reader.remaining() / 4));
+        let mut next_id = 0u32;
+        for _ in 0..count {
+            let delta = reader.u32()?;
+            let id = next_id.wrapping_add(delta);
+            file_data_ids.push(id);
+            next_id = id.wrapping_add(1);
+        }
+
+        match mfst_version {
+            None => {
+                // Interleaved content key and name hash records
+                for id in file_data_ids {
+                    reader.bytes(CKEY_SIZE)?;
+                    entries.push(RootEntry {
+                        file_data_id: id,
+                        name_hash: Some(reader.u64()?),
+                    });
+                }
+            }
+            Some(_) => {
+                reader.bytes(count * CKEY_SIZE)?;
+                let has_names = content_flags & CONTENT_NO_NAME_HASH == 0;
+                for id in file_data_ids {
+                    let name_hash = if has_names { Some(reader.u64()?) } else { None };
+                    entries.push(RootEntry {
+                        file_data_id: id,
+                        name_hash,
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Parse one `FileDataID;path` line of a community listfile
+///
+/// Paths are returned with MPQ-style backslash separators.
+pub fn parse_listfile_csv_line(line: &str) -> Option<(u32, String)> {
+    let (id, path) = line.trim().split_once(';')?;
+    let path = path.trim();
+    if path.is_empty() {
+        return None;
+    }
+    Some((id.trim().parse().ok()?, path.replace('/', "\\")))
+}
+
+/// Names from a community listfile for the files of a root manifest
+///
+/// Files whose root record carries a name hash only take a name that hashes
+/// to it, which rejects stale listfile entries. Returns the names and the
+/// number of rejected entries.
+pub fn names_for_root(root: &[RootEntry], names: &HashMap<u32, String>) -> (Vec<String>, usize) {
+    let mut seen = std::collections::HashSet::new();
+    let mut resolved = Vec::new();
+    let mut rejected = 0;
+
+    for entry in root {
+        if !seen.insert(entry.file_data_id) {
+            continue;
+        }
+        let Some(name) = names.get(&entry.file_data_id) else {
+            continue;
+        };
+        match entry.name_hash {
+            Some(hash) if wow_mpq::jenkins96(name) != hash => rejected += 1,
+            _ => resolved.push(name.clone()),
+        }
+    }
+
+    (resolved, rejected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn legacy_block(ids: &[u32], names: &[&str]) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&(ids.len() as u32).to_le_bytes());
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(&2u32.to_le_bytes()); // enUS
+        let mut prev: Option<u32> = None;
+        for &id in ids {
+            let delta = prev.map_or(id, |p| id - p - 1);
+            data.extend_from_slice(&delta.to_le_bytes());
+            prev = Some(id);
+        }
+        for name in names {
+            data.extend_from_slice(&[0xAA; CKEY_SIZE]);
+            data.extend_from_slice(&wow_mpq::jenkins96(name).to_le_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn test_parse_legacy_root() {
+        let data = legacy_block(&[10, 11, 20], &["a.blp", "b.blp", "c.m2"]);
+        let entries = parse_root(&data).unwrap();
+        let ids: Vec<_> = entries.iter().map(|e| e.file_data_id).collect();
+        assert_eq!(ids, [10, 11, 20]);
+        assert_eq!(entries[2].name_hash, Some(wow_mpq::jenkins96("C.M2")));
+    }
+
+    #[test]
+    fn test_parse_mfst_root_without_names() {
+        let mut data = Vec::new();
+        data.extend_from_slice(&MFST_MAGIC.to_le_bytes());
+        data.extend_from_slice(&2u32.to_le_bytes()); // total files
+        data.extend_from_slice(&0u32.to_le_bytes()); // named files
+        data.extend_from_slice(&2u32.to_le_bytes());
+        data.extend_from_slice(&CONTENT_NO_NAME_HASH.to_le_bytes());
+        data.extend_from_slice(&2u32.to_le_bytes());
+        data.extend_from_slice(&5u32.to_le_bytes());
+        data.extend_from_slice(&3u32.to_le_bytes());
+        data.extend_from_slice(&[0; 2 * CKEY_SIZE]);
+
+        let entries = parse_root(&data).unwrap();
+        assert_eq!(
+            entries,
+            [
+                RootEntry {
+                    file_data_id: 5,
+                    name_hash: None
+                },
+                RootEntry {
+                    file_data_id: 9,
+                    name_hash: None
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_names_for_root() {
+        let root = parse_root(&legacy_block(&[1, 2], &["a.blp", "b.blp"])).unwrap();
+        let names = HashMap::from([
+            (1, "A.BLP".to_string()),
+            (2, "renamed.blp".to_string()),
+            (3, "c.blp".to_string()),
+        ]);
+        let (resolved, rejected) = names_for_root(&root, &names);
+        assert_eq!(resolved, ["A.BLP"]);
+        assert_eq!(rejected, 1);
+    }
+
+    #[test]
+    fn test_parse_listfile_csv_line() {
+        assert_eq!(
+            parse_listfile_csv_line("53;world/maps/test.wdt\r"),
+            Some((53, "world\\maps\\test.wdt".to_string()))
+        );
+        assert_eq!(parse_listfile_csv_line("# comment"), None);
+        assert_eq!(parse_listfile_csv_line("7;"), None);
+    }
+}
@@ -65,8 +65,12 @@ impl Database {
     }
 
     /// Get the default database path
+    ///
+    /// The active profile's `hash_db` takes precedence over the shared database.
     pub fn default_path() -> Result<PathBuf> {
-        if let Some(proj_dirs) = ProjectDirs::from("network", "kogito", "warcraft-rs") {
+        if let Some(path) = crate::utils::config::active().and_then(|p| p.hash_db.clone()) {
+            Ok(path)
+        } else if let Some(proj_dirs) = ProjectDirs::from("network", "kogito", "warcraft-rs") {
             let data_dir = proj_dirs.data_dir();
             Ok(data_dir.join("mpq-hashes.db"))
         } else {
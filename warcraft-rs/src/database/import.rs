@@ -1,10 +1,12 @@
 //! Import functionality for populating the database
 
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
+use super::casc;
 use super::lookup::HashLookup;
 use super::{Database, DatabaseError};
 
@@ -25,10 +27,14 @@ pub enum ImportError {
 pub(super) type ImportResult<T> = std::result::Result<T, ImportError>;
 
 /// Source types for importing filenames
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub enum ImportSource {
     /// Import from a listfile (text file with one filename per line)
     Listfile,
+    /// Import from a community listfile (`FileDataID;path` CSV lines)
+    CommunityListfile,
+    /// Import the files of a CASC root manifest, named by a community listfile
+    CascRoot { listfile: PathBuf },
     /// Import from an MPQ archive's internal listfile
     Archive,
     /// Scan a directory for filenames matching WoW patterns
@@ -64,6 +70,8 @@ impl<'a> Importer<'a> {
     ) -> ImportResult<ImportStats> {
         match source_type {
             ImportSource::Listfile => self.import_listfile(path).await,
+            ImportSource::CommunityListfile => self.import_community_listfile(path).await,
+            ImportSource::CascRoot { listfile } => self.import_casc_root(path, &listfile).await,
             ImportSource::Archive => self.import_archive(path).await,
             ImportSource::Directory => self.import_directory(path).await,
         }
@@ -114,6 +122,62 @@ impl<'a> Importer<'a> {
         Ok(stats)
     }
 
+    /// Import from a community listfile CSV
+    async fn import_community_listfile(&self, path: &Path) -> ImportResult<ImportStats> {
+        let mut stats = ImportStats::default();
+        let source = format!("listfile-csv:{}", path.display());
+
+        let mut batch = Vec::new();
+        for (_, filename) in read_community_listfile(path, &mut stats)? {
+            batch.push((filename, Some(source.clone())));
+            if batch.len() >= 1000 {
+                self.process_batch(&mut batch, &mut stats).await?;
+            }
+        }
+
+        if !batch.is_empty() {
+            self.process_batch(&mut batch, &mut stats).await?;
+        }
+
+        Ok(stats)
+    }
+
+    /// Import the named files of a CASC root manifest
+    async fn import_casc_root(&self, path: &Path, listfile: &Path) -> ImportResult<ImportStats> {
+        let root = casc::parse_root(&std::fs::read(path)?)?;
+        let source = format!("casc-root:{}", path.display());
+
+        let mut listfile_stats = ImportStats::default();
+        let names: HashMap<u32, String> = read_community_listfile(listfile, &mut listfile_stats)?
+            .into_iter()
+            .collect();
+
+        let (filenames, rejected) = casc::names_for_root(&root, &names);
+        if rejected > 0 {
+            log::warn!("{rejected} listfile names do not match the root's name hashes");
+        }
+
+        let mut stats = ImportStats {
+            files_processed: root.len(),
+            errors: listfile_stats.errors + rejected,
+            ..Default::default()
+        };
+
+        let mut batch = Vec::new();
+        for filename in filenames {
+            batch.push((filename, Some(source.clone())));
+            if batch.len() >= 1000 {
+                self.process_batch(&mut batch, &mut stats).await?;
+            }
+        }
+
+        if !batch.is_empty() {
+            self.process_batch(&mut batch, &mut stats).await?;
+        }
+
+        Ok(stats)
+    }
+
     /// Import from an MPQ archive
     async fn import_archive(&self, path: &Path) -> ImportResult<ImportStats> {
         let mut stats = ImportStats::default();
@@ -233,3 +297,33 @@ impl<'a> Importer<'a> {
         Ok(())
     }
 }
+
+/// Read the `(FileDataID, path)` entries of a community listfile
+///
+/// Malformed lines are counted as errors in `stats`; comments and blank lines
+/// are skipped.
+fn read_community_listfile(
+    path: &Path,
+    stats: &mut ImportStats,
+) -> ImportResult<Vec<(u32, String)>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut entries = Vec::new();
+
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        stats.files_processed += 1;
+        match casc::parse_listfile_csv_line(line) {
+            Some(entry) => entries.push(entry),
+            None => {
+                log::debug!("Skipping malformed listfile line: {line}");
+                stats.errors += 1;
+            }
+        }
+    }
+
+    Ok(entries)
+}
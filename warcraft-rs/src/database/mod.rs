@@ -4,10 +4,12 @@
 //! relationships between filenames and their MPQ hash values, enabling file
 //! resolution even when archives lack internal listfiles.
 
+mod casc;
 mod connection;
 mod import;
 mod lookup;
 mod models;
+mod resolve;
 mod schema;
 
 pub use connection::{Database, DatabaseError};
@@ -16,6 +18,7 @@ pub use import::{ImportSource, Importer};
 pub use lookup::{HashLookup, HetHashLookup};
 #[allow(unused_imports)]
 pub use models::{ArchiveRecord, FileRecord, HashType};
+pub use resolve::{NameResolver, learn_names};
 
 // Re-export hash computation functions from wow-mpq
 pub use wow_mpq::{calculate_het_hashes, calculate_mpq_hashes};
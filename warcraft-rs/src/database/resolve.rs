@@ -0,0 +1,68 @@
+//! Automatic filename resolution for archives without complete listfiles
+//!
+//! Names flow both ways: [`NameResolver`] looks names up for anonymous
+//! entries, and [`learn_names`] stores names that reads proved to exist.
+
+use wow_mpq::FileEntry;
+
+use super::Database;
+use super::lookup::HashLookup;
+
+type Result<T> = super::connection::Result<T>;
+
+/// Resolves archive file names from the hash database
+///
+/// Unlike [`Database::open_default`], opening a resolver never creates a
+/// database, so commands can consult it on every run without side effects.
+#[derive(Debug)]
+pub struct NameResolver {
+    db: Database,
+}
+
+impl NameResolver {
+    /// Open the default hash database if one has been created
+    pub async fn open_default() -> Result<Option<Self>> {
+        let path = Database::default_path()?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            db: Database::open(&path).await?,
+        }))
+    }
+
+    /// Known names for entries from [`wow_mpq::Archive::list_all_with_hashes`]
+    ///
+    /// Only entries carrying classic hash table hashes can be resolved; HET
+    /// indexed files of v3+ archives are not looked up.
+    pub async fn names_for_entries(&self, entries: &[FileEntry]) -> Result<Vec<String>> {
+        let hashes: Vec<(u32, u32)> = entries.iter().filter_map(|e| e.hashes).collect();
+        Ok(self
+            .db
+            .lookup_filenames(&hashes)
+            .await?
+            .into_iter()
+            .filter_map(|(_, _, name)| name)
+            .collect())
+    }
+}
+
+/// Store names learned from successful reads in the default hash database
+///
+/// The database is created if needed. Names whose hashes are already known
+/// keep their entry; new ones are tagged with `source`, usually `learned:`
+/// and the archive's file name. Returns the number of names added.
+pub async fn learn_names(names: &[String], source: &str) -> Result<usize> {
+    if names.is_empty() {
+        return Ok(0);
+    }
+    let db = Database::open_default().await?;
+    let mut entries: Vec<(&str, Option<&str>)> = Vec::new();
+    for name in names {
+        if !db.filename_exists(name).await? {
+            entries.push((name.as_str(), Some(source)));
+        }
+    }
+    let (added, _) = db.store_filenames(&entries).await?;
+    Ok(added)
+}
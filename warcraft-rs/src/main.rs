@@ -33,13 +33,16 @@ async fn main() -> Result<()> {
         log::set_max_level(log::LevelFilter::Error);
     }
 
+    // Select the game installation profile, if any
+    utils::config::init(cli.profile.as_deref())?;
+
     // Execute command
     match cli.command {
         #[cfg(feature = "mpq")]
         Commands::Mpq { command } => commands::mpq::execute(command).await,
 
         #[cfg(feature = "dbc")]
-        Commands::Dbc { command } => commands::dbc::execute(command),
+        Commands::Dbc { command } => commands::dbc::execute(command).await,
 
         #[cfg(feature = "dbc")]
         Commands::Dbd { command } => command.execute(),
@@ -62,6 +65,67 @@ async fn main() -> Result<()> {
         #[cfg(feature = "wdl")]
         Commands::Wdl { command } => commands::wdl::execute(command),
 
+        #[cfg(feature = "mpq")]
+        Commands::ExtractAsset {
+            asset,
+            data_dir,
+            locale,
+            output,
+            convert,
+            with_dependencies,
+        } => commands::asset::execute(commands::asset::ExtractAssetParams {
+            asset,
+            data_dir,
+            locale,
+            output,
+            convert,
+            with_dependencies,
+        }),
+
+        #[cfg(feature = "mpq")]
+        Commands::AssetDeps {
+            asset,
+            data_dir,
+            locale,
+            format,
+        } => commands::asset::execute_deps(commands::asset::AssetDepsParams {
+            asset,
+            data_dir,
+            locale,
+            format,
+        }),
+
+        #[cfg(feature = "mpq")]
+        Commands::Compare {
+            old,
+            new,
+            old_locale,
+            new_locale,
+            kinds,
+            content,
+            format,
+            output,
+            max_records,
+        } => commands::compare::execute(commands::compare::CompareParams {
+            old,
+            new,
+            old_locale,
+            new_locale,
+            kinds,
+            content,
+            format,
+            output,
+            max_records,
+        }),
+
+        Commands::Tree {
+            file,
+            depth,
+            filter,
+            no_color,
+            compact,
+        } => commands::tree::execute(&file, depth, filter.as_deref(), no_color, compact),
+
         Commands::Completions { shell } => {
             print_completions(shell, &mut Cli::command());
             Ok(())
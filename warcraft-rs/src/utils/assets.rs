@@ -0,0 +1,116 @@
+//! Reading referenced game assets from an extracted tree or from archives
+
+use anyhow::{Context, Result};
+use std::borrow::Cow;
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use wow_blp::{convert::blp_to_image, parser::parse_blp};
+use wow_mpq::path::{canonical_path, remap_extension};
+use wow_mpq::{Archive, Storage};
+
+/// Where referenced assets (textures, skins, doodads) are looked up
+pub enum AssetSource {
+    /// A directory of extracted files, matched case-insensitively
+    Directory(PathBuf),
+    /// A single MPQ archive, or a full client installation mounted in patch
+    /// order
+    Storage(Box<dyn Storage>),
+}
+
+impl AssetSource {
+    /// Open an MPQ archive, a client `Data` directory, or an extracted tree
+    pub fn open(path: &Path) -> Result<Self> {
+        if path.is_file() {
+            let archive = Archive::open(path)
+                .with_context(|| format!("Failed to open archive {}", path.display()))?;
+            return Ok(Self::Storage(Box::new(archive)));
+        }
+        if !path.is_dir() {
+            anyhow::bail!("Asset source not found: {}", path.display());
+        }
+
+        #[cfg(feature = "mpq")]
+        if contains_archives(path) {
+            let (chain, _) = crate::commands::mpq::chain::mount_chain(path, None, None)?;
+            return Ok(Self::Storage(Box::new(chain)));
+        }
+
+        Ok(Self::Directory(path.to_path_buf()))
+    }
+
+    /// Read an asset by its client path, returning `None` if it does not exist
+    ///
+    /// The path may use either separator and any case; legacy extensions are
+    /// tried as written first, then as the file current clients use.
+    pub fn read(&mut self, name: &str) -> Result<Option<Vec<u8>>> {
+        let name = canonical_path(name);
+        if let Some(data) = self.read_exact(&name)? {
+            return Ok(Some(data));
+        }
+        match remap_extension(&name) {
+            Cow::Owned(remapped) => self.read_exact(&remapped),
+            Cow::Borrowed(_) => Ok(None),
+        }
+    }
+
+    fn read_exact(&mut self, name: &str) -> Result<Option<Vec<u8>>> {
+        match self {
+            Self::Directory(root) => match find_case_insensitive(root, name) {
+                Some(path) => {
+                    Ok(Some(fs::read(&path).with_context(|| {
+                        format!("Failed to read {}", path.display())
+                    })?))
+                }
+                None => Ok(None),
+            },
+            Self::Storage(storage) => Ok(storage.read_if_exists(name)?),
+        }
+    }
+}
+
+#[cfg(feature = "mpq")]
+fn contains_archives(dir: &Path) -> bool {
+    let is_mpq_dir = |dir: &Path| {
+        fs::read_dir(dir).is_ok_and(|entries| {
+            entries.filter_map(|e| e.ok()).any(|e| {
+                e.path()
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("mpq"))
+            })
+        })
+    };
+    is_mpq_dir(dir) || is_mpq_dir(&dir.join("Data")) || is_mpq_dir(&dir.join("data"))
+}
+
+/// Decode a BLP texture and re-encode its top mipmap as PNG
+pub fn blp_to_png(blp: &[u8]) -> Result<Vec<u8>> {
+    let blp = parse_blp(blp).map_err(|e| anyhow::anyhow!("{e}"))?;
+    let image = blp_to_image(&blp, 0)?;
+    let mut png = Cursor::new(Vec::new());
+    image.write_to(&mut png, image::ImageFormat::Png)?;
+    Ok(png.into_inner())
+}
+
+/// Resolve a backslash-separated client path below `root`, ignoring case
+pub fn find_case_insensitive(root: &Path, name: &str) -> Option<PathBuf> {
+    let mut current = root.to_path_buf();
+    for component in name.split(['\\', '/']).filter(|c| !c.is_empty()) {
+        let exact = current.join(component);
+        if exact.exists() {
+            current = exact;
+            continue;
+        }
+        current = fs::read_dir(&current)
+            .ok()?
+            .filter_map(|e| e.ok())
+            .find(|e| {
+                e.file_name()
+                    .to_str()
+                    .is_some_and(|n| n.eq_ignore_ascii_case(component))
+            })?
+            .path();
+    }
+    current.is_file().then_some(current)
+}
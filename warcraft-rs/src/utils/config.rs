@@ -0,0 +1,266 @@
+//! User configuration with named game installation profiles
+//!
+//! The configuration lives at `~/.config/warcraft-rs/config.toml` (the
+//! platform's config directory outside Linux), or wherever `WARCRAFT_RS_CONFIG`
+//! points. Each profile describes one game install:
+//!
+//! ```toml
+//! default_profile = "wotlk"
+//!
+//! [profiles.wotlk]
+//! data_dir = "~/Games/WoW-3.3.5a/Data"
+//! locale = "enUS"
+//! listfile = "~/wow/listfile-wotlk.txt"
+//! hash_db = "~/wow/wotlk-hashes.db"
+//! learn_names = true
+//! ```
+//!
+//! The selected profile only supplies defaults; explicit command-line options
+//! always take precedence.
+
+use anyhow::{Context, Result};
+use directories::{BaseDirs, ProjectDirs};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Environment variable overriding the config file location
+pub const CONFIG_ENV: &str = "WARCRAFT_RS_CONFIG";
+
+/// Profile selected for this run
+static ACTIVE: OnceLock<Profile> = OnceLock::new();
+
+/// A named game installation
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Profile {
+    pub name: String,
+    /// Installation or `Data` directory holding the client's MPQ archives
+    pub data_dir: Option<PathBuf>,
+    /// Client locale such as `enUS`
+    pub locale: Option<String>,
+    /// External listfile used to name files in archives without one
+    pub listfile: Option<PathBuf>,
+    /// Hash database used instead of the shared default
+    pub hash_db: Option<PathBuf>,
+    /// Record names of files read by name that archives' listfiles lack
+    pub learn_names: bool,
+}
+
+/// Parsed configuration file
+#[derive(Debug, Default)]
+pub struct Config {
+    /// Profile used when `--profile` is not given
+    pub default_profile: Option<String>,
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+impl Config {
+    /// Location of the config file, if a home directory can be determined
+    pub fn path() -> Option<PathBuf> {
+        if let Some(path) = std::env::var_os(CONFIG_ENV) {
+            return Some(PathBuf::from(path));
+        }
+        ProjectDirs::from("network", "kogito", "warcraft-rs")
+            .map(|dirs| dirs.config_dir().join("config.toml"))
+    }
+
+    /// Load a config file; a missing file yields an empty configuration
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        Self::parse(&text, path.parent().unwrap_or(Path::new(".")))
+            .with_context(|| format!("Invalid config file {}", path.display()))
+    }
+
+    /// Parse config text; relative paths are resolved against `base_dir`
+    pub fn parse(text: &str, base_dir: &Path) -> Result<Self> {
+        let table: toml::Table = text.parse()?;
+        let mut config = Self::default();
+
+        for (key, value) in &table {
+            match key.as_str() {
+                "default_profile" => {
+                    let name = value.as_str().context("default_profile must be a string")?;
+                    config.default_profile = Some(name.to_string());
+                }
+                "profiles" => {
+                    let profiles = value.as_table().context("profiles must be a table")?;
+                    for (name, value) in profiles {
+                        let table = value
+                            .as_table()
+                            .with_context(|| format!("profiles.{name} must be a table"))?;
+                        config
+                            .profiles
+                            .insert(name.clone(), Profile::from_table(name, table, base_dir)?);
+                    }
+                }
+                other => log::warn!("Ignoring unknown config key '{other}'"),
+            }
+        }
+
+        if let Some(name) = &config.default_profile
+            && !config.profiles.contains_key(name)
+        {
+            anyhow::bail!("default_profile '{name}' is not defined");
+        }
+        Ok(config)
+    }
+
+    /// Look up a profile by name
+    pub fn profile(&self, name: &str) -> Result<&Profile> {
+        self.profiles.get(name).with_context(|| {
+            if self.profiles.is_empty() {
+                format!("Unknown profile '{name}': no profiles are defined")
+            } else {
+                let known: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+                format!("Unknown profile '{name}' (available: {})", known.join(", "))
+            }
+        })
+    }
+}
+
+impl Profile {
+    fn from_table(name: &str, table: &toml::Table, base_dir: &Path) -> Result<Self> {
+        let mut profile = Profile {
+            name: name.to_string(),
+            ..Default::default()
+        };
+        for (key, value) in table {
+            if key == "learn_names" {
+                profile.learn_names = value
+                    .as_bool()
+                    .with_context(|| format!("profiles.{name}.{key} must be a boolean"))?;
+                continue;
+            }
+            let text = value
+                .as_str()
+                .with_context(|| format!("profiles.{name}.{key} must be a string"))?;
+            match key.as_str() {
+                "data_dir" => profile.data_dir = Some(resolve_path(text, base_dir)),
+                "locale" => profile.locale = Some(text.to_string()),
+                "listfile" => profile.listfile = Some(resolve_path(text, base_dir)),
+                "hash_db" => profile.hash_db = Some(resolve_path(text, base_dir)),
+                other => log::warn!("Ignoring unknown key '{other}' in profile '{name}'"),
+            }
+        }
+        Ok(profile)
+    }
+}
+
+/// Expand a leading `~` and anchor relative paths at the config file's directory
+fn resolve_path(text: &str, base_dir: &Path) -> PathBuf {
+    let path = match text.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with(['/', '\\']) => match BaseDirs::new() {
+            Some(dirs) => dirs.home_dir().join(rest.trim_start_matches(['/', '\\'])),
+            None => PathBuf::from(text),
+        },
+        _ => PathBuf::from(text),
+    };
+    if path.is_relative() {
+        base_dir.join(path)
+    } else {
+        path
+    }
+}
+
+/// Select the profile for this run: the requested one, else the config's default
+///
+/// A broken config file is only an error when a profile was asked for.
+pub fn init(requested: Option<&str>) -> Result<()> {
+    let Some(path) = Config::path() else {
+        if let Some(name) = requested {
+            anyhow::bail!("Cannot locate the config file to load profile '{name}'");
+        }
+        return Ok(());
+    };
+
+    let config = match Config::load(&path) {
+        Ok(config) => config,
+        Err(e) if requested.is_none() => {
+            log::warn!("{e:#}");
+            return Ok(());
+        }
+        Err(e) => return Err(e),
+    };
+    if let Some(name) = requested
+        && !path.exists()
+    {
+        anyhow::bail!(
+            "Profile '{name}' requested but there is no config file at {}",
+            path.display()
+        );
+    }
+
+    let Some(name) = requested.or(config.default_profile.as_deref()) else {
+        return Ok(());
+    };
+    let profile = config.profile(name)?.clone();
+    log::info!("Using profile '{name}' from {}", path.display());
+    let _ = ACTIVE.set(profile);
+    Ok(())
+}
+
+/// The profile selected by [`init`], if any
+pub fn active() -> Option<&'static Profile> {
+    ACTIVE.get()
+}
+
+/// Data directory of the active profile
+pub fn profile_data_dir() -> Option<PathBuf> {
+    active().and_then(|profile| profile.data_dir.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"
+default_profile = "wotlk"
+
+[profiles.wotlk]
+data_dir = "/games/wotlk/Data"
+locale = "enUS"
+listfile = "listfile.txt"
+
+[profiles.vanilla]
+data_dir = "/games/vanilla"
+hash_db = "/data/vanilla.db"
+learn_names = true
+"#;
+
+    #[test]
+    fn test_parse_profiles() {
+        let config = Config::parse(SAMPLE, Path::new("/etc/wr")).unwrap();
+        assert_eq!(config.default_profile.as_deref(), Some("wotlk"));
+
+        let wotlk = config.profile("wotlk").unwrap();
+        assert_eq!(wotlk.data_dir, Some(PathBuf::from("/games/wotlk/Data")));
+        assert_eq!(wotlk.locale.as_deref(), Some("enUS"));
+        assert_eq!(
+            wotlk.listfile,
+            Some(Path::new("/etc/wr").join("listfile.txt"))
+        );
+        assert_eq!(wotlk.hash_db, None);
+        assert!(!wotlk.learn_names);
+
+        let vanilla = config.profile("vanilla").unwrap();
+        assert_eq!(vanilla.hash_db, Some(PathBuf::from("/data/vanilla.db")));
+        assert!(vanilla.learn_names);
+    }
+
+    #[test]
+    fn test_unknown_profile_lists_available() {
+        let config = Config::parse(SAMPLE, Path::new(".")).unwrap();
+        let error = config.profile("tbc").unwrap_err().to_string();
+        assert!(error.contains("vanilla, wotlk"), "{error}");
+    }
+
+    #[test]
+    fn test_undefined_default_profile_is_rejected() {
+        assert!(Config::parse("default_profile = \"cata\"", Path::new(".")).is_err());
+    }
+}
@@ -0,0 +1,407 @@
+//! Files an asset needs, found by following its references through a storage
+//!
+//! Starting from a root asset (an M2 model, a WMO building or an ADT tile),
+//! every file it references is read from a [`Storage`] and searched for
+//! references in turn:
+//!
+//! - M2 models: skin profiles, external `.anim` sequences and hardcoded
+//!   textures
+//! - WMO roots: group files, textures and doodad models
+//! - ADT tiles: split files (`_tex0`, `_obj0`, ...), textures, doodad models
+//!   and buildings
+//!
+//! Skin profiles, WMO groups and ADT split files are *required*: the asset is
+//! unusable without them. Everything else is an optional dependency the
+//! client shows a placeholder for when it is missing.
+
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, VecDeque};
+use wow_mpq::Storage;
+use wow_mpq::path::{normalize_asset_path, path_key};
+
+/// Kind of file, by extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssetKind {
+    Model,
+    Building,
+    Terrain,
+    Texture,
+    Other,
+}
+
+impl AssetKind {
+    pub fn of(name: &str) -> Self {
+        let extension = name
+            .rsplit_once('.')
+            .map(|(_, ext)| ext.to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("m2" | "mdx") => Self::Model,
+            Some("wmo") => Self::Building,
+            Some("adt") => Self::Terrain,
+            Some("blp") => Self::Texture,
+            _ => Self::Other,
+        }
+    }
+}
+
+/// `World\wmo\Building_000.wmo` names a group file rather than a root
+pub fn is_wmo_group(name: &str) -> bool {
+    name.rsplit_once('.')
+        .map(|(stem, _)| stem)
+        .and_then(|stem| stem.rsplit_once('_'))
+        .is_some_and(|(_, suffix)| suffix.len() == 3 && suffix.bytes().all(|b| b.is_ascii_digit()))
+}
+
+fn strip_extension(name: &str) -> &str {
+    name.rsplit_once('.').map_or(name, |(stem, _)| stem)
+}
+
+/// A file of the dependency set
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dependency {
+    /// Client path, with backslash separators
+    pub name: String,
+    /// Size in bytes
+    pub size: u64,
+    /// Whether the file is the root or a part it cannot be used without
+    pub required: bool,
+    /// The file through which it was first reached; `None` for the root
+    pub referenced_by: Option<String>,
+    /// Files it references, whether found or not
+    pub references: Vec<String>,
+}
+
+/// A referenced file the storage does not hold
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingDependency {
+    /// Client path as referenced
+    pub name: String,
+    /// Whether the referencing asset is unusable without it
+    pub required: bool,
+    /// The file that references it
+    pub referenced_by: String,
+}
+
+/// Everything an asset references, directly or indirectly
+#[derive(Debug, Clone, Default)]
+pub struct DependencyGraph {
+    /// Files found, the root first, in the order they were reached
+    pub files: Vec<Dependency>,
+    /// References that could not be resolved
+    pub missing: Vec<MissingDependency>,
+}
+
+impl DependencyGraph {
+    /// Combined size of the files found
+    pub fn total_size(&self) -> u64 {
+        self.files.iter().map(|file| file.size).sum()
+    }
+
+    /// Whether a required part is missing
+    pub fn is_incomplete(&self) -> bool {
+        self.missing.iter().any(|missing| missing.required)
+    }
+}
+
+/// Files referenced by an asset, split into required parts and optional dependencies
+#[derive(Default)]
+struct References {
+    required: Vec<String>,
+    optional: Vec<String>,
+}
+
+#[cfg(feature = "m2")]
+fn model_references(name: &str, data: &[u8], storage: &dyn Storage) -> Result<References> {
+    use wow_m2::chunks::texture::M2TextureType;
+
+    let format = wow_m2::parse_m2(&mut std::io::Cursor::new(data))
+        .with_context(|| format!("Failed to parse {name}"))?;
+    let model = format.model();
+    let stem = strip_extension(name);
+    let mut references = References::default();
+
+    // Models from before Wrath of the Lich King embed their skin profiles
+    if model.header.version > 263 {
+        let skins = model.header.num_skin_profiles.unwrap_or(1).max(1);
+        references
+            .required
+            .extend((0..skins).map(|i| format!("{stem}{i:02}.skin")));
+
+        // Sequences without the 0x20 flag keep their keyframes in .anim files
+        for animation in &model.animations {
+            if animation.flags & 0x20 == 0 {
+                let anim = format!(
+                    "{stem}{:04}-{:02}.anim",
+                    animation.animation_id, animation.sub_animation_id
+                );
+                if storage.contains(&anim) {
+                    references.optional.push(anim);
+                }
+            }
+        }
+    }
+
+    references.optional.extend(
+        model
+            .textures
+            .iter()
+            .filter(|texture| texture.texture_type == M2TextureType::Hardcoded)
+            .map(|texture| texture.filename.string.to_string_lossy())
+            .filter(|texture| !texture.is_empty()),
+    );
+    Ok(references)
+}
+
+#[cfg(not(feature = "m2"))]
+fn model_references(_name: &str, _data: &[u8], _storage: &dyn Storage) -> Result<References> {
+    anyhow::bail!("M2 models require the 'm2' feature to be enabled")
+}
+
+#[cfg(feature = "wmo")]
+fn building_references(name: &str, data: &[u8]) -> Result<References> {
+    use wow_wmo::{ParsedWmo, parse_wmo};
+
+    let mut references = References::default();
+    let root = match parse_wmo(&mut std::io::Cursor::new(data))
+        .with_context(|| format!("Failed to parse {name}"))?
+    {
+        ParsedWmo::Root(root) => root,
+        // Group files carry no references of their own
+        ParsedWmo::Group(_) => return Ok(references),
+    };
+
+    let stem = strip_extension(name);
+    references
+        .required
+        .extend((0..root.n_groups).map(|i| format!("{stem}_{i:03}.wmo")));
+    references.optional.extend(root.textures.iter().cloned());
+    references
+        .optional
+        .extend(root.doodad_names.iter().cloned());
+    Ok(references)
+}
+
+#[cfg(not(feature = "wmo"))]
+fn building_references(_name: &str, _data: &[u8]) -> Result<References> {
+    anyhow::bail!("WMO buildings require the 'wmo' feature to be enabled")
+}
+
+#[cfg(feature = "adt")]
+fn terrain_references(name: &str, data: &[u8], storage: &dyn Storage) -> Result<References> {
+    use wow_adt::{ParsedAdt, parse_adt};
+
+    /// Split files that accompany a Cataclysm and later root tile
+    const SPLIT_SUFFIXES: [&str; 5] = ["_tex0", "_tex1", "_obj0", "_obj1", "_lod"];
+
+    let mut references = References::default();
+    match parse_adt(&mut std::io::Cursor::new(data))
+        .with_context(|| format!("Failed to parse {name}"))?
+    {
+        ParsedAdt::Root(root) => {
+            let stem = strip_extension(name);
+            references.required.extend(
+                SPLIT_SUFFIXES
+                    .iter()
+                    .map(|suffix| format!("{stem}{suffix}.adt"))
+                    .filter(|split| storage.contains(split)),
+            );
+            references.optional.extend(root.textures.iter().cloned());
+            references.optional.extend(root.models.iter().cloned());
+            references.optional.extend(root.wmos.iter().cloned());
+        }
+        ParsedAdt::Tex0(tex) | ParsedAdt::Tex1(tex) => {
+            references.optional.extend(tex.textures);
+        }
+        ParsedAdt::Obj0(obj) | ParsedAdt::Obj1(obj) => {
+            references.optional.extend(obj.models);
+            references.optional.extend(obj.wmos);
+        }
+        ParsedAdt::Lod(_) => {}
+    }
+    Ok(references)
+}
+
+#[cfg(not(feature = "adt"))]
+fn terrain_references(_name: &str, _data: &[u8], _storage: &dyn Storage) -> Result<References> {
+    anyhow::bail!("ADT tiles require the 'adt' feature to be enabled")
+}
+
+fn references(name: &str, data: &[u8], storage: &dyn Storage) -> Result<References> {
+    match AssetKind::of(name) {
+        AssetKind::Model => model_references(name, data, storage),
+        AssetKind::Building if !is_wmo_group(name) => building_references(name, data),
+        AssetKind::Terrain => terrain_references(name, data, storage),
+        _ => Ok(References::default()),
+    }
+}
+
+/// Where a name walked before ended up
+enum Seen {
+    Found(usize),
+    Missing(usize),
+}
+
+/// Walk the files `root` references, handing each one found to `visit`
+///
+/// Required parts are always followed; optional dependencies only with
+/// `follow_optional`. Fails if the root itself is not in the storage or a
+/// file found cannot be parsed.
+pub fn walk_dependencies(
+    storage: &mut dyn Storage,
+    root: &str,
+    follow_optional: bool,
+    mut visit: impl FnMut(&Dependency, &[u8]) -> Result<()>,
+) -> Result<DependencyGraph> {
+    let mut graph = DependencyGraph::default();
+    let mut seen: BTreeMap<String, Seen> = BTreeMap::new();
+    // (client path, required, referenced by)
+    let mut queue = VecDeque::from([(normalize_asset_path(root), true, None::<String>)]);
+
+    while let Some((name, required, referenced_by)) = queue.pop_front() {
+        let key = path_key(&name);
+        if let Some(seen) = seen.get(&key) {
+            // A part first reached as an optional dependency is still a part
+            if required {
+                match *seen {
+                    Seen::Found(index) => graph.files[index].required = true,
+                    Seen::Missing(index) => graph.missing[index].required = true,
+                }
+            }
+            continue;
+        }
+
+        let Some(data) = storage
+            .read_if_exists(&name)
+            .with_context(|| format!("Failed to read {name}"))?
+        else {
+            let Some(referenced_by) = referenced_by else {
+                anyhow::bail!("{name} was not found in the game data");
+            };
+            seen.insert(key, Seen::Missing(graph.missing.len()));
+            graph.missing.push(MissingDependency {
+                name,
+                required,
+                referenced_by,
+            });
+            continue;
+        };
+
+        let found = references(&name, &data, storage)?;
+        let normalized = |names: Vec<String>| -> Vec<String> {
+            names.iter().map(|r| normalize_asset_path(r)).collect()
+        };
+        let required_refs = normalized(found.required);
+        let optional_refs = normalized(found.optional);
+
+        let file = Dependency {
+            name: name.clone(),
+            size: data.len() as u64,
+            required,
+            referenced_by,
+            references: required_refs
+                .iter()
+                .chain(&optional_refs)
+                .cloned()
+                .collect(),
+        };
+        visit(&file, &data)?;
+        seen.insert(key, Seen::Found(graph.files.len()));
+        graph.files.push(file);
+
+        queue.extend(
+            required_refs
+                .into_iter()
+                .map(|r| (r, true, Some(name.clone()))),
+        );
+        if follow_optional {
+            queue.extend(
+                optional_refs
+                    .into_iter()
+                    .map(|r| (r, false, Some(name.clone()))),
+            );
+        }
+    }
+
+    Ok(graph)
+}
+
+/// The full dependency set of `root`, required parts and optional
+/// dependencies alike
+pub fn dependencies(storage: &mut dyn Storage, root: &str) -> Result<DependencyGraph> {
+    walk_dependencies(storage, root, true, |_, _| Ok(()))
+}
+
+#[cfg(all(test, feature = "adt"))]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::path::Path;
+    use wow_adt::AdtVersion;
+    use wow_adt::builder::AdtBuilder;
+
+    /// Files held in memory, keyed like the hash table does
+    #[derive(Default)]
+    struct MemoryStorage(HashMap<String, Vec<u8>>);
+
+    impl Storage for MemoryStorage {
+        fn open(_path: &Path) -> wow_mpq::Result<Self> {
+            Ok(Self::default())
+        }
+
+        fn read(&mut self, name: &str) -> wow_mpq::Result<Vec<u8>> {
+            self.0
+                .get(&path_key(name))
+                .cloned()
+                .ok_or_else(|| wow_mpq::Error::FileNotFound(name.to_string()))
+        }
+
+        fn contains(&self, name: &str) -> bool {
+            self.0.contains_key(&path_key(name))
+        }
+
+        fn list(&mut self) -> wow_mpq::Result<Vec<wow_mpq::StorageEntry>> {
+            Ok(Vec::new())
+        }
+    }
+
+    #[test]
+    fn test_tile_dependencies() {
+        let tile = AdtBuilder::new()
+            .with_version(AdtVersion::WotLK)
+            .add_texture("Tileset/Grass.blp")
+            .add_texture("Tileset/Gone.blp")
+            .add_model("World/Tree.m2")
+            .build()
+            .unwrap()
+            .to_bytes()
+            .unwrap();
+        let tile_size = tile.len() as u64;
+
+        let mut storage = MemoryStorage::default();
+        storage
+            .0
+            .insert(path_key("World\\Maps\\Test\\Test_32_32.adt"), tile);
+        storage.0.insert(path_key("tileset/grass.blp"), vec![0; 10]);
+
+        let graph = dependencies(&mut storage, "World/Maps/Test/Test_32_32.adt").unwrap();
+        let names: Vec<_> = graph.files.iter().map(|file| file.name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["World\\Maps\\Test\\Test_32_32.adt", "Tileset\\Grass.blp"]
+        );
+        assert_eq!(graph.total_size(), tile_size + 10);
+        assert_eq!(graph.files[0].references.len(), 3);
+
+        let missing: Vec<_> = graph.missing.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(missing, ["Tileset\\Gone.blp", "World\\Tree.m2"]);
+        assert!(!graph.is_incomplete());
+        assert!(
+            graph
+                .missing
+                .iter()
+                .all(|m| m.referenced_by == "World\\Maps\\Test\\Test_32_32.adt")
+        );
+
+        assert!(dependencies(&mut storage, "World\\Maps\\Test\\Other.adt").is_err());
+    }
+}
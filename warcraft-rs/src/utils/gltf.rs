@@ -0,0 +1,534 @@
+//! Minimal glTF 2.0 writer shared by the model exporters
+//!
+//! The builder collects geometry, materials, embedded images, skins and
+//! animations into a single binary buffer and writes either a self-contained
+//! `.glb` or a `.gltf` JSON document with a sibling `.bin` file.
+
+use anyhow::{Context, Result};
+use serde_json::{Value, json};
+use std::fs;
+use std::path::Path;
+
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+const UNSIGNED_BYTE: u32 = 5121;
+const UNSIGNED_SHORT: u32 = 5123;
+const UNSIGNED_INT: u32 = 5125;
+const FLOAT: u32 = 5126;
+
+/// Convert a position or direction from WoW's Z-up space into glTF's Y-up space
+pub fn wow_to_gltf([x, y, z]: [f32; 3]) -> [f32; 3] {
+    [x, z, -y]
+}
+
+/// Convert a rotation quaternion (x, y, z, w) from WoW space into glTF space
+pub fn wow_quat_to_gltf([x, y, z, w]: [f32; 4]) -> [f32; 4] {
+    [x, z, -y, w]
+}
+
+/// How a material's alpha channel should be interpreted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AlphaMode {
+    Opaque,
+    Mask,
+    Blend,
+}
+
+/// Incrementally assembled glTF document
+#[derive(Default)]
+pub struct GltfBuilder {
+    buffer: Vec<u8>,
+    buffer_views: Vec<Value>,
+    accessors: Vec<Value>,
+    images: Vec<Value>,
+    textures: Vec<Value>,
+    materials: Vec<Value>,
+    meshes: Vec<Value>,
+    nodes: Vec<Value>,
+    skins: Vec<Value>,
+    animations: Vec<Value>,
+    /// KHR_lights_punctual light definitions
+    lights: Vec<Value>,
+    scene_roots: Vec<usize>,
+}
+
+impl GltfBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn push_view(&mut self, bytes: &[u8], stride: Option<usize>, target: Option<u32>) -> usize {
+        while !self.buffer.len().is_multiple_of(4) {
+            self.buffer.push(0);
+        }
+        let offset = self.buffer.len();
+        self.buffer.extend_from_slice(bytes);
+
+        let mut view = json!({
+            "buffer": 0,
+            "byteOffset": offset,
+            "byteLength": bytes.len(),
+        });
+        if let Some(stride) = stride {
+            view["byteStride"] = json!(stride);
+        }
+        if let Some(target) = target {
+            view["target"] = json!(target);
+        }
+        self.buffer_views.push(view);
+        self.buffer_views.len() - 1
+    }
+
+    fn push_accessor(&mut self, accessor: Value) -> usize {
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+
+    /// Add a float accessor of `N`-component elements (`N` = 1, 2, 3, 4 or 16)
+    pub fn add_floats<const N: usize>(&mut self, data: &[[f32; N]], vertex_data: bool) -> usize {
+        let bytes: Vec<u8> = data
+            .iter()
+            .flatten()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        let target = vertex_data.then_some(ARRAY_BUFFER);
+        let view = self.push_view(&bytes, None, target);
+
+        let mut min = [f32::INFINITY; N];
+        let mut max = [f32::NEG_INFINITY; N];
+        for element in data {
+            for (i, &v) in element.iter().enumerate() {
+                min[i] = min[i].min(v);
+                max[i] = max[i].max(v);
+            }
+        }
+
+        let mut accessor = json!({
+            "bufferView": view,
+            "componentType": FLOAT,
+            "count": data.len(),
+            "type": element_type(N),
+        });
+        // Bounds are mandatory for POSITION and animation inputs; emit them
+        // for every small accessor since they are cheap and help viewers.
+        if !data.is_empty() && N <= 4 {
+            accessor["min"] = json!(min.to_vec());
+            accessor["max"] = json!(max.to_vec());
+        }
+        self.push_accessor(accessor)
+    }
+
+    /// Add a `VEC4` accessor of unsigned bytes, optionally normalized
+    pub fn add_bytes4(&mut self, data: &[[u8; 4]], normalized: bool) -> usize {
+        let bytes: Vec<u8> = data.iter().flatten().copied().collect();
+        let view = self.push_view(&bytes, None, Some(ARRAY_BUFFER));
+        let mut accessor = json!({
+            "bufferView": view,
+            "componentType": UNSIGNED_BYTE,
+            "count": data.len(),
+            "type": "VEC4",
+        });
+        if normalized {
+            accessor["normalized"] = json!(true);
+        }
+        self.push_accessor(accessor)
+    }
+
+    /// Add a triangle index accessor, using 16-bit indices when possible
+    pub fn add_indices(&mut self, indices: &[u32]) -> usize {
+        let (bytes, component): (Vec<u8>, u32) =
+            if indices.iter().all(|&i| i <= u32::from(u16::MAX)) {
+                (
+                    indices
+                        .iter()
+                        .flat_map(|&i| (i as u16).to_le_bytes())
+                        .collect(),
+                    UNSIGNED_SHORT,
+                )
+            } else {
+                (
+                    indices.iter().flat_map(|i| i.to_le_bytes()).collect(),
+                    UNSIGNED_INT,
+                )
+            };
+        let view = self.push_view(&bytes, None, Some(ELEMENT_ARRAY_BUFFER));
+        self.push_accessor(json!({
+            "bufferView": view,
+            "componentType": component,
+            "count": indices.len(),
+            "type": "SCALAR",
+        }))
+    }
+
+    /// Embed a PNG image and create a texture sampling it
+    pub fn add_png_texture(&mut self, name: &str, png: &[u8]) -> usize {
+        let view = self.push_view(png, None, None);
+        self.images.push(json!({
+            "name": name,
+            "bufferView": view,
+            "mimeType": "image/png",
+        }));
+        self.textures.push(json!({
+            "source": self.images.len() - 1,
+            "sampler": 0,
+        }));
+        self.textures.len() - 1
+    }
+
+    /// Reference an external image file and create a texture sampling it
+    pub fn add_texture_uri(&mut self, name: &str, uri: &str) -> usize {
+        self.images.push(json!({
+            "name": name,
+            "uri": uri,
+        }));
+        self.textures.push(json!({
+            "source": self.images.len() - 1,
+            "sampler": 0,
+        }));
+        self.textures.len() - 1
+    }
+
+    /// Add a material, optionally textured
+    pub fn add_material(
+        &mut self,
+        name: &str,
+        texture: Option<usize>,
+        alpha_mode: AlphaMode,
+        double_sided: bool,
+    ) -> usize {
+        let mut pbr = json!({
+            "metallicFactor": 0.0,
+            "roughnessFactor": 1.0,
+        });
+        if let Some(texture) = texture {
+            pbr["baseColorTexture"] = json!({ "index": texture });
+        }
+
+        let mut material = json!({
+            "name": name,
+            "pbrMetallicRoughness": pbr,
+            "doubleSided": double_sided,
+        });
+        match alpha_mode {
+            AlphaMode::Opaque => {}
+            AlphaMode::Mask => {
+                material["alphaMode"] = json!("MASK");
+                material["alphaCutoff"] = json!(0.5);
+            }
+            AlphaMode::Blend => material["alphaMode"] = json!("BLEND"),
+        }
+        self.materials.push(material);
+        self.materials.len() - 1
+    }
+
+    /// Add a mesh made of the given primitives
+    ///
+    /// Each primitive is a JSON object with `attributes`, `indices` and an
+    /// optional `material`, as produced by [`primitive`].
+    pub fn add_mesh(&mut self, name: &str, primitives: Vec<Value>) -> usize {
+        self.meshes.push(json!({
+            "name": name,
+            "primitives": primitives,
+        }));
+        self.meshes.len() - 1
+    }
+
+    /// Add a node; see [`node`] for building the JSON object
+    /// Add a `KHR_lights_punctual` light, referenced from nodes by index
+    pub fn add_light(&mut self, light: Value) -> usize {
+        self.lights.push(light);
+        self.lights.len() - 1
+    }
+
+    pub fn add_node(&mut self, node: Value) -> usize {
+        self.nodes.push(node);
+        self.nodes.len() - 1
+    }
+
+    /// Attach child nodes to a parent node
+    pub fn set_children(&mut self, parent: usize, children: &[usize]) {
+        if !children.is_empty() {
+            self.nodes[parent]["children"] = json!(children);
+        }
+    }
+
+    /// Set a property on an existing node
+    pub fn set_node_property(&mut self, node: usize, key: &str, value: Value) {
+        self.nodes[node][key] = value;
+    }
+
+    /// Mark a node as a root of the default scene
+    pub fn add_scene_root(&mut self, node: usize) {
+        self.scene_roots.push(node);
+    }
+
+    /// Add a skin over `joints` with the given inverse bind matrices
+    pub fn add_skin(
+        &mut self,
+        name: &str,
+        joints: &[usize],
+        inverse_bind_matrices: &[[f32; 16]],
+        skeleton: Option<usize>,
+    ) -> usize {
+        let ibm = self.add_floats(inverse_bind_matrices, false);
+        let mut skin = json!({
+            "name": name,
+            "joints": joints,
+            "inverseBindMatrices": ibm,
+        });
+        if let Some(skeleton) = skeleton {
+            skin["skeleton"] = json!(skeleton);
+        }
+        self.skins.push(skin);
+        self.skins.len() - 1
+    }
+
+    /// Start a new animation and return a handle for adding channels
+    pub fn begin_animation(&mut self, name: &str) -> AnimationBuilder<'_> {
+        AnimationBuilder {
+            builder: self,
+            name: name.to_string(),
+            samplers: Vec::new(),
+            channels: Vec::new(),
+        }
+    }
+
+    fn document(&self, buffer_uri: Option<&str>) -> Value {
+        let mut buffer = json!({ "byteLength": self.buffer.len() });
+        if let Some(uri) = buffer_uri {
+            buffer["uri"] = json!(uri);
+        }
+
+        let mut doc = json!({
+            "asset": {
+                "version": "2.0",
+                "generator": concat!("warcraft-rs ", env!("CARGO_PKG_VERSION")),
+            },
+            "scene": 0,
+            "scenes": [{ "nodes": self.scene_roots }],
+            "nodes": self.nodes,
+            "buffers": [buffer],
+            "bufferViews": self.buffer_views,
+            "accessors": self.accessors,
+        });
+
+        let optional = [
+            ("meshes", &self.meshes),
+            ("materials", &self.materials),
+            ("textures", &self.textures),
+            ("images", &self.images),
+            ("skins", &self.skins),
+            ("animations", &self.animations),
+        ];
+        for (key, values) in optional {
+            if !values.is_empty() {
+                doc[key] = json!(values);
+            }
+        }
+        if !self.lights.is_empty() {
+            doc["extensionsUsed"] = json!(["KHR_lights_punctual"]);
+            doc["extensions"] = json!({ "KHR_lights_punctual": { "lights": self.lights } });
+        }
+        if !self.textures.is_empty() {
+            // Repeat wrapping with trilinear filtering, matching the client
+            doc["samplers"] = json!([{
+                "magFilter": 9729,
+                "minFilter": 9987,
+                "wrapS": 10497,
+                "wrapT": 10497,
+            }]);
+        }
+        doc
+    }
+
+    /// Write the document, choosing binary glTF when the extension is `.glb`
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let binary = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| e.eq_ignore_ascii_case("glb"));
+
+        if binary {
+            fs::write(path, self.to_glb()?)
+                .with_context(|| format!("Failed to write {}", path.display()))
+        } else {
+            let bin_path = path.with_extension("bin");
+            let bin_name = bin_path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .context("Invalid output file name")?;
+            let doc = self.document(Some(bin_name));
+            fs::write(&bin_path, &self.buffer)
+                .with_context(|| format!("Failed to write {}", bin_path.display()))?;
+            fs::write(path, serde_json::to_string_pretty(&doc)?)
+                .with_context(|| format!("Failed to write {}", path.display()))
+        }
+    }
+
+    /// Serialize as a binary glTF container
+    pub fn to_glb(&self) -> Result<Vec<u8>> {
+        let mut json_chunk = serde_json::to_vec(&self.document(None))?;
+        while !json_chunk.len().is_multiple_of(4) {
+            json_chunk.push(b' ');
+        }
+        let mut bin_chunk = self.buffer.clone();
+        while !bin_chunk.len().is_multiple_of(4) {
+            bin_chunk.push(0);
+        }
+
+        let total = 12 + 8 + json_chunk.len() + 8 + bin_chunk.len();
+        let mut out = Vec::with_capacity(total);
+        out.extend_from_slice(b"glTF");
+        out.extend_from_slice(&2u32.to_le_bytes());
+        out.extend_from_slice(&(total as u32).to_le_bytes());
+        out.extend_from_slice(&(json_chunk.len() as u32).to_le_bytes());
+        out.extend_from_slice(b"JSON");
+        out.extend_from_slice(&json_chunk);
+        out.extend_from_slice(&(bin_chunk.len() as u32).to_le_bytes());
+        out.extend_from_slice(b"BIN\0");
+        out.extend_from_slice(&bin_chunk);
+        Ok(out)
+    }
+}
+
+/// Collects samplers and channels for one animation
+pub struct AnimationBuilder<'a> {
+    builder: &'a mut GltfBuilder,
+    name: String,
+    samplers: Vec<Value>,
+    channels: Vec<Value>,
+}
+
+impl AnimationBuilder<'_> {
+    /// Add a linearly interpolated channel; `times` are in seconds
+    pub fn add_channel<const N: usize>(
+        &mut self,
+        node: usize,
+        path: &str,
+        times: &[[f32; 1]],
+        values: &[[f32; N]],
+    ) {
+        let input = self.builder.add_floats(times, false);
+        let output = self.builder.add_floats(values, false);
+        self.samplers.push(json!({
+            "input": input,
+            "output": output,
+            "interpolation": "LINEAR",
+        }));
+        self.channels.push(json!({
+            "sampler": self.samplers.len() - 1,
+            "target": { "node": node, "path": path },
+        }));
+    }
+
+    /// Store the animation; animations without channels are dropped
+    pub fn finish(self) -> bool {
+        if self.channels.is_empty() {
+            return false;
+        }
+        self.builder.animations.push(json!({
+            "name": self.name,
+            "samplers": self.samplers,
+            "channels": self.channels,
+        }));
+        true
+    }
+}
+
+/// Build a triangle primitive from attribute accessors
+pub fn primitive(attributes: &[(&str, usize)], indices: usize, material: Option<usize>) -> Value {
+    let attributes: serde_json::Map<String, Value> = attributes
+        .iter()
+        .map(|(name, accessor)| (name.to_string(), json!(accessor)))
+        .collect();
+    let mut primitive = json!({
+        "attributes": attributes,
+        "indices": indices,
+        "mode": 4,
+    });
+    if let Some(material) = material {
+        primitive["material"] = json!(material);
+    }
+    primitive
+}
+
+/// Build a node with an optional translation, rotation and scale
+pub fn node(
+    name: &str,
+    translation: Option<[f32; 3]>,
+    rotation: Option<[f32; 4]>,
+    scale: Option<[f32; 3]>,
+) -> Value {
+    let mut node = json!({ "name": name });
+    if let Some(t) = translation {
+        node["translation"] = json!(t);
+    }
+    if let Some(r) = rotation {
+        node["rotation"] = json!(r);
+    }
+    if let Some(s) = scale {
+        node["scale"] = json!(s);
+    }
+    node
+}
+
+/// Column-major translation matrix
+pub fn translation_matrix([x, y, z]: [f32; 3]) -> [f32; 16] {
+    [
+        1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, x, y, z, 1.0,
+    ]
+}
+
+fn element_type(components: usize) -> &'static str {
+    match components {
+        1 => "SCALAR",
+        2 => "VEC2",
+        3 => "VEC3",
+        4 => "VEC4",
+        16 => "MAT4",
+        _ => unreachable!("unsupported accessor width {components}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glb_layout() {
+        let mut builder = GltfBuilder::new();
+        let positions =
+            builder.add_floats(&[[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]], true);
+        let indices = builder.add_indices(&[0, 1, 2]);
+        let mesh = builder.add_mesh(
+            "tri",
+            vec![primitive(&[("POSITION", positions)], indices, None)],
+        );
+        let mut root = node("root", None, None, None);
+        root["mesh"] = json!(mesh);
+        let root = builder.add_node(root);
+        builder.add_scene_root(root);
+
+        let glb = builder.to_glb().unwrap();
+        assert_eq!(&glb[0..4], b"glTF");
+        assert_eq!(
+            u32::from_le_bytes(glb[8..12].try_into().unwrap()) as usize,
+            glb.len()
+        );
+        assert_eq!(&glb[16..20], b"JSON");
+
+        let json_len = u32::from_le_bytes(glb[12..16].try_into().unwrap()) as usize;
+        let doc: Value = serde_json::from_slice(&glb[20..20 + json_len]).unwrap();
+        assert_eq!(doc["accessors"][0]["max"], json!([1.0, 1.0, 0.0]));
+        assert_eq!(doc["accessors"][1]["componentType"], json!(UNSIGNED_SHORT));
+    }
+
+    #[test]
+    fn test_coordinate_conversion_is_rotation() {
+        // Z-up becomes Y-up and handedness is preserved
+        assert_eq!(wow_to_gltf([0.0, 0.0, 1.0]), [0.0, 1.0, 0.0]);
+        assert_eq!(wow_to_gltf([0.0, 1.0, 0.0]), [0.0, 0.0, -1.0]);
+        assert_eq!(wow_to_gltf([1.0, 0.0, 0.0]), [1.0, 0.0, 0.0]);
+    }
+}
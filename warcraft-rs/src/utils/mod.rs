@@ -52,19 +52,21 @@ pub mod progress;
 ))]
 pub mod table;
 
-#[cfg(any(
-    feature = "mpq",
-    feature = "dbc",
-    feature = "blp",
-    feature = "m2",
-    feature = "wmo",
-    feature = "adt",
-    feature = "wdt",
-    feature = "wdl",
-    test
-))]
+pub mod config;
+
 pub mod tree;
 
+pub mod dependencies;
+
+#[cfg(any(feature = "m2", feature = "wmo", feature = "adt"))]
+pub mod assets;
+
+#[cfg(any(feature = "m2", feature = "wmo", feature = "adt"))]
+pub mod gltf;
+
+#[cfg(any(feature = "m2", feature = "wmo", feature = "adt"))]
+pub mod scene;
+
 // Re-export utilities only when actually used by commands
 #[cfg(feature = "mpq")]
 pub use format::*;
@@ -72,10 +74,10 @@ pub use format::*;
 #[cfg(feature = "mpq")]
 pub use io::*;
 
-#[cfg(any(feature = "mpq", feature = "wdl"))]
+#[cfg(any(feature = "mpq", feature = "blp", feature = "wdl"))]
 pub use progress::*;
 
-#[cfg(any(feature = "mpq", feature = "wdl"))]
+#[cfg(any(feature = "mpq", feature = "dbc", feature = "wdl"))]
 pub use table::*;
 
 #[cfg(any(
@@ -0,0 +1,614 @@
+//! Format-neutral static scenes and their OBJ and glTF writers
+//!
+//! Exporters describe geometry in WoW's Z-up model space; the writers take
+//! care of the axis conversion, texture conversion and file layout.
+
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::utils::assets::{AssetSource, blp_to_png};
+use crate::utils::gltf::{AlphaMode, GltfBuilder, node, primitive, wow_quat_to_gltf, wow_to_gltf};
+
+/// How a surface is combined with what is behind it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum BlendMode {
+    #[default]
+    Opaque,
+    /// Alpha blended
+    Alpha,
+    /// Added to the background
+    Additive,
+    /// Multiplied with the background
+    Modulate,
+}
+
+/// Surface description shared by every exporter
+///
+/// The M2, WMO and ADT exporters resolve their own material records into
+/// this, so the OBJ and glTF writers translate materials in one place.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ResolvedMaterial {
+    /// Client paths of the textures (`.blp`), diffuse texture first
+    pub textures: Vec<String>,
+    pub blend_mode: BlendMode,
+    /// Pixels below the alpha cutoff are discarded
+    pub alpha_test: bool,
+    /// Both faces are drawn
+    pub two_sided: bool,
+}
+
+impl ResolvedMaterial {
+    /// Material with an `EGxBlend` mode, as used by M2 and WMO materials
+    pub fn from_gx_blend(blend: u32, textures: Vec<String>, two_sided: bool) -> Self {
+        let (blend_mode, alpha_test) = match blend {
+            0 => (BlendMode::Opaque, false),
+            1 => (BlendMode::Opaque, true),
+            3 | 4 | 7 => (BlendMode::Additive, false),
+            5 | 6 => (BlendMode::Modulate, false),
+            _ => (BlendMode::Alpha, false),
+        };
+        Self {
+            textures,
+            blend_mode,
+            alpha_test,
+            two_sided,
+        }
+    }
+
+    /// Closest `EGxBlend` mode, for writing the material back
+    pub fn gx_blend(&self) -> u32 {
+        match (self.blend_mode, self.alpha_test) {
+            (BlendMode::Opaque, false) => 0,
+            (BlendMode::Opaque, true) => 1,
+            (BlendMode::Alpha, _) => 2,
+            (BlendMode::Additive, _) => 4,
+            (BlendMode::Modulate, _) => 5,
+        }
+    }
+
+    /// Client path of the diffuse texture
+    pub fn diffuse(&self) -> Option<&str> {
+        self.textures.first().map(String::as_str)
+    }
+
+    /// glTF has no additive or modulated blending; both become `BLEND`
+    pub fn alpha_mode(&self) -> AlphaMode {
+        match (self.alpha_test, self.blend_mode) {
+            (true, _) => AlphaMode::Mask,
+            (false, BlendMode::Opaque) => AlphaMode::Opaque,
+            (false, _) => AlphaMode::Blend,
+        }
+    }
+}
+
+/// Triangles drawn with one material
+#[derive(Debug, Clone)]
+pub struct MeshPart {
+    pub indices: Vec<u32>,
+    pub material: ResolvedMaterial,
+}
+
+/// Indexed geometry in WoW model space
+#[derive(Debug, Clone, Default)]
+pub struct Mesh {
+    pub name: String,
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    pub uvs: Vec<[f32; 2]>,
+    /// Second texture coordinate set, exported as `TEXCOORD_1`
+    pub uvs2: Vec<[f32; 2]>,
+    /// RGBA vertex colors, exported as `COLOR_0`
+    pub colors: Vec<[u8; 4]>,
+    /// Second RGBA vertex color set, exported as `COLOR_1`
+    pub colors2: Vec<[u8; 4]>,
+    pub parts: Vec<MeshPart>,
+}
+
+impl Mesh {
+    pub fn triangle_count(&self) -> usize {
+        self.parts.iter().map(|p| p.indices.len() / 3).sum()
+    }
+}
+
+/// A placement of a mesh in the scene
+#[derive(Debug, Clone)]
+pub struct Instance {
+    pub name: String,
+    pub mesh: usize,
+    pub translation: [f32; 3],
+    /// Quaternion (x, y, z, w)
+    pub rotation: [f32; 4],
+    pub scale: f32,
+}
+
+impl Instance {
+    /// An untransformed placement
+    pub fn identity(name: &str, mesh: usize) -> Self {
+        Self {
+            name: name.to_string(),
+            mesh,
+            translation: [0.0; 3],
+            rotation: [0.0, 0.0, 0.0, 1.0],
+            scale: 1.0,
+        }
+    }
+
+    fn transform_point(&self, p: [f32; 3]) -> [f32; 3] {
+        let r = rotate(self.rotation, p.map(|v| v * self.scale));
+        [
+            r[0] + self.translation[0],
+            r[1] + self.translation[1],
+            r[2] + self.translation[2],
+        ]
+    }
+}
+
+/// Rotate a vector by a unit quaternion (x, y, z, w)
+pub(crate) fn rotate([qx, qy, qz, qw]: [f32; 4], [vx, vy, vz]: [f32; 3]) -> [f32; 3] {
+    // v' = v + 2w(q x v) + 2 q x (q x v)
+    let tx = 2.0 * (qy * vz - qz * vy);
+    let ty = 2.0 * (qz * vx - qx * vz);
+    let tz = 2.0 * (qx * vy - qy * vx);
+    [
+        vx + qw * tx + (qy * tz - qz * ty),
+        vy + qw * ty + (qz * tx - qx * tz),
+        vz + qw * tz + (qx * ty - qy * tx),
+    ]
+}
+
+/// Shape of a punctual light
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LightKind {
+    Point,
+    /// Cone angles in radians
+    Spot {
+        inner_cone: f32,
+        outer_cone: f32,
+    },
+    Directional,
+}
+
+/// A light placed in the scene (glTF only)
+#[derive(Debug, Clone)]
+pub struct Light {
+    pub name: String,
+    pub kind: LightKind,
+    /// RGB in 0..1
+    pub color: [f32; 3],
+    pub intensity: f32,
+    /// Distance at which the light fades out, if limited
+    pub range: Option<f32>,
+    pub position: [f32; 3],
+    /// Direction spot and directional lights shine in
+    pub direction: [f32; 3],
+}
+
+impl Light {
+    /// Rotation turning a glTF light's local -Z, WoW's +Y, onto its direction
+    fn rotation(&self) -> [f32; 4] {
+        let [x, y, z] = self.direction;
+        let length = (x * x + y * y + z * z).sqrt();
+        if length < f32::EPSILON {
+            return [0.0, 0.0, 0.0, 1.0];
+        }
+        let [x, y, z] = [x / length, y / length, z / length];
+        // Shortest arc from (0, 1, 0): axis = forward x direction, w = 1 + dot
+        let w = 1.0 + y;
+        if w < 1e-6 {
+            // Pointing straight back; turn half way around Z
+            return [0.0, 0.0, 1.0, 0.0];
+        }
+        let [qx, qy, qz] = [z, 0.0, -x];
+        let norm = (qx * qx + qy * qy + qz * qz + w * w).sqrt();
+        [qx / norm, qy / norm, qz / norm, w / norm]
+    }
+}
+
+/// Meshes, their placements and lights
+#[derive(Debug, Clone, Default)]
+pub struct Scene {
+    pub name: String,
+    pub meshes: Vec<Mesh>,
+    pub instances: Vec<Instance>,
+    pub lights: Vec<Light>,
+}
+
+/// How textures referenced by materials are written
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum TextureMode {
+    /// Untextured materials
+    None,
+    /// Convert to PNG files in a `textures` folder next to the model
+    Extract,
+    /// Store PNG data inside the output file (glTF only)
+    Embed,
+}
+
+/// Converts and caches textures while a scene is written
+pub struct TextureWriter {
+    mode: TextureMode,
+    source: Option<AssetSource>,
+    output_dir: PathBuf,
+    /// Lowercased client path -> relative PNG path
+    cache: HashMap<String, Option<String>>,
+    pub written: usize,
+    pub missing: Vec<String>,
+}
+
+impl TextureWriter {
+    pub fn new(mode: TextureMode, source: Option<AssetSource>, output_dir: &Path) -> Self {
+        Self {
+            mode,
+            source,
+            output_dir: output_dir.to_path_buf(),
+            cache: HashMap::new(),
+            written: 0,
+            missing: Vec::new(),
+        }
+    }
+
+    /// Load and convert a texture, returning its PNG bytes
+    fn load_png(&mut self, client_path: &str) -> Option<Vec<u8>> {
+        let source = self.source.as_mut()?;
+        match source
+            .read(client_path)
+            .and_then(|blp| blp.map(|b| blp_to_png(&b)).transpose())
+        {
+            Ok(Some(png)) => Some(png),
+            Ok(None) => {
+                self.missing.push(client_path.to_string());
+                None
+            }
+            Err(e) => {
+                log::warn!("Failed to convert texture {client_path}: {e:#}");
+                self.missing.push(client_path.to_string());
+                None
+            }
+        }
+    }
+
+    /// Extract a texture next to the model and return its relative path
+    fn extract(&mut self, client_path: &str) -> Result<Option<String>> {
+        let key = wow_mpq::path::path_key(client_path);
+        if let Some(cached) = self.cache.get(&key) {
+            return Ok(cached.clone());
+        }
+
+        let relative = self.load_png(client_path).map(|png| {
+            let relative = texture_file_name(client_path);
+            (relative, png)
+        });
+        let result = match relative {
+            Some((relative, png)) => {
+                let path = self.output_dir.join(&relative);
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(&path, png)
+                    .with_context(|| format!("Failed to write {}", path.display()))?;
+                self.written += 1;
+                Some(relative)
+            }
+            None => None,
+        };
+        self.cache.insert(key, result.clone());
+        Ok(result)
+    }
+}
+
+/// `World\Foo\Bar.blp` -> `textures/world/foo/bar.png`
+fn texture_file_name(client_path: &str) -> String {
+    let normalized = wow_mpq::path::canonical_path(client_path)
+        .replace('\\', "/")
+        .to_lowercase();
+    let stem = normalized
+        .strip_suffix(".blp")
+        .unwrap_or(normalized.as_str());
+    format!("textures/{stem}.png")
+}
+
+fn material_name(index: usize, material: &ResolvedMaterial) -> String {
+    match material.diffuse() {
+        Some(texture) => {
+            let file = texture.rsplit(['\\', '/']).next().unwrap_or(texture);
+            let stem = file.rsplit_once('.').map_or(file, |(stem, _)| stem);
+            format!("{index:03}_{stem}")
+        }
+        None => format!("{index:03}_untextured"),
+    }
+}
+
+/// Write a Wavefront OBJ with a companion MTL file
+///
+/// Instances are baked into world space since OBJ has no node hierarchy.
+pub fn write_obj(scene: &Scene, path: &Path, textures: &mut TextureWriter) -> Result<()> {
+    let mtl_path = path.with_extension("mtl");
+    let mtl_name = mtl_path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .context("Invalid output file name")?
+        .to_string();
+
+    let mut materials: HashMap<ResolvedMaterial, String> = HashMap::new();
+    let mut mtl = String::from("# Exported by warcraft-rs\n");
+    let mut obj = format!("# Exported by warcraft-rs\nmtllib {mtl_name}\n");
+    // OBJ indices are 1-based and global across the file
+    let (mut v_base, mut vt_base, mut vn_base) = (1usize, 1usize, 1usize);
+
+    for instance in &scene.instances {
+        let mesh = &scene.meshes[instance.mesh];
+        writeln!(obj, "o {}", instance.name)?;
+
+        let has_uv = mesh.uvs.len() == mesh.positions.len();
+        let has_normal = mesh.normals.len() == mesh.positions.len();
+
+        for &p in &mesh.positions {
+            let [x, y, z] = wow_to_gltf(instance.transform_point(p));
+            writeln!(obj, "v {x} {y} {z}")?;
+        }
+        if has_uv {
+            for &uv in &mesh.uvs {
+                // OBJ puts the texture origin at the bottom left
+                writeln!(obj, "vt {} {}", uv[0], 1.0 - uv[1])?;
+            }
+        }
+        if has_normal {
+            for &n in &mesh.normals {
+                let [x, y, z] = wow_to_gltf(rotate(instance.rotation, n));
+                writeln!(obj, "vn {x} {y} {z}")?;
+            }
+        }
+
+        for part in &mesh.parts {
+            let next_index = materials.len();
+            let name = match materials.get(&part.material) {
+                Some(name) => name.clone(),
+                None => {
+                    let name = material_name(next_index, &part.material);
+                    write_mtl_entry(&mut mtl, &name, &part.material, textures)?;
+                    materials.insert(part.material.clone(), name.clone());
+                    name
+                }
+            };
+            writeln!(obj, "usemtl {name}")?;
+
+            for tri in part.indices.chunks_exact(3) {
+                obj.push('f');
+                for &i in tri {
+                    let (v, vt, vn) = (
+                        i as usize + v_base,
+                        i as usize + vt_base,
+                        i as usize + vn_base,
+                    );
+                    match (has_uv, has_normal) {
+                        (true, true) => write!(obj, " {v}/{vt}/{vn}")?,
+                        (true, false) => write!(obj, " {v}/{vt}")?,
+                        (false, true) => write!(obj, " {v}//{vn}")?,
+                        (false, false) => write!(obj, " {v}")?,
+                    }
+                }
+                obj.push('\n');
+            }
+        }
+        v_base += mesh.positions.len();
+        if has_uv {
+            vt_base += mesh.uvs.len();
+        }
+        if has_normal {
+            vn_base += mesh.normals.len();
+        }
+    }
+
+    fs::write(&mtl_path, mtl).with_context(|| format!("Failed to write {}", mtl_path.display()))?;
+    fs::write(path, obj).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn write_mtl_entry(
+    mtl: &mut String,
+    name: &str,
+    material: &ResolvedMaterial,
+    textures: &mut TextureWriter,
+) -> Result<()> {
+    writeln!(mtl, "\nnewmtl {name}")?;
+    writeln!(mtl, "Ka 0 0 0\nKd 1 1 1\nKs 0 0 0\nillum 1")?;
+
+    let texture = match (material.diffuse(), textures.mode) {
+        (Some(texture), TextureMode::Extract | TextureMode::Embed) => textures.extract(texture)?,
+        _ => None,
+    };
+    if let Some(texture) = texture {
+        writeln!(mtl, "map_Kd {texture}")?;
+        if material.alpha_mode() != AlphaMode::Opaque {
+            writeln!(mtl, "map_d {texture}")?;
+        }
+    }
+    Ok(())
+}
+
+/// Write a glTF scene; `.glb` embeds everything, `.gltf` writes a `.bin` next to it
+pub fn write_gltf(scene: &Scene, path: &Path, textures: &mut TextureWriter) -> Result<()> {
+    let mut builder = GltfBuilder::new();
+    let mut materials: HashMap<ResolvedMaterial, usize> = HashMap::new();
+
+    let mut meshes = Vec::with_capacity(scene.meshes.len());
+    for mesh in &scene.meshes {
+        if mesh.parts.is_empty() || mesh.positions.is_empty() {
+            meshes.push(None);
+            continue;
+        }
+
+        let positions: Vec<[f32; 3]> = mesh.positions.iter().map(|&p| wow_to_gltf(p)).collect();
+        let mut attributes = vec![("POSITION", builder.add_floats(&positions, true))];
+        if mesh.normals.len() == mesh.positions.len() {
+            let normals: Vec<[f32; 3]> = mesh.normals.iter().map(|&n| wow_to_gltf(n)).collect();
+            attributes.push(("NORMAL", builder.add_floats(&normals, true)));
+        }
+        if mesh.uvs.len() == mesh.positions.len() {
+            attributes.push(("TEXCOORD_0", builder.add_floats(&mesh.uvs, true)));
+        }
+        if mesh.uvs2.len() == mesh.positions.len() {
+            attributes.push(("TEXCOORD_1", builder.add_floats(&mesh.uvs2, true)));
+        }
+        if mesh.colors.len() == mesh.positions.len() {
+            attributes.push(("COLOR_0", builder.add_bytes4(&mesh.colors, true)));
+        }
+        if mesh.colors2.len() == mesh.positions.len() {
+            attributes.push(("COLOR_1", builder.add_bytes4(&mesh.colors2, true)));
+        }
+
+        let mut primitives = Vec::with_capacity(mesh.parts.len());
+        for part in &mesh.parts {
+            let material = match materials.get(&part.material) {
+                Some(&material) => material,
+                None => {
+                    let texture = match (part.material.diffuse(), textures.mode) {
+                        (Some(texture), TextureMode::Extract) => textures
+                            .extract(texture)?
+                            .map(|uri| builder.add_texture_uri(texture, &uri)),
+                        (Some(texture), TextureMode::Embed) => textures
+                            .load_png(texture)
+                            .map(|png| builder.add_png_texture(texture, &png)),
+                        _ => None,
+                    };
+                    let name = material_name(materials.len(), &part.material);
+                    let material = builder.add_material(
+                        &name,
+                        texture,
+                        part.material.alpha_mode(),
+                        part.material.two_sided,
+                    );
+                    if texture.is_some() && textures.mode == TextureMode::Embed {
+                        textures.written += 1;
+                    }
+                    materials.insert(part.material.clone(), material);
+                    material
+                }
+            };
+            let indices = builder.add_indices(&part.indices);
+            primitives.push(primitive(&attributes, indices, Some(material)));
+        }
+        meshes.push(Some(builder.add_mesh(&mesh.name, primitives)));
+    }
+
+    let root = builder.add_node(node(&scene.name, None, None, None));
+    builder.add_scene_root(root);
+
+    let mut children = Vec::with_capacity(scene.instances.len());
+    for instance in &scene.instances {
+        let Some(mesh) = meshes[instance.mesh] else {
+            continue;
+        };
+        let identity = instance.translation == [0.0; 3]
+            && instance.rotation == [0.0, 0.0, 0.0, 1.0]
+            && instance.scale == 1.0;
+        let mut value = if identity {
+            node(&instance.name, None, None, None)
+        } else {
+            node(
+                &instance.name,
+                Some(wow_to_gltf(instance.translation)),
+                Some(wow_quat_to_gltf(instance.rotation)),
+                Some([instance.scale; 3]),
+            )
+        };
+        value["mesh"] = serde_json::json!(mesh);
+        children.push(builder.add_node(value));
+    }
+    for light in &scene.lights {
+        let mut definition = serde_json::json!({
+            "name": light.name,
+            "color": light.color,
+            "intensity": light.intensity,
+        });
+        match light.kind {
+            LightKind::Point => definition["type"] = "point".into(),
+            LightKind::Spot {
+                inner_cone,
+                outer_cone,
+            } => {
+                definition["type"] = "spot".into();
+                definition["spot"] = serde_json::json!({
+                    "innerConeAngle": inner_cone,
+                    "outerConeAngle": outer_cone,
+                });
+            }
+            LightKind::Directional => definition["type"] = "directional".into(),
+        }
+        if let Some(range) = light.range.filter(|&r| r > 0.0) {
+            definition["range"] = range.into();
+        }
+        let index = builder.add_light(definition);
+
+        let rotation = (light.kind != LightKind::Point).then(|| wow_quat_to_gltf(light.rotation()));
+        let mut value = node(
+            &light.name,
+            Some(wow_to_gltf(light.position)),
+            rotation,
+            None,
+        );
+        value["extensions"] = serde_json::json!({ "KHR_lights_punctual": { "light": index } });
+        children.push(builder.add_node(value));
+    }
+    builder.set_children(root, &children);
+
+    builder.write(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotate_quarter_turn() {
+        // 90 degrees around Z maps +X onto +Y
+        let half = std::f32::consts::FRAC_PI_4;
+        let r = rotate([0.0, 0.0, half.sin(), half.cos()], [1.0, 0.0, 0.0]);
+        assert!((r[0]).abs() < 1e-6 && (r[1] - 1.0).abs() < 1e-6 && r[2].abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_gx_blend_materials() {
+        let textures = vec!["A.blp".to_string(), "B.blp".to_string()];
+        let key = ResolvedMaterial::from_gx_blend(1, textures, true);
+        assert_eq!(key.diffuse(), Some("A.blp"));
+        assert_eq!(key.alpha_mode(), AlphaMode::Mask);
+        assert_eq!(key.gx_blend(), 1);
+
+        let modes: Vec<_> = (0..8)
+            .map(|blend| ResolvedMaterial::from_gx_blend(blend, Vec::new(), false))
+            .map(|m| (m.blend_mode, m.alpha_mode(), m.gx_blend()))
+            .collect();
+        assert_eq!(modes[0], (BlendMode::Opaque, AlphaMode::Opaque, 0));
+        assert_eq!(modes[2], (BlendMode::Alpha, AlphaMode::Blend, 2));
+        assert_eq!(modes[3], (BlendMode::Additive, AlphaMode::Blend, 4));
+        assert_eq!(modes[6], (BlendMode::Modulate, AlphaMode::Blend, 5));
+        assert_eq!(ResolvedMaterial::default().diffuse(), None);
+    }
+
+    #[test]
+    fn test_light_rotation_points_along_direction() {
+        let light = Light {
+            name: String::new(),
+            kind: LightKind::Directional,
+            color: [1.0; 3],
+            intensity: 1.0,
+            range: None,
+            position: [0.0; 3],
+            direction: [0.0, 0.0, -1.0],
+        };
+        // Lights shine along +Y in WoW space before rotation
+        let r = rotate(light.rotation(), [0.0, 1.0, 0.0]);
+        assert!(r[0].abs() < 1e-6 && r[1].abs() < 1e-6 && (r[2] + 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_texture_file_name() {
+        assert_eq!(
+            texture_file_name("Dungeons\\Textures\\Stone.BLP"),
+            "textures/dungeons/textures/stone.png"
+        );
+    }
+}